@@ -2,21 +2,90 @@
 //!
 //! Full-featured command-line interface for managing and querying GroundEffect.
 
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use anyhow::Result;
 use chrono::{DateTime, NaiveDate, TimeZone, Utc};
 use chrono_tz::Tz;
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand};
 use serde::Serialize;
 
-use groundeffect_core::config::{Config, DaemonConfig, EmbeddingFallback, EmbeddingProvider};
-use groundeffect_core::db::Database;
+use groundeffect_core::availability::{availability_context, WorkingHours};
+use groundeffect_core::bounces::{find_send_status, thread_delivery, DeliveryStatus};
+use groundeffect_core::classify::resolve_categories;
+use groundeffect_core::config::{
+    active_profile, daemon_log_dir, expand_home, home_dir, set_active_profile, Config,
+    DaemonConfig, EmbeddingFallback, EmbeddingProvider,
+};
+use groundeffect_core::config_layers::{self, set_cli_overrides};
+use groundeffect_core::control;
+use groundeffect_core::db::{Database, ACTIVITY_TABLE, ITINERARY_TABLE, RECEIPTS_TABLE};
+use groundeffect_core::digest::{
+    build_digest, digest_addresses, render_text, send_digest, DigestPeriod,
+};
+use groundeffect_core::drafts::fetch_draft;
 use groundeffect_core::embedding::{EmbeddingEngine, EmbeddingModel, HybridEmbeddingProvider};
-use groundeffect_core::models::{Account, AccountStatus, CalendarEvent, Email, EventTime};
-use groundeffect_core::oauth::OAuthManager;
-use groundeffect_core::search::{CalendarSearchOptions, SearchEngine, SearchOptions};
+use groundeffect_core::forward::{
+    forward_body, forward_subject, load_forward_attachments, OutgoingAttachment,
+};
+use groundeffect_core::fts::{self, FtsRun, FtsStrategy, IndexState};
+use groundeffect_core::health::{DaemonHealth, CRASH_LOOP_WINDOW_SECS, RECENT_INCIDENT_SECS};
+use groundeffect_core::ics::build_feed;
+use groundeffect_core::keychain::OAuthTokens;
+use groundeffect_core::label_suggestions::{
+    suggest_labels, unlabeled_emails, DEFAULT_GROUP_SIMILARITY, DEFAULT_MIN_GROUP_SIZE,
+};
+use groundeffect_core::launcher::{email_items, gmail_web_url};
+use groundeffect_core::lint::{has_severe, lint_outgoing, LintSeverity};
+use groundeffect_core::logs::{self, LogQuery};
+use groundeffect_core::mcp::get_tool_definitions;
+use groundeffect_core::models::{
+    event_color_name, normalize_message_id, parse_category_retention, parse_retention_age,
+    parse_storage_size, split_email_id, Account, AccountStatus, ActivitySource, AuditActor,
+    AuditEntry, CalendarEvent, Conference, DirectoryPerson, Email, EmailAuth, EmailCategory,
+    EventFilter, EventTime, Extraction, ExtractionKind, ItineraryItem, ItineraryKind, LabelType,
+    Reminder, ReminderMethod, RetentionPolicy, Task, TaskSource, TaskStatus, Thread, Transparency,
+    Visibility,
+};
+use groundeffect_core::network;
+use groundeffect_core::oauth::{OAuthManager, ServiceAccountCredentials, UserInfo};
+use groundeffect_core::raw::fetch_raw;
+use groundeffect_core::receipts::{parse_month, spending_report};
+use groundeffect_core::recovery::{FtsCoverage, RecoveryReport};
+use groundeffect_core::relocate;
+use groundeffect_core::rename;
+use groundeffect_core::render::{self, RenderMode};
+use groundeffect_core::retention::{PruneReport, RetentionReport};
+use groundeffect_core::scheduling::{
+    find_conflicts, meet_request, parse_duration, parse_reminder, parse_window, patch_event,
+    place_hold, propose_meeting_times, EventOptions, MeetingRequest, NewEventTime, Recurrence,
+};
+use groundeffect_core::search::{
+    understand_query, CalendarSearchOptions, Diversity, ExpertiseOptions, FusionOptions,
+    QueryUnderstanding, RecencyBoost, ResultType, ScoreComponents, SearchCacheStats, SearchEngine,
+    SearchMode, SearchOptions, SynonymDictionary, ThreadSearchOptions, UnifiedItem,
+    UnifiedSearchOptions,
+};
+use groundeffect_core::signatures::{
+    fetch_gmail_signatures, resolve_signature, Signature, SignatureChoice,
+};
+use groundeffect_core::spotlight;
+use groundeffect_core::sync::{
+    add_gmail_label, attendee_profiles, fetch_gmail_body, fetch_remote_email, list_gmail_labels,
+    load_availability, resolve_attendees, resolve_label_folder, search_gmail, SyncManager,
+    REMOTE_SEARCH_MAX_RESULTS,
+};
+use groundeffect_core::sync_window::{BatteryPolicy, PolicyState};
+use groundeffect_core::token_health::{check_account_token, TokenHealthReport, TokenState};
 use groundeffect_core::token_provider::create_token_provider;
+use groundeffect_core::travel::group_trips;
+use groundeffect_core::unanswered::{find_unanswered, parse_age};
+use groundeffect_core::vectors::{
+    read_vectors, validate_vectors, write_vectors, VectorFormat, VectorTable,
+};
+use groundeffect_core::EMBEDDING_DIMENSION;
 
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 
@@ -44,6 +113,12 @@ QUICK START:
   3. Search emails:    groundeffect email search \"meeting notes\"
   4. Search calendar:  groundeffect calendar search \"standup\"
 
+FINDING COMMANDS:
+  groundeffect help search <words>   Fuzzy-search commands, flags and MCP tools
+
+TROUBLESHOOTING:
+  groundeffect doctor --human        Daemon state and accounts needing re-auth
+
 OUTPUT FORMAT:
   All commands output JSON by default (best for programmatic/AI use).
   Add --human only for direct terminal reading by humans.
@@ -75,7 +150,7 @@ EXAMPLES:
   groundeffect account show user@gmail.com
   groundeffect sync status"
 )]
-#[command(version)]
+#[command(version, disable_help_subcommand = true)]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
@@ -83,8 +158,36 @@ struct Cli {
     /// Output in human-readable format instead of JSON. Applies to all subcommands.
     #[arg(long, global = true)]
     human: bool,
+
+    /// Use a separate profile with its own config, database, attachments,
+    /// tokens and daemon (e.g. "work", "personal"). Defaults to $GROUNDEFFECT_PROFILE.
+    #[arg(long, global = true, value_name = "NAME")]
+    profile: Option<String>,
+
+    /// Report what a destructive command would remove without changing anything
+    /// (account delete, sync reset, db vectors import). No --confirm needed.
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    /// Run searches in this process instead of through the running daemon
+    #[arg(long, global = true)]
+    no_daemon: bool,
+
+    /// Give up after this long (e.g. 30s, 500ms, 2m) instead of waiting on a
+    /// slow database query or API call. Exits with code 124 and a TIMEOUT error.
+    #[arg(long, global = true, value_name = "DURATION", value_parser = parse_timeout)]
+    timeout: Option<std::time::Duration>,
+
+    /// Override a setting for this run (e.g. search.embedding_batch_size=64,
+    /// daemon.logging_enabled=true). Repeatable; beats config files and
+    /// GROUNDEFFECT__* environment variables.
+    #[arg(long = "set", global = true, value_name = "KEY=VALUE")]
+    set: Vec<String>,
 }
 
+/// Exit code when `--timeout` expires (same as coreutils `timeout`)
+const TIMEOUT_EXIT_CODE: i32 = 124;
+
 #[derive(Subcommand)]
 enum Commands {
     /// Search, list, and view emails. Use 'email search' for semantic search across all synced emails.
@@ -97,6 +200,11 @@ enum Commands {
         #[command(subcommand)]
         command: CalendarCommands,
     },
+    /// Search and list conversations (one row per Gmail thread, built during sync).
+    Thread {
+        #[command(subcommand)]
+        command: ThreadCommands,
+    },
     /// View account details including sync settings (sync_email_since, sync_attachments).
     Account {
         #[command(subcommand)]
@@ -117,6 +225,318 @@ enum Commands {
         #[command(subcommand)]
         command: ConfigCommands,
     },
+    /// List activity (repo, issue, action) parsed from GitHub, Jira and Asana notification emails.
+    Activity {
+        #[command(subcommand)]
+        command: ActivityCommands,
+    },
+    /// List receipts and invoices (merchant, amount, currency, date) detected in email.
+    Receipts {
+        #[command(subcommand)]
+        command: ReceiptsCommands,
+    },
+    /// Spending totals from receipts, by month and/or merchant.
+    Stats {
+        #[command(subcommand)]
+        command: StatsCommands,
+    },
+    /// List and complete action items found in email ("can you send the deck by Friday?").
+    Tasks {
+        #[command(subcommand)]
+        command: TasksCommands,
+    },
+    /// Find people by what they write about ("who should I ask about kubernetes budgets?").
+    Contact {
+        #[command(subcommand)]
+        command: ContactCommands,
+    },
+    /// Daily or weekly digest: important new mail, threads awaiting your reply, upcoming meetings, conflicts and follow-ups due.
+    /// Returns JSON: {period, generated_at, since, until, timezone, important_emails, unanswered, upcoming_meetings, conflicts, follow_ups}.
+    #[command(long_about = "Gather what needs attention into one digest.
+
+SECTIONS:
+  important_emails    Received mail from people in the period (not bulk,
+                      automated or spam), starred and directly addressed
+                      first
+  unanswered          Threads where someone is waiting for your reply
+                      (one week back for a daily digest, two for weekly)
+  upcoming_meetings   Events starting in the next day or week
+  conflicts           Upcoming timed meetings that overlap
+  follow_ups          Open tasks due by the end of the period, overdue
+                      ones included
+
+The JSON is meant to be handed to an assistant for a written briefing.
+--human prints the plain-text version, and --send emails it (from the
+[digest] account to the [digest] address, or yourself). The daemon can send
+it on a schedule: set enabled = true under [digest] in config.toml.
+
+EXAMPLES:
+  groundeffect digest
+  groundeffect digest --period week --human
+  groundeffect digest --account work --send")]
+    Digest {
+        /// What the digest covers: day (default) or week
+        #[arg(long, value_parser = parse_digest_period, default_value = "day")]
+        period: DigestPeriod,
+        /// Only this account (email or alias)
+        #[arg(long)]
+        account: Option<String>,
+        /// Email the plain-text digest (see [digest] account and to)
+        #[arg(long)]
+        send: bool,
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
+    /// List flights and hotel stays parsed from confirmation emails, grouped into trips.
+    Travel {
+        #[command(subcommand)]
+        command: TravelCommands,
+    },
+    /// Review sends, drafts, event creation, account changes and resets made from the CLI or MCP.
+    Audit {
+        #[command(subcommand)]
+        command: AuditCommands,
+    },
+    /// Database maintenance: export and import embedding vectors.
+    Db {
+        #[command(subcommand)]
+        command: DbCommands,
+    },
+    /// Full-text index coverage, last update and rebuild times, and manual rebuilds.
+    Index {
+        #[command(subcommand)]
+        command: IndexCommands,
+    },
+    /// Search emails, calendar events, attachments and contacts at once, or 'search stats' for cache statistics.
+    /// Returns JSON array of results with: type, score, type_score, type_rank and the fields of that type.
+    #[command(
+        args_conflicts_with_subcommands = true,
+        long_about = "Search emails, calendar events, attachments and contacts in one call.
+
+Emails and events are ranked by hybrid search (as in 'email search' and
+'calendar search'), attachments by how many query words their filename
+contains, and contacts (people in the From, To or Cc of synced mail) by how
+many query words their name or address contains, then by how often they
+appear. The results are interleaved into one list.
+
+RESPONSE FIELDS:
+  type        - email, event, attachment or contact
+  score       - Score relative to the best result of the same type (0-1)
+  type_score  - Score within the type (search score, or share of query words matched)
+  type_rank   - Position among results of the same type
+  ...         - The result's own fields: an email as in 'email search', an event
+                as in 'calendar search', an attachment (attachment_id, filename,
+                mime_type, size, downloaded, email_id, account_id, subject, from,
+                date) or a contact (email, name, message_count, last_contact)
+
+Use 'email search' or 'calendar search' for their filters.
+
+EXAMPLES:
+  groundeffect search \"quarterly report\"
+  groundeffect search \"priya\" --types contact,email
+  groundeffect search \"lease agreement\" --types attachment --account work
+  groundeffect search stats --human"
+    )]
+    Search {
+        #[command(subcommand)]
+        command: Option<SearchCommands>,
+        /// Search query
+        query: Option<String>,
+        /// Result types to include, comma-separated: email, event, attachment, contact (default: all)
+        #[arg(long, value_delimiter = ',', value_parser = parse_result_type)]
+        types: Vec<ResultType>,
+        /// Filter to specific account(s) by email address. Can specify multiple.
+        #[arg(long)]
+        account: Option<Vec<String>>,
+        /// Maximum number of results overall (default: 20, max: 100)
+        #[arg(long, default_value = "20")]
+        limit: usize,
+        /// Also search Spam and Trash (default: [search] include_spam_trash)
+        #[arg(long)]
+        include_spam_trash: bool,
+        /// Retrievers to run for emails and events: hybrid (default), semantic or keyword
+        #[arg(long, value_parser = parse_search_mode, default_value = "hybrid")]
+        mode: SearchMode,
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
+    /// Fast email search for launchers (Alfred, Raycast), or open a result in Gmail.
+    /// Returns compact JSON: {items: [{uid, title, subtitle, arg, quicklookurl}]}.
+    #[command(
+        long_about = "Fast email search for launchers such as Alfred and Raycast.
+
+Prints one line of JSON in Alfred's script filter format: each item has the
+subject as title, sender, date and account as subtitle, and the email ID as
+arg. Pass the arg to --open to open the message in Gmail on the web, signed
+in as its account.
+
+With the daemon running, the search runs there (database open, model
+loaded), which keeps it well under 100ms. Without it, quick falls back to
+keyword search in-process rather than loading the embedding model.
+
+ALFRED:
+  Script Filter:  groundeffect quick \"{query}\"
+  Run Script:     groundeffect quick --open \"{query}\"
+
+EXAMPLES:
+  groundeffect quick \"quarterly report\"
+  groundeffect quick \"flight\" --account work --limit 5
+  groundeffect quick --open 'me@gmail.com:<CAF=abc123@mail.gmail.com>'"
+    )]
+    Quick {
+        /// Search query
+        #[arg(required_unless_present = "open", conflicts_with = "open")]
+        query: Option<String>,
+        /// Open this email (an item's arg) in Gmail on the web instead of searching
+        #[arg(long, value_name = "EMAIL_ID")]
+        open: Option<String>,
+        /// Only this account (email or alias)
+        #[arg(long)]
+        account: Option<String>,
+        /// Maximum number of results (default: 9, max: 50)
+        #[arg(long, default_value = "9")]
+        limit: usize,
+    },
+    /// Disk usage breakdown: database tables, attachments per account, raw copies, models and logs.
+    /// Returns JSON: {data_dir, total_bytes, database: {total_bytes, tables: [...]}, attachments, raw, models, logs, other_bytes, top_senders_by_size?}.
+    #[command(
+        long_about = "Show where local storage goes, so you know what to prune.
+
+REPORTS:
+  database             Each LanceDB table: rows, column data (with the
+                       estimated share taken by embedding vectors), full-text
+                       and ID indexes, and version metadata
+  attachments          Downloaded attachments per account
+  raw                  Stored raw message copies per account ([sync] store_raw)
+  models               Downloaded embedding models
+  logs                 Daemon logs
+  other                Caches and daemon state files
+
+With --top-senders-by-size, also lists the senders whose mail (email size
+plus downloaded attachments) takes the most space; --account limits that
+list to one account. Mail can then be dropped with a retention policy
+('groundeffect account configure <account> --retain ...') or by excluding
+labels.
+
+JSON FIELDS:
+  tables[]             {name, rows, data_bytes, vector_bytes, index_bytes, metadata_bytes}
+  attachments, raw     {total_bytes, accounts: [{account, bytes, files}]}
+  models, logs         {bytes, files}
+  top_senders_by_size  [{email, name, emails, email_bytes, attachment_bytes}]
+
+EXAMPLES:
+  groundeffect storage --human
+  groundeffect storage --top-senders-by-size --human
+  groundeffect storage --top-senders-by-size 25 --account work"
+    )]
+    Storage {
+        /// Also list the senders taking the most space (default 10)
+        #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "10")]
+        top_senders_by_size: Option<usize>,
+        /// Limit the sender list to this account (email or alias)
+        #[arg(long)]
+        account: Option<String>,
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
+    /// Read the daemon and MCP server logs, filtered by time, level and account.
+    /// Returns JSON: {log_dir, count, entries: [{timestamp, level, source, target, message, fields?}]}.
+    #[command(
+        long_about = "Read, filter and pretty-print the daemon and MCP server logs.
+
+Reads the daily log files in the data directory's logs/ folder (daemon.log.*
+and mcp.log.*), in either log format (general.log_format = \"text\" or
+\"json\"), oldest entry first. Logging must be enabled for there to be
+anything to read: 'groundeffect config settings --logging true' for the
+daemon, GROUNDEFFECT_MCP_LOGGING=1 for the MCP server.
+
+FILTERS:
+  --since     An age (30m, 12h, 7d, 2w) or a date (YYYY-MM-DD)
+  --level     Minimum level: error, warn, info, debug or trace
+  --account   Entries mentioning this account (email or alias)
+  --source    Only daemon or mcp
+
+EXAMPLES:
+  groundeffect logs --since 1h --level warn --human
+  groundeffect logs --since 1d --account work --human
+  groundeffect logs --source mcp --limit 20"
+    )]
+    Logs {
+        /// Only entries newer than this: an age (30m, 12h, 7d) or a date (YYYY-MM-DD)
+        #[arg(long)]
+        since: Option<String>,
+        /// Minimum level: error, warn, info, debug or trace
+        #[arg(long)]
+        level: Option<String>,
+        /// Only entries mentioning this account (email or alias)
+        #[arg(long)]
+        account: Option<String>,
+        /// Only this log: daemon or mcp
+        #[arg(long, value_parser = ["daemon", "mcp"])]
+        source: Option<String>,
+        /// Show at most this many entries, newest kept (default: 200)
+        #[arg(long, default_value = "200")]
+        limit: usize,
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
+    /// Diagnose problems: config, database, embedding model, credentials, daemon, disk, and account tokens.
+    /// Returns JSON: {healthy, daemon: {running, pid, crash_looping}, checks: [{name, status, message, fix, duration_ms, details}], accounts: [...], problems: [{area, account, message, fix}]}.
+    #[command(long_about = "Diagnose common problems.
+
+Runs these checks, each reported as ok, warn, fail or skipped with the
+command that fixes it:
+
+  config               config.toml and daemon.toml parse; settings are usable
+  database             LanceDB opens and every table can be read
+  embedding_model      local model is downloaded; how long it takes to load
+                       (and the accuracy cost of int8 weights, if configured)
+  embedding_api        remote embedding provider answers with vectors that fit
+                       the stored column (skipped with --offline)
+  oauth_credentials    Google client ID/secret in the environment or ~/.secrets
+  daemon               daemon is running and not crash-looping
+  sync_watchdog        no account sync stalled and was restarted in the last day
+  launchd              macOS agent matches daemon.toml and is loaded
+  disk_space           free space on the volume holding the data directory
+  oauth_callback_port  port 8085 is free for 'account add'
+
+It also refreshes each account's OAuth token to find accounts whose access
+was revoked or expired. A revoked token stops sync for that account; doctor
+reports why it failed (e.g. invalid_grant after a password change) and the
+command that fixes it. 'healthy' is false when any check fails or any
+account needs attention; warnings don't affect it.
+
+The daemon runs the same token check periodically (sync.token_check_interval_secs)
+and can post a desktop notification when an account needs re-authentication
+(sync.reauth_notifications).
+
+EXAMPLES:
+  groundeffect doctor --human
+  groundeffect doctor --offline    # Use the daemon's last check, no network
+  groundeffect doctor --quick      # Don't load the embedding model")]
+    Doctor {
+        /// Don't contact Google; report the daemon's last token check instead
+        #[arg(long)]
+        offline: bool,
+        /// Skip loading the embedding model (only check that it's downloaded)
+        #[arg(long)]
+        quick: bool,
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
+    /// Show help for a command, or fuzzy-search commands, flags and MCP tools with 'help search'.
+    #[command(args_conflicts_with_subcommands = true)]
+    Help {
+        #[command(subcommand)]
+        command: Option<HelpCommands>,
+        /// Command to show help for (e.g. "email search")
+        path: Vec<String>,
+    },
 }
 
 // ============================================================================
@@ -139,19 +559,41 @@ RESPONSE FIELDS:
   to          - Array of recipient email addresses
   subject     - Email subject line
   date        - ISO 8601 timestamp
-  snippet     - Preview of email body (first ~100 chars)
+  snippet     - Part of the body that matched the query (start of the body if only the meaning matched)
+  snippet_html - Snippet with matched terms in <em> (with --highlight)
   account_id  - Which synced account this email belongs to
   score       - Relevance score (higher = better match)
+  score_components - BM25 and vector scores and ranks (and recency factor) behind the score
 
 SEARCH TIPS:
   - Query uses semantic search: \"budget discussions\" finds related emails even without exact words
+  - --mode keyword matches exact words only (order numbers, names); --mode semantic ignores wording
+  - --recency-weight 0.3 favors recent matches (\"the flight confirmation\" usually means the latest)
+  - Only the best match of each thread is shown; --all-thread-messages shows every one
+  - --mmr-lambda 0.7 trades some relevance for results unlike those above them
   - Combine with filters for precise results: --from, --after, --before
+  - --smart-query reads filters from the question itself: \"from Dana about the offsite last month\"
   - Date format is YYYY-MM-DD
 
 EXAMPLES:
   groundeffect email search \"quarterly budget\"
   groundeffect email search \"project status\" --from manager@company.com
-  groundeffect email search \"invoice\" --after 2024-01-01 --has-attachment"
+  groundeffect email search \"invoice\" --after 2024-01-01 --has-attachment
+  groundeffect email search \"project update\" --category personal
+  groundeffect email search \"renewal\" --tag clients
+  groundeffect email search \"deploy failure\" --include-activity
+  groundeffect email search \"contract\" --from alice@example.com --after 2024-06-01 --explain
+  groundeffect email search \"wire transfer\" --highlight
+  groundeffect email search \"INV-20931\" --mode keyword
+  groundeffect email search \"flight confirmation\" --recency-weight 0.3
+  groundeffect email search \"quarterly planning\" --mmr-lambda 0.7
+  groundeffect email search \"promised Bob\" --sent --after 2024-06-01
+  groundeffect email search \"from Dana about the offsite last month\" --smart-query
+
+GitHub, Jira and Asana notification emails are left out by default (see
+'activity list'); pass --include-activity to search them too. Spam and Trash
+are left out unless --folder names one of them; pass --include-spam-trash
+(or set include_spam_trash = true under [search]) to search them too."
     )]
     Search {
         /// Natural language search query. Uses semantic search - finds conceptually similar content.
@@ -174,12 +616,124 @@ EXAMPLES:
         /// Only return emails that have attachments
         #[arg(long)]
         has_attachment: bool,
+        /// Filter by sender category: personal, newsletter, notification, transactional
+        #[arg(long, value_parser = parse_category)]
+        category: Option<EmailCategory>,
+        /// Filter by a tag added by [[tagging.rules]] in config
+        #[arg(long)]
+        tag: Option<String>,
+        /// Only emails you sent (Sent folder, or from your address or a send-as identity)
+        #[arg(long, conflicts_with = "received")]
+        sent: bool,
+        /// Only emails you received
+        #[arg(long)]
+        received: bool,
+        /// Also search GitHub/Jira/Asana notifications collapsed into activity items
+        #[arg(long)]
+        include_activity: bool,
+        /// Also search Spam and Trash (default: [search] include_spam_trash)
+        #[arg(long)]
+        include_spam_trash: bool,
+        /// Filter to specific account(s) by email address. Can specify multiple.
+        #[arg(long)]
+        account: Option<Vec<String>>,
+        /// Maximum number of results to return (default: 10, max: 100)
+        #[arg(long, default_value = "10")]
+        limit: usize,
+        /// Show the search strategy chosen (full hybrid search, or a metadata
+        /// pre-filter then rerank when --from/--to plus a date narrow it down)
+        #[arg(long)]
+        explain: bool,
+        /// Add snippet_html with the matched query terms wrapped in <em>
+        #[arg(long)]
+        highlight: bool,
+        /// Retrievers to run: hybrid (default), semantic (vector only) or keyword (BM25 only)
+        #[arg(long, value_parser = parse_search_mode, default_value = "hybrid")]
+        mode: SearchMode,
+        /// Share of the score from recency, 0.0-1.0 (default: [search] recency_weight)
+        #[arg(long)]
+        recency_weight: Option<f32>,
+        /// Show every matching email of a thread, not just the best one
+        #[arg(long)]
+        all_thread_messages: bool,
+        /// Relevance vs novelty for MMR diversification, 0.0-1.0; 1.0 is
+        /// relevance only (default: [search] mmr_lambda)
+        #[arg(long)]
+        mmr_lambda: Option<f32>,
+        /// Take sender, recipient, date, attachment and sent-by-me filters from
+        /// the query ("from Dana about the offsite last month"); flags win
+        #[arg(long)]
+        smart_query: bool,
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
+    /// Find emails similar to another one by vector similarity.
+    /// Returns the same JSON format as search.
+    #[command(long_about = "Find emails similar to another one by vector similarity.
+
+Ranks synced mail by how close its embedding is to the given email's, the
+way 'email search' ranks by a query. The email itself, its copies in other
+folders and the rest of its thread are left out. Needs embeddings: the email
+must have been embedded by sync.
+
+RESPONSE FIELDS:
+  Same as 'email search'; score is the vector similarity.
+
+EXAMPLES:
+  groundeffect email similar \"work@company.com:<abc@mail.gmail.com>\"
+  groundeffect email similar <id> --limit 20 --account personal
+  groundeffect email similar <id> --include-spam-trash --human")]
+    Similar {
+        /// Email ID to find similar emails for
+        id: String,
         /// Filter to specific account(s) by email address. Can specify multiple.
         #[arg(long)]
         account: Option<Vec<String>>,
+        /// Also consider Spam and Trash (default: [search] include_spam_trash)
+        #[arg(long)]
+        include_spam_trash: bool,
+        /// Maximum number of results to return (default: 10, max: 100)
+        #[arg(long, default_value = "10")]
+        limit: usize,
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
+    /// Search Gmail itself for mail that isn't synced locally.
+    /// Returns the same JSON format as list, plus whether each hit is stored locally.
+    #[command(long_about = "Search Gmail itself for mail that isn't synced locally.
+
+Runs a Gmail search query (the same syntax as the Gmail search box) through
+the Gmail API, so it finds mail older than the sync window, in unsynced
+labels, or in headers-only accounts' bodies. Each hit is downloaded and
+parsed the way sync parses mail.
+
+RESPONSE FIELDS:
+  Same as 'email list', plus:
+  local       - Whether the email is in the local index (after --import)
+  imported    - Added to the local index by this command (with --import)
+
+Use --import to store the hits locally so 'email search' finds them. Emails
+already in the local index are left as they are.
+
+EXAMPLES:
+  groundeffect email remote-search --gmail-query \"from:alice has:attachment newer_than:7d\"
+  groundeffect email remote-search --gmail-query \"subject:invoice before:2019/01/01\" --account work
+  groundeffect email remote-search --gmail-query \"label:receipts\" --limit 50 --import")]
+    RemoteSearch {
+        /// Gmail search query, e.g. \"from:foo has:attachment newer_than:7d\"
+        #[arg(long)]
+        gmail_query: String,
+        /// Search only this account (default: all accounts)
+        #[arg(long)]
+        account: Option<String>,
         /// Maximum number of results to return (default: 10, max: 100)
         #[arg(long, default_value = "10")]
         limit: usize,
+        /// Store the hits in the local index
+        #[arg(long)]
+        import: bool,
         /// Human-readable output instead of JSON
         #[arg(long)]
         human: bool,
@@ -190,6 +744,13 @@ EXAMPLES:
         /// Filter to specific account by email address
         #[arg(long)]
         account: Option<String>,
+        /// Filter by sender category: personal, newsletter, notification, transactional
+        #[arg(long, value_parser = parse_category)]
+        category: Option<EmailCategory>,
+        /// Only mail that failed SPF/DKIM/DMARC or whose display name
+        /// impersonates another address (see security_warnings)
+        #[arg(long)]
+        suspicious: bool,
         /// Maximum number of results (default: 10, max: 100)
         #[arg(long, default_value = "10")]
         limit: usize,
@@ -197,21 +758,182 @@ EXAMPLES:
         #[arg(long)]
         human: bool,
     },
-    /// Show full email content by ID. Returns: id, from, to, cc, subject, date, folder, account_id, body, thread_id, attachments.
+    /// List emails addressed to you that you haven't replied to.
+    /// Returns JSON array with: id, thread_id, from, to, subject, date, snippet, waiting_days, pending_count.
+    #[command(
+        long_about = "List emails addressed to you that you haven't replied to.
+
+Finds messages received since --since with one of your addresses in To (not
+just Cc) where you sent nothing later in the same thread. Newsletters,
+notifications, receipts and GitHub/Jira/Asana activity are skipped. Your
+addresses are the account's own plus send-as addresses under
+[compose.identities]. Relies on the Sent folder being synced.
+
+One entry per thread: its latest unanswered message, newest first.
+
+RESPONSE FIELDS:
+  id            - Email ID (use with 'email show')
+  thread_id     - Gmail thread ID (use with 'email thread')
+  from, to      - Sender and recipients
+  subject, date - Of the unanswered message
+  waiting_days  - Whole days since it arrived
+  pending_count - Unanswered messages in the thread (including this one)
+
+EXAMPLES:
+  groundeffect email unanswered --since 7d
+  groundeffect email unanswered --since 2w --account work --human
+  groundeffect email unanswered --since 2024-06-01"
+    )]
+    Unanswered {
+        /// How far back to look: an age (12h, 7d, 2w) or a date (YYYY-MM-DD)
+        #[arg(long, default_value = "7d")]
+        since: String,
+        /// Filter to a specific account by email address or alias
+        #[arg(long)]
+        account: Option<String>,
+        /// Maximum number of results (default: 50, max: 500)
+        #[arg(long, default_value = "50")]
+        limit: usize,
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
+    /// Show full email content by ID. Returns: id, from, to, cc, subject, date, folder, account_id, body, thread_id, attachments, auth, headers_only.
+    #[command(long_about = "Show full email content by ID.
+
+Returns: id, from, to, cc, subject, date, folder, account_id, body, thread_id, attachments, auth, headers_only.
+
+Use --message-id to look an email up by its RFC Message-ID header instead, e.g.
+from a mid: or message:// link in a note or calendar event. If several accounts
+have the message, the newest copy is shown unless --account picks one.
+
+Accounts configured with --headers-only store no bodies: body is just the
+snippet and headers_only is true. Add --fetch to download the full body and
+attachment list from the Gmail API (shown, not stored; body_fetched is true).
+
+An email that isn't in the local index (older than the synced range, or not
+synced yet) is reported as not found. With --remote it is fetched from the
+Gmail API instead and parsed the way sync parses mail (remote is true in the
+output); --save also stores it locally, without an embedding, so keyword
+search finds it from then on. --remote needs --message-id or an email ID of
+the form <account>:<Message-ID>; with --message-id and no --account, every
+account is tried.
+
+EXAMPLES:
+  groundeffect email show 18c2f1a9d3e4b5f6
+  groundeffect email show 18c2f1a9d3e4b5f6 --fetch
+  groundeffect email show --message-id '<CAF=abc123@mail.gmail.com>' --remote --save
+  groundeffect email show --message-id '<CAF=abc123@mail.gmail.com>'
+  groundeffect email show --message-id 'message://%3CCAF=abc123@mail.gmail.com%3E' --account work")]
     Show {
         /// Email ID (from search/list results)
-        id: String,
+        #[arg(required_unless_present = "message_id", conflicts_with = "message_id")]
+        id: Option<String>,
+        /// RFC Message-ID instead of an email ID (with or without <>, or a mid:/message:// link)
+        #[arg(long)]
+        message_id: Option<String>,
+        /// With --message-id: account whose copy to show
+        #[arg(long, requires = "message_id")]
+        account: Option<String>,
+        /// Output format: json, or markdown (HTML rendered with tables, lists and links)
+        #[arg(long, value_parser = ["json", "markdown"], default_value = "json")]
+        format: String,
+        /// Download the body from Gmail if only headers were synced
+        #[arg(long)]
+        fetch: bool,
+        /// Fetch the email from Gmail if it isn't in the local index
+        #[arg(long)]
+        remote: bool,
+        /// With --remote: store the fetched email locally
+        #[arg(long, requires = "remote")]
+        save: bool,
         /// Human-readable output instead of JSON
         #[arg(long)]
         human: bool,
     },
+    /// Print an email's raw RFC 2822 source, all headers included.
+    #[command(
+        long_about = "Print an email's raw RFC 2822 source, all headers included.
+
+Useful for checking DKIM-Signature, Authentication-Results and Received-SPF
+headers, or exporting a single message as .eml. The source is fetched from
+Gmail (without marking the message read) unless a local copy exists; set
+store_raw = true under [sync] to keep a gzip-compressed copy of every synced
+message.
+
+EXAMPLES:
+  groundeffect email raw <id> --headers
+  groundeffect email raw <id> --output message.eml"
+    )]
+    Raw {
+        /// Email ID (from search/list results)
+        id: String,
+        /// Only the header block
+        #[arg(long)]
+        headers: bool,
+        /// Write the source to a file instead of stdout
+        #[arg(long)]
+        output: Option<PathBuf>,
+        /// Human-readable confirmation instead of JSON (with --output)
+        #[arg(long)]
+        human: bool,
+    },
     /// Show all emails in a thread by Gmail thread ID.
+    /// Use --format markdown --output <file> to export the thread as a document.
     Thread {
         /// Gmail thread ID (numeric, from email show result's thread_id field)
         thread_id: String,
         /// Filter to specific account(s)
         #[arg(long)]
         account: Option<Vec<String>>,
+        /// Output format: json, or markdown (one document, oldest message first)
+        #[arg(long, value_parser = ["json", "markdown"], default_value = "json")]
+        format: String,
+        /// Collapse quoted replies and repeated signatures (markdown only)
+        #[arg(long)]
+        compact: bool,
+        /// Write the output to a file instead of stdout
+        #[arg(long)]
+        output: Option<PathBuf>,
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
+    /// Check whether a sent email was delivered, from the bounces that came back.
+    /// Returns JSON with: email_id, subject, date, recipients, status, failed_recipients, delayed_recipients, bounces.
+    #[command(
+        long_about = "Check whether a sent email was delivered, from the bounces that came back.
+
+Bounces (delivery status notifications from mailer-daemon or postmaster) are
+parsed for the recipients they report on, the status code and the receiving
+server's response, and linked to the sent email by the Message-ID they quote
+or reply to, or else by thread. Mail received up to 7 days after sending is
+checked. 'email thread' output flags the same failures on each sent message.
+
+STATUS:
+  sent     - No bounce came back (delivery isn't confirmed, just not refused)
+  delayed  - A server is still retrying some recipients
+  failed   - Some recipients bounced (see failed_recipients)
+
+RESPONSE FIELDS:
+  email_id, message_id - Of the sent email
+  thread_id            - Gmail thread ID (use with 'email thread')
+  recipients           - To, Cc and Bcc addresses
+  status               - sent, delayed or failed
+  failed_recipients    - Recipients reported as undeliverable
+  delayed_recipients   - Recipients still being retried
+  bounces              - [{email_id, date, kind, recipients, status, diagnostic}]
+
+EXAMPLES:
+  groundeffect email send-status 'me@gmail.com:CAF=abc123@mail.gmail.com'
+  groundeffect email send-status '<CAF=abc123@mail.gmail.com>' --human"
+    )]
+    SendStatus {
+        /// Email ID or RFC Message-ID of the sent email
+        message_id: String,
+        /// With a Message-ID: account that sent it
+        #[arg(long)]
+        account: Option<String>,
         /// Human-readable output instead of JSON
         #[arg(long)]
         human: bool,
@@ -246,9 +968,35 @@ OPTIONAL PARAMETERS:
   --cc <emails>      CC recipients - can specify multiple times
   --bcc <emails>     BCC recipients - can specify multiple times
   --reply-to <id>    Email ID to reply to (sets In-Reply-To/References headers)
+  --as <address>     Send-as address to use in From (must be a Gmail send-as alias)
   --html             Force HTML format (auto-detected by default)
   --save-as-draft    Save as draft instead of sending
   --confirm          Actually send (without this, returns preview only)
+  --strict-lint      Refuse to send or save when the formatting lint finds severe issues
+
+FORMATTING LINT:
+  Every preview runs a pre-send lint and lists issues under \"lint\":
+  all-caps subject, HTML without a plain-text part, link text pointing to a
+  different domain than its URL, and oversized inline images. Severe issues
+  block sending with --strict-lint or when compose.block_on_severe_lint = true.
+
+IDENTITY DEFAULTS:
+  Defaults for a send-as address are read from config.toml and applied
+  automatically when sending as that address (or from the account itself):
+
+    [compose.identities.\"support@example.com\"]
+    display_name = \"Example Support\"
+    signature = \"The Example Support Team\"
+    reply_to = \"help@example.com\"
+    cc = [\"support-archive@example.com\"]
+
+SIGNATURES:
+  --signature default (the default) appends the identity's configured
+  signature, or its Gmail signature when none is configured. --signature none
+  sends without one; --signature <name> uses a [compose.signatures.<name>]
+  entry (text and/or html) or another send-as address's Gmail signature.
+  HTML mail gets the HTML version, the plain-text part the text version.
+  'email signatures' lists what's available.
 
 EXAMPLES:
   # Preview an email
@@ -265,7 +1013,13 @@ EXAMPLES:
   groundeffect email send --from work --to alice@example.com --subject \"Draft\" --body \"Content\" --save-as-draft
 
   # Reply to an existing email
-  groundeffect email send --from work --to bob@example.com --reply-to 18abc123 --subject \"Re: Question\" --body \"Yes\" --confirm")]
+  groundeffect email send --from work --to bob@example.com --reply-to 18abc123 --subject \"Re: Question\" --body \"Yes\" --confirm
+
+  # Send as an alias (applies its signature, reply-to and default CC)
+  groundeffect email send --from work --as support@example.com --to bob@example.com --subject \"Your ticket\" --body \"Fixed!\"
+
+  # Quick note without a signature
+  groundeffect email send --from work --to bob@example.com --subject \"Lunch?\" --body \"12:30?\" --signature none")]
     Send {
         /// Account to send from (email or alias)
         #[arg(long)]
@@ -288,66 +1042,250 @@ EXAMPLES:
         /// Email ID to reply to
         #[arg(long)]
         reply_to: Option<String>,
+        /// Send-as address to use in From (applies its configured defaults)
+        #[arg(long = "as", value_name = "ADDRESS")]
+        send_as: Option<String>,
         /// Force HTML format (auto-detected by default based on content)
         #[arg(long)]
         html: bool,
+        /// Signature: default (configured, else Gmail's), none, or a name from
+        /// [compose.signatures] or a send-as address
+        #[arg(long, default_value = "default")]
+        signature: String,
         /// Save as draft instead of sending (returns draft_id)
         #[arg(long)]
         save_as_draft: bool,
         /// Confirm and send (without this, returns preview only)
         #[arg(long)]
         confirm: bool,
+        /// Refuse to send when the formatting lint finds severe issues
+        #[arg(long)]
+        strict_lint: bool,
         /// Human-readable output instead of JSON
         #[arg(long)]
         human: bool,
     },
-    /// Get an email attachment content or path.
-    /// Returns JSON: {filename, mime_type, size, content?|path?, downloaded}.
+    /// Forward one or more emails with their original headers and attachments.
+    /// Returns JSON: {status: "preview"|"sent"|"draft_created", email: {...}, message_id?: string}.
     #[command(
-        long_about = "Get an email attachment by email ID and filename or attachment ID.
+        long_about = "Forward one or more emails with their original headers and attachments.
 
-If the attachment has been downloaded, returns the local file path (for binary files)
-or the content directly (for text files like .txt, .csv, .json, etc).
+Each email is quoted below a \"Forwarded message\" block with its original
+From, Date, Subject, To and Cc, oldest first, under your --comment and
+signature. Their attachments are attached again: read from disk when sync
+downloaded them, otherwise fetched from Gmail on demand (as are the bodies of
+headers-only mail).
 
-If the attachment hasn't been downloaded, returns an error with instructions
-to enable sync_attachments or use 'sync download-attachments'.
+Works in the same stages as 'email send': without --confirm or
+--save-as-draft it returns a preview (listing the attachments' names and
+sizes, without downloading them), --confirm sends, --save-as-draft saves a
+draft. The lint, --as, --signature and
+--strict-lint behave as they do for 'email send'.
 
 REQUIRED PARAMETERS:
-  <email_id>         Email ID containing the attachment
+  <ids>...           Email IDs to forward (one message containing all of them)
+  --to <emails>      Recipient(s) - can specify multiple times
 
-OPTIONAL PARAMETERS (one required):
-  --filename <name>  Attachment filename (case-insensitive match)
-  --attachment-id    Attachment ID (from email show output)
+OPTIONAL PARAMETERS:
+  --comment <text>   Your note above the forwarded emails
+  --from <account>   Account to send from (default: the first email's account)
+  --subject <text>   Subject (default: \"Fwd: \" and the first email's subject)
+  --cc, --bcc        Additional recipients
 
 EXAMPLES:
-  groundeffect email attachment 18abc123 --filename report.pdf
-  groundeffect email attachment 18abc123 --attachment-id att_456"
+  # Preview a forward
+  groundeffect email forward 18abc123 --to alice@example.com --comment \"FYI\"
+
+  # Forward two emails as one message and send
+  groundeffect email forward 18abc123 18def456 --to alice@example.com --comment \"Both quotes\" --confirm"
     )]
-    Attachment {
-        /// Email ID containing the attachment
-        email_id: String,
-        /// Attachment filename
+    Forward {
+        /// Email IDs to forward
+        #[arg(required = true)]
+        ids: Vec<String>,
+        /// Recipient email address(es)
+        #[arg(long, required = true)]
+        to: Vec<String>,
+        /// Account to send from (default: the first email's account)
         #[arg(long)]
-        filename: Option<String>,
-        /// Attachment ID
+        from: Option<String>,
+        /// Note above the forwarded emails
         #[arg(long)]
-        attachment_id: Option<String>,
+        comment: Option<String>,
+        /// Subject (default: "Fwd: " and the first email's subject)
+        #[arg(long)]
+        subject: Option<String>,
+        /// CC recipients
+        #[arg(long)]
+        cc: Option<Vec<String>>,
+        /// BCC recipients
+        #[arg(long)]
+        bcc: Option<Vec<String>>,
+        /// Send-as address to use in From (applies its configured defaults)
+        #[arg(long = "as", value_name = "ADDRESS")]
+        send_as: Option<String>,
+        /// Force HTML format (auto-detected from the comment by default)
+        #[arg(long)]
+        html: bool,
+        /// Signature below the comment: default, none, or a name
+        #[arg(long, default_value = "default")]
+        signature: String,
+        /// Save as draft instead of sending (returns draft_id)
+        #[arg(long)]
+        save_as_draft: bool,
+        /// Confirm and send (without this, returns preview only)
+        #[arg(long)]
+        confirm: bool,
+        /// Refuse to send when the formatting lint finds severe issues
+        #[arg(long)]
+        strict_lint: bool,
         /// Human-readable output instead of JSON
         #[arg(long)]
         human: bool,
     },
-    /// List Gmail folders/labels.
-    /// Returns JSON: {folders: [...]}.
-    #[command(long_about = "List available Gmail folders/labels.
-
-Returns the standard Gmail system folders. Custom labels are not yet supported.
+    /// Group recent unlabeled mail by similarity and suggest Gmail labels for it.
+    /// Returns JSON: {account_id, since, emails_considered, suggestions: [...], applied?: [...]}.
+    #[command(
+        long_about = "Group recent unlabeled mail by similarity and suggest Gmail labels for it.
+
+Looks at received mail from the last --days that has no user label in Gmail
+(Gmail is asked which, since labels aren't stored locally), groups it by
+embedding similarity, and proposes a label for each group: the sender's
+organization when most of the group comes from one domain, otherwise the
+words its subjects share. Needs embeddings.
+
+RESPONSE FIELDS (per suggestion):
+  index         - Number to pass to --apply
+  label         - Proposed label name
+  size          - Emails in the group
+  cohesion      - How alike the emails are (0-1)
+  top_senders   - Most frequent senders
+  examples      - Newest emails of the group (id, from, subject, date)
+  email_ids     - Every email in the group
+
+Apply suggestions with --apply <index> (or <index>=<label> to rename), then
+re-run with --confirm to add the labels in Gmail; labels that don't exist
+are created. Without --confirm, --apply only shows what would be labeled.
+Groups come out the same as long as no new mail arrives in between.
 
-RESPONSE:
-  folders - Array of folder names like \"INBOX\", \"[Gmail]/Sent Mail\", etc.
+EXAMPLES:
+  groundeffect email suggest-labels --account work --human
+  groundeffect email suggest-labels --days 90 --min-size 5 --similarity 0.85
+  groundeffect email suggest-labels --apply 1 --apply \"3=Kids/School\" --confirm"
+    )]
+    SuggestLabels {
+        /// Account to look at (required with several accounts)
+        #[arg(long)]
+        account: Option<String>,
+        /// How far back to look, in days
+        #[arg(long, default_value = "30")]
+        days: u32,
+        /// Smallest group worth suggesting
+        #[arg(long, default_value_t = DEFAULT_MIN_GROUP_SIZE)]
+        min_size: usize,
+        /// Cosine similarity an email needs to join a group (0-1; higher = tighter groups)
+        #[arg(long, default_value_t = DEFAULT_GROUP_SIMILARITY)]
+        similarity: f32,
+        /// Maximum number of suggestions
+        #[arg(long, default_value = "10")]
+        limit: usize,
+        /// Suggestion to apply, as <index> or <index>=<label> (repeatable)
+        #[arg(long, value_name = "INDEX[=LABEL]")]
+        apply: Vec<String>,
+        /// Add the labels in Gmail (without this, --apply only previews)
+        #[arg(long)]
+        confirm: bool,
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
+    /// Get an email attachment content or path.
+    /// Returns JSON: {filename, mime_type, size, content?|path?, downloaded}.
+    #[command(
+        long_about = "Get an email attachment by email ID and filename or attachment ID.
+
+If the attachment has been downloaded, returns the local file path (for binary files)
+or the content directly (for text files like .txt, .csv, .json, etc).
+
+If the attachment hasn't been downloaded, returns an error with instructions
+to enable sync_attachments or use 'sync download-attachments'.
+
+REQUIRED PARAMETERS:
+  <email_id>         Email ID containing the attachment
+
+OPTIONAL PARAMETERS (one required):
+  --filename <name>  Attachment filename (case-insensitive match)
+  --attachment-id    Attachment ID (from email show output)
+
+EXAMPLES:
+  groundeffect email attachment 18abc123 --filename report.pdf
+  groundeffect email attachment 18abc123 --attachment-id att_456"
+    )]
+    Attachment {
+        /// Email ID containing the attachment
+        email_id: String,
+        /// Attachment filename
+        #[arg(long)]
+        filename: Option<String>,
+        /// Attachment ID
+        #[arg(long)]
+        attachment_id: Option<String>,
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
+    /// List Gmail folders/labels.
+    /// Returns JSON: {accounts: [{account, folders, labels}]}.
+    #[command(long_about = "List Gmail folders/labels for each account.
+
+Fetches system and user-created labels from the Gmail API, including label
+colors and message counts. Results are cached for an hour; use --refresh to
+refetch. Label names and IDs are valid values for `email search --folder`.
+
+RESPONSE (per account):
+  account    - Account email address
+  fetched_at - When the labels were fetched from Gmail
+  folders    - Folder names like \"INBOX\", \"[Gmail]/Sent Mail\", \"Work/Projects\"
+  labels     - Array of {id, name, label_type, color, messages_total, messages_unread}
 
 EXAMPLES:
-  groundeffect email folders")]
+  groundeffect email folders
+  groundeffect email folders --account work --refresh")]
     Folders {
+        /// Filter to specific account(s) by email address or alias
+        #[arg(long)]
+        account: Option<Vec<String>>,
+        /// Bypass the label cache and refetch from Gmail
+        #[arg(long)]
+        refresh: bool,
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
+    /// List the signatures available to --signature.
+    /// Returns JSON: {accounts: [{account, send_as}], named}.
+    #[command(
+        long_about = "List the signatures available to 'email send --signature'.
+
+Fetches each account's send-as addresses and their Gmail signatures from the
+Gmail API, and lists the named signatures under [compose.signatures].
+
+RESPONSE:
+  accounts - Per account: {account, send_as: [{send_as, display_name,
+             is_default, signature, local_override}]}. signature has text
+             and html versions (null when none is set in Gmail);
+             local_override means [compose.identities] sets a signature
+             for the address, which 'default' uses instead
+  named    - {name: {text, html}} from [compose.signatures]
+
+EXAMPLES:
+  groundeffect email signatures
+  groundeffect email signatures --account work --human"
+    )]
+    Signatures {
+        /// Only this account (email or alias)
+        #[arg(long)]
+        account: Option<String>,
         /// Human-readable output instead of JSON
         #[arg(long)]
         human: bool,
@@ -357,6 +1295,11 @@ EXAMPLES:
         #[command(subcommand)]
         command: DraftCommands,
     },
+    /// Reservations and package deliveries found in email (flights, hotels, restaurants, packages).
+    Extractions {
+        #[command(subcommand)]
+        command: ExtractionsCommands,
+    },
 }
 
 // ============================================================================
@@ -374,9 +1317,13 @@ Use 'email draft send' to send the draft later.
 HTML SUPPORT:
   Same as 'email send' - auto-detects markdown/URLs or use --html flag.
 
+SIGNATURES:
+  Same as 'email send' - --signature default|none|<name>.
+
 EXAMPLES:
   groundeffect email draft create --from work --to alice@example.com --subject \"Draft\" --body \"Content\"
-  groundeffect email draft create --from me --to bob@example.com --subject \"HTML\" --body \"**Bold**\" --html")]
+  groundeffect email draft create --from me --to bob@example.com --subject \"HTML\" --body \"**Bold**\" --html
+  groundeffect email draft create --from work --to alice@example.com --subject \"Offer\" --body \"...\" --signature formal")]
     Create {
         /// Account to create draft from (email or alias)
         #[arg(long)]
@@ -402,6 +1349,13 @@ EXAMPLES:
         /// Email ID to reply to
         #[arg(long)]
         reply_to: Option<String>,
+        /// Send-as address to use in From (applies its configured defaults)
+        #[arg(long = "as", value_name = "ADDRESS")]
+        send_as: Option<String>,
+        /// Signature: default (configured, else Gmail's), none, or a name from
+        /// [compose.signatures] or a send-as address
+        #[arg(long, default_value = "default")]
+        signature: String,
         /// Human-readable output instead of JSON
         #[arg(long)]
         human: bool,
@@ -533,6 +1487,59 @@ EXAMPLES:
     },
 }
 
+// ============================================================================
+// Extractions Commands
+// ============================================================================
+
+#[derive(Subcommand)]
+enum ExtractionsCommands {
+    /// List reservations and package deliveries found in email.
+    /// Returns JSON array with: id, email_id, account_id, kind, title, provider, confirmation, start, end, all_day, timezone, origin, destination, location, party_size, tracking_number, tracking_url, status, email_date.
+    #[command(long_about = "List reservations and package deliveries found in email.
+
+During sync, emails are scanned for flights, hotel stays, restaurant
+reservations and package deliveries. Most airlines, hotels, booking sites
+and stores include schema.org markup, which is read first; shipping
+notifications without it are recognized by their subject and a UPS, USPS,
+FedEx or Amazon tracking number. The 'email_id' is the email it was found
+in, so 'email show <email_id>' shows the original.
+
+Without --upcoming, everything is listed newest email first. With
+--upcoming, only reservations that haven't ended and packages that haven't
+been delivered are listed, soonest first. A package appears once, with the
+status of its latest email.
+
+RESPONSE FIELDS:
+  kind            - flight, hotel, restaurant or package
+  start, end      - Departure/arrival, check-in/check-out, reservation time,
+                    or expected delivery (UTC)
+  party_size      - Number of guests (restaurants)
+  tracking_number - Carrier tracking number (packages)
+  status          - shipped, out_for_delivery or delivered (packages)
+
+EXAMPLES:
+  groundeffect email extractions list --type flight --upcoming
+  groundeffect email extractions list --type package --upcoming --human
+  groundeffect email extractions list --type restaurant --account work")]
+    List {
+        /// Only this type: flight, hotel, restaurant, package
+        #[arg(long = "type", value_parser = parse_extraction_kind)]
+        kind: Option<ExtractionKind>,
+        /// Only reservations not yet over and packages not yet delivered, soonest first
+        #[arg(long)]
+        upcoming: bool,
+        /// Filter to a specific account by email address or alias
+        #[arg(long)]
+        account: Option<String>,
+        /// Maximum number of results (default: 50, max: 500)
+        #[arg(long, default_value = "50")]
+        limit: usize,
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
+}
+
 // ============================================================================
 // Calendar Commands
 // ============================================================================
@@ -554,11 +1561,13 @@ RESPONSE FIELDS:
   account_id  - Which synced account this event belongs to
   calendar_id - Google Calendar ID
   score       - Relevance score (higher = better match)
+  score_components - BM25 and vector scores and ranks behind the score
 
 EXAMPLES:
   groundeffect calendar search \"team standup\"
   groundeffect calendar search \"1:1 meeting\" --after 2024-01-01
-  groundeffect calendar search \"quarterly review\" --limit 20")]
+  groundeffect calendar search \"quarterly review\" --limit 20
+  groundeffect calendar search \"dentist\" --mode keyword")]
     Search {
         /// Natural language search query. Uses semantic search.
         query: String,
@@ -577,6 +1586,9 @@ EXAMPLES:
         /// Maximum number of results (default: 10, max: 100)
         #[arg(long, default_value = "10")]
         limit: usize,
+        /// Retrievers to run: hybrid (default), semantic (vector only) or keyword (BM25 only)
+        #[arg(long, value_parser = parse_search_mode, default_value = "hybrid")]
+        mode: SearchMode,
         /// Human-readable output instead of JSON
         #[arg(long)]
         human: bool,
@@ -613,6 +1625,10 @@ RESPONSE FIELDS:
   start       - Start time (ISO 8601 or YYYY-MM-DD for all-day events)
   end         - End time (ISO 8601 or YYYY-MM-DD for all-day events)
   location    - Event location (may be null)
+  conference  - Video call to join (provider, join_url, ...), if any
+  show_as     - busy, or free for events that don't block time
+  visibility  - default, public, private or confidential
+  color       - Event color name, if set
   account_id  - Which synced account this event belongs to
   calendar_id - Google Calendar ID
 
@@ -632,7 +1648,13 @@ EXAMPLES:
   groundeffect calendar events --from 2024-01-07 --account work@example.com
 
   # Today's events (default)
-  groundeffect calendar events"
+  groundeffect calendar events
+
+  # This week as a grid of hours x days
+  groundeffect calendar events --grid
+
+  # Only events that block time (skip free placeholders)
+  groundeffect calendar events --show-as busy"
     )]
     Events {
         /// Start date (YYYY-MM-DD). Defaults to today if not specified.
@@ -650,9 +1672,21 @@ EXAMPLES:
         /// Human-readable output instead of JSON
         #[arg(long)]
         human: bool,
+        /// Draw a week grid (hours x days) instead of a list; implies --human
+        #[arg(long)]
+        grid: bool,
+        /// Only events shown as busy or free (free: placeholders, FYI events)
+        #[arg(long)]
+        show_as: Option<String>,
+        /// Only events with this visibility (default, public, private)
+        #[arg(long)]
+        visibility: Option<String>,
+        /// Only events with this color (name or colorId 1-11)
+        #[arg(long)]
+        color: Option<String>,
     },
     /// Create a new calendar event via Google Calendar API.
-    /// Returns JSON: {success: bool, event: {id, summary, start, end, html_link}}.
+    /// Returns JSON: {success: bool, conflicts: [...], event: {id, summary, start, end, all_day, start_local, end_local, timezone, recurrence, conference, show_as, visibility, color, reminders, html_link}}.
     #[command(long_about = "Create a new calendar event via Google Calendar API.
 
 REQUIRED PARAMETERS:
@@ -662,15 +1696,40 @@ REQUIRED PARAMETERS:
   --end <datetime>   End time in ISO 8601 format (e.g., 2024-01-15T11:00:00)
 
 OPTIONAL PARAMETERS:
+  --all-day             All-day event: --start is the first day and --end the
+                        last day (YYYY-MM-DD, inclusive; default: one day)
   --description <text>  Event description/notes
   --location <text>     Event location
-  --attendees <emails>  Attendee email addresses - can specify multiple times
+  --attendees <who>     Attendee email address, or a name to look up in the
+                        Google Workspace directory (\"Priya\" or \"Priya in Design\")
+                        - can specify multiple times
   --calendar <id>       Calendar ID (default: 'primary')
+  --timezone <tz>       IANA timezone for the times (default: general.timezone)
+  --repeat <freq>       Repeat daily, weekdays, weekly, biweekly, monthly or yearly
+    --on <days>         Days for weekly repeats (e.g. mon,wed)
+    --until <date>      Last day it repeats (YYYY-MM-DD)
+    --count <n>         Number of occurrences
+  --recurrence <rule>   Raw RRULE instead of --repeat (\"FREQ=MONTHLY;BYDAY=1MO\")
+  --with-meet           Add a Google Meet call (join link returned in 'conference')
+  --color <color>       Event color: lavender, sage, grape, flamingo, banana,
+                        tangerine, peacock, graphite, blueberry, basil, tomato
+  --visibility <who>    default, public or private
+  --show-as <busy|free> Whether the event blocks time (default: busy)
+  --reminder <when>     Reminder before the event: 10m, popup:1h, email:1d
+                        - can specify up to 5 times
+  --no-reminders        No reminders (default: the calendar's reminders)
+  --force               Create the event even if it overlaps existing events
 
 DATETIME FORMAT:
-  Use ISO 8601: YYYY-MM-DDTHH:MM:SS (times are in UTC)
+  Use ISO 8601: YYYY-MM-DDTHH:MM:SS, read in --timezone (or your configured
+  timezone). Times with an offset (2024-01-15T10:00:00-05:00) are exact.
   Example: 2024-01-15T10:00:00
 
+ALL-DAY EVENTS:
+  Google stores all-day events with an exclusive end date, so a three-day
+  offsite from --start 2024-01-15 --end 2024-01-17 is sent (and shown in
+  JSON) with end 2024-01-18.
+
 EXAMPLES:
   # Create a simple meeting
   groundeffect calendar create --account work --summary \"Team Standup\" \\
@@ -680,7 +1739,39 @@ EXAMPLES:
   groundeffect calendar create --account work --summary \"Project Review\" \\
     --start 2024-01-15T14:00:00 --end 2024-01-15T15:00:00 \\
     --location \"Conference Room A\" \\
-    --attendees alice@example.com --attendees bob@example.com")]
+    --attendees alice@example.com --attendees bob@example.com
+
+  # Invite coworkers by name (Workspace accounts)
+  groundeffect calendar create --account work --summary \"Design Sync\" \\
+    --start 2024-01-16T10:00:00 --end 2024-01-16T10:30:00 \\
+    --attendees \"Priya in Design\" --attendees \"Marco Rossi\"
+
+  # Three-day offsite
+  groundeffect calendar create --account work --summary \"Offsite\" \\
+    --all-day --start 2024-01-15 --end 2024-01-17
+
+  # Weekly 1:1 with a Meet link on Mondays and Wednesdays until June
+  groundeffect calendar create --account work --summary \"1:1 with Sam\" \\
+    --start 2024-01-15T10:00:00 --end 2024-01-15T10:30:00 \\
+    --repeat weekly --on mon,wed --until 2024-06-30 --with-meet
+
+  # Private focus block that doesn't block time, with an email reminder
+  groundeffect calendar create --account work --summary \"Focus\" \\
+    --start 2024-01-16T13:00:00 --end 2024-01-16T15:00:00 \\
+    --visibility private --show-as free --color sage --reminder email:1h
+
+CONFLICTS:
+  The new event is checked against synced events on every account first.
+  If it overlaps any (ignoring free, cancelled and declined events), nothing
+  is created and the overlapping events are returned in 'conflicts'; pass
+  --force to create it anyway. Repeating events are checked for their first
+  occurrence only.
+
+DIRECTORY LOOKUP:
+  Names are resolved with the Workspace directory (results cached for a day)
+  and must match exactly one person. It needs directory access, which is
+  requested when the account is added; accounts added earlier can grant it
+  with 'groundeffect account reauth <account>'.")]
     Create {
         /// Account to create event in (email or alias)
         #[arg(long)]
@@ -688,24 +1779,312 @@ EXAMPLES:
         /// Event title
         #[arg(long)]
         summary: String,
-        /// Start time (ISO 8601: YYYY-MM-DDTHH:MM:SS)
+        /// Start time (ISO 8601: YYYY-MM-DDTHH:MM:SS), or first day with --all-day
         #[arg(long)]
         start: String,
-        /// End time (ISO 8601: YYYY-MM-DDTHH:MM:SS)
+        /// End time (ISO 8601: YYYY-MM-DDTHH:MM:SS), or last day with --all-day
+        #[arg(long, required_unless_present = "all_day")]
+        end: Option<String>,
+        /// All-day event: --start and --end are dates (YYYY-MM-DD)
         #[arg(long)]
-        end: String,
+        all_day: bool,
         /// Event description
         #[arg(long)]
         description: Option<String>,
         /// Event location
         #[arg(long)]
         location: Option<String>,
-        /// Attendee email addresses
+        /// Attendee email addresses or directory names ("Priya in Design")
         #[arg(long)]
         attendees: Option<Vec<String>>,
         /// Calendar ID (default: primary)
         #[arg(long, default_value = "primary")]
         calendar: String,
+        /// IANA timezone for --start/--end (default: general.timezone)
+        #[arg(long)]
+        timezone: Option<String>,
+        /// Raw recurrence rule ("FREQ=WEEKLY;BYDAY=MO,WE")
+        #[arg(long, conflicts_with_all = ["repeat", "on", "until", "count"])]
+        recurrence: Option<String>,
+        /// Repeat: daily, weekdays, weekly, biweekly, monthly or yearly
+        #[arg(long)]
+        repeat: Option<String>,
+        /// Days for weekly/biweekly repeats, comma-separated (mon,wed)
+        #[arg(long, value_delimiter = ',', requires = "repeat")]
+        on: Vec<String>,
+        /// Last day the event repeats (YYYY-MM-DD)
+        #[arg(long, requires = "repeat", conflicts_with = "count")]
+        until: Option<String>,
+        /// Number of occurrences
+        #[arg(long, requires = "repeat")]
+        count: Option<u32>,
+        /// Add a Google Meet call and return its join link
+        #[arg(long)]
+        with_meet: bool,
+        /// Event color: a name (tomato, sage, ...) or colorId 1-11
+        #[arg(long)]
+        color: Option<String>,
+        /// Who can see the details: default, public or private
+        #[arg(long)]
+        visibility: Option<String>,
+        /// Whether the event blocks time: busy or free
+        #[arg(long)]
+        show_as: Option<String>,
+        /// Reminder before the event ("10m", "popup:1h", "email:1d"); repeatable
+        #[arg(long)]
+        reminder: Vec<String>,
+        /// No reminders
+        #[arg(long, conflicts_with = "reminder")]
+        no_reminders: bool,
+        /// Create the event even if it overlaps existing events
+        #[arg(long)]
+        force: bool,
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
+    /// Change a synced event's title, description, location, color, visibility, show-as or reminders.
+    /// Returns JSON: {success: bool, event: {id, account, summary, location, show_as, visibility, color, reminders, html_link}}.
+    #[command(long_about = "Change a synced event in Google Calendar.
+
+Only the options given are changed; everything else is kept. The local copy
+('calendar show', 'calendar events') catches up on the next sync.
+
+OPTIONS:
+  --summary <text>      New title
+  --description <text>  New description
+  --location <text>     New location
+  --color <color>       Event color: lavender, sage, grape, flamingo, banana,
+                        tangerine, peacock, graphite, blueberry, basil, tomato
+  --visibility <who>    default, public or private
+  --show-as <busy|free> Whether the event blocks time
+  --reminder <when>     Replace the reminders: 10m, popup:1h, email:1d
+                        - can specify up to 5 times
+  --no-reminders        Remove all reminders
+
+EXAMPLES:
+  # Mark a placeholder as free so it doesn't count as busy
+  groundeffect calendar update <event_id> --show-as free
+
+  # Make an event private and remind by email a day before
+  groundeffect calendar update <event_id> --visibility private --reminder email:1d")]
+    Update {
+        /// Event ID (from 'calendar events' or 'calendar search')
+        id: String,
+        /// New title
+        #[arg(long)]
+        summary: Option<String>,
+        /// New description
+        #[arg(long)]
+        description: Option<String>,
+        /// New location
+        #[arg(long)]
+        location: Option<String>,
+        /// Event color: a name (tomato, sage, ...) or colorId 1-11
+        #[arg(long)]
+        color: Option<String>,
+        /// Who can see the details: default, public or private
+        #[arg(long)]
+        visibility: Option<String>,
+        /// Whether the event blocks time: busy or free
+        #[arg(long)]
+        show_as: Option<String>,
+        /// Reminder before the event ("10m", "popup:1h", "email:1d"); repeatable, replaces the current ones
+        #[arg(long)]
+        reminder: Vec<String>,
+        /// Remove all reminders
+        #[arg(long, conflicts_with = "reminder")]
+        no_reminders: bool,
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
+    /// Show working hours, out-of-office blocks and vacation responder status.
+    /// Returns JSON with: working_hours, in_working_hours, away_now, next_available, away.
+    #[command(long_about = "Show working hours and when you're away.
+
+Working hours come from [availability] in config.toml (Google Calendar
+doesn't expose its working hours setting). Out-of-office events on the
+primary calendar and the Gmail vacation responder are fetched on each
+calendar sync and cached; --refresh fetches them now.
+
+RESPONSE FIELDS:
+  working_hours    - days, start and end (your timezone)
+  in_working_hours - Whether it's working hours now
+  away_now         - Whether an out-of-office block or auto-reply is active
+  next_available   - Next working time outside away blocks
+  away             - Current and upcoming away blocks (source, summary, start, end)
+  accounts         - Vacation responder settings per account
+
+EXAMPLES:
+  groundeffect calendar availability --human
+  groundeffect calendar availability --account work --refresh")]
+    Availability {
+        /// Filter to specific account(s) by email address or alias
+        #[arg(long)]
+        account: Option<Vec<String>>,
+        /// Fetch from Gmail and Calendar instead of using the cache
+        #[arg(long)]
+        refresh: bool,
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
+    /// Propose meeting times that suit you and the attendees.
+    /// Returns JSON with: window_start, window_end, not_visible, slots (start, end, score, free, busy, unknown), hold.
+    #[command(long_about = "Propose meeting times that suit you and the attendees.
+
+Candidate slots fall within your working hours ([availability] in
+config.toml) and avoid your events on every synced calendar, out-of-office
+blocks and vacation responder periods. Each attendee's Google Calendar
+free/busy is then checked: slots are ranked by the share of attendees free,
+with attendees whose calendars aren't shared with you counting as half.
+
+--hold places a tentative event for the best slot on your primary calendar.
+Attendees are named in its description but not invited.
+
+WINDOWS:
+  today, tomorrow, this-week, next-week, a length from now (3d, 2w) or a
+  date range (2024-01-15..2024-01-19, end inclusive). At most 31 days.
+
+RESPONSE FIELDS:
+  window_start/window_end - Time searched (UTC)
+  not_visible             - Attendees whose free/busy couldn't be read
+  slots                   - Best first: start, end, local_start, score,
+                            free, busy, unknown
+  hold                    - Tentative event placed (with --hold)
+
+EXAMPLES:
+  groundeffect calendar propose --attendees alice@example.com,bob@example.com \\
+    --duration 45m --window next-week
+
+  # Hold the best time this week
+  groundeffect calendar propose --attendees \"Priya in Design\" --window this-week --hold")]
+    Propose {
+        /// Attendee email addresses or directory names, comma-separated
+        #[arg(long, value_delimiter = ',')]
+        attendees: Vec<String>,
+        /// Meeting length (45m, 1h, 1h30m)
+        #[arg(long, default_value = "30m")]
+        duration: String,
+        /// When to look (today, tomorrow, this-week, next-week, 3d, or YYYY-MM-DD..YYYY-MM-DD)
+        #[arg(long, default_value = "next-week")]
+        window: String,
+        /// Account to check free/busy with and place the hold in (default: first account)
+        #[arg(long)]
+        account: Option<String>,
+        /// Maximum number of slots to propose
+        #[arg(long, default_value = "5")]
+        limit: usize,
+        /// Place a tentative event for the best slot
+        #[arg(long)]
+        hold: bool,
+        /// Title of the hold event (default: "Hold: meeting with <attendees>")
+        #[arg(long)]
+        title: Option<String>,
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
+    /// Print the merged ICS feed of synced calendars (as the daemon publishes it with [ics_feed]).
+    #[command(long_about = "Print the ICS feed of synced calendars.
+
+The primary calendars of the [ics_feed] accounts (all accounts by default)
+are merged into one iCalendar document, from past_days back to future_days
+ahead. A meeting on the calendars of two accounts appears once, and
+cancelled events are left out. With busy_only = true, events show only as
+\"Busy\".
+
+With enabled = true under [ics_feed] in config.toml, the daemon rewrites the
+feed to calendar.ics in the data directory (or [ics_feed] path) every
+interval_secs, and with port set also serves it at
+http://127.0.0.1:<port>/calendar.ics for other calendar apps to subscribe to.
+
+EXAMPLES:
+  groundeffect calendar feed > merged.ics
+  groundeffect calendar feed --output ~/Calendars/all.ics")]
+    Feed {
+        /// Write the feed to this file instead of printing it
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+}
+
+// ============================================================================
+// Thread Commands
+// ============================================================================
+
+#[derive(Subcommand)]
+enum ThreadCommands {
+    /// Search conversations as a whole using hybrid search.
+    /// Returns JSON array with: id, gmail_thread_id, subject, participants, message_count, last_activity, score.
+    #[command(
+        long_about = "Search conversations as a whole using hybrid BM25 + vector search.
+
+Each Gmail thread is indexed as one document: its subject, participants and
+every message (quoted replies and repeated signatures collapsed). A question
+answered across several replies matches the thread even when no single email
+contains all of it.
+
+RESPONSE FIELDS:
+  id               - Thread ID ({account_id}:{gmail_thread_id})
+  gmail_thread_id  - Use with 'email thread' to read the messages
+  subject          - Subject of the first message
+  participants     - Senders and recipients: [{name, email}]
+  message_count    - Number of synced messages
+  unread_count     - Messages not yet read
+  first_date       - Date of the first message (ISO 8601)
+  last_activity    - Date of the latest message (ISO 8601)
+  last_from        - Sender of the latest message
+  snippet          - Preview of the latest message
+  has_attachments  - Whether any message has attachments
+  has_sent         - Whether you wrote any of the messages
+  email_ids        - Email IDs, oldest first (use with 'email show')
+  score            - Relevance score (higher = better match)
+  score_components - BM25 and vector scores and ranks behind the score
+
+EXAMPLES:
+  groundeffect thread search \"pricing negotiation with acme\"
+  groundeffect thread search \"offsite planning\" --after 2024-01-01
+  groundeffect thread search \"contract\" --participant bob@acme.io --human"
+    )]
+    Search {
+        /// Natural language search query
+        query: String,
+        /// Only threads with a participant whose address or name contains this
+        #[arg(long)]
+        participant: Option<String>,
+        /// Only threads active after this date (format: YYYY-MM-DD)
+        #[arg(long)]
+        after: Option<String>,
+        /// Only threads active before this date (format: YYYY-MM-DD)
+        #[arg(long)]
+        before: Option<String>,
+        /// Filter to specific account(s) by email address
+        #[arg(long)]
+        account: Option<Vec<String>>,
+        /// Maximum number of results (default: 10, max: 100)
+        #[arg(long, default_value = "10")]
+        limit: usize,
+        /// Retrievers to run: hybrid (default), semantic (vector only) or keyword (BM25 only)
+        #[arg(long, value_parser = parse_search_mode, default_value = "hybrid")]
+        mode: SearchMode,
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
+    /// List conversations by latest activity, most recent first.
+    /// Returns JSON array with the same fields as 'thread search' (without score).
+    List {
+        /// Filter to a specific account by email address or alias
+        #[arg(long)]
+        account: Option<String>,
+        /// Only threads active on or after this date (format: YYYY-MM-DD)
+        #[arg(long)]
+        since: Option<String>,
+        /// Maximum number of results (default: 20, max: 1000)
+        #[arg(long, default_value = "20")]
+        limit: usize,
         /// Human-readable output instead of JSON
         #[arg(long)]
         human: bool,
@@ -775,13 +2154,43 @@ PREREQUISITES:
   Get credentials from: https://console.cloud.google.com/apis/credentials
   (Create OAuth 2.0 Client ID > Desktop app, enable Gmail & Calendar APIs)
 
+GOOGLE WORKSPACE (no browser):
+  With a service account that has domain-wide delegation, pass its JSON key
+  and the mailbox to sync. A Workspace admin must authorize the service
+  account's client ID for these scopes (Admin console > Security > API
+  controls > Domain-wide delegation):
+    https://mail.google.com/
+    https://www.googleapis.com/auth/gmail.send
+    https://www.googleapis.com/auth/calendar
+    https://www.googleapis.com/auth/userinfo.email
+    https://www.googleapis.com/auth/userinfo.profile
+  The key is stored with the account's tokens, so the file can be removed
+  afterwards.
+
+HEADLESS MACHINES (over SSH):
+  --device-code prints a URL and a short code instead of opening a browser
+  and listening on port 8085. Enter the code on any device; the command
+  waits until you approve. Needs an OAuth client of type \"TVs and Limited
+  Input devices\" in the same Google Cloud project.
+
 EXAMPLES:
   groundeffect account add
-  groundeffect account add --years 5 --alias work")]
+  groundeffect account add --years 5 --alias work
+  groundeffect account add --device-code --years 1
+  groundeffect account add --service-account key.json --impersonate user@corp.com --years 2")]
     Add {
         /// How many years of email history to sync (1-20, or 'all')
         #[arg(long)]
         years: Option<String>,
+        /// Service account JSON key with domain-wide delegation (skips the browser flow)
+        #[arg(long, value_name = "KEY_FILE", requires = "impersonate")]
+        service_account: Option<PathBuf>,
+        /// Workspace user to sync as (with --service-account)
+        #[arg(long, value_name = "EMAIL", requires = "service_account")]
+        impersonate: Option<String>,
+        /// Authenticate with a code entered on another device (no local browser)
+        #[arg(long, conflicts_with = "service_account")]
+        device_code: bool,
         /// Enable automatic attachment downloading
         #[arg(long)]
         attachments: bool,
@@ -803,16 +2212,20 @@ REQUIRED PARAMETERS:
 
 EXAMPLES:
   groundeffect account reauth work
-  groundeffect account reauth user@gmail.com")]
+  groundeffect account reauth user@gmail.com
+  groundeffect account reauth work --device-code")]
     Reauth {
         /// Account email or alias
         account: String,
+        /// Authenticate with a code entered on another device (no local browser)
+        #[arg(long)]
+        device_code: bool,
         /// Human-readable output instead of JSON
         #[arg(long)]
         human: bool,
     },
     /// Delete an account and all its synced data.
-    /// Returns JSON: {success: bool, deleted: {account, emails, events}}.
+    /// Returns JSON: {success: bool, deleted: {account, emails, events}}, or with --dry-run {success, dry_run, account, would_delete: {...}}.
     #[command(long_about = "Delete an account and all its synced data.
 
 This will:
@@ -823,8 +2236,11 @@ This will:
 
 WARNING: This action is irreversible!
 
+Use --dry-run to see what would be removed (counts, sizes and date ranges)
+without deleting anything.
+
 EXAMPLES:
-  groundeffect account delete user@gmail.com
+  groundeffect account delete user@gmail.com --dry-run
   groundeffect account delete user@gmail.com --confirm")]
     Delete {
         /// Account email or alias
@@ -836,21 +2252,96 @@ EXAMPLES:
         #[arg(long)]
         human: bool,
     },
-    /// Configure account settings (alias, sync_attachments).
-    /// Returns JSON: {success: bool, changes: [...], account: {id, alias, sync_attachments}}.
+    /// Move an account to a new email address, keeping everything synced under the old one.
+    /// Returns JSON: {success, from, to, rows: {table: count}, tokens_moved, files: {attachments, raw_messages, caches, failed}, config_updated}.
+    #[command(long_about = "Move an account to a new email address.
+
+When a Google account's primary address changes (e.g. a domain migration),
+everything synced so far is stored under the old address. This rewrites it
+to the new one:
+
+  database   The account record and its sync state, emails, events,
+             threads, activity, receipts, itineraries, reservations,
+             packages and tasks. All tables change or none do.
+  tokens     The account's OAuth tokens (the Google account is the same,
+             so no re-authentication is needed)
+  files      Downloaded attachments, stored raw messages, and the label,
+             availability and directory caches
+  config     Aliases, per-account settings, and the digest, ICS feed,
+             Apple export and Spotlight account lists in config.toml
+
+The audit log keeps the old address. Stop the daemon first; --dry-run shows
+what would be moved without changing anything.
+
+EXAMPLES:
+  groundeffect --dry-run account rename me@old.com me@new.com
+  groundeffect account rename work me@new.com --human")]
+    Rename {
+        /// Current account email or alias
+        account: String,
+        /// New email address
+        new_email: String,
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
+    /// Configure account settings (alias, sync_attachments, label filters, storage limit, retention, headers-only sync).
+    /// Returns JSON: {success: bool, changes: [...], account: {id, alias, sync_attachments, include_labels, exclude_labels, max_storage_bytes, retention, headers_only}}.
     #[command(long_about = "Configure account settings.
 
 CONFIGURABLE SETTINGS:
-  --alias <name>       Set a friendly alias (e.g., 'work', 'personal')
-  --alias \"\"           Remove the alias
-  --attachments        Enable automatic attachment downloading
-  --no-attachments     Disable automatic attachment downloading
+  --alias <name>            Set a friendly alias (e.g., 'work', 'personal')
+  --alias \"\"                Remove the alias
+  --attachments             Enable automatic attachment downloading
+  --no-attachments          Disable automatic attachment downloading
+  --include-label <label>   Only sync emails with one of these Gmail labels (repeatable)
+  --exclude-label <label>   Skip emails with these Gmail labels (repeatable)
+  --clear-labels            Remove all label filters
+  --max-storage <size>      Cap local storage (e.g., 5GB, 500MB); 'none' removes the cap
+  --retain <age>            Keep mail locally for this long (e.g., 2y, 6m, 90d); 'none' keeps all
+  --retain-category <c=age> Keep a category for less time (e.g., newsletter=30d; repeatable);
+                            '<category>=none' removes the rule
+  --headers-only            Store only headers and snippets (no bodies or attachments)
+  --full-bodies             Store full message bodies again (the default)
+
+LABEL FILTERS:
+  Each --include-label/--exclude-label list replaces the previous one.
+  System labels (INBOX, SPAM, STARRED, ...) and categories (Promotions,
+  Social, Updates, Forums) are recognized. Filters apply from the next sync;
+  already-synced emails are kept until 'groundeffect sync reset'.
+
+STORAGE LIMIT:
+  Counts indexed email size plus downloaded attachments. Once the limit is
+  reached the daemon stops downloading attachments and backfilling older
+  history for the account; new mail keeps syncing. 'groundeffect sync status'
+  reports the condition along with suggested retention actions.
+
+RETENTION:
+  The daemon prunes mail older than the --retain age once a day (configure
+  with retention_interval_secs under [sync]), deleting it from the local
+  index with its embedding, downloaded attachments and stored raw copy.
+  Nothing is deleted in Gmail, and backfill stops at the retention age.
+  Categories are GroundEffect's sender categories (personal, newsletter,
+  notification, transactional; 'promotions' means newsletter), since Gmail
+  labels aren't stored locally. 'groundeffect sync status' shows the last run.
+
+HEADERS-ONLY SYNC:
+  For huge archival accounts. Sync stores each message's headers and a short
+  snippet but no body or attachments, keeping disk use minimal while sender,
+  subject and date search keep working. 'groundeffect email show <id> --fetch'
+  downloads the body from Gmail when you need it. Applies to mail synced from
+  now on; 'groundeffect sync reset' re-syncs existing mail in the new mode.
 
 Note: Changes to attachment settings require a daemon restart to take effect.
 
 EXAMPLES:
   groundeffect account configure user@gmail.com --alias work
   groundeffect account configure work --attachments
+  groundeffect account configure work --exclude-label Promotions --exclude-label Spam
+  groundeffect account configure work --include-label INBOX --include-label Work
+  groundeffect account configure work --max-storage 5GB
+  groundeffect account configure work --retain 2y --retain-category promotions=30d
+  groundeffect account configure archive --headers-only
   groundeffect account configure user@gmail.com --alias \"\" --no-attachments")]
     Configure {
         /// Account email or alias
@@ -864,6 +2355,30 @@ EXAMPLES:
         /// Disable automatic attachment downloading
         #[arg(long)]
         no_attachments: bool,
+        /// Only sync emails with one of these Gmail labels
+        #[arg(long = "include-label", value_name = "LABEL")]
+        include_labels: Vec<String>,
+        /// Skip emails with these Gmail labels
+        #[arg(long = "exclude-label", value_name = "LABEL")]
+        exclude_labels: Vec<String>,
+        /// Remove all label filters
+        #[arg(long)]
+        clear_labels: bool,
+        /// Local storage limit (e.g. 5GB, 500MB; "none" to remove)
+        #[arg(long, value_name = "SIZE")]
+        max_storage: Option<String>,
+        /// Keep mail locally for this long (e.g. 2y, 6m, 90d; "none" to keep all)
+        #[arg(long, value_name = "AGE")]
+        retain: Option<String>,
+        /// Keep a category for less time, e.g. newsletter=30d ("<category>=none" removes it)
+        #[arg(long = "retain-category", value_name = "CATEGORY=AGE")]
+        retain_categories: Vec<String>,
+        /// Store only headers and snippets (no bodies or attachments)
+        #[arg(long, conflicts_with_all = ["full_bodies", "attachments"])]
+        headers_only: bool,
+        /// Store full message bodies (undoes --headers-only)
+        #[arg(long)]
+        full_bodies: bool,
         /// Human-readable output instead of JSON
         #[arg(long)]
         human: bool,
@@ -897,6 +2412,24 @@ RESPONSE FIELDS:
   attachments_size_bytes - Size of downloaded attachments
   sync_email_since      - Configured email sync cutoff (ISO 8601, may be null)
   sync_attachments      - Whether attachment download is enabled (boolean)
+  storage_used_bytes    - Local storage used (indexed emails + downloaded attachments)
+  max_storage_bytes     - Configured storage limit (may be null)
+  storage_quota_exceeded - Limit reached: attachment downloads and backfill are paused
+  retention_suggestions - Commands to free space or raise the limit (only when exceeded)
+  retention             - Retention policy {max_age_days, category_max_age_days} (only when set)
+  last_prune            - Last daemon pruning run {pruned_at, emails, email_bytes, attachments,
+                          attachment_bytes, raw_copies, by_rule} (may be null)
+  headers_only          - Only headers and snippets are stored (only when enabled)
+
+VERBOSE (--verbose):
+  The response becomes {accounts: [...], consistency: {checked_at, summary, issues}}.
+  consistency is the daemon's last startup scan, which compares each account's sync
+  cursors with the stored data and checks full-text indexes and embeddings. Each
+  issue is {account_id (when it concerns one), kind, detail, action, status}:
+    kind    - future_cursor, cursor_without_data, cursor_mismatch, fts_index_missing,
+              fts_index_stale or missing_embeddings
+    status  - repaired, scheduled (running in the background), failed or skipped
+  Cursors are fixed before syncing starts; index rebuilds and embedding run after.
 
 IMPORTANT:
   - oldest_email shows actual data range, sync_email_since shows configured limit
@@ -905,18 +2438,22 @@ IMPORTANT:
 
 EXAMPLES:
   groundeffect sync status
-  groundeffect sync status --account user@gmail.com"
+  groundeffect sync status --account user@gmail.com
+  groundeffect sync status --verbose --human"
     )]
     Status {
         /// Filter to specific account by email address
         #[arg(long)]
         account: Option<String>,
+        /// Include the daemon's last startup consistency scan
+        #[arg(long)]
+        verbose: bool,
         /// Human-readable output instead of JSON
         #[arg(long)]
         human: bool,
     },
     /// Reset sync data for an account (deletes synced emails/events).
-    /// Returns JSON: {success: bool, deleted: {emails, events}}.
+    /// Returns JSON: {success: bool, deleted: {emails, events}}, or with --dry-run {success, dry_run, account, data_type, would_delete: {...}}.
     #[command(long_about = "Reset sync data for an account.
 
 WARNING: This permanently deletes synced emails and/or calendar events!
@@ -930,7 +2467,15 @@ OPTIONAL PARAMETERS:
 
 After reset, the daemon will re-sync from the configured sync_email_since date.
 
+DRY RUN:
+  --dry-run reports what would be deleted (email, event, activity, receipt
+  and itinerary counts, indexed email bytes, and the date range covered)
+  without changing anything. --confirm is not needed.
+
 EXAMPLES:
+  # Preview a reset
+  groundeffect sync reset --account work --dry-run
+
   # Reset all data
   groundeffect sync reset --account work --confirm
 
@@ -1030,6 +2575,55 @@ EXAMPLES:
         #[arg(long)]
         human: bool,
     },
+    /// Export events and contacts to macOS Calendar and Contacts (one-way).
+    /// Returns JSON: {success, calendars: [{name, events}], contacts_added}, or with --dry-run {success, dry_run, plan}.
+    #[command(
+        long_about = "Export events and contacts to macOS Calendar and Contacts.
+
+Copies the accounts listed under [apple] in the config, so Siri, Spotlight and
+other apps see them. Nothing is read back. Needs a build with the apple-bridge
+feature (cargo build --release --features apple-bridge).
+
+  calendar_accounts  Each account's events go to its own local calendar,
+                     \"groundeffect: <alias>\", which is replaced on every export
+  contact_accounts   People you've sent at least contacts_min_sent emails are
+                     added to Contacts (group \"groundeffect: <alias>\") unless
+                     a card already has their email; cards are never changed
+
+With [apple] enabled = true the daemon exports every interval_secs. With
+--dry-run, lists what would be written (contacts already in Contacts included).
+
+EXAMPLES:
+  groundeffect sync apple --human
+  groundeffect --dry-run sync apple"
+    )]
+    Apple {
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
+    /// Write Spotlight stubs so macOS system search finds synced emails.
+    /// Returns JSON: {dir, written, unchanged, removed}.
+    #[command(
+        long_about = "Write Spotlight stubs so macOS system search finds synced emails.
+
+Each email in the [spotlight] window gets a small text file holding its
+subject, addresses, date and body, which Spotlight indexes. Opening a result
+runs `groundeffect email show <id> --human` in Terminal. Stubs of emails that
+left the window are removed. With [spotlight] enabled = true the daemon does
+this every interval_secs.
+
+Stubs go to ~/Library/Caches/Metadata/groundeffect unless [spotlight] path is
+set. To remove them all, delete that directory.
+
+EXAMPLES:
+  groundeffect sync spotlight --human"
+    )]
+    Spotlight {
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
 }
 
 // ============================================================================
@@ -1038,16 +2632,41 @@ EXAMPLES:
 
 #[derive(Subcommand)]
 enum DaemonCommands {
-    /// Check if daemon is running. Returns JSON: {running: bool, pid: number|null}.
+    /// Check if daemon is running. Returns JSON: {running: bool, pid: number|null, health?: {...}}.
+    #[command(long_about = "Check if the daemon is running.
+
+With --health, also reports watchdog data recorded by the daemon: restart
+counts, recent unclean exits, the last heartbeat and the last panic with its
+backtrace. launchd and systemd restart a crashing daemon automatically, so a daemon can
+look \"running\" while it is crash-looping; --health makes that visible.
+
+HEALTH FIELDS:
+  crash_looping         - 3+ unclean exits in the last 10 minutes
+  sync_backoff_factor   - Poll intervals are multiplied by this while crash-looping
+  restart_count         - Starts that followed an unclean exit
+  recent_crashes        - Times of unclean exits within the crash-loop window
+  last_heartbeat        - Last heartbeat from the running daemon
+  heartbeat_stale       - Daemon marked running but has stopped heartbeating
+  last_panic            - {at, message, location, thread, backtrace}
+  sync_incidents        - Stalled syncs the watchdog cancelled and restarted:
+                          {at, account_id, kind, detail}; kind is no_progress,
+                          idle_wedged or repeated_error
+
+EXAMPLES:
+  groundeffect daemon status
+  groundeffect daemon status --health --human")]
     Status {
+        /// Include restart counts, crash-loop state and last panic
+        #[arg(long)]
+        health: bool,
         /// Human-readable output instead of JSON
         #[arg(long)]
         human: bool,
     },
-    /// Start the background sync daemon. Uses launchd if plist exists, otherwise direct spawn.
-    /// Returns JSON: {status: "started"|"already_running"|"error", method?: "launchd"|"direct"}.
+    /// Start the background sync daemon. Uses launchd/systemd if installed, otherwise direct spawn.
+    /// Returns JSON: {status: "started"|"already_running"|"error", method?: "launchd"|"systemd"|"direct"}.
     Start {
-        /// Enable file logging (only for direct spawn, not launchd)
+        /// Enable file logging (only for direct spawn, not launchd/systemd)
         #[arg(long)]
         logging: bool,
         /// Human-readable output instead of JSON
@@ -1060,19 +2679,47 @@ enum DaemonCommands {
         #[arg(long)]
         human: bool,
     },
-    /// Restart the daemon (stop then start). Returns JSON: {status: "restarted", method: "launchd"|"direct"}.
+    /// Restart the daemon (stop then start). Returns JSON: {status: "restarted", method: "launchd"|"systemd"|"direct"}.
     Restart {
-        /// Enable file logging (only for direct spawn, not launchd)
+        /// Enable file logging (only for direct spawn, not launchd/systemd)
         #[arg(long)]
         logging: bool,
         /// Human-readable output instead of JSON
         #[arg(long)]
         human: bool,
     },
-    /// Install launchd agent for auto-start at login. Uses smart defaults (no prompts).
-    /// Returns JSON: {status: "installed"|"already_installed"|"error"}.
+    /// Apply config.toml changes to the running daemon without restarting it.
+    /// Returns JSON: {status: "reloaded"|"not_running"|"error", applied?: [...], restart_required?: [...]}.
+    #[command(
+        long_about = "Apply config.toml changes to the running daemon without restarting it.
+
+The daemon also notices edits to config.toml by itself within a few seconds;
+this applies them right away and reports what changed. Settings that fail
+validation are refused and the daemon keeps its current ones.
+
+APPLIED AT RUNTIME:
+  general.log_level
+  sync.email_poll_interval_secs, sync.calendar_poll_interval_secs
+  sync.max_concurrent_fetches, sync.rate_limit_per_second
+  sync.attachment_max_size_mb
+
+Any other change is listed under restart_required and takes effect after
+'groundeffect daemon restart'.
+
+EXAMPLES:
+  groundeffect daemon reload
+  groundeffect daemon reload --human"
+    )]
+    Reload {
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
+    /// Install launchd agent (macOS) or systemd user unit (Linux) for auto-start at login.
+    /// Uses smart defaults (no prompts). Returns JSON: {status: "installed"|"already_installed"|"error"}.
     #[command(
-        long_about = "Install the launchd agent for automatic daemon startup at login.
+        long_about = "Install the launchd agent (macOS) or systemd user unit (Linux) for
+automatic daemon startup at login.
 
 Uses sensible defaults:
   - Logging: disabled (logs to ~/.local/share/groundeffect/logs/ when enabled)
@@ -1083,9 +2730,13 @@ Uses sensible defaults:
 The daemon will start automatically after installation and on every login.
 
 FILES CREATED:
-  ~/Library/LaunchAgents/com.groundeffect.daemon.plist
+  ~/Library/LaunchAgents/com.groundeffect.daemon.plist   (macOS)
+  ~/.config/systemd/user/groundeffect-daemon.service     (Linux)
   ~/.config/groundeffect/daemon.toml
 
+On Linux, run `loginctl enable-linger $USER` to keep the daemon running
+while logged out (e.g. on servers).
+
 TO CUSTOMIZE SETTINGS:
   groundeffect config settings
 
@@ -1103,14 +2754,16 @@ EXAMPLES:
         #[arg(long)]
         human: bool,
     },
-    /// Uninstall launchd agent. Stops the daemon and removes auto-start.
+    /// Uninstall launchd agent or systemd unit. Stops the daemon and removes auto-start.
     /// Returns JSON: {status: "uninstalled"|"not_installed"}.
     #[command(
-        long_about = "Uninstall the launchd agent and stop automatic daemon startup.
+        long_about = "Uninstall the launchd agent (macOS) or systemd user unit (Linux) and stop
+automatic daemon startup.
 
 This will:
   1. Stop the running daemon (if any)
-  2. Remove the launchd plist from ~/Library/LaunchAgents/
+  2. Remove the launchd plist from ~/Library/LaunchAgents/ or the unit from
+     ~/.config/systemd/user/
   3. The daemon will no longer start automatically at login
 
 Note: This does NOT remove synced data or configuration files.
@@ -1179,7 +2832,7 @@ CONFIGURABLE SETTINGS:
   --calendar-interval <secs> Calendar poll interval (60-3600 seconds)
   --max-fetches <num>        Max concurrent fetches (1-50)
   --timezone <tz>            User timezone (e.g., America/Los_Angeles, UTC)
-  --embedding-provider <p>   Embedding backend: local | openrouter | remote
+  --embedding-provider <p>   Embedding backend: local | openai | ollama | openrouter | remote
   --embedding-batch-size <n> Embedding + IMAP fetch batch size (1-1024, default 128)
   --openrouter-model <id>    OpenRouter embedding model (when provider=openrouter)
   --openrouter-api-key-env <name>
@@ -1204,6 +2857,10 @@ EXAMPLES:
   # Use OpenRouter embeddings
   groundeffect config settings --embedding-provider openrouter
 
+  # Use OpenAI ($OPENAI_API_KEY) or a local Ollama server
+  groundeffect config settings --embedding-provider openai
+  groundeffect config settings --embedding-provider ollama
+
   # Set embedding/fetch batch size (128 recommended for Gmail/OpenRouter stability)
   groundeffect config settings --embedding-batch-size 128
 
@@ -1225,7 +2882,7 @@ EXAMPLES:
         /// User timezone (e.g., America/Los_Angeles, UTC, Europe/London)
         #[arg(long)]
         timezone: Option<String>,
-        /// Embedding backend: local, openrouter, or remote
+        /// Embedding backend: local, openai, ollama, openrouter, or remote
         #[arg(long)]
         embedding_provider: Option<String>,
         /// Embedding + IMAP fetch batch size (1-1024, default 128)
@@ -1241,747 +2898,2742 @@ EXAMPLES:
         #[arg(long)]
         human: bool,
     },
-}
 
-// ============================================================================
-// JSON Output Types
-// ============================================================================
+    /// Show what this build can reach over the network.
+    #[command(long_about = "Show what this build can reach over the network.
 
-#[derive(Serialize)]
-struct EmailResult {
-    id: String,
-    from: String,
-    to: Vec<String>,
-    subject: String,
-    date: String,
-    folder: String,
-    account_id: String,
-    snippet: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    score: Option<f32>,
-}
+Lists each network path, whether it is compiled into this binary, and whether
+the current config uses it. Apart from Google's APIs, GroundEffect only
+contacts endpoints you configure.
 
-impl EmailResult {
-    fn from_email(email: &Email, score: Option<f32>) -> Self {
-        Self {
-            id: email.id.clone(),
-            from: email.from.to_string(),
-            to: email.to.iter().map(|a| a.to_string()).collect(),
-            subject: email.subject.clone(),
-            date: email.date.to_rfc3339(),
-            folder: email.folder.clone(),
-            account_id: email.account_id.clone(),
-            snippet: email.snippet.clone(),
-            score,
-        }
-    }
-}
+BUILD FEATURES (compile paths out entirely):
+  no-remote-embedding  No OpenAI, Ollama, OpenRouter or custom embedding client
+  offline-search       No model downloads either (implies no-remote-embedding)
+  no-llm               No language model calls
 
-#[derive(Serialize)]
-struct EmailDetail {
-    id: String,
-    from: String,
-    to: Vec<String>,
-    cc: Vec<String>,
-    subject: String,
-    date: String,
-    folder: String,
-    account_id: String,
-    body: String,
-    thread_id: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    attachments: Option<Vec<AttachmentInfo>>,
-}
+  cargo build --release --features offline-search,no-llm
 
-#[derive(Serialize)]
-struct AttachmentInfo {
-    id: String,
-    filename: String,
-    mime_type: String,
-    size: u64,
-}
+EXAMPLE:
+  groundeffect config capabilities --human")]
+    Capabilities {
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
 
-impl EmailDetail {
-    fn from_email(email: &Email) -> Self {
-        let attachments = if email.attachments.is_empty() {
-            None
-        } else {
-            Some(
-                email
-                    .attachments
-                    .iter()
-                    .map(|a| AttachmentInfo {
-                        id: a.id.clone(),
-                        filename: a.filename.clone(),
-                        mime_type: a.mime_type.clone(),
-                        size: a.size,
-                    })
-                    .collect(),
-            )
-        };
-        Self {
-            id: email.id.clone(),
-            from: email.from.to_string(),
-            to: email.to.iter().map(|a| a.to_string()).collect(),
-            cc: email.cc.iter().map(|a| a.to_string()).collect(),
-            subject: email.subject.clone(),
-            date: email.date.to_rfc3339(),
-            folder: email.folder.clone(),
-            account_id: email.account_id.clone(),
-            body: email.resolved_body(),
-            thread_id: email.gmail_thread_id.to_string(),
-            attachments,
-        }
-    }
-}
+    /// Move the data directory (database, attachments, models) somewhere else.
+    /// Returns JSON: {success, from, to, files, bytes, method, attachments_updated, dry_run}.
+    #[command(
+        long_about = "Move the data directory somewhere else, e.g. an external volume.
 
-#[derive(Serialize)]
-struct EventResult {
-    id: String,
-    summary: String,
-    start: String,
-    end: String,
-    location: Option<String>,
-    account_id: String,
-    calendar_id: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    score: Option<f32>,
-}
+Moves the database, downloaded attachments, embedding models, logs and daemon
+state, then updates data_dir (and log_file, if it was inside) in config.toml
+and the stored paths of downloaded attachments.
 
-impl EventResult {
-    fn from_event(event: &CalendarEvent, score: Option<f32>) -> Self {
-        Self {
-            id: event.id.clone(),
-            summary: event.summary.clone(),
-            start: format_event_time(&event.start),
-            end: format_event_time(&event.end),
-            location: event.location.clone(),
-            account_id: event.account_id.clone(),
-            calendar_id: event.calendar_id.clone(),
-            score,
-        }
-    }
-}
+On the same volume the directory is renamed. Across volumes everything is
+copied, the copy is checked file by file count and size, and only then is the
+original removed; if the check fails the original is left untouched.
 
-fn format_event_time(et: &EventTime) -> String {
-    match et {
-        EventTime::DateTime(dt) => dt.to_rfc3339(),
-        EventTime::Date(d) => d.to_string(),
-    }
-}
+The target must not exist or be an empty directory. Stop the daemon (and any
+MCP server) first. If the daemon is installed, run 'groundeffect daemon
+install' afterwards so its log paths follow.
 
-#[derive(Serialize)]
-struct AccountResult {
-    email: String,
-    alias: Option<String>,
-    display_name: String,
-    status: String,
-    added_at: String,
-    last_sync_email: Option<String>,
-    last_sync_calendar: Option<String>,
-}
+EXAMPLES:
+  groundeffect config relocate --data-dir /Volumes/External/ge --dry-run
+  groundeffect config relocate --data-dir /Volumes/External/ge --human"
+    )]
+    Relocate {
+        /// New data directory
+        #[arg(long)]
+        data_dir: PathBuf,
+        /// Show what would move without moving it
+        #[arg(long)]
+        dry_run: bool,
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
 
-impl AccountResult {
-    fn from_account(account: &Account) -> Self {
-        Self {
-            email: account.id.clone(),
-            alias: account.alias.clone(),
-            display_name: account.display_name.clone(),
-            status: format!("{:?}", account.status),
-            added_at: account.added_at.to_rfc3339(),
-            last_sync_email: account.last_sync_email.map(|d| d.to_rfc3339()),
-            last_sync_calendar: account.last_sync_calendar.map(|d| d.to_rfc3339()),
-        }
-    }
-}
+    /// Show the effective settings of config.toml and daemon.toml.
+    /// Returns JSON: {settings: {...}} or, with --origin, {settings: [{key, value, origin, source}]}.
+    #[command(long_about = "Show the effective settings, after every override.
 
-#[derive(Serialize)]
-struct SyncStatus {
-    account: String,
-    status: String,
-    email_count: u64,
-    event_count: u64,
-    oldest_email: Option<String>,
-    newest_email: Option<String>,
-    oldest_event: Option<String>,
-    newest_event: Option<String>,
-    last_email_sync: Option<String>,
-    last_calendar_sync: Option<String>,
-    attachments_total: usize,
-    attachments_downloaded: usize,
-    attachments_size_bytes: u64,
-    sync_email_since: Option<String>,
-    sync_attachments: bool,
-    estimated_total_emails: Option<u64>,
-    emails_remaining: Option<u64>,
-}
+Each setting comes from the last of these layers that sets it:
 
-#[derive(Serialize)]
-struct DaemonStatus {
-    running: bool,
-    pid: Option<u32>,
+  default   built-in default
+  file      config.toml (daemon.* settings: daemon.toml)
+  env       GROUNDEFFECT__<SECTION>__<KEY>, e.g.
+            GROUNDEFFECT__SEARCH__EMBEDDING_BATCH_SIZE=64
+            GROUNDEFFECT__DAEMON__LOGGING_ENABLED=true
+  cli       --set <key>=<value> on any command, e.g.
+            --set general.timezone=Europe/London
+
+Values are read as TOML (64, true, [\"INBOX\"]); anything else is a string.
+
+EXAMPLES:
+  groundeffect config show --human
+  groundeffect config show --origin --human
+  groundeffect --set sync.store_raw=true config show --origin")]
+    Show {
+        /// List every setting with the layer it comes from
+        #[arg(long)]
+        origin: bool,
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
+
+    /// Check config.toml, daemon.toml and overrides for unknown keys and bad values.
+    /// Returns JSON: {valid, issues: [{key, origin, source, problem}]}. Exits 1 when invalid.
+    #[command(long_about = "Check the settings for mistakes.
+
+Reports, with the layer each came from (file, env or cli):
+  - keys no setting reads (typos, settings from other versions)
+  - values of the wrong type (a string where a number goes)
+  - values out of range (unknown timezone, poll intervals outside 60-3600s)
+
+EXAMPLES:
+  groundeffect config validate --human
+  GROUNDEFFECT__SYNC__STORE_RAW=yes groundeffect config validate")]
+    Validate {
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
 }
 
 // ============================================================================
-// Main Entry Point
+// Activity Commands
 // ============================================================================
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let cli = Cli::parse();
-    let global_human = cli.human;
+#[derive(Subcommand)]
+enum ActivityCommands {
+    /// List activity items, newest first.
+    /// Returns JSON array with: id, account_id, source, project, item, title, action, actor, url, date.
+    #[command(long_about = "List activity parsed from notification emails.
 
-    match cli.command {
-        Commands::Email { command } => handle_email_command(command, global_human).await,
-        Commands::Calendar { command } => handle_calendar_command(command, global_human).await,
-        Commands::Account { command } => handle_account_command(command, global_human).await,
-        Commands::Sync { command } => handle_sync_command(command, global_human).await,
-        Commands::Daemon { command } => handle_daemon_command(command, global_human).await,
-        Commands::Config { command } => handle_config_command(command).await,
-    }
+During sync, notification emails from GitHub, Jira and Asana are collapsed
+into one activity item each: the repository or project, the issue/PR/task,
+what happened (commented, merged, assigned, status changed, ...) and who did
+it. These emails are left out of 'email search' unless --include-activity is
+passed. The item 'id' is the email ID, so 'email show <id>' shows the original.
+
+Configure under [activity] in config.toml: enabled, sources
+(github, jira, asana) and hide_from_search.
+
+EXAMPLES:
+  groundeffect activity list --human
+  groundeffect activity list --source github --project acme/widgets
+  groundeffect activity list --source jira --after 2024-06-01 --limit 50")]
+    List {
+        /// Filter to a specific account by email address or alias
+        #[arg(long)]
+        account: Option<String>,
+        /// Only activity from this tool: github, jira, asana
+        #[arg(long, value_parser = parse_activity_source)]
+        source: Option<ActivitySource>,
+        /// Only this repository (owner/repo), Jira project key, or Asana project ID
+        #[arg(long)]
+        project: Option<String>,
+        /// Only activity after this date (format: YYYY-MM-DD)
+        #[arg(long)]
+        after: Option<String>,
+        /// Maximum number of results (default: 20, max: 200)
+        #[arg(long, default_value = "20")]
+        limit: usize,
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
 }
 
 // ============================================================================
-// Email Command Handlers
+// Receipts Commands
 // ============================================================================
 
-async fn handle_email_command(command: EmailCommands, global_human: bool) -> Result<()> {
-    match command {
-        EmailCommands::Search {
-            query,
-            from,
-            to,
-            after,
-            before,
-            folder,
-            has_attachment,
-            account,
-            limit,
-            human,
-        } => {
-            let human = human || global_human;
-            let config = Config::load().unwrap_or_default();
-            let db = Arc::new(Database::open(config.lancedb_dir()).await?);
+#[derive(Subcommand)]
+enum ReceiptsCommands {
+    /// List receipts, newest first.
+    /// Returns JSON array with: id, account_id, merchant, amount, currency, date, subject, sender, source.
+    #[command(long_about = "List receipts and invoices detected in email.
 
-            // Initialize embedding engine with hybrid remote/local support
-            // Skip loading local model if using remote with BM25 fallback (saves CPU/memory)
-            let local_embedding = if config.search.remote_embeddings_enabled()
-                && config.search.embedding_fallback == EmbeddingFallback::Bm25
-            {
-                None
-            } else {
-                let model_type = EmbeddingModel::from_str(&config.search.embedding_model)
-                    .unwrap_or(EmbeddingModel::BgeBaseEn);
-                Some(Arc::new(EmbeddingEngine::from_cache(
-                    config.models_dir(),
-                    model_type,
-                    config.search.use_gpu,
-                )?))
-            };
-            let embedding = Arc::new(HybridEmbeddingProvider::from_search_config(
-                local_embedding,
-                &config.search,
-            )?);
+During sync, emails that look like receipts, invoices or payment
+confirmations (by subject, billing sender addresses such as receipts@ or
+billing@, and attachments named like invoice.pdf) are recorded with the
+merchant, total, currency and transaction date. When the total is only in a
+PDF or text attachment, it is read once the attachment is downloaded.
+The receipt 'id' is the email ID, so 'email show <id>' shows the original.
 
-            let search_engine = SearchEngine::new(db.clone(), embedding);
+--csv prints date,merchant,amount,currency,subject,sender,account_id,email_id
+for spreadsheets and expense tools. --human ends with totals per currency.
 
-            // Resolve account aliases to IDs
-            let accounts = if let Some(accts) = account {
-                let all_accounts = db.list_accounts().await?;
-                let resolved: Vec<String> = accts
-                    .iter()
-                    .filter_map(|a| resolve_account(&all_accounts, a))
-                    .collect();
-                if resolved.is_empty() {
-                    None
-                } else {
-                    Some(resolved)
-                }
-            } else {
-                None
-            };
+Configure under [receipts] in config.toml: enabled and parse_attachments.
 
-            let mut options = SearchOptions::new(limit.min(100));
-            options.accounts = accounts;
-            options.folder = folder;
-            options.from = from;
-            options.to = to;
-            options.date_from = parse_date(&after, &config.general.timezone);
-            options.date_to = parse_date(&before, &config.general.timezone);
-            options.has_attachment = if has_attachment { Some(true) } else { None };
-
-            let results = search_engine.search_emails(&query, &options).await?;
+EXAMPLES:
+  groundeffect receipts list --month 2024-06 --csv > june.csv
+  groundeffect receipts list --month 2024-06 --human
+  groundeffect receipts list --merchant uber --limit 20")]
+    List {
+        /// Filter to a specific account by email address or alias
+        #[arg(long)]
+        account: Option<String>,
+        /// Only receipts from this month (format: YYYY-MM)
+        #[arg(long)]
+        month: Option<String>,
+        /// Only merchants whose name contains this (case-insensitive)
+        #[arg(long)]
+        merchant: Option<String>,
+        /// Maximum number of results (default: 100, max: 1000)
+        #[arg(long, default_value = "100")]
+        limit: usize,
+        /// CSV output instead of JSON
+        #[arg(long, conflicts_with = "human")]
+        csv: bool,
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
+}
 
-            if human {
-                if results.is_empty() {
-                    println!("No emails found.");
-                } else {
-                    println!("\nFound {} emails:\n", results.len());
-                    for result in &results {
-                        let e = &result.email;
-                        println!("📧 {} (score: {:.2})", e.subject, result.score);
-                        println!("   From: {}", e.from);
-                        println!("   Date: {}", e.date.format("%Y-%m-%d %H:%M"));
-                        println!("   ID: {}", e.id);
-                        println!();
-                    }
-                }
-            } else {
-                let json_results: Vec<serde_json::Value> = results
-                    .iter()
-                    .map(|r| {
-                        serde_json::json!({
-                            "id": r.email.id,
-                            "from": r.email.from.to_string(),
-                            "to": r.email.to.iter().map(|a| a.to_string()).collect::<Vec<_>>(),
-                            "subject": r.email.subject,
-                            "date": r.email.date.to_rfc3339(),
-                            "snippet": r.email.snippet,
-                            "account_id": r.email.account_id,
-                            "score": r.score
-                        })
-                    })
-                    .collect();
-                println!("{}", serde_json::to_string_pretty(&json_results)?);
-            }
-        }
+// ============================================================================
+// Stats Commands
+// ============================================================================
 
-        EmailCommands::List {
-            account,
-            limit,
-            human,
-        } => {
-            let human = human || global_human;
-            let config = Config::load().unwrap_or_default();
-            let db = Database::open(config.lancedb_dir()).await?;
+#[derive(Subcommand)]
+enum StatsCommands {
+    /// Total spending from receipts, optionally by month and/or merchant.
+    /// Returns JSON array with: month, merchant, currency, total, count.
+    #[command(long_about = "Total spending from receipts detected in email.
 
-            let account_id = if let Some(acct) = account {
-                let all_accounts = db.list_accounts().await?;
-                resolve_account(&all_accounts, &acct)
-            } else {
-                None
-            };
+Sums the receipts 'receipts list' shows, including totals read from PDF
+and text attachments. Without --by-month or --by-merchant there is one
+row per currency; each flag splits the totals further. Amounts in
+different currencies are never added together, and receipts whose total
+couldn't be found are left out. Months are in your configured timezone.
 
-            let emails = db
-                .list_recent_emails(account_id.as_deref(), limit.min(100))
-                .await?;
+RESPONSE FIELDS:
+  month    - YYYY-MM (with --by-month)
+  merchant - Merchant name (with --by-merchant)
+  currency - ISO 4217 code, or \"unknown\"
+  total    - Sum of the amounts
+  count    - Number of receipts summed
 
-            if human {
-                if emails.is_empty() {
-                    println!("No emails found.");
-                } else {
-                    println!("\nRecent {} emails:\n", emails.len());
-                    for email in &emails {
-                        println!("📧 {}", email.subject);
-                        println!("   From: {}", email.from);
-                        println!("   Date: {}", email.date.format("%Y-%m-%d %H:%M"));
-                        println!("   ID: {}", email.id);
-                        println!();
-                    }
-                }
-            } else {
-                let json_results: Vec<EmailResult> = emails
-                    .iter()
-                    .map(|e| EmailResult::from_email(e, None))
-                    .collect();
-                println!("{}", serde_json::to_string_pretty(&json_results)?);
-            }
-        }
+EXAMPLES:
+  groundeffect stats spending --by-month --human
+  groundeffect stats spending --by-merchant --after 2024-01-01 --before 2025-01-01
+  groundeffect stats spending --by-month --by-merchant --merchant aws")]
+    Spending {
+        /// One row per month
+        #[arg(long)]
+        by_month: bool,
+        /// One row per merchant
+        #[arg(long)]
+        by_merchant: bool,
+        /// Only receipts on or after this date (format: YYYY-MM-DD)
+        #[arg(long)]
+        after: Option<String>,
+        /// Only receipts before this date (format: YYYY-MM-DD)
+        #[arg(long)]
+        before: Option<String>,
+        /// Only merchants whose name contains this (case-insensitive)
+        #[arg(long)]
+        merchant: Option<String>,
+        /// Filter to a specific account by email address or alias
+        #[arg(long)]
+        account: Option<String>,
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
+}
 
-        EmailCommands::Show { id, human } => {
-            let human = human || global_human;
-            let config = Config::load().unwrap_or_default();
-            let db = Database::open(config.lancedb_dir()).await?;
+// ============================================================================
+// Tasks Commands
+// ============================================================================
 
-            match db.get_email(&id).await? {
-                Some(email) => {
-                    if human {
-                        println!("\n📧 {}", email.subject);
-                        println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-                        println!("From: {}", email.from);
-                        println!(
-                            "To: {}",
-                            email
-                                .to
-                                .iter()
-                                .map(|a| a.to_string())
-                                .collect::<Vec<_>>()
-                                .join(", ")
-                        );
-                        if !email.cc.is_empty() {
-                            println!(
-                                "CC: {}",
-                                email
-                                    .cc
-                                    .iter()
-                                    .map(|a| a.to_string())
-                                    .collect::<Vec<_>>()
-                                    .join(", ")
-                            );
-                        }
-                        println!("Date: {}", email.date.format("%Y-%m-%d %H:%M:%S"));
-                        println!("Folder: {}", email.folder);
-                        if !email.attachments.is_empty() {
-                            println!(
-                                "Attachments: {}",
-                                email
-                                    .attachments
-                                    .iter()
-                                    .map(|a| a.filename.as_str())
-                                    .collect::<Vec<_>>()
-                                    .join(", ")
-                            );
-                        }
-                        println!("\n{}", email.resolved_body());
-                    } else {
-                        let detail = EmailDetail::from_email(&email);
-                        println!("{}", serde_json::to_string_pretty(&detail)?);
-                    }
-                }
-                None => {
-                    if human {
-                        println!("Email not found: {}", id);
-                    } else {
-                        println!("{{\"error\": \"Email not found\"}}");
-                    }
-                }
-            }
-        }
+#[derive(Subcommand)]
+enum TasksCommands {
+    /// List action items found in email, soonest due first.
+    /// Returns JSON array with: id, email_id, text, requester, subject, due, status.
+    #[command(long_about = "List action items found in email.
 
-        EmailCommands::Thread {
-            thread_id,
-            account,
-            human,
-        } => {
-            let human = human || global_human;
-            let config = Config::load().unwrap_or_default();
-            let db = Database::open(config.lancedb_dir()).await?;
+During sync, inbound mail that expects a reply is read for requests made
+of you (\"can you...\", \"please...\", \"make sure to...\") and a due
+date is guessed from the wording (\"by Friday\", \"tomorrow\", \"Mar 3\").
+An AI assistant that has read an email can replace these with its own
+list through the MCP tool set_email_tasks.
 
-            // Parse thread_id as u64 (gmail_thread_id)
-            let thread_id_num: u64 = thread_id.parse().unwrap_or(0);
+Open tasks with a due date come first, soonest first; the rest follow
+newest first. Marking a task done survives later syncs.
 
-            let account_id = if let Some(accts) = account {
-                let all_accounts = db.list_accounts().await?;
-                accts
-                    .first()
-                    .and_then(|a| resolve_account(&all_accounts, a))
-            } else {
-                None
-            };
+RESPONSE FIELDS:
+  id           - Task ID (use with 'tasks done')
+  email_id     - Email the request came from
+  account_id   - Account the email belongs to
+  text         - The request, as written
+  requester    - Who asked
+  subject      - Subject of the email
+  due          - Guessed due date, YYYY-MM-DD (if any)
+  due_text     - The words the due date was read from
+  status       - open or done
+  source       - heuristic or assistant
+  created_at   - Date of the email
+  completed_at - When it was marked done
 
-            let emails = db
-                .get_emails_by_thread(thread_id_num, account_id.as_deref())
-                .await?;
+EXAMPLES:
+  groundeffect tasks list --human
+  groundeffect tasks list --due-before 2025-03-01
+  groundeffect tasks list --status done --account work")]
+    List {
+        /// Which tasks to show: open, done, all
+        #[arg(long, default_value = "open")]
+        status: String,
+        /// Only tasks due on or before this date (format: YYYY-MM-DD)
+        #[arg(long)]
+        due_before: Option<String>,
+        /// Filter to a specific account by email address or alias
+        #[arg(long)]
+        account: Option<String>,
+        /// Maximum number of tasks
+        #[arg(long, default_value = "50")]
+        limit: usize,
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
+    /// Mark a task done (or open again with --reopen).
+    Done {
+        /// Task ID from 'tasks list'
+        id: String,
+        /// Mark the task open again
+        #[arg(long)]
+        reopen: bool,
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
+}
 
-            if human {
-                if emails.is_empty() {
-                    println!("No emails found in thread: {}", thread_id);
-                } else {
-                    println!("\nThread {} ({} emails):\n", thread_id, emails.len());
-                    for email in &emails {
-                        println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-                        println!("📧 {}", email.subject);
-                        println!("From: {}", email.from);
-                        println!("Date: {}", email.date.format("%Y-%m-%d %H:%M"));
-                        println!(
-                            "\n{}\n",
-                            email.resolved_body().chars().take(500).collect::<String>()
-                        );
-                    }
-                }
-            } else {
-                let json_results: Vec<EmailDetail> =
-                    emails.iter().map(EmailDetail::from_email).collect();
-                println!("{}", serde_json::to_string_pretty(&json_results)?);
-            }
-        }
+// ============================================================================
+// Contact Commands
+// ============================================================================
 
-        EmailCommands::Send {
-            from,
-            to,
-            subject,
-            body,
-            cc,
-            bcc,
-            reply_to,
-            html,
-            save_as_draft,
-            confirm,
-            human,
-        } => {
-            let human = human || global_human;
-            email_send(
-                &from,
-                to,
-                &subject,
-                &body,
-                cc,
-                bcc,
-                reply_to,
-                html,
-                save_as_draft,
-                confirm,
-                human,
-            )
-            .await?;
-        }
+#[derive(Subcommand)]
+enum ContactCommands {
+    /// Rank contacts by how much their mail is about a topic.
+    /// Returns JSON array with: email, name, score, similarity, from_them, to_them, last_contact, evidence.
+    #[command(long_about = "Rank contacts by how much their mail is about a topic.
+
+Answers \"who should I ask about ...\" from your own mailbox. Every person
+you exchange mail with gets a profile built from the embeddings of the mail
+they sent you and the mail you sent them (To or Cc). The query's embedding
+is compared with each profile; people seen in only a few emails are ranked
+lower, so one on-topic message doesn't beat someone who writes about it all
+the time. Newsletters and automated mail are left out. Needs embeddings.
 
-        EmailCommands::Draft { command } => {
-            handle_draft_command(command, global_human).await?;
-        }
+RESPONSE FIELDS:
+  email        - Contact's address
+  name         - Display name, when known
+  score        - Similarity damped for contacts with few emails (rank by this)
+  similarity   - Cosine similarity of the contact's profile to the query
+  from_them    - Emails they sent you
+  to_them      - Emails you sent them
+  last_contact - Date of the newest email
+  evidence     - Their emails most like the query: id, subject, date, sent, similarity
 
-        EmailCommands::Attachment {
-            email_id,
-            filename,
-            attachment_id,
-            human,
-        } => {
-            let human = human || global_human;
-            email_attachment(
-                &email_id,
-                filename.as_deref(),
-                attachment_id.as_deref(),
-                human,
-            )
-            .await?;
-        }
+EXAMPLES:
+  groundeffect contact expertise \"kubernetes budget approvals\"
+  groundeffect contact expertise \"visa sponsorship\" --since 2024-01-01 --human
+  groundeffect contact expertise \"SOC 2 audit\" --account work --min-emails 5")]
+    Expertise {
+        /// What you need help with
+        query: String,
+        /// Only use mail from this account (email or alias)
+        #[arg(long)]
+        account: Option<String>,
+        /// How far back to look: an age (90d, 12w) or a date (YYYY-MM-DD)
+        #[arg(long, default_value = "365d")]
+        since: String,
+        /// Only rank contacts with at least this many emails
+        #[arg(long, default_value = "2")]
+        min_emails: usize,
+        /// Maximum number of contacts (default: 10, max: 100)
+        #[arg(long, default_value = "10")]
+        limit: usize,
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
+}
 
-        EmailCommands::Folders { human } => {
-            let human = human || global_human;
-            let folders = vec![
-                "INBOX",
-                "[Gmail]/All Mail",
-                "[Gmail]/Drafts",
-                "[Gmail]/Important",
-                "[Gmail]/Sent Mail",
-                "[Gmail]/Spam",
-                "[Gmail]/Starred",
-                "[Gmail]/Trash",
-            ];
+// ============================================================================
+// Travel Commands
+// ============================================================================
 
-            if human {
-                println!("\n📁 Gmail Folders:\n");
-                for folder in &folders {
-                    println!("  {}", folder);
-                }
-            } else {
-                println!(
-                    "{}",
-                    serde_json::to_string_pretty(&serde_json::json!({
-                        "folders": folders
-                    }))?
-                );
-            }
-        }
-    }
+#[derive(Subcommand)]
+enum TravelCommands {
+    /// List upcoming trips, soonest first.
+    /// Returns JSON array of trips: {start, end, destinations, items: [...]}.
+    #[command(long_about = "List upcoming trips.
 
-    Ok(())
+During sync, flight and hotel confirmation emails that carry schema.org
+reservation markup (most airlines, hotel chains and booking sites) are parsed
+into itinerary items. Items close together, or before the flight home, are
+grouped into one trip. Times are stored in UTC; --human shows them in the
+local time of the departure or arrival airport.
+
+Set [travel] create_events = true in config.toml to add upcoming flights and
+hotel stays to Google Calendar during sync. It is off by default because
+Gmail may already add them.
+
+EXAMPLES:
+  groundeffect travel trips --human
+  groundeffect travel trips --days 30")]
+    Trips {
+        /// Filter to a specific account by email address or alias
+        #[arg(long)]
+        account: Option<String>,
+        /// How many days ahead to look (default: 90)
+        #[arg(long, default_value = "90")]
+        days: i64,
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
+    /// List itinerary items (flights and hotel stays), soonest first.
+    /// Returns JSON array with: id, email_id, account_id, kind, title, provider, confirmation, start, end, all_day, start_timezone, end_timezone, origin, destination, location, event_id.
+    #[command(long_about = "List itinerary items.
+
+Each flight leg and hotel stay is one item. The 'email_id' is the
+confirmation email, so 'email show <email_id>' shows the original. By default
+only items that haven't ended yet are listed; use --after for past travel.
+
+EXAMPLES:
+  groundeffect travel list --human
+  groundeffect travel list --kind flight --after 2024-01-01 --before 2025-01-01")]
+    List {
+        /// Filter to a specific account by email address or alias
+        #[arg(long)]
+        account: Option<String>,
+        /// Only this kind: flight, hotel
+        #[arg(long, value_parser = parse_itinerary_kind)]
+        kind: Option<ItineraryKind>,
+        /// Only items ending after this date (format: YYYY-MM-DD, default: now)
+        #[arg(long)]
+        after: Option<String>,
+        /// Only items starting before this date (format: YYYY-MM-DD)
+        #[arg(long)]
+        before: Option<String>,
+        /// Maximum number of results (default: 50, max: 500)
+        #[arg(long, default_value = "50")]
+        limit: usize,
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
 }
 
 // ============================================================================
-// Calendar Command Handlers
+// Audit Commands
 // ============================================================================
 
-async fn handle_calendar_command(command: CalendarCommands, global_human: bool) -> Result<()> {
-    match command {
-        CalendarCommands::Search {
-            query,
-            after,
-            before,
-            calendar,
-            account,
-            limit,
-            human,
-        } => {
-            let human = human || global_human;
-            let config = Config::load().unwrap_or_default();
-            let db = Arc::new(Database::open(config.lancedb_dir()).await?);
+#[derive(Subcommand)]
+enum AuditCommands {
+    /// List recorded actions, newest first.
+    /// Returns JSON array with: id, timestamp, actor, action, account_id, params, success, error.
+    #[command(long_about = "List recorded actions, newest first.
 
-            // Initialize embedding engine with hybrid remote/local support
-            // Skip loading local model if using remote with BM25 fallback (saves CPU/memory)
-            let local_embedding = if config.search.remote_embeddings_enabled()
-                && config.search.embedding_fallback == EmbeddingFallback::Bm25
-            {
-                None
-            } else {
-                let model_type = EmbeddingModel::from_str(&config.search.embedding_model)
-                    .unwrap_or(EmbeddingModel::BgeBaseEn);
-                Some(Arc::new(EmbeddingEngine::from_cache(
-                    config.models_dir(),
-                    model_type,
-                    config.search.use_gpu,
-                )?))
-            };
-            let embedding = Arc::new(HybridEmbeddingProvider::from_search_config(
-                local_embedding,
-                &config.search,
-            )?);
+Every action that changes mail, calendars, or accounts is appended to a local
+audit log, whether it came from this CLI (actor 'cli') or an AI assistant over
+MCP (actor 'mcp'). Failed MCP attempts are recorded too, with the error.
 
-            let search_engine = SearchEngine::new(db.clone(), embedding);
+ACTIONS:
+  send_email, create_draft, update_draft, send_draft, delete_draft,
+  create_event, add_account, configure_account, rename_account,
+  delete_account, sync_reset, sync_extend, sync_resume_from
 
-            // Resolve account aliases
-            let accounts = if let Some(accts) = account {
-                let all_accounts = db.list_accounts().await?;
-                let resolved: Vec<String> = accts
-                    .iter()
-                    .filter_map(|a| resolve_account(&all_accounts, a))
-                    .collect();
-                if resolved.is_empty() {
-                    None
-                } else {
-                    Some(resolved)
-                }
-            } else {
-                None
-            };
+Long parameters such as email bodies are truncated. Previews (send without
+--confirm) and dry runs change nothing and are not recorded.
 
-            let options = CalendarSearchOptions {
-                accounts,
-                limit: limit.min(100),
-                calendar_id: calendar,
-                date_from: parse_date(&after, &config.general.timezone),
-                date_to: parse_date(&before, &config.general.timezone),
-            };
+EXAMPLES:
+  groundeffect audit list --since 2024-06-01 --human
+  groundeffect audit list --actor mcp --action send_email")]
+    List {
+        /// Only actions on or after this date (format: YYYY-MM-DD)
+        #[arg(long)]
+        since: Option<String>,
+        /// Only actions by: cli, mcp
+        #[arg(long, value_parser = parse_audit_actor)]
+        actor: Option<AuditActor>,
+        /// Only this action (e.g. send_email)
+        #[arg(long)]
+        action: Option<String>,
+        /// Filter to a specific account by email address or alias
+        #[arg(long)]
+        account: Option<String>,
+        /// Maximum number of results (default: 50, max: 1000)
+        #[arg(long, default_value = "50")]
+        limit: usize,
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
+}
 
-            let results = search_engine.search_calendar(&query, &options).await?;
+// ============================================================================
+// Search Commands
+// ============================================================================
 
-            if human {
-                if results.is_empty() {
-                    println!("No events found.");
-                } else {
-                    println!("\nFound {} events:\n", results.len());
-                    for result in &results {
-                        println!("📅 {} (score: {:.2})", result.event.summary, result.score);
-                        println!(
-                            "   When: {} - {}",
-                            format_event_time_human(&result.event.start),
-                            format_event_time_human(&result.event.end)
-                        );
-                        if let Some(loc) = &result.event.location {
-                            println!("   Where: {}", loc);
-                        }
-                        println!("   ID: {}", result.event.id);
-                        println!();
-                    }
-                }
-            } else {
-                let json_results: Vec<EventResult> = results
-                    .iter()
-                    .map(|r| EventResult::from_event(&r.event, Some(r.score)))
-                    .collect();
-                println!("{}", serde_json::to_string_pretty(&json_results)?);
-            }
-        }
+#[derive(Subcommand)]
+enum SearchCommands {
+    /// Show hit/miss counts of the MCP server's search result cache.
+    /// Returns JSON: {recorded, hits, misses, hit_rate, evictions, expired, invalidations, entries, capacity, ttl_secs, pid, updated_at}.
+    #[command(
+        long_about = "Show hit/miss counts of the MCP server's search result cache.
 
-        CalendarCommands::List { account, human } => {
-            let human = human || global_human;
-            let config = Config::load().unwrap_or_default();
-            let db = Database::open(config.lancedb_dir()).await?;
-            let accounts = db.list_accounts().await?;
+The MCP server caches email and calendar search results so an assistant
+repeating a search gets an instant answer. Entries are dropped when they
+are older than [search] cache_ttl_secs, when the cache is over
+[search] cache_entries (least recently used first), or as soon as the
+daemon writes to the table they came from.
 
-            let _ = account; // Filter not implemented yet
+Counts are written by the server that searched most recently and reset
+when it restarts.
 
-            if human {
-                println!("\n📅 Calendars:\n");
-                for account in &accounts {
-                    println!("Account: {}", account.id);
-                    let event_count = db.count_events(Some(&account.id)).await.unwrap_or(0);
-                    println!("  Events synced: {}", event_count);
-                    println!();
-                }
-            } else {
-                #[derive(Serialize)]
-                struct CalendarInfo {
-                    account: String,
-                    event_count: u64,
-                }
-                let mut results = Vec::new();
-                for account in &accounts {
-                    let event_count = db.count_events(Some(&account.id)).await.unwrap_or(0);
-                    results.push(CalendarInfo {
-                        account: account.id.clone(),
-                        event_count,
-                    });
-                }
-                println!("{}", serde_json::to_string_pretty(&results)?);
-            }
-        }
+EXAMPLES:
+  groundeffect search stats --human"
+    )]
+    Stats {
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
+}
 
-        CalendarCommands::Show { id, human } => {
-            let human = human || global_human;
-            let config = Config::load().unwrap_or_default();
-            let db = Database::open(config.lancedb_dir()).await?;
+// ============================================================================
+// Db Commands
+// ============================================================================
 
-            match db.get_event(&id).await? {
-                Some(event) => {
-                    if human {
-                        println!("\n📅 {}", event.summary);
-                        println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-                        println!(
-                            "When: {} - {}",
-                            format_event_time_human(&event.start),
-                            format_event_time_human(&event.end)
-                        );
-                        if let Some(loc) = &event.location {
-                            println!("Where: {}", loc);
-                        }
-                        // Show organizer
-                        if let Some(org) = &event.organizer {
-                            let org_name = org.name.as_deref().unwrap_or(&org.email);
-                            println!("\nOrganizer: {} <{}>", org_name, org.email);
-                        }
-                        // Show attendees with response status
-                        if !event.attendees.is_empty() {
-                            println!("\nAttendees ({}):", event.attendees.len());
-                            for attendee in &event.attendees {
-                                let name = attendee.name.as_deref().unwrap_or(&attendee.email);
-                                let status_icon = match &attendee.response_status {
-                                    Some(s) => match s {
-                                        groundeffect_core::models::AttendeeStatus::Accepted => "✓",
-                                        groundeffect_core::models::AttendeeStatus::Declined => "✗",
-                                        groundeffect_core::models::AttendeeStatus::Tentative => "?",
-                                        groundeffect_core::models::AttendeeStatus::NeedsAction => {
-                                            "?"
-                                        }
-                                    },
-                                    None => " ",
-                                };
-                                let optional_suffix =
-                                    if attendee.optional { " (optional)" } else { "" };
-                                println!(
-                                    "  {} {} <{}>{}",
-                                    status_icon, name, attendee.email, optional_suffix
-                                );
-                            }
-                        }
-                        // Show description last
-                        if let Some(desc) = &event.description {
-                            println!("\n{}", desc);
-                        }
-                    } else {
-                        #[derive(Serialize)]
-                        struct EventDetail {
-                            id: String,
-                            summary: String,
-                            start: String,
-                            end: String,
-                            #[serde(skip_serializing_if = "Option::is_none")]
-                            location: Option<String>,
-                            #[serde(skip_serializing_if = "Option::is_none")]
-                            description: Option<String>,
-                            #[serde(skip_serializing_if = "Option::is_none")]
-                            organizer: Option<AttendeeDetail>,
-                            attendees: Vec<AttendeeDetail>,
-                            account_id: String,
-                            calendar_id: String,
-                        }
-                        let detail = EventDetail {
-                            id: event.id.clone(),
+#[derive(Subcommand)]
+enum DbCommands {
+    /// Export or import embedding vectors for external embedding pipelines.
+    Vectors {
+        #[command(subcommand)]
+        command: VectorCommands,
+    },
+}
+
+#[derive(Subcommand)]
+enum VectorCommands {
+    /// Export (id, vector) pairs to a Parquet or JSON Lines file.
+    /// Returns JSON: {success, table, format, path, exported, with_vector, without_vector}.
+    #[command(
+        long_about = "Export (id, vector) pairs so embeddings can be computed elsewhere.
+
+Rows that have not been embedded yet are exported with a null vector. Use
+--with-text to include the text each embedding is computed from, and
+--missing-only to export just the rows that still need a vector.
+
+COLUMNS:
+  id      - Email or event ID
+  vector  - Embedding (768 floats, null if not embedded)
+  text    - Text to embed (only with --with-text)
+
+EXAMPLES:
+  groundeffect db vectors export --output vectors.parquet
+  groundeffect db vectors export --output todo.parquet --with-text --missing-only
+  groundeffect db vectors export --output events.jsonl --format jsonl --table events"
+    )]
+    Export {
+        /// Output file path
+        #[arg(long, short)]
+        output: PathBuf,
+        /// File format: parquet or jsonl
+        #[arg(long, default_value = "parquet")]
+        format: String,
+        /// Table to export: emails or events
+        #[arg(long, default_value = "emails")]
+        table: String,
+        /// Only export rows for this account (email or alias)
+        #[arg(long)]
+        account: Option<String>,
+        /// Include the text to embed
+        #[arg(long)]
+        with_text: bool,
+        /// Only export rows without an embedding
+        #[arg(long)]
+        missing_only: bool,
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
+    /// Import (id, vector) pairs, replacing stored embeddings.
+    /// Returns JSON: {success, table, imported, unknown_ids, dry_run}.
+    #[command(long_about = "Import externally computed embeddings.
+
+The file needs an 'id' column and a 'vector' column. Parquet vectors may be
+fixed- or variable-size lists of float32 or float64 (as written by pyarrow,
+polars or pandas). Every vector must have 768 dimensions; the whole file is
+validated before anything is written. IDs that don't exist are reported and
+skipped.
+
+Vectors should come from the same model the search side uses to embed
+queries (see 'groundeffect config settings --embedding-provider').
+
+EXAMPLES:
+  groundeffect db vectors import vectors.parquet
+  groundeffect db vectors import vectors.jsonl --dry-run
+  groundeffect db vectors import events.parquet --table events")]
+    Import {
+        /// Input file path
+        file: PathBuf,
+        /// File format: parquet or jsonl (default: from file extension)
+        #[arg(long)]
+        format: Option<String>,
+        /// Table to import into: emails or events
+        #[arg(long, default_value = "emails")]
+        table: String,
+        /// Validate the file without writing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
+}
+
+// ============================================================================
+// Index Commands
+// ============================================================================
+
+#[derive(Subcommand)]
+enum IndexCommands {
+    /// Show how many rows each full-text index covers and when the indexes were last updated.
+    /// Returns JSON: {strategy, fts: [{table, column, indexed_rows, unindexed_rows}], last_update, last_rebuild}.
+    #[command(long_about = "Show the state of the full-text indexes.
+
+New rows aren't in a full-text index until the daemon updates it; until
+then keyword search finds them with a slower scan. The daemon updates the
+indexes after index.fts_update_rows rows were written, or
+index.fts_max_interval_secs after the first unindexed write, whichever
+comes first.
+
+RESPONSE FIELDS:
+  strategy       incremental (index new rows only) or rebuild ([index] fts_strategy)
+  fts[]          {table, column, indexed_rows (null: no index), unindexed_rows}
+  last_update    {at, mode, duration_ms} of the latest update of either kind
+  last_rebuild   {at, mode, duration_ms} of the latest full rebuild
+
+EXAMPLES:
+  groundeffect index status --human")]
+    Status {
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
+    /// Rebuild the full-text indexes now instead of waiting for the daemon.
+    /// Returns JSON: {success, mode, at, duration_ms, fts: [...]}.
+    #[command(long_about = "Rebuild the full-text indexes now.
+
+Recreates each full-text index from every row, or with --incremental adds
+only the rows written since the last update. Runs in this process, so it
+works whether or not the daemon is running; the time is recorded for
+'index status'.
+
+EXAMPLES:
+  groundeffect index rebuild --fts
+  groundeffect index rebuild --fts --incremental --human")]
+    Rebuild {
+        /// Rebuild the full-text (keyword search) indexes
+        #[arg(long)]
+        fts: bool,
+        /// Only add rows written since the last update
+        #[arg(long)]
+        incremental: bool,
+        /// Human-readable output instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
+}
+
+// ============================================================================
+// Help Commands
+// ============================================================================
+
+#[derive(Subcommand)]
+enum HelpCommands {
+    /// Fuzzy-search all subcommands, flags and MCP tools, with usage examples.
+    /// Returns ranked matches; use --json for {query, results: [{kind, name, description, usage, examples, score}]}.
+    #[command(long_about = "Fuzzy-search everything groundeffect can do.
+
+Matches the words against every subcommand, flag and MCP tool (names,
+parameters and descriptions). Typos are tolerated, so 'calender invite'
+still finds calendar commands. Results are ranked and include usage lines
+and examples taken from the command help.
+
+KINDS:
+  command   - A CLI subcommand (e.g. 'email search')
+  flag      - A flag of a subcommand (e.g. 'email search --from')
+  mcp_tool  - A tool exposed by the MCP server (e.g. 'search_emails')
+
+EXAMPLES:
+  groundeffect help search download attachments
+  groundeffect help search calender invite --limit 5
+  groundeffect help search storage limit --json")]
+    Search {
+        /// Words to search for
+        #[arg(required = true)]
+        words: Vec<String>,
+        /// Maximum number of results
+        #[arg(long, default_value = "10")]
+        limit: usize,
+        /// Output JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+// ============================================================================
+// JSON Output Types
+// ============================================================================
+
+#[derive(Serialize)]
+struct EmailResult {
+    id: String,
+    from: String,
+    to: Vec<String>,
+    subject: String,
+    date: String,
+    folder: String,
+    account_id: String,
+    snippet: String,
+    is_sent: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    category: Option<EmailCategory>,
+    /// Tags added by local tagging rules
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    /// Failed SPF/DKIM/DMARC checks or a spoofed display name
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    security_warnings: Vec<String>,
+    /// The conversation in Gmail on the web
+    web_link: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    score: Option<f32>,
+    /// Whether the email is in the local index (remote search results)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    local: Option<bool>,
+    /// Added to the local index by this command
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    imported: bool,
+}
+
+impl EmailResult {
+    fn from_email(email: &Email, score: Option<f32>) -> Self {
+        Self {
+            id: email.id.clone(),
+            from: email.from.to_string(),
+            to: email.to.iter().map(|a| a.to_string()).collect(),
+            subject: email.subject.clone(),
+            date: email.date.to_rfc3339(),
+            folder: email.folder.clone(),
+            account_id: email.account_id.clone(),
+            snippet: email.snippet.clone(),
+            is_sent: email.is_sent,
+            category: email.category,
+            tags: email.tags.clone(),
+            security_warnings: email
+                .auth
+                .as_ref()
+                .map(|a| a.warnings.clone())
+                .unwrap_or_default(),
+            web_link: email.web_link(),
+            score,
+            local: None,
+            imported: false,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct EmailDetail {
+    id: String,
+    from: String,
+    to: Vec<String>,
+    cc: Vec<String>,
+    subject: String,
+    date: String,
+    folder: String,
+    account_id: String,
+    body: String,
+    thread_id: String,
+    /// The conversation in Gmail on the web
+    web_link: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    attachments: Option<Vec<AttachmentInfo>>,
+    /// SPF/DKIM/DMARC verdicts, Return-Path and warnings (received mail)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    auth: Option<EmailAuth>,
+    /// Only headers and the snippet are stored locally
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    headers_only: bool,
+    /// The body was downloaded from Gmail for this request
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    body_fetched: bool,
+    /// The email isn't in the local index and was fetched from Gmail
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    remote: bool,
+    /// Sent mail that bounced: failed or delayed (thread output)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    delivery_status: Option<DeliveryStatus>,
+    /// Recipients reported as undeliverable (thread output)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    failed_recipients: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct AttachmentInfo {
+    id: String,
+    filename: String,
+    mime_type: String,
+    size: u64,
+}
+
+impl EmailDetail {
+    fn from_email(email: &Email) -> Self {
+        let attachments = if email.attachments.is_empty() {
+            None
+        } else {
+            Some(
+                email
+                    .attachments
+                    .iter()
+                    .map(|a| AttachmentInfo {
+                        id: a.id.clone(),
+                        filename: a.filename.clone(),
+                        mime_type: a.mime_type.clone(),
+                        size: a.size,
+                    })
+                    .collect(),
+            )
+        };
+        Self {
+            id: email.id.clone(),
+            from: email.from.to_string(),
+            to: email.to.iter().map(|a| a.to_string()).collect(),
+            cc: email.cc.iter().map(|a| a.to_string()).collect(),
+            subject: email.subject.clone(),
+            date: email.date.to_rfc3339(),
+            folder: email.folder.clone(),
+            account_id: email.account_id.clone(),
+            body: match email.resolved_body() {
+                body if body.is_empty() && email.headers_only => email.snippet.clone(),
+                body => body,
+            },
+            thread_id: email.gmail_thread_id.to_string(),
+            web_link: email.web_link(),
+            attachments,
+            auth: email.auth.clone(),
+            headers_only: email.headers_only,
+            body_fetched: false,
+            remote: false,
+            delivery_status: None,
+            failed_recipients: Vec::new(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct EventResult {
+    id: String,
+    summary: String,
+    start: String,
+    end: String,
+    /// Start in the user's timezone
+    start_local: String,
+    end_local: String,
+    location: Option<String>,
+    account_id: String,
+    calendar_id: String,
+    /// The event in Google Calendar on the web
+    web_link: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    score: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    score_components: Option<ScoreComponents>,
+}
+
+impl EventResult {
+    fn from_event(event: &CalendarEvent, score: Option<f32>, tz: Tz) -> Self {
+        Self {
+            id: event.id.clone(),
+            summary: event.summary.clone(),
+            start: format_event_time(&event.start),
+            end: format_event_time(&event.end),
+            start_local: event.start.to_local_string(tz),
+            end_local: event.end.to_local_string(tz),
+            location: event.location.clone(),
+            account_id: event.account_id.clone(),
+            calendar_id: event.calendar_id.clone(),
+            web_link: event.web_link(),
+            score,
+            score_components: None,
+        }
+    }
+}
+
+fn format_event_time(et: &EventTime) -> String {
+    match et {
+        EventTime::DateTime(dt) => dt.to_rfc3339(),
+        EventTime::Date(d) => d.to_string(),
+    }
+}
+
+#[derive(Serialize)]
+struct AccountResult {
+    email: String,
+    alias: Option<String>,
+    display_name: String,
+    status: String,
+    added_at: String,
+    last_sync_email: Option<String>,
+    last_sync_calendar: Option<String>,
+}
+
+impl AccountResult {
+    fn from_account(account: &Account) -> Self {
+        Self {
+            email: account.id.clone(),
+            alias: account.alias.clone(),
+            display_name: account.display_name.clone(),
+            status: format!("{:?}", account.status),
+            added_at: account.added_at.to_rfc3339(),
+            last_sync_email: account.last_sync_email.map(|d| d.to_rfc3339()),
+            last_sync_calendar: account.last_sync_calendar.map(|d| d.to_rfc3339()),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct SyncStatus {
+    account: String,
+    status: String,
+    email_count: u64,
+    event_count: u64,
+    oldest_email: Option<String>,
+    newest_email: Option<String>,
+    oldest_event: Option<String>,
+    newest_event: Option<String>,
+    last_email_sync: Option<String>,
+    last_calendar_sync: Option<String>,
+    attachments_total: usize,
+    attachments_downloaded: usize,
+    attachments_size_bytes: u64,
+    sync_email_since: Option<String>,
+    sync_attachments: bool,
+    estimated_total_emails: Option<u64>,
+    emails_remaining: Option<u64>,
+    storage_used_bytes: u64,
+    max_storage_bytes: Option<u64>,
+    storage_quota_exceeded: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    retention_suggestions: Vec<String>,
+    #[serde(skip_serializing_if = "RetentionPolicy::is_empty")]
+    retention: RetentionPolicy,
+    last_prune: Option<PruneReport>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    headers_only: bool,
+}
+
+#[derive(Serialize)]
+struct DaemonStatus {
+    running: bool,
+    pid: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    health: Option<serde_json::Value>,
+    sync_policy: serde_json::Value,
+}
+
+// ============================================================================
+// Main Entry Point
+// ============================================================================
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let global_human = cli.human;
+    let dry_run = cli.dry_run;
+    let timeout = cli.timeout;
+    let use_daemon = !cli.no_daemon;
+
+    // Select the profile before any config or data path is resolved
+    if let Some(profile) = &cli.profile {
+        set_active_profile(profile)?;
+    }
+    set_cli_overrides(&cli.set)?;
+
+    let command = async move {
+        match cli.command {
+            Commands::Email { command } => {
+                handle_email_command(command, global_human, use_daemon).await
+            }
+            Commands::Calendar { command } => {
+                handle_calendar_command(command, global_human, use_daemon).await
+            }
+            Commands::Thread { command } => {
+                handle_thread_command(command, global_human, use_daemon).await
+            }
+            Commands::Account { command } => {
+                handle_account_command(command, global_human, dry_run).await
+            }
+            Commands::Sync { command } => handle_sync_command(command, global_human, dry_run).await,
+            Commands::Daemon { command } => handle_daemon_command(command, global_human).await,
+            Commands::Config { command } => {
+                handle_config_command(command, global_human, dry_run).await
+            }
+            Commands::Activity { command } => handle_activity_command(command, global_human).await,
+            Commands::Receipts { command } => handle_receipts_command(command, global_human).await,
+            Commands::Stats { command } => handle_stats_command(command, global_human).await,
+            Commands::Tasks { command } => handle_tasks_command(command, global_human).await,
+            Commands::Contact { command } => handle_contact_command(command, global_human).await,
+            Commands::Digest {
+                period,
+                account,
+                send,
+                human,
+            } => handle_digest_command(period, account, send, human || global_human).await,
+            Commands::Travel { command } => handle_travel_command(command, global_human).await,
+            Commands::Audit { command } => handle_audit_command(command, global_human).await,
+            Commands::Db { command } => handle_db_command(command, global_human, dry_run).await,
+            Commands::Index { command } => handle_index_command(command, global_human).await,
+            Commands::Search {
+                command: Some(command),
+                ..
+            } => handle_search_command(command, global_human),
+            Commands::Search {
+                command: None,
+                query,
+                types,
+                account,
+                limit,
+                include_spam_trash,
+                mode,
+                human,
+            } => {
+                let Some(query) = query else {
+                    anyhow::bail!(
+                        "Give a search query, e.g. groundeffect search \"quarterly report\""
+                    );
+                };
+                handle_unified_search(
+                    query,
+                    types,
+                    account,
+                    limit,
+                    include_spam_trash,
+                    mode,
+                    human || global_human,
+                )
+                .await
+            }
+            Commands::Quick {
+                query,
+                open,
+                account,
+                limit,
+            } => handle_quick_command(query, open, account, limit, use_daemon).await,
+            Commands::Storage {
+                top_senders_by_size,
+                account,
+                human,
+            } => handle_storage_command(top_senders_by_size, account, human || global_human).await,
+            Commands::Logs {
+                since,
+                level,
+                account,
+                source,
+                limit,
+                human,
+            } => handle_logs_command(since, level, account, source, limit, human || global_human),
+            Commands::Doctor {
+                offline,
+                quick,
+                human,
+            } => handle_doctor_command(offline, quick, human || global_human).await,
+            Commands::Help { command, path } => handle_help_command(command, path),
+        }
+    };
+
+    // Dropping the command future cancels its pending DB queries and HTTP
+    // calls at their next await point
+    let Some(limit) = timeout else {
+        return command.await;
+    };
+    match tokio::time::timeout(limit, command).await {
+        Ok(result) => result,
+        Err(_) => {
+            if global_human {
+                eprintln!("Error: timed out after {:?}", limit);
+            } else {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&serde_json::json!({
+                        "success": false,
+                        "error": format!("timed out after {:?}", limit),
+                        "code": "TIMEOUT",
+                        "timeout_ms": limit.as_millis() as u64,
+                    }))?
+                );
+            }
+            std::process::exit(TIMEOUT_EXIT_CODE);
+        }
+    }
+}
+
+/// Parse a `--timeout` value: a number with an `ms`, `s`, `m` or `h` suffix
+/// (plain numbers are seconds)
+fn parse_timeout(s: &str) -> std::result::Result<std::time::Duration, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let value: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid duration '{}' (e.g. 30s, 500ms, 2m)", s))?;
+    let duration = match unit {
+        "ms" => std::time::Duration::from_millis(value),
+        "" | "s" => std::time::Duration::from_secs(value),
+        "m" => std::time::Duration::from_secs(value * 60),
+        "h" => std::time::Duration::from_secs(value * 3600),
+        _ => {
+            return Err(format!(
+                "invalid duration unit '{}' (use ms, s, m or h)",
+                unit
+            ))
+        }
+    };
+    if duration.is_zero() {
+        return Err("timeout must be greater than zero".to_string());
+    }
+    Ok(duration)
+}
+
+// ============================================================================
+// Email Command Handlers
+// ============================================================================
+
+/// Sync's storage pipeline, for mail fetched from Gmail on request (loads
+/// the embedding model like [`load_search_engine`])
+async fn email_store(
+    config: &Config,
+    db: Arc<Database>,
+    oauth: Arc<OAuthManager>,
+) -> Result<SyncManager> {
+    let embedding = load_search_engine(config, db.clone()).await?.embedding();
+    Ok(SyncManager::new(
+        db,
+        Arc::new(config.clone()),
+        oauth,
+        embedding,
+    ))
+}
+
+/// Search engine for in-process searches, loading the embedding model
+/// (skipped when using a remote provider with BM25 fallback)
+async fn load_search_engine(config: &Config, db: Arc<Database>) -> Result<SearchEngine> {
+    let local_embedding = if config.search.remote_embeddings_enabled()
+        && config.search.embedding_fallback == EmbeddingFallback::Bm25
+    {
+        None
+    } else {
+        let model_type = EmbeddingModel::from_str(&config.search.embedding_model)
+            .unwrap_or(EmbeddingModel::BgeBaseEn);
+        Some(Arc::new(EmbeddingEngine::from_cache(
+            config.models_dir(),
+            model_type,
+            config.search.use_gpu,
+            config.search.embedding_precision,
+        )?))
+    };
+    let dimension = db.vector_dimension(VectorTable::Emails);
+    let embedding = Arc::new(
+        HybridEmbeddingProvider::from_search_config(local_embedding, &config.search)?
+            .with_vector_dimension(dimension),
+    );
+    Ok(SearchEngine::new(db, embedding))
+}
+
+async fn handle_email_command(
+    command: EmailCommands,
+    global_human: bool,
+    use_daemon: bool,
+) -> Result<()> {
+    match command {
+        EmailCommands::Search {
+            query,
+            from,
+            to,
+            after,
+            before,
+            folder,
+            has_attachment,
+            category,
+            tag,
+            sent,
+            received,
+            include_activity,
+            include_spam_trash,
+            account,
+            limit,
+            explain,
+            highlight,
+            mode,
+            recency_weight,
+            all_thread_messages,
+            mmr_lambda,
+            smart_query,
+            human,
+        } => {
+            let human = human || global_human;
+            let config = Config::load().unwrap_or_default();
+            let mut db = None;
+
+            // Resolve account aliases to IDs
+            let accounts = if let Some(accts) = account {
+                let handle = Arc::new(Database::open(config.lancedb_dir()).await?);
+                let all_accounts = handle.list_accounts().await?;
+                db = Some(handle);
+                let resolved: Vec<String> = accts
+                    .iter()
+                    .filter_map(|a| resolve_account(&all_accounts, a))
+                    .collect();
+                if resolved.is_empty() {
+                    None
+                } else {
+                    Some(resolved)
+                }
+            } else {
+                None
+            };
+
+            let mut options = SearchOptions::new(limit.min(100));
+            options.accounts = accounts;
+            options.folder = folder.map(|f| resolve_label_folder(&config, &f));
+            options.from = from;
+            options.to = to;
+            options.date_from = parse_date(&after, &config.general.timezone);
+            options.date_to = parse_date(&before, &config.general.timezone);
+            options.has_attachment = if has_attachment { Some(true) } else { None };
+            options.category = category;
+            options.tag = tag;
+            options.sent = match (sent, received) {
+                (true, _) => Some(true),
+                (_, true) => Some(false),
+                _ => None,
+            };
+            options.exclude_activity = config.activity.hide_from_search && !include_activity;
+            options.include_spam_trash = config.search.include_spam_trash || include_spam_trash;
+            options.highlight = highlight;
+            options.fusion = FusionOptions::from_config(&config.search).with_mode(mode);
+            options.recency = RecencyBoost::from_config(&config.search);
+            if let Some(weight) = recency_weight {
+                if !(0.0..=1.0).contains(&weight) {
+                    anyhow::bail!("--recency-weight must be between 0.0 and 1.0");
+                }
+                options.recency = options.recency.with_weight(weight);
+            }
+            options.diversity = Diversity::from_config(&config.search);
+            if all_thread_messages {
+                options.diversity.dedup_threads = false;
+            }
+            if let Some(lambda) = mmr_lambda {
+                if !(0.0..=1.0).contains(&lambda) {
+                    anyhow::bail!("--mmr-lambda must be between 0.0 and 1.0");
+                }
+                options.diversity.mmr_lambda = lambda;
+            }
+
+            let tz: Tz = config.general.timezone.parse().unwrap_or(Tz::UTC);
+            let understood = smart_query
+                .then(|| understand_query(&query, Utc::now().with_timezone(&tz)))
+                .filter(|u| !u.is_empty());
+            let query = match &understood {
+                Some(understood) => {
+                    understood.apply(&mut options);
+                    understood.query.clone()
+                }
+                None => query,
+            };
+
+            // Run in the daemon when it's up (database open, model loaded)
+            let proxied = if use_daemon {
+                control::search_emails(&config.control_socket_path(), &query, &options).await
+            } else {
+                None
+            };
+            let (results, plan) = match proxied {
+                Some(found) => found,
+                None => {
+                    let db = match db {
+                        Some(db) => db,
+                        None => Arc::new(Database::open(config.lancedb_dir()).await?),
+                    };
+                    load_search_engine(&config, db)
+                        .await?
+                        .search_emails_with_plan(&query, &options)
+                        .await?
+                }
+            };
+
+            if human {
+                if let Some(understood) = &understood {
+                    println!("Understood: {}", describe_understanding(understood, tz));
+                }
+                if explain {
+                    println!("Strategy: {} ({})", plan.strategy.as_str(), plan.reason);
+                    println!(
+                        "Mode: {}, fusion: {}",
+                        plan.mode.as_str(),
+                        plan.fusion.as_str()
+                    );
+                    if let Some(filter) = &plan.filter {
+                        println!("Filter: {}", filter);
+                    }
+                    for expansion in &plan.expansions {
+                        println!(
+                            "Expanded {}: \"{}\" (weight {:.2})",
+                            expansion.term, expansion.query, expansion.weight
+                        );
+                    }
+                    println!(
+                        "BM25 hits: {}, vector hits: {}, {}ms",
+                        plan.bm25_hits, plan.vector_hits, plan.elapsed_ms
+                    );
+                }
+                if results.is_empty() {
+                    println!("No emails found.");
+                } else {
+                    println!("\nFound {} emails:\n", results.len());
+                    for result in &results {
+                        let e = &result.email;
+                        println!("📧 {} (score: {:.2})", e.subject, result.score);
+                        if e.is_sent {
+                            let to: Vec<String> = e.to.iter().map(|a| a.to_string()).collect();
+                            println!("   To: {} (sent)", to.join(", "));
+                        } else {
+                            println!("   From: {}", e.from);
+                        }
+                        println!("   Date: {}", e.date.format("%Y-%m-%d %H:%M"));
+                        if !e.tags.is_empty() {
+                            println!("   Tags: {}", e.tags.join(", "));
+                        }
+                        println!("   {}", e.snippet);
+                        println!("   ID: {}", e.id);
+                        println!();
+                    }
+                }
+            } else {
+                let json_results: Vec<serde_json::Value> = results
+                    .iter()
+                    .map(|r| {
+                        serde_json::json!({
+                            "id": r.email.id,
+                            "from": r.email.from.to_string(),
+                            "to": r.email.to.iter().map(|a| a.to_string()).collect::<Vec<_>>(),
+                            "subject": r.email.subject,
+                            "date": r.email.date.to_rfc3339(),
+                            "snippet": r.email.snippet,
+                            "snippet_html": r.email.snippet_html,
+                            "account_id": r.email.account_id,
+                            "folder": r.email.folder,
+                            "is_sent": r.email.is_sent,
+                            "category": r.email.category,
+                            "tags": r.email.tags,
+                            "score": r.score,
+                            "score_components": r.score_components
+                        })
+                    })
+                    .collect();
+                if explain {
+                    let mut output = serde_json::json!({
+                        "plan": plan,
+                        "results": json_results,
+                    });
+                    if let Some(understood) = &understood {
+                        output["understood"] = serde_json::json!(understood);
+                    }
+                    println!("{}", serde_json::to_string_pretty(&output)?);
+                } else {
+                    println!("{}", serde_json::to_string_pretty(&json_results)?);
+                }
+            }
+        }
+
+        EmailCommands::Similar {
+            id,
+            account,
+            include_spam_trash,
+            limit,
+            human,
+        } => {
+            let human = human || global_human;
+            let config = Config::load().unwrap_or_default();
+            let db = Arc::new(Database::open(config.lancedb_dir()).await?);
+
+            let mut options = SearchOptions::new(limit.min(100));
+            if let Some(accts) = account {
+                let all_accounts = db.list_accounts().await?;
+                let resolved: Vec<String> = accts
+                    .iter()
+                    .filter_map(|a| resolve_account(&all_accounts, a))
+                    .collect();
+                if !resolved.is_empty() {
+                    options.accounts = Some(resolved);
+                }
+            }
+            options.include_spam_trash = config.search.include_spam_trash || include_spam_trash;
+
+            let results = load_search_engine(&config, db)
+                .await?
+                .similar_emails(&id, &options)
+                .await?;
+
+            if human {
+                if results.is_empty() {
+                    println!("No similar emails found.");
+                } else {
+                    println!("\nFound {} similar emails:\n", results.len());
+                    for result in &results {
+                        let e = &result.email;
+                        println!("📧 {} (similarity: {:.2})", e.subject, result.score);
+                        println!("   From: {}", e.from);
+                        println!("   Date: {}", e.date.format("%Y-%m-%d %H:%M"));
+                        println!("   {}", e.snippet);
+                        println!("   ID: {}", e.id);
+                        println!();
+                    }
+                }
+            } else {
+                let json_results: Vec<serde_json::Value> = results
+                    .iter()
+                    .map(|r| {
+                        serde_json::json!({
+                            "id": r.email.id,
+                            "from": r.email.from.to_string(),
+                            "to": r.email.to.iter().map(|a| a.to_string()).collect::<Vec<_>>(),
+                            "subject": r.email.subject,
+                            "date": r.email.date.to_rfc3339(),
+                            "snippet": r.email.snippet,
+                            "account_id": r.email.account_id,
+                            "folder": r.email.folder,
+                            "is_sent": r.email.is_sent,
+                            "category": r.email.category,
+                            "tags": r.email.tags,
+                            "score": r.score,
+                            "score_components": r.score_components
+                        })
+                    })
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&json_results)?);
+            }
+        }
+
+        EmailCommands::List {
+            account,
+            category,
+            suspicious,
+            limit,
+            human,
+        } => {
+            let human = human || global_human;
+            let config = Config::load().unwrap_or_default();
+            let db = Database::open(config.lancedb_dir()).await?;
+
+            let account_id = if let Some(acct) = account {
+                let all_accounts = db.list_accounts().await?;
+                resolve_account(&all_accounts, &acct)
+            } else {
+                None
+            };
+
+            let emails = db
+                .list_recent_emails(account_id.as_deref(), category, suspicious, limit.min(100))
+                .await?;
+
+            if human {
+                if emails.is_empty() {
+                    println!("No emails found.");
+                } else {
+                    println!("\nRecent {} emails:\n", emails.len());
+                    for email in &emails {
+                        println!("📧 {}", email.subject);
+                        println!("   From: {}", email.from);
+                        println!("   Date: {}", email.date.format("%Y-%m-%d %H:%M"));
+                        if let Some(auth) = email.auth.as_ref().filter(|a| a.suspicious) {
+                            println!("   ⚠️  {}", auth.warnings.join("; "));
+                        }
+                        println!("   ID: {}", email.id);
+                        println!();
+                    }
+                }
+            } else {
+                let json_results: Vec<EmailResult> = emails
+                    .iter()
+                    .map(|e| EmailResult::from_email(e, None))
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&json_results)?);
+            }
+        }
+
+        EmailCommands::RemoteSearch {
+            gmail_query,
+            account,
+            limit,
+            import,
+            human,
+        } => {
+            let human = human || global_human;
+            let config = Config::load().unwrap_or_default();
+            let db = Arc::new(Database::open(config.lancedb_dir()).await?);
+
+            let all_accounts = db.list_accounts().await?;
+            let account_ids = match account {
+                Some(a) => vec![resolve_account(&all_accounts, &a)
+                    .ok_or_else(|| anyhow::anyhow!("Account not found: {}", a))?],
+                None => all_accounts.into_iter().map(|a| a.id).collect(),
+            };
+            if account_ids.is_empty() {
+                anyhow::bail!("No accounts configured. Run 'groundeffect account add' first.");
+            }
+
+            let limit = limit.clamp(1, REMOTE_SEARCH_MAX_RESULTS);
+            let token_provider = create_token_provider(&config).await?;
+            let oauth = Arc::new(OAuthManager::new(token_provider));
+            let mut emails = Vec::new();
+            for account_id in &account_ids {
+                emails.extend(
+                    search_gmail(&config, oauth.clone(), account_id, &gmail_query, limit).await?,
+                );
+            }
+            emails.sort_by(|a, b| b.date.cmp(&a.date));
+            emails.truncate(limit);
+            resolve_categories(&mut emails, None);
+
+            let mut results = Vec::with_capacity(emails.len());
+            let mut to_import = Vec::new();
+            for email in &emails {
+                // Leave stored copies (and their embeddings) alone
+                let local = db.get_email(&email.id).await?.is_some();
+                let imported = import && !local;
+                if imported {
+                    to_import.push(email.clone());
+                }
+                let mut result = EmailResult::from_email(email, None);
+                result.local = Some(local || imported);
+                result.imported = imported;
+                results.push(result);
+            }
+            if !to_import.is_empty() {
+                email_store(&config, db.clone(), oauth.clone())
+                    .await?
+                    .store_fetched(&to_import)
+                    .await?;
+            }
+
+            if human {
+                if results.is_empty() {
+                    println!("No emails found on Gmail.");
+                } else {
+                    println!("\nFound {} emails on Gmail:\n", results.len());
+                    for (email, result) in emails.iter().zip(&results) {
+                        println!("📧 {}", email.subject);
+                        println!("   From: {}", email.from);
+                        println!("   Date: {}", email.date.format("%Y-%m-%d %H:%M"));
+                        println!("   {}", email.snippet);
+                        println!(
+                            "   ID: {}{}",
+                            email.id,
+                            if result.imported {
+                                " (imported)"
+                            } else if result.local == Some(true) {
+                                " (already local)"
+                            } else {
+                                ""
+                            }
+                        );
+                        println!();
+                    }
+                    if import {
+                        let imported = results.iter().filter(|r| r.imported).count();
+                        println!("Imported {} of {} emails.", imported, results.len());
+                    }
+                }
+            } else {
+                println!("{}", serde_json::to_string_pretty(&results)?);
+            }
+        }
+
+        EmailCommands::Unanswered {
+            since,
+            account,
+            limit,
+            human,
+        } => {
+            let human = human || global_human;
+            let config = Config::load().unwrap_or_default();
+            let db = Database::open(config.lancedb_dir()).await?;
+
+            let since = match parse_age(&since) {
+                Some(age) => Utc::now() - age,
+                None => {
+                    parse_date(&Some(since.clone()), &config.general.timezone).ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "Invalid --since '{}'. Use an age like 7d or YYYY-MM-DD",
+                            since
+                        )
+                    })?
+                }
+            };
+            let account_id = if let Some(acct) = account {
+                let all_accounts = db.list_accounts().await?;
+                resolve_account(&all_accounts, &acct)
+            } else {
+                None
+            };
+
+            let emails = db.list_emails_since(account_id.as_deref(), since).await?;
+            let own_addresses: Vec<String> = config.compose.identities.keys().cloned().collect();
+            let mut unanswered = find_unanswered(&emails, &own_addresses, Utc::now());
+            unanswered.truncate(limit.clamp(1, 500));
+
+            if human {
+                if unanswered.is_empty() {
+                    println!("Nothing waiting for a reply.");
+                } else {
+                    println!("\n{} emails waiting for a reply:\n", unanswered.len());
+                    for item in &unanswered {
+                        println!("📧 {}", item.email.subject);
+                        println!("   From: {}", item.email.from);
+                        println!(
+                            "   Waiting: {} day{}{}",
+                            item.waiting_days,
+                            if item.waiting_days == 1 { "" } else { "s" },
+                            if item.pending_count > 1 {
+                                format!(" ({} messages)", item.pending_count)
+                            } else {
+                                String::new()
+                            }
+                        );
+                        println!("   ID: {}", item.email.id);
+                        println!();
+                    }
+                }
+            } else {
+                println!("{}", serde_json::to_string_pretty(&unanswered)?);
+            }
+        }
+
+        EmailCommands::Show {
+            id,
+            message_id,
+            account,
+            format,
+            fetch,
+            remote,
+            save,
+            human,
+        } => {
+            let human = human || global_human;
+            let config = Config::load().unwrap_or_default();
+            let db = Arc::new(Database::open(config.lancedb_dir()).await?);
+
+            // Where to look on Gmail if the email isn't stored locally
+            let (mut email, id, remote_target) = match (id, message_id) {
+                (_, Some(raw)) => {
+                    let message_id = normalize_message_id(&raw)
+                        .ok_or_else(|| anyhow::anyhow!("Invalid message ID: {}", raw))?;
+                    let account_id = match account {
+                        Some(a) => {
+                            let all_accounts = db.list_accounts().await?;
+                            Some(
+                                resolve_account(&all_accounts, &a)
+                                    .ok_or_else(|| anyhow::anyhow!("Account not found: {}", a))?,
+                            )
+                        }
+                        None => None,
+                    };
+                    let email = db
+                        .get_emails_by_message_id(&message_id, account_id.as_deref())
+                        .await?
+                        .into_iter()
+                        .next();
+                    (
+                        email,
+                        format!("<{}>", message_id),
+                        Some((account_id, message_id)),
+                    )
+                }
+                (Some(id), None) => {
+                    let target = split_email_id(&id).map(|(a, m)| (Some(a.to_string()), m));
+                    (db.get_email(&id).await?, id, target)
+                }
+                (None, None) => unreachable!("clap requires an id or --message-id"),
+            };
+
+            let mut fetched_remotely = false;
+            if email.is_none() && remote {
+                let (account_id, message_id) = remote_target.ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "--remote needs --message-id or an email ID of the form <account>:<Message-ID>"
+                    )
+                })?;
+                let account_ids = match account_id {
+                    Some(account_id) => vec![account_id],
+                    None => db
+                        .list_accounts()
+                        .await?
+                        .into_iter()
+                        .map(|a| a.id)
+                        .collect(),
+                };
+                let token_provider = create_token_provider(&config).await?;
+                let oauth = Arc::new(OAuthManager::new(token_provider));
+                email =
+                    fetch_remote_email(&config, oauth.clone(), &account_ids, &message_id).await?;
+                if let Some(found) = email.as_mut() {
+                    fetched_remotely = true;
+                    if save {
+                        email_store(&config, db.clone(), oauth.clone())
+                            .await?
+                            .store_fetched(std::slice::from_ref(found))
+                            .await?;
+                        resolve_categories(std::slice::from_mut(found), None);
+                    }
+                }
+            }
+
+            let mut body_fetched = false;
+            if let Some(email) = email.as_mut().filter(|e| fetch && e.headers_only) {
+                let token_provider = create_token_provider(&config).await?;
+                let oauth = OAuthManager::new(token_provider);
+                fetch_gmail_body(&oauth, email).await?;
+                body_fetched = true;
+            }
+
+            match email {
+                Some(email) => {
+                    if format == "markdown" {
+                        print!(
+                            "{}",
+                            render::email_to_markdown(&email, &email.markdown_body())
+                        );
+                    } else if human {
+                        println!("\n📧 {}", email.subject);
+                        println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+                        println!("From: {}", email.from);
+                        println!(
+                            "To: {}",
+                            email
+                                .to
+                                .iter()
+                                .map(|a| a.to_string())
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        );
+                        if !email.cc.is_empty() {
+                            println!(
+                                "CC: {}",
+                                email
+                                    .cc
+                                    .iter()
+                                    .map(|a| a.to_string())
+                                    .collect::<Vec<_>>()
+                                    .join(", ")
+                            );
+                        }
+                        println!("Date: {}", email.date.format("%Y-%m-%d %H:%M:%S"));
+                        println!("Folder: {}", email.folder);
+                        println!("Link: {}", email.web_link());
+                        if fetched_remotely {
+                            println!(
+                                "Source: Gmail ({})",
+                                if save {
+                                    "saved locally"
+                                } else {
+                                    "not stored locally"
+                                }
+                            );
+                        }
+                        if let Some(auth) = &email.auth {
+                            println!("Auth: {}", format_auth_human(auth));
+                            if let Some(return_path) = &auth.return_path {
+                                println!("Return-Path: {}", return_path);
+                            }
+                            for warning in &auth.warnings {
+                                println!("⚠️  {}", warning);
+                            }
+                        }
+                        if !email.attachments.is_empty() {
+                            println!(
+                                "Attachments: {}",
+                                email
+                                    .attachments
+                                    .iter()
+                                    .map(|a| a.filename.as_str())
+                                    .collect::<Vec<_>>()
+                                    .join(", ")
+                            );
+                        }
+                        if email.headers_only && !body_fetched {
+                            println!("\n{}", email.snippet);
+                            println!(
+                                "\n(Headers-only account: body not stored. Add --fetch to download it from Gmail.)"
+                            );
+                        } else {
+                            println!("\n{}", email.resolved_body());
+                        }
+                    } else {
+                        let mut detail = EmailDetail::from_email(&email);
+                        detail.body_fetched = body_fetched;
+                        detail.remote = fetched_remotely;
+                        println!("{}", serde_json::to_string_pretty(&detail)?);
+                    }
+                }
+                None => {
+                    if human {
+                        println!("Email not found: {}", id);
+                        if !remote {
+                            println!("Add --remote to fetch it from Gmail.");
+                        }
+                    } else {
+                        println!("{{\"error\": \"Email not found\"}}");
+                    }
+                }
+            }
+        }
+
+        EmailCommands::Raw {
+            id,
+            headers,
+            output,
+            human,
+        } => {
+            let human = human || global_human;
+            let config = Config::load().unwrap_or_default();
+            let db = Database::open(config.lancedb_dir()).await?;
+            let email = db
+                .get_email(&id)
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("Email not found: {}", id))?;
+
+            let token_provider = create_token_provider(&config).await?;
+            let oauth = Arc::new(OAuthManager::new(token_provider));
+            let raw = fetch_raw(&config, oauth, &email).await?;
+            let bytes = if headers { raw.headers() } else { &raw.source };
+
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, bytes)?;
+                    if human {
+                        println!("✓ Wrote {} bytes to {}", bytes.len(), path.display());
+                    } else {
+                        println!(
+                            "{}",
+                            serde_json::json!({
+                                "status": "exported",
+                                "id": email.id,
+                                "message_id": email.message_id,
+                                "bytes": bytes.len(),
+                                "cached": raw.cached,
+                                "path": path,
+                            })
+                        );
+                    }
+                }
+                None => {
+                    use std::io::Write;
+                    std::io::stdout().write_all(bytes)?;
+                }
+            }
+        }
+
+        EmailCommands::Thread {
+            thread_id,
+            account,
+            format,
+            compact,
+            output,
+            human,
+        } => {
+            let human = human || global_human;
+            let config = Config::load().unwrap_or_default();
+            let db = Database::open(config.lancedb_dir()).await?;
+
+            // Parse thread_id as u64 (gmail_thread_id)
+            let thread_id_num: u64 = thread_id.parse().unwrap_or(0);
+
+            let account_id = if let Some(accts) = account {
+                let all_accounts = db.list_accounts().await?;
+                accts
+                    .first()
+                    .and_then(|a| resolve_account(&all_accounts, a))
+            } else {
+                None
+            };
+
+            let emails = db
+                .get_emails_by_thread(thread_id_num, account_id.as_deref())
+                .await?;
+            let delivery = thread_delivery(&emails);
+            let thread_details = || -> Vec<EmailDetail> {
+                emails
+                    .iter()
+                    .map(|email| {
+                        let mut detail = EmailDetail::from_email(email);
+                        if let Some(status) = delivery.get(&email.id) {
+                            detail.delivery_status = Some(status.status);
+                            detail.failed_recipients = status.failed_recipients.clone();
+                        }
+                        detail
+                    })
+                    .collect()
+            };
+
+            if format == "markdown" || output.is_some() {
+                if emails.is_empty() {
+                    anyhow::bail!("No emails found in thread: {}", thread_id);
+                }
+                let rendered = if format == "markdown" {
+                    let mode = if compact {
+                        RenderMode::Compact
+                    } else {
+                        RenderMode::Full
+                    };
+                    render::thread_to_markdown(&emails, mode)
+                } else {
+                    serde_json::to_string_pretty(&thread_details())?
+                };
+
+                match output {
+                    Some(path) => {
+                        std::fs::write(&path, &rendered)?;
+                        if human {
+                            println!("✓ Exported {} emails to {}", emails.len(), path.display());
+                        } else {
+                            println!(
+                                "{}",
+                                serde_json::json!({
+                                    "status": "exported",
+                                    "thread_id": thread_id,
+                                    "emails": emails.len(),
+                                    "format": format,
+                                    "path": path,
+                                })
+                            );
+                        }
+                    }
+                    None => print!("{}", rendered),
+                }
+            } else if human {
+                if emails.is_empty() {
+                    println!("No emails found in thread: {}", thread_id);
+                } else {
+                    println!("\nThread {} ({} emails):\n", thread_id, emails.len());
+                    for email in &emails {
+                        println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+                        println!("📧 {}", email.subject);
+                        println!("From: {}", email.from);
+                        println!("Date: {}", email.date.format("%Y-%m-%d %H:%M"));
+                        if let Some(warning) = delivery
+                            .get(&email.id)
+                            .and_then(|s| delivery_warning(s.status, &s.failed_recipients))
+                        {
+                            println!("{}", warning);
+                        }
+                        println!(
+                            "\n{}\n",
+                            email.resolved_body().chars().take(500).collect::<String>()
+                        );
+                    }
+                }
+            } else {
+                println!("{}", serde_json::to_string_pretty(&thread_details())?);
+            }
+        }
+
+        EmailCommands::SendStatus {
+            message_id,
+            account,
+            human,
+        } => {
+            let human = human || global_human;
+            let config = Config::load().unwrap_or_default();
+            let db = Database::open(config.lancedb_dir()).await?;
+
+            let account_id = match account {
+                Some(a) => {
+                    let all_accounts = db.list_accounts().await?;
+                    Some(
+                        resolve_account(&all_accounts, &a)
+                            .ok_or_else(|| anyhow::anyhow!("Account not found: {}", a))?,
+                    )
+                }
+                None => None,
+            };
+            let status = find_send_status(&db, &message_id, account_id.as_deref()).await?;
+
+            if human {
+                println!("\n📧 {}", status.subject);
+                println!(
+                    "   Sent: {} to {}",
+                    status.date.format("%Y-%m-%d %H:%M"),
+                    status.recipients.join(", ")
+                );
+                match delivery_warning(status.status, &status.failed_recipients) {
+                    Some(warning) => println!("   {}", warning),
+                    None => println!("   ✓ No bounces received"),
+                }
+                if !status.delayed_recipients.is_empty() {
+                    println!(
+                        "   ⏳ Still retrying: {}",
+                        status.delayed_recipients.join(", ")
+                    );
+                }
+                for bounce in &status.bounces {
+                    println!(
+                        "   Bounce {} ({}{}): {}",
+                        bounce.date.format("%Y-%m-%d %H:%M"),
+                        bounce.kind.as_str(),
+                        bounce
+                            .status
+                            .as_ref()
+                            .map(|s| format!(", {}", s))
+                            .unwrap_or_default(),
+                        bounce.diagnostic.as_deref().unwrap_or("no diagnostic")
+                    );
+                }
+                println!();
+            } else {
+                println!("{}", serde_json::to_string_pretty(&status)?);
+            }
+        }
+
+        EmailCommands::Send {
+            from,
+            to,
+            subject,
+            body,
+            cc,
+            bcc,
+            reply_to,
+            send_as,
+            html,
+            signature,
+            save_as_draft,
+            confirm,
+            strict_lint,
+            human,
+        } => {
+            let human = human || global_human;
+            email_send(
+                &from,
+                to,
+                &subject,
+                &body,
+                cc,
+                bcc,
+                reply_to,
+                send_as,
+                html,
+                &SignatureChoice::parse(&signature),
+                None,
+                save_as_draft,
+                confirm,
+                strict_lint,
+                human,
+            )
+            .await?;
+        }
+
+        EmailCommands::Forward {
+            ids,
+            to,
+            from,
+            comment,
+            subject,
+            cc,
+            bcc,
+            send_as,
+            html,
+            signature,
+            save_as_draft,
+            confirm,
+            strict_lint,
+            human,
+        } => {
+            let human = human || global_human;
+            let config = Config::load().unwrap_or_default();
+            let db = Database::open(config.lancedb_dir()).await?;
+            let oauth = Arc::new(OAuthManager::new(create_token_provider(&config).await?));
+
+            let mut emails = Vec::with_capacity(ids.len());
+            for id in &ids {
+                let email = db
+                    .get_email(id)
+                    .await?
+                    .ok_or_else(|| anyhow::anyhow!("Email not found: {}", id))?;
+                emails.push(email);
+            }
+            emails.sort_by_key(|e| e.date);
+
+            // Attachments are only loaded to send or save the forward (those
+            // sync didn't download come from Gmail); a preview lists them
+            let mut attachments = Vec::new();
+            if confirm || save_as_draft {
+                for email in &mut emails {
+                    attachments
+                        .extend(load_forward_attachments(&config, oauth.clone(), email).await?);
+                }
+            }
+
+            let from = from.unwrap_or_else(|| emails[0].account_id.clone());
+            let subject = subject.unwrap_or_else(|| forward_subject(&emails));
+            let forward = Forward {
+                emails,
+                attachments,
+            };
+            email_send(
+                &from,
+                to,
+                &subject,
+                comment.as_deref().unwrap_or_default(),
+                cc,
+                bcc,
+                None,
+                send_as,
+                html,
+                &SignatureChoice::parse(&signature),
+                Some(&forward),
+                save_as_draft,
+                confirm,
+                strict_lint,
+                human,
+            )
+            .await?;
+        }
+
+        EmailCommands::SuggestLabels {
+            account,
+            days,
+            min_size,
+            similarity,
+            limit,
+            apply,
+            confirm,
+            human,
+        } => {
+            let human = human || global_human;
+            if !(0.0..=1.0).contains(&similarity) {
+                anyhow::bail!("--similarity must be between 0 and 1");
+            }
+            let choices = apply
+                .iter()
+                .map(|choice| parse_label_choice(choice))
+                .collect::<Result<Vec<_>>>()?;
+
+            let config = Config::load().unwrap_or_default();
+            let db = Database::open(config.lancedb_dir()).await?;
+            let all_accounts = db.list_accounts().await?;
+            let account_id = match account {
+                Some(a) => resolve_account(&all_accounts, &a)
+                    .ok_or_else(|| anyhow::anyhow!("Account not found: {}", a))?,
+                None => match all_accounts.as_slice() {
+                    [only] => only.id.clone(),
+                    [] => anyhow::bail!(
+                        "No accounts configured. Run 'groundeffect account add' first."
+                    ),
+                    _ => anyhow::bail!("Several accounts are configured; pick one with --account"),
+                },
+            };
+
+            let oauth = OAuthManager::new(create_token_provider(&config).await?);
+            let since = Utc::now() - chrono::Duration::days(days as i64);
+            let emails = unlabeled_emails(&db, &oauth, &account_id, since).await?;
+            let embedded = emails.iter().filter(|e| e.embedding.is_some()).count();
+            let mut suggestions = suggest_labels(&emails, similarity, min_size);
+            suggestions.truncate(limit);
+
+            let mut applied = Vec::new();
+            for (index, name) in &choices {
+                let suggestion = suggestions
+                    .iter()
+                    .find(|s| s.index == *index)
+                    .ok_or_else(|| anyhow::anyhow!("No suggestion {}", index))?;
+                let label = name.clone().unwrap_or_else(|| suggestion.label.clone());
+                let mut entry = serde_json::json!({
+                    "index": index,
+                    "label": label,
+                    "messages": suggestion.gmail_message_ids.len(),
+                    "applied": false,
+                });
+                if confirm {
+                    let created =
+                        add_gmail_label(&oauth, &account_id, &label, &suggestion.gmail_message_ids)
+                            .await?;
+                    entry["applied"] = serde_json::json!(true);
+                    entry["label_id"] = serde_json::json!(created.id);
+                    record_audit(
+                        &db,
+                        "label_emails",
+                        Some(&account_id),
+                        serde_json::json!({
+                            "label": label,
+                            "email_ids": suggestion.email_ids,
+                        }),
+                    )
+                    .await;
+                }
+                applied.push(entry);
+            }
+
+            if human {
+                if embedded == 0 {
+                    println!(
+                        "No unlabeled mail with embeddings in the last {} days for {}.",
+                        days, account_id
+                    );
+                } else if suggestions.is_empty() {
+                    println!(
+                        "No groups of {} or more similar emails among {} unlabeled emails.",
+                        min_size, embedded
+                    );
+                } else {
+                    println!(
+                        "\n{} label suggestions from {} unlabeled emails (last {} days):\n",
+                        suggestions.len(),
+                        embedded,
+                        days
+                    );
+                    for s in &suggestions {
+                        println!(
+                            "{}. {} ({} emails, cohesion {:.2})",
+                            s.index, s.label, s.size, s.cohesion
+                        );
+                        println!("   Senders: {}", s.top_senders.join(", "));
+                        for example in &s.examples {
+                            println!(
+                                "   - {} | {} | {}",
+                                example.date.format("%Y-%m-%d"),
+                                example.from,
+                                example.subject
+                            );
+                        }
+                        println!();
+                    }
+                }
+                for entry in &applied {
+                    if entry["applied"] == true {
+                        println!(
+                            "✓ Labeled {} emails \"{}\"",
+                            entry["messages"],
+                            entry["label"].as_str().unwrap_or_default()
+                        );
+                    } else {
+                        println!(
+                            "Would label {} emails \"{}\"",
+                            entry["messages"],
+                            entry["label"].as_str().unwrap_or_default()
+                        );
+                    }
+                }
+                if !applied.is_empty() && !confirm {
+                    println!("Re-run with --confirm to add the labels in Gmail.");
+                }
+            } else {
+                let mut output = serde_json::json!({
+                    "account_id": account_id,
+                    "since": since.to_rfc3339(),
+                    "emails_considered": embedded,
+                    "suggestions": suggestions,
+                });
+                if !applied.is_empty() {
+                    output["applied"] = serde_json::json!(applied);
+                }
+                println!("{}", serde_json::to_string_pretty(&output)?);
+            }
+        }
+
+        EmailCommands::Draft { command } => {
+            handle_draft_command(command, global_human).await?;
+        }
+
+        EmailCommands::Extractions { command } => {
+            handle_extractions_command(command, global_human).await?;
+        }
+
+        EmailCommands::Attachment {
+            email_id,
+            filename,
+            attachment_id,
+            human,
+        } => {
+            let human = human || global_human;
+            email_attachment(
+                &email_id,
+                filename.as_deref(),
+                attachment_id.as_deref(),
+                human,
+            )
+            .await?;
+        }
+
+        EmailCommands::Folders {
+            account,
+            refresh,
+            human,
+        } => {
+            let human = human || global_human;
+            let config = Config::load().unwrap_or_default();
+            let db = Database::open(config.lancedb_dir()).await?;
+            let all_accounts = db.list_accounts().await?;
+
+            let account_ids: Vec<String> = match account {
+                Some(accts) => accts
+                    .iter()
+                    .filter_map(|a| resolve_account(&all_accounts, a))
+                    .collect(),
+                None => all_accounts.iter().map(|a| a.id.clone()).collect(),
+            };
+            if account_ids.is_empty() {
+                anyhow::bail!("No matching accounts found");
+            }
+
+            let token_provider = create_token_provider(&config).await?;
+            let oauth = OAuthManager::new(token_provider);
+
+            let mut caches = Vec::new();
+            for account_id in &account_ids {
+                caches.push(list_gmail_labels(&oauth, &config, account_id, refresh).await?);
+            }
+
+            if human {
+                for cache in &caches {
+                    println!("\n📁 Gmail Labels for {}:\n", cache.account_id);
+                    for label in &cache.labels {
+                        let counts = match (label.messages_total, label.messages_unread) {
+                            (Some(total), Some(unread)) if unread > 0 => {
+                                format!(" ({} messages, {} unread)", total, unread)
+                            }
+                            (Some(total), _) => format!(" ({} messages)", total),
+                            _ => String::new(),
+                        };
+                        let kind = match label.label_type {
+                            LabelType::System => "",
+                            LabelType::User => " [user]",
+                        };
+                        println!("  {}{}{}", label.imap_folder(), kind, counts);
+                    }
+                }
+                println!();
+            } else {
+                let results: Vec<serde_json::Value> = caches
+                    .iter()
+                    .map(|cache| {
+                        let folders: Vec<String> =
+                            cache.labels.iter().map(|l| l.imap_folder()).collect();
+                        serde_json::json!({
+                            "account": cache.account_id,
+                            "fetched_at": cache.fetched_at.to_rfc3339(),
+                            "folders": folders,
+                            "labels": cache.labels,
+                        })
+                    })
+                    .collect();
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&serde_json::json!({ "accounts": results }))?
+                );
+            }
+        }
+
+        EmailCommands::Signatures { account, human } => {
+            let human = human || global_human;
+            let config = Config::load().unwrap_or_default();
+            let db = Database::open(config.lancedb_dir()).await?;
+            let all_accounts = db.list_accounts().await?;
+
+            let account_ids: Vec<String> = match account {
+                Some(a) => vec![resolve_account(&all_accounts, &a)
+                    .ok_or_else(|| anyhow::anyhow!("Account not found: {}", a))?],
+                None => all_accounts.iter().map(|a| a.id.clone()).collect(),
+            };
+
+            let token_provider = create_token_provider(&config).await?;
+            let oauth = OAuthManager::new(token_provider);
+
+            let mut accounts = Vec::new();
+            for account_id in &account_ids {
+                accounts.push((
+                    account_id,
+                    fetch_gmail_signatures(&oauth, account_id).await?,
+                ));
+            }
+            let local_override = |send_as: &str| {
+                config
+                    .compose
+                    .identity(send_as)
+                    .and_then(|i| i.local_signature())
+                    .is_some()
+            };
+            let mut named: Vec<(&String, Signature)> = config
+                .compose
+                .signatures
+                .keys()
+                .filter_map(|name| config.compose.signature(name).map(|s| (name, s)))
+                .collect();
+            named.sort_by(|a, b| a.0.cmp(b.0));
+
+            if human {
+                for (account_id, send_as) in &accounts {
+                    println!("\n✍️  Signatures for {}:\n", account_id);
+                    for entry in send_as {
+                        println!(
+                            "  {}{}{}",
+                            entry.send_as,
+                            if entry.is_default { " (default)" } else { "" },
+                            if local_override(&entry.send_as) {
+                                " [overridden in config]"
+                            } else {
+                                ""
+                            }
+                        );
+                        match entry.signature() {
+                            Some(signature) => {
+                                for line in signature.text.lines() {
+                                    println!("    {}", line);
+                                }
+                            }
+                            None => println!("    (no Gmail signature)"),
+                        }
+                    }
+                }
+                if !named.is_empty() {
+                    println!("\nNamed signatures ([compose.signatures]):\n");
+                    for (name, signature) in &named {
+                        println!("  {}", name);
+                        for line in signature.text.lines() {
+                            println!("    {}", line);
+                        }
+                    }
+                }
+                println!();
+            } else {
+                let results: Vec<serde_json::Value> = accounts
+                    .iter()
+                    .map(|(account_id, send_as)| {
+                        let send_as: Vec<serde_json::Value> = send_as
+                            .iter()
+                            .map(|entry| {
+                                serde_json::json!({
+                                    "send_as": entry.send_as,
+                                    "display_name": entry.display_name,
+                                    "is_default": entry.is_default,
+                                    "signature": entry.signature(),
+                                    "local_override": local_override(&entry.send_as),
+                                })
+                            })
+                            .collect();
+                        serde_json::json!({ "account": account_id, "send_as": send_as })
+                    })
+                    .collect();
+                let named: serde_json::Map<String, serde_json::Value> = named
+                    .into_iter()
+                    .map(|(name, signature)| (name.clone(), serde_json::json!(signature)))
+                    .collect();
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&serde_json::json!({
+                        "accounts": results,
+                        "named": named,
+                    }))?
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// ============================================================================
+// Calendar Command Handlers
+// ============================================================================
+
+async fn handle_calendar_command(
+    command: CalendarCommands,
+    global_human: bool,
+    use_daemon: bool,
+) -> Result<()> {
+    match command {
+        CalendarCommands::Search {
+            query,
+            after,
+            before,
+            calendar,
+            account,
+            limit,
+            mode,
+            human,
+        } => {
+            let human = human || global_human;
+            let config = Config::load().unwrap_or_default();
+            let mut db = None;
+
+            // Resolve account aliases
+            let accounts = if let Some(accts) = account {
+                let handle = Arc::new(Database::open(config.lancedb_dir()).await?);
+                let all_accounts = handle.list_accounts().await?;
+                db = Some(handle);
+                let resolved: Vec<String> = accts
+                    .iter()
+                    .filter_map(|a| resolve_account(&all_accounts, a))
+                    .collect();
+                if resolved.is_empty() {
+                    None
+                } else {
+                    Some(resolved)
+                }
+            } else {
+                None
+            };
+
+            let options = CalendarSearchOptions {
+                accounts,
+                limit: limit.min(100),
+                calendar_id: calendar,
+                date_from: parse_date(&after, &config.general.timezone),
+                date_to: parse_date(&before, &config.general.timezone),
+                fusion: FusionOptions::from_config(&config.search).with_mode(mode),
+            };
+
+            // Run in the daemon when it's up (database open, model loaded)
+            let proxied = if use_daemon {
+                control::search_calendar(&config.control_socket_path(), &query, &options).await
+            } else {
+                None
+            };
+            let results = match proxied {
+                Some(results) => results,
+                None => {
+                    let db = match db {
+                        Some(db) => db,
+                        None => Arc::new(Database::open(config.lancedb_dir()).await?),
+                    };
+                    load_search_engine(&config, db)
+                        .await?
+                        .search_calendar(&query, &options)
+                        .await?
+                }
+            };
+
+            let tz: Tz = config.general.timezone.parse().unwrap_or(Tz::UTC);
+            if human {
+                if results.is_empty() {
+                    println!("No events found.");
+                } else {
+                    println!("\nFound {} events:\n", results.len());
+                    for result in &results {
+                        println!("📅 {} (score: {:.2})", result.event.summary, result.score);
+                        println!(
+                            "   When: {}",
+                            format_event_span_human(&result.event.start, &result.event.end, tz)
+                        );
+                        if let Some(loc) = &result.event.location {
+                            println!("   Where: {}", loc);
+                        }
+                        println!("   ID: {}", result.event.id);
+                        println!();
+                    }
+                }
+            } else {
+                let json_results: Vec<EventResult> = results
+                    .iter()
+                    .map(|r| EventResult {
+                        score_components: Some(r.score_components.clone()),
+                        ..EventResult::from_event(&r.event, Some(r.score), tz)
+                    })
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&json_results)?);
+            }
+        }
+
+        CalendarCommands::List { account, human } => {
+            let human = human || global_human;
+            let config = Config::load().unwrap_or_default();
+            let db = Database::open(config.lancedb_dir()).await?;
+            let accounts = db.list_accounts().await?;
+
+            let _ = account; // Filter not implemented yet
+
+            if human {
+                println!("\n📅 Calendars:\n");
+                for account in &accounts {
+                    println!("Account: {}", account.id);
+                    let event_count = db.count_events(Some(&account.id)).await.unwrap_or(0);
+                    println!("  Events synced: {}", event_count);
+                    println!();
+                }
+            } else {
+                #[derive(Serialize)]
+                struct CalendarInfo {
+                    account: String,
+                    event_count: u64,
+                }
+                let mut results = Vec::new();
+                for account in &accounts {
+                    let event_count = db.count_events(Some(&account.id)).await.unwrap_or(0);
+                    results.push(CalendarInfo {
+                        account: account.id.clone(),
+                        event_count,
+                    });
+                }
+                println!("{}", serde_json::to_string_pretty(&results)?);
+            }
+        }
+
+        CalendarCommands::Show { id, human } => {
+            let human = human || global_human;
+            let config = Config::load().unwrap_or_default();
+            let db = Database::open(config.lancedb_dir()).await?;
+
+            let tz: Tz = config.general.timezone.parse().unwrap_or(Tz::UTC);
+            match db.get_event(&id).await? {
+                Some(event) => {
+                    // Read-through directory profiles, opt-in via [directory] attendee_profiles
+                    let profiles = if config.directory.attendee_profiles {
+                        let token_provider = create_token_provider(&config).await?;
+                        let oauth = OAuthManager::new(token_provider);
+                        let emails: Vec<String> = event
+                            .organizer
+                            .iter()
+                            .chain(&event.attendees)
+                            .map(|a| a.email.clone())
+                            .collect();
+                        attendee_profiles(&oauth, &config, &event.account_id, &emails).await
+                    } else {
+                        HashMap::new()
+                    };
+
+                    if human {
+                        println!("\n📅 {}", event.summary);
+                        println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+                        println!(
+                            "When: {} ({})",
+                            format_event_span_human(&event.start, &event.end, tz),
+                            tz
+                        );
+                        if let Some(loc) = &event.location {
+                            println!("Where: {}", loc);
+                        }
+                        if let Some(conference) = &event.conference {
+                            println!("Join: {}", format_conference_human(conference));
+                        }
+                        if event.transparency == Transparency::Transparent {
+                            println!("Show as: free");
+                        }
+                        if event.visibility != Visibility::Default {
+                            println!("Visibility: {}", event.visibility.as_str());
+                        }
+                        if let Some(color) = event.color_id.as_deref().and_then(event_color_name) {
+                            println!("Color: {}", color);
+                        }
+                        if !event.reminders.is_empty() {
+                            println!("Reminders: {}", format_reminders_human(&event.reminders));
+                        }
+                        println!("Link: {}", event.web_link());
+                        // Show organizer
+                        if let Some(org) = &event.organizer {
+                            let org_name = org.name.as_deref().unwrap_or(&org.email);
+                            println!("\nOrganizer: {} <{}>", org_name, org.email);
+                            print_attendee_profile(&profiles, &org.email, "  ");
+                        }
+                        // Show attendees with response status
+                        if !event.attendees.is_empty() {
+                            println!("\nAttendees ({}):", event.attendees.len());
+                            for attendee in &event.attendees {
+                                let name = attendee.name.as_deref().unwrap_or(&attendee.email);
+                                let status_icon = match &attendee.response_status {
+                                    Some(s) => match s {
+                                        groundeffect_core::models::AttendeeStatus::Accepted => "✓",
+                                        groundeffect_core::models::AttendeeStatus::Declined => "✗",
+                                        groundeffect_core::models::AttendeeStatus::Tentative => "?",
+                                        groundeffect_core::models::AttendeeStatus::NeedsAction => {
+                                            "?"
+                                        }
+                                    },
+                                    None => " ",
+                                };
+                                let optional_suffix =
+                                    if attendee.optional { " (optional)" } else { "" };
+                                println!(
+                                    "  {} {} <{}>{}",
+                                    status_icon, name, attendee.email, optional_suffix
+                                );
+                                print_attendee_profile(&profiles, &attendee.email, "      ");
+                            }
+                        }
+                        // Show description last
+                        if let Some(desc) = &event.description {
+                            println!("\n{}", desc);
+                        }
+                    } else {
+                        #[derive(Serialize)]
+                        struct EventDetail {
+                            id: String,
+                            summary: String,
+                            start: String,
+                            end: String,
+                            start_local: String,
+                            end_local: String,
+                            /// Timezone the event was created in
+                            timezone: String,
+                            #[serde(skip_serializing_if = "Option::is_none")]
+                            location: Option<String>,
+                            #[serde(skip_serializing_if = "Option::is_none")]
+                            description: Option<String>,
+                            /// Video call to join
+                            #[serde(skip_serializing_if = "Option::is_none")]
+                            conference: Option<Conference>,
+                            /// "busy" or "free"
+                            show_as: &'static str,
+                            visibility: Visibility,
+                            #[serde(skip_serializing_if = "Option::is_none")]
+                            color: Option<&'static str>,
+                            #[serde(skip_serializing_if = "Vec::is_empty")]
+                            reminders: Vec<Reminder>,
+                            #[serde(skip_serializing_if = "Option::is_none")]
+                            organizer: Option<AttendeeDetail>,
+                            attendees: Vec<AttendeeDetail>,
+                            #[serde(skip_serializing_if = "HashMap::is_empty")]
+                            attendee_profiles: HashMap<String, DirectoryPerson>,
+                            account_id: String,
+                            calendar_id: String,
+                            /// The event in Google Calendar on the web
+                            web_link: String,
+                        }
+                        let detail = EventDetail {
+                            id: event.id.clone(),
                             summary: event.summary.clone(),
                             start: format_event_time(&event.start),
                             end: format_event_time(&event.end),
+                            start_local: event.start.to_local_string(tz),
+                            end_local: event.end.to_local_string(tz),
+                            timezone: event.timezone.clone(),
                             location: event.location.clone(),
                             description: event.description.clone(),
+                            conference: event.conference.clone(),
+                            show_as: event.transparency.show_as(),
+                            visibility: event.visibility,
+                            color: event.color_id.as_deref().and_then(event_color_name),
+                            reminders: event.reminders.clone(),
                             organizer: event.organizer.as_ref().map(|o| AttendeeDetail {
                                 email: o.email.clone(),
                                 name: o.name.clone(),
@@ -2004,389 +5656,3761 @@ async fn handle_calendar_command(command: CalendarCommands, global_human: bool)
                                     optional: a.optional,
                                 })
                                 .collect(),
+                            attendee_profiles: profiles,
                             account_id: event.account_id.clone(),
                             calendar_id: event.calendar_id.clone(),
+                            web_link: event.web_link(),
+                        };
+                        println!("{}", serde_json::to_string_pretty(&detail)?);
+                    }
+                }
+                None => {
+                    if human {
+                        println!("Event not found: {}", id);
+                    } else {
+                        println!("{{\"error\": \"Event not found\"}}");
+                    }
+                }
+            }
+        }
+
+        CalendarCommands::Events {
+            from,
+            to,
+            account,
+            limit,
+            human,
+            grid,
+            show_as,
+            visibility,
+            color,
+        } => {
+            let human = human || grid || global_human;
+            let options = EventOptions::parse(
+                color.as_deref(),
+                visibility.as_deref(),
+                show_as.as_deref(),
+                None,
+            )?;
+            let filter = EventFilter {
+                show_as: options.transparency,
+                visibility: options.visibility,
+                color_id: options.color_id,
+            };
+            let config = Config::load().unwrap_or_default();
+            let db = Database::open(config.lancedb_dir()).await?;
+
+            // Default to today if --from not specified
+            let from_date = match &from {
+                Some(d) => d.clone(),
+                None => chrono::Utc::now().format("%Y-%m-%d").to_string(),
+            };
+
+            // Default to 7 days after from if --to not specified
+            let to_date = match &to {
+                Some(d) => d.clone(),
+                None => {
+                    let from_parsed = chrono::NaiveDate::parse_from_str(&from_date, "%Y-%m-%d")
+                        .unwrap_or_else(|_| chrono::Utc::now().date_naive());
+                    (from_parsed + chrono::Duration::days(7))
+                        .format("%Y-%m-%d")
+                        .to_string()
+                }
+            };
+
+            let accounts_ref = account
+                .as_ref()
+                .map(|v| v.iter().map(|s| s.clone()).collect::<Vec<_>>());
+            let events = db
+                .list_events_in_range(
+                    accounts_ref.as_deref(),
+                    &from_date,
+                    &to_date,
+                    &filter,
+                    limit.min(200),
+                )
+                .await?;
+
+            let tz: Tz = config.general.timezone.parse().unwrap_or(Tz::UTC);
+            if human {
+                if events.is_empty() {
+                    println!("No events found from {} to {}.", from_date, to_date);
+                } else if grid {
+                    let parse = |d: &str| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d");
+                    match (parse(&from_date), parse(&to_date)) {
+                        (Ok(start), Ok(end)) => print_week_grid(&events, start, end, tz),
+                        _ => println!("--grid needs --from and --to as YYYY-MM-DD"),
+                    }
+                } else {
+                    println!(
+                        "\n📅 Events from {} to {} ({} events)\n",
+                        from_date,
+                        to_date,
+                        events.len()
+                    );
+                    let mut current_date = String::new();
+                    for event in &events {
+                        let event_date = match &event.start {
+                            EventTime::DateTime(dt) => {
+                                dt.with_timezone(&tz).format("%Y-%m-%d").to_string()
+                            }
+                            EventTime::Date(d) => d.to_string(),
+                        };
+                        if event_date != current_date {
+                            current_date = event_date.clone();
+                            // Parse and format as weekday
+                            if let Ok(d) =
+                                chrono::NaiveDate::parse_from_str(&current_date, "%Y-%m-%d")
+                            {
+                                println!("━━ {} ━━", d.format("%A, %B %e, %Y"));
+                            } else {
+                                println!("━━ {} ━━", current_date);
+                            }
+                        }
+                        let time_str = match &event.start {
+                            EventTime::DateTime(dt) => {
+                                dt.with_timezone(&tz).format("%l:%M %p").to_string()
+                            }
+                            EventTime::Date(_) => "All day".to_string(),
+                        };
+                        let duration = match (&event.start, &event.end) {
+                            (EventTime::DateTime(s), EventTime::DateTime(e)) => {
+                                let mins = (*e - *s).num_minutes();
+                                if mins >= 60 {
+                                    format!(" ({}h{}m)", mins / 60, mins % 60)
+                                } else {
+                                    format!(" ({}m)", mins)
+                                }
+                            }
+                            (EventTime::Date(s), EventTime::Date(e))
+                                if (*e - *s).num_days() > 1 =>
+                            {
+                                format!(" ({} days)", (*e - *s).num_days())
+                            }
+                            _ => String::new(),
+                        };
+                        let free = if event.transparency == Transparency::Transparent {
+                            " [free]"
+                        } else {
+                            ""
+                        };
+                        println!(
+                            "  {} {}{}{}",
+                            time_str.trim(),
+                            event.summary,
+                            duration,
+                            free
+                        );
+                        if let Some(loc) = &event.location {
+                            if !loc.is_empty() {
+                                println!("          📍 {}", loc);
+                            }
+                        }
+                        if let Some(conference) = &event.conference {
+                            println!("          🎥 {}", format_conference_human(conference));
+                        }
+                        // Show organizer info if it's someone else's event
+                        if let Some(org) = &event.organizer {
+                            let org_name = org.name.as_deref().unwrap_or(&org.email);
+                            // Check if organizer is different from the account
+                            if org.email != event.account_id {
+                                println!("          👤 Invited by: {}", org_name);
+                            }
+                        }
+                    }
+                    println!();
+                }
+            } else {
+                #[derive(Serialize)]
+                struct EventResult {
+                    id: String,
+                    summary: String,
+                    start: String,
+                    end: String,
+                    start_local: String,
+                    end_local: String,
+                    #[serde(skip_serializing_if = "Option::is_none")]
+                    location: Option<String>,
+                    #[serde(skip_serializing_if = "Option::is_none")]
+                    conference: Option<Conference>,
+                    show_as: &'static str,
+                    visibility: Visibility,
+                    #[serde(skip_serializing_if = "Option::is_none")]
+                    color: Option<&'static str>,
+                    #[serde(skip_serializing_if = "Option::is_none")]
+                    organizer: Option<AttendeeDetail>,
+                    attendees: Vec<AttendeeDetail>,
+                    account_id: String,
+                    calendar_id: String,
+                    web_link: String,
+                }
+                let results: Vec<EventResult> = events
+                    .iter()
+                    .map(|e| EventResult {
+                        id: e.id.clone(),
+                        summary: e.summary.clone(),
+                        start: format_event_time(&e.start),
+                        end: format_event_time(&e.end),
+                        start_local: e.start.to_local_string(tz),
+                        end_local: e.end.to_local_string(tz),
+                        location: e.location.clone(),
+                        conference: e.conference.clone(),
+                        show_as: e.transparency.show_as(),
+                        visibility: e.visibility,
+                        color: e.color_id.as_deref().and_then(event_color_name),
+                        organizer: e.organizer.as_ref().map(|o| AttendeeDetail {
+                            email: o.email.clone(),
+                            name: o.name.clone(),
+                            response_status: o
+                                .response_status
+                                .as_ref()
+                                .map(|s| format!("{:?}", s).to_lowercase()),
+                            optional: o.optional,
+                        }),
+                        attendees: e
+                            .attendees
+                            .iter()
+                            .map(|a| AttendeeDetail {
+                                email: a.email.clone(),
+                                name: a.name.clone(),
+                                response_status: a
+                                    .response_status
+                                    .as_ref()
+                                    .map(|s| format!("{:?}", s).to_lowercase()),
+                                optional: a.optional,
+                            })
+                            .collect(),
+                        account_id: e.account_id.clone(),
+                        calendar_id: e.calendar_id.clone(),
+                        web_link: e.web_link(),
+                    })
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&results)?);
+            }
+        }
+
+        CalendarCommands::Create {
+            account,
+            summary,
+            start,
+            end,
+            all_day,
+            description,
+            location,
+            attendees,
+            calendar,
+            timezone,
+            recurrence,
+            repeat,
+            on,
+            until,
+            count,
+            with_meet,
+            color,
+            visibility,
+            show_as,
+            reminder,
+            no_reminders,
+            force,
+            human,
+        } => {
+            let human = human || global_human;
+            let options = parse_event_options(
+                color.as_deref(),
+                visibility.as_deref(),
+                show_as.as_deref(),
+                &reminder,
+                no_reminders,
+            )?;
+            let until = match until {
+                Some(until) => Some(
+                    chrono::NaiveDate::parse_from_str(&until, "%Y-%m-%d").map_err(|_| {
+                        anyhow::anyhow!("Invalid --until '{}' (use YYYY-MM-DD)", until)
+                    })?,
+                ),
+                None => None,
+            };
+            let recurrence = Recurrence {
+                rule: recurrence,
+                repeat,
+                on,
+                until,
+                count,
+            };
+            calendar_create(
+                &account,
+                &summary,
+                &start,
+                end.as_deref(),
+                all_day,
+                description.as_deref(),
+                location.as_deref(),
+                attendees,
+                &calendar,
+                timezone.as_deref(),
+                &recurrence,
+                with_meet,
+                &options,
+                force,
+                human,
+            )
+            .await?;
+        }
+
+        CalendarCommands::Update {
+            id,
+            summary,
+            description,
+            location,
+            color,
+            visibility,
+            show_as,
+            reminder,
+            no_reminders,
+            human,
+        } => {
+            let human = human || global_human;
+            let options = parse_event_options(
+                color.as_deref(),
+                visibility.as_deref(),
+                show_as.as_deref(),
+                &reminder,
+                no_reminders,
+            )?;
+            calendar_update(
+                &id,
+                summary.as_deref(),
+                description.as_deref(),
+                location.as_deref(),
+                &options,
+                human,
+            )
+            .await?;
+        }
+
+        CalendarCommands::Availability {
+            account,
+            refresh,
+            human,
+        } => {
+            let human = human || global_human;
+            let config = Config::load().unwrap_or_default();
+            let db = Database::open(config.lancedb_dir()).await?;
+            let all_accounts = db.list_accounts().await?;
+            let hours = WorkingHours::from_config(&config.availability, &config.general.timezone)?;
+
+            let account_ids: Vec<String> = match account {
+                Some(accts) => accts
+                    .iter()
+                    .filter_map(|a| resolve_account(&all_accounts, a))
+                    .collect(),
+                None => all_accounts.iter().map(|a| a.id.clone()).collect(),
+            };
+            let token_provider = create_token_provider(&config).await?;
+            let oauth = OAuthManager::new(token_provider);
+
+            let mut states = Vec::new();
+            for account_id in &account_ids {
+                match load_availability(&oauth, &config, account_id, refresh).await {
+                    Ok(state) => states.push(state),
+                    Err(e) => eprintln!("⚠️  Couldn't load availability for {}: {}", account_id, e),
+                }
+            }
+            let context = availability_context(&hours, &states, Utc::now());
+
+            if human {
+                let tz = hours.timezone;
+                let when = |time: DateTime<Utc>| {
+                    time.with_timezone(&tz)
+                        .format("%a %b %-d %H:%M")
+                        .to_string()
+                };
+                println!(
+                    "\n🕘 Working hours: {} {}–{} ({})",
+                    context.working_hours.days.join(", "),
+                    context.working_hours.start,
+                    context.working_hours.end,
+                    context.timezone
+                );
+                let status = match (context.away_now, context.in_working_hours) {
+                    (true, _) => "away",
+                    (false, true) => "working",
+                    (false, false) => "outside working hours",
+                };
+                println!("   Now: {}", status);
+                if let Some(next) = context.next_available.filter(|_| status != "working") {
+                    println!("   Next available: {}", when(next));
+                }
+
+                if context.away.is_empty() {
+                    println!("\nNo upcoming out-of-office blocks.");
+                } else {
+                    println!("\n🏝️  Away:");
+                    for block in &context.away {
+                        let span = match (block.start, block.end) {
+                            (Some(start), Some(end)) => format!("{} → {}", when(start), when(end)),
+                            (Some(start), None) => format!("from {}", when(start)),
+                            (None, Some(end)) => format!("until {}", when(end)),
+                            (None, None) => "until turned off".to_string(),
                         };
-                        println!("{}", serde_json::to_string_pretty(&detail)?);
+                        let label = match block.source {
+                            "vacation_responder" => "Vacation responder",
+                            _ => block.summary.as_deref().unwrap_or("Out of office"),
+                        };
+                        println!("   {}  {}  ({})", span, label, block.account_id);
+                    }
+                }
+            } else {
+                println!("{}", serde_json::to_string_pretty(&context)?);
+            }
+        }
+
+        CalendarCommands::Feed { output } => {
+            let config = Config::load().unwrap_or_default();
+            let db = Database::open(config.lancedb_dir()).await?;
+            let ics = build_feed(&db, &config, Utc::now()).await?;
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, &ics)?;
+                    eprintln!(
+                        "Wrote {} events to {}",
+                        ics.matches("BEGIN:VEVENT").count(),
+                        path.display()
+                    );
+                }
+                None => print!("{}", ics),
+            }
+        }
+        CalendarCommands::Propose {
+            attendees,
+            duration,
+            window,
+            account,
+            limit,
+            hold,
+            title,
+            human,
+        } => {
+            let human = human || global_human;
+            let config = Config::load().unwrap_or_default();
+            let db = Database::open(config.lancedb_dir()).await?;
+            let all_accounts = db.list_accounts().await?;
+            let account_id = match account {
+                Some(a) => resolve_account(&all_accounts, &a)
+                    .ok_or_else(|| anyhow::anyhow!("Account not found: {}", a))?,
+                None => all_accounts
+                    .first()
+                    .map(|a| a.id.clone())
+                    .ok_or_else(|| anyhow::anyhow!("No accounts configured"))?,
+            };
+            let Some(duration) = parse_duration(&duration) else {
+                anyhow::bail!("Invalid duration '{}' (use 45m, 1h or 1h30m)", duration);
+            };
+            let tz: Tz = config.general.timezone.parse().unwrap_or(Tz::UTC);
+            let Some(window) = parse_window(&window, Utc::now(), tz) else {
+                anyhow::bail!(
+                    "Invalid window '{}' (use today, tomorrow, this-week, next-week, 3d or YYYY-MM-DD..YYYY-MM-DD)",
+                    window
+                );
+            };
+
+            let token_provider = create_token_provider(&config).await?;
+            let oauth = OAuthManager::new(token_provider);
+            let attendees = resolve_attendees(&oauth, &config, &account_id, &attendees).await?;
+            let request = MeetingRequest {
+                attendees,
+                duration,
+                window,
+                limit,
+            };
+            let mut proposal =
+                propose_meeting_times(&db, &oauth, &config, &account_id, &request).await?;
+
+            if hold {
+                if let Some(best) = proposal.slots.first() {
+                    let title = title.unwrap_or_else(|| {
+                        if request.attendees.is_empty() {
+                            "Hold".to_string()
+                        } else {
+                            format!("Hold: meeting with {}", request.attendees.join(", "))
+                        }
+                    });
+                    let placed =
+                        place_hold(&oauth, &account_id, best, &title, &request.attendees).await?;
+                    record_audit(
+                        &db,
+                        "create_event",
+                        Some(&account_id),
+                        serde_json::json!({
+                            "summary": title,
+                            "start": placed.start,
+                            "end": placed.end,
+                            "status": "tentative",
+                            "event_id": placed.event_id,
+                        }),
+                    )
+                    .await;
+                    proposal.hold = Some(placed);
+                }
+            }
+
+            if human {
+                let when = |time: DateTime<Utc>| {
+                    time.with_timezone(&tz)
+                        .format("%a %b %-d %H:%M")
+                        .to_string()
+                };
+                println!(
+                    "\n📅 {} min slots, {} → {} ({})",
+                    proposal.duration_minutes,
+                    when(proposal.window_start),
+                    when(proposal.window_end),
+                    proposal.timezone
+                );
+                if !proposal.not_visible.is_empty() {
+                    println!(
+                        "   Calendars not visible: {}",
+                        proposal.not_visible.join(", ")
+                    );
+                }
+                if proposal.slots.is_empty() {
+                    println!("\nNo free slots in this window.");
+                }
+                for (i, slot) in proposal.slots.iter().enumerate() {
+                    println!(
+                        "\n{}. {}–{}  (score {:.2})",
+                        i + 1,
+                        when(slot.start),
+                        slot.end.with_timezone(&tz).format("%H:%M"),
+                        slot.score
+                    );
+                    for (label, people) in [
+                        ("free", &slot.free),
+                        ("busy", &slot.busy),
+                        ("unknown", &slot.unknown),
+                    ] {
+                        if !people.is_empty() {
+                            println!("   {}: {}", label, people.join(", "));
+                        }
+                    }
+                }
+                if let Some(placed) = &proposal.hold {
+                    println!(
+                        "\n✅ Hold placed for {} (id: {})",
+                        when(placed.start),
+                        placed.event_id
+                    );
+                }
+            } else {
+                println!("{}", serde_json::to_string_pretty(&proposal)?);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// "start - end" in `tz`; all-day events as their first and last day
+fn format_event_span_human(start: &EventTime, end: &EventTime, tz: Tz) -> String {
+    match (start, end) {
+        (EventTime::Date(first), EventTime::Date(end)) => {
+            // All-day end dates are exclusive
+            let last = (*end - chrono::Duration::days(1)).max(*first);
+            if last == *first {
+                format!("{} (all day)", first)
+            } else {
+                format!("{} to {} (all day)", first, last)
+            }
+        }
+        _ => format!(
+            "{} - {}",
+            format_event_time_human(start, tz),
+            format_event_time_human(end, tz)
+        ),
+    }
+}
+
+/// "SPF pass, DKIM pass, DMARC fail" (checks Gmail didn't record are left out)
+fn format_auth_human(auth: &EmailAuth) -> String {
+    let checks: Vec<String> = [
+        ("SPF", auth.spf),
+        ("DKIM", auth.dkim),
+        ("DMARC", auth.dmarc),
+    ]
+    .into_iter()
+    .filter_map(|(name, verdict)| verdict.map(|v| format!("{} {}", name, v.as_str())))
+    .collect();
+    if checks.is_empty() {
+        "not recorded".to_string()
+    } else {
+        checks.join(", ")
+    }
+}
+
+/// Event options from `--color`, `--visibility`, `--show-as`, `--reminder`
+/// and `--no-reminders`
+fn parse_event_options(
+    color: Option<&str>,
+    visibility: Option<&str>,
+    show_as: Option<&str>,
+    reminders: &[String],
+    no_reminders: bool,
+) -> Result<EventOptions> {
+    let reminders = if no_reminders {
+        Some(Vec::new())
+    } else if reminders.is_empty() {
+        None
+    } else {
+        Some(
+            reminders
+                .iter()
+                .map(|r| parse_reminder(r))
+                .collect::<Result<Vec<_>, _>>()?,
+        )
+    };
+    Ok(EventOptions::parse(color, visibility, show_as, reminders)?)
+}
+
+/// "popup 10m, email 1d" ("none" for no reminders)
+fn format_reminders_human(reminders: &[Reminder]) -> String {
+    if reminders.is_empty() {
+        return "none".to_string();
+    }
+    reminders
+        .iter()
+        .map(|r| {
+            let lead = match r.minutes {
+                m if m > 0 && m % 1440 == 0 => format!("{}d", m / 1440),
+                m if m > 0 && m % 60 == 0 => format!("{}h", m / 60),
+                m => format!("{}m", m),
+            };
+            let method = match r.method {
+                ReminderMethod::Popup => "popup",
+                ReminderMethod::Email => "email",
+            };
+            format!("{} {}", method, lead)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Print the options set on a created or updated event
+fn print_event_options_human(options: &EventOptions) {
+    if let Some(transparency) = &options.transparency {
+        println!("   Show as: {}", transparency.show_as());
+    }
+    if let Some(visibility) = options.visibility {
+        println!("   Visibility: {}", visibility.as_str());
+    }
+    if let Some(color) = options.color_id.as_deref().and_then(event_color_name) {
+        println!("   Color: {}", color);
+    }
+    if let Some(reminders) = &options.reminders {
+        println!("   Reminders: {}", format_reminders_human(reminders));
+    }
+}
+
+/// "join_url (provider)", plus the dial-in when there is one
+fn format_conference_human(conference: &Conference) -> String {
+    let mut text = format!("{} ({})", conference.join_url, conference.provider);
+    if let Some(phone) = &conference.phone {
+        text.push_str(&format!(", dial-in {}", phone));
+        if let Some(passcode) = &conference.passcode {
+            text.push_str(&format!(" PIN {}", passcode));
+        }
+    }
+    text
+}
+
+fn format_event_time_human(et: &EventTime, tz: Tz) -> String {
+    match et {
+        EventTime::DateTime(dt) => dt.with_timezone(&tz).format("%Y-%m-%d %H:%M").to_string(),
+        EventTime::Date(d) => d.to_string(),
+    }
+}
+
+/// Width of a day column in `calendar events --grid`
+const GRID_COLUMN_WIDTH: usize = 14;
+
+/// Half-hour rows shown when no timed event falls outside them (8:00-18:00)
+const GRID_DEFAULT_SLOTS: (usize, usize) = (16, 36);
+
+/// Print events as week grids: a column per day, a row per half hour
+///
+/// Event blocks start with their title and continue with a bar; a `+n`
+/// after the title counts other events overlapping that slot.
+fn print_week_grid(events: &[CalendarEvent], from: NaiveDate, to: NaiveDate, tz: Tz) {
+    let days: Vec<NaiveDate> = from.iter_days().take_while(|d| *d < to).collect();
+    for week in days.chunks(7) {
+        print_grid_week(events, week, tz);
+    }
+}
+
+/// Print one week of the grid
+fn print_grid_week(events: &[CalendarEvent], days: &[NaiveDate], tz: Tz) {
+    // (day, slot) -> events in that half hour; (day, slot, event) for block starts
+    let mut slots: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+    let mut starts: HashMap<(usize, usize), usize> = HashMap::new();
+    let mut all_day: HashMap<usize, Vec<usize>> = HashMap::new();
+
+    for (idx, event) in events.iter().enumerate() {
+        match (&event.start, &event.end) {
+            (EventTime::DateTime(start), EventTime::DateTime(end)) => {
+                let start = start.with_timezone(&tz).naive_local();
+                let end = end.with_timezone(&tz).naive_local();
+                for (day_idx, day) in days.iter().enumerate() {
+                    let day_start = day.and_hms_opt(0, 0, 0).unwrap_or_default();
+                    let from_min = (start - day_start).num_minutes().max(0);
+                    let to_min = (end - day_start).num_minutes().min(24 * 60);
+                    if to_min <= from_min || from_min >= 24 * 60 {
+                        continue;
+                    }
+                    let first = (from_min / 30) as usize;
+                    let last = ((to_min + 29) / 30) as usize;
+                    for slot in first..last.max(first + 1) {
+                        slots.entry((day_idx, slot)).or_default().push(idx);
+                    }
+                    starts.entry((day_idx, first)).or_insert(idx);
+                }
+            }
+            (EventTime::Date(start), end) => {
+                let end = match end {
+                    EventTime::Date(end) => *end,
+                    EventTime::DateTime(end) => end.with_timezone(&tz).date_naive(),
+                };
+                for (day_idx, day) in days.iter().enumerate() {
+                    if day >= start && (day < &end || day == start) {
+                        all_day.entry(day_idx).or_default().push(idx);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let occupied = slots.keys().map(|(_, slot)| *slot);
+    let first_slot = occupied.clone().min().unwrap_or(GRID_DEFAULT_SLOTS.0);
+    let last_slot = occupied
+        .max()
+        .map(|s| s + 1)
+        .unwrap_or(GRID_DEFAULT_SLOTS.1);
+    let (first_slot, last_slot) = (
+        first_slot.min(GRID_DEFAULT_SLOTS.0),
+        last_slot.max(GRID_DEFAULT_SLOTS.1),
+    );
+
+    let rule = |left: &str, mid: &str, right: &str| {
+        let cells = vec!["─".repeat(GRID_COLUMN_WIDTH); days.len()];
+        format!("{}{}{}{}", "─".repeat(6), left, cells.join(mid), right)
+    };
+
+    println!();
+    println!("{}", rule("┬", "┬", "┐"));
+    let header: Vec<String> = days
+        .iter()
+        .map(|d| fit_cell(&d.format("%a %b %e").to_string()))
+        .collect();
+    println!("{:>6}│{}│", "", header.join("│"));
+
+    if !all_day.is_empty() {
+        let cells: Vec<String> = (0..days.len())
+            .map(|day| match all_day.get(&day) {
+                Some(ids) => grid_label(events, ids[0], ids.len()),
+                None => fit_cell(""),
+            })
+            .collect();
+        println!("{:>6}│{}│", "all", cells.join("│"));
+    }
+    println!("{}", rule("┼", "┼", "┤"));
+
+    for slot in first_slot..last_slot {
+        let label = if slot % 2 == 0 {
+            format!("{:02}:00", slot / 2)
+        } else {
+            String::new()
+        };
+        let cells: Vec<String> = (0..days.len())
+            .map(|day| {
+                let ids = slots.get(&(day, slot)).map(Vec::as_slice).unwrap_or(&[]);
+                match starts.get(&(day, slot)) {
+                    Some(&idx) => grid_label(events, idx, ids.len()),
+                    None if !ids.is_empty() => format!("▌{}", " ".repeat(GRID_COLUMN_WIDTH - 1)),
+                    None if slot % 2 == 0 => fit_cell("·"),
+                    None => fit_cell(""),
+                }
+            })
+            .collect();
+        println!("{:>6}│{}│", label, cells.join("│"));
+    }
+    println!("{}", rule("┴", "┴", "┘"));
+}
+
+/// Cell starting an event block: bar, title and a count of overlapping events
+fn grid_label(events: &[CalendarEvent], idx: usize, overlapping: usize) -> String {
+    let suffix = if overlapping > 1 {
+        format!(" +{}", overlapping - 1)
+    } else {
+        String::new()
+    };
+    let room = GRID_COLUMN_WIDTH - 1 - suffix.chars().count();
+    let title: String = fit_cell(&events[idx].summary).chars().take(room).collect();
+    format!("▌{}{}", title, suffix)
+}
+
+/// Pad or truncate text to one grid column
+fn fit_cell(text: &str) -> String {
+    let text = text.trim();
+    if text.chars().count() > GRID_COLUMN_WIDTH {
+        let cut: String = text.chars().take(GRID_COLUMN_WIDTH - 1).collect();
+        format!("{}…", cut)
+    } else {
+        format!("{:<width$}", text, width = GRID_COLUMN_WIDTH)
+    }
+}
+
+/// Print an attendee's directory title and department under their name
+fn print_attendee_profile(profiles: &HashMap<String, DirectoryPerson>, email: &str, indent: &str) {
+    let Some(person) = profiles.get(&email.to_lowercase()) else {
+        return;
+    };
+    let role: Vec<&str> = [&person.title, &person.department]
+        .into_iter()
+        .flatten()
+        .map(String::as_str)
+        .collect();
+    if !role.is_empty() {
+        println!("{}{}", indent, role.join(", "));
+    }
+}
+
+// ============================================================================
+// Account Command Handlers
+// ============================================================================
+
+async fn handle_account_command(
+    command: AccountCommands,
+    global_human: bool,
+    global_dry_run: bool,
+) -> Result<()> {
+    match command {
+        AccountCommands::List { human } => {
+            let human = human || global_human;
+            let config = Config::load().unwrap_or_default();
+            let db = Database::open(config.lancedb_dir()).await?;
+            let accounts = db.list_accounts().await?;
+
+            if human {
+                if accounts.is_empty() {
+                    println!("No accounts configured.");
+                    println!("\nAdd an account with:");
+                    println!("  groundeffect-daemon add-account");
+                } else {
+                    println!("\n📧 Accounts:\n");
+                    for account in &accounts {
+                        let status_icon = match account.status {
+                            AccountStatus::Active => "✓",
+                            AccountStatus::NeedsReauth => "⚠",
+                            AccountStatus::Disabled => "○",
+                            AccountStatus::Syncing => "↻",
+                        };
+                        let alias = account
+                            .alias
+                            .as_ref()
+                            .map(|a| format!(" ({})", a))
+                            .unwrap_or_default();
+                        println!("{} {}{}", status_icon, account.id, alias);
+                        println!("  Status: {:?}", account.status);
+                        println!("  Display name: {}", account.display_name);
+                        println!();
+                    }
+                }
+            } else {
+                let json_results: Vec<AccountResult> =
+                    accounts.iter().map(AccountResult::from_account).collect();
+                println!("{}", serde_json::to_string_pretty(&json_results)?);
+            }
+        }
+
+        AccountCommands::Show { account, human } => {
+            let human = human || global_human;
+            let config = Config::load().unwrap_or_default();
+            let db = Database::open(config.lancedb_dir()).await?;
+            let accounts = db.list_accounts().await?;
+
+            let account_id = resolve_account(&accounts, &account);
+
+            match account_id {
+                Some(id) => {
+                    if let Some(acct) = db.get_account(&id).await? {
+                        let email_count = db.count_emails(Some(&id)).await.unwrap_or(0);
+                        let event_count = db.count_events(Some(&id)).await.unwrap_or(0);
+                        let (att_total, att_downloaded, att_size) =
+                            db.get_attachment_stats(&id).await.unwrap_or((0, 0, 0));
+
+                        if human {
+                            println!("\n📧 Account: {}", acct.id);
+                            println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+                            if let Some(alias) = &acct.alias {
+                                println!("Alias: {}", alias);
+                            }
+                            println!("Display name: {}", acct.display_name);
+                            println!("Status: {:?}", acct.status);
+                            println!("Added: {}", acct.added_at.format("%Y-%m-%d"));
+                            println!("\n⚙️  Settings:");
+                            if let Some(since) = acct.sync_email_since {
+                                println!("  Sync emails since: {}", since.format("%Y-%m-%d"));
+                            } else {
+                                println!("  Sync emails since: (default)");
+                            }
+                            println!(
+                                "  Sync attachments: {}",
+                                if acct.sync_attachments {
+                                    "enabled"
+                                } else {
+                                    "disabled"
+                                }
+                            );
+                            println!("\n📊 Stats:");
+                            println!("  Emails: {}", email_count);
+                            println!("  Events: {}", event_count);
+                            println!(
+                                "  Attachments: {}/{} downloaded ({} bytes)",
+                                att_downloaded, att_total, att_size
+                            );
+                            if let Some(last) = acct.last_sync_email {
+                                println!("  Last email sync: {}", last.format("%Y-%m-%d %H:%M"));
+                            }
+                            if let Some(last) = acct.last_sync_calendar {
+                                println!("  Last calendar sync: {}", last.format("%Y-%m-%d %H:%M"));
+                            }
+                        } else {
+                            #[derive(Serialize)]
+                            struct AccountDetail {
+                                email: String,
+                                alias: Option<String>,
+                                display_name: String,
+                                status: String,
+                                added_at: String,
+                                email_count: u64,
+                                event_count: u64,
+                                attachments_total: usize,
+                                attachments_downloaded: usize,
+                                attachments_size_bytes: u64,
+                                last_sync_email: Option<String>,
+                                last_sync_calendar: Option<String>,
+                                sync_email_since: Option<String>,
+                                sync_attachments: bool,
+                            }
+                            let detail = AccountDetail {
+                                email: acct.id.clone(),
+                                alias: acct.alias.clone(),
+                                display_name: acct.display_name.clone(),
+                                status: format!("{:?}", acct.status),
+                                added_at: acct.added_at.to_rfc3339(),
+                                email_count,
+                                event_count,
+                                attachments_total: att_total,
+                                attachments_downloaded: att_downloaded,
+                                attachments_size_bytes: att_size,
+                                last_sync_email: acct.last_sync_email.map(|d| d.to_rfc3339()),
+                                last_sync_calendar: acct.last_sync_calendar.map(|d| d.to_rfc3339()),
+                                sync_email_since: acct.sync_email_since.map(|d| d.to_rfc3339()),
+                                sync_attachments: acct.sync_attachments,
+                            };
+                            println!("{}", serde_json::to_string_pretty(&detail)?);
+                        }
+                    }
+                }
+                None => {
+                    if human {
+                        println!("Account not found: {}", account);
+                    } else {
+                        println!("{{\"error\": \"Account not found\"}}");
+                    }
+                }
+            }
+        }
+
+        AccountCommands::Add {
+            years,
+            service_account,
+            impersonate,
+            device_code,
+            attachments,
+            alias,
+            human,
+        } => {
+            let human = human || global_human;
+            let service_account = match (service_account, impersonate) {
+                (Some(key), Some(user)) => {
+                    Some(ServiceAccountCredentials::from_key_file(&key, &user)?)
+                }
+                _ => None,
+            };
+            account_add(
+                years,
+                service_account,
+                device_code,
+                attachments,
+                alias,
+                human,
+            )
+            .await?;
+        }
+
+        AccountCommands::Reauth {
+            account,
+            device_code,
+            human,
+        } => {
+            let human = human || global_human;
+            account_reauth(&account, device_code, human).await?;
+        }
+
+        AccountCommands::Delete {
+            account,
+            confirm,
+            human,
+        } => {
+            let human = human || global_human;
+            account_delete(&account, confirm, global_dry_run, human).await?;
+        }
+
+        AccountCommands::Rename {
+            account,
+            new_email,
+            human,
+        } => {
+            let human = human || global_human;
+            account_rename(&account, &new_email, global_dry_run, human).await?;
+        }
+
+        AccountCommands::Configure {
+            account,
+            alias,
+            attachments,
+            no_attachments,
+            include_labels,
+            exclude_labels,
+            clear_labels,
+            max_storage,
+            retain,
+            retain_categories,
+            headers_only,
+            full_bodies,
+            human,
+        } => {
+            let human = human || global_human;
+            account_configure(
+                &account,
+                alias,
+                attachments,
+                no_attachments,
+                include_labels,
+                exclude_labels,
+                clear_labels,
+                max_storage,
+                retain,
+                retain_categories,
+                headers_only,
+                full_bodies,
+                human,
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+// ============================================================================
+// Sync Command Handlers
+// ============================================================================
+
+async fn handle_sync_command(
+    command: SyncCommands,
+    global_human: bool,
+    global_dry_run: bool,
+) -> Result<()> {
+    match command {
+        SyncCommands::Status {
+            account,
+            verbose,
+            human,
+        } => {
+            let human = human || global_human;
+            let config = Config::load().unwrap_or_default();
+            let db = Database::open(config.lancedb_dir()).await?;
+            let accounts = db.list_accounts().await?;
+
+            let target_accounts: Vec<&Account> = if let Some(acct) = &account {
+                accounts
+                    .iter()
+                    .filter(|a| a.id == *acct || a.alias.as_ref() == Some(acct))
+                    .collect()
+            } else {
+                accounts.iter().collect()
+            };
+
+            if target_accounts.is_empty() {
+                if human {
+                    println!("No accounts found.");
+                } else {
+                    println!("[]");
+                }
+                return Ok(());
+            }
+
+            // Check daemon status
+            let daemon_running = check_daemon_running();
+
+            if human {
+                println!("\n📊 GroundEffect Sync Status\n");
+                if daemon_running {
+                    println!("Daemon: ✓ running");
+                } else {
+                    println!("Daemon: ✗ not running");
+                }
+                println!();
+            }
+
+            let mut statuses = Vec::new();
+            let retention_report = RetentionReport::load(&config.retention_report_file());
+
+            for account in target_accounts {
+                let email_count = db.count_emails(Some(&account.id)).await.unwrap_or(0);
+                let event_count = db.count_events(Some(&account.id)).await.unwrap_or(0);
+                let (oldest_email, newest_email) = db
+                    .get_email_sync_boundaries(&account.id)
+                    .await
+                    .unwrap_or((None, None));
+                let (oldest_event, newest_event) = db
+                    .get_event_sync_boundaries(&account.id)
+                    .await
+                    .unwrap_or((None, None));
+                let (att_total, att_downloaded, att_size) = db
+                    .get_attachment_stats(&account.id)
+                    .await
+                    .unwrap_or((0, 0, 0));
+
+                // Calculate remaining emails if we have an estimate
+                let emails_remaining = account
+                    .estimated_total_emails
+                    .map(|total| total.saturating_sub(email_count));
+
+                let usage = db.get_storage_usage(&account.id).await.unwrap_or_default();
+                let quota_exceeded = account.storage_quota_exceeded(&usage);
+                let retention_suggestions = if quota_exceeded {
+                    account.retention_suggestions(&usage)
+                } else {
+                    vec![]
+                };
+
+                let status = SyncStatus {
+                    account: account.id.clone(),
+                    status: format!("{:?}", account.status),
+                    email_count,
+                    event_count,
+                    oldest_email: oldest_email.map(|d| d.format("%Y-%m-%d").to_string()),
+                    newest_email: newest_email.map(|d| d.format("%Y-%m-%d").to_string()),
+                    oldest_event: oldest_event.map(|d| d.format("%Y-%m-%d").to_string()),
+                    newest_event: newest_event.map(|d| d.format("%Y-%m-%d").to_string()),
+                    last_email_sync: account.last_sync_email.map(|d| d.to_rfc3339()),
+                    last_calendar_sync: account.last_sync_calendar.map(|d| d.to_rfc3339()),
+                    attachments_total: att_total,
+                    attachments_downloaded: att_downloaded,
+                    attachments_size_bytes: att_size,
+                    sync_email_since: account.sync_email_since.map(|d| d.to_rfc3339()),
+                    sync_attachments: account.sync_attachments,
+                    estimated_total_emails: account.estimated_total_emails,
+                    emails_remaining,
+                    storage_used_bytes: usage.total(),
+                    max_storage_bytes: account.max_storage_bytes,
+                    storage_quota_exceeded: quota_exceeded,
+                    retention_suggestions,
+                    retention: account.retention.clone(),
+                    last_prune: retention_report.get(&account.id).cloned(),
+                    headers_only: account.headers_only,
+                };
+
+                if human {
+                    let status_icon = match account.status {
+                        AccountStatus::Active => "✓",
+                        AccountStatus::NeedsReauth => "⚠",
+                        AccountStatus::Disabled => "○",
+                        AccountStatus::Syncing => "↻",
+                    };
+                    let alias = account
+                        .alias
+                        .as_ref()
+                        .map(|a| format!(" ({})", a))
+                        .unwrap_or_default();
+                    println!("{}  {}{}", status_icon, account.id, alias);
+                    println!("   Status: {:?}", account.status);
+                    if let Some(since) = account.sync_email_since {
+                        println!("   ⚙️  Sync since: {}", since.format("%Y-%m-%d"));
+                    }
+                    // Show email count with total and remaining if available
+                    if let Some(total) = account.estimated_total_emails {
+                        let remaining = total.saturating_sub(email_count);
+                        if remaining > 0 {
+                            println!(
+                                "   📨 Emails: {} / {} ({} remaining)",
+                                email_count, total, remaining
+                            );
+                        } else {
+                            println!("   📨 Emails: {} (sync complete)", email_count);
+                        }
+                    } else {
+                        println!("   📨 Emails: {}", email_count);
+                    }
+                    if let Some(oldest) = &status.oldest_email {
+                        println!("      Oldest: {}", oldest);
+                    }
+                    if let Some(newest) = &status.newest_email {
+                        println!("      Newest: {}", newest);
+                    }
+                    if let Some(last) = account.last_sync_email {
+                        println!("      Last sync: {}", format_relative_time(last));
+                    }
+                    println!("   📅 Events: {}", event_count);
+                    if let Some(oldest) = &status.oldest_event {
+                        println!("      Oldest: {}", oldest);
+                    }
+                    if let Some(newest) = &status.newest_event {
+                        println!("      Newest: {}", newest);
+                    }
+                    if let Some(last) = account.last_sync_calendar {
+                        println!("      Last sync: {}", format_relative_time(last));
+                    }
+                    println!(
+                        "   📎 Attachments: {}/{} downloaded ({})",
+                        att_downloaded,
+                        att_total,
+                        format_bytes(att_size)
+                    );
+                    if account.sync_attachments {
+                        println!("      Auto-download: enabled");
+                    }
+                    match account.max_storage_bytes {
+                        Some(max) => println!(
+                            "   💾 Storage: {} / {}",
+                            format_bytes(usage.total()),
+                            format_bytes(max)
+                        ),
+                        None => println!("   💾 Storage: {}", format_bytes(usage.total())),
+                    }
+                    if account.headers_only {
+                        println!("      Headers only: bodies and attachments aren't stored");
+                    }
+                    if quota_exceeded {
+                        println!(
+                            "      ⚠ Storage limit reached: attachment downloads and backfill paused"
+                        );
+                        println!("      To free space or stop growth:");
+                        for suggestion in &status.retention_suggestions {
+                            println!("        - {}", suggestion);
+                        }
+                    }
+                    if !account.retention.is_empty() {
+                        println!("   🗑  Retention: {}", account.retention.describe());
+                        match &status.last_prune {
+                            Some(prune) => println!(
+                                "      Last pruned: {} ({})",
+                                format_relative_time(prune.pruned_at),
+                                prune.summary()
+                            ),
+                            None => println!("      Last pruned: never"),
+                        }
+                    }
+                    println!();
+                }
+
+                statuses.push(status);
+            }
+
+            let recovery = RecoveryReport::load(&config.recovery_report_file());
+            if human && verbose {
+                match recovery.checked_at {
+                    Some(checked_at) => {
+                        println!(
+                            "🩺 Consistency scan ({}): {}",
+                            format_relative_time(checked_at),
+                            recovery.summary()
+                        );
+                        for issue in &recovery.issues {
+                            let account = issue
+                                .account_id
+                                .as_ref()
+                                .map(|id| format!("{}: ", id))
+                                .unwrap_or_default();
+                            println!(
+                                "   {}{} → {} ({:?})",
+                                account, issue.detail, issue.action, issue.status
+                            );
+                        }
+                    }
+                    None => println!("🩺 Consistency scan: not run yet (runs at daemon start)"),
+                }
+                println!();
+            } else if verbose {
+                let output = serde_json::json!({
+                    "accounts": statuses,
+                    "consistency": {
+                        "checked_at": recovery.checked_at,
+                        "summary": recovery.summary(),
+                        "issues": recovery.issues,
+                    },
+                });
+                println!("{}", serde_json::to_string_pretty(&output)?);
+            } else if !human {
+                println!("{}", serde_json::to_string_pretty(&statuses)?);
+            }
+        }
+
+        SyncCommands::Reset {
+            account,
+            data_type,
+            confirm,
+            human,
+        } => {
+            let human = human || global_human;
+            sync_reset(&account, &data_type, confirm, global_dry_run, human).await?;
+        }
+
+        SyncCommands::Extend {
+            account,
+            target_date,
+            human,
+        } => {
+            let human = human || global_human;
+            sync_extend(&account, &target_date, human).await?;
+        }
+
+        SyncCommands::ResumeFrom {
+            account,
+            target_date,
+            human,
+        } => {
+            let human = human || global_human;
+            sync_resume_from(&account, &target_date, human).await?;
+        }
+
+        SyncCommands::DownloadAttachments { account, human } => {
+            let human = human || global_human;
+            sync_download_attachments(&account, human).await?;
+        }
+
+        SyncCommands::Apple { human } => {
+            let human = human || global_human;
+            sync_apple(global_dry_run, human).await?;
+        }
+
+        SyncCommands::Spotlight { human } => {
+            let human = human || global_human;
+            let config = Config::load().unwrap_or_default();
+            let db = Database::open(config.lancedb_dir()).await?;
+            let report = spotlight::export(&db, &config, Utc::now()).await?;
+            if human {
+                println!("✅ Spotlight stubs updated in {}", report.dir.display());
+                println!("   Written: {}", report.written);
+                println!("   Unchanged: {}", report.unchanged);
+                println!("   Removed: {}", report.removed);
+            } else {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// ============================================================================
+// Daemon Command Handlers
+// ============================================================================
+
+async fn handle_daemon_command(command: DaemonCommands, global_human: bool) -> Result<()> {
+    match command {
+        DaemonCommands::Status { health, human } => {
+            let human = human || global_human;
+            let running = check_daemon_running();
+            let pid = get_daemon_pid();
+            let config = Config::load().unwrap_or_default();
+            let daemon_health = health.then(|| DaemonHealth::load(&config.daemon_health_file()));
+            let daemon_config = DaemonConfig::load().unwrap_or_default();
+            // Only the running daemon keeps the state current
+            let policy = running
+                .then(|| PolicyState::load(&config.sync_policy_file()))
+                .flatten();
+
+            if human {
+                if running {
+                    println!("Daemon: ✓ running (PID: {})", pid.unwrap_or(0));
+                } else {
+                    println!("Daemon: ✗ not running");
+                }
+                print_sync_policy(&daemon_config, policy.as_ref());
+                if let Some(h) = &daemon_health {
+                    print_daemon_health(h);
+                }
+            } else {
+                let status = DaemonStatus {
+                    running,
+                    pid,
+                    health: daemon_health.as_ref().map(daemon_health_json),
+                    sync_policy: sync_policy_json(&daemon_config, policy.as_ref()),
+                };
+                println!("{}", serde_json::to_string_pretty(&status)?);
+            }
+        }
+
+        DaemonCommands::Start { logging, human } => {
+            let human = human || global_human;
+            if check_daemon_running() {
+                if human {
+                    println!("Daemon is already running.");
+                } else {
+                    println!("{{\"status\": \"already_running\"}}");
+                }
+                return Ok(());
+            }
+
+            let plist_path = DaemonConfig::launchd_plist_path();
+
+            let service = if DaemonConfig::is_launchd_installed() {
+                let output = std::process::Command::new("launchctl")
+                    .args(["load", "-w", plist_path.to_str().unwrap()])
+                    .output()?;
+                Some(("launchd", output))
+            } else if DaemonConfig::is_systemd_installed() {
+                let output = systemctl_user(&["start", &DaemonConfig::systemd_unit_name()])?;
+                Some(("systemd", output))
+            } else {
+                None
+            };
+
+            if let Some((method, output)) = service {
+                if human {
+                    if output.status.success() {
+                        println!("✓ Daemon started via {}", method);
+                    } else {
+                        println!(
+                            "Failed to start daemon: {}",
+                            String::from_utf8_lossy(&output.stderr)
+                        );
+                    }
+                } else {
+                    if output.status.success() {
+                        println!("{{\"status\": \"started\", \"method\": \"{}\"}}", method);
+                    } else {
+                        println!(
+                            "{{\"status\": \"error\", \"message\": \"{}\" }}",
+                            String::from_utf8_lossy(&output.stderr).replace('"', "\\\"")
+                        );
+                    }
+                }
+            } else {
+                let mut cmd = std::process::Command::new("groundeffect-daemon");
+                if logging {
+                    cmd.arg("--log");
+                }
+                cmd.args(DaemonConfig::profile_args());
+                cmd.spawn()?;
+
+                if human {
+                    println!("✓ Daemon started directly");
+                } else {
+                    println!("{{\"status\": \"started\", \"method\": \"direct\"}}");
+                }
+            }
+        }
+
+        DaemonCommands::Stop { human } => {
+            let human = human || global_human;
+            if !check_daemon_running() {
+                if human {
+                    println!("Daemon is not running.");
+                } else {
+                    println!("{{\"status\": \"not_running\"}}");
+                }
+                return Ok(());
+            }
+
+            let plist_path = DaemonConfig::launchd_plist_path();
+
+            if DaemonConfig::is_launchd_installed() {
+                let _ = std::process::Command::new("launchctl")
+                    .args(["unload", plist_path.to_str().unwrap()])
+                    .output();
+            } else if DaemonConfig::is_systemd_installed() {
+                // Stop through systemd so Restart=always doesn't bring it back
+                let _ = systemctl_user(&["stop", &DaemonConfig::systemd_unit_name()]);
+            }
+
+            let _ = std::process::Command::new("pkill")
+                .args(["-f", &DaemonConfig::process_pattern()])
+                .output();
+
+            if human {
+                println!("✓ Daemon stopped");
+            } else {
+                println!("{{\"status\": \"stopped\"}}");
+            }
+        }
+
+        DaemonCommands::Restart { logging, human } => {
+            let human = human || global_human;
+
+            if DaemonConfig::is_systemd_installed() {
+                let output = systemctl_user(&["restart", &DaemonConfig::systemd_unit_name()])?;
+                if output.status.success() {
+                    if human {
+                        println!("✓ Daemon restarted via systemd");
+                    } else {
+                        println!("{{\"status\": \"restarted\", \"method\": \"systemd\"}}");
+                    }
+                } else if human {
+                    println!(
+                        "Failed to restart daemon: {}",
+                        String::from_utf8_lossy(&output.stderr)
+                    );
+                } else {
+                    println!(
+                        "{}",
+                        serde_json::json!({
+                            "status": "error",
+                            "message": String::from_utf8_lossy(&output.stderr).trim(),
+                        })
+                    );
+                }
+                return Ok(());
+            }
+
+            let _ = std::process::Command::new("pkill")
+                .args(["-f", &DaemonConfig::process_pattern()])
+                .output();
+
+            std::thread::sleep(std::time::Duration::from_millis(500));
+
+            let plist_path = DaemonConfig::launchd_plist_path();
+
+            if DaemonConfig::is_launchd_installed() {
+                let _ = std::process::Command::new("launchctl")
+                    .args(["load", "-w", plist_path.to_str().unwrap()])
+                    .output();
+
+                if human {
+                    println!("✓ Daemon restarted via launchd");
+                } else {
+                    println!("{{\"status\": \"restarted\", \"method\": \"launchd\"}}");
+                }
+            } else {
+                let mut cmd = std::process::Command::new("groundeffect-daemon");
+                if logging {
+                    cmd.arg("--log");
+                }
+                cmd.args(DaemonConfig::profile_args());
+                cmd.spawn()?;
+
+                if human {
+                    println!("✓ Daemon restarted");
+                } else {
+                    println!("{{\"status\": \"restarted\", \"method\": \"direct\"}}");
+                }
+            }
+        }
+
+        DaemonCommands::Reload { human } => {
+            let human = human || global_human;
+            let config = Config::load().unwrap_or_default();
+
+            match control::reload(&config.control_socket_path()).await {
+                None => {
+                    if human {
+                        println!("Daemon is not running.");
+                    } else {
+                        println!("{{\"status\": \"not_running\"}}");
+                    }
+                }
+                Some(Err(e)) => {
+                    if human {
+                        println!("Reload failed: {}", e);
+                    } else {
+                        println!(
+                            "{}",
+                            serde_json::to_string_pretty(&serde_json::json!({
+                                "status": "error",
+                                "message": e.to_string(),
+                            }))?
+                        );
+                    }
+                    std::process::exit(1);
+                }
+                Some(Ok(report)) => {
+                    if human {
+                        if report.is_empty() {
+                            println!("✓ Daemon reloaded (no changes)");
+                        } else {
+                            println!("✓ Daemon reloaded");
+                        }
+                        for key in &report.applied {
+                            println!("  applied: {}", key);
+                        }
+                        for key in &report.restart_required {
+                            println!("  needs restart: {}", key);
+                        }
+                    } else {
+                        println!(
+                            "{}",
+                            serde_json::to_string_pretty(&serde_json::json!({
+                                "status": "reloaded",
+                                "applied": report.applied,
+                                "restart_required": report.restart_required,
+                            }))?
+                        );
                     }
                 }
-                None => {
-                    if human {
-                        println!("Event not found: {}", id);
-                    } else {
-                        println!("{{\"error\": \"Event not found\"}}");
+            }
+        }
+
+        DaemonCommands::Install { logging, human } => {
+            let human = human || global_human;
+            daemon_install(logging, human)?;
+        }
+
+        DaemonCommands::Uninstall { human } => {
+            let human = human || global_human;
+            daemon_uninstall(human)?;
+        }
+    }
+
+    Ok(())
+}
+
+// ============================================================================
+// Helper Functions
+// ============================================================================
+
+fn check_daemon_running() -> bool {
+    std::process::Command::new("pgrep")
+        .args(["-f", &DaemonConfig::process_pattern()])
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+fn get_daemon_pid() -> Option<u32> {
+    std::process::Command::new("pgrep")
+        .args(["-f", &DaemonConfig::process_pattern()])
+        .output()
+        .ok()
+        .and_then(|o| {
+            if o.status.success() {
+                String::from_utf8_lossy(&o.stdout)
+                    .lines()
+                    .next()
+                    .and_then(|s| s.trim().parse().ok())
+            } else {
+                None
+            }
+        })
+}
+
+/// Restart the daemon. Returns the method used ("launchd", "systemd" or "direct") or None if not running.
+fn restart_daemon() -> Option<&'static str> {
+    // Check if daemon is running first
+    if !check_daemon_running() {
+        return None;
+    }
+
+    if DaemonConfig::is_systemd_installed() {
+        let _ = systemctl_user(&["restart", &DaemonConfig::systemd_unit_name()]);
+        return Some("systemd");
+    }
+
+    // Kill existing daemon
+    let _ = std::process::Command::new("pkill")
+        .args(["-f", &DaemonConfig::process_pattern()])
+        .output();
+
+    std::thread::sleep(std::time::Duration::from_millis(500));
+
+    let plist_path = DaemonConfig::launchd_plist_path();
+
+    if DaemonConfig::is_launchd_installed() {
+        let _ = std::process::Command::new("launchctl")
+            .args(["load", "-w", plist_path.to_str().unwrap()])
+            .output();
+        Some("launchd")
+    } else {
+        let _ = std::process::Command::new("groundeffect-daemon")
+            .args(DaemonConfig::profile_args())
+            .spawn();
+        Some("direct")
+    }
+}
+
+/// Run `systemctl --user` with the given arguments
+fn systemctl_user(args: &[&str]) -> std::io::Result<std::process::Output> {
+    std::process::Command::new("systemctl")
+        .arg("--user")
+        .args(args)
+        .output()
+}
+
+/// Parse an email category for clap
+fn parse_category(s: &str) -> std::result::Result<EmailCategory, String> {
+    EmailCategory::from_str(s).ok_or_else(|| {
+        format!(
+            "invalid category '{}' (expected personal, newsletter, notification, or transactional)",
+            s
+        )
+    })
+}
+
+/// Filters taken from a query, for --human output
+fn describe_understanding(understood: &QueryUnderstanding, tz: Tz) -> String {
+    let mut parts = vec![format!("\"{}\"", understood.query)];
+    if let Some(from) = &understood.from {
+        parts.push(format!("from {}", from));
+    }
+    if let Some(to) = &understood.to {
+        parts.push(format!("to {}", to));
+    }
+    let day = |dt: &DateTime<Utc>| dt.with_timezone(&tz).format("%Y-%m-%d").to_string();
+    match (&understood.date_from, &understood.date_to) {
+        (Some(from), Some(to)) => parts.push(format!("{} to {}", day(from), day(to))),
+        (Some(from), None) => parts.push(format!("since {}", day(from))),
+        (None, Some(to)) => parts.push(format!("until {}", day(to))),
+        (None, None) => {}
+    }
+    if understood.has_attachment == Some(true) {
+        parts.push("with attachments".to_string());
+    }
+    match understood.sent {
+        Some(true) => parts.push("sent by you".to_string()),
+        Some(false) => parts.push("received".to_string()),
+        None => {}
+    }
+    parts.join(", ")
+}
+
+/// Parse a unified search result type for clap
+fn parse_result_type(s: &str) -> std::result::Result<ResultType, String> {
+    ResultType::from_name(s).ok_or_else(|| {
+        format!(
+            "invalid result type '{}' (expected email, event, attachment, or contact)",
+            s
+        )
+    })
+}
+
+/// Parse a search mode for clap
+fn parse_digest_period(s: &str) -> std::result::Result<DigestPeriod, String> {
+    DigestPeriod::from_str(s)
+        .ok_or_else(|| format!("invalid period '{}' (expected day or week)", s))
+}
+
+fn parse_search_mode(s: &str) -> std::result::Result<SearchMode, String> {
+    SearchMode::from_name(s).ok_or_else(|| {
+        format!(
+            "invalid search mode '{}' (expected hybrid, semantic, or keyword)",
+            s
+        )
+    })
+}
+
+/// Parse an activity source for clap
+fn parse_activity_source(s: &str) -> std::result::Result<ActivitySource, String> {
+    ActivitySource::from_str(s)
+        .ok_or_else(|| format!("invalid source '{}' (expected github, jira, or asana)", s))
+}
+
+fn parse_audit_actor(s: &str) -> std::result::Result<AuditActor, String> {
+    AuditActor::from_str(s).ok_or_else(|| format!("invalid actor '{}' (expected cli or mcp)", s))
+}
+
+fn parse_itinerary_kind(s: &str) -> std::result::Result<ItineraryKind, String> {
+    ItineraryKind::from_str(s)
+        .ok_or_else(|| format!("invalid kind '{}' (expected flight or hotel)", s))
+}
+
+fn parse_extraction_kind(s: &str) -> std::result::Result<ExtractionKind, String> {
+    ExtractionKind::from_str(s).ok_or_else(|| {
+        format!(
+            "invalid type '{}' (expected flight, hotel, restaurant, or package)",
+            s
+        )
+    })
+}
+
+/// JSON view of the daemon health file, including derived crash-loop state
+fn daemon_health_json(health: &DaemonHealth) -> serde_json::Value {
+    serde_json::json!({
+        "crash_looping": health.is_crash_looping(),
+        "sync_backoff_factor": health.sync_backoff_factor(),
+        "started_at": health.started_at.map(|t| t.to_rfc3339()),
+        "last_heartbeat": health.last_heartbeat.map(|t| t.to_rfc3339()),
+        "heartbeat_stale": health.heartbeat_stale(Utc::now()),
+        "last_clean_shutdown": health.last_clean_shutdown.map(|t| t.to_rfc3339()),
+        "total_starts": health.total_starts,
+        "restart_count": health.restart_count,
+        "recent_crashes": health.recent_crashes.iter().map(|t| t.to_rfc3339()).collect::<Vec<_>>(),
+        "last_panic": health.last_panic,
+        "idle_shed": {
+            "shed": health.shed,
+            "shed_count": health.shed_count,
+            "restore_count": health.restore_count,
+            "last_shed_at": health.last_shed_at.map(|t| t.to_rfc3339()),
+            "last_restore_at": health.last_restore_at.map(|t| t.to_rfc3339()),
+        },
+        "sync_incidents": health.sync_incidents,
+    })
+}
+
+fn print_daemon_health(health: &DaemonHealth) {
+    let fmt_time = |t: Option<DateTime<Utc>>| {
+        t.map(|t| {
+            t.with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+        })
+        .unwrap_or_else(|| "never".to_string())
+    };
+
+    println!();
+    if health.is_crash_looping() {
+        println!(
+            "Health: ⚠ crash-looping ({} unclean exits in the last {} minutes, sync slowed {}x)",
+            health.recent_crashes.len(),
+            CRASH_LOOP_WINDOW_SECS / 60,
+            health.sync_backoff_factor()
+        );
+    } else if health.heartbeat_stale(Utc::now()) {
+        println!("Health: ⚠ no recent heartbeat (daemon may be hung or killed)");
+    } else {
+        println!("Health: ✓ ok");
+    }
+    println!("  Started:        {}", fmt_time(health.started_at));
+    println!("  Last heartbeat: {}", fmt_time(health.last_heartbeat));
+    println!("  Clean shutdown: {}", fmt_time(health.last_clean_shutdown));
+    println!(
+        "  Restarts:       {} unclean of {} total starts",
+        health.restart_count, health.total_starts
+    );
+    println!(
+        "  Idle shedding:  {} ({} shed, {} restored; last shed {}, last restore {})",
+        if health.shed {
+            "resources released"
+        } else {
+            "warm"
+        },
+        health.shed_count,
+        health.restore_count,
+        fmt_time(health.last_shed_at),
+        fmt_time(health.last_restore_at)
+    );
+
+    match &health.last_panic {
+        Some(panic) => {
+            println!("\nLast panic ({}):", fmt_time(Some(panic.at)));
+            println!("  {}", panic.message);
+            if let Some(location) = &panic.location {
+                println!("  at {}", location);
+            }
+            println!("\n{}", panic.backtrace);
+        }
+        None => println!("  Last panic:     none recorded"),
+    }
+
+    if !health.sync_incidents.is_empty() {
+        println!("\nStalled syncs restarted by the watchdog:");
+        for incident in &health.sync_incidents {
+            println!(
+                "  {}  {}: {}",
+                fmt_time(Some(incident.at)),
+                incident.account_id,
+                incident.detail
+            );
+        }
+    }
+}
+
+/// The sync window settings and, while the daemon runs, what they allow now
+fn sync_policy_json(
+    daemon_config: &DaemonConfig,
+    policy: Option<&PolicyState>,
+) -> serde_json::Value {
+    serde_json::json!({
+        "quiet_hours": daemon_config.quiet_hours,
+        "on_battery": daemon_config.on_battery,
+        "battery_max_concurrent_fetches": daemon_config.battery_max_concurrent_fetches,
+        "attachments_wifi_only": daemon_config.attachments_wifi_only,
+        "summary": policy.map(PolicyState::summary),
+        "state": policy,
+    })
+}
+
+fn print_sync_policy(daemon_config: &DaemonConfig, policy: Option<&PolicyState>) {
+    let mut settings = Vec::new();
+    if let Some(quiet_hours) = &daemon_config.quiet_hours {
+        settings.push(format!("quiet hours {}", quiet_hours));
+    }
+    match daemon_config.on_battery {
+        BatteryPolicy::Normal => {}
+        BatteryPolicy::Throttle => settings.push(format!(
+            "on battery: throttle to {}",
+            daemon_config.battery_max_concurrent_fetches
+        )),
+        BatteryPolicy::Pause => settings.push("on battery: pause".to_string()),
+    }
+    if daemon_config.attachments_wifi_only {
+        settings.push("attachments on Wi-Fi only".to_string());
+    }
+
+    if let Some(policy) = policy {
+        println!("Sync:   {}", policy.summary());
+    }
+    if !settings.is_empty() {
+        println!("Window: {}", settings.join(", "));
+    }
+}
+
+fn resolve_account(accounts: &[Account], query: &str) -> Option<String> {
+    accounts
+        .iter()
+        .find(|a| a.id == query || a.alias.as_ref() == Some(&query.to_string()))
+        .map(|a| a.id.clone())
+}
+
+/// Parse a date string in the user's timezone and convert to UTC.
+///
+/// If timezone parsing fails, falls back to UTC.
+fn parse_date(date_str: &Option<String>, timezone: &str) -> Option<DateTime<Utc>> {
+    date_str.as_ref().and_then(|s| {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").ok().map(|d| {
+            let naive_dt = d.and_hms_opt(0, 0, 0).unwrap();
+
+            // Try to parse the timezone, fallback to UTC
+            if let Ok(tz) = timezone.parse::<Tz>() {
+                // Convert from user's timezone to UTC
+                tz.from_local_datetime(&naive_dt)
+                    .single()
+                    .map(|dt| dt.with_timezone(&Utc))
+                    .unwrap_or_else(|| naive_dt.and_utc())
+            } else {
+                // Invalid timezone string, treat as UTC
+                naive_dt.and_utc()
+            }
+        })
+    })
+}
+
+fn format_relative_time(dt: DateTime<Utc>) -> String {
+    let now = Utc::now();
+    let duration = now.signed_duration_since(dt);
+
+    if duration.num_seconds() < 60 {
+        "just now".to_string()
+    } else if duration.num_minutes() < 60 {
+        let mins = duration.num_minutes();
+        format!("{} minute{} ago", mins, if mins == 1 { "" } else { "s" })
+    } else if duration.num_hours() < 24 {
+        let hours = duration.num_hours();
+        format!("{} hour{} ago", hours, if hours == 1 { "" } else { "s" })
+    } else if duration.num_days() < 7 {
+        let days = duration.num_days();
+        format!("{} day{} ago", days, if days == 1 { "" } else { "s" })
+    } else {
+        dt.format("%Y-%m-%d %H:%M").to_string()
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    } else if bytes < 1024 * 1024 * 1024 {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    } else {
+        format!("{:.2} GB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+    }
+}
+
+// ============================================================================
+// Daemon Install/Uninstall Functions
+// ============================================================================
+
+/// Login services are managed by launchd on macOS and systemd on Linux
+fn print_service_unsupported(human: bool) {
+    if human {
+        println!("Daemon install is only supported on macOS (launchd) and Linux (systemd).");
+        println!("Run `groundeffect-daemon` under your platform's service manager instead.");
+    } else {
+        println!(
+            "{}",
+            serde_json::json!({
+                "status": "unsupported",
+                "reason": "daemon install requires launchd (macOS) or systemd (Linux)",
+            })
+        );
+    }
+}
+
+fn daemon_install(logging: Option<bool>, human: bool) -> Result<()> {
+    if cfg!(target_os = "linux") {
+        return systemd_install(logging, human);
+    }
+    if !cfg!(target_os = "macos") {
+        print_service_unsupported(human);
+        return Ok(());
+    }
+
+    let plist_path = DaemonConfig::launchd_plist_path();
+
+    // Check if already installed
+    if plist_path.exists() {
+        if human {
+            println!("Launchd agent already installed at {:?}", plist_path);
+            println!(
+                "To reinstall, run: groundeffect daemon uninstall && groundeffect daemon install"
+            );
+        } else {
+            println!(
+                "{{\"status\": \"already_installed\", \"plist_path\": \"{}\"}}",
+                plist_path.display()
+            );
+        }
+        return Ok(());
+    }
+
+    // Load existing config or create defaults
+    let mut daemon_config = DaemonConfig::load_file().unwrap_or_default();
+
+    // Only override logging if explicitly specified
+    if let Some(log_enabled) = logging {
+        daemon_config.logging_enabled = log_enabled;
+    }
+    daemon_config.save()?;
+
+    // Find daemon binary
+    let daemon_path = find_daemon_binary()?;
+
+    // Create LaunchAgents directory
+    if let Some(parent) = plist_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    // Create log directory
+    let log_dir = daemon_log_dir();
+    std::fs::create_dir_all(&log_dir)?;
+
+    // Generate plist content
+    let plist_content = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>/bin/bash</string>
+        <string>-c</string>
+        <string>source ~/.secrets 2>/dev/null; exec {daemon_path}{logging_flag}{profile_flag}</string>
+    </array>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <true/>
+    <key>StandardOutPath</key>
+    <string>{stdout}</string>
+    <key>StandardErrorPath</key>
+    <string>{stderr}</string>
+    <key>SoftResourceLimits</key>
+    <dict>
+        <key>NumberOfFiles</key>
+        <integer>65536</integer>
+    </dict>
+    <key>EnvironmentVariables</key>
+    <dict>
+        <key>GROUNDEFFECT_EMAIL_POLL_INTERVAL</key>
+        <string>{email_interval}</string>
+        <key>GROUNDEFFECT_CALENDAR_POLL_INTERVAL</key>
+        <string>{calendar_interval}</string>
+        <key>GROUNDEFFECT_MAX_CONCURRENT_FETCHES</key>
+        <string>{max_fetches}</string>
+    </dict>
+</dict>
+</plist>"#,
+        label = DaemonConfig::launchd_label(),
+        daemon_path = daemon_path.display(),
+        logging_flag = if daemon_config.logging_enabled {
+            " --log"
+        } else {
+            ""
+        },
+        profile_flag = DaemonConfig::profile_args()
+            .iter()
+            .map(|arg| format!(" {}", arg))
+            .collect::<String>(),
+        stdout = log_dir.join("stdout.log").display(),
+        stderr = log_dir.join("stderr.log").display(),
+        email_interval = daemon_config.email_poll_interval_secs,
+        calendar_interval = daemon_config.calendar_poll_interval_secs,
+        max_fetches = daemon_config.max_concurrent_fetches,
+    );
+
+    std::fs::write(&plist_path, plist_content)?;
+
+    // Load the launchd agent
+    let output = std::process::Command::new("launchctl")
+        .args(["load", "-w", plist_path.to_str().unwrap()])
+        .output()?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !stderr.contains("service already loaded") {
+            if human {
+                println!("Failed to load launchd agent: {}", stderr);
+            } else {
+                println!(
+                    "{{\"status\": \"error\", \"message\": \"{}\"}}",
+                    stderr.replace('"', "\\\"")
+                );
+            }
+            return Ok(());
+        }
+    }
+
+    if human {
+        println!("✓ Launchd agent installed");
+        println!("  Plist: {:?}", plist_path);
+        println!("  Config: {:?}", DaemonConfig::config_path());
+        println!("  Logs: {:?}", log_dir);
+        println!("\nThe daemon will start automatically at login.");
+        println!("To customize settings: groundeffect config settings");
+    } else {
+        println!(
+            "{{\"status\": \"installed\", \"plist_path\": \"{}\", \"config_path\": \"{}\"}}",
+            plist_path.display(),
+            DaemonConfig::config_path().display()
+        );
+    }
+
+    Ok(())
+}
+
+fn daemon_uninstall(human: bool) -> Result<()> {
+    if cfg!(target_os = "linux") {
+        return systemd_uninstall(human);
+    }
+    if !cfg!(target_os = "macos") {
+        print_service_unsupported(human);
+        return Ok(());
+    }
+
+    let plist_path = DaemonConfig::launchd_plist_path();
+
+    if !plist_path.exists() {
+        if human {
+            println!("Launchd agent is not installed.");
+        } else {
+            println!("{{\"status\": \"not_installed\"}}");
+        }
+        return Ok(());
+    }
+
+    // Unload the agent
+    let _ = std::process::Command::new("launchctl")
+        .args(["unload", "-w", plist_path.to_str().unwrap()])
+        .output();
+
+    // Remove the plist file
+    std::fs::remove_file(&plist_path)?;
+
+    if human {
+        println!("✓ Launchd agent uninstalled");
+        println!("The daemon will no longer start automatically at login.");
+        println!("\nNote: Synced data and configuration files are preserved.");
+    } else {
+        println!("{{\"status\": \"uninstalled\"}}");
+    }
+
+    Ok(())
+}
+
+/// Install a systemd user unit mirroring the launchd agent: same command
+/// line, environment, log files and always-restart policy
+fn systemd_install(logging: Option<bool>, human: bool) -> Result<()> {
+    let unit_path = DaemonConfig::systemd_unit_path();
+    let unit_name = DaemonConfig::systemd_unit_name();
+
+    // Check if already installed
+    if unit_path.exists() {
+        if human {
+            println!("Systemd unit already installed at {:?}", unit_path);
+            println!(
+                "To reinstall, run: groundeffect daemon uninstall && groundeffect daemon install"
+            );
+        } else {
+            println!(
+                "{}",
+                serde_json::json!({"status": "already_installed", "unit_path": unit_path})
+            );
+        }
+        return Ok(());
+    }
+
+    // Load existing config or create defaults
+    let mut daemon_config = DaemonConfig::load_file().unwrap_or_default();
+
+    // Only override logging if explicitly specified
+    if let Some(log_enabled) = logging {
+        daemon_config.logging_enabled = log_enabled;
+    }
+    daemon_config.save()?;
+
+    let daemon_path = find_daemon_binary()?;
+
+    if let Some(parent) = unit_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let log_dir = daemon_log_dir();
+    std::fs::create_dir_all(&log_dir)?;
+
+    let unit_content = format!(
+        r#"[Unit]
+Description=GroundEffect email and calendar sync daemon{profile_desc}
+After=network-online.target
+Wants=network-online.target
+
+[Service]
+Type=simple
+ExecStart=/bin/bash -c 'source ~/.secrets 2>/dev/null; exec {daemon_path}{logging_flag}{profile_flag}'
+Restart=always
+RestartSec=5
+LimitNOFILE=65536
+Environment=GROUNDEFFECT_EMAIL_POLL_INTERVAL={email_interval}
+Environment=GROUNDEFFECT_CALENDAR_POLL_INTERVAL={calendar_interval}
+Environment=GROUNDEFFECT_MAX_CONCURRENT_FETCHES={max_fetches}
+StandardOutput=append:{stdout}
+StandardError=append:{stderr}
+
+[Install]
+WantedBy=default.target
+"#,
+        profile_desc = active_profile()
+            .map(|profile| format!(" ({})", profile))
+            .unwrap_or_default(),
+        daemon_path = daemon_path.display(),
+        logging_flag = if daemon_config.logging_enabled {
+            " --log"
+        } else {
+            ""
+        },
+        profile_flag = DaemonConfig::profile_args()
+            .iter()
+            .map(|arg| format!(" {}", arg))
+            .collect::<String>(),
+        stdout = log_dir.join("stdout.log").display(),
+        stderr = log_dir.join("stderr.log").display(),
+        email_interval = daemon_config.email_poll_interval_secs,
+        calendar_interval = daemon_config.calendar_poll_interval_secs,
+        max_fetches = daemon_config.max_concurrent_fetches,
+    );
+
+    std::fs::write(&unit_path, unit_content)?;
+
+    // Pick up the new unit, then enable it at login and start it now
+    let _ = systemctl_user(&["daemon-reload"]);
+    let output = systemctl_user(&["enable", "--now", &unit_name])?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if human {
+            println!("Failed to enable systemd unit: {}", stderr);
+        } else {
+            println!(
+                "{}",
+                serde_json::json!({"status": "error", "message": stderr.trim()})
+            );
+        }
+        return Ok(());
+    }
+
+    if human {
+        println!("✓ Systemd unit installed");
+        println!("  Unit: {:?}", unit_path);
+        println!("  Config: {:?}", DaemonConfig::config_path());
+        println!("  Logs: {:?}", log_dir);
+        println!("\nThe daemon will start automatically at login.");
+        println!("To keep it running while logged out: loginctl enable-linger $USER");
+        println!("To customize settings: groundeffect config settings");
+    } else {
+        println!(
+            "{}",
+            serde_json::json!({
+                "status": "installed",
+                "unit_path": unit_path,
+                "config_path": DaemonConfig::config_path(),
+            })
+        );
+    }
+
+    Ok(())
+}
+
+fn systemd_uninstall(human: bool) -> Result<()> {
+    let unit_path = DaemonConfig::systemd_unit_path();
+
+    if !unit_path.exists() {
+        if human {
+            println!("Systemd unit is not installed.");
+        } else {
+            println!("{{\"status\": \"not_installed\"}}");
+        }
+        return Ok(());
+    }
+
+    // Stop the daemon and disable it at login
+    let _ = systemctl_user(&["disable", "--now", &DaemonConfig::systemd_unit_name()]);
+
+    std::fs::remove_file(&unit_path)?;
+    let _ = systemctl_user(&["daemon-reload"]);
+
+    if human {
+        println!("✓ Systemd unit uninstalled");
+        println!("The daemon will no longer start automatically at login.");
+        println!("\nNote: Synced data and configuration files are preserved.");
+    } else {
+        println!("{{\"status\": \"uninstalled\"}}");
+    }
+
+    Ok(())
+}
+
+fn find_daemon_binary() -> Result<std::path::PathBuf> {
+    // Check common installation paths
+    let home = home_dir();
+    let paths = [
+        // Homebrew on Apple Silicon
+        std::path::PathBuf::from("/opt/homebrew/bin/groundeffect-daemon"),
+        // Homebrew on Intel
+        std::path::PathBuf::from("/usr/local/bin/groundeffect-daemon"),
+        // Cargo install
+        home.join(".cargo").join("bin").join("groundeffect-daemon"),
+    ];
+
+    for path in &paths {
+        if path.exists() {
+            return Ok(path.clone());
+        }
+    }
+
+    // Use which to find it
+    let output = std::process::Command::new("which")
+        .arg("groundeffect-daemon")
+        .output();
+
+    if let Ok(output) = output {
+        if output.status.success() {
+            let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            if !path.is_empty() {
+                return Ok(std::path::PathBuf::from(path));
+            }
+        }
+    }
+
+    anyhow::bail!(
+        "Could not find groundeffect-daemon binary. Make sure it's installed and in your PATH."
+    )
+}
+
+// ============================================================================
+// Account Add/Delete/Configure Functions
+// ============================================================================
+
+async fn account_add(
+    years: Option<String>,
+    service_account: Option<ServiceAccountCredentials>,
+    device_code: bool,
+    attachments: bool,
+    alias: Option<String>,
+    human: bool,
+) -> Result<()> {
+    use dialoguer::{Confirm, Input, Select};
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+
+    // Load config and initialize token provider early
+    let config = Config::load().unwrap_or_default();
+    let token_provider = create_token_provider(&config).await?;
+
+    // Check for OAuth credentials
+    let client_id = std::env::var("GROUNDEFFECT_GOOGLE_CLIENT_ID");
+    let client_secret = std::env::var("GROUNDEFFECT_GOOGLE_CLIENT_SECRET");
+
+    if service_account.is_none() && (client_id.is_err() || client_secret.is_err()) {
+        if human {
+            println!("\n❌ OAuth credentials not configured!\n");
+            println!("Please set the following environment variables:");
+            println!("  export GROUNDEFFECT_GOOGLE_CLIENT_ID=\"your-client-id\"");
+            println!("  export GROUNDEFFECT_GOOGLE_CLIENT_SECRET=\"your-client-secret\"");
+            println!("\nYou can get these from the Google Cloud Console:");
+            println!("  https://console.cloud.google.com/apis/credentials");
+            println!("\nMake sure to:");
+            println!("  1. Create an OAuth 2.0 Client ID (Desktop app type)");
+            println!("  2. Add http://localhost:8085/oauth/callback as a redirect URI");
+            println!("  3. Enable Gmail API and Google Calendar API\n");
+        } else {
+            println!("{{\"success\": false, \"error\": \"OAuth credentials not configured\"}}");
+        }
+        return Ok(());
+    }
+
+    // Interactive prompts if not provided via args
+    let years_to_sync = match years {
+        Some(y) => y,
+        None => {
+            if human {
+                let options = &[
+                    "1 year (recommended)",
+                    "2 years",
+                    "5 years",
+                    "10 years",
+                    "All email history",
+                ];
+                let selection = Select::new()
+                    .with_prompt("How many years of email history to sync?")
+                    .items(options)
+                    .default(0)
+                    .interact()?;
+                match selection {
+                    0 => "1".to_string(),
+                    1 => "2".to_string(),
+                    2 => "5".to_string(),
+                    3 => "10".to_string(),
+                    _ => "all".to_string(),
+                }
+            } else {
+                "1".to_string() // Default for non-interactive
+            }
+        }
+    };
+
+    let sync_attachments = if !attachments && human {
+        Confirm::new()
+            .with_prompt("Enable automatic attachment downloading?")
+            .default(false)
+            .interact()?
+    } else {
+        attachments
+    };
+
+    let account_alias = match alias {
+        Some(a) => {
+            if a.is_empty() {
+                None
+            } else {
+                Some(a)
+            }
+        }
+        None => {
+            if human {
+                let input: String = Input::new()
+                    .with_prompt("Alias for this account (optional, press Enter to skip)")
+                    .allow_empty(true)
+                    .interact_text()?;
+                if input.is_empty() {
+                    None
+                } else {
+                    Some(input)
+                }
+            } else {
+                None
+            }
+        }
+    };
+
+    // Parse years_to_sync
+    let sync_since = if years_to_sync.eq_ignore_ascii_case("all") {
+        None
+    } else {
+        let years: u32 = years_to_sync.parse().unwrap_or(1);
+        let years = years.clamp(1, 20);
+        Some(Utc::now() - chrono::Duration::days(years as i64 * 365))
+    };
+
+    let oauth = OAuthManager::new(token_provider.clone());
+
+    let (tokens, user_info) = if let Some(credentials) = &service_account {
+        if human {
+            println!(
+                "\n🔐 Authenticating as {} via service account {}...\n",
+                credentials.subject, credentials.client_email
+            );
+        }
+        let tokens = oauth.service_account_tokens(credentials).await?;
+        let user_info = oauth.get_user_info(&tokens.access_token).await?;
+        if !user_info.email.eq_ignore_ascii_case(&credentials.subject) {
+            anyhow::bail!(
+                "Service account authenticated as {} instead of {}",
+                user_info.email,
+                credentials.subject
+            );
+        }
+        (tokens, user_info)
+    } else if device_code {
+        match device_code_login(&oauth, human).await {
+            Ok(login) => login,
+            Err(e) => {
+                if human {
+                    println!("❌ OAuth error: {}", e);
+                } else {
+                    println!(
+                        "{}",
+                        serde_json::json!({"success": false, "error": e.to_string()})
+                    );
+                }
+                return Ok(());
+            }
+        }
+    } else {
+        if human {
+            println!("\n🔐 Opening browser for Google authentication...\n");
+        }
+
+        let state = format!("groundeffect_{}", uuid::Uuid::new_v4());
+        let auth_url = oauth.authorization_url(&state);
+
+        if human {
+            println!("If the browser doesn't open, visit this URL manually:");
+            println!("{}\n", auth_url);
+        }
+
+        // Open browser
+        if let Err(e) = open::that(&auth_url) {
+            if human {
+                println!("Failed to open browser: {}", e);
+            }
+        }
+
+        // Start local HTTP server to receive callback
+        let listener = TcpListener::bind("127.0.0.1:8085").await?;
+        if human {
+            println!("⏳ Waiting for authentication callback on http://localhost:8085 ...\n");
+        }
+
+        // Accept one connection with timeout
+        let callback_result = tokio::time::timeout(
+            std::time::Duration::from_secs(300),
+            async {
+                let (mut socket, _) = listener.accept().await?;
+                let mut reader = BufReader::new(&mut socket);
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).await?;
+
+                // Parse callback
+                let (code, received_state) = parse_oauth_callback(&request_line)?;
+
+                if received_state != state {
+                    let response = "HTTP/1.1 400 Bad Request\r\nContent-Type: text/html\r\n\r\n<h1>Error: Invalid state</h1>";
+                    socket.write_all(response.as_bytes()).await?;
+                    anyhow::bail!("OAuth state mismatch - possible CSRF attack");
+                }
+
+                // Send success response
+                let success_html = "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\n\r\n\
+                    <!DOCTYPE html><html><body style='font-family: sans-serif; padding: 40px; text-align: center;'>\
+                    <h1>Authentication Successful!</h1><p>You can close this window.</p></body></html>";
+                socket.write_all(success_html.as_bytes()).await?;
+
+                Ok::<String, anyhow::Error>(code)
+            }
+        ).await;
+
+        let code = match callback_result {
+            Ok(Ok(code)) => code,
+            Ok(Err(e)) => {
+                if human {
+                    println!("❌ OAuth error: {}", e);
+                } else {
+                    println!("{{\"success\": false, \"error\": \"{}\"}}", e);
+                }
+                return Ok(());
+            }
+            Err(_) => {
+                if human {
+                    println!("❌ OAuth timeout: no callback received within 5 minutes");
+                } else {
+                    println!("{{\"success\": false, \"error\": \"OAuth timeout\"}}");
+                }
+                return Ok(());
+            }
+        };
+
+        if human {
+            println!("✅ Received authorization code, exchanging for tokens...\n");
+        }
+
+        // Exchange code for tokens
+        oauth.exchange_code(&code).await?
+    };
+
+    // Store tokens
+    token_provider
+        .store_tokens(&user_info.email, &tokens)
+        .await?;
+
+    // Open database and create/update account
+    std::fs::create_dir_all(config.lancedb_dir())?;
+    let db = Database::open(config.lancedb_dir()).await?;
+
+    let is_existing = db.get_account(&user_info.email).await?.is_some();
+
+    if let Some(existing) = db.get_account(&user_info.email).await? {
+        let mut updated = existing;
+        updated.status = AccountStatus::Active;
+        updated.alias = account_alias.clone().or(updated.alias);
+        updated.sync_email_since = sync_since;
+        updated.sync_attachments = sync_attachments;
+        db.upsert_account(&updated).await?;
+    } else {
+        let account = Account {
+            id: user_info.email.clone(),
+            alias: account_alias.clone(),
+            display_name: user_info.name.unwrap_or_else(|| user_info.email.clone()),
+            added_at: Utc::now(),
+            last_sync_email: None,
+            last_sync_calendar: None,
+            status: AccountStatus::Active,
+            sync_email_since: sync_since,
+            oldest_email_synced: None,
+            oldest_event_synced: None,
+            sync_attachments,
+            estimated_total_emails: None,
+            include_labels: vec![],
+            exclude_labels: vec![],
+            max_storage_bytes: None,
+            retention: Default::default(),
+            headers_only: false,
+        };
+        db.upsert_account(&account).await?;
+    }
+    record_audit(
+        &db,
+        "add_account",
+        Some(&user_info.email),
+        serde_json::json!({
+            "alias": account_alias,
+            "years_to_sync": years_to_sync,
+            "sync_attachments": sync_attachments,
+            "existing": is_existing,
+        }),
+    )
+    .await;
+
+    if human {
+        if is_existing {
+            println!("🎉 Re-authenticated account: {}", user_info.email);
+        } else {
+            println!("🎉 Successfully added account: {}", user_info.email);
+        }
+        if let Some(a) = &account_alias {
+            println!("   Alias: {}", a);
+        }
+        println!("   Sync {} of email history", years_to_sync);
+        println!(
+            "   Attachments: {}",
+            if sync_attachments {
+                "enabled"
+            } else {
+                "disabled"
+            }
+        );
+        println!("\nThe daemon will start syncing automatically if running.");
+    } else {
+        println!("{{\"success\": true, \"account\": {{\"id\": \"{}\", \"alias\": {}, \"years_to_sync\": \"{}\", \"sync_attachments\": {}}}}}",
+            user_info.email,
+            account_alias.as_ref().map(|a| format!("\"{}\"", a)).unwrap_or("null".to_string()),
+            years_to_sync,
+            sync_attachments);
+    }
+
+    Ok(())
+}
+
+async fn account_reauth(account: &str, device_code: bool, human: bool) -> Result<()> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+
+    let config = Config::load().unwrap_or_default();
+    let token_provider = create_token_provider(&config).await?;
+    let db = Database::open(config.lancedb_dir()).await?;
+    let accounts = db.list_accounts().await?;
+
+    let email = accounts
+        .iter()
+        .find(|a| a.id == account || a.alias.as_ref() == Some(&account.to_string()))
+        .map(|a| a.id.clone());
+
+    let email = match email {
+        Some(e) => e,
+        None => {
+            if human {
+                println!("❌ Account not found: {}", account);
+            } else {
+                println!("{{\"success\": false, \"error\": \"Account not found\"}}");
+            }
+            return Ok(());
+        }
+    };
+
+    // Service account accounts re-mint a token from the stored key
+    let stored = token_provider.get_tokens(&email).await?;
+    if stored.is_some_and(|t| t.service_account.is_some()) {
+        let oauth = OAuthManager::new(token_provider.clone());
+        oauth.refresh_token(&email).await?;
+        if let Some(mut existing) = db.get_account(&email).await? {
+            existing.status = AccountStatus::Active;
+            db.upsert_account(&existing).await?;
+        }
+        if human {
+            println!("🎉 Re-authenticated account via service account: {}", email);
+        } else {
+            println!(
+                "{}",
+                serde_json::json!({
+                    "success": true,
+                    "account": email,
+                    "status": "active",
+                    "method": "service_account",
+                })
+            );
+        }
+        return Ok(());
+    }
+
+    // Check for OAuth credentials
+    let client_id = std::env::var("GROUNDEFFECT_GOOGLE_CLIENT_ID");
+    let client_secret = std::env::var("GROUNDEFFECT_GOOGLE_CLIENT_SECRET");
+
+    if client_id.is_err() || client_secret.is_err() {
+        if human {
+            println!("\n❌ OAuth credentials not configured!\n");
+            println!("Please set the following environment variables:");
+            println!("  export GROUNDEFFECT_GOOGLE_CLIENT_ID=\"your-client-id\"");
+            println!("  export GROUNDEFFECT_GOOGLE_CLIENT_SECRET=\"your-client-secret\"");
+            println!("\nYou can get these from the Google Cloud Console:");
+            println!("  https://console.cloud.google.com/apis/credentials");
+            println!("\nMake sure to:");
+            println!("  1. Create an OAuth 2.0 Client ID (Desktop app type)");
+            println!("  2. Add http://localhost:8085/oauth/callback as a redirect URI");
+            println!("  3. Enable Gmail API and Google Calendar API\n");
+        } else {
+            println!("{{\"success\": false, \"error\": \"OAuth credentials not configured\"}}");
+        }
+        return Ok(());
+    }
+
+    let oauth = OAuthManager::new(token_provider.clone());
+    let (tokens, user_info) = if device_code {
+        if human {
+            println!("\n🔐 Re-authenticating account: {}", email);
+        }
+        match device_code_login(&oauth, human).await {
+            Ok(login) => login,
+            Err(e) => {
+                if human {
+                    println!("❌ OAuth error: {}", e);
+                } else {
+                    println!(
+                        "{}",
+                        serde_json::json!({"success": false, "error": e.to_string()})
+                    );
+                }
+                return Ok(());
+            }
+        }
+    } else {
+        if human {
+            println!("\n🔐 Re-authenticating account: {}", email);
+            println!("   Opening browser for Google authentication...\n");
+        }
+
+        let state = format!("groundeffect_reauth_{}", uuid::Uuid::new_v4());
+        let auth_url = oauth.authorization_url(&state);
+
+        if human {
+            println!("If the browser doesn't open, visit this URL manually:");
+            println!("{}\n", auth_url);
+        }
+
+        if let Err(e) = open::that(&auth_url) {
+            if human {
+                println!("Failed to open browser: {}", e);
+            }
+        }
+
+        // Start local HTTP server to receive callback
+        let listener = TcpListener::bind("127.0.0.1:8085").await?;
+        if human {
+            println!("⏳ Waiting for authentication callback on http://localhost:8085 ...\n");
+        }
+
+        // Accept one connection with timeout
+        let callback_result = tokio::time::timeout(
+            std::time::Duration::from_secs(300),
+            async {
+                let (mut socket, _) = listener.accept().await?;
+                let mut reader = BufReader::new(&mut socket);
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).await?;
+
+                // Parse callback
+                let (code, received_state) = parse_oauth_callback(&request_line)?;
+
+                if received_state != state {
+                    let response = "HTTP/1.1 400 Bad Request\r\nContent-Type: text/html\r\n\r\n<h1>Error: Invalid state</h1>";
+                    socket.write_all(response.as_bytes()).await?;
+                    anyhow::bail!("OAuth state mismatch - possible CSRF attack");
+                }
+
+                // Send success response
+                let success_html = "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\n\r\n\
+                    <!DOCTYPE html><html><body style='font-family: sans-serif; padding: 40px; text-align: center;'>\
+                    <h1>Authentication Successful!</h1><p>You can close this window.</p></body></html>";
+                socket.write_all(success_html.as_bytes()).await?;
+
+                Ok::<String, anyhow::Error>(code)
+            }
+        ).await;
+
+        let code = match callback_result {
+            Ok(Ok(code)) => code,
+            Ok(Err(e)) => {
+                if human {
+                    println!("❌ OAuth error: {}", e);
+                } else {
+                    println!("{{\"success\": false, \"error\": \"{}\"}}", e);
+                }
+                return Ok(());
+            }
+            Err(_) => {
+                if human {
+                    println!("❌ OAuth timeout: no callback received within 5 minutes");
+                } else {
+                    println!("{{\"success\": false, \"error\": \"OAuth timeout\"}}");
+                }
+                return Ok(());
+            }
+        };
+
+        if human {
+            println!("✅ Received authorization code, exchanging for tokens...\n");
+        }
+
+        // Exchange code for tokens
+        oauth.exchange_code(&code).await?
+    };
+
+    if user_info.email != email {
+        if human {
+            println!(
+                "❌ Authenticated as {}, expected {}",
+                user_info.email, email
+            );
+            println!("Please retry and select the correct Google account.");
+        } else {
+            println!(
+                "{{\"success\": false, \"error\": \"Authenticated as {} but expected {}\"}}",
+                user_info.email, email
+            );
+        }
+        return Ok(());
+    }
+
+    token_provider.store_tokens(&email, &tokens).await?;
+
+    if let Some(mut existing) = db.get_account(&email).await? {
+        existing.status = AccountStatus::Active;
+        if let Some(name) = user_info.name {
+            if !name.trim().is_empty() {
+                existing.display_name = name;
+            }
+        }
+        db.upsert_account(&existing).await?;
+    }
+
+    if human {
+        println!("🎉 Re-authenticated account: {}", email);
+    } else {
+        println!(
+            "{{\"success\": true, \"account\": \"{}\", \"status\": \"active\"}}",
+            email
+        );
+    }
+
+    Ok(())
+}
+
+/// Run the OAuth device authorization flow: show the code to enter on
+/// another device, then wait for approval. Instructions go to stderr in JSON
+/// mode so stdout stays a single JSON result.
+async fn device_code_login(oauth: &OAuthManager, human: bool) -> Result<(OAuthTokens, UserInfo)> {
+    let device = oauth.request_device_code().await?;
+    let minutes = (device.expires_in / 60).max(1);
+
+    if human {
+        println!("\n🔐 On any device, visit:\n");
+        println!("   {}\n", device.verification_url);
+        println!("   and enter the code: {}\n", device.user_code);
+        println!(
+            "⏳ Waiting for authorization (code expires in {} minutes)...\n",
+            minutes
+        );
+    } else {
+        eprintln!(
+            "{}",
+            serde_json::json!({
+                "verification_url": device.verification_url,
+                "user_code": device.user_code,
+                "expires_in": device.expires_in,
+            })
+        );
+    }
+
+    let login = oauth.poll_device_authorization(&device).await?;
+    if human {
+        println!("✅ Device authorized\n");
+    }
+    Ok(login)
+}
+
+fn parse_oauth_callback(request_line: &str) -> Result<(String, String)> {
+    let parts: Vec<&str> = request_line.split_whitespace().collect();
+    if parts.len() < 2 {
+        anyhow::bail!("Invalid HTTP request");
+    }
+
+    let path = parts[1];
+    if !path.starts_with("/oauth/callback") {
+        anyhow::bail!("Unexpected callback path: {}", path);
+    }
+
+    let query_start = path
+        .find('?')
+        .ok_or_else(|| anyhow::anyhow!("No query string"))?;
+    let query = &path[query_start + 1..];
+
+    let mut code = None;
+    let mut state = None;
+
+    for param in query.split('&') {
+        let mut kv = param.splitn(2, '=');
+        let key = kv.next().unwrap_or("");
+        let value = kv.next().unwrap_or("");
+        match key {
+            "code" => code = Some(urlencoding::decode(value).unwrap_or_default().into_owned()),
+            "state" => state = Some(urlencoding::decode(value).unwrap_or_default().into_owned()),
+            _ => {}
+        }
+    }
+
+    let code = code.ok_or_else(|| anyhow::anyhow!("No authorization code in callback"))?;
+    let state = state.ok_or_else(|| anyhow::anyhow!("No state in callback"))?;
+
+    Ok((code, state))
+}
+
+async fn account_delete(account: &str, confirm: bool, dry_run: bool, human: bool) -> Result<()> {
+    if !confirm && !dry_run {
+        if human {
+            println!("❌ Must pass --confirm flag to delete an account.");
+            println!(
+                "\nThis will permanently delete all synced emails and events for this account."
+            );
+            println!("Example: groundeffect account delete {} --confirm", account);
+        } else {
+            println!("{{\"success\": false, \"error\": \"Must pass --confirm to delete\"}}");
+        }
+        return Ok(());
+    }
+
+    let config = Config::load().unwrap_or_default();
+    let db = Database::open(config.lancedb_dir()).await?;
+    let accounts = db.list_accounts().await?;
+
+    // Resolve account
+    let email = accounts
+        .iter()
+        .find(|a| a.id == account || a.alias.as_ref() == Some(&account.to_string()))
+        .map(|a| a.id.clone());
+
+    match email {
+        Some(email) if dry_run => {
+            let preview = deletion_preview(&db, &email, "all").await?;
+            if human {
+                println!("🔍 Dry run: deleting {} would remove:", email);
+                print_deletion_preview(&preview);
+                println!("   the account record and its OAuth tokens");
+                println!("\nNothing was changed. Re-run with --confirm to delete.");
+            } else {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&serde_json::json!({
+                        "success": true,
+                        "dry_run": true,
+                        "account": email,
+                        "would_delete": preview,
+                    }))?
+                );
+            }
+        }
+        Some(email) => {
+            let token_provider = create_token_provider(&config).await?;
+
+            // Delete synced data
+            let (email_count, event_count) = db.clear_account_sync_data(&email).await?;
+
+            // Delete account
+            db.delete_account(&email).await?;
+            record_audit(
+                &db,
+                "delete_account",
+                Some(&email),
+                serde_json::json!({ "emails": email_count, "events": event_count }),
+            )
+            .await;
+
+            // Delete tokens
+            if let Err(e) = token_provider.delete_tokens(&email).await {
+                if human {
+                    println!("Warning: Failed to delete tokens: {}", e);
+                }
+            }
+
+            if human {
+                println!("✅ Account deleted: {}", email);
+                println!("   {} emails removed", email_count);
+                println!("   {} events removed", event_count);
+            } else {
+                println!("{{\"success\": true, \"deleted\": {{\"account\": \"{}\", \"emails\": {}, \"events\": {}}}}}",
+                    email, email_count, event_count);
+            }
+        }
+        None => {
+            if human {
+                println!("❌ Account not found: {}", account);
+            } else {
+                println!("{{\"success\": false, \"error\": \"Account not found\"}}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Move an account and everything keyed by its address to `new_email`
+async fn account_rename(account: &str, new_email: &str, dry_run: bool, human: bool) -> Result<()> {
+    let new = new_email.trim();
+    if !new.contains('@') {
+        anyhow::bail!("'{}' is not an email address", new);
+    }
+
+    let config = Config::load().unwrap_or_default();
+    let db = Database::open(config.lancedb_dir()).await?;
+    let accounts = db.list_accounts().await?;
+    let old = resolve_account(&accounts, account)
+        .ok_or_else(|| anyhow::anyhow!("Account not found: {}", account))?;
+    if accounts.iter().any(|a| a.id.eq_ignore_ascii_case(new)) {
+        anyhow::bail!(
+            "An account {} already exists; delete it first with 'groundeffect account delete {} --confirm'",
+            new,
+            new
+        );
+    }
+    if !dry_run && check_daemon_running() {
+        anyhow::bail!(
+            "The daemon is running; stop it first with 'groundeffect daemon stop', then rename"
+        );
+    }
+
+    if dry_run {
+        let mut rows = serde_json::Map::new();
+        rows.insert(
+            "emails".to_string(),
+            db.count_emails(Some(&old)).await?.into(),
+        );
+        rows.insert(
+            "events".to_string(),
+            db.count_events(Some(&old)).await?.into(),
+        );
+        for (table, count) in db.count_email_derived_rows(&old).await? {
+            rows.insert(table.to_string(), count.into());
+        }
+        if human {
+            println!("🔍 Dry run: renaming {} to {} would move:", old, new);
+            for (table, count) in &rows {
+                println!("   {} {}", count, table);
+            }
+            println!("   the account record, its OAuth tokens, attachments and caches");
+            println!("\nNothing was changed. Run again without --dry-run to rename.");
+        } else {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "success": true,
+                    "dry_run": true,
+                    "from": old,
+                    "to": new,
+                    "rows": rows,
+                }))?
+            );
+        }
+        return Ok(());
+    }
+
+    // Tokens are copied first and the old ones removed last, so a failed
+    // rename leaves the account able to sync under its old address
+    let token_provider = create_token_provider(&config).await?;
+    let tokens = token_provider.get_tokens(&old).await?;
+    if let Some(tokens) = &tokens {
+        token_provider.store_tokens(new, tokens).await?;
+    }
+    let renamed = match db
+        .rename_account(&old, new, &config.attachments_dir())
+        .await
+    {
+        Ok(renamed) => renamed,
+        Err(e) => {
+            if tokens.is_some() {
+                let _ = token_provider.delete_tokens(new).await;
+            }
+            return Err(e.into());
+        }
+    };
+    let mut warnings = Vec::new();
+    if tokens.is_some() {
+        if let Err(e) = token_provider.delete_tokens(&old).await {
+            warnings.push(format!("Failed to delete the old tokens: {}", e));
+        }
+    }
+
+    let files = rename::move_files(&config, &old, new, &renamed.email_ids);
+    warnings.extend(
+        files
+            .failed
+            .iter()
+            .map(|failure| format!("Not moved: {}", failure)),
+    );
+
+    let mut file_config = Config::load_file()?;
+    let config_updated = rename::rename_in_config(&mut file_config, &old, new);
+    if config_updated {
+        file_config.save()?;
+    }
+
+    record_audit(
+        &db,
+        "rename_account",
+        Some(new),
+        serde_json::json!({ "from": old, "rows": renamed.tables }),
+    )
+    .await;
+
+    if human {
+        println!("✅ Account renamed: {} → {}", old, new);
+        for (table, count) in &renamed.tables {
+            println!("   {} {} moved", count, table);
+        }
+        if tokens.is_some() {
+            println!("   OAuth tokens moved");
+        }
+        if files.attachments {
+            println!("   Attachments moved");
+        }
+        if files.raw_messages > 0 {
+            println!("   {} raw messages moved", files.raw_messages);
+        }
+        if config_updated {
+            println!("   config.toml updated");
+        }
+        for warning in &warnings {
+            println!("⚠️  {}", warning);
+        }
+    } else {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "success": true,
+                "from": old,
+                "to": new,
+                "rows": renamed.tables,
+                "tokens_moved": tokens.is_some(),
+                "files": files,
+                "config_updated": config_updated,
+                "warnings": warnings,
+            }))?
+        );
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn account_configure(
+    account: &str,
+    alias: Option<String>,
+    attachments: bool,
+    no_attachments: bool,
+    include_labels: Vec<String>,
+    exclude_labels: Vec<String>,
+    clear_labels: bool,
+    max_storage: Option<String>,
+    retain: Option<String>,
+    retain_categories: Vec<String>,
+    headers_only: bool,
+    full_bodies: bool,
+    human: bool,
+) -> Result<()> {
+    // Parse the storage limit up front ("none"/"0" removes it)
+    let max_storage = match max_storage.as_deref().map(str::trim) {
+        None => None,
+        Some(v) if v.eq_ignore_ascii_case("none") || v == "0" => Some(None),
+        Some(v) => match parse_storage_size(v) {
+            Some(bytes) => Some(Some(bytes)),
+            None => {
+                if human {
+                    println!("❌ Invalid storage size: {} (use e.g. 5GB or 500MB)", v);
+                } else {
+                    println!(
+                        "{}",
+                        serde_json::json!({
+                            "success": false,
+                            "error": format!("Invalid storage size: {}", v),
+                        })
+                    );
+                }
+                return Ok(());
+            }
+        },
+    };
+
+    // Parse retention ages up front as well ("none" removes a rule)
+    let retain = match retain.as_deref().map(str::trim) {
+        None => None,
+        Some(v) if v.eq_ignore_ascii_case("none") => Some(None),
+        Some(v) => match parse_retention_age(v) {
+            Some(days) => Some(Some(days)),
+            None => {
+                print_configure_error(
+                    human,
+                    &format!("Invalid retention age: {} (use e.g. 2y, 6m or 90d)", v),
+                );
+                return Ok(());
+            }
+        },
+    };
+    let mut category_rules = Vec::new();
+    for rule in &retain_categories {
+        match parse_category_retention(rule) {
+            Some(parsed) => category_rules.push(parsed),
+            None => {
+                print_configure_error(
+                    human,
+                    &format!(
+                        "Invalid category retention: {} (use e.g. newsletter=30d)",
+                        rule
+                    ),
+                );
+                return Ok(());
+            }
+        }
+    }
+
+    let config = Config::load().unwrap_or_default();
+    let db = Database::open(config.lancedb_dir()).await?;
+    let accounts = db.list_accounts().await?;
+
+    // Resolve account
+    let email = accounts
+        .iter()
+        .find(|a| a.id == account || a.alias.as_ref() == Some(&account.to_string()))
+        .map(|a| a.id.clone());
+
+    match email {
+        Some(email) => {
+            let mut acct = db.get_account(&email).await?.unwrap();
+            let mut changes = vec![];
+
+            // Update alias
+            if let Some(new_alias) = alias {
+                if new_alias.is_empty() {
+                    if acct.alias.is_some() {
+                        acct.alias = None;
+                        changes.push("alias removed".to_string());
                     }
+                } else {
+                    acct.alias = Some(new_alias.clone());
+                    changes.push(format!("alias set to '{}'", new_alias));
                 }
             }
-        }
 
-        CalendarCommands::Events {
-            from,
-            to,
-            account,
-            limit,
-            human,
-        } => {
-            let human = human || global_human;
-            let config = Config::load().unwrap_or_default();
-            let db = Database::open(config.lancedb_dir()).await?;
+            // Update attachments
+            if attachments && !no_attachments {
+                if !acct.sync_attachments {
+                    acct.sync_attachments = true;
+                    changes.push("sync_attachments enabled".to_string());
+                }
+            } else if no_attachments && !attachments {
+                if acct.sync_attachments {
+                    acct.sync_attachments = false;
+                    changes.push("sync_attachments disabled".to_string());
+                }
+            }
 
-            // Default to today if --from not specified
-            let from_date = match &from {
-                Some(d) => d.clone(),
-                None => chrono::Utc::now().format("%Y-%m-%d").to_string(),
-            };
+            // Update sync mode; headers-only mail has no attachments to download
+            if headers_only {
+                if !acct.headers_only {
+                    acct.headers_only = true;
+                    changes.push("headers_only enabled".to_string());
+                }
+                if acct.sync_attachments {
+                    acct.sync_attachments = false;
+                    changes.push("sync_attachments disabled".to_string());
+                }
+            } else if full_bodies && acct.headers_only {
+                acct.headers_only = false;
+                changes.push("headers_only disabled".to_string());
+            }
 
-            // Default to 7 days after from if --to not specified
-            let to_date = match &to {
-                Some(d) => d.clone(),
-                None => {
-                    let from_parsed = chrono::NaiveDate::parse_from_str(&from_date, "%Y-%m-%d")
-                        .unwrap_or_else(|_| chrono::Utc::now().date_naive());
-                    (from_parsed + chrono::Duration::days(7))
-                        .format("%Y-%m-%d")
-                        .to_string()
+            // Update label filters
+            if clear_labels && (!acct.include_labels.is_empty() || !acct.exclude_labels.is_empty())
+            {
+                acct.include_labels.clear();
+                acct.exclude_labels.clear();
+                changes.push("label filters cleared".to_string());
+            }
+            if !include_labels.is_empty() && acct.include_labels != include_labels {
+                changes.push(format!("include_labels set to {:?}", include_labels));
+                acct.include_labels = include_labels;
+            }
+            if !exclude_labels.is_empty() && acct.exclude_labels != exclude_labels {
+                changes.push(format!("exclude_labels set to {:?}", exclude_labels));
+                acct.exclude_labels = exclude_labels;
+            }
+
+            // Update storage limit
+            if let Some(limit) = max_storage {
+                if acct.max_storage_bytes != limit {
+                    acct.max_storage_bytes = limit;
+                    changes.push(match limit {
+                        Some(bytes) => format!("max_storage set to {}", format_bytes(bytes)),
+                        None => "max_storage removed".to_string(),
+                    });
                 }
-            };
+            }
 
-            let accounts_ref = account
-                .as_ref()
-                .map(|v| v.iter().map(|s| s.clone()).collect::<Vec<_>>());
-            let events = db
-                .list_events_in_range(
-                    accounts_ref.as_deref(),
-                    &from_date,
-                    &to_date,
-                    limit.min(200),
-                )
-                .await?;
+            // Update retention policy
+            if let Some(days) = retain {
+                if acct.retention.max_age_days != days {
+                    acct.retention.max_age_days = days;
+                    changes.push(match days {
+                        Some(days) => format!("retention set to {} days", days),
+                        None => "retention removed".to_string(),
+                    });
+                }
+            }
+            for (category, days) in category_rules {
+                let rules = &mut acct.retention.category_max_age_days;
+                let previous = match days {
+                    Some(days) => rules.insert(category, days),
+                    None => rules.remove(&category),
+                };
+                if previous != days {
+                    changes.push(match days {
+                        Some(days) => {
+                            format!("{} retention set to {} days", category.as_str(), days)
+                        }
+                        None => format!("{} retention removed", category.as_str()),
+                    });
+                }
+            }
 
-            if human {
-                if events.is_empty() {
-                    println!("No events found from {} to {}.", from_date, to_date);
+            if changes.is_empty() {
+                if human {
+                    println!("No changes specified.");
+                    println!("\nCurrent settings:");
+                    println!("  Alias: {}", acct.alias.as_deref().unwrap_or("(none)"));
+                    println!("  Sync attachments: {}", acct.sync_attachments);
+                    println!(
+                        "  Include labels: {}",
+                        format_label_list(&acct.include_labels)
+                    );
+                    println!(
+                        "  Exclude labels: {}",
+                        format_label_list(&acct.exclude_labels)
+                    );
+                    println!(
+                        "  Max storage: {}",
+                        acct.max_storage_bytes
+                            .map(format_bytes)
+                            .unwrap_or_else(|| "(unlimited)".to_string())
+                    );
+                    println!("  Retention: {}", acct.retention.describe());
+                    println!("  Headers only: {}", acct.headers_only);
                 } else {
                     println!(
-                        "\n📅 Events from {} to {} ({} events)\n",
-                        from_date,
-                        to_date,
-                        events.len()
+                        "{}",
+                        serde_json::to_string_pretty(&serde_json::json!({
+                            "success": true,
+                            "changes": changes,
+                            "account": configured_account_json(&acct),
+                        }))?
+                    );
+                }
+            } else {
+                db.upsert_account(&acct).await?;
+                record_audit(
+                    &db,
+                    "configure_account",
+                    Some(&email),
+                    serde_json::json!({ "changes": changes }),
+                )
+                .await;
+
+                if human {
+                    println!("✅ Account configured: {}", email);
+                    for change in &changes {
+                        println!("   - {}", change);
+                    }
+                    if changes.iter().any(|c| c.contains("sync_attachments")) {
+                        println!("\nRestart the daemon for attachment changes to take effect.");
+                    }
+                    if changes.iter().any(|c| c.contains("label")) {
+                        println!("\nLabel filters apply from the next sync.");
+                    }
+                    if changes.iter().any(|c| c.contains("retention set")) {
+                        println!("\nThe daemon prunes older mail on its next retention run.");
+                    }
+                    if changes.iter().any(|c| c.contains("headers_only")) {
+                        println!(
+                            "\nThe sync mode applies to mail synced from now on; \
+                             'groundeffect sync reset' re-syncs existing mail."
+                        );
+                    }
+                } else {
+                    println!(
+                        "{}",
+                        serde_json::to_string_pretty(&serde_json::json!({
+                            "success": true,
+                            "changes": changes,
+                            "account": configured_account_json(&acct),
+                        }))?
                     );
-                    let mut current_date = String::new();
-                    for event in &events {
-                        let event_date = match &event.start {
-                            EventTime::DateTime(dt) => dt.format("%Y-%m-%d").to_string(),
-                            EventTime::Date(d) => d.to_string(),
-                        };
-                        if event_date != current_date {
-                            current_date = event_date.clone();
-                            // Parse and format as weekday
-                            if let Ok(d) =
-                                chrono::NaiveDate::parse_from_str(&current_date, "%Y-%m-%d")
-                            {
-                                println!("━━ {} ━━", d.format("%A, %B %e, %Y"));
-                            } else {
-                                println!("━━ {} ━━", current_date);
-                            }
-                        }
-                        let time_str = match &event.start {
-                            EventTime::DateTime(dt) => dt.format("%l:%M %p").to_string(),
-                            EventTime::Date(_) => "All day".to_string(),
-                        };
-                        let duration = match (&event.start, &event.end) {
-                            (EventTime::DateTime(s), EventTime::DateTime(e)) => {
-                                let mins = (*e - *s).num_minutes();
-                                if mins >= 60 {
-                                    format!(" ({}h{}m)", mins / 60, mins % 60)
-                                } else {
-                                    format!(" ({}m)", mins)
-                                }
-                            }
-                            _ => String::new(),
-                        };
-                        println!("  {} {}{}", time_str.trim(), event.summary, duration);
-                        if let Some(loc) = &event.location {
-                            if !loc.is_empty() {
-                                println!("          📍 {}", loc);
-                            }
-                        }
-                        // Show organizer info if it's someone else's event
-                        if let Some(org) = &event.organizer {
-                            let org_name = org.name.as_deref().unwrap_or(&org.email);
-                            // Check if organizer is different from the account
-                            if org.email != event.account_id {
-                                println!("          👤 Invited by: {}", org_name);
-                            }
-                        }
-                    }
-                    println!();
-                }
-            } else {
-                #[derive(Serialize)]
-                struct EventResult {
-                    id: String,
-                    summary: String,
-                    start: String,
-                    end: String,
-                    #[serde(skip_serializing_if = "Option::is_none")]
-                    location: Option<String>,
-                    #[serde(skip_serializing_if = "Option::is_none")]
-                    organizer: Option<AttendeeDetail>,
-                    attendees: Vec<AttendeeDetail>,
-                    account_id: String,
-                    calendar_id: String,
                 }
-                let results: Vec<EventResult> = events
-                    .iter()
-                    .map(|e| EventResult {
-                        id: e.id.clone(),
-                        summary: e.summary.clone(),
-                        start: format_event_time(&e.start),
-                        end: format_event_time(&e.end),
-                        location: e.location.clone(),
-                        organizer: e.organizer.as_ref().map(|o| AttendeeDetail {
-                            email: o.email.clone(),
-                            name: o.name.clone(),
-                            response_status: o
-                                .response_status
-                                .as_ref()
-                                .map(|s| format!("{:?}", s).to_lowercase()),
-                            optional: o.optional,
-                        }),
-                        attendees: e
-                            .attendees
-                            .iter()
-                            .map(|a| AttendeeDetail {
-                                email: a.email.clone(),
-                                name: a.name.clone(),
-                                response_status: a
-                                    .response_status
-                                    .as_ref()
-                                    .map(|s| format!("{:?}", s).to_lowercase()),
-                                optional: a.optional,
-                            })
-                            .collect(),
-                        account_id: e.account_id.clone(),
-                        calendar_id: e.calendar_id.clone(),
-                    })
-                    .collect();
-                println!("{}", serde_json::to_string_pretty(&results)?);
             }
         }
-
-        CalendarCommands::Create {
-            account,
-            summary,
-            start,
-            end,
-            description,
-            location,
-            attendees,
-            calendar,
-            human,
-        } => {
-            let human = human || global_human;
-            calendar_create(
-                &account,
-                &summary,
-                &start,
-                &end,
-                description.as_deref(),
-                location.as_deref(),
-                attendees,
-                &calendar,
-                human,
-            )
-            .await?;
+        None => {
+            if human {
+                println!("❌ Account not found: {}", account);
+            } else {
+                println!("{{\"success\": false, \"error\": \"Account not found\"}}");
+            }
         }
     }
 
     Ok(())
 }
 
-fn format_event_time_human(et: &EventTime) -> String {
-    match et {
-        EventTime::DateTime(dt) => dt.format("%Y-%m-%d %H:%M").to_string(),
-        EventTime::Date(d) => d.to_string(),
+/// Account settings echoed back by `account configure`
+fn configured_account_json(acct: &Account) -> serde_json::Value {
+    serde_json::json!({
+        "id": acct.id,
+        "alias": acct.alias,
+        "sync_attachments": acct.sync_attachments,
+        "include_labels": acct.include_labels,
+        "exclude_labels": acct.exclude_labels,
+        "max_storage_bytes": acct.max_storage_bytes,
+        "retention": acct.retention,
+        "headers_only": acct.headers_only,
+    })
+}
+
+/// Report an invalid `account configure` argument
+fn print_configure_error(human: bool, message: &str) {
+    if human {
+        println!("❌ {}", message);
+    } else {
+        println!(
+            "{}",
+            serde_json::json!({
+                "success": false,
+                "error": message,
+            })
+        );
+    }
+}
+
+fn format_label_list(labels: &[String]) -> String {
+    if labels.is_empty() {
+        "(none)".to_string()
+    } else {
+        labels.join(", ")
     }
 }
 
 // ============================================================================
-// Account Command Handlers
+// Activity Command Handlers
 // ============================================================================
 
-async fn handle_account_command(command: AccountCommands, global_human: bool) -> Result<()> {
+async fn handle_activity_command(command: ActivityCommands, global_human: bool) -> Result<()> {
     match command {
-        AccountCommands::List { human } => {
+        ActivityCommands::List {
+            account,
+            source,
+            project,
+            after,
+            limit,
+            human,
+        } => {
             let human = human || global_human;
             let config = Config::load().unwrap_or_default();
             let db = Database::open(config.lancedb_dir()).await?;
-            let accounts = db.list_accounts().await?;
+
+            let account_id = if let Some(acct) = account {
+                let all_accounts = db.list_accounts().await?;
+                resolve_account(&all_accounts, &acct)
+            } else {
+                None
+            };
+            let since = parse_date(&after, &config.general.timezone);
+
+            let items = db
+                .list_activity(
+                    account_id.as_deref(),
+                    source,
+                    project.as_deref(),
+                    since,
+                    limit.min(200),
+                )
+                .await?;
 
             if human {
-                if accounts.is_empty() {
-                    println!("No accounts configured.");
-                    println!("\nAdd an account with:");
-                    println!("  groundeffect-daemon add-account");
+                if items.is_empty() {
+                    println!("No activity found.");
+                    if !config.activity.enabled {
+                        println!("Activity parsing is disabled ([activity] enabled = false).");
+                    }
                 } else {
-                    println!("\n📧 Accounts:\n");
-                    for account in &accounts {
-                        let status_icon = match account.status {
-                            AccountStatus::Active => "✓",
-                            AccountStatus::NeedsReauth => "⚠",
-                            AccountStatus::Disabled => "○",
-                            AccountStatus::Syncing => "↻",
+                    println!("\nRecent {} activity items:\n", items.len());
+                    for item in &items {
+                        let target = match &item.item {
+                            Some(key) => format!("{} {}", item.project, key),
+                            None => item.project.clone(),
                         };
-                        let alias = account
-                            .alias
-                            .as_ref()
-                            .map(|a| format!(" ({})", a))
-                            .unwrap_or_default();
-                        println!("{} {}{}", status_icon, account.id, alias);
-                        println!("  Status: {:?}", account.status);
-                        println!("  Display name: {}", account.display_name);
+                        println!("🔔 [{}] {} — {}", item.source, target, item.title);
+                        match &item.actor {
+                            Some(actor) => println!("   {} by {}", item.action, actor),
+                            None => println!("   {}", item.action),
+                        }
+                        println!("   Date: {}", item.date.format("%Y-%m-%d %H:%M"));
+                        if let Some(url) = &item.url {
+                            println!("   URL: {}", url);
+                        }
+                        println!("   ID: {}", item.id);
                         println!();
                     }
                 }
             } else {
-                let json_results: Vec<AccountResult> =
-                    accounts.iter().map(AccountResult::from_account).collect();
-                println!("{}", serde_json::to_string_pretty(&json_results)?);
+                println!("{}", serde_json::to_string_pretty(&items)?);
             }
         }
+    }
 
-        AccountCommands::Show { account, human } => {
+    Ok(())
+}
+
+// ============================================================================
+// Receipts Command Handlers
+// ============================================================================
+
+async fn handle_receipts_command(command: ReceiptsCommands, global_human: bool) -> Result<()> {
+    match command {
+        ReceiptsCommands::List {
+            account,
+            month,
+            merchant,
+            limit,
+            csv,
+            human,
+        } => {
             let human = human || global_human;
             let config = Config::load().unwrap_or_default();
             let db = Database::open(config.lancedb_dir()).await?;
-            let accounts = db.list_accounts().await?;
 
-            let account_id = resolve_account(&accounts, &account);
+            let account_id = if let Some(acct) = account {
+                let all_accounts = db.list_accounts().await?;
+                resolve_account(&all_accounts, &acct)
+            } else {
+                None
+            };
+            let (since, until) = match &month {
+                Some(month) => {
+                    let Some((start, end)) = parse_month(month) else {
+                        anyhow::bail!("Invalid month '{}' (expected YYYY-MM)", month);
+                    };
+                    let tz = &config.general.timezone;
+                    (
+                        parse_date(&Some(start.to_string()), tz),
+                        parse_date(&Some(end.to_string()), tz),
+                    )
+                }
+                None => (None, None),
+            };
 
-            match account_id {
-                Some(id) => {
-                    if let Some(acct) = db.get_account(&id).await? {
-                        let email_count = db.count_emails(Some(&id)).await.unwrap_or(0);
-                        let event_count = db.count_events(Some(&id)).await.unwrap_or(0);
-                        let (att_total, att_downloaded, att_size) =
-                            db.get_attachment_stats(&id).await.unwrap_or((0, 0, 0));
+            let receipts = db
+                .list_receipts(
+                    account_id.as_deref(),
+                    since,
+                    until,
+                    merchant.as_deref(),
+                    limit.min(1000),
+                )
+                .await?;
 
-                        if human {
-                            println!("\n📧 Account: {}", acct.id);
-                            println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-                            if let Some(alias) = &acct.alias {
-                                println!("Alias: {}", alias);
-                            }
-                            println!("Display name: {}", acct.display_name);
-                            println!("Status: {:?}", acct.status);
-                            println!("Added: {}", acct.added_at.format("%Y-%m-%d"));
-                            println!("\n⚙️  Settings:");
-                            if let Some(since) = acct.sync_email_since {
-                                println!("  Sync emails since: {}", since.format("%Y-%m-%d"));
-                            } else {
-                                println!("  Sync emails since: (default)");
-                            }
-                            println!(
-                                "  Sync attachments: {}",
-                                if acct.sync_attachments {
-                                    "enabled"
-                                } else {
-                                    "disabled"
-                                }
-                            );
-                            println!("\n📊 Stats:");
-                            println!("  Emails: {}", email_count);
-                            println!("  Events: {}", event_count);
-                            println!(
-                                "  Attachments: {}/{} downloaded ({} bytes)",
-                                att_downloaded, att_total, att_size
-                            );
-                            if let Some(last) = acct.last_sync_email {
-                                println!("  Last email sync: {}", last.format("%Y-%m-%d %H:%M"));
-                            }
-                            if let Some(last) = acct.last_sync_calendar {
-                                println!("  Last calendar sync: {}", last.format("%Y-%m-%d %H:%M"));
-                            }
-                        } else {
-                            #[derive(Serialize)]
-                            struct AccountDetail {
-                                email: String,
-                                alias: Option<String>,
-                                display_name: String,
-                                status: String,
-                                added_at: String,
-                                email_count: u64,
-                                event_count: u64,
-                                attachments_total: usize,
-                                attachments_downloaded: usize,
-                                attachments_size_bytes: u64,
-                                last_sync_email: Option<String>,
-                                last_sync_calendar: Option<String>,
-                                sync_email_since: Option<String>,
-                                sync_attachments: bool,
-                            }
-                            let detail = AccountDetail {
-                                email: acct.id.clone(),
-                                alias: acct.alias.clone(),
-                                display_name: acct.display_name.clone(),
-                                status: format!("{:?}", acct.status),
-                                added_at: acct.added_at.to_rfc3339(),
-                                email_count,
-                                event_count,
-                                attachments_total: att_total,
-                                attachments_downloaded: att_downloaded,
-                                attachments_size_bytes: att_size,
-                                last_sync_email: acct.last_sync_email.map(|d| d.to_rfc3339()),
-                                last_sync_calendar: acct.last_sync_calendar.map(|d| d.to_rfc3339()),
-                                sync_email_since: acct.sync_email_since.map(|d| d.to_rfc3339()),
-                                sync_attachments: acct.sync_attachments,
-                            };
-                            println!("{}", serde_json::to_string_pretty(&detail)?);
+            if csv {
+                println!("date,merchant,amount,currency,subject,sender,account_id,email_id");
+                for receipt in &receipts {
+                    let amount = receipt
+                        .amount
+                        .map(|a| format!("{:.2}", a))
+                        .unwrap_or_default();
+                    let fields = [
+                        receipt.date.format("%Y-%m-%d").to_string(),
+                        receipt.merchant.clone(),
+                        amount,
+                        receipt.currency.clone().unwrap_or_default(),
+                        receipt.subject.clone(),
+                        receipt.sender.clone(),
+                        receipt.account_id.clone(),
+                        receipt.id.clone(),
+                    ];
+                    let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+                    println!("{}", row.join(","));
+                }
+            } else if human {
+                if receipts.is_empty() {
+                    println!("No receipts found.");
+                    if !config.receipts.enabled {
+                        println!("Receipt extraction is disabled ([receipts] enabled = false).");
+                    }
+                } else {
+                    println!("\n{} receipts:\n", receipts.len());
+                    let mut totals: std::collections::BTreeMap<String, f64> = Default::default();
+                    for receipt in &receipts {
+                        let amount = match (receipt.amount, &receipt.currency) {
+                            (Some(a), Some(c)) => format!("{:.2} {}", a, c),
+                            (Some(a), None) => format!("{:.2}", a),
+                            (None, _) => "amount unknown".to_string(),
+                        };
+                        println!(
+                            "🧾 {} — {} — {}",
+                            receipt.date.format("%Y-%m-%d"),
+                            receipt.merchant,
+                            amount
+                        );
+                        println!("   {}", receipt.subject);
+                        println!("   ID: {}", receipt.id);
+                        println!();
+                        if let Some(a) = receipt.amount {
+                            let currency = receipt.currency.as_deref().unwrap_or("(unknown)");
+                            *totals.entry(currency.to_string()).or_default() += a;
                         }
                     }
-                }
-                None => {
-                    if human {
-                        println!("Account not found: {}", account);
-                    } else {
-                        println!("{{\"error\": \"Account not found\"}}");
+                    for (currency, total) in &totals {
+                        println!("Total {}: {:.2}", currency, total);
                     }
                 }
+            } else {
+                println!("{}", serde_json::to_string_pretty(&receipts)?);
             }
         }
+    }
 
-        AccountCommands::Add {
-            years,
-            attachments,
-            alias,
-            human,
-        } => {
-            let human = human || global_human;
-            account_add(years, attachments, alias, human).await?;
-        }
+    Ok(())
+}
 
-        AccountCommands::Reauth { account, human } => {
-            let human = human || global_human;
-            account_reauth(&account, human).await?;
-        }
+// ============================================================================
+// Stats Command Handlers
+// ============================================================================
 
-        AccountCommands::Delete {
+async fn handle_stats_command(command: StatsCommands, global_human: bool) -> Result<()> {
+    match command {
+        StatsCommands::Spending {
+            by_month,
+            by_merchant,
+            after,
+            before,
+            merchant,
             account,
-            confirm,
             human,
         } => {
             let human = human || global_human;
-            account_delete(&account, confirm, human).await?;
-        }
+            let config = Config::load().unwrap_or_default();
+            let db = Database::open(config.lancedb_dir()).await?;
+
+            let account_id = if let Some(acct) = account {
+                let all_accounts = db.list_accounts().await?;
+                resolve_account(&all_accounts, &acct)
+            } else {
+                None
+            };
+            let tz_name = &config.general.timezone;
+            let receipts = db
+                .list_receipts(
+                    account_id.as_deref(),
+                    parse_date(&after, tz_name),
+                    parse_date(&before, tz_name),
+                    merchant.as_deref(),
+                    usize::MAX,
+                )
+                .await?;
+            let tz: Tz = tz_name.parse().unwrap_or(Tz::UTC);
+            let rows = spending_report(&receipts, by_month, by_merchant, tz);
 
-        AccountCommands::Configure {
-            account,
-            alias,
-            attachments,
-            no_attachments,
-            human,
-        } => {
-            let human = human || global_human;
-            account_configure(&account, alias, attachments, no_attachments, human).await?;
+            if human {
+                if rows.is_empty() {
+                    println!("No receipts with amounts found.");
+                    if !config.receipts.enabled {
+                        println!("Receipt extraction is disabled ([receipts] enabled = false).");
+                    }
+                } else {
+                    println!();
+                    let mut month = None;
+                    for row in &rows {
+                        if by_month && month != row.month.as_ref() {
+                            month = row.month.as_ref();
+                            println!("📅 {}", row.month.as_deref().unwrap_or_default());
+                        }
+                        let label = row.merchant.as_deref().unwrap_or("Total");
+                        let indent = if by_month { "   " } else { "" };
+                        println!(
+                            "{}{:<32} {:>12.2} {}  ({} receipts)",
+                            indent, label, row.total, row.currency, row.count
+                        );
+                    }
+                }
+                let unpriced = receipts.iter().filter(|r| r.amount.is_none()).count();
+                if unpriced > 0 {
+                    println!("\n{} receipts without a total are not included.", unpriced);
+                }
+            } else {
+                println!("{}", serde_json::to_string_pretty(&rows)?);
+            }
         }
     }
 
@@ -2394,192 +9418,347 @@ async fn handle_account_command(command: AccountCommands, global_human: bool) ->
 }
 
 // ============================================================================
-// Sync Command Handlers
+// Tasks Command Handlers
 // ============================================================================
 
-async fn handle_sync_command(command: SyncCommands, global_human: bool) -> Result<()> {
+async fn handle_tasks_command(command: TasksCommands, global_human: bool) -> Result<()> {
+    let config = Config::load().unwrap_or_default();
+    let db = Database::open(config.lancedb_dir()).await?;
+
     match command {
-        SyncCommands::Status { account, human } => {
+        TasksCommands::List {
+            status,
+            due_before,
+            account,
+            limit,
+            human,
+        } => {
             let human = human || global_human;
-            let config = Config::load().unwrap_or_default();
-            let db = Database::open(config.lancedb_dir()).await?;
-            let accounts = db.list_accounts().await?;
-
-            let target_accounts: Vec<&Account> = if let Some(acct) = &account {
-                accounts
-                    .iter()
-                    .filter(|a| a.id == *acct || a.alias.as_ref() == Some(acct))
-                    .collect()
+            let status = match status.to_lowercase().as_str() {
+                "all" => None,
+                other => match TaskStatus::from_str(other) {
+                    Some(status) => Some(status),
+                    None => anyhow::bail!("Unknown status '{}'. Use open, done or all.", other),
+                },
+            };
+            let due_before = match due_before {
+                Some(date) => Some(NaiveDate::parse_from_str(&date, "%Y-%m-%d").map_err(|_| {
+                    anyhow::anyhow!("Invalid --due-before '{}'. Use YYYY-MM-DD.", date)
+                })?),
+                None => None,
+            };
+            let account_id = if let Some(acct) = account {
+                let all_accounts = db.list_accounts().await?;
+                resolve_account(&all_accounts, &acct)
             } else {
-                accounts.iter().collect()
+                None
             };
+            let tasks = db
+                .list_tasks(account_id.as_deref(), status, due_before, limit)
+                .await?;
 
-            if target_accounts.is_empty() {
-                if human {
-                    println!("No accounts found.");
-                } else {
-                    println!("[]");
+            if human {
+                if tasks.is_empty() {
+                    println!("No tasks found.");
+                    if !config.tasks.enabled {
+                        println!("Task extraction is disabled ([tasks] enabled = false).");
+                    }
                 }
-                return Ok(());
+                for task in &tasks {
+                    print_task(task);
+                }
+            } else {
+                println!("{}", serde_json::to_string_pretty(&tasks)?);
             }
-
-            // Check daemon status
-            let daemon_running = check_daemon_running();
+        }
+        TasksCommands::Done { id, reopen, human } => {
+            let human = human || global_human;
+            let status = if reopen {
+                TaskStatus::Open
+            } else {
+                TaskStatus::Done
+            };
+            let Some(task) = db.set_task_status(&id, status).await? else {
+                anyhow::bail!("Task not found: {}", id);
+            };
 
             if human {
-                println!("\n📊 GroundEffect Sync Status\n");
-                if daemon_running {
-                    println!("Daemon: ✓ running");
-                } else {
-                    println!("Daemon: ✗ not running");
-                }
-                println!();
+                print_task(&task);
+            } else {
+                println!("{}", serde_json::to_string_pretty(&task)?);
             }
+        }
+    }
 
-            let mut statuses = Vec::new();
+    Ok(())
+}
 
-            for account in target_accounts {
-                let email_count = db.count_emails(Some(&account.id)).await.unwrap_or(0);
-                let event_count = db.count_events(Some(&account.id)).await.unwrap_or(0);
-                let (oldest_email, newest_email) = db
-                    .get_email_sync_boundaries(&account.id)
-                    .await
-                    .unwrap_or((None, None));
-                let (oldest_event, newest_event) = db
-                    .get_event_sync_boundaries(&account.id)
-                    .await
-                    .unwrap_or((None, None));
-                let (att_total, att_downloaded, att_size) = db
-                    .get_attachment_stats(&account.id)
-                    .await
-                    .unwrap_or((0, 0, 0));
+fn print_task(task: &Task) {
+    let check = match task.status {
+        TaskStatus::Open => "☐",
+        TaskStatus::Done => "☑",
+    };
+    println!("{} {}", check, task.text);
+    let mut details = vec![format!("from {}", task.requester)];
+    if let Some(due) = task.due {
+        details.push(format!("due {}", due.format("%a %b %-d")));
+    }
+    if task.source == TaskSource::Assistant {
+        details.push("added by assistant".to_string());
+    }
+    println!("   {}", details.join(", "));
+    println!("   Re: {}", task.subject);
+    println!("   ID: {}", task.id);
+    println!();
+}
 
-                // Calculate remaining emails if we have an estimate
-                let emails_remaining = account
-                    .estimated_total_emails
-                    .map(|total| total.saturating_sub(email_count));
+// ============================================================================
+// Contact Command Handlers
+// ============================================================================
 
-                let status = SyncStatus {
-                    account: account.id.clone(),
-                    status: format!("{:?}", account.status),
-                    email_count,
-                    event_count,
-                    oldest_email: oldest_email.map(|d| d.format("%Y-%m-%d").to_string()),
-                    newest_email: newest_email.map(|d| d.format("%Y-%m-%d").to_string()),
-                    oldest_event: oldest_event.map(|d| d.format("%Y-%m-%d").to_string()),
-                    newest_event: newest_event.map(|d| d.format("%Y-%m-%d").to_string()),
-                    last_email_sync: account.last_sync_email.map(|d| d.to_rfc3339()),
-                    last_calendar_sync: account.last_sync_calendar.map(|d| d.to_rfc3339()),
-                    attachments_total: att_total,
-                    attachments_downloaded: att_downloaded,
-                    attachments_size_bytes: att_size,
-                    sync_email_since: account.sync_email_since.map(|d| d.to_rfc3339()),
-                    sync_attachments: account.sync_attachments,
-                    estimated_total_emails: account.estimated_total_emails,
-                    emails_remaining,
-                };
+async fn handle_contact_command(command: ContactCommands, global_human: bool) -> Result<()> {
+    let config = Config::load().unwrap_or_default();
+    let db = Arc::new(Database::open(config.lancedb_dir()).await?);
 
-                if human {
-                    let status_icon = match account.status {
-                        AccountStatus::Active => "✓",
-                        AccountStatus::NeedsReauth => "⚠",
-                        AccountStatus::Disabled => "○",
-                        AccountStatus::Syncing => "↻",
-                    };
-                    let alias = account
-                        .alias
-                        .as_ref()
-                        .map(|a| format!(" ({})", a))
-                        .unwrap_or_default();
-                    println!("{}  {}{}", status_icon, account.id, alias);
-                    println!("   Status: {:?}", account.status);
-                    if let Some(since) = account.sync_email_since {
-                        println!("   ⚙️  Sync since: {}", since.format("%Y-%m-%d"));
-                    }
-                    // Show email count with total and remaining if available
-                    if let Some(total) = account.estimated_total_emails {
-                        let remaining = total.saturating_sub(email_count);
-                        if remaining > 0 {
-                            println!(
-                                "   📨 Emails: {} / {} ({} remaining)",
-                                email_count, total, remaining
-                            );
-                        } else {
-                            println!("   📨 Emails: {} (sync complete)", email_count);
-                        }
-                    } else {
-                        println!("   📨 Emails: {}", email_count);
-                    }
-                    if let Some(oldest) = &status.oldest_email {
-                        println!("      Oldest: {}", oldest);
-                    }
-                    if let Some(newest) = &status.newest_email {
-                        println!("      Newest: {}", newest);
-                    }
-                    if let Some(last) = account.last_sync_email {
-                        println!("      Last sync: {}", format_relative_time(last));
-                    }
-                    println!("   📅 Events: {}", event_count);
-                    if let Some(oldest) = &status.oldest_event {
-                        println!("      Oldest: {}", oldest);
-                    }
-                    if let Some(newest) = &status.newest_event {
-                        println!("      Newest: {}", newest);
-                    }
-                    if let Some(last) = account.last_sync_calendar {
-                        println!("      Last sync: {}", format_relative_time(last));
-                    }
-                    println!(
-                        "   📎 Attachments: {}/{} downloaded ({})",
-                        att_downloaded,
-                        att_total,
-                        format_bytes(att_size)
-                    );
-                    if account.sync_attachments {
-                        println!("      Auto-download: enabled");
-                    }
-                    println!();
+    match command {
+        ContactCommands::Expertise {
+            query,
+            account,
+            since,
+            min_emails,
+            limit,
+            human,
+        } => {
+            let human = human || global_human;
+            let since = match parse_age(&since) {
+                Some(age) => Utc::now() - age,
+                None => {
+                    parse_date(&Some(since.clone()), &config.general.timezone).ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "Invalid --since '{}'. Use an age like 365d or YYYY-MM-DD",
+                            since
+                        )
+                    })?
+                }
+            };
+            let accounts = match account {
+                Some(acct) => {
+                    let all_accounts = db.list_accounts().await?;
+                    let id = resolve_account(&all_accounts, &acct)
+                        .ok_or_else(|| anyhow::anyhow!("Account not found: {}", acct))?;
+                    Some(vec![id])
                 }
+                None => None,
+            };
+            let options = ExpertiseOptions {
+                accounts,
+                since,
+                min_emails: min_emails.max(1),
+                limit: limit.min(100),
+                own_addresses: config.compose.identities.keys().cloned().collect(),
+            };
 
-                statuses.push(status);
-            }
+            let contacts = load_search_engine(&config, db)
+                .await?
+                .contact_expertise(&query, &options)
+                .await?;
 
             if !human {
-                println!("{}", serde_json::to_string_pretty(&statuses)?);
+                println!("{}", serde_json::to_string_pretty(&contacts)?);
+                return Ok(());
+            }
+            if contacts.is_empty() {
+                println!("No contacts found. Try a longer --since or a lower --min-emails.");
+                return Ok(());
+            }
+            for (i, contact) in contacts.iter().enumerate() {
+                match &contact.name {
+                    Some(name) => println!("{}. {} <{}>", i + 1, name, contact.email),
+                    None => println!("{}. {}", i + 1, contact.email),
+                }
+                println!(
+                    "   score {:.2}, {} from them, {} to them, last {}",
+                    contact.score,
+                    contact.from_them,
+                    contact.to_them,
+                    contact.last_contact.format("%Y-%m-%d")
+                );
+                for evidence in &contact.evidence {
+                    println!(
+                        "   {} {} {}",
+                        if evidence.sent { "→" } else { "←" },
+                        evidence.date.format("%Y-%m-%d"),
+                        evidence.subject
+                    );
+                }
+                println!();
             }
         }
+    }
+    Ok(())
+}
 
-        SyncCommands::Reset {
-            account,
-            data_type,
-            confirm,
-            human,
-        } => {
-            let human = human || global_human;
-            sync_reset(&account, &data_type, confirm, human).await?;
+// ============================================================================
+// Digest Command Handler
+// ============================================================================
+
+async fn handle_digest_command(
+    period: DigestPeriod,
+    account: Option<String>,
+    send: bool,
+    human: bool,
+) -> Result<()> {
+    let config = Config::load().unwrap_or_default();
+    let db = Database::open(config.lancedb_dir()).await?;
+    let tz: Tz = config.general.timezone.parse().unwrap_or(Tz::UTC);
+
+    let account_id = match account {
+        Some(acct) => {
+            let all_accounts = db.list_accounts().await?;
+            Some(
+                resolve_account(&all_accounts, &acct)
+                    .ok_or_else(|| anyhow::anyhow!("Account not found: {}", acct))?,
+            )
+        }
+        None => None,
+    };
+    let digest = build_digest(&db, &config, period, account_id.as_deref(), Utc::now()).await?;
+
+    if send {
+        let (from, to) = digest_addresses(&db, &config).await?;
+        let from = account_id.unwrap_or(from);
+        let oauth = OAuthManager::new(create_token_provider(&config).await?);
+        let message_id = send_digest(&oauth, &digest, &from, &to, tz).await?;
+        record_audit(
+            &db,
+            "send_digest",
+            Some(&from),
+            serde_json::json!({ "to": to, "period": period.as_str(), "message_id": message_id }),
+        )
+        .await;
+        if human {
+            println!("Sent {} digest to {}", period.as_str(), to);
+        } else {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "sent": true,
+                    "from": from,
+                    "to": to,
+                    "message_id": message_id,
+                }))?
+            );
         }
+        return Ok(());
+    }
+
+    if human {
+        print!("{}", render_text(&digest, tz));
+    } else {
+        println!("{}", serde_json::to_string_pretty(&digest)?);
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Travel Command Handlers
+// ============================================================================
 
-        SyncCommands::Extend {
+async fn handle_travel_command(command: TravelCommands, global_human: bool) -> Result<()> {
+    let config = Config::load().unwrap_or_default();
+    let db = Database::open(config.lancedb_dir()).await?;
+
+    match command {
+        TravelCommands::Trips {
             account,
-            target_date,
+            days,
             human,
         } => {
             let human = human || global_human;
-            sync_extend(&account, &target_date, human).await?;
-        }
+            let account_id = if let Some(acct) = account {
+                let all_accounts = db.list_accounts().await?;
+                resolve_account(&all_accounts, &acct)
+            } else {
+                None
+            };
+            let now = Utc::now();
+            let items = db
+                .list_itinerary(
+                    account_id.as_deref(),
+                    None,
+                    Some(now),
+                    Some(now + chrono::Duration::days(days.max(1))),
+                    500,
+                )
+                .await?;
+            let trips = group_trips(items);
 
-        SyncCommands::ResumeFrom {
+            if human {
+                if trips.is_empty() {
+                    println!("No upcoming trips in the next {} days.", days);
+                    if !config.travel.enabled {
+                        println!("Itinerary parsing is disabled ([travel] enabled = false).");
+                    }
+                }
+                for trip in &trips {
+                    let places = if trip.destinations.is_empty() {
+                        "Trip".to_string()
+                    } else {
+                        trip.destinations.join(", ")
+                    };
+                    println!(
+                        "\n🧳 {} ({} – {})\n",
+                        places,
+                        trip.start.format("%b %-d"),
+                        trip.end.format("%b %-d, %Y")
+                    );
+                    for item in &trip.items {
+                        print_itinerary_item(item, &config.general.timezone);
+                    }
+                }
+            } else {
+                println!("{}", serde_json::to_string_pretty(&trips)?);
+            }
+        }
+        TravelCommands::List {
             account,
-            target_date,
+            kind,
+            after,
+            before,
+            limit,
             human,
         } => {
             let human = human || global_human;
-            sync_resume_from(&account, &target_date, human).await?;
-        }
+            let account_id = if let Some(acct) = account {
+                let all_accounts = db.list_accounts().await?;
+                resolve_account(&all_accounts, &acct)
+            } else {
+                None
+            };
+            let since = parse_date(&after, &config.general.timezone).unwrap_or_else(Utc::now);
+            let until = parse_date(&before, &config.general.timezone);
+            let items = db
+                .list_itinerary(
+                    account_id.as_deref(),
+                    kind,
+                    Some(since),
+                    until,
+                    limit.min(500),
+                )
+                .await?;
 
-        SyncCommands::DownloadAttachments { account, human } => {
-            let human = human || global_human;
-            sync_download_attachments(&account, human).await?;
+            if human {
+                if items.is_empty() {
+                    println!("No itinerary items found.");
+                } else {
+                    println!("\n{} itinerary items:\n", items.len());
+                    for item in &items {
+                        print_itinerary_item(item, &config.general.timezone);
+                    }
+                }
+            } else {
+                println!("{}", serde_json::to_string_pretty(&items)?);
+            }
         }
     }
 
@@ -2587,1120 +9766,2443 @@ async fn handle_sync_command(command: SyncCommands, global_human: bool) -> Resul
 }
 
 // ============================================================================
-// Daemon Command Handlers
+// Extractions Command Handlers
 // ============================================================================
 
-async fn handle_daemon_command(command: DaemonCommands, global_human: bool) -> Result<()> {
+async fn handle_extractions_command(
+    command: ExtractionsCommands,
+    global_human: bool,
+) -> Result<()> {
+    let config = Config::load().unwrap_or_default();
+    let db = Database::open(config.lancedb_dir()).await?;
+
     match command {
-        DaemonCommands::Status { human } => {
+        ExtractionsCommands::List {
+            kind,
+            upcoming,
+            account,
+            limit,
+            human,
+        } => {
             let human = human || global_human;
-            let running = check_daemon_running();
-            let pid = get_daemon_pid();
+            let account_id = if let Some(acct) = account {
+                let all_accounts = db.list_accounts().await?;
+                resolve_account(&all_accounts, &acct)
+            } else {
+                None
+            };
+            let items = db
+                .list_extractions(
+                    account_id.as_deref(),
+                    kind,
+                    upcoming.then(Utc::now),
+                    limit.min(500),
+                )
+                .await?;
 
             if human {
-                if running {
-                    println!("Daemon: ✓ running (PID: {})", pid.unwrap_or(0));
+                if items.is_empty() {
+                    println!("No reservations or packages found.");
+                    if !config.extractions.enabled {
+                        println!("Extraction is disabled ([extractions] enabled = false).");
+                    }
                 } else {
-                    println!("Daemon: ✗ not running");
+                    println!("\n{} found:\n", items.len());
+                    for item in &items {
+                        print_extraction(item, &config.general.timezone);
+                    }
                 }
             } else {
-                let status = DaemonStatus { running, pid };
-                println!("{}", serde_json::to_string_pretty(&status)?);
+                println!("{}", serde_json::to_string_pretty(&items)?);
             }
         }
+    }
 
-        DaemonCommands::Start { logging, human } => {
-            let human = human || global_human;
-            if check_daemon_running() {
-                if human {
-                    println!("Daemon is already running.");
-                } else {
-                    println!("{{\"status\": \"already_running\"}}");
-                }
-                return Ok(());
-            }
-
-            let plist_path = dirs::home_dir()
-                .unwrap_or_default()
-                .join("Library/LaunchAgents/com.groundeffect.daemon.plist");
-
-            if plist_path.exists() {
-                let output = std::process::Command::new("launchctl")
-                    .args(["load", "-w", plist_path.to_str().unwrap()])
-                    .output()?;
+    Ok(())
+}
 
-                if human {
-                    if output.status.success() {
-                        println!("✓ Daemon started via launchd");
-                    } else {
-                        println!(
-                            "Failed to start daemon: {}",
-                            String::from_utf8_lossy(&output.stderr)
-                        );
-                    }
-                } else {
-                    if output.status.success() {
-                        println!("{{\"status\": \"started\", \"method\": \"launchd\"}}");
-                    } else {
-                        println!(
-                            "{{\"status\": \"error\", \"message\": \"{}\" }}",
-                            String::from_utf8_lossy(&output.stderr).replace('"', "\\\"")
-                        );
-                    }
-                }
-            } else {
-                let mut cmd = std::process::Command::new("groundeffect-daemon");
-                if logging {
-                    cmd.arg("--log");
-                }
-                cmd.spawn()?;
+/// Print a reservation in its local time, or a package with its status
+fn print_extraction(item: &Extraction, default_tz: &str) {
+    let tz: Tz = item
+        .timezone
+        .as_deref()
+        .unwrap_or(default_tz)
+        .parse()
+        .unwrap_or(Tz::UTC);
+    let when = |time: DateTime<Utc>| {
+        if item.all_day {
+            time.format("%a %b %-d").to_string()
+        } else {
+            time.with_timezone(&tz)
+                .format("%a %b %-d %H:%M %Z")
+                .to_string()
+        }
+    };
+    let span = match (item.start, item.end) {
+        (Some(start), Some(end)) => Some(format!("{} → {}", when(start), when(end))),
+        (Some(start), None) => Some(when(start)),
+        _ => None,
+    };
 
-                if human {
-                    println!("✓ Daemon started directly");
-                } else {
-                    println!("{{\"status\": \"started\", \"method\": \"direct\"}}");
+    let icon = match item.kind {
+        ExtractionKind::Flight => "✈️ ",
+        ExtractionKind::Hotel => "🏨",
+        ExtractionKind::Restaurant => "🍽️ ",
+        ExtractionKind::Package => "📦",
+    };
+    println!("{} {}", icon, item.title);
+    match item.kind {
+        ExtractionKind::Package => {
+            let status = item
+                .status
+                .as_deref()
+                .unwrap_or("shipped")
+                .replace('_', " ");
+            match &span {
+                Some(expected) if !item.is_delivered() => {
+                    println!("   {}, expected {}", status, expected)
                 }
+                _ => println!("   {}", status),
+            }
+            if let Some(number) = &item.tracking_number {
+                let carrier = item.provider.as_deref().unwrap_or("Tracking");
+                println!("   {}: {}", carrier, number);
+            }
+        }
+        _ => {
+            if let Some(span) = &span {
+                println!("   {}", span);
+            }
+            if let Some(size) = item.party_size {
+                println!("   Party of {}", size);
+            }
+            if let Some(location) = &item.location {
+                println!("   {}", location);
             }
         }
+    }
+    if let Some(confirmation) = &item.confirmation {
+        println!("   Confirmation: {}", confirmation);
+    }
+    println!("   Email: {}", item.email_id);
+    println!();
+}
 
-        DaemonCommands::Stop { human } => {
+// ============================================================================
+// Thread Command Handlers
+// ============================================================================
+
+async fn handle_thread_command(
+    command: ThreadCommands,
+    global_human: bool,
+    use_daemon: bool,
+) -> Result<()> {
+    match command {
+        ThreadCommands::Search {
+            query,
+            participant,
+            after,
+            before,
+            account,
+            limit,
+            mode,
+            human,
+        } => {
             let human = human || global_human;
-            if !check_daemon_running() {
-                if human {
-                    println!("Daemon is not running.");
+            let config = Config::load().unwrap_or_default();
+            let mut db = None;
+
+            // Resolve account aliases
+            let accounts = if let Some(accts) = account {
+                let handle = Arc::new(Database::open(config.lancedb_dir()).await?);
+                let all_accounts = handle.list_accounts().await?;
+                db = Some(handle);
+                let resolved: Vec<String> = accts
+                    .iter()
+                    .filter_map(|a| resolve_account(&all_accounts, a))
+                    .collect();
+                if resolved.is_empty() {
+                    None
                 } else {
-                    println!("{{\"status\": \"not_running\"}}");
+                    Some(resolved)
                 }
-                return Ok(());
-            }
-
-            let plist_path = dirs::home_dir()
-                .unwrap_or_default()
-                .join("Library/LaunchAgents/com.groundeffect.daemon.plist");
+            } else {
+                None
+            };
 
-            if plist_path.exists() {
-                let _ = std::process::Command::new("launchctl")
-                    .args(["unload", plist_path.to_str().unwrap()])
-                    .output();
-            }
+            let options = ThreadSearchOptions {
+                accounts,
+                limit: limit.min(100),
+                participant,
+                date_from: parse_date(&after, &config.general.timezone),
+                date_to: parse_date(&before, &config.general.timezone),
+                fusion: FusionOptions::from_config(&config.search).with_mode(mode),
+            };
 
-            let _ = std::process::Command::new("pkill")
-                .args(["-f", "groundeffect-daemon"])
-                .output();
+            // Run in the daemon when it's up (database open, model loaded)
+            let proxied = if use_daemon {
+                control::search_threads(&config.control_socket_path(), &query, &options).await
+            } else {
+                None
+            };
+            let results = match proxied {
+                Some(results) => results,
+                None => {
+                    let db = match db {
+                        Some(db) => db,
+                        None => Arc::new(Database::open(config.lancedb_dir()).await?),
+                    };
+                    load_search_engine(&config, db)
+                        .await?
+                        .search_threads(&query, &options)
+                        .await?
+                }
+            };
 
             if human {
-                println!("✓ Daemon stopped");
+                if results.is_empty() {
+                    println!("No threads found.");
+                } else {
+                    println!("\nFound {} threads:\n", results.len());
+                    for result in &results {
+                        print_thread_human(&result.thread, Some(result.score));
+                    }
+                }
             } else {
-                println!("{{\"status\": \"stopped\"}}");
+                println!("{}", serde_json::to_string_pretty(&results)?);
             }
         }
 
-        DaemonCommands::Restart { logging, human } => {
+        ThreadCommands::List {
+            account,
+            since,
+            limit,
+            human,
+        } => {
             let human = human || global_human;
+            let config = Config::load().unwrap_or_default();
+            let db = Database::open(config.lancedb_dir()).await?;
 
-            let _ = std::process::Command::new("pkill")
-                .args(["-f", "groundeffect-daemon"])
-                .output();
-
-            std::thread::sleep(std::time::Duration::from_millis(500));
-
-            let plist_path = dirs::home_dir()
-                .unwrap_or_default()
-                .join("Library/LaunchAgents/com.groundeffect.daemon.plist");
-
-            if plist_path.exists() {
-                let _ = std::process::Command::new("launchctl")
-                    .args(["load", "-w", plist_path.to_str().unwrap()])
-                    .output();
-
-                if human {
-                    println!("✓ Daemon restarted via launchd");
-                } else {
-                    println!("{{\"status\": \"restarted\", \"method\": \"launchd\"}}");
-                }
+            let account_id = if let Some(acct) = account {
+                let all_accounts = db.list_accounts().await?;
+                resolve_account(&all_accounts, &acct)
             } else {
-                let mut cmd = std::process::Command::new("groundeffect-daemon");
-                if logging {
-                    cmd.arg("--log");
-                }
-                cmd.spawn()?;
+                None
+            };
+            let since = parse_date(&since, &config.general.timezone);
+            let threads = db
+                .list_threads(account_id.as_deref(), since, limit.clamp(1, 1000))
+                .await?;
 
-                if human {
-                    println!("✓ Daemon restarted");
+            if human {
+                if threads.is_empty() {
+                    println!("No threads found.");
                 } else {
-                    println!("{{\"status\": \"restarted\", \"method\": \"direct\"}}");
+                    println!("\n{} threads:\n", threads.len());
+                    for thread in &threads {
+                        print_thread_human(thread, None);
+                    }
                 }
+            } else {
+                println!("{}", serde_json::to_string_pretty(&threads)?);
             }
         }
+    }
 
-        DaemonCommands::Install { logging, human } => {
-            let human = human || global_human;
-            daemon_install(logging, human)?;
-        }
+    Ok(())
+}
 
-        DaemonCommands::Uninstall { human } => {
-            let human = human || global_human;
-            daemon_uninstall(human)?;
-        }
+/// Warning for sent mail that bounced (None when nothing came back)
+fn delivery_warning(status: DeliveryStatus, failed_recipients: &[String]) -> Option<String> {
+    match status {
+        DeliveryStatus::Failed if failed_recipients.is_empty() => {
+            Some("⚠ Delivery failed".to_string())
+        }
+        DeliveryStatus::Failed => Some(format!(
+            "⚠ Delivery failed to: {}",
+            failed_recipients.join(", ")
+        )),
+        DeliveryStatus::Delayed => Some("⏳ Delivery delayed".to_string()),
+        DeliveryStatus::Sent => None,
     }
+}
 
-    Ok(())
+fn print_thread_human(thread: &Thread, score: Option<f32>) {
+    let subject = if thread.subject.is_empty() {
+        "(no subject)"
+    } else {
+        &thread.subject
+    };
+    match score {
+        Some(score) => println!("🧵 {} (score: {:.2})", subject, score),
+        None => println!("🧵 {}", subject),
+    }
+    println!(
+        "   {} message{}{}, last {} from {}",
+        thread.message_count,
+        if thread.message_count == 1 { "" } else { "s" },
+        if thread.unread_count > 0 {
+            format!(" ({} unread)", thread.unread_count)
+        } else {
+            String::new()
+        },
+        format_relative_time(thread.last_activity),
+        thread.last_from.to_string_full()
+    );
+    let participants: Vec<&str> = thread
+        .participants
+        .iter()
+        .map(|p| p.name.as_deref().unwrap_or(&p.email))
+        .collect();
+    println!("   With: {}", participants.join(", "));
+    println!("   Thread ID: {}", thread.gmail_thread_id);
+    println!();
 }
 
 // ============================================================================
-// Helper Functions
+// Audit Command Handlers
 // ============================================================================
 
-fn check_daemon_running() -> bool {
-    std::process::Command::new("pgrep")
-        .args(["-f", "groundeffect-daemon"])
-        .output()
-        .map(|o| o.status.success())
-        .unwrap_or(false)
-}
+async fn handle_audit_command(command: AuditCommands, global_human: bool) -> Result<()> {
+    match command {
+        AuditCommands::List {
+            since,
+            actor,
+            action,
+            account,
+            limit,
+            human,
+        } => {
+            let human = human || global_human;
+            let config = Config::load().unwrap_or_default();
+            let db = Database::open(config.lancedb_dir()).await?;
 
-fn get_daemon_pid() -> Option<u32> {
-    std::process::Command::new("pgrep")
-        .args(["-f", "groundeffect-daemon"])
-        .output()
-        .ok()
-        .and_then(|o| {
-            if o.status.success() {
-                String::from_utf8_lossy(&o.stdout)
-                    .lines()
-                    .next()
-                    .and_then(|s| s.trim().parse().ok())
+            let account_id = if let Some(acct) = account {
+                let all_accounts = db.list_accounts().await?;
+                resolve_account(&all_accounts, &acct)
             } else {
                 None
-            }
-        })
-}
+            };
+            let since = parse_date(&since, &config.general.timezone);
+            let entries = db
+                .list_audit(
+                    since,
+                    actor,
+                    action.as_deref(),
+                    account_id.as_deref(),
+                    limit.clamp(1, 1000),
+                )
+                .await?;
 
-/// Restart the daemon. Returns the method used ("launchd" or "direct") or None if not running.
-fn restart_daemon() -> Option<&'static str> {
-    // Check if daemon is running first
-    if !check_daemon_running() {
-        return None;
+            if human {
+                if entries.is_empty() {
+                    println!("No recorded actions.");
+                    return Ok(());
+                }
+                let tz: Tz = config.general.timezone.parse().unwrap_or(Tz::UTC);
+                println!("\n{} recorded actions:\n", entries.len());
+                for entry in &entries {
+                    println!(
+                        "{} {} [{}] {}{}",
+                        if entry.success { "✅" } else { "❌" },
+                        entry.timestamp.with_timezone(&tz).format("%Y-%m-%d %H:%M"),
+                        entry.actor.as_str(),
+                        entry.action,
+                        entry
+                            .account_id
+                            .as_ref()
+                            .map(|id| format!(" ({})", id))
+                            .unwrap_or_default()
+                    );
+                    if let Some(error) = &entry.error {
+                        println!("   Error: {}", error);
+                    }
+                    if !entry.params.is_null() {
+                        println!("   {}", entry.params);
+                    }
+                    println!();
+                }
+            } else {
+                println!("{}", serde_json::to_string_pretty(&entries)?);
+            }
+        }
     }
 
-    // Kill existing daemon
-    let _ = std::process::Command::new("pkill")
-        .args(["-f", "groundeffect-daemon"])
-        .output();
-
-    std::thread::sleep(std::time::Duration::from_millis(500));
-
-    let plist_path = dirs::home_dir()
-        .unwrap_or_default()
-        .join("Library/LaunchAgents/com.groundeffect.daemon.plist");
+    Ok(())
+}
 
-    if plist_path.exists() {
-        let _ = std::process::Command::new("launchctl")
-            .args(["load", "-w", plist_path.to_str().unwrap()])
-            .output();
-        Some("launchd")
-    } else {
-        let _ = std::process::Command::new("groundeffect-daemon").spawn();
-        Some("direct")
+/// Parse a `--apply` value: a suggestion index, optionally with "=<label>"
+fn parse_label_choice(value: &str) -> Result<(usize, Option<String>)> {
+    let (index, label) = match value.split_once('=') {
+        Some((index, label)) => (index, Some(label.trim().to_string())),
+        None => (value, None),
+    };
+    let index = index
+        .trim()
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid --apply '{}' (use e.g. 2 or 2=Receipts)", value))?;
+    match label {
+        Some(label) if label.is_empty() => {
+            anyhow::bail!("Invalid --apply '{}': the label is empty", value)
+        }
+        label => Ok((index, label)),
     }
 }
 
-fn resolve_account(accounts: &[Account], query: &str) -> Option<String> {
-    accounts
-        .iter()
-        .find(|a| a.id == query || a.alias.as_ref() == Some(&query.to_string()))
-        .map(|a| a.id.clone())
+/// Append a completed CLI action to the audit log
+async fn record_audit(
+    db: &Database,
+    action: &str,
+    account_id: Option<&str>,
+    params: serde_json::Value,
+) {
+    let entry = AuditEntry::new(AuditActor::Cli, action, account_id, params);
+    if let Err(e) = db.record_audit(&entry).await {
+        eprintln!("Warning: failed to record audit entry: {}", e);
+    }
 }
 
-/// Parse a date string in the user's timezone and convert to UTC.
-///
-/// If timezone parsing fails, falls back to UTC.
-fn parse_date(date_str: &Option<String>, timezone: &str) -> Option<DateTime<Utc>> {
-    date_str.as_ref().and_then(|s| {
-        NaiveDate::parse_from_str(s, "%Y-%m-%d").ok().map(|d| {
-            let naive_dt = d.and_hms_opt(0, 0, 0).unwrap();
+/// Print a flight or hotel stay in local time at each end
+fn print_itinerary_item(item: &ItineraryItem, default_tz: &str) {
+    let local = |time: DateTime<Utc>, tz: Option<&str>| {
+        let tz: Tz = tz
+            .or(Some(default_tz))
+            .and_then(|tz| tz.parse().ok())
+            .unwrap_or(Tz::UTC);
+        time.with_timezone(&tz)
+            .format("%a %b %-d %H:%M %Z")
+            .to_string()
+    };
 
-            // Try to parse the timezone, fallback to UTC
-            if let Ok(tz) = timezone.parse::<Tz>() {
-                // Convert from user's timezone to UTC
-                tz.from_local_datetime(&naive_dt)
-                    .single()
-                    .map(|dt| dt.with_timezone(&Utc))
-                    .unwrap_or_else(|| naive_dt.and_utc())
+    match item.kind {
+        ItineraryKind::Flight => {
+            println!("✈️  {}", item.title);
+            let depart = local(item.start, item.start_timezone.as_deref());
+            match item.end {
+                Some(end) => println!(
+                    "   {} → {}",
+                    depart,
+                    local(end, item.end_timezone.as_deref())
+                ),
+                None => println!("   Departs {}", depart),
+            }
+        }
+        ItineraryKind::Hotel => {
+            println!("🏨 {}", item.title);
+            if item.all_day {
+                let checkout = item
+                    .end
+                    .map(|end| format!(" → {}", end.format("%a %b %-d")))
+                    .unwrap_or_default();
+                println!("   {}{}", item.start.format("%a %b %-d"), checkout);
             } else {
-                // Invalid timezone string, treat as UTC
-                naive_dt.and_utc()
+                let checkout = item
+                    .end
+                    .map(|end| format!(" → {}", local(end, item.end_timezone.as_deref())))
+                    .unwrap_or_default();
+                println!(
+                    "   {}{}",
+                    local(item.start, item.start_timezone.as_deref()),
+                    checkout
+                );
             }
-        })
-    })
-}
-
-fn format_relative_time(dt: DateTime<Utc>) -> String {
-    let now = Utc::now();
-    let duration = now.signed_duration_since(dt);
-
-    if duration.num_seconds() < 60 {
-        "just now".to_string()
-    } else if duration.num_minutes() < 60 {
-        let mins = duration.num_minutes();
-        format!("{} minute{} ago", mins, if mins == 1 { "" } else { "s" })
-    } else if duration.num_hours() < 24 {
-        let hours = duration.num_hours();
-        format!("{} hour{} ago", hours, if hours == 1 { "" } else { "s" })
-    } else if duration.num_days() < 7 {
-        let days = duration.num_days();
-        format!("{} day{} ago", days, if days == 1 { "" } else { "s" })
-    } else {
-        dt.format("%Y-%m-%d %H:%M").to_string()
+            if let Some(location) = &item.location {
+                println!("   {}", location);
+            }
+        }
     }
+    if let Some(confirmation) = &item.confirmation {
+        println!("   Confirmation: {}", confirmation);
+    }
+    if item.event_id.is_some() {
+        println!("   (on your calendar)");
+    }
+    println!("   Email: {}", item.email_id);
+    println!();
 }
 
-fn format_bytes(bytes: u64) -> String {
-    if bytes < 1024 {
-        format!("{} B", bytes)
-    } else if bytes < 1024 * 1024 {
-        format!("{:.1} KB", bytes as f64 / 1024.0)
-    } else if bytes < 1024 * 1024 * 1024 {
-        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+/// Quote a CSV field when it contains a delimiter, quote, or newline
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
     } else {
-        format!("{:.2} GB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+        value.to_string()
     }
 }
 
 // ============================================================================
-// Daemon Install/Uninstall Functions
+// Quick Search (launchers)
 // ============================================================================
 
-fn daemon_install(logging: Option<bool>, human: bool) -> Result<()> {
-    let plist_path = DaemonConfig::launchd_plist_path();
-
-    // Check if already installed
-    if plist_path.exists() {
-        if human {
-            println!("Launchd agent already installed at {:?}", plist_path);
-            println!(
-                "To reinstall, run: groundeffect daemon uninstall && groundeffect daemon install"
-            );
-        } else {
-            println!(
-                "{{\"status\": \"already_installed\", \"plist_path\": \"{}\"}}",
-                plist_path.display()
-            );
-        }
+/// Launcher search: one line of script filter JSON, or open an email in Gmail
+async fn handle_quick_command(
+    query: Option<String>,
+    open: Option<String>,
+    account: Option<String>,
+    limit: usize,
+    use_daemon: bool,
+) -> Result<()> {
+    if let Some(id) = open {
+        let url = gmail_web_url(&id).ok_or_else(|| {
+            anyhow::anyhow!("Not an email ID: {} (expected <account>:<Message-ID>)", id)
+        })?;
+        open::that(&url)?;
+        println!("{}", serde_json::json!({ "url": url }));
         return Ok(());
     }
 
-    // Load existing config or create defaults
-    let mut daemon_config = DaemonConfig::load().unwrap_or_default();
-
-    // Only override logging if explicitly specified
-    if let Some(log_enabled) = logging {
-        daemon_config.logging_enabled = log_enabled;
+    let query = query.unwrap_or_default();
+    let config = Config::load().unwrap_or_default();
+    let mut options = SearchOptions::new(limit.clamp(1, 50));
+    if let Some(account) = account {
+        let id = config
+            .resolve_account(&account)
+            .ok_or_else(|| anyhow::anyhow!("Account not found: {}", account))?;
+        options.accounts = Some(vec![id]);
     }
-    daemon_config.save()?;
+    options.exclude_activity = config.activity.hide_from_search;
+    options.diversity = Diversity::from_config(&config.search);
 
-    // Find daemon binary
-    let daemon_path = find_daemon_binary()?;
+    let proxied = if use_daemon {
+        control::search_emails(&config.control_socket_path(), &query, &options).await
+    } else {
+        None
+    };
+    let results = match proxied {
+        Some((results, _)) => results,
+        None => {
+            // Loading the embedding model is the slow part, so without the
+            // daemon only keyword search runs
+            options.fusion.mode = SearchMode::Keyword;
+            let db = Arc::new(Database::open(config.lancedb_dir()).await?);
+            let embedding = Arc::new(HybridEmbeddingProvider::from_search_config(
+                None,
+                &config.search,
+            )?);
+            SearchEngine::new(db, embedding)
+                .search_emails(&query, &options)
+                .await?
+        }
+    };
 
-    // Create LaunchAgents directory
-    if let Some(parent) = plist_path.parent() {
-        std::fs::create_dir_all(parent)?;
+    let tz: Tz = config.general.timezone.parse().unwrap_or(Tz::UTC);
+    println!(
+        "{}",
+        serde_json::to_string(&email_items(&results, &config, tz))?
+    );
+    Ok(())
+}
+
+// ============================================================================
+// Db Command Handlers
+// ============================================================================
+
+async fn handle_unified_search(
+    query: String,
+    types: Vec<ResultType>,
+    account: Option<Vec<String>>,
+    limit: usize,
+    include_spam_trash: bool,
+    mode: SearchMode,
+    human: bool,
+) -> Result<()> {
+    let config = Config::load().unwrap_or_default();
+    let db = Arc::new(Database::open(config.lancedb_dir()).await?);
+
+    let mut options = UnifiedSearchOptions {
+        types,
+        limit: limit.min(100),
+        include_spam_trash: config.search.include_spam_trash || include_spam_trash,
+        exclude_activity: config.activity.hide_from_search,
+        fusion: FusionOptions::from_config(&config.search).with_mode(mode),
+        diversity: Diversity::from_config(&config.search),
+        ..Default::default()
+    };
+    if let Some(accts) = account {
+        let all_accounts = db.list_accounts().await?;
+        let resolved: Vec<String> = accts
+            .iter()
+            .filter_map(|a| resolve_account(&all_accounts, a))
+            .collect();
+        if !resolved.is_empty() {
+            options.accounts = Some(resolved);
+        }
     }
 
-    // Create log directory
-    let log_dir = dirs::home_dir()
-        .unwrap_or_default()
-        .join(".local")
-        .join("share")
-        .join("groundeffect")
-        .join("logs");
-    std::fs::create_dir_all(&log_dir)?;
+    let results = load_search_engine(&config, db)
+        .await?
+        .search_all(&query, &options)
+        .await?;
 
-    // Generate plist content
-    let plist_content = format!(
-        r#"<?xml version="1.0" encoding="UTF-8"?>
-<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
-<plist version="1.0">
-<dict>
-    <key>Label</key>
-    <string>com.groundeffect.daemon</string>
-    <key>ProgramArguments</key>
-    <array>
-        <string>/bin/bash</string>
-        <string>-c</string>
-        <string>source ~/.secrets 2>/dev/null; exec {daemon_path}{logging_flag}</string>
-    </array>
-    <key>RunAtLoad</key>
-    <true/>
-    <key>KeepAlive</key>
-    <true/>
-    <key>StandardOutPath</key>
-    <string>{stdout}</string>
-    <key>StandardErrorPath</key>
-    <string>{stderr}</string>
-    <key>SoftResourceLimits</key>
-    <dict>
-        <key>NumberOfFiles</key>
-        <integer>65536</integer>
-    </dict>
-    <key>EnvironmentVariables</key>
-    <dict>
-        <key>GROUNDEFFECT_EMAIL_POLL_INTERVAL</key>
-        <string>{email_interval}</string>
-        <key>GROUNDEFFECT_CALENDAR_POLL_INTERVAL</key>
-        <string>{calendar_interval}</string>
-        <key>GROUNDEFFECT_MAX_CONCURRENT_FETCHES</key>
-        <string>{max_fetches}</string>
-    </dict>
-</dict>
-</plist>"#,
-        daemon_path = daemon_path.display(),
-        logging_flag = if daemon_config.logging_enabled {
-            " --log"
-        } else {
-            ""
-        },
-        stdout = log_dir.join("stdout.log").display(),
-        stderr = log_dir.join("stderr.log").display(),
-        email_interval = daemon_config.email_poll_interval_secs,
-        calendar_interval = daemon_config.calendar_poll_interval_secs,
-        max_fetches = daemon_config.max_concurrent_fetches,
-    );
+    if !human {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+        return Ok(());
+    }
+    if results.is_empty() {
+        println!("No results found.");
+        return Ok(());
+    }
 
-    std::fs::write(&plist_path, plist_content)?;
+    let tz: Tz = config.general.timezone.parse().unwrap_or(Tz::UTC);
+    println!("\nFound {} results:\n", results.len());
+    for result in &results {
+        match &result.item {
+            UnifiedItem::Email(e) => {
+                println!("📧 {} (score: {:.2})", e.subject, result.score);
+                println!("   From: {}", e.from);
+                println!("   Date: {}", e.date.format("%Y-%m-%d %H:%M"));
+                println!("   ID: {}", e.id);
+            }
+            UnifiedItem::Event(event) => {
+                println!("📅 {} (score: {:.2})", event.summary, result.score);
+                println!(
+                    "   When: {}",
+                    format_event_span_human(&event.start, &event.end, tz)
+                );
+                if let Some(loc) = &event.location {
+                    println!("   Where: {}", loc);
+                }
+                println!("   ID: {}", event.id);
+            }
+            UnifiedItem::Attachment(a) => {
+                println!(
+                    "📎 {} ({}, score: {:.2})",
+                    a.filename,
+                    format_bytes(a.size),
+                    result.score
+                );
+                println!("   Email: {} from {}", a.subject, a.from);
+                println!("   Date: {}", a.date.format("%Y-%m-%d %H:%M"));
+                println!("   Email ID: {}", a.email_id);
+            }
+            UnifiedItem::Contact(c) => {
+                match &c.name {
+                    Some(name) => {
+                        println!("👤 {} <{}> (score: {:.2})", name, c.email, result.score)
+                    }
+                    None => println!("👤 {} (score: {:.2})", c.email, result.score),
+                }
+                println!(
+                    "   {} emails, last {}",
+                    c.message_count,
+                    c.last_contact.format("%Y-%m-%d")
+                );
+            }
+        }
+        println!();
+    }
+    Ok(())
+}
 
-    // Load the launchd agent
-    let output = std::process::Command::new("launchctl")
-        .args(["load", "-w", plist_path.to_str().unwrap()])
-        .output()?;
+fn handle_search_command(command: SearchCommands, global_human: bool) -> Result<()> {
+    match command {
+        SearchCommands::Stats { human } => {
+            let human = human || global_human;
+            let config = Config::load().unwrap_or_default();
+            let recorded = SearchCacheStats::load(&config.search_cache_stats_file());
+            let stats = recorded.clone().unwrap_or_else(|| SearchCacheStats {
+                capacity: config.search.cache_entries,
+                ttl_secs: config.search.cache_ttl_secs,
+                ..Default::default()
+            });
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        if !stderr.contains("service already loaded") {
             if human {
-                println!("Failed to load launchd agent: {}", stderr);
+                if recorded.is_none() {
+                    println!("No MCP server has searched yet.");
+                } else {
+                    let updated = stats
+                        .updated_at
+                        .map(format_relative_time)
+                        .unwrap_or_else(|| "unknown".to_string());
+                    let pid = stats
+                        .pid
+                        .map(|p| p.to_string())
+                        .unwrap_or_else(|| "?".to_string());
+                    println!(
+                        "\nSearch cache (MCP server pid {}, updated {})\n",
+                        pid, updated
+                    );
+                    println!(
+                        "  Hits:      {} of {} ({:.1}%)",
+                        stats.hits,
+                        stats.hits + stats.misses,
+                        stats.hit_rate() * 100.0
+                    );
+                    println!("  Entries:   {} of {}", stats.entries, stats.capacity);
+                    println!(
+                        "  Dropped:   {} evicted, {} expired, {} invalidated by writes",
+                        stats.evictions, stats.expired, stats.invalidations
+                    );
+                }
+                if stats.capacity == 0 {
+                    println!("  Cache disabled ([search] cache_entries = 0)");
+                } else {
+                    println!("  TTL:       {}s", stats.ttl_secs);
+                }
             } else {
-                println!(
-                    "{{\"status\": \"error\", \"message\": \"{}\"}}",
-                    stderr.replace('"', "\\\"")
-                );
+                let mut value = serde_json::to_value(&stats)?;
+                value["recorded"] = serde_json::json!(recorded.is_some());
+                value["hit_rate"] = serde_json::json!(stats.hit_rate());
+                println!("{}", serde_json::to_string_pretty(&value)?);
             }
-            return Ok(());
         }
     }
+    Ok(())
+}
 
-    if human {
-        println!("✓ Launchd agent installed");
-        println!("  Plist: {:?}", plist_path);
-        println!("  Config: {:?}", DaemonConfig::config_path());
-        println!("  Logs: {:?}", log_dir);
-        println!("\nThe daemon will start automatically at login.");
-        println!("To customize settings: groundeffect config settings");
-    } else {
-        println!(
-            "{{\"status\": \"installed\", \"plist_path\": \"{}\", \"config_path\": \"{}\"}}",
-            plist_path.display(),
-            DaemonConfig::config_path().display()
-        );
+async fn handle_db_command(
+    command: DbCommands,
+    global_human: bool,
+    global_dry_run: bool,
+) -> Result<()> {
+    match command {
+        DbCommands::Vectors { command } => match command {
+            VectorCommands::Export {
+                output,
+                format,
+                table,
+                account,
+                with_text,
+                missing_only,
+                human,
+            } => {
+                let human = human || global_human;
+                vectors_export(
+                    &output,
+                    &format,
+                    &table,
+                    account,
+                    with_text,
+                    missing_only,
+                    human,
+                )
+                .await
+            }
+            VectorCommands::Import {
+                file,
+                format,
+                table,
+                dry_run,
+                human,
+            } => {
+                let human = human || global_human;
+                let dry_run = dry_run || global_dry_run;
+                vectors_import(&file, format, &table, dry_run, human).await
+            }
+        },
     }
-
-    Ok(())
 }
 
-fn daemon_uninstall(human: bool) -> Result<()> {
-    let plist_path = DaemonConfig::launchd_plist_path();
+async fn vectors_export(
+    output: &Path,
+    format: &str,
+    table: &str,
+    account: Option<String>,
+    with_text: bool,
+    missing_only: bool,
+    human: bool,
+) -> Result<()> {
+    let format = VectorFormat::parse(format)?;
+    let table = VectorTable::parse(table)?;
+    let config = Config::load().unwrap_or_default();
+    let db = Database::open(config.lancedb_dir()).await?;
 
-    if !plist_path.exists() {
-        if human {
-            println!("Launchd agent is not installed.");
-        } else {
-            println!("{{\"status\": \"not_installed\"}}");
+    let account_id = match account {
+        Some(a) => {
+            let accounts = db.list_accounts().await?;
+            Some(
+                resolve_account(&accounts, &a)
+                    .ok_or_else(|| anyhow::anyhow!("Account not found: {}", a))?,
+            )
         }
-        return Ok(());
-    }
-
-    // Unload the agent
-    let _ = std::process::Command::new("launchctl")
-        .args(["unload", "-w", plist_path.to_str().unwrap()])
-        .output();
+        None => None,
+    };
 
-    // Remove the plist file
-    std::fs::remove_file(&plist_path)?;
+    let mut records = db
+        .export_vectors(
+            table,
+            account_id.as_deref(),
+            with_text.then_some(&config.embedding),
+        )
+        .await?;
+    if missing_only {
+        records.retain(|r| r.vector.is_none());
+    }
+    let with_vector = records.iter().filter(|r| r.vector.is_some()).count();
+    let exported = write_vectors(output, format, &records)?;
 
     if human {
-        println!("✓ Launchd agent uninstalled");
-        println!("The daemon will no longer start automatically at login.");
-        println!("\nNote: Synced data and configuration files are preserved.");
+        println!(
+            "✅ Exported {} {} vectors to {} ({} embedded, {} without a vector)",
+            exported,
+            table.as_str(),
+            output.display(),
+            with_vector,
+            exported - with_vector
+        );
     } else {
-        println!("{{\"status\": \"uninstalled\"}}");
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "success": true,
+                "table": table.as_str(),
+                "format": format.as_str(),
+                "path": output.display().to_string(),
+                "exported": exported,
+                "with_vector": with_vector,
+                "without_vector": exported - with_vector,
+            }))?
+        );
     }
-
     Ok(())
 }
 
-fn find_daemon_binary() -> Result<std::path::PathBuf> {
-    // Check common installation paths
-    let home = dirs::home_dir().unwrap_or_default();
-    let paths = [
-        // Homebrew on Apple Silicon
-        std::path::PathBuf::from("/opt/homebrew/bin/groundeffect-daemon"),
-        // Homebrew on Intel
-        std::path::PathBuf::from("/usr/local/bin/groundeffect-daemon"),
-        // Cargo install
-        home.join(".cargo/bin/groundeffect-daemon"),
-    ];
+async fn vectors_import(
+    file: &Path,
+    format: Option<String>,
+    table: &str,
+    dry_run: bool,
+    human: bool,
+) -> Result<()> {
+    let format = match format {
+        Some(f) => VectorFormat::parse(&f)?,
+        None => VectorFormat::from_path(file).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Can't infer format from {}, pass --format parquet|jsonl",
+                file.display()
+            )
+        })?,
+    };
+    let table = VectorTable::parse(table)?;
 
-    for path in &paths {
-        if path.exists() {
-            return Ok(path.clone());
-        }
-    }
+    let config = Config::load().unwrap_or_default();
+    let db = Database::open(config.lancedb_dir()).await?;
+    let dimension = db.vector_dimension(table);
 
-    // Use which to find it
-    let output = std::process::Command::new("which")
-        .arg("groundeffect-daemon")
-        .output();
+    let records = read_vectors(file, format)?;
+    validate_vectors(&records, dimension)?;
 
-    if let Ok(output) = output {
-        if output.status.success() {
-            let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
-            if !path.is_empty() {
-                return Ok(std::path::PathBuf::from(path));
+    let (imported, unknown_ids) = if dry_run {
+        (0, vec![])
+    } else {
+        db.import_vectors(table, &records).await?
+    };
+
+    if human {
+        if dry_run {
+            println!(
+                "✅ {} vectors in {} are valid ({} dimensions)",
+                records.len(),
+                file.display(),
+                dimension
+            );
+        } else {
+            println!("✅ Imported {} {} vectors", imported, table.as_str());
+            if !unknown_ids.is_empty() {
+                println!("   Skipped {} unknown ids:", unknown_ids.len());
+                for id in unknown_ids.iter().take(10) {
+                    println!("   - {}", id);
+                }
             }
         }
+    } else {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "success": true,
+                "table": table.as_str(),
+                "records": records.len(),
+                "imported": imported,
+                "unknown_ids": unknown_ids,
+                "dry_run": dry_run,
+            }))?
+        );
     }
-
-    anyhow::bail!(
-        "Could not find groundeffect-daemon binary. Make sure it's installed and in your PATH."
-    )
+    Ok(())
 }
 
 // ============================================================================
-// Account Add/Delete/Configure Functions
+// Storage Command Handler
 // ============================================================================
 
-async fn account_add(
-    years: Option<String>,
-    attachments: bool,
-    alias: Option<String>,
+/// Bytes and files under a directory, per account subdirectory
+#[derive(Serialize)]
+struct AccountDirUsage {
+    account: String,
+    bytes: u64,
+    files: u64,
+}
+
+/// Size of a directory (zero when it doesn't exist)
+fn dir_usage(dir: &std::path::Path) -> relocate::DirSummary {
+    relocate::summarize(dir).unwrap_or_default()
+}
+
+/// Per-account subdirectories of `dir` (attachments, raw copies), largest first
+fn account_dir_usage(dir: &std::path::Path) -> Vec<AccountDirUsage> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut accounts: Vec<AccountDirUsage> = entries
+        .flatten()
+        .filter(|e| e.file_type().map(|t| t.is_dir()).unwrap_or(false))
+        .map(|e| {
+            let summary = dir_usage(&e.path());
+            AccountDirUsage {
+                account: e.file_name().to_string_lossy().to_string(),
+                bytes: summary.bytes,
+                files: summary.files,
+            }
+        })
+        .collect();
+    accounts.sort_by(|a, b| b.bytes.cmp(&a.bytes));
+    accounts
+}
+
+fn handle_logs_command(
+    since: Option<String>,
+    level: Option<String>,
+    account: Option<String>,
+    source: Option<String>,
+    limit: usize,
     human: bool,
 ) -> Result<()> {
-    use dialoguer::{Confirm, Input, Select};
-    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-    use tokio::net::TcpListener;
-
-    // Load config and initialize token provider early
     let config = Config::load().unwrap_or_default();
-    let token_provider = create_token_provider(&config).await?;
+    let since = match since {
+        Some(since) => Some(match parse_age(&since) {
+            Some(age) => Utc::now() - age,
+            None => {
+                parse_date(&Some(since.clone()), &config.general.timezone).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Invalid --since '{}'. Use an age like 1h or YYYY-MM-DD",
+                        since
+                    )
+                })?
+            }
+        }),
+        None => None,
+    };
+    let level = match level {
+        Some(level) => Some(logs::parse_level(&level).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Invalid --level '{}'. Use error, warn, info, debug or trace",
+                level
+            )
+        })?),
+        None => None,
+    };
+    let query = LogQuery {
+        since,
+        level,
+        account: account.map(|a| config.resolve_account(&a).unwrap_or(a)),
+        source,
+        limit: Some(limit),
+    };
 
-    // Check for OAuth credentials
-    let client_id = std::env::var("GROUNDEFFECT_GOOGLE_CLIENT_ID");
-    let client_secret = std::env::var("GROUNDEFFECT_GOOGLE_CLIENT_SECRET");
+    let log_dir = config.log_dir();
+    let entries = logs::read(&log_dir, &query)?;
 
-    if client_id.is_err() || client_secret.is_err() {
-        if human {
-            println!("\n❌ OAuth credentials not configured!\n");
-            println!("Please set the following environment variables:");
-            println!("  export GROUNDEFFECT_GOOGLE_CLIENT_ID=\"your-client-id\"");
-            println!("  export GROUNDEFFECT_GOOGLE_CLIENT_SECRET=\"your-client-secret\"");
-            println!("\nYou can get these from the Google Cloud Console:");
-            println!("  https://console.cloud.google.com/apis/credentials");
-            println!("\nMake sure to:");
-            println!("  1. Create an OAuth 2.0 Client ID (Desktop app type)");
-            println!("  2. Add http://localhost:8085/oauth/callback as a redirect URI");
-            println!("  3. Enable Gmail API and Google Calendar API\n");
-        } else {
-            println!("{{\"success\": false, \"error\": \"OAuth credentials not configured\"}}");
+    if human {
+        if entries.is_empty() {
+            println!("No log entries found in {}", log_dir.display());
+            return Ok(());
         }
-        return Ok(());
-    }
-
-    // Interactive prompts if not provided via args
-    let years_to_sync = match years {
-        Some(y) => y,
-        None => {
-            if human {
-                let options = &[
-                    "1 year (recommended)",
-                    "2 years",
-                    "5 years",
-                    "10 years",
-                    "All email history",
-                ];
-                let selection = Select::new()
-                    .with_prompt("How many years of email history to sync?")
-                    .items(options)
-                    .default(0)
-                    .interact()?;
-                match selection {
-                    0 => "1".to_string(),
-                    1 => "2".to_string(),
-                    2 => "5".to_string(),
-                    3 => "10".to_string(),
-                    _ => "all".to_string(),
-                }
-            } else {
-                "1".to_string() // Default for non-interactive
+        let tz = config.general.timezone.parse::<Tz>().unwrap_or(Tz::UTC);
+        for entry in &entries {
+            let mut message = entry.message.clone();
+            for (key, value) in &entry.fields {
+                message.push_str(&format!(" {}={}", key, value));
             }
+            println!(
+                "{} {:>5} {:<6} {}",
+                entry
+                    .timestamp
+                    .with_timezone(&tz)
+                    .format("%Y-%m-%d %H:%M:%S"),
+                entry.level,
+                entry.source,
+                message.replace('\n', "\n                                 ")
+            );
         }
-    };
-
-    let sync_attachments = if !attachments && human {
-        Confirm::new()
-            .with_prompt("Enable automatic attachment downloading?")
-            .default(false)
-            .interact()?
     } else {
-        attachments
-    };
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "log_dir": log_dir,
+                "count": entries.len(),
+                "entries": entries,
+            }))?
+        );
+    }
+    Ok(())
+}
 
-    let account_alias = match alias {
-        Some(a) => {
-            if a.is_empty() {
-                None
-            } else {
-                Some(a)
+async fn handle_index_command(command: IndexCommands, global_human: bool) -> Result<()> {
+    let config = Config::load().unwrap_or_default();
+    let db = Database::open(config.lancedb_dir()).await?;
+    let state_file = config.index_state_file();
+
+    match command {
+        IndexCommands::Status { human } => {
+            let coverage = db.fts_coverage().await?;
+            let state = IndexState::load(&state_file);
+            if !(human || global_human) {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&serde_json::json!({
+                        "strategy": config.index.fts_strategy,
+                        "fts": fts_coverage_json(&coverage),
+                        "last_update": state.last_update,
+                        "last_rebuild": state.last_rebuild,
+                    }))?
+                );
+                return Ok(());
+            }
+
+            println!(
+                "\n🔎 Full-text indexes ({} updates)\n",
+                config.index.fts_strategy.as_str()
+            );
+            print_fts_coverage(&coverage);
+            let describe = |run: &Option<FtsRun>| match run {
+                Some(run) => format!(
+                    "{} ({}, {} ms)",
+                    format_relative_time(run.at),
+                    run.mode.as_str(),
+                    run.duration_ms
+                ),
+                None => "never".to_string(),
+            };
+            println!();
+            println!("  Last update:  {}", describe(&state.last_update));
+            println!("  Last rebuild: {}", describe(&state.last_rebuild));
+        }
+        IndexCommands::Rebuild {
+            fts,
+            incremental,
+            human,
+        } => {
+            if !fts {
+                anyhow::bail!("Name the indexes to rebuild, e.g. groundeffect index rebuild --fts");
             }
-        }
-        None => {
-            if human {
-                let input: String = Input::new()
-                    .with_prompt("Alias for this account (optional, press Enter to skip)")
-                    .allow_empty(true)
-                    .interact_text()?;
-                if input.is_empty() {
-                    None
-                } else {
-                    Some(input)
-                }
+            let mode = if incremental {
+                FtsStrategy::Incremental
             } else {
-                None
+                FtsStrategy::Rebuild
+            };
+            let run = fts::update_indexes(&db, mode, &state_file).await?;
+            let coverage = db.fts_coverage().await?;
+            if !(human || global_human) {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&serde_json::json!({
+                        "success": true,
+                        "mode": run.mode,
+                        "at": run.at,
+                        "duration_ms": run.duration_ms,
+                        "fts": fts_coverage_json(&coverage),
+                    }))?
+                );
+                return Ok(());
             }
+
+            let verb = match run.mode {
+                FtsStrategy::Incremental => "Updated",
+                FtsStrategy::Rebuild => "Rebuilt",
+            };
+            println!(
+                "\n✅ {} full-text indexes in {} ms\n",
+                verb, run.duration_ms
+            );
+            print_fts_coverage(&coverage);
         }
-    };
+    }
+    Ok(())
+}
 
-    // Parse years_to_sync
-    let sync_since = if years_to_sync.eq_ignore_ascii_case("all") {
-        None
-    } else {
-        let years: u32 = years_to_sync.parse().unwrap_or(1);
-        let years = years.clamp(1, 20);
-        Some(Utc::now() - chrono::Duration::days(years as i64 * 365))
-    };
+fn fts_coverage_json(coverage: &[FtsCoverage]) -> serde_json::Value {
+    coverage
+        .iter()
+        .map(|c| {
+            serde_json::json!({
+                "table": c.table,
+                "column": c.column,
+                "indexed_rows": c.indexed_rows,
+                "unindexed_rows": c.unindexed_rows,
+            })
+        })
+        .collect()
+}
 
-    if human {
-        println!("\n🔐 Opening browser for Google authentication...\n");
+fn print_fts_coverage(coverage: &[FtsCoverage]) {
+    for c in coverage {
+        let name = format!("{}.{}", c.table, c.column);
+        match c.indexed_rows {
+            Some(indexed) => println!(
+                "  {:<22} {} indexed, {} not yet",
+                name, indexed, c.unindexed_rows
+            ),
+            None => println!("  {:<22} no index ({} rows)", name, c.unindexed_rows),
+        }
     }
+}
 
-    let oauth = OAuthManager::new(token_provider.clone());
-    let state = format!("groundeffect_{}", uuid::Uuid::new_v4());
-    let auth_url = oauth.authorization_url(&state);
+async fn handle_storage_command(
+    top_senders_by_size: Option<usize>,
+    account: Option<String>,
+    human: bool,
+) -> Result<()> {
+    let config = Config::load().unwrap_or_default();
+    let data_dir = &config.general.data_dir;
+    let db = Database::open(config.lancedb_dir()).await?;
 
-    if human {
-        println!("If the browser doesn't open, visit this URL manually:");
-        println!("{}\n", auth_url);
+    let tables = db.table_usage().await?;
+    let database_bytes = dir_usage(&config.lancedb_dir()).bytes;
+    let attachments = account_dir_usage(&config.attachments_dir());
+    let attachment_bytes = dir_usage(&config.attachments_dir()).bytes;
+    let raw = account_dir_usage(&config.raw_dir());
+    let raw_bytes = dir_usage(&config.raw_dir()).bytes;
+    let models = dir_usage(&config.models_dir());
+    let logs = dir_usage(&config.log_dir());
+    let total_bytes = dir_usage(data_dir).bytes;
+    let other_bytes = total_bytes
+        .saturating_sub(database_bytes + attachment_bytes + raw_bytes + models.bytes + logs.bytes);
+
+    let senders = match top_senders_by_size {
+        Some(limit) => {
+            let account_id = match &account {
+                Some(a) => {
+                    let accounts = db.list_accounts().await?;
+                    Some(
+                        resolve_account(&accounts, a)
+                            .ok_or_else(|| anyhow::anyhow!("Account not found: {}", a))?,
+                    )
+                }
+                None => None,
+            };
+            Some(
+                db.top_senders_by_size(account_id.as_deref(), limit.max(1))
+                    .await?,
+            )
+        }
+        None => None,
+    };
+
+    if !human {
+        let mut value = serde_json::json!({
+            "data_dir": data_dir,
+            "total_bytes": total_bytes,
+            "database": { "total_bytes": database_bytes, "tables": tables },
+            "attachments": { "total_bytes": attachment_bytes, "accounts": attachments },
+            "raw": { "total_bytes": raw_bytes, "accounts": raw },
+            "models": models,
+            "logs": logs,
+            "other_bytes": other_bytes,
+        });
+        if let Some(senders) = senders {
+            value["top_senders_by_size"] = serde_json::json!(senders);
+        }
+        println!("{}", serde_json::to_string_pretty(&value)?);
+        return Ok(());
     }
 
-    // Open browser
-    if let Err(e) = open::that(&auth_url) {
-        if human {
-            println!("Failed to open browser: {}", e);
+    println!(
+        "\n💾 Storage: {} in {}\n",
+        format_bytes(total_bytes),
+        data_dir.display()
+    );
+    println!("{:<28} {:>10}", "Database", format_bytes(database_bytes));
+    for table in &tables {
+        let mut detail = vec![format!("indexes {}", format_bytes(table.index_bytes))];
+        if table.vector_bytes > 0 {
+            detail.insert(0, format!("vectors ~{}", format_bytes(table.vector_bytes)));
+        }
+        println!(
+            "  {:<26} {:>10}  {} rows ({})",
+            table.name,
+            format_bytes(table.total()),
+            table.rows,
+            detail.join(", ")
+        );
+    }
+    println!(
+        "{:<28} {:>10}",
+        "Attachments",
+        format_bytes(attachment_bytes)
+    );
+    for usage in &attachments {
+        println!(
+            "  {:<26} {:>10}  {} files",
+            usage.account,
+            format_bytes(usage.bytes),
+            usage.files
+        );
+    }
+    if raw_bytes > 0 {
+        println!("{:<28} {:>10}", "Raw copies", format_bytes(raw_bytes));
+        for usage in &raw {
+            println!(
+                "  {:<26} {:>10}  {} files",
+                usage.account,
+                format_bytes(usage.bytes),
+                usage.files
+            );
         }
     }
+    println!("{:<28} {:>10}", "Models", format_bytes(models.bytes));
+    println!("{:<28} {:>10}", "Logs", format_bytes(logs.bytes));
+    println!(
+        "{:<28} {:>10}",
+        "Other (caches, state)",
+        format_bytes(other_bytes)
+    );
 
-    // Start local HTTP server to receive callback
-    let listener = TcpListener::bind("127.0.0.1:8085").await?;
-    if human {
-        println!("⏳ Waiting for authentication callback on http://localhost:8085 ...\n");
+    if let Some(senders) = senders {
+        println!("\nTop senders by size:");
+        if senders.is_empty() {
+            println!("  (no emails)");
+        }
+        for (i, sender) in senders.iter().enumerate() {
+            let name = sender
+                .name
+                .as_ref()
+                .map(|n| format!(" ({})", n))
+                .unwrap_or_default();
+            let attachments = if sender.attachment_bytes > 0 {
+                format!(", {} attachments", format_bytes(sender.attachment_bytes))
+            } else {
+                String::new()
+            };
+            println!(
+                "  {:>2}. {:>10}  {}{}  {} emails{}",
+                i + 1,
+                format_bytes(sender.total()),
+                sender.email,
+                name,
+                sender.emails,
+                attachments
+            );
+        }
+        println!(
+            "\nTo drop old mail locally: groundeffect account configure <account> --retain <age>"
+        );
     }
 
-    // Accept one connection with timeout
-    let callback_result = tokio::time::timeout(
-        std::time::Duration::from_secs(300),
-        async {
-            let (mut socket, _) = listener.accept().await?;
-            let mut reader = BufReader::new(&mut socket);
-            let mut request_line = String::new();
-            reader.read_line(&mut request_line).await?;
+    Ok(())
+}
 
-            // Parse callback
-            let (code, received_state) = parse_oauth_callback(&request_line)?;
+// ============================================================================
+// Doctor Command Handler
+// ============================================================================
 
-            if received_state != state {
-                let response = "HTTP/1.1 400 Bad Request\r\nContent-Type: text/html\r\n\r\n<h1>Error: Invalid state</h1>";
-                socket.write_all(response.as_bytes()).await?;
-                anyhow::bail!("OAuth state mismatch - possible CSRF attack");
-            }
+/// A problem found by `doctor`, with the command that fixes it
+#[derive(Serialize)]
+struct DoctorProblem {
+    area: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    account: Option<String>,
+    message: String,
+    fix: String,
+}
 
-            // Send success response
-            let success_html = "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\n\r\n\
-                <!DOCTYPE html><html><body style='font-family: sans-serif; padding: 40px; text-align: center;'>\
-                <h1>Authentication Successful!</h1><p>You can close this window.</p></body></html>";
-            socket.write_all(success_html.as_bytes()).await?;
+/// Outcome of a single `doctor` check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum CheckStatus {
+    Ok,
+    Warn,
+    Fail,
+    Skipped,
+}
 
-            Ok::<String, anyhow::Error>(code)
-        }
-    ).await;
+/// One `doctor` check, with the command that fixes it when it isn't ok
+#[derive(Serialize)]
+struct DoctorCheck {
+    name: &'static str,
+    status: CheckStatus,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fix: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_ms: Option<u64>,
+    #[serde(skip_serializing_if = "serde_json::Value::is_null")]
+    details: serde_json::Value,
+}
 
-    let code = match callback_result {
-        Ok(Ok(code)) => code,
-        Ok(Err(e)) => {
-            if human {
-                println!("❌ OAuth error: {}", e);
-            } else {
-                println!("{{\"success\": false, \"error\": \"{}\"}}", e);
-            }
-            return Ok(());
-        }
-        Err(_) => {
-            if human {
-                println!("❌ OAuth timeout: no callback received within 5 minutes");
-            } else {
-                println!("{{\"success\": false, \"error\": \"OAuth timeout\"}}");
-            }
-            return Ok(());
+impl DoctorCheck {
+    fn new(name: &'static str, status: CheckStatus, message: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            message: message.into(),
+            fix: None,
+            duration_ms: None,
+            details: serde_json::Value::Null,
         }
-    };
+    }
 
-    if human {
-        println!("✅ Received authorization code, exchanging for tokens...\n");
+    fn fix(mut self, fix: impl Into<String>) -> Self {
+        self.fix = Some(fix.into());
+        self
     }
 
-    // Exchange code for tokens
-    let (tokens, user_info) = oauth.exchange_code(&code).await?;
+    fn details(mut self, details: serde_json::Value) -> Self {
+        self.details = details;
+        self
+    }
 
-    // Store tokens
-    token_provider
-        .store_tokens(&user_info.email, &tokens)
-        .await?;
+    fn timed(mut self, started: std::time::Instant) -> Self {
+        self.duration_ms = Some(started.elapsed().as_millis() as u64);
+        self
+    }
+}
 
-    // Open database and create/update account
-    std::fs::create_dir_all(config.lancedb_dir())?;
-    let db = Database::open(config.lancedb_dir()).await?;
+/// Port the OAuth callback listens on while adding an account
+const OAUTH_CALLBACK_PORT: u16 = 8085;
 
-    let is_existing = db.get_account(&user_info.email).await?.is_some();
+/// Free disk space below which doctor warns / fails (bytes)
+const DISK_WARN_BYTES: u64 = 5 * 1024 * 1024 * 1024;
+const DISK_FAIL_BYTES: u64 = 1024 * 1024 * 1024;
 
-    if let Some(existing) = db.get_account(&user_info.email).await? {
-        let mut updated = existing;
-        updated.status = AccountStatus::Active;
-        updated.alias = account_alias.clone().or(updated.alias);
-        updated.sync_email_since = sync_since;
-        updated.sync_attachments = sync_attachments;
-        db.upsert_account(&updated).await?;
+async fn handle_doctor_command(offline: bool, quick: bool, human: bool) -> Result<()> {
+    let mut checks = Vec::new();
+
+    let (config_check, config) = doctor_check_config();
+    checks.push(config_check);
+
+    let started = std::time::Instant::now();
+    let db = match Database::open(config.lancedb_dir()).await {
+        Ok(db) => {
+            checks.push(doctor_check_tables(&db).await.timed(started));
+            Some(db)
+        }
+        Err(e) => {
+            checks.push(
+                DoctorCheck::new(
+                    "database",
+                    CheckStatus::Fail,
+                    format!("Can't open database at {:?}: {}", config.lancedb_dir(), e),
+                )
+                .fix("Check the directory's permissions and free space, then stop the daemon ('groundeffect daemon stop') and retry")
+                .timed(started),
+            );
+            None
+        }
+    };
+
+    let accounts = match &db {
+        Some(db) => db.list_accounts().await?,
+        None => Vec::new(),
+    };
+
+    checks.push(doctor_check_embedding_model(&config, quick));
+    checks.push(doctor_check_embedding_api(&config, db.as_ref(), offline).await);
+    checks.push(doctor_check_oauth_credentials(!accounts.is_empty()));
+
+    // Daemon
+    let running = check_daemon_running();
+    let pid = get_daemon_pid();
+    let daemon_health = DaemonHealth::load(&config.daemon_health_file());
+    checks.push(if !running {
+        DoctorCheck::new(
+            "daemon",
+            CheckStatus::Fail,
+            "Daemon is not running, nothing is syncing",
+        )
+        .fix("groundeffect daemon start")
+    } else if daemon_health.is_crash_looping() {
+        DoctorCheck::new(
+            "daemon",
+            CheckStatus::Fail,
+            format!(
+                "Daemon is crash-looping ({} unclean exits in {} minutes)",
+                daemon_health.recent_crashes.len(),
+                CRASH_LOOP_WINDOW_SECS / 60
+            ),
+        )
+        .fix("groundeffect daemon status --health --human")
     } else {
-        let account = Account {
-            id: user_info.email.clone(),
-            alias: account_alias.clone(),
-            display_name: user_info.name.unwrap_or_else(|| user_info.email.clone()),
-            added_at: Utc::now(),
-            last_sync_email: None,
-            last_sync_calendar: None,
-            status: AccountStatus::Active,
-            sync_email_since: sync_since,
-            oldest_email_synced: None,
-            oldest_event_synced: None,
-            sync_attachments,
-            estimated_total_emails: None,
+        let message = match pid {
+            Some(pid) => format!("Running (PID: {})", pid),
+            None => "Running".to_string(),
         };
-        db.upsert_account(&account).await?;
+        DoctorCheck::new("daemon", CheckStatus::Ok, message)
+    });
+
+    let incidents = daemon_health.recent_sync_incidents(Utc::now());
+    checks.push(match incidents.last() {
+        Some(latest) => DoctorCheck::new(
+            "sync_watchdog",
+            CheckStatus::Warn,
+            format!(
+                "Restarted {} stalled sync(s) in the last {} hours (latest: {}: {})",
+                incidents.len(),
+                RECENT_INCIDENT_SECS / 3600,
+                latest.account_id,
+                latest.detail
+            ),
+        )
+        .fix("groundeffect logs --since 1d --level warn --human"),
+        None => DoctorCheck::new(
+            "sync_watchdog",
+            CheckStatus::Ok,
+            "No stalled syncs in the last day",
+        ),
+    });
+    checks.push(doctor_check_launchd());
+    checks.push(doctor_check_disk_space(&config.general.data_dir));
+    checks.push(doctor_check_callback_port());
+
+    let mut problems: Vec<DoctorProblem> = checks
+        .iter()
+        .filter(|check| check.status == CheckStatus::Fail)
+        .map(|check| DoctorProblem {
+            area: check.name,
+            account: None,
+            message: check.message.clone(),
+            fix: check.fix.clone().unwrap_or_default(),
+        })
+        .collect();
+
+    // Accounts: refresh each token unless offline
+    let health_file = config.token_health_file();
+    let mut token_health = TokenHealthReport::load(&health_file);
+    if !offline && !accounts.is_empty() {
+        let token_provider = create_token_provider(&config).await?;
+        let oauth = OAuthManager::new(token_provider);
+        for account in &accounts {
+            let check = check_account_token(&oauth, &account.id).await;
+            // The daemon still notifies: recording doesn't mark anything notified
+            token_health.record(&account.id, &check, Utc::now());
+        }
+        if let Err(e) = token_health.save(&health_file) {
+            eprintln!("Warning: failed to write token health file: {}", e);
+        }
+    }
+
+    for account in &accounts {
+        let health = token_health.accounts.get(&account.id);
+        let state = health.map(|h| h.state);
+        let reason = health.and_then(|h| h.reason.clone());
+        let needs_reauth = state == Some(TokenState::NeedsReauth)
+            || (state.is_none() && account.status == AccountStatus::NeedsReauth);
+        if needs_reauth {
+            problems.push(DoctorProblem {
+                area: "account",
+                account: Some(account.id.clone()),
+                message: reason.unwrap_or_else(|| "Account needs re-authentication".to_string()),
+                fix: format!("groundeffect account reauth {}", account.id),
+            });
+        } else if state == Some(TokenState::Unknown) {
+            problems.push(DoctorProblem {
+                area: "account",
+                account: Some(account.id.clone()),
+                message: format!(
+                    "Couldn't verify tokens: {}",
+                    reason.unwrap_or_else(|| "unknown error".to_string())
+                ),
+                fix: "Check your network connection and run 'groundeffect doctor' again"
+                    .to_string(),
+            });
+        }
     }
 
     if human {
-        if is_existing {
-            println!("🎉 Re-authenticated account: {}", user_info.email);
-        } else {
-            println!("🎉 Successfully added account: {}", user_info.email);
+        println!("Checks");
+        for check in &checks {
+            let icon = match check.status {
+                CheckStatus::Ok => "✓",
+                CheckStatus::Warn => "⚠",
+                CheckStatus::Fail => "✗",
+                CheckStatus::Skipped => "-",
+            };
+            let timing = check
+                .duration_ms
+                .map(|ms| format!(" ({} ms)", ms))
+                .unwrap_or_default();
+            println!("  {} {}: {}{}", icon, check.name, check.message, timing);
+            if let Some(note) = check.details.get("accuracy_note").and_then(|n| n.as_str()) {
+                println!("      note: {}", note);
+            }
+            if check.status == CheckStatus::Warn {
+                if let Some(fix) = &check.fix {
+                    println!("      fix: {}", fix);
+                }
+            }
         }
-        if let Some(a) = &account_alias {
-            println!("   Alias: {}", a);
+
+        println!("\nAccounts");
+        if accounts.is_empty() {
+            println!("  (none) - add one with 'groundeffect account add'");
         }
-        println!("   Sync {} of email history", years_to_sync);
-        println!(
-            "   Attachments: {}",
-            if sync_attachments {
-                "enabled"
-            } else {
-                "disabled"
+        for account in &accounts {
+            let label = match &account.alias {
+                Some(alias) => format!("{} ({})", account.id, alias),
+                None => account.id.clone(),
+            };
+            let problem = problems
+                .iter()
+                .find(|p| p.account.as_deref() == Some(account.id.as_str()));
+            match problem {
+                Some(problem) => {
+                    println!("  ✗ {}", label);
+                    println!("      {}", problem.message);
+                    println!("      fix: {}", problem.fix);
+                }
+                None => {
+                    let checked = token_health
+                        .accounts
+                        .get(&account.id)
+                        .map(|h| {
+                            format!(
+                                " (checked {})",
+                                h.checked_at
+                                    .with_timezone(&chrono::Local)
+                                    .format("%Y-%m-%d %H:%M")
+                            )
+                        })
+                        .unwrap_or_default();
+                    println!("  ✓ {} - tokens ok{}", label, checked);
+                }
             }
-        );
-        println!("\nThe daemon will start syncing automatically if running.");
+        }
+
+        println!();
+        if problems.is_empty() {
+            println!("No problems found.");
+        } else {
+            println!("{} problem(s) found.", problems.len());
+            for problem in problems.iter().filter(|p| p.account.is_none()) {
+                println!("  ✗ {}", problem.message);
+                println!("      fix: {}", problem.fix);
+            }
+        }
     } else {
-        println!("{{\"success\": true, \"account\": {{\"id\": \"{}\", \"alias\": {}, \"years_to_sync\": \"{}\", \"sync_attachments\": {}}}}}",
-            user_info.email,
-            account_alias.as_ref().map(|a| format!("\"{}\"", a)).unwrap_or("null".to_string()),
-            years_to_sync,
-            sync_attachments);
+        let account_json: Vec<serde_json::Value> = accounts
+            .iter()
+            .map(|account| {
+                let health = token_health.accounts.get(&account.id);
+                serde_json::json!({
+                    "id": account.id,
+                    "alias": account.alias,
+                    "status": format!("{:?}", account.status).to_lowercase(),
+                    "token_state": health.map(|h| h.state),
+                    "reason": health.and_then(|h| h.reason.clone()),
+                    "checked_at": health.map(|h| h.checked_at.to_rfc3339()),
+                    "last_ok": health.and_then(|h| h.last_ok).map(|t| t.to_rfc3339()),
+                })
+            })
+            .collect();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "healthy": problems.is_empty(),
+                "daemon": {
+                    "running": running,
+                    "pid": pid,
+                    "crash_looping": daemon_health.is_crash_looping(),
+                },
+                "checks": checks,
+                "accounts": account_json,
+                "problems": problems,
+            }))?
+        );
     }
 
     Ok(())
 }
 
-async fn account_reauth(account: &str, human: bool) -> Result<()> {
-    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-    use tokio::net::TcpListener;
-
-    let config = Config::load().unwrap_or_default();
-    let token_provider = create_token_provider(&config).await?;
-    let db = Database::open(config.lancedb_dir()).await?;
-    let accounts = db.list_accounts().await?;
-
-    let email = accounts
-        .iter()
-        .find(|a| a.id == account || a.alias.as_ref() == Some(&account.to_string()))
-        .map(|a| a.id.clone());
-
-    let email = match email {
-        Some(e) => e,
-        None => {
-            if human {
-                println!("❌ Account not found: {}", account);
-            } else {
-                println!("{{\"success\": false, \"error\": \"Account not found\"}}");
-            }
-            return Ok(());
+/// Load config.toml and daemon.toml and look for settings that won't work
+fn doctor_check_config() -> (DoctorCheck, Config) {
+    let path = Config::config_path();
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            let check = DoctorCheck::new(
+                "config",
+                CheckStatus::Fail,
+                format!("Can't parse {:?}: {}", path, e),
+            )
+            .fix(format!(
+                "Fix the error in {:?}, or move it aside to use defaults",
+                path
+            ));
+            return (check, Config::default());
         }
     };
 
-    // Check for OAuth credentials
-    let client_id = std::env::var("GROUNDEFFECT_GOOGLE_CLIENT_ID");
-    let client_secret = std::env::var("GROUNDEFFECT_GOOGLE_CLIENT_SECRET");
-
-    if client_id.is_err() || client_secret.is_err() {
-        if human {
-            println!("\n❌ OAuth credentials not configured!\n");
-            println!("Please set the following environment variables:");
-            println!("  export GROUNDEFFECT_GOOGLE_CLIENT_ID=\"your-client-id\"");
-            println!("  export GROUNDEFFECT_GOOGLE_CLIENT_SECRET=\"your-client-secret\"");
-            println!("\nYou can get these from the Google Cloud Console:");
-            println!("  https://console.cloud.google.com/apis/credentials");
-            println!("\nMake sure to:");
-            println!("  1. Create an OAuth 2.0 Client ID (Desktop app type)");
-            println!("  2. Add http://localhost:8085/oauth/callback as a redirect URI");
-            println!("  3. Enable Gmail API and Google Calendar API\n");
-        } else {
-            println!("{{\"success\": false, \"error\": \"OAuth credentials not configured\"}}");
+    let mut issues = Vec::new();
+    if config.general.timezone.parse::<Tz>().is_err() {
+        issues.push(format!(
+            "general.timezone '{}' is not an IANA timezone (e.g. America/Los_Angeles), UTC is used",
+            config.general.timezone
+        ));
+    }
+    if EmbeddingModel::from_str(&config.search.embedding_model).is_none() {
+        issues.push(format!(
+            "search.embedding_model '{}' is unknown, bge-base-en-v1.5 is used",
+            config.search.embedding_model
+        ));
+    }
+    let provider = config.search.effective_embedding_provider();
+    match config.search.embedding_endpoint() {
+        Some(_) if !network::REMOTE_EMBEDDING => {
+            issues.push(
+                "search.embedding_provider needs remote embedding, which this build leaves out (no-remote-embedding)"
+                    .into(),
+            );
         }
-        return Ok(());
+        None if provider == EmbeddingProvider::Remote => {
+            issues.push("search.embedding_provider is 'remote' but embedding_url is unset".into());
+        }
+        Some(endpoint) => {
+            if let Some(env_name) = &endpoint.api_key_env {
+                if std::env::var(env_name).map_or(true, |key| key.trim().is_empty()) {
+                    issues.push(format!(
+                        "search.embedding_provider is '{}' but ${} is not set",
+                        provider.as_str(),
+                        env_name
+                    ));
+                }
+            }
+        }
+        None => {}
     }
-
-    if human {
-        println!("\n🔐 Re-authenticating account: {}", email);
-        println!("   Opening browser for Google authentication...\n");
+    if let Err(e) = config.embedding.validate() {
+        issues.push(format!("[embedding]: {}", e));
+    }
+    if let Err(e) = SynonymDictionary::load(&Config::synonyms_path()) {
+        issues.push(e.to_string());
+    }
+    if let Err(e) = DaemonConfig::load() {
+        issues.push(format!(
+            "Can't parse {:?}: {}",
+            DaemonConfig::config_path(),
+            e
+        ));
     }
 
-    let oauth = OAuthManager::new(token_provider.clone());
-    let state = format!("groundeffect_reauth_{}", uuid::Uuid::new_v4());
-    let auth_url = oauth.authorization_url(&state);
+    let details = serde_json::json!({
+        "path": path,
+        "exists": path.exists(),
+        "issues": issues,
+    });
+    let check = if !issues.is_empty() {
+        DoctorCheck::new("config", CheckStatus::Warn, issues.join("; "))
+            .fix(format!("Edit {:?}", path))
+    } else if path.exists() {
+        DoctorCheck::new("config", CheckStatus::Ok, format!("Loaded {:?}", path))
+    } else {
+        DoctorCheck::new("config", CheckStatus::Ok, "No config file, using defaults")
+    };
+    (check.details(details), config)
+}
 
-    if human {
-        println!("If the browser doesn't open, visit this URL manually:");
-        println!("{}\n", auth_url);
+/// Open every table and count its rows
+async fn doctor_check_tables(db: &Database) -> DoctorCheck {
+    let mut details = serde_json::Map::new();
+    let mut broken = Vec::new();
+    for (name, count) in db.table_row_counts().await {
+        match count {
+            Ok(rows) => {
+                details.insert(name.to_string(), serde_json::json!(rows));
+            }
+            Err(e) => {
+                details.insert(
+                    name.to_string(),
+                    serde_json::json!({ "error": e.to_string() }),
+                );
+                broken.push(format!("{} ({})", name, e));
+            }
+        }
     }
 
-    if let Err(e) = open::that(&auth_url) {
-        if human {
-            println!("Failed to open browser: {}", e);
-        }
+    if broken.is_empty() {
+        let rows = |name: &str| details.get(name).and_then(|v| v.as_u64()).unwrap_or(0);
+        let message = format!(
+            "All tables readable ({} emails, {} events)",
+            rows("emails"),
+            rows("events")
+        );
+        DoctorCheck::new("database", CheckStatus::Ok, message)
+            .details(serde_json::Value::Object(details))
+    } else {
+        DoctorCheck::new(
+            "database",
+            CheckStatus::Fail,
+            format!("Unreadable tables: {}", broken.join(", ")),
+        )
+        .fix("Stop the daemon ('groundeffect daemon stop'), then rebuild with 'groundeffect sync reset --account <account> --confirm'")
+        .details(serde_json::Value::Object(details))
     }
+}
 
-    // Start local HTTP server to receive callback
-    let listener = TcpListener::bind("127.0.0.1:8085").await?;
-    if human {
-        println!("⏳ Waiting for authentication callback on http://localhost:8085 ...\n");
+/// Find the local embedding model and time how long it takes to load
+fn doctor_check_embedding_model(config: &Config, quick: bool) -> DoctorCheck {
+    if config.search.remote_embeddings_enabled()
+        && config.search.embedding_fallback == EmbeddingFallback::Bm25
+    {
+        return DoctorCheck::new(
+            "embedding_model",
+            CheckStatus::Skipped,
+            "Not used (remote embeddings with BM25 fallback)",
+        );
     }
 
-    // Accept one connection with timeout
-    let callback_result = tokio::time::timeout(
-        std::time::Duration::from_secs(300),
-        async {
-            let (mut socket, _) = listener.accept().await?;
-            let mut reader = BufReader::new(&mut socket);
-            let mut request_line = String::new();
-            reader.read_line(&mut request_line).await?;
+    let model_type = EmbeddingModel::from_str(&config.search.embedding_model)
+        .unwrap_or(EmbeddingModel::BgeBaseEn);
+    let Some(model_dir) = EmbeddingEngine::find_cached_model(config.models_dir(), model_type)
+    else {
+        return DoctorCheck::new(
+            "embedding_model",
+            CheckStatus::Warn,
+            format!(
+                "{} is not downloaded yet, the next sync or search downloads it",
+                model_type.model_id()
+            ),
+        )
+        .fix("groundeffect email search \"test\"");
+    };
+    let precision = config.search.embedding_precision;
+    let details = serde_json::json!({
+        "model": model_type.model_id(),
+        "path": model_dir,
+        "precision": precision.as_str(),
+        "accuracy_note": precision.accuracy_note(),
+    });
 
-            // Parse callback
-            let (code, received_state) = parse_oauth_callback(&request_line)?;
+    if quick {
+        return DoctorCheck::new(
+            "embedding_model",
+            CheckStatus::Ok,
+            format!("{} found (not loaded, --quick)", model_type.model_id()),
+        )
+        .details(details);
+    }
 
-            if received_state != state {
-                let response = "HTTP/1.1 400 Bad Request\r\nContent-Type: text/html\r\n\r\n<h1>Error: Invalid state</h1>";
-                socket.write_all(response.as_bytes()).await?;
-                anyhow::bail!("OAuth state mismatch - possible CSRF attack");
-            }
+    let started = std::time::Instant::now();
+    match EmbeddingEngine::from_cache(
+        config.models_dir(),
+        model_type,
+        config.search.use_gpu,
+        config.search.embedding_precision,
+    ) {
+        Ok(engine) => {
+            let device = if engine.device().is_cpu() {
+                "CPU"
+            } else {
+                "GPU"
+            };
+            DoctorCheck::new(
+                "embedding_model",
+                CheckStatus::Ok,
+                format!(
+                    "{} loaded on {} ({})",
+                    model_type.model_id(),
+                    device,
+                    precision.as_str()
+                ),
+            )
+            .details(details)
+            .timed(started)
+        }
+        Err(e) => DoctorCheck::new(
+            "embedding_model",
+            CheckStatus::Fail,
+            format!("{} failed to load: {}", model_type.model_id(), e),
+        )
+        .fix(format!(
+            "Delete {:?} so the model is downloaded again",
+            model_dir
+        ))
+        .details(details)
+        .timed(started),
+    }
+}
 
-            // Send success response
-            let success_html = "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\n\r\n\
-                <!DOCTYPE html><html><body style='font-family: sans-serif; padding: 40px; text-align: center;'>\
-                <h1>Authentication Successful!</h1><p>You can close this window.</p></body></html>";
-            socket.write_all(success_html.as_bytes()).await?;
+/// Embed a test text with the remote provider and compare the vector width
+/// with the stored embedding column
+async fn doctor_check_embedding_api(
+    config: &Config,
+    db: Option<&Database>,
+    offline: bool,
+) -> DoctorCheck {
+    let Some(endpoint) = config.search.embedding_endpoint() else {
+        return DoctorCheck::new(
+            "embedding_api",
+            CheckStatus::Skipped,
+            "Not used (local embeddings)",
+        );
+    };
+    let provider = endpoint.provider.as_str();
+    let details = serde_json::json!({
+        "provider": provider,
+        "url": endpoint.url,
+        "model": endpoint.model,
+        "api_key_env": endpoint.api_key_env,
+    });
+    if offline {
+        return DoctorCheck::new(
+            "embedding_api",
+            CheckStatus::Skipped,
+            format!("{} not contacted (--offline)", provider),
+        )
+        .details(details);
+    }
 
-            Ok::<String, anyhow::Error>(code)
-        }
-    ).await;
+    let dimension = match db {
+        Some(db) => db.vector_dimension(VectorTable::Emails),
+        None => config
+            .search
+            .vector_dimension()
+            .unwrap_or(EMBEDDING_DIMENSION),
+    };
+    let started = std::time::Instant::now();
+    let probe = match HybridEmbeddingProvider::from_search_config(None, &config.search) {
+        Ok(embedding) => {
+            embedding
+                .with_vector_dimension(dimension)
+                .probe_remote()
+                .await
+        }
+        Err(e) => Some(Err(e)),
+    };
+    match probe {
+        Some(Ok(width)) => DoctorCheck::new(
+            "embedding_api",
+            CheckStatus::Ok,
+            format!(
+                "{} answered with {}-dimensional vectors from {}",
+                provider, width, endpoint.model
+            ),
+        )
+        .details(details)
+        .timed(started),
+        Some(Err(e)) => DoctorCheck::new(
+            "embedding_api",
+            CheckStatus::Fail,
+            format!("{} embedding failed: {}", provider, e),
+        )
+        .fix(format!(
+            "Check search.embedding_api in {:?} (URL, model, API key); searches use search.embedding_fallback meanwhile",
+            Config::config_path()
+        ))
+        .details(details)
+        .timed(started),
+        None => DoctorCheck::new(
+            "embedding_api",
+            CheckStatus::Fail,
+            format!("{} client could not be set up", provider),
+        )
+        .fix(match &endpoint.api_key_env {
+            Some(env_name) => format!("export {}=...", env_name),
+            None => format!("Check search.embedding_api in {:?}", Config::config_path()),
+        })
+        .details(details),
+    }
+}
 
-    let code = match callback_result {
-        Ok(Ok(code)) => code,
-        Ok(Err(e)) => {
-            if human {
-                println!("❌ OAuth error: {}", e);
+/// Find the Google OAuth client credentials in the environment or ~/.secrets
+fn doctor_check_oauth_credentials(has_accounts: bool) -> DoctorCheck {
+    const ID_VARS: [&str; 3] = [
+        "GROUNDEFFECT_CLIENT_ID",
+        "GROUNDEFFECT_GOOGLE_CLIENT_ID",
+        "GOOGLE_CLIENT_ID",
+    ];
+    const SECRET_VARS: [&str; 3] = [
+        "GROUNDEFFECT_CLIENT_SECRET",
+        "GROUNDEFFECT_GOOGLE_CLIENT_SECRET",
+        "GOOGLE_CLIENT_SECRET",
+    ];
+    let set = |var: &str| std::env::var(var).is_ok_and(|v| !v.trim().is_empty());
+    let env_id = ID_VARS.into_iter().find(|var| set(var));
+    let env_secret = SECRET_VARS.into_iter().find(|var| set(var));
+
+    // The launchd agent only sees credentials exported from ~/.secrets
+    let secrets_path = home_dir().join(".secrets");
+    let in_secrets = std::fs::read_to_string(&secrets_path).is_ok_and(|contents| {
+        let exported = |prefixes: &[&str]| {
+            contents.lines().any(|line| {
+                let line = line.trim();
+                prefixes
+                    .iter()
+                    .any(|var| line.starts_with(&format!("export {}=", var)))
+            })
+        };
+        exported(&ID_VARS[..2]) && exported(&SECRET_VARS[..2])
+    });
+
+    let fix = "Add 'export GROUNDEFFECT_GOOGLE_CLIENT_ID=...' and 'export GROUNDEFFECT_GOOGLE_CLIENT_SECRET=...' to ~/.secrets";
+    match (env_id, env_secret) {
+        (Some(id), Some(secret)) => {
+            let details = serde_json::json!({
+                "source": "environment",
+                "client_id_var": id,
+                "client_secret_var": secret,
+                "in_secrets_file": in_secrets,
+            });
+            if cfg!(target_os = "macos")
+                && DaemonConfig::launchd_plist_path().exists()
+                && !in_secrets
+            {
+                DoctorCheck::new(
+                    "oauth_credentials",
+                    CheckStatus::Warn,
+                    format!(
+                        "Set in this shell ({}, {}) but not in ~/.secrets, which is all the launchd daemon sees",
+                        id, secret
+                    ),
+                )
+                .fix(fix)
+                .details(details)
             } else {
-                println!("{{\"success\": false, \"error\": \"{}\"}}", e);
+                DoctorCheck::new(
+                    "oauth_credentials",
+                    CheckStatus::Ok,
+                    format!("Set in the environment ({}, {})", id, secret),
+                )
+                .details(details)
             }
-            return Ok(());
         }
-        Err(_) => {
-            if human {
-                println!("❌ OAuth timeout: no callback received within 5 minutes");
+        _ if in_secrets => {
+            DoctorCheck::new("oauth_credentials", CheckStatus::Ok, "Set in ~/.secrets")
+                .details(serde_json::json!({ "source": "secrets_file" }))
+        }
+        _ => {
+            // Without accounts nothing needs refreshing yet
+            let status = if has_accounts {
+                CheckStatus::Fail
             } else {
-                println!("{{\"success\": false, \"error\": \"OAuth timeout\"}}");
-            }
-            return Ok(());
+                CheckStatus::Warn
+            };
+            DoctorCheck::new(
+                "oauth_credentials",
+                status,
+                "Google OAuth client ID/secret not found in the environment or ~/.secrets",
+            )
+            .fix(fix)
         }
-    };
+    }
+}
 
-    if human {
-        println!("✅ Received authorization code, exchanging for tokens...\n");
+/// Compare the installed launchd agent with daemon.toml and the active profile
+fn doctor_check_launchd() -> DoctorCheck {
+    if !cfg!(target_os = "macos") {
+        return DoctorCheck::new(
+            "launchd",
+            CheckStatus::Skipped,
+            "launchd is only used on macOS",
+        );
     }
 
-    // Exchange code for tokens
-    let (tokens, user_info) = oauth.exchange_code(&code).await?;
+    let plist_path = DaemonConfig::launchd_plist_path();
+    let Ok(plist) = std::fs::read_to_string(&plist_path) else {
+        return DoctorCheck::new(
+            "launchd",
+            CheckStatus::Ok,
+            "Agent not installed, the daemon only runs when started manually",
+        )
+        .details(serde_json::json!({ "plist_path": plist_path, "installed": false }));
+    };
 
-    if user_info.email != email {
-        if human {
-            println!(
-                "❌ Authenticated as {}, expected {}",
-                user_info.email, email
-            );
-            println!("Please retry and select the correct Google account.");
-        } else {
-            println!(
-                "{{\"success\": false, \"error\": \"Authenticated as {} but expected {}\"}}",
-                user_info.email, email
-            );
+    let mut issues = Vec::new();
+    let label = DaemonConfig::launchd_label();
+    if !plist.contains(&format!("<string>{}</string>", label)) {
+        issues.push(format!("label is not {}", label));
+    }
+
+    let exec = regex::Regex::new(r"exec (\S+)").unwrap();
+    match exec.captures(&plist) {
+        Some(caps) if !Path::new(&caps[1]).exists() => {
+            issues.push(format!("daemon binary {} doesn't exist", &caps[1]));
         }
-        return Ok(());
+        Some(_) => {}
+        None => issues.push("no daemon command found".to_string()),
     }
 
-    token_provider.store_tokens(&email, &tokens).await?;
+    let profile_args = DaemonConfig::profile_args().join(" ");
+    if !profile_args.is_empty() && !plist.contains(&profile_args) {
+        issues.push(format!("daemon command doesn't pass '{}'", profile_args));
+    }
 
-    if let Some(mut existing) = db.get_account(&email).await? {
-        existing.status = AccountStatus::Active;
-        if let Some(name) = user_info.name {
-            if !name.trim().is_empty() {
-                existing.display_name = name;
+    let daemon_config = DaemonConfig::load().unwrap_or_default();
+    if plist.contains(" --log") != daemon_config.logging_enabled {
+        issues.push(format!(
+            "logging is {} in daemon.toml but not in the plist",
+            if daemon_config.logging_enabled {
+                "enabled"
+            } else {
+                "disabled"
             }
+        ));
+    }
+    let expected_env = [
+        (
+            "GROUNDEFFECT_EMAIL_POLL_INTERVAL",
+            daemon_config.email_poll_interval_secs.to_string(),
+        ),
+        (
+            "GROUNDEFFECT_CALENDAR_POLL_INTERVAL",
+            daemon_config.calendar_poll_interval_secs.to_string(),
+        ),
+        (
+            "GROUNDEFFECT_MAX_CONCURRENT_FETCHES",
+            daemon_config.max_concurrent_fetches.to_string(),
+        ),
+    ];
+    for (key, expected) in &expected_env {
+        let pattern = format!(r"<key>{}</key>\s*<string>([^<]*)</string>", key);
+        let actual = regex::Regex::new(&pattern)
+            .unwrap()
+            .captures(&plist)
+            .map(|caps| caps[1].to_string());
+        if actual.as_deref() != Some(expected.as_str()) {
+            issues.push(format!(
+                "{} is {} in the plist but {} in daemon.toml",
+                key,
+                actual.as_deref().unwrap_or("unset"),
+                expected
+            ));
         }
-        db.upsert_account(&existing).await?;
     }
 
-    if human {
-        println!("🎉 Re-authenticated account: {}", email);
+    let loaded = std::process::Command::new("launchctl")
+        .args(["list", &label])
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false);
+
+    let details = serde_json::json!({
+        "plist_path": plist_path,
+        "installed": true,
+        "loaded": loaded,
+        "issues": issues,
+    });
+    if !issues.is_empty() {
+        DoctorCheck::new(
+            "launchd",
+            CheckStatus::Warn,
+            format!("Plist out of date: {}", issues.join("; ")),
+        )
+        .fix("groundeffect daemon uninstall && groundeffect daemon install")
+        .details(details)
+    } else if !loaded {
+        DoctorCheck::new(
+            "launchd",
+            CheckStatus::Warn,
+            "Agent installed but not loaded, it won't start at login",
+        )
+        .fix(format!("launchctl load -w {}", plist_path.display()))
+        .details(details)
     } else {
-        println!(
-            "{{\"success\": true, \"account\": \"{}\", \"status\": \"active\"}}",
-            email
-        );
+        DoctorCheck::new("launchd", CheckStatus::Ok, "Agent installed and loaded").details(details)
     }
-
-    Ok(())
 }
 
-fn parse_oauth_callback(request_line: &str) -> Result<(String, String)> {
-    let parts: Vec<&str> = request_line.split_whitespace().collect();
-    if parts.len() < 2 {
-        anyhow::bail!("Invalid HTTP request");
+/// Check free space on the volume holding the data directory
+fn doctor_check_disk_space(data_dir: &Path) -> DoctorCheck {
+    // df needs an existing path; the data dir may not be created yet
+    let existing = data_dir
+        .ancestors()
+        .find(|p| p.exists())
+        .unwrap_or(Path::new("/"));
+    let available = std::process::Command::new("df")
+        .arg("-Pk")
+        .arg(existing)
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .and_then(|o| {
+            let stdout = String::from_utf8_lossy(&o.stdout).to_string();
+            let kb: u64 = stdout
+                .lines()
+                .nth(1)?
+                .split_whitespace()
+                .nth(3)?
+                .parse()
+                .ok()?;
+            Some(kb * 1024)
+        });
+
+    let Some(available) = available else {
+        return DoctorCheck::new(
+            "disk_space",
+            CheckStatus::Skipped,
+            "Couldn't determine free space (df failed)",
+        );
+    };
+
+    let message = format!(
+        "{} free on the volume holding {:?}",
+        format_bytes(available),
+        data_dir
+    );
+    let details = serde_json::json!({
+        "data_dir": data_dir,
+        "available_bytes": available,
+    });
+    let fix = "Free up disk space, or cap local storage with 'groundeffect account configure <account> --max-storage 5GB'";
+    if available < DISK_FAIL_BYTES {
+        DoctorCheck::new("disk_space", CheckStatus::Fail, message)
+            .fix(fix)
+            .details(details)
+    } else if available < DISK_WARN_BYTES {
+        DoctorCheck::new("disk_space", CheckStatus::Warn, message)
+            .fix(fix)
+            .details(details)
+    } else {
+        DoctorCheck::new("disk_space", CheckStatus::Ok, message).details(details)
     }
+}
 
-    let path = parts[1];
-    if !path.starts_with("/oauth/callback") {
-        anyhow::bail!("Unexpected callback path: {}", path);
+/// Check that the OAuth callback port is free for 'account add'
+fn doctor_check_callback_port() -> DoctorCheck {
+    match std::net::TcpListener::bind(("127.0.0.1", OAUTH_CALLBACK_PORT)) {
+        Ok(_) => DoctorCheck::new(
+            "oauth_callback_port",
+            CheckStatus::Ok,
+            format!("Port {} is free", OAUTH_CALLBACK_PORT),
+        ),
+        Err(e) => DoctorCheck::new(
+            "oauth_callback_port",
+            CheckStatus::Warn,
+            format!(
+                "Port {} is unavailable ({}), so 'account add' can't receive the OAuth callback",
+                OAUTH_CALLBACK_PORT, e
+            ),
+        )
+        .fix(format!(
+            "Stop the process shown by 'lsof -nP -iTCP:{} -sTCP:LISTEN', or use 'account add --device-code'",
+            OAUTH_CALLBACK_PORT
+        )),
     }
+}
 
-    let query_start = path
-        .find('?')
-        .ok_or_else(|| anyhow::anyhow!("No query string"))?;
-    let query = &path[query_start + 1..];
+// ============================================================================
+// Help Command Handlers
+// ============================================================================
 
-    let mut code = None;
-    let mut state = None;
+/// One searchable entry in the help index
+#[derive(Serialize)]
+struct HelpEntry {
+    /// "command", "flag" or "mcp_tool"
+    kind: &'static str,
+    name: String,
+    description: String,
+    usage: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    examples: Vec<String>,
+    score: f64,
+    /// Lowercased words from the name (and MCP parameter names)
+    #[serde(skip)]
+    name_words: Vec<String>,
+    /// Lowercased words from the description
+    #[serde(skip)]
+    description_words: Vec<String>,
+}
 
-    for param in query.split('&') {
-        let mut kv = param.splitn(2, '=');
-        let key = kv.next().unwrap_or("");
-        let value = kv.next().unwrap_or("");
-        match key {
-            "code" => code = Some(urlencoding::decode(value).unwrap_or_default().into_owned()),
-            "state" => state = Some(urlencoding::decode(value).unwrap_or_default().into_owned()),
-            _ => {}
+impl HelpEntry {
+    fn new(
+        kind: &'static str,
+        name: String,
+        description: String,
+        usage: String,
+        examples: Vec<String>,
+    ) -> Self {
+        Self {
+            kind,
+            name_words: help_words(&name),
+            description_words: help_words(&description),
+            name,
+            description,
+            usage,
+            examples,
+            score: 0.0,
         }
     }
-
-    let code = code.ok_or_else(|| anyhow::anyhow!("No authorization code in callback"))?;
-    let state = state.ok_or_else(|| anyhow::anyhow!("No state in callback"))?;
-
-    Ok((code, state))
 }
 
-async fn account_delete(account: &str, confirm: bool, human: bool) -> Result<()> {
-    if !confirm {
-        if human {
-            println!("❌ Must pass --confirm flag to delete an account.");
-            println!(
-                "\nThis will permanently delete all synced emails and events for this account."
-            );
-            println!("Example: groundeffect account delete {} --confirm", account);
-        } else {
-            println!("{{\"success\": false, \"error\": \"Must pass --confirm to delete\"}}");
+fn handle_help_command(command: Option<HelpCommands>, path: Vec<String>) -> Result<()> {
+    match command {
+        Some(HelpCommands::Search { words, limit, json }) => help_search(&words, limit, json),
+        None => {
+            let mut cli = Cli::command();
+            cli.build();
+            let mut target = &mut cli;
+            for name in &path {
+                target = target
+                    .find_subcommand_mut(name)
+                    .ok_or_else(|| anyhow::anyhow!("Unknown command: {}", path.join(" ")))?;
+            }
+            target.print_long_help()?;
+            Ok(())
         }
-        return Ok(());
     }
+}
 
-    let config = Config::load().unwrap_or_default();
-    let token_provider = create_token_provider(&config).await?;
-    let db = Database::open(config.lancedb_dir()).await?;
-    let accounts = db.list_accounts().await?;
-
-    // Resolve account
-    let email = accounts
-        .iter()
-        .find(|a| a.id == account || a.alias.as_ref() == Some(&account.to_string()))
-        .map(|a| a.id.clone());
-
-    match email {
-        Some(email) => {
-            // Delete synced data
-            let (email_count, event_count) = db.clear_account_sync_data(&email).await?;
+fn help_search(words: &[String], limit: usize, json: bool) -> Result<()> {
+    let query: Vec<String> = words.iter().flat_map(|w| help_words(w)).collect();
 
-            // Delete account
-            db.delete_account(&email).await?;
+    let mut results: Vec<HelpEntry> = build_help_index()
+        .into_iter()
+        .filter_map(|mut entry| {
+            entry.score = help_score(&entry, &query);
+            (entry.score > 0.0).then_some(entry)
+        })
+        .collect();
+    results.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.name.len().cmp(&b.name.len()))
+    });
+    results.truncate(limit);
 
-            // Delete tokens
-            if let Err(e) = token_provider.delete_tokens(&email).await {
-                if human {
-                    println!("Warning: Failed to delete tokens: {}", e);
-                }
-            }
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "query": words.join(" "),
+                "results": results,
+            }))?
+        );
+        return Ok(());
+    }
 
-            if human {
-                println!("✅ Account deleted: {}", email);
-                println!("   {} emails removed", email_count);
-                println!("   {} events removed", event_count);
-            } else {
-                println!("{{\"success\": true, \"deleted\": {{\"account\": \"{}\", \"emails\": {}, \"events\": {}}}}}",
-                    email, email_count, event_count);
-            }
+    if results.is_empty() {
+        println!(
+            "No commands, flags or MCP tools match '{}'.",
+            words.join(" ")
+        );
+        return Ok(());
+    }
+    for entry in &results {
+        println!("{}  ({})", entry.name, entry.kind.replace('_', " "));
+        if !entry.description.is_empty() {
+            println!("  {}", entry.description.lines().next().unwrap_or_default());
         }
-        None => {
-            if human {
-                println!("❌ Account not found: {}", account);
-            } else {
-                println!("{{\"success\": false, \"error\": \"Account not found\"}}");
-            }
+        println!("  Usage: {}", entry.usage);
+        for example in entry.examples.iter().take(2) {
+            println!("  Example: {}", example);
         }
+        println!();
     }
-
     Ok(())
 }
 
-async fn account_configure(
-    account: &str,
-    alias: Option<String>,
-    attachments: bool,
-    no_attachments: bool,
-    human: bool,
-) -> Result<()> {
-    let config = Config::load().unwrap_or_default();
-    let db = Database::open(config.lancedb_dir()).await?;
-    let accounts = db.list_accounts().await?;
-
-    // Resolve account
-    let email = accounts
-        .iter()
-        .find(|a| a.id == account || a.alias.as_ref() == Some(&account.to_string()))
-        .map(|a| a.id.clone());
-
-    match email {
-        Some(email) => {
-            let mut acct = db.get_account(&email).await?.unwrap();
-            let mut changes = vec![];
-
-            // Update alias
-            if let Some(new_alias) = alias {
-                if new_alias.is_empty() {
-                    if acct.alias.is_some() {
-                        acct.alias = None;
-                        changes.push("alias removed".to_string());
-                    }
-                } else {
-                    acct.alias = Some(new_alias.clone());
-                    changes.push(format!("alias set to '{}'", new_alias));
+/// Index every subcommand, flag and MCP tool
+fn build_help_index() -> Vec<HelpEntry> {
+    let mut cli = Cli::command();
+    cli.build();
+
+    let mut entries = Vec::new();
+    for arg in cli.get_arguments().filter(|a| a.is_global_set()) {
+        if let Some(long) = arg.get_long() {
+            let usage = format!("groundeffect --{}{} <command>", long, help_value_hint(arg));
+            let description = arg.get_help().map(|h| h.to_string()).unwrap_or_default();
+            entries.push(HelpEntry::new(
+                "flag",
+                format!("--{}", long),
+                description,
+                usage,
+                Vec::new(),
+            ));
+        }
+    }
+    collect_command_help(&mut cli, &[], &mut entries);
+
+    for tool in get_tool_definitions() {
+        let properties = tool.input_schema["properties"].as_object().cloned();
+        let required: Vec<String> = tool.input_schema["required"]
+            .as_array()
+            .map(|r| {
+                r.iter()
+                    .filter_map(|v| v.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let arguments: serde_json::Map<String, serde_json::Value> = required
+            .iter()
+            .map(|name| {
+                let kind = properties
+                    .as_ref()
+                    .and_then(|p| p.get(name))
+                    .and_then(|schema| schema["type"].as_str())
+                    .unwrap_or("value");
+                (name.clone(), serde_json::json!(format!("<{}>", kind)))
+            })
+            .collect();
+        let usage = serde_json::json!({"name": tool.name, "arguments": arguments}).to_string();
+
+        let mut entry = HelpEntry::new("mcp_tool", tool.name, tool.description, usage, Vec::new());
+        if let Some(properties) = &properties {
+            for (param, schema) in properties {
+                entry.name_words.extend(help_words(param));
+                if let Some(description) = schema["description"].as_str() {
+                    entry.description_words.extend(help_words(description));
                 }
             }
+        }
+        entries.push(entry);
+    }
+    entries
+}
 
-            // Update attachments
-            if attachments && !no_attachments {
-                if !acct.sync_attachments {
-                    acct.sync_attachments = true;
-                    changes.push("sync_attachments enabled".to_string());
-                }
-            } else if no_attachments && !attachments {
-                if acct.sync_attachments {
-                    acct.sync_attachments = false;
-                    changes.push("sync_attachments disabled".to_string());
-                }
+fn collect_command_help(cmd: &mut clap::Command, path: &[String], entries: &mut Vec<HelpEntry>) {
+    for sub in cmd.get_subcommands_mut() {
+        let mut sub_path = path.to_vec();
+        sub_path.push(sub.get_name().to_string());
+        let name = sub_path.join(" ");
+
+        let description = sub.get_about().map(|a| a.to_string()).unwrap_or_default();
+        let examples: Vec<String> = sub
+            .get_long_about()
+            .map(|a| a.to_string())
+            .unwrap_or_default()
+            .lines()
+            .map(str::trim)
+            .filter(|line| line.starts_with("groundeffect "))
+            .map(String::from)
+            .collect();
+        let usage = sub.render_usage().to_string();
+        let usage = usage.trim_start_matches("Usage:").trim().to_string();
+
+        // Per-command --human duplicates the global flag
+        for arg in sub.get_arguments() {
+            if arg.is_global_set() || arg.is_hide_set() {
+                continue;
+            }
+            let Some(long) = arg.get_long() else {
+                continue;
+            };
+            if matches!(long, "help" | "version" | "human") {
+                continue;
             }
+            let flag = format!("--{}", long);
+            entries.push(HelpEntry::new(
+                "flag",
+                format!("{} {}", name, flag),
+                arg.get_help().map(|h| h.to_string()).unwrap_or_default(),
+                format!("groundeffect {} {}{}", name, flag, help_value_hint(arg)),
+                examples
+                    .iter()
+                    .filter(|e| e.split_whitespace().any(|w| w == flag))
+                    .cloned()
+                    .collect(),
+            ));
+        }
 
-            if changes.is_empty() {
-                if human {
-                    println!("No changes specified.");
-                    println!("\nCurrent settings:");
-                    println!("  Alias: {}", acct.alias.as_deref().unwrap_or("(none)"));
-                    println!("  Sync attachments: {}", acct.sync_attachments);
-                } else {
-                    println!("{{\"success\": true, \"changes\": [], \"account\": {{\"id\": \"{}\", \"alias\": {}, \"sync_attachments\": {}}}}}",
-                        acct.id,
-                        acct.alias.as_ref().map(|a| format!("\"{}\"", a)).unwrap_or("null".to_string()),
-                        acct.sync_attachments);
-                }
-            } else {
-                db.upsert_account(&acct).await?;
+        entries.push(HelpEntry::new(
+            "command",
+            name,
+            description,
+            usage,
+            examples,
+        ));
+        collect_command_help(sub, &sub_path, entries);
+    }
+}
 
-                if human {
-                    println!("✅ Account configured: {}", email);
-                    for change in &changes {
-                        println!("   - {}", change);
-                    }
-                    if changes.iter().any(|c| c.contains("sync_attachments")) {
-                        println!("\nRestart the daemon for attachment changes to take effect.");
-                    }
-                } else {
-                    println!("{{\"success\": true, \"changes\": {:?}, \"account\": {{\"id\": \"{}\", \"alias\": {}, \"sync_attachments\": {}}}}}",
-                        changes,
-                        acct.id,
-                        acct.alias.as_ref().map(|a| format!("\"{}\"", a)).unwrap_or("null".to_string()),
-                        acct.sync_attachments);
-                }
-            }
+/// " <VALUE>" for flags that take a value
+fn help_value_hint(arg: &clap::Arg) -> String {
+    if !arg.get_action().takes_values() {
+        return String::new();
+    }
+    let name = arg
+        .get_value_names()
+        .and_then(|names| names.first())
+        .map(|n| n.to_string())
+        .unwrap_or_else(|| arg.get_id().as_str().to_uppercase());
+    format!(" <{}>", name)
+}
+
+/// Lowercased alphanumeric words
+fn help_words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect()
+}
+
+/// Score an entry: each query word counts its best match against the name
+/// (weighted 3x) or the description. Exact beats prefix beats substring
+/// beats a near-miss spelling.
+fn help_score(entry: &HelpEntry, query: &[String]) -> f64 {
+    let mut score = 0.0;
+    let mut matched = 0;
+    for word in query {
+        let in_name = best_word_match(word, &entry.name_words) * 3.0;
+        let in_description = best_word_match(word, &entry.description_words);
+        let best = in_name.max(in_description);
+        if best > 0.0 {
+            matched += 1;
+            score += best;
         }
-        None => {
-            if human {
-                println!("❌ Account not found: {}", account);
+    }
+    // Require most of the query to match so long queries stay precise
+    if matched * 2 < query.len() {
+        return 0.0;
+    }
+    if entry.kind == "command" {
+        score += 0.1;
+    }
+    score
+}
+
+fn best_word_match(word: &str, candidates: &[String]) -> f64 {
+    candidates
+        .iter()
+        .map(|candidate| {
+            if candidate == word {
+                1.0
+            } else if word.len() >= 2 && candidate.starts_with(word) {
+                0.8
+            } else if word.len() >= 3 && candidate.contains(word) {
+                0.6
+            } else if word.len() >= 4 && edit_distance(word, candidate) <= word.len() / 4 {
+                0.5
             } else {
-                println!("{{\"success\": false, \"error\": \"Account not found\"}}");
+                0.0
             }
-        }
-    }
+        })
+        .fold(0.0, f64::max)
+}
 
-    Ok(())
+/// Levenshtein distance between two words
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(prev[j + 1] + 1).min(current[j] + 1);
+        }
+        prev = current;
+    }
+    prev[b.len()]
 }
 
 // ============================================================================
 // Config Command Handlers
 // ============================================================================
 
-async fn handle_config_command(command: ConfigCommands) -> Result<()> {
+async fn handle_config_command(
+    command: ConfigCommands,
+    global_human: bool,
+    global_dry_run: bool,
+) -> Result<()> {
     match command {
         ConfigCommands::AddPermissions => config_add_permissions().await,
         ConfigCommands::RemovePermissions => config_remove_permissions().await,
+        ConfigCommands::Capabilities { human } => config_capabilities(human),
+        ConfigCommands::Show { origin, human } => config_show(origin, human || global_human),
+        ConfigCommands::Validate { human } => config_validate(human || global_human),
+        ConfigCommands::Relocate {
+            data_dir,
+            dry_run,
+            human,
+        } => config_relocate(&data_dir, dry_run || global_dry_run, human || global_human).await,
         ConfigCommands::Settings {
             logging,
             email_interval,
@@ -3730,13 +12232,96 @@ async fn handle_config_command(command: ConfigCommands) -> Result<()> {
     }
 }
 
+/// Move the data directory and point the config and stored attachment
+/// paths at the new location
+async fn config_relocate(data_dir: &Path, dry_run: bool, human: bool) -> Result<()> {
+    let mut config = Config::load_file()?;
+    let from = config.general.data_dir.clone();
+    let mut to = expand_home(data_dir);
+    if to.is_relative() {
+        to = std::env::current_dir()?.join(to);
+    }
+
+    if !dry_run && check_daemon_running() {
+        anyhow::bail!(
+            "The daemon is running; stop it first with 'groundeffect daemon stop', then relocate"
+        );
+    }
+    relocate::validate(&from, &to)?;
+    let summary = relocate::summarize(&from)?;
+
+    let (method, attachments_updated) = if dry_run {
+        (None, 0)
+    } else {
+        let method = relocate::move_dir(&from, &to)?;
+        config.general.data_dir = to.clone();
+        if let Some(log_file) = relocate::rebase(&config.general.log_file, &from, &to) {
+            config.general.log_file = log_file;
+        }
+        config.save()?;
+
+        let db = Database::open(config.lancedb_dir()).await?;
+        let updated = db.rebase_attachment_paths(&from, &to).await?;
+        (Some(method), updated)
+    };
+    let daemon_installed =
+        Config::launchd_plist_path().exists() || Config::systemd_unit_path().exists();
+
+    if human {
+        if dry_run {
+            println!(
+                "Would move {} files ({}) from {} to {}",
+                summary.files,
+                format_bytes(summary.bytes),
+                from.display(),
+                to.display()
+            );
+        } else {
+            let how = match method {
+                Some(relocate::MoveMethod::Copy) => "copied across volumes, original removed",
+                _ => "renamed",
+            };
+            println!(
+                "✅ Moved {} files ({}) to {} ({})",
+                summary.files,
+                format_bytes(summary.bytes),
+                to.display(),
+                how
+            );
+            println!(
+                "   Updated data_dir in {} and {} attachment paths",
+                Config::config_path().display(),
+                attachments_updated
+            );
+            if daemon_installed {
+                println!(
+                    "   Run 'groundeffect daemon install' so the daemon logs to the new location"
+                );
+            }
+        }
+    } else {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "success": true,
+                "from": from,
+                "to": to,
+                "files": summary.files,
+                "bytes": summary.bytes,
+                "method": method,
+                "attachments_updated": attachments_updated,
+                "reinstall_daemon": !dry_run && daemon_installed,
+                "dry_run": dry_run,
+            }))?
+        );
+    }
+    Ok(())
+}
+
 async fn config_add_permissions() -> Result<()> {
     use std::fs;
-    use std::path::PathBuf;
 
-    let home =
-        std::env::var("HOME").map_err(|_| anyhow::anyhow!("HOME environment variable not set"))?;
-    let home_path = PathBuf::from(&home);
+    let home_path = home_dir();
     let settings_path = home_path.join(".claude").join("settings.json");
 
     let permission = "Bash(groundeffect:*)";
@@ -3837,11 +12422,8 @@ async fn config_add_permissions() -> Result<()> {
 
 async fn config_remove_permissions() -> Result<()> {
     use std::fs;
-    use std::path::PathBuf;
 
-    let home =
-        std::env::var("HOME").map_err(|_| anyhow::anyhow!("HOME environment variable not set"))?;
-    let settings_path = PathBuf::from(&home).join(".claude").join("settings.json");
+    let settings_path = home_dir().join(".claude").join("settings.json");
 
     if !settings_path.exists() {
         println!("No Claude Code settings file found");
@@ -3883,6 +12465,12 @@ async fn config_remove_permissions() -> Result<()> {
 // Email Send/Attachment Functions
 // ============================================================================
 
+/// Emails being forwarded, with the attachments to send on
+struct Forward {
+    emails: Vec<Email>,
+    attachments: Vec<OutgoingAttachment>,
+}
+
 async fn email_send(
     from: &str,
     to: Vec<String>,
@@ -3891,13 +12479,18 @@ async fn email_send(
     cc: Option<Vec<String>>,
     bcc: Option<Vec<String>>,
     reply_to: Option<String>,
+    send_as: Option<String>,
     force_html: bool,
+    signature: &SignatureChoice,
+    forward: Option<&Forward>,
     save_as_draft: bool,
     confirm: bool,
+    strict_lint: bool,
     human: bool,
 ) -> Result<()> {
     let config = Config::load().unwrap_or_default();
     let token_provider = create_token_provider(&config).await?;
+    let oauth = OAuthManager::new(token_provider);
     let db = Database::open(config.lancedb_dir()).await?;
     let accounts = db.list_accounts().await?;
 
@@ -3907,9 +12500,42 @@ async fn email_send(
         .find(|a| a.id == from || a.alias.as_ref() == Some(&from.to_string()))
         .ok_or_else(|| anyhow::anyhow!("Account not found: {}", from))?;
     let from_email = &account.id;
-    let display_name = &account.display_name;
 
-    let cc_list = cc.unwrap_or_default();
+    // Send-as identity and its configured defaults
+    let sender = send_as.unwrap_or_else(|| from_email.clone());
+    let identity = config
+        .compose
+        .identity(&sender)
+        .cloned()
+        .unwrap_or_default();
+    let display_name = identity
+        .display_name
+        .as_deref()
+        .unwrap_or(&account.display_name);
+    let signature =
+        resolve_signature(&config.compose, &oauth, from_email, &sender, signature).await?;
+
+    // Detect if HTML formatting is needed (from what was written, not what's forwarded)
+    let is_html = force_html || detect_html_content(body);
+
+    // A forward's comment is signed above the forwarded emails
+    let (body, signature) = match forward {
+        Some(forward) => {
+            let tz: Tz = config.general.timezone.parse().unwrap_or(Tz::UTC);
+            let comment = signature
+                .as_ref()
+                .map_or_else(|| body.to_string(), |s| s.append_text(body));
+            (
+                forward_body(comment.trim_start(), &forward.emails, tz),
+                None,
+            )
+        }
+        None => (body.to_string(), signature),
+    };
+    let body = body.as_str();
+    let attachments = forward.map_or(&[][..], |f| &f.attachments[..]);
+
+    let cc_list = identity.merge_cc(&to, &cc.unwrap_or_default());
     let bcc_list = bcc.unwrap_or_default();
 
     // Build reply headers if replying
@@ -3927,15 +12553,48 @@ async fn email_send(
         }
     }
 
-    // Detect if HTML formatting is needed
-    let is_html = force_html || detect_html_content(body);
+    // Lint the message parts exactly as build_email_message will render them
+    let lint_issues = if config.compose.lint_enabled {
+        let (plain_part, html_part) = message_parts(body, is_html, signature.as_ref());
+        lint_outgoing(
+            &final_subject,
+            &plain_part,
+            html_part.as_deref(),
+            &config.compose,
+        )
+    } else {
+        Vec::new()
+    };
+    let block_on_lint = strict_lint || config.compose.block_on_severe_lint;
+    let signed_body = signature
+        .as_ref()
+        .map_or_else(|| body.to_string(), |s| s.append_text(body));
 
     // If not confirmed and not saving as draft, return preview
     if !confirm && !save_as_draft {
+        // A forward's attachments aren't loaded yet, so list what sync recorded
+        let listed: Vec<serde_json::Value> = match forward {
+            Some(forward) => forward
+                .emails
+                .iter()
+                .flat_map(|e| &e.attachments)
+                .map(|a| {
+                    serde_json::json!({
+                        "filename": a.filename,
+                        "mime_type": a.mime_type,
+                        "size": a.size,
+                    })
+                })
+                .collect(),
+            None => attachment_summary(attachments),
+        };
         if human {
             println!("\n📧 Email Preview (NOT SENT)");
             println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-            println!("From: {} <{}>", display_name, from_email);
+            println!("From: {} <{}>", display_name, sender);
+            if let Some(addr) = &identity.reply_to {
+                println!("Reply-To: {}", addr);
+            }
             println!("To: {}", to.join(", "));
             if !cc_list.is_empty() {
                 println!("CC: {}", cc_list.join(", "));
@@ -3950,8 +12609,29 @@ async fn email_send(
             if in_reply_to.is_some() {
                 println!("(Reply to message)");
             }
-            println!("\n{}", body);
+            for attachment in &listed {
+                println!(
+                    "📎 {} ({})",
+                    attachment["filename"].as_str().unwrap_or_default(),
+                    format_bytes(attachment["size"].as_u64().unwrap_or(0))
+                );
+            }
+            println!("\n{}", signed_body);
             println!("\n━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+            if !lint_issues.is_empty() {
+                println!("Formatting lint:");
+                for issue in &lint_issues {
+                    let icon = match issue.severity {
+                        LintSeverity::Severe => "❌",
+                        LintSeverity::Warning => "⚠️",
+                    };
+                    println!("  {} {}", icon, issue.message);
+                }
+                if block_on_lint && has_severe(&lint_issues) {
+                    println!("Severe issues must be fixed before this email can be sent.");
+                }
+                println!();
+            }
             println!("To send: add --confirm | To save as draft: add --save-as-draft");
         } else {
             println!(
@@ -3960,16 +12640,42 @@ async fn email_send(
                     "status": "preview",
                     "message": "Add --confirm to send, or --save-as-draft to save as draft",
                     "email": {
-                        "from": format!("{} <{}>", display_name, from_email),
+                        "from": format!("{} <{}>", display_name, sender),
                         "to": to,
                         "cc": cc_list,
                         "bcc": bcc_list,
                         "subject": final_subject,
-                        "body": body,
+                        "body": signed_body,
                         "is_html": is_html,
                         "in_reply_to": in_reply_to,
                         "references": references,
-                    }
+                        "reply_to": identity.reply_to,
+                        "attachments": listed,
+                    },
+                    "lint": lint_issues,
+                    "lint_blocking": block_on_lint && has_severe(&lint_issues),
+                }))?
+            );
+        }
+        return Ok(());
+    }
+
+    if block_on_lint && has_severe(&lint_issues) {
+        if human {
+            println!("❌ Not sent: the formatting lint found severe issues:");
+            for issue in lint_issues
+                .iter()
+                .filter(|i| i.severity == LintSeverity::Severe)
+            {
+                println!("   - {}", issue.message);
+            }
+        } else {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "blocked",
+                    "message": "Formatting lint found severe issues; fix them or drop --strict-lint",
+                    "lint": lint_issues,
                 }))?
             );
         }
@@ -3979,22 +12685,24 @@ async fn email_send(
     // Build RFC 2822 message with HTML support
     let message = build_email_message(
         display_name,
-        from_email,
+        &sender,
         &to,
         &cc_list,
         &bcc_list,
         &final_subject,
         body,
         is_html,
+        signature.as_ref(),
         in_reply_to.as_deref(),
         references.as_deref(),
+        identity.reply_to.as_deref(),
+        attachments,
     );
 
     // Base64url encode the message
     let encoded = URL_SAFE_NO_PAD.encode(message.as_bytes());
 
     // Get access token
-    let oauth = OAuthManager::new(token_provider);
     let access_token = oauth.get_valid_token(from_email).await?;
     let client = reqwest::Client::new();
 
@@ -4027,6 +12735,24 @@ async fn email_send(
         let result: serde_json::Value = response.json().await?;
         let draft_id = result["id"].as_str().unwrap_or("unknown");
         let message_id = result["message"]["id"].as_str().unwrap_or("unknown");
+        record_audit(
+            &db,
+            "create_draft",
+            Some(from_email),
+            serde_json::json!({
+                "from": sender,
+                "to": to,
+                "cc": cc_list,
+                "bcc": bcc_list,
+                "subject": final_subject,
+                "body": body,
+                "reply_to": reply_to,
+                "forwarded": forward.map(|f| f.emails.iter().map(|e| &e.id).collect::<Vec<_>>()),
+                "attachments": attachment_summary(attachments),
+                "draft_id": draft_id,
+            }),
+        )
+        .await;
 
         if human {
             println!("✅ Draft created successfully!");
@@ -4044,7 +12770,7 @@ async fn email_send(
                     "status": "draft_created",
                     "draft_id": draft_id,
                     "message_id": message_id,
-                    "from": format!("{} <{}>", display_name, from_email),
+                    "from": format!("{} <{}>", display_name, sender),
                     "to": to,
                     "subject": final_subject,
                 }))?
@@ -4080,6 +12806,24 @@ async fn email_send(
 
     let result: serde_json::Value = response.json().await?;
     let message_id = result["id"].as_str().unwrap_or("unknown");
+    record_audit(
+        &db,
+        "send_email",
+        Some(from_email),
+        serde_json::json!({
+            "from": sender,
+            "to": to,
+            "cc": cc_list,
+            "bcc": bcc_list,
+            "subject": final_subject,
+            "body": body,
+            "reply_to": reply_to,
+            "forwarded": forward.map(|f| f.emails.iter().map(|e| &e.id).collect::<Vec<_>>()),
+            "attachments": attachment_summary(attachments),
+            "message_id": message_id,
+        }),
+    )
+    .await;
 
     if human {
         println!("✅ Email sent successfully!");
@@ -4092,7 +12836,7 @@ async fn email_send(
             serde_json::to_string_pretty(&serde_json::json!({
                 "status": "sent",
                 "message_id": message_id,
-                "from": format!("{} <{}>", display_name, from_email),
+                "from": format!("{} <{}>", display_name, sender),
                 "to": to,
                 "subject": final_subject,
             }))?
@@ -4102,6 +12846,20 @@ async fn email_send(
     Ok(())
 }
 
+/// Filenames, types and sizes of outgoing attachments
+fn attachment_summary(attachments: &[OutgoingAttachment]) -> Vec<serde_json::Value> {
+    attachments
+        .iter()
+        .map(|a| {
+            serde_json::json!({
+                "filename": a.filename,
+                "mime_type": a.mime_type,
+                "size": a.data.len(),
+            })
+        })
+        .collect()
+}
+
 async fn email_attachment(
     email_id: &str,
     filename: Option<&str>,
@@ -4265,18 +13023,41 @@ async fn email_attachment(
 // Calendar Create Function
 // ============================================================================
 
+#[allow(clippy::too_many_arguments)]
 async fn calendar_create(
     account: &str,
     summary: &str,
     start: &str,
-    end: &str,
+    end: Option<&str>,
+    all_day: bool,
     description: Option<&str>,
     location: Option<&str>,
     attendees: Option<Vec<String>>,
     calendar_id: &str,
+    timezone: Option<&str>,
+    recurrence: &Recurrence,
+    with_meet: bool,
+    options: &EventOptions,
+    force: bool,
     human: bool,
 ) -> Result<()> {
     let config = Config::load().unwrap_or_default();
+    let timezone = timezone.unwrap_or(&config.general.timezone);
+    let Ok(tz) = timezone.parse::<Tz>() else {
+        anyhow::bail!(
+            "Unknown timezone '{}' (use an IANA name like America/New_York)",
+            timezone
+        );
+    };
+    let Some(when) = NewEventTime::parse(start, end, all_day, tz) else {
+        if all_day {
+            anyhow::bail!(
+                "--all-day needs --start and --end as YYYY-MM-DD, with --end on or after --start"
+            );
+        }
+        anyhow::bail!("--start and --end must be ISO 8601 times (YYYY-MM-DDTHH:MM:SS), with --end after --start");
+    };
+    let rrule = recurrence.to_rrule(&when)?;
     let token_provider = create_token_provider(&config).await?;
     let db = Database::open(config.lancedb_dir()).await?;
     let accounts = db.list_accounts().await?;
@@ -4288,19 +13069,50 @@ async fn calendar_create(
         .map(|a| a.id.clone())
         .ok_or_else(|| anyhow::anyhow!("Account not found: {}", account))?;
 
+    // Check for overlapping events before touching the API
+    let conflicts = match when.interval(tz) {
+        Some((start, end)) => find_conflicts(&db, &config, start, end).await?,
+        None => Vec::new(),
+    };
+    let local = |at: DateTime<Utc>| at.with_timezone(&tz).format("%Y-%m-%d %H:%M").to_string();
+    if !conflicts.is_empty() && !force {
+        if human {
+            println!("⚠️  Not created: overlaps {} event(s)", conflicts.len());
+            for conflict in &conflicts {
+                println!(
+                    "   {} ({} to {}, {})",
+                    conflict.summary,
+                    local(conflict.start),
+                    local(conflict.end),
+                    conflict.account_id
+                );
+            }
+            println!("   Use --force to create it anyway.");
+        } else {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "success": false,
+                    "error": "Event overlaps existing events; use --force to create it anyway",
+                    "conflicts": conflicts,
+                }))?
+            );
+        }
+        return Ok(());
+    }
+
     // Build event body
+    let (start_body, end_body) = when.to_api();
     let mut event_body = serde_json::json!({
         "summary": summary,
-        "start": {
-            "dateTime": start,
-            "timeZone": "UTC"
-        },
-        "end": {
-            "dateTime": end,
-            "timeZone": "UTC"
-        }
+        "start": start_body,
+        "end": end_body
     });
 
+    if let Some(rule) = &rrule {
+        event_body["recurrence"] = serde_json::json!([rule]);
+    }
+
     if let Some(desc) = description {
         event_body["description"] = serde_json::json!(desc);
     }
@@ -4309,23 +13121,51 @@ async fn calendar_create(
         event_body["location"] = serde_json::json!(loc);
     }
 
-    if let Some(att) = &attendees {
-        if !att.is_empty() {
-            event_body["attendees"] = serde_json::json!(att
-                .iter()
-                .map(|email| serde_json::json!({"email": email}))
-                .collect::<Vec<_>>());
+    let oauth = OAuthManager::new(token_provider);
+
+    // Resolve attendee names through the Workspace directory
+    let attendees = match resolve_attendees(
+        &oauth,
+        &config,
+        &account_email,
+        &attendees.unwrap_or_default(),
+    )
+    .await
+    {
+        Ok(attendees) => attendees,
+        Err(e) => {
+            if human {
+                println!("❌ {}", e);
+            } else {
+                println!(
+                    "{}",
+                    serde_json::json!({"success": false, "error": e.to_string()})
+                );
+            }
+            return Ok(());
         }
+    };
+
+    if !attendees.is_empty() {
+        event_body["attendees"] = serde_json::json!(attendees
+            .iter()
+            .map(|email| serde_json::json!({"email": email}))
+            .collect::<Vec<_>>());
+    }
+
+    if with_meet {
+        event_body["conferenceData"] = meet_request();
     }
 
+    options.apply(&mut event_body);
+
     // Get access token
-    let oauth = OAuthManager::new(token_provider);
     let access_token = oauth.get_valid_token(&account_email).await?;
 
     // Create event via Google Calendar API
     let client = reqwest::Client::new();
     let url = format!(
-        "https://www.googleapis.com/calendar/v3/calendars/{}/events",
+        "https://www.googleapis.com/calendar/v3/calendars/{}/events?conferenceDataVersion=1",
         urlencoding::encode(calendar_id)
     );
 
@@ -4342,27 +13182,176 @@ async fn calendar_create(
         if human {
             println!("❌ Failed to create event: {} - {}", status, error_body);
         } else {
-            println!(
-                "{{\"success\": false, \"error\": \"{} - {}\"}}",
-                status,
-                error_body.replace('"', "\\\"")
-            );
+            println!(
+                "{{\"success\": false, \"error\": \"{} - {}\"}}",
+                status,
+                error_body.replace('"', "\\\"")
+            );
+        }
+        return Ok(());
+    }
+
+    let created_event: serde_json::Value = response.json().await?;
+    let event_id = created_event["id"].as_str().unwrap_or("unknown");
+    let html_link = created_event["htmlLink"].as_str();
+    let conference = Conference::from_google(&created_event);
+    let (start, end) = when.event_times();
+    record_audit(
+        &db,
+        "create_event",
+        Some(&account_email),
+        serde_json::json!({
+            "summary": summary,
+            "start": format_event_time(&start),
+            "end": format_event_time(&end),
+            "all_day": all_day,
+            "timezone": tz.name(),
+            "recurrence": rrule,
+            "description": description,
+            "location": location,
+            "attendees": attendees,
+            "calendar_id": calendar_id,
+            "with_meet": with_meet,
+            "color": event_body["colorId"],
+            "visibility": event_body["visibility"],
+            "transparency": event_body["transparency"],
+            "reminders": event_body["reminders"],
+            "event_id": event_id,
+        }),
+    )
+    .await;
+
+    if human {
+        println!("✅ Event created successfully!");
+        println!("   Title: {}", summary);
+        println!(
+            "   When: {} ({})",
+            format_event_span_human(&start, &end, tz),
+            tz
+        );
+        if let Some(rule) = &rrule {
+            println!("   Repeats: {}", rule.trim_start_matches("RRULE:"));
+        }
+        if let Some(loc) = location {
+            println!("   Location: {}", loc);
+        }
+        if !attendees.is_empty() {
+            println!("   Attendees: {}", attendees.join(", "));
+        }
+        if let Some(conference) = &conference {
+            println!("   Join: {}", format_conference_human(conference));
+        } else if with_meet {
+            println!(
+                "   Join: Meet link still being set up; see `calendar show` after the next sync"
+            );
+        }
+        print_event_options_human(options);
+        if let Some(link) = html_link {
+            println!("   Link: {}", link);
+        }
+        if !conflicts.is_empty() {
+            println!("   Overlaps: {}", conflicts.len());
+        }
+    } else {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "success": true,
+                "conflicts": conflicts,
+                "event": {
+                    "id": event_id,
+                    "summary": summary,
+                    "start": format_event_time(&start),
+                    "end": format_event_time(&end),
+                    "all_day": all_day,
+                    "start_local": start.to_local_string(tz),
+                    "end_local": end.to_local_string(tz),
+                    "timezone": tz.name(),
+                    "recurrence": rrule,
+                    "calendar_id": calendar_id,
+                    "account": account_email,
+                    "attendees": attendees,
+                    "conference": conference,
+                    "show_as": created_event["transparency"]
+                        .as_str()
+                        .and_then(Transparency::parse)
+                        .unwrap_or_default()
+                        .show_as(),
+                    "visibility": created_event["visibility"].as_str().unwrap_or("default"),
+                    "color": created_event["colorId"].as_str().and_then(event_color_name),
+                    "reminders": created_event["reminders"],
+                    "html_link": html_link
+                }
+            }))?
+        );
+    }
+
+    Ok(())
+}
+
+async fn calendar_update(
+    id: &str,
+    summary: Option<&str>,
+    description: Option<&str>,
+    location: Option<&str>,
+    options: &EventOptions,
+    human: bool,
+) -> Result<()> {
+    let config = Config::load().unwrap_or_default();
+    let db = Database::open(config.lancedb_dir()).await?;
+    let Some(event) = db.get_event(id).await? else {
+        if human {
+            println!("Event not found: {}", id);
+        } else {
+            println!("{{\"success\": false, \"error\": \"Event not found\"}}");
         }
         return Ok(());
+    };
+
+    let mut changes = serde_json::json!({});
+    if let Some(summary) = summary {
+        changes["summary"] = serde_json::json!(summary);
+    }
+    if let Some(description) = description {
+        changes["description"] = serde_json::json!(description);
+    }
+    if let Some(location) = location {
+        changes["location"] = serde_json::json!(location);
+    }
+    options.apply(&mut changes);
+    if changes.as_object().is_some_and(|c| c.is_empty()) {
+        anyhow::bail!("Nothing to change: pass --summary, --description, --location, --color, --visibility, --show-as, --reminder or --no-reminders");
     }
 
-    let created_event: serde_json::Value = response.json().await?;
-    let event_id = created_event["id"].as_str().unwrap_or("unknown");
-    let html_link = created_event["htmlLink"].as_str();
+    let token_provider = create_token_provider(&config).await?;
+    let oauth = OAuthManager::new(token_provider);
+    let result = patch_event(&oauth, &event, &changes).await;
+    let mut audit = changes.clone();
+    audit["event_id"] = serde_json::json!(event.id);
+    record_audit(&db, "update_event", Some(&event.account_id), audit).await;
+    let updated = match result {
+        Ok(updated) => updated,
+        Err(e) => {
+            if human {
+                println!("❌ {}", e);
+            } else {
+                println!(
+                    "{}",
+                    serde_json::json!({"success": false, "error": e.to_string()})
+                );
+            }
+            return Ok(());
+        }
+    };
 
+    let title = updated["summary"].as_str().unwrap_or(&event.summary);
     if human {
-        println!("✅ Event created successfully!");
-        println!("   Title: {}", summary);
-        println!("   When: {} to {}", start, end);
-        if let Some(loc) = location {
-            println!("   Location: {}", loc);
+        println!("✅ Event updated: {}", title);
+        if let Some(location) = location {
+            println!("   Location: {}", location);
         }
-        if let Some(link) = html_link {
+        print_event_options_human(options);
+        if let Some(link) = updated["htmlLink"].as_str() {
             println!("   Link: {}", link);
         }
     } else {
@@ -4371,13 +13360,19 @@ async fn calendar_create(
             serde_json::to_string_pretty(&serde_json::json!({
                 "success": true,
                 "event": {
-                    "id": event_id,
-                    "summary": summary,
-                    "start": start,
-                    "end": end,
-                    "calendar_id": calendar_id,
-                    "account": account_email,
-                    "html_link": html_link
+                    "id": event.id,
+                    "account": event.account_id,
+                    "summary": title,
+                    "location": updated["location"],
+                    "show_as": updated["transparency"]
+                        .as_str()
+                        .and_then(Transparency::parse)
+                        .unwrap_or_default()
+                        .show_as(),
+                    "visibility": updated["visibility"].as_str().unwrap_or("default"),
+                    "color": updated["colorId"].as_str().and_then(event_color_name),
+                    "reminders": updated["reminders"],
+                    "html_link": updated["htmlLink"]
                 }
             }))?
         );
@@ -4390,7 +13385,13 @@ async fn calendar_create(
 // Sync Management Functions
 // ============================================================================
 
-async fn sync_reset(account: &str, data_type: &str, confirm: bool, human: bool) -> Result<()> {
+async fn sync_reset(
+    account: &str,
+    data_type: &str,
+    confirm: bool,
+    dry_run: bool,
+    human: bool,
+) -> Result<()> {
     if !["email", "calendar", "all"].contains(&data_type) {
         if human {
             println!("❌ Invalid data_type. Must be 'email', 'calendar', or 'all'");
@@ -4400,7 +13401,7 @@ async fn sync_reset(account: &str, data_type: &str, confirm: bool, human: bool)
         return Ok(());
     }
 
-    if !confirm {
+    if !confirm && !dry_run {
         if human {
             println!("❌ Must pass --confirm to reset sync data.");
             println!(
@@ -4439,6 +13440,30 @@ async fn sync_reset(account: &str, data_type: &str, confirm: bool, human: bool)
         }
     };
 
+    if dry_run {
+        let preview = deletion_preview(&db, &email, data_type).await?;
+        if human {
+            println!(
+                "🔍 Dry run: resetting {} sync data for {} would remove:",
+                data_type, email
+            );
+            print_deletion_preview(&preview);
+            println!("\nNothing was changed. Re-run with --confirm to reset.");
+        } else {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "success": true,
+                    "dry_run": true,
+                    "account": email,
+                    "data_type": data_type,
+                    "would_delete": preview,
+                }))?
+            );
+        }
+        return Ok(());
+    }
+
     // Clear sync data based on type
     let (email_count, event_count) = match data_type {
         "email" => {
@@ -4472,6 +13497,17 @@ async fn sync_reset(account: &str, data_type: &str, confirm: bool, human: bool)
         }
         db.upsert_account(&acct).await?;
     }
+    record_audit(
+        &db,
+        "sync_reset",
+        Some(&email),
+        serde_json::json!({
+            "data_type": data_type,
+            "emails": email_count,
+            "events": event_count,
+        }),
+    )
+    .await;
 
     if human {
         println!("✅ Reset {} sync data for {}", data_type, email);
@@ -4495,6 +13531,89 @@ async fn sync_reset(account: &str, data_type: &str, confirm: bool, human: bool)
     Ok(())
 }
 
+/// What deleting an account's synced data would remove
+#[derive(Serialize)]
+struct DeletionPreview {
+    emails: u64,
+    events: u64,
+    activity: usize,
+    receipts: usize,
+    itinerary: usize,
+    /// Size of the indexed emails (downloaded attachment files are kept)
+    email_bytes: u64,
+    oldest_email: Option<String>,
+    newest_email: Option<String>,
+    oldest_event: Option<String>,
+    newest_event: Option<String>,
+}
+
+/// Count what `sync reset` (or `account delete`, with "all") would remove
+async fn deletion_preview(
+    db: &Database,
+    account_id: &str,
+    data_type: &str,
+) -> Result<DeletionPreview> {
+    let day = |d: Option<DateTime<Utc>>| d.map(|d| d.format("%Y-%m-%d").to_string());
+    let mut preview = DeletionPreview {
+        emails: 0,
+        events: 0,
+        activity: 0,
+        receipts: 0,
+        itinerary: 0,
+        email_bytes: 0,
+        oldest_email: None,
+        newest_email: None,
+        oldest_event: None,
+        newest_event: None,
+    };
+
+    if data_type != "calendar" {
+        preview.emails = db.count_emails(Some(account_id)).await?;
+        preview.email_bytes = db.get_storage_usage(account_id).await?.email_bytes;
+        for (table, count) in db.count_email_derived_rows(account_id).await? {
+            match table {
+                ACTIVITY_TABLE => preview.activity = count,
+                RECEIPTS_TABLE => preview.receipts = count,
+                ITINERARY_TABLE => preview.itinerary = count,
+                _ => {}
+            }
+        }
+        let (oldest, newest) = db.get_email_sync_boundaries(account_id).await?;
+        preview.oldest_email = day(oldest);
+        preview.newest_email = day(newest);
+    }
+    if data_type != "email" {
+        preview.events = db.count_events(Some(account_id)).await?;
+        let (oldest, newest) = db.get_event_sync_boundaries(account_id).await?;
+        preview.oldest_event = day(oldest);
+        preview.newest_event = day(newest);
+    }
+
+    Ok(preview)
+}
+
+fn print_deletion_preview(preview: &DeletionPreview) {
+    let range = |oldest: &Option<String>, newest: &Option<String>| match (oldest, newest) {
+        (Some(oldest), Some(newest)) => format!(" ({} to {})", oldest, newest),
+        _ => String::new(),
+    };
+    println!(
+        "   {} emails, {}{}",
+        preview.emails,
+        format_bytes(preview.email_bytes),
+        range(&preview.oldest_email, &preview.newest_email)
+    );
+    println!(
+        "   {} activity items, {} receipts, {} itinerary items",
+        preview.activity, preview.receipts, preview.itinerary
+    );
+    println!(
+        "   {} events{}",
+        preview.events,
+        range(&preview.oldest_event, &preview.newest_event)
+    );
+}
+
 async fn sync_extend(account: &str, target_date: &str, human: bool) -> Result<()> {
     let config = Config::load().unwrap_or_default();
     let db = Database::open(config.lancedb_dir()).await?;
@@ -4554,6 +13673,16 @@ async fn sync_extend(account: &str, target_date: &str, human: bool) -> Result<()
     let mut updated = acct.clone();
     updated.sync_email_since = Some(target_datetime);
     db.upsert_account(&updated).await?;
+    record_audit(
+        &db,
+        "sync_extend",
+        Some(&email),
+        serde_json::json!({
+            "previous": current_sync_from.format("%Y-%m-%d").to_string(),
+            "target_date": target_date,
+        }),
+    )
+    .await;
 
     let additional_days = (current_sync_from - target_datetime).num_days();
 
@@ -4636,6 +13765,13 @@ async fn sync_resume_from(account: &str, target_date: &str, human: bool) -> Resu
     updated.last_sync_email = None;
     updated.last_sync_calendar = None;
     db.upsert_account(&updated).await?;
+    record_audit(
+        &db,
+        "sync_resume_from",
+        Some(&email),
+        serde_json::json!({ "target_date": target_date }),
+    )
+    .await;
 
     if human {
         println!("✅ Sync will resume from {} for {}", target_date, email);
@@ -4753,10 +13889,170 @@ async fn sync_download_attachments(account: &str, human: bool) -> Result<()> {
     Ok(())
 }
 
+/// Export the `[apple]` accounts to macOS Calendar and Contacts
+#[cfg(feature = "apple-bridge")]
+async fn sync_apple(dry_run: bool, human: bool) -> Result<()> {
+    use groundeffect_core::apple;
+
+    let config = Config::load().unwrap_or_default();
+    if config.apple.calendar_accounts.is_empty() && config.apple.contact_accounts.is_empty() {
+        return Err(anyhow::anyhow!(
+            "No accounts to export. Set calendar_accounts or contact_accounts under [apple] in the config."
+        ));
+    }
+    let db = Database::open(config.lancedb_dir()).await?;
+    let plan = apple::build_plan(&db, &config, Utc::now()).await?;
+
+    if dry_run {
+        if human {
+            println!("Would export to Calendar and Contacts:");
+            for calendar in &plan.calendars {
+                println!("  📅 {}: {} events", calendar.name, calendar.events.len());
+            }
+            for group in &plan.groups {
+                println!(
+                    "  👤 {}: {} people (ones already in Contacts are skipped)",
+                    group.name,
+                    group.contacts.len()
+                );
+            }
+        } else {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "success": true,
+                    "dry_run": true,
+                    "plan": plan,
+                }))?
+            );
+        }
+        return Ok(());
+    }
+
+    let report = apple::apply_plan(&plan).await?;
+    if human {
+        println!("✅ Exported to Calendar and Contacts");
+        for calendar in &report.calendars {
+            println!("   📅 {}: {} events", calendar.name, calendar.events);
+        }
+        if !plan.groups.is_empty() {
+            println!("   👤 Contacts added: {}", report.contacts_added);
+        }
+    } else {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "success": true,
+                "calendars": report.calendars,
+                "contacts_added": report.contacts_added,
+            }))?
+        );
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "apple-bridge"))]
+async fn sync_apple(_dry_run: bool, _human: bool) -> Result<()> {
+    Err(anyhow::anyhow!(
+        "This build lacks the apple-bridge feature. Rebuild with: cargo build --release --features apple-bridge"
+    ))
+}
+
 // ============================================================================
 // Config Settings Function
 // ============================================================================
 
+/// Report network paths compiled into this build
+fn config_capabilities(human: bool) -> Result<()> {
+    let config = Config::load().unwrap_or_default();
+    let report = network::network_report(&config);
+
+    if human {
+        println!("Network access for this build");
+        println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+        for path in &report {
+            let state = match (path.compiled, path.active) {
+                (false, _) => "compiled out",
+                (true, true) => "in use",
+                (true, false) => "not in use",
+            };
+            println!("{:<18} {:<14} {}", path.name, state, path.description);
+            if path.compiled && !path.endpoints.is_empty() {
+                println!("{:<33} {}", "", path.endpoints.join(", "));
+            }
+        }
+    } else {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "features": {
+                    "no-remote-embedding": !network::REMOTE_EMBEDDING,
+                    "offline-search": !network::MODEL_DOWNLOAD,
+                    "no-llm": !network::LLM,
+                    "apple-bridge": cfg!(feature = "apple-bridge"),
+                },
+                "network": report,
+            }))?
+        );
+    }
+    Ok(())
+}
+
+fn config_show(origin: bool, human: bool) -> Result<()> {
+    let settings = config_layers::effective_settings()?;
+
+    if human {
+        for setting in &settings {
+            if origin {
+                println!("{} = {}  # {}", setting.key, setting.value, setting.origin);
+            } else {
+                println!("{} = {}", setting.key, setting.value);
+            }
+        }
+    } else if origin {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({ "settings": settings }))?
+        );
+    } else {
+        let mut config = serde_json::to_value(Config::load()?)?;
+        config["daemon"] = serde_json::to_value(DaemonConfig::load()?)?;
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({ "settings": config }))?
+        );
+    }
+    Ok(())
+}
+
+fn config_validate(human: bool) -> Result<()> {
+    let issues = config_layers::validate()?;
+
+    if human {
+        if issues.is_empty() {
+            println!("✅ Settings are valid");
+        } else {
+            println!("❌ {} problem(s) in the settings:\n", issues.len());
+            for issue in &issues {
+                println!("  {}: {}", issue.key, issue.problem);
+                println!("    from {}", issue.origin);
+            }
+        }
+    } else {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "valid": issues.is_empty(),
+                "issues": issues,
+            }))?
+        );
+    }
+    if !issues.is_empty() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
 async fn config_settings(
     logging: Option<bool>,
     email_interval: Option<u64>,
@@ -4769,8 +14065,8 @@ async fn config_settings(
     openrouter_api_key_env: Option<String>,
     human: bool,
 ) -> Result<()> {
-    let mut daemon_config = DaemonConfig::load().unwrap_or_default();
-    let mut config = Config::load().unwrap_or_default();
+    let mut daemon_config = DaemonConfig::load_file().unwrap_or_default();
+    let mut config = Config::load_file().unwrap_or_default();
     let mut changes = vec![];
 
     // Apply daemon config changes
@@ -4826,18 +14122,18 @@ async fn config_settings(
     // Apply search config changes
     let mut search_config_changed = false;
     if let Some(provider) = embedding_provider {
-        let normalized = provider.trim().to_lowercase();
-        let parsed = match normalized.as_str() {
-            "local" => EmbeddingProvider::Local,
-            "openrouter" => EmbeddingProvider::OpenRouter,
-            "remote" => EmbeddingProvider::Remote,
-            _ => {
-                return Err(anyhow::anyhow!(
-                    "Invalid embedding provider '{}'. Use: local, openrouter, remote.",
-                    provider
-                ));
-            }
-        };
+        let parsed = EmbeddingProvider::from_name(&provider).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Invalid embedding provider '{}'. Use: local, openai, ollama, openrouter, remote.",
+                provider
+            )
+        })?;
+        let normalized = parsed.as_str();
+        if parsed != EmbeddingProvider::Local && !network::REMOTE_EMBEDDING {
+            return Err(anyhow::anyhow!(
+                "This build has no remote embedding support (no-remote-embedding). Use: local."
+            ));
+        }
 
         if config.search.embedding_provider != Some(parsed) {
             config.search.embedding_provider = Some(parsed);
@@ -4895,31 +14191,27 @@ async fn config_settings(
 
     if human {
         let provider = config.search.effective_embedding_provider();
-        let provider_label = match provider {
-            EmbeddingProvider::Local => "local",
-            EmbeddingProvider::OpenRouter => "openrouter",
-            EmbeddingProvider::Remote => "remote",
-        };
         println!("\n⚙️  Settings");
         println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
         println!("Timezone: {}", config.general.timezone);
-        println!("Embedding provider: {}", provider_label);
+        println!("Embedding provider: {}", provider.as_str());
         println!(
             "Embedding batch size: {} (IMAP fetch: {})",
             config.search.effective_embedding_batch_size(),
             config.search.effective_imap_fetch_batch_size()
         );
-        if provider == EmbeddingProvider::OpenRouter {
-            println!("OpenRouter model: {}", config.search.openrouter_model);
+        if let Some(endpoint) = config.search.embedding_endpoint() {
+            println!("Embedding URL: {}", endpoint.url);
+            println!("Embedding model: {}", endpoint.model);
+            if let Some(env_name) = &endpoint.api_key_env {
+                println!("Embedding API key env: {}", env_name);
+            }
             println!(
-                "OpenRouter API key env: {}",
-                config.search.openrouter_api_key_env
+                "Embedding requests: {} texts max, {} attempts",
+                endpoint.max_batch_size, endpoint.max_attempts
             );
         } else if provider == EmbeddingProvider::Remote {
-            println!(
-                "Remote embedding URL: {}",
-                config.search.embedding_url.as_deref().unwrap_or("(unset)")
-            );
+            println!("Remote embedding URL: (unset)");
         }
         println!("Logging enabled: {}", daemon_config.logging_enabled);
         println!(
@@ -4935,14 +14227,7 @@ async fn config_settings(
             daemon_config.max_concurrent_fetches
         );
         println!("\nDaemon config: {:?}", DaemonConfig::config_path());
-        println!(
-            "Config file: {:?}",
-            dirs::home_dir()
-                .unwrap_or_default()
-                .join(".config")
-                .join("groundeffect")
-                .join("config.toml")
-        );
+        println!("Config file: {:?}", Config::config_path());
 
         if !changes.is_empty() {
             println!("\nChanges made:");
@@ -4953,17 +14238,21 @@ async fn config_settings(
         }
     } else {
         let provider = config.search.effective_embedding_provider();
-        let provider_label = match provider {
-            EmbeddingProvider::Local => "local",
-            EmbeddingProvider::OpenRouter => "openrouter",
-            EmbeddingProvider::Remote => "remote",
-        };
+        let endpoint = config.search.embedding_endpoint();
         println!(
             "{}",
             serde_json::to_string_pretty(&serde_json::json!({
                 "settings": {
                     "timezone": config.general.timezone,
-                    "embedding_provider": provider_label,
+                    "embedding_provider": provider.as_str(),
+                    "embedding_api": endpoint.as_ref().map(|e| serde_json::json!({
+                        "url": e.url,
+                        "model": e.model,
+                        "api_key_env": e.api_key_env,
+                        "max_batch_size": e.max_batch_size,
+                        "max_input_chars": e.max_input_chars,
+                        "max_attempts": e.max_attempts,
+                    })),
                     "embedding_batch_size": config.search.effective_embedding_batch_size(),
                     "imap_fetch_batch_size": config.search.effective_imap_fetch_batch_size(),
                     "openrouter_model": config.search.openrouter_model,
@@ -4998,10 +14287,25 @@ async fn handle_draft_command(command: DraftCommands, global_human: bool) -> Res
             bcc,
             html,
             reply_to,
+            send_as,
+            signature,
             human,
         } => {
             let human = human || global_human;
-            draft_create(&from, to, &subject, &body, cc, bcc, html, reply_to, human).await?;
+            draft_create(
+                &from,
+                to,
+                &subject,
+                &body,
+                cc,
+                bcc,
+                html,
+                reply_to,
+                send_as,
+                &SignatureChoice::parse(&signature),
+                human,
+            )
+            .await?;
         }
         DraftCommands::List { from, limit, human } => {
             let human = human || global_human;
@@ -5062,10 +14366,13 @@ async fn draft_create(
     bcc: Option<Vec<String>>,
     force_html: bool,
     reply_to: Option<String>,
+    send_as: Option<String>,
+    signature: &SignatureChoice,
     human: bool,
 ) -> Result<()> {
     let config = Config::load().unwrap_or_default();
     let token_provider = create_token_provider(&config).await?;
+    let oauth = OAuthManager::new(token_provider);
     let db = Database::open(config.lancedb_dir()).await?;
     let accounts = db.list_accounts().await?;
 
@@ -5074,9 +14381,22 @@ async fn draft_create(
         .find(|a| a.id == from || a.alias.as_ref() == Some(&from.to_string()))
         .ok_or_else(|| anyhow::anyhow!("Account not found: {}", from))?;
     let from_email = &account.id;
-    let display_name = &account.display_name;
 
-    let cc_list = cc.unwrap_or_default();
+    // Send-as identity and its configured defaults
+    let sender = send_as.unwrap_or_else(|| from_email.clone());
+    let identity = config
+        .compose
+        .identity(&sender)
+        .cloned()
+        .unwrap_or_default();
+    let display_name = identity
+        .display_name
+        .as_deref()
+        .unwrap_or(&account.display_name);
+    let signature =
+        resolve_signature(&config.compose, &oauth, from_email, &sender, signature).await?;
+
+    let cc_list = identity.merge_cc(&to, &cc.unwrap_or_default());
     let bcc_list = bcc.unwrap_or_default();
 
     let mut in_reply_to = None;
@@ -5097,20 +14417,22 @@ async fn draft_create(
 
     let message = build_email_message(
         display_name,
-        from_email,
+        &sender,
         &to,
         &cc_list,
         &bcc_list,
         &final_subject,
         body,
         is_html,
+        signature.as_ref(),
         in_reply_to.as_deref(),
         references.as_deref(),
+        identity.reply_to.as_deref(),
+        &[],
     );
 
     let encoded = URL_SAFE_NO_PAD.encode(message.as_bytes());
 
-    let oauth = OAuthManager::new(token_provider);
     let access_token = oauth.get_valid_token(from_email).await?;
     let client = reqwest::Client::new();
 
@@ -5139,6 +14461,22 @@ async fn draft_create(
     let result: serde_json::Value = response.json().await?;
     let draft_id = result["id"].as_str().unwrap_or("unknown");
     let message_id = result["message"]["id"].as_str().unwrap_or("unknown");
+    record_audit(
+        &db,
+        "create_draft",
+        Some(from_email),
+        serde_json::json!({
+            "from": sender,
+            "to": to,
+            "cc": cc_list,
+            "bcc": bcc_list,
+            "subject": final_subject,
+            "body": body,
+            "reply_to": reply_to,
+            "draft_id": draft_id,
+        }),
+    )
+    .await;
 
     if human {
         println!("✅ Draft created successfully!");
@@ -5151,7 +14489,7 @@ async fn draft_create(
                 "status": "draft_created",
                 "draft_id": draft_id,
                 "message_id": message_id,
-                "from": format!("{} <{}>", display_name, from_email),
+                "from": format!("{} <{}>", display_name, sender),
                 "to": to,
                 "subject": final_subject,
             }))?
@@ -5414,103 +14752,43 @@ async fn draft_update(
         .find(|a| a.id == from || a.alias.as_ref() == Some(&from.to_string()))
         .ok_or_else(|| anyhow::anyhow!("Account not found: {}", from))?;
     let from_email = &account.id;
-    let display_name = &account.display_name;
 
     let oauth = OAuthManager::new(token_provider);
     let access_token = oauth.get_valid_token(from_email).await?;
     let client = reqwest::Client::new();
 
     // Get existing draft
-    let existing_response = client
-        .get(format!(
-            "https://gmail.googleapis.com/gmail/v1/users/me/drafts/{}?format=full",
-            draft_id
-        ))
-        .bearer_auth(&access_token)
-        .send()
-        .await?;
-
-    if !existing_response.status().is_success() {
-        let status = existing_response.status();
-        let error_body = existing_response.text().await.unwrap_or_default();
-        if human {
-            println!("❌ Failed to get draft: {} - {}", status, error_body);
-        } else {
-            println!(
-                "{{\"error\": \"{} - {}\"}}",
-                status,
-                error_body.replace('"', "\\\"")
-            );
-        }
-        return Ok(());
-    }
-
-    let existing: serde_json::Value = existing_response.json().await?;
-    let headers = existing["message"]["payload"]["headers"].as_array();
-    let mut existing_subject = String::new();
-    let mut existing_to = String::new();
-    let mut existing_cc = String::new();
-    let mut existing_body = String::new();
-
-    if let Some(hdrs) = headers {
-        for h in hdrs {
-            match h["name"].as_str().unwrap_or("") {
-                "Subject" => existing_subject = h["value"].as_str().unwrap_or("").to_string(),
-                "To" => existing_to = h["value"].as_str().unwrap_or("").to_string(),
-                "Cc" => existing_cc = h["value"].as_str().unwrap_or("").to_string(),
-                _ => {}
+    let existing = match fetch_draft(&oauth, from_email, draft_id).await {
+        Ok(existing) => existing,
+        Err(e) => {
+            if human {
+                println!("❌ Failed to get draft: {}", e);
+            } else {
+                println!("{}", serde_json::json!({ "error": e.to_string() }));
             }
+            return Ok(());
         }
-    }
+    };
 
-    let payload = &existing["message"]["payload"];
-    let mime_type = payload["mimeType"].as_str().unwrap_or("");
-    if mime_type.starts_with("multipart/") {
-        if let Some(parts) = payload["parts"].as_array() {
-            for part in parts {
-                if part["mimeType"].as_str().unwrap_or("") == "text/plain" {
-                    if let Some(body_data) = part["body"]["data"].as_str() {
-                        if let Ok(decoded) = URL_SAFE_NO_PAD.decode(body_data) {
-                            if let Ok(text) = String::from_utf8(decoded) {
-                                existing_body = text;
-                                break;
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    } else if let Some(body_data) = payload["body"]["data"].as_str() {
-        if let Ok(decoded) = URL_SAFE_NO_PAD.decode(body_data) {
-            if let Ok(text) = String::from_utf8(decoded) {
-                existing_body = text;
-            }
-        }
-    }
+    // Keep the draft's send-as address and its identity's defaults
+    let (sender, identity) = existing.sender(&config.compose, from_email);
+    let display_name = identity
+        .display_name
+        .as_deref()
+        .unwrap_or(&account.display_name);
 
-    let final_to: Vec<String> = to.unwrap_or_else(|| {
-        existing_to
-            .split(',')
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
-            .collect()
-    });
-    let final_subject = subject.unwrap_or(existing_subject);
-    let final_body = body.unwrap_or(existing_body);
-    let final_cc: Vec<String> = cc.unwrap_or_else(|| {
-        existing_cc
-            .split(',')
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
-            .collect()
-    });
-    let final_bcc: Vec<String> = bcc.unwrap_or_default();
+    let final_to = to.unwrap_or(existing.to);
+    let final_subject = subject.unwrap_or(existing.subject);
+    let final_body = body.unwrap_or(existing.body);
+    let final_cc = cc.unwrap_or(existing.cc);
+    let final_bcc = bcc.unwrap_or(existing.bcc);
 
     let is_html = force_html || detect_html_content(&final_body);
 
+    // The body already carries the signature added when the draft was made
     let message = build_email_message(
         display_name,
-        from_email,
+        &sender,
         &final_to,
         &final_cc,
         &final_bcc,
@@ -5518,7 +14796,10 @@ async fn draft_update(
         &final_body,
         is_html,
         None,
-        None,
+        existing.in_reply_to.as_deref(),
+        existing.references.as_deref(),
+        identity.reply_to.as_deref(),
+        &existing.attachments,
     );
 
     let encoded = URL_SAFE_NO_PAD.encode(message.as_bytes());
@@ -5550,6 +14831,20 @@ async fn draft_update(
 
     let result: serde_json::Value = response.json().await?;
     let new_draft_id = result["id"].as_str().unwrap_or(draft_id);
+    record_audit(
+        &db,
+        "update_draft",
+        Some(from_email),
+        serde_json::json!({
+            "draft_id": new_draft_id,
+            "to": final_to,
+            "cc": final_cc,
+            "bcc": final_bcc,
+            "subject": final_subject,
+            "body": final_body,
+        }),
+    )
+    .await;
 
     if human {
         println!("✅ Draft updated successfully!");
@@ -5560,7 +14855,7 @@ async fn draft_update(
             "{}",
             serde_json::to_string_pretty(&serde_json::json!({
                 "status": "updated", "draft_id": new_draft_id,
-                "from": format!("{} <{}>", display_name, from_email),
+                "from": format!("{} <{}>", display_name, sender),
                 "to": final_to, "subject": final_subject,
             }))?
         );
@@ -5631,6 +14926,18 @@ async fn draft_send(from: &str, draft_id: &str, human: bool) -> Result<()> {
 
     let result: serde_json::Value = response.json().await?;
     let message_id = result["id"].as_str().unwrap_or("unknown");
+    record_audit(
+        &db,
+        "send_draft",
+        Some(from_email),
+        serde_json::json!({
+            "draft_id": draft_id,
+            "to": to,
+            "subject": subject,
+            "message_id": message_id,
+        }),
+    )
+    .await;
 
     if human {
         println!("✅ Draft sent successfully!");
@@ -5688,6 +14995,13 @@ async fn draft_delete(from: &str, draft_id: &str, human: bool) -> Result<()> {
         }
         return Ok(());
     }
+    record_audit(
+        &db,
+        "delete_draft",
+        Some(from_email),
+        serde_json::json!({ "draft_id": draft_id }),
+    )
+    .await;
 
     if human {
         println!("✅ Draft deleted successfully!");
@@ -5798,14 +15112,21 @@ fn build_email_message(
     subject: &str,
     body: &str,
     is_html: bool,
+    signature: Option<&Signature>,
     in_reply_to: Option<&str>,
     references: Option<&str>,
+    reply_to: Option<&str>,
+    attachments: &[OutgoingAttachment],
 ) -> String {
     let encoded_name = encode_display_name(display_name);
     let from_header = format!("{} <{}>", encoded_name, from_email);
 
     let mut message = format!("From: {}\r\nTo: {}\r\n", from_header, to.join(", "));
 
+    if let Some(addr) = reply_to {
+        message.push_str(&format!("Reply-To: {}\r\n", addr));
+    }
+
     if !cc.is_empty() {
         message.push_str(&format!("Cc: {}\r\n", cc.join(", ")));
     }
@@ -5821,10 +15142,18 @@ fn build_email_message(
 
     message.push_str(&format!("Subject: {}\r\nMIME-Version: 1.0\r\n", subject));
 
-    if is_html {
+    // Attachments wrap the body in multipart/mixed
+    let mixed_boundary = format!("----=_Mixed_{}", chrono::Utc::now().timestamp_millis());
+    if !attachments.is_empty() {
+        message.push_str(&format!(
+            "Content-Type: multipart/mixed; boundary=\"{}\"\r\n\r\n--{}\r\n",
+            mixed_boundary, mixed_boundary
+        ));
+    }
+
+    let (plain_body, html_body) = message_parts(body, is_html, signature);
+    if let Some(html_body) = html_body {
         let boundary = format!("----=_Part_{}", chrono::Utc::now().timestamp_millis());
-        let html_body = convert_to_html(body);
-        let plain_body = strip_html_tags(&html_body);
 
         message.push_str(&format!(
             "Content-Type: multipart/alternative; boundary=\"{}\"\r\n\r\n",
@@ -5835,8 +15164,37 @@ fn build_email_message(
         message.push_str(&format!("--{}--\r\n", boundary));
     } else {
         message.push_str("Content-Type: text/plain; charset=utf-8\r\n\r\n");
-        message.push_str(body);
+        message.push_str(&plain_body);
+    }
+
+    if !attachments.is_empty() {
+        for attachment in attachments {
+            message.push_str(&format!("\r\n--{}\r\n", mixed_boundary));
+            message.push_str(&attachment.mime_part());
+        }
+        message.push_str(&format!("\r\n--{}--\r\n", mixed_boundary));
     }
 
     message
 }
+
+/// Plain-text part and (for HTML mail) HTML part of a message body, with
+/// the signature appended to each
+fn message_parts(
+    body: &str,
+    is_html: bool,
+    signature: Option<&Signature>,
+) -> (String, Option<String>) {
+    let html = is_html.then(|| convert_to_html(body));
+    let plain = match &html {
+        Some(html) => strip_html_tags(html),
+        None => body.to_string(),
+    };
+    match signature {
+        Some(signature) => (
+            signature.append_text(&plain),
+            html.map(|html| signature.append_html(&html)),
+        ),
+        None => (plain, html),
+    }
+}
@@ -0,0 +1,484 @@
+//! SaaS notification parsing
+//!
+//! GitHub, Jira, and Asana send an email for every comment, status change,
+//! and review, which crowds out real mail in search. Built-in parsers
+//! recognise these notifications at sync time and collapse each into a
+//! structured activity item (project, item, action) stored in its own table.
+//! The email is kept but tagged with its source, so search can leave it out
+//! and `activity list` can show the items instead.
+
+use crate::config::ActivityConfig;
+use crate::models::{ActivityItem, ActivitySource, Email};
+
+/// Parse a notification email with the enabled parsers.
+///
+/// Returns None for emails no parser recognises.
+pub fn parse_activity(email: &Email, sources: &[ActivitySource]) -> Option<ActivityItem> {
+    sources.iter().find_map(|source| {
+        let parsed = match source {
+            ActivitySource::GitHub => parse_github(email),
+            ActivitySource::Jira => parse_jira(email),
+            ActivitySource::Asana => parse_asana(email),
+        }?;
+        Some(ActivityItem {
+            id: email.id.clone(),
+            account_id: email.account_id.clone(),
+            source: *source,
+            project: parsed.project,
+            item: parsed.item,
+            title: parsed.title,
+            action: parsed.action,
+            actor: parsed.actor,
+            url: parsed.url,
+            date: email.date,
+        })
+    })
+}
+
+/// Collapse notification emails into activity items.
+///
+/// Tags each recognised email with its activity source and returns the
+/// items to store alongside the emails.
+pub fn extract_activity(emails: &mut [Email], config: &ActivityConfig) -> Vec<ActivityItem> {
+    if !config.enabled {
+        return Vec::new();
+    }
+    emails
+        .iter_mut()
+        .filter_map(|email| {
+            let item = parse_activity(email, &config.sources)?;
+            email.activity_source = Some(item.source);
+            Some(item)
+        })
+        .collect()
+}
+
+/// Fields a parser extracts; the rest come from the email
+struct ParsedActivity {
+    project: String,
+    item: Option<String>,
+    title: String,
+    action: String,
+    actor: Option<String>,
+    url: Option<String>,
+}
+
+/// GitHub: `[owner/repo] Title (PR #45)` from notifications@github.com
+fn parse_github(email: &Email) -> Option<ParsedActivity> {
+    let from = email.from.email.to_lowercase();
+    if from != "notifications@github.com" && from != "noreply@github.com" {
+        return None;
+    }
+
+    let (is_reply, subject) = strip_reply_prefix(&email.subject);
+    let rest = subject.strip_prefix('[')?;
+    let (project, title) = rest.split_once(']')?;
+    if !project.contains('/') {
+        return None;
+    }
+    let mut title = title.trim();
+
+    // Trailing "(Issue #123)", "(PR #45)", or "(Discussion #7)"
+    let mut item = None;
+    if let Some(open) = title.rfind(" (") {
+        let tail = &title[open + 2..];
+        if let Some(number) = tail
+            .strip_suffix(')')
+            .and_then(|t| t.split_once(" #"))
+            .map(|(_, n)| n)
+            .filter(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+        {
+            item = Some(format!("#{}", number));
+            title = title[..open].trim();
+        }
+    }
+
+    let lines = body_lines(&email.body_plain);
+    let (action, mention) = github_action(&lines, subject, is_reply, item.is_some());
+    let actor = mention.or_else(|| display_name(email, &["GitHub", "notifications"]));
+    let repo_url = format!("https://github.com/{}/", project);
+    let url = find_url(&email.body_plain, |url| url.starts_with(&repo_url));
+
+    Some(ParsedActivity {
+        project: project.to_string(),
+        item,
+        title: title.to_string(),
+        action,
+        actor,
+        url,
+    })
+}
+
+/// Work out what happened from the body of a GitHub notification, plus the
+/// @handle of whoever did it when the body names them
+fn github_action(
+    lines: &[&str],
+    subject: &str,
+    is_reply: bool,
+    has_item: bool,
+) -> (String, Option<String>) {
+    const EVENTS: &[(&str, &str)] = &[
+        (" approved this pull request", "approved"),
+        (
+            " requested changes on this pull request",
+            "requested changes",
+        ),
+        (" requested your review", "review requested"),
+        (" pushed ", "pushed"),
+        (" commented on this pull request", "commented"),
+        (" commented on this issue", "commented"),
+    ];
+
+    for line in lines {
+        if line.starts_with("Merged ") {
+            return ("merged".to_string(), None);
+        }
+        if line.starts_with("Closed ") {
+            return ("closed".to_string(), None);
+        }
+        if line.starts_with("Reopened ") {
+            return ("reopened".to_string(), None);
+        }
+        for (pattern, action) in EVENTS {
+            if let Some(idx) = line.find(pattern) {
+                let mention = line[..idx]
+                    .strip_prefix('@')
+                    .filter(|handle| !handle.contains(' '))
+                    .map(|handle| handle.to_string());
+                return (action.to_string(), mention);
+            }
+        }
+    }
+
+    let subject = subject.to_lowercase();
+    let action = if subject.contains("run failed") {
+        "run failed"
+    } else if is_reply {
+        "commented"
+    } else if has_item {
+        "opened"
+    } else {
+        "notified"
+    };
+    (action.to_string(), None)
+}
+
+/// Jira: `[JIRA] (PROJ-123) Title` or `[JIRA] Alice commented on PROJ-123: Title`
+fn parse_jira(email: &Email) -> Option<ParsedActivity> {
+    let from = email.from.email.to_lowercase();
+    let (local, domain) = from.split_once('@')?;
+    if !local.contains("jira") && !domain.ends_with("atlassian.net") {
+        return None;
+    }
+
+    let (_, subject) = strip_reply_prefix(&email.subject);
+    let subject = subject.strip_prefix("[JIRA]").unwrap_or(subject).trim();
+    let (start, key) = find_issue_key(subject)?;
+    let project = key.split('-').next()?.to_string();
+    let title = subject[start + key.len()..]
+        .trim_start_matches([')', ']', ':', ' '])
+        .trim()
+        .to_string();
+
+    // The verb is either before the key in the subject or in the first lines
+    // of the body ("Alice Smith changed the status to Done")
+    let lines = body_lines(&email.body_plain);
+    let action = std::iter::once(&subject[..start])
+        .chain(lines.iter().take(5).copied())
+        .find_map(jira_action)
+        .unwrap_or("updated")
+        .to_string();
+
+    let browse_path = format!("/browse/{}", key);
+    let url = find_url(&email.body_plain, |url| url.contains(&browse_path));
+
+    Some(ParsedActivity {
+        project,
+        item: Some(key.to_string()),
+        title: if title.is_empty() {
+            key.to_string()
+        } else {
+            title
+        },
+        action,
+        actor: display_name(email, &["Jira", "JIRA"]),
+        url,
+    })
+}
+
+fn jira_action(text: &str) -> Option<&'static str> {
+    const ACTIONS: &[(&str, &str)] = &[
+        ("mentioned you", "mentioned"),
+        ("commented", "commented"),
+        ("changed the status", "status changed"),
+        ("resolved", "resolved"),
+        ("assigned", "assigned"),
+        ("created", "created"),
+        ("changed", "updated"),
+        ("updated", "updated"),
+    ];
+    let text = text.to_lowercase();
+    ACTIONS
+        .iter()
+        .find(|(pattern, _)| text.contains(pattern))
+        .map(|(_, action)| *action)
+}
+
+/// Find the first Jira issue key ("PROJ-123") in text
+fn find_issue_key(text: &str) -> Option<(usize, &str)> {
+    text.char_indices()
+        .filter(|(i, c)| {
+            c.is_ascii_uppercase() && (*i == 0 || !text.as_bytes()[i - 1].is_ascii_alphanumeric())
+        })
+        .find_map(|(start, _)| {
+            let rest = &text[start..];
+            let prefix_len = rest
+                .find(|c: char| !(c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_'))
+                .unwrap_or(rest.len());
+            let digits = rest[prefix_len..].strip_prefix('-')?;
+            let digits_len = digits
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(digits.len());
+            if prefix_len >= 2 && digits_len > 0 {
+                Some((start, &rest[..prefix_len + 1 + digits_len]))
+            } else {
+                None
+            }
+        })
+}
+
+/// Asana: task links to app.asana.com, subject "Alice assigned a task to you: Title"
+fn parse_asana(email: &Email) -> Option<ParsedActivity> {
+    let from = email.from.email.to_lowercase();
+    if !from.ends_with("@asana.com") && !from.ends_with(".asana.com") {
+        return None;
+    }
+
+    let url = find_url(&email.body_plain, |url| {
+        url.starts_with("https://app.asana.com/")
+    })?;
+    let (project, task) = asana_ids(&url)?;
+
+    let (_, subject) = strip_reply_prefix(&email.subject);
+    let (prefix, title) = match subject.split_once(": ") {
+        Some((prefix, title)) => (prefix, title.trim()),
+        None => ("", subject.trim()),
+    };
+    let action = {
+        let text = prefix.to_lowercase();
+        if text.contains("assigned") {
+            "assigned"
+        } else if text.contains("mentioned") {
+            "mentioned"
+        } else if text.contains("commented") {
+            "commented"
+        } else if text.contains("completed") {
+            "completed"
+        } else if text.contains("due") {
+            "due"
+        } else {
+            "updated"
+        }
+    };
+
+    Some(ParsedActivity {
+        project,
+        item: Some(task),
+        title: title.to_string(),
+        action: action.to_string(),
+        actor: display_name(email, &["Asana"]),
+        url: Some(url),
+    })
+}
+
+/// Project and task IDs from `/0/<project>/<task>` or
+/// `/1/<workspace>/project/<project>/task/<task>` links
+fn asana_ids(url: &str) -> Option<(String, String)> {
+    let path = url.strip_prefix("https://app.asana.com/")?;
+    let segments: Vec<&str> = path
+        .split(['?', '#'])
+        .next()?
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect();
+    let after = |name: &str| {
+        segments
+            .iter()
+            .position(|s| *s == name)
+            .and_then(|i| segments.get(i + 1))
+            .map(|s| s.to_string())
+    };
+
+    match (after("project"), after("task")) {
+        (Some(project), Some(task)) => Some((project, task)),
+        _ if segments.first() == Some(&"0") && segments.len() >= 3 => {
+            Some((segments[1].to_string(), segments[2].to_string()))
+        }
+        _ => None,
+    }
+}
+
+/// Strip "Re:"/"RE:" and report whether it was there
+fn strip_reply_prefix(subject: &str) -> (bool, &str) {
+    let trimmed = subject.trim();
+    match trimmed.get(..3) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("re:") => (true, trimmed[3..].trim_start()),
+        _ => (false, trimmed),
+    }
+}
+
+/// Non-empty trimmed body lines
+fn body_lines(body: &str) -> Vec<&str> {
+    body.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+/// Sender display name without the tool's own name ("Alice (Jira)" -> "Alice")
+fn display_name(email: &Email, tool_names: &[&str]) -> Option<String> {
+    let mut name = email.from.name.as_deref()?.trim();
+    for tool in tool_names {
+        if let Some(stripped) = name.strip_suffix(&format!("({})", tool)) {
+            name = stripped.trim();
+        }
+    }
+    let is_tool = tool_names
+        .iter()
+        .any(|tool| name.eq_ignore_ascii_case(tool));
+    (!name.is_empty() && !is_tool).then(|| name.to_string())
+}
+
+/// First URL in text accepted by `matches`
+fn find_url(text: &str, matches: impl Fn(&str) -> bool) -> Option<String> {
+    text.split(|c: char| c.is_whitespace() || c == '<' || c == '"' || c == '(')
+        .filter(|token| token.starts_with("https://"))
+        .map(|token| token.trim_end_matches(['>', ')', ']', '.', ',', ';']))
+        .find(|&url| matches(url))
+        .map(String::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Address;
+
+    fn email(from_name: &str, from_email: &str, subject: &str, body: &str) -> Email {
+        Email {
+            from: Address::with_name(from_name, from_email),
+            body_plain: body.to_string(),
+            ..Email::test("<msg@example.com>", from_email, subject)
+        }
+    }
+
+    #[test]
+    fn test_github_pull_request_review() {
+        let email = email(
+            "Alice Smith",
+            "notifications@github.com",
+            "Re: [acme/widgets] Add retry to uploader (PR #45)",
+            "@alice approved this pull request.\n\nLooks good!\n\n—\nReply to this email \
+             directly, view it on GitHub:\nhttps://github.com/acme/widgets/pull/45#pullrequestreview-1",
+        );
+        let item = parse_activity(&email, &ActivitySource::ALL).unwrap();
+        assert_eq!(item.source, ActivitySource::GitHub);
+        assert_eq!(item.project, "acme/widgets");
+        assert_eq!(item.item.as_deref(), Some("#45"));
+        assert_eq!(item.title, "Add retry to uploader");
+        assert_eq!(item.action, "approved");
+        assert_eq!(item.actor.as_deref(), Some("alice"));
+        assert_eq!(
+            item.url.as_deref(),
+            Some("https://github.com/acme/widgets/pull/45#pullrequestreview-1")
+        );
+    }
+
+    #[test]
+    fn test_github_comment_and_merge() {
+        let comment = email(
+            "Bob",
+            "notifications@github.com",
+            "Re: [acme/widgets] Crash on startup (Issue #12)",
+            "I can reproduce this on 1.2.",
+        );
+        let item = parse_activity(&comment, &ActivitySource::ALL).unwrap();
+        assert_eq!(item.action, "commented");
+        assert_eq!(item.actor.as_deref(), Some("Bob"));
+
+        let merged = email(
+            "Alice Smith",
+            "notifications@github.com",
+            "Re: [acme/widgets] Add retry to uploader (PR #45)",
+            "Merged #45 into main.",
+        );
+        assert_eq!(
+            parse_activity(&merged, &ActivitySource::ALL)
+                .unwrap()
+                .action,
+            "merged"
+        );
+    }
+
+    #[test]
+    fn test_jira_issue_update() {
+        let email = email(
+            "Priya Patel (Jira)",
+            "jira@acme.atlassian.net",
+            "[JIRA] (OPS-1024) Rotate database credentials",
+            "Priya Patel changed the status to Done\n\nOPS-1024\nhttps://acme.atlassian.net/browse/OPS-1024",
+        );
+        let item = parse_activity(&email, &ActivitySource::ALL).unwrap();
+        assert_eq!(item.source, ActivitySource::Jira);
+        assert_eq!(item.project, "OPS");
+        assert_eq!(item.item.as_deref(), Some("OPS-1024"));
+        assert_eq!(item.title, "Rotate database credentials");
+        assert_eq!(item.action, "status changed");
+        assert_eq!(item.actor.as_deref(), Some("Priya Patel"));
+        assert_eq!(
+            item.url.as_deref(),
+            Some("https://acme.atlassian.net/browse/OPS-1024")
+        );
+    }
+
+    #[test]
+    fn test_asana_assignment() {
+        let email = email(
+            "Ines (Asana)",
+            "no-reply@asana.com",
+            "Ines assigned a task to you: Draft Q3 roadmap",
+            "View task: https://app.asana.com/0/1201/1202/f",
+        );
+        let item = parse_activity(&email, &ActivitySource::ALL).unwrap();
+        assert_eq!(item.source, ActivitySource::Asana);
+        assert_eq!(item.project, "1201");
+        assert_eq!(item.item.as_deref(), Some("1202"));
+        assert_eq!(item.title, "Draft Q3 roadmap");
+        assert_eq!(item.action, "assigned");
+        assert_eq!(item.actor.as_deref(), Some("Ines"));
+    }
+
+    #[test]
+    fn test_extract_respects_config() {
+        let mut emails = vec![
+            email(
+                "Bob",
+                "notifications@github.com",
+                "[acme/widgets] Crash on startup (Issue #12)",
+                "Steps to reproduce...",
+            ),
+            email("Alice", "alice@example.com", "[lunch] Friday?", "Tacos?"),
+        ];
+
+        let disabled = ActivityConfig {
+            sources: vec![ActivitySource::Jira],
+            ..Default::default()
+        };
+        assert!(extract_activity(&mut emails, &disabled).is_empty());
+
+        let items = extract_activity(&mut emails, &ActivityConfig::default());
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].action, "opened");
+        assert_eq!(emails[0].activity_source, Some(ActivitySource::GitHub));
+        assert_eq!(emails[1].activity_source, None);
+    }
+}
@@ -0,0 +1,530 @@
+//! One-way export to the macOS Calendar and Contacts stores
+//!
+//! With the `apple-bridge` feature, selected accounts can be copied into
+//! Calendar.app and Contacts.app so Siri, Spotlight and other apps that read
+//! EventKit and the address book see them. Nothing is ever read back.
+//!
+//! Scoping is per account and opt-in (`[apple] calendar_accounts` and
+//! `contact_accounts`):
+//!
+//! - Events go to a local calendar per account, "groundeffect: <alias>",
+//!   which GroundEffect owns: each export replaces its events, so changes
+//!   and cancellations carry over. Other calendars are never touched.
+//! - Contacts are the people the user has written to at least
+//!   `contacts_min_sent` times. A person is only added when no card in the
+//!   address book has their email yet, and goes into the account's group
+//!   "groundeffect: <alias>". Cards are never changed or deleted, since the
+//!   user may have edited them.
+//!
+//! The stores are written by a JavaScript for Automation script run with
+//! `osascript`, so macOS asks once for permission to control Calendar and
+//! Contacts.
+
+use std::collections::{HashMap, HashSet};
+use std::process::Stdio;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tracing::info;
+
+use crate::config::Config;
+use crate::db::Database;
+use crate::error::{Error, Result};
+use crate::ics::unique_events;
+use crate::models::{CalendarEvent, Email, EventTime};
+
+/// Prefix of the calendars and contact groups the export owns
+const NAME_PREFIX: &str = "groundeffect: ";
+
+/// Script that applies an [`ExportPlan`] read from stdin and prints an
+/// [`ExportReport`]
+const EXPORT_SCRIPT: &str = r#"
+ObjC.import('Foundation');
+
+function run() {
+    const input = $.NSFileHandle.fileHandleWithStandardInput.readDataToEndOfFile;
+    const plan = JSON.parse($.NSString.alloc.initWithDataEncoding(input, $.NSUTF8StringEncoding).js);
+    const report = { calendars: [], contacts_added: 0 };
+
+    if (plan.calendars.length > 0) {
+        const app = Application('Calendar');
+        for (const c of plan.calendars) {
+            if (app.calendars.whose({ name: c.name })().length === 0) {
+                app.calendars.push(app.Calendar({ name: c.name }));
+            }
+            const calendar = app.calendars.whose({ name: c.name })()[0];
+            const old = calendar.events();
+            for (let i = old.length - 1; i >= 0; i--) {
+                old[i].delete();
+            }
+            for (const e of c.events) {
+                calendar.events.push(app.Event({
+                    summary: e.summary,
+                    startDate: new Date(e.start),
+                    endDate: new Date(e.end),
+                    alldayEvent: e.all_day,
+                    location: e.location || '',
+                    description: e.notes || '',
+                    url: e.url || '',
+                }));
+            }
+            report.calendars.push({ name: c.name, events: c.events.length });
+        }
+    }
+
+    if (plan.groups.length > 0) {
+        const app = Application('Contacts');
+        const known = new Set();
+        for (const emails of app.people.emails.value()) {
+            for (const email of emails) {
+                known.add(String(email).toLowerCase());
+            }
+        }
+        for (const g of plan.groups) {
+            if (app.groups.whose({ name: g.name })().length === 0) {
+                app.groups.push(app.Group({ name: g.name }));
+            }
+            const group = app.groups.whose({ name: g.name })()[0];
+            for (const p of g.contacts) {
+                if (known.has(p.email)) {
+                    continue;
+                }
+                const person = app.Person({
+                    firstName: p.first_name || '',
+                    lastName: p.last_name || '',
+                    note: 'Added by groundeffect',
+                });
+                app.people.push(person);
+                person.emails.push(app.Email({ label: 'work', value: p.email }));
+                app.add(person, { to: group });
+                known.add(p.email);
+                report.contacts_added++;
+            }
+        }
+        app.save();
+    }
+
+    return JSON.stringify(report);
+}
+"#;
+
+/// An event as written to Calendar.app
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportEvent {
+    pub summary: String,
+
+    /// RFC 3339 in UTC, or local midnight (`2024-06-03T00:00:00`) for
+    /// all-day events
+    pub start: String,
+
+    /// Exclusive end, in the same form as `start`
+    pub end: String,
+
+    pub all_day: bool,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+
+    /// Video call link, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+/// The calendar an account's events are written to
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportCalendar {
+    pub account: String,
+    pub name: String,
+    pub events: Vec<ExportEvent>,
+}
+
+/// A person to add to Contacts.app
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExportContact {
+    /// Email address (lowercase)
+    pub email: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_name: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_name: Option<String>,
+
+    /// Emails the user sent them
+    pub sent: usize,
+}
+
+/// The contact group an account's people are added to
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportGroup {
+    pub account: String,
+    pub name: String,
+    pub contacts: Vec<ExportContact>,
+}
+
+/// Everything one export writes
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ExportPlan {
+    pub calendars: Vec<ExportCalendar>,
+    pub groups: Vec<ExportGroup>,
+}
+
+impl ExportPlan {
+    pub fn is_empty(&self) -> bool {
+        self.calendars.is_empty() && self.groups.is_empty()
+    }
+}
+
+/// Events written to one calendar
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalendarReport {
+    pub name: String,
+    pub events: usize,
+}
+
+/// What an export changed
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExportReport {
+    pub calendars: Vec<CalendarReport>,
+
+    /// People added to Contacts (ones already there are skipped)
+    pub contacts_added: usize,
+}
+
+/// Name of the calendar and contact group owned for an account
+pub fn bridge_name(config: &Config, account_id: &str) -> String {
+    format!(
+        "{}{}",
+        NAME_PREFIX,
+        config.get_alias(account_id).unwrap_or(account_id)
+    )
+}
+
+/// Events to write for one account's calendar: cancelled events and repeats
+/// are left out, as in the ICS feed
+pub fn export_events(events: &[CalendarEvent]) -> Vec<ExportEvent> {
+    unique_events(events)
+        .into_iter()
+        .map(|event| ExportEvent {
+            summary: event.summary.clone(),
+            start: export_time(&event.start),
+            end: export_time(&event.end),
+            all_day: event.all_day,
+            location: event.location.clone().filter(|l| !l.trim().is_empty()),
+            notes: event.description.clone().filter(|d| !d.trim().is_empty()),
+            url: event.conference.as_ref().map(|c| c.join_url.clone()),
+        })
+        .collect()
+}
+
+/// People the user sent at least `min_sent` of `emails` (To or Cc), most
+/// written-to first
+///
+/// Addresses in `own` (lowercase) are skipped. The name comes from the
+/// first email that gives one, split into first and last name at the last
+/// space.
+pub fn export_contacts(
+    emails: &[Email],
+    own: &HashSet<String>,
+    min_sent: usize,
+) -> Vec<ExportContact> {
+    let mut people: HashMap<String, (Option<String>, usize)> = HashMap::new();
+    for email in emails
+        .iter()
+        .filter(|e| e.is_sent || own.contains(&e.from.email.trim().to_lowercase()))
+    {
+        let mut seen = HashSet::new();
+        for person in email.to.iter().chain(&email.cc) {
+            let key = person.email.trim().to_lowercase();
+            if key.is_empty() || own.contains(&key) || !seen.insert(key.clone()) {
+                continue;
+            }
+            let entry = people.entry(key).or_insert((None, 0));
+            entry.1 += 1;
+            if entry.0.is_none() {
+                entry.0 = person
+                    .name
+                    .as_deref()
+                    .map(|n| n.trim().trim_matches(|c| c == '"' || c == '\''))
+                    .filter(|n| !n.is_empty() && !n.contains('@'))
+                    .map(str::to_string);
+            }
+        }
+    }
+
+    let mut contacts: Vec<ExportContact> = people
+        .into_iter()
+        .filter(|(_, (_, sent))| *sent >= min_sent.max(1))
+        .map(|(email, (name, sent))| {
+            let (first_name, last_name) = match name.as_deref().and_then(|n| n.rsplit_once(' ')) {
+                Some((first, last)) => (Some(first.trim().to_string()), Some(last.to_string())),
+                None => (name, None),
+            };
+            ExportContact {
+                email,
+                first_name,
+                last_name,
+                sent,
+            }
+        })
+        .collect();
+    contacts.sort_by(|a, b| b.sent.cmp(&a.sent).then_with(|| a.email.cmp(&b.email)));
+    contacts
+}
+
+/// Build the export for the `[apple]` accounts around `now`
+pub async fn build_plan(db: &Database, config: &Config, now: DateTime<Utc>) -> Result<ExportPlan> {
+    let apple = &config.apple;
+    let resolve = |accounts: &[String]| {
+        accounts
+            .iter()
+            .map(|a| {
+                config
+                    .resolve_account(a)
+                    .ok_or_else(|| Error::AccountNotFound(a.clone()))
+            })
+            .collect::<Result<Vec<String>>>()
+    };
+
+    let mut plan = ExportPlan::default();
+    for account in resolve(&apple.calendar_accounts)? {
+        let events = db
+            .list_events_overlapping(
+                Some(std::slice::from_ref(&account)),
+                now - Duration::days(apple.past_days as i64),
+                now + Duration::days(apple.future_days as i64),
+            )
+            .await?;
+        plan.calendars.push(ExportCalendar {
+            name: bridge_name(config, &account),
+            events: export_events(&events),
+            account,
+        });
+    }
+
+    let contact_accounts = resolve(&apple.contact_accounts)?;
+    if !contact_accounts.is_empty() {
+        let own: HashSet<String> = db
+            .list_accounts()
+            .await?
+            .into_iter()
+            .map(|a| a.id.to_lowercase())
+            .collect();
+        let since = now - Duration::days(apple.contacts_since_days as i64);
+        for account in contact_accounts {
+            let emails = db.list_emails_since(Some(&account), since).await?;
+            plan.groups.push(ExportGroup {
+                name: bridge_name(config, &account),
+                contacts: export_contacts(&emails, &own, apple.contacts_min_sent),
+                account,
+            });
+        }
+    }
+    Ok(plan)
+}
+
+/// Write `plan` into Calendar.app and Contacts.app
+pub async fn apply_plan(plan: &ExportPlan) -> Result<ExportReport> {
+    if !cfg!(target_os = "macos") {
+        return Err(Error::Other(
+            "Exporting to Calendar and Contacts needs macOS".to_string(),
+        ));
+    }
+    if plan.is_empty() {
+        return Ok(ExportReport::default());
+    }
+
+    let mut child = Command::new("osascript")
+        .args(["-l", "JavaScript", "-e", EXPORT_SCRIPT])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| Error::Other(format!("Failed to run osascript: {}", e)))?;
+    if let Some(mut pipe) = child.stdin.take() {
+        pipe.write_all(&serde_json::to_vec(plan)?).await?;
+    }
+
+    let output = child.wait_with_output().await?;
+    if !output.status.success() {
+        return Err(Error::Other(format!(
+            "Export to Calendar and Contacts failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
+
+/// Build the `[apple]` export and write it
+pub async fn export(db: &Database, config: &Config, now: DateTime<Utc>) -> Result<ExportReport> {
+    let plan = build_plan(db, config, now).await?;
+    let report = apply_plan(&plan).await?;
+    info!(
+        "Exported {} events to {} calendars and added {} contacts",
+        report.calendars.iter().map(|c| c.events).sum::<usize>(),
+        report.calendars.len(),
+        report.contacts_added
+    );
+    Ok(report)
+}
+
+/// Calendar.app reads date-only strings as UTC, so all-day events are given
+/// as local midnight instead
+fn export_time(time: &EventTime) -> String {
+    match time {
+        EventTime::Date(d) => format!("{}T00:00:00", d.format("%Y-%m-%d")),
+        EventTime::DateTime(dt) => dt.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Address, Conference, EventStatus, Transparency, Visibility};
+    use chrono::{NaiveDate, TimeZone};
+
+    fn event(id: &str, start: EventTime, end: EventTime) -> CalendarEvent {
+        CalendarEvent {
+            id: id.to_string(),
+            account_id: "me@example.com".to_string(),
+            account_alias: None,
+            google_event_id: id.to_string(),
+            ical_uid: format!("{}@google.com", id),
+            etag: String::new(),
+            summary: format!("Meeting {}", id),
+            description: None,
+            location: None,
+            all_day: start.is_all_day(),
+            start,
+            end,
+            timezone: "UTC".to_string(),
+            recurrence_rule: None,
+            recurrence_id: None,
+            organizer: None,
+            attendees: vec![],
+            status: EventStatus::Confirmed,
+            transparency: Transparency::Opaque,
+            visibility: Visibility::Default,
+            color_id: None,
+            reminders: vec![],
+            conference: None,
+            embedding: None,
+            calendar_id: "primary".to_string(),
+            synced_at: Utc::now(),
+        }
+    }
+
+    fn sent(n: i64, to: &[Address], cc: &[Address]) -> Email {
+        Email {
+            gmail_message_id: n as u64,
+            gmail_thread_id: n as u64,
+            uid: n as u32,
+            folder: "[Gmail]/Sent Mail".to_string(),
+            is_sent: true,
+            to: to.to_vec(),
+            cc: cc.to_vec(),
+            date: Utc.with_ymd_and_hms(2024, 6, 1, 9, 0, 0).unwrap() + Duration::hours(n),
+            ..Email::test(
+                &format!("<{}@x>", n),
+                "me@example.com",
+                &format!("Email {}", n),
+            )
+        }
+    }
+
+    #[test]
+    fn test_events_keep_all_day_dates_local() {
+        let start = Utc.with_ymd_and_hms(2024, 6, 3, 9, 0, 0).unwrap();
+        let mut call = event(
+            "a1",
+            EventTime::DateTime(start),
+            EventTime::DateTime(start + Duration::minutes(30)),
+        );
+        call.location = Some("  ".to_string());
+        call.conference = Some(Conference {
+            provider: "Google Meet".to_string(),
+            join_url: "https://meet.google.com/abc-defg-hij".to_string(),
+            meeting_code: None,
+            phone: None,
+            passcode: None,
+        });
+        let mut cancelled = call.clone();
+        cancelled.ical_uid = "c1@google.com".to_string();
+        cancelled.status = EventStatus::Cancelled;
+        let day = NaiveDate::from_ymd_opt(2024, 6, 4).unwrap();
+        let offsite = event(
+            "b1",
+            EventTime::Date(day),
+            EventTime::Date(day.succ_opt().unwrap()),
+        );
+
+        let exported = export_events(&[call, cancelled, offsite]);
+        assert_eq!(exported.len(), 2);
+        assert_eq!(exported[0].start, "2024-06-03T09:00:00Z");
+        assert_eq!(exported[0].end, "2024-06-03T09:30:00Z");
+        assert_eq!(exported[0].location, None);
+        assert_eq!(
+            exported[0].url.as_deref(),
+            Some("https://meet.google.com/abc-defg-hij")
+        );
+        assert!(exported[1].all_day);
+        assert_eq!(exported[1].start, "2024-06-04T00:00:00");
+        assert_eq!(exported[1].end, "2024-06-05T00:00:00");
+    }
+
+    #[test]
+    fn test_contacts_are_people_written_to_often() {
+        let own: HashSet<String> = ["me@example.com".to_string()].into_iter().collect();
+        let emails = vec![
+            sent(
+                1,
+                &[Address::with_name("Pat van Lee", "Pat@Example.com")],
+                &[],
+            ),
+            sent(
+                2,
+                &[Address::new("pat@example.com")],
+                &[
+                    Address::new("once@example.com"),
+                    Address::new("me@example.com"),
+                ],
+            ),
+            sent(3, &[Address::with_name("\"Sam\"", "sam@example.com")], &[]),
+            sent(
+                4,
+                &[Address::with_name("sam@example.com", "sam@example.com")],
+                &[],
+            ),
+        ];
+
+        let contacts = export_contacts(&emails, &own, 2);
+        assert_eq!(
+            contacts,
+            vec![
+                ExportContact {
+                    email: "pat@example.com".to_string(),
+                    first_name: Some("Pat van".to_string()),
+                    last_name: Some("Lee".to_string()),
+                    sent: 2,
+                },
+                ExportContact {
+                    email: "sam@example.com".to_string(),
+                    first_name: Some("Sam".to_string()),
+                    last_name: None,
+                    sent: 2,
+                },
+            ]
+        );
+
+        // Received mail never adds anyone
+        let mut received = sent(5, &[Address::new("me@example.com")], &[]);
+        received.is_sent = false;
+        received.from = Address::new("stranger@example.com");
+        assert!(export_contacts(&[received], &own, 1).is_empty());
+    }
+}
@@ -0,0 +1,345 @@
+//! Working hours and out-of-office context for scheduling
+//!
+//! Combines the configured working hours (`[availability]`) with each
+//! account's cached vacation responder and out-of-office calendar blocks
+//! (see [`crate::sync::load_availability`]), so an MCP client suggesting
+//! meeting times knows when the user works and when they're away.
+
+use chrono::{DateTime, Datelike, Duration, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::Serialize;
+
+use crate::config::AvailabilityConfig;
+use crate::error::{Error, Result};
+use crate::models::{AvailabilityState, VacationResponder};
+
+/// Working days are searched this far ahead for the next working time
+const MAX_SEARCH_DAYS: i64 = 366;
+
+/// Configured working hours, in the user's timezone
+#[derive(Debug, Clone)]
+pub struct WorkingHours {
+    pub timezone: Tz,
+    pub days: Vec<Weekday>,
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl WorkingHours {
+    /// Working hours from `[availability]`, read in `timezone`
+    pub fn from_config(config: &AvailabilityConfig, timezone: &str) -> Result<Self> {
+        let invalid = |field: &str, reason: String| Error::InvalidConfig {
+            field: format!("availability.{}", field),
+            reason,
+        };
+        let time = |field: &str, value: &str| {
+            NaiveTime::parse_from_str(value.trim(), "%H:%M")
+                .map_err(|_| invalid(field, format!("'{}' is not HH:MM", value)))
+        };
+        let days = config
+            .working_days
+            .iter()
+            .map(|day| {
+                day.trim().parse::<Weekday>().map_err(|_| {
+                    invalid(
+                        "working_days",
+                        format!("'{}' is not a day (mon to sun)", day),
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let start = time("work_start", &config.work_start)?;
+        let end = time("work_end", &config.work_end)?;
+        if start >= end {
+            return Err(invalid(
+                "work_start",
+                format!(
+                    "{} is not before work_end ({})",
+                    config.work_start, config.work_end
+                ),
+            ));
+        }
+
+        Ok(Self {
+            timezone: timezone.parse().unwrap_or(Tz::UTC),
+            days,
+            start,
+            end,
+        })
+    }
+
+    /// Whether `at` falls within working hours
+    pub fn contains(&self, at: DateTime<Utc>) -> bool {
+        let local = at.with_timezone(&self.timezone);
+        self.days.contains(&local.weekday())
+            && local.time() >= self.start
+            && local.time() < self.end
+    }
+
+    /// Start of the first working period at or after `at` (`at` itself when
+    /// it's within working hours)
+    pub fn next_start(&self, at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        if self.contains(at) {
+            return Some(at);
+        }
+        let today = at.with_timezone(&self.timezone).date_naive();
+        (0..=MAX_SEARCH_DAYS)
+            .map(|offset| today + Duration::days(offset))
+            .filter(|day| self.days.contains(&day.weekday()))
+            .filter_map(|day| {
+                self.timezone
+                    .from_local_datetime(&day.and_time(self.start))
+                    .earliest()
+            })
+            .map(|start| start.with_timezone(&Utc))
+            .find(|start| *start >= at)
+    }
+}
+
+/// A stretch of time the user is away
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AwayBlock {
+    /// Account whose calendar or vacation responder it came from
+    pub account_id: String,
+
+    /// "calendar" (an out-of-office event) or "vacation_responder"
+    pub source: &'static str,
+
+    /// Event title or auto-reply subject
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+
+    /// Start of the block (None: already running, with no start set)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<DateTime<Utc>>,
+
+    /// End of the block (None: until the responder is turned off)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<DateTime<Utc>>,
+}
+
+impl AwayBlock {
+    fn contains(&self, at: DateTime<Utc>) -> bool {
+        self.start.is_none_or(|start| start <= at) && self.end.is_none_or(|end| at < end)
+    }
+
+    /// The block as a start and end, open ends widened to the time range's
+    /// limits
+    pub fn interval(&self) -> (DateTime<Utc>, DateTime<Utc>) {
+        (
+            self.start.unwrap_or(DateTime::<Utc>::MIN_UTC),
+            self.end.unwrap_or(DateTime::<Utc>::MAX_UTC),
+        )
+    }
+}
+
+/// Configured working hours, for display
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkingHoursSummary {
+    /// Working days ("mon" to "sun")
+    pub days: Vec<String>,
+    /// Start of the working day (HH:MM)
+    pub start: String,
+    /// End of the working day (HH:MM)
+    pub end: String,
+}
+
+/// What a scheduling assistant needs to know about the user's availability
+#[derive(Debug, Clone, Serialize)]
+pub struct AvailabilityContext {
+    /// Current time in the user's timezone
+    pub now: String,
+
+    /// The user's IANA timezone
+    pub timezone: String,
+
+    /// Configured working hours
+    pub working_hours: WorkingHoursSummary,
+
+    /// Whether it is currently working hours
+    pub in_working_hours: bool,
+
+    /// Whether an out-of-office block or vacation responder is active now
+    pub away_now: bool,
+
+    /// Next time within working hours and outside away blocks (None when
+    /// away with no end set)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_available: Option<DateTime<Utc>>,
+
+    /// Current and upcoming away blocks, soonest first
+    pub away: Vec<AwayBlock>,
+
+    /// Vacation responder settings per account
+    pub accounts: Vec<AccountAvailability>,
+}
+
+/// One account's vacation responder, and when it was fetched
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountAvailability {
+    pub account_id: String,
+
+    /// When the vacation and out-of-office state was fetched
+    pub fetched_at: DateTime<Utc>,
+
+    /// Vacation responder settings (None if Gmail couldn't be asked)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vacation_responder: Option<VacationResponder>,
+}
+
+/// Availability context at `now`
+pub fn availability_context(
+    hours: &WorkingHours,
+    states: &[AvailabilityState],
+    now: DateTime<Utc>,
+) -> AvailabilityContext {
+    let mut away: Vec<AwayBlock> = Vec::new();
+    for state in states {
+        if let Some(vacation) = state.vacation.as_ref().filter(|v| v.enabled) {
+            away.push(vacation_block(&state.account_id, vacation));
+        }
+        away.extend(state.out_of_office.iter().map(|block| AwayBlock {
+            account_id: state.account_id.clone(),
+            source: "calendar",
+            summary: Some(block.summary.clone()),
+            start: Some(block.start),
+            end: Some(block.end),
+        }));
+    }
+    away.retain(|block| block.end.is_none_or(|end| end > now));
+    away.sort_by_key(|block| block.start.unwrap_or(DateTime::<Utc>::MIN_UTC));
+
+    AvailabilityContext {
+        now: now.with_timezone(&hours.timezone).to_rfc3339(),
+        timezone: hours.timezone.name().to_string(),
+        working_hours: WorkingHoursSummary {
+            days: hours
+                .days
+                .iter()
+                .map(|day| day.to_string().to_lowercase())
+                .collect(),
+            start: hours.start.format("%H:%M").to_string(),
+            end: hours.end.format("%H:%M").to_string(),
+        },
+        in_working_hours: hours.contains(now),
+        away_now: away.iter().any(|block| block.contains(now)),
+        next_available: next_available(hours, &away, now),
+        away,
+        accounts: states
+            .iter()
+            .map(|state| AccountAvailability {
+                account_id: state.account_id.clone(),
+                fetched_at: state.fetched_at,
+                vacation_responder: state.vacation.clone(),
+            })
+            .collect(),
+    }
+}
+
+fn vacation_block(account_id: &str, vacation: &VacationResponder) -> AwayBlock {
+    AwayBlock {
+        account_id: account_id.to_string(),
+        source: "vacation_responder",
+        summary: vacation.subject.clone(),
+        start: vacation.start,
+        end: vacation.end,
+    }
+}
+
+/// First working time at or after `now` that isn't in an away block
+fn next_available(
+    hours: &WorkingHours,
+    away: &[AwayBlock],
+    now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    let mut candidate = hours.next_start(now)?;
+    // Each step moves past at least one block, so this ends
+    for _ in 0..=away.len() {
+        match away.iter().find(|block| block.contains(candidate)) {
+            Some(block) => candidate = hours.next_start(block.end?)?,
+            None => return Some(candidate),
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::OutOfOffice;
+
+    fn hours() -> WorkingHours {
+        WorkingHours::from_config(&AvailabilityConfig::default(), "America/New_York").unwrap()
+    }
+
+    fn utc(y: i32, m: u32, d: u32, h: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_working_hours() {
+        let hours = hours();
+        // Monday 2025-03-10 10:00 EDT
+        assert!(hours.contains(utc(2025, 3, 10, 14)));
+        // Monday 20:00 EDT: next start is Tuesday 09:00 EDT
+        assert!(!hours.contains(utc(2025, 3, 11, 0)));
+        assert_eq!(
+            hours.next_start(utc(2025, 3, 11, 0)),
+            Some(utc(2025, 3, 11, 13))
+        );
+        // Saturday: next start is Monday
+        assert_eq!(
+            hours.next_start(utc(2025, 3, 15, 15)),
+            Some(utc(2025, 3, 17, 13))
+        );
+
+        let bad = AvailabilityConfig {
+            work_start: "18:00".to_string(),
+            ..AvailabilityConfig::default()
+        };
+        assert!(WorkingHours::from_config(&bad, "UTC").is_err());
+    }
+
+    #[test]
+    fn test_context_skips_away_blocks() {
+        let now = utc(2025, 3, 10, 14);
+        let state = AvailabilityState {
+            account_id: "me@example.com".to_string(),
+            fetched_at: now,
+            vacation: Some(VacationResponder {
+                enabled: false,
+                start: None,
+                end: None,
+                subject: None,
+                message: None,
+                contacts_only: false,
+                domain_only: false,
+            }),
+            out_of_office: vec![
+                OutOfOffice {
+                    event_id: "past".to_string(),
+                    summary: "Dentist".to_string(),
+                    start: utc(2025, 3, 3, 14),
+                    end: utc(2025, 3, 3, 16),
+                    auto_decline: false,
+                    decline_message: None,
+                },
+                OutOfOffice {
+                    event_id: "trip".to_string(),
+                    summary: "Vacation".to_string(),
+                    start: utc(2025, 3, 10, 4),
+                    end: utc(2025, 3, 13, 4),
+                    auto_decline: true,
+                    decline_message: None,
+                },
+            ],
+        };
+
+        let context = availability_context(&hours(), &[state], now);
+        assert!(context.in_working_hours);
+        assert!(context.away_now);
+        assert_eq!(context.away.len(), 1);
+        // Back Thursday morning
+        assert_eq!(context.next_available, Some(utc(2025, 3, 13, 13)));
+    }
+}
@@ -0,0 +1,698 @@
+//! Bounce and delivery-failure tracking
+//!
+//! Mail servers report undeliverable mail with a bounce (a delivery status
+//! notification) from `mailer-daemon` or `postmaster`. A bounce is parsed
+//! for the recipients it reports on, the enhanced status code and the
+//! receiving server's diagnostic, then linked back to the sent message: by
+//! the Message-ID it quotes or replies to, or else by thread, since Gmail
+//! threads bounces with the original, or by the recipient and subject it
+//! mentions. `groundeffect email send-status` and the thread views use this
+//! to tell whether sent mail was delivered.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Duration, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::db::Database;
+use crate::error::{Error, Result};
+use crate::models::{normalize_message_id, Email};
+
+/// How long after sending a bounce may arrive (servers retry for up to 5 days)
+pub const BOUNCE_WINDOW_DAYS: i64 = 7;
+
+/// Allowance for a bounce's Date header running behind the sent message's
+const CLOCK_SKEW_MINUTES: i64 = 5;
+
+/// Local parts of addresses bounces come from
+const BOUNCE_SENDERS: &[&str] = &["mailer-daemon", "mailerdaemon", "mail-daemon", "postmaster"];
+
+/// Subject prefixes of bounces, lowercase
+const BOUNCE_SUBJECTS: &[&str] = &[
+    "delivery status notification",
+    "undeliverable:",
+    "undelivered mail returned to sender",
+    "undelivered mail",
+    "mail delivery failed",
+    "mail delivery failure",
+    "delivery failure",
+    "delivery has failed",
+    "delivery delayed",
+    "returned mail:",
+    "failure notice",
+    "warning: could not send message",
+    "mail system error",
+];
+
+/// Phrases of a bounce saying delivery is still being retried, lowercase
+const DELAY_MARKERS: &[&str] = &[
+    "(delay)",
+    "delivery delayed",
+    "delivery incomplete",
+    "delivery has been delayed",
+    "has not yet been delivered",
+    "will retry",
+    "will keep trying",
+];
+
+/// Whether a bounce reports a permanent failure or a delay
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BounceKind {
+    /// The message won't be delivered
+    Failed,
+    /// A server is still retrying
+    Delayed,
+}
+
+impl BounceKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Failed => "failed",
+            Self::Delayed => "delayed",
+        }
+    }
+}
+
+/// A parsed bounce
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bounce {
+    /// ID of the bounce email
+    pub email_id: String,
+
+    pub date: DateTime<Utc>,
+
+    pub kind: BounceKind,
+
+    /// Recipients the bounce reports on
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recipients: Vec<String>,
+
+    /// Enhanced status code (e.g. "5.1.1")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+
+    /// The receiving server's explanation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diagnostic: Option<String>,
+
+    /// Message-ID of the bounced message (without angle brackets), when the
+    /// bounce names it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_message_id: Option<String>,
+}
+
+/// Delivery outcome of a sent message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    /// No bounce has come back
+    Sent,
+    /// Delivery to some recipients is being retried
+    Delayed,
+    /// Some recipients bounced
+    Failed,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Sent => "sent",
+            Self::Delayed => "delayed",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+impl std::fmt::Display for DeliveryStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Delivery status of a sent message and the bounces behind it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SendStatus {
+    pub email_id: String,
+
+    pub message_id: String,
+
+    pub account_id: String,
+
+    pub subject: String,
+
+    pub date: DateTime<Utc>,
+
+    pub thread_id: String,
+
+    /// To, Cc and Bcc addresses
+    pub recipients: Vec<String>,
+
+    pub status: DeliveryStatus,
+
+    /// Recipients reported as undeliverable
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failed_recipients: Vec<String>,
+
+    /// Recipients whose delivery is still being retried
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub delayed_recipients: Vec<String>,
+
+    /// Linked bounces, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bounces: Vec<Bounce>,
+}
+
+/// Whether an email is a bounce, from its sender or subject
+pub fn is_bounce(email: &Email) -> bool {
+    if email.is_sent {
+        return false;
+    }
+    let local_part = email
+        .from
+        .email
+        .split('@')
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let subject = email.subject.trim().to_lowercase();
+    BOUNCE_SENDERS.contains(&local_part.as_str())
+        || BOUNCE_SUBJECTS.iter().any(|s| subject.starts_with(s))
+}
+
+/// Parse a bounce; None if the email isn't one
+pub fn parse_bounce(email: &Email) -> Option<Bounce> {
+    if !is_bounce(email) {
+        return None;
+    }
+    let body = if email.body_plain.is_empty() {
+        email.snippet.as_str()
+    } else {
+        email.body_plain.as_str()
+    };
+
+    let status = status_code(body);
+    let kind = match status.as_deref().and_then(|s| s.chars().next()) {
+        Some('5') => BounceKind::Failed,
+        Some('4') => BounceKind::Delayed,
+        _ => {
+            let text = format!("{}\n{}", email.subject, body).to_lowercase();
+            if DELAY_MARKERS.iter().any(|m| text.contains(m)) {
+                BounceKind::Delayed
+            } else {
+                BounceKind::Failed
+            }
+        }
+    };
+
+    let original_message_id = quoted_message_id(body)
+        .or_else(|| email.in_reply_to.clone())
+        .or_else(|| email.references.last().cloned())
+        .map(|id| bare_message_id(&id).to_string())
+        .filter(|id| !id.is_empty());
+
+    Some(Bounce {
+        email_id: email.id.clone(),
+        date: email.date,
+        kind,
+        recipients: reported_recipients(body, email),
+        status,
+        diagnostic: diagnostic(body),
+        original_message_id,
+    })
+}
+
+/// Bounces among `emails` that report on `sent`, oldest first
+///
+/// A bounce naming a Message-ID belongs to that message. One that doesn't
+/// belongs to the latest message sent before it in the same thread (when
+/// the thread is known), or failing that to a message sent shortly before
+/// it whose recipient and subject it mentions.
+pub fn bounces_for(sent: &Email, emails: &[Email]) -> Vec<Bounce> {
+    let sent_id = bare_message_id(&sent.message_id);
+    let recipients = recipients(sent);
+    let skew = Duration::minutes(CLOCK_SKEW_MINUTES);
+
+    let mut bounces: Vec<Bounce> = emails
+        .iter()
+        .filter(|e| e.account_id == sent.account_id)
+        .filter_map(|e| Some((e, parse_bounce(e)?)))
+        .filter(|(email, bounce)| {
+            if let Some(original) = &bounce.original_message_id {
+                return original == sent_id;
+            }
+            if bounce.date < sent.date - skew
+                || bounce.date > sent.date + Duration::days(BOUNCE_WINDOW_DAYS)
+            {
+                return false;
+            }
+            if sent.gmail_thread_id != 0 && email.gmail_thread_id == sent.gmail_thread_id {
+                // A later message sent in the thread would be the one bouncing
+                return !emails.iter().any(|other| {
+                    other.is_sent
+                        && other.gmail_thread_id == sent.gmail_thread_id
+                        && other.date > sent.date
+                        && other.date <= bounce.date + skew
+                });
+            }
+            let body = email.body_plain.to_lowercase();
+            let subject = sent.subject.trim().to_lowercase();
+            !subject.is_empty()
+                && (body.contains(&subject) || email.subject.to_lowercase().contains(&subject))
+                && recipients.iter().any(|r| body.contains(&r.to_lowercase()))
+        })
+        .map(|(email, mut bounce)| {
+            // Bounces that don't list recipients usually still mention them
+            if bounce.recipients.is_empty() {
+                let body = email.body_plain.to_lowercase();
+                bounce.recipients = recipients
+                    .iter()
+                    .filter(|r| body.contains(&r.to_lowercase()))
+                    .cloned()
+                    .collect();
+            }
+            bounce
+        })
+        .collect();
+    bounces.sort_by_key(|b| b.date);
+    bounces
+}
+
+/// Delivery status of `sent` given the bounces among `emails`
+///
+/// A failure without recipients is taken to cover everyone it was sent to.
+pub fn send_status(sent: &Email, emails: &[Email]) -> SendStatus {
+    let recipients = recipients(sent);
+    let bounces = bounces_for(sent, emails);
+
+    let mut failed: Vec<String> = Vec::new();
+    let mut delayed: Vec<String> = Vec::new();
+    for bounce in &bounces {
+        let reported = if bounce.recipients.is_empty() {
+            &recipients
+        } else {
+            &bounce.recipients
+        };
+        let list = match bounce.kind {
+            BounceKind::Failed => &mut failed,
+            BounceKind::Delayed => &mut delayed,
+        };
+        for recipient in reported {
+            if !list.iter().any(|r| r.eq_ignore_ascii_case(recipient)) {
+                list.push(recipient.clone());
+            }
+        }
+    }
+    delayed.retain(|d| !failed.iter().any(|f| f.eq_ignore_ascii_case(d)));
+
+    let status = if !failed.is_empty() {
+        DeliveryStatus::Failed
+    } else if !delayed.is_empty() {
+        DeliveryStatus::Delayed
+    } else {
+        DeliveryStatus::Sent
+    };
+
+    SendStatus {
+        email_id: sent.id.clone(),
+        message_id: sent.message_id.clone(),
+        account_id: sent.account_id.clone(),
+        subject: sent.subject.clone(),
+        date: sent.date,
+        thread_id: sent.gmail_thread_id.to_string(),
+        recipients,
+        status,
+        failed_recipients: failed,
+        delayed_recipients: delayed,
+        bounces,
+    }
+}
+
+/// Sent messages of a thread that bounced, by email ID
+pub fn thread_delivery(emails: &[Email]) -> HashMap<String, SendStatus> {
+    if !emails.iter().any(is_bounce) {
+        return HashMap::new();
+    }
+    emails
+        .iter()
+        .filter(|e| e.is_sent)
+        .map(|e| send_status(e, emails))
+        .filter(|s| s.status != DeliveryStatus::Sent)
+        .map(|s| (s.email_id.clone(), s))
+        .collect()
+}
+
+/// Delivery status of a sent message, by email ID or Message-ID (in any
+/// form [`normalize_message_id`] takes)
+///
+/// Looks for bounces in the message's thread and in mail that arrived within
+/// [`BOUNCE_WINDOW_DAYS`] of sending.
+pub async fn find_send_status(
+    db: &Database,
+    id: &str,
+    account_id: Option<&str>,
+) -> Result<SendStatus> {
+    let sent = match db.get_email(id).await? {
+        Some(email) => Some(email),
+        None => {
+            let message_id = normalize_message_id(id).unwrap_or_else(|| id.to_string());
+            let mut emails = db.get_emails_by_message_id(&message_id, account_id).await?;
+            match emails.iter().position(|e| e.is_sent) {
+                Some(i) => Some(emails.swap_remove(i)),
+                None => emails.into_iter().next(),
+            }
+        }
+    }
+    .ok_or_else(|| Error::EmailNotFound(id.to_string()))?;
+    if !sent.is_sent {
+        return Err(Error::InvalidRequest(format!(
+            "{} is not a message you sent",
+            id
+        )));
+    }
+
+    let mut emails = if sent.gmail_thread_id != 0 {
+        db.get_emails_by_thread(sent.gmail_thread_id, Some(&sent.account_id))
+            .await?
+    } else {
+        Vec::new()
+    };
+    let nearby = db
+        .list_emails_between(
+            &sent.account_id,
+            sent.date - Duration::minutes(CLOCK_SKEW_MINUTES),
+            sent.date + Duration::days(BOUNCE_WINDOW_DAYS),
+        )
+        .await?;
+    for email in nearby {
+        if !emails.iter().any(|e| e.id == email.id) {
+            emails.push(email);
+        }
+    }
+
+    Ok(send_status(&sent, &emails))
+}
+
+/// To, Cc and Bcc addresses of a message
+fn recipients(email: &Email) -> Vec<String> {
+    let mut recipients: Vec<String> = Vec::new();
+    for address in email.to.iter().chain(&email.cc).chain(&email.bcc) {
+        if !address.email.is_empty()
+            && !recipients
+                .iter()
+                .any(|r| r.eq_ignore_ascii_case(&address.email))
+        {
+            recipients.push(address.email.clone());
+        }
+    }
+    recipients
+}
+
+fn bare_message_id(id: &str) -> &str {
+    id.trim().trim_start_matches('<').trim_end_matches('>')
+}
+
+/// Address pattern used inside the recipient patterns
+const ADDRESS: &str = r"[A-Za-z0-9._%+'=-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}";
+
+/// Recipients a bounce says it couldn't deliver to
+fn reported_recipients(body: &str, bounce: &Email) -> Vec<String> {
+    static PATTERNS: OnceLock<Vec<Regex>> = OnceLock::new();
+    let patterns = PATTERNS.get_or_init(|| {
+        [
+            // Delivery status fields
+            format!(
+                r"(?im)^\s*(?:final|original)-recipient:\s*rfc822;\s*<?({})",
+                ADDRESS
+            ),
+            // Gmail and Exchange
+            format!(
+                r"(?i)(?:wasn't|was not|couldn't be|could not be|not) delivered to\s+<?({})",
+                ADDRESS
+            ),
+            format!(r"(?i)delivering your message to\s+<?({})", ADDRESS),
+            format!(
+                r"(?i)your message to\s+<?({})>?\s+couldn't be delivered",
+                ADDRESS
+            ),
+            // Postfix ("<bob@example.com>: host ... said: ...")
+            format!(r"(?m)^\s*<({})>:", ADDRESS),
+            // Exim ("The following address(es) failed:" then one per line)
+            format!(r"(?m)^\s{{2,}}<?({})>?\s*$", ADDRESS),
+        ]
+        .iter()
+        .map(|pattern| Regex::new(pattern).unwrap())
+        .collect()
+    });
+
+    let mut recipients: Vec<String> = Vec::new();
+    for pattern in patterns {
+        for caps in pattern.captures_iter(body) {
+            let address = &caps[1];
+            let own = address.eq_ignore_ascii_case(&bounce.account_id)
+                || address.eq_ignore_ascii_case(&bounce.from.email);
+            if !own && !recipients.iter().any(|r| r.eq_ignore_ascii_case(address)) {
+                recipients.push(address.to_string());
+            }
+        }
+    }
+    recipients
+}
+
+/// Enhanced status code of a failure (4.x.x or 5.x.x)
+fn status_code(body: &str) -> Option<String> {
+    static STATUS_FIELD: OnceLock<Regex> = OnceLock::new();
+    static CODE: OnceLock<Regex> = OnceLock::new();
+    let field = STATUS_FIELD
+        .get_or_init(|| Regex::new(r"(?im)^\s*status:\s*([45]\.\d{1,3}\.\d{1,3})\b").unwrap());
+    if let Some(caps) = field.captures(body) {
+        return Some(caps[1].to_string());
+    }
+    let code =
+        CODE.get_or_init(|| Regex::new(r"(?m)(?:^|[\s(\[#])([45]\.\d{1,3}\.\d{1,3})\b").unwrap());
+    code.captures_iter(body)
+        .map(|caps| caps.get(1).unwrap())
+        // Skip the tail of an IP address or version number
+        .find(|m| {
+            let rest = &body[m.end()..];
+            !(rest.starts_with('.') && rest[1..].starts_with(|c: char| c.is_ascii_digit()))
+        })
+        .map(|m| m.as_str().to_string())
+}
+
+/// The receiving server's response, shortened to one line
+fn diagnostic(body: &str) -> Option<String> {
+    static PATTERNS: OnceLock<Vec<Regex>> = OnceLock::new();
+    let patterns = PATTERNS.get_or_init(|| {
+        [
+            r"(?im)^\s*diagnostic-code:\s*(?:smtp;\s*)?(\S.*)$",
+            r"(?i)the response (?:from the remote server )?was:\s*(\S[^\n]*)",
+            r"(?im)^\s*remote server returned\s+'?(\S[^\n]*)",
+        ]
+        .iter()
+        .map(|pattern| Regex::new(pattern).unwrap())
+        .collect()
+    });
+    patterns
+        .iter()
+        .find_map(|pattern| pattern.captures(body))
+        .map(|caps| {
+            caps[1]
+                .trim()
+                .trim_end_matches('\'')
+                .chars()
+                .take(300)
+                .collect::<String>()
+        })
+        .filter(|d| !d.is_empty())
+}
+
+/// Message-ID from original headers quoted in the bounce
+fn quoted_message_id(body: &str) -> Option<String> {
+    static MESSAGE_ID: OnceLock<Regex> = OnceLock::new();
+    let message_id =
+        MESSAGE_ID.get_or_init(|| Regex::new(r"(?im)^\s*message-id:\s*<([^>\s]+)>").unwrap());
+    message_id.captures(body).map(|caps| caps[1].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Address;
+    use chrono::TimeZone;
+
+    fn email(id: &str, from: &str, to: &str, subject: &str, minute: u32, body: &str) -> Email {
+        Email {
+            gmail_thread_id: 7,
+            is_sent: from == "me@example.com",
+            to: vec![Address::new(to)],
+            date: Utc.with_ymd_and_hms(2024, 6, 3, 9, minute, 0).unwrap(),
+            body_plain: body.to_string(),
+            ..Email::test(id, from, subject)
+        }
+    }
+
+    const GMAIL_BOUNCE: &str = "** Address not found **\n\nYour message wasn't delivered to bob@acme.io because the address couldn't be found, or is unable to receive mail.\n\nThe response from the remote server was:\n550 5.1.1 The email account that you tried to reach does not exist.";
+
+    #[test]
+    fn test_parse_gmail_bounce() {
+        let mut bounce = email(
+            "b1@mx.google.com",
+            "mailer-daemon@googlemail.com",
+            "me@example.com",
+            "Delivery Status Notification (Failure)",
+            1,
+            GMAIL_BOUNCE,
+        );
+        bounce.in_reply_to = Some("<s1@example.com>".to_string());
+
+        let parsed = parse_bounce(&bounce).unwrap();
+        assert_eq!(parsed.kind, BounceKind::Failed);
+        assert_eq!(parsed.recipients, vec!["bob@acme.io"]);
+        assert_eq!(parsed.status.as_deref(), Some("5.1.1"));
+        assert!(parsed
+            .diagnostic
+            .unwrap()
+            .starts_with("550 5.1.1 The email account"));
+        assert_eq!(
+            parsed.original_message_id.as_deref(),
+            Some("s1@example.com")
+        );
+
+        let normal = email("n1", "bob@acme.io", "me@example.com", "Lunch?", 1, "");
+        assert!(parse_bounce(&normal).is_none());
+    }
+
+    #[test]
+    fn test_parse_delay_and_dsn_fields() {
+        let delay = email(
+            "b2",
+            "postmaster@mail.acme.io",
+            "me@example.com",
+            "Delayed Mail (still being retried)",
+            1,
+            "This is the mail system at host mail.acme.io.\n\nFinal-Recipient: rfc822; ann@slow.example\nAction: delayed\nStatus: 4.4.1\nDiagnostic-Code: X-Postfix; connect to mx.slow.example[10.4.4.1]:25: Connection timed out\n\nMessage-ID: <s2@example.com>",
+        );
+        let parsed = parse_bounce(&delay).unwrap();
+        assert_eq!(parsed.kind, BounceKind::Delayed);
+        assert_eq!(parsed.recipients, vec!["ann@slow.example"]);
+        assert_eq!(parsed.status.as_deref(), Some("4.4.1"));
+        assert!(parsed.diagnostic.unwrap().starts_with("X-Postfix; connect"));
+        assert_eq!(
+            parsed.original_message_id.as_deref(),
+            Some("s2@example.com")
+        );
+
+        // An IP address isn't a status code
+        assert_eq!(status_code("connect to [10.5.1.1]: refused"), None);
+        assert_eq!(
+            status_code("said: 550 5.7.1 rejected"),
+            Some("5.7.1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_send_status_links_bounces() {
+        let first = email(
+            "s1@example.com",
+            "me@example.com",
+            "bob@acme.io",
+            "Q3 pricing",
+            0,
+            "Hi Bob",
+        );
+        let mut bounce = email(
+            "b1@mx.google.com",
+            "mailer-daemon@googlemail.com",
+            "me@example.com",
+            "Delivery Status Notification (Failure)",
+            1,
+            GMAIL_BOUNCE,
+        );
+        bounce.in_reply_to = Some("s1@example.com".to_string());
+        let mut resend = email(
+            "s3@example.com",
+            "me@example.com",
+            "robert@acme.io",
+            "Re: Q3 pricing",
+            10,
+            "Resending",
+        );
+        resend.cc = vec![Address::new("bob@acme.io")];
+        let emails = vec![first.clone(), bounce, resend.clone()];
+
+        let status = send_status(&first, &emails);
+        assert_eq!(status.status, DeliveryStatus::Failed);
+        assert_eq!(status.failed_recipients, vec!["bob@acme.io"]);
+        assert_eq!(status.bounces.len(), 1);
+
+        // The bounce names the first message, not the resend
+        assert_eq!(send_status(&resend, &emails).status, DeliveryStatus::Sent);
+        let delivery = thread_delivery(&emails);
+        assert_eq!(delivery.len(), 1);
+        assert!(delivery.contains_key(&first.id));
+    }
+
+    #[test]
+    fn test_bounce_without_message_id_matches_recipient_and_subject() {
+        let mut first = email("s1", "me@example.com", "bob@acme.io", "Q3 pricing", 0, "Hi");
+        let mut second = email(
+            "s2",
+            "me@example.com",
+            "ann@acme.io",
+            "Re: Q3 pricing",
+            5,
+            "Hi",
+        );
+        let mut bounce = email(
+            "b1",
+            "postmaster@acme.io",
+            "me@example.com",
+            "Undeliverable: Re: Q3 pricing",
+            6,
+            "Delivery has failed to these recipients or groups:\n\nann@acme.io\n\nSubject: Re: Q3 pricing",
+        );
+        // Unknown thread: only the message whose recipient the bounce names
+        // is linked
+        for e in [&mut first, &mut second, &mut bounce] {
+            e.gmail_thread_id = 0;
+        }
+        let emails = vec![first.clone(), second.clone(), bounce];
+
+        assert_eq!(send_status(&first, &emails).status, DeliveryStatus::Sent);
+        let status = send_status(&second, &emails);
+        assert_eq!(status.status, DeliveryStatus::Failed);
+        assert_eq!(status.failed_recipients, vec!["ann@acme.io"]);
+    }
+
+    #[test]
+    fn test_thread_bounce_without_message_id() {
+        let first = email("s1", "me@example.com", "bob@acme.io", "Q3 pricing", 0, "Hi");
+        let second = email(
+            "s2",
+            "me@example.com",
+            "ann@acme.io",
+            "Re: Q3 pricing",
+            5,
+            "Hi",
+        );
+        let bounce = email(
+            "b1",
+            "postmaster@acme.io",
+            "me@example.com",
+            "Undeliverable: Re: Q3 pricing",
+            6,
+            "Delivery has failed to these recipients or groups.",
+        );
+        let emails = vec![first.clone(), second.clone(), bounce];
+
+        // Only the latest message sent before the bounce is linked, and with
+        // no recipients listed it counts for everyone it went to
+        assert_eq!(send_status(&first, &emails).status, DeliveryStatus::Sent);
+        let status = send_status(&second, &emails);
+        assert_eq!(status.status, DeliveryStatus::Failed);
+        assert_eq!(status.failed_recipients, vec!["ann@acme.io"]);
+    }
+}
@@ -0,0 +1,361 @@
+//! Email category classification
+//!
+//! Classifies emails as personal, newsletter, notification, or transactional.
+//! Header and sender heuristics run first at parse time; emails they can't
+//! decide on are resolved by comparing the email embedding against category
+//! prototype embeddings once embeddings are available.
+
+use tracing::{debug, warn};
+
+use crate::embedding::HybridEmbeddingProvider;
+use crate::error::Result;
+use crate::models::{Email, EmailCategory};
+
+/// Minimum cosine similarity lead a non-personal prototype needs over the
+/// personal prototype before the embedding classifier overrides "personal"
+const EMBEDDING_MIN_MARGIN: f32 = 0.03;
+
+/// Subject fragments that indicate receipts, orders, and account mail
+const TRANSACTIONAL_SUBJECT_HINTS: &[&str] = &[
+    "receipt",
+    "invoice",
+    "order confirmation",
+    "your order",
+    "order #",
+    "has shipped",
+    "shipping confirmation",
+    "delivery confirmation",
+    "payment received",
+    "payment confirmation",
+    "booking confirmation",
+    "reservation confirmed",
+    "password reset",
+    "reset your password",
+    "verification code",
+    "verify your email",
+    "confirm your email",
+    "your statement",
+    "refund",
+];
+
+/// Sender local-part fragments used by automated notification systems
+const NOTIFICATION_SENDER_HINTS: &[&str] = &[
+    "notification",
+    "notifications",
+    "notify",
+    "alert",
+    "alerts",
+    "mailer-daemon",
+    "postmaster",
+    "calendar-notification",
+    "builds",
+    "jira",
+];
+
+/// Sender local-part fragments used by newsletters and marketing mail
+const NEWSLETTER_SENDER_HINTS: &[&str] = &[
+    "newsletter",
+    "news",
+    "digest",
+    "marketing",
+    "promo",
+    "promotions",
+    "offers",
+    "deals",
+    "updates",
+    "weekly",
+];
+
+/// Sender local-parts that are never a human
+const NO_REPLY_SENDER_HINTS: &[&str] = &[
+    "noreply",
+    "no-reply",
+    "no_reply",
+    "donotreply",
+    "do-not-reply",
+    "do_not_reply",
+];
+
+/// Header and sender signals extracted from a raw message
+#[derive(Debug, Default, Clone)]
+pub struct CategorySignals<'a> {
+    /// Sender email address
+    pub from_email: &'a str,
+    /// Subject line
+    pub subject: &'a str,
+    /// List-Unsubscribe header present
+    pub has_list_unsubscribe: bool,
+    /// List-Id header present
+    pub has_list_id: bool,
+    /// Precedence header value (bulk, list, junk)
+    pub precedence: Option<&'a str>,
+    /// Auto-Submitted header value (auto-generated, auto-replied)
+    pub auto_submitted: Option<&'a str>,
+}
+
+impl<'a> CategorySignals<'a> {
+    /// Build signals from a parsed message
+    pub fn from_message(message: &'a mail_parser::Message<'a>, from_email: &'a str) -> Self {
+        let header_text = |name: &'static str| -> Option<&'a str> {
+            message
+                .header_raw(name)
+                .map(|v| v.trim())
+                .filter(|v| !v.is_empty())
+        };
+
+        Self {
+            from_email,
+            subject: message.subject().unwrap_or(""),
+            has_list_unsubscribe: header_text("List-Unsubscribe").is_some(),
+            has_list_id: header_text("List-Id").is_some(),
+            precedence: header_text("Precedence"),
+            auto_submitted: header_text("Auto-Submitted"),
+        }
+    }
+}
+
+fn sender_local_part(from_email: &str) -> String {
+    from_email.split('@').next().unwrap_or("").to_lowercase()
+}
+
+fn local_part_matches(local: &str, hints: &[&str]) -> bool {
+    local
+        .split(|c: char| c == '.' || c == '+' || c == '_' || c == '-')
+        .chain(std::iter::once(local))
+        .any(|token| hints.contains(&token))
+}
+
+/// Classify using headers and sender heuristics.
+///
+/// Returns None when there is no automated-mail signal; the caller decides
+/// between personal and the embedding classifier in that case.
+pub fn classify_heuristic(signals: &CategorySignals<'_>) -> Option<EmailCategory> {
+    let local = sender_local_part(signals.from_email);
+    let subject = signals.subject.to_lowercase();
+    let no_reply = NO_REPLY_SENDER_HINTS
+        .iter()
+        .any(|hint| local.contains(hint));
+    let auto_submitted = signals
+        .auto_submitted
+        .map(|v| !v.eq_ignore_ascii_case("no"))
+        .unwrap_or(false);
+    let bulk_precedence = signals
+        .precedence
+        .map(|p| {
+            let p = p.to_lowercase();
+            p == "bulk" || p == "list" || p == "junk"
+        })
+        .unwrap_or(false);
+    let is_list = signals.has_list_unsubscribe || signals.has_list_id || bulk_precedence;
+
+    // Receipts and account mail often carry List-Unsubscribe, so check them first
+    if TRANSACTIONAL_SUBJECT_HINTS
+        .iter()
+        .any(|hint| subject.contains(hint))
+    {
+        return Some(EmailCategory::Transactional);
+    }
+
+    if auto_submitted || local_part_matches(&local, NOTIFICATION_SENDER_HINTS) {
+        return Some(EmailCategory::Notification);
+    }
+
+    if is_list || local_part_matches(&local, NEWSLETTER_SENDER_HINTS) {
+        return Some(EmailCategory::Newsletter);
+    }
+
+    if no_reply {
+        return Some(EmailCategory::Notification);
+    }
+
+    None
+}
+
+/// Prototype descriptions embedded once and compared against email embeddings
+fn prototype_texts() -> Vec<(EmailCategory, &'static str)> {
+    vec![
+        (
+            EmailCategory::Personal,
+            "Hi, thanks for getting back to me. Are you free to chat tomorrow? Let me know what you think. Best, Sam",
+        ),
+        (
+            EmailCategory::Newsletter,
+            "This week's newsletter: top stories, featured articles, and exclusive offers. Read more on our blog. Unsubscribe or update your preferences.",
+        ),
+        (
+            EmailCategory::Notification,
+            "You have a new notification. Someone commented on your post, your build failed, a new sign-in was detected, or your report is ready.",
+        ),
+        (
+            EmailCategory::Transactional,
+            "Thank you for your order. Your receipt, invoice, and payment confirmation are below. Order number, total charged, shipping and delivery details.",
+        ),
+    ]
+}
+
+pub(crate) fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+/// Embedding-based classifier using one prototype vector per category
+pub struct CategoryClassifier {
+    prototypes: Vec<(EmailCategory, Vec<f32>)>,
+}
+
+impl CategoryClassifier {
+    /// Build prototypes using the configured embedding provider.
+    ///
+    /// Returns None when embeddings are unavailable (BM25-only mode).
+    pub async fn new(embedding: &HybridEmbeddingProvider) -> Result<Option<Self>> {
+        let prototypes = prototype_texts();
+        let texts: Vec<String> = prototypes.iter().map(|(_, t)| t.to_string()).collect();
+
+        let Some(vectors) = embedding.embed_batch(&texts).await? else {
+            debug!("Embeddings unavailable, category classifier uses heuristics only");
+            return Ok(None);
+        };
+
+        Ok(Some(Self::from_prototypes(
+            prototypes
+                .into_iter()
+                .map(|(category, _)| category)
+                .zip(vectors)
+                .collect(),
+        )))
+    }
+
+    /// Build from precomputed prototype vectors
+    pub fn from_prototypes(prototypes: Vec<(EmailCategory, Vec<f32>)>) -> Self {
+        Self { prototypes }
+    }
+
+    /// Classify an email embedding, biased toward personal when uncertain
+    pub fn classify(&self, embedding: &[f32]) -> EmailCategory {
+        let score_of = |category: EmailCategory| -> f32 {
+            self.prototypes
+                .iter()
+                .find(|(c, _)| *c == category)
+                .map(|(_, v)| cosine_similarity(embedding, v))
+                .unwrap_or(0.0)
+        };
+
+        let personal = score_of(EmailCategory::Personal);
+        let best = self
+            .prototypes
+            .iter()
+            .filter(|(c, _)| *c != EmailCategory::Personal)
+            .map(|(c, v)| (*c, cosine_similarity(embedding, v)))
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+
+        match best {
+            Some((category, score)) if score - personal >= EMBEDDING_MIN_MARGIN => category,
+            _ => EmailCategory::Personal,
+        }
+    }
+}
+
+/// Fill in categories the heuristics left undecided.
+///
+/// Uses the embedding classifier when both it and the email embedding are
+/// available, otherwise defaults to personal.
+pub fn resolve_categories(emails: &mut [Email], classifier: Option<&CategoryClassifier>) {
+    for email in emails.iter_mut().filter(|e| e.category.is_none()) {
+        let category = match (classifier, email.embedding.as_deref()) {
+            (Some(classifier), Some(embedding)) => classifier.classify(embedding),
+            _ => EmailCategory::Personal,
+        };
+        email.category = Some(category);
+    }
+}
+
+/// Build the embedding classifier, logging instead of failing sync
+pub async fn build_classifier(embedding: &HybridEmbeddingProvider) -> Option<CategoryClassifier> {
+    match CategoryClassifier::new(embedding).await {
+        Ok(classifier) => classifier,
+        Err(e) => {
+            warn!("Failed to build category classifier: {}", e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signals<'a>(from_email: &'a str, subject: &'a str) -> CategorySignals<'a> {
+        CategorySignals {
+            from_email,
+            subject,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn plain_sender_is_undecided() {
+        assert_eq!(
+            classify_heuristic(&signals("alice@example.com", "Lunch?")),
+            None
+        );
+    }
+
+    #[test]
+    fn list_unsubscribe_marks_newsletter() {
+        let mut s = signals("alice@substack.com", "My weekly thoughts");
+        s.has_list_unsubscribe = true;
+        assert_eq!(classify_heuristic(&s), Some(EmailCategory::Newsletter));
+    }
+
+    #[test]
+    fn bulk_precedence_marks_newsletter() {
+        let mut s = signals("team@example.com", "Product update");
+        s.precedence = Some("bulk");
+        assert_eq!(classify_heuristic(&s), Some(EmailCategory::Newsletter));
+    }
+
+    #[test]
+    fn receipt_subject_wins_over_list_headers() {
+        let mut s = signals("orders@shop.example", "Your receipt from Shop");
+        s.has_list_unsubscribe = true;
+        assert_eq!(classify_heuristic(&s), Some(EmailCategory::Transactional));
+    }
+
+    #[test]
+    fn notification_sender_marks_notification() {
+        let mut s = signals("notifications@github.com", "[repo] New issue");
+        s.has_list_id = true;
+        assert_eq!(classify_heuristic(&s), Some(EmailCategory::Notification));
+    }
+
+    #[test]
+    fn auto_submitted_marks_notification() {
+        let mut s = signals("someone@example.com", "Out of office");
+        s.auto_submitted = Some("auto-replied");
+        assert_eq!(classify_heuristic(&s), Some(EmailCategory::Notification));
+    }
+
+    #[test]
+    fn no_reply_sender_marks_notification() {
+        assert_eq!(
+            classify_heuristic(&signals("no-reply@service.example", "Weekly report ready")),
+            Some(EmailCategory::Notification)
+        );
+    }
+
+    #[test]
+    fn embedding_classifier_requires_margin() {
+        let classifier = CategoryClassifier::from_prototypes(vec![
+            (EmailCategory::Personal, vec![1.0, 0.0]),
+            (EmailCategory::Newsletter, vec![0.0, 1.0]),
+        ]);
+        assert_eq!(classifier.classify(&[0.1, 1.0]), EmailCategory::Newsletter);
+        assert_eq!(classifier.classify(&[1.0, 1.0]), EmailCategory::Personal);
+    }
+}
@@ -1,11 +1,24 @@
 //! Configuration management for GroundEffect
 
+use crate::config_layers::{Layers, DAEMON_SECTION};
+use crate::digest::DigestPeriod;
+use crate::embedding::EmbeddingModel;
 use crate::error::{Error, Result};
+use crate::mcp::McpCapability;
+use crate::models::{ActivitySource, EmailCategory};
+use crate::signatures::Signature;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 use tracing::info;
 
+/// Environment variable selecting the active profile
+pub const PROFILE_ENV: &str = "GROUNDEFFECT_PROFILE";
+
+/// Active profile, resolved once per process
+static ACTIVE_PROFILE: OnceLock<Option<String>> = OnceLock::new();
+
 /// Main configuration structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -21,10 +34,82 @@ pub struct Config {
     #[serde(default)]
     pub search: SearchConfig,
 
+    /// Full-text index maintenance
+    #[serde(default)]
+    pub index: IndexConfig,
+
     /// UI settings
     #[serde(default)]
     pub ui: UiConfig,
 
+    /// Outgoing mail settings
+    #[serde(default)]
+    pub compose: ComposeConfig,
+
+    /// SaaS notification parsing settings
+    #[serde(default)]
+    pub activity: ActivityConfig,
+
+    /// Receipt and invoice extraction settings
+    #[serde(default)]
+    pub receipts: ReceiptsConfig,
+
+    /// Travel itinerary settings
+    #[serde(default)]
+    pub travel: TravelConfig,
+
+    /// Reservation and package delivery extraction settings
+    #[serde(default)]
+    pub extractions: ExtractionsConfig,
+
+    /// Action item extraction settings
+    #[serde(default)]
+    pub tasks: TasksConfig,
+
+    /// Local tagging rules applied during sync
+    #[serde(default)]
+    pub tagging: TaggingConfig,
+
+    /// Scheduled digest emails
+    #[serde(default)]
+    pub digest: DigestConfig,
+
+    /// ICS feed of synced calendars published by the daemon
+    #[serde(default)]
+    pub ics_feed: IcsFeedConfig,
+
+    /// Local read-only CalDAV server for the merged calendar
+    #[serde(default)]
+    pub caldav: CaldavConfig,
+
+    /// Prometheus metrics for daemon health
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+
+    /// One-way export to macOS Calendar and Contacts
+    #[serde(default)]
+    pub apple: AppleConfig,
+
+    /// Spotlight stubs for synced emails
+    #[serde(default)]
+    pub spotlight: SpotlightConfig,
+
+    /// Working hours and out-of-office lookups
+    #[serde(default)]
+    pub availability: AvailabilityConfig,
+
+    /// Embedding text templates
+    #[serde(default)]
+    pub embedding: EmbeddingConfig,
+
+    /// Workspace directory settings
+    #[serde(default)]
+    pub directory: DirectoryConfig,
+
+    /// MCP server settings
+    #[serde(default)]
+    pub mcp: McpConfig,
+
     /// Account aliases
     #[serde(default)]
     pub accounts: AccountsConfig,
@@ -40,7 +125,25 @@ impl Default for Config {
             general: GeneralConfig::default(),
             sync: SyncConfig::default(),
             search: SearchConfig::default(),
+            index: IndexConfig::default(),
             ui: UiConfig::default(),
+            compose: ComposeConfig::default(),
+            activity: ActivityConfig::default(),
+            receipts: ReceiptsConfig::default(),
+            travel: TravelConfig::default(),
+            extractions: ExtractionsConfig::default(),
+            tasks: TasksConfig::default(),
+            tagging: TaggingConfig::default(),
+            digest: DigestConfig::default(),
+            ics_feed: IcsFeedConfig::default(),
+            caldav: CaldavConfig::default(),
+            metrics: MetricsConfig::default(),
+            apple: AppleConfig::default(),
+            spotlight: SpotlightConfig::default(),
+            availability: AvailabilityConfig::default(),
+            embedding: EmbeddingConfig::default(),
+            directory: DirectoryConfig::default(),
+            mcp: McpConfig::default(),
             accounts: AccountsConfig::default(),
             tokens: TokenProviderConfig::default(),
         }
@@ -75,6 +178,19 @@ impl Default for Config {
 /// database_url_env = "DATABASE_URL"
 /// encryption_key_env = "GE_TOKEN_ENCRYPTION_KEY"
 /// ```
+///
+/// Secret Service keyring (Linux desktops, via libsecret's `secret-tool`):
+/// ```toml
+/// [tokens]
+/// provider = "secret_service"
+/// ```
+///
+/// Encrypted files (headless servers):
+/// ```toml
+/// [tokens]
+/// provider = "encrypted_file"
+/// encryption_key_env = "GE_TOKEN_ENCRYPTION_KEY"
+/// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "provider", rename_all = "snake_case")]
 pub enum TokenProviderConfig {
@@ -136,6 +252,22 @@ pub enum TokenProviderConfig {
         #[serde(skip_serializing_if = "Option::is_none")]
         user_id_env: Option<String>,
     },
+
+    /// Freedesktop Secret Service keyring (GNOME Keyring, KWallet, ...)
+    /// Requires libsecret's `secret-tool` on PATH
+    SecretService,
+
+    /// AES-256-GCM encrypted token files
+    /// Stores tokens in ~/.config/groundeffect/tokens/<account>.enc
+    EncryptedFile {
+        /// Environment variable containing the encryption key
+        #[serde(default = "default_token_encryption_key_env")]
+        encryption_key_env: String,
+    },
+}
+
+fn default_token_encryption_key_env() -> String {
+    "GE_TOKEN_ENCRYPTION_KEY".to_string()
 }
 
 impl Default for TokenProviderConfig {
@@ -151,6 +283,10 @@ pub struct GeneralConfig {
     #[serde(default = "default_log_level")]
     pub log_level: String,
 
+    /// Format of the daemon and MCP server log files (see [`crate::logs`])
+    #[serde(default)]
+    pub log_format: LogFormat,
+
     /// Log file path
     #[serde(default = "default_log_file")]
     pub log_file: PathBuf,
@@ -174,6 +310,7 @@ impl Default for GeneralConfig {
     fn default() -> Self {
         Self {
             log_level: default_log_level(),
+            log_format: LogFormat::default(),
             log_file: default_log_file(),
             data_dir: default_data_dir(),
             timezone: default_timezone(),
@@ -181,6 +318,17 @@ impl Default for GeneralConfig {
     }
 }
 
+/// How log lines are written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    /// `2024-06-03 09:00:00.123  INFO ThreadId(02) target: message`
+    #[default]
+    Text,
+    /// One JSON object per line, with an RFC 3339 timestamp
+    Json,
+}
+
 /// Sync settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SyncConfig {
@@ -196,7 +344,7 @@ pub struct SyncConfig {
     #[serde(default = "default_poll_interval")]
     pub calendar_poll_interval_secs: u64,
 
-    /// Max parallel email fetches per account
+    /// Max account syncs (email or calendar) running at once
     #[serde(default = "default_concurrent_fetches")]
     pub max_concurrent_fetches: usize,
 
@@ -207,6 +355,41 @@ pub struct SyncConfig {
     /// Global rate limit (requests per second)
     #[serde(default = "default_rate_limit")]
     pub rate_limit_per_second: u32,
+
+    /// How often the daemon checks that each account's tokens still refresh
+    /// (seconds, 0 disables)
+    #[serde(default = "default_token_check_interval")]
+    pub token_check_interval_secs: u64,
+
+    /// Post a desktop notification when an account needs re-authentication
+    #[serde(default = "default_true")]
+    pub reauth_notifications: bool,
+
+    /// Release the embedding model, IMAP IDLE connections and caches after
+    /// this long without queries or sync work (seconds, 0 disables)
+    #[serde(default = "default_shed_idle_after")]
+    pub shed_idle_after_secs: u64,
+
+    /// Keep a gzip-compressed copy of each synced message's raw source, so
+    /// `email raw` doesn't need to fetch it
+    #[serde(default)]
+    pub store_raw: bool,
+
+    /// How often the daemon prunes mail past each account's retention
+    /// policy (seconds, 0 disables)
+    #[serde(default = "default_retention_interval")]
+    pub retention_interval_secs: u64,
+
+    /// The daemon's watchdog restarts an account whose sync makes no
+    /// progress for this long, or whose IMAP IDLE goes this long past its
+    /// renewal (seconds, 0 disables the watchdog)
+    #[serde(default = "default_stall_timeout")]
+    pub stall_timeout_secs: u64,
+
+    /// The watchdog also restarts an account after this many syncs in a
+    /// row fail with the same error (0 disables)
+    #[serde(default = "default_stall_error_repeats")]
+    pub stall_error_repeats: u32,
 }
 
 impl Default for SyncConfig {
@@ -218,6 +401,13 @@ impl Default for SyncConfig {
             max_concurrent_fetches: 10,
             attachment_max_size_mb: 100,
             rate_limit_per_second: 10,
+            token_check_interval_secs: 3600,
+            reauth_notifications: true,
+            shed_idle_after_secs: 3600,
+            store_raw: false,
+            retention_interval_secs: 86400,
+            stall_timeout_secs: default_stall_timeout(),
+            stall_error_repeats: default_stall_error_repeats(),
         }
     }
 }
@@ -247,11 +437,172 @@ pub enum EmbeddingProvider {
     /// Use the local Candle model
     Local,
     /// Use OpenRouter embeddings API
+    #[serde(rename = "openrouter", alias = "open_router")]
     OpenRouter,
+    /// Use the OpenAI embeddings API (or any OpenAI-compatible endpoint)
+    #[serde(rename = "openai", alias = "open_ai")]
+    OpenAi,
+    /// Use an Ollama server's `/api/embed`
+    Ollama,
     /// Use custom remote `/embed` HTTP service (legacy/default when embedding_url is set)
     Remote,
 }
 
+impl EmbeddingProvider {
+    /// Name used in config and status output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Local => "local",
+            Self::OpenRouter => "openrouter",
+            Self::OpenAi => "openai",
+            Self::Ollama => "ollama",
+            Self::Remote => "remote",
+        }
+    }
+
+    /// Parse a provider name (as accepted in config)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "local" => Some(Self::Local),
+            "openrouter" | "open_router" => Some(Self::OpenRouter),
+            "openai" | "open_ai" => Some(Self::OpenAi),
+            "ollama" => Some(Self::Ollama),
+            "remote" => Some(Self::Remote),
+            _ => None,
+        }
+    }
+}
+
+/// Overrides for the remote embedding provider (`[search.embedding_api]`)
+///
+/// Every field is optional; unset fields use the provider's defaults (see
+/// [`SearchConfig::embedding_endpoint`]).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EmbeddingApiConfig {
+    /// Base URL (e.g. `https://api.openai.com/v1`, `http://localhost:11434`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    /// Model identifier sent with each request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+
+    /// Environment variable holding the API key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key_env: Option<String>,
+
+    /// Header carrying the API key. `Authorization` sends `Bearer <key>`;
+    /// any other header (e.g. Azure's `api-key`) sends the key as is
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_header: Option<String>,
+
+    /// Extra headers sent with every request
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+
+    /// Ask OpenAI-compatible models for vectors the size of the stored
+    /// column (default: on for `text-embedding-3` models)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub send_dimensions: Option<bool>,
+
+    /// Width of the vectors the model returns. When it differs from the
+    /// stored column, the daemon rebuilds the column and re-embeds
+    /// (default: keep the column's width)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dimensions: Option<usize>,
+
+    /// Most texts per request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_batch_size: Option<usize>,
+
+    /// Longer texts are cut to this many characters (0 = never)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_input_chars: Option<usize>,
+
+    /// Tries per request for network errors, 429 and 5xx responses
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_attempts: Option<u32>,
+
+    /// Wait before the first retry, doubled for each later one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_backoff_ms: Option<u64>,
+}
+
+/// Fully resolved remote embedding settings
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmbeddingEndpoint {
+    pub provider: EmbeddingProvider,
+    pub url: String,
+    pub model: String,
+    pub api_key_env: Option<String>,
+    pub auth_header: String,
+    pub headers: HashMap<String, String>,
+    pub send_dimensions: bool,
+    pub dimensions: Option<usize>,
+    pub max_batch_size: usize,
+    pub max_input_chars: usize,
+    pub max_attempts: u32,
+    pub retry_backoff_ms: u64,
+    pub timeout_ms: u64,
+}
+
+/// Numeric precision of the local embedding model's weights
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmbeddingPrecision {
+    /// Full-precision weights, as published
+    #[default]
+    F32,
+    /// Linear layers quantized to 8 bits when the model loads (CPU only)
+    Int8,
+}
+
+impl EmbeddingPrecision {
+    /// Name used in config and status output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::F32 => "f32",
+            Self::Int8 => "int8",
+        }
+    }
+
+    /// What quantization costs, for status output (None at full precision)
+    pub fn accuracy_note(&self) -> Option<&'static str> {
+        match self {
+            Self::F32 => None,
+            Self::Int8 => Some(
+                "int8 weights use about a quarter of the model memory but embeddings \
+                 are slightly less accurate than f32; rankings can shift a little \
+                 until existing mail is re-embedded",
+            ),
+        }
+    }
+}
+
+/// How BM25 and vector rankings are combined
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FusionStrategy {
+    /// Reciprocal rank fusion: `weight / (rrf_k + rank)`, summed. Ignores
+    /// raw scores, so it's robust to their different scales
+    #[default]
+    Rrf,
+    /// Weighted sum of each ranking's scores, min-max normalized to 0-1.
+    /// Keeps how far apart results score, not just their order
+    #[serde(alias = "weighted_sum")]
+    Weighted,
+}
+
+impl FusionStrategy {
+    /// Name used in config and output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Rrf => "rrf",
+            Self::Weighted => "weighted",
+        }
+    }
+}
+
 /// Search settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchConfig {
@@ -263,6 +614,11 @@ pub struct SearchConfig {
     #[serde(default = "default_true", alias = "use_metal")]
     pub use_gpu: bool,
 
+    /// Weight precision of the local model; `int8` cuts memory on machines
+    /// without a GPU (and always runs on the CPU)
+    #[serde(default)]
+    pub embedding_precision: EmbeddingPrecision,
+
     /// BM25 weight in hybrid search (0.0-1.0)
     #[serde(default = "default_search_weight")]
     pub bm25_weight: f32,
@@ -271,11 +627,53 @@ pub struct SearchConfig {
     #[serde(default = "default_search_weight")]
     pub vector_weight: f32,
 
+    /// How BM25 and vector results are combined
+    #[serde(default)]
+    pub fusion: FusionStrategy,
+
+    /// Rank offset for reciprocal rank fusion; larger values flatten the
+    /// advantage of top-ranked results
+    #[serde(default = "default_rrf_k")]
+    pub rrf_k: f32,
+
+    /// Share of an email's final score that comes from how recent it is
+    /// (0.0 = relevance only, the default; 1.0 = date only)
+    #[serde(default)]
+    pub recency_weight: f32,
+
+    /// Age in days at which the recency boost halves
+    #[serde(default = "default_recency_half_life_days")]
+    pub recency_half_life_days: f32,
+
+    /// Show only the best-matching email of each thread in email search
+    #[serde(default = "default_true")]
+    pub dedup_threads: bool,
+
+    /// MMR trade-off between relevance and novelty in email search
+    /// (1.0 = relevance only, the default; lower values favor results unlike
+    /// those already shown)
+    #[serde(default = "default_mmr_lambda")]
+    pub mmr_lambda: f32,
+
+    /// Search Spam and Trash by default (`--include-spam-trash` per search)
+    #[serde(default)]
+    pub include_spam_trash: bool,
+
     /// Batch size for local embedding generation
     /// Small values (1-2) for memory-constrained environments, larger (32+) for powerful GPUs
     #[serde(default = "default_embedding_batch_size")]
     pub embedding_batch_size: usize,
 
+    /// Padded tokens per local forward pass, to fit GPU memory
+    /// (0 = 16384 on a GPU, 4096 on the CPU)
+    #[serde(default)]
+    pub embedding_max_batch_tokens: usize,
+
+    /// How long the local engine waits for concurrent requests to batch
+    /// together (up to `embedding_batch_size` texts)
+    #[serde(default = "default_embedding_batch_wait_ms")]
+    pub embedding_batch_wait_ms: u64,
+
     /// Minimum texts to use remote GPU embedding service (if configured)
     /// Below this threshold, use local embedding
     #[serde(default = "default_embedding_gpu_threshold")]
@@ -305,6 +703,11 @@ pub struct SearchConfig {
     #[serde(default = "default_openrouter_api_key_env")]
     pub openrouter_api_key_env: String,
 
+    /// Per-provider overrides for remote embedding (URL, model, auth,
+    /// batching and retries)
+    #[serde(default)]
+    pub embedding_api: EmbeddingApiConfig,
+
     /// What to do when remote embedding service is unavailable
     #[serde(default)]
     pub embedding_fallback: EmbeddingFallback,
@@ -312,6 +715,14 @@ pub struct SearchConfig {
     /// Timeout for remote embedding requests in milliseconds
     #[serde(default = "default_embedding_timeout_ms")]
     pub embedding_timeout_ms: u64,
+
+    /// Search results the MCP server keeps cached (0 disables the cache)
+    #[serde(default = "default_search_cache_entries")]
+    pub cache_entries: usize,
+
+    /// Seconds a cached search result stays valid
+    #[serde(default = "default_search_cache_ttl_secs")]
+    pub cache_ttl_secs: u64,
 }
 
 impl Default for SearchConfig {
@@ -319,17 +730,30 @@ impl Default for SearchConfig {
         Self {
             embedding_model: default_embedding_model(),
             use_gpu: true,
+            embedding_precision: EmbeddingPrecision::default(),
             bm25_weight: 0.5,
             vector_weight: 0.5,
+            fusion: FusionStrategy::default(),
+            rrf_k: default_rrf_k(),
+            recency_weight: 0.0,
+            recency_half_life_days: default_recency_half_life_days(),
+            dedup_threads: true,
+            mmr_lambda: default_mmr_lambda(),
+            include_spam_trash: false,
             embedding_batch_size: default_embedding_batch_size(),
+            embedding_max_batch_tokens: 0,
+            embedding_batch_wait_ms: default_embedding_batch_wait_ms(),
             embedding_gpu_threshold: default_embedding_gpu_threshold(),
             embedding_url: None,
             embedding_provider: None,
             openrouter_base_url: default_openrouter_base_url(),
             openrouter_model: default_openrouter_model(),
             openrouter_api_key_env: default_openrouter_api_key_env(),
+            embedding_api: EmbeddingApiConfig::default(),
             embedding_fallback: EmbeddingFallback::default(),
             embedding_timeout_ms: default_embedding_timeout_ms(),
+            cache_entries: default_search_cache_entries(),
+            cache_ttl_secs: default_search_cache_ttl_secs(),
         }
     }
 }
@@ -361,10 +785,103 @@ impl SearchConfig {
 
     /// True when search should attempt a remote embedding provider.
     pub fn remote_embeddings_enabled(&self) -> bool {
-        match self.effective_embedding_provider() {
-            EmbeddingProvider::Local => false,
-            EmbeddingProvider::OpenRouter => true,
-            EmbeddingProvider::Remote => self.embedding_url.is_some(),
+        self.embedding_endpoint().is_some()
+    }
+
+    /// Remote embedding settings: `[search.embedding_api]` overrides, then
+    /// the older flat settings (`embedding_url`, `openrouter_*`), then the
+    /// provider's defaults. None for `local`, or `remote` without a URL.
+    pub fn embedding_endpoint(&self) -> Option<EmbeddingEndpoint> {
+        let api = &self.embedding_api;
+        let provider = self.effective_embedding_provider();
+        // (url, model, api key env, max batch, max input chars, attempts)
+        let (url, model, api_key_env, max_batch_size, max_input_chars, max_attempts) =
+            match provider {
+                EmbeddingProvider::Local => return None,
+                EmbeddingProvider::Remote => (
+                    api.url.clone().or_else(|| self.embedding_url.clone())?,
+                    "bge-base-en-v1.5".to_string(),
+                    None,
+                    1024,
+                    0,
+                    3,
+                ),
+                EmbeddingProvider::OpenRouter => (
+                    self.openrouter_base_url.clone(),
+                    self.openrouter_model.clone(),
+                    Some(self.openrouter_api_key_env.clone()),
+                    1024,
+                    12_000,
+                    2,
+                ),
+                EmbeddingProvider::OpenAi => (
+                    "https://api.openai.com/v1".to_string(),
+                    "text-embedding-3-small".to_string(),
+                    Some("OPENAI_API_KEY".to_string()),
+                    2048,
+                    24_000,
+                    3,
+                ),
+                EmbeddingProvider::Ollama => (
+                    "http://localhost:11434".to_string(),
+                    "nomic-embed-text".to_string(),
+                    None,
+                    64,
+                    0,
+                    3,
+                ),
+            };
+        let model = api.model.clone().unwrap_or(model);
+        let send_dimensions = api.send_dimensions.unwrap_or_else(|| {
+            matches!(
+                provider,
+                EmbeddingProvider::OpenAi | EmbeddingProvider::OpenRouter
+            ) && model.contains("text-embedding-3")
+        });
+
+        Some(EmbeddingEndpoint {
+            provider,
+            url: api.url.clone().unwrap_or(url),
+            model,
+            api_key_env: api
+                .api_key_env
+                .clone()
+                .or(api_key_env)
+                .filter(|name| !name.trim().is_empty()),
+            auth_header: api
+                .auth_header
+                .clone()
+                .unwrap_or_else(|| "Authorization".to_string()),
+            headers: api.headers.clone(),
+            send_dimensions,
+            dimensions: api.dimensions.filter(|d| *d > 0),
+            max_batch_size: api.max_batch_size.unwrap_or(max_batch_size).max(1),
+            max_input_chars: api.max_input_chars.unwrap_or(max_input_chars),
+            max_attempts: api.max_attempts.unwrap_or(max_attempts).max(1),
+            retry_backoff_ms: api.retry_backoff_ms.unwrap_or(500),
+            timeout_ms: self.embedding_timeout_ms,
+        })
+    }
+
+    /// The model stored vectors should come from: `<provider>:<model>` for a
+    /// remote provider, otherwise the local model name
+    pub fn vector_model(&self) -> String {
+        match self.embedding_endpoint() {
+            Some(endpoint) => format!("{}:{}", endpoint.provider.as_str(), endpoint.model),
+            None => self.embedding_model.clone(),
+        }
+    }
+
+    /// Width of the vectors the configured model produces, when known: the
+    /// local model's, or `embedding_api.dimensions` for a remote provider
+    pub fn vector_dimension(&self) -> Option<usize> {
+        match self.embedding_endpoint() {
+            Some(endpoint) => endpoint.dimensions,
+            None => Some(
+                EmbeddingModel::from_str(&self.embedding_model)
+                    .unwrap_or(EmbeddingModel::BgeBaseEn)
+                    .dimension(),
+            ),
         }
     }
 }
@@ -395,147 +912,1238 @@ impl Default for UiConfig {
     }
 }
 
-/// Account-related configuration
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
-pub struct AccountsConfig {
-    /// Account aliases (alias -> email address)
-    #[serde(default)]
-    pub aliases: HashMap<String, String>,
-
-    /// Per-account settings
-    #[serde(flatten)]
-    pub accounts: HashMap<String, AccountConfig>,
-}
-
-/// Per-account configuration
+/// Outgoing mail settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AccountConfig {
-    /// Whether syncing is enabled for this account
+pub struct ComposeConfig {
+    /// Run the pre-send formatting lint on outgoing mail
     #[serde(default = "default_true")]
-    pub sync_enabled: bool,
+    pub lint_enabled: bool,
 
-    /// Whether to sync emails for this account
-    #[serde(default = "default_true")]
-    pub sync_email: bool,
+    /// Refuse to send or save drafts when the lint finds severe issues
+    #[serde(default)]
+    pub block_on_severe_lint: bool,
 
-    /// Whether to sync calendar for this account
-    #[serde(default = "default_true")]
-    pub sync_calendar: bool,
+    /// Inline (data URI) images larger than this are flagged (KB)
+    #[serde(default = "default_max_inline_image_kb")]
+    pub max_inline_image_kb: u64,
 
-    /// Folders to sync (empty = all folders)
+    /// Per-identity defaults, keyed by the send-as address
     #[serde(default)]
-    pub folders: Vec<String>,
+    pub identities: HashMap<String, IdentityConfig>,
+
+    /// Named signatures for `--signature <name>`
+    #[serde(default)]
+    pub signatures: HashMap<String, SignatureConfig>,
 }
 
-impl Default for AccountConfig {
+impl Default for ComposeConfig {
     fn default() -> Self {
         Self {
-            sync_enabled: true,
-            sync_email: true,
-            sync_calendar: true,
-            folders: vec![],
+            lint_enabled: true,
+            block_on_severe_lint: false,
+            max_inline_image_kb: default_max_inline_image_kb(),
+            identities: HashMap::new(),
+            signatures: HashMap::new(),
         }
     }
 }
 
-// Default value functions
-fn default_log_level() -> String {
-    "info".to_string()
-}
+impl ComposeConfig {
+    /// Look up the defaults for a send-as address (case-insensitive)
+    pub fn identity(&self, address: &str) -> Option<&IdentityConfig> {
+        self.identities
+            .iter()
+            .find(|(addr, _)| addr.eq_ignore_ascii_case(address))
+            .map(|(_, identity)| identity)
+    }
 
-fn default_log_file() -> PathBuf {
-    get_data_dir().join("logs").join("groundeffect.log")
+    /// A named signature (case-insensitive), None if unknown or blank
+    pub fn signature(&self, name: &str) -> Option<Signature> {
+        self.signatures
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .and_then(|(_, s)| Signature::from_parts(s.text.as_deref(), s.html.as_deref()))
+    }
 }
 
-fn default_data_dir() -> PathBuf {
-    get_data_dir()
-}
+/// A signature defined in config
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SignatureConfig {
+    /// Plain-text version (derived from `html` when unset)
+    #[serde(default)]
+    pub text: Option<String>,
 
-fn default_true() -> bool {
-    true
+    /// HTML version, used in HTML mail
+    #[serde(default)]
+    pub html: Option<String>,
 }
 
-fn default_poll_interval() -> u64 {
-    300
-}
+/// SaaS notification parsing settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityConfig {
+    /// Collapse notification emails into activity items during sync
+    #[serde(default = "default_true")]
+    pub enabled: bool,
 
-fn default_concurrent_fetches() -> usize {
-    10
-}
+    /// Built-in parsers to run (github, jira, asana)
+    #[serde(default = "default_activity_sources")]
+    pub sources: Vec<ActivitySource>,
 
-fn default_max_attachment_size() -> u64 {
-    100
+    /// Leave collapsed notifications out of email search unless asked for
+    #[serde(default = "default_true")]
+    pub hide_from_search: bool,
 }
 
-fn default_rate_limit() -> u32 {
-    10
+impl Default for ActivityConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            sources: default_activity_sources(),
+            hide_from_search: true,
+        }
+    }
 }
 
-fn default_embedding_model() -> String {
-    "bge-base-en-v1.5".to_string()
+/// Receipt and invoice extraction settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReceiptsConfig {
+    /// Detect receipts during sync and record them in the receipts table
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Read totals from downloaded PDF and text attachments
+    #[serde(default = "default_true")]
+    pub parse_attachments: bool,
 }
 
-fn default_search_weight() -> f32 {
-    0.5
+impl Default for ReceiptsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            parse_attachments: true,
+        }
+    }
 }
 
-fn default_embedding_batch_size() -> usize {
-    128 // Stable default for Gmail IMAP backfill + OpenRouter embedding throughput
+/// Travel itinerary settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TravelConfig {
+    /// Parse flight and hotel confirmation emails during sync
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Add upcoming flights and hotel stays to Google Calendar
+    #[serde(default)]
+    pub create_events: bool,
+
+    /// Calendar the events are added to
+    #[serde(default = "default_travel_calendar")]
+    pub calendar_id: String,
 }
 
-fn default_embedding_gpu_threshold() -> usize {
-    10 // Route most bulk work to GPU service
+impl Default for TravelConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            create_events: false,
+            calendar_id: default_travel_calendar(),
+        }
+    }
 }
 
-fn default_embedding_timeout_ms() -> u64 {
-    30000 // 30 seconds - embedding can be slow on first request (model loading)
+/// Reservation and package delivery extraction settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtractionsConfig {
+    /// Extract flights, hotels, restaurant reservations and packages during sync
+    #[serde(default = "default_true")]
+    pub enabled: bool,
 }
 
-fn default_openrouter_base_url() -> String {
-    "https://openrouter.ai/api/v1".to_string()
+impl Default for ExtractionsConfig {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
 }
 
-fn default_openrouter_model() -> String {
-    "openai/text-embedding-3-small".to_string()
+/// Action item extraction settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TasksConfig {
+    /// Record requests made of you ("can you send the deck by Friday") during sync
+    #[serde(default = "default_true")]
+    pub enabled: bool,
 }
 
-fn default_openrouter_api_key_env() -> String {
-    "OPENROUTER_API_KEY".to_string()
+impl Default for TasksConfig {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
 }
 
-fn default_recent_items() -> usize {
-    5
+/// Digest emails the daemon sends on a schedule
+///
+/// ```toml
+/// [digest]
+/// enabled = true
+/// period = "week"       # or "day"
+/// hour = 7              # local hour it goes out
+/// weekday = "mon"       # for weekly digests
+/// account = "work"      # account it's sent from (default: the first one)
+/// to = "me@example.com" # recipient (default: that account)
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestConfig {
+    /// Email a digest on schedule (the CLI and MCP tool work regardless)
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Day or week
+    #[serde(default)]
+    pub period: DigestPeriod,
+
+    /// Local hour (0-23) the digest is sent at
+    #[serde(default = "default_digest_hour")]
+    pub hour: u32,
+
+    /// Day of the week a weekly digest is sent on
+    #[serde(default = "default_digest_weekday")]
+    pub weekday: chrono::Weekday,
+
+    /// Account to send from (email or alias)
+    #[serde(default)]
+    pub account: Option<String>,
+
+    /// Recipient
+    #[serde(default)]
+    pub to: Option<String>,
 }
 
-/// Get the data directory (XDG: ~/.local/share/groundeffect)
-fn get_data_dir() -> PathBuf {
-    dirs::home_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join(".local")
-        .join("share")
-        .join("groundeffect")
+impl Default for DigestConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            period: DigestPeriod::default(),
+            hour: default_digest_hour(),
+            weekday: default_digest_weekday(),
+            account: None,
+            to: None,
+        }
+    }
 }
 
-/// Get the config directory (XDG: ~/.config/groundeffect)
-fn get_config_dir() -> PathBuf {
-    dirs::home_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join(".config")
-        .join("groundeffect")
+fn default_digest_hour() -> u32 {
+    7
 }
 
-impl Config {
-    /// Load configuration from the default path
-    pub fn load() -> Result<Self> {
-        let config_path = get_config_dir().join("config.toml");
-        Self::load_from(&config_path)
-    }
+fn default_digest_weekday() -> chrono::Weekday {
+    chrono::Weekday::Mon
+}
 
-    /// Load configuration from a specific path
-    pub fn load_from(path: &PathBuf) -> Result<Self> {
-        if path.exists() {
+/// Read-only ICS feed of synced calendars, merged across accounts
+///
+/// ```toml
+/// [ics_feed]
+/// enabled = true
+/// accounts = ["work", "me@gmail.com"]  # default: every account
+/// port = 8788                          # serve http://127.0.0.1:8788/calendar.ics
+/// busy_only = false                    # true: times only, shown as "Busy"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IcsFeedConfig {
+    /// Have the daemon keep the feed file up to date
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Accounts whose calendars are included (email or alias; empty = all)
+    #[serde(default)]
+    pub accounts: Vec<String>,
+
+    /// Where the feed is written (default: calendar.ics in the data directory)
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+
+    /// Also serve the feed on this loopback port
+    #[serde(default)]
+    pub port: Option<u16>,
+
+    /// How often the feed is rewritten
+    #[serde(default = "default_ics_feed_interval")]
+    pub interval_secs: u64,
+
+    /// Days of past events included
+    #[serde(default = "default_ics_feed_past_days")]
+    pub past_days: u32,
+
+    /// Days of upcoming events included
+    #[serde(default = "default_ics_feed_future_days")]
+    pub future_days: u32,
+
+    /// Publish only when you're busy, without titles or details
+    #[serde(default)]
+    pub busy_only: bool,
+
+    /// Calendar name shown by subscribing apps
+    #[serde(default = "default_ics_feed_name")]
+    pub name: String,
+}
+
+impl Default for IcsFeedConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            accounts: Vec::new(),
+            path: None,
+            port: None,
+            interval_secs: default_ics_feed_interval(),
+            past_days: default_ics_feed_past_days(),
+            future_days: default_ics_feed_future_days(),
+            busy_only: false,
+            name: default_ics_feed_name(),
+        }
+    }
+}
+
+fn default_ics_feed_interval() -> u64 {
+    900
+}
+
+fn default_ics_feed_past_days() -> u32 {
+    30
+}
+
+fn default_ics_feed_future_days() -> u32 {
+    180
+}
+
+fn default_ics_feed_name() -> String {
+    "groundeffect".to_string()
+}
+
+/// Read-only CalDAV server on loopback, so calendar apps can show the
+/// merged calendar (the accounts, window and `busy_only` of `[ics_feed]`)
+///
+/// ```toml
+/// [caldav]
+/// enabled = true
+/// port = 8789
+/// password = "any-local-secret"   # optional; any user name is accepted
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaldavConfig {
+    /// Have the daemon run the server
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Port on 127.0.0.1
+    #[serde(default = "default_caldav_port")]
+    pub port: u16,
+
+    /// Password clients must give (Basic auth); None accepts any
+    #[serde(default)]
+    pub password: Option<String>,
+}
+
+impl Default for CaldavConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: default_caldav_port(),
+            password: None,
+        }
+    }
+}
+
+fn default_caldav_port() -> u16 {
+    crate::dav::CALDAV_DEFAULT_PORT
+}
+
+/// Prometheus metrics on loopback, so sync stalls can be alerted on
+///
+/// ```toml
+/// [metrics]
+/// enabled = true
+/// port = 8790                                   # http://127.0.0.1:8790/metrics
+/// textfile = "/var/lib/node_exporter/groundeffect.prom"  # optional
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsConfig {
+    /// Have the daemon serve the metrics
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Port on 127.0.0.1
+    #[serde(default = "default_metrics_port")]
+    pub port: u16,
+
+    /// Also write the metrics to this file, for node_exporter's textfile
+    /// collector
+    #[serde(default)]
+    pub textfile: Option<PathBuf>,
+
+    /// How often the textfile is rewritten
+    #[serde(default = "default_metrics_interval")]
+    pub interval_secs: u64,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: default_metrics_port(),
+            textfile: None,
+            interval_secs: default_metrics_interval(),
+        }
+    }
+}
+
+fn default_metrics_port() -> u16 {
+    crate::metrics::METRICS_DEFAULT_PORT
+}
+
+fn default_metrics_interval() -> u64 {
+    60
+}
+
+/// When the daemon brings the full-text indexes up to date after syncs
+///
+/// ```toml
+/// [index]
+/// fts_strategy = "incremental"   # or "rebuild" to recreate the indexes each time
+/// fts_update_rows = 500          # update once this many rows are unindexed...
+/// fts_max_interval_secs = 300    # ...or this long after the first of them
+/// fts_min_interval_secs = 30     # but never more often than this
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexConfig {
+    /// Index only new rows, or rebuild the indexes from scratch
+    #[serde(default)]
+    pub fts_strategy: crate::fts::FtsStrategy,
+
+    /// Unindexed rows that trigger an update
+    #[serde(default = "default_fts_update_rows")]
+    pub fts_update_rows: usize,
+
+    /// Shortest time between two updates
+    #[serde(default = "default_fts_min_interval")]
+    pub fts_min_interval_secs: u64,
+
+    /// Longest a written row waits to be indexed
+    #[serde(default = "default_fts_max_interval")]
+    pub fts_max_interval_secs: u64,
+}
+
+impl Default for IndexConfig {
+    fn default() -> Self {
+        Self {
+            fts_strategy: crate::fts::FtsStrategy::default(),
+            fts_update_rows: default_fts_update_rows(),
+            fts_min_interval_secs: default_fts_min_interval(),
+            fts_max_interval_secs: default_fts_max_interval(),
+        }
+    }
+}
+
+fn default_fts_update_rows() -> usize {
+    500
+}
+
+fn default_fts_min_interval() -> u64 {
+    30
+}
+
+fn default_fts_max_interval() -> u64 {
+    300
+}
+
+/// One-way export of selected accounts into macOS Calendar and Contacts, so
+/// Siri and Spotlight see them (needs a build with the `apple-bridge`
+/// feature)
+///
+/// ```toml
+/// [apple]
+/// enabled = true
+/// calendar_accounts = ["work"]          # each gets a "groundeffect: work" calendar
+/// contact_accounts = ["me@gmail.com"]   # people you've written to, added if missing
+/// contacts_min_sent = 2
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppleConfig {
+    /// Have the daemon export on an interval
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Accounts whose events are exported (email or alias; empty = none)
+    #[serde(default)]
+    pub calendar_accounts: Vec<String>,
+
+    /// Accounts whose contacts are exported (email or alias; empty = none)
+    #[serde(default)]
+    pub contact_accounts: Vec<String>,
+
+    /// How often the daemon exports
+    #[serde(default = "default_apple_interval")]
+    pub interval_secs: u64,
+
+    /// Days of past events exported
+    #[serde(default = "default_apple_past_days")]
+    pub past_days: u32,
+
+    /// Days of upcoming events exported
+    #[serde(default = "default_apple_future_days")]
+    pub future_days: u32,
+
+    /// Emails the user must have sent someone before they're exported
+    #[serde(default = "default_apple_contacts_min_sent")]
+    pub contacts_min_sent: usize,
+
+    /// Days of sent mail counted for contacts
+    #[serde(default = "default_apple_contacts_since_days")]
+    pub contacts_since_days: u32,
+}
+
+impl Default for AppleConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            calendar_accounts: Vec::new(),
+            contact_accounts: Vec::new(),
+            interval_secs: default_apple_interval(),
+            past_days: default_apple_past_days(),
+            future_days: default_apple_future_days(),
+            contacts_min_sent: default_apple_contacts_min_sent(),
+            contacts_since_days: default_apple_contacts_since_days(),
+        }
+    }
+}
+
+fn default_apple_interval() -> u64 {
+    3600
+}
+
+fn default_apple_past_days() -> u32 {
+    7
+}
+
+fn default_apple_future_days() -> u32 {
+    90
+}
+
+fn default_apple_contacts_min_sent() -> usize {
+    2
+}
+
+fn default_apple_contacts_since_days() -> u32 {
+    365
+}
+
+/// Spotlight stubs, so system-wide search on macOS finds synced emails
+///
+/// ```toml
+/// [spotlight]
+/// enabled = true
+/// accounts = ["work"]   # default: every account
+/// days = 365            # emails dated within the last year
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpotlightConfig {
+    /// Have the daemon keep the stubs up to date
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Accounts whose emails get stubs (email or alias; empty = all)
+    #[serde(default)]
+    pub accounts: Vec<String>,
+
+    /// Days of mail with stubs
+    #[serde(default = "default_spotlight_days")]
+    pub days: u32,
+
+    /// Where stubs are written (default: ~/Library/Caches/Metadata/groundeffect)
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+
+    /// How often the daemon updates the stubs
+    #[serde(default = "default_spotlight_interval")]
+    pub interval_secs: u64,
+}
+
+impl Default for SpotlightConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            accounts: Vec::new(),
+            days: default_spotlight_days(),
+            path: None,
+            interval_secs: default_spotlight_interval(),
+        }
+    }
+}
+
+fn default_spotlight_days() -> u32 {
+    365
+}
+
+fn default_spotlight_interval() -> u64 {
+    3600
+}
+
+/// Local tagging rules applied during sync
+///
+/// ```toml
+/// [[tagging.rules]]
+/// tag = "clients"
+/// from_domains = ["acme.io", "globex.com"]
+///
+/// [[tagging.rules]]
+/// tag = "hiring"
+/// keywords = ["candidate", "interview loop"]
+/// similar_to = ["Here is the feedback from today's onsite interview"]
+/// category = "personal"
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TaggingConfig {
+    /// Rules, each adding its tag to the mail it matches
+    #[serde(default)]
+    pub rules: Vec<TagRule>,
+}
+
+/// A tagging rule, matching mail that meets every condition it sets
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagRule {
+    /// Tag added to matching mail (lowercased)
+    pub tag: String,
+
+    /// Sender domains, subdomains included; any of them matches
+    #[serde(default)]
+    pub from_domains: Vec<String>,
+
+    /// Words or phrases in the subject or body; any of them matches
+    #[serde(default)]
+    pub keywords: Vec<String>,
+
+    /// Example emails; mail semantically similar to any of them matches
+    /// (needs embeddings)
+    #[serde(default)]
+    pub similar_to: Vec<String>,
+
+    /// Cosine similarity to an example needed to match `similar_to`
+    #[serde(default = "default_tag_min_similarity")]
+    pub min_similarity: f32,
+
+    /// Category set on matching mail, replacing the classifier's
+    #[serde(default)]
+    pub category: Option<EmailCategory>,
+}
+
+impl Default for TagRule {
+    fn default() -> Self {
+        Self {
+            tag: String::new(),
+            from_domains: Vec::new(),
+            keywords: Vec::new(),
+            similar_to: Vec::new(),
+            min_similarity: default_tag_min_similarity(),
+            category: None,
+        }
+    }
+}
+
+/// Working hours and out-of-office lookups
+///
+/// Google Calendar doesn't expose working hours through its API, so they
+/// are set here (in `general.timezone`). The Gmail vacation responder and
+/// out-of-office calendar events are fetched on each calendar sync.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AvailabilityConfig {
+    /// Days you work ("mon" to "sun")
+    #[serde(default = "default_working_days")]
+    pub working_days: Vec<String>,
+
+    /// Start of the working day (HH:MM)
+    #[serde(default = "default_work_start")]
+    pub work_start: String,
+
+    /// End of the working day (HH:MM)
+    #[serde(default = "default_work_end")]
+    pub work_end: String,
+
+    /// How far ahead out-of-office events are fetched
+    #[serde(default = "default_availability_lookahead_days")]
+    pub lookahead_days: u32,
+}
+
+impl Default for AvailabilityConfig {
+    fn default() -> Self {
+        Self {
+            working_days: default_working_days(),
+            work_start: default_work_start(),
+            work_end: default_work_end(),
+            lookahead_days: default_availability_lookahead_days(),
+        }
+    }
+}
+
+fn default_working_days() -> Vec<String> {
+    ["mon", "tue", "wed", "thu", "fri"]
+        .iter()
+        .map(|d| d.to_string())
+        .collect()
+}
+
+fn default_work_start() -> String {
+    "09:00".to_string()
+}
+
+fn default_work_end() -> String {
+    "17:00".to_string()
+}
+
+fn default_availability_lookahead_days() -> u32 {
+    60
+}
+
+/// What text is embedded for emails and events
+///
+/// Templates use `{field}` placeholders (see `embedding::EMAIL_TEMPLATE_FIELDS`
+/// and `EVENT_TEMPLATE_FIELDS`). When a template changes, the daemon re-embeds
+/// the affected table in the background.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EmbeddingConfig {
+    /// Email template (default: subject twice, sender, body, attachment names)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email_template: Option<String>,
+
+    /// Event template (default: summary twice, description, location, attendees)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event_template: Option<String>,
+}
+
+/// Workspace directory settings
+///
+/// With `attendee_profiles` on, event details include each attendee's job
+/// title, department and profile photo from the People API. Profiles are
+/// looked up on first read and cached on disk for `profile_ttl_secs`.
+/// Needs directory access (Google Workspace accounts only).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectoryConfig {
+    /// Add attendee profiles to event details
+    #[serde(default)]
+    pub attendee_profiles: bool,
+
+    /// How long a fetched profile is reused
+    #[serde(default = "default_profile_ttl")]
+    pub profile_ttl_secs: u64,
+}
+
+impl Default for DirectoryConfig {
+    fn default() -> Self {
+        Self {
+            attendee_profiles: false,
+            profile_ttl_secs: default_profile_ttl(),
+        }
+    }
+}
+
+fn default_profile_ttl() -> u64 {
+    7 * 86400
+}
+
+/// MCP server settings
+///
+/// `allowed_capabilities` limits what MCP clients can do, e.g. `["read"]` for
+/// search-only access or `["read", "draft"]` to allow drafts but not sending.
+/// Tools outside the allowed capabilities are hidden from tools/list and
+/// calls to them are rejected.
+///
+/// Tools in `confirm_tools` (e.g. `["send_email", "send_draft"]`) need two
+/// calls: the first returns a confirmation token that must be passed back as
+/// `confirmation_token` within `confirm_timeout_secs`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpConfig {
+    /// Capabilities granted to MCP clients (default: all)
+    #[serde(default = "default_mcp_capabilities")]
+    pub allowed_capabilities: Vec<McpCapability>,
+
+    /// Tools whose changing calls need a confirmation token (default: none)
+    #[serde(default)]
+    pub confirm_tools: Vec<String>,
+
+    /// How long a confirmation token stays valid
+    #[serde(default = "default_confirm_timeout")]
+    pub confirm_timeout_secs: u64,
+
+    /// Emails in the `groundeffect://inbox/recent` resource
+    #[serde(default = "default_resource_recent_emails")]
+    pub resource_recent_emails: usize,
+
+    /// Longest text a resource returns before it is cut off
+    #[serde(default = "default_resource_max_chars")]
+    pub resource_max_chars: usize,
+
+    /// Requests the stdio server works on at once
+    #[serde(default = "default_max_concurrent_requests")]
+    pub max_concurrent_requests: usize,
+
+    /// Seconds a request may run before it is abandoned with a TIMEOUT error
+    /// (0 = no limit). Tool calls that change something always run to completion.
+    #[serde(default = "default_request_timeout_secs")]
+    pub request_timeout_secs: u64,
+}
+
+impl Default for McpConfig {
+    fn default() -> Self {
+        Self {
+            allowed_capabilities: default_mcp_capabilities(),
+            confirm_tools: Vec::new(),
+            confirm_timeout_secs: default_confirm_timeout(),
+            resource_recent_emails: default_resource_recent_emails(),
+            resource_max_chars: default_resource_max_chars(),
+            max_concurrent_requests: default_max_concurrent_requests(),
+            request_timeout_secs: default_request_timeout_secs(),
+        }
+    }
+}
+
+impl McpConfig {
+    /// Whether MCP clients may use a capability
+    pub fn allows(&self, capability: McpCapability) -> bool {
+        self.allowed_capabilities.contains(&capability)
+    }
+
+    /// Whether a tool needs two-phase confirmation
+    pub fn requires_confirmation(&self, tool: &str) -> bool {
+        self.confirm_tools.iter().any(|t| t == tool)
+    }
+}
+
+fn default_mcp_capabilities() -> Vec<McpCapability> {
+    McpCapability::ALL.to_vec()
+}
+
+fn default_confirm_timeout() -> u64 {
+    120
+}
+
+fn default_resource_recent_emails() -> usize {
+    20
+}
+
+fn default_resource_max_chars() -> usize {
+    20_000
+}
+
+fn default_max_concurrent_requests() -> usize {
+    8
+}
+
+fn default_request_timeout_secs() -> u64 {
+    120
+}
+
+/// Defaults applied when sending as a particular address
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IdentityConfig {
+    /// Display name for the From header (defaults to the account's name)
+    #[serde(default)]
+    pub display_name: Option<String>,
+
+    /// Signature appended to the body, separated by "-- "; overrides the
+    /// Gmail signature of this address
+    #[serde(default)]
+    pub signature: Option<String>,
+
+    /// HTML version of the signature, used in HTML mail
+    #[serde(default)]
+    pub signature_html: Option<String>,
+
+    /// Reply-To address
+    #[serde(default)]
+    pub reply_to: Option<String>,
+
+    /// Addresses always copied on outgoing mail
+    #[serde(default)]
+    pub cc: Vec<String>,
+}
+
+impl IdentityConfig {
+    /// The locally configured signature, if any
+    pub fn local_signature(&self) -> Option<Signature> {
+        Signature::from_parts(self.signature.as_deref(), self.signature_html.as_deref())
+    }
+
+    /// Add the default CCs that aren't already a To or CC recipient
+    pub fn merge_cc(&self, to: &[String], cc: &[String]) -> Vec<String> {
+        let mut merged = cc.to_vec();
+        for addr in &self.cc {
+            let present = to
+                .iter()
+                .chain(merged.iter())
+                .any(|existing| existing.eq_ignore_ascii_case(addr));
+            if !present {
+                merged.push(addr.clone());
+            }
+        }
+        merged
+    }
+}
+
+/// Account-related configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AccountsConfig {
+    /// Account aliases (alias -> email address)
+    #[serde(default)]
+    pub aliases: HashMap<String, String>,
+
+    /// Per-account settings
+    #[serde(flatten)]
+    pub accounts: HashMap<String, AccountConfig>,
+}
+
+/// Per-account configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountConfig {
+    /// Whether syncing is enabled for this account
+    #[serde(default = "default_true")]
+    pub sync_enabled: bool,
+
+    /// Whether to sync emails for this account
+    #[serde(default = "default_true")]
+    pub sync_email: bool,
+
+    /// Whether to sync calendar for this account
+    #[serde(default = "default_true")]
+    pub sync_calendar: bool,
+
+    /// Folders to sync (empty = all folders)
+    #[serde(default)]
+    pub folders: Vec<String>,
+}
+
+impl Default for AccountConfig {
+    fn default() -> Self {
+        Self {
+            sync_enabled: true,
+            sync_email: true,
+            sync_calendar: true,
+            folders: vec![],
+        }
+    }
+}
+
+// Default value functions
+fn default_log_level() -> String {
+    "info".to_string()
+}
+
+fn default_log_file() -> PathBuf {
+    get_data_dir().join("logs").join("groundeffect.log")
+}
+
+fn default_data_dir() -> PathBuf {
+    get_data_dir()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_activity_sources() -> Vec<ActivitySource> {
+    ActivitySource::ALL.to_vec()
+}
+
+fn default_tag_min_similarity() -> f32 {
+    0.75
+}
+
+fn default_travel_calendar() -> String {
+    "primary".to_string()
+}
+
+fn default_poll_interval() -> u64 {
+    300
+}
+
+fn default_token_check_interval() -> u64 {
+    3600
+}
+
+fn default_retention_interval() -> u64 {
+    86400
+}
+
+fn default_stall_timeout() -> u64 {
+    1800
+}
+
+fn default_stall_error_repeats() -> u32 {
+    5
+}
+
+fn default_shed_idle_after() -> u64 {
+    3600
+}
+
+fn default_concurrent_fetches() -> usize {
+    10
+}
+
+fn default_battery_concurrent_fetches() -> usize {
+    2
+}
+
+fn default_max_attachment_size() -> u64 {
+    100
+}
+
+fn default_rate_limit() -> u32 {
+    10
+}
+
+fn default_embedding_model() -> String {
+    "bge-base-en-v1.5".to_string()
+}
+
+fn default_search_weight() -> f32 {
+    0.5
+}
+
+fn default_rrf_k() -> f32 {
+    60.0
+}
+
+fn default_recency_half_life_days() -> f32 {
+    30.0
+}
+
+fn default_mmr_lambda() -> f32 {
+    1.0
+}
+
+fn default_embedding_batch_size() -> usize {
+    128 // Stable default for Gmail IMAP backfill + OpenRouter embedding throughput
+}
+
+fn default_embedding_batch_wait_ms() -> u64 {
+    5
+}
+
+fn default_embedding_gpu_threshold() -> usize {
+    10 // Route most bulk work to GPU service
+}
+
+fn default_embedding_timeout_ms() -> u64 {
+    30000 // 30 seconds - embedding can be slow on first request (model loading)
+}
+
+fn default_search_cache_entries() -> usize {
+    256
+}
+
+fn default_search_cache_ttl_secs() -> u64 {
+    300
+}
+
+fn default_openrouter_base_url() -> String {
+    "https://openrouter.ai/api/v1".to_string()
+}
+
+fn default_openrouter_model() -> String {
+    "openai/text-embedding-3-small".to_string()
+}
+
+fn default_openrouter_api_key_env() -> String {
+    "OPENROUTER_API_KEY".to_string()
+}
+
+fn default_recent_items() -> usize {
+    5
+}
+
+fn default_max_inline_image_kb() -> u64 {
+    100
+}
+
+/// The user's home directory ("." if it can't be determined)
+pub fn home_dir() -> PathBuf {
+    dirs::home_dir().unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// Expand a leading `~` to the home directory
+pub fn expand_home(path: &Path) -> PathBuf {
+    match path.strip_prefix("~") {
+        Ok(rest) => home_dir().join(rest),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+/// An XDG base directory: the variable's value if it is an absolute path,
+/// otherwise `fallback` under the home directory
+fn xdg_base(value: Option<PathBuf>, home: &Path, fallback: &[&str]) -> PathBuf {
+    value.filter(|dir| dir.is_absolute()).unwrap_or_else(|| {
+        fallback
+            .iter()
+            .fold(home.to_path_buf(), |dir, c| dir.join(c))
+    })
+}
+
+/// Where application data lives: %LOCALAPPDATA% on Windows, otherwise
+/// $XDG_DATA_HOME or ~/.local/share (also on macOS, where existing installs
+/// already keep their data there)
+fn data_home() -> PathBuf {
+    if cfg!(windows) {
+        dirs::data_local_dir().unwrap_or_else(home_dir)
+    } else {
+        let value = std::env::var_os("XDG_DATA_HOME").map(PathBuf::from);
+        xdg_base(value, &home_dir(), &[".local", "share"])
+    }
+}
+
+/// Where configuration lives: %APPDATA% on Windows, otherwise
+/// $XDG_CONFIG_HOME or ~/.config
+fn config_home() -> PathBuf {
+    if cfg!(windows) {
+        dirs::config_dir().unwrap_or_else(home_dir)
+    } else {
+        let value = std::env::var_os("XDG_CONFIG_HOME").map(PathBuf::from);
+        xdg_base(value, &home_dir(), &[".config"])
+    }
+}
+
+/// Get the default data directory (~/.local/share/groundeffect, or
+/// .../groundeffect/profiles/<name> for a named profile). The config's
+/// `general.data_dir` overrides it.
+pub fn get_data_dir() -> PathBuf {
+    profile_scoped_dir(&data_home().join("groundeffect"), active_profile())
+}
+
+/// Get the config directory (~/.config/groundeffect, or
+/// .../groundeffect/profiles/<name> for a named profile)
+pub fn get_config_dir() -> PathBuf {
+    profile_scoped_dir(&config_home().join("groundeffect"), active_profile())
+}
+
+/// Restrict a file or directory to its owner (`mode` such as 0o600). Unix
+/// permission bits don't exist on Windows, where this does nothing.
+pub fn restrict_to_owner(path: &Path, mode: u32) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (path, mode);
+        Ok(())
+    }
+}
+
+/// Get the directory holding file-based OAuth tokens
+pub fn tokens_dir() -> PathBuf {
+    get_config_dir().join("tokens")
+}
+
+/// Get the daemon log directory for the active profile (inside the
+/// configured data directory)
+pub fn daemon_log_dir() -> PathBuf {
+    Config::load()
+        .map(|config| config.log_dir())
+        .unwrap_or_else(|_| get_data_dir().join("logs"))
+}
+
+fn profile_scoped_dir(base: &Path, profile: Option<&str>) -> PathBuf {
+    match profile {
+        Some(name) => base.join("profiles").join(name),
+        None => base.to_path_buf(),
+    }
+}
+
+/// Select the profile for this process.
+///
+/// Profiles keep config, database, attachments and tokens in separate
+/// directories. Must be called before any path is resolved; "default" selects
+/// the unscoped directories.
+pub fn set_active_profile(name: &str) -> Result<()> {
+    validate_profile_name(name)?;
+    let profile = (name != "default").then(|| name.to_string());
+    match ACTIVE_PROFILE.get() {
+        Some(existing) if *existing != profile => Err(Error::Config(format!(
+            "Profile already set to '{}'",
+            existing.as_deref().unwrap_or("default")
+        ))),
+        Some(_) => Ok(()),
+        None => {
+            let _ = ACTIVE_PROFILE.set(profile);
+            Ok(())
+        }
+    }
+}
+
+/// The active profile name (None for the default profile).
+///
+/// Falls back to the GROUNDEFFECT_PROFILE environment variable when no
+/// profile was set explicitly.
+pub fn active_profile() -> Option<&'static str> {
+    ACTIVE_PROFILE
+        .get_or_init(|| {
+            std::env::var(PROFILE_ENV)
+                .ok()
+                .filter(|name| validate_profile_name(name).is_ok() && name != "default")
+        })
+        .as_deref()
+}
+
+/// Profile names become directory names and launchd labels
+pub fn validate_profile_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::Config(format!(
+            "Invalid profile name '{}': use letters, digits, '-' or '_'",
+            name
+        )))
+    }
+}
+
+impl Config {
+    /// Get the config file path for the active profile
+    pub fn config_path() -> PathBuf {
+        get_config_dir().join("config.toml")
+    }
+
+    /// Get the search synonyms/acronyms dictionary path
+    pub fn synonyms_path() -> PathBuf {
+        get_config_dir().join("search.synonyms.toml")
+    }
+
+    /// Load the effective configuration: defaults, config.toml, then
+    /// `GROUNDEFFECT__*` environment variables and `--set` overrides
+    pub fn load() -> Result<Self> {
+        let mut config: Config = Self::layers()?.resolve()?;
+        config.expand_paths();
+        Ok(config)
+    }
+
+    /// The layers behind [`Config::load`]
+    pub fn layers() -> Result<Layers> {
+        Layers::read(&Self::config_path(), "")
+    }
+
+    /// Load config.toml alone, without environment or command line
+    /// overrides, to change and save it
+    pub fn load_file() -> Result<Self> {
+        Self::load_from(&Self::config_path())
+    }
+
+    /// Load configuration from a specific path
+    pub fn load_from(path: &PathBuf) -> Result<Self> {
+        if path.exists() {
             let contents = std::fs::read_to_string(path)?;
-            let config: Config = toml::from_str(&contents)?;
+            let mut config: Config = toml::from_str(&contents)?;
+            config.expand_paths();
             info!("Loaded configuration from {:?}", path);
             Ok(config)
         } else {
@@ -544,10 +2152,66 @@ impl Config {
         }
     }
 
+    fn expand_paths(&mut self) {
+        self.general.data_dir = expand_home(&self.general.data_dir);
+        self.general.log_file = expand_home(&self.general.log_file);
+    }
+
+    /// Settings that parse but can't be used, as (key, problem)
+    pub fn problems(&self) -> Vec<(String, String)> {
+        let mut problems = Vec::new();
+        if self.general.timezone.parse::<chrono_tz::Tz>().is_err() {
+            problems.push((
+                "general.timezone".to_string(),
+                format!(
+                    "unknown timezone '{}' (use an IANA name like America/Los_Angeles)",
+                    self.general.timezone
+                ),
+            ));
+        }
+        if self.general.log_level.parse::<tracing::Level>().is_err() {
+            problems.push((
+                "general.log_level".to_string(),
+                format!(
+                    "unknown log level '{}' (use trace, debug, info, warn or error)",
+                    self.general.log_level
+                ),
+            ));
+        }
+        for (key, secs) in [
+            (
+                "sync.email_poll_interval_secs",
+                self.sync.email_poll_interval_secs,
+            ),
+            (
+                "sync.calendar_poll_interval_secs",
+                self.sync.calendar_poll_interval_secs,
+            ),
+        ] {
+            if secs == 0 {
+                problems.push((key.to_string(), "must be at least 1".to_string()));
+            }
+        }
+        if !(1..=1024).contains(&self.search.embedding_batch_size) {
+            problems.push((
+                "search.embedding_batch_size".to_string(),
+                "must be between 1 and 1024".to_string(),
+            ));
+        }
+        for (alias, email) in &self.accounts.aliases {
+            if !email.contains('@') {
+                problems.push((
+                    format!("accounts.aliases.{}", alias),
+                    format!("'{}' is not an email address", email),
+                ));
+            }
+        }
+        problems
+    }
+
     /// Save configuration to the default path
     pub fn save(&self) -> Result<()> {
-        let config_path = get_config_dir().join("config.toml");
-        self.save_to(&config_path)
+        self.save_to(&Self::config_path())
     }
 
     /// Save configuration to a specific path
@@ -561,6 +2225,11 @@ impl Config {
         Ok(())
     }
 
+    /// Get the log directory
+    pub fn log_dir(&self) -> PathBuf {
+        self.general.data_dir.join("logs")
+    }
+
     /// Get the LanceDB directory
     pub fn lancedb_dir(&self) -> PathBuf {
         self.general.data_dir.join("lancedb")
@@ -571,11 +2240,21 @@ impl Config {
         self.general.data_dir.join("attachments")
     }
 
+    /// Get the directory holding compressed raw message sources
+    pub fn raw_dir(&self) -> PathBuf {
+        self.general.data_dir.join("raw")
+    }
+
     /// Get the models directory
     pub fn models_dir(&self) -> PathBuf {
         self.general.data_dir.join("models")
     }
 
+    /// Model and templates the stored embeddings were computed with
+    pub fn embedding_state_path(&self) -> PathBuf {
+        self.general.data_dir.join("embedding_templates.json")
+    }
+
     /// Get the sync state directory
     pub fn sync_state_dir(&self) -> PathBuf {
         self.general.data_dir.join("cache").join("sync_state")
@@ -586,11 +2265,113 @@ impl Config {
         self.general.data_dir.join("daemon.pid")
     }
 
+    /// Get the daemon's control socket path (CLI searches run through it)
+    pub fn control_socket_path(&self) -> PathBuf {
+        self.general.data_dir.join("daemon.sock")
+    }
+
+    /// Get the daemon health file path (restart counts, last panic)
+    pub fn daemon_health_file(&self) -> PathBuf {
+        self.general.data_dir.join("daemon_health.json")
+    }
+
+    /// Get the token health file path (written by the daemon's token checks)
+    pub fn token_health_file(&self) -> PathBuf {
+        self.general.data_dir.join("token_health.json")
+    }
+
+    /// When the daemon last emailed a scheduled digest
+    pub fn digest_state_file(&self) -> PathBuf {
+        self.general.data_dir.join("digest_last_sent")
+    }
+
+    /// Where the ICS feed is written
+    pub fn ics_feed_file(&self) -> PathBuf {
+        match &self.ics_feed.path {
+            Some(path) => expand_home(path),
+            None => self.general.data_dir.join("calendar.ics"),
+        }
+    }
+
+    /// Where the metrics textfile is written, if anywhere
+    pub fn metrics_textfile(&self) -> Option<PathBuf> {
+        self.metrics.textfile.as_deref().map(expand_home)
+    }
+
+    /// Where Spotlight stubs are written
+    pub fn spotlight_dir(&self) -> PathBuf {
+        match &self.spotlight.path {
+            Some(path) => expand_home(path),
+            None => profile_scoped_dir(
+                &home_dir().join("Library/Caches/Metadata/groundeffect"),
+                active_profile(),
+            ),
+        }
+    }
+
+    /// Get the retention report path (written by the daemon's pruning runs)
+    pub fn retention_report_file(&self) -> PathBuf {
+        self.general.data_dir.join("retention_report.json")
+    }
+
+    /// Get the startup consistency scan report path (written by the daemon)
+    pub fn recovery_report_file(&self) -> PathBuf {
+        self.general.data_dir.join("recovery_report.json")
+    }
+
+    /// Get the search cache stats file path (written by the MCP server)
+    pub fn search_cache_stats_file(&self) -> PathBuf {
+        self.general.data_dir.join("search_cache_stats.json")
+    }
+
+    /// Get the full-text index state file path (last update and rebuild)
+    pub fn index_state_file(&self) -> PathBuf {
+        self.general.data_dir.join("index_state.json")
+    }
+
+    /// Get the sync policy state file path (written by the daemon)
+    pub fn sync_policy_file(&self) -> PathBuf {
+        self.general.data_dir.join("sync_policy.json")
+    }
+
+    /// Get the directory of email batches recorded before they're written
+    pub fn write_log_dir(&self) -> PathBuf {
+        self.general.data_dir.join("write_log")
+    }
+
     /// Get the sync progress file path (for MCP to read daemon progress)
     pub fn sync_progress_file(&self) -> PathBuf {
         self.general.data_dir.join("sync_progress.json")
     }
 
+    /// Get the directory holding cached Gmail label lists
+    pub fn label_cache_dir(&self) -> PathBuf {
+        self.general.data_dir.join("cache").join("labels")
+    }
+
+    /// Get the Gmail label cache file for an account
+    pub fn label_cache_file(&self, account_id: &str) -> PathBuf {
+        self.label_cache_dir().join(format!("{}.json", account_id))
+    }
+
+    /// Get the vacation responder / out-of-office state file for an account
+    pub fn availability_cache_file(&self, account_id: &str) -> PathBuf {
+        self.general
+            .data_dir
+            .join("cache")
+            .join("availability")
+            .join(format!("{}.json", account_id))
+    }
+
+    /// Get the Workspace directory lookup cache file for an account
+    pub fn directory_cache_file(&self, account_id: &str) -> PathBuf {
+        self.general
+            .data_dir
+            .join("cache")
+            .join("directory")
+            .join(format!("{}.json", account_id))
+    }
+
     /// Resolve an account identifier (email or alias) to an email address
     pub fn resolve_account(&self, identifier: &str) -> Option<String> {
         // Check if it's an alias first
@@ -625,7 +2406,7 @@ impl Config {
 /// Stored separately at ~/.config/groundeffect/daemon.toml
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DaemonConfig {
-    /// Enable logging to ~/.local/share/groundeffect/logs/
+    /// Enable logging to the data directory's logs/ folder
     #[serde(default)]
     pub logging_enabled: bool,
 
@@ -640,6 +2421,22 @@ pub struct DaemonConfig {
     /// Max concurrent email fetches (default: 10)
     #[serde(default = "default_concurrent_fetches")]
     pub max_concurrent_fetches: usize,
+
+    /// Daily local time range with no syncing, e.g. "22:00-07:00"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quiet_hours: Option<String>,
+
+    /// What to do while on battery: normal, throttle or pause
+    #[serde(default)]
+    pub on_battery: crate::sync_window::BatteryPolicy,
+
+    /// Max concurrent email fetches while throttled on battery (default: 2)
+    #[serde(default = "default_battery_concurrent_fetches")]
+    pub battery_max_concurrent_fetches: usize,
+
+    /// Hold attachment downloads unless on Wi-Fi or a wired network
+    #[serde(default)]
+    pub attachments_wifi_only: bool,
 }
 
 impl Default for DaemonConfig {
@@ -649,6 +2446,10 @@ impl Default for DaemonConfig {
             email_poll_interval_secs: 300,
             calendar_poll_interval_secs: 300,
             max_concurrent_fetches: 10,
+            quiet_hours: None,
+            on_battery: crate::sync_window::BatteryPolicy::default(),
+            battery_max_concurrent_fetches: 2,
+            attachments_wifi_only: false,
         }
     }
 }
@@ -659,8 +2460,20 @@ impl DaemonConfig {
         get_config_dir().join("daemon.toml")
     }
 
-    /// Load daemon config from disk (returns defaults if not found)
+    /// Load the effective daemon config: defaults, daemon.toml, then
+    /// `GROUNDEFFECT__DAEMON__*` environment variables and `--set daemon.*`
     pub fn load() -> Result<Self> {
+        Self::layers()?.resolve()
+    }
+
+    /// The layers behind [`DaemonConfig::load`]
+    pub fn layers() -> Result<Layers> {
+        Layers::read(&Self::config_path(), DAEMON_SECTION)
+    }
+
+    /// Load daemon.toml alone (returns defaults if not found), to change and
+    /// save it
+    pub fn load_file() -> Result<Self> {
         let path = Self::config_path();
         if path.exists() {
             let contents = std::fs::read_to_string(&path)?;
@@ -673,6 +2486,40 @@ impl DaemonConfig {
         }
     }
 
+    /// Settings that parse but can't be used, as (key, problem)
+    pub fn problems(&self) -> Vec<(String, String)> {
+        let mut problems = Vec::new();
+        for (key, secs) in [
+            ("email_poll_interval_secs", self.email_poll_interval_secs),
+            (
+                "calendar_poll_interval_secs",
+                self.calendar_poll_interval_secs,
+            ),
+        ] {
+            if !(60..=3600).contains(&secs) {
+                problems.push((key.to_string(), "must be between 60 and 3600".to_string()));
+            }
+        }
+        if !(1..=50).contains(&self.max_concurrent_fetches) {
+            problems.push((
+                "max_concurrent_fetches".to_string(),
+                "must be between 1 and 50".to_string(),
+            ));
+        }
+        if !(1..=50).contains(&self.battery_max_concurrent_fetches) {
+            problems.push((
+                "battery_max_concurrent_fetches".to_string(),
+                "must be between 1 and 50".to_string(),
+            ));
+        }
+        if let Err(Error::InvalidConfig { reason, .. }) =
+            crate::sync_window::SyncSchedule::from_config(self)
+        {
+            problems.push(("quiet_hours".to_string(), reason));
+        }
+        problems
+    }
+
     /// Save daemon config to disk
     pub fn save(&self) -> Result<()> {
         let path = Self::config_path();
@@ -685,18 +2532,63 @@ impl DaemonConfig {
         Ok(())
     }
 
+    /// Get the launchd label (one agent per profile)
+    pub fn launchd_label() -> String {
+        match active_profile() {
+            Some(profile) => format!("com.groundeffect.daemon.{}", profile),
+            None => "com.groundeffect.daemon".to_string(),
+        }
+    }
+
     /// Get the launchd plist path
     pub fn launchd_plist_path() -> PathBuf {
-        dirs::home_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
+        home_dir()
             .join("Library")
             .join("LaunchAgents")
-            .join("com.groundeffect.daemon.plist")
+            .join(format!("{}.plist", Self::launchd_label()))
+    }
+
+    /// Get the systemd user unit name (one unit per profile)
+    pub fn systemd_unit_name() -> String {
+        match active_profile() {
+            Some(profile) => format!("groundeffect-daemon-{}.service", profile),
+            None => "groundeffect-daemon.service".to_string(),
+        }
+    }
+
+    /// Get the systemd user unit path (~/.config/systemd/user)
+    pub fn systemd_unit_path() -> PathBuf {
+        config_home()
+            .join("systemd")
+            .join("user")
+            .join(Self::systemd_unit_name())
+    }
+
+    /// Arguments selecting the active profile, for spawning the daemon
+    pub fn profile_args() -> Vec<String> {
+        match active_profile() {
+            Some(profile) => vec!["--profile".to_string(), profile.to_string()],
+            None => vec![],
+        }
     }
 
-    /// Check if launchd agent is installed
+    /// `pgrep -f` pattern matching this profile's daemon process (and not
+    /// other profiles' daemons or `groundeffect-daemon mcp`)
+    pub fn process_pattern() -> String {
+        match active_profile() {
+            Some(profile) => format!("groundeffect-daemon.* --profile {}$", profile),
+            None => "groundeffect-daemon( run| --log)*$".to_string(),
+        }
+    }
+
+    /// Check if launchd agent is installed (always false off macOS)
     pub fn is_launchd_installed() -> bool {
-        Self::launchd_plist_path().exists()
+        cfg!(target_os = "macos") && Self::launchd_plist_path().exists()
+    }
+
+    /// Check if systemd user unit is installed (always false off Linux)
+    pub fn is_systemd_installed() -> bool {
+        cfg!(target_os = "linux") && Self::systemd_unit_path().exists()
     }
 }
 
@@ -745,6 +2637,28 @@ mod tests {
         assert!(!config.search.remote_embeddings_enabled());
     }
 
+    #[test]
+    fn test_fusion_from_toml() {
+        let config: Config =
+            toml::from_str("[search]\nfusion = \"weighted_sum\"\nbm25_weight = 0.7").unwrap();
+        assert_eq!(config.search.fusion, FusionStrategy::Weighted);
+        assert_eq!(config.search.bm25_weight, 0.7);
+        assert_eq!(config.search.vector_weight, 0.5);
+        assert_eq!(Config::default().search.fusion, FusionStrategy::Rrf);
+        assert_eq!(Config::default().search.rrf_k, 60.0);
+    }
+
+    #[test]
+    fn test_embedding_precision_from_toml() {
+        let config: Config = toml::from_str("[search]\nembedding_precision = \"int8\"").unwrap();
+        assert_eq!(config.search.embedding_precision, EmbeddingPrecision::Int8);
+        assert!(config.search.embedding_precision.accuracy_note().is_some());
+        assert_eq!(
+            Config::default().search.embedding_precision,
+            EmbeddingPrecision::F32
+        );
+    }
+
     #[test]
     fn test_effective_embedding_provider_legacy_remote_url() {
         let mut config = Config::default();
@@ -756,6 +2670,80 @@ mod tests {
         assert!(config.search.remote_embeddings_enabled());
     }
 
+    #[test]
+    fn test_embedding_endpoint_provider_defaults() {
+        let config: Config = toml::from_str("[search]\nembedding_provider = \"ollama\"").unwrap();
+        let endpoint = config.search.embedding_endpoint().unwrap();
+        assert_eq!(endpoint.url, "http://localhost:11434");
+        assert_eq!(endpoint.model, "nomic-embed-text");
+        assert_eq!(endpoint.api_key_env, None);
+        assert!(!endpoint.send_dimensions);
+
+        let config: Config = toml::from_str("[search]\nembedding_provider = \"openai\"").unwrap();
+        let endpoint = config.search.embedding_endpoint().unwrap();
+        assert_eq!(endpoint.api_key_env.as_deref(), Some("OPENAI_API_KEY"));
+        assert_eq!(endpoint.auth_header, "Authorization");
+        assert!(endpoint.send_dimensions);
+
+        // The older spelling written by earlier versions still loads
+        let config: Config =
+            toml::from_str("[search]\nembedding_provider = \"open_router\"").unwrap();
+        assert_eq!(
+            config.search.effective_embedding_provider(),
+            EmbeddingProvider::OpenRouter
+        );
+        assert!(Config::default().search.embedding_endpoint().is_none());
+    }
+
+    #[test]
+    fn test_embedding_api_overrides() {
+        let config: Config = toml::from_str(
+            r#"
+[search]
+embedding_provider = "openai"
+
+[search.embedding_api]
+url = "https://example.openai.azure.com/openai/deployments/embed"
+auth_header = "api-key"
+api_key_env = "AZURE_OPENAI_KEY"
+max_batch_size = 16
+max_attempts = 0
+headers = { "X-Team" = "mail" }
+"#,
+        )
+        .unwrap();
+        let endpoint = config.search.embedding_endpoint().unwrap();
+        assert!(endpoint.url.contains("azure"));
+        assert_eq!(endpoint.auth_header, "api-key");
+        assert_eq!(endpoint.api_key_env.as_deref(), Some("AZURE_OPENAI_KEY"));
+        assert_eq!(endpoint.max_batch_size, 16);
+        assert_eq!(endpoint.max_attempts, 1);
+        assert_eq!(
+            endpoint.headers.get("X-Team").map(String::as_str),
+            Some("mail")
+        );
+    }
+
+    #[test]
+    fn test_vector_model_and_dimension() {
+        let mut config = Config::default();
+        assert_eq!(config.search.vector_dimension(), Some(768));
+
+        config.search.embedding_model = "all-MiniLM-L6-v2".to_string();
+        assert_eq!(config.search.vector_model(), "all-MiniLM-L6-v2");
+        assert_eq!(config.search.vector_dimension(), Some(384));
+
+        // A remote model's width is unknown unless configured
+        config.search.embedding_provider = Some(EmbeddingProvider::OpenAi);
+        assert_eq!(
+            config.search.vector_model(),
+            "openai:text-embedding-3-small"
+        );
+        assert_eq!(config.search.vector_dimension(), None);
+        config.search.embedding_api.dimensions = Some(1536);
+        assert_eq!(config.search.vector_dimension(), Some(1536));
+    }
+
     #[test]
     fn test_effective_batch_sizes_are_sanitized() {
         let mut config = Config::default();
@@ -772,4 +2760,112 @@ mod tests {
         assert_eq!(config.search.effective_embedding_batch_size(), 1024);
         assert_eq!(config.search.effective_imap_fetch_batch_size(), 1024);
     }
+
+    #[test]
+    fn test_xdg_base_dirs() {
+        let home = PathBuf::from("/home/u");
+        assert_eq!(
+            xdg_base(None, &home, &[".local", "share"]),
+            PathBuf::from("/home/u/.local/share")
+        );
+        assert_eq!(
+            xdg_base(Some(PathBuf::from("/data")), &home, &[".local", "share"]),
+            PathBuf::from("/data")
+        );
+        // Relative values are invalid per the XDG spec
+        assert_eq!(
+            xdg_base(Some(PathBuf::from("data")), &home, &[".config"]),
+            PathBuf::from("/home/u/.config")
+        );
+    }
+
+    #[test]
+    fn test_expand_home() {
+        assert_eq!(
+            expand_home(Path::new("~/ge/data")),
+            home_dir().join("ge").join("data")
+        );
+        assert_eq!(expand_home(Path::new("~")), home_dir());
+        assert_eq!(
+            expand_home(Path::new("/Volumes/External/ge")),
+            PathBuf::from("/Volumes/External/ge")
+        );
+    }
+
+    #[test]
+    fn test_profile_scoped_dirs() {
+        let base = PathBuf::from("/home/u/.local/share/groundeffect");
+        assert_eq!(profile_scoped_dir(&base, None), base);
+        assert_eq!(
+            profile_scoped_dir(&base, Some("work")),
+            base.join("profiles").join("work")
+        );
+
+        assert!(validate_profile_name("work").is_ok());
+        assert!(validate_profile_name("client_a-2").is_ok());
+        assert!(validate_profile_name("").is_err());
+        assert!(validate_profile_name("../personal").is_err());
+        assert!(validate_profile_name("my profile").is_err());
+    }
+
+    #[test]
+    fn test_identity_defaults() {
+        let config: Config = toml::from_str(
+            r#"
+            [compose.identities."Support@Example.com"]
+            signature = "Example Support"
+            reply_to = "help@example.com"
+            cc = ["archive@example.com", "bob@example.com"]
+
+            [compose.signatures.formal]
+            html = "<b>Jane Doe</b><br>Example Inc."
+            "#,
+        )
+        .unwrap();
+
+        let identity = config.compose.identity("support@example.com").unwrap();
+        assert_eq!(identity.reply_to.as_deref(), Some("help@example.com"));
+        assert!(config.compose.identity("me@example.com").is_none());
+
+        let signature = identity.local_signature().unwrap();
+        let body = signature.append_text("Thanks!\n");
+        assert_eq!(body, "Thanks!\n\n-- \nExample Support");
+        assert_eq!(signature.append_text(&body), body);
+
+        let formal = config.compose.signature("Formal").unwrap();
+        assert_eq!(formal.text, "Jane Doe\nExample Inc.");
+        assert!(config.compose.signature("casual").is_none());
+
+        let cc = identity.merge_cc(
+            &["Bob@example.com".to_string()],
+            &["carol@example.com".to_string()],
+        );
+        assert_eq!(cc, vec!["carol@example.com", "archive@example.com"]);
+    }
+
+    #[test]
+    fn test_mcp_capabilities() {
+        assert!(Config::default().mcp.allows(McpCapability::Send));
+
+        let config: Config =
+            toml::from_str("[mcp]\nallowed_capabilities = [\"read\", \"draft\"]").unwrap();
+        assert!(config.mcp.allows(McpCapability::Draft));
+        assert!(!config.mcp.allows(McpCapability::Send));
+
+        assert!(toml::from_str::<Config>("[mcp]\nallowed_capabilities = [\"admin\"]").is_err());
+    }
+
+    #[test]
+    fn test_linux_token_providers() {
+        let config: Config = toml::from_str("[tokens]\nprovider = \"secret_service\"").unwrap();
+        assert!(matches!(config.tokens, TokenProviderConfig::SecretService));
+
+        let config: Config = toml::from_str("[tokens]\nprovider = \"encrypted_file\"").unwrap();
+        match config.tokens {
+            TokenProviderConfig::EncryptedFile { encryption_key_env } => {
+                assert_eq!(encryption_key_env, "GE_TOKEN_ENCRYPTION_KEY")
+            }
+            other => panic!("unexpected provider {:?}", other),
+        }
+    }
 }
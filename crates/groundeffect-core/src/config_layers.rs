@@ -0,0 +1,632 @@
+//! Layered settings
+//!
+//! Each setting comes from the last of these layers that sets it:
+//!
+//! 1. the built-in default
+//! 2. the config file: `config.toml`, or `daemon.toml` for `daemon.*`
+//! 3. an environment variable: `GROUNDEFFECT__SEARCH__EMBEDDING_BATCH_SIZE=64`
+//!    sets `search.embedding_batch_size` (`__` separates sections and keys)
+//! 4. `--set search.embedding_batch_size=64` on the command line
+//!
+//! Environment and command line values are read as TOML (`20`, `true`,
+//! `["INBOX"]`); anything that isn't valid TOML is taken as a string.
+//! [`Layers`] remembers which layer set each key, for `config show
+//! --origin`, and can check every override on its own, so `config validate`
+//! names the key behind a bad value and finds keys no setting reads.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use toml::{Table, Value};
+use tracing::info;
+
+use crate::config::{Config, DaemonConfig};
+use crate::error::{Error, Result};
+
+/// Prefix of environment variables that override settings
+pub const ENV_PREFIX: &str = "GROUNDEFFECT__";
+
+/// Section of the settings stored in daemon.toml
+pub const DAEMON_SECTION: &str = "daemon";
+
+/// `--set` overrides, registered once per process
+static CLI_OVERRIDES: OnceLock<Vec<(String, String)>> = OnceLock::new();
+
+/// Register the command line's `--set KEY=VALUE` overrides.
+///
+/// Must be called before any config is loaded.
+pub fn set_cli_overrides(overrides: &[String]) -> Result<()> {
+    let parsed = overrides
+        .iter()
+        .map(|o| {
+            let (key, value) = o
+                .split_once('=')
+                .ok_or_else(|| Error::Config(format!("Invalid --set '{}': use KEY=VALUE", o)))?;
+            let key = key.trim();
+            override_table(key, value)?;
+            Ok((key.to_string(), value.to_string()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    CLI_OVERRIDES
+        .set(parsed)
+        .map_err(|_| Error::Config("Command line overrides already set".to_string()))
+}
+
+fn cli_overrides() -> &'static [(String, String)] {
+    CLI_OVERRIDES.get().map(Vec::as_slice).unwrap_or_default()
+}
+
+/// The layer a setting comes from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "origin", content = "source", rename_all = "lowercase")]
+pub enum Origin {
+    Default,
+    /// Config file path
+    File(PathBuf),
+    /// Environment variable name
+    Env(String),
+    /// `--set` on the command line
+    Cli,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::Default => write!(f, "default"),
+            Origin::File(path) => write!(f, "file {}", path.display()),
+            Origin::Env(name) => write!(f, "env {}", name),
+            Origin::Cli => write!(f, "--set"),
+        }
+    }
+}
+
+/// An effective setting and the layer it comes from
+#[derive(Debug, Clone, Serialize)]
+pub struct Setting {
+    pub key: String,
+    pub value: Value,
+    #[serde(flatten)]
+    pub origin: Origin,
+}
+
+/// A setting `config validate` objects to
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigIssue {
+    pub key: String,
+    #[serde(flatten)]
+    pub origin: Origin,
+    pub problem: String,
+}
+
+/// The layers behind one config file
+#[derive(Debug, Clone)]
+pub struct Layers {
+    /// Prefix of this file's keys ("" for config.toml)
+    section: String,
+    /// File, environment and command line, lowest first
+    overrides: Vec<(Origin, Table)>,
+}
+
+impl Layers {
+    /// Read the layers over the defaults: the file at `path`, and the
+    /// environment variables and `--set` overrides for keys in `section`
+    pub fn read(path: &Path, section: &str) -> Result<Self> {
+        let mut overrides = Vec::new();
+        if path.exists() {
+            let contents = std::fs::read_to_string(path)?;
+            overrides.push((Origin::File(path.to_path_buf()), toml::from_str(&contents)?));
+            info!("Loaded settings from {:?}", path);
+        }
+
+        let mut vars: Vec<(String, String, String)> = std::env::vars_os()
+            .filter_map(|(name, value)| {
+                let name = name.into_string().ok()?;
+                let key = env_key(&name)?;
+                Some((name, key, value.into_string().ok()?))
+            })
+            .collect();
+        vars.sort();
+        for (name, key, value) in &vars {
+            if let Some(key) = section_key(key, section) {
+                overrides.push((Origin::Env(name.clone()), override_table(key, value)?));
+            }
+        }
+        for (key, value) in cli_overrides() {
+            if let Some(key) = section_key(key, section) {
+                overrides.push((Origin::Cli, override_table(key, value)?));
+            }
+        }
+
+        Ok(Self {
+            section: section.to_string(),
+            overrides,
+        })
+    }
+
+    /// The settings of all layers combined
+    ///
+    /// A value of the wrong type is an error naming the key and the layer
+    /// that set it.
+    pub fn resolve<T: DeserializeOwned>(&self) -> Result<T> {
+        let (table, _) = self.merged();
+        deserialize(table).map_err(|problem| {
+            let culprit = self
+                .overrides
+                .iter()
+                .flat_map(|(origin, layer)| {
+                    leaves(layer)
+                        .into_iter()
+                        .map(move |(path, value)| (origin, path, value))
+                })
+                .find(|(_, path, value)| {
+                    deserialize::<T>(table_of(&[(path.clone(), (*value).clone())])).is_err()
+                });
+            match culprit {
+                Some((origin, path, _)) => Error::Config(format!(
+                    "Invalid setting {} (from {}): {}",
+                    self.key(&path),
+                    origin,
+                    problem
+                )),
+                None => Error::Config(format!("Invalid settings: {}", problem)),
+            }
+        })
+    }
+
+    /// Overrides with keys no setting reads, or values the setting can't take
+    ///
+    /// Each override is checked on its own, over the defaults. Keys in the same table
+    /// of the same layer are checked together, since some only make sense as
+    /// a group (`[tokens]`).
+    pub fn check<T: Serialize + DeserializeOwned>(&self) -> Vec<ConfigIssue> {
+        let mut issues = Vec::new();
+        for (origin, layer) in &self.overrides {
+            let mut groups: BTreeMap<Vec<String>, Vec<(Vec<String>, Value)>> = BTreeMap::new();
+            for (path, value) in leaves(layer) {
+                let parent = path[..path.len() - 1].to_vec();
+                groups
+                    .entry(parent)
+                    .or_default()
+                    .push((path, value.clone()));
+            }
+
+            for (parent, group) in groups {
+                let resolved = match deserialize::<T>(table_of(&group)) {
+                    Ok(resolved) => resolved,
+                    Err(problem) => {
+                        let before = issues.len();
+                        for leaf in &group {
+                            if let Err(problem) =
+                                deserialize::<T>(table_of(std::slice::from_ref(leaf)))
+                            {
+                                issues.push(ConfigIssue {
+                                    key: self.key(&leaf.0),
+                                    origin: origin.clone(),
+                                    problem,
+                                });
+                            }
+                        }
+                        if issues.len() == before {
+                            issues.push(ConfigIssue {
+                                key: self.key(&parent),
+                                origin: origin.clone(),
+                                problem,
+                            });
+                        }
+                        continue;
+                    }
+                };
+
+                let Ok(with_group) = to_table(&resolved) else {
+                    continue;
+                };
+                for (i, (path, value)) in group.iter().enumerate() {
+                    if !self.is_read::<T>(&with_group, &group, i, path, value) {
+                        issues.push(ConfigIssue {
+                            key: self.key(path),
+                            origin: origin.clone(),
+                            problem: "unknown key".to_string(),
+                        });
+                    }
+                }
+            }
+        }
+        issues
+    }
+
+    /// Every setting of `value` (resolved from these layers) with its layer
+    pub fn settings<T: Serialize>(&self, value: &T) -> Result<Vec<Setting>> {
+        let (_, origins) = self.merged();
+        let table = to_table(value)?;
+        Ok(leaves(&table)
+            .into_iter()
+            .map(|(path, value)| Setting {
+                key: self.key(&path),
+                value: value.clone(),
+                origin: origins.get(&path).cloned().unwrap_or(Origin::Default),
+            })
+            .collect())
+    }
+
+    /// The layer that set `key` (a key as shown by [`Setting`])
+    pub fn origin(&self, key: &str) -> Origin {
+        let (_, origins) = self.merged();
+        origins
+            .into_iter()
+            .rev()
+            .find(|(path, _)| self.key(path) == key)
+            .map(|(_, origin)| origin)
+            .unwrap_or(Origin::Default)
+    }
+
+    /// Every override applied in order (serde fills in the defaults), and
+    /// the layer behind each overridden key
+    fn merged(&self) -> (Table, BTreeMap<Vec<String>, Origin>) {
+        let mut table = Table::new();
+        let mut origins = BTreeMap::new();
+        for (origin, layer) in &self.overrides {
+            for (path, value) in leaves(layer) {
+                set_path(&mut table, &path, value.clone());
+                origins.insert(path, origin.clone());
+            }
+        }
+        (table, origins)
+    }
+
+    /// Whether the group's `i`th key is read by some setting: it's kept
+    /// when the settings are written back, or it changes them (an old name
+    /// such as `use_metal`)
+    fn is_read<T: Serialize + DeserializeOwned>(
+        &self,
+        with_group: &Table,
+        group: &[(Vec<String>, Value)],
+        i: usize,
+        path: &[String],
+        value: &Value,
+    ) -> bool {
+        if get_path(with_group, path).is_some() {
+            return true;
+        }
+        let mut others = group.to_vec();
+        others.remove(i);
+        let resolve = |overrides: &[(Vec<String>, Value)]| {
+            deserialize::<T>(table_of(overrides))
+                .ok()
+                .and_then(|v| to_table(&v).ok())
+        };
+        let without = resolve(&others);
+        if without.as_ref() != Some(with_group) {
+            return true;
+        }
+        // The value may have been the default; a flipped flag shows it's read
+        if let Value::Boolean(b) = value {
+            others.push((path.to_vec(), Value::Boolean(!b)));
+            return resolve(&others) != without;
+        }
+        false
+    }
+
+    /// A key path as shown to the user, with this file's section
+    fn key(&self, path: &[String]) -> String {
+        let mut parts: Vec<String> = path.iter().map(|p| quote_key(p)).collect();
+        if !self.section.is_empty() {
+            parts.insert(0, self.section.clone());
+        }
+        parts.join(".")
+    }
+}
+
+/// Every effective setting of config.toml and daemon.toml (as `daemon.*`),
+/// with the layer it comes from
+pub fn effective_settings() -> Result<Vec<Setting>> {
+    let mut settings = Config::layers()?.settings(&Config::load()?)?;
+    settings.extend(DaemonConfig::layers()?.settings(&DaemonConfig::load()?)?);
+    Ok(settings)
+}
+
+/// Everything wrong with the settings: unknown keys, values of the wrong
+/// type, and values out of range
+pub fn validate() -> Result<Vec<ConfigIssue>> {
+    let config_layers = Config::layers()?;
+    let daemon_layers = DaemonConfig::layers()?;
+    let mut issues = config_layers.check::<Config>();
+    issues.extend(daemon_layers.check::<DaemonConfig>());
+
+    let mut problems = Vec::new();
+    if let Ok(config) = config_layers.resolve::<Config>() {
+        problems.extend(
+            config
+                .problems()
+                .into_iter()
+                .map(|(key, problem)| (config_layers.origin(&key), key, problem)),
+        );
+    }
+    if let Ok(daemon) = daemon_layers.resolve::<DaemonConfig>() {
+        problems.extend(daemon.problems().into_iter().map(|(key, problem)| {
+            let key = format!("{}.{}", DAEMON_SECTION, key);
+            (daemon_layers.origin(&key), key, problem)
+        }));
+    }
+    issues.extend(
+        problems
+            .into_iter()
+            .map(|(origin, key, problem)| ConfigIssue {
+                key,
+                origin,
+                problem,
+            }),
+    );
+    Ok(issues)
+}
+
+/// Keys whose values differ between two versions of the settings, sorted
+pub fn changed_keys<T: Serialize>(old: &T, new: &T) -> Result<Vec<String>> {
+    let (old, new) = (to_table(old)?, to_table(new)?);
+    let old_leaves: BTreeMap<Vec<String>, &Value> = leaves(&old).into_iter().collect();
+    let new_leaves: BTreeMap<Vec<String>, &Value> = leaves(&new).into_iter().collect();
+    let mut paths: Vec<&Vec<String>> = old_leaves.keys().chain(new_leaves.keys()).collect();
+    paths.sort();
+    paths.dedup();
+    Ok(paths
+        .into_iter()
+        .filter(|path| old_leaves.get(*path) != new_leaves.get(*path))
+        .map(|path| {
+            path.iter()
+                .map(|p| quote_key(p))
+                .collect::<Vec<_>>()
+                .join(".")
+        })
+        .collect())
+}
+
+/// The dotted key an environment variable overrides:
+/// `GROUNDEFFECT__SEARCH__EMBEDDING_BATCH_SIZE` -> `search.embedding_batch_size`
+fn env_key(name: &str) -> Option<String> {
+    let parts: Vec<String> = name
+        .strip_prefix(ENV_PREFIX)?
+        .split("__")
+        .map(str::to_lowercase)
+        .collect();
+    if parts.iter().any(String::is_empty) {
+        return None;
+    }
+    Some(parts.join("."))
+}
+
+/// `key` within `section` ("" for config.toml, whose keys are everything
+/// outside `daemon.`)
+fn section_key<'a>(key: &'a str, section: &str) -> Option<&'a str> {
+    if section.is_empty() {
+        let daemon = key
+            .strip_prefix(DAEMON_SECTION)
+            .is_some_and(|rest| rest.starts_with('.'));
+        (!daemon).then_some(key)
+    } else {
+        key.strip_prefix(section)?.strip_prefix('.')
+    }
+}
+
+/// A table holding `raw` at the dotted `key`
+fn override_table(key: &str, raw: &str) -> Result<Table> {
+    let invalid_key = || Error::Config(format!("Invalid setting name '{}'", key));
+
+    // Parse the key as TOML too, so quoted parts (`accounts."me@example.com"`) work
+    let mut node: Table = toml::from_str(&format!("{} = 0", key)).map_err(|_| invalid_key())?;
+    let mut path = Vec::new();
+    loop {
+        if node.len() != 1 {
+            return Err(invalid_key());
+        }
+        let (name, value) = node.into_iter().next().ok_or_else(invalid_key)?;
+        path.push(name);
+        match value {
+            Value::Table(inner) => node = inner,
+            Value::Integer(0) => break,
+            _ => return Err(invalid_key()),
+        }
+    }
+
+    let value = toml::from_str::<Table>(&format!("value = {}", raw))
+        .ok()
+        .filter(|t| t.len() == 1)
+        .and_then(|mut t| t.remove("value"))
+        .unwrap_or_else(|| Value::String(raw.to_string()));
+    let mut table = Table::new();
+    set_path(&mut table, &path, value);
+    Ok(table)
+}
+
+/// A table holding only `overrides`
+fn table_of(overrides: &[(Vec<String>, Value)]) -> Table {
+    let mut table = Table::new();
+    for (path, value) in overrides {
+        set_path(&mut table, path, value.clone());
+    }
+    table
+}
+
+/// Every non-table value under `table` with its key path
+fn leaves(table: &Table) -> Vec<(Vec<String>, &Value)> {
+    let mut out = Vec::new();
+    let mut pending = vec![(Vec::new(), table)];
+    while let Some((prefix, table)) = pending.pop() {
+        for (name, value) in table {
+            let mut path = prefix.clone();
+            path.push(name.clone());
+            match value {
+                Value::Table(inner) => pending.push((path, inner)),
+                value => out.push((path, value)),
+            }
+        }
+    }
+    out.sort_by(|a, b| a.0.cmp(&b.0));
+    out
+}
+
+fn set_path(table: &mut Table, path: &[String], value: Value) {
+    let Some((last, parents)) = path.split_last() else {
+        return;
+    };
+    let mut node = table;
+    for name in parents {
+        let entry = node
+            .entry(name.clone())
+            .or_insert_with(|| Value::Table(Table::new()));
+        if !entry.is_table() {
+            *entry = Value::Table(Table::new());
+        }
+        let Value::Table(inner) = entry else {
+            unreachable!()
+        };
+        node = inner;
+    }
+    node.insert(last.clone(), value);
+}
+
+fn get_path<'a>(table: &'a Table, path: &[String]) -> Option<&'a Value> {
+    let (last, parents) = path.split_last()?;
+    let mut node = table;
+    for name in parents {
+        node = node.get(name)?.as_table()?;
+    }
+    node.get(last)
+}
+
+fn to_table<T: Serialize>(value: &T) -> Result<Table> {
+    match Value::try_from(value).map_err(|e| Error::Config(e.to_string()))? {
+        Value::Table(table) => Ok(table),
+        _ => Err(Error::Config("Settings must be a table".to_string())),
+    }
+}
+
+fn deserialize<T: DeserializeOwned>(table: Table) -> std::result::Result<T, String> {
+    Value::Table(table)
+        .try_into()
+        .map_err(|e: toml::de::Error| e.message().to_string())
+}
+
+/// A key part, quoted if TOML needs it to be
+fn quote_key(part: &str) -> String {
+    let bare = !part.is_empty()
+        && part
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if bare {
+        part.to_string()
+    } else {
+        Value::String(part.to_string()).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layers(overrides: Vec<(Origin, &str)>) -> Layers {
+        Layers {
+            section: String::new(),
+            overrides: overrides
+                .into_iter()
+                .map(|(origin, toml)| (origin, toml::from_str(toml).unwrap()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_override_keys() {
+        assert_eq!(
+            env_key("GROUNDEFFECT__SEARCH__EMBEDDING_BATCH_SIZE").as_deref(),
+            Some("search.embedding_batch_size")
+        );
+        assert_eq!(env_key("GROUNDEFFECT_PROFILE"), None);
+        assert_eq!(env_key("GROUNDEFFECT__SEARCH____X"), None);
+        assert_eq!(section_key("daemon.logging_enabled", ""), None);
+        assert_eq!(
+            section_key("daemon.logging_enabled", DAEMON_SECTION),
+            Some("logging_enabled")
+        );
+        assert_eq!(section_key("daemonx.a", ""), Some("daemonx.a"));
+
+        let table = override_table("accounts.\"me@example.com\".folders", "[\"INBOX\"]").unwrap();
+        assert_eq!(
+            table.to_string().trim(),
+            "[accounts.\"me@example.com\"]\nfolders = [\"INBOX\"]"
+        );
+        let table = override_table("general.timezone", "America/New_York").unwrap();
+        assert_eq!(
+            table["general"]["timezone"].as_str(),
+            Some("America/New_York")
+        );
+        // A value can't smuggle in a second key
+        let table = override_table("ui.show_recent_items", "1\nother = 2").unwrap();
+        assert_eq!(
+            table["ui"]["show_recent_items"].as_str(),
+            Some("1\nother = 2")
+        );
+        assert!(override_table("a = 1 #", "2").is_err());
+    }
+
+    #[test]
+    fn test_later_layers_win_and_are_traced() {
+        let file = Origin::File(PathBuf::from("config.toml"));
+        let env = Origin::Env("GROUNDEFFECT__GENERAL__TIMEZONE".to_string());
+        let layers = layers(vec![
+            (
+                file.clone(),
+                "[general]\ntimezone = \"Europe/London\"\n[search]\nuse_metal = false",
+            ),
+            (env.clone(), "[general]\ntimezone = \"Asia/Tokyo\""),
+        ]);
+
+        let config: Config = layers.resolve().unwrap();
+        assert_eq!(config.general.timezone, "Asia/Tokyo");
+        assert!(!config.search.use_gpu);
+
+        let settings = layers.settings(&config).unwrap();
+        let origin = |key: &str| {
+            settings
+                .iter()
+                .find(|s| s.key == key)
+                .map(|s| s.origin.clone())
+        };
+        assert_eq!(origin("general.timezone"), Some(env.clone()));
+        assert_eq!(origin("general.log_level"), Some(Origin::Default));
+        assert_eq!(layers.origin("general.timezone"), env);
+        assert!(layers.check::<Config>().is_empty());
+    }
+
+    #[test]
+    fn test_check_names_unknown_keys_and_bad_values() {
+        let file = Origin::File(PathBuf::from("config.toml"));
+        let layers = layers(vec![
+            (
+                file.clone(),
+                "[search]\nuse_metal = true\ndefualt_limit = 5\n[tokens]\nprovider = \"encrypted_file\"",
+            ),
+            (Origin::Cli, "[sync]\nrate_limit_per_second = \"lots\""),
+        ]);
+
+        let issues = layers.check::<Config>();
+        let found: Vec<(&str, &Origin)> =
+            issues.iter().map(|i| (i.key.as_str(), &i.origin)).collect();
+        assert_eq!(
+            found,
+            vec![
+                ("search.defualt_limit", &file),
+                ("sync.rate_limit_per_second", &Origin::Cli)
+            ]
+        );
+        assert_eq!(issues[0].problem, "unknown key");
+
+        let err = layers.resolve::<Config>().unwrap_err().to_string();
+        assert!(
+            err.contains("sync.rate_limit_per_second (from --set)"),
+            "{}",
+            err
+        );
+    }
+}
@@ -0,0 +1,314 @@
+//! Control socket for the running daemon
+//!
+//! The daemon keeps LanceDB open and the embedding model loaded. It listens
+//! on a Unix socket in the data directory (`daemon.sock`) so CLI searches can
+//! run there instead of paying that startup cost on every invocation. Each
+//! connection carries one JSON request line and gets one JSON response line.
+//!
+//! Clients fall back to running in-process whenever the socket is missing,
+//! nothing is listening, or the daemon answers with an error. The socket
+//! also takes `groundeffect daemon reload` requests (see [`crate::reload`]).
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::oneshot;
+
+use crate::error::{Error, Result};
+use crate::models::EmailSearchResult;
+use crate::reload::ReloadReport;
+use crate::search::{
+    CalendarSearchOptions, CalendarSearchResult, SearchOptions, SearchPlan, ThreadSearchOptions,
+    ThreadSearchResult,
+};
+
+/// A request sent over the control socket
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum ControlRequest {
+    /// Check the daemon is listening; answers with its version and pid
+    Ping,
+
+    /// Hybrid email search; answers with `[results, plan]`
+    SearchEmails {
+        query: String,
+        options: SearchOptions,
+    },
+
+    /// Hybrid calendar search; answers with the results
+    SearchCalendar {
+        query: String,
+        options: CalendarSearchOptions,
+    },
+
+    /// Hybrid thread search; answers with the results
+    SearchThreads {
+        query: String,
+        options: ThreadSearchOptions,
+    },
+
+    /// Re-read the config and apply what can change at runtime; answers
+    /// with a [`ReloadReport`]
+    Reload,
+}
+
+/// The daemon's answer to a [`ControlRequest`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ControlResponse {
+    Ok(Value),
+    Error(String),
+}
+
+/// A reload asked for over the socket; the daemon answers on the sender
+pub type ReloadRequest = oneshot::Sender<Result<ReloadReport>>;
+
+/// Run an email search in the daemon, or None if it can't (not running,
+/// older version, or the search failed there)
+pub async fn search_emails(
+    socket: &Path,
+    query: &str,
+    options: &SearchOptions,
+) -> Option<(Vec<EmailSearchResult>, SearchPlan)> {
+    let request = ControlRequest::SearchEmails {
+        query: query.to_string(),
+        options: options.clone(),
+    };
+    let value = unix::request(socket, &request).await?;
+    serde_json::from_value(value).ok()
+}
+
+/// Run a calendar search in the daemon, or None if it can't
+pub async fn search_calendar(
+    socket: &Path,
+    query: &str,
+    options: &CalendarSearchOptions,
+) -> Option<Vec<CalendarSearchResult>> {
+    let request = ControlRequest::SearchCalendar {
+        query: query.to_string(),
+        options: options.clone(),
+    };
+    let value = unix::request(socket, &request).await?;
+    serde_json::from_value(value).ok()
+}
+
+/// Run a thread search in the daemon, or None if it can't
+pub async fn search_threads(
+    socket: &Path,
+    query: &str,
+    options: &ThreadSearchOptions,
+) -> Option<Vec<ThreadSearchResult>> {
+    let request = ControlRequest::SearchThreads {
+        query: query.to_string(),
+        options: options.clone(),
+    };
+    let value = unix::request(socket, &request).await?;
+    serde_json::from_value(value).ok()
+}
+
+/// The daemon's version and pid, or None if nothing is listening
+pub async fn ping(socket: &Path) -> Option<Value> {
+    unix::request(socket, &ControlRequest::Ping).await
+}
+
+/// Have the daemon reload its config. None if nothing is listening; an
+/// error if the daemon kept its old settings.
+pub async fn reload(socket: &Path) -> Option<Result<ReloadReport>> {
+    match unix::call(socket, &ControlRequest::Reload).await? {
+        ControlResponse::Ok(value) => Some(serde_json::from_value(value).map_err(Error::from)),
+        ControlResponse::Error(e) => Some(Err(Error::Other(e))),
+    }
+}
+
+#[cfg(unix)]
+pub use unix::serve;
+
+#[cfg(unix)]
+mod unix {
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+
+    use serde_json::Value;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::{UnixListener, UnixStream};
+    use tokio::sync::{mpsc, oneshot};
+    use tracing::{debug, info};
+
+    use super::{ControlRequest, ControlResponse, ReloadRequest};
+    use crate::error::{Error, Result};
+    use crate::search::SearchEngine;
+
+    /// Answer control requests on `socket` until the task is dropped
+    ///
+    /// Reload requests are passed on to `reloads`. A socket file left by a
+    /// daemon that didn't shut down cleanly is replaced. The socket is only
+    /// accessible to the current user.
+    pub async fn serve(
+        socket: PathBuf,
+        search: Arc<SearchEngine>,
+        reloads: mpsc::Sender<ReloadRequest>,
+    ) -> Result<()> {
+        if socket.exists() {
+            std::fs::remove_file(&socket)?;
+        }
+        let listener = UnixListener::bind(&socket)?;
+        std::fs::set_permissions(&socket, std::fs::Permissions::from_mode(0o600))?;
+        info!("Control socket listening on {:?}", socket);
+
+        loop {
+            let (stream, _) = listener.accept().await?;
+            let search = search.clone();
+            let reloads = reloads.clone();
+            tokio::spawn(async move {
+                if let Err(e) = handle_connection(stream, &search, &reloads).await {
+                    debug!("Control connection failed: {}", e);
+                }
+            });
+        }
+    }
+
+    async fn handle_connection(
+        stream: UnixStream,
+        search: &SearchEngine,
+        reloads: &mpsc::Sender<ReloadRequest>,
+    ) -> Result<()> {
+        let (reader, mut writer) = stream.into_split();
+        let mut line = String::new();
+        BufReader::new(reader).read_line(&mut line).await?;
+
+        let response = match serde_json::from_str::<ControlRequest>(&line) {
+            Ok(request) => match dispatch(request, search, reloads).await {
+                Ok(value) => ControlResponse::Ok(value),
+                Err(e) => ControlResponse::Error(e.to_string()),
+            },
+            Err(e) => ControlResponse::Error(format!("Invalid request: {}", e)),
+        };
+
+        let mut json = serde_json::to_string(&response)?;
+        json.push('\n');
+        writer.write_all(json.as_bytes()).await?;
+        writer.shutdown().await?;
+        Ok(())
+    }
+
+    async fn dispatch(
+        request: ControlRequest,
+        search: &SearchEngine,
+        reloads: &mpsc::Sender<ReloadRequest>,
+    ) -> Result<Value> {
+        match request {
+            ControlRequest::Ping => Ok(serde_json::json!({
+                "version": env!("CARGO_PKG_VERSION"),
+                "pid": std::process::id(),
+            })),
+            ControlRequest::SearchEmails { query, options } => {
+                let found = search.search_emails_with_plan(&query, &options).await?;
+                Ok(serde_json::to_value(found)?)
+            }
+            ControlRequest::SearchCalendar { query, options } => {
+                let results = search.search_calendar(&query, &options).await?;
+                Ok(serde_json::to_value(results)?)
+            }
+            ControlRequest::SearchThreads { query, options } => {
+                let results = search.search_threads(&query, &options).await?;
+                Ok(serde_json::to_value(results)?)
+            }
+            ControlRequest::Reload => {
+                let gone = || Error::Other("Daemon is shutting down".to_string());
+                let (tx, rx) = oneshot::channel();
+                reloads.send(tx).await.map_err(|_| gone())?;
+                let report = rx.await.map_err(|_| gone())??;
+                Ok(serde_json::to_value(report)?)
+            }
+        }
+    }
+
+    /// Send one request; None if the daemon isn't reachable or answered with an error
+    pub(super) async fn request(socket: &Path, request: &ControlRequest) -> Option<Value> {
+        match call(socket, request).await? {
+            ControlResponse::Ok(value) => Some(value),
+            ControlResponse::Error(e) => {
+                debug!("Daemon could not handle {:?}: {}", request, e);
+                None
+            }
+        }
+    }
+
+    /// Send one request; None if the daemon isn't reachable
+    pub(super) async fn call(socket: &Path, request: &ControlRequest) -> Option<ControlResponse> {
+        let stream = UnixStream::connect(socket).await.ok()?;
+        let (reader, mut writer) = stream.into_split();
+
+        let mut json = serde_json::to_string(request).ok()?;
+        json.push('\n');
+        writer.write_all(json.as_bytes()).await.ok()?;
+
+        let mut line = String::new();
+        BufReader::new(reader).read_line(&mut line).await.ok()?;
+        match serde_json::from_str(&line) {
+            Ok(response) => Some(response),
+            Err(e) => {
+                debug!("Unreadable control response: {}", e);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod unix {
+    use std::path::Path;
+
+    use serde_json::Value;
+
+    use super::{ControlRequest, ControlResponse};
+
+    /// No control socket on this platform; callers run in-process
+    pub(super) async fn request(_socket: &Path, _request: &ControlRequest) -> Option<Value> {
+        None
+    }
+
+    /// No control socket on this platform; there's no daemon to reach
+    pub(super) async fn call(_socket: &Path, _request: &ControlRequest) -> Option<ControlResponse> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_wire_format() {
+        let request = ControlRequest::SearchEmails {
+            query: "invoice".to_string(),
+            options: SearchOptions::new(5),
+        };
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["op"], "search_emails");
+        assert_eq!(json["options"]["limit"], 5);
+
+        let parsed: ControlRequest = serde_json::from_value(json).unwrap();
+        assert!(matches!(parsed, ControlRequest::SearchEmails { query, .. } if query == "invoice"));
+    }
+
+    #[test]
+    fn test_response_wire_format() {
+        let ok = serde_json::to_string(&ControlResponse::Ok(serde_json::json!([]))).unwrap();
+        assert_eq!(ok, r#"{"ok":[]}"#);
+        let err = serde_json::to_string(&ControlResponse::Error("boom".to_string())).unwrap();
+        assert_eq!(err, r#"{"error":"boom"}"#);
+    }
+
+    #[tokio::test]
+    async fn test_missing_socket_falls_back() {
+        let socket = std::env::temp_dir().join("groundeffect-no-such-daemon.sock");
+        assert!(ping(&socket).await.is_none());
+        assert!(search_emails(&socket, "q", &SearchOptions::new(5))
+            .await
+            .is_none());
+        assert!(reload(&socket).await.is_none());
+    }
+}
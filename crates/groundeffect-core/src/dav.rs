@@ -0,0 +1,621 @@
+//! Read-only CalDAV server for the merged calendar
+//!
+//! macOS Calendar (or any CalDAV client) can add the merged calendar of
+//! `[ics_feed]` as an account at `http://127.0.0.1:<port>/`. Only as much
+//! of CalDAV is implemented as that needs: discovery through the principal
+//! and calendar home, one calendar collection, PROPFIND listings with ETags
+//! and a collection CTag, and the calendar-multiget and calendar-query
+//! reports. Every privilege but read is withheld and writes answer 403, so
+//! clients show the calendar and its events as read-only.
+//!
+//! Requests are parsed by the MCP HTTP transport. The server only listens on
+//! loopback, and requests naming another Host are refused so web pages
+//! can't reach it through DNS rebinding.
+
+use std::net::SocketAddr;
+use std::sync::{Arc, OnceLock};
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use regex::Regex;
+use sha2::{Digest, Sha256};
+use tokio::io::BufReader;
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, info, warn};
+
+use crate::config::Config;
+use crate::db::Database;
+use crate::error::Result;
+use crate::ics::{feed_events, render_calendar, unique_events};
+use crate::mcp::http::{
+    constant_time_eq, read_request, write_response, HttpRequest, HttpResponse, IDLE_TIMEOUT,
+};
+use crate::models::{CalendarEvent, EventTime};
+
+/// Default port for the CalDAV server
+pub const CALDAV_DEFAULT_PORT: u16 = 8789;
+
+/// The one principal
+const PRINCIPAL_PATH: &str = "/principals/me/";
+
+/// Calendar home holding the merged calendar
+const HOME_PATH: &str = "/calendars/";
+
+/// The merged calendar collection
+const CALENDAR_PATH: &str = "/calendars/unified/";
+
+/// Methods answered at all (writes get 403)
+const ALLOW: &str = "OPTIONS, GET, HEAD, PROPFIND, REPORT";
+
+const XML_CONTENT_TYPE: &str = "application/xml; charset=utf-8";
+const EVENT_CONTENT_TYPE: &str = "text/calendar; charset=utf-8; component=vevent";
+
+/// A resource the server knows
+#[derive(Debug, PartialEq)]
+enum Resource {
+    Root,
+    Principal,
+    Home,
+    Calendar,
+    /// An event, by the name in its href (without `.ics`)
+    Event(String),
+}
+
+impl Resource {
+    /// Resource at a request path
+    fn at(path: &str) -> Option<Self> {
+        let path = path.split('?').next().unwrap_or_default();
+        let path = path.trim_end_matches('/');
+        match path {
+            "" => Some(Self::Root),
+            p if format!("{}/", p) == PRINCIPAL_PATH => Some(Self::Principal),
+            p if format!("{}/", p) == HOME_PATH => Some(Self::Home),
+            p if format!("{}/", p) == CALENDAR_PATH => Some(Self::Calendar),
+            p => p
+                .strip_prefix(CALENDAR_PATH)
+                .and_then(|name| name.strip_suffix(".ics"))
+                .filter(|name| !name.is_empty() && !name.contains('/'))
+                .map(|name| Self::Event(name.to_string())),
+        }
+    }
+}
+
+/// An event as served: href, ETag and iCalendar body
+struct DavEvent {
+    name: String,
+    etag: String,
+    ics: String,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+}
+
+impl DavEvent {
+    fn href(&self) -> String {
+        format!("{}{}.ics", CALENDAR_PATH, self.name)
+    }
+}
+
+/// The CalDAV server
+pub struct CaldavServer {
+    db: Arc<Database>,
+    config: Arc<Config>,
+}
+
+impl CaldavServer {
+    pub fn new(db: Arc<Database>, config: Arc<Config>) -> Self {
+        Self { db, config }
+    }
+
+    /// Serve on `127.0.0.1:[caldav] port` until the process exits
+    pub async fn run(self: Arc<Self>) -> Result<()> {
+        let addr = SocketAddr::from(([127, 0, 0, 1], self.config.caldav.port));
+        let listener = TcpListener::bind(addr).await?;
+        info!(
+            "Serving the merged calendar over CalDAV on http://{}/ (auth: {})",
+            addr,
+            if self.config.caldav.password.is_some() {
+                "password"
+            } else {
+                "none"
+            }
+        );
+        loop {
+            let (stream, peer) = match listener.accept().await {
+                Ok(conn) => conn,
+                Err(e) => {
+                    warn!("Failed to accept CalDAV connection: {}", e);
+                    continue;
+                }
+            };
+            let server = self.clone();
+            tokio::spawn(async move {
+                if let Err(e) = server.serve_connection(stream).await {
+                    debug!("CalDAV connection from {} ended: {}", peer, e);
+                }
+            });
+        }
+    }
+
+    /// Answer requests on one connection until it closes or goes idle
+    async fn serve_connection(&self, stream: TcpStream) -> Result<()> {
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+        loop {
+            let request = match tokio::time::timeout(IDLE_TIMEOUT, read_request(&mut reader)).await
+            {
+                Ok(Ok(Some(request))) => request,
+                Ok(Ok(None)) | Err(_) => return Ok(()),
+                Ok(Err(e)) => {
+                    let response = HttpResponse::text(400, &e.to_string());
+                    write_response(&mut writer, &response, true).await?;
+                    return Ok(());
+                }
+            };
+            let close = request.wants_close();
+            let response = match self.handle(&request).await {
+                Ok(response) => response,
+                Err(e) => {
+                    warn!("CalDAV {} {} failed: {}", request.method, request.path, e);
+                    HttpResponse::text(500, &e.to_string())
+                }
+            };
+            write_response(&mut writer, &response, close).await?;
+            if close {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Route one request
+    async fn handle(&self, request: &HttpRequest) -> Result<HttpResponse> {
+        if let Some(response) = check_access(request, self.config.caldav.password.as_deref()) {
+            return Ok(response);
+        }
+        if request.path.starts_with("/.well-known/caldav") {
+            return Ok(HttpResponse::empty(301).with_header("Location", PRINCIPAL_PATH));
+        }
+
+        match request.method.as_str() {
+            "OPTIONS" => Ok(HttpResponse::empty(200)
+                .with_header("DAV", "1, 3, calendar-access")
+                .with_header("Allow", ALLOW)),
+            "PROPFIND" => {
+                let Some(resource) = Resource::at(&request.path) else {
+                    return Ok(HttpResponse::text(404, "Not found"));
+                };
+                let children = request.header("depth").map(str::trim) != Some("0");
+                self.propfind(resource, children).await
+            }
+            "REPORT" => {
+                if Resource::at(&request.path) != Some(Resource::Calendar) {
+                    return Ok(HttpResponse::text(404, "Not found"));
+                }
+                self.report(&String::from_utf8_lossy(&request.body)).await
+            }
+            "GET" | "HEAD" => {
+                let Some(Resource::Event(name)) = Resource::at(&request.path) else {
+                    return Ok(HttpResponse::text(404, "Not found"));
+                };
+                let events = self.events().await?;
+                let Some(event) = events.into_iter().find(|e| e.name == name) else {
+                    return Ok(HttpResponse::text(404, "Not found"));
+                };
+                let mut response = HttpResponse {
+                    status: 200,
+                    content_type: Some(EVENT_CONTENT_TYPE),
+                    headers: vec![("ETag", event.etag)],
+                    body: event.ics.into_bytes(),
+                };
+                if request.method == "HEAD" {
+                    response.body.clear();
+                }
+                Ok(response)
+            }
+            "PUT" | "DELETE" | "PROPPATCH" | "MKCOL" | "MKCALENDAR" | "MOVE" | "COPY" | "POST"
+            | "LOCK" | "UNLOCK" | "ACL" => Ok(HttpResponse::text(403, "The calendar is read-only")),
+            _ => Ok(HttpResponse::empty(405).with_header("Allow", ALLOW)),
+        }
+    }
+
+    /// Properties of a resource and, unless Depth is 0, its children
+    async fn propfind(&self, resource: Resource, children: bool) -> Result<HttpResponse> {
+        let mut responses = Vec::new();
+        match resource {
+            Resource::Root | Resource::Principal => {
+                let href = if resource == Resource::Root {
+                    "/"
+                } else {
+                    PRINCIPAL_PATH
+                };
+                let principal = if resource == Resource::Principal {
+                    "<d:principal/>"
+                } else {
+                    ""
+                };
+                responses.push(dav_response(
+                    href,
+                    &format!(
+                        "<d:resourcetype><d:collection/>{}</d:resourcetype>{}<d:displayname>{}</d:displayname>",
+                        principal,
+                        principal_props(),
+                        xml_escape(&self.config.ics_feed.name)
+                    ),
+                ));
+            }
+            Resource::Home => {
+                responses.push(dav_response(
+                    HOME_PATH,
+                    &format!(
+                        "<d:resourcetype><d:collection/></d:resourcetype>{}{}",
+                        principal_props(),
+                        read_only_privileges()
+                    ),
+                ));
+                if children {
+                    let events = self.events().await?;
+                    responses.push(self.calendar_response(&events));
+                }
+            }
+            Resource::Calendar => {
+                let events = self.events().await?;
+                responses.push(self.calendar_response(&events));
+                if children {
+                    responses.extend(events.iter().map(event_props));
+                }
+            }
+            Resource::Event(name) => {
+                let events = self.events().await?;
+                let Some(event) = events.iter().find(|e| e.name == name) else {
+                    return Ok(HttpResponse::text(404, "Not found"));
+                };
+                responses.push(event_props(event));
+            }
+        }
+        Ok(multistatus(&responses))
+    }
+
+    /// Answer calendar-multiget (the events at the given hrefs) and
+    /// calendar-query (every event, within its time-range if one is given)
+    async fn report(&self, body: &str) -> Result<HttpResponse> {
+        let events = self.events().await?;
+        let mut responses = Vec::new();
+        if body.contains("calendar-multiget") {
+            for href in parse_hrefs(body) {
+                let name = match Resource::at(&href) {
+                    Some(Resource::Event(name)) => name,
+                    _ => String::new(),
+                };
+                match events.iter().find(|e| e.name == name) {
+                    Some(event) => responses.push(event_data(event)),
+                    None => responses.push(format!(
+                        "<d:response><d:href>{}</d:href><d:status>HTTP/1.1 404 Not Found</d:status></d:response>",
+                        xml_escape(&href)
+                    )),
+                }
+            }
+        } else {
+            let range = parse_time_range(body);
+            responses.extend(
+                events
+                    .iter()
+                    .filter(|e| match range {
+                        Some((start, end)) => e.start < end && e.end > start,
+                        None => true,
+                    })
+                    .map(event_data),
+            );
+        }
+        Ok(multistatus(&responses))
+    }
+
+    /// The calendar collection's properties
+    fn calendar_response(&self, events: &[DavEvent]) -> String {
+        let mut hasher = Sha256::new();
+        for event in events {
+            hasher.update(event.name.as_bytes());
+            hasher.update(event.etag.as_bytes());
+        }
+        let ctag = hex_prefix(&hasher.finalize());
+        dav_response(
+            CALENDAR_PATH,
+            &format!(
+                "<d:resourcetype><d:collection/><c:calendar/></d:resourcetype>\
+                 <d:displayname>{}</d:displayname>\
+                 <c:supported-calendar-component-set><c:comp name=\"VEVENT\"/></c:supported-calendar-component-set>\
+                 <cs:getctag>{}</cs:getctag>{}{}",
+                xml_escape(&self.config.ics_feed.name),
+                ctag,
+                principal_props(),
+                read_only_privileges()
+            ),
+        )
+    }
+
+    /// The merged events, each rendered on its own
+    async fn events(&self) -> Result<Vec<DavEvent>> {
+        let feed = &self.config.ics_feed;
+        let events = feed_events(&self.db, &self.config, Utc::now()).await?;
+        Ok(unique_events(&events)
+            .into_iter()
+            .map(|event| {
+                // DTSTAMP is the sync time, so the body (and ETag) only
+                // changes when the event does
+                let ics = render_calendar(
+                    std::slice::from_ref(event),
+                    &feed.name,
+                    feed.busy_only,
+                    event.synced_at,
+                );
+                let (start, end) = event_bounds(event);
+                DavEvent {
+                    name: event_name(&event.google_event_id),
+                    etag: format!("\"{}\"", hex_prefix(&Sha256::digest(ics.as_bytes()))),
+                    ics,
+                    start,
+                    end,
+                }
+            })
+            .collect())
+    }
+}
+
+/// Refuse requests for another Host, and without the password when one is set
+fn check_access(request: &HttpRequest, password: Option<&str>) -> Option<HttpResponse> {
+    if !is_local_host(request.header("host")) {
+        return Some(HttpResponse::text(403, "Host not allowed"));
+    }
+
+    let expected = password.filter(|p| !p.is_empty())?;
+    let given = request
+        .header("authorization")
+        .and_then(|v| v.strip_prefix("Basic "))
+        .and_then(|v| {
+            use base64::{engine::general_purpose::STANDARD, Engine};
+            STANDARD.decode(v.trim()).ok()
+        })
+        .and_then(|v| String::from_utf8(v).ok())
+        .and_then(|v| v.split_once(':').map(|(_, pass)| pass.to_string()))
+        .unwrap_or_default();
+    if constant_time_eq(given.as_bytes(), expected.as_bytes()) {
+        None
+    } else {
+        Some(
+            HttpResponse::text(401, "Wrong or missing password")
+                .with_header("WWW-Authenticate", "Basic realm=\"groundeffect\""),
+        )
+    }
+}
+
+/// Whether a Host header names this machine (a missing one is taken as
+/// loopback), so pages can't reach a loopback server through DNS rebinding
+pub(crate) fn is_local_host(host: Option<&str>) -> bool {
+    let host = host.unwrap_or("127.0.0.1");
+    let host = match host.strip_prefix('[') {
+        Some(v6) => v6.split(']').next().unwrap_or_default(),
+        None => host.split(':').next().unwrap_or_default(),
+    };
+    matches!(host, "localhost" | "127.0.0.1" | "::1")
+}
+
+/// Principal discovery properties shared by every collection
+fn principal_props() -> String {
+    format!(
+        "<d:current-user-principal><d:href>{p}</d:href></d:current-user-principal>\
+         <d:principal-URL><d:href>{p}</d:href></d:principal-URL>\
+         <c:calendar-home-set><d:href>{h}</d:href></c:calendar-home-set>",
+        p = PRINCIPAL_PATH,
+        h = HOME_PATH
+    )
+}
+
+/// Read is the only privilege
+fn read_only_privileges() -> &'static str {
+    "<d:current-user-privilege-set>\
+     <d:privilege><d:read/></d:privilege>\
+     <d:privilege><c:read-free-busy/></d:privilege>\
+     </d:current-user-privilege-set>"
+}
+
+/// An event's listing properties
+fn event_props(event: &DavEvent) -> String {
+    dav_response(
+        &event.href(),
+        &format!(
+            "<d:resourcetype/><d:getetag>{}</d:getetag><d:getcontenttype>{}</d:getcontenttype>",
+            xml_escape(&event.etag),
+            EVENT_CONTENT_TYPE
+        ),
+    )
+}
+
+/// An event's ETag and iCalendar data, for reports
+fn event_data(event: &DavEvent) -> String {
+    dav_response(
+        &event.href(),
+        &format!(
+            "<d:getetag>{}</d:getetag><c:calendar-data>{}</c:calendar-data>",
+            xml_escape(&event.etag),
+            xml_escape(&event.ics)
+        ),
+    )
+}
+
+/// One `<d:response>` with its properties found
+fn dav_response(href: &str, props: &str) -> String {
+    format!(
+        "<d:response><d:href>{}</d:href><d:propstat><d:prop>{}</d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>",
+        xml_escape(href),
+        props
+    )
+}
+
+/// A 207 Multi-Status response
+fn multistatus(responses: &[String]) -> HttpResponse {
+    let body = format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\
+         <d:multistatus xmlns:d=\"DAV:\" xmlns:c=\"urn:ietf:params:xml:ns:caldav\" xmlns:cs=\"http://calendarserver.org/ns/\">{}</d:multistatus>",
+        responses.concat()
+    );
+    HttpResponse {
+        status: 207,
+        content_type: Some(XML_CONTENT_TYPE),
+        headers: Vec::new(),
+        body: body.into_bytes(),
+    }
+}
+
+/// Hrefs listed in a calendar-multiget body, as paths
+fn parse_hrefs(body: &str) -> Vec<String> {
+    static HREF: OnceLock<Regex> = OnceLock::new();
+    let re = HREF.get_or_init(|| {
+        Regex::new(r"(?s)<(?:[A-Za-z0-9_-]+:)?href(?:\s[^>]*)?>\s*([^<]*?)\s*</").unwrap()
+    });
+    re.captures_iter(body)
+        .map(|c| {
+            let href = c[1].trim();
+            // Absolute URLs are reduced to their path
+            match href.split_once("://") {
+                Some((_, rest)) => rest.find('/').map(|i| &rest[i..]).unwrap_or("/"),
+                None => href,
+            }
+            .to_string()
+        })
+        .collect()
+}
+
+/// The `time-range` of a calendar-query, when it gives both ends
+fn parse_time_range(body: &str) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    static TIME_RANGE: OnceLock<Regex> = OnceLock::new();
+    let re = TIME_RANGE.get_or_init(|| {
+        Regex::new(r#"time-range[^>]*?start="(\d{8}T\d{6}Z)"[^>]*?end="(\d{8}T\d{6}Z)""#).unwrap()
+    });
+    let caps = re.captures(body)?;
+    let parse = |s: &str| {
+        NaiveDateTime::parse_from_str(s, "%Y%m%dT%H%M%SZ")
+            .ok()
+            .map(|t| t.and_utc())
+    };
+    Some((parse(&caps[1])?, parse(&caps[2])?))
+}
+
+/// Start and end of an event in UTC (all-day dates from midnight UTC)
+fn event_bounds(event: &CalendarEvent) -> (DateTime<Utc>, DateTime<Utc>) {
+    let utc = |time: &EventTime| match time {
+        EventTime::DateTime(dt) => *dt,
+        EventTime::Date(d) => d.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc(),
+    };
+    let start = utc(&event.start);
+    (start, utc(&event.end).max(start))
+}
+
+/// An event's href name: its Google event ID, limited to URL-safe characters
+fn event_name(google_event_id: &str) -> String {
+    google_event_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+/// First 16 hex digits of a digest
+fn hex_prefix(digest: &[u8]) -> String {
+    digest
+        .iter()
+        .take(8)
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn request(headers: &[(&str, &str)]) -> HttpRequest {
+        HttpRequest {
+            method: "PROPFIND".to_string(),
+            path: "/".to_string(),
+            headers: headers
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            body: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_resource_paths() {
+        assert_eq!(Resource::at("/"), Some(Resource::Root));
+        assert_eq!(Resource::at("/principals/me"), Some(Resource::Principal));
+        assert_eq!(Resource::at("/calendars/"), Some(Resource::Home));
+        assert_eq!(Resource::at("/calendars/unified"), Some(Resource::Calendar));
+        assert_eq!(
+            Resource::at("/calendars/unified/abc_20240603T090000Z.ics"),
+            Some(Resource::Event("abc_20240603T090000Z".to_string()))
+        );
+        assert_eq!(Resource::at("/calendars/other/x.ics"), None);
+        assert_eq!(event_name("a b@c"), "a-b-c");
+    }
+
+    #[test]
+    fn test_access_checks_host_and_password() {
+        assert!(check_access(&request(&[("Host", "127.0.0.1:8789")]), None).is_none());
+        assert!(check_access(&request(&[("Host", "localhost")]), None).is_none());
+        assert_eq!(
+            check_access(&request(&[("Host", "evil.example:8789")]), None)
+                .unwrap()
+                .status,
+            403
+        );
+
+        // "me:s3cret" and "me:nope"
+        let ok = request(&[("Authorization", "Basic bWU6czNjcmV0")]);
+        assert!(check_access(&ok, Some("s3cret")).is_none());
+        let wrong = request(&[("Authorization", "Basic bWU6bm9wZQ==")]);
+        assert_eq!(check_access(&wrong, Some("s3cret")).unwrap().status, 401);
+        assert_eq!(
+            check_access(&request(&[]), Some("s3cret")).unwrap().status,
+            401
+        );
+    }
+
+    #[test]
+    fn test_report_bodies() {
+        let multiget = r#"<?xml version="1.0"?>
+            <C:calendar-multiget xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+              <D:prop><D:getetag/><C:calendar-data/></D:prop>
+              <D:href>/calendars/unified/a1.ics</D:href>
+              <href xmlns="DAV:">http://127.0.0.1:8789/calendars/unified/b2.ics</href>
+            </C:calendar-multiget>"#;
+        assert_eq!(
+            parse_hrefs(multiget),
+            vec!["/calendars/unified/a1.ics", "/calendars/unified/b2.ics"]
+        );
+
+        let query = r#"<C:calendar-query xmlns:C="urn:ietf:params:xml:ns:caldav">
+              <C:filter><C:comp-filter name="VCALENDAR"><C:comp-filter name="VEVENT">
+                <C:time-range start="20240601T000000Z" end="20240701T000000Z"/>
+              </C:comp-filter></C:comp-filter></C:filter>
+            </C:calendar-query>"#;
+        assert_eq!(
+            parse_time_range(query),
+            Some((
+                Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2024, 7, 1, 0, 0, 0).unwrap()
+            ))
+        );
+        assert_eq!(parse_time_range("<C:calendar-query/>"), None);
+    }
+}
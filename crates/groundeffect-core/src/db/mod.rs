@@ -4,33 +4,62 @@
 //! Uses LanceDB's built-in BM25 full-text search and vector ANN search.
 
 mod schema;
+mod transaction;
+mod usage;
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use arrow_array::{
-    ArrayRef, Float32Array, Int64Array, RecordBatch, RecordBatchIterator, StringArray, UInt32Array,
-    UInt64Array,
+    Array, ArrayRef, FixedSizeListArray, Float32Array, Int64Array, RecordBatch,
+    RecordBatchIterator, StringArray, UInt32Array, UInt64Array,
 };
 use arrow_schema::{DataType, Field, Schema};
 use chrono::{DateTime, Utc};
 use lancedb::index::scalar::FtsIndexBuilder;
 use lancedb::index::Index;
 use lancedb::query::{ExecutableQuery, QueryBase};
+use lancedb::table::{NewColumnTransform, OptimizeAction, OptimizeOptions};
 use lancedb::{connect, Connection, Table};
 use parking_lot::RwLock;
 use tracing::{debug, info};
 
+use crate::config::EmbeddingConfig;
 use crate::error::{Error, Result};
-use crate::models::{Account, CalendarEvent, Email};
+use crate::models::{
+    Account, ActivityItem, ActivitySource, AuditActor, AuditEntry, CalendarEvent, Email,
+    EmailCategory, EventFilter, Extraction, ExtractionKind, ItineraryItem, ItineraryKind, Receipt,
+    StorageUsage, Task, TaskSource, TaskStatus, Thread,
+};
+use crate::recovery::FtsCoverage;
+use crate::rename::{rewrite_batch, RenamedRows, Rewrite};
+use crate::vectors::{VectorRecord, VectorTable};
 use crate::EMBEDDING_DIMENSION;
 
 pub use schema::*;
+pub use transaction::*;
+pub use usage::*;
 
 /// Database table names
 pub const EMAILS_TABLE: &str = "emails";
 pub const EVENTS_TABLE: &str = "events";
 pub const ACCOUNTS_TABLE: &str = "accounts";
+pub const ACTIVITY_TABLE: &str = "activity";
+pub const RECEIPTS_TABLE: &str = "receipts";
+pub const ITINERARY_TABLE: &str = "itinerary";
+pub const AUDIT_TABLE: &str = "audit_log";
+pub const THREADS_TABLE: &str = "threads";
+pub const EXTRACTIONS_TABLE: &str = "extractions";
+pub const TASKS_TABLE: &str = "tasks";
+
+/// Columns with full-text indexes, by table
+pub const FTS_COLUMNS: &[(&str, &str)] = &[
+    (EMAILS_TABLE, "subject"),
+    (EMAILS_TABLE, "body_plain"),
+    (EVENTS_TABLE, "summary"),
+    (EVENTS_TABLE, "description"),
+    (THREADS_TABLE, "content"),
+];
 
 /// Date validation constants for sync boundary calculations.
 /// Dates outside this range are ignored to prevent a single bad record from breaking sync.
@@ -45,17 +74,103 @@ fn is_reasonable_date(dt: &DateTime<Utc>) -> bool {
     dt.year() >= MIN_VALID_YEAR && dt.year() <= max_year
 }
 
+/// Add nullable columns present in `expected` but missing from an existing table.
+///
+/// Unlike the accounts table, the emails table is too large to drop and rebuild,
+/// so new email columns are appended in place and backfilled with NULL.
+///
+/// Returns the names of the columns that were added.
+async fn add_missing_columns(
+    table: &Table,
+    table_name: &str,
+    expected: &Schema,
+) -> Result<Vec<String>> {
+    let existing = table.schema().await?;
+    let missing: Vec<(String, String)> = expected
+        .fields()
+        .iter()
+        .filter(|field| existing.field_with_name(field.name()).is_err())
+        .filter_map(|field| {
+            let sql_type = match field.data_type() {
+                DataType::Utf8 => "STRING",
+                DataType::Int64 => "BIGINT",
+                DataType::Boolean => "BOOLEAN",
+                DataType::Float32 => "FLOAT",
+                _ => return None,
+            };
+            Some((field.name().clone(), format!("CAST(NULL AS {})", sql_type)))
+        })
+        .collect();
+
+    if missing.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    info!(
+        "Adding columns {:?} to {} table",
+        missing
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>(),
+        table_name
+    );
+    let added = missing.iter().map(|(name, _)| name.clone()).collect();
+    table
+        .add_columns(NewColumnTransform::SqlExpressions(missing), None)
+        .await?;
+    Ok(added)
+}
+
+/// Width of a table's `embedding` column
+async fn stored_dimension(table: &Table, table_name: &str) -> Result<usize> {
+    let schema = table.schema().await?;
+    match schema.field_with_name("embedding").map(|f| f.data_type()) {
+        Ok(DataType::FixedSizeList(_, size)) => Ok(*size as usize),
+        _ => Err(Error::Other(format!(
+            "{} table has no fixed-size embedding column",
+            table_name
+        ))),
+    }
+}
+
 /// LanceDB database wrapper
 pub struct Database {
     connection: Connection,
+    /// Directory the database lives in
+    path: PathBuf,
     emails: RwLock<Option<Table>>,
     events: RwLock<Option<Table>>,
     accounts: RwLock<Option<Table>>,
+    activity: RwLock<Option<Table>>,
+    receipts: RwLock<Option<Table>>,
+    itinerary: RwLock<Option<Table>>,
+    audit: RwLock<Option<Table>>,
+    threads: RwLock<Option<Table>>,
+    extractions: RwLock<Option<Table>>,
+    tasks: RwLock<Option<Table>>,
+    /// Embedding column widths, read from the tables when they're opened
+    email_dimension: RwLock<usize>,
+    event_dimension: RwLock<usize>,
+    /// Held while emails are stored: two writers replacing the same rows at
+    /// once could both delete before either adds, leaving duplicates
+    email_writes: tokio::sync::Mutex<()>,
 }
 
 impl Database {
     /// Open or create a database at the given path
+    ///
+    /// New emails and events tables get [`EMBEDDING_DIMENSION`]-wide vector
+    /// columns; see [`Database::open_with_dimension`].
     pub async fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::open_with_dimension(path, EMBEDDING_DIMENSION).await
+    }
+
+    /// Open or create a database, creating missing emails and events tables
+    /// with `dimension`-wide vector columns
+    ///
+    /// Existing tables keep the width they were built with (see
+    /// [`Database::vector_dimension`] and [`Database::resize_vectors`]).
+    pub async fn open_with_dimension(path: impl AsRef<Path>, dimension: usize) -> Result<Self> {
         let path = path.as_ref();
         info!("Opening LanceDB at {:?}", path);
 
@@ -68,9 +183,20 @@ impl Database {
 
         let db = Self {
             connection,
+            path: path.to_path_buf(),
             emails: RwLock::new(None),
             events: RwLock::new(None),
             accounts: RwLock::new(None),
+            activity: RwLock::new(None),
+            receipts: RwLock::new(None),
+            itinerary: RwLock::new(None),
+            audit: RwLock::new(None),
+            threads: RwLock::new(None),
+            extractions: RwLock::new(None),
+            tasks: RwLock::new(None),
+            email_dimension: RwLock::new(dimension),
+            event_dimension: RwLock::new(dimension),
+            email_writes: tokio::sync::Mutex::new(()),
         };
 
         // Initialize tables
@@ -88,7 +214,7 @@ impl Database {
         // Create emails table if it doesn't exist
         if !table_names.contains(&EMAILS_TABLE.to_string()) {
             info!("Creating emails table");
-            let schema = email_schema();
+            let schema = email_schema(*self.email_dimension.read());
             let batch = empty_email_batch(&schema);
             let batches = RecordBatchIterator::new(vec![Ok(batch)], Arc::new(schema.clone()));
             let table = self
@@ -113,19 +239,48 @@ impl Database {
                 .execute()
                 .await?;
 
+            // And on message_id, for resolving Message-ID references
+            table
+                .create_index(&["message_id"], Index::BTree(Default::default()))
+                .execute()
+                .await?;
+
             // Note: Vector index will be created lazily once we have data
             // LanceDB requires data to train the IVF index
 
             *self.emails.write() = Some(table);
         } else {
             let table = self.connection.open_table(EMAILS_TABLE).execute().await?;
+            let dimension = stored_dimension(&table, EMAILS_TABLE).await?;
+            let added = add_missing_columns(&table, EMAILS_TABLE, &email_schema(dimension)).await?;
+            if added.iter().any(|name| name == "is_sent") {
+                // Existing rows predate folder tracking; mail from the
+                // account's own address is the best signal they have
+                table
+                    .update()
+                    .only_if("is_sent IS NULL")
+                    .column("is_sent", "lower(from_email) = lower(account_id)")
+                    .execute()
+                    .await?;
+                // They also stored the INBOX UID as the Gmail message and
+                // thread ID, which collides with Sent folder UIDs; mark them
+                // unknown so they stay out of threading
+                table
+                    .update()
+                    .only_if("gmail_message_id = uid")
+                    .column("gmail_message_id", "CAST(0 AS BIGINT UNSIGNED)")
+                    .column("gmail_thread_id", "CAST(0 AS BIGINT UNSIGNED)")
+                    .execute()
+                    .await?;
+            }
+            *self.email_dimension.write() = dimension;
             *self.emails.write() = Some(table);
         }
 
         // Create events table if it doesn't exist
         if !table_names.contains(&EVENTS_TABLE.to_string()) {
             info!("Creating events table");
-            let schema = event_schema();
+            let schema = event_schema(*self.event_dimension.read());
             let batch = empty_event_batch(&schema);
             let batches = RecordBatchIterator::new(vec![Ok(batch)], Arc::new(schema.clone()));
             let table = self
@@ -155,6 +310,9 @@ impl Database {
             *self.events.write() = Some(table);
         } else {
             let table = self.connection.open_table(EVENTS_TABLE).execute().await?;
+            let dimension = stored_dimension(&table, EVENTS_TABLE).await?;
+            add_missing_columns(&table, EVENTS_TABLE, &event_schema(dimension)).await?;
+            *self.event_dimension.write() = dimension;
             *self.events.write() = Some(table);
         }
 
@@ -230,10 +388,163 @@ impl Database {
             }
         }
 
+        // Create activity table if it doesn't exist
+        if !table_names.contains(&ACTIVITY_TABLE.to_string()) {
+            info!("Creating activity table");
+            let schema = activity_schema();
+            let batch = empty_activity_batch(&schema);
+            let batches = RecordBatchIterator::new(vec![Ok(batch)], Arc::new(schema.clone()));
+            let table = self
+                .connection
+                .create_table(ACTIVITY_TABLE, Box::new(batches))
+                .execute()
+                .await?;
+            *self.activity.write() = Some(table);
+        } else {
+            let table = self.connection.open_table(ACTIVITY_TABLE).execute().await?;
+            *self.activity.write() = Some(table);
+        }
+
+        // Create receipts table if it doesn't exist
+        if !table_names.contains(&RECEIPTS_TABLE.to_string()) {
+            info!("Creating receipts table");
+            let schema = receipt_schema();
+            let batch = empty_receipt_batch(&schema);
+            let batches = RecordBatchIterator::new(vec![Ok(batch)], Arc::new(schema.clone()));
+            let table = self
+                .connection
+                .create_table(RECEIPTS_TABLE, Box::new(batches))
+                .execute()
+                .await?;
+            *self.receipts.write() = Some(table);
+        } else {
+            let table = self.connection.open_table(RECEIPTS_TABLE).execute().await?;
+            *self.receipts.write() = Some(table);
+        }
+
+        // Create itinerary table if it doesn't exist
+        if !table_names.contains(&ITINERARY_TABLE.to_string()) {
+            info!("Creating itinerary table");
+            let schema = itinerary_schema();
+            let batch = empty_itinerary_batch(&schema);
+            let batches = RecordBatchIterator::new(vec![Ok(batch)], Arc::new(schema.clone()));
+            let table = self
+                .connection
+                .create_table(ITINERARY_TABLE, Box::new(batches))
+                .execute()
+                .await?;
+            *self.itinerary.write() = Some(table);
+        } else {
+            let table = self
+                .connection
+                .open_table(ITINERARY_TABLE)
+                .execute()
+                .await?;
+            *self.itinerary.write() = Some(table);
+        }
+
+        // Create extractions table if it doesn't exist
+        if !table_names.contains(&EXTRACTIONS_TABLE.to_string()) {
+            info!("Creating extractions table");
+            let schema = extraction_schema();
+            let batch = empty_extraction_batch(&schema);
+            let batches = RecordBatchIterator::new(vec![Ok(batch)], Arc::new(schema.clone()));
+            let table = self
+                .connection
+                .create_table(EXTRACTIONS_TABLE, Box::new(batches))
+                .execute()
+                .await?;
+            *self.extractions.write() = Some(table);
+        } else {
+            let table = self
+                .connection
+                .open_table(EXTRACTIONS_TABLE)
+                .execute()
+                .await?;
+            *self.extractions.write() = Some(table);
+        }
+
+        // Create tasks table if it doesn't exist
+        if !table_names.contains(&TASKS_TABLE.to_string()) {
+            info!("Creating tasks table");
+            let schema = task_schema();
+            let batch = empty_task_batch(&schema);
+            let batches = RecordBatchIterator::new(vec![Ok(batch)], Arc::new(schema.clone()));
+            let table = self
+                .connection
+                .create_table(TASKS_TABLE, Box::new(batches))
+                .execute()
+                .await?;
+            *self.tasks.write() = Some(table);
+        } else {
+            let table = self.connection.open_table(TASKS_TABLE).execute().await?;
+            *self.tasks.write() = Some(table);
+        }
+
+        // Create audit log table if it doesn't exist
+        if !table_names.contains(&AUDIT_TABLE.to_string()) {
+            info!("Creating audit log table");
+            let schema = audit_schema();
+            let batch = empty_audit_batch(&schema);
+            let batches = RecordBatchIterator::new(vec![Ok(batch)], Arc::new(schema.clone()));
+            let table = self
+                .connection
+                .create_table(AUDIT_TABLE, Box::new(batches))
+                .execute()
+                .await?;
+            *self.audit.write() = Some(table);
+        } else {
+            let table = self.connection.open_table(AUDIT_TABLE).execute().await?;
+            *self.audit.write() = Some(table);
+        }
+
+        // Threads are derived from emails and share their vector width; a
+        // table left at another width is rebuilt rather than migrated
+        let email_dimension = *self.email_dimension.read();
+        let table = if table_names.contains(&THREADS_TABLE.to_string()) {
+            let table = self.connection.open_table(THREADS_TABLE).execute().await?;
+            if stored_dimension(&table, THREADS_TABLE).await? == email_dimension {
+                Some(table)
+            } else {
+                info!("Threads table vector width differs from emails, recreating it");
+                self.connection.drop_table(THREADS_TABLE, &[]).await?;
+                None
+            }
+        } else {
+            None
+        };
+        let table = match table {
+            Some(table) => table,
+            None => self.create_threads_table(email_dimension).await?,
+        };
+        *self.threads.write() = Some(table);
+
         info!("Database tables initialized");
         Ok(())
     }
 
+    /// Create an empty threads table with `dimension`-wide vectors
+    async fn create_threads_table(&self, dimension: usize) -> Result<Table> {
+        info!("Creating threads table");
+        let schema = thread_schema(dimension);
+        let batch = empty_thread_batch(&schema);
+        let batches = RecordBatchIterator::new(vec![Ok(batch)], Arc::new(schema.clone()));
+        let table = self
+            .connection
+            .create_table(THREADS_TABLE, Box::new(batches))
+            .execute()
+            .await?;
+        table
+            .create_index(&["content"], Index::FTS(FtsIndexBuilder::default()))
+            .execute()
+            .await?;
+        table
+            .create_index(&["id"], Index::BTree(Default::default()))
+            .execute()
+            .await?;
+        Ok(table)
+    }
+
     /// Refresh table handles to see latest data
     /// Call this before queries if data may have been written by another process
     pub async fn refresh_tables(&self) -> Result<()> {
@@ -241,16 +552,54 @@ impl Database {
 
         if table_names.contains(&EMAILS_TABLE.to_string()) {
             let table = self.connection.open_table(EMAILS_TABLE).execute().await?;
+            *self.email_dimension.write() = stored_dimension(&table, EMAILS_TABLE).await?;
             *self.emails.write() = Some(table);
         }
         if table_names.contains(&EVENTS_TABLE.to_string()) {
             let table = self.connection.open_table(EVENTS_TABLE).execute().await?;
+            *self.event_dimension.write() = stored_dimension(&table, EVENTS_TABLE).await?;
             *self.events.write() = Some(table);
         }
         if table_names.contains(&ACCOUNTS_TABLE.to_string()) {
             let table = self.connection.open_table(ACCOUNTS_TABLE).execute().await?;
             *self.accounts.write() = Some(table);
         }
+        if table_names.contains(&ACTIVITY_TABLE.to_string()) {
+            let table = self.connection.open_table(ACTIVITY_TABLE).execute().await?;
+            *self.activity.write() = Some(table);
+        }
+        if table_names.contains(&RECEIPTS_TABLE.to_string()) {
+            let table = self.connection.open_table(RECEIPTS_TABLE).execute().await?;
+            *self.receipts.write() = Some(table);
+        }
+        if table_names.contains(&ITINERARY_TABLE.to_string()) {
+            let table = self
+                .connection
+                .open_table(ITINERARY_TABLE)
+                .execute()
+                .await?;
+            *self.itinerary.write() = Some(table);
+        }
+        if table_names.contains(&EXTRACTIONS_TABLE.to_string()) {
+            let table = self
+                .connection
+                .open_table(EXTRACTIONS_TABLE)
+                .execute()
+                .await?;
+            *self.extractions.write() = Some(table);
+        }
+        if table_names.contains(&TASKS_TABLE.to_string()) {
+            let table = self.connection.open_table(TASKS_TABLE).execute().await?;
+            *self.tasks.write() = Some(table);
+        }
+        if table_names.contains(&AUDIT_TABLE.to_string()) {
+            let table = self.connection.open_table(AUDIT_TABLE).execute().await?;
+            *self.audit.write() = Some(table);
+        }
+        if table_names.contains(&THREADS_TABLE.to_string()) {
+            let table = self.connection.open_table(THREADS_TABLE).execute().await?;
+            *self.threads.write() = Some(table);
+        }
 
         debug!("Refreshed table handles");
         Ok(())
@@ -301,6 +650,17 @@ impl Database {
                     debug!("emails.id index: {}", e);
                 }
             }
+
+            if !existing_columns.contains("message_id") {
+                info!("Creating BTree index on emails.message_id...");
+                if let Err(e) = table
+                    .create_index(&["message_id"], Index::BTree(Default::default()))
+                    .execute()
+                    .await
+                {
+                    debug!("emails.message_id index: {}", e);
+                }
+            }
         }
 
         // Events table indexes
@@ -323,6 +683,26 @@ impl Database {
             }
         }
 
+        // Threads table indexes
+        if let Ok(table) = self.threads_table() {
+            let existing_indices = table.list_indices().await.unwrap_or_default();
+            let existing_columns: std::collections::HashSet<_> = existing_indices
+                .iter()
+                .flat_map(|idx| idx.columns.clone())
+                .collect();
+
+            if !existing_columns.contains("content") {
+                info!("Creating FTS index on threads.content...");
+                if let Err(e) = table
+                    .create_index(&["content"], Index::FTS(FtsIndexBuilder::default()))
+                    .execute()
+                    .await
+                {
+                    debug!("threads.content FTS index: {}", e);
+                }
+            }
+        }
+
         debug!("Index check complete");
         Ok(())
     }
@@ -371,10 +751,78 @@ impl Database {
             }
         }
 
+        // Rebuild threads FTS index
+        if let Ok(table) = self.threads_table() {
+            if let Err(e) = table
+                .create_index(&["content"], Index::FTS(FtsIndexBuilder::default()))
+                .execute()
+                .await
+            {
+                debug!("Failed to rebuild threads.content FTS index: {}", e);
+            }
+        }
+
         info!("FTS index rebuild complete in {:?}", start.elapsed());
         Ok(())
     }
 
+    /// Add rows written since the last update to the existing indexes of
+    /// the tables in [`FTS_COLUMNS`], without re-reading the rows already
+    /// indexed
+    pub async fn update_fts_indexes(&self) -> Result<()> {
+        info!("Updating FTS indexes...");
+        let start = std::time::Instant::now();
+        let mut tables: Vec<&str> = FTS_COLUMNS.iter().map(|&(table, _)| table).collect();
+        tables.dedup();
+        for table_name in tables {
+            let Ok(table) = self.connection.open_table(table_name).execute().await else {
+                continue;
+            };
+            if let Err(e) = table
+                .optimize(OptimizeAction::Index(OptimizeOptions::default()))
+                .await
+            {
+                debug!("Failed to update {} indexes: {}", table_name, e);
+            }
+        }
+        info!("FTS index update complete in {:?}", start.elapsed());
+        Ok(())
+    }
+
+    /// How many rows each full-text index in [`FTS_COLUMNS`] covers and
+    /// misses; tables that can't be opened are left out
+    pub async fn fts_coverage(&self) -> Result<Vec<FtsCoverage>> {
+        let mut coverage = Vec::new();
+        for &(table_name, column) in FTS_COLUMNS {
+            let Ok(table) = self.connection.open_table(table_name).execute().await else {
+                continue;
+            };
+            let indices = table.list_indices().await?;
+            let stats = match indices
+                .iter()
+                .find(|index| index.columns.iter().any(|c| c == column))
+            {
+                Some(index) => table.index_stats(&index.name).await?,
+                None => None,
+            };
+            coverage.push(match stats {
+                Some(stats) => FtsCoverage {
+                    table: table_name,
+                    column,
+                    indexed_rows: Some(stats.num_indexed_rows),
+                    unindexed_rows: stats.num_unindexed_rows,
+                },
+                None => FtsCoverage {
+                    table: table_name,
+                    column,
+                    indexed_rows: None,
+                    unindexed_rows: table.count_rows(None).await?,
+                },
+            });
+        }
+        Ok(coverage)
+    }
+
     /// Get the emails table
     pub fn emails_table(&self) -> Result<Table> {
         self.emails
@@ -383,6 +831,53 @@ impl Database {
             .ok_or_else(|| Error::TableNotFound(EMAILS_TABLE.to_string()))
     }
 
+    /// Width of a table's stored embedding column
+    pub fn vector_dimension(&self, table: VectorTable) -> usize {
+        match table {
+            VectorTable::Emails => *self.email_dimension.read(),
+            VectorTable::Events => *self.event_dimension.read(),
+        }
+    }
+
+    /// Rebuild a table's embedding column at a new width
+    ///
+    /// Used when the configured embedding model changes dimension. Every
+    /// stored vector is discarded (rows fall back to BM25 until re-embedded),
+    /// but the rows themselves are kept in place.
+    pub async fn resize_vectors(&self, table: VectorTable, dimension: usize) -> Result<()> {
+        let (lance_table, schema) = match table {
+            VectorTable::Emails => (self.emails_table()?, email_schema(dimension)),
+            VectorTable::Events => (self.events_table()?, event_schema(dimension)),
+        };
+        let field = schema.field_with_name("embedding")?.clone();
+
+        info!(
+            "Resizing {} embedding column from {} to {} dimensions",
+            table.as_str(),
+            self.vector_dimension(table),
+            dimension
+        );
+        lance_table.drop_columns(&["embedding"]).await?;
+        lance_table
+            .add_columns(
+                NewColumnTransform::AllNulls(Arc::new(Schema::new(vec![field]))),
+                None,
+            )
+            .await?;
+
+        match table {
+            VectorTable::Emails => {
+                *self.email_dimension.write() = dimension;
+                // Thread vectors come from the same model; they're recomputed
+                // by rebuilding the threads
+                self.connection.drop_table(THREADS_TABLE, &[]).await?;
+                *self.threads.write() = Some(self.create_threads_table(dimension).await?);
+            }
+            VectorTable::Events => *self.event_dimension.write() = dimension,
+        }
+        Ok(())
+    }
+
     /// Get the events table
     pub fn events_table(&self) -> Result<Table> {
         self.events
@@ -399,11 +894,92 @@ impl Database {
             .ok_or_else(|| Error::TableNotFound(ACCOUNTS_TABLE.to_string()))
     }
 
+    /// Row count of every table, or the error opening or counting it
+    pub async fn table_row_counts(&self) -> Vec<(&'static str, Result<usize>)> {
+        let mut counts = Vec::new();
+        for name in [
+            EMAILS_TABLE,
+            EVENTS_TABLE,
+            ACCOUNTS_TABLE,
+            ACTIVITY_TABLE,
+            RECEIPTS_TABLE,
+            ITINERARY_TABLE,
+            EXTRACTIONS_TABLE,
+            TASKS_TABLE,
+            AUDIT_TABLE,
+            THREADS_TABLE,
+        ] {
+            let count = match self.connection.open_table(name).execute().await {
+                Ok(table) => table.count_rows(None).await.map_err(Error::from),
+                Err(e) => Err(Error::from(e)),
+            };
+            counts.push((name, count));
+        }
+        counts
+    }
+
+    /// Get the activity table
+    pub fn activity_table(&self) -> Result<Table> {
+        self.activity
+            .read()
+            .clone()
+            .ok_or_else(|| Error::TableNotFound(ACTIVITY_TABLE.to_string()))
+    }
+
+    /// Get the receipts table
+    pub fn receipts_table(&self) -> Result<Table> {
+        self.receipts
+            .read()
+            .clone()
+            .ok_or_else(|| Error::TableNotFound(RECEIPTS_TABLE.to_string()))
+    }
+
+    /// Get the itinerary table
+    pub fn itinerary_table(&self) -> Result<Table> {
+        self.itinerary
+            .read()
+            .clone()
+            .ok_or_else(|| Error::TableNotFound(ITINERARY_TABLE.to_string()))
+    }
+
+    /// Get the extractions table
+    pub fn extractions_table(&self) -> Result<Table> {
+        self.extractions
+            .read()
+            .clone()
+            .ok_or_else(|| Error::TableNotFound(EXTRACTIONS_TABLE.to_string()))
+    }
+
+    /// Get the tasks table
+    pub fn tasks_table(&self) -> Result<Table> {
+        self.tasks
+            .read()
+            .clone()
+            .ok_or_else(|| Error::TableNotFound(TASKS_TABLE.to_string()))
+    }
+
+    /// Get the audit log table
+    pub fn audit_table(&self) -> Result<Table> {
+        self.audit
+            .read()
+            .clone()
+            .ok_or_else(|| Error::TableNotFound(AUDIT_TABLE.to_string()))
+    }
+
+    /// Get the threads table
+    pub fn threads_table(&self) -> Result<Table> {
+        self.threads
+            .read()
+            .clone()
+            .ok_or_else(|| Error::TableNotFound(THREADS_TABLE.to_string()))
+    }
+
     /// Insert or update an email
     pub async fn upsert_email(&self, email: &Email) -> Result<()> {
         let table = self.emails_table()?;
-        let batch = email_to_batch(email)?;
-        let batches = RecordBatchIterator::new(vec![Ok(batch)], Arc::new(email_schema()));
+        let dimension = self.vector_dimension(VectorTable::Emails);
+        let batch = email_to_batch(email, dimension)?;
+        let batches = RecordBatchIterator::new(vec![Ok(batch)], Arc::new(email_schema(dimension)));
 
         // Delete existing if present
         table.delete(&format!("id = '{}'", email.id)).await.ok(); // Ignore if not found
@@ -429,93 +1005,408 @@ impl Database {
         table.delete(&filter).await.ok();
 
         // Insert new
-        let batch = emails_to_batch(emails)?;
-        let batches = RecordBatchIterator::new(vec![Ok(batch)], Arc::new(email_schema()));
+        let dimension = self.vector_dimension(VectorTable::Emails);
+        let batch = emails_to_batch(emails, dimension)?;
+        let batches = RecordBatchIterator::new(vec![Ok(batch)], Arc::new(email_schema(dimension)));
         table.add(Box::new(batches)).execute().await?;
 
         debug!("Upserted {} emails", emails.len());
         Ok(())
     }
 
-    /// Insert or update a calendar event
-    pub async fn upsert_event(&self, event: &CalendarEvent) -> Result<()> {
-        self.upsert_events(&[event.clone()]).await
+    /// Store emails together with the activity items and receipts derived
+    /// from them; either all of them land or none do
+    pub async fn store_emails_with_derived(
+        &self,
+        emails: &[Email],
+        activity: &[ActivityItem],
+        receipts: &[Receipt],
+    ) -> Result<()> {
+        let _writing = self.email_writes.lock().await;
+        let mut tx = self.transaction();
+        let result = async {
+            tx.upsert_emails(emails).await?;
+            tx.upsert_activity(activity).await?;
+            tx.upsert_receipts(receipts).await
+        }
+        .await;
+        tx.finish(result).await
     }
 
-    /// Insert or update multiple calendar events
-    pub async fn upsert_events(&self, events: &[CalendarEvent]) -> Result<()> {
-        if events.is_empty() {
+    /// Insert or update activity items parsed from notification emails
+    pub async fn upsert_activity(&self, items: &[ActivityItem]) -> Result<()> {
+        if items.is_empty() {
             return Ok(());
         }
 
-        let table = self.events_table()?;
+        let table = self.activity_table()?;
 
         // Delete existing
-        let ids: Vec<String> = events.iter().map(|e| format!("'{}'", e.id)).collect();
+        let ids: Vec<String> = items.iter().map(|i| format!("'{}'", i.id)).collect();
         let filter = format!("id IN ({})", ids.join(", "));
         table.delete(&filter).await.ok();
 
         // Insert new
-        let batch = events_to_batch(events)?;
-        let batches = RecordBatchIterator::new(vec![Ok(batch)], Arc::new(event_schema()));
+        let batch = activity_to_batch(items)?;
+        let batches = RecordBatchIterator::new(vec![Ok(batch)], Arc::new(activity_schema()));
         table.add(Box::new(batches)).execute().await?;
 
-        debug!("Upserted {} events", events.len());
+        debug!("Upserted {} activity items", items.len());
         Ok(())
     }
 
-    /// Insert or update an account
-    pub async fn upsert_account(&self, account: &Account) -> Result<()> {
-        let table = self.accounts_table()?;
-
-        // Read existing account data as backup before deletion
-        let existing = self.get_account(&account.id).await?;
-
-        // Prepare the new batch
-        let batch = account_to_batch(account)?;
-        let batches = RecordBatchIterator::new(vec![Ok(batch)], Arc::new(account_schema()));
+    /// Insert or update receipts extracted from emails
+    pub async fn upsert_receipts(&self, receipts: &[Receipt]) -> Result<()> {
+        if receipts.is_empty() {
+            return Ok(());
+        }
 
-        // Delete existing if present
-        table.delete(&format!("id = '{}'", account.id)).await.ok();
+        let table = self.receipts_table()?;
 
-        // Try to insert new data
-        let result = table.add(Box::new(batches)).execute().await;
+        // Delete existing
+        let ids: Vec<String> = receipts.iter().map(|r| format!("'{}'", r.id)).collect();
+        let filter = format!("id IN ({})", ids.join(", "));
+        table.delete(&filter).await.ok();
 
-        // If insert fails, try to restore the old data
-        if let Err(e) = result {
-            tracing::error!("Failed to insert account {}: {}", account.id, e);
-            if let Some(old_account) = existing {
-                tracing::warn!(
-                    "Attempting to restore previous account data for {}",
-                    account.id
-                );
-                // Try to restore the old account data
-                let restore_batch = account_to_batch(&old_account)?;
-                let restore_batches =
-                    RecordBatchIterator::new(vec![Ok(restore_batch)], Arc::new(account_schema()));
-                if let Err(restore_err) = table.add(Box::new(restore_batches)).execute().await {
-                    tracing::error!(
-                        "Failed to restore account {}: {} - DATA LOSS OCCURRED",
-                        account.id,
-                        restore_err
-                    );
-                } else {
-                    tracing::info!(
-                        "Successfully restored previous account data for {}",
-                        account.id
-                    );
-                }
-            }
-            return Err(Error::Database(e));
-        }
+        // Insert new
+        let batch = receipts_to_batch(receipts)?;
+        let batches = RecordBatchIterator::new(vec![Ok(batch)], Arc::new(receipt_schema()));
+        table.add(Box::new(batches)).execute().await?;
 
-        debug!("Upserted account {}", account.id);
+        debug!("Upserted {} receipts", receipts.len());
         Ok(())
     }
 
-    /// Get an email by ID
-    pub async fn get_email(&self, id: &str) -> Result<Option<Email>> {
-        let table = self.emails_table()?;
+    /// Insert or update itinerary items parsed from confirmation emails.
+    ///
+    /// Calendar event IDs already recorded for an item are kept, so re-syncing
+    /// an email doesn't add its flights to the calendar twice. Returns the
+    /// items as stored.
+    pub async fn upsert_itinerary(&self, items: &[ItineraryItem]) -> Result<Vec<ItineraryItem>> {
+        if items.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let table = self.itinerary_table()?;
+        let ids: Vec<String> = items.iter().map(|i| format!("'{}'", i.id)).collect();
+        let filter = format!("id IN ({})", ids.join(", "));
+
+        // Carry over event IDs from the stored rows
+        let results = table.query().only_if(filter.clone()).execute().await?;
+        let batches: Vec<RecordBatch> = results.try_collect().await?;
+        let existing: std::collections::HashMap<String, String> = batches
+            .iter()
+            .flat_map(|batch| {
+                (0..batch.num_rows()).filter_map(move |i| batch_to_itinerary(batch, i))
+            })
+            .filter_map(|item| Some((item.id, item.event_id?)))
+            .collect();
+        let items: Vec<ItineraryItem> = items
+            .iter()
+            .cloned()
+            .map(|mut item| {
+                if item.event_id.is_none() {
+                    item.event_id = existing.get(&item.id).cloned();
+                }
+                item
+            })
+            .collect();
+
+        // Delete existing
+        table.delete(&filter).await.ok();
+
+        // Insert new
+        let batch = itinerary_to_batch(&items)?;
+        let batches = RecordBatchIterator::new(vec![Ok(batch)], Arc::new(itinerary_schema()));
+        table.add(Box::new(batches)).execute().await?;
+
+        debug!("Upserted {} itinerary items", items.len());
+        Ok(items)
+    }
+
+    /// Insert or update reservations and package deliveries found in emails
+    pub async fn upsert_extractions(&self, items: &[Extraction]) -> Result<()> {
+        if items.is_empty() {
+            return Ok(());
+        }
+
+        let table = self.extractions_table()?;
+
+        // Delete existing
+        let ids: Vec<String> = items.iter().map(|i| format!("'{}'", i.id)).collect();
+        table
+            .delete(&format!("id IN ({})", ids.join(", ")))
+            .await
+            .ok();
+
+        // Insert new
+        let batch = extractions_to_batch(items)?;
+        let batches = RecordBatchIterator::new(vec![Ok(batch)], Arc::new(extraction_schema()));
+        table.add(Box::new(batches)).execute().await?;
+
+        debug!("Upserted {} extractions", items.len());
+        Ok(())
+    }
+
+    /// Insert or update tasks found in emails
+    ///
+    /// Tasks already marked done stay done when their email is processed
+    /// again, and heuristic tasks are dropped for emails an MCP client has
+    /// written tasks for.
+    pub async fn upsert_tasks(&self, tasks: &[Task]) -> Result<()> {
+        if tasks.is_empty() {
+            return Ok(());
+        }
+
+        let table = self.tasks_table()?;
+        let mut email_ids: Vec<String> =
+            tasks.iter().map(|t| format!("'{}'", t.email_id)).collect();
+        email_ids.sort();
+        email_ids.dedup();
+
+        let results = table
+            .query()
+            .only_if(format!("email_id IN ({})", email_ids.join(", ")))
+            .execute()
+            .await?;
+        let batches: Vec<RecordBatch> = results.try_collect().await?;
+        let existing: Vec<Task> = batches
+            .iter()
+            .flat_map(|batch| (0..batch.num_rows()).map(move |i| batch_to_task(batch, i)))
+            .collect();
+        let reviewed: std::collections::HashSet<&str> = existing
+            .iter()
+            .filter(|t| t.source == TaskSource::Assistant)
+            .map(|t| t.email_id.as_str())
+            .collect();
+        let done: std::collections::HashMap<&str, Option<DateTime<Utc>>> = existing
+            .iter()
+            .filter(|t| t.status == TaskStatus::Done)
+            .map(|t| (t.id.as_str(), t.completed_at))
+            .collect();
+
+        let tasks: Vec<Task> = tasks
+            .iter()
+            .filter(|t| {
+                t.source == TaskSource::Assistant || !reviewed.contains(t.email_id.as_str())
+            })
+            .cloned()
+            .map(|mut task| {
+                if let Some(completed_at) = done.get(task.id.as_str()) {
+                    task.status = TaskStatus::Done;
+                    task.completed_at = *completed_at;
+                }
+                task
+            })
+            .collect();
+        if tasks.is_empty() {
+            return Ok(());
+        }
+
+        // Delete existing
+        let ids: Vec<String> = tasks.iter().map(|t| format!("'{}'", t.id)).collect();
+        table
+            .delete(&format!("id IN ({})", ids.join(", ")))
+            .await
+            .ok();
+
+        // Insert new
+        let batch = tasks_to_batch(&tasks)?;
+        let batches = RecordBatchIterator::new(vec![Ok(batch)], Arc::new(task_schema()));
+        table.add(Box::new(batches)).execute().await?;
+
+        debug!("Upserted {} tasks", tasks.len());
+        Ok(())
+    }
+
+    /// Replace an email's open tasks with `tasks` (written by an MCP client
+    /// that read the email); returns the tasks as stored
+    ///
+    /// Tasks already marked done are kept, and new tasks are numbered past
+    /// them so they don't pick up a done task's status.
+    pub async fn set_email_tasks(&self, email_id: &str, tasks: &[Task]) -> Result<Vec<Task>> {
+        let table = self.tasks_table()?;
+        let email_filter = format!("email_id = '{}'", email_id.replace('\'', "''"));
+        table
+            .delete(&format!("{} AND status = 'open'", email_filter))
+            .await?;
+
+        let results = table.query().only_if(email_filter).execute().await?;
+        let batches: Vec<RecordBatch> = results.try_collect().await?;
+        let kept: std::collections::HashSet<String> = batches
+            .iter()
+            .flat_map(|batch| (0..batch.num_rows()).map(move |i| batch_to_task(batch, i).id))
+            .collect();
+
+        let mut index = 0;
+        let tasks: Vec<Task> = tasks
+            .iter()
+            .cloned()
+            .map(|mut task| {
+                while kept.contains(&Task::id_for(email_id, task.source, index)) {
+                    index += 1;
+                }
+                task.id = Task::id_for(email_id, task.source, index);
+                index += 1;
+                task
+            })
+            .collect();
+        self.upsert_tasks(&tasks).await?;
+        Ok(tasks)
+    }
+
+    /// Mark a task open or done; returns the updated task, or None if there
+    /// is no task with that ID
+    pub async fn set_task_status(&self, id: &str, status: TaskStatus) -> Result<Option<Task>> {
+        let table = self.tasks_table()?;
+        let filter = format!("id = '{}'", id.replace('\'', "''"));
+
+        let results = table.query().only_if(filter.clone()).execute().await?;
+        let batches: Vec<RecordBatch> = results.try_collect().await?;
+        let Some(mut task) = batches
+            .iter()
+            .find(|b| b.num_rows() > 0)
+            .map(|batch| batch_to_task(batch, 0))
+        else {
+            return Ok(None);
+        };
+
+        task.completed_at = match status {
+            TaskStatus::Done => Some(Utc::now()),
+            TaskStatus::Open => None,
+        };
+        task.status = status;
+        let completed_at = task
+            .completed_at
+            .map(|t| t.timestamp().to_string())
+            .unwrap_or_else(|| "NULL".to_string());
+        table
+            .update()
+            .only_if(filter)
+            .column("status", format!("'{}'", status.as_str()))
+            .column("completed_at", completed_at)
+            .execute()
+            .await?;
+        Ok(Some(task))
+    }
+
+    /// Insert or update conversation threads
+    pub async fn upsert_threads(&self, threads: &[Thread]) -> Result<()> {
+        if threads.is_empty() {
+            return Ok(());
+        }
+
+        let table = self.threads_table()?;
+
+        // Delete existing
+        let ids: Vec<String> = threads
+            .iter()
+            .map(|t| format!("'{}'", t.id.replace('\'', "''")))
+            .collect();
+        let filter = format!("id IN ({})", ids.join(", "));
+        table.delete(&filter).await.ok();
+
+        // Insert new
+        let dimension = self.vector_dimension(VectorTable::Emails);
+        let batch = threads_to_batch(threads, dimension)?;
+        let batches = RecordBatchIterator::new(vec![Ok(batch)], Arc::new(thread_schema(dimension)));
+        table.add(Box::new(batches)).execute().await?;
+
+        debug!("Upserted {} threads", threads.len());
+        Ok(())
+    }
+
+    /// Append an entry to the audit log (entries are never updated or deleted)
+    pub async fn record_audit(&self, entry: &AuditEntry) -> Result<()> {
+        let table = self.audit_table()?;
+        let batch = audit_to_batch(std::slice::from_ref(entry))?;
+        let batches = RecordBatchIterator::new(vec![Ok(batch)], Arc::new(audit_schema()));
+        table.add(Box::new(batches)).execute().await?;
+
+        debug!("Recorded audit entry {} ({})", entry.action, entry.id);
+        Ok(())
+    }
+
+    /// Insert or update a calendar event
+    pub async fn upsert_event(&self, event: &CalendarEvent) -> Result<()> {
+        self.upsert_events(&[event.clone()]).await
+    }
+
+    /// Insert or update multiple calendar events
+    pub async fn upsert_events(&self, events: &[CalendarEvent]) -> Result<()> {
+        if events.is_empty() {
+            return Ok(());
+        }
+
+        let table = self.events_table()?;
+
+        // Delete existing
+        let ids: Vec<String> = events.iter().map(|e| format!("'{}'", e.id)).collect();
+        let filter = format!("id IN ({})", ids.join(", "));
+        table.delete(&filter).await.ok();
+
+        // Insert new
+        let dimension = self.vector_dimension(VectorTable::Events);
+        let batch = events_to_batch(events, dimension)?;
+        let batches = RecordBatchIterator::new(vec![Ok(batch)], Arc::new(event_schema(dimension)));
+        table.add(Box::new(batches)).execute().await?;
+
+        debug!("Upserted {} events", events.len());
+        Ok(())
+    }
+
+    /// Insert or update an account
+    pub async fn upsert_account(&self, account: &Account) -> Result<()> {
+        let table = self.accounts_table()?;
+
+        // Read existing account data as backup before deletion
+        let existing = self.get_account(&account.id).await?;
+
+        // Prepare the new batch
+        let batch = account_to_batch(account)?;
+        let batches = RecordBatchIterator::new(vec![Ok(batch)], Arc::new(account_schema()));
+
+        // Delete existing if present
+        table.delete(&format!("id = '{}'", account.id)).await.ok();
+
+        // Try to insert new data
+        let result = table.add(Box::new(batches)).execute().await;
+
+        // If insert fails, try to restore the old data
+        if let Err(e) = result {
+            tracing::error!("Failed to insert account {}: {}", account.id, e);
+            if let Some(old_account) = existing {
+                tracing::warn!(
+                    "Attempting to restore previous account data for {}",
+                    account.id
+                );
+                // Try to restore the old account data
+                let restore_batch = account_to_batch(&old_account)?;
+                let restore_batches =
+                    RecordBatchIterator::new(vec![Ok(restore_batch)], Arc::new(account_schema()));
+                if let Err(restore_err) = table.add(Box::new(restore_batches)).execute().await {
+                    tracing::error!(
+                        "Failed to restore account {}: {} - DATA LOSS OCCURRED",
+                        account.id,
+                        restore_err
+                    );
+                } else {
+                    tracing::info!(
+                        "Successfully restored previous account data for {}",
+                        account.id
+                    );
+                }
+            }
+            return Err(Error::Database(e));
+        }
+
+        debug!("Upserted account {}", account.id);
+        Ok(())
+    }
+
+    /// Get an email by ID
+    pub async fn get_email(&self, id: &str) -> Result<Option<Email>> {
+        let table = self.emails_table()?;
         let results = table
             .query()
             .only_if(&format!("id = '{}'", id))
@@ -532,6 +1423,91 @@ impl Database {
         Ok(Some(email))
     }
 
+    /// Stored embedding of an email (None when the email is unknown or was
+    /// stored without one)
+    pub async fn get_email_embedding(&self, id: &str) -> Result<Option<Vec<f32>>> {
+        let table = self.emails_table()?;
+        let results = table
+            .query()
+            .only_if(&format!("id = '{}'", id.replace('\'', "''")))
+            .select(lancedb::query::Select::columns(&["id", "embedding"]))
+            .limit(1)
+            .execute()
+            .await?;
+
+        let batches: Vec<RecordBatch> = results.try_collect().await?;
+        let Some(batch) = batches.iter().find(|b| b.num_rows() > 0) else {
+            return Ok(None);
+        };
+        Ok(batch
+            .column_by_name("embedding")
+            .and_then(|c| c.as_any().downcast_ref::<FixedSizeListArray>())
+            .filter(|e| !e.is_null(0))
+            .and_then(|e| {
+                e.value(0)
+                    .as_any()
+                    .downcast_ref::<Float32Array>()
+                    .map(|v| v.values().to_vec())
+            })
+            .filter(|v| v.iter().any(|x| *x != 0.0)))
+    }
+
+    /// Embeddings of several emails by ID, leaving out unembedded ones
+    pub async fn get_email_embeddings(
+        &self,
+        ids: &[String],
+    ) -> Result<std::collections::HashMap<String, Vec<f32>>> {
+        let mut embeddings = std::collections::HashMap::new();
+        if ids.is_empty() {
+            return Ok(embeddings);
+        }
+
+        let table = self.emails_table()?;
+        let id_list: Vec<String> = ids
+            .iter()
+            .map(|id| format!("'{}'", id.replace('\'', "''")))
+            .collect();
+        let results = table
+            .query()
+            .only_if(&format!("id IN ({})", id_list.join(", ")))
+            .select(lancedb::query::Select::columns(&["id", "embedding"]))
+            .execute()
+            .await?;
+
+        let batches: Vec<RecordBatch> = results.try_collect().await?;
+        for batch in &batches {
+            let Some(id_col) = batch
+                .column_by_name("id")
+                .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+            else {
+                continue;
+            };
+            let Some(emb_col) = batch
+                .column_by_name("embedding")
+                .and_then(|c| c.as_any().downcast_ref::<FixedSizeListArray>())
+            else {
+                continue;
+            };
+            for i in 0..batch.num_rows() {
+                if emb_col.is_null(i) {
+                    continue;
+                }
+                let Some(values) = emb_col
+                    .value(i)
+                    .as_any()
+                    .downcast_ref::<Float32Array>()
+                    .map(|v| v.values().to_vec())
+                else {
+                    continue;
+                };
+                if values.iter().any(|x| *x != 0.0) {
+                    embeddings.insert(id_col.value(i).to_string(), values);
+                }
+            }
+        }
+        Ok(embeddings)
+    }
+
     /// Get multiple emails by ID in a single query (batch fetch)
     pub async fn get_emails_batch(&self, ids: &[String]) -> Result<Vec<Email>> {
         if ids.is_empty() {
@@ -559,11 +1535,25 @@ impl Database {
     }
 
     /// Get all emails in a thread by gmail_thread_id
+    ///
+    /// Looks the message IDs up in the threads table and fetches just those;
+    /// threads not built yet fall back to scanning the emails table.
     pub async fn get_emails_by_thread(
         &self,
         thread_id: u64,
         account_id: Option<&str>,
     ) -> Result<Vec<Email>> {
+        let threads = self.find_threads(thread_id, account_id).await?;
+        if !threads.is_empty() {
+            let ids: Vec<String> = threads.into_iter().flat_map(|t| t.email_ids).collect();
+            let mut emails = self.get_emails_batch(&ids).await?;
+            if emails.len() == ids.len() {
+                emails.sort_by(|a, b| a.date.cmp(&b.date));
+                return Ok(emails);
+            }
+            debug!("Thread {} is out of date, scanning emails", thread_id);
+        }
+
         let table = self.emails_table()?;
 
         let mut filter = format!("gmail_thread_id = {}", thread_id);
@@ -588,70 +1578,341 @@ impl Database {
         Ok(emails)
     }
 
-    /// Get emails that have attachments but haven't been downloaded yet
-    pub async fn get_emails_with_pending_attachments(
+    /// Emails with this RFC 5322 Message-ID, newest first
+    ///
+    /// Takes the id with or without angle brackets and matches both forms. The
+    /// same message is stored once per account that has it (e.g. sent from one
+    /// account to another).
+    pub async fn get_emails_by_message_id(
         &self,
-        account_id: &str,
+        message_id: &str,
+        account_id: Option<&str>,
     ) -> Result<Vec<Email>> {
         let table = self.emails_table()?;
 
-        // Query emails with non-empty attachments
-        // We filter for emails that have attachments JSON and check in Rust if downloaded
-        let filter = format!("account_id = '{}' AND attachments IS NOT NULL", account_id);
+        let bare = message_id
+            .trim_start_matches('<')
+            .trim_end_matches('>')
+            .replace('\'', "''");
+        let mut filter = format!("message_id IN ('{}', '<{}>')", bare, bare);
+        if let Some(acct) = account_id {
+            filter.push_str(&format!(" AND account_id = '{}'", acct.replace('\'', "''")));
+        }
 
         let results = table.query().only_if(&filter).execute().await?;
-
         let batches: Vec<RecordBatch> = results.try_collect().await?;
 
-        let mut pending_emails = Vec::new();
+        let mut emails = Vec::new();
         for batch in &batches {
             for i in 0..batch.num_rows() {
-                let email = batch_to_email(batch, i)?;
-                // Check if any attachment hasn't been downloaded
-                let has_pending = email.attachments.iter().any(|att| !att.downloaded);
-                if has_pending && !email.attachments.is_empty() {
-                    pending_emails.push(email);
-                }
+                emails.push(batch_to_email(batch, i)?);
             }
         }
+        emails.sort_by(|a, b| b.date.cmp(&a.date));
 
-        Ok(pending_emails)
+        Ok(emails)
     }
 
-    /// Get attachment statistics for an account
-    /// Returns (total_attachments, downloaded_attachments, total_size_bytes)
-    pub async fn get_attachment_stats(&self, account_id: &str) -> Result<(usize, usize, u64)> {
+    /// Emails of an account in any of these Gmail threads (in no particular order)
+    ///
+    /// Reads the emails table directly, for building the threads themselves.
+    pub async fn get_emails_in_threads(
+        &self,
+        account_id: &str,
+        thread_ids: &[u64],
+    ) -> Result<Vec<Email>> {
+        if thread_ids.is_empty() {
+            return Ok(Vec::new());
+        }
         let table = self.emails_table()?;
 
-        let filter = format!("account_id = '{}' AND attachments IS NOT NULL", account_id);
-
+        let ids: Vec<String> = thread_ids.iter().map(|id| id.to_string()).collect();
+        let filter = format!(
+            "account_id = '{}' AND gmail_thread_id IN ({})",
+            account_id,
+            ids.join(", ")
+        );
         let results = table.query().only_if(&filter).execute().await?;
-
         let batches: Vec<RecordBatch> = results.try_collect().await?;
 
-        let mut total = 0usize;
-        let mut downloaded = 0usize;
-        let mut total_size = 0u64;
-
+        let mut emails = Vec::new();
         for batch in &batches {
             for i in 0..batch.num_rows() {
-                let email = batch_to_email(batch, i)?;
-                for att in &email.attachments {
-                    total += 1;
-                    if att.downloaded {
-                        downloaded += 1;
-                        total_size += att.size as u64;
-                    }
-                }
+                emails.push(batch_to_email(batch, i)?);
             }
         }
-
-        Ok((total, downloaded, total_size))
+        Ok(emails)
     }
 
-    /// Get an account by ID (email address)
-    pub async fn get_account(&self, id: &str) -> Result<Option<Account>> {
-        let table = self.accounts_table()?;
+    /// Distinct (account, Gmail thread ID) pairs in the emails table
+    pub async fn list_thread_keys(&self, account_id: Option<&str>) -> Result<Vec<(String, u64)>> {
+        let table = self.emails_table()?;
+
+        let mut query = table.query().select(lancedb::query::Select::columns(&[
+            "account_id",
+            "gmail_thread_id",
+        ]));
+        if let Some(id) = account_id {
+            query = query.only_if(&format!("account_id = '{}'", id));
+        }
+        let results = query.execute().await?;
+        let batches: Vec<RecordBatch> = results.try_collect().await?;
+
+        let mut keys = std::collections::BTreeSet::new();
+        for batch in &batches {
+            let accounts = batch
+                .column_by_name("account_id")
+                .and_then(|c| c.as_any().downcast_ref::<StringArray>());
+            let threads = batch
+                .column_by_name("gmail_thread_id")
+                .and_then(|c| c.as_any().downcast_ref::<UInt64Array>());
+            let (Some(accounts), Some(threads)) = (accounts, threads) else {
+                continue;
+            };
+            for i in 0..batch.num_rows() {
+                if threads.value(i) != 0 {
+                    keys.insert((accounts.value(i).to_string(), threads.value(i)));
+                }
+            }
+        }
+        Ok(keys.into_iter().collect())
+    }
+
+    /// Get a thread by its ID (`{account_id}:{gmail_thread_id}`)
+    pub async fn get_thread(&self, id: &str) -> Result<Option<Thread>> {
+        let table = self.threads_table()?;
+        let results = table
+            .query()
+            .only_if(format!("id = '{}'", id.replace('\'', "''")))
+            .execute()
+            .await?;
+        let batches: Vec<RecordBatch> = results.try_collect().await?;
+        Ok(batches
+            .iter()
+            .find(|b| b.num_rows() > 0)
+            .map(|b| batch_to_thread(b, 0)))
+    }
+
+    /// Threads with a Gmail thread ID (one per account that has it)
+    pub async fn find_threads(
+        &self,
+        gmail_thread_id: u64,
+        account_id: Option<&str>,
+    ) -> Result<Vec<Thread>> {
+        let table = self.threads_table()?;
+
+        let mut filter = format!("gmail_thread_id = {}", gmail_thread_id);
+        if let Some(acct) = account_id {
+            filter.push_str(&format!(" AND account_id = '{}'", acct));
+        }
+        let results = table.query().only_if(filter).execute().await?;
+        let batches: Vec<RecordBatch> = results.try_collect().await?;
+        Ok(batches
+            .iter()
+            .flat_map(|batch| (0..batch.num_rows()).map(move |i| batch_to_thread(batch, i)))
+            .collect())
+    }
+
+    /// List threads by latest activity (most recent first)
+    pub async fn list_threads(
+        &self,
+        account_id: Option<&str>,
+        since: Option<DateTime<Utc>>,
+        limit: usize,
+    ) -> Result<Vec<Thread>> {
+        let table = self.threads_table()?;
+
+        let mut conditions = Vec::new();
+        if let Some(id) = account_id {
+            conditions.push(format!("account_id = '{}'", id));
+        }
+        if let Some(since) = since {
+            conditions.push(format!("last_activity >= {}", since.timestamp()));
+        }
+
+        let mut query = table.query();
+        if !conditions.is_empty() {
+            query = query.only_if(conditions.join(" AND "));
+        }
+        let results = query.execute().await?;
+        let batches: Vec<RecordBatch> = results.try_collect().await?;
+
+        let mut threads: Vec<Thread> = batches
+            .iter()
+            .flat_map(|batch| (0..batch.num_rows()).map(move |i| batch_to_thread(batch, i)))
+            .collect();
+        threads.sort_by(|a, b| b.last_activity.cmp(&a.last_activity));
+        threads.truncate(limit);
+
+        Ok(threads)
+    }
+
+    /// Get threads by ID, in no particular order
+    pub async fn get_threads_batch(&self, ids: &[String]) -> Result<Vec<Thread>> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let table = self.threads_table()?;
+
+        let quoted: Vec<String> = ids
+            .iter()
+            .map(|id| format!("'{}'", id.replace('\'', "''")))
+            .collect();
+        let results = table
+            .query()
+            .only_if(format!("id IN ({})", quoted.join(", ")))
+            .execute()
+            .await?;
+        let batches: Vec<RecordBatch> = results.try_collect().await?;
+        Ok(batches
+            .iter()
+            .flat_map(|batch| (0..batch.num_rows()).map(move |i| batch_to_thread(batch, i)))
+            .collect())
+    }
+
+    /// Get emails that have attachments but haven't been downloaded yet
+    pub async fn get_emails_with_pending_attachments(
+        &self,
+        account_id: &str,
+    ) -> Result<Vec<Email>> {
+        let table = self.emails_table()?;
+
+        // Query emails with non-empty attachments
+        // We filter for emails that have attachments JSON and check in Rust if downloaded
+        let filter = format!("account_id = '{}' AND attachments IS NOT NULL", account_id);
+
+        let results = table.query().only_if(&filter).execute().await?;
+
+        let batches: Vec<RecordBatch> = results.try_collect().await?;
+
+        let mut pending_emails = Vec::new();
+        for batch in &batches {
+            for i in 0..batch.num_rows() {
+                let email = batch_to_email(batch, i)?;
+                // Check if any attachment hasn't been downloaded
+                let has_pending = email.attachments.iter().any(|att| !att.downloaded);
+                if has_pending && !email.attachments.is_empty() {
+                    pending_emails.push(email);
+                }
+            }
+        }
+
+        Ok(pending_emails)
+    }
+
+    /// Point downloaded attachments under `from` at the same files under
+    /// `to`, after the data directory moved. Only the attachments column is
+    /// rewritten, so embeddings are untouched. Returns the emails updated.
+    pub async fn rebase_attachment_paths(&self, from: &Path, to: &Path) -> Result<usize> {
+        let table = self.emails_table()?;
+        let results = table
+            .query()
+            .only_if("attachments IS NOT NULL")
+            .execute()
+            .await?;
+        let batches: Vec<RecordBatch> = results.try_collect().await?;
+
+        let mut updated = 0;
+        for batch in &batches {
+            for i in 0..batch.num_rows() {
+                let mut email = batch_to_email(batch, i)?;
+                let mut changed = false;
+                for att in &mut email.attachments {
+                    let rebased = att
+                        .local_path
+                        .as_deref()
+                        .and_then(|path| crate::relocate::rebase(path, from, to));
+                    if let Some(path) = rebased {
+                        att.local_path = Some(path);
+                        changed = true;
+                    }
+                }
+                if !changed {
+                    continue;
+                }
+
+                let json = serde_json::to_string(&email.attachments)?;
+                table
+                    .update()
+                    .only_if(format!("id = '{}'", email.id.replace('\'', "''")))
+                    .column("attachments", format!("'{}'", json.replace('\'', "''")))
+                    .execute()
+                    .await?;
+                updated += 1;
+            }
+        }
+
+        info!(
+            "Rebased attachment paths of {} emails from {:?} to {:?}",
+            updated, from, to
+        );
+        Ok(updated)
+    }
+
+    /// Get attachment statistics for an account
+    /// Returns (total_attachments, downloaded_attachments, total_size_bytes)
+    pub async fn get_attachment_stats(&self, account_id: &str) -> Result<(usize, usize, u64)> {
+        let table = self.emails_table()?;
+
+        let filter = format!("account_id = '{}' AND attachments IS NOT NULL", account_id);
+
+        let results = table.query().only_if(&filter).execute().await?;
+
+        let batches: Vec<RecordBatch> = results.try_collect().await?;
+
+        let mut total = 0usize;
+        let mut downloaded = 0usize;
+        let mut total_size = 0u64;
+
+        for batch in &batches {
+            for i in 0..batch.num_rows() {
+                let email = batch_to_email(batch, i)?;
+                for att in &email.attachments {
+                    total += 1;
+                    if att.downloaded {
+                        downloaded += 1;
+                        total_size += att.size as u64;
+                    }
+                }
+            }
+        }
+
+        Ok((total, downloaded, total_size))
+    }
+
+    /// Get local storage used by an account (email bodies + downloaded attachments)
+    pub async fn get_storage_usage(&self, account_id: &str) -> Result<StorageUsage> {
+        let table = self.emails_table()?;
+
+        let query = table
+            .query()
+            .select(lancedb::query::Select::columns(&["raw_size"]))
+            .only_if(&format!("account_id = '{}'", account_id));
+
+        let results = query.execute().await?;
+        let batches: Vec<RecordBatch> = results.try_collect().await?;
+
+        let mut email_bytes = 0u64;
+        for batch in &batches {
+            if let Some(size_col) = batch
+                .column_by_name("raw_size")
+                .and_then(|c| c.as_any().downcast_ref::<UInt64Array>())
+            {
+                email_bytes += size_col.iter().flatten().sum::<u64>();
+            }
+        }
+
+        let (_, _, attachment_bytes) = self.get_attachment_stats(account_id).await?;
+
+        Ok(StorageUsage {
+            email_bytes,
+            attachment_bytes,
+        })
+    }
+
+    /// Get an account by ID (email address)
+    pub async fn get_account(&self, id: &str) -> Result<Option<Account>> {
+        let table = self.accounts_table()?;
         let results = table
             .query()
             .only_if(&format!("id = '{}'", id))
@@ -744,6 +2005,24 @@ impl Database {
         emails_table
             .delete(&format!("account_id = '{}'", account_id))
             .await?;
+        self.activity_table()?
+            .delete(&format!("account_id = '{}'", account_id))
+            .await?;
+        self.receipts_table()?
+            .delete(&format!("account_id = '{}'", account_id))
+            .await?;
+        self.itinerary_table()?
+            .delete(&format!("account_id = '{}'", account_id))
+            .await?;
+        self.extractions_table()?
+            .delete(&format!("account_id = '{}'", account_id))
+            .await?;
+        self.tasks_table()?
+            .delete(&format!("account_id = '{}'", account_id))
+            .await?;
+        self.threads_table()?
+            .delete(&format!("account_id = '{}'", account_id))
+            .await?;
         info!("Cleared {} emails for account {}", email_count, account_id);
         Ok(email_count)
     }
@@ -759,6 +2038,220 @@ impl Database {
         Ok(event_count)
     }
 
+    /// Emails of an account dated before `cutoff`, optionally of one category
+    ///
+    /// Bodies and embeddings aren't loaded; this is for retention pruning,
+    /// which only needs ids, threads, sizes and attachment paths.
+    pub async fn list_emails_before(
+        &self,
+        account_id: &str,
+        cutoff: DateTime<Utc>,
+        category: Option<EmailCategory>,
+    ) -> Result<Vec<Email>> {
+        let table = self.emails_table()?;
+
+        let mut filter = format!(
+            "account_id = '{}' AND date < {}",
+            account_id,
+            cutoff.timestamp()
+        );
+        if let Some(category) = category {
+            filter.push_str(&format!(" AND category = '{}'", category.as_str()));
+        }
+
+        let results = table
+            .query()
+            .select(lancedb::query::Select::columns(&[
+                "id",
+                "account_id",
+                "message_id",
+                "gmail_thread_id",
+                "folder",
+                "subject",
+                "from_email",
+                "date",
+                "attachments",
+                "category",
+                "raw_size",
+            ]))
+            .only_if(&filter)
+            .execute()
+            .await?;
+        let batches: Vec<RecordBatch> = results.try_collect().await?;
+
+        let mut emails = Vec::new();
+        for batch in &batches {
+            for i in 0..batch.num_rows() {
+                emails.push(batch_to_email(batch, i)?);
+            }
+        }
+        Ok(emails)
+    }
+
+    /// Delete emails by ID, along with their embeddings and the receipts,
+    /// activity items, itineraries, extractions and tasks found in them
+    ///
+    /// Threads are left alone; callers rebuild or delete the affected ones.
+    pub async fn delete_emails(&self, ids: &[String]) -> Result<()> {
+        // Keep each delete filter a manageable size
+        for chunk in ids.chunks(500) {
+            let quoted: Vec<String> = chunk
+                .iter()
+                .map(|id| format!("'{}'", id.replace('\'', "''")))
+                .collect();
+            let list = quoted.join(", ");
+
+            let id_filter = format!("id IN ({})", list);
+            self.emails_table()?.delete(&id_filter).await?;
+            // Receipts and activity items share the ID of their email
+            self.receipts_table()?.delete(&id_filter).await?;
+            self.activity_table()?.delete(&id_filter).await?;
+            let email_filter = format!("email_id IN ({})", list);
+            self.itinerary_table()?.delete(&email_filter).await?;
+            self.extractions_table()?.delete(&email_filter).await?;
+            self.tasks_table()?.delete(&email_filter).await?;
+        }
+        debug!("Deleted {} emails", ids.len());
+        Ok(())
+    }
+
+    /// Delete threads by ID
+    pub async fn delete_threads(&self, ids: &[String]) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let quoted: Vec<String> = ids
+            .iter()
+            .map(|id| format!("'{}'", id.replace('\'', "''")))
+            .collect();
+        self.threads_table()?
+            .delete(&format!("id IN ({})", quoted.join(", ")))
+            .await?;
+        Ok(())
+    }
+
+    /// Move account `old` and every row keyed by it to the address `new`:
+    /// the account row (with its sync state), its emails and events, and
+    /// the rows derived from its emails. All tables change or none do.
+    ///
+    /// Downloaded attachment paths under `attachments_dir/old` are pointed
+    /// at `attachments_dir/new`; moving the files is up to the caller.
+    pub async fn rename_account(
+        &self,
+        old: &str,
+        new: &str,
+        attachments_dir: &Path,
+    ) -> Result<RenamedRows> {
+        if self.get_account(old).await?.is_none() {
+            return Err(Error::AccountNotFound(old.to_string()));
+        }
+        if self.get_account(new).await?.is_some() {
+            return Err(Error::AccountAlreadyExists(new.to_string()));
+        }
+
+        let emails = [
+            ("account_id", Rewrite::Account),
+            ("id", Rewrite::Id),
+            (
+                "attachments",
+                Rewrite::AttachmentPaths {
+                    from: attachments_dir.join(old),
+                    to: attachments_dir.join(new),
+                },
+            ),
+        ];
+        let events = [
+            ("account_id", Rewrite::Account),
+            ("id", Rewrite::Id),
+            ("calendar_id", Rewrite::Account),
+        ];
+        let derived = [
+            ("account_id", Rewrite::Account),
+            ("id", Rewrite::Id),
+            ("email_id", Rewrite::Id),
+            ("email_ids", Rewrite::IdList),
+        ];
+        let account = [("id", Rewrite::Account)];
+
+        let mut tx = self.transaction();
+        let result = async {
+            let mut renamed = RenamedRows::default();
+            for (name, table, key, columns) in [
+                (
+                    EMAILS_TABLE,
+                    self.emails_table()?,
+                    "account_id",
+                    &emails[..],
+                ),
+                (
+                    EVENTS_TABLE,
+                    self.events_table()?,
+                    "account_id",
+                    &events[..],
+                ),
+                (
+                    ACTIVITY_TABLE,
+                    self.activity_table()?,
+                    "account_id",
+                    &derived[..],
+                ),
+                (
+                    RECEIPTS_TABLE,
+                    self.receipts_table()?,
+                    "account_id",
+                    &derived[..],
+                ),
+                (
+                    ITINERARY_TABLE,
+                    self.itinerary_table()?,
+                    "account_id",
+                    &derived[..],
+                ),
+                (
+                    EXTRACTIONS_TABLE,
+                    self.extractions_table()?,
+                    "account_id",
+                    &derived[..],
+                ),
+                (TASKS_TABLE, self.tasks_table()?, "account_id", &derived[..]),
+                (
+                    THREADS_TABLE,
+                    self.threads_table()?,
+                    "account_id",
+                    &derived[..],
+                ),
+                (ACCOUNTS_TABLE, self.accounts_table()?, "id", &account[..]),
+            ] {
+                let batches = tx
+                    .rename_rows(table, name, key, old, new, |batch| {
+                        rewrite_batch(batch, columns, old, new)
+                    })
+                    .await?;
+                if name == EMAILS_TABLE {
+                    for batch in &batches {
+                        if let Some(ids) = batch
+                            .column_by_name("id")
+                            .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+                        {
+                            renamed
+                                .email_ids
+                                .extend(ids.iter().flatten().map(str::to_string));
+                        }
+                    }
+                }
+                renamed
+                    .tables
+                    .insert(name, batches.iter().map(|b| b.num_rows()).sum());
+            }
+            Ok(renamed)
+        }
+        .await;
+        let renamed = tx.finish(result).await?;
+
+        info!("Renamed account {} to {}", old, new);
+        Ok(renamed)
+    }
+
     /// Delete an account and all its data
     pub async fn delete_account(&self, account_id: &str) -> Result<()> {
         // Delete emails
@@ -773,6 +2266,44 @@ impl Database {
             .delete(&format!("account_id = '{}'", account_id))
             .await?;
 
+        // Delete activity parsed from the account's emails
+        let activity_table = self.activity_table()?;
+        activity_table
+            .delete(&format!("account_id = '{}'", account_id))
+            .await?;
+
+        // Delete receipts extracted from the account's emails
+        let receipts_table = self.receipts_table()?;
+        receipts_table
+            .delete(&format!("account_id = '{}'", account_id))
+            .await?;
+
+        // Delete itineraries parsed from the account's emails
+        let itinerary_table = self.itinerary_table()?;
+        itinerary_table
+            .delete(&format!("account_id = '{}'", account_id))
+            .await?;
+
+        // Delete reservations and packages found in the account's emails
+        let extractions_table = self.extractions_table()?;
+        extractions_table
+            .delete(&format!("account_id = '{}'", account_id))
+            .await?;
+
+        // Delete tasks found in the account's emails
+        let tasks_table = self.tasks_table()?;
+        tasks_table
+            .delete(&format!("account_id = '{}'", account_id))
+            .await?;
+
+        // Delete threads built from the account's emails
+        let threads_table = self.threads_table()?;
+        threads_table
+            .delete(&format!("account_id = '{}'", account_id))
+            .await?;
+
+        // The audit log is kept: it records the deletion itself
+
         // Delete account
         let accounts_table = self.accounts_table()?;
         accounts_table
@@ -783,6 +2314,26 @@ impl Database {
         Ok(())
     }
 
+    /// Rows parsed from an account's emails, which are removed along with them
+    pub async fn count_email_derived_rows(
+        &self,
+        account_id: &str,
+    ) -> Result<Vec<(&'static str, usize)>> {
+        let filter = format!("account_id = '{}'", account_id);
+        let mut counts = Vec::new();
+        for (name, table) in [
+            (ACTIVITY_TABLE, self.activity_table()?),
+            (RECEIPTS_TABLE, self.receipts_table()?),
+            (ITINERARY_TABLE, self.itinerary_table()?),
+            (EXTRACTIONS_TABLE, self.extractions_table()?),
+            (TASKS_TABLE, self.tasks_table()?),
+            (THREADS_TABLE, self.threads_table()?),
+        ] {
+            counts.push((name, table.count_rows(Some(filter.clone())).await?));
+        }
+        Ok(counts)
+    }
+
     /// Count emails, optionally filtered by account
     pub async fn count_emails(&self, account_id: Option<&str>) -> Result<u64> {
         let table = self.emails_table()?;
@@ -894,157 +2445,630 @@ impl Database {
         let results = query.execute().await?;
         let batches: Vec<RecordBatch> = results.try_collect().await?;
 
-        let mut oldest: Option<DateTime<Utc>> = None;
-        let mut newest: Option<DateTime<Utc>> = None;
+        let mut oldest: Option<DateTime<Utc>> = None;
+        let mut newest: Option<DateTime<Utc>> = None;
+
+        for batch in &batches {
+            if let Some(date_col) = batch
+                .column_by_name("date")
+                .and_then(|c| c.as_any().downcast_ref::<Int64Array>())
+            {
+                for i in 0..batch.num_rows() {
+                    if let Some(ts) = DateTime::from_timestamp(date_col.value(i), 0) {
+                        // Skip unreasonable dates to prevent bad data from breaking sync
+                        if !is_reasonable_date(&ts) {
+                            continue;
+                        }
+                        oldest = Some(match oldest {
+                            Some(current) if ts < current => ts,
+                            Some(current) => current,
+                            None => ts,
+                        });
+                        newest = Some(match newest {
+                            Some(current) if ts > current => ts,
+                            Some(current) => current,
+                            None => ts,
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok((oldest, newest))
+    }
+
+    /// Get event sync boundaries for resume (oldest and newest dates)
+    pub async fn get_event_sync_boundaries(
+        &self,
+        account_id: &str,
+    ) -> Result<(Option<DateTime<Utc>>, Option<DateTime<Utc>>)> {
+        let table = self.events_table()?;
+
+        let query = table
+            .query()
+            .select(lancedb::query::Select::columns(&["start"]))
+            .only_if(&format!("account_id = '{}'", account_id));
+
+        let results = query.execute().await?;
+        let batches: Vec<RecordBatch> = results.try_collect().await?;
+
+        let mut oldest: Option<DateTime<Utc>> = None;
+        let mut newest: Option<DateTime<Utc>> = None;
+
+        for batch in &batches {
+            if let Some(start_col) = batch
+                .column_by_name("start")
+                .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+            {
+                for i in 0..batch.num_rows() {
+                    let start_str = start_col.value(i);
+                    // Parse as RFC3339 datetime or date-only
+                    let dt = if let Ok(parsed) = DateTime::parse_from_rfc3339(start_str) {
+                        parsed.with_timezone(&Utc)
+                    } else if let Ok(date) =
+                        chrono::NaiveDate::parse_from_str(start_str, "%Y-%m-%d")
+                    {
+                        date.and_hms_opt(0, 0, 0)
+                            .map(|dt| DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc))
+                            .unwrap_or_else(Utc::now)
+                    } else {
+                        continue;
+                    };
+
+                    // Skip unreasonable dates to prevent bad data from breaking sync
+                    if !is_reasonable_date(&dt) {
+                        continue;
+                    }
+
+                    oldest = Some(match oldest {
+                        Some(current) if dt < current => dt,
+                        Some(current) => current,
+                        None => dt,
+                    });
+                    newest = Some(match newest {
+                        Some(current) if dt > current => dt,
+                        Some(current) => current,
+                        None => dt,
+                    });
+                }
+            }
+        }
+
+        Ok((oldest, newest))
+    }
+
+    /// List recent emails sorted by date (newest first)
+    /// This is optimized for listing without search - no embedding lookup
+    pub async fn list_recent_emails(
+        &self,
+        account_id: Option<&str>,
+        category: Option<EmailCategory>,
+        suspicious_only: bool,
+        limit: usize,
+    ) -> Result<Vec<Email>> {
+        let table = self.emails_table()?;
+
+        // Select all columns except the embedding vector for speed
+        let columns = &[
+            "id",
+            "account_id",
+            "message_id",
+            "gmail_thread_id",
+            "folder",
+            "subject",
+            "from_email",
+            "from_name",
+            "to",
+            "cc",
+            "bcc",
+            "date",
+            "body_plain",
+            "body_html",
+            "snippet",
+            "attachments",
+            "labels",
+            "flags",
+            "uid",
+            "category",
+            "activity_source",
+            "auth",
+            "tags",
+        ];
+
+        let mut query = table
+            .query()
+            .select(lancedb::query::Select::columns(columns));
+
+        let mut conditions = Vec::new();
+        if let Some(id) = account_id {
+            conditions.push(format!("account_id = '{}'", id));
+        }
+        if let Some(category) = category {
+            conditions.push(format!("category = '{}'", category.as_str()));
+        }
+        if suspicious_only {
+            conditions.push("suspicious = true".to_string());
+        }
+        if !conditions.is_empty() {
+            query = query.only_if(conditions.join(" AND "));
+        }
+
+        // LanceDB doesn't have ORDER BY in query API, so we fetch more and sort in memory
+        // For better performance with large datasets, consider adding a date index
+        let results = query.execute().await?;
+        let batches: Vec<RecordBatch> = results.try_collect().await?;
+
+        let mut emails = Vec::new();
+        for batch in &batches {
+            for i in 0..batch.num_rows() {
+                emails.push(batch_to_email(batch, i)?);
+            }
+        }
+
+        // Sort by date descending (newest first)
+        emails.sort_by(|a, b| b.date.cmp(&a.date));
+
+        // Return only the requested limit
+        emails.truncate(limit);
+
+        Ok(emails)
+    }
+
+    /// Up to `limit` emails matching a SQL filter, newest first, without
+    /// bodies or embeddings
+    pub async fn list_email_headers_matching(
+        &self,
+        filter: &str,
+        limit: usize,
+    ) -> Result<Vec<Email>> {
+        let table = self.emails_table()?;
+
+        let columns = &[
+            "id",
+            "account_id",
+            "account_alias",
+            "message_id",
+            "gmail_thread_id",
+            "folder",
+            "subject",
+            "from_email",
+            "from_name",
+            "to",
+            "cc",
+            "date",
+            "snippet",
+            "attachments",
+            "labels",
+            "is_sent",
+            "category",
+        ];
+        let results = table
+            .query()
+            .only_if(filter)
+            .select(lancedb::query::Select::columns(columns))
+            .execute()
+            .await?;
+        let batches: Vec<RecordBatch> = results.try_collect().await?;
+
+        let mut emails = Vec::new();
+        for batch in &batches {
+            for i in 0..batch.num_rows() {
+                emails.push(batch_to_email(batch, i)?);
+            }
+        }
+        emails.sort_by(|a, b| b.date.cmp(&a.date));
+        emails.truncate(limit);
+        Ok(emails)
+    }
+
+    /// Emails (received and sent) dated on or after `since`, in no particular order
+    pub async fn list_emails_since(
+        &self,
+        account_id: Option<&str>,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<Email>> {
+        let table = self.emails_table()?;
+
+        let mut filter = format!("date >= {}", since.timestamp());
+        if let Some(id) = account_id {
+            filter.push_str(&format!(" AND account_id = '{}'", id));
+        }
+        let results = table.query().only_if(filter).execute().await?;
+        let batches: Vec<RecordBatch> = results.try_collect().await?;
+
+        let mut emails = Vec::new();
+        for batch in &batches {
+            for i in 0..batch.num_rows() {
+                emails.push(batch_to_email(batch, i)?);
+            }
+        }
+        Ok(emails)
+    }
+
+    /// Emails of an account dated in `start..=end`, in no particular order
+    pub async fn list_emails_between(
+        &self,
+        account_id: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<Email>> {
+        let table = self.emails_table()?;
+
+        let filter = format!(
+            "account_id = '{}' AND date >= {} AND date <= {}",
+            account_id.replace('\'', "''"),
+            start.timestamp(),
+            end.timestamp()
+        );
+        let results = table.query().only_if(filter).execute().await?;
+        let batches: Vec<RecordBatch> = results.try_collect().await?;
+
+        let mut emails = Vec::new();
+        for batch in &batches {
+            for i in 0..batch.num_rows() {
+                emails.push(batch_to_email(batch, i)?);
+            }
+        }
+        Ok(emails)
+    }
+
+    /// Emails of an account dated on or after `since` with their embeddings
+    /// (None when stored without one), in no particular order
+    pub async fn list_embedded_emails_since(
+        &self,
+        account_id: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<Email>> {
+        let table = self.emails_table()?;
+
+        let filter = format!(
+            "account_id = '{}' AND date >= {}",
+            account_id.replace('\'', "''"),
+            since.timestamp()
+        );
+        let results = table.query().only_if(filter).execute().await?;
+        let batches: Vec<RecordBatch> = results.try_collect().await?;
+
+        let mut emails = Vec::new();
+        for batch in &batches {
+            let embeddings = batch
+                .column_by_name("embedding")
+                .and_then(|c| c.as_any().downcast_ref::<FixedSizeListArray>());
+            for i in 0..batch.num_rows() {
+                let mut email = batch_to_email(batch, i)?;
+                email.embedding = embeddings
+                    .filter(|e| !e.is_null(i))
+                    .and_then(|e| {
+                        e.value(i)
+                            .as_any()
+                            .downcast_ref::<Float32Array>()
+                            .map(|v| v.values().to_vec())
+                    })
+                    .filter(|v| v.iter().any(|x| *x != 0.0));
+                emails.push(email);
+            }
+        }
+        Ok(emails)
+    }
+
+    /// List activity items sorted by date (newest first)
+    pub async fn list_activity(
+        &self,
+        account_id: Option<&str>,
+        source: Option<ActivitySource>,
+        project: Option<&str>,
+        since: Option<DateTime<Utc>>,
+        limit: usize,
+    ) -> Result<Vec<ActivityItem>> {
+        let table = self.activity_table()?;
+
+        let mut conditions = Vec::new();
+        if let Some(id) = account_id {
+            conditions.push(format!("account_id = '{}'", id));
+        }
+        if let Some(source) = source {
+            conditions.push(format!("source = '{}'", source.as_str()));
+        }
+        if let Some(project) = project {
+            conditions.push(format!("project = '{}'", project.replace('\'', "''")));
+        }
+        if let Some(since) = since {
+            conditions.push(format!("date >= {}", since.timestamp()));
+        }
+
+        let mut query = table.query();
+        if !conditions.is_empty() {
+            query = query.only_if(conditions.join(" AND "));
+        }
+
+        let results = query.execute().await?;
+        let batches: Vec<RecordBatch> = results.try_collect().await?;
+
+        let mut items: Vec<ActivityItem> = batches
+            .iter()
+            .flat_map(|batch| {
+                (0..batch.num_rows()).filter_map(move |i| batch_to_activity(batch, i))
+            })
+            .collect();
+
+        // Sort by date descending (newest first)
+        items.sort_by(|a, b| b.date.cmp(&a.date));
+        items.truncate(limit);
+
+        Ok(items)
+    }
+
+    /// List receipts sorted by date (newest first)
+    ///
+    /// `until` is exclusive, so a month is `[first day, first day of next month)`.
+    pub async fn list_receipts(
+        &self,
+        account_id: Option<&str>,
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+        merchant: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<Receipt>> {
+        let table = self.receipts_table()?;
+
+        let mut conditions = Vec::new();
+        if let Some(id) = account_id {
+            conditions.push(format!("account_id = '{}'", id));
+        }
+        if let Some(since) = since {
+            conditions.push(format!("date >= {}", since.timestamp()));
+        }
+        if let Some(until) = until {
+            conditions.push(format!("date < {}", until.timestamp()));
+        }
+
+        let mut query = table.query();
+        if !conditions.is_empty() {
+            query = query.only_if(conditions.join(" AND "));
+        }
+
+        let results = query.execute().await?;
+        let batches: Vec<RecordBatch> = results.try_collect().await?;
+
+        let mut receipts: Vec<Receipt> = batches
+            .iter()
+            .flat_map(|batch| (0..batch.num_rows()).map(move |i| batch_to_receipt(batch, i)))
+            .collect();
+
+        // Merchant match is a case-insensitive substring, done here rather than in SQL
+        if let Some(merchant) = merchant {
+            let needle = merchant.to_lowercase();
+            receipts.retain(|r| r.merchant.to_lowercase().contains(&needle));
+        }
+
+        // Sort by date descending (newest first)
+        receipts.sort_by(|a, b| b.date.cmp(&a.date));
+        receipts.truncate(limit);
+
+        Ok(receipts)
+    }
+
+    /// List itinerary items sorted by start (soonest first)
+    ///
+    /// `since` matches items still in progress, not just ones starting after it.
+    pub async fn list_itinerary(
+        &self,
+        account_id: Option<&str>,
+        kind: Option<ItineraryKind>,
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+        limit: usize,
+    ) -> Result<Vec<ItineraryItem>> {
+        let table = self.itinerary_table()?;
+
+        let mut conditions = Vec::new();
+        if let Some(id) = account_id {
+            conditions.push(format!("account_id = '{}'", id));
+        }
+        if let Some(kind) = kind {
+            conditions.push(format!("kind = '{}'", kind.as_str()));
+        }
+        if let Some(since) = since {
+            conditions.push(format!(
+                "(end_timestamp >= {ts} OR (end_timestamp IS NULL AND start_timestamp >= {ts}))",
+                ts = since.timestamp()
+            ));
+        }
+        if let Some(until) = until {
+            conditions.push(format!("start_timestamp < {}", until.timestamp()));
+        }
+
+        let mut query = table.query();
+        if !conditions.is_empty() {
+            query = query.only_if(conditions.join(" AND "));
+        }
+
+        let results = query.execute().await?;
+        let batches: Vec<RecordBatch> = results.try_collect().await?;
+
+        let mut items: Vec<ItineraryItem> = batches
+            .iter()
+            .flat_map(|batch| {
+                (0..batch.num_rows()).filter_map(move |i| batch_to_itinerary(batch, i))
+            })
+            .collect();
 
-        for batch in &batches {
-            if let Some(date_col) = batch
-                .column_by_name("date")
-                .and_then(|c| c.as_any().downcast_ref::<Int64Array>())
-            {
-                for i in 0..batch.num_rows() {
-                    if let Some(ts) = DateTime::from_timestamp(date_col.value(i), 0) {
-                        // Skip unreasonable dates to prevent bad data from breaking sync
-                        if !is_reasonable_date(&ts) {
-                            continue;
-                        }
-                        oldest = Some(match oldest {
-                            Some(current) if ts < current => ts,
-                            Some(current) => current,
-                            None => ts,
-                        });
-                        newest = Some(match newest {
-                            Some(current) if ts > current => ts,
-                            Some(current) => current,
-                            None => ts,
-                        });
-                    }
-                }
-            }
-        }
+        // Sort by start ascending (soonest first)
+        items.sort_by(|a, b| a.start.cmp(&b.start));
+        items.truncate(limit);
 
-        Ok((oldest, newest))
+        Ok(items)
     }
 
-    /// Get event sync boundaries for resume (oldest and newest dates)
-    pub async fn get_event_sync_boundaries(
+    /// List reservations and package deliveries
+    ///
+    /// Packages are tracked by their latest email, so one that was shipped and
+    /// later delivered is listed once. With `upcoming_from`, only reservations
+    /// not yet over and packages not yet delivered are listed, soonest first
+    /// (packages without an expected date last); otherwise newest email first.
+    pub async fn list_extractions(
         &self,
-        account_id: &str,
-    ) -> Result<(Option<DateTime<Utc>>, Option<DateTime<Utc>>)> {
-        let table = self.events_table()?;
+        account_id: Option<&str>,
+        kind: Option<ExtractionKind>,
+        upcoming_from: Option<DateTime<Utc>>,
+        limit: usize,
+    ) -> Result<Vec<Extraction>> {
+        // Shipping notifications older than this are assumed to have arrived
+        const OPEN_PACKAGE_DAYS: i64 = 30;
 
-        let query = table
-            .query()
-            .select(lancedb::query::Select::columns(&["start"]))
-            .only_if(&format!("account_id = '{}'", account_id));
+        let table = self.extractions_table()?;
+
+        let mut conditions = Vec::new();
+        if let Some(id) = account_id {
+            conditions.push(format!("account_id = '{}'", id));
+        }
+        if let Some(kind) = kind {
+            conditions.push(format!("kind = '{}'", kind.as_str()));
+        }
+        if let Some(now) = upcoming_from {
+            conditions.push(format!(
+                "(end_timestamp >= {ts} OR (end_timestamp IS NULL AND start_timestamp >= {ts}) \
+                 OR (kind = 'package' AND email_timestamp >= {recent}))",
+                ts = now.timestamp(),
+                recent = (now - chrono::Duration::days(OPEN_PACKAGE_DAYS)).timestamp()
+            ));
+        }
+
+        let mut query = table.query();
+        if !conditions.is_empty() {
+            query = query.only_if(conditions.join(" AND "));
+        }
 
         let results = query.execute().await?;
         let batches: Vec<RecordBatch> = results.try_collect().await?;
 
-        let mut oldest: Option<DateTime<Utc>> = None;
-        let mut newest: Option<DateTime<Utc>> = None;
+        let mut items: Vec<Extraction> = batches
+            .iter()
+            .flat_map(|batch| {
+                (0..batch.num_rows()).filter_map(move |i| batch_to_extraction(batch, i))
+            })
+            .collect();
+
+        // Newest email first, keeping the latest status of each package
+        items.sort_by(|a, b| b.email_date.cmp(&a.email_date));
+        let mut tracked = std::collections::HashSet::new();
+        items.retain(|item| match &item.tracking_number {
+            Some(number) => tracked.insert(number.clone()),
+            None => true,
+        });
+
+        if let Some(now) = upcoming_from {
+            // An all-day expected delivery stays upcoming through its day
+            let day_ago = now - chrono::Duration::days(1);
+            items.retain(|item| {
+                item.kind != ExtractionKind::Package
+                    || (!item.is_delivered()
+                        && item.start.unwrap_or(DateTime::<Utc>::MAX_UTC) >= day_ago)
+            });
+            items.sort_by_key(|item| item.start.unwrap_or(DateTime::<Utc>::MAX_UTC));
+        }
+        items.truncate(limit);
 
-        for batch in &batches {
-            if let Some(start_col) = batch
-                .column_by_name("start")
-                .and_then(|c| c.as_any().downcast_ref::<StringArray>())
-            {
-                for i in 0..batch.num_rows() {
-                    let start_str = start_col.value(i);
-                    // Parse as RFC3339 datetime or date-only
-                    let dt = if let Ok(parsed) = DateTime::parse_from_rfc3339(start_str) {
-                        parsed.with_timezone(&Utc)
-                    } else if let Ok(date) =
-                        chrono::NaiveDate::parse_from_str(start_str, "%Y-%m-%d")
-                    {
-                        date.and_hms_opt(0, 0, 0)
-                            .map(|dt| DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc))
-                            .unwrap_or_else(Utc::now)
-                    } else {
-                        continue;
-                    };
+        Ok(items)
+    }
 
-                    // Skip unreasonable dates to prevent bad data from breaking sync
-                    if !is_reasonable_date(&dt) {
-                        continue;
-                    }
+    /// List tasks: open ones by due date (undated last, then newest first),
+    /// done ones most recently completed first
+    ///
+    /// `due_before` keeps tasks due on or before that date.
+    pub async fn list_tasks(
+        &self,
+        account_id: Option<&str>,
+        status: Option<TaskStatus>,
+        due_before: Option<chrono::NaiveDate>,
+        limit: usize,
+    ) -> Result<Vec<Task>> {
+        let table = self.tasks_table()?;
 
-                    oldest = Some(match oldest {
-                        Some(current) if dt < current => dt,
-                        Some(current) => current,
-                        None => dt,
-                    });
-                    newest = Some(match newest {
-                        Some(current) if dt > current => dt,
-                        Some(current) => current,
-                        None => dt,
-                    });
-                }
-            }
+        let mut conditions = Vec::new();
+        if let Some(id) = account_id {
+            conditions.push(format!("account_id = '{}'", id));
+        }
+        if let Some(status) = status {
+            conditions.push(format!("status = '{}'", status.as_str()));
+        }
+        if let Some(date) = due_before {
+            conditions.push(format!("due <= '{}'", date.format("%Y-%m-%d")));
         }
 
-        Ok((oldest, newest))
+        let mut query = table.query();
+        if !conditions.is_empty() {
+            query = query.only_if(conditions.join(" AND "));
+        }
+
+        let results = query.execute().await?;
+        let batches: Vec<RecordBatch> = results.try_collect().await?;
+
+        let mut tasks: Vec<Task> = batches
+            .iter()
+            .flat_map(|batch| (0..batch.num_rows()).map(move |i| batch_to_task(batch, i)))
+            .collect();
+
+        tasks.sort_by(|a, b| match (a.status, b.status) {
+            (TaskStatus::Open, TaskStatus::Done) => std::cmp::Ordering::Less,
+            (TaskStatus::Done, TaskStatus::Open) => std::cmp::Ordering::Greater,
+            (TaskStatus::Done, TaskStatus::Done) => b.completed_at.cmp(&a.completed_at),
+            (TaskStatus::Open, TaskStatus::Open) => a
+                .due
+                .unwrap_or(chrono::NaiveDate::MAX)
+                .cmp(&b.due.unwrap_or(chrono::NaiveDate::MAX))
+                .then(b.created_at.cmp(&a.created_at)),
+        });
+        tasks.truncate(limit);
+
+        Ok(tasks)
     }
 
-    /// List recent emails sorted by date (newest first)
-    /// This is optimized for listing without search - no embedding lookup
-    pub async fn list_recent_emails(
+    /// List audit log entries sorted by time (newest first)
+    pub async fn list_audit(
         &self,
+        since: Option<DateTime<Utc>>,
+        actor: Option<AuditActor>,
+        action: Option<&str>,
         account_id: Option<&str>,
         limit: usize,
-    ) -> Result<Vec<Email>> {
-        let table = self.emails_table()?;
-
-        // Select all columns except the embedding vector for speed
-        let columns = &[
-            "id",
-            "account_id",
-            "message_id",
-            "gmail_thread_id",
-            "folder",
-            "subject",
-            "from_email",
-            "from_name",
-            "to",
-            "cc",
-            "bcc",
-            "date",
-            "body_plain",
-            "body_html",
-            "snippet",
-            "attachments",
-            "labels",
-            "flags",
-            "uid",
-        ];
-
-        let mut query = table
-            .query()
-            .select(lancedb::query::Select::columns(columns));
+    ) -> Result<Vec<AuditEntry>> {
+        let table = self.audit_table()?;
 
+        let mut conditions = Vec::new();
+        if let Some(since) = since {
+            conditions.push(format!("timestamp >= {}", since.timestamp()));
+        }
+        if let Some(actor) = actor {
+            conditions.push(format!("actor = '{}'", actor.as_str()));
+        }
+        if let Some(action) = action {
+            conditions.push(format!("action = '{}'", action.replace('\'', "''")));
+        }
         if let Some(id) = account_id {
-            query = query.only_if(&format!("account_id = '{}'", id));
+            conditions.push(format!("account_id = '{}'", id));
+        }
+
+        let mut query = table.query();
+        if !conditions.is_empty() {
+            query = query.only_if(conditions.join(" AND "));
         }
 
-        // LanceDB doesn't have ORDER BY in query API, so we fetch more and sort in memory
-        // For better performance with large datasets, consider adding a date index
         let results = query.execute().await?;
         let batches: Vec<RecordBatch> = results.try_collect().await?;
 
-        let mut emails = Vec::new();
-        for batch in &batches {
-            for i in 0..batch.num_rows() {
-                emails.push(batch_to_email(batch, i)?);
-            }
-        }
-
-        // Sort by date descending (newest first)
-        emails.sort_by(|a, b| b.date.cmp(&a.date));
+        let mut entries: Vec<AuditEntry> = batches
+            .iter()
+            .flat_map(|batch| {
+                (0..batch.num_rows()).filter_map(move |i| batch_to_audit_entry(batch, i))
+            })
+            .collect();
 
-        // Return only the requested limit
-        emails.truncate(limit);
+        // Sort by timestamp descending (newest first)
+        entries.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+        entries.truncate(limit);
 
-        Ok(emails)
+        Ok(entries)
     }
 
     /// List recent events sorted by start time (newest first)
@@ -1114,6 +3138,7 @@ impl Database {
     /// * `accounts` - Optional list of account IDs to filter by
     /// * `from` - Start of date range (inclusive), as ISO 8601 date string (YYYY-MM-DD)
     /// * `to` - End of date range (exclusive), as ISO 8601 date string (YYYY-MM-DD)
+    /// * `filter` - Show-as, visibility and color filters
     /// * `limit` - Maximum number of events to return
     ///
     /// # Returns
@@ -1123,6 +3148,7 @@ impl Database {
         accounts: Option<&[String]>,
         from: &str,
         to: &str,
+        filter: &EventFilter,
         limit: usize,
     ) -> Result<Vec<CalendarEvent>> {
         let table = self.events_table()?;
@@ -1144,7 +3170,11 @@ impl Database {
             "organizer",
             "attendees",
             "status",
+            "transparency",
             "etag",
+            "conference",
+            "visibility",
+            "color_id",
         ];
 
         // Build filter for date range
@@ -1182,6 +3212,7 @@ impl Database {
 
         // Sort by start time ascending (chronological order)
         events.sort_by(|a, b| a.start.as_date().cmp(&b.start.as_date()));
+        events.retain(|e| filter.matches(e));
 
         // Return only the requested limit
         events.truncate(limit);
@@ -1189,6 +3220,50 @@ impl Database {
         Ok(events)
     }
 
+    /// List events overlapping `start..end`, soonest first
+    ///
+    /// Start and end are stored as ISO 8601 strings, so the overlap test is
+    /// a string comparison; all-day events match by date.
+    pub async fn list_events_overlapping(
+        &self,
+        accounts: Option<&[String]>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<CalendarEvent>> {
+        let table = self.events_table()?;
+
+        // `end` is a SQL keyword, so the column name is escaped
+        let mut filters = vec![
+            format!("start < '{}'", end.to_rfc3339()),
+            format!("`end` > '{}'", start.to_rfc3339()),
+        ];
+        if let Some(accts) = accounts.filter(|a| !a.is_empty()) {
+            let account_list = accts
+                .iter()
+                .map(|a| format!("'{}'", a))
+                .collect::<Vec<_>>()
+                .join(", ");
+            filters.push(format!("account_id IN ({})", account_list));
+        }
+
+        let results = table
+            .query()
+            .only_if(filters.join(" AND "))
+            .execute()
+            .await?;
+        let batches: Vec<RecordBatch> = results.try_collect().await?;
+
+        let mut events = Vec::new();
+        for batch in &batches {
+            for i in 0..batch.num_rows() {
+                events.push(batch_to_event(batch, i)?);
+            }
+        }
+        events.sort_by_key(|e| (e.start.as_date(), e.start.as_datetime()));
+
+        Ok(events)
+    }
+
     /// Get a map of google_event_id -> etag for all events in an account
     /// Used to detect which events have changed during incremental sync
     pub async fn get_event_etags(
@@ -1235,7 +3310,259 @@ impl Database {
         debug!("Loaded {} event etags for {}", etags.len(), account_id);
         Ok(etags)
     }
+
+    /// Read (id, embedding) pairs for export
+    ///
+    /// Rows stored without an embedding (all zeros) are exported with no
+    /// vector. With `text_templates`, the text the embedding is computed from
+    /// is included so an external pipeline can embed it.
+    pub async fn export_vectors(
+        &self,
+        table: VectorTable,
+        account_id: Option<&str>,
+        text_templates: Option<&EmbeddingConfig>,
+    ) -> Result<Vec<VectorRecord>> {
+        let lance_table = match table {
+            VectorTable::Emails => self.emails_table()?,
+            VectorTable::Events => self.events_table()?,
+        };
+
+        let mut query = lance_table.query();
+        if text_templates.is_none() {
+            query = query.select(lancedb::query::Select::columns(&["id", "embedding"]));
+        }
+        if let Some(account_id) = account_id {
+            query = query.only_if(&format!("account_id = '{}'", account_id));
+        }
+
+        let results = query.execute().await?;
+        let batches: Vec<RecordBatch> = results.try_collect().await?;
+
+        let mut records = Vec::new();
+        for batch in &batches {
+            let ids = batch
+                .column_by_name("id")
+                .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+                .ok_or_else(|| Error::Other("Missing id column".to_string()))?;
+            let embeddings = batch
+                .column_by_name("embedding")
+                .and_then(|c| c.as_any().downcast_ref::<FixedSizeListArray>());
+
+            for i in 0..batch.num_rows() {
+                let vector = embeddings
+                    .filter(|e| !e.is_null(i))
+                    .and_then(|e| {
+                        e.value(i)
+                            .as_any()
+                            .downcast_ref::<Float32Array>()
+                            .map(|v| v.values().to_vec())
+                    })
+                    .filter(|v| v.iter().any(|x| *x != 0.0));
+                let text = match text_templates {
+                    Some(templates) => Some(match table {
+                        VectorTable::Emails => templates.email_text(&batch_to_email(batch, i)?),
+                        VectorTable::Events => templates.event_text(&batch_to_event(batch, i)?),
+                    }),
+                    None => None,
+                };
+                records.push(VectorRecord {
+                    id: ids.value(i).to_string(),
+                    vector,
+                    text,
+                });
+            }
+        }
+
+        Ok(records)
+    }
+
+    /// IDs of every row in the emails or events table
+    pub async fn list_ids(&self, table: VectorTable) -> Result<Vec<String>> {
+        let lance_table = match table {
+            VectorTable::Emails => self.emails_table()?,
+            VectorTable::Events => self.events_table()?,
+        };
+
+        let results = lance_table
+            .query()
+            .select(lancedb::query::Select::columns(&["id"]))
+            .execute()
+            .await?;
+        let batches: Vec<RecordBatch> = results.try_collect().await?;
+
+        let mut ids = Vec::new();
+        for batch in &batches {
+            if let Some(col) = batch
+                .column_by_name("id")
+                .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+            {
+                ids.extend((0..col.len()).map(|i| col.value(i).to_string()));
+            }
+        }
+        Ok(ids)
+    }
+
+    /// IDs of rows in the emails or events table stored without an
+    /// embedding (all zeros), e.g. while the embedding provider was down
+    pub async fn ids_without_embeddings(&self, table: VectorTable) -> Result<Vec<String>> {
+        let lance_table = match table {
+            VectorTable::Emails => self.emails_table()?,
+            VectorTable::Events => self.events_table()?,
+        };
+
+        let results = lance_table
+            .query()
+            .select(lancedb::query::Select::columns(&["id", "embedding"]))
+            .execute()
+            .await?;
+        let batches: Vec<RecordBatch> = results.try_collect().await?;
+
+        let mut ids = Vec::new();
+        for batch in &batches {
+            let Some(col) = batch
+                .column_by_name("id")
+                .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+            else {
+                continue;
+            };
+            let embeddings = batch
+                .column_by_name("embedding")
+                .and_then(|c| c.as_any().downcast_ref::<FixedSizeListArray>());
+            for i in 0..batch.num_rows() {
+                let embedded = embeddings
+                    .filter(|e| !e.is_null(i))
+                    .and_then(|e| {
+                        e.value(i)
+                            .as_any()
+                            .downcast_ref::<Float32Array>()
+                            .map(|v| v.values().iter().any(|x| *x != 0.0))
+                    })
+                    .unwrap_or(false);
+                if !embedded {
+                    ids.push(col.value(i).to_string());
+                }
+            }
+        }
+        Ok(ids)
+    }
+
+    /// Replace embeddings with externally computed vectors
+    ///
+    /// Vectors must already be validated. Returns the number of rows updated
+    /// and the IDs that don't exist in the table. Each chunk is written in a
+    /// transaction, so a failure never leaves rows deleted but not re-added.
+    pub async fn import_vectors(
+        &self,
+        table: VectorTable,
+        records: &[VectorRecord],
+    ) -> Result<(usize, Vec<String>)> {
+        const IMPORT_CHUNK_SIZE: usize = 256;
+
+        let mut updated = 0;
+        let mut missing = Vec::new();
+
+        for chunk in records.chunks(IMPORT_CHUNK_SIZE) {
+            let vectors: std::collections::HashMap<&str, &Vec<f32>> = chunk
+                .iter()
+                .filter_map(|r| r.vector.as_ref().map(|v| (r.id.as_str(), v)))
+                .collect();
+            let ids: Vec<String> = vectors.keys().map(|id| id.to_string()).collect();
+
+            let found: std::collections::HashSet<String> = match table {
+                VectorTable::Emails => {
+                    let mut emails = self.get_emails_batch(&ids).await?;
+                    for email in &mut emails {
+                        email.embedding = vectors.get(email.id.as_str()).map(|v| v.to_vec());
+                    }
+                    let mut tx = self.transaction();
+                    let result = tx.upsert_emails(&emails).await;
+                    tx.finish(result).await?;
+                    emails.into_iter().map(|e| e.id).collect()
+                }
+                VectorTable::Events => {
+                    let mut events = self.get_events_batch(&ids).await?;
+                    for event in &mut events {
+                        event.embedding = vectors.get(event.id.as_str()).map(|v| v.to_vec());
+                    }
+                    let mut tx = self.transaction();
+                    let result = tx.upsert_events(&events).await;
+                    tx.finish(result).await?;
+                    events.into_iter().map(|e| e.id).collect()
+                }
+            };
+
+            updated += found.len();
+            missing.extend(ids.into_iter().filter(|id| !found.contains(id)));
+        }
+
+        info!(
+            "Imported {} vectors into {} ({} unknown ids)",
+            updated,
+            table.as_str(),
+            missing.len()
+        );
+        Ok((updated, missing))
+    }
 }
 
 // Helper trait for collecting async streams
 use futures::stream::TryStreamExt;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ReceiptSource;
+
+    fn receipt(id: &str) -> Receipt {
+        Receipt {
+            id: id.to_string(),
+            account_id: "me@example.com".to_string(),
+            merchant: "Uber".to_string(),
+            amount: Some(12.10),
+            currency: Some("USD".to_string()),
+            date: Utc::now(),
+            subject: "Your trip".to_string(),
+            sender: "receipts@uber.com".to_string(),
+            source: ReceiptSource::Body,
+        }
+    }
+
+    fn activity(id: &str) -> ActivityItem {
+        ActivityItem {
+            id: id.to_string(),
+            account_id: "me@example.com".to_string(),
+            source: ActivitySource::GitHub,
+            project: "acme/widgets".to_string(),
+            item: Some("#1".to_string()),
+            title: "Fix the build".to_string(),
+            action: "opened".to_string(),
+            actor: None,
+            url: None,
+            date: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_delete_emails_removes_receipts_and_activity() {
+        let dir = std::env::temp_dir().join(format!("ge-db-{}", uuid::Uuid::new_v4()));
+        let db = Database::open(&dir).await.unwrap();
+        let (pruned, kept) = ("me@example.com:a@x", "me@example.com:b@x");
+        db.upsert_receipts(&[receipt(pruned), receipt(kept)])
+            .await
+            .unwrap();
+        db.upsert_activity(&[activity(pruned), activity(kept)])
+            .await
+            .unwrap();
+
+        db.delete_emails(&[pruned.to_string()]).await.unwrap();
+
+        let receipts = db.list_receipts(None, None, None, None, 10).await.unwrap();
+        let receipt_ids: Vec<&str> = receipts.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(receipt_ids, vec![kept]);
+        let items = db.list_activity(None, None, None, None, 10).await.unwrap();
+        let activity_ids: Vec<&str> = items.iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(activity_ids, vec![kept]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
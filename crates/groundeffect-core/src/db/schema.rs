@@ -3,8 +3,8 @@
 use std::sync::Arc;
 
 use arrow_array::{
-    Array, ArrayRef, BooleanArray, FixedSizeListArray, Float32Array, Int64Array, RecordBatch,
-    StringArray, UInt32Array, UInt64Array,
+    Array, ArrayRef, BooleanArray, FixedSizeListArray, Float32Array, Float64Array, Int64Array,
+    RecordBatch, StringArray, UInt32Array, UInt64Array,
 };
 use arrow_schema::{DataType, Field, Schema};
 use chrono::{DateTime, Utc};
@@ -12,13 +12,14 @@ use lance_arrow::FixedSizeListArrayExt;
 
 use crate::error::{Error, Result};
 use crate::models::{
-    Account, AccountStatus, Address, Attendee, CalendarEvent, Email, EventStatus, EventTime,
-    Reminder, Transparency,
+    Account, AccountStatus, ActivityItem, ActivitySource, Address, Attendee, AuditActor,
+    AuditEntry, CalendarEvent, Conference, Email, EmailCategory, EventStatus, EventTime,
+    Extraction, ExtractionKind, ItineraryItem, ItineraryKind, Receipt, ReceiptSource, Reminder,
+    RetentionPolicy, Task, TaskSource, TaskStatus, Thread, Transparency, Visibility,
 };
-use crate::EMBEDDING_DIMENSION;
 
-/// Create the emails table schema
-pub fn email_schema() -> Schema {
+/// Create the emails table schema with a `dimension`-wide embedding column
+pub fn email_schema(dimension: usize) -> Schema {
     Schema::new(vec![
         // Identifiers
         Field::new("id", DataType::Utf8, false),
@@ -54,18 +55,27 @@ pub fn email_schema() -> Schema {
             "embedding",
             DataType::FixedSizeList(
                 Arc::new(Field::new("item", DataType::Float32, true)),
-                EMBEDDING_DIMENSION as i32,
+                dimension as i32,
             ),
             true,
         ),
         // Sync metadata
         Field::new("synced_at", DataType::Int64, false),
         Field::new("raw_size", DataType::UInt64, false),
+        // Columns added after the initial release. They are appended (and
+        // nullable) so existing tables can be migrated in place.
+        Field::new("category", DataType::Utf8, true),
+        Field::new("activity_source", DataType::Utf8, true),
+        Field::new("is_sent", DataType::Boolean, true),
+        Field::new("auth", DataType::Utf8, true), // JSON EmailAuth
+        Field::new("suspicious", DataType::Boolean, true),
+        Field::new("headers_only", DataType::Boolean, true),
+        Field::new("tags", DataType::Utf8, true), // JSON array
     ])
 }
 
-/// Create the events table schema
-pub fn event_schema() -> Schema {
+/// Create the events table schema with a `dimension`-wide embedding column
+pub fn event_schema(dimension: usize) -> Schema {
     Schema::new(vec![
         // Identifiers
         Field::new("id", DataType::Utf8, false),
@@ -99,13 +109,18 @@ pub fn event_schema() -> Schema {
             "embedding",
             DataType::FixedSizeList(
                 Arc::new(Field::new("item", DataType::Float32, true)),
-                EMBEDDING_DIMENSION as i32,
+                dimension as i32,
             ),
             true,
         ),
         // Sync metadata
         Field::new("calendar_id", DataType::Utf8, false),
         Field::new("synced_at", DataType::Int64, false),
+        // Video call (JSON)
+        Field::new("conference", DataType::Utf8, true),
+        // Display
+        Field::new("visibility", DataType::Utf8, true),
+        Field::new("color_id", DataType::Utf8, true),
     ])
 }
 
@@ -124,6 +139,151 @@ pub fn account_schema() -> Schema {
         Field::new("oldest_event_synced", DataType::Int64, true),
         Field::new("sync_attachments", DataType::Boolean, false),
         Field::new("estimated_total_emails", DataType::Int64, true),
+        Field::new("include_labels", DataType::Utf8, true), // JSON array
+        Field::new("exclude_labels", DataType::Utf8, true), // JSON array
+        Field::new("max_storage_bytes", DataType::Int64, true),
+        Field::new("retention", DataType::Utf8, true), // JSON RetentionPolicy
+        Field::new("headers_only", DataType::Boolean, true),
+    ])
+}
+
+/// Create the activity table schema
+pub fn activity_schema() -> Schema {
+    Schema::new(vec![
+        Field::new("id", DataType::Utf8, false),
+        Field::new("account_id", DataType::Utf8, false),
+        Field::new("source", DataType::Utf8, false),
+        Field::new("project", DataType::Utf8, false),
+        Field::new("item", DataType::Utf8, true),
+        Field::new("title", DataType::Utf8, false),
+        Field::new("action", DataType::Utf8, false),
+        Field::new("actor", DataType::Utf8, true),
+        Field::new("url", DataType::Utf8, true),
+        Field::new("date", DataType::Int64, false), // Unix timestamp
+    ])
+}
+
+/// Create the receipts table schema
+pub fn receipt_schema() -> Schema {
+    Schema::new(vec![
+        Field::new("id", DataType::Utf8, false),
+        Field::new("account_id", DataType::Utf8, false),
+        Field::new("merchant", DataType::Utf8, false),
+        Field::new("amount", DataType::Float64, true),
+        Field::new("currency", DataType::Utf8, true),
+        Field::new("date", DataType::Int64, false), // Unix timestamp
+        Field::new("subject", DataType::Utf8, false),
+        Field::new("sender", DataType::Utf8, false),
+        Field::new("source", DataType::Utf8, false),
+    ])
+}
+
+/// Create the itinerary table schema
+pub fn itinerary_schema() -> Schema {
+    Schema::new(vec![
+        Field::new("id", DataType::Utf8, false),
+        Field::new("email_id", DataType::Utf8, false),
+        Field::new("account_id", DataType::Utf8, false),
+        Field::new("kind", DataType::Utf8, false),
+        Field::new("title", DataType::Utf8, false),
+        Field::new("provider", DataType::Utf8, true),
+        Field::new("confirmation", DataType::Utf8, true),
+        Field::new("start_timestamp", DataType::Int64, false),
+        Field::new("end_timestamp", DataType::Int64, true),
+        Field::new("all_day", DataType::Boolean, false),
+        Field::new("start_timezone", DataType::Utf8, true),
+        Field::new("end_timezone", DataType::Utf8, true),
+        Field::new("origin", DataType::Utf8, true),
+        Field::new("destination", DataType::Utf8, true),
+        Field::new("location", DataType::Utf8, true),
+        Field::new("event_id", DataType::Utf8, true),
+    ])
+}
+
+/// Create the extractions table schema
+pub fn extraction_schema() -> Schema {
+    Schema::new(vec![
+        Field::new("id", DataType::Utf8, false),
+        Field::new("email_id", DataType::Utf8, false),
+        Field::new("account_id", DataType::Utf8, false),
+        Field::new("kind", DataType::Utf8, false),
+        Field::new("title", DataType::Utf8, false),
+        Field::new("provider", DataType::Utf8, true),
+        Field::new("confirmation", DataType::Utf8, true),
+        Field::new("start_timestamp", DataType::Int64, true),
+        Field::new("end_timestamp", DataType::Int64, true),
+        Field::new("all_day", DataType::Boolean, false),
+        Field::new("timezone", DataType::Utf8, true),
+        Field::new("origin", DataType::Utf8, true),
+        Field::new("destination", DataType::Utf8, true),
+        Field::new("location", DataType::Utf8, true),
+        Field::new("party_size", DataType::Int64, true),
+        Field::new("tracking_number", DataType::Utf8, true),
+        Field::new("tracking_url", DataType::Utf8, true),
+        Field::new("status", DataType::Utf8, true),
+        Field::new("email_timestamp", DataType::Int64, false),
+    ])
+}
+
+/// Create the tasks table schema
+pub fn task_schema() -> Schema {
+    Schema::new(vec![
+        Field::new("id", DataType::Utf8, false),
+        Field::new("email_id", DataType::Utf8, false),
+        Field::new("account_id", DataType::Utf8, false),
+        Field::new("text", DataType::Utf8, false),
+        Field::new("requester", DataType::Utf8, false),
+        Field::new("subject", DataType::Utf8, false),
+        Field::new("due", DataType::Utf8, true), // YYYY-MM-DD
+        Field::new("due_text", DataType::Utf8, true),
+        Field::new("status", DataType::Utf8, false),
+        Field::new("source", DataType::Utf8, false),
+        Field::new("created_at", DataType::Int64, false),
+        Field::new("completed_at", DataType::Int64, true),
+    ])
+}
+
+/// Create the audit log table schema
+pub fn audit_schema() -> Schema {
+    Schema::new(vec![
+        Field::new("id", DataType::Utf8, false),
+        Field::new("timestamp", DataType::Int64, false),
+        Field::new("actor", DataType::Utf8, false),
+        Field::new("action", DataType::Utf8, false),
+        Field::new("account_id", DataType::Utf8, true),
+        Field::new("params", DataType::Utf8, false), // JSON
+        Field::new("success", DataType::Boolean, false),
+        Field::new("error", DataType::Utf8, true),
+    ])
+}
+
+/// Create the threads table schema with a `dimension`-wide embedding column
+/// (the same width as the emails table's, since both use the email model)
+pub fn thread_schema(dimension: usize) -> Schema {
+    Schema::new(vec![
+        Field::new("id", DataType::Utf8, false),
+        Field::new("account_id", DataType::Utf8, false),
+        Field::new("gmail_thread_id", DataType::UInt64, false),
+        Field::new("subject", DataType::Utf8, false),
+        Field::new("participants", DataType::Utf8, false), // JSON array of addresses
+        Field::new("message_count", DataType::UInt32, false),
+        Field::new("unread_count", DataType::UInt32, false),
+        Field::new("first_date", DataType::Int64, false), // Unix timestamp
+        Field::new("last_activity", DataType::Int64, false), // Unix timestamp
+        Field::new("last_from", DataType::Utf8, false),   // JSON address
+        Field::new("snippet", DataType::Utf8, false),
+        Field::new("has_attachments", DataType::Boolean, false),
+        Field::new("has_sent", DataType::Boolean, false),
+        Field::new("email_ids", DataType::Utf8, false), // JSON array
+        Field::new("content", DataType::Utf8, false),
+        Field::new(
+            "embedding",
+            DataType::FixedSizeList(
+                Arc::new(Field::new("item", DataType::Float32, true)),
+                dimension as i32,
+            ),
+            true,
+        ),
     ])
 }
 
@@ -137,6 +297,9 @@ pub fn empty_email_batch(schema: &Schema) -> RecordBatch {
             DataType::UInt32 => Arc::new(UInt32Array::from(Vec::<u32>::new())) as ArrayRef,
             DataType::UInt64 => Arc::new(UInt64Array::from(Vec::<u64>::new())) as ArrayRef,
             DataType::Int64 => Arc::new(Int64Array::from(Vec::<i64>::new())) as ArrayRef,
+            DataType::Boolean => {
+                Arc::new(BooleanArray::from(Vec::<Option<bool>>::new())) as ArrayRef
+            }
             DataType::FixedSizeList(_, size) => {
                 let values = Float32Array::from(Vec::<f32>::new());
                 Arc::new(FixedSizeListArray::try_new_from_values(values, *size).unwrap())
@@ -154,6 +317,11 @@ pub fn empty_event_batch(schema: &Schema) -> RecordBatch {
     empty_email_batch(schema) // Same logic
 }
 
+/// Create an empty batch for the threads schema
+pub fn empty_thread_batch(schema: &Schema) -> RecordBatch {
+    empty_email_batch(schema) // Same logic
+}
+
 /// Create an empty batch for the accounts schema
 pub fn empty_account_batch(schema: &Schema) -> RecordBatch {
     let arrays: Vec<ArrayRef> = schema
@@ -170,14 +338,92 @@ pub fn empty_account_batch(schema: &Schema) -> RecordBatch {
     RecordBatch::try_new(Arc::new(schema.clone()), arrays).unwrap()
 }
 
+/// Create an empty batch for the activity schema
+pub fn empty_activity_batch(schema: &Schema) -> RecordBatch {
+    empty_account_batch(schema) // Same logic
+}
+
+/// Create an empty batch for the itinerary schema
+pub fn empty_itinerary_batch(schema: &Schema) -> RecordBatch {
+    empty_account_batch(schema) // Same logic
+}
+
+/// Create an empty batch for the extractions schema
+pub fn empty_extraction_batch(schema: &Schema) -> RecordBatch {
+    empty_account_batch(schema) // Same logic
+}
+
+/// Create an empty batch for the tasks schema
+pub fn empty_task_batch(schema: &Schema) -> RecordBatch {
+    empty_account_batch(schema) // Same logic
+}
+
+/// Create an empty batch for the audit schema
+pub fn empty_audit_batch(schema: &Schema) -> RecordBatch {
+    empty_account_batch(schema) // Same logic
+}
+
+/// Create an empty batch for the receipts schema
+pub fn empty_receipt_batch(schema: &Schema) -> RecordBatch {
+    let arrays: Vec<ArrayRef> = schema
+        .fields()
+        .iter()
+        .map(|field| match field.data_type() {
+            DataType::Utf8 => Arc::new(StringArray::from(Vec::<Option<&str>>::new())) as ArrayRef,
+            DataType::Int64 => Arc::new(Int64Array::from(Vec::<i64>::new())) as ArrayRef,
+            DataType::Float64 => {
+                Arc::new(Float64Array::from(Vec::<Option<f64>>::new())) as ArrayRef
+            }
+            _ => panic!("Unsupported type: {:?}", field.data_type()),
+        })
+        .collect();
+
+    RecordBatch::try_new(Arc::new(schema.clone()), arrays).unwrap()
+}
+
+/// Build the embedding column for a batch
+///
+/// Rows without an embedding are stored as zeros. A vector of any other width
+/// means the embedding model no longer matches the table; report that instead
+/// of Arrow's opaque length error.
+fn embedding_array<'a>(
+    table: &str,
+    embeddings: impl Iterator<Item = (&'a str, Option<&'a Vec<f32>>)>,
+    dimension: usize,
+) -> Result<FixedSizeListArray> {
+    let mut values = Vec::new();
+    for (id, embedding) in embeddings {
+        match embedding {
+            Some(v) if v.len() == dimension => values.extend_from_slice(v),
+            Some(v) => {
+                return Err(Error::Embedding(format!(
+                    "{} embedding for {} has {} dimensions but the {} table stores {}-dimensional vectors; \
+                     the embedding model changed since the table was built. Restart the daemon to migrate \
+                     and re-embed, or set search.embedding_model back",
+                    table,
+                    id,
+                    v.len(),
+                    table,
+                    dimension
+                )))
+            }
+            None => values.extend(std::iter::repeat(0.0).take(dimension)),
+        }
+    }
+    Ok(FixedSizeListArray::try_new_from_values(
+        Float32Array::from(values),
+        dimension as i32,
+    )?)
+}
+
 /// Convert an email to a record batch
-pub fn email_to_batch(email: &Email) -> Result<RecordBatch> {
-    emails_to_batch(&[email.clone()])
+pub fn email_to_batch(email: &Email, dimension: usize) -> Result<RecordBatch> {
+    emails_to_batch(&[email.clone()], dimension)
 }
 
 /// Convert multiple emails to a record batch
-pub fn emails_to_batch(emails: &[Email]) -> Result<RecordBatch> {
-    let schema = email_schema();
+pub fn emails_to_batch(emails: &[Email], dimension: usize) -> Result<RecordBatch> {
+    let schema = email_schema(dimension);
 
     let ids: Vec<&str> = emails.iter().map(|e| e.id.as_str()).collect();
     let account_ids: Vec<&str> = emails.iter().map(|e| e.account_id.as_str()).collect();
@@ -262,23 +508,42 @@ pub fn emails_to_batch(emails: &[Email]) -> Result<RecordBatch> {
         .collect();
 
     // Build embedding array
-    let embedding_values: Vec<f32> = emails
-        .iter()
-        .flat_map(|e| {
-            e.embedding
-                .as_ref()
-                .map(|v| v.clone())
-                .unwrap_or_else(|| vec![0.0; EMBEDDING_DIMENSION])
-        })
-        .collect();
-    let embedding_array = FixedSizeListArray::try_new_from_values(
-        Float32Array::from(embedding_values),
-        EMBEDDING_DIMENSION as i32,
-    )
-    .map_err(|e| Error::Arrow(e))?;
+    let embedding_array = embedding_array(
+        "emails",
+        emails.iter().map(|e| (e.id.as_str(), e.embedding.as_ref())),
+        dimension,
+    )?;
 
     let synced_ats: Vec<i64> = emails.iter().map(|e| e.synced_at.timestamp()).collect();
     let raw_sizes: Vec<u64> = emails.iter().map(|e| e.raw_size).collect();
+    let categories: Vec<Option<&str>> = emails
+        .iter()
+        .map(|e| e.category.map(|c| c.as_str()))
+        .collect();
+    let activity_sources: Vec<Option<&str>> = emails
+        .iter()
+        .map(|e| e.activity_source.map(|s| s.as_str()))
+        .collect();
+    let sent: Vec<bool> = emails.iter().map(|e| e.is_sent).collect();
+    let auths: Vec<Option<String>> = emails
+        .iter()
+        .map(|e| e.auth.as_ref().map(|a| serde_json::to_string(a).unwrap()))
+        .collect();
+    let suspicious: Vec<Option<bool>> = emails
+        .iter()
+        .map(|e| e.auth.as_ref().map(|a| a.suspicious))
+        .collect();
+    let headers_only: Vec<bool> = emails.iter().map(|e| e.headers_only).collect();
+    let tags: Vec<Option<String>> = emails
+        .iter()
+        .map(|e| {
+            if e.tags.is_empty() {
+                None
+            } else {
+                Some(serde_json::to_string(&e.tags).unwrap())
+            }
+        })
+        .collect();
 
     let arrays: Vec<ArrayRef> = vec![
         Arc::new(StringArray::from(ids)),
@@ -321,6 +586,17 @@ pub fn emails_to_batch(emails: &[Email]) -> Result<RecordBatch> {
         Arc::new(embedding_array),
         Arc::new(Int64Array::from(synced_ats)),
         Arc::new(UInt64Array::from(raw_sizes)),
+        Arc::new(StringArray::from(categories)),
+        Arc::new(StringArray::from(activity_sources)),
+        Arc::new(BooleanArray::from(sent)),
+        Arc::new(StringArray::from(
+            auths.iter().map(|s| s.as_deref()).collect::<Vec<_>>(),
+        )),
+        Arc::new(BooleanArray::from(suspicious)),
+        Arc::new(BooleanArray::from(headers_only)),
+        Arc::new(StringArray::from(
+            tags.iter().map(|s| s.as_deref()).collect::<Vec<_>>(),
+        )),
     ];
 
     let batch = RecordBatch::try_new(Arc::new(schema), arrays)?;
@@ -382,6 +658,10 @@ pub fn batch_to_email(batch: &RecordBatch, row: usize) -> Result<Email> {
         .and_then(|s| serde_json::from_str(&s).ok())
         .unwrap_or_default();
 
+    let tags: Vec<String> = get_opt_string("tags")
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default();
+
     let flags: Vec<String> = get_opt_string("flags")
         .and_then(|s| serde_json::from_str(&s).ok())
         .unwrap_or_default();
@@ -405,9 +685,23 @@ pub fn batch_to_email(batch: &RecordBatch, row: usize) -> Result<Email> {
     let date = DateTime::from_timestamp(get_i64("date"), 0).unwrap_or_else(Utc::now);
     let synced_at = DateTime::from_timestamp(get_i64("synced_at"), 0).unwrap_or_else(Utc::now);
 
+    let account_id = get_string("account_id");
+    let from_email = get_string("from_email");
+    // Rows from before the column was backfilled go by the sender
+    let is_sent = batch
+        .column_by_name("is_sent")
+        .and_then(|c| c.as_any().downcast_ref::<BooleanArray>())
+        .filter(|a| a.is_valid(row))
+        .map(|a| a.value(row))
+        .unwrap_or_else(|| from_email.eq_ignore_ascii_case(&account_id));
+    let headers_only = batch
+        .column_by_name("headers_only")
+        .and_then(|c| c.as_any().downcast_ref::<BooleanArray>())
+        .is_some_and(|a| a.is_valid(row) && a.value(row));
+
     Ok(Email {
         id: get_string("id"),
-        account_id: get_string("account_id"),
+        account_id,
         account_alias: get_opt_string("account_alias"),
         message_id: get_string("message_id"),
         gmail_message_id: get_u64("gmail_message_id"),
@@ -417,9 +711,14 @@ pub fn batch_to_email(batch: &RecordBatch, row: usize) -> Result<Email> {
         references,
         folder: get_string("folder"),
         labels,
+        is_sent,
         flags,
+        category: get_opt_string("category").and_then(|s| EmailCategory::from_str(&s)),
+        activity_source: get_opt_string("activity_source")
+            .and_then(|s| ActivitySource::from_str(&s)),
+        tags,
         from: Address {
-            email: get_string("from_email"),
+            email: from_email,
             name: get_opt_string("from_name"),
         },
         to,
@@ -431,20 +730,22 @@ pub fn batch_to_email(batch: &RecordBatch, row: usize) -> Result<Email> {
         body_html: get_opt_string("body_html"),
         snippet: get_string("snippet"),
         attachments,
+        auth: get_opt_string("auth").and_then(|s| serde_json::from_str(&s).ok()),
         embedding: None, // Don't load embedding by default
         synced_at,
         raw_size: get_u64("raw_size"),
+        headers_only,
     })
 }
 
 /// Convert a single event to a record batch
-pub fn event_to_batch(event: &CalendarEvent) -> Result<RecordBatch> {
-    events_to_batch(&[event.clone()])
+pub fn event_to_batch(event: &CalendarEvent, dimension: usize) -> Result<RecordBatch> {
+    events_to_batch(&[event.clone()], dimension)
 }
 
 /// Convert multiple events to a record batch
-pub fn events_to_batch(events: &[CalendarEvent]) -> Result<RecordBatch> {
-    let schema = event_schema();
+pub fn events_to_batch(events: &[CalendarEvent], dimension: usize) -> Result<RecordBatch> {
+    let schema = event_schema(dimension);
 
     let ids: Vec<&str> = events.iter().map(|e| e.id.as_str()).collect();
     let account_ids: Vec<&str> = events.iter().map(|e| e.account_id.as_str()).collect();
@@ -534,22 +835,24 @@ pub fn events_to_batch(events: &[CalendarEvent]) -> Result<RecordBatch> {
         .collect();
 
     // Build embedding array
-    let embedding_values: Vec<f32> = events
-        .iter()
-        .flat_map(|e| {
-            e.embedding
-                .as_ref()
-                .cloned()
-                .unwrap_or_else(|| vec![0.0; EMBEDDING_DIMENSION])
-        })
-        .collect();
-    let embedding_array = FixedSizeListArray::try_new_from_values(
-        Float32Array::from(embedding_values),
-        EMBEDDING_DIMENSION as i32,
+    let embedding_array = embedding_array(
+        "events",
+        events.iter().map(|e| (e.id.as_str(), e.embedding.as_ref())),
+        dimension,
     )?;
 
     let calendar_ids: Vec<&str> = events.iter().map(|e| e.calendar_id.as_str()).collect();
     let synced_ats: Vec<i64> = events.iter().map(|e| e.synced_at.timestamp()).collect();
+    let conferences: Vec<Option<String>> = events
+        .iter()
+        .map(|e| {
+            e.conference
+                .as_ref()
+                .map(|c| serde_json::to_string(c).unwrap())
+        })
+        .collect();
+    let visibilities: Vec<&str> = events.iter().map(|e| e.visibility.as_str()).collect();
+    let color_ids: Vec<Option<&str>> = events.iter().map(|e| e.color_id.as_deref()).collect();
 
     let arrays: Vec<ArrayRef> = vec![
         Arc::new(StringArray::from(ids)),
@@ -592,6 +895,11 @@ pub fn events_to_batch(events: &[CalendarEvent]) -> Result<RecordBatch> {
         Arc::new(embedding_array),
         Arc::new(StringArray::from(calendar_ids)),
         Arc::new(Int64Array::from(synced_ats)),
+        Arc::new(StringArray::from(
+            conferences.iter().map(|s| s.as_deref()).collect::<Vec<_>>(),
+        )),
+        Arc::new(StringArray::from(visibilities)),
+        Arc::new(StringArray::from(color_ids)),
     ];
 
     let batch = RecordBatch::try_new(Arc::new(schema), arrays)?;
@@ -699,6 +1007,10 @@ pub fn batch_to_event(batch: &RecordBatch, row: usize) -> Result<CalendarEvent>
         .and_then(|s| serde_json::from_str(&s).ok())
         .unwrap_or_default();
 
+    // Parse conference JSON
+    let conference: Option<Conference> =
+        get_optional_string("conference").and_then(|s| serde_json::from_str(&s).ok());
+
     let synced_at =
         chrono::DateTime::from_timestamp(get_i64("synced_at"), 0).unwrap_or_else(chrono::Utc::now);
 
@@ -722,7 +1034,10 @@ pub fn batch_to_event(batch: &RecordBatch, row: usize) -> Result<CalendarEvent>
         attendees,
         status,
         transparency,
+        visibility: Visibility::parse(&get_string("visibility")).unwrap_or_default(),
+        color_id: get_optional_string("color_id"),
         reminders,
+        conference,
         embedding: None, // Don't load embedding by default
         calendar_id: get_string("calendar_id"),
         synced_at,
@@ -765,12 +1080,55 @@ pub fn account_to_batch(account: &Account) -> Result<RecordBatch> {
         Arc::new(Int64Array::from(vec![account
             .estimated_total_emails
             .map(|v| v as i64)])),
+        Arc::new(StringArray::from(vec![labels_to_json(
+            &account.include_labels,
+        )])),
+        Arc::new(StringArray::from(vec![labels_to_json(
+            &account.exclude_labels,
+        )])),
+        Arc::new(Int64Array::from(vec![account
+            .max_storage_bytes
+            .map(|v| v as i64)])),
+        Arc::new(StringArray::from(vec![retention_to_json(
+            &account.retention,
+        )])),
+        Arc::new(BooleanArray::from(vec![account.headers_only])),
     ];
 
     let batch = RecordBatch::try_new(Arc::new(schema), arrays)?;
     Ok(batch)
 }
 
+/// Serialize an account label filter as a JSON array (None when empty)
+fn labels_to_json(labels: &[String]) -> Option<String> {
+    if labels.is_empty() {
+        None
+    } else {
+        Some(serde_json::to_string(labels).unwrap())
+    }
+}
+
+/// Parse an account label filter stored as a JSON array
+fn labels_from_json(json: Option<String>) -> Vec<String> {
+    json.and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+/// Serialize an account retention policy (None when it keeps everything)
+fn retention_to_json(policy: &RetentionPolicy) -> Option<String> {
+    if policy.is_empty() {
+        None
+    } else {
+        Some(serde_json::to_string(policy).unwrap())
+    }
+}
+
+/// Parse an account retention policy stored as JSON
+fn retention_from_json(json: Option<String>) -> RetentionPolicy {
+    json.and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
 /// Convert a record batch row to an account (lenient version for schema migration)
 /// Handles missing columns by using defaults
 pub fn batch_to_account_lenient(batch: &RecordBatch, row: usize) -> Result<Account> {
@@ -848,6 +1206,15 @@ pub fn batch_to_account_lenient(batch: &RecordBatch, row: usize) -> Result<Accou
     let sync_attachments = get_bool("sync_attachments");
     // This column may not exist in old schema - defaults to None
     let estimated_total_emails = get_opt_i64("estimated_total_emails").map(|v| v as u64);
+    // These columns may not exist in old schema - default to no label filters
+    let include_labels = labels_from_json(get_opt_string("include_labels"));
+    let exclude_labels = labels_from_json(get_opt_string("exclude_labels"));
+    // This column may not exist in old schema - defaults to no limit
+    let max_storage_bytes = get_opt_i64("max_storage_bytes").map(|v| v as u64);
+    // This column may not exist in old schema - defaults to keeping everything
+    let retention = retention_from_json(get_opt_string("retention"));
+    // This column may not exist in old schema - defaults to full sync
+    let headers_only = get_bool("headers_only");
 
     Ok(Account {
         id: get_string("id"),
@@ -862,6 +1229,11 @@ pub fn batch_to_account_lenient(batch: &RecordBatch, row: usize) -> Result<Accou
         oldest_event_synced,
         sync_attachments,
         estimated_total_emails,
+        include_labels,
+        exclude_labels,
+        max_storage_bytes,
+        retention,
+        headers_only,
     })
 }
 
@@ -939,6 +1311,11 @@ pub fn batch_to_account(batch: &RecordBatch, row: usize) -> Result<Account> {
         get_opt_i64("oldest_event_synced").and_then(|ts| DateTime::from_timestamp(ts, 0));
     let sync_attachments = get_bool("sync_attachments");
     let estimated_total_emails = get_opt_i64("estimated_total_emails").map(|v| v as u64);
+    let include_labels = labels_from_json(get_opt_string("include_labels"));
+    let exclude_labels = labels_from_json(get_opt_string("exclude_labels"));
+    let max_storage_bytes = get_opt_i64("max_storage_bytes").map(|v| v as u64);
+    let retention = retention_from_json(get_opt_string("retention"));
+    let headers_only = get_bool("headers_only");
 
     Ok(Account {
         id: get_string("id"),
@@ -953,5 +1330,678 @@ pub fn batch_to_account(batch: &RecordBatch, row: usize) -> Result<Account> {
         oldest_event_synced,
         sync_attachments,
         estimated_total_emails,
+        include_labels,
+        exclude_labels,
+        max_storage_bytes,
+        retention,
+        headers_only,
     })
 }
+
+/// Convert activity items to a record batch
+pub fn activity_to_batch(items: &[ActivityItem]) -> Result<RecordBatch> {
+    let schema = activity_schema();
+
+    let arrays: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from(
+            items.iter().map(|i| i.id.as_str()).collect::<Vec<_>>(),
+        )),
+        Arc::new(StringArray::from(
+            items
+                .iter()
+                .map(|i| i.account_id.as_str())
+                .collect::<Vec<_>>(),
+        )),
+        Arc::new(StringArray::from(
+            items.iter().map(|i| i.source.as_str()).collect::<Vec<_>>(),
+        )),
+        Arc::new(StringArray::from(
+            items.iter().map(|i| i.project.as_str()).collect::<Vec<_>>(),
+        )),
+        Arc::new(StringArray::from(
+            items.iter().map(|i| i.item.as_deref()).collect::<Vec<_>>(),
+        )),
+        Arc::new(StringArray::from(
+            items.iter().map(|i| i.title.as_str()).collect::<Vec<_>>(),
+        )),
+        Arc::new(StringArray::from(
+            items.iter().map(|i| i.action.as_str()).collect::<Vec<_>>(),
+        )),
+        Arc::new(StringArray::from(
+            items.iter().map(|i| i.actor.as_deref()).collect::<Vec<_>>(),
+        )),
+        Arc::new(StringArray::from(
+            items.iter().map(|i| i.url.as_deref()).collect::<Vec<_>>(),
+        )),
+        Arc::new(Int64Array::from(
+            items.iter().map(|i| i.date.timestamp()).collect::<Vec<_>>(),
+        )),
+    ];
+
+    let batch = RecordBatch::try_new(Arc::new(schema), arrays)?;
+    Ok(batch)
+}
+
+/// Convert a record batch row to an activity item (None for unknown sources)
+pub fn batch_to_activity(batch: &RecordBatch, row: usize) -> Option<ActivityItem> {
+    let get_opt_string = |col: &str| -> Option<String> {
+        batch
+            .column_by_name(col)
+            .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+            .filter(|a| !a.is_null(row))
+            .map(|a| a.value(row).to_string())
+    };
+    let get_string = |col: &str| -> String { get_opt_string(col).unwrap_or_default() };
+
+    let date = batch
+        .column_by_name("date")
+        .and_then(|c| c.as_any().downcast_ref::<Int64Array>())
+        .and_then(|a| DateTime::from_timestamp(a.value(row), 0))
+        .unwrap_or_else(Utc::now);
+
+    Some(ActivityItem {
+        id: get_string("id"),
+        account_id: get_string("account_id"),
+        source: ActivitySource::from_str(&get_string("source"))?,
+        project: get_string("project"),
+        item: get_opt_string("item"),
+        title: get_string("title"),
+        action: get_string("action"),
+        actor: get_opt_string("actor"),
+        url: get_opt_string("url"),
+        date,
+    })
+}
+
+/// Convert receipts to a record batch
+pub fn receipts_to_batch(receipts: &[Receipt]) -> Result<RecordBatch> {
+    let schema = receipt_schema();
+
+    let arrays: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from(
+            receipts.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(),
+        )),
+        Arc::new(StringArray::from(
+            receipts
+                .iter()
+                .map(|r| r.account_id.as_str())
+                .collect::<Vec<_>>(),
+        )),
+        Arc::new(StringArray::from(
+            receipts
+                .iter()
+                .map(|r| r.merchant.as_str())
+                .collect::<Vec<_>>(),
+        )),
+        Arc::new(Float64Array::from(
+            receipts.iter().map(|r| r.amount).collect::<Vec<_>>(),
+        )),
+        Arc::new(StringArray::from(
+            receipts
+                .iter()
+                .map(|r| r.currency.as_deref())
+                .collect::<Vec<_>>(),
+        )),
+        Arc::new(Int64Array::from(
+            receipts
+                .iter()
+                .map(|r| r.date.timestamp())
+                .collect::<Vec<_>>(),
+        )),
+        Arc::new(StringArray::from(
+            receipts
+                .iter()
+                .map(|r| r.subject.as_str())
+                .collect::<Vec<_>>(),
+        )),
+        Arc::new(StringArray::from(
+            receipts
+                .iter()
+                .map(|r| r.sender.as_str())
+                .collect::<Vec<_>>(),
+        )),
+        Arc::new(StringArray::from(
+            receipts
+                .iter()
+                .map(|r| r.source.as_str())
+                .collect::<Vec<_>>(),
+        )),
+    ];
+
+    let batch = RecordBatch::try_new(Arc::new(schema), arrays)?;
+    Ok(batch)
+}
+
+/// Convert a record batch row to a receipt
+pub fn batch_to_receipt(batch: &RecordBatch, row: usize) -> Receipt {
+    let get_opt_string = |col: &str| -> Option<String> {
+        batch
+            .column_by_name(col)
+            .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+            .filter(|a| !a.is_null(row))
+            .map(|a| a.value(row).to_string())
+    };
+    let get_string = |col: &str| -> String { get_opt_string(col).unwrap_or_default() };
+
+    let amount = batch
+        .column_by_name("amount")
+        .and_then(|c| c.as_any().downcast_ref::<Float64Array>())
+        .filter(|a| !a.is_null(row))
+        .map(|a| a.value(row));
+
+    let date = batch
+        .column_by_name("date")
+        .and_then(|c| c.as_any().downcast_ref::<Int64Array>())
+        .and_then(|a| DateTime::from_timestamp(a.value(row), 0))
+        .unwrap_or_else(Utc::now);
+
+    Receipt {
+        id: get_string("id"),
+        account_id: get_string("account_id"),
+        merchant: get_string("merchant"),
+        amount,
+        currency: get_opt_string("currency"),
+        date,
+        subject: get_string("subject"),
+        sender: get_string("sender"),
+        source: ReceiptSource::from_str(&get_string("source")).unwrap_or(ReceiptSource::Body),
+    }
+}
+
+/// Convert itinerary items to a record batch
+pub fn itinerary_to_batch(items: &[ItineraryItem]) -> Result<RecordBatch> {
+    let schema = itinerary_schema();
+
+    let strings = |f: fn(&ItineraryItem) -> &str| -> ArrayRef {
+        Arc::new(StringArray::from(items.iter().map(f).collect::<Vec<_>>()))
+    };
+    let opt_strings = |f: fn(&ItineraryItem) -> Option<&str>| -> ArrayRef {
+        Arc::new(StringArray::from(items.iter().map(f).collect::<Vec<_>>()))
+    };
+
+    let arrays: Vec<ArrayRef> = vec![
+        strings(|i| i.id.as_str()),
+        strings(|i| i.email_id.as_str()),
+        strings(|i| i.account_id.as_str()),
+        strings(|i| i.kind.as_str()),
+        strings(|i| i.title.as_str()),
+        opt_strings(|i| i.provider.as_deref()),
+        opt_strings(|i| i.confirmation.as_deref()),
+        Arc::new(Int64Array::from(
+            items
+                .iter()
+                .map(|i| i.start.timestamp())
+                .collect::<Vec<_>>(),
+        )),
+        Arc::new(Int64Array::from(
+            items
+                .iter()
+                .map(|i| i.end.map(|end| end.timestamp()))
+                .collect::<Vec<_>>(),
+        )),
+        Arc::new(BooleanArray::from(
+            items.iter().map(|i| i.all_day).collect::<Vec<_>>(),
+        )),
+        opt_strings(|i| i.start_timezone.as_deref()),
+        opt_strings(|i| i.end_timezone.as_deref()),
+        opt_strings(|i| i.origin.as_deref()),
+        opt_strings(|i| i.destination.as_deref()),
+        opt_strings(|i| i.location.as_deref()),
+        opt_strings(|i| i.event_id.as_deref()),
+    ];
+
+    let batch = RecordBatch::try_new(Arc::new(schema), arrays)?;
+    Ok(batch)
+}
+
+/// Convert a record batch row to an itinerary item (None for unknown kinds)
+pub fn batch_to_itinerary(batch: &RecordBatch, row: usize) -> Option<ItineraryItem> {
+    let get_opt_string = |col: &str| -> Option<String> {
+        batch
+            .column_by_name(col)
+            .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+            .filter(|a| !a.is_null(row))
+            .map(|a| a.value(row).to_string())
+    };
+    let get_string = |col: &str| -> String { get_opt_string(col).unwrap_or_default() };
+    let get_time = |col: &str| -> Option<DateTime<Utc>> {
+        batch
+            .column_by_name(col)
+            .and_then(|c| c.as_any().downcast_ref::<Int64Array>())
+            .filter(|a| !a.is_null(row))
+            .and_then(|a| DateTime::from_timestamp(a.value(row), 0))
+    };
+
+    let all_day = batch
+        .column_by_name("all_day")
+        .and_then(|c| c.as_any().downcast_ref::<BooleanArray>())
+        .map(|a| a.value(row))
+        .unwrap_or(false);
+
+    Some(ItineraryItem {
+        id: get_string("id"),
+        email_id: get_string("email_id"),
+        account_id: get_string("account_id"),
+        kind: ItineraryKind::from_str(&get_string("kind"))?,
+        title: get_string("title"),
+        provider: get_opt_string("provider"),
+        confirmation: get_opt_string("confirmation"),
+        start: get_time("start_timestamp").unwrap_or_else(Utc::now),
+        end: get_time("end_timestamp"),
+        all_day,
+        start_timezone: get_opt_string("start_timezone"),
+        end_timezone: get_opt_string("end_timezone"),
+        origin: get_opt_string("origin"),
+        destination: get_opt_string("destination"),
+        location: get_opt_string("location"),
+        event_id: get_opt_string("event_id"),
+    })
+}
+
+/// Convert extractions to a record batch
+pub fn extractions_to_batch(items: &[Extraction]) -> Result<RecordBatch> {
+    let schema = extraction_schema();
+
+    let strings = |f: fn(&Extraction) -> &str| -> ArrayRef {
+        Arc::new(StringArray::from(items.iter().map(f).collect::<Vec<_>>()))
+    };
+    let opt_strings = |f: fn(&Extraction) -> Option<&str>| -> ArrayRef {
+        Arc::new(StringArray::from(items.iter().map(f).collect::<Vec<_>>()))
+    };
+    let timestamps = |f: fn(&Extraction) -> Option<i64>| -> ArrayRef {
+        Arc::new(Int64Array::from(items.iter().map(f).collect::<Vec<_>>()))
+    };
+
+    let arrays: Vec<ArrayRef> = vec![
+        strings(|i| i.id.as_str()),
+        strings(|i| i.email_id.as_str()),
+        strings(|i| i.account_id.as_str()),
+        strings(|i| i.kind.as_str()),
+        strings(|i| i.title.as_str()),
+        opt_strings(|i| i.provider.as_deref()),
+        opt_strings(|i| i.confirmation.as_deref()),
+        timestamps(|i| i.start.map(|t| t.timestamp())),
+        timestamps(|i| i.end.map(|t| t.timestamp())),
+        Arc::new(BooleanArray::from(
+            items.iter().map(|i| i.all_day).collect::<Vec<_>>(),
+        )),
+        opt_strings(|i| i.timezone.as_deref()),
+        opt_strings(|i| i.origin.as_deref()),
+        opt_strings(|i| i.destination.as_deref()),
+        opt_strings(|i| i.location.as_deref()),
+        timestamps(|i| i.party_size.map(i64::from)),
+        opt_strings(|i| i.tracking_number.as_deref()),
+        opt_strings(|i| i.tracking_url.as_deref()),
+        opt_strings(|i| i.status.as_deref()),
+        timestamps(|i| Some(i.email_date.timestamp())),
+    ];
+
+    let batch = RecordBatch::try_new(Arc::new(schema), arrays)?;
+    Ok(batch)
+}
+
+/// Convert a record batch row to an extraction (None for unknown kinds)
+pub fn batch_to_extraction(batch: &RecordBatch, row: usize) -> Option<Extraction> {
+    let get_opt_string = |col: &str| -> Option<String> {
+        batch
+            .column_by_name(col)
+            .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+            .filter(|a| !a.is_null(row))
+            .map(|a| a.value(row).to_string())
+    };
+    let get_string = |col: &str| -> String { get_opt_string(col).unwrap_or_default() };
+    let get_i64 = |col: &str| -> Option<i64> {
+        batch
+            .column_by_name(col)
+            .and_then(|c| c.as_any().downcast_ref::<Int64Array>())
+            .filter(|a| !a.is_null(row))
+            .map(|a| a.value(row))
+    };
+    let get_time =
+        |col: &str| -> Option<DateTime<Utc>> { DateTime::from_timestamp(get_i64(col)?, 0) };
+
+    let all_day = batch
+        .column_by_name("all_day")
+        .and_then(|c| c.as_any().downcast_ref::<BooleanArray>())
+        .map(|a| a.value(row))
+        .unwrap_or(false);
+
+    Some(Extraction {
+        id: get_string("id"),
+        email_id: get_string("email_id"),
+        account_id: get_string("account_id"),
+        kind: ExtractionKind::from_str(&get_string("kind"))?,
+        title: get_string("title"),
+        provider: get_opt_string("provider"),
+        confirmation: get_opt_string("confirmation"),
+        start: get_time("start_timestamp"),
+        end: get_time("end_timestamp"),
+        all_day,
+        timezone: get_opt_string("timezone"),
+        origin: get_opt_string("origin"),
+        destination: get_opt_string("destination"),
+        location: get_opt_string("location"),
+        party_size: get_i64("party_size").and_then(|n| u32::try_from(n).ok()),
+        tracking_number: get_opt_string("tracking_number"),
+        tracking_url: get_opt_string("tracking_url"),
+        status: get_opt_string("status"),
+        email_date: get_time("email_timestamp").unwrap_or_else(Utc::now),
+    })
+}
+
+/// Convert tasks to a record batch
+pub fn tasks_to_batch(tasks: &[Task]) -> Result<RecordBatch> {
+    let schema = task_schema();
+
+    let strings = |f: fn(&Task) -> &str| -> ArrayRef {
+        Arc::new(StringArray::from(tasks.iter().map(f).collect::<Vec<_>>()))
+    };
+    let opt_strings = |f: fn(&Task) -> Option<&str>| -> ArrayRef {
+        Arc::new(StringArray::from(tasks.iter().map(f).collect::<Vec<_>>()))
+    };
+
+    let arrays: Vec<ArrayRef> = vec![
+        strings(|t| t.id.as_str()),
+        strings(|t| t.email_id.as_str()),
+        strings(|t| t.account_id.as_str()),
+        strings(|t| t.text.as_str()),
+        strings(|t| t.requester.as_str()),
+        strings(|t| t.subject.as_str()),
+        Arc::new(StringArray::from(
+            tasks
+                .iter()
+                .map(|t| t.due.map(|d| d.format("%Y-%m-%d").to_string()))
+                .collect::<Vec<_>>(),
+        )),
+        opt_strings(|t| t.due_text.as_deref()),
+        strings(|t| t.status.as_str()),
+        strings(|t| t.source.as_str()),
+        Arc::new(Int64Array::from(
+            tasks
+                .iter()
+                .map(|t| t.created_at.timestamp())
+                .collect::<Vec<_>>(),
+        )),
+        Arc::new(Int64Array::from(
+            tasks
+                .iter()
+                .map(|t| t.completed_at.map(|c| c.timestamp()))
+                .collect::<Vec<_>>(),
+        )),
+    ];
+
+    let batch = RecordBatch::try_new(Arc::new(schema), arrays)?;
+    Ok(batch)
+}
+
+/// Convert a record batch row to a task
+pub fn batch_to_task(batch: &RecordBatch, row: usize) -> Task {
+    let get_opt_string = |col: &str| -> Option<String> {
+        batch
+            .column_by_name(col)
+            .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+            .filter(|a| !a.is_null(row))
+            .map(|a| a.value(row).to_string())
+    };
+    let get_string = |col: &str| -> String { get_opt_string(col).unwrap_or_default() };
+    let get_time = |col: &str| -> Option<DateTime<Utc>> {
+        batch
+            .column_by_name(col)
+            .and_then(|c| c.as_any().downcast_ref::<Int64Array>())
+            .filter(|a| !a.is_null(row))
+            .and_then(|a| DateTime::from_timestamp(a.value(row), 0))
+    };
+
+    Task {
+        id: get_string("id"),
+        email_id: get_string("email_id"),
+        account_id: get_string("account_id"),
+        text: get_string("text"),
+        requester: get_string("requester"),
+        subject: get_string("subject"),
+        due: get_opt_string("due")
+            .and_then(|d| chrono::NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok()),
+        due_text: get_opt_string("due_text"),
+        status: TaskStatus::from_str(&get_string("status")).unwrap_or(TaskStatus::Open),
+        source: TaskSource::from_str(&get_string("source")).unwrap_or(TaskSource::Heuristic),
+        created_at: get_time("created_at").unwrap_or_else(Utc::now),
+        completed_at: get_time("completed_at"),
+    }
+}
+
+/// Convert audit entries to a record batch
+pub fn audit_to_batch(entries: &[AuditEntry]) -> Result<RecordBatch> {
+    let schema = audit_schema();
+
+    let arrays: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from(
+            entries.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(),
+        )),
+        Arc::new(Int64Array::from(
+            entries
+                .iter()
+                .map(|e| e.timestamp.timestamp())
+                .collect::<Vec<_>>(),
+        )),
+        Arc::new(StringArray::from(
+            entries.iter().map(|e| e.actor.as_str()).collect::<Vec<_>>(),
+        )),
+        Arc::new(StringArray::from(
+            entries
+                .iter()
+                .map(|e| e.action.as_str())
+                .collect::<Vec<_>>(),
+        )),
+        Arc::new(StringArray::from(
+            entries
+                .iter()
+                .map(|e| e.account_id.as_deref())
+                .collect::<Vec<_>>(),
+        )),
+        Arc::new(StringArray::from(
+            entries
+                .iter()
+                .map(|e| serde_json::to_string(&e.params))
+                .collect::<std::result::Result<Vec<_>, _>>()?,
+        )),
+        Arc::new(BooleanArray::from(
+            entries.iter().map(|e| e.success).collect::<Vec<_>>(),
+        )),
+        Arc::new(StringArray::from(
+            entries
+                .iter()
+                .map(|e| e.error.as_deref())
+                .collect::<Vec<_>>(),
+        )),
+    ];
+
+    let batch = RecordBatch::try_new(Arc::new(schema), arrays)?;
+    Ok(batch)
+}
+
+/// Convert a record batch row to an audit entry (None for an unknown actor)
+pub fn batch_to_audit_entry(batch: &RecordBatch, row: usize) -> Option<AuditEntry> {
+    let get_opt_string = |col: &str| -> Option<String> {
+        batch
+            .column_by_name(col)
+            .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+            .filter(|a| !a.is_null(row))
+            .map(|a| a.value(row).to_string())
+    };
+    let get_string = |col: &str| -> String { get_opt_string(col).unwrap_or_default() };
+
+    let timestamp = batch
+        .column_by_name("timestamp")
+        .and_then(|c| c.as_any().downcast_ref::<Int64Array>())
+        .and_then(|a| DateTime::from_timestamp(a.value(row), 0))
+        .unwrap_or_else(Utc::now);
+    let success = batch
+        .column_by_name("success")
+        .and_then(|c| c.as_any().downcast_ref::<BooleanArray>())
+        .map(|a| a.value(row))
+        .unwrap_or(false);
+
+    Some(AuditEntry {
+        id: get_string("id"),
+        timestamp,
+        actor: AuditActor::from_str(&get_string("actor"))?,
+        action: get_string("action"),
+        account_id: get_opt_string("account_id"),
+        params: serde_json::from_str(&get_string("params")).unwrap_or_default(),
+        success,
+        error: get_opt_string("error"),
+    })
+}
+
+/// Convert threads to a record batch
+pub fn threads_to_batch(threads: &[Thread], dimension: usize) -> Result<RecordBatch> {
+    let schema = thread_schema(dimension);
+
+    let arrays: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from(
+            threads.iter().map(|t| t.id.as_str()).collect::<Vec<_>>(),
+        )),
+        Arc::new(StringArray::from(
+            threads
+                .iter()
+                .map(|t| t.account_id.as_str())
+                .collect::<Vec<_>>(),
+        )),
+        Arc::new(UInt64Array::from(
+            threads
+                .iter()
+                .map(|t| t.gmail_thread_id)
+                .collect::<Vec<_>>(),
+        )),
+        Arc::new(StringArray::from(
+            threads
+                .iter()
+                .map(|t| t.subject.as_str())
+                .collect::<Vec<_>>(),
+        )),
+        Arc::new(StringArray::from(
+            threads
+                .iter()
+                .map(|t| serde_json::to_string(&t.participants))
+                .collect::<std::result::Result<Vec<_>, _>>()?,
+        )),
+        Arc::new(UInt32Array::from(
+            threads.iter().map(|t| t.message_count).collect::<Vec<_>>(),
+        )),
+        Arc::new(UInt32Array::from(
+            threads.iter().map(|t| t.unread_count).collect::<Vec<_>>(),
+        )),
+        Arc::new(Int64Array::from(
+            threads
+                .iter()
+                .map(|t| t.first_date.timestamp())
+                .collect::<Vec<_>>(),
+        )),
+        Arc::new(Int64Array::from(
+            threads
+                .iter()
+                .map(|t| t.last_activity.timestamp())
+                .collect::<Vec<_>>(),
+        )),
+        Arc::new(StringArray::from(
+            threads
+                .iter()
+                .map(|t| serde_json::to_string(&t.last_from))
+                .collect::<std::result::Result<Vec<_>, _>>()?,
+        )),
+        Arc::new(StringArray::from(
+            threads
+                .iter()
+                .map(|t| t.snippet.as_str())
+                .collect::<Vec<_>>(),
+        )),
+        Arc::new(BooleanArray::from(
+            threads
+                .iter()
+                .map(|t| t.has_attachments)
+                .collect::<Vec<_>>(),
+        )),
+        Arc::new(BooleanArray::from(
+            threads.iter().map(|t| t.has_sent).collect::<Vec<_>>(),
+        )),
+        Arc::new(StringArray::from(
+            threads
+                .iter()
+                .map(|t| serde_json::to_string(&t.email_ids))
+                .collect::<std::result::Result<Vec<_>, _>>()?,
+        )),
+        Arc::new(StringArray::from(
+            threads
+                .iter()
+                .map(|t| t.content.as_str())
+                .collect::<Vec<_>>(),
+        )),
+        Arc::new(embedding_array(
+            "threads",
+            threads
+                .iter()
+                .map(|t| (t.id.as_str(), t.embedding.as_ref())),
+            dimension,
+        )?),
+    ];
+
+    let batch = RecordBatch::try_new(Arc::new(schema), arrays)?;
+    Ok(batch)
+}
+
+/// Convert a record batch row to a thread (the embedding isn't loaded)
+pub fn batch_to_thread(batch: &RecordBatch, row: usize) -> Thread {
+    let get_string = |col: &str| -> String {
+        batch
+            .column_by_name(col)
+            .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+            .filter(|a| !a.is_null(row))
+            .map(|a| a.value(row).to_string())
+            .unwrap_or_default()
+    };
+    let get_u32 = |col: &str| -> u32 {
+        batch
+            .column_by_name(col)
+            .and_then(|c| c.as_any().downcast_ref::<UInt32Array>())
+            .map(|a| a.value(row))
+            .unwrap_or(0)
+    };
+    let get_date = |col: &str| -> DateTime<Utc> {
+        batch
+            .column_by_name(col)
+            .and_then(|c| c.as_any().downcast_ref::<Int64Array>())
+            .and_then(|a| DateTime::from_timestamp(a.value(row), 0))
+            .unwrap_or_else(Utc::now)
+    };
+    let get_bool = |col: &str| -> bool {
+        batch
+            .column_by_name(col)
+            .and_then(|c| c.as_any().downcast_ref::<BooleanArray>())
+            .map(|a| a.value(row))
+            .unwrap_or(false)
+    };
+
+    Thread {
+        id: get_string("id"),
+        account_id: get_string("account_id"),
+        gmail_thread_id: batch
+            .column_by_name("gmail_thread_id")
+            .and_then(|c| c.as_any().downcast_ref::<UInt64Array>())
+            .map(|a| a.value(row))
+            .unwrap_or(0),
+        subject: get_string("subject"),
+        participants: serde_json::from_str(&get_string("participants")).unwrap_or_default(),
+        message_count: get_u32("message_count"),
+        unread_count: get_u32("unread_count"),
+        first_date: get_date("first_date"),
+        last_activity: get_date("last_activity"),
+        last_from: serde_json::from_str(&get_string("last_from"))
+            .unwrap_or_else(|_| Address::new("")),
+        snippet: get_string("snippet"),
+        has_attachments: get_bool("has_attachments"),
+        has_sent: get_bool("has_sent"),
+        email_ids: serde_json::from_str(&get_string("email_ids")).unwrap_or_default(),
+        content: get_string("content"),
+        embedding: None,
+    }
+}
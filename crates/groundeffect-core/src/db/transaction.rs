@@ -0,0 +1,364 @@
+//! Staged multi-table writes with compensation
+//!
+//! LanceDB commits every write on its own, so a feature that writes an email
+//! and the rows derived from it (activity items, receipts) can fail halfway
+//! and leave them out of step. A [`WriteTransaction`] snapshots the rows each
+//! write replaces before touching them; if a later write fails, `rollback`
+//! deletes what was written and restores the snapshots, newest first.
+//! Writes replace rows by ID, so repeating one (replaying a batch after a
+//! crash) leaves the same rows as writing it once.
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use arrow_array::{RecordBatch, RecordBatchIterator};
+use arrow_schema::SchemaRef;
+use lancedb::query::{ExecutableQuery, QueryBase};
+use lancedb::Table;
+use tracing::{debug, error};
+
+use super::{
+    activity_schema, activity_to_batch, email_schema, emails_to_batch, event_schema,
+    events_to_batch, receipt_schema, receipts_to_batch, Database,
+};
+use crate::error::Result;
+use crate::models::{ActivityItem, CalendarEvent, Email, Receipt};
+use crate::vectors::VectorTable;
+
+/// How to undo one write
+struct Compensation {
+    table: Table,
+    table_name: &'static str,
+    /// Rows with these IDs were replaced
+    filter: String,
+    /// The rows as they were before the write
+    previous: Vec<RecordBatch>,
+}
+
+/// A group of writes that is applied completely or not at all
+///
+/// ```ignore
+/// let mut tx = db.transaction();
+/// let result = async {
+///     tx.upsert_emails(&emails).await?;
+///     tx.upsert_receipts(&receipts).await
+/// }
+/// .await;
+/// tx.finish(result).await?;
+/// ```
+#[must_use = "call finish, commit or rollback"]
+pub struct WriteTransaction<'a> {
+    db: &'a Database,
+    undo: Vec<Compensation>,
+}
+
+impl Database {
+    /// Start a transaction for writes that must land together
+    pub fn transaction(&self) -> WriteTransaction<'_> {
+        WriteTransaction {
+            db: self,
+            undo: Vec::new(),
+        }
+    }
+}
+
+impl WriteTransaction<'_> {
+    /// Insert or update emails
+    pub async fn upsert_emails(&mut self, emails: &[Email]) -> Result<()> {
+        if emails.is_empty() {
+            return Ok(());
+        }
+        let emails = last_by_id(emails, |e| e.id.as_str());
+        let ids: Vec<&str> = emails.iter().map(|e| e.id.as_str()).collect();
+        let table = self.db.emails_table()?;
+        let dimension = self.db.vector_dimension(VectorTable::Emails);
+        self.replace_rows(
+            table,
+            super::EMAILS_TABLE,
+            &ids,
+            emails_to_batch(&emails, dimension)?,
+            Arc::new(email_schema(dimension)),
+        )
+        .await
+    }
+
+    /// Insert or update calendar events
+    pub async fn upsert_events(&mut self, events: &[CalendarEvent]) -> Result<()> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        let events = last_by_id(events, |e| e.id.as_str());
+        let ids: Vec<&str> = events.iter().map(|e| e.id.as_str()).collect();
+        let table = self.db.events_table()?;
+        let dimension = self.db.vector_dimension(VectorTable::Events);
+        self.replace_rows(
+            table,
+            super::EVENTS_TABLE,
+            &ids,
+            events_to_batch(&events, dimension)?,
+            Arc::new(event_schema(dimension)),
+        )
+        .await
+    }
+
+    /// Insert or update activity items
+    pub async fn upsert_activity(&mut self, items: &[ActivityItem]) -> Result<()> {
+        if items.is_empty() {
+            return Ok(());
+        }
+        let items = last_by_id(items, |i| i.id.as_str());
+        let ids: Vec<&str> = items.iter().map(|i| i.id.as_str()).collect();
+        let table = self.db.activity_table()?;
+        self.replace_rows(
+            table,
+            super::ACTIVITY_TABLE,
+            &ids,
+            activity_to_batch(&items)?,
+            Arc::new(activity_schema()),
+        )
+        .await
+    }
+
+    /// Insert or update receipts
+    pub async fn upsert_receipts(&mut self, receipts: &[Receipt]) -> Result<()> {
+        if receipts.is_empty() {
+            return Ok(());
+        }
+        let receipts = last_by_id(receipts, |r| r.id.as_str());
+        let ids: Vec<&str> = receipts.iter().map(|r| r.id.as_str()).collect();
+        let table = self.db.receipts_table()?;
+        self.replace_rows(
+            table,
+            super::RECEIPTS_TABLE,
+            &ids,
+            receipts_to_batch(&receipts)?,
+            Arc::new(receipt_schema()),
+        )
+        .await
+    }
+
+    /// Move the rows whose `key` column is `old` to `new`, rewriting each
+    /// batch of them with `rewrite`; returns the rewritten batches
+    ///
+    /// Rolling back deletes the rows keyed by `new`, so nothing may be
+    /// keyed by it beforehand.
+    pub async fn rename_rows(
+        &mut self,
+        table: Table,
+        table_name: &'static str,
+        key: &str,
+        old: &str,
+        new: &str,
+        rewrite: impl Fn(&RecordBatch) -> Result<RecordBatch>,
+    ) -> Result<Vec<RecordBatch>> {
+        use futures::TryStreamExt;
+
+        let filter = format!("{} = '{}'", key, old.replace('\'', "''"));
+        let results = table.query().only_if(filter.clone()).execute().await?;
+        let previous: Vec<RecordBatch> = results.try_collect().await?;
+        let renamed = previous
+            .iter()
+            .filter(|batch| batch.num_rows() > 0)
+            .map(&rewrite)
+            .collect::<Result<Vec<_>>>()?;
+        let Some(schema) = renamed.first().map(|batch| batch.schema()) else {
+            return Ok(renamed);
+        };
+
+        self.undo.push(Compensation {
+            table: table.clone(),
+            table_name,
+            filter: format!("{} = '{}'", key, new.replace('\'', "''")),
+            previous,
+        });
+
+        table.delete(&filter).await?;
+        let batches = RecordBatchIterator::new(renamed.iter().cloned().map(Ok), schema);
+        table.add(Box::new(batches)).execute().await?;
+
+        debug!(
+            "Renamed {} rows in {}",
+            renamed.iter().map(|b| b.num_rows()).sum::<usize>(),
+            table_name
+        );
+        Ok(renamed)
+    }
+
+    /// Snapshot the rows with these IDs, then replace them with `batch`
+    async fn replace_rows(
+        &mut self,
+        table: Table,
+        table_name: &'static str,
+        ids: &[&str],
+        batch: RecordBatch,
+        schema: SchemaRef,
+    ) -> Result<()> {
+        use futures::TryStreamExt;
+
+        let quoted: Vec<String> = ids.iter().map(|id| format!("'{}'", id)).collect();
+        let filter = format!("id IN ({})", quoted.join(", "));
+
+        let results = table.query().only_if(filter.clone()).execute().await?;
+        let previous: Vec<RecordBatch> = results.try_collect().await?;
+
+        // Registered before writing so a failed add after the delete is undone too
+        self.undo.push(Compensation {
+            table: table.clone(),
+            table_name,
+            filter: filter.clone(),
+            previous,
+        });
+
+        table.delete(&filter).await?;
+        let batches = RecordBatchIterator::new(vec![Ok(batch)], schema);
+        table.add(Box::new(batches)).execute().await?;
+
+        debug!("Staged {} rows in {}", ids.len(), table_name);
+        Ok(())
+    }
+
+    /// Keep every write
+    pub fn commit(self) {
+        debug!("Committed transaction of {} writes", self.undo.len());
+    }
+
+    /// Undo every write, newest first
+    ///
+    /// Keeps going after a failed step and returns the first error.
+    pub async fn rollback(self) -> Result<()> {
+        let mut first_error = None;
+
+        for step in self.undo.into_iter().rev() {
+            if let Err(e) = step.undo().await {
+                error!("Failed to roll back write to {}: {}", step.table_name, e);
+                first_error.get_or_insert(e);
+            }
+        }
+
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// Commit if the writes succeeded, otherwise roll back and return their error
+    pub async fn finish<T>(self, result: Result<T>) -> Result<T> {
+        match result {
+            Ok(value) => {
+                self.commit();
+                Ok(value)
+            }
+            Err(e) => {
+                error!("Rolling back transaction: {}", e);
+                // The write error is the one worth reporting
+                let _ = self.rollback().await;
+                Err(e)
+            }
+        }
+    }
+}
+
+/// `rows` keeping only the last of any repeated ID, so a batch that
+/// carries a row twice doesn't add it twice
+fn last_by_id<T: Clone>(rows: &[T], id: impl Fn(&T) -> &str) -> Cow<'_, [T]> {
+    let mut seen = HashSet::new();
+    if rows.iter().all(|row| seen.insert(id(row))) {
+        return Cow::Borrowed(rows);
+    }
+    let mut seen = HashSet::new();
+    let mut kept: Vec<T> = rows
+        .iter()
+        .rev()
+        .filter(|row| seen.insert(id(row)))
+        .cloned()
+        .collect();
+    kept.reverse();
+    Cow::Owned(kept)
+}
+
+impl Compensation {
+    async fn undo(&self) -> Result<()> {
+        self.table.delete(&self.filter).await?;
+        if let Some(first) = self.previous.first() {
+            let schema = first.schema();
+            let batches = RecordBatchIterator::new(self.previous.iter().cloned().map(Ok), schema);
+            self.table.add(Box::new(batches)).execute().await?;
+        }
+        debug!("Rolled back write to {}", self.table_name);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ActivitySource;
+    use chrono::Utc;
+
+    fn activity(id: &str, title: &str) -> ActivityItem {
+        ActivityItem {
+            id: id.to_string(),
+            account_id: "me@example.com".to_string(),
+            source: ActivitySource::GitHub,
+            project: "acme/widgets".to_string(),
+            item: Some("#1".to_string()),
+            title: title.to_string(),
+            action: "opened".to_string(),
+            actor: None,
+            url: None,
+            date: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rollback_restores_replaced_rows() {
+        let dir = std::env::temp_dir().join(format!("ge-tx-{}", uuid::Uuid::new_v4()));
+        let db = Database::open(&dir).await.unwrap();
+        db.upsert_activity(&[activity("a1", "Original")])
+            .await
+            .unwrap();
+
+        let mut tx = db.transaction();
+        tx.upsert_activity(&[activity("a1", "Changed"), activity("a2", "New")])
+            .await
+            .unwrap();
+        tx.rollback().await.unwrap();
+
+        let items = db.list_activity(None, None, None, None, 10).await.unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].title, "Original");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_repeated_writes_leave_one_row_per_id() {
+        let dir = std::env::temp_dir().join(format!("ge-tx-{}", uuid::Uuid::new_v4()));
+        let db = Database::open(&dir).await.unwrap();
+
+        for _ in 0..2 {
+            let mut tx = db.transaction();
+            tx.upsert_activity(&[
+                activity("a1", "First"),
+                activity("a2", "Other"),
+                activity("a1", "Second"),
+            ])
+            .await
+            .unwrap();
+            tx.commit();
+        }
+
+        let mut titles: Vec<String> = db
+            .list_activity(None, None, None, None, 10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|i| i.title)
+            .collect();
+        titles.sort();
+        assert_eq!(titles, vec!["Other", "Second"]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,208 @@
+//! Disk usage introspection
+//!
+//! `groundeffect storage` reports where local storage goes so users know what
+//! to prune. Each LanceDB table is a `<name>.lance` directory: column data
+//! under `data/`, full-text and scalar indexes under `_indices/`, and version
+//! manifests, transaction and deletion files alongside. Embedding columns are
+//! stored with the rest of the row data, so their share is estimated from the
+//! number of embedded rows and the vector width.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use arrow_array::RecordBatch;
+use futures::stream::TryStreamExt;
+use lancedb::query::{ExecutableQuery, QueryBase};
+use serde::Serialize;
+
+use super::{batch_to_email, Database, EMAILS_TABLE, EVENTS_TABLE, THREADS_TABLE};
+use crate::error::Result;
+use crate::relocate;
+use crate::vectors::VectorTable;
+
+/// Space one LanceDB table takes on disk
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TableUsage {
+    pub name: String,
+
+    pub rows: u64,
+
+    /// Column data files, including rows not yet compacted away
+    pub data_bytes: u64,
+
+    /// Estimated share of `data_bytes` taken by embedding vectors
+    pub vector_bytes: u64,
+
+    /// Full-text search and scalar (ID lookup) index files
+    pub index_bytes: u64,
+
+    /// Version manifests, transaction and deletion files
+    pub metadata_bytes: u64,
+}
+
+impl TableUsage {
+    /// Total bytes on disk
+    pub fn total(&self) -> u64 {
+        self.data_bytes + self.index_bytes + self.metadata_bytes
+    }
+}
+
+/// Local storage taken by one sender's mail
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SenderUsage {
+    pub email: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// Number of emails
+    pub emails: u64,
+
+    /// Raw size of the emails
+    pub email_bytes: u64,
+
+    /// Size of their downloaded attachments
+    pub attachment_bytes: u64,
+}
+
+impl SenderUsage {
+    /// Total bytes
+    pub fn total(&self) -> u64 {
+        self.email_bytes + self.attachment_bytes
+    }
+}
+
+/// Measure a `<name>.lance` table directory (zero if it doesn't exist)
+///
+/// The returned usage has no name or row count; those come from the table.
+pub fn measure_table_dir(dir: &Path) -> Result<TableUsage> {
+    let mut usage = TableUsage::default();
+    if !dir.is_dir() {
+        return Ok(usage);
+    }
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let bytes = if entry.file_type()?.is_dir() {
+            relocate::summarize(&entry.path())?.bytes
+        } else {
+            entry.metadata()?.len()
+        };
+        match entry.file_name().to_str() {
+            Some("data") => usage.data_bytes += bytes,
+            Some("_indices") => usage.index_bytes += bytes,
+            _ => usage.metadata_bytes += bytes,
+        }
+    }
+    Ok(usage)
+}
+
+impl Database {
+    /// Disk usage of every table, largest first
+    pub async fn table_usage(&self) -> Result<Vec<TableUsage>> {
+        let mut tables = Vec::new();
+        for name in self.connection.table_names().execute().await? {
+            let mut usage = measure_table_dir(&self.path.join(format!("{}.lance", name)))?;
+            let table = self.connection.open_table(&name).execute().await?;
+            usage.rows = table.count_rows(None).await? as u64;
+
+            let dimension = match name.as_str() {
+                // Thread vectors come from the same model as email vectors
+                EMAILS_TABLE | THREADS_TABLE => Some(self.vector_dimension(VectorTable::Emails)),
+                EVENTS_TABLE => Some(self.vector_dimension(VectorTable::Events)),
+                _ => None,
+            };
+            if let Some(dimension) = dimension {
+                let embedded = table
+                    .count_rows(Some("embedding IS NOT NULL".to_string()))
+                    .await? as u64;
+                let estimate = embedded * dimension as u64 * std::mem::size_of::<f32>() as u64;
+                usage.vector_bytes = estimate.min(usage.data_bytes);
+            }
+
+            usage.name = name;
+            tables.push(usage);
+        }
+        tables.sort_by(|a, b| b.total().cmp(&a.total()).then(a.name.cmp(&b.name)));
+        Ok(tables)
+    }
+
+    /// Senders whose mail takes the most local storage (email size plus
+    /// downloaded attachments), largest first
+    pub async fn top_senders_by_size(
+        &self,
+        account_id: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<SenderUsage>> {
+        let table = self.emails_table()?;
+
+        let mut query = table.query().select(lancedb::query::Select::columns(&[
+            "id",
+            "account_id",
+            "from_email",
+            "from_name",
+            "raw_size",
+            "attachments",
+        ]));
+        if let Some(id) = account_id {
+            query = query.only_if(format!("account_id = '{}'", id.replace('\'', "''")));
+        }
+        let results = query.execute().await?;
+        let batches: Vec<RecordBatch> = results.try_collect().await?;
+
+        let mut senders: HashMap<String, SenderUsage> = HashMap::new();
+        for batch in &batches {
+            for i in 0..batch.num_rows() {
+                let email = batch_to_email(batch, i)?;
+                let sender = senders
+                    .entry(email.from.email.to_lowercase())
+                    .or_insert_with(|| SenderUsage {
+                        email: email.from.email.clone(),
+                        ..Default::default()
+                    });
+                if sender.name.is_none() {
+                    sender.name = email.from.name.clone().filter(|n| !n.is_empty());
+                }
+                sender.emails += 1;
+                sender.email_bytes += email.raw_size;
+                sender.attachment_bytes += email
+                    .attachments
+                    .iter()
+                    .filter(|a| a.downloaded)
+                    .map(|a| a.size)
+                    .sum::<u64>();
+            }
+        }
+
+        let mut senders: Vec<SenderUsage> = senders.into_values().collect();
+        senders.sort_by(|a, b| b.total().cmp(&a.total()).then(a.email.cmp(&b.email)));
+        senders.truncate(limit);
+        Ok(senders)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_measure_table_dir() {
+        let dir = std::env::temp_dir().join(format!("ge-usage-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(dir.join("data")).unwrap();
+        fs::create_dir_all(dir.join("_indices/abc")).unwrap();
+        fs::create_dir_all(dir.join("_versions")).unwrap();
+        fs::write(dir.join("data/part-0.lance"), vec![0u8; 1000]).unwrap();
+        fs::write(dir.join("data/part-1.lance"), vec![0u8; 500]).unwrap();
+        fs::write(dir.join("_indices/abc/index.idx"), vec![0u8; 200]).unwrap();
+        fs::write(dir.join("_versions/1.manifest"), vec![0u8; 30]).unwrap();
+
+        let usage = measure_table_dir(&dir).unwrap();
+        assert_eq!(usage.data_bytes, 1500);
+        assert_eq!(usage.index_bytes, 200);
+        assert_eq!(usage.metadata_bytes, 30);
+        assert_eq!(usage.total(), 1730);
+
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(measure_table_dir(&dir).unwrap(), TableUsage::default());
+    }
+}
@@ -0,0 +1,708 @@
+//! Daily and weekly digests
+//!
+//! `groundeffect digest --period day|week` gathers what needs attention into
+//! one structured document: new important mail, threads waiting for a
+//! reply, upcoming meetings and their conflicts, and follow-ups coming due.
+//! It is meant to be handed to an assistant for a written briefing. The
+//! daemon can also email a plain-text rendering on a schedule (`[digest]`).
+
+use std::collections::HashSet;
+use std::fmt::Write as _;
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::config::{Config, DigestConfig};
+use crate::db::Database;
+use crate::error::{Error, Result};
+use crate::models::{
+    is_spam_or_trash, CalendarEvent, Email, EmailCategory, EmailSummary, Task, TaskStatus,
+};
+use crate::oauth::OAuthManager;
+use crate::scheduling::conflicts;
+use crate::unanswered::{find_unanswered, UnansweredEmail};
+
+/// How often the daemon checks whether a scheduled digest is due
+pub const DIGEST_CHECK_SECS: u64 = 300;
+
+/// Most important emails listed
+const MAX_IMPORTANT: usize = 25;
+
+/// Most unanswered threads listed
+const MAX_UNANSWERED: usize = 25;
+
+/// Most follow-ups listed
+const MAX_FOLLOW_UPS: usize = 50;
+
+/// How much time a digest covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DigestPeriod {
+    #[default]
+    Day,
+    Week,
+}
+
+impl DigestPeriod {
+    /// Parse "day" or "week" (also "daily", "weekly")
+    pub fn from_str(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "day" | "daily" => Some(Self::Day),
+            "week" | "weekly" => Some(Self::Week),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Day => "day",
+            Self::Week => "week",
+        }
+    }
+
+    /// Span looked back on for new mail and ahead for meetings
+    pub fn span(&self) -> Duration {
+        match self {
+            Self::Day => Duration::days(1),
+            Self::Week => Duration::weeks(1),
+        }
+    }
+
+    /// How far back threads waiting for a reply are looked for; a reply
+    /// owed for a few days matters even in a daily digest
+    pub fn unanswered_span(&self) -> Duration {
+        match self {
+            Self::Day => Duration::weeks(1),
+            Self::Week => Duration::weeks(2),
+        }
+    }
+}
+
+/// A received email worth the user's attention
+#[derive(Debug, Clone, Serialize)]
+pub struct DigestEmail {
+    #[serde(flatten)]
+    pub email: EmailSummary,
+
+    /// Not opened yet
+    pub unread: bool,
+
+    /// Starred in Gmail
+    pub starred: bool,
+
+    /// The user is in To (not just Cc or a list)
+    pub direct: bool,
+}
+
+/// A meeting in the look-ahead window
+#[derive(Debug, Clone, Serialize)]
+pub struct DigestMeeting {
+    pub id: String,
+    pub account_id: String,
+    pub summary: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub all_day: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub join_url: Option<String>,
+    pub attendees: usize,
+}
+
+/// Two upcoming meetings that overlap
+#[derive(Debug, Clone, Serialize)]
+pub struct DigestConflict {
+    /// Event IDs, earlier-starting first
+    pub ids: [String; 2],
+    pub summaries: [String; 2],
+
+    /// When they overlap
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+/// Everything a digest reports
+#[derive(Debug, Clone, Serialize)]
+pub struct Digest {
+    pub period: DigestPeriod,
+    pub generated_at: DateTime<Utc>,
+
+    /// Start of the look-back window for new mail
+    pub since: DateTime<Utc>,
+
+    /// End of the look-ahead window for meetings and follow-ups
+    pub until: DateTime<Utc>,
+
+    pub timezone: String,
+
+    /// New received mail from people, starred and directly addressed first
+    pub important_emails: Vec<DigestEmail>,
+
+    /// Threads waiting for the user's reply, longest waiting first
+    pub unanswered: Vec<UnansweredEmail>,
+
+    pub upcoming_meetings: Vec<DigestMeeting>,
+    pub conflicts: Vec<DigestConflict>,
+
+    /// Open tasks due by `until`, including overdue ones
+    pub follow_ups: Vec<Task>,
+}
+
+impl Digest {
+    /// Whether there is nothing to report
+    pub fn is_empty(&self) -> bool {
+        self.important_emails.is_empty()
+            && self.unanswered.is_empty()
+            && self.upcoming_meetings.is_empty()
+            && self.follow_ups.is_empty()
+    }
+}
+
+/// Assemble a digest of `period` as of `now`, for one account or all
+pub async fn build_digest(
+    db: &Database,
+    config: &Config,
+    period: DigestPeriod,
+    account_id: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<Digest> {
+    let tz: Tz = config.general.timezone.parse().unwrap_or(Tz::UTC);
+    let since = now - period.span();
+    let until = now + period.span();
+
+    let mut own: Vec<String> = db
+        .list_accounts()
+        .await?
+        .into_iter()
+        .map(|a| a.id)
+        .collect();
+    own.extend(config.compose.identities.keys().cloned());
+
+    let emails = db
+        .list_emails_since(account_id, now - period.unanswered_span())
+        .await?;
+    let recent: Vec<Email> = emails.iter().filter(|e| e.date >= since).cloned().collect();
+    let important_emails = important_emails(&recent, &own, MAX_IMPORTANT);
+
+    let identities: Vec<String> = config.compose.identities.keys().cloned().collect();
+    let mut unanswered = find_unanswered(&emails, &identities, now);
+    unanswered.truncate(MAX_UNANSWERED);
+
+    let accounts = account_id.map(|id| vec![id.to_string()]);
+    let events = db
+        .list_events_overlapping(accounts.as_deref(), now, until)
+        .await?;
+    let upcoming_meetings = upcoming_meetings(&events, now, until, tz);
+    let conflicts = meeting_conflicts(&upcoming_meetings);
+
+    let follow_ups = db
+        .list_tasks(
+            account_id,
+            Some(TaskStatus::Open),
+            Some(until.with_timezone(&tz).date_naive()),
+            MAX_FOLLOW_UPS,
+        )
+        .await?;
+
+    Ok(Digest {
+        period,
+        generated_at: now,
+        since,
+        until,
+        timezone: tz.name().to_string(),
+        important_emails,
+        unanswered,
+        upcoming_meetings,
+        conflicts,
+        follow_ups,
+    })
+}
+
+/// Received mail from people (not bulk, automated or activity mail, and
+/// not in Spam or Trash), plus anything starred; starred first, then mail
+/// addressed directly to one of `own_addresses`, then unread, then newest
+pub fn important_emails(
+    emails: &[Email],
+    own_addresses: &[String],
+    limit: usize,
+) -> Vec<DigestEmail> {
+    let own: HashSet<String> = own_addresses.iter().map(|a| a.to_lowercase()).collect();
+    let mut seen = HashSet::new();
+    let mut picked: Vec<DigestEmail> = emails
+        .iter()
+        .filter(|e| !e.is_sent && !own.contains(&e.from.email.to_lowercase()))
+        .filter(|e| !is_spam_or_trash(&e.folder))
+        .filter(|e| {
+            e.is_flagged()
+                || (e.activity_source.is_none()
+                    && matches!(e.category, None | Some(EmailCategory::Personal)))
+        })
+        .filter(|e| seen.insert(e.message_id.clone()))
+        .map(|e| DigestEmail {
+            email: EmailSummary::from(e),
+            unread: !e.is_read(),
+            starred: e.is_flagged(),
+            direct: e.to.iter().any(|a| {
+                let address = a.email.to_lowercase();
+                address == e.account_id.to_lowercase() || own.contains(&address)
+            }),
+        })
+        .collect();
+    picked.sort_by(|a, b| {
+        b.starred
+            .cmp(&a.starred)
+            .then(b.direct.cmp(&a.direct))
+            .then(b.unread.cmp(&a.unread))
+            .then(b.email.date.cmp(&a.email.date))
+    });
+    picked.truncate(limit);
+    picked
+}
+
+/// Events keeping the user busy between `now` and `until`, soonest first
+pub fn upcoming_meetings(
+    events: &[CalendarEvent],
+    now: DateTime<Utc>,
+    until: DateTime<Utc>,
+    tz: Tz,
+) -> Vec<DigestMeeting> {
+    let mut meetings: Vec<DigestMeeting> = conflicts(events, now, until, tz)
+        .into_iter()
+        .filter_map(|busy| {
+            let event = events.iter().find(|e| e.id == busy.id)?;
+            Some(DigestMeeting {
+                id: busy.id,
+                account_id: busy.account_id,
+                summary: busy.summary,
+                start: busy.start,
+                end: busy.end,
+                all_day: event.all_day,
+                location: event.location.clone().filter(|l| !l.trim().is_empty()),
+                join_url: event.conference.as_ref().map(|c| c.join_url.clone()),
+                attendees: event.attendees.len(),
+            })
+        })
+        .collect();
+    meetings.sort_by(|a, b| a.start.cmp(&b.start).then(a.summary.cmp(&b.summary)));
+    meetings
+}
+
+/// Pairs of timed meetings that overlap
+///
+/// All-day events are left out, and so are copies of one meeting on
+/// several calendars (same summary and times).
+pub fn meeting_conflicts(meetings: &[DigestMeeting]) -> Vec<DigestConflict> {
+    let timed: Vec<&DigestMeeting> = meetings.iter().filter(|m| !m.all_day).collect();
+    let mut found = Vec::new();
+    for (i, first) in timed.iter().enumerate() {
+        for second in &timed[i + 1..] {
+            let same_meeting = first.summary == second.summary
+                && first.start == second.start
+                && first.end == second.end;
+            if same_meeting || !(first.start < second.end && second.start < first.end) {
+                continue;
+            }
+            let (first, second) = if second.start < first.start {
+                (second, first)
+            } else {
+                (first, second)
+            };
+            found.push(DigestConflict {
+                ids: [first.id.clone(), second.id.clone()],
+                summaries: [first.summary.clone(), second.summary.clone()],
+                start: first.start.max(second.start),
+                end: first.end.min(second.end),
+            });
+        }
+    }
+    found
+}
+
+/// Plain-text rendering, for the scheduled email
+pub fn render_text(digest: &Digest, tz: Tz) -> String {
+    let local = |t: DateTime<Utc>| t.with_timezone(&tz);
+    let mut out = String::new();
+    let _ = writeln!(out, "{}\n", digest_subject(digest, tz));
+
+    if digest.is_empty() {
+        out.push_str("Nothing needs your attention.\n");
+        return out;
+    }
+
+    if !digest.important_emails.is_empty() {
+        let _ = writeln!(out, "IMPORTANT EMAIL ({})", digest.important_emails.len());
+        for item in &digest.important_emails {
+            let mark = if item.starred { "* " } else { "" };
+            let _ = writeln!(
+                out,
+                "- {}{}: {} ({})",
+                mark,
+                item.email.from,
+                item.email.subject,
+                local(item.email.date).format("%a %b %-d %H:%M")
+            );
+        }
+        out.push('\n');
+    }
+
+    if !digest.unanswered.is_empty() {
+        let _ = writeln!(out, "WAITING FOR YOUR REPLY ({})", digest.unanswered.len());
+        for item in &digest.unanswered {
+            let _ = writeln!(
+                out,
+                "- {}: {} ({} day{})",
+                item.email.from,
+                item.email.subject,
+                item.waiting_days,
+                if item.waiting_days == 1 { "" } else { "s" }
+            );
+        }
+        out.push('\n');
+    }
+
+    if !digest.upcoming_meetings.is_empty() {
+        let _ = writeln!(
+            out,
+            "UPCOMING MEETINGS ({})",
+            digest.upcoming_meetings.len()
+        );
+        for meeting in &digest.upcoming_meetings {
+            let when = if meeting.all_day {
+                format!("{} all day", local(meeting.start).format("%a %b %-d"))
+            } else {
+                format!(
+                    "{}-{}",
+                    local(meeting.start).format("%a %b %-d %H:%M"),
+                    local(meeting.end).format("%H:%M")
+                )
+            };
+            let _ = writeln!(out, "- {} {}", when, meeting.summary);
+        }
+        out.push('\n');
+    }
+
+    if !digest.conflicts.is_empty() {
+        let _ = writeln!(out, "CONFLICTS ({})", digest.conflicts.len());
+        for conflict in &digest.conflicts {
+            let _ = writeln!(
+                out,
+                "- {}-{}: \"{}\" overlaps \"{}\"",
+                local(conflict.start).format("%a %b %-d %H:%M"),
+                local(conflict.end).format("%H:%M"),
+                conflict.summaries[0],
+                conflict.summaries[1]
+            );
+        }
+        out.push('\n');
+    }
+
+    if !digest.follow_ups.is_empty() {
+        let _ = writeln!(out, "FOLLOW-UPS DUE ({})", digest.follow_ups.len());
+        for task in &digest.follow_ups {
+            let due = task
+                .due
+                .map(|d| format!(", due {}", d.format("%a %b %-d")))
+                .unwrap_or_default();
+            let _ = writeln!(out, "- {} (from {}{})", task.text, task.requester, due);
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Subject line: "Daily digest for Thu Oct 16" or "Weekly digest for the
+/// week of Oct 13"
+pub fn digest_subject(digest: &Digest, tz: Tz) -> String {
+    let date = digest.generated_at.with_timezone(&tz).date_naive();
+    match digest.period {
+        DigestPeriod::Day => format!("Daily digest for {}", date.format("%a %b %-d")),
+        DigestPeriod::Week => {
+            let monday = date - Duration::days(date.weekday().num_days_from_monday() as i64);
+            format!("Weekly digest for the week of {}", monday.format("%b %-d"))
+        }
+    }
+}
+
+/// The most recent time a scheduled digest was due at or before `now`
+pub fn last_scheduled(config: &DigestConfig, now: DateTime<Utc>, tz: Tz) -> Option<DateTime<Utc>> {
+    let today = now.with_timezone(&tz).date_naive();
+    let at = |date: NaiveDate| {
+        tz.from_local_datetime(&date.and_hms_opt(config.hour.min(23), 0, 0)?)
+            .earliest()
+            .map(|t| t.with_timezone(&Utc))
+    };
+    let weekday = match config.period {
+        DigestPeriod::Day => None,
+        DigestPeriod::Week => Some(config.weekday),
+    };
+    (0..=7)
+        .map(|back| today - Duration::days(back))
+        .filter(|date| match weekday {
+            Some(w) => date.weekday() == w,
+            None => true,
+        })
+        .filter_map(at)
+        .find(|time| *time <= now)
+}
+
+/// Whether the scheduled digest should go out, given when one was last sent
+pub fn is_due(
+    config: &DigestConfig,
+    last_sent: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+    tz: Tz,
+) -> bool {
+    if !config.enabled {
+        return false;
+    }
+    match last_scheduled(config, now, tz) {
+        Some(slot) => !matches!(last_sent, Some(sent) if sent >= slot),
+        None => false,
+    }
+}
+
+/// Account a scheduled digest is sent from and its recipient, per `[digest]`
+pub async fn digest_addresses(db: &Database, config: &Config) -> Result<(String, String)> {
+    let account_id = match &config.digest.account {
+        Some(account) => config
+            .resolve_account(account)
+            .ok_or_else(|| Error::AccountNotFound(account.clone()))?,
+        None => db
+            .list_accounts()
+            .await?
+            .into_iter()
+            .next()
+            .map(|a| a.id)
+            .ok_or_else(|| Error::Config("No account to send the digest from".to_string()))?,
+    };
+    let to = config
+        .digest
+        .to
+        .clone()
+        .unwrap_or_else(|| account_id.clone());
+    Ok((account_id, to))
+}
+
+/// Email a digest's plain-text rendering from `account_id` to `to`
+///
+/// Returns the Gmail message ID.
+pub async fn send_digest(
+    oauth: &OAuthManager,
+    digest: &Digest,
+    account_id: &str,
+    to: &str,
+    tz: Tz,
+) -> Result<String> {
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+    let message = format!(
+        "From: {}\r\nTo: {}\r\nSubject: {}\r\nMIME-Version: 1.0\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: 8bit\r\n\r\n{}",
+        account_id,
+        to,
+        digest_subject(digest, tz),
+        render_text(digest, tz).replace('\n', "\r\n")
+    );
+    let access_token = oauth.get_valid_token(account_id).await?;
+    let response = Client::new()
+        .post("https://gmail.googleapis.com/gmail/v1/users/me/messages/send")
+        .bearer_auth(&access_token)
+        .json(&serde_json::json!({ "raw": URL_SAFE_NO_PAD.encode(message.as_bytes()) }))
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(Error::Other(format!(
+            "Gmail API error {}: {}",
+            status, body
+        )));
+    }
+    let json: serde_json::Value = response.json().await?;
+    let id = json["id"].as_str().unwrap_or_default().to_string();
+    info!("Sent {} digest to {} ({})", digest.period.as_str(), to, id);
+    Ok(id)
+}
+
+/// Send the scheduled digest if one is due since `last_sent`
+///
+/// Returns whether the slot is done with: sent, or skipped because there
+/// was nothing to report. Errors leave it due, so the next check retries.
+pub async fn run_scheduled(
+    db: &Database,
+    config: &Config,
+    oauth: &OAuthManager,
+    last_sent: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Result<bool> {
+    let tz: Tz = config.general.timezone.parse().unwrap_or(Tz::UTC);
+    if !is_due(&config.digest, last_sent, now, tz) {
+        return Ok(false);
+    }
+    let (account_id, to) = digest_addresses(db, config).await?;
+    let digest = build_digest(db, config, config.digest.period, None, now).await?;
+    if digest.is_empty() {
+        info!("Nothing to report, skipping the scheduled digest");
+        return Ok(true);
+    }
+    send_digest(oauth, &digest, &account_id, &to, tz).await?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Address;
+
+    fn email(id: &str, from: &str, to: &str, hour: u32) -> Email {
+        Email {
+            is_sent: from == "me@example.com",
+            flags: vec!["\\Seen".to_string()],
+            category: Some(EmailCategory::Personal),
+            to: vec![Address::new(to)],
+            date: Utc.with_ymd_and_hms(2024, 6, 3, hour, 0, 0).unwrap(),
+            ..Email::test(id, from, &format!("Subject {}", id))
+        }
+    }
+
+    fn meeting(id: &str, summary: &str, start: u32, end: u32) -> DigestMeeting {
+        DigestMeeting {
+            id: id.to_string(),
+            account_id: "me@example.com".to_string(),
+            summary: summary.to_string(),
+            start: Utc.with_ymd_and_hms(2024, 6, 3, start, 0, 0).unwrap(),
+            end: Utc.with_ymd_and_hms(2024, 6, 3, end, 0, 0).unwrap(),
+            all_day: false,
+            location: None,
+            join_url: None,
+            attendees: 2,
+        }
+    }
+
+    #[test]
+    fn test_important_emails() {
+        let mut newsletter = email("news", "news@shop.example", "me@example.com", 12);
+        newsletter.category = Some(EmailCategory::Newsletter);
+        let mut starred_newsletter = email("deal", "deals@shop.example", "me@example.com", 8);
+        starred_newsletter.category = Some(EmailCategory::Newsletter);
+        starred_newsletter.flags.push("\\Flagged".to_string());
+        let cc_only = email("cc", "bob@example.com", "team@example.com", 11);
+        let mut unread = email("unread", "dana@example.com", "me@example.com", 9);
+        unread.flags.clear();
+        let read = email("read", "ann@example.com", "me@example.com", 10);
+        let sent = email("sent", "me@example.com", "ann@example.com", 13);
+        let mut trash = email("trash", "eve@example.com", "me@example.com", 14);
+        trash.folder = "[Gmail]/Trash".to_string();
+
+        let own = vec!["me@example.com".to_string()];
+        let emails = [
+            newsletter,
+            starred_newsletter,
+            cc_only,
+            unread,
+            read,
+            sent,
+            trash,
+        ];
+        let picked = important_emails(&emails, &own, 10);
+        let ids: Vec<&str> = picked.iter().map(|e| e.email.message_id.as_str()).collect();
+        assert_eq!(ids, vec!["deal", "unread", "read", "cc"]);
+        assert!(picked[0].starred);
+        assert!(!picked[3].direct);
+
+        assert_eq!(important_emails(&emails, &own, 2).len(), 2);
+    }
+
+    #[test]
+    fn test_meeting_conflicts() {
+        let mut offsite = meeting("offsite", "Offsite", 0, 23);
+        offsite.all_day = true;
+        let meetings = vec![
+            offsite,
+            meeting("standup", "Standup", 9, 10),
+            meeting("review", "Design review", 9, 11),
+            // The same meeting synced from a second calendar
+            meeting("review-copy", "Design review", 9, 11),
+            meeting("lunch", "Lunch", 11, 12),
+        ];
+
+        let found = meeting_conflicts(&meetings);
+        let pairs: Vec<[&str; 2]> = found
+            .iter()
+            .map(|c| [c.ids[0].as_str(), c.ids[1].as_str()])
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![["standup", "review"], ["standup", "review-copy"]]
+        );
+        assert_eq!(
+            found[0].end,
+            Utc.with_ymd_and_hms(2024, 6, 3, 10, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_schedule() {
+        let tz: Tz = "America/New_York".parse().unwrap();
+        let daily = DigestConfig {
+            enabled: true,
+            hour: 7,
+            ..DigestConfig::default()
+        };
+        // 2024-06-05 (a Wednesday) 06:00 in New York: yesterday's 07:00 slot
+        let early = Utc.with_ymd_and_hms(2024, 6, 5, 10, 0, 0).unwrap();
+        let slot = last_scheduled(&daily, early, tz).unwrap();
+        assert_eq!(slot, Utc.with_ymd_and_hms(2024, 6, 4, 11, 0, 0).unwrap());
+
+        let later = early + Duration::hours(2);
+        assert!(is_due(&daily, Some(slot), later, tz));
+        assert!(!is_due(&daily, Some(later), later + Duration::hours(1), tz));
+        assert!(!is_due(
+            &DigestConfig {
+                enabled: false,
+                ..daily.clone()
+            },
+            None,
+            later,
+            tz
+        ));
+
+        let weekly = DigestConfig {
+            period: DigestPeriod::Week,
+            weekday: chrono::Weekday::Mon,
+            ..daily
+        };
+        assert_eq!(
+            last_scheduled(&weekly, later, tz).unwrap(),
+            Utc.with_ymd_and_hms(2024, 6, 3, 11, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_render_text() {
+        let now = Utc.with_ymd_and_hms(2024, 6, 3, 12, 0, 0).unwrap();
+        let mut digest = Digest {
+            period: DigestPeriod::Day,
+            generated_at: now,
+            since: now - Duration::days(1),
+            until: now + Duration::days(1),
+            timezone: "UTC".to_string(),
+            important_emails: vec![],
+            unanswered: vec![],
+            upcoming_meetings: vec![],
+            conflicts: vec![],
+            follow_ups: vec![],
+        };
+        assert!(render_text(&digest, Tz::UTC).contains("Nothing needs your attention"));
+
+        digest.upcoming_meetings = vec![meeting("standup", "Standup", 14, 15)];
+        let text = render_text(&digest, Tz::UTC);
+        assert!(text.starts_with("Daily digest for Mon Jun 3\n"));
+        assert!(text.contains("UPCOMING MEETINGS (1)\n- Mon Jun 3 14:00-15:00 Standup"));
+        assert!(!text.contains("CONFLICTS"));
+    }
+}
@@ -0,0 +1,180 @@
+//! Updating Gmail drafts in place
+//!
+//! Gmail replaces a draft's whole message on update, so `draft update` (CLI
+//! and MCP) rebuilds it from the current one: the fields not being changed,
+//! the send-as From with its identity's display name and Reply-To, the
+//! reply headers that keep it in its thread, and its attachments. Default
+//! CCs are only added when a draft is created, so one removed since stays
+//! removed.
+
+use base64::engine::general_purpose::{URL_SAFE, URL_SAFE_NO_PAD};
+use base64::Engine;
+use mail_parser::{HeaderValue, MessageParser};
+use reqwest::Client;
+
+use crate::config::{ComposeConfig, IdentityConfig};
+use crate::error::{Error, Result};
+use crate::forward::{source_attachments, OutgoingAttachment};
+use crate::oauth::OAuthManager;
+
+/// Gmail API drafts endpoint
+pub const DRAFTS_URL: &str = "https://gmail.googleapis.com/gmail/v1/users/me/drafts";
+
+/// A draft's current message
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExistingDraft {
+    /// Bare From address, if the draft has one
+    pub from: Option<String>,
+
+    pub to: Vec<String>,
+
+    pub cc: Vec<String>,
+
+    pub bcc: Vec<String>,
+
+    pub subject: String,
+
+    /// Plain-text body (including any signature added at creation)
+    pub body: String,
+
+    /// In-Reply-To header value, with angle brackets
+    pub in_reply_to: Option<String>,
+
+    /// References header value, with angle brackets
+    pub references: Option<String>,
+
+    pub attachments: Vec<OutgoingAttachment>,
+}
+
+impl ExistingDraft {
+    /// Parse a draft's message source
+    pub fn parse(source: &[u8]) -> Result<Self> {
+        let parsed = MessageParser::default()
+            .parse(source)
+            .ok_or_else(|| Error::InvalidEmailFormat("Failed to parse draft".to_string()))?;
+        let addresses = |header: Option<&mail_parser::Address>| -> Vec<String> {
+            header
+                .map(|addrs| {
+                    addrs
+                        .iter()
+                        .filter_map(|addr| addr.address().map(str::to_string))
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        let message_ids = |value: &HeaderValue| -> Option<String> {
+            let ids: Vec<String> = value
+                .as_text_list()
+                .map(|list| list.into_iter().map(|id| format!("<{}>", id)).collect())
+                .unwrap_or_default();
+            (!ids.is_empty()).then(|| ids.join(" "))
+        };
+
+        Ok(Self {
+            from: addresses(parsed.from()).into_iter().next(),
+            to: addresses(parsed.to()),
+            cc: addresses(parsed.cc()),
+            bcc: addresses(parsed.bcc()),
+            subject: parsed.subject().unwrap_or_default().to_string(),
+            body: parsed
+                .body_text(0)
+                .map(|s| s.to_string())
+                .unwrap_or_default(),
+            in_reply_to: message_ids(parsed.in_reply_to()),
+            references: message_ids(parsed.references()),
+            attachments: source_attachments(&parsed),
+        })
+    }
+
+    /// Send-as address of the draft (`account_id` if it has no usable From)
+    /// and the defaults configured for it
+    pub fn sender(&self, compose: &ComposeConfig, account_id: &str) -> (String, IdentityConfig) {
+        let sender = self
+            .from
+            .clone()
+            .filter(|addr| addr.contains('@'))
+            .unwrap_or_else(|| account_id.to_string());
+        let identity = compose.identity(&sender).cloned().unwrap_or_default();
+        (sender, identity)
+    }
+}
+
+/// Fetch a draft's current message from Gmail
+pub async fn fetch_draft(
+    oauth: &OAuthManager,
+    account_id: &str,
+    draft_id: &str,
+) -> Result<ExistingDraft> {
+    let access_token = oauth.get_valid_token(account_id).await?;
+    let response = Client::new()
+        .get(format!("{}/{}", DRAFTS_URL, draft_id))
+        .query(&[("format", "raw")])
+        .bearer_auth(&access_token)
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(Error::Other(format!(
+            "Gmail API error {}: {}",
+            status, body
+        )));
+    }
+    let json: serde_json::Value = response.json().await?;
+    let raw = json["message"]["raw"]
+        .as_str()
+        .ok_or_else(|| Error::Other("Gmail API returned no raw draft".to_string()))?;
+    let source = URL_SAFE
+        .decode(raw)
+        .or_else(|_| URL_SAFE_NO_PAD.decode(raw))
+        .map_err(|e| Error::InvalidEmailFormat(format!("Invalid raw draft: {}", e)))?;
+    ExistingDraft::parse(&source)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DRAFT: &str = "From: Work Me <me+work@example.com>\r\nTo: Bob <bob@acme.io>, ann@acme.io\r\nCc: carl@acme.io\r\nSubject: Re: Q3 pricing\r\nIn-Reply-To: <1@acme.io>\r\nReferences: <0@acme.io> <1@acme.io>\r\nMIME-Version: 1.0\r\nContent-Type: multipart/mixed; boundary=\"b\"\r\n\r\n--b\r\nContent-Type: text/plain; charset=utf-8\r\n\r\nSounds good\r\n--b\r\nContent-Type: text/csv; name=\"q3.csv\"\r\nContent-Disposition: attachment; filename=\"q3.csv\"\r\n\r\na,b\r\n--b--\r\n";
+
+    #[test]
+    fn test_parse_keeps_headers_and_attachments() {
+        let draft = ExistingDraft::parse(DRAFT.as_bytes()).unwrap();
+        assert_eq!(draft.from.as_deref(), Some("me+work@example.com"));
+        assert_eq!(draft.to, vec!["bob@acme.io", "ann@acme.io"]);
+        assert_eq!(draft.cc, vec!["carl@acme.io"]);
+        assert_eq!(draft.subject, "Re: Q3 pricing");
+        assert_eq!(draft.body.trim(), "Sounds good");
+        assert_eq!(draft.in_reply_to.as_deref(), Some("<1@acme.io>"));
+        assert_eq!(draft.references.as_deref(), Some("<0@acme.io> <1@acme.io>"));
+        assert_eq!(draft.attachments.len(), 1);
+        assert_eq!(draft.attachments[0].filename, "q3.csv");
+    }
+
+    #[test]
+    fn test_sender_from_bare_or_named_address() {
+        let mut compose = ComposeConfig::default();
+        compose.identities.insert(
+            "me+work@example.com".to_string(),
+            IdentityConfig {
+                reply_to: Some("team@example.com".to_string()),
+                ..Default::default()
+            },
+        );
+
+        let named = ExistingDraft::parse(DRAFT.as_bytes()).unwrap();
+        let (sender, identity) = named.sender(&compose, "me@example.com");
+        assert_eq!(sender, "me+work@example.com");
+        assert_eq!(identity.reply_to.as_deref(), Some("team@example.com"));
+
+        let bare = ExistingDraft::parse(b"From: me+work@example.com\r\nSubject: Hi\r\n\r\nHi\r\n")
+            .unwrap();
+        assert_eq!(
+            bare.sender(&compose, "me@example.com").0,
+            "me+work@example.com"
+        );
+
+        let none = ExistingDraft::parse(b"Subject: Hi\r\n\r\nHi\r\n").unwrap();
+        assert_eq!(none.sender(&compose, "me@example.com").0, "me@example.com");
+    }
+}
@@ -0,0 +1,166 @@
+//! Dynamic batching for the local embedding engine
+//!
+//! Callers (concurrent account syncs, re-embedding, search queries) each ask
+//! for a few texts at a time. The batcher queues their requests and runs them
+//! together: once the first request arrives it waits up to `max_wait` for more,
+//! or until `max_texts` texts are queued, then embeds everything in one call
+//! on a blocking thread and hands each caller its share. The engine splits the
+//! combined batch into GPU-sized forward passes.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::{mpsc, oneshot};
+use tracing::debug;
+
+use crate::error::{Error, Result};
+use crate::metrics::{self, Queue};
+
+/// Embeds a batch of texts (the local engine in production)
+pub type EmbedFn = Arc<dyn Fn(&[String]) -> Result<Vec<Vec<f32>>> + Send + Sync>;
+
+struct Job {
+    texts: Vec<String>,
+    reply: oneshot::Sender<Result<Vec<Vec<f32>>>>,
+}
+
+/// Queue that merges concurrent embedding requests into larger batches
+pub struct EmbeddingBatcher {
+    jobs: mpsc::UnboundedSender<Job>,
+}
+
+impl EmbeddingBatcher {
+    /// Start the batching worker (needs a tokio runtime)
+    pub fn new(embed: EmbedFn, max_texts: usize, max_wait: Duration) -> Self {
+        let (jobs, queue) = mpsc::unbounded_channel();
+        tokio::spawn(run_worker(embed, queue, max_texts.max(1), max_wait));
+        Self { jobs }
+    }
+
+    /// Embed texts, possibly together with other callers' texts
+    pub async fn embed_batch(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>> {
+        let (reply, response) = oneshot::channel();
+        let _queued = metrics::enqueue(Queue::Embedding);
+        self.jobs
+            .send(Job { texts, reply })
+            .map_err(|_| Error::Embedding("Embedding worker stopped".to_string()))?;
+        response
+            .await
+            .map_err(|_| Error::Embedding("Embedding worker dropped the request".to_string()))?
+    }
+}
+
+async fn run_worker(
+    embed: EmbedFn,
+    mut queue: mpsc::UnboundedReceiver<Job>,
+    max_texts: usize,
+    max_wait: Duration,
+) {
+    while let Some(first) = queue.recv().await {
+        let mut queued = first.texts.len();
+        let mut jobs = vec![first];
+
+        let deadline = tokio::time::Instant::now() + max_wait;
+        while queued < max_texts {
+            match tokio::time::timeout_at(deadline, queue.recv()).await {
+                Ok(Some(job)) => {
+                    queued += job.texts.len();
+                    jobs.push(job);
+                }
+                _ => break,
+            }
+        }
+
+        let texts: Vec<String> = jobs.iter().flat_map(|job| job.texts.clone()).collect();
+        if jobs.len() > 1 {
+            debug!(
+                "Merged {} embedding requests ({} texts)",
+                jobs.len(),
+                texts.len()
+            );
+        }
+
+        let embed = embed.clone();
+        let result = tokio::task::spawn_blocking(move || embed(&texts))
+            .await
+            .unwrap_or_else(|e| Err(Error::Embedding(format!("Embedding task failed: {}", e))));
+
+        match result {
+            Ok(embeddings) => {
+                let mut embeddings = embeddings.into_iter();
+                for job in jobs {
+                    let share: Vec<Vec<f32>> = embeddings.by_ref().take(job.texts.len()).collect();
+                    let _ = job.reply.send(Ok(share));
+                }
+            }
+            Err(e) => {
+                let message = e.to_string();
+                for job in jobs {
+                    let _ = job.reply.send(Err(Error::Embedding(message.clone())));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Fake engine: one-element vector holding each text's length, and a
+    /// record of batch sizes
+    fn fake_engine(calls: Arc<Mutex<Vec<usize>>>) -> EmbedFn {
+        Arc::new(move |texts: &[String]| {
+            calls.lock().unwrap().push(texts.len());
+            Ok(texts.iter().map(|t| vec![t.len() as f32]).collect())
+        })
+    }
+
+    fn texts(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_requests_share_a_batch() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let batcher =
+            EmbeddingBatcher::new(fake_engine(calls.clone()), 64, Duration::from_millis(50));
+
+        let (a, b) = tokio::join!(
+            batcher.embed_batch(texts(&["a", "bb"])),
+            batcher.embed_batch(texts(&["ccc"]))
+        );
+        assert_eq!(a.unwrap(), vec![vec![1.0], vec![2.0]]);
+        assert_eq!(b.unwrap(), vec![vec![3.0]]);
+        assert_eq!(*calls.lock().unwrap(), vec![3]);
+    }
+
+    #[tokio::test]
+    async fn test_full_batch_runs_without_waiting() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let batcher = EmbeddingBatcher::new(fake_engine(calls.clone()), 2, Duration::from_secs(60));
+
+        let result = tokio::time::timeout(
+            Duration::from_secs(5),
+            batcher.embed_batch(texts(&["a", "b"])),
+        )
+        .await
+        .expect("a full batch must not wait for max_wait");
+        assert_eq!(result.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_errors_reach_every_caller() {
+        let failing: EmbedFn =
+            Arc::new(|_: &[String]| Err(Error::Embedding("out of memory".to_string())));
+        let batcher = EmbeddingBatcher::new(failing, 64, Duration::from_millis(20));
+
+        let (a, b) = tokio::join!(
+            batcher.embed_batch(texts(&["a"])),
+            batcher.embed_batch(texts(&["b"]))
+        );
+        assert!(a.unwrap_err().to_string().contains("out of memory"));
+        assert!(b.unwrap_err().to_string().contains("out of memory"));
+    }
+}
@@ -1,24 +1,41 @@
 //! Embedding pipeline using Candle with Metal acceleration
 //!
 //! Uses bge-base-en-v1.5 (or all-MiniLM-L6-v2) for text embeddings.
-//! Supports both local (CPU/GPU) and remote (HTTP service) embedding generation.
-
-use std::path::Path;
-use std::sync::Arc;
-use std::time::Duration;
+//! Supports both local (CPU/GPU) and remote embedding generation (OpenAI,
+//! Ollama, OpenRouter or a custom HTTP service; see [`remote`]).
+//! Remote embedding is compiled out by the `no-remote-embedding` feature, and
+//! model downloads by `offline-search`.
+
+mod batcher;
+mod quantized;
+#[cfg(not(feature = "no-remote-embedding"))]
+pub mod remote;
+mod template;
+
+pub use batcher::*;
+#[cfg(not(feature = "no-remote-embedding"))]
+pub use remote::RemoteEmbeddingClient;
+pub use template::*;
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
 
 use candle_core::{Device, Tensor};
 use candle_nn::VarBuilder;
 use candle_transformers::models::bert::{BertModel, Config as BertConfig};
-use hf_hub::{api::sync::Api, Repo, RepoType};
-use parking_lot::RwLock;
-use serde::{Deserialize, Serialize};
+#[cfg(not(feature = "offline-search"))]
+use hf_hub::api::sync::Api;
+use hf_hub::{Repo, RepoType};
+use parking_lot::{MappedRwLockReadGuard, Mutex, RwLock, RwLockReadGuard};
 use tokenizers::Tokenizer;
 use tracing::{debug, info, warn};
 
-use crate::config::{EmbeddingFallback, EmbeddingProvider, SearchConfig};
+use crate::config::{EmbeddingFallback, EmbeddingPrecision, EmbeddingProvider, SearchConfig};
 use crate::error::{Error, Result};
-use crate::EMBEDDING_DIMENSION;
+use crate::metrics;
+use quantized::QuantizedBert;
 
 /// Supported embedding models
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -56,13 +73,46 @@ impl EmbeddingModel {
     }
 }
 
+/// Padded tokens per forward pass on a GPU (e.g. 32 texts of 512 tokens)
+const GPU_BATCH_TOKENS: usize = 16_384;
+
+/// Padded tokens per forward pass on the CPU
+const CPU_BATCH_TOKENS: usize = 4_096;
+
+/// Model weights at the configured precision
+enum LoadedModel {
+    F32(BertModel),
+    Int8(QuantizedBert),
+}
+
+impl LoadedModel {
+    fn forward(
+        &self,
+        input_ids: &Tensor,
+        token_type_ids: &Tensor,
+        attention_mask: &Tensor,
+    ) -> candle_core::Result<Tensor> {
+        match self {
+            Self::F32(model) => model.forward(input_ids, token_type_ids, Some(attention_mask)),
+            Self::Int8(model) => model.forward(input_ids, token_type_ids, attention_mask),
+        }
+    }
+}
+
 /// Embedding engine for generating text embeddings
 pub struct EmbeddingEngine {
-    model: Arc<RwLock<BertModel>>,
+    /// Loaded weights (None after `unload`, reloaded on the next embed)
+    model: Arc<RwLock<Option<LoadedModel>>>,
+    weights_path: PathBuf,
+    /// Contents of the model's config.json
+    model_config: String,
+    precision: EmbeddingPrecision,
     tokenizer: Arc<Tokenizer>,
     device: Device,
     model_type: EmbeddingModel,
     max_length: usize,
+    /// Padded tokens per forward pass (0 = pick by device)
+    max_batch_tokens: AtomicUsize,
 }
 
 impl EmbeddingEngine {
@@ -72,49 +122,107 @@ impl EmbeddingEngine {
     /// - On macOS with `metal` feature: Uses Metal
     /// - On Linux with `cuda` feature: Uses CUDA
     /// - Otherwise: Falls back to CPU
-    pub fn new(model_type: EmbeddingModel, use_gpu: bool) -> Result<Self> {
+    ///
+    /// `Int8` precision quantizes the weights as they load and always runs
+    /// on the CPU.
+    pub fn new(
+        model_type: EmbeddingModel,
+        use_gpu: bool,
+        precision: EmbeddingPrecision,
+    ) -> Result<Self> {
         info!(
-            "Initializing embedding engine with model {:?}, gpu={}",
-            model_type, use_gpu
+            "Initializing embedding engine with model {:?}, gpu={}, precision={}",
+            model_type,
+            use_gpu,
+            precision.as_str()
         );
 
         // Select device - try GPU acceleration if enabled
-        let device = if use_gpu {
+        let device = if precision == EmbeddingPrecision::Int8 {
+            if use_gpu {
+                info!("int8 embedding weights run on the CPU; ignoring use_gpu");
+            }
+            Device::Cpu
+        } else if use_gpu {
             Self::select_gpu_device()
         } else {
             info!("GPU disabled, using CPU for embeddings");
             Device::Cpu
         };
 
+        let model_repo = Repo::new(model_type.model_id().to_string(), RepoType::Model);
+
         // Download model from HuggingFace
-        let api = Api::new().map_err(|e| Error::ModelLoading(e.to_string()))?;
-        let repo = api.repo(Repo::new(
-            model_type.model_id().to_string(),
-            RepoType::Model,
-        ));
+        #[cfg(not(feature = "offline-search"))]
+        let repo = Api::new()
+            .map_err(|e| Error::ModelLoading(e.to_string()))?
+            .repo(model_repo);
+        #[cfg(not(feature = "offline-search"))]
+        let fetch = |name: &str| repo.get(name).map_err(|e| e.to_string());
+
+        // Offline builds only read the local HuggingFace cache
+        #[cfg(feature = "offline-search")]
+        let repo = hf_hub::Cache::from_env().repo(model_repo);
+        #[cfg(feature = "offline-search")]
+        let fetch = |name: &str| {
+            repo.get(name).ok_or_else(|| {
+                format!(
+                    "{} is not downloaded, and this build can't download models (offline-search)",
+                    name
+                )
+            })
+        };
 
         info!("Loading model files from HuggingFace...");
 
         // Load tokenizer
-        let tokenizer_path = repo
-            .get("tokenizer.json")
+        let tokenizer_path = fetch("tokenizer.json")
             .map_err(|e| Error::ModelLoading(format!("Failed to get tokenizer: {}", e)))?;
         let tokenizer = Tokenizer::from_file(tokenizer_path)
             .map_err(|e| Error::ModelLoading(format!("Failed to load tokenizer: {}", e)))?;
 
         // Load model config
-        let config_path = repo
-            .get("config.json")
+        let config_path = fetch("config.json")
             .map_err(|e| Error::ModelLoading(format!("Failed to get config: {}", e)))?;
-        let config_str = std::fs::read_to_string(&config_path)?;
-        let config: BertConfig = serde_json::from_str(&config_str)?;
+        let model_config = std::fs::read_to_string(&config_path)?;
 
         // Load model weights
-        let weights_path = repo
-            .get("model.safetensors")
-            .or_else(|_| repo.get("pytorch_model.bin"))
+        let weights_path = fetch("model.safetensors")
+            .or_else(|_| fetch("pytorch_model.bin"))
             .map_err(|e| Error::ModelLoading(format!("Failed to get weights: {}", e)))?;
 
+        let model = Self::load_model(&weights_path, &model_config, &device, precision)?;
+
+        info!("Embedding model loaded successfully");
+
+        Ok(Self {
+            model: Arc::new(RwLock::new(Some(model))),
+            weights_path,
+            model_config,
+            precision,
+            tokenizer: Arc::new(tokenizer),
+            device,
+            model_type,
+            max_length: 512,
+            max_batch_tokens: AtomicUsize::new(0),
+        })
+    }
+
+    /// Read BERT weights onto the device, quantizing them for `Int8`
+    fn load_model(
+        weights_path: &Path,
+        model_config: &str,
+        device: &Device,
+        precision: EmbeddingPrecision,
+    ) -> Result<LoadedModel> {
+        if precision == EmbeddingPrecision::Int8 {
+            return Ok(LoadedModel::Int8(QuantizedBert::load(
+                weights_path,
+                model_config,
+            )?));
+        }
+
+        let config: BertConfig = serde_json::from_str(model_config)?;
         let vb = if weights_path
             .extension()
             .map(|e| e == "safetensors")
@@ -124,27 +232,63 @@ impl EmbeddingEngine {
                 VarBuilder::from_mmaped_safetensors(
                     &[weights_path],
                     candle_core::DType::F32,
-                    &device,
+                    device,
                 )
                 .map_err(|e| Error::ModelLoading(format!("Failed to load safetensors: {}", e)))?
             }
         } else {
-            VarBuilder::from_pth(weights_path, candle_core::DType::F32, &device)
+            VarBuilder::from_pth(weights_path, candle_core::DType::F32, device)
                 .map_err(|e| Error::ModelLoading(format!("Failed to load weights: {}", e)))?
         };
 
-        let model = BertModel::load(vb, &config)
-            .map_err(|e| Error::ModelLoading(format!("Failed to load model: {}", e)))?;
+        BertModel::load(vb, &config)
+            .map(LoadedModel::F32)
+            .map_err(|e| Error::ModelLoading(format!("Failed to load model: {}", e)))
+    }
 
-        info!("Embedding model loaded successfully");
+    /// The loaded model, reading the weights back in if they were unloaded
+    fn loaded_model(&self) -> Result<MappedRwLockReadGuard<'_, LoadedModel>> {
+        loop {
+            match RwLockReadGuard::try_map(self.model.read(), Option::as_ref) {
+                Ok(model) => return Ok(model),
+                Err(guard) => drop(guard),
+            }
+            let mut slot = self.model.write();
+            if slot.is_none() {
+                let start = Instant::now();
+                *slot = Some(Self::load_model(
+                    &self.weights_path,
+                    &self.model_config,
+                    &self.device,
+                    self.precision,
+                )?);
+                info!(
+                    "Reloaded embedding model in {:.1}s",
+                    start.elapsed().as_secs_f64()
+                );
+            }
+        }
+    }
 
-        Ok(Self {
-            model: Arc::new(RwLock::new(model)),
-            tokenizer: Arc::new(tokenizer),
-            device,
-            model_type,
-            max_length: 512,
-        })
+    /// Drop the model weights to free memory; the next embed reloads them.
+    /// Returns false if they weren't loaded.
+    pub fn unload(&self) -> bool {
+        let unloaded = self.model.write().take().is_some();
+        if unloaded {
+            self.sync();
+            info!("Unloaded embedding model");
+        }
+        unloaded
+    }
+
+    /// Whether the model weights are in memory
+    pub fn is_loaded(&self) -> bool {
+        self.model.read().is_some()
+    }
+
+    /// Precision the weights were loaded at
+    pub fn precision(&self) -> EmbeddingPrecision {
+        self.precision
     }
 
     /// Load from a local cache directory
@@ -152,6 +296,7 @@ impl EmbeddingEngine {
         cache_dir: impl AsRef<Path>,
         model_type: EmbeddingModel,
         use_gpu: bool,
+        precision: EmbeddingPrecision,
     ) -> Result<Self> {
         let cache_dir = cache_dir.as_ref();
         let model_dir = cache_dir.join(model_type.model_id().replace("/", "--"));
@@ -162,7 +307,38 @@ impl EmbeddingEngine {
             std::env::set_var("HF_HOME", cache_dir);
         }
 
-        Self::new(model_type, use_gpu)
+        Self::new(model_type, use_gpu, precision)
+    }
+
+    /// Find a downloaded copy of the model, without downloading or loading it.
+    ///
+    /// Looks in the cache directory and in the HuggingFace hub cache
+    /// (`$HF_HOME/hub`, or `~/.cache/huggingface/hub`).
+    pub fn find_cached_model(
+        cache_dir: impl AsRef<Path>,
+        model_type: EmbeddingModel,
+    ) -> Option<PathBuf> {
+        let cache_dir = cache_dir.as_ref();
+        let flat_id = model_type.model_id().replace("/", "--");
+        let hub_dir = format!("models--{}", flat_id);
+
+        let mut candidates = vec![
+            cache_dir.join(&flat_id),
+            cache_dir.join("hub").join(&hub_dir),
+        ];
+        if let Ok(hf_home) = std::env::var("HF_HOME") {
+            candidates.push(PathBuf::from(hf_home).join("hub").join(&hub_dir));
+        }
+        if let Some(home) = dirs::home_dir() {
+            candidates.push(
+                home.join(".cache")
+                    .join("huggingface")
+                    .join("hub")
+                    .join(&hub_dir),
+            );
+        }
+
+        candidates.into_iter().find(|dir| dir.exists())
     }
 
     /// Select the best available GPU device based on compiled features
@@ -215,19 +391,35 @@ impl EmbeddingEngine {
         Ok(embeddings.into_iter().next().unwrap_or_default())
     }
 
+    /// Limit padded tokens per forward pass (0 = 16384 on a GPU, 4096 on the CPU)
+    pub fn set_max_batch_tokens(&self, tokens: usize) {
+        self.max_batch_tokens.store(tokens, Ordering::Relaxed);
+    }
+
+    /// Padded tokens per forward pass
+    pub fn max_batch_tokens(&self) -> usize {
+        match self.max_batch_tokens.load(Ordering::Relaxed) {
+            0 if matches!(self.device, Device::Cpu) => CPU_BATCH_TOKENS,
+            0 => GPU_BATCH_TOKENS,
+            tokens => tokens,
+        }
+    }
+
     /// Generate embeddings for a batch of texts
+    ///
+    /// Texts are sorted by length and split into forward passes of at most
+    /// [`Self::max_batch_tokens`] padded tokens, so short texts aren't padded
+    /// to the longest one and large batches fit in GPU memory.
     pub fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
         if texts.is_empty() {
             return Ok(vec![]);
         }
 
         debug!("Generating embeddings for {} texts", texts.len());
+        let start = Instant::now();
 
         // Tokenize all texts (CPU work)
-        let mut all_input_ids = Vec::new();
-        let mut all_attention_masks = Vec::new();
-        let mut max_len = 0;
-
+        let mut encoded = Vec::with_capacity(texts.len());
         for text in texts {
             let encoding = self
                 .tokenizer
@@ -246,10 +438,46 @@ impl EmbeddingEngine {
                 ids.truncate(self.max_length);
                 mask.truncate(self.max_length);
             }
+            encoded.push((ids, mask));
+        }
 
-            max_len = max_len.max(ids.len());
-            all_input_ids.push(ids);
-            all_attention_masks.push(mask);
+        let lengths: Vec<usize> = encoded.iter().map(|(ids, _)| ids.len()).collect();
+        let batches = plan_batches(&lengths, self.max_batch_tokens());
+
+        let mut result = vec![Vec::new(); texts.len()];
+        for batch in &batches {
+            let inputs: Vec<&(Vec<u32>, Vec<u32>)> = batch.iter().map(|&i| &encoded[i]).collect();
+            for (&i, embedding) in batch.iter().zip(self.forward(&inputs)?) {
+                result[i] = embedding;
+            }
+        }
+
+        let tokens: usize = lengths.iter().sum();
+        let elapsed = start.elapsed();
+        let rate = tokens as f64 / elapsed.as_secs_f64().max(1e-6);
+        if texts.len() > 1 {
+            info!(
+                "Embedded {} texts ({} tokens, {} passes) in {:?}: {:.0} tokens/s",
+                texts.len(),
+                tokens,
+                batches.len(),
+                elapsed,
+                rate
+            );
+        } else {
+            debug!("Embedded 1 text ({} tokens) in {:?}", tokens, elapsed);
+        }
+        Ok(result)
+    }
+
+    /// One forward pass over tokenized texts, padded to the longest
+    fn forward(&self, inputs: &[&(Vec<u32>, Vec<u32>)]) -> Result<Vec<Vec<f32>>> {
+        let max_len = inputs.iter().map(|(ids, _)| ids.len()).max().unwrap_or(0);
+        let mut all_input_ids = Vec::with_capacity(inputs.len());
+        let mut all_attention_masks = Vec::with_capacity(inputs.len());
+        for (ids, mask) in inputs {
+            all_input_ids.push(ids.clone());
+            all_attention_masks.push(mask.clone());
         }
 
         // Pad all sequences to max_len
@@ -260,7 +488,7 @@ impl EmbeddingEngine {
             }
         }
 
-        let batch_size = texts.len();
+        let batch_size = inputs.len();
         let input_ids_flat: Vec<u32> = all_input_ids.into_iter().flatten().collect();
         let attention_mask_flat: Vec<u32> = all_attention_masks.into_iter().flatten().collect();
 
@@ -284,9 +512,9 @@ impl EmbeddingEngine {
                     })?;
 
             // Run model
-            let model = self.model.read();
+            let model = self.loaded_model()?;
             let output = model
-                .forward(&input_ids, &token_type_ids, Some(&attention_mask))
+                .forward(&input_ids, &token_type_ids, &attention_mask)
                 .map_err(|e| Error::Embedding(format!("Model forward pass failed: {}", e)))?;
 
             // Mean pooling over sequence dimension
@@ -307,22 +535,14 @@ impl EmbeddingEngine {
         // Sync GPU and release buffers AFTER tensors are dropped
         self.sync();
 
-        // CPU-only work from here
+        // CPU-only work from here. Vectors keep the model's own width; a
+        // table built for another model is migrated rather than padded.
         let dim = self.model_type.dimension();
         let result: Vec<Vec<f32>> = embeddings_vec
             .chunks(dim)
-            .map(|chunk| {
-                let mut v = chunk.to_vec();
-                // Pad to EMBEDDING_DIMENSION if needed
-                while v.len() < EMBEDDING_DIMENSION {
-                    v.push(0.0);
-                }
-                v.truncate(EMBEDDING_DIMENSION);
-                v
-            })
+            .map(|chunk| chunk.to_vec())
             .collect();
 
-        debug!("Generated {} embeddings", result.len());
         Ok(result)
     }
 
@@ -403,428 +623,26 @@ impl EmbeddingEngine {
     }
 }
 
-// ============================================================================
-// Remote Embedding Client
-// ============================================================================
-
-/// Request body for the custom remote /embed endpoint
-#[derive(Debug, Serialize)]
-struct RemoteEmbedRequest {
-    texts: Vec<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    model: Option<String>,
-}
-
-/// Response body from the custom remote /embed endpoint
-#[derive(Debug, Deserialize)]
-struct RemoteEmbedResponse {
-    embeddings: Vec<Vec<f32>>,
-    model: String,
-    dimension: usize,
-    count: usize,
-}
-
-/// Request body for OpenRouter /embeddings endpoint
-#[derive(Debug, Serialize)]
-struct OpenRouterEmbedRequest {
-    model: String,
-    input: Vec<String>,
-    encoding_format: String,
-}
-
-/// Response body from OpenRouter /embeddings endpoint
-#[derive(Debug, Deserialize)]
-struct OpenRouterEmbedResponse {
-    data: Vec<OpenRouterEmbedItem>,
-}
-
-#[derive(Debug, Deserialize)]
-struct OpenRouterErrorResponse {
-    error: OpenRouterErrorBody,
-}
-
-#[derive(Debug, Deserialize)]
-struct OpenRouterErrorBody {
-    message: String,
-    #[serde(default)]
-    code: Option<serde_json::Value>,
-}
-
-#[derive(Debug, Deserialize)]
-struct OpenRouterEmbedItem {
-    embedding: Vec<f32>,
-    index: usize,
-}
-
-const OPENROUTER_MAX_ATTEMPTS: usize = 2;
-const OPENROUTER_LOG_PREVIEW_CHARS: usize = 400;
-const OPENROUTER_MAX_INPUT_CHARS: usize = 12_000;
-
-#[derive(Debug)]
-enum RemoteEmbeddingKind {
-    DawnCompatible { model: String },
-    OpenRouter { api_key: String, model: String },
-}
-
-/// Client for remote embedding service (custom /embed or OpenRouter)
-pub struct RemoteEmbeddingClient {
-    client: reqwest::Client,
-    url: String,
-    kind: RemoteEmbeddingKind,
-}
-
-impl RemoteEmbeddingClient {
-    /// Create a new remote embedding client for custom /embed APIs.
-    pub fn new(url: String, timeout_ms: u64) -> Result<Self> {
-        let timeout = Duration::from_millis(timeout_ms);
-        let client = reqwest::Client::builder()
-            .timeout(timeout)
-            .build()
-            .map_err(|e| Error::Embedding(format!("Failed to create HTTP client: {}", e)))?;
-
-        info!("Created remote embedding client for {}", url);
-        Ok(Self {
-            client,
-            url,
-            kind: RemoteEmbeddingKind::DawnCompatible {
-                model: "bge-base-en-v1.5".to_string(),
-            },
-        })
-    }
-
-    /// Create a new remote embedding client for OpenRouter.
-    pub fn new_openrouter(
-        url: String,
-        api_key: String,
-        model: String,
-        timeout_ms: u64,
-    ) -> Result<Self> {
-        let timeout = Duration::from_millis(timeout_ms);
-        let client = reqwest::Client::builder()
-            .timeout(timeout)
-            .build()
-            .map_err(|e| Error::Embedding(format!("Failed to create HTTP client: {}", e)))?;
-
-        info!("Created OpenRouter embedding client for {}", url);
-        Ok(Self {
-            client,
-            url,
-            kind: RemoteEmbeddingKind::OpenRouter { api_key, model },
-        })
-    }
-
-    /// Generate embeddings for a batch of texts using the remote service
-    pub async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
-        if texts.is_empty() {
-            return Ok(vec![]);
-        }
-
-        debug!(
-            "Requesting embeddings for {} texts from {}",
-            texts.len(),
-            self.url
-        );
-
-        match &self.kind {
-            RemoteEmbeddingKind::DawnCompatible { model } => {
-                let request = RemoteEmbedRequest {
-                    texts: texts.to_vec(),
-                    model: Some(model.clone()),
-                };
-
-                let response = self
-                    .client
-                    .post(format!("{}/embed", self.url.trim_end_matches('/')))
-                    .json(&request)
-                    .send()
-                    .await
-                    .map_err(|e| {
-                        Error::Embedding(format!("Remote embedding request failed: {}", e))
-                    })?;
-
-                if !response.status().is_success() {
-                    let status = response.status();
-                    let body = response.text().await.unwrap_or_default();
-                    return Err(Error::Embedding(format!(
-                        "Remote embedding service returned {}: {}",
-                        status, body
-                    )));
-                }
-
-                let result: RemoteEmbedResponse = response.json().await.map_err(|e| {
-                    Error::Embedding(format!("Failed to parse embedding response: {}", e))
-                })?;
-
-                debug!(
-                    "Received {} embeddings (dimension: {}, model: {}) from remote service",
-                    result.count, result.dimension, result.model
-                );
-
-                Ok(Self::fit_embeddings(result.embeddings))
-            }
-            RemoteEmbeddingKind::OpenRouter { api_key, model } => {
-                self.embed_openrouter_batch(api_key, model, texts).await
-            }
+/// Group text indices into forward passes of at most `max_tokens` padded
+/// tokens, shortest texts first so texts of similar length share a pass
+fn plan_batches(lengths: &[usize], max_tokens: usize) -> Vec<Vec<usize>> {
+    let mut order: Vec<usize> = (0..lengths.len()).collect();
+    order.sort_by_key(|&i| lengths[i]);
+
+    let mut batches: Vec<Vec<usize>> = Vec::new();
+    let mut current: Vec<usize> = Vec::new();
+    for i in order {
+        // Sorted ascending, so this text is the longest in the pass
+        let padded = (current.len() + 1) * lengths[i].max(1);
+        if !current.is_empty() && padded > max_tokens {
+            batches.push(std::mem::take(&mut current));
         }
+        current.push(i);
     }
-
-    /// Generate embedding for a single text
-    pub async fn embed(&self, text: &str) -> Result<Vec<f32>> {
-        let embeddings = self.embed_batch(&[text.to_string()]).await?;
-        Ok(embeddings.into_iter().next().unwrap_or_default())
-    }
-
-    /// Check if the remote service is available
-    pub async fn is_available(&self) -> bool {
-        match &self.kind {
-            RemoteEmbeddingKind::DawnCompatible { .. } => {
-                match self
-                    .client
-                    .get(format!("{}/health", self.url.trim_end_matches('/')))
-                    .send()
-                    .await
-                {
-                    Ok(resp) => resp.status().is_success(),
-                    Err(_) => false,
-                }
-            }
-            // OpenRouter has no lightweight unauthenticated health check.
-            RemoteEmbeddingKind::OpenRouter { .. } => true,
-        }
-    }
-
-    fn fit_embeddings(embeddings: Vec<Vec<f32>>) -> Vec<Vec<f32>> {
-        embeddings
-            .into_iter()
-            .map(|mut e| {
-                // Pad or truncate to EMBEDDING_DIMENSION
-                while e.len() < EMBEDDING_DIMENSION {
-                    e.push(0.0);
-                }
-                e.truncate(EMBEDDING_DIMENSION);
-                e
-            })
-            .collect()
-    }
-
-    fn truncate_for_log(value: &str) -> String {
-        let mut chars = value.chars();
-        let preview: String = chars.by_ref().take(OPENROUTER_LOG_PREVIEW_CHARS).collect();
-        if chars.next().is_some() {
-            format!("{}...", preview)
-        } else {
-            preview
-        }
-    }
-
-    fn parse_openrouter_embeddings(body: &[u8]) -> Result<Vec<Vec<f32>>> {
-        if let Ok(mut result) = serde_json::from_slice::<OpenRouterEmbedResponse>(body) {
-            // Keep the same order as input.
-            result.data.sort_by_key(|item| item.index);
-            return Ok(result.data.into_iter().map(|item| item.embedding).collect());
-        }
-
-        if let Ok(err) = serde_json::from_slice::<OpenRouterErrorResponse>(body) {
-            let code = err
-                .error
-                .code
-                .as_ref()
-                .map(std::string::ToString::to_string)
-                .unwrap_or_else(|| "unknown".to_string());
-            return Err(Error::Embedding(format!(
-                "OpenRouter error (code={}): {}",
-                code, err.error.message
-            )));
-        }
-
-        let parse_error = serde_json::from_slice::<OpenRouterEmbedResponse>(body).unwrap_err();
-        let preview = Self::truncate_for_log(&String::from_utf8_lossy(body));
-        Err(Error::Embedding(format!(
-            "Failed to parse OpenRouter response JSON: {}. Body preview: {}",
-            parse_error, preview
-        )))
-    }
-
-    fn sanitize_openrouter_inputs(texts: &[String]) -> (Vec<String>, usize) {
-        let mut truncated = 0usize;
-        let sanitized = texts
-            .iter()
-            .map(|text| {
-                let char_count = text.chars().count();
-                if char_count > OPENROUTER_MAX_INPUT_CHARS {
-                    truncated += 1;
-                    text.chars()
-                        .take(OPENROUTER_MAX_INPUT_CHARS)
-                        .collect::<String>()
-                } else {
-                    text.clone()
-                }
-            })
-            .collect::<Vec<_>>();
-
-        (sanitized, truncated)
-    }
-
-    async fn request_openrouter_embeddings(
-        &self,
-        api_key: &str,
-        model: &str,
-        texts: &[String],
-    ) -> Result<Vec<Vec<f32>>> {
-        let request = OpenRouterEmbedRequest {
-            model: model.to_string(),
-            input: texts.to_vec(),
-            encoding_format: "float".to_string(),
-        };
-        let endpoint = format!("{}/embeddings", self.url.trim_end_matches('/'));
-        let mut last_error = None;
-
-        for attempt in 1..=OPENROUTER_MAX_ATTEMPTS {
-            let response = match self
-                .client
-                .post(&endpoint)
-                .header(reqwest::header::ACCEPT_ENCODING, "identity")
-                .bearer_auth(api_key)
-                .json(&request)
-                .send()
-                .await
-            {
-                Ok(resp) => resp,
-                Err(e) => {
-                    let msg = format!("OpenRouter embedding request failed: {}", e);
-                    if attempt < OPENROUTER_MAX_ATTEMPTS {
-                        warn!("{} (attempt {}/{})", msg, attempt, OPENROUTER_MAX_ATTEMPTS);
-                        last_error = Some(msg);
-                        continue;
-                    }
-                    return Err(Error::Embedding(msg));
-                }
-            };
-
-            let status = response.status();
-            let content_encoding = response
-                .headers()
-                .get(reqwest::header::CONTENT_ENCODING)
-                .and_then(|v| v.to_str().ok())
-                .unwrap_or("identity")
-                .to_string();
-
-            let body = match response.bytes().await {
-                Ok(bytes) => bytes,
-                Err(e) => {
-                    let msg = format!(
-                        "Failed to decode OpenRouter response body (content-encoding={}): {}",
-                        content_encoding, e
-                    );
-                    if attempt < OPENROUTER_MAX_ATTEMPTS {
-                        warn!("{} (attempt {}/{})", msg, attempt, OPENROUTER_MAX_ATTEMPTS);
-                        last_error = Some(msg);
-                        continue;
-                    }
-                    return Err(Error::Embedding(msg));
-                }
-            };
-
-            if !status.is_success() {
-                let body_preview = Self::truncate_for_log(&String::from_utf8_lossy(&body));
-                return Err(Error::Embedding(format!(
-                    "OpenRouter returned {}: {}",
-                    status, body_preview
-                )));
-            }
-
-            let embeddings = match Self::parse_openrouter_embeddings(&body) {
-                Ok(embeddings) => embeddings,
-                Err(e) => {
-                    let msg = e.to_string();
-                    if attempt < OPENROUTER_MAX_ATTEMPTS {
-                        warn!("{} (attempt {}/{})", msg, attempt, OPENROUTER_MAX_ATTEMPTS);
-                        last_error = Some(msg);
-                        continue;
-                    }
-                    return Err(e);
-                }
-            };
-
-            if embeddings.len() != texts.len() {
-                let msg = format!(
-                    "OpenRouter returned {} embeddings for {} inputs",
-                    embeddings.len(),
-                    texts.len()
-                );
-                if attempt < OPENROUTER_MAX_ATTEMPTS {
-                    warn!("{} (attempt {}/{})", msg, attempt, OPENROUTER_MAX_ATTEMPTS);
-                    last_error = Some(msg);
-                    continue;
-                }
-                return Err(Error::Embedding(msg));
-            }
-
-            return Ok(embeddings);
-        }
-
-        Err(Error::Embedding(last_error.unwrap_or_else(|| {
-            "OpenRouter embedding request failed".to_string()
-        })))
-    }
-
-    async fn embed_openrouter_batch(
-        &self,
-        api_key: &str,
-        model: &str,
-        texts: &[String],
-    ) -> Result<Vec<Vec<f32>>> {
-        let mut output = Vec::with_capacity(texts.len());
-        let mut pending = vec![texts.to_vec()];
-
-        while let Some(chunk) = pending.pop() {
-            let (sanitized, truncated) = Self::sanitize_openrouter_inputs(&chunk);
-            if truncated > 0 {
-                warn!(
-                    "Truncated {} OpenRouter embedding inputs to {} chars",
-                    truncated, OPENROUTER_MAX_INPUT_CHARS
-                );
-            }
-
-            match self
-                .request_openrouter_embeddings(api_key, model, &sanitized)
-                .await
-            {
-                Ok(embeddings) => {
-                    debug!(
-                        "Received {} embeddings from OpenRouter model {}",
-                        embeddings.len(),
-                        model
-                    );
-                    output.extend(Self::fit_embeddings(embeddings));
-                }
-                Err(err) => {
-                    if chunk.len() > 1 {
-                        let mid = chunk.len() / 2;
-                        warn!(
-                            "OpenRouter batch of {} failed ({}). Splitting into {} + {}",
-                            chunk.len(),
-                            err,
-                            mid,
-                            chunk.len() - mid
-                        );
-
-                        // LIFO stack: push right then left so left is processed first.
-                        pending.push(chunk[mid..].to_vec());
-                        pending.push(chunk[..mid].to_vec());
-                    } else {
-                        warn!(
-                            "OpenRouter failed for a single input ({}). Using zero-vector fallback for this item",
-                            err
-                        );
-                        output.push(vec![0.0; EMBEDDING_DIMENSION]);
-                    }
-                }
-            }
-        }
-
-        Ok(output)
+    if !current.is_empty() {
+        batches.push(current);
     }
+    batches
 }
 
 // ============================================================================
@@ -833,9 +651,15 @@ impl RemoteEmbeddingClient {
 
 /// Hybrid embedding provider that uses remote service with local fallback
 pub struct HybridEmbeddingProvider {
+    #[cfg(not(feature = "no-remote-embedding"))]
     remote: Option<RemoteEmbeddingClient>,
     local: Option<Arc<EmbeddingEngine>>,
     fallback: EmbeddingFallback,
+    /// Texts and wait time for merging local requests (None = call the engine directly)
+    batching: Option<(usize, Duration)>,
+    batcher: OnceLock<EmbeddingBatcher>,
+    /// Last embed or `touch` (for idle resource shedding)
+    last_used: Mutex<Instant>,
 }
 
 impl HybridEmbeddingProvider {
@@ -848,20 +672,22 @@ impl HybridEmbeddingProvider {
         timeout_ms: u64,
         fallback: EmbeddingFallback,
     ) -> Result<Self> {
-        let remote = if let Some(url) = remote_url {
-            match RemoteEmbeddingClient::new(url.clone(), timeout_ms) {
-                Ok(client) => {
-                    info!("Remote embedding service configured at {}", url);
-                    Some(client)
-                }
-                Err(e) => {
-                    warn!("Failed to create remote embedding client: {}", e);
-                    None
-                }
-            }
-        } else {
-            None
-        };
+        #[cfg(feature = "no-remote-embedding")]
+        if remote_url.is_some() {
+            return Err(remote_embedding_compiled_out());
+        }
+        #[cfg(feature = "no-remote-embedding")]
+        let _ = timeout_ms;
+
+        #[cfg(not(feature = "no-remote-embedding"))]
+        let remote = remote_url.and_then(|url| {
+            let search = SearchConfig {
+                embedding_url: Some(url),
+                embedding_timeout_ms: timeout_ms,
+                ..SearchConfig::default()
+            };
+            Self::remote_client(&search)
+        });
 
         // If no local engine and fallback is Local, change to Bm25
         let actual_fallback = if local.is_none() && fallback == EmbeddingFallback::Local {
@@ -872,74 +698,29 @@ impl HybridEmbeddingProvider {
         };
 
         Ok(Self {
+            #[cfg(not(feature = "no-remote-embedding"))]
             remote,
             local,
             fallback: actual_fallback,
+            batching: None,
+            batcher: OnceLock::new(),
+            last_used: Mutex::new(Instant::now()),
         })
     }
 
-    /// Create from search config. Supports local, remote, and OpenRouter providers.
+    /// Create from search config. Supports local, OpenAI, Ollama, OpenRouter and
+    /// custom remote providers.
     pub fn from_search_config(
         local: Option<Arc<EmbeddingEngine>>,
         search: &SearchConfig,
     ) -> Result<Self> {
-        let remote = match search.effective_embedding_provider() {
-            EmbeddingProvider::Local => None,
-            EmbeddingProvider::Remote => {
-                if let Some(url) = search.embedding_url.clone() {
-                    match RemoteEmbeddingClient::new(url.clone(), search.embedding_timeout_ms) {
-                        Ok(client) => {
-                            info!("Remote embedding service configured at {}", url);
-                            Some(client)
-                        }
-                        Err(e) => {
-                            warn!("Failed to create remote embedding client: {}", e);
-                            None
-                        }
-                    }
-                } else {
-                    warn!("embedding_provider is 'remote' but search.embedding_url is unset; using fallback only");
-                    None
-                }
-            }
-            EmbeddingProvider::OpenRouter => {
-                let env_name = search.openrouter_api_key_env.trim();
-                if env_name.is_empty() {
-                    warn!("search.openrouter_api_key_env is empty; using fallback only");
-                    None
-                } else {
-                    match std::env::var(env_name) {
-                        Ok(api_key) if !api_key.trim().is_empty() => {
-                            match RemoteEmbeddingClient::new_openrouter(
-                                search.openrouter_base_url.clone(),
-                                api_key,
-                                search.openrouter_model.clone(),
-                                search.embedding_timeout_ms,
-                            ) {
-                                Ok(client) => {
-                                    info!(
-                                        "OpenRouter embedding configured with model {}",
-                                        search.openrouter_model
-                                    );
-                                    Some(client)
-                                }
-                                Err(e) => {
-                                    warn!("Failed to create OpenRouter embedding client: {}", e);
-                                    None
-                                }
-                            }
-                        }
-                        _ => {
-                            warn!(
-                                "OpenRouter provider selected but API key env '{}' is missing/empty; using fallback only",
-                                env_name
-                            );
-                            None
-                        }
-                    }
-                }
-            }
-        };
+        #[cfg(feature = "no-remote-embedding")]
+        if search.effective_embedding_provider() != EmbeddingProvider::Local {
+            return Err(remote_embedding_compiled_out());
+        }
+
+        #[cfg(not(feature = "no-remote-embedding"))]
+        let remote = Self::remote_client(search);
 
         // If no local engine and fallback is Local, change to Bm25
         let actual_fallback =
@@ -950,13 +731,79 @@ impl HybridEmbeddingProvider {
                 search.embedding_fallback
             };
 
+        if let Some(local) = &local {
+            local.set_max_batch_tokens(search.embedding_max_batch_tokens);
+        }
+
         Ok(Self {
+            #[cfg(not(feature = "no-remote-embedding"))]
             remote,
             local,
             fallback: actual_fallback,
+            batching: Some((
+                search.effective_embedding_batch_size(),
+                Duration::from_millis(search.embedding_batch_wait_ms),
+            )),
+            batcher: OnceLock::new(),
+            last_used: Mutex::new(Instant::now()),
         })
     }
 
+    /// Client for the configured remote provider; None (with a warning) when
+    /// it is unset or can't be set up, so embedding uses the fallback
+    #[cfg(not(feature = "no-remote-embedding"))]
+    fn remote_client(search: &SearchConfig) -> Option<RemoteEmbeddingClient> {
+        let Some(endpoint) = search.embedding_endpoint() else {
+            if search.effective_embedding_provider() == EmbeddingProvider::Remote {
+                warn!("embedding_provider is 'remote' but search.embedding_url is unset; using fallback only");
+            }
+            return None;
+        };
+        match RemoteEmbeddingClient::new(&endpoint) {
+            Ok(client) => {
+                info!(
+                    "{} embedding configured at {} with model {}",
+                    client.provider(),
+                    endpoint.url,
+                    endpoint.model
+                );
+                Some(client)
+            }
+            Err(e) => {
+                warn!("{}; using fallback only", e);
+                None
+            }
+        }
+    }
+
+    /// Check remote vectors against the stored embedding column's width
+    /// (see [`crate::db::Database::vector_dimension`])
+    pub fn with_vector_dimension(self, dimension: usize) -> Self {
+        #[cfg(not(feature = "no-remote-embedding"))]
+        return Self {
+            remote: self.remote.map(|client| client.with_dimension(dimension)),
+            ..self
+        };
+        #[cfg(feature = "no-remote-embedding")]
+        {
+            let _ = dimension;
+            self
+        }
+    }
+
+    /// Queue merging concurrent requests to the local engine, started on first use
+    fn local_batcher(&self, local: &Arc<EmbeddingEngine>) -> Option<&EmbeddingBatcher> {
+        let (max_texts, max_wait) = self.batching?;
+        Some(self.batcher.get_or_init(|| {
+            let engine = local.clone();
+            EmbeddingBatcher::new(
+                Arc::new(move |texts: &[String]| engine.embed_batch(texts)),
+                max_texts,
+                max_wait,
+            )
+        }))
+    }
+
     /// Generate embeddings for a batch of texts
     ///
     /// If remote service is configured and available, uses remote.
@@ -965,12 +812,16 @@ impl HybridEmbeddingProvider {
         if texts.is_empty() {
             return Ok(Some(vec![]));
         }
+        self.touch();
+        let started = Instant::now();
 
         // Try remote first if configured
+        #[cfg(not(feature = "no-remote-embedding"))]
         if let Some(ref remote) = self.remote {
             match remote.embed_batch(texts).await {
                 Ok(embeddings) => {
                     debug!("Used remote embedding service for {} texts", texts.len());
+                    metrics::record_embedded(texts.len(), started.elapsed());
                     return Ok(Some(embeddings));
                 }
                 Err(e) => {
@@ -984,7 +835,11 @@ impl HybridEmbeddingProvider {
             EmbeddingFallback::Local => {
                 if let Some(ref local) = self.local {
                     debug!("Falling back to local embedding for {} texts", texts.len());
-                    let embeddings = local.embed_batch(texts)?;
+                    let embeddings = match self.local_batcher(local) {
+                        Some(batcher) => batcher.embed_batch(texts.to_vec()).await?,
+                        None => local.embed_batch(texts)?,
+                    };
+                    metrics::record_embedded(texts.len(), started.elapsed());
                     Ok(Some(embeddings))
                 } else {
                     debug!("No local engine, falling back to BM25-only");
@@ -1012,11 +867,31 @@ impl HybridEmbeddingProvider {
 
     /// Check if remote service is available
     pub async fn is_remote_available(&self) -> bool {
+        #[cfg(not(feature = "no-remote-embedding"))]
         if let Some(remote) = &self.remote {
-            remote.is_available().await
-        } else {
-            false
+            return remote.is_available().await;
+        }
+        false
+    }
+
+    /// Embed one text with the remote provider only, skipping the fallback
+    /// (for `doctor`); None when no remote provider is set up
+    pub async fn probe_remote(&self) -> Option<Result<usize>> {
+        #[cfg(not(feature = "no-remote-embedding"))]
+        if let Some(remote) = &self.remote {
+            let probe = remote.embed("groundeffect embedding check").await;
+            return Some(probe.map(|embedding| embedding.len()));
         }
+        None
+    }
+
+    /// Whether any provider can compute embeddings (false in BM25-only setups)
+    pub fn can_embed(&self) -> bool {
+        #[cfg(not(feature = "no-remote-embedding"))]
+        if self.remote.is_some() {
+            return true;
+        }
+        self.local.is_some()
     }
 
     /// Get the embedding dimension (768 for bge-base-en-v1.5)
@@ -1030,114 +905,54 @@ impl HybridEmbeddingProvider {
     pub fn local_engine(&self) -> Option<&Arc<EmbeddingEngine>> {
         self.local.as_ref()
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::time::Duration;
-
-    use super::{RemoteEmbeddingClient, EMBEDDING_DIMENSION, OPENROUTER_MAX_INPUT_CHARS};
-
-    #[test]
-    fn parse_openrouter_embeddings_preserves_input_order_by_index() {
-        let payload = br#"{
-            "object":"list",
-            "data":[
-                {"embedding":[10.0,11.0],"index":1},
-                {"embedding":[20.0,21.0],"index":0}
-            ]
-        }"#;
-
-        let embeddings = RemoteEmbeddingClient::parse_openrouter_embeddings(payload).unwrap();
-        assert_eq!(embeddings.len(), 2);
-        assert_eq!(embeddings[0], vec![20.0, 21.0]);
-        assert_eq!(embeddings[1], vec![10.0, 11.0]);
-    }
-
-    #[test]
-    fn parse_openrouter_embeddings_returns_context_on_invalid_json() {
-        let payload = br#"{"data":"bad-shape"}"#;
-        let err = RemoteEmbeddingClient::parse_openrouter_embeddings(payload).unwrap_err();
-        let msg = err.to_string();
-
-        assert!(msg.contains("Failed to parse OpenRouter response JSON"));
-        assert!(msg.contains("Body preview"));
+    /// Record activity that didn't need an embedding (e.g. a cached search)
+    pub fn touch(&self) {
+        *self.last_used.lock() = Instant::now();
     }
 
-    #[test]
-    fn parse_openrouter_embeddings_surfaces_openrouter_error_payload() {
-        let payload = br#"{
-            "error": {
-                "message": "No successful provider responses.",
-                "code": 404
-            }
-        }"#;
-        let err = RemoteEmbeddingClient::parse_openrouter_embeddings(payload).unwrap_err();
-        let msg = err.to_string();
-
-        assert!(msg.contains("OpenRouter error"));
-        assert!(msg.contains("No successful provider responses."));
-        assert!(msg.contains("404"));
+    /// Time since texts were last embedded or `touch` was called
+    pub fn idle_for(&self) -> Duration {
+        self.last_used.lock().elapsed()
     }
 
-    #[test]
-    fn sanitize_openrouter_inputs_truncates_oversized_values() {
-        let long = "a".repeat(OPENROUTER_MAX_INPUT_CHARS + 5000);
-        let short = "short".to_string();
-        let inputs = vec![long, short.clone()];
-
-        let (sanitized, truncated) = RemoteEmbeddingClient::sanitize_openrouter_inputs(&inputs);
-        assert_eq!(truncated, 1);
-        assert_eq!(sanitized.len(), 2);
-        assert_eq!(sanitized[0].chars().count(), OPENROUTER_MAX_INPUT_CHARS);
-        assert_eq!(sanitized[1], short);
+    /// Unload the local model until the next embed; false if there was
+    /// nothing to unload
+    pub fn unload_local(&self) -> bool {
+        self.local.as_ref().is_some_and(|local| local.unload())
     }
+}
 
-    #[tokio::test]
-    #[ignore = "Live network smoke test; requires OPENROUTER_API_KEY"]
-    async fn openrouter_live_batch_1024_no_zero_vector_fallback() {
-        let api_key = std::env::var("OPENROUTER_API_KEY")
-            .expect("OPENROUTER_API_KEY must be set for live OpenRouter smoke test");
-        assert!(
-            !api_key.trim().is_empty(),
-            "OPENROUTER_API_KEY must not be empty"
-        );
-
-        let client = RemoteEmbeddingClient::new_openrouter(
-            "https://openrouter.ai/api/v1".to_string(),
-            api_key,
-            "openai/text-embedding-3-small".to_string(),
-            120_000,
-        )
-        .expect("failed to construct OpenRouter client");
-
-        let inputs: Vec<String> = (0..1024)
-            .map(|i| format!("groundeffect openrouter batch smoke {}", i))
-            .collect();
+/// Error for configs asking for a remote embedding provider in a build
+/// without one
+#[cfg(feature = "no-remote-embedding")]
+fn remote_embedding_compiled_out() -> Error {
+    Error::Config(
+        "This build has no remote embedding support (no-remote-embedding); \
+         set search.embedding_provider = \"local\""
+            .to_string(),
+    )
+}
 
-        let start = std::time::Instant::now();
-        let embeddings = client
-            .embed_batch(&inputs)
-            .await
-            .expect("OpenRouter 1024 batch embedding request failed");
-        eprintln!(
-            "openrouter_live_batch_1024_no_zero_vector_fallback elapsed={:?}",
-            start.elapsed()
-        );
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        assert_eq!(embeddings.len(), 1024);
-        for emb in &embeddings {
-            assert_eq!(emb.len(), EMBEDDING_DIMENSION);
-            assert!(
-                emb.iter().any(|v| *v != 0.0),
-                "detected all-zero fallback vector in live OpenRouter smoke test"
-            );
+    #[test]
+    fn test_plan_batches_respects_token_budget() {
+        let lengths = [10, 500, 12, 11, 480];
+        let batches = plan_batches(&lengths, 900);
+        // Short texts share a pass; the long ones can't fit two to a pass
+        assert_eq!(batches, vec![vec![0, 3, 2], vec![4], vec![1]]);
+        for batch in &batches {
+            let longest = batch.iter().map(|&i| lengths[i]).max().unwrap();
+            assert!(batch.len() == 1 || batch.len() * longest <= 900);
         }
+    }
 
-        // Keep this around as a hard guard against regressing to very slow fallback recursion.
-        assert!(
-            start.elapsed() < Duration::from_secs(180),
-            "1024 batch took unexpectedly long"
-        );
+    #[test]
+    fn test_plan_batches_oversized_text_gets_own_pass() {
+        assert_eq!(plan_batches(&[600, 5], 100), vec![vec![1], vec![0]]);
+        assert!(plan_batches(&[], 100).is_empty());
     }
 }
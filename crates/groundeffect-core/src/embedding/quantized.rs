@@ -0,0 +1,309 @@
+//! BERT encoder with int8 weights for low-memory machines
+//!
+//! Loads the same checkpoint as the full-precision model and quantizes every
+//! linear layer to 8 bits (GGML `Q8_0`) as it is read, so the resident model
+//! is about a quarter of the f32 size and matrix multiplies run on int8 dot
+//! products. Embedding tables and layer norms stay in f32. Runs on the CPU.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use candle_core::quantized::{GgmlDType, QMatMul, QTensor};
+use candle_core::safetensors::MmapedSafetensors;
+use candle_core::{DType, Device, Module, Tensor};
+use candle_nn::{Embedding, LayerNorm};
+use serde::Deserialize;
+
+use crate::error::{Error, Result};
+
+/// The parts of `config.json` the encoder needs
+#[derive(Debug, Clone, Deserialize)]
+struct BertShape {
+    hidden_size: usize,
+    num_hidden_layers: usize,
+    num_attention_heads: usize,
+    #[serde(default = "default_layer_norm_eps")]
+    layer_norm_eps: f64,
+}
+
+fn default_layer_norm_eps() -> f64 {
+    1e-12
+}
+
+/// Checkpoint tensors, read one at a time
+enum Weights {
+    Safetensors(MmapedSafetensors),
+    Pickle(HashMap<String, Tensor>),
+}
+
+impl Weights {
+    fn open(path: &Path) -> Result<Self> {
+        let load_error =
+            |e: candle_core::Error| Error::ModelLoading(format!("Failed to load weights: {}", e));
+        if path.extension().is_some_and(|e| e == "safetensors") {
+            let mmaped = unsafe { MmapedSafetensors::new(path) }.map_err(load_error)?;
+            Ok(Self::Safetensors(mmaped))
+        } else {
+            let tensors = candle_core::pickle::read_all(path).map_err(load_error)?;
+            Ok(Self::Pickle(tensors.into_iter().collect()))
+        }
+    }
+
+    fn contains(&self, name: &str) -> bool {
+        match self {
+            Self::Safetensors(st) => st.get(name).is_ok(),
+            Self::Pickle(tensors) => tensors.contains_key(name),
+        }
+    }
+
+    fn get(&self, name: &str) -> Result<Tensor> {
+        let tensor = match self {
+            Self::Safetensors(st) => st.load(name, &Device::Cpu).ok(),
+            Self::Pickle(tensors) => tensors.get(name).cloned(),
+        };
+        tensor
+            .ok_or_else(|| Error::ModelLoading(format!("Missing tensor {} in checkpoint", name)))?
+            .to_dtype(DType::F32)
+            .map_err(candle_error)
+    }
+}
+
+fn candle_error(e: candle_core::Error) -> Error {
+    Error::ModelLoading(e.to_string())
+}
+
+struct QLinear {
+    weight: QMatMul,
+    bias: Tensor,
+}
+
+impl QLinear {
+    fn load(weights: &Weights, prefix: &str) -> Result<Self> {
+        let weight = weights.get(&format!("{}.weight", prefix))?;
+        let weight = QTensor::quantize(&weight, GgmlDType::Q8_0).map_err(candle_error)?;
+        Ok(Self {
+            weight: QMatMul::from_qtensor(weight).map_err(candle_error)?,
+            bias: weights.get(&format!("{}.bias", prefix))?,
+        })
+    }
+
+    fn forward(&self, xs: &Tensor) -> candle_core::Result<Tensor> {
+        self.weight.forward(xs)?.broadcast_add(&self.bias)
+    }
+}
+
+fn layer_norm(weights: &Weights, prefix: &str, eps: f64) -> Result<LayerNorm> {
+    // Older checkpoints name the parameters gamma/beta
+    let (weight, bias) = if weights.contains(&format!("{}.weight", prefix)) {
+        ("weight", "bias")
+    } else {
+        ("gamma", "beta")
+    };
+    Ok(LayerNorm::new(
+        weights.get(&format!("{}.{}", prefix, weight))?,
+        weights.get(&format!("{}.{}", prefix, bias))?,
+        eps,
+    ))
+}
+
+struct Layer {
+    query: QLinear,
+    key: QLinear,
+    value: QLinear,
+    attention_output: QLinear,
+    attention_norm: LayerNorm,
+    intermediate: QLinear,
+    output: QLinear,
+    output_norm: LayerNorm,
+}
+
+impl Layer {
+    fn load(weights: &Weights, prefix: &str, eps: f64) -> Result<Self> {
+        Ok(Self {
+            query: QLinear::load(weights, &format!("{}.attention.self.query", prefix))?,
+            key: QLinear::load(weights, &format!("{}.attention.self.key", prefix))?,
+            value: QLinear::load(weights, &format!("{}.attention.self.value", prefix))?,
+            attention_output: QLinear::load(
+                weights,
+                &format!("{}.attention.output.dense", prefix),
+            )?,
+            attention_norm: layer_norm(
+                weights,
+                &format!("{}.attention.output.LayerNorm", prefix),
+                eps,
+            )?,
+            intermediate: QLinear::load(weights, &format!("{}.intermediate.dense", prefix))?,
+            output: QLinear::load(weights, &format!("{}.output.dense", prefix))?,
+            output_norm: layer_norm(weights, &format!("{}.output.LayerNorm", prefix), eps)?,
+        })
+    }
+
+    fn forward(
+        &self,
+        hidden: &Tensor,
+        mask_bias: &Tensor,
+        heads: usize,
+    ) -> candle_core::Result<Tensor> {
+        let (batch, len, width) = hidden.dims3()?;
+        let head_dim = width / heads;
+        let split_heads = |xs: Tensor| -> candle_core::Result<Tensor> {
+            xs.reshape((batch, len, heads, head_dim))?
+                .transpose(1, 2)?
+                .contiguous()
+        };
+
+        let query = split_heads(self.query.forward(hidden)?)?;
+        let key = split_heads(self.key.forward(hidden)?)?;
+        let value = split_heads(self.value.forward(hidden)?)?;
+
+        let scores = (query.matmul(&key.t()?)? / (head_dim as f64).sqrt())?;
+        let probs = candle_nn::ops::softmax_last_dim(&scores.broadcast_add(mask_bias)?)?;
+        let context = probs
+            .matmul(&value)?
+            .transpose(1, 2)?
+            .reshape((batch, len, width))?;
+
+        let attended = self
+            .attention_norm
+            .forward(&(self.attention_output.forward(&context)? + hidden)?)?;
+        let intermediate = self.intermediate.forward(&attended)?.gelu_erf()?;
+        self.output_norm
+            .forward(&(self.output.forward(&intermediate)? + attended)?)
+    }
+}
+
+/// BERT encoder whose linear layers hold int8 weights
+pub(crate) struct QuantizedBert {
+    word_embeddings: Embedding,
+    position_embeddings: Embedding,
+    token_type_embeddings: Embedding,
+    embedding_norm: LayerNorm,
+    layers: Vec<Layer>,
+    heads: usize,
+}
+
+impl QuantizedBert {
+    /// Read and quantize a BERT checkpoint (`.safetensors` or `.bin`)
+    pub(crate) fn load(weights_path: &Path, config_json: &str) -> Result<Self> {
+        let shape: BertShape = serde_json::from_str(config_json)?;
+        let weights = Weights::open(weights_path)?;
+
+        // Some checkpoints keep the encoder under a "bert." prefix
+        let root = if weights.contains("bert.embeddings.word_embeddings.weight") {
+            "bert."
+        } else {
+            ""
+        };
+        let embedding = |name: &str| -> Result<Embedding> {
+            Ok(Embedding::new(
+                weights.get(&format!("{}embeddings.{}.weight", root, name))?,
+                shape.hidden_size,
+            ))
+        };
+
+        let layers = (0..shape.num_hidden_layers)
+            .map(|i| {
+                Layer::load(
+                    &weights,
+                    &format!("{}encoder.layer.{}", root, i),
+                    shape.layer_norm_eps,
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            word_embeddings: embedding("word_embeddings")?,
+            position_embeddings: embedding("position_embeddings")?,
+            token_type_embeddings: embedding("token_type_embeddings")?,
+            embedding_norm: layer_norm(
+                &weights,
+                &format!("{}embeddings.LayerNorm", root),
+                shape.layer_norm_eps,
+            )?,
+            layers,
+            heads: shape.num_attention_heads,
+        })
+    }
+
+    /// Hidden states for a padded batch, shaped like `BertModel::forward`
+    pub(crate) fn forward(
+        &self,
+        input_ids: &Tensor,
+        token_type_ids: &Tensor,
+        attention_mask: &Tensor,
+    ) -> candle_core::Result<Tensor> {
+        let (_, len) = input_ids.dims2()?;
+        let positions = Tensor::arange(0u32, len as u32, input_ids.device())?;
+        let embeddings = self
+            .word_embeddings
+            .forward(input_ids)?
+            .broadcast_add(&self.position_embeddings.forward(&positions)?)?
+            .add(&self.token_type_embeddings.forward(token_type_ids)?)?;
+        let mut hidden = self.embedding_norm.forward(&embeddings)?;
+
+        // 0 for real tokens, a large negative number for padding
+        let mask_bias = attention_mask
+            .to_dtype(DType::F32)?
+            .affine(10_000.0, -10_000.0)?
+            .unsqueeze(1)?
+            .unsqueeze(1)?;
+        for layer in &self.layers {
+            hidden = layer.forward(&hidden, &mask_bias, self.heads)?;
+        }
+        Ok(hidden)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candle_core::D;
+
+    #[test]
+    fn test_shape_from_bert_config() {
+        let shape: BertShape = serde_json::from_str(
+            r#"{"hidden_size": 768, "num_hidden_layers": 12, "num_attention_heads": 12,
+                "intermediate_size": 3072, "hidden_act": "gelu"}"#,
+        )
+        .unwrap();
+        assert_eq!(shape.hidden_size / shape.num_attention_heads, 64);
+        assert_eq!(shape.layer_norm_eps, 1e-12);
+    }
+
+    #[test]
+    fn test_quantized_linear_matches_f32() {
+        // Q8_0 quantizes in blocks of 32 along the input dimension
+        let weight = Tensor::arange(0f32, 64.0 * 4.0, &Device::Cpu)
+            .unwrap()
+            .affine(0.01, -1.0)
+            .unwrap()
+            .reshape((4, 64))
+            .unwrap();
+        let bias = Tensor::new(&[0.5f32, -0.5, 0.0, 1.0], &Device::Cpu).unwrap();
+        let input = Tensor::ones((1, 2, 64), DType::F32, &Device::Cpu).unwrap();
+
+        let exact = input
+            .broadcast_matmul(&weight.t().unwrap())
+            .unwrap()
+            .broadcast_add(&bias)
+            .unwrap();
+        let linear = QLinear {
+            weight: QMatMul::from_qtensor(QTensor::quantize(&weight, GgmlDType::Q8_0).unwrap())
+                .unwrap(),
+            bias,
+        };
+        let quantized = linear.forward(&input).unwrap();
+
+        let error = (exact - quantized)
+            .unwrap()
+            .abs()
+            .unwrap()
+            .max_keepdim(D::Minus1)
+            .unwrap()
+            .flatten_all()
+            .unwrap()
+            .to_vec1::<f32>()
+            .unwrap();
+        assert!(error.iter().all(|e| *e < 0.1), "error {:?}", error);
+    }
+}
@@ -0,0 +1,87 @@
+//! Custom `/embed` service (the original remote embedding API)
+//!
+//! `POST {url}/embed` with `{"texts": [...], "model": "..."}` answers
+//! `{"embeddings": [[...]], "model": "...", "dimension": N, "count": N}`;
+//! `GET {url}/health` answers 200 when the service is up.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use super::{post_json, truncate_for_log, EmbeddingAdapter, RemoteFailure};
+
+/// Request body for the custom remote /embed endpoint
+#[derive(Debug, Serialize)]
+struct RemoteEmbedRequest<'a> {
+    texts: &'a [String],
+    model: &'a str,
+}
+
+/// Response body from the custom remote /embed endpoint
+#[derive(Debug, Deserialize)]
+struct RemoteEmbedResponse {
+    embeddings: Vec<Vec<f32>>,
+    #[serde(default)]
+    model: String,
+    #[serde(default)]
+    dimension: usize,
+}
+
+/// Adapter for a custom `/embed` service
+pub struct CustomEmbeddingAdapter {
+    url: String,
+    model: String,
+}
+
+impl CustomEmbeddingAdapter {
+    pub fn new(url: String, model: String) -> Self {
+        Self { url, model }
+    }
+}
+
+#[async_trait]
+impl EmbeddingAdapter for CustomEmbeddingAdapter {
+    fn name(&self) -> &'static str {
+        "Remote embedding service"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn embed(
+        &self,
+        http: &reqwest::Client,
+        texts: &[String],
+        _dimension: usize,
+    ) -> Result<Vec<Vec<f32>>, RemoteFailure> {
+        let request = RemoteEmbedRequest {
+            texts,
+            model: &self.model,
+        };
+        let endpoint = format!("{}/embed", self.url);
+        let body = post_json(http, self.name(), &endpoint, &request).await?;
+
+        let result: RemoteEmbedResponse = serde_json::from_slice(&body).map_err(|e| {
+            RemoteFailure::Transient(format!(
+                "Failed to parse embedding response: {}. Body preview: {}",
+                e,
+                truncate_for_log(&String::from_utf8_lossy(&body))
+            ))
+        })?;
+        debug!(
+            "Received {} embeddings (dimension: {}, model: {}) from remote service",
+            result.embeddings.len(),
+            result.dimension,
+            result.model
+        );
+        Ok(result.embeddings)
+    }
+
+    async fn is_available(&self, http: &reqwest::Client) -> bool {
+        match http.get(format!("{}/health", self.url)).send().await {
+            Ok(resp) => resp.status().is_success(),
+            Err(_) => false,
+        }
+    }
+}
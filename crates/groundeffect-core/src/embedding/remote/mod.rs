@@ -0,0 +1,664 @@
+//! Remote embedding providers
+//!
+//! Each provider is an [`EmbeddingAdapter`] that knows one API's request and
+//! response shapes. [`RemoteEmbeddingClient`] wraps an adapter with what they
+//! share: auth and extra headers, batching limits, retries, and a check that
+//! the vectors fit the stored embedding column.
+//!
+//! Compiled out by the `no-remote-embedding` feature, which guarantees email
+//! and event text never leaves the machine for embedding.
+//!
+//! # Configuration
+//!
+//! In `config.toml`:
+//!
+//! ```toml
+//! # OpenAI (key from $OPENAI_API_KEY)
+//! [search]
+//! embedding_provider = "openai"
+//!
+//! # Ollama on this machine or the network
+//! [search]
+//! embedding_provider = "ollama"
+//! [search.embedding_api]
+//! url = "http://gpu-box:11434"
+//! model = "nomic-embed-text"
+//!
+//! # Any OpenAI-compatible endpoint, e.g. Azure OpenAI
+//! [search]
+//! embedding_provider = "openai"
+//! [search.embedding_api]
+//! url = "https://example.openai.azure.com/openai/deployments/embed"
+//! api_key_env = "AZURE_OPENAI_KEY"
+//! auth_header = "api-key"
+//! headers = { "api-version" = "2024-02-01" }
+//! max_batch_size = 16
+//! ```
+
+mod custom;
+mod ollama;
+mod openai;
+
+pub use custom::CustomEmbeddingAdapter;
+pub use ollama::OllamaEmbeddingAdapter;
+pub use openai::OpenAiEmbeddingAdapter;
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::Serialize;
+use tracing::{debug, info, warn};
+
+use crate::config::{EmbeddingEndpoint, EmbeddingProvider};
+use crate::error::{Error, Result};
+use crate::EMBEDDING_DIMENSION;
+
+const LOG_PREVIEW_CHARS: usize = 400;
+
+/// Why a request to an embedding provider failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RemoteFailure {
+    /// Worth retrying: network errors, timeouts, 429 and 5xx responses
+    Transient(String),
+    /// The provider refused these inputs; smaller batches may get through
+    Rejected(String),
+    /// Retrying won't help: bad credentials, unknown model, wrong dimension
+    Fatal(String),
+}
+
+impl RemoteFailure {
+    fn message(&self) -> &str {
+        match self {
+            Self::Transient(msg) | Self::Rejected(msg) | Self::Fatal(msg) => msg,
+        }
+    }
+}
+
+impl From<RemoteFailure> for Error {
+    fn from(failure: RemoteFailure) -> Self {
+        Error::Embedding(failure.message().to_string())
+    }
+}
+
+/// One remote embedding API
+///
+/// Adapters only translate requests and responses; [`RemoteEmbeddingClient`]
+/// handles headers, batching, retries and dimension checks.
+#[async_trait]
+pub trait EmbeddingAdapter: Send + Sync {
+    /// Provider name for logs and errors
+    fn name(&self) -> &'static str;
+
+    /// Model the adapter asks for
+    fn model(&self) -> &str;
+
+    /// Embed one batch, in input order
+    ///
+    /// `dimension` is the width of the stored vector column, for APIs that
+    /// can shorten their output.
+    async fn embed(
+        &self,
+        http: &reqwest::Client,
+        texts: &[String],
+        dimension: usize,
+    ) -> std::result::Result<Vec<Vec<f32>>, RemoteFailure>;
+
+    /// Whether the service answers; providers without a cheap check say yes
+    async fn is_available(&self, _http: &reqwest::Client) -> bool {
+        true
+    }
+}
+
+/// POST `body` as JSON and return the response body, sorting failures into
+/// retryable, rejected and fatal
+pub(crate) async fn post_json<T: Serialize + ?Sized>(
+    http: &reqwest::Client,
+    provider: &str,
+    url: &str,
+    body: &T,
+) -> std::result::Result<Vec<u8>, RemoteFailure> {
+    let response = http.post(url).json(body).send().await.map_err(|e| {
+        RemoteFailure::Transient(format!("{} embedding request failed: {}", provider, e))
+    })?;
+
+    let status = response.status();
+    let body = response.bytes().await.map_err(|e| {
+        RemoteFailure::Transient(format!("Failed to read {} response body: {}", provider, e))
+    })?;
+    if status.is_success() {
+        return Ok(body.to_vec());
+    }
+
+    let msg = format!(
+        "{} returned {}: {}",
+        provider,
+        status,
+        truncate_for_log(&String::from_utf8_lossy(&body))
+    );
+    Err(match status.as_u16() {
+        408 | 429 | 500..=599 => RemoteFailure::Transient(msg),
+        401 | 403 | 404 => RemoteFailure::Fatal(msg),
+        _ => RemoteFailure::Rejected(msg),
+    })
+}
+
+pub(crate) fn truncate_for_log(value: &str) -> String {
+    let mut chars = value.chars();
+    let preview: String = chars.by_ref().take(LOG_PREVIEW_CHARS).collect();
+    if chars.next().is_some() {
+        format!("{}...", preview)
+    } else {
+        preview
+    }
+}
+
+/// Client for a remote embedding provider
+pub struct RemoteEmbeddingClient {
+    http: reqwest::Client,
+    adapter: Box<dyn EmbeddingAdapter>,
+    url: String,
+    max_batch_size: usize,
+    max_input_chars: usize,
+    max_attempts: u32,
+    retry_backoff: Duration,
+    /// Width of the stored vector column
+    dimension: usize,
+}
+
+impl RemoteEmbeddingClient {
+    /// Create a client from resolved settings, reading the API key from
+    /// `api_key_env` when one is configured
+    pub fn new(endpoint: &EmbeddingEndpoint) -> Result<Self> {
+        let api_key = match &endpoint.api_key_env {
+            Some(env_name) => match std::env::var(env_name) {
+                Ok(key) if !key.trim().is_empty() => Some(key.trim().to_string()),
+                _ => {
+                    return Err(Error::Config(format!(
+                        "{} embeddings need an API key in ${}",
+                        endpoint.provider.as_str(),
+                        env_name
+                    )))
+                }
+            },
+            None => None,
+        };
+        Self::with_api_key(endpoint, api_key)
+    }
+
+    /// Create a client from resolved settings and an explicit API key
+    pub fn with_api_key(endpoint: &EmbeddingEndpoint, api_key: Option<String>) -> Result<Self> {
+        let headers = Self::headers(endpoint, api_key.as_deref())?;
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_millis(endpoint.timeout_ms))
+            .default_headers(headers)
+            .build()
+            .map_err(|e| Error::Embedding(format!("Failed to create HTTP client: {}", e)))?;
+
+        let url = endpoint.url.trim_end_matches('/').to_string();
+        let model = endpoint.model.clone();
+        let adapter: Box<dyn EmbeddingAdapter> = match endpoint.provider {
+            EmbeddingProvider::Local => {
+                return Err(Error::Config(
+                    "The local provider has no remote embedding client".to_string(),
+                ))
+            }
+            EmbeddingProvider::Remote => Box::new(CustomEmbeddingAdapter::new(url.clone(), model)),
+            EmbeddingProvider::OpenAi => Box::new(OpenAiEmbeddingAdapter::new(
+                "OpenAI",
+                url.clone(),
+                model,
+                endpoint.send_dimensions,
+            )),
+            EmbeddingProvider::OpenRouter => Box::new(OpenAiEmbeddingAdapter::new(
+                "OpenRouter",
+                url.clone(),
+                model,
+                endpoint.send_dimensions,
+            )),
+            EmbeddingProvider::Ollama => Box::new(OllamaEmbeddingAdapter::new(url.clone(), model)),
+        };
+
+        info!(
+            "Created {} embedding client for {} (model {})",
+            adapter.name(),
+            url,
+            adapter.model()
+        );
+        Ok(Self {
+            http,
+            adapter,
+            url,
+            max_batch_size: endpoint.max_batch_size.max(1),
+            max_input_chars: endpoint.max_input_chars,
+            max_attempts: endpoint.max_attempts.max(1),
+            retry_backoff: Duration::from_millis(endpoint.retry_backoff_ms),
+            dimension: EMBEDDING_DIMENSION,
+        })
+    }
+
+    /// Auth and extra headers sent with every request
+    fn headers(endpoint: &EmbeddingEndpoint, api_key: Option<&str>) -> Result<HeaderMap> {
+        let invalid = |what: &str, e: &dyn std::fmt::Display| {
+            Error::Config(format!("Invalid embedding API header {}: {}", what, e))
+        };
+        let mut headers = HeaderMap::new();
+        for (name, value) in &endpoint.headers {
+            let name = HeaderName::from_bytes(name.as_bytes()).map_err(|e| invalid(name, &e))?;
+            let value = HeaderValue::from_str(value).map_err(|e| invalid(name.as_str(), &e))?;
+            headers.insert(name, value);
+        }
+        if let Some(key) = api_key {
+            let name = HeaderName::from_bytes(endpoint.auth_header.as_bytes())
+                .map_err(|e| invalid(&endpoint.auth_header, &e))?;
+            let value = if name == reqwest::header::AUTHORIZATION {
+                format!("Bearer {}", key)
+            } else {
+                key.to_string()
+            };
+            let mut value =
+                HeaderValue::from_str(&value).map_err(|e| invalid(name.as_str(), &e))?;
+            value.set_sensitive(true);
+            headers.insert(name, value);
+        }
+        if endpoint.provider == EmbeddingProvider::OpenRouter {
+            // OpenRouter's compressed responses have failed to decode mid-body
+            headers.insert(
+                reqwest::header::ACCEPT_ENCODING,
+                HeaderValue::from_static("identity"),
+            );
+        }
+        Ok(headers)
+    }
+
+    /// Expect vectors this wide (the stored embedding column)
+    pub fn with_dimension(mut self, dimension: usize) -> Self {
+        self.dimension = dimension;
+        self
+    }
+
+    /// Provider name for logs and status output
+    pub fn provider(&self) -> &'static str {
+        self.adapter.name()
+    }
+
+    /// Base URL of the provider
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Generate embeddings for a batch of texts using the remote service
+    ///
+    /// Texts are cut to `max_input_chars` and sent `max_batch_size` at a
+    /// time. A batch the provider refuses is split in half until the
+    /// offending text is alone; that text gets a zero vector.
+    pub async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(vec![]);
+        }
+
+        debug!(
+            "Requesting embeddings for {} texts from {}",
+            texts.len(),
+            self.url
+        );
+
+        let (texts, truncated) = truncate_inputs(texts, self.max_input_chars);
+        if truncated > 0 {
+            warn!(
+                "Truncated {} {} embedding inputs to {} chars",
+                truncated,
+                self.provider(),
+                self.max_input_chars
+            );
+        }
+
+        let mut output = Vec::with_capacity(texts.len());
+        for chunk in texts.chunks(self.max_batch_size) {
+            output.extend(self.embed_chunk(chunk).await?);
+        }
+        Ok(output)
+    }
+
+    /// Generate embedding for a single text
+    pub async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let embeddings = self.embed_batch(&[text.to_string()]).await?;
+        Ok(embeddings.into_iter().next().unwrap_or_default())
+    }
+
+    /// Check if the remote service is available
+    pub async fn is_available(&self) -> bool {
+        self.adapter.is_available(&self.http).await
+    }
+
+    async fn embed_chunk(&self, chunk: &[String]) -> Result<Vec<Vec<f32>>> {
+        let mut output = Vec::with_capacity(chunk.len());
+        let mut pending = vec![chunk];
+
+        while let Some(part) = pending.pop() {
+            match self.request(part).await {
+                Ok(embeddings) => output.extend(embeddings),
+                Err(RemoteFailure::Rejected(msg)) if part.len() > 1 => {
+                    let mid = part.len() / 2;
+                    warn!(
+                        "{} batch of {} failed ({}). Splitting into {} + {}",
+                        self.provider(),
+                        part.len(),
+                        msg,
+                        mid,
+                        part.len() - mid
+                    );
+                    // LIFO stack: push right then left so left is processed first.
+                    pending.push(&part[mid..]);
+                    pending.push(&part[..mid]);
+                }
+                Err(RemoteFailure::Rejected(msg)) => {
+                    warn!(
+                        "{} failed for a single input ({}). Using zero-vector fallback for this item",
+                        self.provider(),
+                        msg
+                    );
+                    output.push(vec![0.0; self.dimension]);
+                }
+                Err(failure) => return Err(failure.into()),
+            }
+        }
+
+        Ok(output)
+    }
+
+    /// One request, retried with exponential backoff while failures are transient
+    async fn request(&self, texts: &[String]) -> std::result::Result<Vec<Vec<f32>>, RemoteFailure> {
+        let mut attempt = 1;
+        loop {
+            let result = self
+                .adapter
+                .embed(&self.http, texts, self.dimension)
+                .await
+                .and_then(|embeddings| self.check(texts.len(), embeddings));
+            match result {
+                Err(RemoteFailure::Transient(msg)) if attempt < self.max_attempts => {
+                    let delay = self.retry_backoff * 2u32.saturating_pow(attempt - 1);
+                    warn!(
+                        "{} (attempt {}/{}), retrying in {:?}",
+                        msg, attempt, self.max_attempts, delay
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                other => return other,
+            }
+        }
+    }
+
+    /// One vector per input, each as wide as the stored column
+    fn check(
+        &self,
+        inputs: usize,
+        embeddings: Vec<Vec<f32>>,
+    ) -> std::result::Result<Vec<Vec<f32>>, RemoteFailure> {
+        if embeddings.len() != inputs {
+            return Err(RemoteFailure::Transient(format!(
+                "{} returned {} embeddings for {} inputs",
+                self.provider(),
+                embeddings.len(),
+                inputs
+            )));
+        }
+        if let Some(wrong) = embeddings.iter().find(|e| e.len() != self.dimension) {
+            return Err(RemoteFailure::Fatal(format!(
+                "{} model {} returns {}-dimensional vectors but the stored embedding column holds {}; \
+                 set search.embedding_api.dimensions = {} and restart the daemon to rebuild the column, \
+                 or pick a model with {} dimensions (search.embedding_api.send_dimensions shortens text-embedding-3 output)",
+                self.provider(),
+                self.adapter.model(),
+                wrong.len(),
+                self.dimension,
+                wrong.len(),
+                self.dimension
+            )));
+        }
+        Ok(embeddings)
+    }
+}
+
+/// Cut texts longer than `max_chars` characters (0 = no limit); returns the
+/// texts and how many were cut
+fn truncate_inputs(texts: &[String], max_chars: usize) -> (Vec<String>, usize) {
+    if max_chars == 0 {
+        return (texts.to_vec(), 0);
+    }
+    let mut truncated = 0usize;
+    let sanitized = texts
+        .iter()
+        .map(|text| {
+            if text.chars().count() > max_chars {
+                truncated += 1;
+                text.chars().take(max_chars).collect::<String>()
+            } else {
+                text.clone()
+            }
+        })
+        .collect::<Vec<_>>();
+
+    (sanitized, truncated)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use super::*;
+    use crate::config::SearchConfig;
+
+    /// Adapter that answers from a script and records batch sizes
+    struct ScriptedAdapter {
+        calls: Arc<Mutex<Vec<usize>>>,
+        failures: Mutex<Vec<RemoteFailure>>,
+        width: usize,
+        /// Texts containing this are refused
+        poison: Option<&'static str>,
+    }
+
+    #[async_trait]
+    impl EmbeddingAdapter for ScriptedAdapter {
+        fn name(&self) -> &'static str {
+            "scripted"
+        }
+
+        fn model(&self) -> &str {
+            "test-model"
+        }
+
+        async fn embed(
+            &self,
+            _http: &reqwest::Client,
+            texts: &[String],
+            _dimension: usize,
+        ) -> std::result::Result<Vec<Vec<f32>>, RemoteFailure> {
+            self.calls.lock().unwrap().push(texts.len());
+            if let Some(failure) = self.failures.lock().unwrap().pop() {
+                return Err(failure);
+            }
+            if let Some(poison) = self.poison {
+                if texts.iter().any(|t| t.contains(poison)) {
+                    return Err(RemoteFailure::Rejected("bad input".to_string()));
+                }
+            }
+            Ok(texts.iter().map(|_| vec![1.0; self.width]).collect())
+        }
+    }
+
+    fn test_client(adapter: ScriptedAdapter, max_batch_size: usize) -> RemoteEmbeddingClient {
+        RemoteEmbeddingClient {
+            http: reqwest::Client::new(),
+            adapter: Box::new(adapter),
+            url: "http://test".to_string(),
+            max_batch_size,
+            max_input_chars: 0,
+            max_attempts: 3,
+            retry_backoff: Duration::from_millis(1),
+            dimension: 4,
+        }
+    }
+
+    fn scripted(calls: &Arc<Mutex<Vec<usize>>>, failures: Vec<RemoteFailure>) -> ScriptedAdapter {
+        ScriptedAdapter {
+            calls: calls.clone(),
+            failures: Mutex::new(failures),
+            width: 4,
+            poison: None,
+        }
+    }
+
+    fn texts(n: usize) -> Vec<String> {
+        (0..n).map(|i| format!("text {}", i)).collect()
+    }
+
+    #[tokio::test]
+    async fn test_batches_respect_provider_limit() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let client = test_client(scripted(&calls, vec![]), 4);
+        let embeddings = client.embed_batch(&texts(10)).await.unwrap();
+        assert_eq!(embeddings.len(), 10);
+        assert_eq!(*calls.lock().unwrap(), vec![4, 4, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_transient_failures_are_retried() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let failures = vec![
+            RemoteFailure::Transient("503".to_string()),
+            RemoteFailure::Transient("429".to_string()),
+        ];
+        let client = test_client(scripted(&calls, failures), 8);
+        assert_eq!(client.embed_batch(&texts(2)).await.unwrap().len(), 2);
+        assert_eq!(calls.lock().unwrap().len(), 3);
+
+        // Gives up after max_attempts
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let failures = (0..5)
+            .map(|_| RemoteFailure::Transient("timeout".to_string()))
+            .collect();
+        let client = test_client(scripted(&calls, failures), 8);
+        assert!(client.embed_batch(&texts(2)).await.is_err());
+        assert_eq!(calls.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_fatal_failures_stop_immediately() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let failures = vec![RemoteFailure::Fatal("401 Unauthorized".to_string())];
+        let client = test_client(scripted(&calls, failures), 8);
+        let err = client.embed_batch(&texts(4)).await.unwrap_err();
+        assert!(err.to_string().contains("401"));
+        assert_eq!(*calls.lock().unwrap(), vec![4]);
+    }
+
+    #[tokio::test]
+    async fn test_rejected_input_is_isolated() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let mut adapter = scripted(&calls, vec![]);
+        adapter.poison = Some("text 2");
+        let client = test_client(adapter, 8);
+
+        let embeddings = client.embed_batch(&texts(4)).await.unwrap();
+        assert_eq!(embeddings.len(), 4);
+        assert_eq!(embeddings[2], vec![0.0; 4]);
+        assert_eq!(embeddings[3], vec![1.0; 4]);
+    }
+
+    #[tokio::test]
+    async fn test_dimension_mismatch_is_fatal() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let mut adapter = scripted(&calls, vec![]);
+        adapter.width = 1536;
+        let client = test_client(adapter, 8);
+
+        let err = client.embed_batch(&texts(3)).await.unwrap_err();
+        assert!(err.to_string().contains("1536-dimensional"));
+        assert_eq!(calls.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_truncate_inputs() {
+        let long = "a".repeat(12_000 + 5000);
+        let short = "short".to_string();
+        let inputs = vec![long, short.clone()];
+
+        let (sanitized, truncated) = truncate_inputs(&inputs, 12_000);
+        assert_eq!(truncated, 1);
+        assert_eq!(sanitized[0].chars().count(), 12_000);
+        assert_eq!(sanitized[1], short);
+        assert_eq!(truncate_inputs(&inputs, 0).1, 0);
+    }
+
+    #[test]
+    fn test_auth_headers() {
+        let mut search = SearchConfig {
+            embedding_provider: Some(EmbeddingProvider::OpenAi),
+            ..SearchConfig::default()
+        };
+        let endpoint = search.embedding_endpoint().unwrap();
+        let headers = RemoteEmbeddingClient::headers(&endpoint, Some("sk-test")).unwrap();
+        assert_eq!(headers["authorization"], "Bearer sk-test");
+
+        search.embedding_api.auth_header = Some("api-key".to_string());
+        search
+            .embedding_api
+            .headers
+            .insert("X-Team".to_string(), "mail".to_string());
+        let endpoint = search.embedding_endpoint().unwrap();
+        let headers = RemoteEmbeddingClient::headers(&endpoint, Some("sk-test")).unwrap();
+        assert_eq!(headers["api-key"], "sk-test");
+        assert_eq!(headers["x-team"], "mail");
+        assert!(!headers.contains_key("authorization"));
+
+        search.embedding_provider = Some(EmbeddingProvider::Ollama);
+        let endpoint = search.embedding_endpoint().unwrap();
+        let headers = RemoteEmbeddingClient::headers(&endpoint, None).unwrap();
+        assert!(!headers.contains_key("api-key"));
+    }
+
+    #[tokio::test]
+    #[ignore = "Live network smoke test; requires OPENROUTER_API_KEY"]
+    async fn openrouter_live_batch_1024_no_zero_vector_fallback() {
+        let search = SearchConfig {
+            embedding_provider: Some(EmbeddingProvider::OpenRouter),
+            embedding_timeout_ms: 120_000,
+            ..SearchConfig::default()
+        };
+        let client = RemoteEmbeddingClient::new(&search.embedding_endpoint().unwrap())
+            .expect("OPENROUTER_API_KEY must be set for live OpenRouter smoke test");
+
+        let inputs: Vec<String> = (0..1024)
+            .map(|i| format!("groundeffect openrouter batch smoke {}", i))
+            .collect();
+
+        let start = std::time::Instant::now();
+        let embeddings = client
+            .embed_batch(&inputs)
+            .await
+            .expect("OpenRouter 1024 batch embedding request failed");
+        eprintln!(
+            "openrouter_live_batch_1024_no_zero_vector_fallback elapsed={:?}",
+            start.elapsed()
+        );
+
+        assert_eq!(embeddings.len(), 1024);
+        for emb in &embeddings {
+            assert_eq!(emb.len(), EMBEDDING_DIMENSION);
+            assert!(
+                emb.iter().any(|v| *v != 0.0),
+                "detected all-zero fallback vector in live OpenRouter smoke test"
+            );
+        }
+
+        // Keep this around as a hard guard against regressing to very slow fallback recursion.
+        assert!(
+            start.elapsed() < Duration::from_secs(180),
+            "1024 batch took unexpectedly long"
+        );
+    }
+}
@@ -0,0 +1,105 @@
+//! Ollama's `/api/embed` API
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use super::{post_json, truncate_for_log, EmbeddingAdapter, RemoteFailure};
+
+/// Request body for /api/embed
+#[derive(Debug, Serialize)]
+struct EmbedRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+/// Response body from /api/embed
+#[derive(Debug, Deserialize)]
+struct EmbedResponse {
+    embeddings: Vec<Vec<f32>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+/// Adapter for an Ollama server (local or on the network)
+pub struct OllamaEmbeddingAdapter {
+    url: String,
+    model: String,
+}
+
+impl OllamaEmbeddingAdapter {
+    pub fn new(url: String, model: String) -> Self {
+        Self { url, model }
+    }
+
+    fn parse_embeddings(body: &[u8]) -> Result<Vec<Vec<f32>>, RemoteFailure> {
+        if let Ok(result) = serde_json::from_slice::<EmbedResponse>(body) {
+            return Ok(result.embeddings);
+        }
+        if let Ok(err) = serde_json::from_slice::<ErrorResponse>(body) {
+            return Err(RemoteFailure::Rejected(format!(
+                "Ollama error: {}",
+                err.error
+            )));
+        }
+        Err(RemoteFailure::Transient(format!(
+            "Failed to parse Ollama response JSON. Body preview: {}",
+            truncate_for_log(&String::from_utf8_lossy(body))
+        )))
+    }
+}
+
+#[async_trait]
+impl EmbeddingAdapter for OllamaEmbeddingAdapter {
+    fn name(&self) -> &'static str {
+        "Ollama"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn embed(
+        &self,
+        http: &reqwest::Client,
+        texts: &[String],
+        _dimension: usize,
+    ) -> Result<Vec<Vec<f32>>, RemoteFailure> {
+        let request = EmbedRequest {
+            model: &self.model,
+            input: texts,
+        };
+        let endpoint = format!("{}/api/embed", self.url);
+        let body = post_json(http, "Ollama", &endpoint, &request).await?;
+        Self::parse_embeddings(&body)
+    }
+
+    async fn is_available(&self, http: &reqwest::Client) -> bool {
+        match http.get(format!("{}/api/version", self.url)).send().await {
+            Ok(resp) => resp.status().is_success(),
+            Err(_) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_embeddings_and_errors() {
+        let embeddings = OllamaEmbeddingAdapter::parse_embeddings(
+            br#"{"model":"nomic-embed-text","embeddings":[[0.1,0.2],[0.3,0.4]]}"#,
+        )
+        .unwrap();
+        assert_eq!(embeddings, vec![vec![0.1, 0.2], vec![0.3, 0.4]]);
+
+        let err = OllamaEmbeddingAdapter::parse_embeddings(
+            br#"{"error":"model \"nomic-embed-text\" not found, try pulling it first"}"#,
+        )
+        .unwrap_err();
+        assert!(format!("{:?}", err).contains("try pulling it first"));
+    }
+}
@@ -0,0 +1,199 @@
+//! OpenAI-compatible `/embeddings` API (OpenAI, OpenRouter, Azure OpenAI and
+//! most hosted or self-hosted gateways)
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use super::{post_json, truncate_for_log, EmbeddingAdapter, RemoteFailure};
+
+/// Request body for the /embeddings endpoint
+#[derive(Debug, Serialize)]
+struct EmbeddingsRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+    encoding_format: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dimensions: Option<usize>,
+}
+
+/// Response body from the /embeddings endpoint
+#[derive(Debug, Deserialize)]
+struct EmbeddingsResponse {
+    data: Vec<EmbeddingItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingItem {
+    embedding: Vec<f32>,
+    index: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+    error: ErrorBody,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorBody {
+    message: String,
+    #[serde(default)]
+    code: Option<serde_json::Value>,
+}
+
+/// Adapter for OpenAI-compatible embedding APIs
+pub struct OpenAiEmbeddingAdapter {
+    name: &'static str,
+    url: String,
+    model: String,
+    send_dimensions: bool,
+}
+
+impl OpenAiEmbeddingAdapter {
+    /// `name` labels logs and errors ("OpenAI", "OpenRouter"); with
+    /// `send_dimensions` requests ask for vectors as wide as the stored column
+    pub fn new(name: &'static str, url: String, model: String, send_dimensions: bool) -> Self {
+        Self {
+            name,
+            url,
+            model,
+            send_dimensions,
+        }
+    }
+
+    fn parse_embeddings(&self, body: &[u8]) -> Result<Vec<Vec<f32>>, RemoteFailure> {
+        if let Ok(mut result) = serde_json::from_slice::<EmbeddingsResponse>(body) {
+            // Keep the same order as input.
+            result.data.sort_by_key(|item| item.index);
+            return Ok(result.data.into_iter().map(|item| item.embedding).collect());
+        }
+
+        // Some gateways (OpenRouter) report upstream errors with a 200 status
+        if let Ok(err) = serde_json::from_slice::<ErrorResponse>(body) {
+            let code = err
+                .error
+                .code
+                .as_ref()
+                .map(std::string::ToString::to_string)
+                .unwrap_or_else(|| "unknown".to_string());
+            return Err(RemoteFailure::Rejected(format!(
+                "{} error (code={}): {}",
+                self.name, code, err.error.message
+            )));
+        }
+
+        let parse_error = serde_json::from_slice::<EmbeddingsResponse>(body).unwrap_err();
+        let preview = truncate_for_log(&String::from_utf8_lossy(body));
+        Err(RemoteFailure::Transient(format!(
+            "Failed to parse {} response JSON: {}. Body preview: {}",
+            self.name, parse_error, preview
+        )))
+    }
+}
+
+#[async_trait]
+impl EmbeddingAdapter for OpenAiEmbeddingAdapter {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn embed(
+        &self,
+        http: &reqwest::Client,
+        texts: &[String],
+        dimension: usize,
+    ) -> Result<Vec<Vec<f32>>, RemoteFailure> {
+        let request = EmbeddingsRequest {
+            model: &self.model,
+            input: texts,
+            encoding_format: "float",
+            dimensions: self.send_dimensions.then_some(dimension),
+        };
+        let endpoint = format!("{}/embeddings", self.url);
+        let body = post_json(http, self.name, &endpoint, &request).await?;
+        self.parse_embeddings(&body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adapter() -> OpenAiEmbeddingAdapter {
+        OpenAiEmbeddingAdapter::new(
+            "OpenRouter",
+            "https://openrouter.ai/api/v1".to_string(),
+            "openai/text-embedding-3-small".to_string(),
+            true,
+        )
+    }
+
+    #[test]
+    fn parse_embeddings_preserves_input_order_by_index() {
+        let payload = br#"{
+            "object":"list",
+            "data":[
+                {"embedding":[10.0,11.0],"index":1},
+                {"embedding":[20.0,21.0],"index":0}
+            ]
+        }"#;
+
+        let embeddings = adapter().parse_embeddings(payload).unwrap();
+        assert_eq!(embeddings.len(), 2);
+        assert_eq!(embeddings[0], vec![20.0, 21.0]);
+        assert_eq!(embeddings[1], vec![10.0, 11.0]);
+    }
+
+    #[test]
+    fn parse_embeddings_returns_context_on_invalid_json() {
+        let payload = br#"{"data":"bad-shape"}"#;
+        let err = adapter().parse_embeddings(payload).unwrap_err();
+        let msg = format!("{:?}", err);
+
+        assert!(msg.contains("Failed to parse OpenRouter response JSON"));
+        assert!(msg.contains("Body preview"));
+    }
+
+    #[test]
+    fn parse_embeddings_surfaces_error_payload() {
+        let payload = br#"{
+            "error": {
+                "message": "No successful provider responses.",
+                "code": 404
+            }
+        }"#;
+        let err = adapter().parse_embeddings(payload).unwrap_err();
+        let msg = format!("{:?}", err);
+
+        assert!(matches!(err, RemoteFailure::Rejected(_)));
+        assert!(msg.contains("OpenRouter error"));
+        assert!(msg.contains("No successful provider responses."));
+        assert!(msg.contains("404"));
+    }
+
+    #[test]
+    fn request_asks_for_column_width_when_enabled() {
+        let texts = vec!["hello".to_string()];
+        let request = EmbeddingsRequest {
+            model: "text-embedding-3-small",
+            input: &texts,
+            encoding_format: "float",
+            dimensions: Some(768),
+        };
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["dimensions"], 768);
+        assert_eq!(json["input"][0], "hello");
+
+        let request = EmbeddingsRequest {
+            dimensions: None,
+            ..request
+        };
+        assert!(serde_json::to_value(&request)
+            .unwrap()
+            .get("dimensions")
+            .is_none());
+    }
+}
@@ -0,0 +1,321 @@
+//! Configurable text templates for what gets embedded
+//!
+//! By default emails embed their subject (twice), sender, body and attachment
+//! names, and events their summary, description, location, organizer and
+//! attendees with their addresses (see `searchable_text`). `[embedding] email_template` / `event_template` replace
+//! that layout with `{field}` placeholders, e.g. `"{subject}\n{from}\n{body_clean}"`.
+
+use crate::config::EmbeddingConfig;
+use crate::error::{Error, Result};
+use crate::models::{Address, Attendee, CalendarEvent, Email, EventTime};
+use crate::render::compact_body;
+
+/// Placeholders available in email templates
+pub const EMAIL_TEMPLATE_FIELDS: &[&str] = &[
+    "subject",
+    "from",
+    "from_name",
+    "from_email",
+    "to",
+    "cc",
+    "date",
+    "body",
+    "body_clean",
+    "snippet",
+    "attachments",
+    "labels",
+    "folder",
+];
+
+/// Version of the default event text, recorded with the stored vectors;
+/// bumping it re-embeds events that use the default
+pub const DEFAULT_EVENT_TEXT_VERSION: u32 = 2;
+
+/// Placeholders available in event templates
+pub const EVENT_TEMPLATE_FIELDS: &[&str] = &[
+    "summary",
+    "description",
+    "location",
+    "organizer",
+    "organizer_email",
+    "attendees",
+    "attendee_emails",
+    "start",
+    "end",
+    "calendar",
+];
+
+impl EmbeddingConfig {
+    /// Text embedded for an email
+    pub fn email_text(&self, email: &Email) -> String {
+        match &self.email_template {
+            Some(template) => render_email_template(template, email),
+            None => email.searchable_text(),
+        }
+    }
+
+    /// Text embedded for a calendar event
+    pub fn event_text(&self, event: &CalendarEvent) -> String {
+        match &self.event_template {
+            Some(template) => render_event_template(template, event),
+            None => event.searchable_text(),
+        }
+    }
+
+    /// Check both templates for unknown placeholders
+    pub fn validate(&self) -> Result<()> {
+        if let Some(template) = &self.email_template {
+            validate_template(template, EMAIL_TEMPLATE_FIELDS)?;
+        }
+        if let Some(template) = &self.event_template {
+            validate_template(template, EVENT_TEMPLATE_FIELDS)?;
+        }
+        Ok(())
+    }
+}
+
+/// Render an email template
+pub fn render_email_template(template: &str, email: &Email) -> String {
+    let addresses = |list: &[Address]| {
+        list.iter()
+            .map(|a| a.to_string_full())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    render(template, |field| match field {
+        "subject" => Some(email.subject.clone()),
+        "from" => Some(email.from.to_string_full()),
+        "from_name" => Some(email.from.name.clone().unwrap_or_default()),
+        "from_email" => Some(email.from.email.clone()),
+        "to" => Some(addresses(&email.to)),
+        "cc" => Some(addresses(&email.cc)),
+        "date" => Some(email.date.format("%Y-%m-%d").to_string()),
+        "body" => Some(Email::embedding_body_excerpt(&email.resolved_body())),
+        "body_clean" => Some(Email::embedding_body_excerpt(&compact_body(
+            &email.markdown_body(),
+        ))),
+        "snippet" => Some(email.snippet.clone()),
+        "attachments" => Some(
+            email
+                .attachments
+                .iter()
+                .map(|a| a.filename.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+        "labels" => Some(email.labels.join(", ")),
+        "folder" => Some(email.folder.clone()),
+        _ => None,
+    })
+}
+
+/// Render an event template
+pub fn render_event_template(template: &str, event: &CalendarEvent) -> String {
+    let person = |a: &Attendee| a.name.clone().unwrap_or_else(|| a.email.clone());
+    let time = |t: &EventTime| match t {
+        EventTime::DateTime(dt) => dt.format("%Y-%m-%d %H:%M").to_string(),
+        EventTime::Date(d) => d.format("%Y-%m-%d").to_string(),
+    };
+
+    render(template, |field| match field {
+        "summary" => Some(event.summary.clone()),
+        "description" => Some(event.description.clone().unwrap_or_default()),
+        "location" => Some(event.location.clone().unwrap_or_default()),
+        "organizer" => Some(event.organizer.as_ref().map(person).unwrap_or_default()),
+        "organizer_email" => Some(
+            event
+                .organizer
+                .as_ref()
+                .map(|a| a.email.clone())
+                .unwrap_or_default(),
+        ),
+        "attendees" => Some(
+            event
+                .attendees
+                .iter()
+                .map(person)
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+        "attendee_emails" => Some(
+            event
+                .attendees
+                .iter()
+                .map(|a| a.email.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+        "start" => Some(time(&event.start)),
+        "end" => Some(time(&event.end)),
+        "calendar" => Some(event.calendar_id.clone()),
+        _ => None,
+    })
+}
+
+/// Check that a template only uses known placeholders
+pub fn validate_template(template: &str, fields: &[&str]) -> Result<()> {
+    let unknown: Vec<String> = placeholders(template)
+        .filter(|name| !fields.contains(name))
+        .map(|name| format!("{{{}}}", name))
+        .collect();
+    if unknown.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::Config(format!(
+            "Unknown embedding template placeholder(s) {}; available: {}",
+            unknown.join(", "),
+            fields.join(", ")
+        )))
+    }
+}
+
+/// Names inside `{...}` (`{{` and `}}` are literal braces)
+fn placeholders(template: &str) -> impl Iterator<Item = &str> {
+    let mut rest = template;
+    std::iter::from_fn(move || loop {
+        let start = rest.find('{')?;
+        if rest[start..].starts_with("{{") {
+            rest = &rest[start + 2..];
+            continue;
+        }
+        let end = rest[start..].find('}')? + start;
+        let name = &rest[start + 1..end];
+        rest = &rest[end + 1..];
+        return Some(name);
+    })
+}
+
+/// Substitute `{field}` placeholders; unknown ones are left as written
+fn render(template: &str, value: impl Fn(&str) -> Option<String>) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find(['{', '}']) {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            out.push_str(&rest[..1]);
+            rest = &rest[2..];
+            continue;
+        }
+        if rest.starts_with('}') {
+            out.push('}');
+            rest = &rest[1..];
+            continue;
+        }
+
+        let Some(end) = rest.find('}') else {
+            break;
+        };
+        match value(&rest[1..end]) {
+            Some(text) => out.push_str(&text),
+            None => out.push_str(&rest[..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{EventStatus, Transparency, Visibility};
+
+    fn pricing_sync() -> CalendarEvent {
+        let attendee = |email: &str, name: Option<&str>| Attendee {
+            email: email.to_string(),
+            name: name.map(String::from),
+            response_status: None,
+            optional: false,
+        };
+        CalendarEvent {
+            id: "evt".to_string(),
+            account_id: "me@example.com".to_string(),
+            account_alias: None,
+            google_event_id: "g1".to_string(),
+            ical_uid: "g1".to_string(),
+            etag: String::new(),
+            summary: "Pricing sync".to_string(),
+            description: None,
+            location: Some("Room 4".to_string()),
+            start: EventTime::Date(chrono::NaiveDate::from_ymd_opt(2024, 5, 2).unwrap()),
+            end: EventTime::Date(chrono::NaiveDate::from_ymd_opt(2024, 5, 2).unwrap()),
+            timezone: "UTC".to_string(),
+            all_day: true,
+            recurrence_rule: None,
+            recurrence_id: None,
+            organizer: Some(attendee("me@example.com", None)),
+            attendees: vec![
+                attendee("sarah@example.com", Some("Sarah Lee")),
+                attendee("bob@example.com", None),
+            ],
+            status: EventStatus::default(),
+            transparency: Transparency::default(),
+            visibility: Visibility::default(),
+            color_id: None,
+            reminders: Vec::new(),
+            conference: None,
+            embedding: None,
+            calendar_id: "primary".to_string(),
+            synced_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_default_event_text_has_people_and_place() {
+        let text = EmbeddingConfig::default().event_text(&pricing_sync());
+        assert!(text.contains("Location: Room 4."));
+        assert!(text.contains("Organizer: me@example.com."));
+        assert!(text.contains("Attendees: Sarah Lee <sarah@example.com>, bob@example.com."));
+    }
+
+    #[test]
+    fn test_event_template_people_fields() {
+        let text = render_event_template(
+            "{summary} | {attendees} | {attendee_emails} | {organizer_email}",
+            &pricing_sync(),
+        );
+        assert_eq!(
+            text,
+            "Pricing sync | Sarah Lee, bob@example.com | sarah@example.com, bob@example.com | me@example.com"
+        );
+    }
+
+    #[test]
+    fn test_render_substitutes_known_fields() {
+        let text = render(
+            "{subject}\n{from} {{literal}} {nope}",
+            |field| match field {
+                "subject" => Some("Quarterly plan".to_string()),
+                "from" => Some("Ann <ann@example.com>".to_string()),
+                _ => None,
+            },
+        );
+        assert_eq!(
+            text,
+            "Quarterly plan\nAnn <ann@example.com> {literal} {nope}"
+        );
+    }
+
+    #[test]
+    fn test_render_keeps_unterminated_placeholder() {
+        let text = render("{subject} and {body", |_| Some("x".to_string()));
+        assert_eq!(text, "x and {body");
+    }
+
+    #[test]
+    fn test_validate_template() {
+        assert!(
+            validate_template("{subject}\n{from}\n{body_clean}", EMAIL_TEMPLATE_FIELDS).is_ok()
+        );
+        assert!(validate_template("{{subject}}", &[]).is_ok());
+
+        let err = validate_template("{subject} {sender}", EMAIL_TEMPLATE_FIELDS).unwrap_err();
+        assert!(err.to_string().contains("{sender}"));
+        assert!(validate_template("{summary} {where}", EVENT_TEMPLATE_FIELDS).is_err());
+    }
+}
@@ -15,6 +15,9 @@ pub enum Error {
     #[error("Arrow error: {0}")]
     Arrow(#[from] arrow::error::ArrowError),
 
+    #[error("Parquet error: {0}")]
+    Parquet(#[from] parquet::errors::ParquetError),
+
     #[error("Table not found: {0}")]
     TableNotFound(String),
 
@@ -61,6 +64,11 @@ pub enum Error {
     #[error("Tool not found: {0}")]
     ToolNotFound(String),
 
+    #[error(
+        "{tool} needs the '{capability}' capability, which is not in mcp.allowed_capabilities"
+    )]
+    CapabilityDenied { tool: String, capability: String },
+
     #[error("Resource not found: {0}")]
     ResourceNotFound(String),
 
@@ -109,6 +117,12 @@ pub enum Error {
     #[error("Rate limited, retry after {retry_after_secs} seconds")]
     RateLimited { retry_after_secs: u64 },
 
+    #[error("{operation} timed out after {timeout_secs} seconds")]
+    Timeout {
+        operation: String,
+        timeout_secs: u64,
+    },
+
     // Generic errors
     #[error("{0}")]
     Other(String),
@@ -135,8 +149,10 @@ impl Error {
             Error::EventNotFound(_) => "EVENT_NOT_FOUND",
             Error::InvalidRequest(_) => "INVALID_REQUEST",
             Error::ToolNotFound(_) => "TOOL_NOT_FOUND",
+            Error::CapabilityDenied { .. } => "CAPABILITY_DENIED",
             Error::ResourceNotFound(_) => "RESOURCE_NOT_FOUND",
             Error::RateLimited { .. } => "RATE_LIMITED",
+            Error::Timeout { .. } => "TIMEOUT",
             Error::Database(_) | Error::Arrow(_) => "DATABASE_ERROR",
             Error::Imap(_) | Error::CalDav(_) | Error::Sync(_) | Error::ConnectionFailed { .. } => {
                 "SYNC_ERROR"
@@ -152,7 +168,13 @@ impl Error {
                 Some("Please re-authenticate in GroundEffect preferences")
             }
             Error::RateLimited { .. } => Some("Please wait and try again"),
+            Error::Timeout { .. } => {
+                Some("Narrow the request (filters, date range, limit) and try again")
+            }
             Error::ConnectionFailed { .. } => Some("Check your network connection"),
+            Error::CapabilityDenied { .. } => Some(
+                "Ask the user to add the capability to [mcp] allowed_capabilities in config.toml",
+            ),
             _ => None,
         }
     }
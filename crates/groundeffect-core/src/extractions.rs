@@ -0,0 +1,388 @@
+//! Reservation and package delivery extraction
+//!
+//! Flights and hotel stays come from the travel itinerary parser. Restaurant
+//! reservations (`FoodEstablishmentReservation`) and package deliveries
+//! (`ParcelDelivery`) are read from the same schema.org JSON-LD markup.
+//! Shipping notifications without markup fall back to heuristics: a shipping
+//! subject plus a tracking number in a known carrier's format.
+
+use std::sync::OnceLock;
+
+use chrono_tz::Tz;
+use regex::Regex;
+use serde_json::Value;
+
+use crate::config::ExtractionsConfig;
+use crate::models::{Email, Extraction, ExtractionKind};
+use crate::travel::{
+    collect_reservations, extract_itinerary, json_ld_documents, parse_time, postal_address, text,
+    type_name,
+};
+
+/// Package status: handed to the carrier
+pub const STATUS_SHIPPED: &str = "shipped";
+
+/// Package status: on the truck today
+pub const STATUS_OUT_FOR_DELIVERY: &str = "out_for_delivery";
+
+/// Package status: delivered
+pub const STATUS_DELIVERED: &str = "delivered";
+
+/// Subject fragments used by shipping notifications
+const SHIPPING_SUBJECT_HINTS: &[&str] = &[
+    "shipped",
+    "on its way",
+    "on the way",
+    "out for delivery",
+    "delivered",
+    "shipment",
+    "tracking number",
+];
+
+/// Extract reservations and package deliveries from an email.
+///
+/// `default_tz` places local times whose timezone is unknown.
+pub fn extract_structured(email: &Email, default_tz: Tz) -> Vec<Extraction> {
+    let mut items: Vec<Extraction> = extract_itinerary(email, default_tz)
+        .iter()
+        .map(|item| Extraction::from_itinerary(item, email.date))
+        .collect();
+
+    let documents = json_ld_documents(email);
+    let mut objects = Vec::new();
+    for document in &documents {
+        collect_reservations(document, &mut objects);
+    }
+    for object in objects {
+        let item = match type_name(object).as_deref() {
+            Some("FoodEstablishmentReservation") => parse_restaurant(object, email, default_tz),
+            Some("ParcelDelivery") => parse_parcel(object, email, default_tz),
+            _ => None,
+        };
+        // Repeated markup (one block per recipient) describes the same thing
+        if let Some(item) = item.filter(|item| {
+            !items.iter().any(|i| {
+                i.kind == item.kind
+                    && i.title == item.title
+                    && i.start == item.start
+                    && i.tracking_number == item.tracking_number
+            })
+        }) {
+            items.push(item);
+        }
+    }
+
+    if !items.iter().any(|i| i.kind == ExtractionKind::Package) {
+        items.extend(shipping_notification(email));
+    }
+
+    for (i, item) in items.iter_mut().enumerate() {
+        item.id = format!("{}#{}", email.id, i);
+        item.email_id = email.id.clone();
+        item.account_id = email.account_id.clone();
+    }
+    items
+}
+
+/// Extract reservations and package deliveries from a batch of synced emails
+pub fn extract_all(
+    emails: &[Email],
+    config: &ExtractionsConfig,
+    default_tz: Tz,
+) -> Vec<Extraction> {
+    if !config.enabled {
+        return Vec::new();
+    }
+    emails
+        .iter()
+        .flat_map(|email| extract_structured(email, default_tz))
+        .collect()
+}
+
+/// An extraction with only the fields every kind has
+fn blank(kind: ExtractionKind, title: String, email: &Email) -> Extraction {
+    Extraction {
+        id: String::new(),
+        email_id: String::new(),
+        account_id: String::new(),
+        kind,
+        title,
+        provider: None,
+        confirmation: None,
+        start: None,
+        end: None,
+        all_day: false,
+        timezone: None,
+        origin: None,
+        destination: None,
+        location: None,
+        party_size: None,
+        tracking_number: None,
+        tracking_url: None,
+        status: None,
+        email_date: email.date,
+    }
+}
+
+fn parse_restaurant(reservation: &Value, email: &Email, default_tz: Tz) -> Option<Extraction> {
+    let restaurant = &reservation["reservationFor"];
+    let name = text(restaurant, "name")?;
+    let (start, all_day) = parse_time(&text(reservation, "startTime")?, None, default_tz)?;
+
+    let party_size = match &reservation["partySize"] {
+        Value::Number(n) => n.as_u64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    };
+
+    Some(Extraction {
+        provider: text(reservation, "provider").or_else(|| text(reservation, "broker")),
+        confirmation: text(reservation, "reservationNumber"),
+        start: Some(start),
+        all_day,
+        location: postal_address(&restaurant["address"]).0,
+        party_size: party_size.and_then(|n| u32::try_from(n).ok()),
+        ..blank(ExtractionKind::Restaurant, name, email)
+    })
+}
+
+fn parse_parcel(delivery: &Value, email: &Email, default_tz: Tz) -> Option<Extraction> {
+    let tracking_number = text(delivery, "trackingNumber");
+    let tracking_url = text(delivery, "trackingUrl");
+    let expected = text(delivery, "expectedArrivalUntil")
+        .or_else(|| text(delivery, "expectedArrivalFrom"))
+        .and_then(|t| parse_time(&t, None, default_tz));
+    if tracking_number.is_none() && tracking_url.is_none() && expected.is_none() {
+        return None;
+    }
+
+    let order = &delivery["partOfOrder"];
+    let merchant = text(order, "merchant")
+        .or_else(|| text(order, "seller"))
+        .or_else(|| email.from.name.clone());
+    let title = text(&delivery["itemShipped"], "name").unwrap_or_else(|| match &merchant {
+        Some(merchant) => format!("Package from {}", merchant),
+        None => "Package".to_string(),
+    });
+    let status = text(delivery, "deliveryStatus")
+        .or_else(|| text(order, "orderStatus"))
+        .map(|s| delivery_status(&s));
+
+    Some(Extraction {
+        provider: text(delivery, "carrier").or_else(|| text(delivery, "provider")),
+        confirmation: text(order, "orderNumber"),
+        start: expected.map(|(time, _)| time),
+        all_day: expected.is_some_and(|(_, all_day)| all_day),
+        tracking_number,
+        tracking_url,
+        status: Some(status.unwrap_or_else(|| STATUS_SHIPPED.to_string())),
+        ..blank(ExtractionKind::Package, title, email)
+    })
+}
+
+/// Normalize a schema.org order or delivery status ("OrderDelivered",
+/// "http://schema.org/OrderInTransit", "Out for delivery")
+fn delivery_status(status: &str) -> String {
+    let status: String = status
+        .rsplit('/')
+        .next()
+        .unwrap_or(status)
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect::<String>()
+        .to_lowercase();
+    if status.contains("outfordelivery") {
+        STATUS_OUT_FOR_DELIVERY
+    } else if status.contains("delivered") {
+        STATUS_DELIVERED
+    } else {
+        STATUS_SHIPPED
+    }
+    .to_string()
+}
+
+/// A package from a shipping notification without markup: a shipping subject
+/// and a tracking number in the body
+fn shipping_notification(email: &Email) -> Option<Extraction> {
+    let subject = email.subject.to_lowercase();
+    if !SHIPPING_SUBJECT_HINTS.iter().any(|h| subject.contains(h)) {
+        return None;
+    }
+    let body = if email.body_plain.trim().is_empty() {
+        email.markdown_body()
+    } else {
+        email.body_plain.clone()
+    };
+    let (carrier, tracking_number) = find_tracking_number(&format!("{}\n{}", email.subject, body))?;
+
+    let status = if subject.contains("out for delivery") {
+        STATUS_OUT_FOR_DELIVERY
+    } else if delivered_regex().is_match(&subject) {
+        STATUS_DELIVERED
+    } else {
+        STATUS_SHIPPED
+    };
+    let sender = email
+        .from
+        .name
+        .clone()
+        .unwrap_or_else(|| email.from.email.clone());
+
+    Some(Extraction {
+        provider: Some(carrier.to_string()),
+        tracking_number: Some(tracking_number),
+        status: Some(status.to_string()),
+        ..blank(
+            ExtractionKind::Package,
+            format!("Package from {}", sender),
+            email,
+        )
+    })
+}
+
+/// The first tracking number in a known carrier format, with the carrier
+fn find_tracking_number(text: &str) -> Option<(&'static str, String)> {
+    static PATTERNS: OnceLock<Vec<(&'static str, Regex)>> = OnceLock::new();
+    let patterns = PATTERNS.get_or_init(|| {
+        vec![
+            ("UPS", Regex::new(r"\b1Z[0-9A-Z]{16}\b").unwrap()),
+            ("USPS", Regex::new(r"\b9[2-5]\d{20}\b").unwrap()),
+            ("Amazon Logistics", Regex::new(r"\bTBA\d{12}\b").unwrap()),
+            ("FedEx", Regex::new(r"\b(\d{12}|\d{15})\b").unwrap()),
+        ]
+    });
+    let mentions_fedex = text.to_lowercase().contains("fedex");
+    patterns
+        .iter()
+        // Bare 12/15-digit numbers are only FedEx's when FedEx is named
+        .filter(|(carrier, _)| *carrier != "FedEx" || mentions_fedex)
+        .find_map(|(carrier, regex)| Some((*carrier, regex.find(text)?.as_str().to_string())))
+}
+
+fn delivered_regex() -> &'static Regex {
+    static DELIVERED: OnceLock<Regex> = OnceLock::new();
+    DELIVERED.get_or_init(|| Regex::new(r"^delivered\b|\b(has been|was) delivered\b").unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Address;
+    use chrono::{TimeZone, Utc};
+
+    fn email(subject: &str, body_plain: &str, html: Option<String>) -> Email {
+        Email {
+            from: Address::with_name("Acme Store", "orders@acme.example"),
+            date: Utc.with_ymd_and_hms(2024, 5, 20, 9, 30, 0).unwrap(),
+            body_plain: body_plain.to_string(),
+            body_html: html,
+            ..Email::test("<conf@example.com>", "orders@acme.example", subject)
+        }
+    }
+
+    fn script(json: &str) -> Option<String> {
+        Some(format!(
+            "<html><head><script type=\"application/ld+json\">{}</script></head><body>Hi</body></html>",
+            json
+        ))
+    }
+
+    #[test]
+    fn test_restaurant_reservation() {
+        let json = r#"{
+            "@context": "http://schema.org",
+            "@type": "FoodEstablishmentReservation",
+            "reservationNumber": "OT-12345",
+            "broker": {"@type": "Organization", "name": "OpenTable"},
+            "partySize": "4",
+            "startTime": "2024-06-08T19:30:00-07:00",
+            "reservationFor": {
+                "@type": "FoodEstablishment",
+                "name": "Chez Example",
+                "address": {
+                    "@type": "PostalAddress",
+                    "streetAddress": "12 Valencia St",
+                    "addressLocality": "San Francisco"
+                }
+            }
+        }"#;
+        let items = extract_structured(&email("Reservation confirmed", "", script(json)), Tz::UTC);
+        assert_eq!(items.len(), 1);
+        let dinner = &items[0];
+        assert_eq!(dinner.kind, ExtractionKind::Restaurant);
+        assert_eq!(dinner.title, "Chez Example");
+        assert_eq!(dinner.provider.as_deref(), Some("OpenTable"));
+        assert_eq!(dinner.party_size, Some(4));
+        assert_eq!(
+            dinner.start,
+            Some(Utc.with_ymd_and_hms(2024, 6, 9, 2, 30, 0).unwrap())
+        );
+        assert_eq!(
+            dinner.location.as_deref(),
+            Some("12 Valencia St, San Francisco")
+        );
+        assert_eq!(dinner.id, "me@example.com:<conf@example.com>#0");
+    }
+
+    #[test]
+    fn test_parcel_delivery_markup() {
+        let json = r#"{
+            "@context": "http://schema.org",
+            "@type": "ParcelDelivery",
+            "carrier": {"@type": "Organization", "name": "UPS"},
+            "trackingNumber": "1Z999AA10123456784",
+            "trackingUrl": "https://www.ups.com/track?tracknum=1Z999AA10123456784",
+            "expectedArrivalUntil": "2024-05-23",
+            "itemShipped": {"@type": "Product", "name": "Espresso grinder"},
+            "partOfOrder": {
+                "@type": "Order",
+                "orderNumber": "A-1001",
+                "merchant": {"@type": "Organization", "name": "Acme Store"},
+                "orderStatus": "http://schema.org/OrderInTransit"
+            }
+        }"#;
+        let items = extract_structured(&email("Your order has shipped", "", script(json)), Tz::UTC);
+        assert_eq!(items.len(), 1);
+        let package = &items[0];
+        assert_eq!(package.kind, ExtractionKind::Package);
+        assert_eq!(package.title, "Espresso grinder");
+        assert_eq!(package.provider.as_deref(), Some("UPS"));
+        assert_eq!(package.confirmation.as_deref(), Some("A-1001"));
+        assert_eq!(package.status.as_deref(), Some(STATUS_SHIPPED));
+        assert!(package.all_day);
+    }
+
+    #[test]
+    fn test_shipping_notification_heuristics() {
+        let shipped = email(
+            "Your Acme order is out for delivery",
+            "Track it with USPS: 9400111899223397623910",
+            None,
+        );
+        let items = extract_structured(&shipped, Tz::UTC);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].provider.as_deref(), Some("USPS"));
+        assert_eq!(
+            items[0].tracking_number.as_deref(),
+            Some("9400111899223397623910")
+        );
+        assert_eq!(items[0].status.as_deref(), Some(STATUS_OUT_FOR_DELIVERY));
+        assert_eq!(items[0].title, "Package from Acme Store");
+
+        // A bare 12-digit number is only FedEx's when FedEx is named
+        let invoice = email("Shipment update", "Invoice 123456789012", None);
+        assert!(extract_structured(&invoice, Tz::UTC).is_empty());
+        let fedex = email(
+            "Delivered: your package",
+            "FedEx tracking 123456789012",
+            None,
+        );
+        let items = extract_structured(&fedex, Tz::UTC);
+        assert_eq!(items[0].provider.as_deref(), Some("FedEx"));
+        assert_eq!(items[0].status.as_deref(), Some(STATUS_DELIVERED));
+
+        // "Will be delivered" hasn't been
+        assert_eq!(delivery_status("OrderDelivered"), STATUS_DELIVERED);
+        assert!(!delivered_regex().is_match("your package will be delivered tomorrow"));
+    }
+}
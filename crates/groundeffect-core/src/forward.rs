@@ -0,0 +1,232 @@
+//! Forwarding synced mail
+//!
+//! `groundeffect email forward` sends one or more emails on, each below a
+//! "Forwarded message" block with its original From, Date, Subject, To and Cc
+//! the way Gmail lays out a forward, with their attachments attached again.
+//! Attachments sync didn't download, and the bodies of headers-only mail,
+//! come from the message source, fetched on demand.
+
+use std::fs;
+use std::sync::Arc;
+
+use chrono_tz::Tz;
+use mail_parser::{Message, MessageParser, MimeHeaders};
+
+use crate::config::Config;
+use crate::error::{Error, Result};
+use crate::models::{Address, Email};
+use crate::oauth::OAuthManager;
+use crate::raw::fetch_raw;
+use crate::sync::MessageBody;
+
+/// Line opening each forwarded email
+const FORWARD_SEPARATOR: &str = "---------- Forwarded message ---------";
+
+/// A file attached to an outgoing message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutgoingAttachment {
+    pub filename: String,
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
+
+impl OutgoingAttachment {
+    /// MIME part of the attachment, base64-encoded in 76-character lines
+    pub fn mime_part(&self) -> String {
+        use base64::engine::general_purpose::STANDARD;
+        use base64::Engine;
+        let name = if self.filename.is_ascii() {
+            format!(
+                "\"{}\"",
+                self.filename.replace('\\', "\\\\").replace('"', "\\\"")
+            )
+        } else {
+            format!(
+                "\"=?UTF-8?B?{}?=\"",
+                STANDARD.encode(self.filename.as_bytes())
+            )
+        };
+        let encoded = STANDARD.encode(&self.data);
+        let lines: Vec<&str> = encoded
+            .as_bytes()
+            .chunks(76)
+            .map(|line| std::str::from_utf8(line).unwrap_or_default())
+            .collect();
+        format!(
+            "Content-Type: {}; name={}\r\nContent-Disposition: attachment; filename={}\r\nContent-Transfer-Encoding: base64\r\n\r\n{}\r\n",
+            self.mime_type,
+            name,
+            name,
+            lines.join("\r\n")
+        )
+    }
+}
+
+/// Attachments of an email to forward, read from disk when sync downloaded
+/// them all, otherwise from the message source
+///
+/// The body of a headers-only email is filled in from the source as well.
+pub async fn load_forward_attachments(
+    config: &Config,
+    oauth: Arc<OAuthManager>,
+    email: &mut Email,
+) -> Result<Vec<OutgoingAttachment>> {
+    let on_disk: Option<Vec<OutgoingAttachment>> = email
+        .attachments
+        .iter()
+        .map(|a| {
+            let path = a.local_path.as_ref().filter(|_| a.downloaded)?;
+            Some(OutgoingAttachment {
+                filename: a.filename.clone(),
+                // Older syncs stored only the top-level type
+                mime_type: if a.mime_type.contains('/') {
+                    a.mime_type.clone()
+                } else {
+                    "application/octet-stream".to_string()
+                },
+                data: fs::read(path).ok()?,
+            })
+        })
+        .collect();
+    if let (Some(attachments), false) = (on_disk, email.headers_only) {
+        return Ok(attachments);
+    }
+
+    let raw = fetch_raw(config, oauth, email).await?;
+    let parsed = MessageParser::default()
+        .parse(&raw.source)
+        .ok_or_else(|| Error::InvalidEmailFormat(format!("Failed to parse {}", email.id)))?;
+    if email.headers_only {
+        MessageBody::from_message(&parsed).apply(email);
+        email.headers_only = false;
+    }
+    Ok(source_attachments(&parsed))
+}
+
+/// Attachments of a parsed message with their content
+pub(crate) fn source_attachments(parsed: &Message) -> Vec<OutgoingAttachment> {
+    parsed
+        .attachments()
+        .map(|att| OutgoingAttachment {
+            filename: att.attachment_name().unwrap_or("attachment").to_string(),
+            mime_type: att
+                .content_type()
+                .map(|ct| format!("{}/{}", ct.ctype(), ct.subtype().unwrap_or("octet-stream")))
+                .unwrap_or_else(|| "application/octet-stream".to_string()),
+            data: att.contents().to_vec(),
+        })
+        .collect()
+}
+
+/// Subject of a forward: "Fwd: " and the first email's subject, noting how
+/// many more are included
+pub fn forward_subject(emails: &[Email]) -> String {
+    let subject = emails.first().map(|e| e.subject.trim()).unwrap_or_default();
+    let lower = subject.to_lowercase();
+    let mut subject = if lower.starts_with("fwd:") || lower.starts_with("fw:") {
+        subject.to_string()
+    } else {
+        format!("Fwd: {}", subject)
+    };
+    if emails.len() > 1 {
+        subject.push_str(&format!(" (and {} more)", emails.len() - 1));
+    }
+    subject
+}
+
+/// Body of a forward: the comment, then each email with its original
+/// headers (dates in `tz`)
+pub fn forward_body(comment: &str, emails: &[Email], tz: Tz) -> String {
+    let mut body = comment.trim_end().to_string();
+    for email in emails {
+        if !body.is_empty() {
+            body.push_str("\n\n");
+        }
+        body.push_str(FORWARD_SEPARATOR);
+        body.push_str(&format!("\nFrom: {}", email.from.to_string_full()));
+        body.push_str(&format!(
+            "\nDate: {}",
+            email
+                .date
+                .with_timezone(&tz)
+                .format("%a, %b %-d, %Y at %-I:%M %p")
+        ));
+        body.push_str(&format!("\nSubject: {}", email.subject));
+        let join = |addresses: &[Address]| {
+            addresses
+                .iter()
+                .map(|a| a.to_string_full())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        body.push_str(&format!("\nTo: {}", join(&email.to)));
+        if !email.cc.is_empty() {
+            body.push_str(&format!("\nCc: {}", join(&email.cc)));
+        }
+        body.push_str("\n\n");
+        body.push_str(email.resolved_body().trim_end());
+    }
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn email(subject: &str, body: &str) -> Email {
+        Email {
+            from: Address::with_name("Bob Stone", "bob@acme.io"),
+            to: vec![Address::new("me@example.com")],
+            cc: vec![Address::with_name("Ann", "ann@acme.io")],
+            date: Utc.with_ymd_and_hms(2024, 6, 3, 16, 5, 0).unwrap(),
+            body_plain: body.to_string(),
+            ..Email::test("<1@x>", "bob@acme.io", subject)
+        }
+    }
+
+    #[test]
+    fn test_forward_subject() {
+        assert_eq!(
+            forward_subject(&[email("Q3 pricing", "")]),
+            "Fwd: Q3 pricing"
+        );
+        assert_eq!(
+            forward_subject(&[email("FW: Q3 pricing", "")]),
+            "FW: Q3 pricing"
+        );
+        assert_eq!(
+            forward_subject(&[email("Q3 pricing", ""), email("Contract", "")]),
+            "Fwd: Q3 pricing (and 1 more)"
+        );
+    }
+
+    #[test]
+    fn test_forward_body() {
+        let body = forward_body(
+            "FYI\n",
+            &[email("Q3 pricing", "Can we do 10% off?\n")],
+            chrono_tz::America::New_York,
+        );
+        assert_eq!(
+            body,
+            "FYI\n\n---------- Forwarded message ---------\nFrom: Bob Stone <bob@acme.io>\nDate: Mon, Jun 3, 2024 at 12:05 PM\nSubject: Q3 pricing\nTo: me@example.com\nCc: Ann <ann@acme.io>\n\nCan we do 10% off?"
+        );
+        assert!(forward_body("", &[email("Q3", "Hi")], Tz::UTC)
+            .starts_with("---------- Forwarded message ---------\n"));
+    }
+
+    #[test]
+    fn test_source_attachments() {
+        let source = b"From: bob@acme.io\r\nSubject: Report\r\nMIME-Version: 1.0\r\nContent-Type: multipart/mixed; boundary=\"b\"\r\n\r\n--b\r\nContent-Type: text/plain\r\n\r\nSee attached.\r\n--b\r\nContent-Type: application/pdf; name=\"report.pdf\"\r\nContent-Disposition: attachment; filename=\"report.pdf\"\r\nContent-Transfer-Encoding: base64\r\n\r\nJVBERi0xLjQ=\r\n--b--\r\n";
+        let parsed = MessageParser::default().parse(&source[..]).unwrap();
+        assert_eq!(
+            source_attachments(&parsed),
+            vec![OutgoingAttachment {
+                filename: "report.pdf".to_string(),
+                mime_type: "application/pdf".to_string(),
+                data: b"%PDF-1.4".to_vec(),
+            }]
+        );
+    }
+}
@@ -0,0 +1,263 @@
+//! Full-text index maintenance
+//!
+//! LanceDB doesn't add new rows to a full-text index as they're written;
+//! until the index is updated they're only found by a slower flat scan.
+//! The daemon tells an [`FtsScheduler`] how many rows each sync wrote and
+//! updates the indexes when it says they're due: after
+//! `index.fts_update_rows` unindexed rows, or `index.fts_max_interval_secs`
+//! after the first of them, but never more often than
+//! `index.fts_min_interval_secs`. A busy initial sync gets a few large
+//! updates instead of one per batch, and a quiet inbox still becomes
+//! searchable within the max interval.
+//!
+//! With the `incremental` strategy an update only indexes the new rows;
+//! `rebuild` recreates each index from scratch. Every run is recorded in
+//! `index_state.json` for `index status`.
+
+use std::path::Path;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::config::IndexConfig;
+use crate::db::Database;
+use crate::error::Result;
+
+/// How often the daemon asks the scheduler whether an update is due
+pub const FTS_CHECK_SECS: u64 = 10;
+
+/// How the full-text indexes are brought up to date
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FtsStrategy {
+    /// Add only the rows written since the last update
+    #[default]
+    Incremental,
+    /// Recreate each index from every row
+    Rebuild,
+}
+
+impl FtsStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FtsStrategy::Incremental => "incremental",
+            FtsStrategy::Rebuild => "rebuild",
+        }
+    }
+}
+
+/// One run of the index update
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FtsRun {
+    pub at: DateTime<Utc>,
+
+    pub mode: FtsStrategy,
+
+    pub duration_ms: u64,
+}
+
+/// When the full-text indexes were last updated and rebuilt
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IndexState {
+    /// Latest run of either kind
+    pub last_update: Option<FtsRun>,
+
+    /// Latest full rebuild
+    pub last_rebuild: Option<FtsRun>,
+}
+
+impl IndexState {
+    /// Read the state, or an empty one if there is none yet
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// Write the state to disk
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, json)
+    }
+
+    pub fn record(&mut self, run: FtsRun) {
+        if run.mode == FtsStrategy::Rebuild {
+            self.last_rebuild = Some(run.clone());
+        }
+        self.last_update = Some(run);
+    }
+}
+
+/// Decides when written rows are worth an index update
+#[derive(Debug, Clone)]
+pub struct FtsScheduler {
+    update_rows: usize,
+    min_interval: Duration,
+    max_interval: Duration,
+
+    /// Rows written since the last update
+    pending_rows: usize,
+
+    /// When the oldest of those rows was written
+    pending_since: Option<DateTime<Utc>>,
+
+    last_update: Option<DateTime<Utc>>,
+}
+
+impl FtsScheduler {
+    pub fn new(config: &IndexConfig) -> Self {
+        Self {
+            update_rows: config.fts_update_rows.max(1),
+            min_interval: Duration::seconds(config.fts_min_interval_secs as i64),
+            max_interval: Duration::seconds(config.fts_max_interval_secs as i64),
+            pending_rows: 0,
+            pending_since: None,
+            last_update: None,
+        }
+    }
+
+    /// `rows` rows were written to an indexed table
+    pub fn record_writes(&mut self, rows: usize, now: DateTime<Utc>) {
+        if rows == 0 {
+            return;
+        }
+        self.pending_rows += rows;
+        self.pending_since.get_or_insert(now);
+    }
+
+    /// Rows written since the last update
+    pub fn pending_rows(&self) -> usize {
+        self.pending_rows
+    }
+
+    /// Whether the indexes should be updated now
+    pub fn due(&self, now: DateTime<Utc>) -> bool {
+        let Some(since) = self.pending_since else {
+            return false;
+        };
+        if let Some(last) = self.last_update {
+            if now - last < self.min_interval {
+                return false;
+            }
+        }
+        self.pending_rows >= self.update_rows || now - since >= self.max_interval
+    }
+
+    /// The indexes were updated, covering everything written before `now`
+    pub fn updated(&mut self, now: DateTime<Utc>) {
+        self.pending_rows = 0;
+        self.pending_since = None;
+        self.last_update = Some(now);
+    }
+}
+
+/// Bring the full-text indexes up to date and record the run in the state
+/// file
+///
+/// An incremental update can only extend an index that exists, so a
+/// missing index turns it into a rebuild.
+pub async fn update_indexes(db: &Database, mode: FtsStrategy, state_file: &Path) -> Result<FtsRun> {
+    let mode = match mode {
+        FtsStrategy::Incremental
+            if db
+                .fts_coverage()
+                .await?
+                .iter()
+                .any(|c| c.indexed_rows.is_none()) =>
+        {
+            info!("A full-text index is missing; rebuilding instead of updating");
+            FtsStrategy::Rebuild
+        }
+        mode => mode,
+    };
+
+    let start = std::time::Instant::now();
+    match mode {
+        FtsStrategy::Incremental => db.update_fts_indexes().await?,
+        FtsStrategy::Rebuild => db.rebuild_fts_indexes().await?,
+    }
+    let run = FtsRun {
+        at: Utc::now(),
+        mode,
+        duration_ms: start.elapsed().as_millis() as u64,
+    };
+
+    let mut state = IndexState::load(state_file);
+    state.record(run.clone());
+    state.save(state_file)?;
+    Ok(run)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn scheduler() -> FtsScheduler {
+        FtsScheduler::new(&IndexConfig {
+            fts_strategy: FtsStrategy::Incremental,
+            fts_update_rows: 100,
+            fts_min_interval_secs: 30,
+            fts_max_interval_secs: 300,
+        })
+    }
+
+    #[test]
+    fn test_update_due_after_enough_rows_or_time() {
+        let start = Utc.with_ymd_and_hms(2024, 6, 3, 9, 0, 0).unwrap();
+        let mut fts = scheduler();
+        assert!(!fts.due(start + Duration::hours(1)));
+
+        // A trickle waits for the max interval
+        fts.record_writes(3, start);
+        fts.record_writes(0, start + Duration::seconds(10));
+        assert!(!fts.due(start + Duration::seconds(299)));
+        assert!(fts.due(start + Duration::seconds(300)));
+        fts.updated(start + Duration::seconds(300));
+        assert_eq!(fts.pending_rows(), 0);
+
+        // A burst is due once it reaches the row threshold...
+        let later = start + Duration::seconds(400);
+        fts.record_writes(60, later);
+        assert!(!fts.due(later));
+        fts.record_writes(60, later);
+        assert!(fts.due(later));
+        fts.updated(later);
+
+        // ...but not sooner than the min interval after the last update
+        fts.record_writes(500, later + Duration::seconds(5));
+        assert!(!fts.due(later + Duration::seconds(29)));
+        assert!(fts.due(later + Duration::seconds(30)));
+    }
+
+    #[test]
+    fn test_state_keeps_last_rebuild() {
+        let at = Utc.with_ymd_and_hms(2024, 6, 3, 9, 0, 0).unwrap();
+        let mut state = IndexState::default();
+        state.record(FtsRun {
+            at,
+            mode: FtsStrategy::Rebuild,
+            duration_ms: 900,
+        });
+        state.record(FtsRun {
+            at: at + Duration::minutes(5),
+            mode: FtsStrategy::Incremental,
+            duration_ms: 40,
+        });
+
+        let path = std::env::temp_dir().join(format!("ge-index-{}.json", uuid::Uuid::new_v4()));
+        state.save(&path).unwrap();
+        let state = IndexState::load(&path);
+        let _ = std::fs::remove_file(&path);
+
+        let update = state.last_update.unwrap();
+        assert_eq!(update.mode, FtsStrategy::Incremental);
+        assert_eq!(update.at, at + Duration::minutes(5));
+        assert_eq!(state.last_rebuild.unwrap().at, at);
+    }
+}
@@ -0,0 +1,377 @@
+//! Daemon health tracking and crash-loop detection
+//!
+//! launchd's KeepAlive (and systemd's Restart=always) restarts the daemon
+//! whenever it exits, which hides crash loops: the daemon looks "running"
+//! while it dies and re-syncs every few seconds. The daemon records each
+//! start, clean shutdown, heartbeat and panic in a small status file so
+//! repeated unclean exits can be detected, surfaced via
+//! `groundeffect daemon status --health`, and used to slow down sync work
+//! until the daemon has been stable for a while.
+
+use std::backtrace::Backtrace;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Unclean exits within this window count towards a crash loop (10 minutes)
+pub const CRASH_LOOP_WINDOW_SECS: i64 = 600;
+
+/// Number of unclean exits within the window that constitutes a crash loop
+pub const CRASH_LOOP_THRESHOLD: usize = 3;
+
+/// Uptime after which the daemon is considered stable again (10 minutes)
+pub const STABLE_UPTIME_SECS: u64 = 600;
+
+/// How often the running daemon refreshes its heartbeat
+pub const HEARTBEAT_INTERVAL_SECS: u64 = 60;
+
+/// Upper bound on the sync interval multiplier while crash-looping
+const MAX_SYNC_BACKOFF_FACTOR: u32 = 16;
+
+/// Sync watchdog incidents kept in the health file
+const MAX_SYNC_INCIDENTS: usize = 20;
+
+/// How far back `doctor` looks for sync watchdog incidents (a day)
+pub const RECENT_INCIDENT_SECS: i64 = 86400;
+
+/// A panic captured by the daemon's panic hook
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PanicRecord {
+    /// When the panic happened
+    pub at: DateTime<Utc>,
+
+    /// Panic message
+    pub message: String,
+
+    /// Source location ("file:line:col"), if known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+
+    /// Thread that panicked
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thread: Option<String>,
+
+    /// Captured backtrace
+    pub backtrace: String,
+}
+
+/// Why the sync watchdog restarted an account
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StallKind {
+    /// A sync ran without progress for the stall timeout
+    NoProgress,
+
+    /// The IMAP IDLE connection stopped renewing
+    IdleWedged,
+
+    /// Syncs kept failing with the same error
+    RepeatedError,
+}
+
+/// An account sync the watchdog found stuck and restarted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncIncident {
+    pub at: DateTime<Utc>,
+
+    pub account_id: String,
+
+    pub kind: StallKind,
+
+    /// What was seen, e.g. "no progress for 30 minutes"
+    pub detail: String,
+}
+
+/// Persistent daemon health state
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DaemonHealth {
+    /// PID of the most recent daemon process
+    pub pid: Option<u32>,
+
+    /// Whether the most recent daemon process is still running (cleared on
+    /// clean shutdown, so a set flag at startup means the last run crashed)
+    pub running: bool,
+
+    /// When the most recent daemon process started
+    pub started_at: Option<DateTime<Utc>>,
+
+    /// Last heartbeat from the running daemon
+    pub last_heartbeat: Option<DateTime<Utc>>,
+
+    /// Last clean shutdown
+    pub last_clean_shutdown: Option<DateTime<Utc>>,
+
+    /// Total number of daemon starts recorded
+    pub total_starts: u64,
+
+    /// Starts that followed an unclean exit
+    pub restart_count: u64,
+
+    /// Unclean exits within the crash-loop window
+    pub recent_crashes: Vec<DateTime<Utc>>,
+
+    /// Most recent panic, with backtrace
+    pub last_panic: Option<PanicRecord>,
+
+    /// Whether idle resources (embedding model, IMAP IDLE connections,
+    /// caches) are currently released
+    pub shed: bool,
+
+    /// Times the running daemon released idle resources
+    pub shed_count: u64,
+
+    /// Times it brought them back after new activity
+    pub restore_count: u64,
+
+    /// Most recent release
+    pub last_shed_at: Option<DateTime<Utc>>,
+
+    /// Most recent restore
+    pub last_restore_at: Option<DateTime<Utc>>,
+
+    /// Stalled syncs the watchdog restarted, oldest first (kept across runs)
+    pub sync_incidents: Vec<SyncIncident>,
+}
+
+impl DaemonHealth {
+    /// Load health state from disk (default if missing or unreadable)
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// Write health state to disk
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, json)
+    }
+
+    /// Record a daemon start. Returns true if the previous run exited uncleanly.
+    pub fn record_start(&mut self, pid: u32, now: DateTime<Utc>) -> bool {
+        let crashed = self.running;
+        if crashed {
+            self.restart_count += 1;
+            self.recent_crashes
+                .push(self.last_heartbeat.or(self.started_at).unwrap_or(now));
+        }
+        self.prune_crashes(now);
+
+        self.pid = Some(pid);
+        self.running = true;
+        self.started_at = Some(now);
+        self.last_heartbeat = Some(now);
+        self.total_starts += 1;
+        self.shed = false;
+        self.shed_count = 0;
+        self.restore_count = 0;
+        crashed
+    }
+
+    /// Record that idle resources were released
+    pub fn record_shed(&mut self, now: DateTime<Utc>) {
+        self.shed = true;
+        self.shed_count += 1;
+        self.last_shed_at = Some(now);
+    }
+
+    /// Record that released resources were brought back
+    pub fn record_restore(&mut self, now: DateTime<Utc>) {
+        self.shed = false;
+        self.restore_count += 1;
+        self.last_restore_at = Some(now);
+    }
+
+    /// Record a stalled sync the watchdog restarted
+    pub fn record_sync_incident(&mut self, incident: SyncIncident) {
+        self.sync_incidents.push(incident);
+        let excess = self.sync_incidents.len().saturating_sub(MAX_SYNC_INCIDENTS);
+        self.sync_incidents.drain(..excess);
+    }
+
+    /// Watchdog incidents within [`RECENT_INCIDENT_SECS`] of `now`
+    pub fn recent_sync_incidents(&self, now: DateTime<Utc>) -> Vec<&SyncIncident> {
+        let cutoff = now - Duration::seconds(RECENT_INCIDENT_SECS);
+        self.sync_incidents
+            .iter()
+            .filter(|incident| incident.at >= cutoff)
+            .collect()
+    }
+
+    /// Record a heartbeat from the running daemon
+    pub fn record_heartbeat(&mut self, now: DateTime<Utc>) {
+        self.last_heartbeat = Some(now);
+    }
+
+    /// Record a clean shutdown
+    pub fn record_shutdown(&mut self, now: DateTime<Utc>) {
+        self.running = false;
+        self.shed = false;
+        self.last_clean_shutdown = Some(now);
+    }
+
+    /// Forget past crashes once the daemon has stayed up long enough
+    pub fn mark_stable(&mut self) {
+        self.recent_crashes.clear();
+    }
+
+    /// Whether the daemon is restarting repeatedly
+    pub fn is_crash_looping(&self) -> bool {
+        self.recent_crashes.len() >= CRASH_LOOP_THRESHOLD
+    }
+
+    /// Multiplier for sync intervals: 1 normally, doubling with each crash
+    /// beyond the threshold while crash-looping
+    pub fn sync_backoff_factor(&self) -> u32 {
+        if !self.is_crash_looping() {
+            return 1;
+        }
+        let excess = (self.recent_crashes.len() - CRASH_LOOP_THRESHOLD) as u32;
+        2u32.saturating_pow(excess + 1).min(MAX_SYNC_BACKOFF_FACTOR)
+    }
+
+    /// Whether the heartbeat is older than a few heartbeat intervals
+    pub fn heartbeat_stale(&self, now: DateTime<Utc>) -> bool {
+        match self.last_heartbeat {
+            Some(at) if self.running => {
+                now - at > Duration::seconds(3 * HEARTBEAT_INTERVAL_SECS as i64)
+            }
+            _ => false,
+        }
+    }
+
+    fn prune_crashes(&mut self, now: DateTime<Utc>) {
+        let cutoff = now - Duration::seconds(CRASH_LOOP_WINDOW_SECS);
+        self.recent_crashes.retain(|at| *at >= cutoff);
+    }
+}
+
+/// Install a panic hook that records the panic (with backtrace) in the
+/// health file before running the default hook
+pub fn install_panic_hook(health_file: PathBuf) {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let message = if let Some(s) = info.payload().downcast_ref::<&str>() {
+            s.to_string()
+        } else if let Some(s) = info.payload().downcast_ref::<String>() {
+            s.clone()
+        } else {
+            "unknown panic payload".to_string()
+        };
+
+        let record = PanicRecord {
+            at: Utc::now(),
+            message,
+            location: info
+                .location()
+                .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column())),
+            thread: std::thread::current().name().map(|n| n.to_string()),
+            backtrace: Backtrace::force_capture().to_string(),
+        };
+
+        let mut health = DaemonHealth::load(&health_file);
+        health.last_panic = Some(record);
+        let _ = health.save(&health_file);
+
+        default_hook(info);
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean_restart_is_not_a_crash() {
+        let now = Utc::now();
+        let mut health = DaemonHealth::default();
+        assert!(!health.record_start(100, now));
+        health.record_shutdown(now + Duration::seconds(30));
+        assert!(!health.record_start(101, now + Duration::seconds(60)));
+        assert_eq!(health.restart_count, 0);
+        assert_eq!(health.total_starts, 2);
+        assert!(!health.is_crash_looping());
+    }
+
+    #[test]
+    fn test_detects_crash_loop_and_backs_off() {
+        let now = Utc::now();
+        let mut health = DaemonHealth::default();
+        health.record_start(100, now);
+
+        // Each start without a clean shutdown counts as a crash
+        for i in 1..=4 {
+            assert!(health.record_start(100 + i, now + Duration::seconds(i as i64 * 10)));
+        }
+        assert_eq!(health.restart_count, 4);
+        assert!(health.is_crash_looping());
+        assert_eq!(health.sync_backoff_factor(), 4);
+
+        health.mark_stable();
+        assert!(!health.is_crash_looping());
+        assert_eq!(health.sync_backoff_factor(), 1);
+    }
+
+    #[test]
+    fn test_old_crashes_fall_out_of_window() {
+        let now = Utc::now();
+        let mut health = DaemonHealth::default();
+        health.record_start(100, now);
+        health.record_start(101, now + Duration::seconds(10));
+        health.record_start(102, now + Duration::seconds(20));
+
+        // Next crash happens long after the earlier ones
+        let later = now + Duration::seconds(CRASH_LOOP_WINDOW_SECS + 60);
+        health.record_heartbeat(later);
+        health.record_start(103, later);
+        assert_eq!(health.recent_crashes.len(), 1);
+        assert!(!health.is_crash_looping());
+    }
+
+    #[test]
+    fn test_shed_and_restore_counts_reset_per_run() {
+        let now = Utc::now();
+        let mut health = DaemonHealth::default();
+        health.record_start(100, now);
+        health.record_shed(now + Duration::seconds(3600));
+        assert!(health.shed);
+        health.record_restore(now + Duration::seconds(4000));
+        health.record_shed(now + Duration::seconds(8000));
+        assert_eq!((health.shed_count, health.restore_count), (2, 1));
+        assert_eq!(health.last_shed_at, Some(now + Duration::seconds(8000)));
+
+        health.record_shutdown(now + Duration::seconds(9000));
+        assert!(!health.shed);
+        health.record_start(101, now + Duration::seconds(9100));
+        assert_eq!((health.shed_count, health.restore_count), (0, 0));
+    }
+
+    #[test]
+    fn test_sync_incidents_are_capped_and_filtered_by_age() {
+        let now = Utc::now();
+        let mut health = DaemonHealth::default();
+        for i in 0..MAX_SYNC_INCIDENTS + 5 {
+            health.record_sync_incident(SyncIncident {
+                at: now - Duration::hours((MAX_SYNC_INCIDENTS + 5 - i) as i64 * 2),
+                account_id: format!("a{}@example.com", i),
+                kind: StallKind::NoProgress,
+                detail: "no progress for 30 minutes".to_string(),
+            });
+        }
+        assert_eq!(health.sync_incidents.len(), MAX_SYNC_INCIDENTS);
+        assert_eq!(health.sync_incidents[0].account_id, "a5@example.com");
+
+        // Two hours apart, so the last day holds the newest 12
+        assert_eq!(health.recent_sync_incidents(now).len(), 12);
+
+        // Files written before incidents were recorded still load
+        let old: DaemonHealth = serde_json::from_str(r#"{"running":true}"#).unwrap();
+        assert!(old.sync_incidents.is_empty());
+    }
+}
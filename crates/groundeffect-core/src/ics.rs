@@ -0,0 +1,482 @@
+//! ICS feed of synced calendars
+//!
+//! The daemon can publish the primary calendars of selected accounts,
+//! merged into one read-only iCalendar feed, so another calendar app can
+//! subscribe to the combined view. The feed is rewritten to a file on a
+//! schedule (`[ics_feed]`) and, with a port set, also served on loopback
+//! at `http://127.0.0.1:<port>/calendar.ics`.
+//!
+//! Sync stores recurring events as their individual instances, so every
+//! instance is published as its own event and no RRULE is written. A
+//! meeting on the calendars of two accounts (the same iCalendar UID and
+//! start) appears once.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Duration, Utc};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, info, warn};
+
+use crate::config::Config;
+use crate::dav::is_local_host;
+use crate::db::Database;
+use crate::error::{Error, Result};
+use crate::models::{
+    AttendeeStatus, CalendarEvent, EventStatus, EventTime, Transparency, Visibility,
+};
+
+/// Path the feed is served on
+pub const ICS_FEED_PATH: &str = "/calendar.ics";
+
+/// Product identifier written into the feed
+const PRODID: &str = "-//groundeffect//Calendar Feed//EN";
+
+/// Longest request head accepted by the feed server
+const MAX_REQUEST_HEAD: usize = 8 * 1024;
+
+/// Content lines are folded after this many octets (RFC 5545 3.1)
+const MAX_LINE_OCTETS: usize = 75;
+
+/// Events of `[ics_feed]` accounts in its window around `now`, as an
+/// iCalendar document
+pub async fn build_feed(db: &Database, config: &Config, now: DateTime<Utc>) -> Result<String> {
+    let events = feed_events(db, config, now).await?;
+    let feed = &config.ics_feed;
+    Ok(render_calendar(&events, &feed.name, feed.busy_only, now))
+}
+
+/// Synced events of the `[ics_feed]` accounts in its window around `now`,
+/// soonest first (repeats across accounts not yet merged)
+pub async fn feed_events(
+    db: &Database,
+    config: &Config,
+    now: DateTime<Utc>,
+) -> Result<Vec<CalendarEvent>> {
+    let feed = &config.ics_feed;
+    let accounts = feed
+        .accounts
+        .iter()
+        .map(|a| {
+            config
+                .resolve_account(a)
+                .ok_or_else(|| Error::AccountNotFound(a.clone()))
+        })
+        .collect::<Result<Vec<String>>>()?;
+
+    db.list_events_overlapping(
+        Some(&accounts),
+        now - Duration::days(feed.past_days as i64),
+        now + Duration::days(feed.future_days as i64),
+    )
+    .await
+}
+
+/// The events to publish: cancelled ones are left out, as are repeats of an
+/// event already kept (same iCalendar UID and start, as when a meeting is on
+/// the calendars of two accounts)
+pub fn unique_events(events: &[CalendarEvent]) -> Vec<&CalendarEvent> {
+    let mut seen = HashSet::new();
+    events
+        .iter()
+        .filter(|e| e.status != EventStatus::Cancelled)
+        .filter(|e| seen.insert((e.ical_uid.as_str(), time_key(&e.start))))
+        .collect()
+}
+
+/// Build the feed and replace the file at `[ics_feed] path`
+///
+/// The file is written next to its final name and renamed over it, so a
+/// subscriber never reads half a feed. Returns the path.
+pub async fn write_feed(db: &Database, config: &Config, now: DateTime<Utc>) -> Result<PathBuf> {
+    let ics = build_feed(db, config, now).await?;
+    let path = config.ics_feed_file();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("ics.tmp");
+    std::fs::write(&tmp, ics)?;
+    std::fs::rename(&tmp, &path)?;
+    Ok(path)
+}
+
+/// Serve the feed file read-only on `127.0.0.1:port`
+///
+/// Each request reads the file as last written, so the server never holds
+/// the database. Requests naming another Host are refused, as by the CalDAV
+/// server.
+pub async fn serve_feed(port: u16, path: PathBuf) -> Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port)).await?;
+    info!(
+        "Serving calendar feed on http://127.0.0.1:{}{}",
+        port, ICS_FEED_PATH
+    );
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                warn!("Failed to accept calendar feed connection: {}", e);
+                continue;
+            }
+        };
+        let path = path.clone();
+        tokio::spawn(async move {
+            if let Err(e) = answer(stream, &path).await {
+                debug!("Calendar feed connection from {} ended: {}", peer, e);
+            }
+        });
+    }
+}
+
+/// Answer one request and close the connection
+async fn answer(mut stream: TcpStream, path: &Path) -> Result<()> {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 || head.len() + n > MAX_REQUEST_HEAD {
+            return Ok(());
+        }
+        head.extend_from_slice(&buf[..n]);
+    }
+    let head = String::from_utf8_lossy(&head);
+    let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+    let method = request_line.next().unwrap_or_default();
+    let target = request_line.next().unwrap_or_default();
+
+    let (status, content_type, body) = if !is_local_host(host_header(&head)) {
+        (
+            "403 Forbidden",
+            "text/plain; charset=utf-8",
+            b"Host not allowed".to_vec(),
+        )
+    } else if target.split('?').next() != Some(ICS_FEED_PATH) {
+        (
+            "404 Not Found",
+            "text/plain; charset=utf-8",
+            b"Not found".to_vec(),
+        )
+    } else if method != "GET" && method != "HEAD" {
+        (
+            "405 Method Not Allowed",
+            "text/plain; charset=utf-8",
+            b"Read-only feed".to_vec(),
+        )
+    } else {
+        match tokio::fs::read(path).await {
+            Ok(ics) => ("200 OK", "text/calendar; charset=utf-8", ics),
+            Err(_) => (
+                "503 Service Unavailable",
+                "text/plain; charset=utf-8",
+                b"Feed not written yet".to_vec(),
+            ),
+        }
+    };
+
+    let mut response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    )
+    .into_bytes();
+    if method != "HEAD" {
+        response.extend_from_slice(&body);
+    }
+    stream.write_all(&response).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// The Host header of a request head
+fn host_header(head: &str) -> Option<&str> {
+    head.lines().skip(1).find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.trim()
+            .eq_ignore_ascii_case("host")
+            .then(|| value.trim())
+    })
+}
+
+/// Render events as one VCALENDAR
+///
+/// Only [`unique_events`] are written. With `busy_only`, events carry only
+/// their time and show as "Busy".
+pub fn render_calendar(
+    events: &[CalendarEvent],
+    name: &str,
+    busy_only: bool,
+    now: DateTime<Utc>,
+) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:{}", PRODID),
+        "CALSCALE:GREGORIAN".to_string(),
+        "METHOD:PUBLISH".to_string(),
+        format!("X-WR-CALNAME:{}", escape_text(name)),
+    ];
+
+    for event in unique_events(events) {
+        lines.extend(event_lines(event, busy_only, now));
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut out = String::new();
+    for line in &lines {
+        out.push_str(&fold_line(line));
+    }
+    out
+}
+
+/// The VEVENT lines of one event
+fn event_lines(event: &CalendarEvent, busy_only: bool, now: DateTime<Utc>) -> Vec<String> {
+    let mut lines = vec![
+        "BEGIN:VEVENT".to_string(),
+        // Instances of a recurring event share the iCalendar UID, so each
+        // is identified by its Google event ID instead
+        format!("UID:{}@groundeffect", event.google_event_id),
+        format!("DTSTAMP:{}", format_utc(now)),
+    ];
+    match (&event.start, &event.end) {
+        (EventTime::Date(start), end) => {
+            // DTEND is exclusive; an end on or before the start is one day
+            let end = end.as_date().max(*start + Duration::days(1));
+            lines.push(format!("DTSTART;VALUE=DATE:{}", start.format("%Y%m%d")));
+            lines.push(format!("DTEND;VALUE=DATE:{}", end.format("%Y%m%d")));
+        }
+        (EventTime::DateTime(start), end) => {
+            let end = end.as_datetime().filter(|e| e > start).unwrap_or(*start);
+            lines.push(format!("DTSTART:{}", format_utc(*start)));
+            lines.push(format!("DTEND:{}", format_utc(end)));
+        }
+    }
+    lines.push(format!(
+        "TRANSP:{}",
+        match event.transparency {
+            Transparency::Opaque => "OPAQUE",
+            Transparency::Transparent => "TRANSPARENT",
+        }
+    ));
+
+    if busy_only {
+        lines.push("SUMMARY:Busy".to_string());
+        lines.push("CLASS:PRIVATE".to_string());
+        lines.push("END:VEVENT".to_string());
+        return lines;
+    }
+
+    lines.push(format!("SUMMARY:{}", escape_text(&event.summary)));
+    if event.status == EventStatus::Tentative {
+        lines.push("STATUS:TENTATIVE".to_string());
+    } else {
+        lines.push("STATUS:CONFIRMED".to_string());
+    }
+    if matches!(
+        event.visibility,
+        Visibility::Private | Visibility::Confidential
+    ) {
+        lines.push("CLASS:PRIVATE".to_string());
+    }
+    if let Some(location) = event.location.as_deref().filter(|l| !l.is_empty()) {
+        lines.push(format!("LOCATION:{}", escape_text(location)));
+    }
+    if let Some(description) = event.description.as_deref().filter(|d| !d.is_empty()) {
+        lines.push(format!("DESCRIPTION:{}", escape_text(description)));
+    }
+    if let Some(conference) = &event.conference {
+        lines.push(format!("URL:{}", conference.join_url));
+    }
+    if let Some(organizer) = &event.organizer {
+        lines.push(format!(
+            "ORGANIZER{}:mailto:{}",
+            common_name(organizer.name.as_deref()),
+            organizer.email
+        ));
+    }
+    for attendee in &event.attendees {
+        let partstat = match attendee.response_status {
+            Some(AttendeeStatus::Accepted) => "ACCEPTED",
+            Some(AttendeeStatus::Declined) => "DECLINED",
+            Some(AttendeeStatus::Tentative) => "TENTATIVE",
+            Some(AttendeeStatus::NeedsAction) | None => "NEEDS-ACTION",
+        };
+        let role = if attendee.optional {
+            "OPT-PARTICIPANT"
+        } else {
+            "REQ-PARTICIPANT"
+        };
+        lines.push(format!(
+            "ATTENDEE{};ROLE={};PARTSTAT={}:mailto:{}",
+            common_name(attendee.name.as_deref()),
+            role,
+            partstat,
+            attendee.email
+        ));
+    }
+    lines.push("END:VEVENT".to_string());
+    lines
+}
+
+/// An event time as a dedup key: the date or the UTC instant
+fn time_key(time: &EventTime) -> String {
+    match time {
+        EventTime::Date(d) => d.format("%Y%m%d").to_string(),
+        EventTime::DateTime(dt) => format_utc(*dt),
+    }
+}
+
+/// `;CN=...` for a display name, quoted (names often hold commas)
+fn common_name(name: Option<&str>) -> String {
+    match name.map(str::trim).filter(|n| !n.is_empty()) {
+        Some(name) => format!(";CN=\"{}\"", name.replace('"', "'")),
+        None => String::new(),
+    }
+}
+
+/// A UTC date-time in iCalendar basic format
+fn format_utc(time: DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Escape a TEXT value (RFC 5545 3.3.11)
+fn escape_text(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            ';' => out.push_str("\\;"),
+            ',' => out.push_str("\\,"),
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Fold a content line into CRLF-terminated lines of at most 75 octets,
+/// continuation lines starting with a space, never splitting a character
+fn fold_line(line: &str) -> String {
+    let mut out = String::with_capacity(line.len() + 8);
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > MAX_LINE_OCTETS {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Attendee;
+    use chrono::{NaiveDate, TimeZone};
+
+    fn event(id: &str, uid: &str, start: EventTime, end: EventTime) -> CalendarEvent {
+        CalendarEvent {
+            id: id.to_string(),
+            account_id: "me@example.com".to_string(),
+            account_alias: None,
+            google_event_id: id.to_string(),
+            ical_uid: uid.to_string(),
+            etag: String::new(),
+            summary: format!("Meeting {}", id),
+            description: None,
+            location: None,
+            all_day: start.is_all_day(),
+            start,
+            end,
+            timezone: "UTC".to_string(),
+            recurrence_rule: None,
+            recurrence_id: None,
+            organizer: None,
+            attendees: vec![],
+            status: EventStatus::Confirmed,
+            transparency: Transparency::Opaque,
+            visibility: Visibility::Default,
+            color_id: None,
+            reminders: vec![],
+            conference: None,
+            embedding: None,
+            calendar_id: "primary".to_string(),
+            synced_at: Utc::now(),
+        }
+    }
+
+    fn at(hour: u32) -> EventTime {
+        EventTime::DateTime(Utc.with_ymd_and_hms(2024, 6, 3, hour, 0, 0).unwrap())
+    }
+
+    #[test]
+    fn test_feed_only_answers_local_hosts() {
+        let head = "GET /calendar.ics HTTP/1.1\r\nhost: 127.0.0.1:8790\r\n\r\n";
+        assert_eq!(host_header(head), Some("127.0.0.1:8790"));
+        assert!(is_local_host(host_header(head)));
+
+        let rebound = "GET /calendar.ics HTTP/1.1\r\nHost: evil.example:8790\r\n\r\n";
+        assert!(!is_local_host(host_header(rebound)));
+        assert!(is_local_host(host_header("GET / HTTP/1.0\r\n\r\n")));
+    }
+
+    #[test]
+    fn test_text_is_escaped_and_long_lines_folded() {
+        assert_eq!(escape_text("a,b;c\\d\r\ne"), "a\\,b\\;c\\\\d\\ne");
+
+        let line = format!("SUMMARY:{}", "é".repeat(60));
+        let folded = fold_line(&line);
+        assert!(folded.ends_with("\r\n"));
+        for part in folded.trim_end_matches("\r\n").split("\r\n") {
+            assert!(part.len() <= MAX_LINE_OCTETS, "{} octets", part.len());
+        }
+        assert_eq!(folded.replace("\r\n ", "").trim_end(), line);
+    }
+
+    #[test]
+    fn test_calendar_merges_accounts_and_skips_cancelled() {
+        let now = Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap();
+        let mut standup = event("a1", "standup@google.com", at(9), at(10));
+        standup.description = Some("Daily, quick".to_string());
+        standup.attendees = vec![Attendee {
+            email: "pat@example.com".to_string(),
+            name: Some("Pat, Lee".to_string()),
+            response_status: Some(AttendeeStatus::Accepted),
+            optional: true,
+        }];
+        let mut same_on_other_account = standup.clone();
+        same_on_other_account.google_event_id = "b1".to_string();
+        same_on_other_account.account_id = "work@example.com".to_string();
+        let mut cancelled = event("c1", "cancelled@google.com", at(11), at(12));
+        cancelled.status = EventStatus::Cancelled;
+        let holiday = event(
+            "d1",
+            "holiday@google.com",
+            EventTime::Date(NaiveDate::from_ymd_opt(2024, 6, 4).unwrap()),
+            EventTime::Date(NaiveDate::from_ymd_opt(2024, 6, 4).unwrap()),
+        );
+
+        let events = [standup, same_on_other_account, cancelled, holiday];
+        let ics = render_calendar(&events, "All calendars", false, now);
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 2);
+        assert!(ics.contains("UID:a1@groundeffect\r\n"));
+        assert!(ics.contains("DTSTART:20240603T090000Z\r\n"));
+        assert!(ics.contains("DESCRIPTION:Daily\\, quick\r\n"));
+        assert!(ics.replace("\r\n ", "").contains(
+            "ATTENDEE;CN=\"Pat, Lee\";ROLE=OPT-PARTICIPANT;PARTSTAT=ACCEPTED:mailto:pat@example.com\r\n"
+        ));
+        assert!(ics.contains("DTSTART;VALUE=DATE:20240604\r\nDTEND;VALUE=DATE:20240605\r\n"));
+        assert!(!ics.contains("Meeting c1"));
+
+        let busy = render_calendar(&events, "All calendars", true, now);
+        assert_eq!(busy.matches("SUMMARY:Busy").count(), 2);
+        assert!(!busy.contains("Meeting") && !busy.contains("ATTENDEE"));
+    }
+}
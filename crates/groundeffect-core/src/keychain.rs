@@ -7,12 +7,13 @@ use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
-use std::os::unix::fs::PermissionsExt;
 use std::path::PathBuf;
 use std::sync::LazyLock;
 use tracing::{debug, error, info};
 
+use crate::config::{restrict_to_owner, tokens_dir};
 use crate::error::{Error, Result};
+use crate::oauth::ServiceAccountCredentials;
 
 /// In-memory token cache to avoid repeated file reads
 static TOKEN_CACHE: LazyLock<RwLock<HashMap<String, OAuthTokens>>> =
@@ -32,6 +33,11 @@ pub struct OAuthTokens {
 
     /// Token scopes
     pub scopes: Vec<String>,
+
+    /// Service account credentials (domain-wide delegation). When set,
+    /// `refresh_token` is empty and access tokens are minted from the key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service_account: Option<ServiceAccountCredentials>,
 }
 
 impl OAuthTokens {
@@ -49,20 +55,24 @@ impl OAuthTokens {
     }
 }
 
-/// Get the tokens directory path (XDG: ~/.config/groundeffect/tokens)
-fn tokens_dir() -> PathBuf {
-    dirs::home_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join(".config")
-        .join("groundeffect")
-        .join("tokens")
+/// Sanitize an account ID for use as a file name (replace @ and . with _)
+pub(crate) fn account_file_stem(account_id: &str) -> String {
+    account_id.replace('@', "_at_").replace('.', "_")
+}
+
+/// Reverse `account_file_stem`: `name_at_domain_tld` -> `name@domain.tld`
+///
+/// Underscores in the local part are kept; those in the domain become dots.
+pub(crate) fn account_from_file_stem(stem: &str) -> Option<String> {
+    let at_pos = stem.find("_at_")?;
+    let local = &stem[..at_pos];
+    let domain = stem[at_pos + 4..].replace('_', ".");
+    Some(format!("{}@{}", local, domain))
 }
 
 /// Get the token file path for an account
 fn token_file_path(account_id: &str) -> PathBuf {
-    // Sanitize account_id for use as filename (replace @ and . with _)
-    let safe_name = account_id.replace('@', "_at_").replace('.', "_");
-    tokens_dir().join(format!("{}.json", safe_name))
+    tokens_dir().join(format!("{}.json", account_file_stem(account_id)))
 }
 
 /// Ensure the tokens directory exists with proper permissions
@@ -71,7 +81,7 @@ fn ensure_tokens_dir() -> Result<()> {
     if !dir.exists() {
         fs::create_dir_all(&dir)?;
         // Set directory permissions to 700 (owner rwx only)
-        fs::set_permissions(&dir, fs::Permissions::from_mode(0o700))?;
+        restrict_to_owner(&dir, 0o700)?;
     }
     Ok(())
 }
@@ -109,7 +119,7 @@ impl KeychainManager {
             })?;
 
             // Set file permissions to 600 (owner rw only)
-            fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).map_err(|e| {
+            restrict_to_owner(&path, 0o600).map_err(|e| {
                 error!("Failed to set token file permissions: {}", e);
                 Error::Token(format!("Failed to set permissions: {}", e))
             })?;
@@ -225,6 +235,7 @@ mod tests {
             refresh_token: "test".to_string(),
             expires_at: chrono::Utc::now().timestamp() + 3600, // 1 hour from now
             scopes: vec![],
+            service_account: None,
         };
         assert!(!tokens.is_expired());
         assert!(!tokens.is_definitely_expired());
@@ -234,6 +245,7 @@ mod tests {
             refresh_token: "test".to_string(),
             expires_at: chrono::Utc::now().timestamp() - 100, // 100 seconds ago
             scopes: vec![],
+            service_account: None,
         };
         assert!(expired_tokens.is_expired());
         assert!(expired_tokens.is_definitely_expired());
@@ -243,6 +255,7 @@ mod tests {
             refresh_token: "test".to_string(),
             expires_at: chrono::Utc::now().timestamp() + 60, // 1 minute from now
             scopes: vec![],
+            service_account: None,
         };
         assert!(soon_expired_tokens.is_expired()); // Within 5-minute grace period
         assert!(!soon_expired_tokens.is_definitely_expired());
@@ -253,4 +266,15 @@ mod tests {
         let path = token_file_path("test@example.com");
         assert!(path.to_string_lossy().contains("test_at_example_com.json"));
     }
+
+    #[test]
+    fn test_account_file_stem_round_trip() {
+        let stem = account_file_stem("first.last@example.co.uk");
+        assert_eq!(stem, "first_last_at_example_co_uk");
+        assert_eq!(
+            account_from_file_stem("user_at_example_com").as_deref(),
+            Some("user@example.com")
+        );
+        assert_eq!(account_from_file_stem("not-an-account"), None);
+    }
 }
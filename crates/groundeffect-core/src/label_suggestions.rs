@@ -0,0 +1,387 @@
+//! Label suggestions for unlabeled mail
+//!
+//! `email suggest-labels` groups recent received mail that has no user label
+//! in Gmail by embedding similarity, and proposes a label for each group:
+//! the sender domain most of it comes from, or else the words its subjects
+//! share. Each suggestion comes with example messages, and the ones picked
+//! can then be applied in Gmail.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::classify::cosine_similarity;
+use crate::db::Database;
+use crate::error::Result;
+use crate::models::Email;
+use crate::oauth::OAuthManager;
+use crate::sync::unlabeled_message_ids;
+
+/// Cosine similarity an email needs to a group's centroid to join it
+pub const DEFAULT_GROUP_SIMILARITY: f32 = 0.8;
+
+/// Groups smaller than this aren't suggested
+pub const DEFAULT_MIN_GROUP_SIZE: usize = 3;
+
+/// Most unlabeled messages asked of Gmail per account
+const MAX_CANDIDATES: usize = 2000;
+
+/// Example messages shown per suggestion
+const EXAMPLES_PER_SUGGESTION: usize = 3;
+
+/// Senders listed per suggestion
+const TOP_SENDERS: usize = 3;
+
+/// Share of a group one sender domain needs to name it
+const DOMAIN_SHARE: f32 = 0.6;
+
+/// Share of a group's subjects a word needs to name it
+const SUBJECT_WORD_SHARE: f32 = 0.4;
+
+/// Domains shared by unrelated people, which say nothing about a group
+const SHARED_DOMAINS: &[&str] = &[
+    "gmail.com",
+    "googlemail.com",
+    "outlook.com",
+    "hotmail.com",
+    "live.com",
+    "yahoo.com",
+    "icloud.com",
+    "me.com",
+    "aol.com",
+    "proton.me",
+    "protonmail.com",
+];
+
+/// Subject words too common to name a group
+const SUBJECT_STOPWORDS: &[&str] = &[
+    "the", "and", "for", "your", "you", "with", "from", "this", "that", "are", "has", "have",
+    "our", "new", "now", "fwd", "was", "will", "can", "all", "not", "out", "get", "just", "about",
+    "more", "here", "what", "how", "its",
+];
+
+/// An example message of a suggested group
+#[derive(Debug, Clone, Serialize)]
+pub struct LabelExample {
+    pub id: String,
+    pub from: String,
+    pub subject: String,
+    pub date: DateTime<Utc>,
+}
+
+/// A group of similar unlabeled emails and the label proposed for it
+#[derive(Debug, Clone, Serialize)]
+pub struct LabelSuggestion {
+    /// Position in the list (from 1), used to pick suggestions to apply
+    pub index: usize,
+
+    /// Proposed label name
+    pub label: String,
+
+    /// Emails in the group
+    pub size: usize,
+
+    /// Mean similarity of the emails to the group's centroid (0-1)
+    pub cohesion: f32,
+
+    /// Most frequent senders
+    pub top_senders: Vec<String>,
+
+    /// Newest emails of the group
+    pub examples: Vec<LabelExample>,
+
+    /// Every email in the group, newest first
+    pub email_ids: Vec<String>,
+
+    /// X-GM-MSGIDs of the emails, for labeling them in Gmail
+    #[serde(skip)]
+    pub gmail_message_ids: Vec<u64>,
+}
+
+/// Received mail of an account dated on or after `since` that has no user
+/// label in Gmail, with embeddings, newest first
+///
+/// Emails are matched to Gmail's answer by X-GM-MSGID. Mail stored before
+/// sync recorded X-GM-MSGIDs has none (0) and is left out until resynced.
+pub async fn unlabeled_emails(
+    db: &Database,
+    oauth: &OAuthManager,
+    account_id: &str,
+    since: DateTime<Utc>,
+) -> Result<Vec<Email>> {
+    let unlabeled = unlabeled_message_ids(oauth, account_id, since, MAX_CANDIDATES).await?;
+    let mut emails: Vec<Email> = db
+        .list_embedded_emails_since(account_id, since)
+        .await?
+        .into_iter()
+        .filter(|e| {
+            !e.is_sent && e.gmail_message_id != 0 && unlabeled.contains(&e.gmail_message_id)
+        })
+        .collect();
+    emails.sort_by(|a, b| b.date.cmp(&a.date));
+    Ok(emails)
+}
+
+/// Group emails by embedding similarity and propose a label per group
+///
+/// Emails join the group whose centroid they're most similar to when that
+/// reaches `similarity`, newest first, and start a group otherwise. Groups
+/// of fewer than `min_size` emails are dropped; the rest are ordered
+/// largest first. Emails without an embedding are skipped.
+pub fn suggest_labels(emails: &[Email], similarity: f32, min_size: usize) -> Vec<LabelSuggestion> {
+    let mut ordered: Vec<&Email> = emails.iter().filter(|e| e.embedding.is_some()).collect();
+    ordered.sort_by(|a, b| b.date.cmp(&a.date));
+
+    // Centroids are kept as sums; cosine similarity ignores the scale
+    let mut groups: Vec<(Vec<f32>, Vec<&Email>)> = Vec::new();
+    for email in ordered {
+        let embedding = email.embedding.as_deref().unwrap_or_default();
+        let best = groups
+            .iter()
+            .enumerate()
+            .map(|(i, (centroid, _))| (i, cosine_similarity(embedding, centroid)))
+            .filter(|(_, score)| *score >= similarity)
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+        match best {
+            Some((i, _)) => {
+                let (centroid, members) = &mut groups[i];
+                for (c, x) in centroid.iter_mut().zip(embedding) {
+                    *c += x;
+                }
+                members.push(email);
+            }
+            None => groups.push((embedding.to_vec(), vec![email])),
+        }
+    }
+
+    groups.retain(|(_, members)| members.len() >= min_size.max(1));
+    groups.sort_by(|a, b| b.1.len().cmp(&a.1.len()));
+
+    let mut used: HashSet<String> = HashSet::new();
+    groups
+        .iter()
+        .enumerate()
+        .map(|(i, (centroid, members))| {
+            let mut label = group_label(members).unwrap_or_else(|| format!("Group {}", i + 1));
+            let base = label.clone();
+            let mut n = 2;
+            while !used.insert(label.to_lowercase()) {
+                label = format!("{} {}", base, n);
+                n += 1;
+            }
+
+            let cohesion = members
+                .iter()
+                .map(|e| cosine_similarity(e.embedding.as_deref().unwrap_or_default(), centroid))
+                .sum::<f32>()
+                / members.len() as f32;
+
+            LabelSuggestion {
+                index: i + 1,
+                label,
+                size: members.len(),
+                cohesion,
+                top_senders: top_senders(members),
+                examples: members
+                    .iter()
+                    .take(EXAMPLES_PER_SUGGESTION)
+                    .map(|e| LabelExample {
+                        id: e.id.clone(),
+                        from: e.from.to_string(),
+                        subject: e.subject.clone(),
+                        date: e.date,
+                    })
+                    .collect(),
+                email_ids: members.iter().map(|e| e.id.clone()).collect(),
+                gmail_message_ids: members
+                    .iter()
+                    .map(|e| e.gmail_message_id)
+                    .filter(|id| *id != 0)
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+/// Name for a group: its main sender's organization, else the words most
+/// of its subjects share
+fn group_label(members: &[&Email]) -> Option<String> {
+    let total = members.len() as f32;
+
+    let mut domains: HashMap<String, usize> = HashMap::new();
+    for email in members {
+        let domain = email
+            .from
+            .email
+            .rsplit_once('@')
+            .map(|(_, d)| d.to_lowercase())
+            .unwrap_or_default();
+        if !domain.is_empty() && !SHARED_DOMAINS.contains(&domain.as_str()) {
+            *domains.entry(domain).or_default() += 1;
+        }
+    }
+    let top_domain = domains
+        .iter()
+        .max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0)));
+    if let Some((domain, count)) = top_domain {
+        if *count as f32 >= total * DOMAIN_SHARE {
+            return Some(capitalize(organization(domain)));
+        }
+    }
+
+    let mut words: HashMap<String, usize> = HashMap::new();
+    for email in members {
+        let subject_words: HashSet<String> = email
+            .subject
+            .to_lowercase()
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| w.chars().count() >= 3 && !w.chars().all(|c| c.is_ascii_digit()))
+            .filter(|w| !SUBJECT_STOPWORDS.contains(w))
+            .map(str::to_string)
+            .collect();
+        for word in subject_words {
+            *words.entry(word).or_default() += 1;
+        }
+    }
+    let mut shared: Vec<(String, usize)> = words
+        .into_iter()
+        .filter(|(_, count)| *count as f32 >= total * SUBJECT_WORD_SHARE)
+        .collect();
+    shared.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    if shared.is_empty() {
+        return None;
+    }
+    Some(
+        shared
+            .iter()
+            .take(2)
+            .map(|(word, _)| capitalize(word))
+            .collect::<Vec<_>>()
+            .join(" "),
+    )
+}
+
+/// The registered part of a domain ("mail.github.com" -> "github",
+/// "shop.example.co.uk" -> "example")
+fn organization(domain: &str) -> &str {
+    let parts: Vec<&str> = domain.split('.').collect();
+    match parts.len() {
+        0 | 1 => domain,
+        n if n >= 3
+            && parts[n - 1].len() == 2
+            && ["co", "com", "org", "net", "ac", "gov"].contains(&parts[n - 2]) =>
+        {
+            parts[n - 3]
+        }
+        n => parts[n - 2],
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Most frequent sender addresses of a group
+fn top_senders(members: &[&Email]) -> Vec<String> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for email in members {
+        *counts.entry(email.from.email.to_lowercase()).or_default() += 1;
+    }
+    let mut senders: Vec<(String, usize)> = counts.into_iter().collect();
+    senders.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    senders
+        .into_iter()
+        .take(TOP_SENDERS)
+        .map(|(sender, _)| sender)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Address;
+    use chrono::{Duration, TimeZone};
+
+    fn email(n: i64, from: &str, subject: &str, embedding: Vec<f32>) -> Email {
+        Email {
+            gmail_message_id: n as u64,
+            gmail_thread_id: n as u64,
+            uid: n as u32,
+            to: vec![Address::new("me@example.com")],
+            date: Utc.with_ymd_and_hms(2024, 6, 1, 9, 0, 0).unwrap() + Duration::hours(n),
+            embedding: Some(embedding),
+            ..Email::test(&format!("<{}@x>", n), from, subject)
+        }
+    }
+
+    #[test]
+    fn test_groups_by_similarity() {
+        let emails = vec![
+            email(1, "billing@stripe.com", "Your receipt", vec![1.0, 0.0, 0.0]),
+            email(
+                2,
+                "billing@stripe.com",
+                "Your receipt",
+                vec![0.95, 0.1, 0.0],
+            ),
+            email(3, "support@eu.stripe.com", "Invoice", vec![0.9, 0.05, 0.1]),
+            email(
+                4,
+                "ann@gmail.com",
+                "Soccer practice moved",
+                vec![0.0, 1.0, 0.0],
+            ),
+            email(
+                5,
+                "bob@yahoo.com",
+                "Soccer practice snacks",
+                vec![0.1, 0.95, 0.0],
+            ),
+            email(
+                6,
+                "cat@hotmail.com",
+                "Practice schedule",
+                vec![0.0, 0.9, 0.1],
+            ),
+            email(7, "dan@example.org", "Lunch?", vec![0.0, 0.0, 1.0]),
+        ];
+        let suggestions = suggest_labels(&emails, 0.8, 3);
+
+        assert_eq!(suggestions.len(), 2);
+        assert_eq!(suggestions[0].label, "Practice Soccer");
+        assert_eq!(suggestions[1].label, "Stripe");
+        assert_eq!(suggestions[1].index, 2);
+        assert_eq!(suggestions[1].size, 3);
+        assert_eq!(suggestions[1].top_senders[0], "billing@stripe.com");
+        assert_eq!(suggestions[1].examples[0].id, "me@example.com:<3@x>");
+        assert_eq!(suggestions[1].gmail_message_ids, vec![3, 2, 1]);
+        assert!(suggestions[1].cohesion > 0.9);
+    }
+
+    #[test]
+    fn test_labels_are_unique() {
+        let emails = vec![
+            email(1, "a@acme.io", "x", vec![1.0, 0.0]),
+            email(2, "b@acme.io", "y", vec![1.0, 0.0]),
+            email(3, "a@acme.io", "z", vec![0.0, 1.0]),
+            email(4, "b@acme.io", "w", vec![0.0, 1.0]),
+        ];
+        let labels: Vec<String> = suggest_labels(&emails, 0.8, 2)
+            .into_iter()
+            .map(|s| s.label)
+            .collect();
+        assert_eq!(labels, vec!["Acme", "Acme 2"]);
+    }
+
+    #[test]
+    fn test_organization() {
+        assert_eq!(organization("mail.github.com"), "github");
+        assert_eq!(organization("shop.example.co.uk"), "example");
+        assert_eq!(organization("localhost"), "localhost");
+    }
+}
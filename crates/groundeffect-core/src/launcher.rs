@@ -0,0 +1,148 @@
+//! Launcher integrations (Alfred, Raycast)
+//!
+//! `groundeffect quick` answers a launcher's script filter: email search
+//! results as compact items, each with a title, a subtitle and an `arg`
+//! (the email ID) that the launcher hands to the follow-up action,
+//! `groundeffect quick --open <id>`, which opens the message in Gmail on
+//! the web. The items use Alfred's script filter format, which Raycast
+//! script commands can read as well.
+
+use chrono_tz::Tz;
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::models::{gmail_message_link, split_email_id, EmailSearchResult};
+
+/// One result row in a launcher
+#[derive(Debug, Clone, Serialize)]
+pub struct LauncherItem {
+    /// Stable ID, so launchers can learn which results get picked
+    pub uid: String,
+
+    /// The subject
+    pub title: String,
+
+    /// Sender, date and account
+    pub subtitle: String,
+
+    /// Email ID, passed to `groundeffect quick --open`
+    pub arg: String,
+
+    /// The message in Gmail on the web (Alfred shows it on ⇧ / ⌘Y)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quicklookurl: Option<String>,
+}
+
+/// Script filter output: `{"items": [...]}`
+#[derive(Debug, Clone, Serialize)]
+pub struct LauncherItems {
+    pub items: Vec<LauncherItem>,
+}
+
+/// Launcher items for email search results, with dates in `tz`
+pub fn email_items(results: &[EmailSearchResult], config: &Config, tz: Tz) -> LauncherItems {
+    let items = results
+        .iter()
+        .map(|result| {
+            let email = &result.email;
+            let from = email
+                .from
+                .name
+                .as_deref()
+                .map(str::trim)
+                .filter(|n| !n.is_empty())
+                .unwrap_or(&email.from.email);
+            let account = email
+                .account_alias
+                .as_deref()
+                .or_else(|| config.get_alias(&email.account_id))
+                .unwrap_or(&email.account_id);
+            let title = match email.subject.trim() {
+                "" => "(no subject)".to_string(),
+                subject => subject.to_string(),
+            };
+            LauncherItem {
+                uid: email.id.clone(),
+                title,
+                subtitle: format!(
+                    "{} · {} · {}",
+                    from,
+                    email.date.with_timezone(&tz).format("%b %-d, %Y"),
+                    account
+                ),
+                arg: email.id.clone(),
+                quicklookurl: gmail_web_url(&email.id),
+            }
+        })
+        .collect();
+    LauncherItems { items }
+}
+
+/// Link that opens an email in Gmail on the web, signed in as its account
+///
+/// Built from the email ID (`<account>:<Message-ID>`) alone, so opening a
+/// result needs no database lookup. None if `email_id` isn't an email ID.
+pub fn gmail_web_url(email_id: &str) -> Option<String> {
+    let (account, message_id) = split_email_id(email_id)?;
+    Some(gmail_message_link(account, &message_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Address, EmailSummary};
+    use crate::search::ScoreComponents;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn test_gmail_web_url() {
+        assert_eq!(
+            gmail_web_url("me@example.com:<CAF=ab+c@mail.gmail.com>").as_deref(),
+            Some(
+                "https://mail.google.com/mail/u/me%40example.com/#search/rfc822msgid%3ACAF%3Dab%2Bc%40mail.gmail.com"
+            )
+        );
+        assert_eq!(gmail_web_url("18c2f1a9d3e4b5f6"), None);
+    }
+
+    #[test]
+    fn test_email_items() {
+        let mut config = Config::default();
+        config
+            .accounts
+            .aliases
+            .insert("work".to_string(), "me@example.com".to_string());
+        let result = EmailSearchResult {
+            email: EmailSummary {
+                id: "me@example.com:<1@x>".to_string(),
+                account_id: "me@example.com".to_string(),
+                account_alias: None,
+                message_id: "<1@x>".to_string(),
+                thread_id: "1".to_string(),
+                from: Address::with_name(" ", "pat@example.com"),
+                to: vec![],
+                subject: String::new(),
+                date: Utc.with_ymd_and_hms(2024, 6, 3, 2, 0, 0).unwrap(),
+                snippet: String::new(),
+                snippet_html: None,
+                has_attachments: false,
+                web_link: String::new(),
+                attachments: vec![],
+                labels: vec![],
+                is_sent: false,
+                category: None,
+                tags: vec![],
+                security_warnings: vec![],
+            },
+            score: 1.0,
+            score_components: ScoreComponents::default(),
+            markdown_summary: String::new(),
+        };
+
+        let items = email_items(&[result], &config, chrono_tz::America::Los_Angeles).items;
+        assert_eq!(items[0].title, "(no subject)");
+        assert_eq!(items[0].subtitle, "pat@example.com · Jun 2, 2024 · work");
+        assert_eq!(items[0].arg, "me@example.com:<1@x>");
+        assert!(items[0].quicklookurl.is_some());
+    }
+}
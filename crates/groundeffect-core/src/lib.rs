@@ -3,24 +3,66 @@
 //! High-performance email and calendar sync with LanceDB storage
 //! and MCP server for Claude Code integration.
 
+pub mod activity;
+#[cfg(feature = "apple-bridge")]
+pub mod apple;
+pub mod availability;
+pub mod bounces;
+pub mod classify;
 pub mod config;
+pub mod config_layers;
+pub mod control;
+pub mod dav;
 pub mod db;
+pub mod digest;
+pub mod drafts;
 pub mod embedding;
 pub mod error;
+pub mod extractions;
+pub mod forward;
+pub mod fts;
+pub mod health;
+pub mod ics;
 pub mod keychain;
+pub mod label_suggestions;
+pub mod launcher;
+pub mod lint;
+pub mod logs;
 pub mod mcp;
+pub mod metrics;
 pub mod models;
+pub mod network;
 pub mod oauth;
+pub mod raw;
+pub mod receipts;
+pub mod recovery;
+pub mod reload;
+pub mod relocate;
+pub mod rename;
+pub mod render;
+pub mod retention;
+pub mod scheduling;
 pub mod search;
+pub mod security;
+pub mod signatures;
+pub mod spotlight;
 pub mod sync;
+pub mod sync_window;
+pub mod tagging;
+pub mod tasks;
+pub mod threads;
+pub mod token_health;
 pub mod token_provider;
+pub mod travel;
+pub mod unanswered;
+pub mod vectors;
 
 pub use config::Config;
 pub use error::{Error, Result};
 pub use models::*;
 pub use token_provider::{create_token_provider, TokenProvider};
 
-/// Vector dimension for embeddings (768 for bge-base-en-v1.5)
+/// Default width of new vector columns (bge-base-en-v1.5, the default model)
 pub const EMBEDDING_DIMENSION: usize = 768;
 
 /// Application name for config paths
@@ -0,0 +1,246 @@
+//! Pre-send formatting lint for outgoing mail
+//!
+//! Catches formatting patterns that commonly trip spam filters or break
+//! DKIM-signed rendering: HTML mail without a usable plain-text part, links
+//! whose visible text points somewhere other than the href, oversized inline
+//! images, and shouted subjects.
+
+use regex::Regex;
+use serde::Serialize;
+
+use crate::config::ComposeConfig;
+
+/// Subjects shorter than this (in letters) are never flagged as all-caps
+const ALL_CAPS_MIN_LETTERS: usize = 8;
+
+/// Share of uppercase letters at which a subject counts as all-caps
+const ALL_CAPS_RATIO: f32 = 0.8;
+
+/// Inline images this many times over the limit are severe rather than warnings
+const SEVERE_IMAGE_MULTIPLIER: u64 = 10;
+
+/// How serious a lint finding is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LintSeverity {
+    /// Likely to hurt deliverability; shown in the preview
+    Warning,
+    /// Likely to be rejected or flagged as phishing; can block sending
+    Severe,
+}
+
+/// A single lint finding
+#[derive(Debug, Clone, Serialize)]
+pub struct LintIssue {
+    /// Stable identifier (e.g. "link_text_mismatch")
+    pub code: &'static str,
+    pub severity: LintSeverity,
+    pub message: String,
+}
+
+impl LintIssue {
+    fn new(code: &'static str, severity: LintSeverity, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            severity,
+            message: message.into(),
+        }
+    }
+}
+
+/// Lint an outgoing message as it will be sent.
+///
+/// `html_body` is the rendered HTML part (None for plain-text mail) and
+/// `plain_body` the plain-text part that accompanies it.
+pub fn lint_outgoing(
+    subject: &str,
+    plain_body: &str,
+    html_body: Option<&str>,
+    config: &ComposeConfig,
+) -> Vec<LintIssue> {
+    let mut issues = Vec::new();
+
+    if is_all_caps(subject) {
+        issues.push(LintIssue::new(
+            "all_caps_subject",
+            LintSeverity::Warning,
+            "Subject is written in all caps, which spam filters penalize",
+        ));
+    }
+
+    if let Some(html) = html_body {
+        if plain_body.trim().is_empty() {
+            issues.push(LintIssue::new(
+                "missing_plain_text",
+                LintSeverity::Severe,
+                "HTML message has no readable plain-text part",
+            ));
+        }
+        issues.extend(check_links(html));
+        issues.extend(check_inline_images(html, config.max_inline_image_kb));
+    }
+
+    issues
+}
+
+/// Whether any issue is severe enough to block sending
+pub fn has_severe(issues: &[LintIssue]) -> bool {
+    issues.iter().any(|i| i.severity == LintSeverity::Severe)
+}
+
+fn is_all_caps(subject: &str) -> bool {
+    let letters: Vec<char> = subject.chars().filter(|c| c.is_alphabetic()).collect();
+    if letters.len() < ALL_CAPS_MIN_LETTERS {
+        return false;
+    }
+    let upper = letters.iter().filter(|c| c.is_uppercase()).count();
+    upper as f32 / letters.len() as f32 >= ALL_CAPS_RATIO
+}
+
+/// Extract the host from a URL or bare domain, lowercased and without "www."
+fn link_host(text: &str) -> Option<String> {
+    let text = text.trim().to_lowercase();
+    if text.is_empty() || text.contains(char::is_whitespace) {
+        return None;
+    }
+    let rest = text
+        .strip_prefix("https://")
+        .or_else(|| text.strip_prefix("http://"))
+        .unwrap_or(&text);
+    let host = rest
+        .split(|c| c == '/' || c == '?' || c == '#' || c == ':')
+        .next()?;
+    let host = host.strip_prefix("www.").unwrap_or(host);
+
+    let valid_chars = host
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-');
+    let valid_tld = host
+        .rsplit('.')
+        .next()
+        .map(|tld| tld.len() >= 2 && tld.chars().all(|c| c.is_ascii_alphabetic()))
+        .unwrap_or(false);
+    if host.contains('.') && valid_chars && valid_tld {
+        Some(host.to_string())
+    } else {
+        None
+    }
+}
+
+fn check_links(html: &str) -> Vec<LintIssue> {
+    let anchor = Regex::new(r#"(?is)<a\s[^>]*href\s*=\s*["']([^"']+)["'][^>]*>(.*?)</a>"#).unwrap();
+    let tag = Regex::new(r"<[^>]+>").unwrap();
+
+    let mut issues = Vec::new();
+    for cap in anchor.captures_iter(html) {
+        let href = &cap[1];
+        let text = tag.replace_all(&cap[2], "");
+        let (Some(text_host), Some(href_host)) = (link_host(&text), link_host(href)) else {
+            continue;
+        };
+        let same_site = text_host == href_host
+            || text_host.ends_with(&format!(".{}", href_host))
+            || href_host.ends_with(&format!(".{}", text_host));
+        if !same_site {
+            issues.push(LintIssue::new(
+                "link_text_mismatch",
+                LintSeverity::Severe,
+                format!(
+                    "Link text shows {} but points to {}",
+                    text.trim(),
+                    href_host
+                ),
+            ));
+        }
+    }
+    issues
+}
+
+fn check_inline_images(html: &str, max_kb: u64) -> Vec<LintIssue> {
+    let data_uri =
+        Regex::new(r#"(?i)src\s*=\s*["']data:image/[a-z0-9.+-]+;base64,([^"']*)["']"#).unwrap();
+
+    let mut issues = Vec::new();
+    for cap in data_uri.captures_iter(html) {
+        // Base64 encodes 3 bytes in 4 characters
+        let size_kb = (cap[1].len() as u64 * 3 / 4) / 1024;
+        if size_kb <= max_kb {
+            continue;
+        }
+        let severity = if size_kb > max_kb * SEVERE_IMAGE_MULTIPLIER {
+            LintSeverity::Severe
+        } else {
+            LintSeverity::Warning
+        };
+        issues.push(LintIssue::new(
+            "large_inline_image",
+            severity,
+            format!(
+                "Inline image is {} KB (limit {} KB); attach or link it instead",
+                size_kb, max_kb
+            ),
+        ));
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lint(subject: &str, plain: &str, html: Option<&str>) -> Vec<LintIssue> {
+        lint_outgoing(subject, plain, html, &ComposeConfig::default())
+    }
+
+    #[test]
+    fn clean_message_has_no_issues() {
+        let html = r#"See <a href="https://example.com/docs">https://example.com/docs</a>"#;
+        assert!(lint(
+            "Quarterly update",
+            "See https://example.com/docs",
+            Some(html)
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn flags_all_caps_subject() {
+        let issues = lint("URGENT ACTION REQUIRED", "hello", None);
+        assert_eq!(issues[0].code, "all_caps_subject");
+        assert!(!has_severe(&issues));
+        assert!(lint("FYI", "hello", None).is_empty());
+    }
+
+    #[test]
+    fn flags_missing_plain_text() {
+        let issues = lint(
+            "Hello",
+            "  ",
+            Some("<img src=\"https://example.com/a.png\">"),
+        );
+        assert!(issues.iter().any(|i| i.code == "missing_plain_text"));
+        assert!(has_severe(&issues));
+    }
+
+    #[test]
+    fn flags_link_text_mismatch() {
+        let html = r#"<a href="https://evil.example.net/login">https://mybank.com</a>"#;
+        let issues = lint("Hello", "text", Some(html));
+        assert_eq!(issues[0].code, "link_text_mismatch");
+    }
+
+    #[test]
+    fn allows_subdomain_links_and_plain_text_labels() {
+        let html = r#"<a href="https://docs.example.com/x">example.com</a> <a href="https://t.co/abc">click here</a>"#;
+        assert!(lint("Hello", "text", Some(html)).is_empty());
+    }
+
+    #[test]
+    fn flags_large_inline_image() {
+        let payload = "A".repeat(200 * 1024);
+        let html = format!("<img src=\"data:image/png;base64,{}\">", payload);
+        let issues = lint("Hello", "text", Some(&html));
+        assert_eq!(issues[0].code, "large_inline_image");
+        assert_eq!(issues[0].severity, LintSeverity::Warning);
+    }
+}
@@ -0,0 +1,344 @@
+//! Reading the daemon and MCP server logs
+//!
+//! With logging enabled, the daemon writes `daemon.log` and the MCP server
+//! `mcp.log` in the log directory, rotated daily (`daemon.log.2024-06-03`).
+//! Lines are text (`2024-06-03 09:00:00.123  INFO ThreadId(02)
+//! groundeffect_core::sync: message`, in local time) or, with
+//! `log_format = "json"` under `[general]`, one JSON object per line.
+//! [`read`] parses both, so `groundeffect logs` can filter by time, level
+//! and account without grepping the raw files.
+
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tracing::Level;
+
+use crate::error::Result;
+
+/// Log file names, before the date suffix rotation adds
+pub const LOG_FILES: [&str; 2] = ["daemon.log", "mcp.log"];
+
+/// Timestamp format of text log lines (local time)
+pub const TEXT_TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.3f";
+
+/// One log event
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LogEntry {
+    pub timestamp: DateTime<Utc>,
+
+    /// TRACE, DEBUG, INFO, WARN or ERROR
+    pub level: String,
+
+    /// Which log it's from: "daemon" or "mcp"
+    pub source: String,
+
+    /// Module that logged it
+    pub target: String,
+
+    pub message: String,
+
+    /// Structured fields besides the message (JSON logs only)
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    pub fields: Map<String, Value>,
+}
+
+/// Which entries [`read`] returns
+#[derive(Debug, Clone, Default)]
+pub struct LogQuery {
+    /// Only entries at or after this time
+    pub since: Option<DateTime<Utc>>,
+
+    /// Only entries at this level or more severe
+    pub level: Option<Level>,
+
+    /// Only entries mentioning this text (an account's address),
+    /// case-insensitively
+    pub account: Option<String>,
+
+    /// Only this log ("daemon" or "mcp")
+    pub source: Option<String>,
+
+    /// Only the newest this many
+    pub limit: Option<usize>,
+}
+
+impl LogQuery {
+    /// Whether `entry` passes the time, level, account and source filters
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        if self.since.is_some_and(|since| entry.timestamp < since) {
+            return false;
+        }
+        if self.source.as_ref().is_some_and(|s| *s != entry.source) {
+            return false;
+        }
+        if let Some(level) = self.level {
+            // More verbose levels compare greater
+            if parse_level(&entry.level).is_none_or(|l| l > level) {
+                return false;
+            }
+        }
+        if let Some(account) = &self.account {
+            let account = account.to_lowercase();
+            let mentions = |text: &str| text.to_lowercase().contains(&account);
+            if !mentions(&entry.message) && !entry.fields.values().any(|v| mentions(&v.to_string()))
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// A level name such as "warn" or "ERROR"
+pub fn parse_level(value: &str) -> Option<Level> {
+    value.trim().parse().ok()
+}
+
+/// Entries in the log files under `dir` that match `query`, oldest first
+pub fn read(dir: &Path, query: &LogQuery) -> Result<Vec<LogEntry>> {
+    let mut entries = Vec::new();
+    for (path, source) in log_files(dir, query.since)? {
+        if query.source.as_ref().is_some_and(|s| *s != source) {
+            continue;
+        }
+        let contents = std::fs::read_to_string(&path)?;
+        entries.extend(
+            parse_lines(&contents, &source)
+                .into_iter()
+                .filter(|e| query.matches(e)),
+        );
+    }
+    entries.sort_by_key(|e| e.timestamp);
+    if let Some(limit) = query.limit {
+        entries.drain(..entries.len().saturating_sub(limit));
+    }
+    Ok(entries)
+}
+
+/// Log files under `dir` with their source, skipping days before `since`
+pub fn log_files(dir: &Path, since: Option<DateTime<Utc>>) -> Result<Vec<(PathBuf, String)>> {
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+    // Files are named for the UTC day they cover
+    let first_day = since.map(|s| s.date_naive() - Duration::days(1));
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        for file in LOG_FILES {
+            let Some(rest) = name.strip_prefix(file) else {
+                continue;
+            };
+            let day = match rest.strip_prefix('.') {
+                Some(date) => match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+                    Ok(day) => Some(day),
+                    Err(_) => continue,
+                },
+                None if rest.is_empty() => None,
+                None => continue,
+            };
+            if day.zip(first_day).is_some_and(|(day, first)| day < first) {
+                continue;
+            }
+            let source = file.trim_end_matches(".log").to_string();
+            files.push((path.clone(), source));
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Parse a log file's contents; lines that don't start an entry (a
+/// multi-line message or backtrace) continue the one before
+pub fn parse_lines(contents: &str, source: &str) -> Vec<LogEntry> {
+    let mut entries: Vec<LogEntry> = Vec::new();
+    for line in contents.lines() {
+        match parse_line(line, source) {
+            Some(entry) => entries.push(entry),
+            None => {
+                if let Some(last) = entries.last_mut() {
+                    last.message.push('\n');
+                    last.message.push_str(line);
+                }
+            }
+        }
+    }
+    entries
+}
+
+/// Parse one text or JSON log line
+pub fn parse_line(line: &str, source: &str) -> Option<LogEntry> {
+    if line.starts_with('{') {
+        parse_json_line(line, source)
+    } else {
+        parse_text_line(line, source)
+    }
+}
+
+fn parse_json_line(line: &str, source: &str) -> Option<LogEntry> {
+    let Value::Object(mut object) = serde_json::from_str(line).ok()? else {
+        return None;
+    };
+    let timestamp = parse_timestamp(object.get("timestamp")?.as_str()?)?;
+    let level = object.get("level")?.as_str()?.to_uppercase();
+    let target = object
+        .get("target")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    let mut fields = match object.remove("fields") {
+        Some(Value::Object(fields)) => fields,
+        _ => Map::new(),
+    };
+    let message = match fields.remove("message") {
+        Some(Value::String(message)) => message,
+        Some(other) => other.to_string(),
+        None => String::new(),
+    };
+    Some(LogEntry {
+        timestamp,
+        level,
+        source: source.to_string(),
+        target,
+        message,
+        fields,
+    })
+}
+
+/// `2024-06-03 09:00:00.123  INFO ThreadId(02) target: message`
+fn parse_text_line(line: &str, source: &str) -> Option<LogEntry> {
+    let timestamp_len = "2024-06-03 09:00:00.123".len();
+    let timestamp = parse_timestamp(line.get(..timestamp_len)?)?;
+    let rest = line[timestamp_len..].trim_start();
+    let (level, rest) = rest.split_once(' ')?;
+    parse_level(level)?;
+    let rest = rest.trim_start();
+    let rest = match rest.strip_prefix("ThreadId(") {
+        Some(after) => after.split_once(") ")?.1,
+        None => rest,
+    };
+    let (target, message) = match rest.split_once(": ") {
+        Some((target, message)) if !target.contains(' ') => (target, message),
+        _ => ("", rest),
+    };
+    Some(LogEntry {
+        timestamp,
+        level: level.to_string(),
+        source: source.to_string(),
+        target: target.to_string(),
+        message: message.to_string(),
+        fields: Map::new(),
+    })
+}
+
+/// An RFC 3339 timestamp, or a text log's local one
+fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Some(timestamp.with_timezone(&Utc));
+    }
+    let naive = NaiveDateTime::parse_from_str(value, TEXT_TIMESTAMP_FORMAT).ok()?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_text_and_json_lines() {
+        let text = parse_line(
+            "2024-06-03 09:00:00.123  WARN ThreadId(02) groundeffect_core::sync: Sync failed for me@example.com: timeout",
+            "daemon",
+        )
+        .unwrap();
+        assert_eq!(text.level, "WARN");
+        assert_eq!(text.target, "groundeffect_core::sync");
+        assert_eq!(text.message, "Sync failed for me@example.com: timeout");
+
+        let json = parse_line(
+            r#"{"timestamp":"2024-06-03T09:00:00.123+02:00","level":"ERROR","fields":{"message":"IDLE dropped","account":"me@example.com"},"target":"groundeffect_core::sync::imap","threadId":"ThreadId(3)"}"#,
+            "mcp",
+        )
+        .unwrap();
+        assert_eq!(
+            json.timestamp,
+            Utc.with_ymd_and_hms(2024, 6, 3, 7, 0, 0).unwrap() + Duration::milliseconds(123)
+        );
+        assert_eq!(json.level, "ERROR");
+        assert_eq!(json.source, "mcp");
+        assert_eq!(json.message, "IDLE dropped");
+        assert_eq!(json.fields["account"], "me@example.com");
+
+        assert!(parse_line("   at src/main.rs:10", "daemon").is_none());
+        assert!(parse_line("2024-06-03 09:00:00.123 LOUD hi", "daemon").is_none());
+    }
+
+    #[test]
+    fn test_query_filters() {
+        let contents = "\
+2024-06-03 09:00:00.000  INFO ThreadId(01) groundeffect_daemon: Starting
+2024-06-03 09:05:00.000  WARN ThreadId(02) groundeffect_core::sync: Sync failed for Me@Example.com: timeout
+backtrace line
+2024-06-03 09:10:00.000 ERROR ThreadId(02) groundeffect_core::sync: Sync failed for other@example.com: 401";
+        let entries = parse_lines(contents, "daemon");
+        assert_eq!(entries.len(), 3);
+        assert!(entries[1].message.ends_with("timeout\nbacktrace line"));
+
+        let query = LogQuery {
+            level: parse_level("warn"),
+            account: Some("me@example.com".to_string()),
+            ..Default::default()
+        };
+        let matched: Vec<_> = entries.iter().filter(|e| query.matches(e)).collect();
+        assert_eq!(matched, vec![&entries[1]]);
+
+        let query = LogQuery {
+            since: Some(entries[2].timestamp),
+            ..Default::default()
+        };
+        assert_eq!(entries.iter().filter(|e| query.matches(e)).count(), 1);
+    }
+
+    #[test]
+    fn test_read_rotated_files() {
+        let dir = std::env::temp_dir().join(format!("ge-logs-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let line = |time: &str, message: &str| {
+            format!(
+                "2024-06-03 {}.000  INFO ThreadId(01) groundeffect: {}\n",
+                time, message
+            )
+        };
+        std::fs::write(
+            dir.join("daemon.log.2024-06-03"),
+            line("09:00:00", "one") + &line("09:02:00", "three"),
+        )
+        .unwrap();
+        std::fs::write(dir.join("mcp.log.2024-06-03"), line("09:01:00", "two")).unwrap();
+        std::fs::write(dir.join("daemon.log.2024-05-01"), line("09:00:00", "old")).unwrap();
+        std::fs::write(dir.join("stdout.log"), line("09:00:00", "other")).unwrap();
+
+        let since = Utc.with_ymd_and_hms(2024, 6, 2, 0, 0, 0).unwrap();
+        let query = LogQuery {
+            since: Some(since),
+            limit: Some(2),
+            ..Default::default()
+        };
+        let messages: Vec<String> = read(&dir, &query)
+            .unwrap()
+            .into_iter()
+            .map(|e| format!("{}:{}", e.source, e.message))
+            .collect();
+        assert_eq!(messages, vec!["mcp:two", "daemon:three"]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
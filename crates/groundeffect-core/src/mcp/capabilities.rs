@@ -0,0 +1,225 @@
+//! Capabilities an MCP client may be granted (`[mcp] allowed_capabilities`)
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A group of MCP tool calls that can be allowed or withheld
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum McpCapability {
+    /// Search and read mail, drafts, calendars, accounts and sync status
+    Read,
+    /// Create, update and delete drafts
+    Draft,
+    /// Send email (including sending drafts)
+    Send,
+    /// Create and update calendar events
+    Calendar,
+    /// Add/delete/configure accounts, change sync, control the daemon, and
+    /// change stored mail (imports, tasks)
+    Manage,
+}
+
+impl McpCapability {
+    /// Every capability (the default)
+    pub const ALL: &'static [McpCapability] = &[
+        Self::Read,
+        Self::Draft,
+        Self::Send,
+        Self::Calendar,
+        Self::Manage,
+    ];
+
+    /// Stable lowercase name (as written in config.toml)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::Draft => "draft",
+            Self::Send => "send",
+            Self::Calendar => "calendar",
+            Self::Manage => "manage",
+        }
+    }
+
+    /// Parse a capability name (case-insensitive)
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "read" => Some(Self::Read),
+            "draft" => Some(Self::Draft),
+            "send" => Some(Self::Send),
+            "calendar" => Some(Self::Calendar),
+            "manage" => Some(Self::Manage),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for McpCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Capabilities under which a tool can be called at all
+///
+/// A tool is advertised in tools/list when any of these is allowed; whether a
+/// particular call goes through is decided by [`required_capability`].
+/// Unknown tools have none, so a new tool stays hidden until classified.
+pub fn tool_capabilities(tool: &str) -> &'static [McpCapability] {
+    use McpCapability::*;
+    match tool {
+        "search_all"
+        | "search_emails"
+        | "find_similar_emails"
+        | "find_experts"
+        | "get_digest"
+        | "list_emails"
+        | "list_activity"
+        | "list_unanswered"
+        | "list_receipts"
+        | "spending_report"
+        | "list_trips"
+        | "list_extractions"
+        | "list_tasks"
+        | "get_thread"
+        | "get_send_status"
+        | "search_threads"
+        | "list_threads"
+        | "list_folders"
+        | "get_attachment"
+        | "list_drafts"
+        | "get_draft"
+        | "search_events"
+        | "get_event"
+        | "list_events"
+        | "list_calendars"
+        | "availability_context" => &[Read],
+        // Importing or saving fetched mail changes the local store
+        "remote_search_emails" | "get_email" => &[Read, Manage],
+        "complete_task" | "set_email_tasks" => &[Manage],
+        "send_email" => &[Draft, Send],
+        "create_draft" | "update_draft" | "delete_draft" => &[Draft],
+        "send_draft" => &[Send],
+        "create_event" | "update_event" => &[Calendar],
+        "propose_meeting_times" => &[Read, Calendar],
+        "manage_accounts" | "manage_sync" | "manage_daemon" => &[Read, Manage],
+        _ => &[],
+    }
+}
+
+/// Capability a tool call needs, given its arguments
+pub fn required_capability(tool: &str, args: &Value) -> McpCapability {
+    use McpCapability::*;
+    let action = args["action"].as_str().unwrap_or_default();
+    match tool {
+        // Saving as a draft never sends; previews belong to the send flow
+        "send_email"
+            if args["save_as_draft"].as_bool().unwrap_or(false)
+                && !args["confirm"].as_bool().unwrap_or(false) =>
+        {
+            Draft
+        }
+        "send_email" | "send_draft" => Send,
+        "create_draft" | "update_draft" | "delete_draft" => Draft,
+        "create_event" | "update_event" => Calendar,
+        "propose_meeting_times" if args["hold"].as_bool().unwrap_or(false) => Calendar,
+        "manage_accounts" if matches!(action, "list" | "get") => Read,
+        "manage_sync" | "manage_daemon" if action == "status" => Read,
+        "manage_accounts" | "manage_sync" | "manage_daemon" => Manage,
+        "remote_search_emails" if args["import"].as_bool().unwrap_or(false) => Manage,
+        "get_email" if args["save"].as_bool().unwrap_or(false) => Manage,
+        "remote_search_emails" | "get_email" | "propose_meeting_times" => Read,
+        // Tools with one capability need it for every call; unknown tools
+        // need the most powerful one
+        _ => match tool_capabilities(tool) {
+            [only] => *only,
+            _ => Manage,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::tools::get_tool_definitions;
+    use serde_json::json;
+
+    #[test]
+    fn test_required_capability() {
+        use McpCapability::*;
+        assert_eq!(required_capability("search_emails", &json!({})), Read);
+        assert_eq!(
+            required_capability("send_email", &json!({"confirm": true})),
+            Send
+        );
+        assert_eq!(required_capability("send_email", &json!({})), Send);
+        assert_eq!(
+            required_capability("send_email", &json!({"save_as_draft": true})),
+            Draft
+        );
+        assert_eq!(required_capability("send_draft", &json!({})), Send);
+        assert_eq!(required_capability("create_event", &json!({})), Calendar);
+        assert_eq!(required_capability("update_event", &json!({})), Calendar);
+        assert_eq!(
+            required_capability("propose_meeting_times", &json!({})),
+            Read
+        );
+        assert_eq!(
+            required_capability("propose_meeting_times", &json!({"hold": true})),
+            Calendar
+        );
+        assert_eq!(
+            required_capability("manage_accounts", &json!({"action": "list"})),
+            Read
+        );
+        assert_eq!(
+            required_capability("manage_sync", &json!({"action": "reset"})),
+            Manage
+        );
+        assert_eq!(
+            required_capability("manage_daemon", &json!({"action": "status"})),
+            Read
+        );
+        assert_eq!(
+            required_capability("remote_search_emails", &json!({})),
+            Read
+        );
+        assert_eq!(
+            required_capability("remote_search_emails", &json!({"import": true})),
+            Manage
+        );
+        assert_eq!(required_capability("get_email", &json!({})), Read);
+        assert_eq!(
+            required_capability("get_email", &json!({"save": true})),
+            Manage
+        );
+        assert_eq!(required_capability("complete_task", &json!({})), Manage);
+        assert_eq!(required_capability("set_email_tasks", &json!({})), Manage);
+        assert_eq!(required_capability("no_such_tool", &json!({})), Manage);
+        assert!(tool_capabilities("no_such_tool").is_empty());
+    }
+
+    #[test]
+    fn test_every_call_is_covered_by_tool_capabilities() {
+        // Arguments that change which capability a call needs
+        let variants = [
+            json!({}),
+            json!({"confirm": true}),
+            json!({"save_as_draft": true}),
+            json!({"hold": true}),
+            json!({"import": true}),
+            json!({"save": true}),
+            json!({"action": "list"}),
+            json!({"action": "status"}),
+            json!({"action": "delete"}),
+        ];
+        for tool in get_tool_definitions() {
+            let allowed = tool_capabilities(&tool.name);
+            assert!(!allowed.is_empty(), "{} is not classified", tool.name);
+            for args in &variants {
+                let needed = required_capability(&tool.name, args);
+                assert!(allowed.contains(&needed), "{} {}", tool.name, args);
+            }
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! Two-phase confirmation for MCP tools listed in `[mcp] confirm_tools`
+//!
+//! The first call to such a tool doesn't run it: it returns a pending
+//! confirmation token. The tool only runs when it is called again with the
+//! same arguments plus `confirmation_token`, before the token expires. Each
+//! token works once.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use serde_json::Value;
+
+use crate::error::{Error, Result};
+
+/// Argument carrying the token on the second call
+pub const CONFIRMATION_TOKEN_ARG: &str = "confirmation_token";
+
+/// A call waiting to be confirmed
+#[derive(Debug)]
+struct PendingCall {
+    tool: String,
+    arguments: Value,
+    expires_at: Instant,
+}
+
+/// Outstanding confirmation tokens
+#[derive(Debug)]
+pub struct ConfirmationStore {
+    timeout: Duration,
+    pending: Mutex<HashMap<String, PendingCall>>,
+}
+
+impl ConfirmationStore {
+    /// Tokens expire `timeout` after they are issued
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// How long tokens stay valid
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Hold a call and return the token that confirms it
+    pub fn issue(&self, tool: &str, arguments: &Value) -> String {
+        let token = uuid::Uuid::new_v4().to_string();
+        let now = Instant::now();
+
+        let mut pending = self.pending.lock();
+        pending.retain(|_, call| call.expires_at > now);
+        pending.insert(
+            token.clone(),
+            PendingCall {
+                tool: tool.to_string(),
+                arguments: without_token(arguments),
+                expires_at: now + self.timeout,
+            },
+        );
+        token
+    }
+
+    /// Use up a token; fails unless it was issued for this exact call and
+    /// hasn't expired
+    pub fn redeem(&self, tool: &str, arguments: &Value, token: &str) -> Result<()> {
+        let call = self.pending.lock().remove(token).ok_or_else(|| {
+            Error::InvalidRequest(format!(
+                "Unknown or already used confirmation token for {}; call it again without {} to get a new one",
+                tool, CONFIRMATION_TOKEN_ARG
+            ))
+        })?;
+
+        if call.expires_at <= Instant::now() {
+            return Err(Error::InvalidRequest(format!(
+                "Confirmation token for {} expired after {}s; call it again without {} to get a new one",
+                tool,
+                self.timeout.as_secs(),
+                CONFIRMATION_TOKEN_ARG
+            )));
+        }
+        if call.tool != tool || call.arguments != without_token(arguments) {
+            return Err(Error::InvalidRequest(format!(
+                "Confirmation token was issued for a different {} call; arguments must match the first call exactly",
+                call.tool
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Arguments with the confirmation token removed
+pub fn without_token(arguments: &Value) -> Value {
+    let mut arguments = arguments.clone();
+    if let Some(map) = arguments.as_object_mut() {
+        map.remove(CONFIRMATION_TOKEN_ARG);
+    }
+    arguments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_token_confirms_matching_call_once() {
+        let store = ConfirmationStore::new(Duration::from_secs(60));
+        let args = json!({"to": ["a@example.com"], "confirm": true});
+        let token = store.issue("send_email", &args);
+
+        let mut confirmed = args.clone();
+        confirmed[CONFIRMATION_TOKEN_ARG] = json!(token);
+        assert!(store.redeem("send_email", &confirmed, &token).is_ok());
+        assert!(store.redeem("send_email", &confirmed, &token).is_err());
+    }
+
+    #[test]
+    fn test_token_rejects_changed_arguments() {
+        let store = ConfirmationStore::new(Duration::from_secs(60));
+        let token = store.issue("send_email", &json!({"to": ["a@example.com"]}));
+        assert!(store
+            .redeem("send_email", &json!({"to": ["b@example.com"]}), &token)
+            .is_err());
+        assert!(store.redeem("send_email", &json!({}), "made-up").is_err());
+    }
+
+    #[test]
+    fn test_token_expires() {
+        let store = ConfirmationStore::new(Duration::ZERO);
+        let token = store.issue("send_draft", &json!({"draft_id": "d1"}));
+        let err = store
+            .redeem("send_draft", &json!({"draft_id": "d1"}), &token)
+            .unwrap_err();
+        assert!(err.to_string().contains("expired"));
+    }
+}
@@ -0,0 +1,493 @@
+//! Streamable HTTP transport for the MCP server
+//!
+//! Clients POST JSON-RPC messages (single or batched) to `/mcp` and get the
+//! responses back as `application/json`, or as a one-event SSE stream when
+//! they only accept `text/event-stream`. The server never starts requests
+//! of its own, so GET (server-to-client streams) answers 405.
+//!
+//! With a bearer token set, every request must carry
+//! `Authorization: Bearer <token>`. Without one the server only listens on
+//! loopback, and browser requests from other origins are refused so web
+//! pages can't reach it through DNS rebinding.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::Value;
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader,
+};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, info, warn};
+
+use super::protocol::{JsonRpcError, JsonRpcRequest, JsonRpcResponse};
+use super::McpServer;
+use crate::error::{Error, Result};
+
+/// Path the MCP endpoint is served on
+pub const MCP_HTTP_PATH: &str = "/mcp";
+
+/// Default port for `groundeffect-daemon mcp --transport http`
+pub const MCP_HTTP_DEFAULT_PORT: u16 = 8787;
+
+/// Environment variable holding the bearer token
+pub const MCP_HTTP_TOKEN_ENV: &str = "GROUNDEFFECT_MCP_TOKEN";
+
+/// Largest request body accepted
+const MAX_BODY_BYTES: usize = 4 * 1024 * 1024;
+
+/// How long a connection may sit idle between requests
+pub(crate) const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A parsed HTTP/1.1 request
+#[derive(Debug)]
+pub(crate) struct HttpRequest {
+    pub(crate) method: String,
+    pub(crate) path: String,
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) body: Vec<u8>,
+}
+
+impl HttpRequest {
+    /// Header value by case-insensitive name
+    pub(crate) fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Whether the client wants the connection closed after this request
+    pub(crate) fn wants_close(&self) -> bool {
+        self.header("connection")
+            .is_some_and(|v| v.eq_ignore_ascii_case("close"))
+    }
+}
+
+/// An HTTP response
+#[derive(Debug)]
+pub(crate) struct HttpResponse {
+    pub(crate) status: u16,
+    pub(crate) content_type: Option<&'static str>,
+    pub(crate) headers: Vec<(&'static str, String)>,
+    pub(crate) body: Vec<u8>,
+}
+
+impl HttpResponse {
+    pub(crate) fn empty(status: u16) -> Self {
+        Self {
+            status,
+            content_type: None,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    pub(crate) fn text(status: u16, message: &str) -> Self {
+        Self {
+            status,
+            content_type: Some("text/plain; charset=utf-8"),
+            headers: Vec::new(),
+            body: message.as_bytes().to_vec(),
+        }
+    }
+
+    pub(crate) fn with_header(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.headers.push((name, value.into()));
+        self
+    }
+}
+
+impl McpServer {
+    /// Run the MCP server over Streamable HTTP on `addr`
+    ///
+    /// Requires a bearer token unless `addr` is a loopback address.
+    pub async fn run_http(self: Arc<Self>, addr: SocketAddr, token: Option<String>) -> Result<()> {
+        let token = token.filter(|t| !t.trim().is_empty());
+        if token.is_none() && !addr.ip().is_loopback() {
+            return Err(Error::Config(format!(
+                "Refusing to serve MCP on {} without authentication; set {} or bind to 127.0.0.1",
+                addr, MCP_HTTP_TOKEN_ENV
+            )));
+        }
+
+        let listener = TcpListener::bind(addr).await?;
+        info!(
+            "Starting MCP server on http://{}{} (auth: {})",
+            addr,
+            MCP_HTTP_PATH,
+            if token.is_some() {
+                "bearer token"
+            } else {
+                "none, loopback only"
+            }
+        );
+
+        let token = Arc::new(token);
+        loop {
+            let (stream, peer) = match listener.accept().await {
+                Ok(conn) => conn,
+                Err(e) => {
+                    warn!("Failed to accept MCP connection: {}", e);
+                    continue;
+                }
+            };
+            let server = self.clone();
+            let token = token.clone();
+            tokio::spawn(async move {
+                if let Err(e) = server.serve_connection(stream, token.as_deref()).await {
+                    debug!("MCP connection from {} ended: {}", peer, e);
+                }
+            });
+        }
+    }
+
+    /// Answer requests on one connection until it closes or goes idle
+    async fn serve_connection(&self, stream: TcpStream, token: Option<&str>) -> Result<()> {
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+
+        loop {
+            let request = match tokio::time::timeout(IDLE_TIMEOUT, read_request(&mut reader)).await
+            {
+                Ok(Ok(Some(request))) => request,
+                Ok(Ok(None)) | Err(_) => return Ok(()),
+                Ok(Err(e)) => {
+                    let response = HttpResponse::text(400, &e.to_string());
+                    write_response(&mut writer, &response, true).await?;
+                    return Ok(());
+                }
+            };
+
+            let close = request.wants_close();
+            let response = self.handle_http(&request, token).await;
+            write_response(&mut writer, &response, close).await?;
+            if close {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Route one HTTP request
+    async fn handle_http(&self, request: &HttpRequest, token: Option<&str>) -> HttpResponse {
+        let path = request.path.split('?').next().unwrap_or_default();
+        if path != MCP_HTTP_PATH {
+            return HttpResponse::text(404, "Not found");
+        }
+        if let Some(response) = check_access(request, token) {
+            return response;
+        }
+
+        // GET would open a server-to-client stream, and there are no
+        // sessions to DELETE
+        if request.method != "POST" {
+            return HttpResponse::empty(405).with_header("Allow", "POST");
+        }
+
+        let Some(reply) = self.handle_body(&request.body).await else {
+            // Only notifications or responses: nothing to send back
+            return HttpResponse::empty(202);
+        };
+        let json = match serde_json::to_string(&reply) {
+            Ok(json) => json,
+            Err(e) => return HttpResponse::text(500, &e.to_string()),
+        };
+
+        if prefers_event_stream(request.header("accept")) {
+            HttpResponse {
+                status: 200,
+                content_type: Some("text/event-stream"),
+                headers: vec![("Cache-Control", "no-cache".to_string())],
+                body: format!("event: message\ndata: {}\n\n", json).into_bytes(),
+            }
+        } else {
+            HttpResponse {
+                status: 200,
+                content_type: Some("application/json"),
+                headers: Vec::new(),
+                body: json.into_bytes(),
+            }
+        }
+    }
+
+    /// Handle a POSTed JSON-RPC message or batch. None when nothing needs a
+    /// reply.
+    async fn handle_body(&self, body: &[u8]) -> Option<Value> {
+        let message: Value = match serde_json::from_slice(body) {
+            Ok(message) => message,
+            Err(e) => return Some(parse_error(format!("Parse error: {}", e))),
+        };
+
+        match message {
+            Value::Array(messages) => {
+                let mut replies = Vec::new();
+                for message in messages {
+                    if let Some(reply) = self.handle_message(message).await {
+                        replies.push(reply);
+                    }
+                }
+                (!replies.is_empty()).then_some(Value::Array(replies))
+            }
+            message => self.handle_message(message).await,
+        }
+    }
+
+    /// Handle one JSON-RPC message; notifications and client responses get
+    /// no reply
+    async fn handle_message(&self, message: Value) -> Option<Value> {
+        if message.get("method").is_none() {
+            // A response to a server request; we never send any
+            return None;
+        }
+        let request: JsonRpcRequest = match serde_json::from_value(message) {
+            Ok(request) => request,
+            Err(e) => return Some(parse_error(format!("Invalid request: {}", e))),
+        };
+        if request.id.is_none() {
+            self.handle_request(&request).await;
+            return None;
+        }
+        let response = self.handle_request(&request).await;
+        serde_json::to_value(&response).ok()
+    }
+}
+
+/// Reject requests with a missing or wrong token, or a foreign browser origin
+fn check_access(request: &HttpRequest, token: Option<&str>) -> Option<HttpResponse> {
+    match token {
+        Some(expected) => {
+            let given = request
+                .header("authorization")
+                .and_then(|v| v.strip_prefix("Bearer "))
+                .map(str::trim)
+                .unwrap_or_default();
+            if !constant_time_eq(given.as_bytes(), expected.as_bytes()) {
+                return Some(
+                    HttpResponse::text(401, "Missing or invalid bearer token")
+                        .with_header("WWW-Authenticate", "Bearer"),
+                );
+            }
+        }
+        None => {
+            if let Some(origin) = request.header("origin") {
+                if !is_local_origin(origin) {
+                    return Some(HttpResponse::text(403, "Origin not allowed"));
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Whether an Origin header points at this machine
+fn is_local_origin(origin: &str) -> bool {
+    let host = origin
+        .split("://")
+        .nth(1)
+        .unwrap_or(origin)
+        .trim_end_matches('/');
+    let host = if host.starts_with('[') {
+        host.split(']').next().map(|h| &h[1..]).unwrap_or(host)
+    } else {
+        host.split(':').next().unwrap_or(host)
+    };
+    matches!(host, "localhost" | "127.0.0.1" | "::1")
+}
+
+/// Compare secrets without leaking where they differ
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Whether to answer with SSE: only when the client doesn't take plain JSON
+fn prefers_event_stream(accept: Option<&str>) -> bool {
+    let Some(accept) = accept else {
+        return false;
+    };
+    let types: Vec<&str> = accept
+        .split(',')
+        .map(|t| t.split(';').next().unwrap_or_default().trim())
+        .collect();
+    types.contains(&"text/event-stream")
+        && !types
+            .iter()
+            .any(|t| *t == "application/json" || *t == "*/*")
+}
+
+fn parse_error(message: String) -> Value {
+    serde_json::to_value(JsonRpcResponse {
+        jsonrpc: "2.0".to_string(),
+        id: None,
+        result: None,
+        error: Some(JsonRpcError {
+            code: -32700,
+            message,
+            data: None,
+        }),
+    })
+    .unwrap_or(Value::Null)
+}
+
+/// Read one request; None if the connection closed first
+pub(crate) async fn read_request<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+) -> Result<Option<HttpRequest>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line).await? == 0 {
+        return Ok(None);
+    }
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Err(Error::InvalidRequest(format!(
+            "Malformed request line: {}",
+            line.trim()
+        )));
+    };
+    let (method, path) = (method.to_string(), path.to_string());
+
+    let mut headers = Vec::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+
+    let mut request = HttpRequest {
+        method,
+        path,
+        headers,
+        body: Vec::new(),
+    };
+    let length = match request.header("content-length") {
+        Some(value) => value
+            .parse::<usize>()
+            .map_err(|_| Error::InvalidRequest(format!("Invalid Content-Length: {}", value)))?,
+        None => 0,
+    };
+    if length > MAX_BODY_BYTES {
+        return Err(Error::InvalidRequest(format!(
+            "Request body of {} bytes is over the {} byte limit",
+            length, MAX_BODY_BYTES
+        )));
+    }
+    request.body = vec![0; length];
+    reader.read_exact(&mut request.body).await?;
+    Ok(Some(request))
+}
+
+pub(crate) async fn write_response<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    response: &HttpResponse,
+    close: bool,
+) -> Result<()> {
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Length: {}\r\n",
+        response.status,
+        reason(response.status),
+        response.body.len()
+    );
+    if let Some(content_type) = response.content_type {
+        head.push_str(&format!("Content-Type: {}\r\n", content_type));
+    }
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    if close {
+        head.push_str("Connection: close\r\n");
+    }
+    head.push_str("\r\n");
+
+    writer.write_all(head.as_bytes()).await?;
+    writer.write_all(&response.body).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        202 => "Accepted",
+        207 => "Multi-Status",
+        301 => "Moved Permanently",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(headers: &[(&str, &str)]) -> HttpRequest {
+        HttpRequest {
+            method: "POST".to_string(),
+            path: MCP_HTTP_PATH.to_string(),
+            headers: headers
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            body: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_request() {
+        let raw = b"POST /mcp HTTP/1.1\r\nHost: localhost\r\nContent-Length: 2\r\n\r\n{}";
+        let mut reader = BufReader::new(&raw[..]);
+        let request = read_request(&mut reader).await.unwrap().unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/mcp");
+        assert_eq!(request.header("content-length"), Some("2"));
+        assert_eq!(request.body, b"{}");
+
+        let mut empty = BufReader::new(&b""[..]);
+        assert!(read_request(&mut empty).await.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_bearer_token() {
+        let ok = request(&[("Authorization", "Bearer s3cret")]);
+        assert!(check_access(&ok, Some("s3cret")).is_none());
+
+        let wrong = request(&[("Authorization", "Bearer nope")]);
+        assert_eq!(check_access(&wrong, Some("s3cret")).unwrap().status, 401);
+        assert_eq!(
+            check_access(&request(&[]), Some("s3cret")).unwrap().status,
+            401
+        );
+    }
+
+    #[test]
+    fn test_origin_without_token() {
+        let local = request(&[("Origin", "http://localhost:3000")]);
+        assert!(check_access(&local, None).is_none());
+        assert!(check_access(&request(&[]), None).is_none());
+
+        let foreign = request(&[("Origin", "https://evil.example")]);
+        assert_eq!(check_access(&foreign, None).unwrap().status, 403);
+        assert!(is_local_origin("http://[::1]:8787"));
+    }
+
+    #[test]
+    fn test_prefers_event_stream() {
+        assert!(!prefers_event_stream(None));
+        assert!(!prefers_event_stream(Some(
+            "application/json, text/event-stream"
+        )));
+        assert!(prefers_event_stream(Some("text/event-stream")));
+    }
+}
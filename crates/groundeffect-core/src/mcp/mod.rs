@@ -1,20 +1,33 @@
 //! MCP (Model Context Protocol) server implementation
 //!
-//! Provides stdio JSON-RPC interface for Claude Code integration.
+//! Provides a JSON-RPC interface for Claude Code integration, over stdio or
+//! Streamable HTTP.
 
+mod capabilities;
+mod confirmation;
+pub(crate) mod http;
+mod prompts;
 mod protocol;
 mod resources;
 mod tools;
 
+pub use capabilities::*;
+pub use confirmation::*;
+pub use http::*;
+pub use prompts::*;
 pub use protocol::*;
 pub use resources::*;
 pub use tools::*;
 
+use std::collections::HashMap;
 use std::sync::Arc;
 
+use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::{mpsc, Semaphore};
+use tokio::task::{AbortHandle, JoinSet};
 use tracing::{debug, error, info, warn};
 
 use crate::config::Config;
@@ -30,6 +43,7 @@ pub struct McpServer {
     config: Arc<Config>,
     search: Arc<SearchEngine>,
     oauth: Arc<OAuthManager>,
+    confirmations: ConfirmationStore,
 }
 
 impl McpServer {
@@ -40,25 +54,49 @@ impl McpServer {
         embedding: Arc<HybridEmbeddingProvider>,
         oauth: Arc<OAuthManager>,
     ) -> Self {
-        let search = Arc::new(SearchEngine::new(db.clone(), embedding));
+        let search = Arc::new(SearchEngine::new(db.clone(), embedding).with_cache(&config));
+        let confirmations = ConfirmationStore::new(std::time::Duration::from_secs(
+            config.mcp.confirm_timeout_secs,
+        ));
 
         Self {
             db,
             config,
             search,
             oauth,
+            confirmations,
         }
     }
 
     /// Run the MCP server on stdio
-    pub async fn run(&self) -> Result<()> {
+    ///
+    /// Each request runs on its own tokio task (at most
+    /// `[mcp] max_concurrent_requests` at once), so a slow search doesn't hold
+    /// up other calls; responses are written as they finish. A
+    /// `notifications/cancelled` for a request still running aborts it,
+    /// unless it is a tool call that changes something.
+    pub async fn run(self: Arc<Self>) -> Result<()> {
         info!("Starting MCP server on stdio");
 
-        let stdin = tokio::io::stdin();
-        let stdout = tokio::io::stdout();
+        // A single writer keeps concurrent responses from interleaving
+        let (responses, mut outgoing) = mpsc::unbounded_channel::<String>();
+        let writer = tokio::spawn(async move {
+            let mut stdout = tokio::io::stdout();
+            while let Some(response_json) = outgoing.recv().await {
+                stdout.write_all(response_json.as_bytes()).await?;
+                stdout.write_all(b"\n").await?;
+                stdout.flush().await?;
+            }
+            Ok::<(), std::io::Error>(())
+        });
+
+        let limit = Arc::new(Semaphore::new(
+            self.config.mcp.max_concurrent_requests.max(1),
+        ));
+        let in_flight: Arc<Mutex<HashMap<String, AbortHandle>>> = Arc::default();
+        let mut tasks = JoinSet::new();
 
-        let mut reader = BufReader::new(stdin);
-        let mut stdout = stdout;
+        let mut reader = BufReader::new(tokio::io::stdin());
         let mut line = String::new();
 
         loop {
@@ -71,6 +109,9 @@ impl McpServer {
                 break;
             }
 
+            // Reap finished tasks so the set doesn't grow
+            while tasks.try_join_next().is_some() {}
+
             let line = line.trim();
             if line.is_empty() {
                 continue;
@@ -92,25 +133,63 @@ impl McpServer {
                             data: None,
                         }),
                     };
-                    let response_json = serde_json::to_string(&error_response)?;
-                    stdout.write_all(response_json.as_bytes()).await?;
-                    stdout.write_all(b"\n").await?;
-                    stdout.flush().await?;
+                    let _ = responses.send(serde_json::to_string(&error_response)?);
                     continue;
                 }
             };
 
-            // Handle request
-            let response = self.handle_request(&request).await;
+            if request.method == "notifications/cancelled" {
+                cancel_request(&request.params, &in_flight);
+                continue;
+            }
+
+            // Stop reading while every slot is busy
+            let permit = limit
+                .clone()
+                .acquire_owned()
+                .await
+                .map_err(|e| Error::Other(e.to_string()))?;
+
+            let key = request
+                .id
+                .as_ref()
+                .filter(|_| is_cancellable(&request))
+                .map(Value::to_string);
+            let server = self.clone();
+            let responses = responses.clone();
+            let task_in_flight = in_flight.clone();
+            let task_key = key.clone();
+
+            // Locked across the spawn so a fast request can't finish before
+            // it is registered
+            let mut registered = in_flight.lock();
+            let handle = tasks.spawn(async move {
+                let response = server.handle_request(&request).await;
+                drop(permit);
+                if let Some(key) = &task_key {
+                    task_in_flight.lock().remove(key);
+                }
 
-            // Send response
-            let response_json = serde_json::to_string(&response)?;
-            debug!("Sending response: {}", response_json);
-            stdout.write_all(response_json.as_bytes()).await?;
-            stdout.write_all(b"\n").await?;
-            stdout.flush().await?;
+                // Send response
+                match serde_json::to_string(&response) {
+                    Ok(response_json) => {
+                        debug!("Sending response: {}", response_json);
+                        let _ = responses.send(response_json);
+                    }
+                    Err(e) => error!("Failed to serialize response: {}", e),
+                }
+            });
+            if let Some(key) = key {
+                registered.insert(key, handle);
+            }
+            drop(registered);
         }
 
+        // Let running requests answer before closing stdout
+        while tasks.join_next().await.is_some() {}
+        drop(responses);
+        writer.await.map_err(|e| Error::Other(e.to_string()))??;
+
         Ok(())
     }
 
@@ -133,25 +212,52 @@ impl McpServer {
 
         info!("→ {}", request_desc);
 
-        let result = match method.as_str() {
-            // MCP protocol methods
-            "initialize" => self.handle_initialize(&request.params).await,
-            "initialized" | "notifications/initialized" => Ok(Value::Null),
-            "ping" => Ok(Value::String("pong".to_string())),
+        let dispatch = async {
+            match method.as_str() {
+                // MCP protocol methods
+                "initialize" => self.handle_initialize(&request.params).await,
+                "initialized" | "notifications/initialized" => Ok(Value::Null),
+                "ping" => Ok(Value::String("pong".to_string())),
 
-            // Tool listing
-            "tools/list" => self.handle_tools_list().await,
+                // Tool listing
+                "tools/list" => self.handle_tools_list().await,
 
-            // Tool execution
-            "tools/call" => self.handle_tools_call(&request.params).await,
+                // Tool execution
+                "tools/call" => self.handle_tools_call(&request.params).await,
 
-            // Resource listing
-            "resources/list" => self.handle_resources_list().await,
+                // Resource listing
+                "resources/list" => self.handle_resources_list().await,
 
-            // Resource reading
-            "resources/read" => self.handle_resources_read(&request.params).await,
+                // Resource template listing
+                "resources/templates/list" => self.handle_resource_templates_list().await,
 
-            _ => Err(Error::McpProtocol(format!("Unknown method: {}", method))),
+                // Resource reading
+                "resources/read" => self.handle_resources_read(&request.params).await,
+
+                // Prompt listing
+                "prompts/list" => self.handle_prompts_list().await,
+
+                // Prompt expansion
+                "prompts/get" => self.handle_prompts_get(&request.params).await,
+
+                _ => Err(Error::McpProtocol(format!("Unknown method: {}", method))),
+            }
+        };
+
+        // Dropping the request future cancels its pending DB queries and
+        // HTTP calls at their next await point
+        let timeout_secs = self.config.mcp.request_timeout_secs;
+        let result = if timeout_secs > 0 && is_cancellable(request) {
+            let limit = std::time::Duration::from_secs(timeout_secs);
+            match tokio::time::timeout(limit, dispatch).await {
+                Ok(result) => result,
+                Err(_) => Err(Error::Timeout {
+                    operation: request_desc.clone(),
+                    timeout_secs,
+                }),
+            }
+        } else {
+            dispatch.await
         };
 
         let elapsed = start.elapsed();
@@ -193,10 +299,15 @@ impl McpServer {
 
     /// Handle initialize request
     async fn handle_initialize(&self, _params: &Option<Value>) -> Result<Value> {
+        // Name servers per profile so clients can register several side by side
+        let name = match crate::config::active_profile() {
+            Some(profile) => format!("groundeffect-{}", profile),
+            None => "groundeffect".to_string(),
+        };
         Ok(serde_json::json!({
             "protocolVersion": "2024-11-05",
             "serverInfo": {
-                "name": "groundeffect",
+                "name": name,
                 "version": env!("CARGO_PKG_VERSION")
             },
             "capabilities": {
@@ -204,16 +315,37 @@ impl McpServer {
                 "resources": {
                     "subscribe": false,
                     "listChanged": false
+                },
+                "prompts": {
+                    "listChanged": false
                 }
             }
         }))
     }
 
     /// Handle tools/list request
+    ///
+    /// Only tools usable with the allowed capabilities are listed, and tools
+    /// needing confirmation advertise the `confirmation_token` argument.
     async fn handle_tools_list(&self) -> Result<Value> {
-        Ok(serde_json::json!({
-            "tools": get_tool_definitions()
-        }))
+        let tools: Vec<ToolDefinition> = get_tool_definitions()
+            .into_iter()
+            .filter(|tool| {
+                tool_capabilities(&tool.name)
+                    .iter()
+                    .any(|capability| self.config.mcp.allows(*capability))
+            })
+            .map(|mut tool| {
+                if self.config.mcp.requires_confirmation(&tool.name) {
+                    tool.input_schema["properties"][CONFIRMATION_TOKEN_ARG] = serde_json::json!({
+                        "type": "string",
+                        "description": "Token from this tool's pending_confirmation response. Call first without it, confirm with the user, then repeat the identical call with the token."
+                    });
+                }
+                tool
+            })
+            .collect();
+        Ok(serde_json::json!({ "tools": tools }))
     }
 
     /// Handle tools/call request
@@ -226,11 +358,50 @@ impl McpServer {
             .as_str()
             .ok_or_else(|| Error::InvalidRequest("Missing tool name".to_string()))?;
 
-        let arguments = params
+        let mut arguments = params
             .get("arguments")
             .cloned()
             .unwrap_or(Value::Object(Default::default()));
 
+        let capability = required_capability(name, &arguments);
+        if !self.config.mcp.allows(capability) {
+            return Err(Error::CapabilityDenied {
+                tool: name.to_string(),
+                capability: capability.to_string(),
+            });
+        }
+
+        // Changing calls to tools in confirm_tools run only when confirmed
+        if self.config.mcp.requires_confirmation(name)
+            && tools::audited_action(name, &arguments).is_some()
+        {
+            match arguments[CONFIRMATION_TOKEN_ARG].as_str() {
+                Some(token) => {
+                    self.confirmations.redeem(name, &arguments, token)?;
+                    arguments = without_token(&arguments);
+                }
+                None => {
+                    let token = self.confirmations.issue(name, &arguments);
+                    let pending = serde_json::json!({
+                        "status": "pending_confirmation",
+                        "tool": name,
+                        "confirmation_token": token,
+                        "expires_in_secs": self.confirmations.timeout().as_secs(),
+                        "message": format!(
+                            "{} was not run. Confirm with the user, then call it again with the same arguments plus \"{}\".",
+                            name, CONFIRMATION_TOKEN_ARG
+                        ),
+                    });
+                    return Ok(serde_json::json!({
+                        "content": [{
+                            "type": "text",
+                            "text": serde_json::to_string_pretty(&pending)?
+                        }]
+                    }));
+                }
+            }
+        }
+
         let tool_handler = ToolHandler::new(
             self.db.clone(),
             self.config.clone(),
@@ -243,9 +414,22 @@ impl McpServer {
 
     /// Handle resources/list request
     async fn handle_resources_list(&self) -> Result<Value> {
-        Ok(serde_json::json!({
-            "resources": get_resource_definitions()
-        }))
+        let resources = if self.config.mcp.allows(McpCapability::Read) {
+            get_resource_definitions()
+        } else {
+            Vec::new()
+        };
+        Ok(serde_json::json!({ "resources": resources }))
+    }
+
+    /// Handle resources/templates/list request
+    async fn handle_resource_templates_list(&self) -> Result<Value> {
+        let templates = if self.config.mcp.allows(McpCapability::Read) {
+            get_resource_templates()
+        } else {
+            Vec::new()
+        };
+        Ok(serde_json::json!({ "resourceTemplates": templates }))
     }
 
     /// Handle resources/read request
@@ -258,8 +442,154 @@ impl McpServer {
             .as_str()
             .ok_or_else(|| Error::InvalidRequest("Missing resource URI".to_string()))?;
 
+        if !self.config.mcp.allows(McpCapability::Read) {
+            return Err(Error::CapabilityDenied {
+                tool: "resources/read".to_string(),
+                capability: McpCapability::Read.to_string(),
+            });
+        }
+
         let resource_handler = ResourceHandler::new(self.db.clone(), self.config.clone());
 
         resource_handler.read(uri).await
     }
+
+    /// Handle prompts/list request
+    ///
+    /// Prompts only guide read-only tool calls, so they follow the read
+    /// capability like resources do.
+    async fn handle_prompts_list(&self) -> Result<Value> {
+        let prompts = if self.config.mcp.allows(McpCapability::Read) {
+            get_prompt_definitions()
+        } else {
+            Vec::new()
+        };
+        Ok(serde_json::json!({ "prompts": prompts }))
+    }
+
+    /// Handle prompts/get request
+    async fn handle_prompts_get(&self, params: &Option<Value>) -> Result<Value> {
+        let params = params
+            .as_ref()
+            .ok_or_else(|| Error::InvalidRequest("Missing params".to_string()))?;
+
+        let name = params["name"]
+            .as_str()
+            .ok_or_else(|| Error::InvalidRequest("Missing prompt name".to_string()))?;
+
+        if !self.config.mcp.allows(McpCapability::Read) {
+            return Err(Error::CapabilityDenied {
+                tool: "prompts/get".to_string(),
+                capability: McpCapability::Read.to_string(),
+            });
+        }
+
+        get_prompt(name, &params["arguments"], &self.config)
+    }
+}
+
+/// Abort a running request named by a `notifications/cancelled` message
+///
+/// The request gets no response, as the MCP spec asks. Unknown or finished
+/// requests are ignored.
+fn cancel_request(params: &Option<Value>, in_flight: &Mutex<HashMap<String, AbortHandle>>) {
+    let Some(id) = params
+        .as_ref()
+        .map(|p| &p["requestId"])
+        .filter(|id| !id.is_null())
+    else {
+        return;
+    };
+    match in_flight.lock().remove(&id.to_string()) {
+        Some(handle) => {
+            info!("Cancelled request {}", id);
+            handle.abort();
+        }
+        None => debug!(
+            "Ignoring cancellation of request {} (finished or not cancellable)",
+            id
+        ),
+    }
+}
+
+/// Whether a request can be aborted midway. Tool calls that change
+/// something (sending, writing drafts, creating events) always run to the
+/// end so a cancel can't leave them half done.
+fn is_cancellable(request: &JsonRpcRequest) -> bool {
+    if request.method != "tools/call" {
+        return true;
+    }
+    let params = request.params.as_ref().unwrap_or(&Value::Null);
+    let tool = params["name"].as_str().unwrap_or_default();
+    tools::audited_action(tool, &params["arguments"]).is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(tool: &str, arguments: Value) -> JsonRpcRequest {
+        JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(serde_json::json!(7)),
+            method: "tools/call".to_string(),
+            params: Some(serde_json::json!({"name": tool, "arguments": arguments})),
+        }
+    }
+
+    #[test]
+    fn test_changing_tool_calls_are_not_cancellable() {
+        assert!(is_cancellable(&call(
+            "search_emails",
+            serde_json::json!({"query": "q"})
+        )));
+        assert!(is_cancellable(&call(
+            "send_email",
+            serde_json::json!({"confirm": false})
+        )));
+        assert!(!is_cancellable(&call(
+            "send_email",
+            serde_json::json!({"confirm": true})
+        )));
+        assert!(!is_cancellable(&call(
+            "create_event",
+            serde_json::json!({})
+        )));
+        assert!(is_cancellable(&call("get_email", serde_json::json!({}))));
+        assert!(!is_cancellable(&call(
+            "get_email",
+            serde_json::json!({"save": true})
+        )));
+        assert!(!is_cancellable(&call(
+            "remote_search_emails",
+            serde_json::json!({"import": true})
+        )));
+        assert!(!is_cancellable(&call(
+            "complete_task",
+            serde_json::json!({})
+        )));
+        assert!(!is_cancellable(&call(
+            "set_email_tasks",
+            serde_json::json!({})
+        )));
+        assert!(!is_cancellable(&call(
+            "no_such_tool",
+            serde_json::json!({})
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_aborts_registered_request() {
+        let in_flight = Mutex::new(HashMap::new());
+        let mut tasks = JoinSet::new();
+        let handle = tasks.spawn(std::future::pending::<()>());
+        in_flight
+            .lock()
+            .insert(serde_json::json!(7).to_string(), handle);
+
+        cancel_request(&Some(serde_json::json!({"requestId": 7})), &in_flight);
+
+        assert!(in_flight.lock().is_empty());
+        assert!(tasks.join_next().await.unwrap().unwrap_err().is_cancelled());
+    }
 }
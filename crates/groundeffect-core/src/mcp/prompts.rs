@@ -0,0 +1,238 @@
+//! MCP prompts: canned workflows built from the GroundEffect tools
+//!
+//! Each prompt expands into a user message that spells out which tools to
+//! call, in what order and with which arguments, so clients get common
+//! workflows without a custom skill.
+
+use chrono::{Duration, Utc};
+use chrono_tz::Tz;
+use serde_json::Value;
+
+use crate::config::Config;
+use crate::error::{Error, Result};
+
+use super::protocol::{PromptArgument, PromptDefinition};
+
+/// Get all prompt definitions
+pub fn get_prompt_definitions() -> Vec<PromptDefinition> {
+    let account = || PromptArgument {
+        name: "account".to_string(),
+        description: "Account email or alias to focus on (default: all accounts)".to_string(),
+        required: false,
+    };
+
+    vec![
+        PromptDefinition {
+            name: "morning_briefing".to_string(),
+            description: "Today's schedule, important new mail, activity and upcoming travel"
+                .to_string(),
+            arguments: vec![account()],
+        },
+        PromptDefinition {
+            name: "prep_next_meeting".to_string(),
+            description: "Background on the next meeting: attendees, recent threads, open items"
+                .to_string(),
+            arguments: vec![
+                account(),
+                PromptArgument {
+                    name: "meeting".to_string(),
+                    description: "Words from the meeting title, if not the very next meeting"
+                        .to_string(),
+                    required: false,
+                },
+            ],
+        },
+        PromptDefinition {
+            name: "find_unanswered_emails".to_string(),
+            description: "Personal emails that are still waiting for your reply".to_string(),
+            arguments: vec![
+                account(),
+                PromptArgument {
+                    name: "days".to_string(),
+                    description: "How many days back to look (default: 7)".to_string(),
+                    required: false,
+                },
+            ],
+        },
+    ]
+}
+
+/// Build the messages for a prompts/get request
+pub fn get_prompt(name: &str, arguments: &Value, config: &Config) -> Result<Value> {
+    let arg = |key: &str| {
+        arguments[key]
+            .as_str()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+    };
+    let account = arg("account").map(|a| config.resolve_account(a).unwrap_or(a.to_string()));
+
+    let tz: Tz = config.general.timezone.parse().unwrap_or(Tz::UTC);
+    let now = Utc::now().with_timezone(&tz);
+    let today = now.date_naive();
+
+    let (description, text) = match name {
+        "morning_briefing" => (
+            "Morning briefing",
+            morning_briefing(account.as_deref(), &today.to_string(), &tz.to_string()),
+        ),
+        "prep_next_meeting" => (
+            "Prepare for the next meeting",
+            prep_next_meeting(
+                account.as_deref(),
+                arg("meeting"),
+                &today.to_string(),
+                &(today + Duration::days(7)).to_string(),
+                &now.format("%H:%M").to_string(),
+            ),
+        ),
+        "find_unanswered_emails" => {
+            let days: i64 = match arg("days") {
+                Some(value) => value
+                    .parse()
+                    .ok()
+                    .filter(|d| (1..=90).contains(d))
+                    .ok_or_else(|| {
+                        Error::InvalidRequest(format!(
+                            "days must be a number from 1 to 90, got '{}'",
+                            value
+                        ))
+                    })?,
+                None => 7,
+            };
+            (
+                "Find unanswered emails",
+                find_unanswered_emails(
+                    account.as_deref(),
+                    days,
+                    &(today - Duration::days(days)).to_string(),
+                ),
+            )
+        }
+        _ => return Err(Error::InvalidRequest(format!("Unknown prompt: {}", name))),
+    };
+
+    Ok(serde_json::json!({
+        "description": description,
+        "messages": [{
+            "role": "user",
+            "content": {"type": "text", "text": text}
+        }]
+    }))
+}
+
+/// `"account": "..."` argument fragment for single-account tools
+fn account_arg(account: Option<&str>) -> String {
+    account
+        .map(|a| format!(", \"account\": \"{}\"", a))
+        .unwrap_or_default()
+}
+
+/// `"accounts": ["..."]` argument fragment for multi-account tools
+fn accounts_arg(account: Option<&str>) -> String {
+    account
+        .map(|a| format!(", \"accounts\": [\"{}\"]", a))
+        .unwrap_or_default()
+}
+
+fn morning_briefing(account: Option<&str>, today: &str, tz: &str) -> String {
+    let tomorrow = chrono::NaiveDate::parse_from_str(today, "%Y-%m-%d")
+        .map(|d| (d + Duration::days(1)).to_string())
+        .unwrap_or_default();
+    format!(
+        "Give me my morning briefing for {today} ({tz}).\n\
+         \n\
+         Use the GroundEffect tools in this order:\n\
+         1. `list_events` with {{\"from\": \"{today}\", \"to\": \"{tomorrow}\"{accounts}}} for today's schedule.\n\
+         2. `list_emails` with {{\"category\": \"personal\", \"limit\": 20{account}}} for new mail from people.\n\
+         3. `list_activity` with {{\"date_from\": \"{today}\", \"limit\": 20{account}}} for GitHub/Jira/Asana updates.\n\
+         4. `list_trips` with {{\"days\": 7{account}}} for upcoming travel.\n\
+         \n\
+         Then write a short briefing: the day's meetings with times and anything to prepare, \
+         emails that need a reply or decision (with their IDs), notable activity, and travel \
+         in the next week. Skip newsletters and automated mail. Don't send anything.",
+        accounts = accounts_arg(account),
+        account = account_arg(account),
+    )
+}
+
+fn prep_next_meeting(
+    account: Option<&str>,
+    meeting: Option<&str>,
+    today: &str,
+    week: &str,
+    now: &str,
+) -> String {
+    let which = match meeting {
+        Some(title) => format!("the next meeting whose title matches \"{}\"", title),
+        None => format!("my next meeting (the first one starting after {})", now),
+    };
+    format!(
+        "Help me prepare for {which}.\n\
+         \n\
+         1. `list_events` with {{\"from\": \"{today}\", \"to\": \"{week}\"{accounts}}} and pick the meeting. \
+         Use `get_event` on its ID for the full description and attendee list.\n\
+         2. For each attendee (skip me and large lists), `search_emails` with \
+         {{\"query\": \"<meeting topic>\", \"from\": \"<attendee email>\", \"limit\": 5{accounts}}}.\n\
+         3. `get_thread` with {{\"thread_id\": \"<id>\", \"render\": \"compact\"}} on the most relevant threads.\n\
+         \n\
+         Summarize: when and where the meeting is, who is attending and their role in recent \
+         conversations, open questions or commitments from those threads, and suggested talking points.",
+        accounts = accounts_arg(account),
+    )
+}
+
+fn find_unanswered_emails(account: Option<&str>, days: i64, since: &str) -> String {
+    format!(
+        "Find emails from the last {days} days that are still waiting for my reply.\n\
+         \n\
+         1. `search_emails` with {{\"query\": \"question request please reply\", \"category\": \"personal\", \
+         \"date_from\": \"{since}\", \"limit\": 50{accounts}}}, and `list_emails` with \
+         {{\"category\": \"personal\", \"limit\": 50{account}}} for anything the search missed.\n\
+         2. For each candidate, `get_thread` with {{\"thread_id\": \"<thread id>\", \"render\": \"compact\"}} and \
+         keep it only if the last message is from someone else and asks me something or expects a response.\n\
+         \n\
+         List them oldest first with sender, subject, date, what they need from me, and the email ID. \
+         Don't draft or send replies unless I ask.",
+        accounts = accounts_arg(account),
+        account = account_arg(account),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prompts_mention_their_tools() {
+        let config = Config::default();
+        for prompt in get_prompt_definitions() {
+            let result = get_prompt(&prompt.name, &serde_json::json!({}), &config).unwrap();
+            let text = result["messages"][0]["content"]["text"].as_str().unwrap();
+            assert!(text.contains("`list_"), "{}", prompt.name);
+            assert!(!text.contains("account"), "{}", prompt.name);
+        }
+    }
+
+    #[test]
+    fn test_prompt_arguments() {
+        let config = Config::default();
+        let result = get_prompt(
+            "find_unanswered_emails",
+            &serde_json::json!({"account": "me@example.com", "days": "3"}),
+            &config,
+        )
+        .unwrap();
+        let text = result["messages"][0]["content"]["text"].as_str().unwrap();
+        assert!(text.contains("last 3 days"));
+        assert!(text.contains("\"accounts\": [\"me@example.com\"]"));
+
+        assert!(get_prompt(
+            "find_unanswered_emails",
+            &serde_json::json!({"days": "soon"}),
+            &config
+        )
+        .is_err());
+        assert!(get_prompt("nope", &serde_json::json!({}), &config).is_err());
+    }
+}
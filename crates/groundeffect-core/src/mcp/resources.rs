@@ -2,38 +2,67 @@
 
 use std::sync::Arc;
 
+use chrono::Utc;
+use chrono_tz::Tz;
 use serde_json::Value;
 use tracing::debug;
 
 use crate::config::Config;
 use crate::db::Database;
 use crate::error::{Error, Result};
+use crate::models::{CalendarEvent, Email, EventFilter, EventTime};
 
-use super::protocol::ResourceDefinition;
+use super::protocol::{ResourceDefinition, ResourceTemplate};
+
+/// Most recent emails across all accounts
+pub const RECENT_EMAILS_URI: &str = "groundeffect://inbox/recent";
+
+/// Today's events in the user's timezone
+pub const TODAY_AGENDA_URI: &str = "groundeffect://calendar/today";
 
 /// Get all resource definitions
 pub fn get_resource_definitions() -> Vec<ResourceDefinition> {
     vec![
         ResourceDefinition {
-            uri: "email://{message_id}".to_string(),
+            uri: RECENT_EMAILS_URI.to_string(),
+            name: "Recent emails".to_string(),
+            description:
+                "Most recent emails across all accounts (sender, subject, date, snippet, ID)"
+                    .to_string(),
+            mime_type: Some("text/markdown".to_string()),
+        },
+        ResourceDefinition {
+            uri: TODAY_AGENDA_URI.to_string(),
+            name: "Today's agenda".to_string(),
+            description: "Today's calendar events in the user's timezone".to_string(),
+            mime_type: Some("text/markdown".to_string()),
+        },
+    ]
+}
+
+/// Get all resource templates
+pub fn get_resource_templates() -> Vec<ResourceTemplate> {
+    vec![
+        ResourceTemplate {
+            uri_template: "email://{message_id}".to_string(),
             name: "Email content".to_string(),
             description: "Raw email content by message ID".to_string(),
             mime_type: Some("message/rfc822".to_string()),
         },
-        ResourceDefinition {
-            uri: "email://{message_id}/body".to_string(),
+        ResourceTemplate {
+            uri_template: "email://{message_id}/body".to_string(),
             name: "Email body".to_string(),
             description: "Email body as markdown".to_string(),
             mime_type: Some("text/markdown".to_string()),
         },
-        ResourceDefinition {
-            uri: "email://{message_id}/attachments/{filename}".to_string(),
+        ResourceTemplate {
+            uri_template: "email://{message_id}/attachments/{filename}".to_string(),
             name: "Email attachment".to_string(),
             description: "Download email attachment".to_string(),
             mime_type: None,
         },
-        ResourceDefinition {
-            uri: "calendar://{event_id}".to_string(),
+        ResourceTemplate {
+            uri_template: "calendar://{event_id}".to_string(),
             name: "Calendar event".to_string(),
             description: "Calendar event in iCalendar format".to_string(),
             mime_type: Some("text/calendar".to_string()),
@@ -41,6 +70,81 @@ pub fn get_resource_definitions() -> Vec<ResourceDefinition> {
     ]
 }
 
+/// Markdown list of emails, cut off at `max_chars`
+pub fn render_recent_emails(emails: &[Email], max_chars: usize) -> String {
+    let entries = emails.iter().map(|e| {
+        format!(
+            "- **{}** — {} ({})\n  {}\n  ID: {}\n",
+            e.subject,
+            e.from,
+            e.date.format("%Y-%m-%d %H:%M"),
+            e.snippet.trim(),
+            e.id
+        )
+    });
+    render_limited("# Recent emails\n\n", entries, emails.len(), max_chars)
+}
+
+/// Markdown agenda of events, with times in `tz`, cut off at `max_chars`
+pub fn render_agenda(events: &[CalendarEvent], tz: Tz, max_chars: usize) -> String {
+    let entries = events.iter().map(|e| {
+        let when = match (&e.start, &e.end) {
+            (EventTime::DateTime(start), EventTime::DateTime(end)) => format!(
+                "{}–{}",
+                start.with_timezone(&tz).format("%H:%M"),
+                end.with_timezone(&tz).format("%H:%M")
+            ),
+            _ => "All day".to_string(),
+        };
+        let location = e
+            .location
+            .as_deref()
+            .map(|l| format!(" @ {}", l))
+            .unwrap_or_default();
+        format!("- {} **{}**{}\n  ID: {}\n", when, e.summary, location, e.id)
+    });
+    let today = Utc::now().with_timezone(&tz).format("%A %Y-%m-%d");
+    render_limited(
+        &format!("# Agenda for {}\n\n", today),
+        entries,
+        events.len(),
+        max_chars,
+    )
+}
+
+/// Join entries under a heading, dropping whole entries past `max_chars`
+fn render_limited(
+    heading: &str,
+    entries: impl Iterator<Item = String>,
+    total: usize,
+    max_chars: usize,
+) -> String {
+    if total == 0 {
+        return format!("{}Nothing here.\n", heading);
+    }
+
+    let mut text = heading.to_string();
+    let mut shown = 0;
+    let mut length = text.chars().count();
+    for entry in entries {
+        let entry_length = entry.chars().count();
+        if shown > 0 && length + entry_length > max_chars {
+            break;
+        }
+        length += entry_length;
+        text.push_str(&entry);
+        shown += 1;
+    }
+    if shown < total {
+        text.push_str(&format!(
+            "\n… {} more not shown (resource_max_chars = {})\n",
+            total - shown,
+            max_chars
+        ));
+    }
+    text
+}
+
 /// Resource handler
 pub struct ResourceHandler {
     db: Arc<Database>,
@@ -58,7 +162,9 @@ impl ResourceHandler {
         debug!("Reading resource: {}", uri);
 
         // Parse the URI
-        if let Some(rest) = uri.strip_prefix("email://") {
+        if let Some(rest) = uri.strip_prefix("groundeffect://") {
+            self.read_groundeffect_resource(rest).await
+        } else if let Some(rest) = uri.strip_prefix("email://") {
             self.read_email_resource(rest).await
         } else if let Some(rest) = uri.strip_prefix("calendar://") {
             self.read_calendar_resource(rest).await
@@ -67,6 +173,51 @@ impl ResourceHandler {
         }
     }
 
+    /// Read a generated resource (recent emails, today's agenda)
+    async fn read_groundeffect_resource(&self, path: &str) -> Result<Value> {
+        let max_chars = self.config.mcp.resource_max_chars;
+
+        let (uri, text) = match path.trim_end_matches('/') {
+            "inbox/recent" => {
+                let emails = self
+                    .db
+                    .list_recent_emails(None, None, false, self.config.mcp.resource_recent_emails)
+                    .await?;
+                (RECENT_EMAILS_URI, render_recent_emails(&emails, max_chars))
+            }
+            "calendar/today" => {
+                let tz: Tz = self.config.general.timezone.parse().unwrap_or(Tz::UTC);
+                let today = Utc::now().with_timezone(&tz).date_naive();
+                let tomorrow = today + chrono::Duration::days(1);
+                let events = self
+                    .db
+                    .list_events_in_range(
+                        None,
+                        &today.format("%Y-%m-%d").to_string(),
+                        &tomorrow.format("%Y-%m-%d").to_string(),
+                        &EventFilter::default(),
+                        200,
+                    )
+                    .await?;
+                (TODAY_AGENDA_URI, render_agenda(&events, tz, max_chars))
+            }
+            _ => {
+                return Err(Error::ResourceNotFound(format!(
+                    "groundeffect://{} (available: {}, {})",
+                    path, RECENT_EMAILS_URI, TODAY_AGENDA_URI
+                )))
+            }
+        };
+
+        Ok(serde_json::json!({
+            "contents": [{
+                "uri": uri,
+                "mimeType": "text/markdown",
+                "text": text
+            }]
+        }))
+    }
+
     /// Read an email resource
     async fn read_email_resource(&self, path: &str) -> Result<Value> {
         let parts: Vec<&str> = path.split('/').collect();
@@ -171,3 +322,28 @@ impl ResourceHandler {
         )))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_limited_drops_whole_entries() {
+        let entries = (0..5).map(|i| format!("- entry {}\n", i));
+        let text = render_limited("# Items\n\n", entries, 5, 30);
+
+        assert!(text.starts_with("# Items\n\n- entry 0\n- entry 1\n"));
+        assert!(!text.contains("entry 2"));
+        assert!(text.contains("… 3 more not shown"));
+    }
+
+    #[test]
+    fn test_render_limited_always_shows_one_entry() {
+        let text = render_limited("# Items\n\n", ["x".repeat(50)].into_iter(), 1, 10);
+        assert!(text.contains(&"x".repeat(50)));
+        assert_eq!(
+            render_limited("# Items\n\n", std::iter::empty(), 0, 10),
+            "# Items\n\nNothing here.\n"
+        );
+    }
+}
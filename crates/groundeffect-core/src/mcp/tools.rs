@@ -10,13 +10,87 @@ use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::TcpListener;
 use tracing::{debug, info, warn};
 
+use super::capabilities::{required_capability, McpCapability};
 use super::protocol::{ToolDefinition, ToolResult};
-use crate::config::{Config, DaemonConfig};
+use crate::availability::{availability_context, WorkingHours};
+use crate::bounces::{find_send_status, thread_delivery};
+use crate::classify::resolve_categories;
+use crate::config::{daemon_log_dir, Config, DaemonConfig};
 use crate::db::Database;
+use crate::digest::{build_digest, DigestPeriod};
+use crate::drafts::fetch_draft;
 use crate::error::{Error, Result};
-use crate::models::{Account, AccountStatus, Email, SendEmailRequest};
+use crate::forward::OutgoingAttachment;
+use crate::lint::{has_severe, lint_outgoing};
+use crate::models::{
+    event_color_name, normalize_message_id, parse_category_retention, parse_retention_age,
+    parse_storage_size, split_email_id, Account, AccountStatus, ActivitySource, AuditActor,
+    AuditEntry, Conference, Email, EmailCategory, EventFilter, ExtractionKind, Reminder,
+    ReminderMethod, SendEmailRequest, Task, TaskSource, TaskStatus, Transparency,
+};
 use crate::oauth::OAuthManager;
-use crate::search::{CalendarSearchOptions, SearchEngine, SearchOptions};
+use crate::receipts::{parse_month, spending_report};
+use crate::render::{RenderMode, ThreadCompactor};
+use crate::retention::RetentionReport;
+use crate::scheduling::{
+    find_conflicts, meet_request, parse_duration, parse_window, patch_event, place_hold,
+    propose_meeting_times, EventOptions, MeetingRequest, NewEventTime, Recurrence,
+};
+use crate::search::{
+    understand_query, CalendarSearchOptions, Diversity, ExpertiseOptions, FusionOptions,
+    RecencyBoost, ResultType, SearchEngine, SearchMode, SearchOptions, ThreadSearchOptions,
+    UnifiedSearchOptions,
+};
+use crate::signatures::{resolve_signature, Signature, SignatureChoice};
+use crate::sync::{
+    attendee_profiles, fetch_gmail_body, fetch_remote_email, list_gmail_labels, load_availability,
+    resolve_attendees, resolve_label_folder, search_gmail, SyncManager, REMOTE_SEARCH_MAX_RESULTS,
+};
+use crate::token_health::{TokenHealthReport, TokenState};
+use crate::travel::group_trips;
+use crate::unanswered::{find_unanswered, parse_age};
+
+/// Audit log action for a tool call, or None when the call changes nothing
+/// (read-only tools, send previews, status checks)
+///
+/// A tool this doesn't know is audited under its own name, so a new tool
+/// that writes is never treated as harmless.
+pub(crate) fn audited_action<'a>(tool: &'a str, args: &Value) -> Option<&'a str> {
+    match tool {
+        "send_email" if args["confirm"].as_bool().unwrap_or(false) => Some("send_email"),
+        "send_email" if args["save_as_draft"].as_bool().unwrap_or(false) => Some("create_draft"),
+        "send_email" => None,
+        "create_draft" => Some("create_draft"),
+        "update_draft" => Some("update_draft"),
+        "send_draft" => Some("send_draft"),
+        "delete_draft" => Some("delete_draft"),
+        "create_event" => Some("create_event"),
+        "update_event" => Some("update_event"),
+        "propose_meeting_times" if args["hold"].as_bool().unwrap_or(false) => Some("create_event"),
+        "manage_accounts" => match args["action"].as_str()? {
+            "add" => Some("add_account"),
+            "delete" => Some("delete_account"),
+            "configure" => Some("configure_account"),
+            _ => None,
+        },
+        "manage_sync" => match args["action"].as_str()? {
+            "reset" => Some("sync_reset"),
+            "extend" => Some("sync_extend"),
+            "resume_from" => Some("sync_resume_from"),
+            _ => None,
+        },
+        // Starts and stops the daemon; no mail or calendar data changes
+        "manage_daemon" => None,
+        "remote_search_emails" if args["import"].as_bool().unwrap_or(false) => {
+            Some("import_emails")
+        }
+        "get_email" if args["save"].as_bool().unwrap_or(false) => Some("save_email"),
+        "complete_task" => Some("complete_task"),
+        "set_email_tasks" => Some("set_email_tasks"),
+        _ if required_capability(tool, args) == McpCapability::Read => None,
+        _ => Some(tool),
+    }
+}
 
 /// Get all tool definitions
 pub fn get_tool_definitions() -> Vec<ToolDefinition> {
@@ -24,7 +98,7 @@ pub fn get_tool_definitions() -> Vec<ToolDefinition> {
         // Account management
         ToolDefinition {
             name: "manage_accounts".to_string(),
-            description: "Manage Gmail/GCal accounts. Actions: 'list' (all accounts), 'get' (one account), 'add' (OAuth flow), 'delete' (remove account+data), 'configure' (update settings).".to_string(),
+            description: "Manage Gmail/GCal accounts. Actions: 'list' (all accounts, plus needs_reauth: accounts whose tokens were revoked or expired, with reason and fix command; tell the user about these), 'get' (one account), 'add' (OAuth flow), 'delete' (remove account+data), 'configure' (update settings).".to_string(),
             input_schema: serde_json::json!({
                 "type": "object",
                 "properties": {
@@ -53,27 +127,673 @@ pub fn get_tool_definitions() -> Vec<ToolDefinition> {
                         "type": "boolean",
                         "description": "For 'configure': enable/disable calendar sync"
                     },
-                    "folders": {
+                    "folders": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "For 'configure': folders to sync (empty array = all folders)"
+                    },
+                    "sync_attachments": {
+                        "type": "boolean",
+                        "description": "For 'configure': enable/disable automatic attachment download during sync (off by default, requires daemon restart)"
+                    },
+                    "include_labels": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "For 'configure': only sync emails with at least one of these Gmail labels, e.g. ['INBOX', 'Work'] (empty array = no include filter)"
+                    },
+                    "exclude_labels": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "For 'configure': never sync emails with these Gmail labels, e.g. ['Promotions', 'Spam'] (empty array = no exclude filter)"
+                    },
+                    "max_storage": {
+                        "type": "string",
+                        "description": "For 'configure': local storage limit, e.g. '5GB' or '500MB' ('none' removes it). When reached, attachment downloads and historical backfill pause; new mail keeps syncing."
+                    },
+                    "retain": {
+                        "type": "string",
+                        "description": "For 'configure': keep mail locally for this long, e.g. '2y', '6m' or '90d' ('none' keeps everything). The daemon prunes older mail with its attachments and embeddings; nothing is deleted in Gmail."
+                    },
+                    "retain_categories": {
+                        "type": "object",
+                        "additionalProperties": {"type": "string"},
+                        "description": "For 'configure': shorter retention per sender category (personal, newsletter, notification, transactional; 'promotions' means newsletter), e.g. {\"newsletter\": \"30d\"}. 'none' removes a category's rule."
+                    },
+                    "headers_only": {
+                        "type": "boolean",
+                        "description": "For 'configure': store only headers and snippets for new mail (no bodies or attachments) to keep disk use minimal on large archival accounts. Bodies can be fetched on demand with get_email fetch=true."
+                    },
+                    "confirm": {
+                        "type": "boolean",
+                        "description": "For 'delete': must be true to confirm deletion"
+                    }
+                },
+                "required": ["action"]
+            }),
+        },
+        ToolDefinition {
+            name: "search_all".to_string(),
+            description: "Search emails, calendar events, attachments and contacts in one call. Start here for general questions when it isn't clear where the answer lives; use search_emails or search_calendar for their filters. Each result has a type (email, event, attachment or contact), score (relative to the best result of its type, used to interleave), type_score and type_rank, plus that type's fields.".to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "query": {
+                        "type": "string",
+                        "description": "Search query (natural language; attachments and contacts match its words)"
+                    },
+                    "types": {
+                        "type": "array",
+                        "items": {"type": "string", "enum": ["email", "event", "attachment", "contact"]},
+                        "description": "Result types to include. Omit for all."
+                    },
+                    "accounts": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "Account(s) to search (email addresses or aliases). Omit to search ALL accounts."
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "default": 20,
+                        "maximum": 100
+                    },
+                    "include_spam_trash": {
+                        "type": "boolean",
+                        "description": "Include mail in Spam and Trash. Default: config [search] include_spam_trash."
+                    },
+                    "mode": {
+                        "type": "string",
+                        "enum": ["hybrid", "semantic", "keyword"],
+                        "default": "hybrid",
+                        "description": "Retrievers to run for emails and events"
+                    }
+                },
+                "required": ["query"]
+            }),
+        },
+        // Email tools
+        ToolDefinition {
+            name: "search_emails".to_string(),
+            description: "Search emails using hybrid BM25 + vector search across one or more accounts".to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "query": {
+                        "type": "string",
+                        "description": "Search query (natural language)"
+                    },
+                    "intent": {
+                        "type": "string",
+                        "enum": ["search", "list"],
+                        "description": "Use 'list' for recent/latest/unread requests (fast path), 'search' for content-based queries (semantic search)"
+                    },
+                    "accounts": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "Account(s) to search (email addresses or aliases). Omit to search ALL accounts."
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "default": 10,
+                        "maximum": 100
+                    },
+                    "folder": {
+                        "type": "string",
+                        "description": "Filter by folder or Gmail label name/ID (e.g., INBOX, SENT, Work/Projects). See list_folders for valid values."
+                    },
+                    "from": {
+                        "type": "string",
+                        "description": "Filter by sender email/name"
+                    },
+                    "to": {
+                        "type": "string",
+                        "description": "Filter by recipient email/name"
+                    },
+                    "date_from": {
+                        "type": "string",
+                        "format": "date",
+                        "description": "Filter emails after this date"
+                    },
+                    "date_to": {
+                        "type": "string",
+                        "format": "date",
+                        "description": "Filter emails before this date"
+                    },
+                    "has_attachment": {
+                        "type": "boolean",
+                        "description": "Filter emails with attachments"
+                    },
+                    "category": {
+                        "type": "string",
+                        "enum": ["personal", "newsletter", "notification", "transactional"],
+                        "description": "Filter by sender category. Use 'personal' for mail written by real people."
+                    },
+                    "tag": {
+                        "type": "string",
+                        "description": "Filter by a tag added by the user's local tagging rules ([[tagging.rules]] in config), e.g. 'clients'"
+                    },
+                    "sent": {
+                        "type": "boolean",
+                        "description": "true: only emails the user sent (e.g. 'what did I promise Bob'); false: only emails they received. Each result has is_sent."
+                    },
+                    "include_activity": {
+                        "type": "boolean",
+                        "description": "Include GitHub/Jira/Asana notification emails. These are collapsed into activity items (see list_activity) and left out of search by default."
+                    },
+                    "include_spam_trash": {
+                        "type": "boolean",
+                        "description": "Include mail in Spam and Trash, left out by default (unless folder names one of them). Default: config [search] include_spam_trash."
+                    },
+                    "highlight": {
+                        "type": "boolean",
+                        "description": "Also return snippet_html with the matched query terms wrapped in <em>. Snippets always show the part of the body that matched."
+                    },
+                    "mode": {
+                        "type": "string",
+                        "enum": ["hybrid", "semantic", "keyword"],
+                        "default": "hybrid",
+                        "description": "Retrievers to run. 'keyword' matches exact words only (order numbers, names, codes); 'semantic' matches meaning regardless of wording; 'hybrid' fuses both. Each result's score_components shows its BM25 and vector scores."
+                    },
+                    "recency_weight": {
+                        "type": "number",
+                        "minimum": 0,
+                        "maximum": 1,
+                        "description": "Share of the score from how recent an email is (0 = relevance only). Use ~0.3 when the user means the latest match, e.g. 'the flight confirmation'. Defaults to the configured value."
+                    },
+                    "dedup_threads": {
+                        "type": "boolean",
+                        "description": "Return only the best-matching email of each thread, so one long thread can't fill the results. Default: config [search] dedup_threads (true). Set false to see every matching message."
+                    },
+                    "mmr_lambda": {
+                        "type": "number",
+                        "minimum": 0,
+                        "maximum": 1,
+                        "description": "Maximal marginal relevance trade-off: 1 ranks by relevance only, lower values push down results similar to ones already ranked. Use ~0.7 for broad questions that need varied results. Defaults to the configured value (1)."
+                    },
+                    "smart_query": {
+                        "type": "boolean",
+                        "default": true,
+                        "description": "Take sender, recipient, date, attachment and sent-by-me filters from the query itself ('from Dana about the offsite last month' searches 'offsite' from Dana in last month). Filters passed as parameters win. The response's 'understood' shows what was taken."
+                    }
+                },
+                "required": ["query"]
+            }),
+        },
+        ToolDefinition {
+            name: "find_similar_emails".to_string(),
+            description: "Find emails similar to a given email by vector similarity (\"other emails like this one\"). Leaves out the email itself and the rest of its thread. Returns the same result format as search_emails.".to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "id": {
+                        "type": "string",
+                        "description": "Email ID to find similar emails for"
+                    },
+                    "accounts": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "Account(s) to look in (email addresses or aliases). Omit to look in ALL accounts."
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "default": 10,
+                        "maximum": 100
+                    },
+                    "include_spam_trash": {
+                        "type": "boolean",
+                        "description": "Include mail in Spam and Trash. Default: config [search] include_spam_trash."
+                    }
+                },
+                "required": ["id"]
+            }),
+        },
+        ToolDefinition {
+            name: "find_experts".to_string(),
+            description: "Rank the user's contacts by how much their mail is about a topic, for \"who should I ask about X\" questions. Each contact's profile combines the embeddings of the mail they sent the user and the mail the user sent them; contacts with few emails rank lower. Newsletters and automated mail are left out. Returns contacts with score, email counts each way, last_contact and evidence (their emails most like the query).".to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "query": {
+                        "type": "string",
+                        "description": "The topic, e.g. 'kubernetes budget approvals'"
+                    },
+                    "account": {
+                        "type": "string",
+                        "description": "Only use mail from this account (email or alias). Omit to use ALL accounts."
+                    },
+                    "since": {
+                        "type": "string",
+                        "default": "365d",
+                        "description": "How far back to look: an age (90d, 12w) or a date (YYYY-MM-DD)"
+                    },
+                    "min_emails": {
+                        "type": "integer",
+                        "default": 2,
+                        "description": "Only rank contacts with at least this many emails"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "default": 10,
+                        "maximum": 100
+                    }
+                },
+                "required": ["query"]
+            }),
+        },
+        ToolDefinition {
+            name: "get_digest".to_string(),
+            description: "Daily or weekly digest of what needs the user's attention, for a morning briefing. Returns important_emails (new mail from people, starred and directly addressed first), unanswered (threads waiting for the user's reply), upcoming_meetings and their conflicts, and follow_ups (open tasks due by the end of the period, overdue included). Summarize it for the user rather than listing it verbatim.".to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "period": {
+                        "type": "string",
+                        "enum": ["day", "week"],
+                        "default": "day",
+                        "description": "day: mail from the last 24 hours and meetings in the next 24; week: the last and next 7 days"
+                    },
+                    "account": {
+                        "type": "string",
+                        "description": "Only this account (email or alias). Omit for ALL accounts."
+                    }
+                }
+            }),
+        },
+        ToolDefinition {
+            name: "list_emails".to_string(),
+            description: "List recent emails sorted by date (newest first). Much faster than search_emails for just getting recent messages.".to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "account": {
+                        "type": "string",
+                        "description": "Account email or alias. Omit to list from ALL accounts."
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "default": 10,
+                        "maximum": 100,
+                        "description": "Number of emails to return"
+                    },
+                    "category": {
+                        "type": "string",
+                        "enum": ["personal", "newsletter", "notification", "transactional"],
+                        "description": "Filter by sender category. Use 'personal' for mail written by real people."
+                    },
+                    "suspicious": {
+                        "type": "boolean",
+                        "default": false,
+                        "description": "Only mail that failed SPF/DKIM/DMARC or whose display name impersonates another address (possible phishing); each result lists its security_warnings"
+                    }
+                }
+            }),
+        },
+        ToolDefinition {
+            name: "remote_search_emails".to_string(),
+            description: "Run a Gmail search query on Gmail itself, for mail that isn't synced locally (older than the synced range, unsynced labels, or bodies of headers-only accounts). Slower than search_emails: each hit is downloaded. Results say whether each email is in the local index; import stores the hits locally.".to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "gmail_query": {
+                        "type": "string",
+                        "description": "Gmail search syntax, e.g. 'from:alice has:attachment newer_than:7d' or 'subject:invoice before:2019/01/01'"
+                    },
+                    "account": {
+                        "type": "string",
+                        "description": "Account email or alias. Omit to search ALL accounts."
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "default": 10,
+                        "maximum": 100,
+                        "description": "Number of emails to return"
+                    },
+                    "import": {
+                        "type": "boolean",
+                        "default": false,
+                        "description": "Store hits that aren't local yet in the local index, embedded and categorized as sync would"
+                    }
+                },
+                "required": ["gmail_query"]
+            }),
+        },
+        ToolDefinition {
+            name: "list_activity".to_string(),
+            description: "List activity parsed from GitHub, Jira and Asana notification emails (newest first): one item per notification with project/repo, issue/PR/task, action (commented, merged, assigned, ...), actor and link. Use this instead of searching emails for tool notifications.".to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "account": {
+                        "type": "string",
+                        "description": "Account email or alias. Omit to list from ALL accounts."
+                    },
+                    "source": {
+                        "type": "string",
+                        "enum": ["github", "jira", "asana"],
+                        "description": "Only activity from this tool"
+                    },
+                    "project": {
+                        "type": "string",
+                        "description": "Only this repository (owner/repo), Jira project key, or Asana project ID"
+                    },
+                    "date_from": {
+                        "type": "string",
+                        "format": "date",
+                        "description": "Only activity on or after this date"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "default": 20,
+                        "maximum": 200,
+                        "description": "Number of items to return"
+                    }
+                }
+            }),
+        },
+        ToolDefinition {
+            name: "list_unanswered".to_string(),
+            description: "List emails addressed directly to the user (in To) that they haven't replied to: nothing sent later in the same thread. Skips newsletters, notifications and receipts. One entry per thread (latest unanswered message) with waiting_days and pending_count. Use to remind the user about dropped threads.".to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "since": {
+                        "type": "string",
+                        "default": "7d",
+                        "description": "How far back to look: an age (12h, 7d, 2w) or a date (YYYY-MM-DD)"
+                    },
+                    "account": {
+                        "type": "string",
+                        "description": "Account email or alias. Omit to check ALL accounts."
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "default": 50,
+                        "maximum": 500,
+                        "description": "Number of emails to return"
+                    }
+                }
+            }),
+        },
+        ToolDefinition {
+            name: "list_receipts".to_string(),
+            description: "List receipts and invoices detected in email (newest first) with merchant, amount, currency and date, plus totals per currency. Use for expense reports and 'what did I spend on X' questions.".to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "account": {
+                        "type": "string",
+                        "description": "Account email or alias. Omit to list from ALL accounts."
+                    },
+                    "month": {
+                        "type": "string",
+                        "description": "Only receipts from this month (YYYY-MM)"
+                    },
+                    "merchant": {
+                        "type": "string",
+                        "description": "Only merchants whose name contains this (case-insensitive)"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "default": 50,
+                        "maximum": 500,
+                        "description": "Number of receipts to return"
+                    }
+                }
+            }),
+        },
+        ToolDefinition {
+            name: "spending_report".to_string(),
+            description: "Total spending from receipts detected in email (including totals read from PDF attachments), optionally split by month and/or merchant. One row per group and currency with total and count; currencies are never mixed. Use for 'how much did I spend on X per month'.".to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "by_month": {
+                        "type": "boolean",
+                        "default": false,
+                        "description": "One row per month (YYYY-MM, user's timezone)"
+                    },
+                    "by_merchant": {
+                        "type": "boolean",
+                        "default": false,
+                        "description": "One row per merchant"
+                    },
+                    "date_from": {
+                        "type": "string",
+                        "format": "date",
+                        "description": "Only receipts on or after this date"
+                    },
+                    "date_to": {
+                        "type": "string",
+                        "format": "date",
+                        "description": "Only receipts before this date"
+                    },
+                    "merchant": {
+                        "type": "string",
+                        "description": "Only merchants whose name contains this (case-insensitive)"
+                    },
+                    "account": {
+                        "type": "string",
+                        "description": "Account email or alias. Omit to include ALL accounts."
+                    }
+                }
+            }),
+        },
+        ToolDefinition {
+            name: "list_trips".to_string(),
+            description: "List upcoming trips parsed from flight and hotel confirmation emails, soonest first. Each trip has start, end, destinations and its flights/hotel stays (times in UTC with the local IANA timezone at each end, confirmation numbers, and the confirmation email_id).".to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "account": {
+                        "type": "string",
+                        "description": "Account email or alias. Omit to list from ALL accounts."
+                    },
+                    "days": {
+                        "type": "integer",
+                        "default": 90,
+                        "maximum": 365,
+                        "description": "How many days ahead to look"
+                    }
+                }
+            }),
+        },
+        ToolDefinition {
+            name: "list_extractions".to_string(),
+            description: "List flights, hotel stays, restaurant reservations and package deliveries found in email (schema.org markup, plus tracking numbers in shipping notifications). Use for 'when is my flight', 'where is dinner tonight' or 'when does my package arrive'. With upcoming=true: reservations not yet over and undelivered packages, soonest first; otherwise newest email first. Times are UTC; email_id is the source email.".to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "type": {
+                        "type": "string",
+                        "enum": ["flight", "hotel", "restaurant", "package"],
+                        "description": "Only this type. Omit for all."
+                    },
+                    "upcoming": {
+                        "type": "boolean",
+                        "default": false,
+                        "description": "Only reservations not yet over and packages not yet delivered"
+                    },
+                    "account": {
+                        "type": "string",
+                        "description": "Account email or alias. Omit to list from ALL accounts."
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "default": 20,
+                        "maximum": 500,
+                        "description": "Number of items to return"
+                    }
+                }
+            }),
+        },
+        ToolDefinition {
+            name: "list_tasks".to_string(),
+            description: "List action items found in email: requests made of the user ('can you send the deck by Friday?') with requester, subject, a guessed due date (YYYY-MM-DD, user's timezone) and the source email_id. Open tasks with a due date come first, soonest first. Use for 'what do I owe people' or 'what's due this week'.".to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "status": {
+                        "type": "string",
+                        "enum": ["open", "done", "all"],
+                        "default": "open",
+                        "description": "Which tasks to list"
+                    },
+                    "due_before": {
+                        "type": "string",
+                        "format": "date",
+                        "description": "Only tasks due on or before this date"
+                    },
+                    "account": {
+                        "type": "string",
+                        "description": "Account email or alias. Omit to list from ALL accounts."
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "default": 50,
+                        "maximum": 500,
+                        "description": "Number of tasks to return"
+                    }
+                }
+            }),
+        },
+        ToolDefinition {
+            name: "complete_task".to_string(),
+            description: "Mark a task from list_tasks done (or open again with reopen=true). Only changes GroundEffect's local task list; nothing is sent.".to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "id": {
+                        "type": "string",
+                        "description": "Task ID from list_tasks"
+                    },
+                    "reopen": {
+                        "type": "boolean",
+                        "default": false,
+                        "description": "Mark the task open again"
+                    }
+                },
+                "required": ["id"]
+            }),
+        },
+        ToolDefinition {
+            name: "set_email_tasks".to_string(),
+            description: "Record the action items you found after reading an email, replacing the open tasks GroundEffect extracted from it automatically (tasks already marked done are kept). Only changes GroundEffect's local task list.".to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "email_id": {
+                        "type": "string",
+                        "description": "Email the tasks come from"
+                    },
+                    "tasks": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "text": {
+                                    "type": "string",
+                                    "description": "What the user has been asked to do"
+                                },
+                                "due": {
+                                    "type": "string",
+                                    "format": "date",
+                                    "description": "Due date (YYYY-MM-DD), if the email gives one"
+                                }
+                            },
+                            "required": ["text"]
+                        }
+                    }
+                },
+                "required": ["email_id", "tasks"]
+            }),
+        },
+        ToolDefinition {
+            name: "get_email".to_string(),
+            description: "Fetch single email by ID, or by its RFC Message-ID (e.g. from a mid: or message:// link in a note or event)".to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "id": {
+                        "type": "string",
+                        "description": "Email ID"
+                    },
+                    "message_id": {
+                        "type": "string",
+                        "description": "RFC 5322 Message-ID instead of id, with or without angle brackets, or a mid:/message:// link"
+                    },
+                    "account": {
+                        "type": "string",
+                        "description": "With message_id: account whose copy to return (default: newest copy on any account)"
+                    },
+                    "render": {
+                        "type": "string",
+                        "enum": ["full", "compact"],
+                        "default": "full",
+                        "description": "'compact' renders HTML mail as markdown, collapses quoted text, drops repeated signatures, and normalizes whitespace. Elided content is replaced with [bracketed markers]."
+                    },
+                    "fetch": {
+                        "type": "boolean",
+                        "default": false,
+                        "description": "For emails synced headers-only (headers_only: true), download the body and attachment list from Gmail. Not stored locally."
+                    },
+                    "remote": {
+                        "type": "boolean",
+                        "default": false,
+                        "description": "If the email isn't in the local index (older than the synced range, or not synced yet), fetch it from Gmail instead of failing. Needs message_id or an id of the form <account>:<Message-ID>; the response has remote: true."
+                    },
+                    "save": {
+                        "type": "boolean",
+                        "default": false,
+                        "description": "With remote: store the fetched email locally, embedded and categorized as sync would"
+                    }
+                }
+            }),
+        },
+        ToolDefinition {
+            name: "get_thread".to_string(),
+            description: "Fetch all emails in a thread. Use render='compact' for long threads to cut tokens several-fold.".to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "thread_id": {
+                        "type": "string",
+                        "description": "Gmail thread ID"
+                    },
+                    "render": {
+                        "type": "string",
+                        "enum": ["full", "compact"],
+                        "default": "full",
+                        "description": "'compact' renders HTML mail as markdown, collapses quoted replies, shows each signature only once per thread, normalizes whitespace, and omits snippets. Elided content is replaced with [bracketed markers]."
+                    },
+                    "accounts": {
                         "type": "array",
                         "items": {"type": "string"},
-                        "description": "For 'configure': folders to sync (empty array = all folders)"
-                    },
-                    "sync_attachments": {
-                        "type": "boolean",
-                        "description": "For 'configure': enable/disable automatic attachment download during sync (off by default, requires daemon restart)"
+                        "description": "Filter to specific accounts"
+                    }
+                },
+                "required": ["thread_id"]
+            }),
+        },
+        ToolDefinition {
+            name: "get_send_status".to_string(),
+            description: "Check whether a sent email was delivered. Bounces from mailer-daemon/postmaster received within 7 days are linked to it by Message-ID or thread. Returns status ('sent' = nothing bounced, 'delayed', 'failed'), failed_recipients, delayed_recipients and the bounces with their status codes and server responses.".to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "id": {
+                        "type": "string",
+                        "description": "Email ID or RFC Message-ID of the sent email"
                     },
-                    "confirm": {
-                        "type": "boolean",
-                        "description": "For 'delete': must be true to confirm deletion"
+                    "account": {
+                        "type": "string",
+                        "description": "With a Message-ID: account that sent it"
                     }
                 },
-                "required": ["action"]
+                "required": ["id"]
             }),
         },
-        // Email tools
         ToolDefinition {
-            name: "search_emails".to_string(),
-            description: "Search emails using hybrid BM25 + vector search across one or more accounts".to_string(),
+            name: "search_threads".to_string(),
+            description: "Search whole conversations (one result per Gmail thread) with hybrid BM25 + vector search over the subject, participants and every message. Better than search_emails when the answer is spread over several replies. Returns participants, message_count, last_activity and gmail_thread_id (use with get_thread).".to_string(),
             input_schema: serde_json::json!({
                 "type": "object",
                 "properties": {
@@ -81,54 +801,48 @@ pub fn get_tool_definitions() -> Vec<ToolDefinition> {
                         "type": "string",
                         "description": "Search query (natural language)"
                     },
-                    "intent": {
-                        "type": "string",
-                        "enum": ["search", "list"],
-                        "description": "Use 'list' for recent/latest/unread requests (fast path), 'search' for content-based queries (semantic search)"
-                    },
                     "accounts": {
                         "type": "array",
                         "items": {"type": "string"},
-                        "description": "Account(s) to search (email addresses or aliases). Omit to search ALL accounts."
-                    },
-                    "limit": {
-                        "type": "integer",
-                        "default": 10,
-                        "maximum": 100
-                    },
-                    "folder": {
-                        "type": "string",
-                        "description": "Filter by folder (e.g., INBOX, Sent)"
-                    },
-                    "from": {
-                        "type": "string",
-                        "description": "Filter by sender email/name"
+                        "description": "Account(s) to search. Omit to search ALL accounts."
                     },
-                    "to": {
+                    "participant": {
                         "type": "string",
-                        "description": "Filter by recipient email/name"
+                        "description": "Only threads with a participant whose address or name contains this"
                     },
                     "date_from": {
                         "type": "string",
                         "format": "date",
-                        "description": "Filter emails after this date"
+                        "description": "Only threads active on or after this date"
                     },
                     "date_to": {
                         "type": "string",
                         "format": "date",
-                        "description": "Filter emails before this date"
+                        "description": "Only threads active on or before this date"
                     },
-                    "has_attachment": {
+                    "limit": {
+                        "type": "integer",
+                        "default": 10,
+                        "maximum": 100
+                    },
+                    "mode": {
+                        "type": "string",
+                        "enum": ["hybrid", "semantic", "keyword"],
+                        "default": "hybrid",
+                        "description": "Retrievers to run: 'keyword' (exact words), 'semantic' (meaning) or 'hybrid' (both, fused)"
+                    },
+                    "smart_query": {
                         "type": "boolean",
-                        "description": "Filter emails with attachments"
+                        "default": true,
+                        "description": "Take participant and date filters from the query itself ('from Dana about the offsite last month'; the sender or recipient becomes the participant). Filters passed as parameters win."
                     }
                 },
                 "required": ["query"]
             }),
         },
         ToolDefinition {
-            name: "list_emails".to_string(),
-            description: "List recent emails sorted by date (newest first). Much faster than search_emails for just getting recent messages.".to_string(),
+            name: "list_threads".to_string(),
+            description: "List conversations by latest activity (most recent first) with subject, participants, message and unread counts, and gmail_thread_id (use with get_thread).".to_string(),
             input_schema: serde_json::json!({
                 "type": "object",
                 "properties": {
@@ -136,48 +850,20 @@ pub fn get_tool_definitions() -> Vec<ToolDefinition> {
                         "type": "string",
                         "description": "Account email or alias. Omit to list from ALL accounts."
                     },
+                    "since": {
+                        "type": "string",
+                        "format": "date",
+                        "description": "Only threads active on or after this date (YYYY-MM-DD)"
+                    },
                     "limit": {
                         "type": "integer",
-                        "default": 10,
-                        "maximum": 100,
-                        "description": "Number of emails to return"
+                        "default": 20,
+                        "maximum": 200,
+                        "description": "Number of threads to return"
                     }
                 }
             }),
         },
-        ToolDefinition {
-            name: "get_email".to_string(),
-            description: "Fetch single email by ID".to_string(),
-            input_schema: serde_json::json!({
-                "type": "object",
-                "properties": {
-                    "id": {
-                        "type": "string",
-                        "description": "Email ID"
-                    }
-                },
-                "required": ["id"]
-            }),
-        },
-        ToolDefinition {
-            name: "get_thread".to_string(),
-            description: "Fetch all emails in a thread".to_string(),
-            input_schema: serde_json::json!({
-                "type": "object",
-                "properties": {
-                    "thread_id": {
-                        "type": "string",
-                        "description": "Gmail thread ID"
-                    },
-                    "accounts": {
-                        "type": "array",
-                        "items": {"type": "string"},
-                        "description": "Filter to specific accounts"
-                    }
-                },
-                "required": ["thread_id"]
-            }),
-        },
         ToolDefinition {
             name: "send_email".to_string(),
             description: "Compose and send an email. By default returns a preview for user confirmation. Set confirm=true to send, or save_as_draft=true to save as draft. Supports HTML via explicit flag or auto-detection of markdown links, plain URLs, bold/italic markdown, or HTML tags.".to_string(),
@@ -188,6 +874,10 @@ pub fn get_tool_definitions() -> Vec<ToolDefinition> {
                         "type": "string",
                         "description": "Account email or alias to send from"
                     },
+                    "send_as": {
+                        "type": "string",
+                        "description": "Send-as address to use in From. Signature, reply-to and default CC configured for this identity under compose.identities are applied automatically."
+                    },
                     "to": {
                         "type": "array",
                         "items": {"type": "string"},
@@ -215,6 +905,11 @@ pub fn get_tool_definitions() -> Vec<ToolDefinition> {
                         "type": "string",
                         "description": "Email ID to reply to (for threading)"
                     },
+                    "signature": {
+                        "type": "string",
+                        "description": "'default' (the identity's signature from config, else its Gmail signature), 'none', or a signature name from compose.signatures or a send-as address whose Gmail signature to use. Plain-text and HTML versions go in the matching message parts.",
+                        "default": "default"
+                    },
                     "html": {
                         "type": "boolean",
                         "description": "Force HTML format. If false, auto-detects based on content (markdown links, URLs, bold/italic, HTML tags)."
@@ -227,6 +922,11 @@ pub fn get_tool_definitions() -> Vec<ToolDefinition> {
                         "type": "boolean",
                         "description": "Set to true to send immediately. If false/omitted, returns preview for user approval.",
                         "default": false
+                    },
+                    "strict_lint": {
+                        "type": "boolean",
+                        "description": "Refuse to send or save when the pre-send formatting lint finds severe issues (link text/URL mismatch, missing plain-text part, huge inline images). The preview always lists lint issues.",
+                        "default": false
                     }
                 },
                 "required": ["from_account", "to", "subject", "body"]
@@ -234,7 +934,7 @@ pub fn get_tool_definitions() -> Vec<ToolDefinition> {
         },
         ToolDefinition {
             name: "list_folders".to_string(),
-            description: "List all IMAP folders".to_string(),
+            description: "List Gmail labels (system and user-created) with colors and message counts. Label names, IDs and folders are valid values for the search_emails folder filter. Labels are cached for an hour.".to_string(),
             input_schema: serde_json::json!({
                 "type": "object",
                 "properties": {
@@ -242,6 +942,11 @@ pub fn get_tool_definitions() -> Vec<ToolDefinition> {
                         "type": "array",
                         "items": {"type": "string"},
                         "description": "Filter to specific accounts"
+                    },
+                    "refresh": {
+                        "type": "boolean",
+                        "description": "Bypass the label cache and refetch from Gmail",
+                        "default": false
                     }
                 }
             }),
@@ -279,6 +984,10 @@ pub fn get_tool_definitions() -> Vec<ToolDefinition> {
                         "type": "string",
                         "description": "Account email or alias to create draft from"
                     },
+                    "send_as": {
+                        "type": "string",
+                        "description": "Send-as address to use in From; applies that identity's configured signature, reply-to and default CC"
+                    },
                     "to": {
                         "type": "array",
                         "items": {"type": "string"},
@@ -309,6 +1018,11 @@ pub fn get_tool_definitions() -> Vec<ToolDefinition> {
                     "reply_to_id": {
                         "type": "string",
                         "description": "Email ID to reply to (for threading)"
+                    },
+                    "signature": {
+                        "type": "string",
+                        "description": "'default' (the identity's signature from config, else its Gmail signature), 'none', or a signature name from compose.signatures or a send-as address whose Gmail signature to use. Plain-text and HTML versions go in the matching message parts.",
+                        "default": "default"
                     }
                 },
                 "required": ["from_account", "to", "subject", "body"]
@@ -467,6 +1181,12 @@ pub fn get_tool_definitions() -> Vec<ToolDefinition> {
                         "type": "string",
                         "format": "date",
                         "description": "Filter events before this date"
+                    },
+                    "mode": {
+                        "type": "string",
+                        "enum": ["hybrid", "semantic", "keyword"],
+                        "default": "hybrid",
+                        "description": "Retrievers to run: 'keyword' (exact words), 'semantic' (meaning) or 'hybrid' (both, fused)"
                     }
                 },
                 "required": ["query"]
@@ -474,7 +1194,7 @@ pub fn get_tool_definitions() -> Vec<ToolDefinition> {
         },
         ToolDefinition {
             name: "get_event".to_string(),
-            description: "Fetch single calendar event by ID".to_string(),
+            description: "Fetch single calendar event by ID. When the user enables attendee profiles, includes attendee_profiles (job title, department, photo URL from the Workspace directory) keyed by email.".to_string(),
             input_schema: serde_json::json!({
                 "type": "object",
                 "properties": {
@@ -488,7 +1208,7 @@ pub fn get_tool_definitions() -> Vec<ToolDefinition> {
         },
         ToolDefinition {
             name: "list_events".to_string(),
-            description: "List calendar events in a date range WITHOUT semantic search. Use this to answer questions like 'what's on my calendar tomorrow' or 'show me my meetings next week'. Unlike search_events, this does NOT require a search query - it simply lists all events in the specified date range chronologically.".to_string(),
+            description: "List calendar events in a date range WITHOUT semantic search. Use this to answer questions like 'what's on my calendar tomorrow' or 'show me my meetings next week'. Unlike search_events, this does NOT require a search query - it simply lists all events in the specified date range chronologically. Events with a video call have 'conference' (provider, join_url, meeting_code, phone, passcode), e.g. for 'what's the Zoom link for my 2pm'. Each event has show_as ('busy' or 'free'), visibility and color; filter with show_as='busy' to skip free placeholder events.".to_string(),
             input_schema: serde_json::json!({
                 "type": "object",
                 "properties": {
@@ -512,6 +1232,20 @@ pub fn get_tool_definitions() -> Vec<ToolDefinition> {
                         "default": 50,
                         "maximum": 200,
                         "description": "Maximum number of events to return"
+                    },
+                    "show_as": {
+                        "type": "string",
+                        "enum": ["busy", "free"],
+                        "description": "Only events that block time ('busy') or don't ('free')"
+                    },
+                    "visibility": {
+                        "type": "string",
+                        "enum": ["default", "public", "private", "confidential"],
+                        "description": "Only events with this visibility"
+                    },
+                    "color": {
+                        "type": "string",
+                        "description": "Only events with this color (name or colorId 1-11)"
                     }
                 }
             }),
@@ -530,9 +1264,71 @@ pub fn get_tool_definitions() -> Vec<ToolDefinition> {
                 }
             }),
         },
+        ToolDefinition {
+            name: "availability_context".to_string(),
+            description: "The user's working hours (from config, in their timezone), whether they're working or away now, the next time they're available, and current/upcoming away blocks from out-of-office calendar events and the Gmail vacation responder. Call before suggesting meeting times so suggestions fall within working hours and outside away blocks.".to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "accounts": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "Accounts whose out-of-office state to include. Omit for ALL accounts."
+                    },
+                    "refresh": {
+                        "type": "boolean",
+                        "default": false,
+                        "description": "Fetch from Gmail and Calendar instead of using the state cached at the last calendar sync (up to an hour old)"
+                    }
+                }
+            }),
+        },
+        ToolDefinition {
+            name: "propose_meeting_times".to_string(),
+            description: "Propose ranked meeting slots for a set of attendees. Slots fall within the user's working hours, avoid their events and away blocks, and are scored by the share of attendees free per Google Calendar free/busy (attendees whose calendars aren't visible count half and are listed in not_visible). Set hold=true to place a tentative event for the best slot (needs the calendar capability); attendees are not invited.".to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "attendees": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "Attendee email addresses, or names to look up in the Google Workspace directory"
+                    },
+                    "duration": {
+                        "type": "string",
+                        "default": "30m",
+                        "description": "Meeting length, e.g. '45m', '1h', '1h30m'"
+                    },
+                    "window": {
+                        "type": "string",
+                        "default": "next-week",
+                        "description": "When to look: 'today', 'tomorrow', 'this-week', 'next-week', a length from now ('3d', '2w'), or 'YYYY-MM-DD..YYYY-MM-DD' (end inclusive). At most 31 days."
+                    },
+                    "account": {
+                        "type": "string",
+                        "description": "Account to query free/busy with and place the hold in (default: first account)"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "default": 5,
+                        "description": "Maximum slots to return"
+                    },
+                    "hold": {
+                        "type": "boolean",
+                        "default": false,
+                        "description": "Place a tentative event for the best slot on the primary calendar"
+                    },
+                    "title": {
+                        "type": "string",
+                        "description": "Hold event title (default: 'Hold: meeting with <attendees>')"
+                    }
+                },
+                "required": ["attendees"]
+            }),
+        },
         ToolDefinition {
             name: "create_event".to_string(),
-            description: "Create a new calendar event".to_string(),
+            description: "Create a new calendar event. If it overlaps synced events (ignoring free, cancelled and declined ones), nothing is created and the overlapping events are returned in 'conflicts'; set force=true to create it anyway. Set with_meet=true to add a Google Meet link.".to_string(),
             input_schema: serde_json::json!({
                 "type": "object",
                 "properties": {
@@ -547,12 +1343,17 @@ pub fn get_tool_definitions() -> Vec<ToolDefinition> {
                     "start": {
                         "type": "string",
                         "format": "date-time",
-                        "description": "Start time (ISO 8601)"
+                        "description": "Start time (ISO 8601), or the first day (YYYY-MM-DD) for all_day events"
                     },
                     "end": {
                         "type": "string",
                         "format": "date-time",
-                        "description": "End time (ISO 8601)"
+                        "description": "End time (ISO 8601), or the last day (YYYY-MM-DD, inclusive) for all_day events"
+                    },
+                    "all_day": {
+                        "type": "boolean",
+                        "default": false,
+                        "description": "All-day event: start and end are dates (YYYY-MM-DD). end defaults to start; Google's exclusive end date (the day after) is returned."
                     },
                     "calendar_id": {
                         "type": "string",
@@ -569,16 +1370,131 @@ pub fn get_tool_definitions() -> Vec<ToolDefinition> {
                     "attendees": {
                         "type": "array",
                         "items": {"type": "string"},
-                        "description": "Attendee email addresses"
+                        "description": "Attendee email addresses, or names to look up in the Google Workspace directory (e.g. 'Priya in Design'). Names must match exactly one person."
+                    },
+                    "timezone": {
+                        "type": "string",
+                        "description": "IANA timezone for start/end times without an offset, and the event's timezone in Google Calendar (default: the user's timezone)"
+                    },
+                    "repeat": {
+                        "type": "string",
+                        "enum": ["daily", "weekdays", "weekly", "biweekly", "monthly", "yearly"],
+                        "description": "Make the event repeat"
+                    },
+                    "on": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "Days for weekly/biweekly repeats, e.g. ['mon', 'wed'] (default: the start's day)"
+                    },
+                    "until": {
+                        "type": "string",
+                        "format": "date",
+                        "description": "Last day the event repeats (YYYY-MM-DD). Use until or count."
+                    },
+                    "count": {
+                        "type": "integer",
+                        "description": "Number of occurrences"
+                    },
+                    "recurrence": {
+                        "type": "string",
+                        "description": "Raw RRULE instead of repeat/on/until/count, e.g. 'FREQ=MONTHLY;BYDAY=1MO'"
+                    },
+                    "with_meet": {
+                        "type": "boolean",
+                        "default": false,
+                        "description": "Add a Google Meet call; the join link is returned in 'conference'"
+                    },
+                    "color": {
+                        "type": "string",
+                        "description": "Event color: lavender, sage, grape, flamingo, banana, tangerine, peacock, graphite, blueberry, basil, tomato (or colorId 1-11)"
+                    },
+                    "visibility": {
+                        "type": "string",
+                        "enum": ["default", "public", "private"],
+                        "description": "Who can see the event's details"
+                    },
+                    "show_as": {
+                        "type": "string",
+                        "enum": ["busy", "free"],
+                        "description": "Whether the event blocks time ('free' for placeholders and FYI events)"
+                    },
+                    "reminders": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "method": {"type": "string", "enum": ["popup", "email"], "default": "popup"},
+                                "minutes": {"type": "integer", "description": "Minutes before the event"}
+                            },
+                            "required": ["minutes"]
+                        },
+                        "description": "Reminder overrides (at most 5); an empty array turns reminders off. Omit for the calendar's defaults."
+                    },
+                    "force": {
+                        "type": "boolean",
+                        "default": false,
+                        "description": "Create the event even if it overlaps existing events"
+                    }
+                },
+                "required": ["account", "summary", "start"]
+            }),
+        },
+        ToolDefinition {
+            name: "update_event".to_string(),
+            description: "Change a synced event's title, description, location, color, visibility, show-as (busy/free) or reminders in Google Calendar. Fields not given are kept. The local copy (list_events, get_event) catches up on the next sync.".to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "id": {
+                        "type": "string",
+                        "description": "Event ID (from list_events, search_calendar or get_event)"
+                    },
+                    "summary": {
+                        "type": "string",
+                        "description": "New title"
+                    },
+                    "description": {
+                        "type": "string",
+                        "description": "New description"
+                    },
+                    "location": {
+                        "type": "string",
+                        "description": "New location"
+                    },
+                    "color": {
+                        "type": "string",
+                        "description": "Event color: lavender, sage, grape, flamingo, banana, tangerine, peacock, graphite, blueberry, basil, tomato (or colorId 1-11)"
+                    },
+                    "visibility": {
+                        "type": "string",
+                        "enum": ["default", "public", "private"],
+                        "description": "Who can see the event's details"
+                    },
+                    "show_as": {
+                        "type": "string",
+                        "enum": ["busy", "free"],
+                        "description": "Whether the event blocks time ('free' for placeholders and FYI events)"
+                    },
+                    "reminders": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "method": {"type": "string", "enum": ["popup", "email"], "default": "popup"},
+                                "minutes": {"type": "integer", "description": "Minutes before the event"}
+                            },
+                            "required": ["minutes"]
+                        },
+                        "description": "Reminder overrides (at most 5); an empty array turns reminders off. Omit to keep the current ones."
                     }
                 },
-                "required": ["account", "summary", "start", "end"]
+                "required": ["id"]
             }),
         },
         // System tools
         ToolDefinition {
             name: "manage_sync".to_string(),
-            description: "Manage sync. Actions: 'status' (show sync status), 'reset' (clear synced data), 'extend' (sync older data), 'resume_from' (force resume from date), 'download_attachments' (download pending attachments).".to_string(),
+            description: "Manage sync. Actions: 'status' (show sync status; needs_reauth lists accounts that stopped syncing until re-authenticated), 'reset' (clear synced data), 'extend' (sync older data), 'resume_from' (force resume from date), 'download_attachments' (download pending attachments).".to_string(),
             input_schema: serde_json::json!({
                 "type": "object",
                 "properties": {
@@ -623,7 +1539,7 @@ pub fn get_tool_definitions() -> Vec<ToolDefinition> {
                     },
                     "logging": {
                         "type": "boolean",
-                        "description": "Enable logging to ~/.local/share/groundeffect/logs/ (for start/restart)"
+                        "description": "Enable logging to the data directory's logs/ folder, ~/.local/share/groundeffect/logs/ by default (for start/restart)"
                     },
                     "email_poll_interval": {
                         "type": "integer",
@@ -783,8 +1699,11 @@ fn build_email_message(
     subject: &str,
     body: &str,
     is_html: bool,
+    signature: Option<&Signature>,
     in_reply_to: Option<&str>,
     references: Option<&str>,
+    reply_to: Option<&str>,
+    attachments: &[OutgoingAttachment],
 ) -> String {
     let encoded_name = encode_display_name(display_name);
     let from_header = format!("{} <{}>", encoded_name, from_email);
@@ -796,6 +1715,10 @@ fn build_email_message(
         to.join(", ")
     );
 
+    if let Some(addr) = reply_to {
+        message.push_str(&format!("Reply-To: {}\r\n", addr));
+    }
+
     if !cc.is_empty() {
         message.push_str(&format!("Cc: {}\r\n", cc.join(", ")));
     }
@@ -813,13 +1736,20 @@ fn build_email_message(
     message.push_str(&format!("Subject: {}\r\n", subject));
     message.push_str("MIME-Version: 1.0\r\n");
 
-    if is_html {
+    // Attachments wrap the body in multipart/mixed
+    let mixed_boundary = format!("----=_Mixed_{}", chrono::Utc::now().timestamp_millis());
+    if !attachments.is_empty() {
+        message.push_str(&format!(
+            "Content-Type: multipart/mixed; boundary=\"{}\"\r\n\r\n--{}\r\n",
+            mixed_boundary, mixed_boundary
+        ));
+    }
+
+    let (plain_body, html_body) = message_parts(body, is_html, signature);
+    if let Some(html_body) = html_body {
         // Build multipart/alternative message
         let boundary = format!("----=_Part_{}", chrono::Utc::now().timestamp_millis());
 
-        let html_body = convert_to_html(body);
-        let plain_body = strip_html_tags(&html_body);
-
         message.push_str(&format!(
             "Content-Type: multipart/alternative; boundary=\"{}\"\r\n\r\n",
             boundary
@@ -844,12 +1774,41 @@ fn build_email_message(
     } else {
         // Plain text only
         message.push_str("Content-Type: text/plain; charset=utf-8\r\n\r\n");
-        message.push_str(body);
+        message.push_str(&plain_body);
+    }
+
+    if !attachments.is_empty() {
+        for attachment in attachments {
+            message.push_str(&format!("\r\n--{}\r\n", mixed_boundary));
+            message.push_str(&attachment.mime_part());
+        }
+        message.push_str(&format!("\r\n--{}--\r\n", mixed_boundary));
     }
 
     message
 }
 
+/// Plain-text part and (for HTML mail) HTML part of a message body, with
+/// the signature appended to each
+fn message_parts(
+    body: &str,
+    is_html: bool,
+    signature: Option<&Signature>,
+) -> (String, Option<String>) {
+    let html = is_html.then(|| convert_to_html(body));
+    let plain = match &html {
+        Some(html) => strip_html_tags(html),
+        None => body.to_string(),
+    };
+    match signature {
+        Some(signature) => (
+            signature.append_text(&plain),
+            html.map(|html| signature.append_html(&html)),
+        ),
+        None => (plain, html),
+    }
+}
+
 /// Parse RFC 2822 email headers and body from raw message
 fn parse_email_headers(raw: &str) -> (std::collections::HashMap<String, String>, String) {
     let mut headers = std::collections::HashMap::new();
@@ -916,6 +1875,81 @@ fn parse_email_addresses(header_value: &str) -> Vec<String> {
         .collect()
 }
 
+/// Parse the optional `category` tool argument
+fn parse_category_arg(args: &Value) -> Result<Option<EmailCategory>> {
+    match args["category"].as_str() {
+        Some(value) => EmailCategory::from_str(value).map(Some).ok_or_else(|| {
+            Error::InvalidRequest(format!(
+                "Invalid category '{}'. Use personal, newsletter, notification, or transactional",
+                value
+            ))
+        }),
+        None => Ok(None),
+    }
+}
+
+/// Parse the optional `mode` search argument
+fn parse_mode_arg(args: &Value) -> Result<SearchMode> {
+    match args["mode"].as_str() {
+        Some(value) => SearchMode::from_name(value).ok_or_else(|| {
+            Error::InvalidRequest(format!(
+                "Invalid mode '{}'. Use hybrid, semantic, or keyword",
+                value
+            ))
+        }),
+        None => Ok(SearchMode::Hybrid),
+    }
+}
+
+/// Parse the optional `render` tool argument
+fn parse_render_arg(args: &Value) -> Result<RenderMode> {
+    match args["render"].as_str() {
+        Some(value) => RenderMode::from_str(value).ok_or_else(|| {
+            Error::InvalidRequest(format!(
+                "Invalid render mode '{}'. Use full or compact",
+                value
+            ))
+        }),
+        None => Ok(RenderMode::Full),
+    }
+}
+
+/// Parse the `color`, `visibility`, `show_as` and `reminders` event arguments
+fn event_options(args: &Value) -> Result<EventOptions> {
+    let reminders = match args["reminders"].as_array() {
+        Some(arr) => Some(
+            arr.iter()
+                .map(|r| {
+                    let method = match r["method"].as_str().unwrap_or("popup") {
+                        "popup" => ReminderMethod::Popup,
+                        "email" => ReminderMethod::Email,
+                        other => {
+                            return Err(Error::InvalidRequest(format!(
+                                "Invalid reminder method '{}'. Use popup or email",
+                                other
+                            )))
+                        }
+                    };
+                    let minutes = r["minutes"].as_i64().ok_or_else(|| {
+                        Error::InvalidRequest("Each reminder needs minutes".to_string())
+                    })?;
+                    Ok(Reminder {
+                        method,
+                        minutes: i32::try_from(minutes).unwrap_or(i32::MAX),
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?,
+        ),
+        None => None,
+    };
+    EventOptions::parse(
+        args["color"].as_str(),
+        args["visibility"].as_str(),
+        args["show_as"].as_str(),
+        reminders,
+    )
+}
+
 /// Tool execution handler
 pub struct ToolHandler {
     db: Arc<Database>,
@@ -940,6 +1974,16 @@ impl ToolHandler {
         }
     }
 
+    /// Sync's storage pipeline, for mail fetched from Gmail on request
+    fn email_store(&self) -> SyncManager {
+        SyncManager::new(
+            self.db.clone(),
+            self.config.clone(),
+            self.oauth.clone(),
+            self.search.embedding(),
+        )
+    }
+
     /// Execute a tool
     pub async fn execute(&self, name: &str, arguments: &Value) -> Result<Value> {
         debug!("Executing tool: {} with args: {:?}", name, arguments);
@@ -949,11 +1993,28 @@ impl ToolHandler {
             "manage_accounts" => self.manage_accounts(arguments).await,
             "manage_sync" => self.manage_sync(arguments).await,
             "manage_daemon" => self.manage_daemon(arguments).await,
+            "search_all" => self.search_all(arguments).await,
             // Email tools
             "search_emails" => self.search_emails(arguments).await,
+            "find_similar_emails" => self.find_similar_emails(arguments).await,
+            "find_experts" => self.find_experts(arguments).await,
+            "get_digest" => self.get_digest(arguments).await,
             "list_emails" => self.list_recent_emails(arguments).await,
+            "remote_search_emails" => self.remote_search_emails(arguments).await,
+            "list_activity" => self.list_activity(arguments).await,
+            "list_unanswered" => self.list_unanswered(arguments).await,
+            "list_receipts" => self.list_receipts(arguments).await,
+            "spending_report" => self.spending_report(arguments).await,
+            "list_trips" => self.list_trips(arguments).await,
+            "list_extractions" => self.list_extractions(arguments).await,
+            "list_tasks" => self.list_tasks(arguments).await,
+            "complete_task" => self.complete_task(arguments).await,
+            "set_email_tasks" => self.set_email_tasks(arguments).await,
             "get_email" => self.get_email(arguments).await,
             "get_thread" => self.get_thread(arguments).await,
+            "get_send_status" => self.get_send_status(arguments).await,
+            "search_threads" => self.search_threads(arguments).await,
+            "list_threads" => self.list_threads(arguments).await,
             "send_email" => self.send_email(arguments).await,
             "list_folders" => self.list_folders(arguments).await,
             "get_attachment" => self.get_attachment(arguments).await,
@@ -969,9 +2030,43 @@ impl ToolHandler {
             "get_event" => self.get_event(arguments).await,
             "list_events" => self.list_calendar_events(arguments).await,
             "list_calendars" => self.list_calendars(arguments).await,
+            "availability_context" => self.availability_context(arguments).await,
+            "propose_meeting_times" => self.propose_meeting_times(arguments).await,
             "create_event" => self.create_event(arguments).await,
+            "update_event" => self.update_event(arguments).await,
             _ => Err(Error::ToolNotFound(name.to_string())),
-        }?;
+        };
+
+        // Record actions that change mail, calendars, or accounts
+        if let Some(action) = audited_action(name, arguments) {
+            let account = arguments["account"]
+                .as_str()
+                .or(arguments["from_account"].as_str())
+                .map(|id| {
+                    self.config
+                        .resolve_account(id)
+                        .unwrap_or_else(|| id.to_string())
+                });
+            let mut entry = AuditEntry::new(
+                AuditActor::Mcp,
+                action,
+                account.as_deref(),
+                arguments.clone(),
+            );
+            // Tools that decline to act (a conflicting event, say) answer
+            // with success: false rather than an error
+            match &result {
+                Err(e) => entry = entry.failed(e),
+                Ok(value) if value["success"] == false => {
+                    entry = entry.failed(value["message"].as_str().unwrap_or("not completed"))
+                }
+                Ok(_) => {}
+            }
+            if let Err(e) = self.db.record_audit(&entry).await {
+                warn!("Failed to record audit entry for {}: {}", action, e);
+            }
+        }
+        let result = result?;
 
         Ok(serde_json::json!({
             "content": [{
@@ -1094,6 +2189,114 @@ impl ToolHandler {
             }
         }
 
+        // Update sync mode if provided; headers-only mail has no attachments
+        if let Some(headers_only) = args.get("headers_only").and_then(|v| v.as_bool()) {
+            if account.headers_only != headers_only {
+                account.headers_only = headers_only;
+                changes.push(format!(
+                    "headers_only: {} (applies to new mail)",
+                    headers_only
+                ));
+            }
+            if headers_only && account.sync_attachments {
+                account.sync_attachments = false;
+                changes.push("sync_attachments: false (restart daemon to apply)".to_string());
+            }
+        }
+
+        // Update label filters if provided (applied on the next sync)
+        if let Some(labels) = args.get("include_labels").and_then(|v| v.as_array()) {
+            let labels: Vec<String> = labels
+                .iter()
+                .filter_map(|l| l.as_str().map(|s| s.to_string()))
+                .collect();
+            if account.include_labels != labels {
+                changes.push(format!("include_labels: {:?}", labels));
+                account.include_labels = labels;
+            }
+        }
+        if let Some(labels) = args.get("exclude_labels").and_then(|v| v.as_array()) {
+            let labels: Vec<String> = labels
+                .iter()
+                .filter_map(|l| l.as_str().map(|s| s.to_string()))
+                .collect();
+            if account.exclude_labels != labels {
+                changes.push(format!("exclude_labels: {:?}", labels));
+                account.exclude_labels = labels;
+            }
+        }
+
+        // Update storage limit if provided ("none" or "0" removes it)
+        if let Some(value) = args.get("max_storage").and_then(|v| v.as_str()) {
+            let value = value.trim();
+            let limit = if value.eq_ignore_ascii_case("none") || value == "0" {
+                None
+            } else {
+                Some(parse_storage_size(value).ok_or_else(|| {
+                    Error::InvalidRequest(format!(
+                        "Invalid max_storage '{}', use a size like '5GB' or '500MB'",
+                        value
+                    ))
+                })?)
+            };
+            if account.max_storage_bytes != limit {
+                account.max_storage_bytes = limit;
+                changes.push(format!(
+                    "max_storage: {}",
+                    limit
+                        .map(format_bytes)
+                        .unwrap_or_else(|| "unlimited".to_string())
+                ));
+            }
+        }
+
+        // Update retention policy if provided ("none" removes a rule)
+        if let Some(value) = args.get("retain").and_then(|v| v.as_str()) {
+            let value = value.trim();
+            let days = if value.eq_ignore_ascii_case("none") {
+                None
+            } else {
+                Some(parse_retention_age(value).ok_or_else(|| {
+                    Error::InvalidRequest(format!(
+                        "Invalid retain '{}', use an age like '2y', '6m' or '90d'",
+                        value
+                    ))
+                })?)
+            };
+            if account.retention.max_age_days != days {
+                account.retention.max_age_days = days;
+                changes.push(format!(
+                    "retain: {}",
+                    days.map(|d| format!("{} days", d))
+                        .unwrap_or_else(|| "everything".to_string())
+                ));
+            }
+        }
+        if let Some(rules) = args.get("retain_categories").and_then(|v| v.as_object()) {
+            for (category, age) in rules {
+                let rule = format!("{}={}", category, age.as_str().unwrap_or_default());
+                let (category, days) = parse_category_retention(&rule).ok_or_else(|| {
+                    Error::InvalidRequest(format!(
+                        "Invalid retain_categories entry '{}', use e.g. {{\"newsletter\": \"30d\"}}",
+                        rule
+                    ))
+                })?;
+                let rules = &mut account.retention.category_max_age_days;
+                let previous = match days {
+                    Some(days) => rules.insert(category, days),
+                    None => rules.remove(&category),
+                };
+                if previous != days {
+                    changes.push(format!(
+                        "retain {}: {}",
+                        category.as_str(),
+                        days.map(|d| format!("{} days", d))
+                            .unwrap_or_else(|| "removed".to_string())
+                    ));
+                }
+            }
+        }
+
         // Save account changes to DB
         if !changes.is_empty() {
             self.db.upsert_account(&account).await?;
@@ -1133,7 +2336,12 @@ impl ToolHandler {
                 "account": {
                     "id": account.id,
                     "alias": account.alias,
-                    "sync_attachments": account.sync_attachments
+                    "sync_attachments": account.sync_attachments,
+                    "include_labels": account.include_labels,
+                    "exclude_labels": account.exclude_labels,
+                    "max_storage_bytes": account.max_storage_bytes,
+                    "retention": account.retention,
+                    "headers_only": account.headers_only
                 }
             }));
         }
@@ -1145,7 +2353,12 @@ impl ToolHandler {
             "account": {
                 "id": account.id,
                 "alias": account.alias,
-                "sync_attachments": account.sync_attachments
+                "sync_attachments": account.sync_attachments,
+                "include_labels": account.include_labels,
+                "exclude_labels": account.exclude_labels,
+                "max_storage_bytes": account.max_storage_bytes,
+                "retention": account.retention,
+                "headers_only": account.headers_only
             },
             "note": "Restart the daemon for sync_email/sync_calendar/folders/sync_attachments changes to take effect"
         }))
@@ -1154,6 +2367,7 @@ impl ToolHandler {
     /// List all accounts
     async fn list_accounts(&self) -> Result<Value> {
         let accounts = self.db.list_accounts().await?;
+        let token_health = TokenHealthReport::load(&self.config.token_health_file());
         Ok(serde_json::json!({
             "accounts": accounts.iter().map(|a| serde_json::json!({
                 "id": a.id,
@@ -1161,8 +2375,10 @@ impl ToolHandler {
                 "display_name": a.display_name,
                 "status": format!("{:?}", a.status).to_lowercase(),
                 "added_at": a.added_at.to_rfc3339(),
-                "sync_attachments": a.sync_attachments
-            })).collect::<Vec<_>>()
+                "sync_attachments": a.sync_attachments,
+                "token_health": token_health.accounts.get(&a.id)
+            })).collect::<Vec<_>>(),
+            "needs_reauth": needs_reauth_json(&accounts, &token_health)
         }))
     }
 
@@ -1316,6 +2532,11 @@ impl ToolHandler {
                 oldest_event_synced: None,
                 sync_attachments: false, // Off by default
                 estimated_total_emails: None,
+                include_labels: vec![],
+                exclude_labels: vec![],
+                max_storage_bytes: None,
+                retention: Default::default(),
+                headers_only: false,
             };
             self.db.upsert_account(&account).await?;
 
@@ -1487,16 +2708,68 @@ Content-Type: text/html; charset=utf-8
             })
         });
 
-        let options = SearchOptions {
+        let mut options = SearchOptions {
             accounts,
             limit,
-            folder: args["folder"].as_str().map(|s| s.to_string()),
+            folder: args["folder"]
+                .as_str()
+                .map(|s| resolve_label_folder(&self.config, s)),
             from: args["from"].as_str().map(|s| s.to_string()),
             to: args["to"].as_str().map(|s| s.to_string()),
             date_from,
             date_to,
             has_attachment: args["has_attachment"].as_bool(),
-            ..Default::default()
+            category: parse_category_arg(args)?,
+            tag: args["tag"].as_str().map(|s| s.to_string()),
+            sent: args["sent"].as_bool(),
+            exclude_activity: self.config.activity.hide_from_search
+                && !args["include_activity"].as_bool().unwrap_or(false),
+            include_spam_trash: args["include_spam_trash"]
+                .as_bool()
+                .unwrap_or(self.config.search.include_spam_trash),
+            highlight: args["highlight"].as_bool().unwrap_or(false),
+            fusion: FusionOptions::from_config(&self.config.search)
+                .with_mode(parse_mode_arg(args)?),
+            recency: match args["recency_weight"].as_f64() {
+                Some(weight) if !(0.0..=1.0).contains(&weight) => {
+                    return Err(Error::InvalidRequest(
+                        "recency_weight must be between 0 and 1".to_string(),
+                    ))
+                }
+                Some(weight) => {
+                    RecencyBoost::from_config(&self.config.search).with_weight(weight as f32)
+                }
+                None => RecencyBoost::from_config(&self.config.search),
+            },
+            diversity: {
+                let mut diversity = Diversity::from_config(&self.config.search);
+                if let Some(dedup) = args["dedup_threads"].as_bool() {
+                    diversity.dedup_threads = dedup;
+                }
+                match args["mmr_lambda"].as_f64() {
+                    Some(lambda) if !(0.0..=1.0).contains(&lambda) => {
+                        return Err(Error::InvalidRequest(
+                            "mmr_lambda must be between 0 and 1".to_string(),
+                        ))
+                    }
+                    Some(lambda) => diversity.mmr_lambda = lambda as f32,
+                    None => {}
+                }
+                diversity
+            },
+        };
+
+        let understood = args["smart_query"]
+            .as_bool()
+            .unwrap_or(true)
+            .then(|| understand_query(query, Utc::now().with_timezone(&tz)))
+            .filter(|u| !u.is_empty());
+        let query = match &understood {
+            Some(understood) => {
+                understood.apply(&mut options);
+                understood.query.as_str()
+            }
+            None => query,
         };
 
         let start = std::time::Instant::now();
@@ -1515,91 +2788,710 @@ Content-Type: text/html; charset=utf-8
                 .collect(),
         };
 
+        let mut response = serde_json::json!({
+            "results": results,
+            "accounts_searched": accounts_searched,
+            "total_count": results.len(),
+            "search_time_ms": search_time
+        });
+        if let Some(understood) = understood {
+            response["understood"] = serde_json::json!(understood);
+        }
+        Ok(response)
+    }
+
+    /// Search emails, events, attachments and contacts at once
+    async fn search_all(&self, args: &Value) -> Result<Value> {
+        let query = args["query"]
+            .as_str()
+            .ok_or_else(|| Error::InvalidRequest("Missing query".to_string()))?;
+        let types = match args["types"].as_array() {
+            Some(values) => values
+                .iter()
+                .filter_map(|v| v.as_str())
+                .map(|name| {
+                    ResultType::from_name(name).ok_or_else(|| {
+                        Error::InvalidRequest(format!(
+                            "Invalid type '{}'. Use email, event, attachment, or contact",
+                            name
+                        ))
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?,
+            None => Vec::new(),
+        };
+
+        let options = UnifiedSearchOptions {
+            types,
+            accounts: args["accounts"].as_array().map(|arr| {
+                arr.iter()
+                    .filter_map(|v| v.as_str())
+                    .filter_map(|id| self.config.resolve_account(id))
+                    .collect::<Vec<_>>()
+            }),
+            limit: (args["limit"].as_u64().unwrap_or(20) as usize).min(100),
+            include_spam_trash: args["include_spam_trash"]
+                .as_bool()
+                .unwrap_or(self.config.search.include_spam_trash),
+            exclude_activity: self.config.activity.hide_from_search,
+            fusion: FusionOptions::from_config(&self.config.search)
+                .with_mode(parse_mode_arg(args)?),
+            diversity: Diversity::from_config(&self.config.search),
+        };
+
+        let start = std::time::Instant::now();
+        let results = self.search.search_all(query, &options).await?;
+
+        Ok(serde_json::json!({
+            "results": results,
+            "total_count": results.len(),
+            "search_time_ms": start.elapsed().as_millis()
+        }))
+    }
+
+    /// Emails nearest another email by vector similarity
+    async fn find_similar_emails(&self, args: &Value) -> Result<Value> {
+        let id = args["id"]
+            .as_str()
+            .ok_or_else(|| Error::InvalidRequest("Missing id".to_string()))?;
+        let limit = (args["limit"].as_u64().unwrap_or(10) as usize).min(100);
+
+        let mut options = SearchOptions::new(limit);
+        options.accounts = args["accounts"].as_array().map(|arr| {
+            arr.iter()
+                .filter_map(|v| v.as_str())
+                .filter_map(|id| self.config.resolve_account(id))
+                .collect::<Vec<_>>()
+        });
+        options.include_spam_trash = args["include_spam_trash"]
+            .as_bool()
+            .unwrap_or(self.config.search.include_spam_trash);
+
+        let start = std::time::Instant::now();
+        let results = self.search.similar_emails(id, &options).await?;
+
+        Ok(serde_json::json!({
+            "source_id": id,
+            "results": results,
+            "total_count": results.len(),
+            "search_time_ms": start.elapsed().as_millis()
+        }))
+    }
+
+    /// Rank contacts by how much their mail is about a topic
+    async fn find_experts(&self, args: &Value) -> Result<Value> {
+        let query = args["query"]
+            .as_str()
+            .ok_or_else(|| Error::InvalidRequest("Missing query".to_string()))?;
+        let since_arg = args["since"].as_str().unwrap_or("365d");
+        let since = match parse_age(since_arg) {
+            Some(age) => Utc::now() - age,
+            None => self
+                .parse_local_date(Some(since_arg), NaiveTime::MIN)
+                .ok_or_else(|| {
+                    Error::InvalidRequest(format!(
+                        "Invalid since '{}'. Use an age like 365d or YYYY-MM-DD",
+                        since_arg
+                    ))
+                })?,
+        };
+        let accounts = match args["account"].as_str() {
+            Some(account) => Some(vec![self
+                .config
+                .resolve_account(account)
+                .ok_or_else(|| Error::AccountNotFound(account.to_string()))?]),
+            None => None,
+        };
+        let options = ExpertiseOptions {
+            accounts,
+            since,
+            min_emails: args["min_emails"].as_u64().unwrap_or(2).max(1) as usize,
+            limit: (args["limit"].as_u64().unwrap_or(10) as usize).min(100),
+            own_addresses: self.config.compose.identities.keys().cloned().collect(),
+        };
+
+        let start = std::time::Instant::now();
+        let contacts = self.search.contact_expertise(query, &options).await?;
+
+        Ok(serde_json::json!({
+            "query": query,
+            "since": since.to_rfc3339(),
+            "contacts": contacts,
+            "total_count": contacts.len(),
+            "search_time_ms": start.elapsed().as_millis()
+        }))
+    }
+
+    /// Daily or weekly digest
+    async fn get_digest(&self, args: &Value) -> Result<Value> {
+        let period = match args["period"].as_str() {
+            Some(p) => DigestPeriod::from_str(p).ok_or_else(|| {
+                Error::InvalidRequest(format!("Invalid period '{}'. Use day or week", p))
+            })?,
+            None => DigestPeriod::Day,
+        };
+        let account_id = match args["account"].as_str() {
+            Some(account) => Some(
+                self.config
+                    .resolve_account(account)
+                    .ok_or_else(|| Error::AccountNotFound(account.to_string()))?,
+            ),
+            None => None,
+        };
+
+        let digest = build_digest(
+            &self.db,
+            &self.config,
+            period,
+            account_id.as_deref(),
+            Utc::now(),
+        )
+        .await?;
+        Ok(serde_json::to_value(digest)?)
+    }
+
+    /// List recent emails (fast, no search)
+    async fn list_recent_emails(&self, args: &Value) -> Result<Value> {
+        let limit = args["limit"].as_u64().unwrap_or(10) as usize;
+        let limit = limit.min(100); // Cap at 100
+
+        // Resolve account if provided (handle both "account" and "accounts" params)
+        let account_id = args["account"]
+            .as_str()
+            .and_then(|id| self.config.resolve_account(id))
+            .or_else(|| {
+                // Also check "accounts" array (for search_emails redirect)
+                args["accounts"]
+                    .as_array()
+                    .and_then(|arr| arr.first())
+                    .and_then(|v| v.as_str())
+                    .and_then(|id| self.config.resolve_account(id))
+            });
+
+        let category = parse_category_arg(args)?;
+        let suspicious = args["suspicious"].as_bool().unwrap_or(false);
+
+        info!(
+            "Listing recent emails: account={:?}, category={:?}, suspicious={}, limit={}",
+            account_id, category, suspicious, limit
+        );
+
+        let start = std::time::Instant::now();
+        let emails = self
+            .db
+            .list_recent_emails(account_id.as_deref(), category, suspicious, limit)
+            .await?;
+        let query_time = start.elapsed().as_millis();
+
+        // Convert to summaries
+        let results: Vec<_> = emails
+            .iter()
+            .map(|e| {
+                serde_json::json!({
+                    "id": e.id,
+                    "subject": e.subject,
+                    "from": e.from.to_string_full(),
+                    "date": e.date.to_rfc3339(),
+                    "snippet": e.snippet,
+                    "folder": e.folder,
+                    "category": e.category,
+                    "tags": e.tags,
+                    "security_warnings": e.auth.as_ref().map(|a| &a.warnings).filter(|w| !w.is_empty()),
+                    "is_read": e.is_read(),
+                    "has_attachments": e.has_attachments(),
+                    "web_link": e.web_link(),
+                    "attachments": e.attachments.iter().map(|a| serde_json::json!({
+                        "id": a.id,
+                        "filename": a.filename,
+                        "mime_type": a.mime_type,
+                        "size_human": a.size_human(),
+                        "downloaded": a.downloaded
+                    })).collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        Ok(serde_json::json!({
+            "emails": results,
+            "count": results.len(),
+            "query_time_ms": query_time
+        }))
+    }
+
+    /// Run a Gmail search query through the Gmail API
+    async fn remote_search_emails(&self, args: &Value) -> Result<Value> {
+        let query = args["gmail_query"]
+            .as_str()
+            .filter(|q| !q.trim().is_empty())
+            .ok_or_else(|| Error::InvalidRequest("Missing gmail_query".to_string()))?;
+        let limit =
+            (args["limit"].as_u64().unwrap_or(10) as usize).clamp(1, REMOTE_SEARCH_MAX_RESULTS);
+        let import = args["import"].as_bool().unwrap_or(false);
+
+        let account_ids = match args["account"].as_str() {
+            Some(account) => vec![self
+                .config
+                .resolve_account(account)
+                .ok_or_else(|| Error::AccountNotFound(account.to_string()))?],
+            None => self
+                .db
+                .list_accounts()
+                .await?
+                .into_iter()
+                .map(|a| a.id)
+                .collect(),
+        };
+
+        info!(
+            "Remote Gmail search: query={:?}, accounts={:?}, limit={}, import={}",
+            query, account_ids, limit, import
+        );
+
+        let start = std::time::Instant::now();
+        let mut emails = Vec::new();
+        for account_id in &account_ids {
+            emails.extend(
+                search_gmail(&self.config, self.oauth.clone(), account_id, query, limit).await?,
+            );
+        }
+        emails.sort_by(|a, b| b.date.cmp(&a.date));
+        emails.truncate(limit);
+        resolve_categories(&mut emails, None);
+
+        let mut results = Vec::with_capacity(emails.len());
+        let mut to_import = Vec::new();
+        for e in &emails {
+            // Leave stored copies (and their embeddings) alone
+            let local = self.db.get_email(&e.id).await?.is_some();
+            let imported = import && !local;
+            if imported {
+                to_import.push(e.clone());
+            }
+            results.push(serde_json::json!({
+                "id": e.id,
+                "account_id": e.account_id,
+                "subject": e.subject,
+                "from": e.from.to_string_full(),
+                "date": e.date.to_rfc3339(),
+                "snippet": e.snippet,
+                "folder": e.folder,
+                "category": e.category,
+                "security_warnings": e.auth.as_ref().map(|a| &a.warnings).filter(|w| !w.is_empty()),
+                "is_read": e.is_read(),
+                "has_attachments": e.has_attachments(),
+                "local": local || imported,
+                "imported": imported
+            }));
+        }
+        if !to_import.is_empty() {
+            self.email_store().store_fetched(&to_import).await?;
+        }
+
+        let mut response = serde_json::json!({
+            "emails": results,
+            "count": results.len(),
+            "query_time_ms": start.elapsed().as_millis()
+        });
+        if import {
+            response["imported"] = serde_json::json!(to_import.len());
+        }
+        Ok(response)
+    }
+
+    /// List activity items parsed from notification emails
+    async fn list_activity(&self, args: &Value) -> Result<Value> {
+        let limit = args["limit"].as_u64().unwrap_or(20).min(200) as usize;
+        let account_id = args["account"]
+            .as_str()
+            .and_then(|id| self.config.resolve_account(id));
+        let source = match args["source"].as_str() {
+            Some(value) => Some(ActivitySource::from_str(value).ok_or_else(|| {
+                Error::InvalidRequest(format!(
+                    "Invalid source '{}'. Use github, jira, or asana",
+                    value
+                ))
+            })?),
+            None => None,
+        };
+        let tz: Tz = self.config.general.timezone.parse().unwrap_or(Tz::UTC);
+        let since = args["date_from"].as_str().and_then(|s| {
+            NaiveDate::parse_from_str(s, "%Y-%m-%d").ok().and_then(|d| {
+                tz.from_local_datetime(&d.and_time(NaiveTime::MIN))
+                    .single()
+                    .map(|dt| dt.with_timezone(&Utc))
+            })
+        });
+
+        let items = self
+            .db
+            .list_activity(
+                account_id.as_deref(),
+                source,
+                args["project"].as_str(),
+                since,
+                limit,
+            )
+            .await?;
+
+        Ok(serde_json::json!({
+            "activity": items,
+            "count": items.len()
+        }))
+    }
+
+    /// List emails waiting for a reply
+    async fn list_unanswered(&self, args: &Value) -> Result<Value> {
+        let limit = args["limit"].as_u64().unwrap_or(50).clamp(1, 500) as usize;
+        let account_id = args["account"]
+            .as_str()
+            .and_then(|id| self.config.resolve_account(id));
+        let since_arg = args["since"].as_str().unwrap_or("7d");
+        let since = match parse_age(since_arg) {
+            Some(age) => Utc::now() - age,
+            None => self
+                .parse_local_date(Some(since_arg), NaiveTime::MIN)
+                .ok_or_else(|| {
+                    Error::InvalidRequest(format!(
+                        "Invalid since '{}'. Use an age like 7d or YYYY-MM-DD",
+                        since_arg
+                    ))
+                })?,
+        };
+
+        let emails = self
+            .db
+            .list_emails_since(account_id.as_deref(), since)
+            .await?;
+        let own_addresses: Vec<String> = self.config.compose.identities.keys().cloned().collect();
+        let mut unanswered = find_unanswered(&emails, &own_addresses, Utc::now());
+        unanswered.truncate(limit);
+
+        Ok(serde_json::json!({
+            "emails": unanswered,
+            "count": unanswered.len(),
+            "since": since.to_rfc3339()
+        }))
+    }
+
+    /// List receipts extracted from email, with totals per currency
+    async fn list_receipts(&self, args: &Value) -> Result<Value> {
+        let limit = args["limit"].as_u64().unwrap_or(50).min(500) as usize;
+        let account_id = args["account"]
+            .as_str()
+            .and_then(|id| self.config.resolve_account(id));
+        let tz: Tz = self.config.general.timezone.parse().unwrap_or(Tz::UTC);
+        let (since, until) = match args["month"].as_str() {
+            Some(month) => {
+                let (start, end) = parse_month(month).ok_or_else(|| {
+                    Error::InvalidRequest(format!("Invalid month '{}'. Use YYYY-MM", month))
+                })?;
+                let to_utc = |d: NaiveDate| {
+                    tz.from_local_datetime(&d.and_time(NaiveTime::MIN))
+                        .earliest()
+                        .map(|dt| dt.with_timezone(&Utc))
+                };
+                (to_utc(start), to_utc(end))
+            }
+            None => (None, None),
+        };
+
+        let receipts = self
+            .db
+            .list_receipts(
+                account_id.as_deref(),
+                since,
+                until,
+                args["merchant"].as_str(),
+                limit,
+            )
+            .await?;
+
+        let mut totals: std::collections::BTreeMap<String, f64> = Default::default();
+        for receipt in &receipts {
+            if let Some(amount) = receipt.amount {
+                let currency = receipt.currency.as_deref().unwrap_or("unknown");
+                *totals.entry(currency.to_string()).or_default() += amount;
+            }
+        }
+        for total in totals.values_mut() {
+            *total = (*total * 100.0).round() / 100.0;
+        }
+
         Ok(serde_json::json!({
-            "results": results,
-            "accounts_searched": accounts_searched,
-            "total_count": results.len(),
-            "search_time_ms": search_time
+            "receipts": receipts,
+            "count": receipts.len(),
+            "totals": totals
         }))
     }
 
-    /// List recent emails (fast, no search)
-    async fn list_recent_emails(&self, args: &Value) -> Result<Value> {
-        let limit = args["limit"].as_u64().unwrap_or(10) as usize;
-        let limit = limit.min(100); // Cap at 100
-
-        // Resolve account if provided (handle both "account" and "accounts" params)
+    /// Total receipts by month and/or merchant
+    async fn spending_report(&self, args: &Value) -> Result<Value> {
         let account_id = args["account"]
             .as_str()
-            .and_then(|id| self.config.resolve_account(id))
-            .or_else(|| {
-                // Also check "accounts" array (for search_emails redirect)
-                args["accounts"]
-                    .as_array()
-                    .and_then(|arr| arr.first())
-                    .and_then(|v| v.as_str())
-                    .and_then(|id| self.config.resolve_account(id))
-            });
+            .and_then(|id| self.config.resolve_account(id));
+        let tz: Tz = self.config.general.timezone.parse().unwrap_or(Tz::UTC);
 
-        info!(
-            "Listing recent emails: account={:?}, limit={}",
-            account_id, limit
+        let receipts = self
+            .db
+            .list_receipts(
+                account_id.as_deref(),
+                self.parse_local_date(args["date_from"].as_str(), NaiveTime::MIN),
+                self.parse_local_date(args["date_to"].as_str(), NaiveTime::MIN),
+                args["merchant"].as_str(),
+                usize::MAX,
+            )
+            .await?;
+        let rows = spending_report(
+            &receipts,
+            args["by_month"].as_bool().unwrap_or(false),
+            args["by_merchant"].as_bool().unwrap_or(false),
+            tz,
         );
 
-        let start = std::time::Instant::now();
-        let emails = self
+        Ok(serde_json::json!({
+            "rows": rows,
+            "receipts_without_total": receipts.iter().filter(|r| r.amount.is_none()).count()
+        }))
+    }
+
+    /// List upcoming trips
+    async fn list_trips(&self, args: &Value) -> Result<Value> {
+        let days = args["days"].as_i64().unwrap_or(90).clamp(1, 365);
+        let account_id = args["account"]
+            .as_str()
+            .and_then(|id| self.config.resolve_account(id));
+        let now = Utc::now();
+
+        let items = self
             .db
-            .list_recent_emails(account_id.as_deref(), limit)
+            .list_itinerary(
+                account_id.as_deref(),
+                None,
+                Some(now),
+                Some(now + chrono::Duration::days(days)),
+                500,
+            )
             .await?;
-        let query_time = start.elapsed().as_millis();
+        let trips = group_trips(items);
 
-        // Convert to summaries
-        let results: Vec<_> = emails
-            .iter()
-            .map(|e| {
-                serde_json::json!({
-                    "id": e.id,
-                    "subject": e.subject,
-                    "from": e.from.to_string_full(),
-                    "date": e.date.to_rfc3339(),
-                    "snippet": e.snippet,
-                    "folder": e.folder,
-                    "is_read": e.is_read(),
-                    "has_attachments": e.has_attachments(),
-                    "attachments": e.attachments.iter().map(|a| serde_json::json!({
-                        "id": a.id,
-                        "filename": a.filename,
-                        "mime_type": a.mime_type,
-                        "size_human": a.size_human(),
-                        "downloaded": a.downloaded
-                    })).collect::<Vec<_>>()
-                })
-            })
-            .collect();
+        Ok(serde_json::json!({
+            "trips": trips,
+            "count": trips.len()
+        }))
+    }
+
+    /// List reservations and package deliveries extracted from email
+    async fn list_extractions(&self, args: &Value) -> Result<Value> {
+        let limit = args["limit"].as_u64().unwrap_or(20).clamp(1, 500) as usize;
+        let account_id = args["account"]
+            .as_str()
+            .and_then(|id| self.config.resolve_account(id));
+        let kind = match args["type"].as_str() {
+            Some(kind) => Some(ExtractionKind::from_str(kind).ok_or_else(|| {
+                Error::InvalidRequest(format!(
+                    "Invalid type '{}'. Use flight, hotel, restaurant or package",
+                    kind
+                ))
+            })?),
+            None => None,
+        };
+        let upcoming = args["upcoming"].as_bool().unwrap_or(false);
+
+        let items = self
+            .db
+            .list_extractions(account_id.as_deref(), kind, upcoming.then(Utc::now), limit)
+            .await?;
 
         Ok(serde_json::json!({
-            "emails": results,
-            "count": results.len(),
-            "query_time_ms": query_time
+            "extractions": items,
+            "count": items.len()
         }))
     }
 
-    /// Maximum body size in chars (~40K chars ≈ 20K tokens with JSON overhead, staying under Claude Code's 25K token limit)
-    const MAX_BODY_CHARS: usize = 40_000;
+    /// List action items found in email
+    async fn list_tasks(&self, args: &Value) -> Result<Value> {
+        let limit = args["limit"].as_u64().unwrap_or(50).clamp(1, 500) as usize;
+        let account_id = args["account"]
+            .as_str()
+            .and_then(|id| self.config.resolve_account(id));
+        let status = match args["status"].as_str().unwrap_or("open") {
+            "all" => None,
+            status => Some(TaskStatus::from_str(status).ok_or_else(|| {
+                Error::InvalidRequest(format!(
+                    "Invalid status '{}'. Use open, done or all",
+                    status
+                ))
+            })?),
+        };
+        let due_before = match args["due_before"].as_str() {
+            Some(date) => Some(NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| {
+                Error::InvalidRequest(format!("Invalid due_before '{}'. Use YYYY-MM-DD", date))
+            })?),
+            None => None,
+        };
 
-    /// Get a single email
-    async fn get_email(&self, args: &Value) -> Result<Value> {
+        let tasks = self
+            .db
+            .list_tasks(account_id.as_deref(), status, due_before, limit)
+            .await?;
+
+        Ok(serde_json::json!({
+            "tasks": tasks,
+            "count": tasks.len()
+        }))
+    }
+
+    /// Mark a task done or open
+    async fn complete_task(&self, args: &Value) -> Result<Value> {
         let id = args["id"]
             .as_str()
             .ok_or_else(|| Error::InvalidRequest("Missing id".to_string()))?;
+        let status = if args["reopen"].as_bool().unwrap_or(false) {
+            TaskStatus::Open
+        } else {
+            TaskStatus::Done
+        };
+
+        let task = self
+            .db
+            .set_task_status(id, status)
+            .await?
+            .ok_or_else(|| Error::InvalidRequest(format!("Task not found: {}", id)))?;
+
+        Ok(serde_json::json!({ "task": task }))
+    }
+
+    /// Replace an email's open tasks with ones the client found
+    async fn set_email_tasks(&self, args: &Value) -> Result<Value> {
+        let email_id = args["email_id"]
+            .as_str()
+            .ok_or_else(|| Error::InvalidRequest("Missing email_id".to_string()))?;
+        let items = args["tasks"]
+            .as_array()
+            .ok_or_else(|| Error::InvalidRequest("Missing tasks".to_string()))?;
 
         let email = self
             .db
-            .get_email(id)
+            .get_email(email_id)
             .await?
-            .ok_or_else(|| Error::EmailNotFound(id.to_string()))?;
+            .ok_or_else(|| Error::EmailNotFound(email_id.to_string()))?;
+
+        let mut tasks = Vec::with_capacity(items.len());
+        for (index, item) in items.iter().enumerate() {
+            let text = item["text"]
+                .as_str()
+                .map(str::trim)
+                .filter(|text| !text.is_empty())
+                .ok_or_else(|| Error::InvalidRequest(format!("Task {} has no text", index)))?;
+            let due = match item["due"].as_str() {
+                Some(date) => Some(NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| {
+                    Error::InvalidRequest(format!("Invalid due '{}'. Use YYYY-MM-DD", date))
+                })?),
+                None => None,
+            };
+            tasks.push(Task {
+                id: Task::id_for(&email.id, TaskSource::Assistant, index),
+                email_id: email.id.clone(),
+                account_id: email.account_id.clone(),
+                text: text.to_string(),
+                requester: email.from.to_string_full(),
+                subject: email.subject.clone(),
+                due,
+                due_text: None,
+                status: TaskStatus::Open,
+                source: TaskSource::Assistant,
+                created_at: email.date,
+                completed_at: None,
+            });
+        }
+
+        let tasks = self.db.set_email_tasks(&email.id, &tasks).await?;
+
+        Ok(serde_json::json!({
+            "email_id": email.id,
+            "tasks": tasks,
+            "count": tasks.len()
+        }))
+    }
+
+    /// Maximum body size in chars (~40K chars ≈ 20K tokens with JSON overhead, staying under Claude Code's 25K token limit)
+    const MAX_BODY_CHARS: usize = 40_000;
+
+    /// Get a single email
+    async fn get_email(&self, args: &Value) -> Result<Value> {
+        // Where to look on Gmail if the email isn't stored locally
+        let (local, remote_target, not_found) = if let Some(raw) = args["message_id"].as_str() {
+            let message_id = normalize_message_id(raw)
+                .ok_or_else(|| Error::InvalidRequest(format!("Invalid message_id: {}", raw)))?;
+            let account_id = match args["account"].as_str() {
+                Some(account) => Some(
+                    self.config
+                        .resolve_account(account)
+                        .ok_or_else(|| Error::AccountNotFound(account.to_string()))?,
+                ),
+                None => None,
+            };
+            let email = self
+                .db
+                .get_emails_by_message_id(&message_id, account_id.as_deref())
+                .await?
+                .into_iter()
+                .next();
+            let not_found = format!("<{}>", message_id);
+            (email, Some((account_id, message_id)), not_found)
+        } else {
+            let id = args["id"]
+                .as_str()
+                .ok_or_else(|| Error::InvalidRequest("Missing id or message_id".to_string()))?;
+            let target = split_email_id(id).map(|(a, m)| (Some(a.to_string()), m));
+            (self.db.get_email(id).await?, target, id.to_string())
+        };
+
+        let remote = local.is_none() && args["remote"].as_bool().unwrap_or(false);
+        let mut email = match (local, remote_target) {
+            (Some(email), _) => email,
+            (None, Some((account_id, message_id))) if remote => {
+                let account_ids = match account_id {
+                    Some(account_id) => vec![account_id],
+                    None => self
+                        .db
+                        .list_accounts()
+                        .await?
+                        .into_iter()
+                        .map(|a| a.id)
+                        .collect(),
+                };
+                let mut email =
+                    fetch_remote_email(&self.config, self.oauth.clone(), &account_ids, &message_id)
+                        .await?
+                        .ok_or_else(|| Error::EmailNotFound(not_found))?;
+                if args["save"].as_bool().unwrap_or(false) {
+                    self.email_store()
+                        .store_fetched(std::slice::from_ref(&email))
+                        .await?;
+                    resolve_categories(std::slice::from_mut(&mut email), None);
+                }
+                email
+            }
+            (None, None) if remote => {
+                return Err(Error::InvalidRequest(
+                    "remote needs message_id or an id of the form <account>:<Message-ID>"
+                        .to_string(),
+                ))
+            }
+            (None, _) => return Err(Error::EmailNotFound(not_found)),
+        };
+
+        let fetch = args["fetch"].as_bool().unwrap_or(false) && email.headers_only;
+        if fetch {
+            fetch_gmail_body(&self.oauth, &mut email).await?;
+        }
 
-        let body = email.resolved_body();
+        let render = parse_render_arg(args)?;
+        let body = match render {
+            RenderMode::Full => email.resolved_body(),
+            RenderMode::Compact => ThreadCompactor::new().compact(&email.markdown_body()),
+        };
 
         // Check if truncation needed
         let total_chars = body.len();
@@ -1634,12 +3526,26 @@ Content-Type: text/html; charset=utf-8
             "attachments": email.attachments,
             "is_read": email.is_read(),
             "is_flagged": email.is_flagged(),
+            "web_link": email.web_link(),
         });
 
         if truncated {
             response["truncated"] = serde_json::json!(true);
             response["total_body_chars"] = serde_json::json!(total_chars);
         }
+        if render == RenderMode::Compact {
+            response["render"] = serde_json::json!(render.as_str());
+        }
+        if let Some(auth) = &email.auth {
+            response["auth"] = serde_json::json!(auth);
+        }
+        if email.headers_only {
+            response["headers_only"] = serde_json::json!(true);
+            response["body_fetched"] = serde_json::json!(fetch);
+        }
+        if remote {
+            response["remote"] = serde_json::json!(true);
+        }
 
         Ok(response)
     }
@@ -1653,6 +3559,7 @@ Content-Type: text/html; charset=utf-8
         let thread_id: u64 = thread_id_str
             .parse()
             .map_err(|_| Error::InvalidRequest("thread_id must be a valid number".to_string()))?;
+        let render = parse_render_arg(args)?;
 
         // Resolve account filter if provided
         let account_id = args["accounts"]
@@ -1673,10 +3580,17 @@ Content-Type: text/html; charset=utf-8
             )));
         }
 
-        // Format each email in the thread
+        let delivery = thread_delivery(&emails);
+
+        // Format each email in the thread (oldest first, so compact mode keeps
+        // the first occurrence of each signature)
+        let mut compactor = ThreadCompactor::new();
         let mut messages = Vec::with_capacity(emails.len());
         for email in &emails {
-            let body = email.resolved_body();
+            let body = match render {
+                RenderMode::Full => email.resolved_body(),
+                RenderMode::Compact => compactor.compact(&email.markdown_body()),
+            };
 
             // Truncate if needed
             let total_chars = body.len();
@@ -1707,27 +3621,143 @@ Content-Type: text/html; charset=utf-8
                 msg["truncated"] = serde_json::json!(true);
                 msg["total_body_chars"] = serde_json::json!(total_chars);
             }
+            if render == RenderMode::Compact {
+                // The snippet only repeats the start of the body
+                if let Some(obj) = msg.as_object_mut() {
+                    obj.remove("snippet");
+                }
+            }
+            if let Some(status) = delivery.get(&email.id) {
+                msg["delivery_status"] = serde_json::json!(status.status);
+                msg["failed_recipients"] = serde_json::json!(status.failed_recipients);
+            }
 
             messages.push(msg);
         }
 
         // Use first email for thread metadata
         let first = &emails[0];
-        Ok(serde_json::json!({
+        let mut thread = serde_json::json!({
             "thread_id": thread_id,
             "account_id": first.account_id,
             "subject": first.subject,
             "message_count": emails.len(),
+            "web_link": first.web_link(),
+            "render": render.as_str(),
             "messages": messages,
+        });
+        if !delivery.is_empty() {
+            thread["delivery_failed"] = serde_json::json!(true);
+        }
+        Ok(thread)
+    }
+
+    /// Delivery status of a sent email, from the bounces that came back
+    async fn get_send_status(&self, args: &Value) -> Result<Value> {
+        let id = args["id"]
+            .as_str()
+            .ok_or_else(|| Error::InvalidRequest("Missing id".to_string()))?;
+        let account_id = args["account"]
+            .as_str()
+            .and_then(|a| self.config.resolve_account(a));
+
+        let status = find_send_status(&self.db, id, account_id.as_deref()).await?;
+        Ok(serde_json::to_value(status)?)
+    }
+
+    /// Search conversation threads
+    async fn search_threads(&self, args: &Value) -> Result<Value> {
+        let query = args["query"]
+            .as_str()
+            .ok_or_else(|| Error::InvalidRequest("Missing query".to_string()))?;
+
+        let limit = args["limit"].as_u64().unwrap_or(10).min(100) as usize;
+
+        // Resolve account aliases
+        let accounts = args["accounts"].as_array().map(|arr| {
+            arr.iter()
+                .filter_map(|v| v.as_str())
+                .filter_map(|id| self.config.resolve_account(id))
+                .collect::<Vec<_>>()
+        });
+
+        let mut options = ThreadSearchOptions {
+            accounts,
+            limit,
+            participant: args["participant"].as_str().map(|s| s.to_string()),
+            date_from: self.parse_local_date(args["date_from"].as_str(), NaiveTime::MIN),
+            date_to: self.parse_local_date(
+                args["date_to"].as_str(),
+                NaiveTime::from_hms_opt(23, 59, 59).unwrap(),
+            ),
+            fusion: FusionOptions::from_config(&self.config.search)
+                .with_mode(parse_mode_arg(args)?),
+        };
+
+        let tz: Tz = self.config.general.timezone.parse().unwrap_or(Tz::UTC);
+        let understood = args["smart_query"]
+            .as_bool()
+            .unwrap_or(true)
+            .then(|| understand_query(query, Utc::now().with_timezone(&tz)))
+            .filter(|u| !u.is_empty());
+        let query = match &understood {
+            Some(understood) => {
+                understood.apply_to_threads(&mut options);
+                understood.query.as_str()
+            }
+            None => query,
+        };
+
+        let start = std::time::Instant::now();
+        let results = self.search.search_threads(query, &options).await?;
+        let search_time = start.elapsed().as_millis();
+
+        let mut response = serde_json::json!({
+            "results": results,
+            "total_count": results.len(),
+            "search_time_ms": search_time
+        });
+        if let Some(understood) = understood {
+            response["understood"] = serde_json::json!(understood);
+        }
+        Ok(response)
+    }
+
+    /// List threads by latest activity
+    async fn list_threads(&self, args: &Value) -> Result<Value> {
+        let limit = args["limit"].as_u64().unwrap_or(20).min(200) as usize;
+        let account_id = args["account"]
+            .as_str()
+            .and_then(|id| self.config.resolve_account(id));
+        let since = self.parse_local_date(args["since"].as_str(), NaiveTime::MIN);
+
+        let threads = self
+            .db
+            .list_threads(account_id.as_deref(), since, limit)
+            .await?;
+
+        Ok(serde_json::json!({
+            "threads": threads,
+            "count": threads.len()
         }))
     }
 
+    /// A YYYY-MM-DD date at a time of day in the configured timezone
+    fn parse_local_date(&self, date: Option<&str>, time: NaiveTime) -> Option<DateTime<Utc>> {
+        let tz: Tz = self.config.general.timezone.parse().unwrap_or(Tz::UTC);
+        let date = NaiveDate::parse_from_str(date?, "%Y-%m-%d").ok()?;
+        tz.from_local_datetime(&date.and_time(time))
+            .single()
+            .map(|dt| dt.with_timezone(&Utc))
+    }
+
     /// Send an email via Gmail API (with optional preview mode)
     async fn send_email(&self, args: &Value) -> Result<Value> {
         // Check flags
         let confirm = args["confirm"].as_bool().unwrap_or(false);
         let save_as_draft = args["save_as_draft"].as_bool().unwrap_or(false);
         let force_html = args["html"].as_bool().unwrap_or(false);
+        let strict_lint = args["strict_lint"].as_bool().unwrap_or(false);
 
         // Get account
         let from_account = args["from_account"]
@@ -1743,7 +3773,22 @@ Content-Type: text/html; charset=utf-8
         let account = self.db.get_account(&from_email).await?.ok_or_else(|| {
             Error::InvalidRequest(format!("Account not found in database: {}", from_email))
         })?;
-        let display_name = &account.display_name;
+
+        // Send-as identity and its configured defaults
+        let sender = args["send_as"]
+            .as_str()
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| from_email.clone());
+        let identity = self
+            .config
+            .compose
+            .identity(&sender)
+            .cloned()
+            .unwrap_or_default();
+        let display_name = identity
+            .display_name
+            .as_deref()
+            .unwrap_or(&account.display_name);
 
         let to: Vec<String> = args["to"]
             .as_array()
@@ -1765,6 +3810,14 @@ Content-Type: text/html; charset=utf-8
         let body = args["body"]
             .as_str()
             .ok_or_else(|| Error::InvalidRequest("Missing body".to_string()))?;
+        let signature = resolve_signature(
+            &self.config.compose,
+            &self.oauth,
+            &from_email,
+            &sender,
+            &SignatureChoice::parse(args["signature"].as_str().unwrap_or_default()),
+        )
+        .await?;
 
         let cc: Vec<String> = args["cc"]
             .as_array()
@@ -1774,6 +3827,7 @@ Content-Type: text/html; charset=utf-8
                     .collect()
             })
             .unwrap_or_default();
+        let cc = identity.merge_cc(&to, &cc);
 
         let bcc: Vec<String> = args["bcc"]
             .as_array()
@@ -1803,37 +3857,66 @@ Content-Type: text/html; charset=utf-8
         // Detect if HTML formatting is needed
         let is_html = force_html || detect_html_content(body);
 
+        // Lint the message parts exactly as build_email_message will render them
+        let lint_issues = if self.config.compose.lint_enabled {
+            let (plain_part, html_part) = message_parts(body, is_html, signature.as_ref());
+            lint_outgoing(
+                &final_subject,
+                &plain_part,
+                html_part.as_deref(),
+                &self.config.compose,
+            )
+        } else {
+            Vec::new()
+        };
+        let lint_blocking =
+            (strict_lint || self.config.compose.block_on_severe_lint) && has_severe(&lint_issues);
+
         // If not confirmed and not saving as draft, return preview for user approval
         if !confirm && !save_as_draft {
             return Ok(serde_json::json!({
                 "status": "preview",
                 "message": "Please review this email. Call send_email again with confirm=true to send, or save_as_draft=true to save as draft.",
                 "email": {
-                    "from": format!("{} <{}>", display_name, from_email),
+                    "from": format!("{} <{}>", display_name, sender),
                     "to": to,
                     "cc": cc,
                     "bcc": bcc,
                     "subject": final_subject,
-                    "body": body,
+                    "body": signature.as_ref().map_or_else(|| body.to_string(), |s| s.append_text(body)),
                     "is_html": is_html,
                     "in_reply_to": in_reply_to,
                     "references": references,
-                }
+                    "reply_to": identity.reply_to,
+                },
+                "lint": lint_issues,
+                "lint_blocking": lint_blocking,
+            }));
+        }
+
+        if lint_blocking {
+            return Ok(serde_json::json!({
+                "status": "blocked",
+                "message": "Formatting lint found severe issues. Fix them and call send_email again, or omit strict_lint.",
+                "lint": lint_issues,
             }));
         }
 
         // Build RFC 2822 message
         let message = build_email_message(
             display_name,
-            &from_email,
+            &sender,
             &to,
             &cc,
             &bcc,
             &final_subject,
             body,
             is_html,
+            signature.as_ref(),
             in_reply_to.as_deref(),
             references.as_deref(),
+            identity.reply_to.as_deref(),
+            &[],
         );
 
         // Base64url encode the message
@@ -1874,7 +3957,7 @@ Content-Type: text/html; charset=utf-8
                 "status": "draft_created",
                 "draft_id": draft_id,
                 "message_id": message_id,
-                "from": format!("{} <{}>", display_name, from_email),
+                "from": format!("{} <{}>", display_name, sender),
                 "to": to,
                 "subject": final_subject,
             }));
@@ -1907,27 +3990,44 @@ Content-Type: text/html; charset=utf-8
         Ok(serde_json::json!({
             "status": "sent",
             "message_id": message_id,
-            "from": format!("{} <{}>", display_name, from_email),
+            "from": format!("{} <{}>", display_name, sender),
             "to": to,
             "subject": final_subject,
         }))
     }
 
-    /// List folders
-    async fn list_folders(&self, _args: &Value) -> Result<Value> {
-        // Return common Gmail folders
-        Ok(serde_json::json!({
-            "folders": [
-                "INBOX",
-                "[Gmail]/All Mail",
-                "[Gmail]/Drafts",
-                "[Gmail]/Important",
-                "[Gmail]/Sent Mail",
-                "[Gmail]/Spam",
-                "[Gmail]/Starred",
-                "[Gmail]/Trash"
-            ]
-        }))
+    /// List folders (Gmail labels) for each account
+    async fn list_folders(&self, args: &Value) -> Result<Value> {
+        let refresh = args["refresh"].as_bool().unwrap_or(false);
+
+        let account_ids: Vec<String> = match args["accounts"].as_array() {
+            Some(arr) => arr
+                .iter()
+                .filter_map(|v| v.as_str())
+                .filter_map(|id| self.config.resolve_account(id))
+                .collect(),
+            None => self
+                .db
+                .list_accounts()
+                .await?
+                .into_iter()
+                .map(|a| a.id)
+                .collect(),
+        };
+
+        let mut results = Vec::new();
+        for account_id in &account_ids {
+            let cache = list_gmail_labels(&self.oauth, &self.config, account_id, refresh).await?;
+            let folders: Vec<String> = cache.labels.iter().map(|l| l.imap_folder()).collect();
+            results.push(serde_json::json!({
+                "account": account_id,
+                "fetched_at": cache.fetched_at.to_rfc3339(),
+                "folders": folders,
+                "labels": cache.labels,
+            }));
+        }
+
+        Ok(serde_json::json!({ "accounts": results }))
     }
 
     /// Get an email attachment
@@ -2094,7 +4194,22 @@ Content-Type: text/html; charset=utf-8
         let account = self.db.get_account(&from_email).await?.ok_or_else(|| {
             Error::InvalidRequest(format!("Account not found in database: {}", from_email))
         })?;
-        let display_name = &account.display_name;
+
+        // Send-as identity and its configured defaults
+        let sender = args["send_as"]
+            .as_str()
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| from_email.clone());
+        let identity = self
+            .config
+            .compose
+            .identity(&sender)
+            .cloned()
+            .unwrap_or_default();
+        let display_name = identity
+            .display_name
+            .as_deref()
+            .unwrap_or(&account.display_name);
 
         let to: Vec<String> = args["to"]
             .as_array()
@@ -2116,6 +4231,14 @@ Content-Type: text/html; charset=utf-8
         let body = args["body"]
             .as_str()
             .ok_or_else(|| Error::InvalidRequest("Missing body".to_string()))?;
+        let signature = resolve_signature(
+            &self.config.compose,
+            &self.oauth,
+            &from_email,
+            &sender,
+            &SignatureChoice::parse(args["signature"].as_str().unwrap_or_default()),
+        )
+        .await?;
 
         let cc: Vec<String> = args["cc"]
             .as_array()
@@ -2125,6 +4248,7 @@ Content-Type: text/html; charset=utf-8
                     .collect()
             })
             .unwrap_or_default();
+        let cc = identity.merge_cc(&to, &cc);
 
         let bcc: Vec<String> = args["bcc"]
             .as_array()
@@ -2162,15 +4286,18 @@ Content-Type: text/html; charset=utf-8
         // Build RFC 2822 message
         let message = build_email_message(
             display_name,
-            &from_email,
+            &sender,
             &to,
             &cc,
             &bcc,
             &final_subject,
             body,
             is_html,
+            signature.as_ref(),
             in_reply_to.as_deref(),
             references.as_deref(),
+            identity.reply_to.as_deref(),
+            &[],
         );
 
         // Base64url encode the message
@@ -2221,7 +4348,7 @@ Content-Type: text/html; charset=utf-8
             "status": "draft_created",
             "draft_id": draft_id,
             "message_id": message_id,
-            "from": format!("{} <{}>", display_name, from_email),
+            "from": format!("{} <{}>", display_name, sender),
             "to": to,
             "subject": final_subject,
         }))
@@ -2471,136 +4598,50 @@ Content-Type: text/html; charset=utf-8
         let db_account = self.db.get_account(&from_email).await?.ok_or_else(|| {
             Error::InvalidRequest(format!("Account not found in database: {}", from_email))
         })?;
-        let display_name = &db_account.display_name;
 
         let access_token = self.oauth.get_valid_token(&from_email).await?;
         let client = reqwest::Client::new();
 
-        // First, get the existing draft to preserve fields not being updated
-        let existing_response = client
-            .get(format!(
-                "https://gmail.googleapis.com/gmail/v1/users/me/drafts/{}?format=full",
-                draft_id
-            ))
-            .bearer_auth(&access_token)
-            .send()
-            .await?;
-
-        if !existing_response.status().is_success() {
-            let status = existing_response.status();
-            let error_body = existing_response.text().await.unwrap_or_default();
-            return Err(Error::Other(format!(
-                "Gmail API error {}: {}",
-                status, error_body
-            )));
-        }
-
-        let existing: serde_json::Value = existing_response.json().await?;
-
-        // Extract existing values
-        let headers = existing["message"]["payload"]["headers"].as_array();
-        let mut existing_subject = String::new();
-        let mut existing_to = Vec::new();
-        let mut existing_cc = Vec::new();
-        let mut existing_body = String::new();
-
-        if let Some(hdrs) = headers {
-            for h in hdrs {
-                let name = h["name"].as_str().unwrap_or("");
-                let value = h["value"].as_str().unwrap_or("");
-                match name {
-                    "Subject" => existing_subject = value.to_string(),
-                    "To" => existing_to = parse_email_addresses(value),
-                    "Cc" => existing_cc = parse_email_addresses(value),
-                    _ => {}
-                }
-            }
-        }
-
-        // Recursively extract text/plain body from potentially nested multipart structures
-        fn extract_text_body_recursive(part: &serde_json::Value) -> Option<String> {
-            use base64::{
-                engine::general_purpose::{URL_SAFE, URL_SAFE_NO_PAD},
-                Engine,
-            };
-            let mime_type = part["mimeType"].as_str().unwrap_or("");
-
-            if mime_type == "text/plain" {
-                if let Some(body_data) = part["body"]["data"].as_str() {
-                    // Gmail uses URL-safe base64 - try with padding first, then without
-                    let decoded = URL_SAFE
-                        .decode(body_data)
-                        .or_else(|_| URL_SAFE_NO_PAD.decode(body_data));
-                    if let Ok(decoded) = decoded {
-                        if let Ok(text) = String::from_utf8(decoded) {
-                            return Some(text);
-                        }
-                    }
-                }
-            } else if mime_type.starts_with("multipart/") {
-                if let Some(parts) = part["parts"].as_array() {
-                    for nested_part in parts {
-                        if let Some(text) = extract_text_body_recursive(nested_part) {
-                            return Some(text);
-                        }
-                    }
-                }
-            }
-            None
-        }
+        // Gmail replaces the whole message, so start from the existing draft
+        let existing = fetch_draft(&self.oauth, &from_email, draft_id).await?;
 
-        // Extract existing body
-        let payload = &existing["message"]["payload"];
-        if let Some(text) = extract_text_body_recursive(payload) {
-            existing_body = text;
-        }
+        // Keep the draft's send-as address and its identity's defaults
+        let (sender, identity) = existing.sender(&self.config.compose, &from_email);
+        let display_name = identity
+            .display_name
+            .as_deref()
+            .unwrap_or(&db_account.display_name);
 
-        // Use provided values or fall back to existing
-        let to: Vec<String> = args["to"]
-            .as_array()
-            .map(|arr| {
+        let string_list = |value: &Value| -> Option<Vec<String>> {
+            value.as_array().map(|arr| {
                 arr.iter()
                     .filter_map(|v| v.as_str().map(|s| s.to_string()))
                     .collect()
             })
-            .unwrap_or(existing_to);
+        };
 
+        // Use provided values or fall back to existing
+        let to = string_list(&args["to"]).unwrap_or(existing.to);
         let subject = args["subject"]
             .as_str()
             .map(|s| s.to_string())
-            .unwrap_or(existing_subject);
-
+            .unwrap_or(existing.subject);
         let body = args["body"]
             .as_str()
             .map(|s| s.to_string())
-            .unwrap_or(existing_body);
-
-        let cc: Vec<String> = args["cc"]
-            .as_array()
-            .map(|arr| {
-                arr.iter()
-                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                    .collect()
-            })
-            .unwrap_or(existing_cc);
-
-        let bcc: Vec<String> = args["bcc"]
-            .as_array()
-            .map(|arr| {
-                arr.iter()
-                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                    .collect()
-            })
-            .unwrap_or_default();
+            .unwrap_or(existing.body);
+        let cc = string_list(&args["cc"]).unwrap_or(existing.cc);
+        let bcc = string_list(&args["bcc"]).unwrap_or(existing.bcc);
 
         // Detect if HTML formatting is needed
         let force_html = args["html"].as_bool().unwrap_or(false);
         let is_html = force_html || detect_html_content(&body);
 
-        // Build RFC 2822 message
+        // Build RFC 2822 message; the body already carries the signature
+        // added when the draft was made
         let message = build_email_message(
             display_name,
-            &from_email,
+            &sender,
             &to,
             &cc,
             &bcc,
@@ -2608,7 +4649,10 @@ Content-Type: text/html; charset=utf-8
             &body,
             is_html,
             None,
-            None,
+            existing.in_reply_to.as_deref(),
+            existing.references.as_deref(),
+            identity.reply_to.as_deref(),
+            &existing.attachments,
         );
 
         // Base64url encode the message
@@ -2645,7 +4689,7 @@ Content-Type: text/html; charset=utf-8
         Ok(serde_json::json!({
             "status": "updated",
             "draft_id": new_draft_id,
-            "from": format!("{} <{}>", display_name, from_email),
+            "from": format!("{} <{}>", display_name, sender),
             "to": to,
             "subject": subject,
         }))
@@ -2827,6 +4871,8 @@ Content-Type: text/html; charset=utf-8
             calendar_id: args["calendar_id"].as_str().map(|s| s.to_string()),
             date_from,
             date_to,
+            fusion: FusionOptions::from_config(&self.config.search)
+                .with_mode(parse_mode_arg(args)?),
         };
 
         let start = std::time::Instant::now();
@@ -2865,7 +4911,22 @@ Content-Type: text/html; charset=utf-8
             .await?
             .ok_or_else(|| Error::Other(format!("Event not found: {}", id)))?;
 
-        Ok(serde_json::to_value(&event)?)
+        let mut response = serde_json::to_value(&event)?;
+        response["web_link"] = serde_json::json!(event.web_link());
+        if self.config.directory.attendee_profiles {
+            let emails: Vec<String> = event
+                .organizer
+                .iter()
+                .chain(&event.attendees)
+                .map(|a| a.email.clone())
+                .collect();
+            let profiles =
+                attendee_profiles(&self.oauth, &self.config, &event.account_id, &emails).await;
+            if !profiles.is_empty() {
+                response["attendee_profiles"] = serde_json::to_value(&profiles)?;
+            }
+        }
+        Ok(response)
     }
 
     /// List calendar events in a date range (no semantic search required)
@@ -2899,9 +4960,21 @@ Content-Type: text/html; charset=utf-8
                 .collect()
         });
 
+        let options = EventOptions::parse(
+            args["color"].as_str(),
+            args["visibility"].as_str(),
+            args["show_as"].as_str(),
+            None,
+        )?;
+        let filter = EventFilter {
+            show_as: options.transparency,
+            visibility: options.visibility,
+            color_id: options.color_id,
+        };
+
         let events = self
             .db
-            .list_events_in_range(accounts.as_deref(), &from, &to, limit.min(200))
+            .list_events_in_range(accounts.as_deref(), &from, &to, &filter, limit.min(200))
             .await?;
 
         // Convert to JSON-friendly format with full attendee/organizer data
@@ -2917,7 +4990,13 @@ Content-Type: text/html; charset=utf-8
                     crate::models::EventTime::DateTime(dt) => dt.to_rfc3339(),
                     crate::models::EventTime::Date(d) => d.to_string(),
                 },
+                "start_local": e.start.to_local_string(tz),
+                "end_local": e.end.to_local_string(tz),
                 "location": e.location,
+                "conference": e.conference,
+                "show_as": e.transparency.show_as(),
+                "visibility": e.visibility,
+                "color": e.color_id.as_deref().and_then(event_color_name),
                 "organizer": e.organizer.as_ref().map(|o| serde_json::json!({
                     "email": o.email,
                     "name": o.name,
@@ -2932,6 +5011,7 @@ Content-Type: text/html; charset=utf-8
                 })).collect::<Vec<_>>(),
                 "account_id": e.account_id,
                 "calendar_id": e.calendar_id,
+                "web_link": e.web_link(),
             })
         }).collect();
 
@@ -3023,6 +5103,110 @@ Content-Type: text/html; charset=utf-8
         }))
     }
 
+    /// Working hours and out-of-office state for scheduling
+    async fn availability_context(&self, args: &Value) -> Result<Value> {
+        let refresh = args["refresh"].as_bool().unwrap_or(false);
+        let hours =
+            WorkingHours::from_config(&self.config.availability, &self.config.general.timezone)?;
+
+        let account_ids: Vec<String> = match args["accounts"].as_array() {
+            Some(arr) => arr
+                .iter()
+                .filter_map(|v| v.as_str())
+                .filter_map(|id| self.config.resolve_account(id))
+                .collect(),
+            None => self
+                .db
+                .list_accounts()
+                .await?
+                .into_iter()
+                .map(|a| a.id)
+                .collect(),
+        };
+
+        let mut states = Vec::new();
+        let mut unavailable = Vec::new();
+        for account_id in &account_ids {
+            match load_availability(&self.oauth, &self.config, account_id, refresh).await {
+                Ok(state) => states.push(state),
+                Err(e) => {
+                    warn!("Failed to load availability for {}: {}", account_id, e);
+                    unavailable.push(serde_json::json!({
+                        "account": account_id,
+                        "error": e.to_string()
+                    }));
+                }
+            }
+        }
+
+        let mut context = serde_json::to_value(availability_context(&hours, &states, Utc::now()))?;
+        if !unavailable.is_empty() {
+            context["unavailable_accounts"] = Value::Array(unavailable);
+        }
+        Ok(context)
+    }
+
+    /// Ranked meeting slots for a set of attendees, optionally held
+    async fn propose_meeting_times(&self, args: &Value) -> Result<Value> {
+        let account_id = match args["account"].as_str() {
+            Some(account) => self
+                .config
+                .resolve_account(account)
+                .ok_or_else(|| Error::AccountNotFound(account.to_string()))?,
+            None => self
+                .db
+                .list_accounts()
+                .await?
+                .into_iter()
+                .next()
+                .map(|a| a.id)
+                .ok_or_else(|| Error::InvalidRequest("No accounts configured".to_string()))?,
+        };
+
+        let duration_arg = args["duration"].as_str().unwrap_or("30m");
+        let duration = parse_duration(duration_arg)
+            .ok_or_else(|| Error::InvalidRequest(format!("Invalid duration: {}", duration_arg)))?;
+        let window_arg = args["window"].as_str().unwrap_or("next-week");
+        let tz: Tz = self.config.general.timezone.parse().unwrap_or(Tz::UTC);
+        let window = parse_window(window_arg, Utc::now(), tz)
+            .ok_or_else(|| Error::InvalidRequest(format!("Invalid window: {}", window_arg)))?;
+
+        let attendees: Vec<String> = args["attendees"]
+            .as_array()
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let request = MeetingRequest {
+            attendees: resolve_attendees(&self.oauth, &self.config, &account_id, &attendees)
+                .await?,
+            duration,
+            window,
+            limit: args["limit"].as_u64().unwrap_or(5) as usize,
+        };
+        let mut proposal =
+            propose_meeting_times(&self.db, &self.oauth, &self.config, &account_id, &request)
+                .await?;
+
+        if args["hold"].as_bool().unwrap_or(false) {
+            if let Some(best) = proposal.slots.first() {
+                let title = match args["title"].as_str() {
+                    Some(title) => title.to_string(),
+                    None if request.attendees.is_empty() => "Hold".to_string(),
+                    None => format!("Hold: meeting with {}", request.attendees.join(", ")),
+                };
+                let hold =
+                    place_hold(&self.oauth, &account_id, best, &title, &request.attendees).await?;
+                info!("Placed hold {} for {}", hold.event_id, account_id);
+                proposal.hold = Some(hold);
+            }
+        }
+
+        Ok(serde_json::to_value(proposal)?)
+    }
+
     /// Create a calendar event via Google Calendar API
     async fn create_event(&self, args: &Value) -> Result<Value> {
         let account = args["account"]
@@ -3042,9 +5226,13 @@ Content-Type: text/html; charset=utf-8
             .as_str()
             .ok_or_else(|| Error::InvalidRequest("Missing start time (ISO 8601)".to_string()))?;
 
-        let end = args["end"]
-            .as_str()
-            .ok_or_else(|| Error::InvalidRequest("Missing end time (ISO 8601)".to_string()))?;
+        let all_day = args["all_day"].as_bool().unwrap_or(false);
+        let end = args["end"].as_str();
+        if end.is_none() && !all_day {
+            return Err(Error::InvalidRequest(
+                "Missing end time (ISO 8601)".to_string(),
+            ));
+        }
 
         // Optional fields
         let calendar_id = args["calendar_id"].as_str().unwrap_or("primary");
@@ -3058,20 +5246,81 @@ Content-Type: text/html; charset=utf-8
                     .collect()
             })
             .unwrap_or_default();
+        let with_meet = args["with_meet"].as_bool().unwrap_or(false);
+        let force = args["force"].as_bool().unwrap_or(false);
+
+        // Times without an offset are in the event's timezone
+        let timezone = args["timezone"]
+            .as_str()
+            .unwrap_or(&self.config.general.timezone);
+        let tz: Tz = timezone.parse().map_err(|_| {
+            Error::InvalidRequest(format!("Unknown timezone: {} (use an IANA name)", timezone))
+        })?;
+
+        let when = NewEventTime::parse(start, end, all_day, tz).ok_or_else(|| {
+            Error::InvalidRequest(if all_day {
+                "all_day events need start and end as YYYY-MM-DD, end on or after start".to_string()
+            } else {
+                "start and end must be ISO 8601 times, end after start".to_string()
+            })
+        })?;
+
+        let until = match args["until"].as_str() {
+            Some(until) => Some(NaiveDate::parse_from_str(until, "%Y-%m-%d").map_err(|_| {
+                Error::InvalidRequest(format!("Invalid until: {} (use YYYY-MM-DD)", until))
+            })?),
+            None => None,
+        };
+        let recurrence = Recurrence {
+            rule: args["recurrence"].as_str().map(|s| s.to_string()),
+            repeat: args["repeat"].as_str().map(|s| s.to_string()),
+            on: args["on"]
+                .as_array()
+                .map(|arr| {
+                    arr.iter()
+                        .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            until,
+            count: args["count"].as_u64().map(|n| n as u32),
+        };
+        let rrule = recurrence.to_rrule(&when)?;
+        let options = event_options(args)?;
+
+        // Check for overlapping events before touching the API (the first
+        // occurrence, for repeating events)
+        let conflicts = match when.interval(tz) {
+            Some((start, end)) => find_conflicts(&self.db, &self.config, start, end).await?,
+            None => Vec::new(),
+        };
+        if !conflicts.is_empty() && !force {
+            return Ok(serde_json::json!({
+                "success": false,
+                "message": format!(
+                    "Event '{}' overlaps {} existing event(s); not created. Set force=true to create it anyway.",
+                    summary,
+                    conflicts.len()
+                ),
+                "conflicts": conflicts
+            }));
+        }
+
+        let attendees =
+            resolve_attendees(&self.oauth, &self.config, &account_email, &attendees).await?;
 
         // Build the event object for Google Calendar API
+        let (start_body, end_body) = when.to_api();
         let mut event_body = serde_json::json!({
             "summary": summary,
-            "start": {
-                "dateTime": start,
-                "timeZone": "UTC"
-            },
-            "end": {
-                "dateTime": end,
-                "timeZone": "UTC"
-            }
+            "start": start_body,
+            "end": end_body
         });
 
+        if let Some(rule) = &rrule {
+            event_body["recurrence"] = serde_json::json!([rule]);
+        }
+
         if let Some(desc) = description {
             event_body["description"] = serde_json::json!(desc);
         }
@@ -3087,13 +5336,19 @@ Content-Type: text/html; charset=utf-8
                 .collect::<Vec<_>>());
         }
 
+        if with_meet {
+            event_body["conferenceData"] = meet_request();
+        }
+
+        options.apply(&mut event_body);
+
         // Get access token
         let access_token = self.oauth.get_valid_token(&account_email).await?;
 
         // Create event via Google Calendar API
         let client = reqwest::Client::new();
         let url = format!(
-            "https://www.googleapis.com/calendar/v3/calendars/{}/events",
+            "https://www.googleapis.com/calendar/v3/calendars/{}/events?conferenceDataVersion=1",
             urlencoding::encode(calendar_id)
         );
 
@@ -3116,24 +5371,102 @@ Content-Type: text/html; charset=utf-8
         let created_event: serde_json::Value = response.json().await?;
         let event_id = created_event["id"].as_str().unwrap_or("unknown");
         let html_link = created_event["htmlLink"].as_str();
+        let conference = Conference::from_google(&created_event);
 
         info!("Created calendar event: {} for {}", event_id, account_email);
+        let (start_time, end_time) = when.event_times();
+
+        let mut message = format!("Event '{}' created successfully", summary);
+        if with_meet && conference.is_none() {
+            message.push_str(
+                "; the Meet link is still being set up, fetch the event after the next sync for it",
+            );
+        }
 
         Ok(serde_json::json!({
             "success": true,
-            "message": format!("Event '{}' created successfully", summary),
+            "message": message,
+            "conflicts": conflicts,
             "event": {
                 "id": event_id,
                 "summary": summary,
-                "start": start,
-                "end": end,
+                "start": start_time.to_local_string(tz),
+                "end": end_time.to_local_string(tz),
+                "all_day": all_day,
+                "timezone": tz.name(),
+                "recurrence": rrule,
                 "calendar_id": calendar_id,
                 "account": account_email,
+                "attendees": attendees,
+                "conference": conference,
+                "show_as": created_event["transparency"]
+                    .as_str()
+                    .and_then(Transparency::parse)
+                    .unwrap_or_default()
+                    .show_as(),
+                "visibility": created_event["visibility"].as_str().unwrap_or("default"),
+                "color": created_event["colorId"].as_str().and_then(event_color_name),
+                "reminders": created_event["reminders"],
                 "html_link": html_link
             }
         }))
     }
 
+    /// Change fields of a synced event via Google Calendar API
+    async fn update_event(&self, args: &Value) -> Result<Value> {
+        let id = args["id"]
+            .as_str()
+            .ok_or_else(|| Error::InvalidRequest("Missing event id".to_string()))?;
+        let event = self
+            .db
+            .get_event(id)
+            .await?
+            .ok_or_else(|| Error::InvalidRequest(format!("Event not found: {}", id)))?;
+
+        let options = event_options(args)?;
+        let mut changes = serde_json::json!({});
+        for field in ["summary", "description", "location"] {
+            if let Some(value) = args[field].as_str() {
+                changes[field] = serde_json::json!(value);
+            }
+        }
+        options.apply(&mut changes);
+        if changes.as_object().is_some_and(|c| c.is_empty()) {
+            return Err(Error::InvalidRequest(
+                "Nothing to change: give summary, description, location, color, visibility, show_as or reminders".to_string(),
+            ));
+        }
+
+        let updated = patch_event(&self.oauth, &event, &changes).await?;
+        info!(
+            "Updated calendar event: {} for {}",
+            event.google_event_id, event.account_id
+        );
+
+        Ok(serde_json::json!({
+            "success": true,
+            "message": format!(
+                "Event '{}' updated",
+                updated["summary"].as_str().unwrap_or(&event.summary)
+            ),
+            "event": {
+                "id": event.id,
+                "account": event.account_id,
+                "summary": updated["summary"],
+                "location": updated["location"],
+                "show_as": updated["transparency"]
+                    .as_str()
+                    .and_then(Transparency::parse)
+                    .unwrap_or_default()
+                    .show_as(),
+                "visibility": updated["visibility"].as_str().unwrap_or("default"),
+                "color": updated["colorId"].as_str().and_then(event_color_name),
+                "reminders": updated["reminders"],
+                "html_link": updated["htmlLink"]
+            }
+        }))
+    }
+
     /// Get sync status for all accounts
     async fn sync_status_all(&self) -> Result<Value> {
         // Refresh table handles to see latest data from daemon
@@ -3221,6 +5554,7 @@ Content-Type: text/html; charset=utf-8
                     "downloaded": att_downloaded,
                     "pending": att_total - att_downloaded
                 },
+                "storage": self.storage_status_json(account).await,
                 "is_syncing": is_syncing,
                 "sync_progress": sync_progress_json
             });
@@ -3228,8 +5562,10 @@ Content-Type: text/html; charset=utf-8
             account_stats.push(stat);
         }
 
+        let token_health = TokenHealthReport::load(&self.config.token_health_file());
         Ok(serde_json::json!({
             "accounts": account_stats,
+            "needs_reauth": needs_reauth_json(&accounts, &token_health),
             "totals": {
                 "email_count": total_emails,
                 "event_count": total_events,
@@ -3291,6 +5627,27 @@ Content-Type: text/html; charset=utf-8
     }
 
     /// Get sync status for an account
+    /// Storage usage against the account's limit, with retention suggestions when exceeded
+    async fn storage_status_json(&self, account: &Account) -> Value {
+        let usage = self
+            .db
+            .get_storage_usage(&account.id)
+            .await
+            .unwrap_or_default();
+        let exceeded = account.storage_quota_exceeded(&usage);
+        serde_json::json!({
+            "used_bytes": usage.total(),
+            "used_human": format_bytes(usage.total()),
+            "max_bytes": account.max_storage_bytes,
+            "quota_exceeded": exceeded,
+            "retention_suggestions": if exceeded {
+                account.retention_suggestions(&usage)
+            } else {
+                vec![]
+            }
+        })
+    }
+
     async fn sync_status(&self, email: &str) -> Result<Value> {
         use chrono::Duration;
 
@@ -3332,6 +5689,12 @@ Content-Type: text/html; charset=utf-8
                     "pending": total_attachments - downloaded_attachments,
                     "total_size_bytes": attachment_size,
                     "total_size_human": format_bytes(attachment_size)
+                },
+                "storage": self.storage_status_json(&account).await,
+                "retention": {
+                    "policy": account.retention,
+                    "last_prune": RetentionReport::load(&self.config.retention_report_file())
+                        .get(email)
                 }
             },
             "message": format!(
@@ -3640,7 +6003,7 @@ Content-Type: text/html; charset=utf-8
         {
             use std::process::Command;
             let output = Command::new("pgrep")
-                .args(["-f", "groundeffect-daemon"])
+                .args(["-f", &DaemonConfig::process_pattern()])
                 .output()
                 .ok()?;
 
@@ -3678,7 +6041,7 @@ Content-Type: text/html; charset=utf-8
     /// Start the daemon
     async fn daemon_start(&self, arguments: &Value) -> Result<Value> {
         // Load existing daemon config
-        let mut daemon_config = DaemonConfig::load().unwrap_or_default();
+        let mut daemon_config = DaemonConfig::load_file().unwrap_or_default();
         let mut config_changed = false;
 
         // Apply parameter overrides from arguments
@@ -3774,7 +6137,7 @@ Content-Type: text/html; charset=utf-8
                         "calendar_poll_interval_secs": daemon_config.calendar_poll_interval_secs,
                         "max_concurrent_fetches": daemon_config.max_concurrent_fetches
                     },
-                    "log_file": if daemon_config.logging_enabled { Some("~/.local/share/groundeffect/logs/daemon.log") } else { None }
+                    "log_file": if daemon_config.logging_enabled { Some(daemon_log_dir().join("daemon.log")) } else { None }
                 }));
             } else {
                 return Err(Error::Other(
@@ -3783,7 +6146,39 @@ Content-Type: text/html; charset=utf-8
             }
         }
 
-        // No launchd - start daemon directly
+        if DaemonConfig::is_systemd_installed() {
+            let output = systemctl_user(&["start", &DaemonConfig::systemd_unit_name()])?;
+            if !output.status.success() {
+                return Err(Error::Other(format!(
+                    "Failed to start daemon via systemctl: {}",
+                    String::from_utf8_lossy(&output.stderr)
+                )));
+            }
+
+            // Wait for daemon to start
+            tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+
+            return match self.is_daemon_running() {
+                Some(pid) => Ok(serde_json::json!({
+                    "success": true,
+                    "message": "Daemon started successfully via systemctl",
+                    "status": "running",
+                    "pid": pid,
+                    "settings": {
+                        "logging_enabled": daemon_config.logging_enabled,
+                        "email_poll_interval_secs": daemon_config.email_poll_interval_secs,
+                        "calendar_poll_interval_secs": daemon_config.calendar_poll_interval_secs,
+                        "max_concurrent_fetches": daemon_config.max_concurrent_fetches
+                    },
+                    "log_file": if daemon_config.logging_enabled { Some(daemon_log_dir().join("daemon.log")) } else { None }
+                })),
+                None => Err(Error::Other(
+                    "Daemon failed to start via systemctl. Check logs for errors.".to_string(),
+                )),
+            };
+        }
+
+        // No service manager - start daemon directly
         // Get daemon binary path
         let daemon_path = self.get_daemon_binary_path()?;
 
@@ -3802,6 +6197,7 @@ Content-Type: text/html; charset=utf-8
         if daemon_config.logging_enabled {
             cmd.arg("--log");
         }
+        cmd.args(DaemonConfig::profile_args());
 
         // Pass through OAuth credentials if available
         if let Some(id) = &client_id {
@@ -3859,7 +6255,7 @@ Content-Type: text/html; charset=utf-8
                     "calendar_poll_interval_secs": daemon_config.calendar_poll_interval_secs,
                     "max_concurrent_fetches": daemon_config.max_concurrent_fetches
                 },
-                "log_file": if daemon_config.logging_enabled { Some("~/.local/share/groundeffect/logs/daemon.log") } else { None }
+                "log_file": if daemon_config.logging_enabled { Some(daemon_log_dir().join("daemon.log")) } else { None }
             }))
         } else {
             // Clean up PID file if daemon didn't start
@@ -3904,8 +6300,17 @@ Content-Type: text/html; charset=utf-8
                     )));
                 }
             }
+        } else if DaemonConfig::is_systemd_installed() {
+            // Stop through systemd so Restart=always doesn't bring it back
+            let output = systemctl_user(&["stop", &DaemonConfig::systemd_unit_name()])?;
+            if !output.status.success() {
+                return Err(Error::Other(format!(
+                    "Failed to stop daemon via systemctl: {}",
+                    String::from_utf8_lossy(&output.stderr)
+                )));
+            }
         } else {
-            // No launchd - send SIGTERM directly
+            // No service manager - send SIGTERM directly
             #[cfg(unix)]
             {
                 let output = Command::new("kill")
@@ -4014,7 +6419,39 @@ Content-Type: text/html; charset=utf-8
             }
         }
 
-        // No launchd - use direct process management
+        if DaemonConfig::is_systemd_installed() {
+            let output = systemctl_user(&["restart", &DaemonConfig::systemd_unit_name()])?;
+            if !output.status.success() {
+                return Err(Error::Other(format!(
+                    "Failed to restart daemon via systemctl: {}",
+                    String::from_utf8_lossy(&output.stderr)
+                )));
+            }
+
+            // Wait for daemon to start
+            tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+
+            let daemon_config = DaemonConfig::load().unwrap_or_default();
+            return match self.is_daemon_running() {
+                Some(pid) => Ok(serde_json::json!({
+                    "success": true,
+                    "message": "Daemon restarted successfully via systemctl",
+                    "status": "running",
+                    "pid": pid,
+                    "settings": {
+                        "logging_enabled": daemon_config.logging_enabled,
+                        "email_poll_interval_secs": daemon_config.email_poll_interval_secs,
+                        "calendar_poll_interval_secs": daemon_config.calendar_poll_interval_secs,
+                        "max_concurrent_fetches": daemon_config.max_concurrent_fetches
+                    }
+                })),
+                None => Err(Error::Other(
+                    "Daemon failed to restart via systemctl".to_string(),
+                )),
+            };
+        }
+
+        // No service manager - use direct process management
         // Stop if running
         if self.is_daemon_running().is_some() {
             self.daemon_stop().await?;
@@ -4031,6 +6468,7 @@ Content-Type: text/html; charset=utf-8
         // Load daemon config
         let daemon_config = DaemonConfig::load().unwrap_or_default();
         let launchd_installed = DaemonConfig::is_launchd_installed();
+        let systemd_installed = DaemonConfig::is_systemd_installed();
 
         match self.is_daemon_running() {
             Some(pid) => {
@@ -4046,7 +6484,8 @@ Content-Type: text/html; charset=utf-8
                         "max_concurrent_fetches": daemon_config.max_concurrent_fetches
                     },
                     "launchd_agent_installed": launchd_installed,
-                    "log_file": if daemon_config.logging_enabled { Some("~/.local/share/groundeffect/logs/daemon.log") } else { None }
+                    "systemd_unit_installed": systemd_installed,
+                    "log_file": if daemon_config.logging_enabled { Some(daemon_log_dir().join("daemon.log")) } else { None }
                 });
 
                 // Try to get process uptime on Unix
@@ -4077,12 +6516,45 @@ Content-Type: text/html; charset=utf-8
                     "calendar_poll_interval_secs": daemon_config.calendar_poll_interval_secs,
                     "max_concurrent_fetches": daemon_config.max_concurrent_fetches
                 },
-                "launchd_agent_installed": launchd_installed
+                "launchd_agent_installed": launchd_installed,
+                "systemd_unit_installed": systemd_installed
             })),
         }
     }
 }
 
+/// Run `systemctl --user` with the given arguments
+fn systemctl_user(args: &[&str]) -> Result<std::process::Output> {
+    Command::new("systemctl")
+        .arg("--user")
+        .args(args)
+        .output()
+        .map_err(|e| Error::Other(format!("Failed to run systemctl: {}", e)))
+}
+
+/// Accounts that need re-authentication, with the reason from the daemon's
+/// token checks and the command that fixes it
+fn needs_reauth_json(accounts: &[Account], token_health: &TokenHealthReport) -> Vec<Value> {
+    accounts
+        .iter()
+        .filter_map(|account| {
+            let health = token_health
+                .accounts
+                .get(&account.id)
+                .filter(|h| h.state == TokenState::NeedsReauth);
+            if health.is_none() && account.status != AccountStatus::NeedsReauth {
+                return None;
+            }
+            Some(serde_json::json!({
+                "account": account.id,
+                "reason": health.and_then(|h| h.reason.clone()),
+                "since": health.and_then(|h| h.needs_reauth_since).map(|t| t.to_rfc3339()),
+                "fix": format!("groundeffect account reauth {}", account.id)
+            }))
+        })
+        .collect()
+}
+
 /// Format bytes as human-readable string
 fn format_bytes(bytes: u64) -> String {
     const KB: u64 = 1024;
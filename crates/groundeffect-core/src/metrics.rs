@@ -0,0 +1,456 @@
+//! Prometheus metrics for daemon health
+//!
+//! With `[metrics]` enabled, the daemon serves its counters and gauges in
+//! the Prometheus text format on `http://127.0.0.1:<port>/metrics` and, with
+//! `textfile` set, also rewrites them to a file for node_exporter's textfile
+//! collector. Counters start at zero when the daemon starts. Sync ages and
+//! table sizes are read from the database when metrics are collected, so an
+//! alert on `groundeffect_last_sync_age_seconds` catches a stalled sync even
+//! when nothing else in the daemon notices.
+
+use std::fmt::Write as _;
+use std::path::Path;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, info, warn};
+
+use crate::db::{Database, TableUsage};
+use crate::error::Result;
+use crate::models::Account;
+use crate::sync::{SyncEvent, SyncType};
+
+/// Path the metrics are served on
+pub const METRICS_PATH: &str = "/metrics";
+
+/// Default loopback port of the metrics endpoint
+pub const METRICS_DEFAULT_PORT: u16 = 8790;
+
+/// Longest request head accepted by the metrics server
+const MAX_REQUEST_HEAD: usize = 8 * 1024;
+
+/// Content type of the Prometheus text format
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Work waiting its turn in the daemon
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Queue {
+    /// Account syncs waiting under `sync.max_concurrent_fetches`
+    Sync,
+
+    /// Requests waiting for (or in) a local embedding batch
+    Embedding,
+}
+
+impl Queue {
+    fn name(self) -> &'static str {
+        match self {
+            Queue::Sync => "sync",
+            Queue::Embedding => "embedding",
+        }
+    }
+
+    fn depth(self) -> &'static AtomicI64 {
+        match self {
+            Queue::Sync => &METRICS.sync_queue,
+            Queue::Embedding => &METRICS.embedding_queue,
+        }
+    }
+}
+
+/// Counts one entry in a [`Queue`] until dropped
+#[derive(Debug)]
+pub struct Queued(Queue);
+
+impl Drop for Queued {
+    fn drop(&mut self) {
+        self.0.depth().fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Add an entry to `queue`'s depth for as long as the guard lives
+pub fn enqueue(queue: Queue) -> Queued {
+    queue.depth().fetch_add(1, Ordering::Relaxed);
+    Queued(queue)
+}
+
+struct Metrics {
+    emails_synced: AtomicU64,
+    events_synced: AtomicU64,
+    sync_errors: AtomicU64,
+    api_requests: AtomicU64,
+    api_rate_limited: AtomicU64,
+    embedded_texts: AtomicU64,
+    embedding_micros: AtomicU64,
+    sync_queue: AtomicI64,
+    embedding_queue: AtomicI64,
+}
+
+static METRICS: Metrics = Metrics {
+    emails_synced: AtomicU64::new(0),
+    events_synced: AtomicU64::new(0),
+    sync_errors: AtomicU64::new(0),
+    api_requests: AtomicU64::new(0),
+    api_rate_limited: AtomicU64::new(0),
+    embedded_texts: AtomicU64::new(0),
+    embedding_micros: AtomicU64::new(0),
+    sync_queue: AtomicI64::new(0),
+    embedding_queue: AtomicI64::new(0),
+};
+
+/// Count a finished sync's items or a sync failure
+pub fn record_sync_event(event: &SyncEvent) {
+    match event {
+        SyncEvent::SyncCompleted {
+            sync_type, count, ..
+        } => {
+            let counter = match sync_type {
+                SyncType::Calendar => &METRICS.events_synced,
+                SyncType::Email | SyncType::All => &METRICS.emails_synced,
+            };
+            counter.fetch_add(*count as u64, Ordering::Relaxed);
+        }
+        SyncEvent::SyncError { .. } => {
+            METRICS.sync_errors.fetch_add(1, Ordering::Relaxed);
+        }
+        _ => {}
+    }
+}
+
+/// Count a Google API request (IMAP command batch or REST call)
+pub fn record_api_request() {
+    METRICS.api_requests.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Count a Google API response, noting 429 Too Many Requests
+pub fn record_api_status(status: reqwest::StatusCode) {
+    if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        METRICS.api_rate_limited.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Count texts embedded and the time it took
+pub fn record_embedded(texts: usize, elapsed: Duration) {
+    METRICS
+        .embedded_texts
+        .fetch_add(texts as u64, Ordering::Relaxed);
+    METRICS
+        .embedding_micros
+        .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+}
+
+/// The in-process counters and gauges at one moment
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Snapshot {
+    pub emails_synced: u64,
+    pub events_synced: u64,
+    pub sync_errors: u64,
+    pub api_requests: u64,
+    pub api_rate_limited: u64,
+    pub embedded_texts: u64,
+    pub embedding_seconds: f64,
+    pub sync_queue: i64,
+    pub embedding_queue: i64,
+    pub uptime_seconds: f64,
+}
+
+/// Current counters and gauges, with uptime measured from `started`
+pub fn snapshot(started: Instant) -> Snapshot {
+    let get = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+    Snapshot {
+        emails_synced: get(&METRICS.emails_synced),
+        events_synced: get(&METRICS.events_synced),
+        sync_errors: get(&METRICS.sync_errors),
+        api_requests: get(&METRICS.api_requests),
+        api_rate_limited: get(&METRICS.api_rate_limited),
+        embedded_texts: get(&METRICS.embedded_texts),
+        embedding_seconds: get(&METRICS.embedding_micros) as f64 / 1e6,
+        sync_queue: METRICS.sync_queue.load(Ordering::Relaxed).max(0),
+        embedding_queue: METRICS.embedding_queue.load(Ordering::Relaxed).max(0),
+        uptime_seconds: started.elapsed().as_secs_f64(),
+    }
+}
+
+/// Render metrics in the Prometheus text format
+pub fn render(
+    snapshot: &Snapshot,
+    accounts: &[Account],
+    tables: &[TableUsage],
+    now: DateTime<Utc>,
+) -> String {
+    let mut out = String::new();
+    let mut family = |name: &str, kind: &str, help: &str, samples: &[(String, String)]| {
+        let _ = writeln!(out, "# HELP groundeffect_{} {}", name, help);
+        let _ = writeln!(out, "# TYPE groundeffect_{} {}", name, kind);
+        for (labels, value) in samples {
+            let _ = writeln!(out, "groundeffect_{}{} {}", name, labels, value);
+        }
+    };
+    let one = |value: String| vec![(String::new(), value)];
+
+    family(
+        "uptime_seconds",
+        "gauge",
+        "Seconds since the daemon started.",
+        &one(format!("{:.3}", snapshot.uptime_seconds)),
+    );
+    family(
+        "synced_total",
+        "counter",
+        "Items stored by completed syncs.",
+        &[
+            (
+                "{kind=\"email\"}".to_string(),
+                snapshot.emails_synced.to_string(),
+            ),
+            (
+                "{kind=\"event\"}".to_string(),
+                snapshot.events_synced.to_string(),
+            ),
+        ],
+    );
+    family(
+        "sync_errors_total",
+        "counter",
+        "Account syncs that failed.",
+        &one(snapshot.sync_errors.to_string()),
+    );
+    family(
+        "api_requests_total",
+        "counter",
+        "Google API requests made.",
+        &one(snapshot.api_requests.to_string()),
+    );
+    family(
+        "api_rate_limited_total",
+        "counter",
+        "Google API responses with status 429.",
+        &one(snapshot.api_rate_limited.to_string()),
+    );
+    family(
+        "embedded_texts_total",
+        "counter",
+        "Texts embedded.",
+        &one(snapshot.embedded_texts.to_string()),
+    );
+    family(
+        "embedding_seconds_total",
+        "counter",
+        "Seconds spent embedding.",
+        &one(format!("{:.3}", snapshot.embedding_seconds)),
+    );
+    family(
+        "queue_depth",
+        "gauge",
+        "Work waiting its turn.",
+        &[Queue::Sync, Queue::Embedding].map(|queue| {
+            let depth = match queue {
+                Queue::Sync => snapshot.sync_queue,
+                Queue::Embedding => snapshot.embedding_queue,
+            };
+            (format!("{{queue=\"{}\"}}", queue.name()), depth.to_string())
+        }),
+    );
+
+    let mut ages = Vec::new();
+    for account in accounts {
+        for (kind, last) in [
+            ("email", account.last_sync_email),
+            ("calendar", account.last_sync_calendar),
+        ] {
+            if let Some(last) = last {
+                let age = (now - last).num_milliseconds().max(0) as f64 / 1000.0;
+                ages.push((
+                    format!(
+                        "{{account=\"{}\",kind=\"{}\"}}",
+                        escape_label(&account.id),
+                        kind
+                    ),
+                    format!("{:.3}", age),
+                ));
+            }
+        }
+    }
+    family(
+        "last_sync_age_seconds",
+        "gauge",
+        "Seconds since an account last finished syncing.",
+        &ages,
+    );
+
+    let label = |table: &TableUsage| format!("{{table=\"{}\"}}", escape_label(&table.name));
+    family(
+        "db_table_bytes",
+        "gauge",
+        "Bytes a database table takes on disk.",
+        &tables
+            .iter()
+            .map(|t| (label(t), t.total().to_string()))
+            .collect::<Vec<_>>(),
+    );
+    family(
+        "db_table_rows",
+        "gauge",
+        "Rows in a database table.",
+        &tables
+            .iter()
+            .map(|t| (label(t), t.rows.to_string()))
+            .collect::<Vec<_>>(),
+    );
+    out
+}
+
+/// Escape a label value (backslash, quote and newline)
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Collect and render every metric
+pub async fn collect(db: &Database, started: Instant) -> Result<String> {
+    let accounts = db.list_accounts().await?;
+    let tables = db.table_usage().await?;
+    Ok(render(&snapshot(started), &accounts, &tables, Utc::now()))
+}
+
+/// Write the metrics to `path` (replaced atomically, for textfile collectors)
+pub async fn write_textfile(db: &Database, started: Instant, path: &Path) -> Result<()> {
+    let metrics = collect(db, started).await?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("prom.tmp");
+    std::fs::write(&tmp, metrics)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Serve the metrics on `http://127.0.0.1:<port>/metrics` until the
+/// listener fails
+pub async fn serve(port: u16, db: std::sync::Arc<Database>, started: Instant) -> Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port)).await?;
+    info!(
+        "Serving metrics on http://127.0.0.1:{}{}",
+        port, METRICS_PATH
+    );
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                warn!("Failed to accept metrics connection: {}", e);
+                continue;
+            }
+        };
+        let db = db.clone();
+        tokio::spawn(async move {
+            if let Err(e) = answer(stream, &db, started).await {
+                debug!("Metrics connection from {} ended: {}", peer, e);
+            }
+        });
+    }
+}
+
+/// Answer one request and close the connection
+async fn answer(mut stream: TcpStream, db: &Database, started: Instant) -> Result<()> {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 || head.len() + n > MAX_REQUEST_HEAD {
+            return Ok(());
+        }
+        head.extend_from_slice(&buf[..n]);
+    }
+    let head = String::from_utf8_lossy(&head);
+    let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+    let method = request_line.next().unwrap_or_default();
+    let target = request_line.next().unwrap_or_default();
+
+    let (status, content_type, body) = if target.split('?').next() != Some(METRICS_PATH) {
+        (
+            "404 Not Found",
+            "text/plain; charset=utf-8",
+            "Not found".to_string(),
+        )
+    } else if method != "GET" && method != "HEAD" {
+        (
+            "405 Method Not Allowed",
+            "text/plain; charset=utf-8",
+            "Read-only endpoint".to_string(),
+        )
+    } else {
+        match collect(db, started).await {
+            Ok(metrics) => ("200 OK", CONTENT_TYPE, metrics),
+            Err(e) => (
+                "503 Service Unavailable",
+                "text/plain; charset=utf-8",
+                format!("Failed to collect metrics: {}", e),
+            ),
+        }
+    };
+
+    let mut response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    )
+    .into_bytes();
+    if method != "HEAD" {
+        response.extend_from_slice(body.as_bytes());
+    }
+    stream.write_all(&response).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_render_prometheus_text() {
+        let now = Utc.with_ymd_and_hms(2024, 6, 3, 9, 0, 0).unwrap();
+        let mut account = Account::new("me@example.com".to_string(), "Me".to_string());
+        account.last_sync_email = Some(now - chrono::Duration::seconds(90));
+        let snapshot = Snapshot {
+            emails_synced: 12,
+            api_rate_limited: 2,
+            sync_queue: 1,
+            ..Default::default()
+        };
+        let tables = [TableUsage {
+            name: "emails".to_string(),
+            rows: 12,
+            data_bytes: 1000,
+            index_bytes: 24,
+            ..Default::default()
+        }];
+
+        let text = render(&snapshot, &[account], &tables, now);
+        assert!(text.contains("# TYPE groundeffect_synced_total counter\n"));
+        assert!(text.contains("groundeffect_synced_total{kind=\"email\"} 12\n"));
+        assert!(text.contains("groundeffect_api_rate_limited_total 2\n"));
+        assert!(text.contains("groundeffect_queue_depth{queue=\"sync\"} 1\n"));
+        assert!(text.contains(
+            "groundeffect_last_sync_age_seconds{account=\"me@example.com\",kind=\"email\"} 90.000\n"
+        ));
+        assert!(!text.contains("kind=\"calendar\""));
+        assert!(text.contains("groundeffect_db_table_bytes{table=\"emails\"} 1024\n"));
+        assert!(text.contains("groundeffect_db_table_rows{table=\"emails\"} 12\n"));
+        for line in text.lines().filter(|l| !l.starts_with('#')) {
+            assert!(line.starts_with("groundeffect_"), "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_escape_label() {
+        assert_eq!(escape_label("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
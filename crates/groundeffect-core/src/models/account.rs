@@ -1,8 +1,12 @@
 //! Account data structures
 
-use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 
+use super::EmailCategory;
+
 /// Status of an account
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -72,6 +76,29 @@ pub struct Account {
     /// Updated by daemon when counting emails to sync
     #[serde(skip_serializing_if = "Option::is_none")]
     pub estimated_total_emails: Option<u64>,
+
+    /// Gmail labels to sync (empty = no include filter)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include_labels: Vec<String>,
+
+    /// Gmail labels to skip during sync
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude_labels: Vec<String>,
+
+    /// Local storage limit in bytes (emails + downloaded attachments)
+    /// When exceeded, attachment downloads and historical backfill pause
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_storage_bytes: Option<u64>,
+
+    /// How long synced mail is kept locally (enforced by the daemon)
+    #[serde(default, skip_serializing_if = "RetentionPolicy::is_empty")]
+    pub retention: RetentionPolicy,
+
+    /// Store only headers and a snippet of each email (no full bodies or
+    /// attachments) to keep disk use minimal on large archival accounts.
+    /// Bodies are fetched from Gmail on demand.
+    #[serde(default)]
+    pub headers_only: bool,
 }
 
 impl Account {
@@ -90,6 +117,11 @@ impl Account {
             oldest_event_synced: None,
             sync_attachments: false,
             estimated_total_emails: None,
+            include_labels: vec![],
+            exclude_labels: vec![],
+            max_storage_bytes: None,
+            retention: RetentionPolicy::default(),
+            headers_only: false,
         }
     }
 
@@ -127,6 +159,249 @@ impl Account {
     pub fn display_id(&self) -> &str {
         self.alias.as_deref().unwrap_or(&self.id)
     }
+
+    /// Oldest date sync fetches mail back to
+    ///
+    /// `sync_email_since` (default 90 days back), but no further than the
+    /// retention limit, so backfill doesn't download mail pruning would drop.
+    pub fn email_sync_target(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let since = self
+            .sync_email_since
+            .unwrap_or_else(|| now - Duration::days(90));
+        match self.retention.email_cutoff(now) {
+            Some(cutoff) => since.max(cutoff),
+            None => since,
+        }
+    }
+
+    /// Gmail search query for the configured label filters (None = sync everything)
+    ///
+    /// Included labels are OR'ed together; excluded labels are negated.
+    pub fn label_filter_query(&self) -> Option<String> {
+        let mut terms = Vec::new();
+
+        match self.include_labels.len() {
+            0 => {}
+            1 => terms.push(gmail_label_term(&self.include_labels[0])),
+            _ => terms.push(format!(
+                "{{{}}}",
+                self.include_labels
+                    .iter()
+                    .map(|l| gmail_label_term(l))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            )),
+        }
+        for label in &self.exclude_labels {
+            terms.push(format!("-{}", gmail_label_term(label)));
+        }
+
+        if terms.is_empty() {
+            None
+        } else {
+            Some(terms.join(" "))
+        }
+    }
+
+    /// Whether local storage exceeds the configured limit
+    pub fn storage_quota_exceeded(&self, usage: &StorageUsage) -> bool {
+        self.max_storage_bytes
+            .map(|max| usage.total() >= max)
+            .unwrap_or(false)
+    }
+
+    /// Actions that would free space or stop growth for this account
+    pub fn retention_suggestions(&self, usage: &StorageUsage) -> Vec<String> {
+        let id = self.display_id();
+        let mut suggestions = Vec::new();
+
+        if self.sync_attachments || usage.attachment_bytes > 0 {
+            suggestions.push(format!(
+                "Stop downloading attachments: groundeffect account configure {} --no-attachments",
+                id
+            ));
+        }
+        if self.exclude_labels.is_empty() {
+            suggestions.push(format!(
+                "Skip bulk mail: groundeffect account configure {} --exclude-label Promotions --exclude-label Social",
+                id
+            ));
+        }
+        suggestions.push(format!(
+            "Raise the limit: groundeffect account configure {} --max-storage <SIZE>",
+            id
+        ));
+        suggestions.push(format!(
+            "Drop already-synced mail so new filters apply to it: groundeffect sync reset --account {} --data-type email --confirm",
+            id
+        ));
+        suggestions
+    }
+}
+
+/// Local storage used by an account
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageUsage {
+    /// Raw size of indexed emails
+    pub email_bytes: u64,
+
+    /// Size of downloaded attachment files
+    pub attachment_bytes: u64,
+}
+
+impl StorageUsage {
+    /// Total bytes used
+    pub fn total(&self) -> u64 {
+        self.email_bytes + self.attachment_bytes
+    }
+}
+
+/// Age limits for locally synced mail
+///
+/// Mail older than `max_age_days` is deleted from the local index, along with
+/// its downloaded attachments, stored raw copy and embedding. Category limits
+/// apply on top, e.g. newsletters after 30 days. Nothing is deleted in Gmail.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    /// Keep mail for this many days
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_age_days: Option<u32>,
+
+    /// Keep mail of these categories for fewer days
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub category_max_age_days: BTreeMap<EmailCategory, u32>,
+}
+
+impl RetentionPolicy {
+    /// No limits configured
+    pub fn is_empty(&self) -> bool {
+        self.max_age_days.is_none() && self.category_max_age_days.is_empty()
+    }
+
+    /// Oldest date kept for all mail
+    pub fn email_cutoff(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.max_age_days
+            .map(|days| now - Duration::days(days as i64))
+    }
+
+    /// Prune rules as (category, cutoff) pairs; None covers all mail
+    ///
+    /// Category limits no shorter than the overall limit are dropped, since
+    /// the overall rule already removes that mail.
+    pub fn cutoffs(&self, now: DateTime<Utc>) -> Vec<(Option<EmailCategory>, DateTime<Utc>)> {
+        let mut cutoffs: Vec<_> = self
+            .email_cutoff(now)
+            .map(|c| (None, c))
+            .into_iter()
+            .collect();
+        for (category, days) in &self.category_max_age_days {
+            if self.max_age_days.is_none_or(|max| *days < max) {
+                cutoffs.push((Some(*category), now - Duration::days(*days as i64)));
+            }
+        }
+        cutoffs
+    }
+
+    /// Short description, e.g. "730 days; newsletter 30 days"
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(days) = self.max_age_days {
+            parts.push(format!("{} days", days));
+        }
+        for (category, days) in &self.category_max_age_days {
+            parts.push(format!("{} {} days", category.as_str(), days));
+        }
+        if parts.is_empty() {
+            "keep everything".to_string()
+        } else {
+            parts.join("; ")
+        }
+    }
+}
+
+/// Parse a retention age like "30d", "12w", "6m", "2y" or "90" into days
+///
+/// A month counts as 30 days and a year as 365. Zero is rejected.
+pub fn parse_retention_age(value: &str) -> Option<u32> {
+    let value = value.trim().to_ascii_lowercase();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u32 = number.parse().ok()?;
+
+    let multiplier = match unit.trim() {
+        "" | "d" | "day" | "days" => 1,
+        "w" | "week" | "weeks" => 7,
+        "m" | "mo" | "month" | "months" => 30,
+        "y" | "year" | "years" => 365,
+        _ => return None,
+    };
+
+    number.checked_mul(multiplier).filter(|days| *days > 0)
+}
+
+/// Parse a per-category rule like "newsletter=30d"
+///
+/// The age is None for "none", which removes the category's rule.
+pub fn parse_category_retention(value: &str) -> Option<(EmailCategory, Option<u32>)> {
+    let (category, age) = value.split_once('=')?;
+    let category = EmailCategory::from_str(category)?;
+    if age.trim().eq_ignore_ascii_case("none") {
+        return Some((category, None));
+    }
+    Some((category, Some(parse_retention_age(age)?)))
+}
+
+/// Parse a human-readable size like "5GB", "500 MB", "1.5G" or "1048576"
+///
+/// Units are binary (1 KB = 1024 bytes), matching how sizes are displayed.
+pub fn parse_storage_size(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number.parse().ok()?;
+
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        "T" | "TB" | "TIB" => 1 << 40,
+        _ => return None,
+    };
+
+    if !number.is_finite() || number < 0.0 {
+        return None;
+    }
+    Some((number * multiplier as f64).round() as u64)
+}
+
+/// Translate a Gmail label name into a search term
+///
+/// System labels and inbox categories use their dedicated operators; user
+/// labels use `label:` with spaces and slashes replaced by hyphens, as Gmail
+/// expects.
+fn gmail_label_term(label: &str) -> String {
+    let normalized = label.trim().to_lowercase();
+    let name = normalized.strip_prefix("category_").unwrap_or(&normalized);
+
+    match name {
+        "inbox" | "spam" | "trash" | "sent" | "draft" | "drafts" | "chats" => {
+            format!("in:{}", name)
+        }
+        "starred" | "important" | "unread" => format!("is:{}", name),
+        "primary" | "personal" | "social" | "promotions" | "updates" | "forums" => {
+            format!("category:{}", name)
+        }
+        _ => format!(
+            "label:{}",
+            name.replace(|c: char| c.is_whitespace() || c == '/', "-")
+        ),
+    }
 }
 
 /// Summary statistics for an account
@@ -188,3 +463,133 @@ pub struct SyncStatus {
     /// Aggregate totals
     pub totals: TotalStats,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account_with_labels(include: &[&str], exclude: &[&str]) -> Account {
+        let mut account = Account::new("user@gmail.com".to_string(), "User".to_string());
+        account.include_labels = include.iter().map(|s| s.to_string()).collect();
+        account.exclude_labels = exclude.iter().map(|s| s.to_string()).collect();
+        account
+    }
+
+    #[test]
+    fn test_label_filter_query_empty() {
+        assert_eq!(account_with_labels(&[], &[]).label_filter_query(), None);
+    }
+
+    #[test]
+    fn test_label_filter_query_include_and_exclude() {
+        let account = account_with_labels(&["INBOX", "Work/Projects"], &["Promotions", "SPAM"]);
+        assert_eq!(
+            account.label_filter_query().as_deref(),
+            Some("{in:inbox label:work-projects} -category:promotions -in:spam")
+        );
+    }
+
+    #[test]
+    fn test_label_filter_query_single_include() {
+        let account = account_with_labels(&["Client Work"], &["CATEGORY_SOCIAL"]);
+        assert_eq!(
+            account.label_filter_query().as_deref(),
+            Some("label:client-work -category:social")
+        );
+    }
+
+    #[test]
+    fn test_parse_storage_size() {
+        assert_eq!(parse_storage_size("5GB"), Some(5 * 1024 * 1024 * 1024));
+        assert_eq!(parse_storage_size("500 mb"), Some(500 * 1024 * 1024));
+        assert_eq!(parse_storage_size("1.5G"), Some(1536 * 1024 * 1024));
+        assert_eq!(parse_storage_size("4096"), Some(4096));
+        assert_eq!(parse_storage_size("5 parsecs"), None);
+        assert_eq!(parse_storage_size("GB"), None);
+    }
+
+    #[test]
+    fn test_storage_quota() {
+        let mut account = account_with_labels(&[], &[]);
+        let usage = StorageUsage {
+            email_bytes: 600,
+            attachment_bytes: 500,
+        };
+        assert!(!account.storage_quota_exceeded(&usage));
+
+        account.max_storage_bytes = Some(1000);
+        assert!(account.storage_quota_exceeded(&usage));
+        assert!(account
+            .retention_suggestions(&usage)
+            .iter()
+            .any(|s| s.contains("--no-attachments")));
+
+        account.max_storage_bytes = Some(2000);
+        assert!(!account.storage_quota_exceeded(&usage));
+    }
+
+    #[test]
+    fn test_parse_retention_age() {
+        assert_eq!(parse_retention_age("30d"), Some(30));
+        assert_eq!(parse_retention_age("12w"), Some(84));
+        assert_eq!(parse_retention_age("6m"), Some(180));
+        assert_eq!(parse_retention_age("2Y"), Some(730));
+        assert_eq!(parse_retention_age("90"), Some(90));
+        assert_eq!(parse_retention_age("0d"), None);
+        assert_eq!(parse_retention_age("2 fortnights"), None);
+        assert_eq!(parse_retention_age("y"), None);
+
+        assert_eq!(
+            parse_category_retention("promotions=30d"),
+            Some((EmailCategory::Newsletter, Some(30)))
+        );
+        assert_eq!(
+            parse_category_retention("notification=none"),
+            Some((EmailCategory::Notification, None))
+        );
+        assert_eq!(parse_category_retention("newsletter"), None);
+        assert_eq!(parse_category_retention("spam=30d"), None);
+    }
+
+    #[test]
+    fn test_retention_cutoffs() {
+        let now = Utc::now();
+        let mut policy = RetentionPolicy::default();
+        assert!(policy.is_empty());
+        assert!(policy.cutoffs(now).is_empty());
+
+        policy.max_age_days = Some(730);
+        policy
+            .category_max_age_days
+            .insert(EmailCategory::Newsletter, 30);
+        // Longer than the overall limit, so it never prunes anything extra
+        policy
+            .category_max_age_days
+            .insert(EmailCategory::Personal, 1000);
+
+        assert_eq!(
+            policy.cutoffs(now),
+            vec![
+                (None, now - Duration::days(730)),
+                (Some(EmailCategory::Newsletter), now - Duration::days(30)),
+            ]
+        );
+        assert_eq!(
+            policy.describe(),
+            "730 days; personal 1000 days; newsletter 30 days"
+        );
+
+        let json = serde_json::to_string(&policy).unwrap();
+        assert_eq!(
+            serde_json::from_str::<RetentionPolicy>(&json).unwrap(),
+            policy
+        );
+
+        // Backfill stops at the retention limit
+        let mut account = account_with_labels(&[], &[]);
+        account.sync_email_since = Some(now - Duration::days(3650));
+        assert_eq!(account.email_sync_target(now), now - Duration::days(3650));
+        account.retention = policy;
+        assert_eq!(account.email_sync_target(now), now - Duration::days(730));
+    }
+}
@@ -0,0 +1,80 @@
+//! Activity items collapsed from SaaS notification emails
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Tool that sent a notification email
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ActivitySource {
+    GitHub,
+    Jira,
+    Asana,
+}
+
+impl ActivitySource {
+    /// All built-in sources
+    pub const ALL: [ActivitySource; 3] = [Self::GitHub, Self::Jira, Self::Asana];
+
+    /// Stable lowercase name (stored in the database)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::GitHub => "github",
+            Self::Jira => "jira",
+            Self::Asana => "asana",
+        }
+    }
+
+    /// Parse a source name (case-insensitive)
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "github" => Some(Self::GitHub),
+            "jira" => Some(Self::Jira),
+            "asana" => Some(Self::Asana),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ActivitySource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A structured activity item parsed from a notification email
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActivityItem {
+    /// Same as the ID of the email it was parsed from
+    pub id: String,
+
+    /// Account identifier (email address)
+    pub account_id: String,
+
+    /// Tool that sent the notification
+    pub source: ActivitySource,
+
+    /// Repository ("owner/repo"), Jira project key, or Asana project ID
+    pub project: String,
+
+    /// Issue or PR number ("#123"), Jira issue key, or Asana task ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item: Option<String>,
+
+    /// Issue, PR, or task title
+    pub title: String,
+
+    /// What happened (e.g. "commented", "merged", "status changed")
+    pub action: String,
+
+    /// Who did it, when the notification names them
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actor: Option<String>,
+
+    /// Link to the item in the tool
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    /// When the notification was sent
+    pub date: DateTime<Utc>,
+}
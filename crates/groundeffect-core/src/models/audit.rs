@@ -0,0 +1,142 @@
+//! Audit log of actions that change mail, calendars, or accounts
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Longest string parameter kept in an audit entry (email bodies are cut here)
+const MAX_PARAM_CHARS: usize = 1000;
+
+/// Who performed an audited action
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditActor {
+    /// The groundeffect command line
+    Cli,
+    /// An MCP client (an AI assistant)
+    Mcp,
+}
+
+impl AuditActor {
+    /// Stable lowercase name (stored in the database)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Cli => "cli",
+            Self::Mcp => "mcp",
+        }
+    }
+
+    /// Parse an actor name (case-insensitive)
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "cli" => Some(Self::Cli),
+            "mcp" => Some(Self::Mcp),
+            _ => None,
+        }
+    }
+}
+
+/// One recorded action
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Unique entry ID
+    pub id: String,
+
+    /// When the action ran
+    pub timestamp: DateTime<Utc>,
+
+    /// CLI or MCP
+    pub actor: AuditActor,
+
+    /// What was done ("send_email", "create_event", "sync_reset", ...)
+    pub action: String,
+
+    /// Account the action applied to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<String>,
+
+    /// Parameters the action was called with (long strings truncated)
+    pub params: Value,
+
+    /// Whether the action completed
+    pub success: bool,
+
+    /// Error message for failed actions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl AuditEntry {
+    /// A completed action
+    pub fn new(actor: AuditActor, action: &str, account_id: Option<&str>, params: Value) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            timestamp: Utc::now(),
+            actor,
+            action: action.to_string(),
+            account_id: account_id.map(str::to_string),
+            params: truncate_params(params),
+            success: true,
+            error: None,
+        }
+    }
+
+    /// Mark the action as failed
+    pub fn failed(mut self, error: impl std::fmt::Display) -> Self {
+        self.success = false;
+        self.error = Some(error.to_string());
+        self
+    }
+}
+
+/// Cut long strings so bodies and attachments don't bloat the log
+fn truncate_params(value: Value) -> Value {
+    match value {
+        Value::String(s) if s.chars().count() > MAX_PARAM_CHARS => {
+            let cut: String = s.chars().take(MAX_PARAM_CHARS).collect();
+            Value::String(format!("{}… [truncated]", cut))
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(truncate_params).collect()),
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| (key, truncate_params(value)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_long_params_are_truncated() {
+        let body = "x".repeat(MAX_PARAM_CHARS + 50);
+        let entry = AuditEntry::new(
+            AuditActor::Mcp,
+            "send_email",
+            Some("me@example.com"),
+            serde_json::json!({"to": ["a@example.com"], "body": body, "confirm": true}),
+        );
+
+        let body = entry.params["body"].as_str().unwrap();
+        assert!(body.ends_with("… [truncated]"));
+        assert_eq!(
+            body.chars().count(),
+            MAX_PARAM_CHARS + "… [truncated]".chars().count()
+        );
+        assert_eq!(entry.params["to"][0], "a@example.com");
+        assert_eq!(entry.params["confirm"], true);
+        assert!(entry.success);
+    }
+
+    #[test]
+    fn test_failed_entry() {
+        let entry = AuditEntry::new(AuditActor::Cli, "sync_reset", None, Value::Null)
+            .failed("Account not found");
+        assert!(!entry.success);
+        assert_eq!(entry.error.as_deref(), Some("Account not found"));
+        assert_eq!(AuditActor::from_str("MCP"), Some(AuditActor::Mcp));
+    }
+}
@@ -0,0 +1,89 @@
+//! Vacation responder and out-of-office state, cached per account
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Gmail vacation responder settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VacationResponder {
+    /// Whether auto-replies are turned on
+    pub enabled: bool,
+
+    /// First moment auto-replies are sent (None: from when it was turned on)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<DateTime<Utc>>,
+
+    /// Last moment auto-replies are sent (None: until turned off)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<DateTime<Utc>>,
+
+    /// Auto-reply subject
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+
+    /// Auto-reply text
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+
+    /// Only people in the user's contacts get the auto-reply
+    pub contacts_only: bool,
+
+    /// Only people in the user's domain get the auto-reply
+    pub domain_only: bool,
+}
+
+impl VacationResponder {
+    /// Whether auto-replies are being sent at `at`
+    pub fn is_active_at(&self, at: DateTime<Utc>) -> bool {
+        self.enabled
+            && self.start.is_none_or(|start| start <= at)
+            && self.end.is_none_or(|end| at < end)
+    }
+}
+
+/// An out-of-office block on the primary calendar
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutOfOffice {
+    /// Google Calendar event ID
+    pub event_id: String,
+
+    /// Event title ("Out of office", "Vacation")
+    pub summary: String,
+
+    /// Start of the block
+    pub start: DateTime<Utc>,
+
+    /// End of the block
+    pub end: DateTime<Utc>,
+
+    /// Calendar declines new invitations during the block
+    pub auto_decline: bool,
+
+    /// Message sent with declined invitations
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decline_message: Option<String>,
+}
+
+/// Availability state fetched from Gmail and Google Calendar for one account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AvailabilityState {
+    /// Account ID (email address)
+    pub account_id: String,
+
+    /// When the state was fetched
+    pub fetched_at: DateTime<Utc>,
+
+    /// Vacation responder settings (None if Gmail couldn't be asked)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vacation: Option<VacationResponder>,
+
+    /// Upcoming out-of-office blocks, soonest first
+    pub out_of_office: Vec<OutOfOffice>,
+}
+
+impl AvailabilityState {
+    /// Whether the state is younger than `max_age_secs`
+    pub fn is_fresh(&self, max_age_secs: u64) -> bool {
+        (Utc::now() - self.fetched_at).num_seconds() < max_age_secs as i64
+    }
+}
@@ -1,7 +1,53 @@
 //! Calendar event data structures
 
 use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Hosts of meeting links found in an event's location or description, and
+/// the product they belong to
+const MEETING_HOSTS: &[(&str, &str)] = &[
+    ("meet.google.com", "Google Meet"),
+    ("zoom.us", "Zoom"),
+    ("teams.microsoft.com", "Microsoft Teams"),
+    ("teams.live.com", "Microsoft Teams"),
+    ("webex.com", "Webex"),
+    ("whereby.com", "Whereby"),
+    ("around.co", "Around"),
+];
+
+/// Google Calendar's event colors: `colorId` and the name the web UI uses
+pub const EVENT_COLORS: &[(&str, &str)] = &[
+    ("1", "lavender"),
+    ("2", "sage"),
+    ("3", "grape"),
+    ("4", "flamingo"),
+    ("5", "banana"),
+    ("6", "tangerine"),
+    ("7", "peacock"),
+    ("8", "graphite"),
+    ("9", "blueberry"),
+    ("10", "basil"),
+    ("11", "tomato"),
+];
+
+/// `colorId` for a color name or ID ("tomato" or "11")
+pub fn event_color_id(value: &str) -> Option<&'static str> {
+    let value = value.trim().to_lowercase();
+    EVENT_COLORS
+        .iter()
+        .find(|(id, name)| *id == value || *name == value)
+        .map(|(id, _)| *id)
+}
+
+/// Name of a `colorId` ("11" is "tomato")
+pub fn event_color_name(id: &str) -> Option<&'static str> {
+    EVENT_COLORS
+        .iter()
+        .find(|(color_id, _)| *color_id == id)
+        .map(|(_, name)| *name)
+}
 
 /// Event time - can be a specific datetime or an all-day date
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -32,6 +78,14 @@ impl EventTime {
             EventTime::Date(d) => *d,
         }
     }
+
+    /// RFC 3339 in `tz` (all-day dates are returned as they are)
+    pub fn to_local_string(&self, tz: Tz) -> String {
+        match self {
+            EventTime::DateTime(dt) => dt.with_timezone(&tz).to_rfc3339(),
+            EventTime::Date(d) => d.to_string(),
+        }
+    }
 }
 
 /// Event status
@@ -65,6 +119,72 @@ impl Default for Transparency {
     }
 }
 
+impl Transparency {
+    /// Parse "busy"/"free" (as Google Calendar's "Show as" calls them) or
+    /// "opaque"/"transparent"
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "busy" | "opaque" => Some(Self::Opaque),
+            "free" | "transparent" => Some(Self::Transparent),
+            _ => None,
+        }
+    }
+
+    /// Google Calendar API value
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Opaque => "opaque",
+            Self::Transparent => "transparent",
+        }
+    }
+
+    /// "busy" or "free"
+    pub fn show_as(&self) -> &'static str {
+        match self {
+            Self::Opaque => "busy",
+            Self::Transparent => "free",
+        }
+    }
+}
+
+/// Who can see an event's details
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Visibility {
+    /// The calendar's default
+    #[default]
+    Default,
+    /// Details visible to everyone who can see the calendar
+    Public,
+    /// Details visible only to attendees
+    Private,
+    /// Same as private (kept for older events)
+    Confidential,
+}
+
+impl Visibility {
+    /// Parse a Google Calendar API visibility
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "default" => Some(Self::Default),
+            "public" => Some(Self::Public),
+            "private" => Some(Self::Private),
+            "confidential" => Some(Self::Confidential),
+            _ => None,
+        }
+    }
+
+    /// Google Calendar API value
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::Public => "public",
+            Self::Private => "private",
+            Self::Confidential => "confidential",
+        }
+    }
+}
+
 /// Event attendee
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Attendee {
@@ -84,6 +204,16 @@ pub struct Attendee {
     pub optional: bool,
 }
 
+impl Attendee {
+    /// "Name <email>", or just the email without a name
+    pub fn to_string_full(&self) -> String {
+        match &self.name {
+            Some(name) => format!("{} <{}>", name, self.email),
+            None => self.email.clone(),
+        }
+    }
+}
+
 /// Attendee response status
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -112,6 +242,97 @@ pub enum ReminderMethod {
     Email,
 }
 
+/// Video conference attached to an event (Google Meet, Zoom, ...)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Conference {
+    /// Conferencing product, e.g. "Google Meet" or "Zoom Meeting"
+    pub provider: String,
+
+    /// Link to join the call
+    pub join_url: String,
+
+    /// Meeting ID or code
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meeting_code: Option<String>,
+
+    /// Dial-in number
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub phone: Option<String>,
+
+    /// Passcode or PIN for joining
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub passcode: Option<String>,
+}
+
+impl Conference {
+    /// The conference on a Google Calendar API event: its `conferenceData`,
+    /// then `hangoutLink`, then a meeting link in the location or
+    /// description (third-party invites often only paste the link)
+    pub fn from_google(json: &Value) -> Option<Self> {
+        let entry_points = json["conferenceData"]["entryPoints"].as_array();
+        let entry = |kind: &str| {
+            entry_points.and_then(|points| points.iter().find(|p| p["entryPointType"] == kind))
+        };
+
+        if let Some(video) = entry("video").filter(|v| v["uri"].is_string()) {
+            let phone = entry("phone");
+            let text = |point: Option<&Value>, field: &str| {
+                point
+                    .and_then(|p| p[field].as_str())
+                    .filter(|s| !s.is_empty())
+                    .map(str::to_string)
+            };
+            return Some(Self {
+                provider: json["conferenceData"]["conferenceSolution"]["name"]
+                    .as_str()
+                    .unwrap_or("Video call")
+                    .to_string(),
+                join_url: video["uri"].as_str().unwrap_or_default().to_string(),
+                meeting_code: text(Some(video), "meetingCode")
+                    .or_else(|| text(Some(&json["conferenceData"]), "conferenceId")),
+                phone: text(phone, "uri").map(|uri| uri.trim_start_matches("tel:").to_string()),
+                passcode: text(Some(video), "passcode")
+                    .or_else(|| text(phone, "pin"))
+                    .or_else(|| text(phone, "passcode")),
+            });
+        }
+
+        if let Some(link) = json["hangoutLink"].as_str() {
+            return Some(Self::from_link(link, "Google Meet"));
+        }
+
+        [&json["location"], &json["description"]]
+            .into_iter()
+            .filter_map(Value::as_str)
+            .find_map(meeting_link)
+            .map(|(link, provider)| Self::from_link(&link, provider))
+    }
+
+    fn from_link(link: &str, provider: &str) -> Self {
+        Self {
+            provider: provider.to_string(),
+            join_url: link.to_string(),
+            meeting_code: None,
+            phone: None,
+            passcode: None,
+        }
+    }
+}
+
+/// First link to a known meeting host in `text` (which may be HTML)
+fn meeting_link(text: &str) -> Option<(String, &'static str)> {
+    text.split(|c: char| c.is_whitespace() || matches!(c, '<' | '>' | '"' | '\'' | '(' | ')'))
+        .filter(|word| word.starts_with("https://"))
+        .map(|word| word.trim_end_matches(['.', ',', ';', ':', '!', '?']))
+        .find_map(|url| {
+            let host = url["https://".len()..].split(['/', '?', '#']).next()?;
+            MEETING_HOSTS
+                .iter()
+                .find(|(domain, _)| host == *domain || host.ends_with(&format!(".{}", domain)))
+                .map(|(_, provider)| (url.to_string(), *provider))
+        })
+}
+
 /// A calendar event
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CalendarEvent {
@@ -187,11 +408,24 @@ pub struct CalendarEvent {
     #[serde(default)]
     pub transparency: Transparency,
 
+    /// Who can see the event's details
+    #[serde(default)]
+    pub visibility: Visibility,
+
+    /// Event color (a `colorId`, see [`EVENT_COLORS`]; None: the calendar's)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color_id: Option<String>,
+
     // === Reminders ===
     /// Event reminders
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub reminders: Vec<Reminder>,
 
+    // === Conferencing ===
+    /// Video call to join (Google Meet, or a Zoom/Teams link in the invite)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conference: Option<Conference>,
+
     // === Search ===
     /// Embedding vector (768 dimensions)
     #[serde(skip)]
@@ -206,6 +440,24 @@ pub struct CalendarEvent {
 }
 
 impl CalendarEvent {
+    /// The event in Google Calendar on the web, signed in as its account
+    ///
+    /// This is the API's `htmlLink`: the `eid` is the event ID and the
+    /// calendar's ID (the account for the primary calendar), base64-encoded.
+    pub fn web_link(&self) -> String {
+        use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+        let calendar = match self.calendar_id.as_str() {
+            "primary" => &self.account_id,
+            id => id,
+        };
+        format!(
+            "https://www.google.com/calendar/event?eid={}&authuser={}",
+            URL_SAFE_NO_PAD.encode(format!("{} {}", self.google_event_id, calendar)),
+            self.account_id
+        )
+    }
+
     /// Get searchable text for embedding
     pub fn searchable_text(&self) -> String {
         let mut text = String::new();
@@ -229,17 +481,20 @@ impl CalendarEvent {
             text.push_str(". ");
         }
 
-        // Attendees
+        // Organizer
+        if let Some(organizer) = &self.organizer {
+            text.push_str("Organizer: ");
+            text.push_str(&organizer.to_string_full());
+            text.push_str(". ");
+        }
+
+        // Attendees, by name and address so a search for either finds the event
         if !self.attendees.is_empty() {
             text.push_str("Attendees: ");
-            for attendee in &self.attendees {
-                if let Some(name) = &attendee.name {
-                    text.push_str(name);
-                } else {
-                    text.push_str(&attendee.email);
-                }
-                text.push_str(", ");
-            }
+            let attendees: Vec<String> =
+                self.attendees.iter().map(|a| a.to_string_full()).collect();
+            text.push_str(&attendees.join(", "));
+            text.push_str(". ");
         }
 
         text
@@ -304,6 +559,35 @@ impl CalendarEvent {
     }
 }
 
+/// Filters for listing events
+#[derive(Debug, Clone, Default)]
+pub struct EventFilter {
+    /// Only events shown as busy (opaque) or free (transparent)
+    pub show_as: Option<Transparency>,
+
+    /// Only events with this visibility
+    pub visibility: Option<Visibility>,
+
+    /// Only events with this `colorId`
+    pub color_id: Option<String>,
+}
+
+impl EventFilter {
+    /// Whether `event` passes every filter that's set
+    pub fn matches(&self, event: &CalendarEvent) -> bool {
+        self.show_as
+            .as_ref()
+            .is_none_or(|show_as| event.transparency == *show_as)
+            && self
+                .visibility
+                .is_none_or(|visibility| event.visibility == visibility)
+            && self
+                .color_id
+                .as_ref()
+                .is_none_or(|color| event.color_id.as_ref() == Some(color))
+    }
+}
+
 /// Calendar event search result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EventSearchResult {
@@ -334,6 +618,9 @@ pub struct EventSummary {
     pub location: Option<String>,
     pub calendar_id: String,
     pub status: EventStatus,
+    /// The event in Google Calendar on the web
+    #[serde(default)]
+    pub web_link: String,
 }
 
 impl From<&CalendarEvent> for EventSummary {
@@ -350,6 +637,7 @@ impl From<&CalendarEvent> for EventSummary {
             location: event.location.clone(),
             calendar_id: event.calendar_id.clone(),
             status: event.status.clone(),
+            web_link: event.web_link(),
         }
     }
 }
@@ -416,3 +704,129 @@ pub struct Calendar {
     /// Calendar timezone
     pub timezone: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn event(
+        transparency: Transparency,
+        visibility: Visibility,
+        color: Option<&str>,
+    ) -> CalendarEvent {
+        CalendarEvent {
+            id: "evt".to_string(),
+            account_id: "me@example.com".to_string(),
+            account_alias: None,
+            google_event_id: "g1".to_string(),
+            ical_uid: "g1".to_string(),
+            etag: String::new(),
+            summary: "Focus time".to_string(),
+            description: None,
+            location: None,
+            start: EventTime::Date(NaiveDate::from_ymd_opt(2025, 3, 17).unwrap()),
+            end: EventTime::Date(NaiveDate::from_ymd_opt(2025, 3, 18).unwrap()),
+            timezone: "UTC".to_string(),
+            all_day: true,
+            recurrence_rule: None,
+            recurrence_id: None,
+            organizer: None,
+            attendees: Vec::new(),
+            status: EventStatus::Confirmed,
+            transparency,
+            visibility,
+            color_id: color.map(str::to_string),
+            reminders: Vec::new(),
+            conference: None,
+            embedding: None,
+            calendar_id: "primary".to_string(),
+            synced_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_event_filter() {
+        assert_eq!(event_color_id("Tomato"), Some("11"));
+        assert_eq!(event_color_id("3"), Some("3"));
+        assert_eq!(event_color_id("mauve"), None);
+        assert_eq!(event_color_name("7"), Some("peacock"));
+        assert_eq!(Transparency::parse("free"), Some(Transparency::Transparent));
+        assert_eq!(Visibility::parse("Private"), Some(Visibility::Private));
+
+        let placeholder = event(Transparency::Transparent, Visibility::Default, None);
+        let private = event(Transparency::Opaque, Visibility::Private, Some("11"));
+
+        let busy = EventFilter {
+            show_as: Some(Transparency::Opaque),
+            ..EventFilter::default()
+        };
+        assert!(!busy.matches(&placeholder));
+        assert!(busy.matches(&private));
+
+        let tomato = EventFilter {
+            color_id: Some("11".to_string()),
+            visibility: Some(Visibility::Private),
+            ..EventFilter::default()
+        };
+        assert!(tomato.matches(&private));
+        assert!(!tomato.matches(&placeholder));
+        assert!(EventFilter::default().matches(&placeholder));
+    }
+
+    #[test]
+    fn test_web_link() {
+        let mut event = event(Transparency::Opaque, Visibility::Default, None);
+        assert_eq!(
+            event.web_link(),
+            "https://www.google.com/calendar/event?eid=ZzEgbWVAZXhhbXBsZS5jb20&authuser=me@example.com"
+        );
+        event.calendar_id = "team@group.calendar.google.com".to_string();
+        assert!(event
+            .web_link()
+            .contains("eid=ZzEgdGVhbUBncm91cC5jYWxlbmRhci5nb29nbGUuY29t&"));
+    }
+
+    #[test]
+    fn test_conference_from_google() {
+        let meet = json!({
+            "hangoutLink": "https://meet.google.com/abc-defg-hij",
+            "conferenceData": {
+                "conferenceId": "abc-defg-hij",
+                "conferenceSolution": {"name": "Google Meet"},
+                "entryPoints": [
+                    {"entryPointType": "video", "uri": "https://meet.google.com/abc-defg-hij"},
+                    {"entryPointType": "phone", "uri": "tel:+1-555-010-9999", "pin": "123456"}
+                ]
+            }
+        });
+        let conference = Conference::from_google(&meet).unwrap();
+        assert_eq!(conference.provider, "Google Meet");
+        assert_eq!(conference.join_url, "https://meet.google.com/abc-defg-hij");
+        assert_eq!(conference.meeting_code.as_deref(), Some("abc-defg-hij"));
+        assert_eq!(conference.phone.as_deref(), Some("+1-555-010-9999"));
+        assert_eq!(conference.passcode.as_deref(), Some("123456"));
+
+        // Only a hangoutLink
+        let conference =
+            Conference::from_google(&json!({"hangoutLink": "https://meet.google.com/xyz"}))
+                .unwrap();
+        assert_eq!(conference.provider, "Google Meet");
+
+        // A Zoom link pasted into an HTML description
+        let zoom = json!({
+            "location": "Room 4",
+            "description": "Join: <a href=\"https://us02web.zoom.us/j/8123456789?pwd=abc\">zoom</a>."
+        });
+        let conference = Conference::from_google(&zoom).unwrap();
+        assert_eq!(conference.provider, "Zoom");
+        assert_eq!(
+            conference.join_url,
+            "https://us02web.zoom.us/j/8123456789?pwd=abc"
+        );
+
+        // Other links don't count
+        let plain = json!({"description": "Agenda: https://docs.google.com/document/d/1 and https://notzoom.us/j/1"});
+        assert_eq!(Conference::from_google(&plain), None);
+    }
+}
@@ -0,0 +1,113 @@
+//! Workspace directory data structures
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A person from the Google Workspace directory
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DirectoryPerson {
+    /// Display name (e.g. "Priya Patel")
+    pub name: String,
+
+    /// Primary email address
+    pub email: String,
+
+    /// Department from the directory profile
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub department: Option<String>,
+
+    /// Job title from the directory profile
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    /// Profile photo URL (None for the default placeholder)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub photo_url: Option<String>,
+}
+
+impl DirectoryPerson {
+    /// Check if the department or title contains `team` (case-insensitive)
+    pub fn in_team(&self, team: &str) -> bool {
+        let team = team.to_lowercase();
+        [&self.department, &self.title]
+            .into_iter()
+            .flatten()
+            .any(|value| value.to_lowercase().contains(&team))
+    }
+
+    /// "Name <email>" with the department, for listing candidates
+    pub fn describe(&self) -> String {
+        match &self.department {
+            Some(department) => format!("{} <{}> ({})", self.name, self.email, department),
+            None => format!("{} <{}>", self.name, self.email),
+        }
+    }
+}
+
+/// Directory search results for one query
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectoryCacheEntry {
+    /// When the query was sent to the directory
+    pub fetched_at: DateTime<Utc>,
+
+    /// Matching people
+    pub people: Vec<DirectoryPerson>,
+}
+
+/// Directory lookups for one account, cached on disk by query
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DirectoryCache {
+    /// Lowercased query -> results
+    pub queries: HashMap<String, DirectoryCacheEntry>,
+}
+
+impl DirectoryCache {
+    /// Cached results for a query if younger than `max_age_secs`
+    pub fn get(&self, query: &str, max_age_secs: u64) -> Option<&[DirectoryPerson]> {
+        self.queries
+            .get(&query.to_lowercase())
+            .filter(|entry| (Utc::now() - entry.fetched_at).num_seconds() < max_age_secs as i64)
+            .map(|entry| entry.people.as_slice())
+    }
+
+    /// Store results for a query
+    pub fn insert(&mut self, query: &str, people: Vec<DirectoryPerson>) {
+        self.queries.insert(
+            query.to_lowercase(),
+            DirectoryCacheEntry {
+                fetched_at: Utc::now(),
+                people,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_lookup_is_case_insensitive_and_expires() {
+        let mut cache = DirectoryCache::default();
+        cache.insert(
+            "Priya",
+            vec![DirectoryPerson {
+                name: "Priya Patel".to_string(),
+                email: "priya@corp.com".to_string(),
+                department: Some("Product Design".to_string()),
+                title: None,
+                photo_url: None,
+            }],
+        );
+
+        let people = cache.get("priya", 60).unwrap();
+        assert!(people[0].in_team("design"));
+        assert!(!people[0].in_team("sales"));
+
+        cache.queries.get_mut("priya").unwrap().fetched_at =
+            Utc::now() - chrono::Duration::hours(2);
+        assert!(cache.get("priya", 3600).is_none());
+    }
+}
@@ -2,13 +2,80 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use tracing::warn;
 
-use super::Attachment;
+use super::{ActivitySource, Attachment};
+use crate::render;
+use crate::search::ScoreComponents;
 
 const SEARCHABLE_BODY_MAX_CHARS: usize = 16_000;
 const SEARCHABLE_BODY_TAIL_CHARS: usize = 2_000;
-const HTML2TEXT_FALLBACK_WIDTH: usize = 100;
+
+/// Result of an SPF, DKIM or DMARC check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthVerdict {
+    Pass,
+    Fail,
+    Softfail,
+    Neutral,
+    None,
+    Temperror,
+    Permerror,
+    Policy,
+}
+
+impl AuthVerdict {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "pass" => Some(Self::Pass),
+            "fail" | "hardfail" => Some(Self::Fail),
+            "softfail" => Some(Self::Softfail),
+            "neutral" => Some(Self::Neutral),
+            "none" => Some(Self::None),
+            "temperror" => Some(Self::Temperror),
+            "permerror" => Some(Self::Permerror),
+            "policy" => Some(Self::Policy),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pass => "pass",
+            Self::Fail => "fail",
+            Self::Softfail => "softfail",
+            Self::Neutral => "neutral",
+            Self::None => "none",
+            Self::Temperror => "temperror",
+            Self::Permerror => "permerror",
+            Self::Policy => "policy",
+        }
+    }
+
+    /// The check says the sender isn't who they claim to be
+    pub fn is_failure(&self) -> bool {
+        matches!(self, Self::Fail | Self::Softfail)
+    }
+}
+
+/// Sender authentication of a received email (see [`crate::security`])
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmailAuth {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spf: Option<AuthVerdict>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dkim: Option<AuthVerdict>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dmarc: Option<AuthVerdict>,
+    /// Envelope sender (Return-Path), where bounces go; can differ from From
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub return_path: Option<String>,
+    /// Failed checks or a spoofed display name
+    pub suspicious: bool,
+    /// Why the email is suspicious
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
 
 /// Email address with optional display name
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -56,6 +123,59 @@ impl std::fmt::Display for Address {
     }
 }
 
+/// Coarse classification of who (or what) sent an email
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmailCategory {
+    /// Written by a real person
+    Personal,
+    /// Mailing lists, newsletters, and marketing mail
+    Newsletter,
+    /// Automated notifications and alerts from services
+    Notification,
+    /// Receipts, order confirmations, invoices, and account security mail
+    Transactional,
+}
+
+impl EmailCategory {
+    /// All categories, in display order
+    pub const ALL: [EmailCategory; 4] = [
+        EmailCategory::Personal,
+        EmailCategory::Newsletter,
+        EmailCategory::Notification,
+        EmailCategory::Transactional,
+    ];
+
+    /// Storage/CLI representation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EmailCategory::Personal => "personal",
+            EmailCategory::Newsletter => "newsletter",
+            EmailCategory::Notification => "notification",
+            EmailCategory::Transactional => "transactional",
+        }
+    }
+
+    /// Parse from string
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "personal" | "human" => Some(EmailCategory::Personal),
+            "newsletter" | "newsletters" | "list" | "bulk" | "promotions" => {
+                Some(EmailCategory::Newsletter)
+            }
+            "notification" | "notifications" => Some(EmailCategory::Notification),
+            "transactional" | "receipt" | "receipts" => Some(EmailCategory::Transactional),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for EmailCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 /// An email message
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Email {
@@ -99,10 +219,27 @@ pub struct Email {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub labels: Vec<String>,
 
+    /// Written by the account owner: synced from the Sent folder, or from
+    /// the account's address or a send-as identity
+    #[serde(default)]
+    pub is_sent: bool,
+
     /// IMAP flags (Seen, Flagged, etc.)
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub flags: Vec<String>,
 
+    /// Sender category (None for emails synced before classification existed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<EmailCategory>,
+
+    /// Set when the email was collapsed into an activity item
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub activity_source: Option<ActivitySource>,
+
+    /// Tags added by local tagging rules (see `[[tagging.rules]]`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+
     // === Headers ===
     /// From address
     pub from: Address,
@@ -142,6 +279,12 @@ pub struct Email {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub attachments: Vec<Attachment>,
 
+    // === Security ===
+    /// SPF/DKIM/DMARC verdicts from Gmail's Authentication-Results (received
+    /// mail synced since they were recorded)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<EmailAuth>,
+
     // === Search ===
     /// Embedding vector (768 dimensions)
     #[serde(skip)]
@@ -153,52 +296,24 @@ pub struct Email {
 
     /// Raw message size in bytes
     pub raw_size: u64,
+
+    /// Synced from a headers-only account: no body beyond the snippet and no
+    /// attachments were stored
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub headers_only: bool,
 }
 
 impl Email {
-    fn sanitize_body_text(text: &str) -> String {
-        text.chars()
-            .filter(|c| !matches!(c, '\u{0000}'..='\u{0008}' | '\u{000B}' | '\u{000C}' | '\u{000E}'..='\u{001F}' | '\u{007F}'))
-            .collect()
-    }
-
-    fn html_to_markdown_with_fallback(html: &str) -> String {
-        let sanitized_html = Self::sanitize_body_text(html);
-        if sanitized_html.trim().is_empty() {
-            return String::new();
-        }
-
-        match html_to_markdown_rs::convert(&sanitized_html, None) {
-            Ok(markdown) => {
-                let sanitized_markdown = Self::sanitize_body_text(&markdown);
-                if !sanitized_markdown.trim().is_empty() {
-                    return sanitized_markdown;
-                }
-            }
-            Err(err) => {
-                warn!(
-                    "HTML->Markdown conversion failed ({} chars), falling back to html2text: {}",
-                    sanitized_html.chars().count(),
-                    err
-                );
-            }
-        }
-
-        let fallback = html2text::from_read(sanitized_html.as_bytes(), HTML2TEXT_FALLBACK_WIDTH)
-            .unwrap_or_default();
-        Self::sanitize_body_text(&fallback)
-    }
-
     /// Resolve the canonical body text used for embeddings, BM25, and user display.
     /// Priority: plaintext body first, otherwise HTML converted to markdown.
     pub fn body_for_indexing_and_display(body_plain: &str, body_html: Option<&str>) -> String {
-        let plain = Self::sanitize_body_text(body_plain);
+        let plain = render::sanitize_text(body_plain);
         if !plain.trim().is_empty() {
             return plain;
         }
 
         match body_html {
-            Some(html) if !html.trim().is_empty() => Self::html_to_markdown_with_fallback(html),
+            Some(html) if !html.trim().is_empty() => render::html_to_markdown(html),
             _ => String::new(),
         }
     }
@@ -208,7 +323,20 @@ impl Email {
         Self::body_for_indexing_and_display(&self.body_plain, self.body_html.as_deref())
     }
 
-    fn embedding_body_excerpt(body: &str) -> String {
+    /// Body rendered as markdown for display and export.
+    /// Unlike [`Self::resolved_body`], HTML wins when present, since it keeps
+    /// tables, links and quote structure that the plain part flattens.
+    pub fn markdown_body(&self) -> String {
+        if let Some(html) = self.body_html.as_deref() {
+            let markdown = render::html_to_markdown(html);
+            if !markdown.trim().is_empty() {
+                return markdown;
+            }
+        }
+        self.resolved_body()
+    }
+
+    pub(crate) fn embedding_body_excerpt(body: &str) -> String {
         let total_chars = body.chars().count();
         if total_chars <= SEARCHABLE_BODY_MAX_CHARS {
             return body.to_string();
@@ -228,6 +356,33 @@ impl Email {
         format!("{} [truncated] {}", head, tail)
     }
 
+    /// The email in Gmail on the web
+    pub fn web_link(&self) -> String {
+        gmail_message_link(&self.account_id, &self.message_id)
+    }
+
+    /// Key shared by the emails of one conversation
+    ///
+    /// The Gmail thread ID when known. Otherwise the conversation's first
+    /// Message-ID (from References, else In-Reply-To, else the email's own)
+    /// with the subject stripped of reply and forward prefixes.
+    pub fn conversation_key(&self) -> String {
+        if self.gmail_thread_id != 0 {
+            return format!("{}:thread:{}", self.account_id, self.gmail_thread_id);
+        }
+        let root = self
+            .references
+            .first()
+            .or(self.in_reply_to.as_ref())
+            .unwrap_or(&self.message_id);
+        format!(
+            "{}:{}:{}",
+            self.account_id,
+            root.trim().trim_start_matches('<').trim_end_matches('>'),
+            base_subject(&self.subject)
+        )
+    }
+
     /// Check if the email has been read
     pub fn is_read(&self) -> bool {
         self.flags.iter().any(|f| f == "\\Seen")
@@ -258,8 +413,12 @@ impl Email {
         text.push_str(&self.from.to_string_full());
         text.push_str(". ");
 
-        // Body
-        let body = self.resolved_body();
+        // Body (just the snippet when only headers were synced)
+        let body = if self.headers_only {
+            self.snippet.clone()
+        } else {
+            self.resolved_body()
+        };
         text.push_str(&Self::embedding_body_excerpt(&body));
 
         // Attachment filenames
@@ -299,9 +458,13 @@ pub struct EmailSearchResult {
     #[serde(flatten)]
     pub email: EmailSummary,
 
-    /// Combined search score (RRF)
+    /// Combined search score (see `search.fusion`)
     pub score: f32,
 
+    /// BM25 and vector scores behind `score`
+    #[serde(default)]
+    pub score_components: ScoreComponents,
+
     /// Markdown summary for LLM consumption
     pub markdown_summary: String,
 }
@@ -330,9 +493,26 @@ pub struct EmailSummary {
     pub subject: String,
     pub date: DateTime<Utc>,
     pub snippet: String,
+    /// Snippet with the query terms in `<em>` (search with highlighting only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snippet_html: Option<String>,
     pub has_attachments: bool,
+    /// The conversation in Gmail on the web
+    #[serde(default)]
+    pub web_link: String,
     pub attachments: Vec<AttachmentSummary>,
     pub labels: Vec<String>,
+    /// Written by the account owner
+    #[serde(default)]
+    pub is_sent: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<EmailCategory>,
+    /// Tags added by local tagging rules
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Failed SPF/DKIM/DMARC checks or a spoofed display name
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub security_warnings: Vec<String>,
 }
 
 impl From<&Email> for EmailSummary {
@@ -348,7 +528,9 @@ impl From<&Email> for EmailSummary {
             subject: email.subject.clone(),
             date: email.date,
             snippet: email.snippet.clone(),
+            snippet_html: None,
             has_attachments: !email.attachments.is_empty(),
+            web_link: email.web_link(),
             attachments: email
                 .attachments
                 .iter()
@@ -361,13 +543,167 @@ impl From<&Email> for EmailSummary {
                 })
                 .collect(),
             labels: email.labels.clone(),
+            is_sent: email.is_sent,
+            category: email.category,
+            tags: email.tags.clone(),
+            security_warnings: email
+                .auth
+                .as_ref()
+                .map(|a| a.warnings.clone())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Bare RFC 5322 Message-ID from how it's written in a header or a link
+///
+/// Accepts `<id@host>`, `id@host`, and `mid:` (RFC 2392) or Apple Mail
+/// `message://` links, percent-encoded or not. Returns the id without angle
+/// brackets, or None when there is no id.
+pub fn normalize_message_id(raw: &str) -> Option<String> {
+    let mut id = raw.trim();
+    for scheme in ["message://", "message:", "mid:"] {
+        if id
+            .get(..scheme.len())
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case(scheme))
+        {
+            id = &id[scheme.len()..];
+            break;
+        }
+    }
+    let decoded = urlencoding::decode(id).map_or_else(|_| id.to_string(), |d| d.into_owned());
+    let id = decoded
+        .trim()
+        .trim_start_matches('<')
+        .trim_end_matches('>')
+        .trim();
+    if id.is_empty() || id.chars().any(char::is_whitespace) {
+        return None;
+    }
+    Some(id.to_string())
+}
+
+/// Link that opens an email in Gmail on the web, signed in as
+/// `account_id`, by searching for its Message-ID
+pub fn gmail_message_link(account_id: &str, message_id: &str) -> String {
+    let message_id = message_id
+        .trim()
+        .trim_start_matches('<')
+        .trim_end_matches('>');
+    format!(
+        "https://mail.google.com/mail/u/{}/#search/rfc822msgid%3A{}",
+        urlencoding::encode(account_id),
+        urlencoding::encode(message_id)
+    )
+}
+
+/// Lowercased subject without "Re:", "Fwd:" and "Fw:" prefixes
+fn base_subject(subject: &str) -> String {
+    let mut subject = subject.trim().to_lowercase();
+    while let Some(rest) = ["re:", "fwd:", "fw:"]
+        .iter()
+        .find_map(|prefix| subject.strip_prefix(prefix))
+    {
+        subject = rest.trim_start().to_string();
+    }
+    subject
+}
+
+/// Account and bare Message-ID an email ID is made of
+///
+/// Sync gives every email the stable ID `<account>:<Message-ID>`. Returns
+/// None for anything else.
+pub fn split_email_id(id: &str) -> Option<(&str, String)> {
+    let (account_id, message_id) = id.split_once(':')?;
+    if !account_id.contains('@') {
+        return None;
+    }
+    Some((account_id, normalize_message_id(message_id)?))
+}
+
+#[cfg(test)]
+impl Email {
+    /// A received email for tests: in `me@example.com`'s INBOX with `id` as
+    /// its Message-ID, dated now and otherwise empty. Set other fields with
+    /// `Email { .., ..Email::test(id, from, subject) }`.
+    pub(crate) fn test(id: &str, from: &str, subject: &str) -> Self {
+        Self {
+            id: format!("me@example.com:{}", id),
+            account_id: "me@example.com".to_string(),
+            account_alias: None,
+            message_id: id.to_string(),
+            gmail_message_id: 0,
+            gmail_thread_id: 0,
+            uid: 0,
+            in_reply_to: None,
+            references: vec![],
+            folder: "INBOX".to_string(),
+            labels: vec![],
+            is_sent: false,
+            flags: vec![],
+            category: None,
+            activity_source: None,
+            tags: vec![],
+            from: Address::new(from),
+            to: vec![],
+            cc: vec![],
+            bcc: vec![],
+            subject: subject.to_string(),
+            date: Utc::now(),
+            body_plain: String::new(),
+            body_html: None,
+            snippet: String::new(),
+            attachments: vec![],
+            auth: None,
+            embedding: None,
+            synced_at: Utc::now(),
+            raw_size: 0,
+            headers_only: false,
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Email, SEARCHABLE_BODY_MAX_CHARS};
+    use super::{
+        gmail_message_link, normalize_message_id, split_email_id, Email, SEARCHABLE_BODY_MAX_CHARS,
+    };
+
+    #[test]
+    fn message_id_normalization() {
+        let id = Some("CAF=abc123@mail.gmail.com".to_string());
+        assert_eq!(normalize_message_id("<CAF=abc123@mail.gmail.com>"), id);
+        assert_eq!(normalize_message_id(" CAF=abc123@mail.gmail.com "), id);
+        assert_eq!(
+            normalize_message_id("mid:CAF%3Dabc123%40mail.gmail.com"),
+            id
+        );
+        assert_eq!(
+            normalize_message_id("message://%3CCAF=abc123@mail.gmail.com%3E"),
+            id
+        );
+        assert_eq!(normalize_message_id("<>"), None);
+        assert_eq!(normalize_message_id("two words"), None);
+    }
+
+    #[test]
+    fn email_id_splitting() {
+        assert_eq!(
+            split_email_id("me@example.com:<CAF=abc:123@mail.gmail.com>"),
+            Some(("me@example.com", "CAF=abc:123@mail.gmail.com".to_string()))
+        );
+        assert_eq!(split_email_id("18c2f1a9d3e4b5f6"), None);
+        assert_eq!(split_email_id("mid:abc@example.com"), None);
+        assert_eq!(split_email_id("me@example.com:<>"), None);
+    }
+
+    #[test]
+    fn message_link_searches_encoded_message_id() {
+        assert_eq!(
+            gmail_message_link("me+work@example.com", "<CAF=ab+c@mail.gmail.com>"),
+            "https://mail.google.com/mail/u/me%2Bwork%40example.com/#search/rfc822msgid%3ACAF%3Dab%2Bc%40mail.gmail.com"
+        );
+    }
 
     #[test]
     fn embedding_body_excerpt_keeps_short_body_unchanged() {
@@ -416,6 +752,29 @@ mod tests {
         assert!(!body.contains('\u{0000}'));
         assert!(!body.contains('\u{0007}'));
     }
+
+    fn email(message_id: &str, thread: u64, subject: &str) -> Email {
+        Email {
+            gmail_thread_id: thread,
+            ..Email::test(message_id, "bob@acme.io", subject)
+        }
+    }
+
+    #[test]
+    fn conversation_key_falls_back_to_references_and_subject() {
+        let first = email("<1@x>", 0, "Q3 pricing");
+        let mut reply = email("<2@x>", 0, "RE: Fwd: Q3 pricing");
+        reply.in_reply_to = Some("<1@x>".to_string());
+        reply.references = vec!["1@x".to_string()];
+        let mut other = email("<3@x>", 0, "Q3 pricing");
+        other.references = vec!["<9@x>".to_string()];
+        assert_eq!(first.conversation_key(), reply.conversation_key());
+        assert_ne!(first.conversation_key(), other.conversation_key());
+
+        // Gmail thread IDs win when known
+        let threaded = email("<4@x>", 42, "Anything");
+        assert_eq!(threaded.conversation_key(), "me@example.com:thread:42");
+    }
 }
 
 /// Request to send an email
@@ -0,0 +1,167 @@
+//! Structured data extracted from email: reservations and package deliveries
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{ItineraryItem, ItineraryKind};
+
+/// Kind of extraction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExtractionKind {
+    Flight,
+    Hotel,
+    Restaurant,
+    Package,
+}
+
+impl ExtractionKind {
+    /// All kinds, in display order
+    pub const ALL: [ExtractionKind; 4] = [
+        ExtractionKind::Flight,
+        ExtractionKind::Hotel,
+        ExtractionKind::Restaurant,
+        ExtractionKind::Package,
+    ];
+
+    /// Stable lowercase name (stored in the database)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Flight => "flight",
+            Self::Hotel => "hotel",
+            Self::Restaurant => "restaurant",
+            Self::Package => "package",
+        }
+    }
+
+    /// Parse a kind name (case-insensitive)
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "flight" => Some(Self::Flight),
+            "hotel" => Some(Self::Hotel),
+            "restaurant" => Some(Self::Restaurant),
+            "package" => Some(Self::Package),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ExtractionKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<ItineraryKind> for ExtractionKind {
+    fn from(kind: ItineraryKind) -> Self {
+        match kind {
+            ItineraryKind::Flight => Self::Flight,
+            ItineraryKind::Hotel => Self::Hotel,
+        }
+    }
+}
+
+/// A reservation or package delivery found in an email
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Extraction {
+    /// Email ID plus the item's position in the email ("<email id>#0")
+    pub id: String,
+
+    /// ID of the email it was found in
+    pub email_id: String,
+
+    /// Account identifier (email address)
+    pub account_id: String,
+
+    /// Flight, hotel, restaurant or package
+    pub kind: ExtractionKind,
+
+    /// Display title ("UA 123 SFO → EWR", the hotel or restaurant, or what was shipped)
+    pub title: String,
+
+    /// Airline, hotel chain, booking site, or carrier
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+
+    /// Booking reference
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirmation: Option<String>,
+
+    /// Departure, check-in, reservation time, or expected delivery
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<DateTime<Utc>>,
+
+    /// Arrival or check-out
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<DateTime<Utc>>,
+
+    /// Start and end are dates without a time
+    pub all_day: bool,
+
+    /// IANA timezone at the start, when known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+
+    /// Departure airport code
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub origin: Option<String>,
+
+    /// Arrival airport code, or the hotel's city
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub destination: Option<String>,
+
+    /// Airport name, or hotel or restaurant address
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+
+    /// Number of guests (restaurants)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub party_size: Option<u32>,
+
+    /// Carrier tracking number (packages)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tracking_number: Option<String>,
+
+    /// Where to track the package
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tracking_url: Option<String>,
+
+    /// Delivery status: shipped, out_for_delivery, delivered (packages)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+
+    /// Date of the email it was found in
+    pub email_date: DateTime<Utc>,
+}
+
+impl Extraction {
+    /// An itinerary item as an extraction (from an email dated `email_date`)
+    pub fn from_itinerary(item: &ItineraryItem, email_date: DateTime<Utc>) -> Self {
+        Self {
+            id: item.id.clone(),
+            email_id: item.email_id.clone(),
+            account_id: item.account_id.clone(),
+            kind: item.kind.into(),
+            title: item.title.clone(),
+            provider: item.provider.clone(),
+            confirmation: item.confirmation.clone(),
+            start: Some(item.start),
+            end: item.end,
+            all_day: item.all_day,
+            timezone: item.start_timezone.clone(),
+            origin: item.origin.clone(),
+            destination: item.destination.clone(),
+            location: item.location.clone(),
+            party_size: None,
+            tracking_number: None,
+            tracking_url: None,
+            status: None,
+            email_date,
+        }
+    }
+
+    /// Whether the package has been delivered
+    pub fn is_delivered(&self) -> bool {
+        self.status.as_deref() == Some("delivered")
+    }
+}
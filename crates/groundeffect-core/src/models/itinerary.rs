@@ -0,0 +1,121 @@
+//! Travel itineraries parsed from confirmation emails
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Kind of itinerary item
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ItineraryKind {
+    Flight,
+    Hotel,
+}
+
+impl ItineraryKind {
+    /// Stable lowercase name (stored in the database)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Flight => "flight",
+            Self::Hotel => "hotel",
+        }
+    }
+
+    /// Parse a kind name (case-insensitive)
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "flight" => Some(Self::Flight),
+            "hotel" => Some(Self::Hotel),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ItineraryKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A flight leg or hotel stay from a confirmation email
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ItineraryItem {
+    /// Email ID plus the item's position in the email ("<email id>#0")
+    pub id: String,
+
+    /// ID of the confirmation email
+    pub email_id: String,
+
+    /// Account identifier (email address)
+    pub account_id: String,
+
+    /// Flight or hotel
+    pub kind: ItineraryKind,
+
+    /// Display title ("UA 123 SFO → EWR", or the hotel name)
+    pub title: String,
+
+    /// Airline or hotel chain
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+
+    /// Booking reference
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirmation: Option<String>,
+
+    /// Departure or check-in
+    pub start: DateTime<Utc>,
+
+    /// Arrival or check-out
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<DateTime<Utc>>,
+
+    /// Start and end are dates without a time (hotel stays)
+    pub all_day: bool,
+
+    /// IANA timezone at the start (departure airport or hotel)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_timezone: Option<String>,
+
+    /// IANA timezone at the end (arrival airport or hotel)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_timezone: Option<String>,
+
+    /// Departure airport code
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub origin: Option<String>,
+
+    /// Arrival airport code, or the hotel's city
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub destination: Option<String>,
+
+    /// Airport name or hotel address
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+
+    /// Google Calendar event created for this item
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_id: Option<String>,
+}
+
+impl ItineraryItem {
+    /// When the item is over (the start when there is no end)
+    pub fn finish(&self) -> DateTime<Utc> {
+        self.end.unwrap_or(self.start)
+    }
+}
+
+/// Itinerary items grouped into one trip
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trip {
+    /// Start of the first item
+    pub start: DateTime<Utc>,
+
+    /// End of the last item
+    pub end: DateTime<Utc>,
+
+    /// Places visited (arrival airports and hotel cities, in order)
+    pub destinations: Vec<String>,
+
+    /// Flights and hotel stays, in order
+    pub items: Vec<ItineraryItem>,
+}
@@ -0,0 +1,198 @@
+//! Gmail label data structures
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// IMAP folders Gmail keeps spam and deleted mail in ("[Google Mail]" in
+/// some locales, "Bin" in British English)
+pub const SPAM_TRASH_FOLDERS: &[&str] = &[
+    "[Gmail]/Spam",
+    "[Gmail]/Trash",
+    "[Gmail]/Bin",
+    "[Google Mail]/Spam",
+    "[Google Mail]/Trash",
+    "[Google Mail]/Bin",
+];
+
+/// Gmail API label ids of Spam and Trash
+pub const SPAM_TRASH_LABELS: &[&str] = &["SPAM", "TRASH"];
+
+/// Whether a folder name or label id is Gmail's Spam or Trash
+pub fn is_spam_or_trash(folder: &str) -> bool {
+    SPAM_TRASH_FOLDERS
+        .iter()
+        .chain(SPAM_TRASH_LABELS)
+        .any(|f| f.eq_ignore_ascii_case(folder))
+}
+
+/// Whether a label is built into Gmail or created by the user
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LabelType {
+    /// Built-in label (INBOX, SENT, CATEGORY_PROMOTIONS, ...)
+    System,
+    /// User-created label
+    User,
+}
+
+/// Label colors as configured in Gmail (hex strings like "#ffffff")
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LabelColor {
+    pub text_color: String,
+    pub background_color: String,
+}
+
+/// A Gmail label with message counts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GmailLabel {
+    /// Gmail label ID (e.g. "INBOX", "Label_123")
+    pub id: String,
+
+    /// Display name (e.g. "INBOX", "Work/Projects")
+    pub name: String,
+
+    /// System or user label
+    pub label_type: LabelType,
+
+    /// Label color (user labels only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<LabelColor>,
+
+    /// Total messages with this label
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub messages_total: Option<u64>,
+
+    /// Unread messages with this label
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub messages_unread: Option<u64>,
+}
+
+impl GmailLabel {
+    /// Check if this label matches an identifier (name or ID, case-insensitive)
+    pub fn matches(&self, identifier: &str) -> bool {
+        self.name.eq_ignore_ascii_case(identifier) || self.id.eq_ignore_ascii_case(identifier)
+    }
+
+    /// IMAP folder name Gmail exposes for this label
+    ///
+    /// User labels appear as folders with the same name; system labels live
+    /// under "[Gmail]/".
+    pub fn imap_folder(&self) -> String {
+        match self.id.as_str() {
+            "SENT" => "[Gmail]/Sent Mail".to_string(),
+            "DRAFT" => "[Gmail]/Drafts".to_string(),
+            "SPAM" => "[Gmail]/Spam".to_string(),
+            "TRASH" => "[Gmail]/Trash".to_string(),
+            "STARRED" => "[Gmail]/Starred".to_string(),
+            "IMPORTANT" => "[Gmail]/Important".to_string(),
+            _ => self.name.clone(),
+        }
+    }
+}
+
+/// Labels fetched from Gmail for one account, cached on disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LabelCache {
+    /// Account ID (email address)
+    pub account_id: String,
+
+    /// When the labels were fetched from Gmail
+    pub fetched_at: DateTime<Utc>,
+
+    /// System labels first, then user labels sorted by name
+    pub labels: Vec<GmailLabel>,
+}
+
+impl LabelCache {
+    /// Create a cache entry, sorting labels for display
+    pub fn new(account_id: impl Into<String>, mut labels: Vec<GmailLabel>) -> Self {
+        labels.sort_by(|a, b| {
+            (a.label_type == LabelType::User, a.name.to_lowercase())
+                .cmp(&(b.label_type == LabelType::User, b.name.to_lowercase()))
+        });
+        Self {
+            account_id: account_id.into(),
+            fetched_at: Utc::now(),
+            labels,
+        }
+    }
+
+    /// Whether the cache is younger than `max_age_secs`
+    pub fn is_fresh(&self, max_age_secs: u64) -> bool {
+        (Utc::now() - self.fetched_at).num_seconds() < max_age_secs as i64
+    }
+
+    /// Find a label by name or ID
+    pub fn find(&self, identifier: &str) -> Option<&GmailLabel> {
+        self.labels.iter().find(|l| l.matches(identifier))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn label(id: &str, name: &str, label_type: LabelType) -> GmailLabel {
+        GmailLabel {
+            id: id.to_string(),
+            name: name.to_string(),
+            label_type,
+            color: None,
+            messages_total: None,
+            messages_unread: None,
+        }
+    }
+
+    #[test]
+    fn test_cache_sorts_system_labels_first() {
+        let cache = LabelCache::new(
+            "user@gmail.com",
+            vec![
+                label("Label_2", "work", LabelType::User),
+                label("SENT", "SENT", LabelType::System),
+                label("Label_1", "Receipts", LabelType::User),
+                label("INBOX", "INBOX", LabelType::System),
+            ],
+        );
+        let names: Vec<&str> = cache.labels.iter().map(|l| l.name.as_str()).collect();
+        assert_eq!(names, vec!["INBOX", "SENT", "Receipts", "work"]);
+    }
+
+    #[test]
+    fn test_find_by_name_or_id() {
+        let cache = LabelCache::new(
+            "user@gmail.com",
+            vec![label("Label_7", "Work/Projects", LabelType::User)],
+        );
+        assert_eq!(cache.find("work/projects").unwrap().id, "Label_7");
+        assert_eq!(cache.find("label_7").unwrap().name, "Work/Projects");
+        assert!(cache.find("Personal").is_none());
+        assert!(cache.is_fresh(60));
+    }
+
+    #[test]
+    fn test_spam_and_trash() {
+        assert!(is_spam_or_trash("[Gmail]/Spam"));
+        assert!(is_spam_or_trash("[Google Mail]/Bin"));
+        assert!(is_spam_or_trash("trash"));
+        assert!(!is_spam_or_trash("INBOX"));
+        assert!(!is_spam_or_trash("[Gmail]/Sent Mail"));
+        assert!(!is_spam_or_trash("Spam reports"));
+    }
+
+    #[test]
+    fn test_imap_folder_names() {
+        assert_eq!(
+            label("SENT", "SENT", LabelType::System).imap_folder(),
+            "[Gmail]/Sent Mail"
+        );
+        assert_eq!(
+            label("INBOX", "INBOX", LabelType::System).imap_folder(),
+            "INBOX"
+        );
+        assert_eq!(
+            label("Label_1", "Work/Projects", LabelType::User).imap_folder(),
+            "Work/Projects"
+        );
+    }
+}
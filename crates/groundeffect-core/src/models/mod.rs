@@ -1,13 +1,36 @@
 //! Data models for GroundEffect
 //!
-//! Core data structures for emails, calendar events, accounts, and attachments.
+//! Core data structures for emails, calendar events, accounts, attachments,
+//! activity items, receipts, travel itineraries, reservations and package
+//! deliveries, action items, conversation threads, out-of-office state, and
+//! the audit log.
 
 mod account;
+mod activity;
 mod attachment;
+mod audit;
+mod availability;
 mod calendar;
+mod directory;
 mod email;
+mod extraction;
+mod itinerary;
+mod label;
+mod receipt;
+mod task;
+mod thread;
 
 pub use account::*;
+pub use activity::*;
 pub use attachment::*;
+pub use audit::*;
+pub use availability::*;
 pub use calendar::*;
+pub use directory::*;
 pub use email::*;
+pub use extraction::*;
+pub use itinerary::*;
+pub use label::*;
+pub use receipt::*;
+pub use task::*;
+pub use thread::*;
@@ -0,0 +1,66 @@
+//! Receipts and invoices extracted from email
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Where a receipt's amount was read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReceiptSource {
+    /// The email body
+    Body,
+    /// A downloaded attachment (PDF or text)
+    Attachment,
+}
+
+impl ReceiptSource {
+    /// Stable lowercase name (stored in the database)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Body => "body",
+            Self::Attachment => "attachment",
+        }
+    }
+
+    /// Parse from string
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "body" => Some(Self::Body),
+            "attachment" => Some(Self::Attachment),
+            _ => None,
+        }
+    }
+}
+
+/// A receipt or invoice found in an email
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Receipt {
+    /// Same as the ID of the email it was extracted from
+    pub id: String,
+
+    /// Account identifier (email address)
+    pub account_id: String,
+
+    /// Merchant name (sender name, or the sender's domain)
+    pub merchant: String,
+
+    /// Total charged, when one could be found
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<f64>,
+
+    /// ISO 4217 currency code (e.g. "USD")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+
+    /// Transaction date (the email date unless the receipt states one)
+    pub date: DateTime<Utc>,
+
+    /// Email subject
+    pub subject: String,
+
+    /// Sender email address
+    pub sender: String,
+
+    /// Where the amount was read from
+    pub source: ReceiptSource,
+}
@@ -0,0 +1,113 @@
+//! Action items found in email ("can you send the deck by Friday")
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Whether a task is still to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskStatus {
+    Open,
+    Done,
+}
+
+impl TaskStatus {
+    /// Stable lowercase name (stored in the database)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Open => "open",
+            Self::Done => "done",
+        }
+    }
+
+    /// Parse a status name (case-insensitive)
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "open" => Some(Self::Open),
+            "done" => Some(Self::Done),
+            _ => None,
+        }
+    }
+}
+
+/// How a task was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskSource {
+    /// Request phrasing matched during sync
+    Heuristic,
+    /// Written by an MCP client (an AI assistant) that read the email
+    Assistant,
+}
+
+impl TaskSource {
+    /// Stable lowercase name (stored in the database)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Heuristic => "heuristic",
+            Self::Assistant => "assistant",
+        }
+    }
+
+    /// Parse a source name (case-insensitive)
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "heuristic" => Some(Self::Heuristic),
+            "assistant" => Some(Self::Assistant),
+            _ => None,
+        }
+    }
+}
+
+/// Something an email asks the account owner to do
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Task {
+    /// Email ID plus the task's source and position ("<email id>#task0")
+    pub id: String,
+
+    /// ID of the email that asked for it
+    pub email_id: String,
+
+    /// Account identifier (email address)
+    pub account_id: String,
+
+    /// The request, as written
+    pub text: String,
+
+    /// Who asked ("Name <email>")
+    pub requester: String,
+
+    /// Subject of the email
+    pub subject: String,
+
+    /// Due date guessed from the request, in the user's timezone
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub due: Option<NaiveDate>,
+
+    /// The words the due date was read from ("by Friday")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub due_text: Option<String>,
+
+    /// Open or done
+    pub status: TaskStatus,
+
+    /// Heuristic or assistant
+    pub source: TaskSource,
+
+    /// Date of the email
+    pub created_at: DateTime<Utc>,
+
+    /// When it was marked done
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+impl Task {
+    /// Row ID of the `index`th task found in an email
+    pub fn id_for(email_id: &str, source: TaskSource, index: usize) -> String {
+        match source {
+            TaskSource::Heuristic => format!("{}#task{}", email_id, index),
+            TaskSource::Assistant => format!("{}#assistant{}", email_id, index),
+        }
+    }
+}
@@ -0,0 +1,78 @@
+//! Email conversations, one row per Gmail thread
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{gmail_message_link, split_email_id, Address};
+
+/// A conversation: the messages of one account sharing a Gmail thread ID
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Thread {
+    /// `{account_id}:{gmail_thread_id}`
+    pub id: String,
+
+    /// Account identifier (email address)
+    pub account_id: String,
+
+    /// X-GM-THRID shared by the thread's messages (as used by `email thread`)
+    pub gmail_thread_id: u64,
+
+    /// Subject of the first message
+    pub subject: String,
+
+    /// Senders and recipients, in order of first appearance
+    pub participants: Vec<Address>,
+
+    /// Number of synced messages
+    pub message_count: u32,
+
+    /// Messages not yet read
+    pub unread_count: u32,
+
+    /// Date of the first message
+    pub first_date: DateTime<Utc>,
+
+    /// Date of the latest message
+    pub last_activity: DateTime<Utc>,
+
+    /// Sender of the latest message
+    pub last_from: Address,
+
+    /// Preview of the latest message
+    pub snippet: String,
+
+    /// Whether any message has attachments
+    pub has_attachments: bool,
+
+    /// Whether the account owner wrote any of the messages
+    pub has_sent: bool,
+
+    /// Email IDs, oldest first
+    pub email_ids: Vec<String>,
+
+    /// Text the embedding was computed from (also what BM25 searches)
+    #[serde(skip)]
+    pub content: String,
+
+    /// Embedding of `content`
+    #[serde(skip)]
+    pub embedding: Option<Vec<f32>>,
+}
+
+impl Thread {
+    /// Row ID of a thread
+    pub fn id_for(account_id: &str, gmail_thread_id: u64) -> String {
+        format!("{}:{}", account_id, gmail_thread_id)
+    }
+
+    /// The conversation in Gmail on the web, found by its first message
+    pub fn web_link(&self) -> String {
+        match self.email_ids.first().and_then(|id| split_email_id(id)) {
+            Some((_, message_id)) => gmail_message_link(&self.account_id, &message_id),
+            None => format!(
+                "https://mail.google.com/mail/u/{}/",
+                urlencoding::encode(&self.account_id)
+            ),
+        }
+    }
+}
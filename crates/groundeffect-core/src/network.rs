@@ -0,0 +1,159 @@
+//! Build-time network guarantees
+//!
+//! Apart from Google's APIs, GroundEffect only contacts endpoints you
+//! configure. Cargo features compile the optional paths out entirely, so a
+//! build can be shown not to contain them:
+//!
+//! - `no-remote-embedding`: no remote embedding client (OpenAI, Ollama,
+//!   OpenRouter or a custom `/embed` service); email and event text is only
+//!   embedded locally
+//! - `offline-search`: implies `no-remote-embedding`, and embedding models
+//!   are only read from disk, never downloaded
+//! - `no-llm`: no language model calls. Nothing calls an LLM yet; the flag
+//!   lets a build pin that guarantee for future features
+//!
+//! [`network_report`] lists what a build can reach, for
+//! `groundeffect config capabilities`.
+
+use serde::Serialize;
+
+use crate::config::{Config, TokenProviderConfig};
+use crate::embedding::{EmbeddingEngine, EmbeddingModel};
+
+/// Whether the remote embedding client is compiled in
+pub const REMOTE_EMBEDDING: bool = cfg!(not(feature = "no-remote-embedding"));
+
+/// Whether embedding models can be downloaded from HuggingFace
+pub const MODEL_DOWNLOAD: bool = cfg!(not(feature = "offline-search"));
+
+/// Whether features may call a language model
+pub const LLM: bool = cfg!(not(feature = "no-llm"));
+
+/// Google hosts used for auth, sync, sending and directory lookups
+pub const GOOGLE_HOSTS: &[&str] = &[
+    "accounts.google.com",
+    "oauth2.googleapis.com",
+    "imap.gmail.com",
+    "gmail.googleapis.com",
+    "www.googleapis.com",
+    "apidata.googleusercontent.com",
+    "people.googleapis.com",
+];
+
+/// One way a build can reach the network
+#[derive(Debug, Clone, Serialize)]
+pub struct NetworkPath {
+    /// Short identifier
+    pub name: &'static str,
+
+    /// What it is used for
+    pub description: &'static str,
+
+    /// Included in this build
+    pub compiled: bool,
+
+    /// Cargo feature that controls it (None if always included)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feature: Option<&'static str>,
+
+    /// Used with the current config
+    pub active: bool,
+
+    /// Hosts it contacts
+    pub endpoints: Vec<String>,
+}
+
+/// Network paths compiled into this build and whether the config uses them
+pub fn network_report(config: &Config) -> Vec<NetworkPath> {
+    let search = &config.search;
+    let remote_endpoint = search.embedding_endpoint().map(|endpoint| endpoint.url);
+
+    let model =
+        EmbeddingModel::from_str(&search.embedding_model).unwrap_or(EmbeddingModel::BgeBaseEn);
+    let model_missing = EmbeddingEngine::find_cached_model(config.models_dir(), model).is_none();
+
+    let token_database = matches!(
+        config.tokens,
+        TokenProviderConfig::Dawn { .. } | TokenProviderConfig::Postgres { .. }
+    );
+
+    vec![
+        NetworkPath {
+            name: "google",
+            description: "Gmail, Google Calendar, OAuth and Workspace directory APIs",
+            compiled: true,
+            feature: None,
+            active: true,
+            endpoints: GOOGLE_HOSTS.iter().map(|h| h.to_string()).collect(),
+        },
+        NetworkPath {
+            name: "remote_embedding",
+            description:
+                "Embedding email and event text with OpenAI, Ollama, OpenRouter or a custom service",
+            compiled: REMOTE_EMBEDDING,
+            feature: Some("no-remote-embedding"),
+            active: REMOTE_EMBEDDING && search.remote_embeddings_enabled(),
+            endpoints: remote_endpoint.into_iter().collect(),
+        },
+        NetworkPath {
+            name: "model_download",
+            description: "Downloading the local embedding model on first use",
+            compiled: MODEL_DOWNLOAD,
+            feature: Some("offline-search"),
+            active: MODEL_DOWNLOAD && model_missing,
+            endpoints: vec!["huggingface.co".to_string()],
+        },
+        NetworkPath {
+            name: "llm",
+            description: "Language model calls (no feature uses one yet)",
+            compiled: LLM,
+            feature: Some("no-llm"),
+            active: false,
+            endpoints: Vec::new(),
+        },
+        NetworkPath {
+            name: "token_database",
+            description: "Postgres token storage (dawn and postgres token providers)",
+            compiled: cfg!(feature = "postgres"),
+            feature: Some("postgres"),
+            active: cfg!(feature = "postgres") && token_database,
+            endpoints: if token_database {
+                vec!["configured database_url".to_string()]
+            } else {
+                Vec::new()
+            },
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config_only_reaches_google_and_model_host() {
+        let report = network_report(&Config::default());
+        let active: Vec<&str> = report
+            .iter()
+            .filter(|path| path.active)
+            .map(|path| path.name)
+            .collect();
+        assert!(active.contains(&"google"));
+        assert!(!active.contains(&"remote_embedding"));
+        assert!(!active.contains(&"llm"));
+        assert!(!active.contains(&"token_database"));
+    }
+
+    #[test]
+    fn test_remote_embedding_endpoint() {
+        let mut config = Config::default();
+        config.search.embedding_url = Some("http://10.0.0.5:8080".to_string());
+        let report = network_report(&config);
+        let remote = report
+            .iter()
+            .find(|path| path.name == "remote_embedding")
+            .unwrap();
+        assert_eq!(remote.endpoints, vec!["http://10.0.0.5:8080".to_string()]);
+        assert_eq!(remote.active, REMOTE_EMBEDDING);
+    }
+}
@@ -0,0 +1,163 @@
+//! Raw RFC 2822 message source
+//!
+//! `groundeffect email raw <id>` returns a message exactly as Gmail has it,
+//! every header included, for DKIM/SPF questions or exporting a single
+//! message as `.eml`. The source is fetched over IMAP on demand. With
+//! `[sync] store_raw = true`, sync also keeps a gzip-compressed copy of each
+//! message under `raw/<account>/`, which is returned without a round trip.
+
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use sha2::{Digest, Sha256};
+
+use crate::config::Config;
+use crate::error::Result;
+use crate::models::Email;
+use crate::oauth::OAuthManager;
+use crate::sync::{GlobalRateLimiter, ImapClient};
+
+/// A message's source and where it came from
+#[derive(Debug, Clone)]
+pub struct RawMessage {
+    pub source: Vec<u8>,
+    /// Read from the local copy rather than fetched over IMAP
+    pub cached: bool,
+}
+
+impl RawMessage {
+    /// The header block, up to (not including) the blank line before the body
+    pub fn headers(&self) -> &[u8] {
+        header_section(&self.source)
+    }
+}
+
+/// Where the compressed copy of an email's source lives under `dir`
+///
+/// Email ids embed the Message-ID, which can hold `/` and other characters
+/// that don't belong in a file name, so the file is named by its hash.
+pub fn raw_path(dir: &Path, email: &Email) -> PathBuf {
+    raw_path_for(dir, &email.account_id, &email.id)
+}
+
+/// [`raw_path`] by account and email ID
+pub fn raw_path_for(dir: &Path, account_id: &str, email_id: &str) -> PathBuf {
+    dir.join(account_id)
+        .join(format!("{:x}.eml.gz", Sha256::digest(email_id.as_bytes())))
+}
+
+/// Save a gzip-compressed copy of an email's source
+pub fn store(dir: &Path, email: &Email, source: &[u8]) -> Result<()> {
+    let path = raw_path(dir, email);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(source)?;
+    // Write then rename, so a crash never leaves a truncated copy behind
+    let tmp = path.with_extension("gz.tmp");
+    fs::write(&tmp, encoder.finish()?)?;
+    fs::rename(&tmp, &path)?;
+    Ok(())
+}
+
+/// The stored source of an email, if there is a copy
+pub fn load(dir: &Path, email: &Email) -> Result<Option<Vec<u8>>> {
+    let path = raw_path(dir, email);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let mut source = Vec::new();
+    GzDecoder::new(fs::File::open(path)?).read_to_end(&mut source)?;
+    Ok(Some(source))
+}
+
+/// The stored copy of an email's source, or else fetch it from Gmail
+///
+/// A fetched source is stored when `[sync] store_raw` is on.
+pub async fn fetch_raw(
+    config: &Config,
+    oauth: Arc<OAuthManager>,
+    email: &Email,
+) -> Result<RawMessage> {
+    let dir = config.raw_dir();
+    if let Some(source) = load(&dir, email)? {
+        return Ok(RawMessage {
+            source,
+            cached: true,
+        });
+    }
+
+    let rate_limiter = Arc::new(GlobalRateLimiter::new(config.sync.rate_limit_per_second));
+    let source = ImapClient::new(&email.account_id, oauth, rate_limiter)
+        .await?
+        .with_folder(&email.folder)
+        .fetch_raw(email.uid)
+        .await?;
+    if config.sync.store_raw {
+        store(&dir, email, &source)?;
+    }
+    Ok(RawMessage {
+        source,
+        cached: false,
+    })
+}
+
+/// Everything before the first empty line (CRLF or bare LF)
+pub fn header_section(source: &[u8]) -> &[u8] {
+    let mut line_start = 0;
+    for (i, byte) in source.iter().enumerate() {
+        if *byte != b'\n' {
+            continue;
+        }
+        let line = &source[line_start..i];
+        if line.is_empty() || line == b"\r" {
+            return &source[..line_start];
+        }
+        line_start = i + 1;
+    }
+    source
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn email(id: &str) -> Email {
+        Email {
+            id: id.to_string(),
+            uid: 7,
+            ..Email::test("<a/b@example.com>", "a@example.com", "Hi")
+        }
+    }
+
+    #[test]
+    fn store_and_load_round_trip() {
+        let dir = std::env::temp_dir().join(format!("ge-raw-{}", uuid::Uuid::new_v4()));
+        let email = email("me@example.com:<a/b@example.com>");
+        let source = b"From: a@example.com\r\nSubject: Hi\r\n\r\nBody\r\n".repeat(50);
+
+        assert!(load(&dir, &email).unwrap().is_none());
+        store(&dir, &email, &source).unwrap();
+        assert_eq!(load(&dir, &email).unwrap().as_deref(), Some(&source[..]));
+        assert!(fs::metadata(raw_path(&dir, &email)).unwrap().len() < source.len() as u64);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn header_section_stops_at_blank_line() {
+        let crlf = b"DKIM-Signature: v=1;\r\n b=abc\r\nSubject: Hi\r\n\r\nBody\r\n\r\nMore";
+        assert_eq!(
+            header_section(crlf),
+            b"DKIM-Signature: v=1;\r\n b=abc\r\nSubject: Hi\r\n"
+        );
+        assert_eq!(header_section(b"Subject: Hi\n\nBody"), b"Subject: Hi\n");
+        assert_eq!(header_section(b"Subject: Hi"), b"Subject: Hi");
+    }
+}
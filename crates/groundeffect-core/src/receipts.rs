@@ -0,0 +1,678 @@
+//! Receipt and invoice extraction
+//!
+//! Finds receipts among synced email with sender and subject heuristics and
+//! receipt-like attachment names, then reads the merchant, total, currency,
+//! and date from the body. PDF and text attachments are parsed once they are
+//! downloaded, which fills in the total for receipts that only carry a PDF.
+//! [`spending_report`] totals them by month and merchant.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use chrono_tz::Tz;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::config::ReceiptsConfig;
+use crate::models::{Attachment, Email, Receipt, ReceiptSource};
+
+/// Subject fragments used by receipts, invoices, and payment confirmations
+const RECEIPT_SUBJECT_HINTS: &[&str] = &[
+    "receipt",
+    "invoice",
+    "your order",
+    "order confirmation",
+    "payment received",
+    "payment confirmation",
+    "your payment",
+    "billing statement",
+    "subscription renewed",
+    "thanks for your purchase",
+    "thank you for your purchase",
+    "thank you for your order",
+    "you paid",
+];
+
+/// Subjects that look transactional but never carry a charge
+const NON_RECEIPT_SUBJECT_HINTS: &[&str] = &[
+    "password",
+    "verification code",
+    "verify your",
+    "sign-in",
+    "security alert",
+];
+
+/// Sender local-part fragments used by billing systems
+const RECEIPT_SENDER_HINTS: &[&str] = &[
+    "receipt", "receipts", "invoice", "invoices", "billing", "payments", "orders",
+];
+
+/// Attachment filename fragments that mark a receipt
+const RECEIPT_FILENAME_HINTS: &[&str] = &["receipt", "invoice", "rechnung", "facture"];
+
+/// Sender display-name suffixes dropped from the merchant name
+const MERCHANT_NAME_SUFFIXES: &[&str] = &[
+    " receipts",
+    " billing",
+    " payments",
+    " invoices",
+    " orders",
+    " team",
+];
+
+/// Attachments larger than this are not parsed (bytes)
+const MAX_ATTACHMENT_PARSE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// An amount of money found in text
+#[derive(Debug, Clone, PartialEq)]
+pub struct Money {
+    pub value: f64,
+    pub currency: Option<String>,
+}
+
+/// Check whether an email looks like a receipt or invoice
+pub fn is_receipt_candidate(email: &Email) -> bool {
+    let subject = email.subject.to_lowercase();
+    if NON_RECEIPT_SUBJECT_HINTS
+        .iter()
+        .any(|hint| subject.contains(hint))
+    {
+        return false;
+    }
+
+    let local = email
+        .from
+        .email
+        .split('@')
+        .next()
+        .unwrap_or("")
+        .to_lowercase();
+    RECEIPT_SUBJECT_HINTS
+        .iter()
+        .any(|hint| subject.contains(hint))
+        || local
+            .split(['.', '+', '_', '-'])
+            .any(|token| RECEIPT_SENDER_HINTS.contains(&token))
+        || receipt_attachments(email).next().is_some()
+}
+
+/// Extract a receipt from an email's body.
+///
+/// Candidates without a total are only kept when they carry a receipt-like
+/// attachment, whose total can be read once it is downloaded.
+pub fn extract_receipt(email: &Email) -> Option<Receipt> {
+    if !is_receipt_candidate(email) {
+        return None;
+    }
+    let mut receipt = base_receipt(email);
+    match find_total(&email.body_plain) {
+        Some(money) => {
+            receipt.amount = Some(money.value);
+            receipt.currency = money.currency;
+        }
+        None if receipt_attachments(email).next().is_some() => {}
+        None => return None,
+    }
+    Some(receipt)
+}
+
+/// Extract receipts from a batch of synced emails
+pub fn extract_receipts(emails: &[Email], config: &ReceiptsConfig) -> Vec<Receipt> {
+    if !config.enabled {
+        return Vec::new();
+    }
+    emails.iter().filter_map(extract_receipt).collect()
+}
+
+/// Extract a receipt using the email's downloaded PDF and text attachments.
+///
+/// The body total wins when there is one. Reads files from disk, so call it
+/// off the async runtime.
+pub fn receipt_from_attachments(email: &Email) -> Option<Receipt> {
+    if !is_receipt_candidate(email) {
+        return None;
+    }
+    let mut receipt = extract_receipt(email).unwrap_or_else(|| base_receipt(email));
+    if receipt.amount.is_some() {
+        return Some(receipt);
+    }
+
+    let parseable = email
+        .attachments
+        .iter()
+        .filter(|att| att.downloaded && !att.is_inline() && att.size <= MAX_ATTACHMENT_PARSE_BYTES);
+    for attachment in parseable {
+        let Some(text) = attachment_text(attachment) else {
+            continue;
+        };
+        if let Some(money) = find_total(&text) {
+            debug!(
+                "Read receipt total from attachment {} of {}",
+                attachment.filename, email.id
+            );
+            receipt.amount = Some(money.value);
+            receipt.currency = money.currency;
+            receipt.source = ReceiptSource::Attachment;
+            if let Some(date) = find_date(&text, email.date) {
+                receipt.date = date;
+            }
+            return Some(receipt);
+        }
+    }
+
+    receipt_attachments(email).next().map(|_| receipt)
+}
+
+/// Receipt fields that come straight from the email
+fn base_receipt(email: &Email) -> Receipt {
+    Receipt {
+        id: email.id.clone(),
+        account_id: email.account_id.clone(),
+        merchant: merchant_name(email),
+        amount: None,
+        currency: None,
+        date: find_date(&email.body_plain, email.date).unwrap_or(email.date),
+        subject: email.subject.clone(),
+        sender: email.from.email.clone(),
+        source: ReceiptSource::Body,
+    }
+}
+
+fn receipt_attachments(email: &Email) -> impl Iterator<Item = &Attachment> {
+    email.attachments.iter().filter(|att| {
+        let name = att.filename.to_lowercase();
+        RECEIPT_FILENAME_HINTS
+            .iter()
+            .any(|hint| name.contains(hint))
+    })
+}
+
+/// Plain text of a downloaded PDF or text attachment
+fn attachment_text(attachment: &Attachment) -> Option<String> {
+    let path = attachment.local_path.as_ref()?;
+    if attachment.is_pdf() {
+        let bytes = std::fs::read(path).ok()?;
+        match pdf_extract::extract_text_from_mem(&bytes) {
+            Ok(text) => Some(text),
+            Err(e) => {
+                debug!("Failed to read PDF {}: {}", attachment.filename, e);
+                None
+            }
+        }
+    } else if attachment.mime_type.starts_with("text/") {
+        std::fs::read_to_string(path).ok()
+    } else {
+        None
+    }
+}
+
+/// Merchant from the sender's display name, or else their domain
+pub fn merchant_name(email: &Email) -> String {
+    if let Some(name) = email.from.name.as_deref().map(str::trim) {
+        let mut name = name.to_string();
+        for suffix in MERCHANT_NAME_SUFFIXES {
+            if name.to_lowercase().ends_with(suffix) {
+                name.truncate(name.len() - suffix.len());
+            }
+        }
+        let lower = name.to_lowercase();
+        let generic = lower.is_empty()
+            || name.contains('@')
+            || ["no-reply", "noreply", "receipts", "billing", "invoice"]
+                .iter()
+                .any(|g| lower == *g);
+        if !generic {
+            return name;
+        }
+    }
+
+    let domain = email.from.email.split('@').nth(1).unwrap_or("");
+    let labels: Vec<&str> = domain.split('.').collect();
+    // "amazon.co.uk" -> "amazon", "mail.uber.com" -> "uber"
+    let label = match labels.len() {
+        0 | 1 => domain,
+        n if n >= 3 && ["co", "com", "org", "net"].contains(&labels[n - 2]) => labels[n - 3],
+        n => labels[n - 2],
+    };
+    let mut chars = label.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => email.from.email.clone(),
+    }
+}
+
+fn money_regex() -> &'static Regex {
+    static MONEY: OnceLock<Regex> = OnceLock::new();
+    MONEY.get_or_init(|| {
+        Regex::new(
+            r"(?x)
+            (?P<pre>US\$|CA\$|A\$|[$€£¥₹]|\b(?:USD|EUR|GBP|CAD|AUD|NZD|JPY|CHF|INR|SEK|NOK|DKK)\b)
+            \s?(?P<num>\d[\d,.]*\d|\d)
+            |
+            (?P<num2>\d[\d,.]*\d|\d)\s?
+            (?P<post>€|\b(?:USD|EUR|GBP|CAD|AUD|NZD|JPY|CHF|INR|SEK|NOK|DKK)\b)",
+        )
+        .unwrap()
+    })
+}
+
+/// Find the total charged in receipt text.
+///
+/// Prefers amounts on lines labelled as the total (the last one wins, since
+/// totals follow subtotals), falling back to the largest amount.
+pub fn find_total(text: &str) -> Option<Money> {
+    let mut found: Vec<(u8, Money)> = Vec::new();
+    // A label on its own line ("Total" / "$12.00") applies to the next line
+    let mut pending_priority = 0;
+
+    for line in text.lines() {
+        let priority = total_priority(line).max(pending_priority);
+        let mut line_has_money = false;
+        for caps in money_regex().captures_iter(line) {
+            let num = caps.name("num").or_else(|| caps.name("num2"));
+            let symbol = caps.name("pre").or_else(|| caps.name("post"));
+            let (Some(num), Some(symbol)) = (num, symbol) else {
+                continue;
+            };
+            let Some(value) = parse_amount(num.as_str()) else {
+                continue;
+            };
+            line_has_money = true;
+            found.push((
+                priority,
+                Money {
+                    value,
+                    currency: currency_code(symbol.as_str()),
+                },
+            ));
+        }
+        pending_priority = if line_has_money || line.trim().is_empty() {
+            0
+        } else {
+            total_priority(line)
+        };
+    }
+
+    let best = found.iter().map(|(p, _)| *p).max()?;
+    if best > 0 {
+        found
+            .into_iter()
+            .rev()
+            .find(|(p, _)| *p == best)
+            .map(|(_, money)| money)
+    } else {
+        found
+            .into_iter()
+            .map(|(_, money)| money)
+            .max_by(|a, b| a.value.total_cmp(&b.value))
+    }
+}
+
+/// How strongly a line is labelled as the total (0 = not at all)
+fn total_priority(line: &str) -> u8 {
+    let line = line.to_lowercase();
+    if [
+        "grand total",
+        "total charged",
+        "amount charged",
+        "total paid",
+        "amount paid",
+        "you paid",
+    ]
+    .iter()
+    .any(|label| line.contains(label))
+    {
+        3
+    } else if line.contains("total") && !line.contains("subtotal") && !line.contains("sub-total") {
+        2
+    } else if ["amount due", "charged", "paid"]
+        .iter()
+        .any(|label| line.contains(label))
+    {
+        1
+    } else {
+        0
+    }
+}
+
+/// Parse "1,234.56", "1.234,56", or "12,50" as a number
+fn parse_amount(raw: &str) -> Option<f64> {
+    let decimal_sep = raw
+        .rfind([',', '.'])
+        .filter(|&idx| (1..=2).contains(&(raw.len() - idx - 1)));
+    let normalized: String = match decimal_sep {
+        Some(idx) => {
+            let integer: String = raw[..idx].chars().filter(char::is_ascii_digit).collect();
+            format!("{}.{}", integer, &raw[idx + 1..])
+        }
+        None => raw.chars().filter(char::is_ascii_digit).collect(),
+    };
+    normalized.parse().ok().filter(|v: &f64| *v > 0.0)
+}
+
+/// ISO 4217 code for a currency symbol or code
+fn currency_code(symbol: &str) -> Option<String> {
+    let code = match symbol {
+        "$" | "US$" => "USD",
+        "CA$" => "CAD",
+        "A$" => "AUD",
+        "€" => "EUR",
+        "£" => "GBP",
+        "¥" => "JPY",
+        "₹" => "INR",
+        code if code.len() == 3 => code,
+        _ => return None,
+    };
+    Some(code.to_string())
+}
+
+fn date_regex() -> &'static Regex {
+    static DATE: OnceLock<Regex> = OnceLock::new();
+    DATE.get_or_init(|| {
+        Regex::new(
+            r"\d{4}-\d{2}-\d{2}|[A-Z][a-z]{2,8}\.? \d{1,2},? \d{4}|\d{1,2} [A-Z][a-z]{2,8}\.? \d{4}|\d{1,2}/\d{1,2}/\d{4}",
+        )
+        .unwrap()
+    })
+}
+
+/// Find a labelled transaction date ("Date paid: June 3, 2024").
+///
+/// Dates more than a year before `sent`, or after it, are ignored.
+pub fn find_date(text: &str, sent: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    const LABELS: &[&str] = &[
+        "date paid",
+        "paid on",
+        "invoice date",
+        "order date",
+        "billed on",
+        "date of issue",
+        "receipt date",
+        "transaction date",
+        "date:",
+    ];
+    const FORMATS: &[&str] = &[
+        "%Y-%m-%d",
+        "%B %d, %Y",
+        "%B %d %Y",
+        "%b %d, %Y",
+        "%b %d %Y",
+        "%d %B %Y",
+        "%d %b %Y",
+        "%m/%d/%Y",
+    ];
+
+    text.lines()
+        .filter(|line| {
+            let line = line.to_lowercase();
+            LABELS.iter().any(|label| line.contains(label))
+        })
+        .flat_map(|line| date_regex().find_iter(line))
+        .find_map(|m| {
+            let value = m.as_str().replace('.', "");
+            let date = FORMATS
+                .iter()
+                .find_map(|format| NaiveDate::parse_from_str(&value, format).ok())?;
+            // Noon UTC keeps the calendar date in any user timezone
+            let date = date.and_hms_opt(12, 0, 0)?.and_utc();
+            (date <= sent + Duration::days(1) && date >= sent - Duration::days(366)).then_some(date)
+        })
+}
+
+/// Parse a "YYYY-MM" month into its first day and the first day of the next month
+pub fn parse_month(month: &str) -> Option<(NaiveDate, NaiveDate)> {
+    let start = NaiveDate::parse_from_str(&format!("{}-01", month.trim()), "%Y-%m-%d").ok()?;
+    let end = start.checked_add_months(chrono::Months::new(1))?;
+    Some((start, end))
+}
+
+/// Total spent in one currency, per month and/or merchant when grouped by them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpendingRow {
+    /// Month ("YYYY-MM", in the user's timezone) when grouped by month
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub month: Option<String>,
+
+    /// Merchant when grouped by merchant
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merchant: Option<String>,
+
+    /// ISO 4217 currency code, or "unknown"
+    pub currency: String,
+
+    /// Sum of the receipts' amounts, rounded to cents
+    pub total: f64,
+
+    /// Number of receipts summed
+    pub count: usize,
+}
+
+/// Total receipts by month, merchant, both, or neither (one row per currency)
+///
+/// Receipts without an amount are left out. Amounts in different currencies
+/// are never added together. Merchants are grouped case-insensitively. Rows
+/// are ordered by month, then largest total first.
+pub fn spending_report(
+    receipts: &[Receipt],
+    by_month: bool,
+    by_merchant: bool,
+    tz: Tz,
+) -> Vec<SpendingRow> {
+    let mut groups: HashMap<(Option<String>, Option<String>, String), SpendingRow> = HashMap::new();
+    for receipt in receipts {
+        let Some(amount) = receipt.amount else {
+            continue;
+        };
+        let month = by_month.then(|| receipt.date.with_timezone(&tz).format("%Y-%m").to_string());
+        let merchant = by_merchant.then(|| receipt.merchant.clone());
+        let currency = receipt
+            .currency
+            .clone()
+            .unwrap_or_else(|| "unknown".to_string());
+        let key = (
+            month.clone(),
+            merchant.as_ref().map(|m| m.to_lowercase()),
+            currency.clone(),
+        );
+        let row = groups.entry(key).or_insert_with(|| SpendingRow {
+            month,
+            merchant,
+            currency,
+            total: 0.0,
+            count: 0,
+        });
+        row.total += amount;
+        row.count += 1;
+    }
+
+    let mut rows: Vec<SpendingRow> = groups
+        .into_values()
+        .map(|mut row| {
+            row.total = (row.total * 100.0).round() / 100.0;
+            row
+        })
+        .collect();
+    rows.sort_by(|a, b| {
+        a.month
+            .cmp(&b.month)
+            .then(b.total.total_cmp(&a.total))
+            .then_with(|| a.merchant.cmp(&b.merchant))
+            .then_with(|| a.currency.cmp(&b.currency))
+    });
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Address;
+    use chrono::TimeZone;
+
+    fn email(from_name: Option<&str>, from_email: &str, subject: &str, body: &str) -> Email {
+        Email {
+            from: Address {
+                name: from_name.map(String::from),
+                email: from_email.to_string(),
+            },
+            date: Utc.with_ymd_and_hms(2024, 6, 4, 9, 30, 0).unwrap(),
+            body_plain: body.to_string(),
+            ..Email::test("<msg@example.com>", from_email, subject)
+        }
+    }
+
+    #[test]
+    fn test_extracts_labelled_total() {
+        let email = email(
+            Some("Acme Cloud Billing"),
+            "billing@acme.io",
+            "Your receipt from Acme Cloud",
+            "Date paid: June 3, 2024\n\nPro plan  $40.00\nSubtotal  $40.00\nTax  $3.50\nTotal\n$43.50\n",
+        );
+        let receipt = extract_receipt(&email).unwrap();
+        assert_eq!(receipt.merchant, "Acme Cloud");
+        assert_eq!(receipt.amount, Some(43.5));
+        assert_eq!(receipt.currency.as_deref(), Some("USD"));
+        assert_eq!(receipt.date.date_naive().to_string(), "2024-06-03");
+        assert_eq!(receipt.source, ReceiptSource::Body);
+    }
+
+    #[test]
+    fn test_european_amounts_and_domain_merchant() {
+        let email = email(
+            None,
+            "rechnung@mail.bahn.de",
+            "Ihre Rechnung / invoice",
+            "Gesamtbetrag (total): 1.234,56 EUR",
+        );
+        let receipt = extract_receipt(&email).unwrap();
+        assert_eq!(receipt.merchant, "Bahn");
+        assert_eq!(receipt.amount, Some(1234.56));
+        assert_eq!(receipt.currency.as_deref(), Some("EUR"));
+    }
+
+    #[test]
+    fn test_skips_non_receipts() {
+        // No amount and no receipt attachment
+        assert!(extract_receipt(&email(
+            Some("Shop"),
+            "orders@shop.example",
+            "Your order has shipped",
+            "Track your package"
+        ))
+        .is_none());
+        // Not a receipt at all
+        assert!(extract_receipt(&email(
+            Some("Alice"),
+            "alice@example.com",
+            "Dinner",
+            "I owe you $20"
+        ))
+        .is_none());
+        // Transactional but never a charge
+        assert!(extract_receipt(&email(
+            Some("Bank"),
+            "billing@bank.example",
+            "Reset your password",
+            "Your balance is $1,000.00"
+        ))
+        .is_none());
+    }
+
+    #[test]
+    fn test_receipt_attachment_keeps_candidate_without_total() {
+        let mut email = email(
+            Some("Vendor Inc"),
+            "accounts@vendor.example",
+            "Documents for June",
+            "Please find attached.",
+        );
+        email.attachments.push(Attachment::new(
+            "a1",
+            "Invoice-1042.pdf",
+            "application/pdf",
+            2048,
+        ));
+        let receipt = extract_receipt(&email).unwrap();
+        assert_eq!(receipt.merchant, "Vendor Inc");
+        assert_eq!(receipt.amount, None);
+    }
+
+    #[test]
+    fn test_parse_amount() {
+        assert_eq!(parse_amount("1,234.56"), Some(1234.56));
+        assert_eq!(parse_amount("1.234,56"), Some(1234.56));
+        assert_eq!(parse_amount("12,5"), Some(12.5));
+        assert_eq!(parse_amount("1,234"), Some(1234.0));
+        assert_eq!(parse_amount("0"), None);
+    }
+
+    #[test]
+    fn test_spending_report_groups() {
+        let receipt =
+            |merchant: &str, amount: Option<f64>, currency: &str, day: (u32, u32)| Receipt {
+                id: String::new(),
+                account_id: "me@example.com".to_string(),
+                merchant: merchant.to_string(),
+                amount,
+                currency: Some(currency.to_string()),
+                date: Utc.with_ymd_and_hms(2024, day.0, day.1, 12, 0, 0).unwrap(),
+                subject: String::new(),
+                sender: String::new(),
+                source: ReceiptSource::Body,
+            };
+        let receipts = vec![
+            receipt("Uber", Some(12.10), "USD", (5, 3)),
+            receipt("uber", Some(20.25), "USD", (6, 1)),
+            receipt("AWS", Some(43.5), "USD", (6, 2)),
+            receipt("Bahn", Some(59.9), "EUR", (6, 9)),
+            receipt("Vendor", None, "USD", (6, 9)),
+        ];
+
+        let totals = spending_report(&receipts, false, false, Tz::UTC);
+        assert_eq!(totals.len(), 2);
+        assert_eq!(
+            (totals[0].currency.as_str(), totals[0].total),
+            ("USD", 75.85)
+        );
+        assert_eq!(totals[0].count, 3);
+
+        let by_merchant = spending_report(&receipts, false, true, Tz::UTC);
+        let uber = by_merchant
+            .iter()
+            .find(|r| r.merchant.as_deref() == Some("Uber"))
+            .unwrap();
+        assert_eq!((uber.total, uber.count), (32.35, 2));
+
+        let by_both = spending_report(&receipts, true, true, Tz::UTC);
+        let rows: Vec<(&str, &str)> = by_both
+            .iter()
+            .map(|r| (r.month.as_deref().unwrap(), r.merchant.as_deref().unwrap()))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                ("2024-05", "Uber"),
+                ("2024-06", "Bahn"),
+                ("2024-06", "AWS"),
+                ("2024-06", "uber"),
+            ]
+        );
+
+        // Months follow the user's timezone
+        let mut late = receipts[0].clone();
+        late.date = Utc.with_ymd_and_hms(2024, 5, 31, 20, 0, 0).unwrap();
+        let tokyo = spending_report(&[late], true, false, chrono_tz::Asia::Tokyo);
+        assert_eq!(tokyo[0].month.as_deref(), Some("2024-06"));
+    }
+
+    #[test]
+    fn test_parse_month() {
+        let (start, end) = parse_month("2024-12").unwrap();
+        assert_eq!(start, NaiveDate::from_ymd_opt(2024, 12, 1).unwrap());
+        assert_eq!(end, NaiveDate::from_ymd_opt(2025, 1, 1).unwrap());
+        assert!(parse_month("2024-13").is_none());
+        assert!(parse_month("June").is_none());
+    }
+}
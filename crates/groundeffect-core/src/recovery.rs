@@ -0,0 +1,427 @@
+//! Startup consistency scan
+//!
+//! A crash, a clock jump or a restored backup can leave the database out of
+//! step with itself: an account's sync cursors no longer match the mail and
+//! events actually stored, full-text indexes miss rows added since they were
+//! built, or emails were stored without embeddings while the embedding
+//! provider was down. On start the daemon compares the cursors with the
+//! stored data and fixes them before syncing ([`check_cursors`]), checks
+//! full-text index coverage ([`check_fts`]), and schedules index rebuilds
+//! and re-embedding in the background. The findings are logged and kept in
+//! `recovery_report.json`, which `groundeffect sync status --verbose` shows.
+
+use std::path::Path;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::Account;
+
+/// How far ahead of the clock a cursor may be before it counts as in the
+/// future (allows for small clock corrections)
+const FUTURE_SLACK_MINUTES: i64 = 5;
+
+/// Oldest and newest stored dates
+pub type Boundaries = (Option<DateTime<Utc>>, Option<DateTime<Utc>>);
+
+/// What kind of inconsistency was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// A last-sync time lies in the future, so incremental syncs would skip mail
+    FutureCursor,
+    /// Cursors are set but nothing is stored (the data was deleted or lost)
+    CursorWithoutData,
+    /// The recorded oldest synced date doesn't match the oldest stored row
+    CursorMismatch,
+    /// A full-text index doesn't exist
+    FtsIndexMissing,
+    /// A full-text index doesn't cover every row
+    FtsIndexStale,
+    /// Rows stored without an embedding, invisible to semantic search
+    MissingEmbeddings,
+}
+
+/// Where a repair stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RepairStatus {
+    Repaired,
+    Scheduled,
+    Failed,
+    /// Can't be repaired right now (e.g. no embedding provider)
+    Skipped,
+}
+
+/// One inconsistency and what was done about it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Issue {
+    /// The account it concerns (None for database-wide issues)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<String>,
+
+    pub kind: IssueKind,
+
+    pub detail: String,
+
+    /// The repair, e.g. "reset to 2024-06-03"
+    pub action: String,
+
+    pub status: RepairStatus,
+}
+
+impl Issue {
+    fn account(account_id: &str, kind: IssueKind, detail: String, action: String) -> Self {
+        Self {
+            account_id: Some(account_id.to_string()),
+            kind,
+            detail,
+            action,
+            status: RepairStatus::Repaired,
+        }
+    }
+}
+
+/// Rows a full-text index covers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FtsCoverage {
+    pub table: &'static str,
+    pub column: &'static str,
+
+    /// Rows in the index (None when there is no index)
+    pub indexed_rows: Option<usize>,
+
+    /// Rows the index doesn't cover
+    pub unindexed_rows: usize,
+}
+
+/// The last startup scan, persisted by the daemon
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecoveryReport {
+    /// When the scan ran (None before the first one)
+    pub checked_at: Option<DateTime<Utc>>,
+
+    pub issues: Vec<Issue>,
+}
+
+impl RecoveryReport {
+    /// An empty report for a scan at `now`
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            checked_at: Some(now),
+            issues: Vec::new(),
+        }
+    }
+
+    /// Load the report from disk (default if missing or unreadable)
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// Write the report to disk
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, json)
+    }
+
+    /// Whether any repair is waiting to run
+    pub fn has_scheduled(&self) -> bool {
+        self.issues
+            .iter()
+            .any(|i| i.status == RepairStatus::Scheduled)
+    }
+
+    /// Set the status of every scheduled repair of these kinds
+    pub fn finish(&mut self, kinds: &[IssueKind], status: RepairStatus) {
+        for issue in &mut self.issues {
+            if kinds.contains(&issue.kind) && issue.status == RepairStatus::Scheduled {
+                issue.status = status;
+            }
+        }
+    }
+
+    /// One-line summary for logs and `sync status`
+    pub fn summary(&self) -> String {
+        if self.issues.is_empty() {
+            return "no issues".to_string();
+        }
+        let count = |status| self.issues.iter().filter(|i| i.status == status).count();
+        let mut parts = Vec::new();
+        for (status, label) in [
+            (RepairStatus::Repaired, "repaired"),
+            (RepairStatus::Scheduled, "scheduled"),
+            (RepairStatus::Failed, "failed"),
+            (RepairStatus::Skipped, "skipped"),
+        ] {
+            let n = count(status);
+            if n > 0 {
+                parts.push(format!("{} {}", n, label));
+            }
+        }
+        format!("{} issues ({})", self.issues.len(), parts.join(", "))
+    }
+}
+
+/// Compare an account's sync cursors with the dates actually stored and fix
+/// them in place; returns what was wrong
+///
+/// A last-sync time in the future is pulled back to the newest stored email
+/// (or cleared for calendars, whose events run into the future), cursors
+/// with nothing stored behind them are cleared, and the oldest synced dates
+/// are set to the oldest stored rows.
+pub fn check_cursors(
+    account: &mut Account,
+    emails: Boundaries,
+    events: Boundaries,
+    now: DateTime<Utc>,
+) -> Vec<Issue> {
+    let id = account.id.clone();
+    let mut issues = Vec::new();
+    let future =
+        |t: Option<DateTime<Utc>>| t.filter(|t| *t > now + Duration::minutes(FUTURE_SLACK_MINUTES));
+    let day = |t: Option<DateTime<Utc>>| match t {
+        Some(t) => t.format("%Y-%m-%d").to_string(),
+        None => "none".to_string(),
+    };
+
+    // Emails
+    let (oldest_email, newest_email) = emails;
+    if let Some(last) = future(account.last_sync_email) {
+        issues.push(Issue::account(
+            &id,
+            IssueKind::FutureCursor,
+            format!("last email sync {} is in the future", last.to_rfc3339()),
+            format!("reset to the newest stored email ({})", day(newest_email)),
+        ));
+        account.last_sync_email = newest_email;
+    }
+    if oldest_email.is_none() {
+        // Set only once emails were stored; a window with no mail in it
+        // leaves it unset
+        if let Some(oldest) = account.oldest_email_synced {
+            issues.push(Issue::account(
+                &id,
+                IssueKind::CursorWithoutData,
+                format!(
+                    "emails were synced back to {} but none are stored",
+                    day(Some(oldest))
+                ),
+                "cleared, so the next sync starts fresh".to_string(),
+            ));
+            account.last_sync_email = None;
+            account.oldest_email_synced = None;
+        }
+    } else if day(account.oldest_email_synced) != day(oldest_email) {
+        issues.push(Issue::account(
+            &id,
+            IssueKind::CursorMismatch,
+            format!(
+                "oldest email synced is recorded as {} but the oldest stored is {}",
+                day(account.oldest_email_synced),
+                day(oldest_email)
+            ),
+            format!("set to {}", day(oldest_email)),
+        ));
+        account.oldest_email_synced = oldest_email;
+    }
+
+    // Events
+    let (oldest_event, _) = events;
+    if let Some(last) = future(account.last_sync_calendar) {
+        issues.push(Issue::account(
+            &id,
+            IssueKind::FutureCursor,
+            format!("last calendar sync {} is in the future", last.to_rfc3339()),
+            "cleared, so the next calendar sync is a full one".to_string(),
+        ));
+        account.last_sync_calendar = None;
+    }
+    if oldest_event.is_none() {
+        if let Some(oldest) = account.oldest_event_synced {
+            issues.push(Issue::account(
+                &id,
+                IssueKind::CursorWithoutData,
+                format!(
+                    "events were synced back to {} but none are stored",
+                    day(Some(oldest))
+                ),
+                "cleared, so the next sync starts fresh".to_string(),
+            ));
+            account.last_sync_calendar = None;
+            account.oldest_event_synced = None;
+        }
+    } else if day(account.oldest_event_synced) != day(oldest_event) {
+        issues.push(Issue::account(
+            &id,
+            IssueKind::CursorMismatch,
+            format!(
+                "oldest event synced is recorded as {} but the oldest stored is {}",
+                day(account.oldest_event_synced),
+                day(oldest_event)
+            ),
+            format!("set to {}", day(oldest_event)),
+        ));
+        account.oldest_event_synced = oldest_event;
+    }
+
+    issues
+}
+
+/// Full-text indexes that are missing or don't cover every row; their
+/// repair (a rebuild) is scheduled
+pub fn check_fts(coverage: &[FtsCoverage]) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    for index in coverage {
+        if index.unindexed_rows == 0 {
+            continue;
+        }
+        let (kind, detail) = match index.indexed_rows {
+            None => (
+                IssueKind::FtsIndexMissing,
+                format!(
+                    "{}.{} has no full-text index ({} rows)",
+                    index.table, index.column, index.unindexed_rows
+                ),
+            ),
+            Some(indexed) => (
+                IssueKind::FtsIndexStale,
+                format!(
+                    "{}.{} full-text index misses {} of {} rows",
+                    index.table,
+                    index.column,
+                    index.unindexed_rows,
+                    indexed + index.unindexed_rows
+                ),
+            ),
+        };
+        issues.push(Issue {
+            account_id: None,
+            kind,
+            detail,
+            action: "rebuild full-text indexes".to_string(),
+            status: RepairStatus::Scheduled,
+        });
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 6, day, 9, 0, 0).unwrap()
+    }
+
+    fn kinds(issues: &[Issue]) -> Vec<IssueKind> {
+        issues.iter().map(|i| i.kind).collect()
+    }
+
+    #[test]
+    fn test_consistent_cursors_are_left_alone() {
+        let mut account = Account::new("me@example.com".to_string(), "Me".to_string());
+        account.last_sync_email = Some(at(10));
+        account.oldest_email_synced = Some(at(1));
+        account.last_sync_calendar = Some(at(10));
+        account.oldest_event_synced = Some(at(2));
+        let before = account.clone();
+
+        let issues = check_cursors(
+            &mut account,
+            (Some(at(1) + Duration::hours(3)), Some(at(9))),
+            (Some(at(2)), Some(at(30))),
+            at(10),
+        );
+        assert!(issues.is_empty());
+        assert_eq!(account.oldest_email_synced, before.oldest_email_synced);
+
+        // Nor are an account with no mail in its window and one that never synced
+        let mut empty = Account::new("empty@example.com".to_string(), "Empty".to_string());
+        empty.last_sync_email = Some(at(10));
+        assert!(check_cursors(&mut empty, (None, None), (None, None), at(10)).is_empty());
+        let mut fresh = Account::new("new@example.com".to_string(), "New".to_string());
+        assert!(check_cursors(&mut fresh, (None, None), (None, None), at(10)).is_empty());
+    }
+
+    #[test]
+    fn test_fixes_cursors_that_disagree_with_the_data() {
+        let mut account = Account::new("me@example.com".to_string(), "Me".to_string());
+        account.last_sync_email = Some(at(20));
+        account.oldest_email_synced = None;
+        account.last_sync_calendar = Some(at(9));
+        account.oldest_event_synced = Some(at(1));
+
+        let issues = check_cursors(
+            &mut account,
+            (Some(at(3)), Some(at(9))),
+            (None, None),
+            at(10),
+        );
+        assert_eq!(
+            kinds(&issues),
+            vec![
+                IssueKind::FutureCursor,
+                IssueKind::CursorMismatch,
+                IssueKind::CursorWithoutData
+            ]
+        );
+        assert!(issues.iter().all(|i| i.status == RepairStatus::Repaired));
+        assert_eq!(issues[1].action, "set to 2024-06-03");
+        assert_eq!(account.last_sync_email, Some(at(9)));
+        assert_eq!(account.oldest_email_synced, Some(at(3)));
+        assert_eq!(account.last_sync_calendar, None);
+        assert_eq!(account.oldest_event_synced, None);
+    }
+
+    #[test]
+    fn test_fts_issues_and_report_summary() {
+        let coverage = [
+            FtsCoverage {
+                table: "emails",
+                column: "subject",
+                indexed_rows: Some(100),
+                unindexed_rows: 0,
+            },
+            FtsCoverage {
+                table: "emails",
+                column: "body_plain",
+                indexed_rows: Some(90),
+                unindexed_rows: 10,
+            },
+            FtsCoverage {
+                table: "threads",
+                column: "content",
+                indexed_rows: None,
+                unindexed_rows: 40,
+            },
+        ];
+        let mut report = RecoveryReport::new(at(10));
+        report.issues = check_fts(&coverage);
+        assert_eq!(
+            kinds(&report.issues),
+            vec![IssueKind::FtsIndexStale, IssueKind::FtsIndexMissing]
+        );
+        assert_eq!(
+            report.issues[0].detail,
+            "emails.body_plain full-text index misses 10 of 100 rows"
+        );
+        assert!(report.has_scheduled());
+        assert_eq!(report.summary(), "2 issues (2 scheduled)");
+
+        report.finish(
+            &[IssueKind::FtsIndexMissing, IssueKind::FtsIndexStale],
+            RepairStatus::Repaired,
+        );
+        assert!(!report.has_scheduled());
+        assert_eq!(report.summary(), "2 issues (2 repaired)");
+        assert_eq!(RecoveryReport::default().summary(), "no issues");
+    }
+}
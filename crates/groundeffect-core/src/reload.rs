@@ -0,0 +1,194 @@
+//! Applying config changes to a running daemon
+//!
+//! The daemon re-reads its settings when config.toml changes (checked every
+//! [`WATCH_INTERVAL_SECS`]) or when `groundeffect daemon reload` asks over
+//! the control socket. Settings in [`RELOADABLE`] take effect right away:
+//! poll timers restart with the new intervals, new fetches use the new rate
+//! and concurrency limits, and the log filter is swapped. Fetches already
+//! running finish under the old limits. Any other change is logged and
+//! waits for a restart.
+
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::config_layers::changed_keys;
+use crate::error::{Error, Result};
+
+/// How often the daemon checks config.toml for changes
+pub const WATCH_INTERVAL_SECS: u64 = 5;
+
+/// Settings the daemon applies without a restart
+pub const RELOADABLE: &[&str] = &[
+    "general.log_level",
+    "sync.email_poll_interval_secs",
+    "sync.calendar_poll_interval_secs",
+    "sync.max_concurrent_fetches",
+    "sync.attachment_max_size_mb",
+    "sync.rate_limit_per_second",
+];
+
+/// What a reload changed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReloadReport {
+    /// Changed settings now in effect
+    pub applied: Vec<String>,
+
+    /// Changed settings that only take effect after a restart
+    pub restart_required: Vec<String>,
+}
+
+impl ReloadReport {
+    /// Nothing changed
+    pub fn is_empty(&self) -> bool {
+        self.applied.is_empty() && self.restart_required.is_empty()
+    }
+}
+
+/// Sort the settings changed between `old` and `new` into those the daemon
+/// can apply and those that need a restart
+pub fn plan(old: &Config, new: &Config) -> Result<ReloadReport> {
+    let mut report = ReloadReport::default();
+    for key in changed_keys(old, new)? {
+        if RELOADABLE.contains(&key.as_str()) {
+            report.applied.push(key);
+        } else {
+            report.restart_required.push(key);
+        }
+    }
+    Ok(report)
+}
+
+/// `running` with the [`RELOADABLE`] settings taken from `new`: the
+/// settings in effect after a reload
+pub fn merge(running: &Config, new: &Config) -> Config {
+    let mut merged = running.clone();
+    merged.general.log_level = new.general.log_level.clone();
+    merged.sync.email_poll_interval_secs = new.sync.email_poll_interval_secs;
+    merged.sync.calendar_poll_interval_secs = new.sync.calendar_poll_interval_secs;
+    merged.sync.max_concurrent_fetches = new.sync.max_concurrent_fetches;
+    merged.sync.attachment_max_size_mb = new.sync.attachment_max_size_mb;
+    merged.sync.rate_limit_per_second = new.sync.rate_limit_per_second;
+    merged
+}
+
+/// Load the settings for a reload, refusing ones that can't be used
+pub fn load() -> Result<Config> {
+    let config = Config::load()?;
+    let problems: Vec<String> = config
+        .problems()
+        .into_iter()
+        .map(|(key, problem)| format!("{} {}", key, problem))
+        .collect();
+    if !problems.is_empty() {
+        return Err(Error::Config(format!(
+            "Not reloading: {}",
+            problems.join("; ")
+        )));
+    }
+    Ok(config)
+}
+
+/// Notices when a file is written, replaced or removed
+#[derive(Debug)]
+pub struct ConfigWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl ConfigWatcher {
+    /// Watch `path` from its current state
+    pub fn new(path: PathBuf) -> Self {
+        let modified = modified(&path);
+        Self { path, modified }
+    }
+
+    /// Whether the file changed since the last call (or since [`ConfigWatcher::new`])
+    pub fn changed(&mut self) -> bool {
+        let modified = modified(&self.path);
+        if modified == self.modified {
+            return false;
+        }
+        self.modified = modified;
+        true
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_plan_splits_reloadable_settings() {
+        let old = Config::default();
+        let mut new = old.clone();
+        new.sync.email_poll_interval_secs = 60;
+        new.general.log_level = "debug".to_string();
+        new.search.embedding_model = "bge-small-en".to_string();
+        new.accounts
+            .aliases
+            .insert("work".to_string(), "me@example.com".to_string());
+
+        let report = plan(&old, &new).unwrap();
+        assert_eq!(
+            report.applied,
+            vec!["general.log_level", "sync.email_poll_interval_secs"]
+        );
+        assert_eq!(
+            report.restart_required,
+            vec!["accounts.aliases.work", "search.embedding_model"]
+        );
+        assert!(plan(&old, &old).unwrap().is_empty());
+
+        // Settings that need a restart stay pending until there is one
+        let running = merge(&old, &new);
+        let again = plan(&running, &new).unwrap();
+        assert!(again.applied.is_empty());
+        assert_eq!(again.restart_required, report.restart_required);
+    }
+
+    #[test]
+    fn test_merge_takes_every_reloadable_setting() {
+        let old = Config::default();
+        let mut new = old.clone();
+        new.general.log_level = "warn".to_string();
+        new.sync.email_poll_interval_secs += 1;
+        new.sync.calendar_poll_interval_secs += 1;
+        new.sync.max_concurrent_fetches += 1;
+        new.sync.attachment_max_size_mb += 1;
+        new.sync.rate_limit_per_second += 1;
+        assert_eq!(plan(&old, &new).unwrap().applied.len(), RELOADABLE.len());
+        assert!(plan(&merge(&old, &new), &new).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_watcher_notices_writes_and_removal() {
+        let path = std::env::temp_dir().join(format!("ge-reload-{}.toml", uuid::Uuid::new_v4()));
+        let mut watcher = ConfigWatcher::new(path.clone());
+        assert!(!watcher.changed());
+
+        std::fs::write(&path, "[sync]\n").unwrap();
+        assert!(watcher.changed());
+        assert!(!watcher.changed());
+
+        let later = SystemTime::now() + Duration::from_secs(10);
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        assert!(watcher.changed());
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(watcher.changed());
+        assert!(!watcher.changed());
+    }
+}
@@ -0,0 +1,279 @@
+//! Moving the data directory
+//!
+//! `groundeffect config relocate --data-dir <path>` moves the database,
+//! attachments, models and daemon state somewhere else (e.g. an external
+//! volume). When both paths are on the same volume the directory is renamed;
+//! otherwise it is copied, the copy is compared with the original, and only
+//! then is the original removed. Sockets and other special files (a stale
+//! `daemon.sock`) are left behind; the daemon recreates them.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::error::{Error, Result};
+
+/// Files and bytes under a directory
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct DirSummary {
+    pub files: u64,
+    pub bytes: u64,
+}
+
+/// How the data was moved
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MoveMethod {
+    /// Same volume: a single rename
+    Rename,
+    /// Different volume: copied, verified, then the original removed
+    Copy,
+}
+
+/// Count the regular files (and symlinks) under `dir` and their sizes
+pub fn summarize(dir: &Path) -> Result<DirSummary> {
+    let mut summary = DirSummary::default();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            let inner = summarize(&entry.path())?;
+            summary.files += inner.files;
+            summary.bytes += inner.bytes;
+        } else if file_type.is_file() || file_type.is_symlink() {
+            summary.files += 1;
+            summary.bytes += entry.metadata()?.len();
+        }
+    }
+    Ok(summary)
+}
+
+/// Check that `from` can be moved to `to`: `from` is a directory, `to` is
+/// missing or an empty directory, and neither contains the other
+pub fn validate(from: &Path, to: &Path) -> Result<()> {
+    if !from.is_dir() {
+        return Err(Error::Config(format!(
+            "Data directory {} does not exist",
+            from.display()
+        )));
+    }
+    if !to.is_absolute() {
+        return Err(Error::Config(format!(
+            "New data directory must be an absolute path: {}",
+            to.display()
+        )));
+    }
+    let from_real = fs::canonicalize(from)?;
+    let to_real = canonicalize_missing(to);
+    if from_real == to_real {
+        return Err(Error::Config(format!(
+            "Data is already in {}",
+            from.display()
+        )));
+    }
+    if to_real.starts_with(&from_real) || from_real.starts_with(&to_real) {
+        return Err(Error::Config(format!(
+            "{} and {} are nested; pick a directory outside the current one",
+            from.display(),
+            to.display()
+        )));
+    }
+    if to.exists() {
+        let empty_dir = to.is_dir() && fs::read_dir(to)?.next().is_none();
+        if !empty_dir {
+            return Err(Error::Config(format!(
+                "{} already exists and is not an empty directory",
+                to.display()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Canonicalize the longest existing ancestor, so paths that don't exist
+/// yet still compare correctly through symlinks (e.g. /tmp on macOS)
+fn canonicalize_missing(path: &Path) -> PathBuf {
+    let mut missing = Vec::new();
+    let mut current = path;
+    loop {
+        if let Ok(real) = fs::canonicalize(current) {
+            return missing
+                .iter()
+                .rev()
+                .fold(real, |dir: PathBuf, name| dir.join(name));
+        }
+        match (current.parent(), current.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name.to_os_string());
+                current = parent;
+            }
+            _ => return path.to_path_buf(),
+        }
+    }
+}
+
+/// Move `from` to `to` (see the module docs); validates first
+pub fn move_dir(from: &Path, to: &Path) -> Result<MoveMethod> {
+    validate(from, to)?;
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if to.exists() {
+        // Empty (checked above); rename needs the target to be absent
+        fs::remove_dir(to)?;
+    }
+
+    if fs::rename(from, to).is_ok() {
+        return Ok(MoveMethod::Rename);
+    }
+
+    let expected = summarize(from)?;
+    let copied = copy_dir(from, to).and_then(|_| summarize(to));
+    match copied {
+        Ok(actual) if actual == expected => {
+            fs::remove_dir_all(from)?;
+            Ok(MoveMethod::Copy)
+        }
+        Ok(actual) => {
+            let _ = fs::remove_dir_all(to);
+            Err(Error::Other(format!(
+                "Copy to {} is incomplete ({} of {} files, {} of {} bytes); nothing was removed",
+                to.display(),
+                actual.files,
+                expected.files,
+                actual.bytes,
+                expected.bytes
+            )))
+        }
+        Err(e) => {
+            let _ = fs::remove_dir_all(to);
+            Err(e)
+        }
+    }
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let target = to.join(entry.file_name());
+        if file_type.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else if file_type.is_symlink() {
+            copy_symlink(&entry.path(), &target)?;
+        } else if file_type.is_file() {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_symlink(link: &Path, target: &Path) -> Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(link)?, target)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(link: &Path, target: &Path) -> Result<()> {
+    fs::copy(link, target)?;
+    Ok(())
+}
+
+/// `path` moved from under `from` to under `to` (None if it wasn't under `from`)
+pub fn rebase(path: &Path, from: &Path, to: &Path) -> Option<PathBuf> {
+    path.strip_prefix(from).ok().map(|rest| to.join(rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "groundeffect-relocate-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn populate(dir: &Path) {
+        fs::create_dir_all(dir.join("lancedb").join("emails.lance")).unwrap();
+        fs::write(
+            dir.join("lancedb").join("emails.lance").join("data"),
+            b"rows",
+        )
+        .unwrap();
+        fs::create_dir_all(dir.join("attachments")).unwrap();
+        fs::write(dir.join("attachments").join("invoice.pdf"), b"%PDF-1.7").unwrap();
+        fs::write(dir.join("daemon_health.json"), b"{}").unwrap();
+    }
+
+    #[test]
+    fn test_move_keeps_every_file() {
+        let root = scratch("move");
+        let from = root.join("old");
+        populate(&from);
+        let before = summarize(&from).unwrap();
+
+        let to = root.join("external").join("ge");
+        move_dir(&from, &to).unwrap();
+        assert!(!from.exists());
+        assert_eq!(summarize(&to).unwrap(), before);
+        assert_eq!(
+            fs::read(to.join("attachments").join("invoice.pdf")).unwrap(),
+            b"%PDF-1.7"
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_copy_matches_original() {
+        let root = scratch("copy");
+        let from = root.join("old");
+        populate(&from);
+        copy_dir(&from, &root.join("copy")).unwrap();
+        assert_eq!(
+            summarize(&from).unwrap(),
+            summarize(&root.join("copy")).unwrap()
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_rejects_unsafe_targets() {
+        let root = scratch("validate");
+        let from = root.join("old");
+        populate(&from);
+
+        assert!(validate(&from, &from).is_err());
+        assert!(validate(&from, &from.join("inner")).is_err());
+        assert!(validate(&from, Path::new("relative/dir")).is_err());
+        assert!(validate(&root.join("missing"), &root.join("new")).is_err());
+
+        let occupied = root.join("occupied");
+        fs::create_dir_all(&occupied).unwrap();
+        fs::write(occupied.join("file"), b"x").unwrap();
+        assert!(validate(&from, &occupied).is_err());
+
+        fs::create_dir_all(root.join("empty")).unwrap();
+        assert!(validate(&from, &root.join("empty")).is_ok());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_rebase() {
+        let from = Path::new("/home/u/.local/share/groundeffect");
+        let to = Path::new("/Volumes/External/ge");
+        assert_eq!(
+            rebase(&from.join("attachments").join("a.pdf"), from, to),
+            Some(to.join("attachments").join("a.pdf"))
+        );
+        assert_eq!(rebase(Path::new("/tmp/a.pdf"), from, to), None);
+    }
+}
@@ -0,0 +1,354 @@
+//! Changing an account's address
+//!
+//! Rows are keyed by the account's email address: `account_id` columns, and
+//! email and thread IDs of the form `{account_id}:...` that the IDs of
+//! activity items, receipts, itineraries, extractions and tasks build on.
+//! When a Google account's primary address changes (a domain migration),
+//! `groundeffect account rename old@x.com new@y.com` rewrites all of them
+//! in one [`crate::db::WriteTransaction`] — a failure part way through puts
+//! every table back — then moves the account's OAuth tokens, downloaded
+//! attachments, stored raw messages and caches, and the address in
+//! `config.toml`. The audit log keeps the old address: it records what
+//! happened under it.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use arrow_array::{Array, ArrayRef, RecordBatch, StringArray};
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::error::Result;
+use crate::models::Attachment;
+use crate::raw::raw_path_for;
+
+/// How a string column changes when its account is renamed
+#[derive(Debug, Clone)]
+pub enum Rewrite {
+    /// Holds the account address itself
+    Account,
+    /// An ID starting with `{account_id}:`
+    Id,
+    /// A JSON array of such IDs
+    IdList,
+    /// A JSON list of attachments whose downloaded files move from one
+    /// directory to another
+    AttachmentPaths { from: PathBuf, to: PathBuf },
+}
+
+impl Rewrite {
+    /// `value` after the account `old` became `new`; values that don't
+    /// belong to `old` are kept
+    pub fn apply(&self, value: &str, old: &str, new: &str) -> String {
+        let rewritten = match self {
+            Rewrite::Account => (value == old).then(|| new.to_string()),
+            Rewrite::Id => rename_id(value, old, new),
+            Rewrite::IdList => serde_json::from_str::<Vec<String>>(value)
+                .ok()
+                .and_then(|ids| {
+                    let ids: Vec<String> = ids
+                        .iter()
+                        .map(|id| rename_id(id, old, new).unwrap_or_else(|| id.clone()))
+                        .collect();
+                    serde_json::to_string(&ids).ok()
+                }),
+            Rewrite::AttachmentPaths { from, to } => serde_json::from_str::<Vec<Attachment>>(value)
+                .ok()
+                .and_then(|mut attachments| {
+                    for attachment in &mut attachments {
+                        let rebased = attachment
+                            .local_path
+                            .as_deref()
+                            .and_then(|path| crate::relocate::rebase(path, from, to));
+                        if rebased.is_some() {
+                            attachment.local_path = rebased;
+                        }
+                    }
+                    serde_json::to_string(&attachments).ok()
+                }),
+        };
+        rewritten.unwrap_or_else(|| value.to_string())
+    }
+}
+
+/// The ID an email, thread or derived row gets when account `old` becomes
+/// `new`, or None if it isn't one of `old`'s
+pub fn rename_id(id: &str, old: &str, new: &str) -> Option<String> {
+    if id == old {
+        return Some(new.to_string());
+    }
+    id.strip_prefix(old)
+        .filter(|rest| rest.starts_with(':'))
+        .map(|rest| format!("{}{}", new, rest))
+}
+
+/// `batch` with `columns` rewritten; columns it doesn't have are skipped
+pub fn rewrite_batch(
+    batch: &RecordBatch,
+    columns: &[(&str, Rewrite)],
+    old: &str,
+    new: &str,
+) -> Result<RecordBatch> {
+    let schema = batch.schema();
+    let mut arrays: Vec<ArrayRef> = batch.columns().to_vec();
+    for (name, rewrite) in columns {
+        let Ok(index) = schema.index_of(name) else {
+            continue;
+        };
+        let Some(values) = arrays[index].as_any().downcast_ref::<StringArray>() else {
+            continue;
+        };
+        let rewritten: StringArray = values
+            .iter()
+            .map(|value| value.map(|v| rewrite.apply(v, old, new)))
+            .collect();
+        arrays[index] = Arc::new(rewritten);
+    }
+    Ok(RecordBatch::try_new(schema, arrays)?)
+}
+
+/// Rows a rename moved
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RenamedRows {
+    /// Rows moved, by table
+    pub tables: BTreeMap<&'static str, usize>,
+
+    /// The account's email IDs after the rename
+    #[serde(skip)]
+    pub email_ids: Vec<String>,
+}
+
+/// What a rename did outside the database
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MovedFiles {
+    /// Downloaded attachments directory moved
+    pub attachments: bool,
+
+    /// Stored raw messages moved
+    pub raw_messages: usize,
+
+    /// Label, availability and directory caches moved
+    pub caches: usize,
+
+    /// Files or directories that couldn't be moved
+    pub failed: Vec<String>,
+}
+
+/// Move the account's files from `old` to `new`
+///
+/// `email_ids` are the account's email IDs after the rename; raw messages
+/// are stored by a hash of the ID, so each one moves to its new name. A
+/// failed move is recorded and the rest carry on: the rows are already
+/// renamed, and attachments and raw messages are fetched again if missing.
+pub fn move_files(config: &Config, old: &str, new: &str, email_ids: &[String]) -> MovedFiles {
+    let mut moved = MovedFiles::default();
+
+    let attachments_dir = config.attachments_dir();
+    match move_path(&attachments_dir.join(old), &attachments_dir.join(new)) {
+        Ok(done) => moved.attachments = done,
+        Err(e) => moved.failed.push(format!("attachments: {}", e)),
+    }
+
+    let raw_dir = config.raw_dir();
+    if raw_dir.join(old).is_dir() {
+        for id in email_ids {
+            let old_id = rename_id(id, new, old).unwrap_or_else(|| id.clone());
+            let from = raw_path_for(&raw_dir, old, &old_id);
+            match move_path(&from, &raw_path_for(&raw_dir, new, id)) {
+                Ok(true) => moved.raw_messages += 1,
+                Ok(false) => {}
+                Err(e) => moved.failed.push(format!("{}: {}", from.display(), e)),
+            }
+        }
+        // Left behind only if something couldn't be moved
+        let _ = fs::remove_dir(raw_dir.join(old));
+    }
+
+    for (from, to) in [
+        (config.label_cache_file(old), config.label_cache_file(new)),
+        (
+            config.availability_cache_file(old),
+            config.availability_cache_file(new),
+        ),
+        (
+            config.directory_cache_file(old),
+            config.directory_cache_file(new),
+        ),
+    ] {
+        match move_path(&from, &to) {
+            Ok(true) => moved.caches += 1,
+            Ok(false) => {}
+            Err(e) => moved.failed.push(format!("{}: {}", from.display(), e)),
+        }
+    }
+
+    moved
+}
+
+/// Rename `from` to `to` if it exists; false if there was nothing to move
+fn move_path(from: &Path, to: &Path) -> std::io::Result<bool> {
+    if !from.exists() {
+        return Ok(false);
+    }
+    if to.exists() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("{} already exists", to.display()),
+        ));
+    }
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(from, to)?;
+    Ok(true)
+}
+
+/// Point the settings that name account `old` by address at `new`:
+/// aliases, per-account settings, and the digest, ICS feed, Apple export
+/// and Spotlight account lists. Returns whether anything changed.
+pub fn rename_in_config(config: &mut Config, old: &str, new: &str) -> bool {
+    let mut changed = false;
+    let mut rename = |value: &mut String| {
+        if value.eq_ignore_ascii_case(old) {
+            *value = new.to_string();
+            changed = true;
+        }
+    };
+
+    for email in config.accounts.aliases.values_mut() {
+        rename(email);
+    }
+    if let Some(account) = config.digest.account.as_mut() {
+        rename(account);
+    }
+    for account in config
+        .ics_feed
+        .accounts
+        .iter_mut()
+        .chain(config.apple.calendar_accounts.iter_mut())
+        .chain(config.apple.contact_accounts.iter_mut())
+        .chain(config.spotlight.accounts.iter_mut())
+    {
+        rename(account);
+    }
+
+    let key = config
+        .accounts
+        .accounts
+        .keys()
+        .find(|key| key.eq_ignore_ascii_case(old))
+        .cloned();
+    if let Some(settings) = key.and_then(|key| config.accounts.accounts.remove(&key)) {
+        config.accounts.accounts.insert(new.to_string(), settings);
+        changed = true;
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_schema::{DataType, Field, Schema};
+
+    #[test]
+    fn test_rename_id_only_touches_the_account() {
+        let (old, new) = ("me@old.com", "me@new.com");
+        assert_eq!(
+            rename_id("me@old.com:<a@b>", old, new).as_deref(),
+            Some("me@new.com:<a@b>")
+        );
+        assert_eq!(
+            rename_id("me@old.com:<a@b>#task0", old, new).as_deref(),
+            Some("me@new.com:<a@b>#task0")
+        );
+        assert_eq!(rename_id("me@old.com", old, new).as_deref(), Some(new));
+        assert_eq!(rename_id("me@old.com.au:<a@b>", old, new), None);
+        assert_eq!(rename_id("you@old.com:<a@b>", old, new), None);
+    }
+
+    #[test]
+    fn test_rewrites_batch_columns() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Utf8, false),
+            Field::new("account_id", DataType::Utf8, false),
+            Field::new("email_ids", DataType::Utf8, false),
+            Field::new("attachments", DataType::Utf8, true),
+        ]));
+        let attachments = serde_json::json!([{
+            "id": "1",
+            "filename": "a.pdf",
+            "mime_type": "application/pdf",
+            "size": 10,
+            "local_path": "/data/attachments/me@old.com/x/a.pdf",
+        }])
+        .to_string();
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(StringArray::from(vec!["me@old.com:42"])),
+                Arc::new(StringArray::from(vec!["me@old.com"])),
+                Arc::new(StringArray::from(vec![
+                    r#"["me@old.com:<a@b>","me@old.com:<c@d>"]"#,
+                ])),
+                Arc::new(StringArray::from(vec![Some(attachments.as_str())])),
+            ],
+        )
+        .unwrap();
+
+        let columns = [
+            ("id", Rewrite::Id),
+            ("account_id", Rewrite::Account),
+            ("email_ids", Rewrite::IdList),
+            (
+                "attachments",
+                Rewrite::AttachmentPaths {
+                    from: PathBuf::from("/data/attachments/me@old.com"),
+                    to: PathBuf::from("/data/attachments/me@new.com"),
+                },
+            ),
+            ("missing", Rewrite::Account),
+        ];
+        let renamed = rewrite_batch(&batch, &columns, "me@old.com", "me@new.com").unwrap();
+        let column = |name: &str| {
+            renamed
+                .column_by_name(name)
+                .unwrap()
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .unwrap()
+                .value(0)
+                .to_string()
+        };
+
+        assert_eq!(column("id"), "me@new.com:42");
+        assert_eq!(column("account_id"), "me@new.com");
+        assert_eq!(
+            column("email_ids"),
+            r#"["me@new.com:<a@b>","me@new.com:<c@d>"]"#
+        );
+        let attachments: Vec<Attachment> = serde_json::from_str(&column("attachments")).unwrap();
+        assert_eq!(
+            attachments[0].local_path.as_deref(),
+            Some(Path::new("/data/attachments/me@new.com/x/a.pdf"))
+        );
+    }
+
+    #[test]
+    fn test_renames_account_in_config() {
+        let mut config = Config::default();
+        config
+            .accounts
+            .aliases
+            .insert("work".to_string(), "Me@Old.com".to_string());
+        config.ics_feed.accounts = vec!["work".to_string(), "me@old.com".to_string()];
+        config.digest.account = Some("other@x.com".to_string());
+        assert!(rename_in_config(&mut config, "me@old.com", "me@new.com"));
+
+        assert_eq!(config.accounts.aliases["work"], "me@new.com");
+        assert_eq!(config.ics_feed.accounts, vec!["work", "me@new.com"]);
+        assert_eq!(config.digest.account.as_deref(), Some("other@x.com"));
+        assert!(!rename_in_config(&mut config, "me@old.com", "me@new.com"));
+    }
+}
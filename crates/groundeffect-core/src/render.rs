@@ -0,0 +1,643 @@
+//! Rendering of email bodies
+//!
+//! [`html_to_markdown`] is the canonical converter for incoming HTML mail. It
+//! is used for indexing, `email show --format markdown`, thread export and
+//! MCP responses, so every surface sees the same text. Before conversion it
+//! drops content the reader never sees (head, styles, hidden preheaders,
+//! tracking pixels) and unwraps tables used for page layout, so only real
+//! data tables become markdown tables.
+//!
+//! Compact rendering deals with the redundancy of long threads: every reply
+//! quotes the message before it, each message carries the same signature,
+//! and HTML-derived text is full of stray whitespace. Compact rendering drops
+//! that redundancy and leaves a bracketed marker wherever content was elided,
+//! so the full body can still be fetched when the elided part matters.
+
+use std::collections::HashSet;
+use std::sync::LazyLock;
+
+use regex::{Captures, Regex};
+use tracing::warn;
+
+use crate::models::{Address, Email};
+
+/// Minimum length of an underscore rule that separates an Outlook reply from
+/// the message it quotes
+const OUTLOOK_RULE_MIN_LEN: usize = 10;
+
+/// Line width for the html2text fallback renderer
+const HTML2TEXT_FALLBACK_WIDTH: usize = 100;
+
+/// HTML comments, including Outlook conditional comments
+static HTML_COMMENT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)<!--.*?-->").unwrap());
+
+/// Elements whose content is never shown to the reader
+static INVISIBLE_ELEMENTS: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    ["head", "style", "script", "title", "template"]
+        .iter()
+        .map(|tag| Regex::new(&format!(r"(?is)<{tag}\b[^>]*>.*?</{tag}\s*>")).unwrap())
+        .collect()
+});
+
+/// Elements hidden with inline CSS (typically the inbox preheader text)
+static HIDDEN_ELEMENTS: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    ["div", "span"]
+        .iter()
+        .map(|tag| {
+            Regex::new(&format!(
+                r#"(?is)<{tag}\b[^>]*style\s*=\s*["'][^"']*display\s*:\s*none[^>]*>.*?</{tag}\s*>"#
+            ))
+            .unwrap()
+        })
+        .collect()
+});
+
+static IMG_TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)<img\b[^>]*>").unwrap());
+
+static IMG_SIZE_ATTR: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)\b(width|height)\s*=\s*["']?(\d+)"#).unwrap());
+
+static TABLE_TAG: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)<(/?)(table|thead|tbody|tfoot|tr|td|th)\b[^>]*>").unwrap());
+
+static PRESENTATION_ROLE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)\brole\s*=\s*["']?presentation"#).unwrap());
+
+/// Markdown links with no visible text
+static EMPTY_LINK: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\[\s*\]\([^)]*\)").unwrap());
+
+static MARKDOWN_IMAGE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"!\[([^\]]*)\]\([^)]*\)").unwrap());
+
+/// How message bodies are rendered in tool responses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RenderMode {
+    /// Body as stored (after HTML conversion)
+    #[default]
+    Full,
+    /// Quotes collapsed, repeated signatures removed, whitespace normalized
+    Compact,
+}
+
+impl RenderMode {
+    /// Storage/API representation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RenderMode::Full => "full",
+            RenderMode::Compact => "compact",
+        }
+    }
+
+    /// Parse from string
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "full" => Some(RenderMode::Full),
+            "compact" => Some(RenderMode::Compact),
+            _ => None,
+        }
+    }
+}
+
+/// Compacts the messages of one thread, oldest first.
+///
+/// Signatures are remembered across calls so a signature is shown the first
+/// time it appears in the thread and elided afterwards.
+#[derive(Debug, Default)]
+pub struct ThreadCompactor {
+    seen_signatures: HashSet<String>,
+}
+
+impl ThreadCompactor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Render one message body in compact form
+    pub fn compact(&mut self, body: &str) -> String {
+        let lines: Vec<String> = body.lines().map(normalize_line).collect();
+        let mut out: Vec<String> = Vec::new();
+        let mut i = 0;
+
+        while i < lines.len() {
+            let line = &lines[i];
+
+            // Outlook-style replies put the whole earlier message below a header
+            if is_original_message_separator(&lines, i) {
+                let remaining = count_non_blank(&lines[i..]);
+                out.push(elided_marker("earlier message", remaining));
+                break;
+            }
+
+            // "On <date>, <name> wrote:" followed by a quote block
+            if let Some(len) = attribution_len(&lines, i) {
+                let end = quote_block_end(&lines, i + len);
+                if end > i + len {
+                    let quoted = count_non_blank(&lines[i + len..end]);
+                    out.push(elided_marker("quoted text", quoted));
+                    i = end;
+                    continue;
+                }
+            }
+
+            if is_quoted(line) {
+                let end = quote_block_end(&lines, i);
+                out.push(elided_marker(
+                    "quoted text",
+                    count_non_blank(&lines[i..end]),
+                ));
+                i = end;
+                continue;
+            }
+
+            // RFC 3676 signature delimiter ("-- ", trailing space already trimmed)
+            if line == "--" {
+                let end = signature_end(&lines, i + 1);
+                let key = lines[i + 1..end].join("\n").to_lowercase();
+                if key.trim().is_empty() || self.seen_signatures.insert(key) {
+                    out.extend(lines[i..end].iter().cloned());
+                } else {
+                    out.push("[signature elided]".to_string());
+                }
+                i = end;
+                continue;
+            }
+
+            if is_mobile_signature(line) {
+                if self.seen_signatures.insert(line.to_lowercase()) {
+                    out.push(line.clone());
+                } else {
+                    out.push("[signature elided]".to_string());
+                }
+                i += 1;
+                continue;
+            }
+
+            out.push(line.clone());
+            i += 1;
+        }
+
+        collapse_blank_lines(out)
+    }
+}
+
+/// Compact a single message body (no cross-message signature tracking)
+pub fn compact_body(body: &str) -> String {
+    ThreadCompactor::new().compact(body)
+}
+
+/// Remove control characters (except tab and newline) that break terminals
+/// and the search index
+pub fn sanitize_text(text: &str) -> String {
+    text.chars()
+        .filter(|c| !matches!(c, '\u{0000}'..='\u{0008}' | '\u{000B}' | '\u{000C}' | '\u{000E}'..='\u{001F}' | '\u{007F}'))
+        .collect()
+}
+
+/// Convert an incoming HTML body to markdown, falling back to html2text if
+/// the markdown converter fails
+pub fn html_to_markdown(html: &str) -> String {
+    let html = sanitize_text(html);
+    if html.trim().is_empty() {
+        return String::new();
+    }
+    let cleaned = clean_html(&html);
+
+    match html_to_markdown_rs::convert(&cleaned, None) {
+        Ok(markdown) => {
+            let markdown = tidy_markdown(&markdown);
+            if !markdown.is_empty() {
+                return markdown;
+            }
+        }
+        Err(err) => {
+            warn!(
+                "HTML->Markdown conversion failed ({} chars), falling back to html2text: {}",
+                cleaned.chars().count(),
+                err
+            );
+        }
+    }
+
+    let fallback =
+        html2text::from_read(cleaned.as_bytes(), HTML2TEXT_FALLBACK_WIDTH).unwrap_or_default();
+    tidy_markdown(&fallback)
+}
+
+/// Render one email as a markdown document: subject heading, header list, body
+pub fn email_to_markdown(email: &Email, body: &str) -> String {
+    let join = |addrs: &[Address]| {
+        addrs
+            .iter()
+            .map(|a| a.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let subject = if email.subject.trim().is_empty() {
+        "(no subject)"
+    } else {
+        email.subject.trim()
+    };
+    let mut out = format!("## {}\n\n", subject);
+    out.push_str(&format!("- **From:** {}\n", email.from));
+    if !email.to.is_empty() {
+        out.push_str(&format!("- **To:** {}\n", join(&email.to)));
+    }
+    if !email.cc.is_empty() {
+        out.push_str(&format!("- **Cc:** {}\n", join(&email.cc)));
+    }
+    out.push_str(&format!(
+        "- **Date:** {}\n",
+        email.date.format("%Y-%m-%d %H:%M UTC")
+    ));
+    if !email.attachments.is_empty() {
+        out.push_str(&format!(
+            "- **Attachments:** {}\n",
+            email
+                .attachments
+                .iter()
+                .map(|a| a.filename.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    out.push('\n');
+    out.push_str(body.trim());
+    out.push('\n');
+    out
+}
+
+/// Render a thread (oldest message first) as one markdown document
+pub fn thread_to_markdown(emails: &[Email], mode: RenderMode) -> String {
+    let Some(first) = emails.first() else {
+        return String::new();
+    };
+
+    let mut compactor = ThreadCompactor::new();
+    let messages: Vec<String> = emails
+        .iter()
+        .map(|email| {
+            let body = match mode {
+                RenderMode::Full => email.markdown_body(),
+                RenderMode::Compact => compactor.compact(&email.markdown_body()),
+            };
+            email_to_markdown(email, &body)
+        })
+        .collect();
+
+    let unit = if emails.len() == 1 {
+        "message"
+    } else {
+        "messages"
+    };
+    format!(
+        "# {}\n\n{} {}\n\n---\n\n{}",
+        first.subject.trim(),
+        emails.len(),
+        unit,
+        messages.join("\n---\n\n")
+    )
+}
+
+/// Drop content the reader never sees and unwrap layout tables
+fn clean_html(html: &str) -> String {
+    let mut html = HTML_COMMENT.replace_all(html, "").into_owned();
+    for element in INVISIBLE_ELEMENTS.iter() {
+        html = element.replace_all(&html, "").into_owned();
+    }
+    for element in HIDDEN_ELEMENTS.iter() {
+        html = element.replace_all(&html, "").into_owned();
+    }
+    html = IMG_TAG
+        .replace_all(&html, |caps: &Captures| {
+            if is_tracking_pixel(&caps[0]) {
+                String::new()
+            } else {
+                caps[0].to_string()
+            }
+        })
+        .into_owned();
+    unwrap_layout_tables(&html)
+}
+
+/// A 0/1 pixel image, used by senders to track opens
+fn is_tracking_pixel(img_tag: &str) -> bool {
+    let sizes: Vec<u32> = IMG_SIZE_ATTR
+        .captures_iter(img_tag)
+        .filter_map(|caps| caps[2].parse().ok())
+        .collect();
+    sizes.len() >= 2 && sizes.iter().all(|&size| size <= 1)
+}
+
+/// Replace tables used for page layout with plain blocks.
+///
+/// Marketing and notification mail nests tables for layout; converting those
+/// to markdown tables produces unreadable pipes. A table counts as layout if
+/// it is marked `role="presentation"`, contains another table, or never has
+/// more than one cell in a row.
+fn unwrap_layout_tables(html: &str) -> String {
+    // First pass: classify each table, in document order
+    let mut layout: Vec<bool> = Vec::new();
+    let mut max_cells: Vec<usize> = Vec::new();
+    let mut open: Vec<usize> = Vec::new();
+    let mut row_cells: Vec<usize> = Vec::new();
+    for caps in TABLE_TAG.captures_iter(html) {
+        let closing = !caps[1].is_empty();
+        match (caps[2].to_ascii_lowercase().as_str(), closing) {
+            ("table", false) => {
+                if let Some(&parent) = open.last() {
+                    layout[parent] = true;
+                }
+                layout.push(PRESENTATION_ROLE.is_match(&caps[0]));
+                max_cells.push(0);
+                open.push(layout.len() - 1);
+                row_cells.push(0);
+            }
+            ("table", true) => {
+                open.pop();
+                row_cells.pop();
+            }
+            ("tr", false) => {
+                if let Some(cells) = row_cells.last_mut() {
+                    *cells = 0;
+                }
+            }
+            ("td" | "th", false) => {
+                if let (Some(&table), Some(cells)) = (open.last(), row_cells.last_mut()) {
+                    *cells += 1;
+                    max_cells[table] = max_cells[table].max(*cells);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let is_layout = |table: usize| layout[table] || max_cells[table] <= 1;
+    if !(0..layout.len()).any(is_layout) {
+        return html.to_string();
+    }
+
+    // Second pass: rewrite the tags that belong to layout tables
+    let mut next_table = 0;
+    let mut open: Vec<usize> = Vec::new();
+    TABLE_TAG
+        .replace_all(html, |caps: &Captures| {
+            let closing = !caps[1].is_empty();
+            let table = if !caps[2].eq_ignore_ascii_case("table") {
+                open.last().copied()
+            } else if closing {
+                open.pop()
+            } else {
+                open.push(next_table);
+                next_table += 1;
+                open.last().copied()
+            };
+            match table {
+                Some(table) if is_layout(table) => {
+                    let tag = if closing { "</div>" } else { "<div>" };
+                    tag.to_string()
+                }
+                _ => caps[0].to_string(),
+            }
+        })
+        .into_owned()
+}
+
+/// Clean up converter output: drop empty links and unlabeled images, strip
+/// invisible characters and trailing whitespace, collapse blank lines
+fn tidy_markdown(markdown: &str) -> String {
+    let markdown = sanitize_text(markdown);
+    let markdown = MARKDOWN_IMAGE.replace_all(&markdown, |caps: &Captures| {
+        let alt = caps[1].trim();
+        if alt.is_empty() {
+            String::new()
+        } else {
+            format!("[image: {}]", alt)
+        }
+    });
+    let markdown = EMPTY_LINK.replace_all(&markdown, "");
+
+    let lines: Vec<String> = markdown
+        .lines()
+        .map(|line| {
+            line.chars()
+                .filter(|c| {
+                    !matches!(
+                        c,
+                        '\u{200B}'..='\u{200D}' | '\u{2060}' | '\u{FEFF}' | '\u{034F}' | '\u{00AD}'
+                    )
+                })
+                .map(|c| if c == '\u{00A0}' { ' ' } else { c })
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        })
+        .collect();
+    collapse_blank_lines(lines)
+}
+
+/// Collapse runs of spaces/tabs and strip trailing whitespace
+fn normalize_line(line: &str) -> String {
+    line.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn is_quoted(line: &str) -> bool {
+    line.starts_with('>')
+}
+
+fn count_non_blank(lines: &[String]) -> usize {
+    lines.iter().filter(|l| !l.is_empty()).count()
+}
+
+fn elided_marker(what: &str, lines: usize) -> String {
+    let unit = if lines == 1 { "line" } else { "lines" };
+    format!("[{} elided: {} {}]", what, lines, unit)
+}
+
+/// Length (1 or 2 lines) of a reply attribution line starting at `i`
+fn attribution_len(lines: &[String], i: usize) -> Option<usize> {
+    let line = &lines[i];
+    if !line.starts_with("On ") {
+        return None;
+    }
+    if line.ends_with("wrote:") {
+        return Some(1);
+    }
+    // Clients often wrap long attributions onto a second line
+    match lines.get(i + 1) {
+        Some(next) if next.ends_with("wrote:") && !is_quoted(next) => Some(2),
+        _ => None,
+    }
+}
+
+/// End (exclusive) of a quote block starting at `start`, absorbing blank lines
+/// between quoted lines
+fn quote_block_end(lines: &[String], start: usize) -> usize {
+    let mut end = start;
+    let mut j = start;
+    while j < lines.len() {
+        if is_quoted(&lines[j]) {
+            j += 1;
+            end = j;
+        } else if lines[j].is_empty() {
+            j += 1;
+        } else {
+            break;
+        }
+    }
+    end
+}
+
+/// End (exclusive) of a signature block starting at `start`
+fn signature_end(lines: &[String], start: usize) -> usize {
+    let mut j = start;
+    while j < lines.len()
+        && !is_quoted(&lines[j])
+        && attribution_len(lines, j).is_none()
+        && !is_original_message_separator(lines, j)
+    {
+        j += 1;
+    }
+    j
+}
+
+fn is_original_message_separator(lines: &[String], i: usize) -> bool {
+    let line = &lines[i];
+    let lower = line.to_lowercase();
+    if line.starts_with('-') && lower.contains("original message") {
+        return true;
+    }
+    if line.len() >= OUTLOOK_RULE_MIN_LEN && line.chars().all(|c| c == '_') {
+        return true;
+    }
+    // Outlook reply header: "From: ..." directly followed by "Sent: ..."
+    lower.starts_with("from:")
+        && lines
+            .get(i + 1)
+            .map(|next| next.to_lowercase().starts_with("sent:"))
+            .unwrap_or(false)
+}
+
+fn is_mobile_signature(line: &str) -> bool {
+    let lower = line.to_lowercase();
+    lower.starts_with("sent from my ") || lower.starts_with("get outlook for ")
+}
+
+/// Keep at most one blank line in a row and trim blank lines at both ends
+fn collapse_blank_lines(lines: Vec<String>) -> String {
+    let mut out: Vec<String> = Vec::with_capacity(lines.len());
+    for line in lines {
+        if line.is_empty() && out.last().map(|l| l.is_empty()).unwrap_or(true) {
+            continue;
+        }
+        out.push(line);
+    }
+    while out.last().map(|l| l.is_empty()).unwrap_or(false) {
+        out.pop();
+    }
+    out.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collapses_quoted_reply_with_attribution() {
+        let body = "Sounds good, see you then.\n\nOn Mon, Jan 6, 2025 at 9:00 AM Alice <alice@example.com> wrote:\n> Can we meet at 3?\n>\n> Alice\n";
+        assert_eq!(
+            compact_body(body),
+            "Sounds good, see you then.\n\n[quoted text elided: 3 lines]"
+        );
+    }
+
+    #[test]
+    fn handles_wrapped_attribution() {
+        let body = "Yes.\n\nOn Mon, Jan 6, 2025 at 9:00 AM Alice Example\n<alice@example.com> wrote:\n\n> Question?";
+        assert_eq!(compact_body(body), "Yes.\n\n[quoted text elided: 1 line]");
+    }
+
+    #[test]
+    fn elides_outlook_original_message() {
+        let body = "Approved.\n\n-----Original Message-----\nFrom: Bob\nSent: Monday\nSubject: Budget\n\nPlease approve.";
+        assert_eq!(
+            compact_body(body),
+            "Approved.\n\n[earlier message elided: 5 lines]"
+        );
+    }
+
+    #[test]
+    fn dedupes_signatures_across_thread() {
+        let mut compactor = ThreadCompactor::new();
+        let first = compactor.compact("Hi all\n\n-- \nBob Smith\nACME Corp");
+        let second = compactor.compact("Following up\n\n-- \nBob Smith\nACME Corp");
+        assert_eq!(first, "Hi all\n\n--\nBob Smith\nACME Corp");
+        assert_eq!(second, "Following up\n\n[signature elided]");
+
+        let third = compactor.compact("Thanks\n\nSent from my iPhone");
+        let fourth = compactor.compact("Again\nSent from my iPhone");
+        assert_eq!(third, "Thanks\n\nSent from my iPhone");
+        assert_eq!(fourth, "Again\n[signature elided]");
+    }
+
+    #[test]
+    fn normalizes_whitespace() {
+        let body = "\n\nHello   there,\t\tworld  \n\n\n\nSecond   paragraph\n\n";
+        assert_eq!(compact_body(body), "Hello there, world\n\nSecond paragraph");
+    }
+
+    #[test]
+    fn unwraps_layout_tables_only() {
+        let html = "<table><tr><td><table><tr><th>A</th><th>B</th></tr></table></td></tr></table>";
+        assert_eq!(
+            unwrap_layout_tables(html),
+            "<div><div><div><table><tr><th>A</th><th>B</th></tr></table></div></div></div>"
+        );
+
+        let presentation = r#"<table role="presentation"><tr><td>x</td><td>y</td></tr></table>"#;
+        assert_eq!(
+            unwrap_layout_tables(presentation),
+            "<div><div><div>x</div><div>y</div></div></div>"
+        );
+    }
+
+    #[test]
+    fn html_markdown_keeps_structure() {
+        let table =
+            "<table><tr><th>Item</th><th>Qty</th></tr><tr><td>Apples</td><td>3</td></tr></table>";
+        let markdown = html_to_markdown(table);
+        assert!(markdown.contains('|'));
+        assert!(markdown.contains("Apples"));
+
+        let reply = "<p>Yes</p><blockquote><p>Question?</p></blockquote>";
+        assert!(html_to_markdown(reply).contains("> Question?"));
+
+        let list = r#"<ul><li>One</li><li><a href="https://example.com">Two</a></li></ul>"#;
+        let markdown = html_to_markdown(list);
+        assert!(markdown.contains("One"));
+        assert!(markdown.contains("[Two](https://example.com)"));
+    }
+
+    #[test]
+    fn html_markdown_drops_invisible_content() {
+        let html = r#"<html><head><title>T</title><style>.x { color: red }</style></head><body>
+<div style="display:none">Preview text</div>
+<!--[if mso]><p>Outlook only</p><![endif]-->
+<table role="presentation"><tr><td><p>Body&nbsp;text</p></td></tr></table>
+<img src="https://t.example/p.gif" width="1" height="1">
+<a href="https://example.com/logo"><img src="https://example.com/logo.png"></a>
+</body></html>"#;
+        let markdown = html_to_markdown(html);
+        assert!(markdown.contains("Body text"));
+        for hidden in ["Preview", "Outlook", "color", "t.example", "logo", "|"] {
+            assert!(!markdown.contains(hidden), "{hidden:?} in {markdown:?}");
+        }
+    }
+
+    #[test]
+    fn parses_render_mode() {
+        assert_eq!(RenderMode::from_str("Compact"), Some(RenderMode::Compact));
+        assert_eq!(RenderMode::from_str("full"), Some(RenderMode::Full));
+        assert_eq!(RenderMode::from_str("terse"), None);
+    }
+}
@@ -0,0 +1,145 @@
+//! Retention pruning reports
+//!
+//! Accounts can cap how long synced mail is kept locally (see
+//! [`RetentionPolicy`](crate::models::RetentionPolicy)). The daemon prunes
+//! mail past those limits on a schedule, deleting the emails with their
+//! embeddings, downloaded attachments and stored raw copies, logs what went,
+//! and records the last run per account in a status file that `groundeffect
+//! sync status` reads.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// What one pruning run removed for an account
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PruneReport {
+    /// When the run finished
+    pub pruned_at: DateTime<Utc>,
+
+    /// Emails deleted from the local index
+    pub emails: u64,
+
+    /// Raw size of the deleted emails
+    pub email_bytes: u64,
+
+    /// Downloaded attachment files deleted
+    pub attachments: u64,
+
+    /// Size of the deleted attachment files
+    pub attachment_bytes: u64,
+
+    /// Stored raw message copies deleted
+    pub raw_copies: u64,
+
+    /// Emails deleted per rule ("all" or a category name)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub by_rule: BTreeMap<String, u64>,
+}
+
+impl PruneReport {
+    /// An empty report for a run finishing at `now`
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            pruned_at: now,
+            ..Default::default()
+        }
+    }
+
+    /// Total bytes freed
+    pub fn freed_bytes(&self) -> u64 {
+        self.email_bytes + self.attachment_bytes
+    }
+
+    /// One-line summary for logs and `sync status`
+    pub fn summary(&self) -> String {
+        if self.emails == 0 {
+            return "nothing to prune".to_string();
+        }
+        let rules: Vec<String> = self
+            .by_rule
+            .iter()
+            .map(|(rule, count)| format!("{} {}", count, rule))
+            .collect();
+        format!(
+            "{} emails ({}), {} attachments, {} raw copies, {} bytes freed",
+            self.emails,
+            rules.join(", "),
+            self.attachments,
+            self.raw_copies,
+            self.freed_bytes()
+        )
+    }
+}
+
+/// Last pruning run for every account, persisted by the daemon
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionReport {
+    pub accounts: BTreeMap<String, PruneReport>,
+}
+
+impl RetentionReport {
+    /// Load the report from disk (default if missing or unreadable)
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// Write the report to disk
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, json)
+    }
+
+    /// Record an account's latest run
+    pub fn record(&mut self, account_id: &str, report: PruneReport) {
+        self.accounts.insert(account_id.to_string(), report);
+    }
+
+    /// An account's latest run, if pruning has run for it
+    pub fn get(&self, account_id: &str) -> Option<&PruneReport> {
+        self.accounts.get(account_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary_and_round_trip() {
+        let now = Utc::now();
+        let mut report = PruneReport::new(now);
+        assert_eq!(report.summary(), "nothing to prune");
+
+        report.emails = 12;
+        report.email_bytes = 1000;
+        report.attachments = 2;
+        report.attachment_bytes = 500;
+        report.raw_copies = 12;
+        report.by_rule.insert("all".to_string(), 4);
+        report.by_rule.insert("newsletter".to_string(), 8);
+        assert_eq!(
+            report.summary(),
+            "12 emails (4 all, 8 newsletter), 2 attachments, 12 raw copies, 1500 bytes freed"
+        );
+
+        let path = std::env::temp_dir().join(format!("ge-retention-{}.json", uuid::Uuid::new_v4()));
+        let mut saved = RetentionReport::default();
+        saved.record("me@example.com", report.clone());
+        saved.save(&path).unwrap();
+
+        let loaded = RetentionReport::load(&path);
+        assert_eq!(loaded.get("me@example.com"), Some(&report));
+        assert!(loaded.get("other@example.com").is_none());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,1257 @@
+//! Meeting time proposals and conflict checks
+//!
+//! Finds slots inside the user's working hours (see [`crate::availability`])
+//! that avoid their own events and time off, and ranks them by how many
+//! attendees are free according to Google Calendar free/busy. Attendees
+//! whose calendars aren't shared with the account count as unknown, not
+//! free.
+//!
+//! [`find_conflicts`] checks a new event against the synced calendars before
+//! it's created.
+
+use std::collections::HashMap;
+
+use chrono::{
+    DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, TimeZone, Timelike, Utc, Weekday,
+};
+use chrono_tz::Tz;
+use reqwest::Client;
+use serde::Serialize;
+use tracing::warn;
+
+use crate::availability::{availability_context, WorkingHours};
+use crate::config::Config;
+use crate::db::Database;
+use crate::error::{Error, Result};
+use crate::models::{
+    event_color_id, AttendeeStatus, CalendarEvent, EventStatus, EventTime, Reminder,
+    ReminderMethod, Transparency, Visibility, EVENT_COLORS,
+};
+use crate::oauth::OAuthManager;
+use crate::sync::{load_availability, query_free_busy, FreeBusy};
+use crate::unanswered::parse_age;
+
+/// Candidate start times are this far apart (minutes)
+const SLOT_STEP_MINUTES: i64 = 30;
+
+/// Slots proposed per day before a second day's are, so proposals spread
+/// across the window
+const MAX_SLOTS_PER_DAY: usize = 2;
+
+/// Longest window searched
+const MAX_WINDOW_DAYS: i64 = 31;
+
+/// RRULE parts Google Calendar accepts
+const RRULE_PARTS: &[&str] = &[
+    "FREQ",
+    "INTERVAL",
+    "COUNT",
+    "UNTIL",
+    "BYDAY",
+    "BYMONTHDAY",
+    "BYMONTH",
+    "BYYEARDAY",
+    "BYWEEKNO",
+    "BYSETPOS",
+    "WKST",
+];
+
+/// Repeat frequencies Google Calendar accepts
+const RRULE_FREQUENCIES: &[&str] = &["DAILY", "WEEKLY", "MONTHLY", "YEARLY"];
+
+/// Most reminder overrides Google Calendar keeps on an event
+const MAX_REMINDERS: usize = 5;
+
+/// Earliest a reminder can fire before an event (four weeks, in minutes)
+const MAX_REMINDER_MINUTES: i64 = 40320;
+
+/// What to find meeting times for
+#[derive(Debug, Clone)]
+pub struct MeetingRequest {
+    /// Attendee email addresses (free/busy is looked up for each)
+    pub attendees: Vec<String>,
+    pub duration: Duration,
+    /// Start and end of the search window
+    pub window: (DateTime<Utc>, DateTime<Utc>),
+    /// Most slots returned
+    pub limit: usize,
+}
+
+/// A proposed meeting time
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProposedSlot {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+
+    /// Start in the user's timezone (RFC 3339)
+    pub local_start: String,
+
+    /// Share of attendees free (unknown counts half); 1.0 with no attendees
+    pub score: f64,
+
+    /// Attendees whose calendars show them free
+    pub free: Vec<String>,
+
+    /// Attendees whose calendars show them busy
+    pub busy: Vec<String>,
+
+    /// Attendees whose calendars aren't visible to the account
+    pub unknown: Vec<String>,
+}
+
+/// A tentative event placed on the user's calendar for a proposed slot
+#[derive(Debug, Clone, Serialize)]
+pub struct Hold {
+    pub event_id: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub html_link: Option<String>,
+}
+
+/// Ranked meeting times for a set of attendees
+#[derive(Debug, Clone, Serialize)]
+pub struct MeetingProposal {
+    /// Account whose calendar access was used for free/busy
+    pub account_id: String,
+    pub window_start: DateTime<Utc>,
+    pub window_end: DateTime<Utc>,
+    pub duration_minutes: i64,
+    pub timezone: String,
+
+    /// Attendees whose free/busy couldn't be read
+    pub not_visible: Vec<String>,
+
+    /// Best slot first
+    pub slots: Vec<ProposedSlot>,
+
+    /// Tentative event placed for the best slot
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hold: Option<Hold>,
+}
+
+/// When a new event happens
+#[derive(Debug, Clone, PartialEq)]
+pub enum NewEventTime {
+    /// A start and end time, shown in `tz`
+    Timed {
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        tz: Tz,
+    },
+    /// Whole days, from `first` up to but not including `end` (Google's end
+    /// dates are exclusive)
+    AllDay { first: NaiveDate, end: NaiveDate },
+}
+
+impl NewEventTime {
+    /// Parse the times given to `create_event`
+    ///
+    /// Timed events take [`parse_event_time`] values and need an end after
+    /// the start. All-day events take dates (YYYY-MM-DD), with `end` the last
+    /// day, inclusive (default: one day).
+    pub fn parse(start: &str, end: Option<&str>, all_day: bool, tz: Tz) -> Option<Self> {
+        if all_day {
+            let date = |value: &str| NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d").ok();
+            let first = date(start)?;
+            let last = match end {
+                Some(end) => date(end)?,
+                None => first,
+            };
+            return (last >= first).then(|| NewEventTime::AllDay {
+                first,
+                end: last + Duration::days(1),
+            });
+        }
+        let start = parse_event_time(start, tz)?;
+        let end = parse_event_time(end?, tz)?;
+        (end > start).then_some(NewEventTime::Timed { start, end, tz })
+    }
+
+    /// `start` and `end` objects for the Google Calendar API
+    pub fn to_api(&self) -> (serde_json::Value, serde_json::Value) {
+        match self {
+            NewEventTime::Timed { start, end, tz } => {
+                let time = |at: &DateTime<Utc>| {
+                    serde_json::json!({
+                        "dateTime": at.with_timezone(tz).to_rfc3339(),
+                        "timeZone": tz.name(),
+                    })
+                };
+                (time(start), time(end))
+            }
+            NewEventTime::AllDay { first, end } => (
+                serde_json::json!({"date": first.to_string()}),
+                serde_json::json!({"date": end.to_string()}),
+            ),
+        }
+    }
+
+    /// The event as it will be stored once synced
+    pub fn event_times(&self) -> (EventTime, EventTime) {
+        match *self {
+            NewEventTime::Timed { start, end, .. } => {
+                (EventTime::DateTime(start), EventTime::DateTime(end))
+            }
+            NewEventTime::AllDay { first, end } => (EventTime::Date(first), EventTime::Date(end)),
+        }
+    }
+
+    /// The time the event takes up, all-day events running from midnight
+    /// to midnight in `tz`
+    pub fn interval(&self, tz: Tz) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        match *self {
+            NewEventTime::Timed { start, end, .. } => Some((start, end)),
+            NewEventTime::AllDay { first, end } => {
+                Some((local_midnight(first, tz)?, local_midnight(end, tz)?))
+            }
+        }
+    }
+}
+
+/// How a new event repeats
+///
+/// Either a raw RRULE, or a `repeat` frequency with optional days, end date
+/// or number of occurrences.
+#[derive(Debug, Clone, Default)]
+pub struct Recurrence {
+    /// RRULE as written ("FREQ=WEEKLY;BYDAY=MO,WE"), checked but not changed
+    pub rule: Option<String>,
+    /// "daily", "weekdays", "weekly", "biweekly", "monthly" or "yearly"
+    pub repeat: Option<String>,
+    /// Days a weekly repeat falls on ("mon", "wed"; default: the start's day)
+    pub on: Vec<String>,
+    /// Last day an occurrence can start (inclusive)
+    pub until: Option<NaiveDate>,
+    /// Number of occurrences
+    pub count: Option<u32>,
+}
+
+impl Recurrence {
+    /// The `RRULE:` line for the event's `recurrence` field (None when it
+    /// doesn't repeat)
+    pub fn to_rrule(&self, when: &NewEventTime) -> Result<Option<String>> {
+        let invalid = |reason: String| Err(Error::InvalidRequest(reason));
+        let has_options = !self.on.is_empty() || self.until.is_some() || self.count.is_some();
+
+        if let Some(rule) = &self.rule {
+            if self.repeat.is_some() || has_options {
+                return invalid(
+                    "a raw recurrence rule can't be combined with repeat, on, until or count"
+                        .to_string(),
+                );
+            }
+            return check_rrule(rule).map(Some);
+        }
+        let Some(repeat) = &self.repeat else {
+            if has_options {
+                return invalid("on, until and count need repeat".to_string());
+            }
+            return Ok(None);
+        };
+
+        let mut parts = match repeat.trim().to_lowercase().as_str() {
+            "daily" => vec!["FREQ=DAILY".to_string()],
+            "weekdays" => vec![
+                "FREQ=WEEKLY".to_string(),
+                "BYDAY=MO,TU,WE,TH,FR".to_string(),
+            ],
+            "weekly" => vec!["FREQ=WEEKLY".to_string()],
+            "biweekly" => vec!["FREQ=WEEKLY".to_string(), "INTERVAL=2".to_string()],
+            "monthly" => vec!["FREQ=MONTHLY".to_string()],
+            "yearly" => vec!["FREQ=YEARLY".to_string()],
+            other => {
+                return invalid(format!(
+                    "unknown repeat '{}' (daily, weekdays, weekly, biweekly, monthly or yearly)",
+                    other
+                ))
+            }
+        };
+        if !self.on.is_empty() {
+            if !matches!(repeat.trim().to_lowercase().as_str(), "weekly" | "biweekly") {
+                return invalid("on only applies to weekly and biweekly repeats".to_string());
+            }
+            let days = self
+                .on
+                .iter()
+                .map(|day| {
+                    rrule_day(day).ok_or_else(|| {
+                        Error::InvalidRequest(format!("'{}' is not a day (mon to sun)", day))
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            parts.push(format!("BYDAY={}", days.join(",")));
+        }
+
+        match (self.until, self.count) {
+            (Some(_), Some(_)) => return invalid("use until or count, not both".to_string()),
+            (Some(until), None) => {
+                let (start, _) = when.event_times();
+                if until < start.as_date() {
+                    return invalid(format!("until {} is before the event starts", until));
+                }
+                parts.push(format!("UNTIL={}", rrule_until(until, when)));
+            }
+            (None, Some(0)) => return invalid("count must be at least 1".to_string()),
+            (None, Some(count)) => parts.push(format!("COUNT={}", count)),
+            (None, None) => {}
+        }
+
+        Ok(Some(format!("RRULE:{}", parts.join(";"))))
+    }
+}
+
+/// Check a raw RRULE, returning it as an `RRULE:` line
+fn check_rrule(rule: &str) -> Result<String> {
+    let invalid =
+        |reason: String| Error::InvalidRequest(format!("invalid recurrence rule: {}", reason));
+    let rule = rule.trim();
+    let body = rule
+        .strip_prefix("RRULE:")
+        .or_else(|| rule.strip_prefix("rrule:"))
+        .unwrap_or(rule)
+        .to_uppercase();
+
+    let mut frequency = None;
+    for part in body.split(';').filter(|part| !part.is_empty()) {
+        let (key, value) = part
+            .split_once('=')
+            .ok_or_else(|| invalid(format!("'{}' is not KEY=VALUE", part)))?;
+        if !RRULE_PARTS.contains(&key) {
+            return Err(invalid(format!("unsupported part '{}'", key)));
+        }
+        if value.is_empty() {
+            return Err(invalid(format!("{} has no value", key)));
+        }
+        if key == "FREQ" {
+            frequency = Some(value);
+        }
+    }
+    match frequency {
+        Some(frequency) if RRULE_FREQUENCIES.contains(&frequency) => Ok(format!("RRULE:{}", body)),
+        Some(frequency) => Err(invalid(format!(
+            "FREQ={} (use DAILY, WEEKLY, MONTHLY or YEARLY)",
+            frequency
+        ))),
+        None => Err(invalid("FREQ is missing".to_string())),
+    }
+}
+
+/// Two-letter RRULE day ("MO") for "mon", "monday" or "mo"
+fn rrule_day(day: &str) -> Option<String> {
+    let day = day.trim();
+    let weekday = match day.to_lowercase().as_str() {
+        "mo" => Weekday::Mon,
+        "tu" => Weekday::Tue,
+        "we" => Weekday::Wed,
+        "th" => Weekday::Thu,
+        "fr" => Weekday::Fri,
+        "sa" => Weekday::Sat,
+        "su" => Weekday::Sun,
+        _ => day.parse::<Weekday>().ok()?,
+    };
+    Some(weekday.to_string()[..2].to_uppercase())
+}
+
+/// UNTIL value covering occurrences that start on `until`: a date for
+/// all-day events, otherwise the end of that day in the event's timezone, in
+/// UTC
+fn rrule_until(until: NaiveDate, when: &NewEventTime) -> String {
+    match when {
+        NewEventTime::AllDay { .. } => until.format("%Y%m%d").to_string(),
+        NewEventTime::Timed { tz, .. } => {
+            let end_of_day = local_midnight(until + Duration::days(1), *tz)
+                .map(|midnight| midnight - Duration::seconds(1))
+                .unwrap_or_else(|| until.and_hms_opt(23, 59, 59).unwrap_or_default().and_utc());
+            end_of_day.format("%Y%m%dT%H%M%SZ").to_string()
+        }
+    }
+}
+
+/// Color, visibility, show-as and reminders for a new or updated event
+///
+/// Unset fields are left to Google Calendar (the calendar's defaults, or the
+/// event's current values on update).
+#[derive(Debug, Clone, Default)]
+pub struct EventOptions {
+    /// `colorId` (see [`crate::models::EVENT_COLORS`])
+    pub color_id: Option<String>,
+    pub visibility: Option<Visibility>,
+    /// Busy (opaque) or free (transparent)
+    pub transparency: Option<Transparency>,
+    /// Reminder overrides (empty: no reminders)
+    pub reminders: Option<Vec<Reminder>>,
+}
+
+impl EventOptions {
+    /// Options from user-facing names: a color name or ID, "public" or
+    /// "private", and "busy" or "free"
+    pub fn parse(
+        color: Option<&str>,
+        visibility: Option<&str>,
+        show_as: Option<&str>,
+        reminders: Option<Vec<Reminder>>,
+    ) -> Result<Self> {
+        let color_id = match color {
+            Some(color) => Some(event_color_id(color).ok_or_else(|| {
+                Error::InvalidRequest(format!(
+                    "Unknown color '{}' (use 1-11 or one of {})",
+                    color,
+                    EVENT_COLORS
+                        .iter()
+                        .map(|(_, name)| *name)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ))
+            })?),
+            None => None,
+        };
+        let visibility = match visibility {
+            Some(value) => Some(Visibility::parse(value).ok_or_else(|| {
+                Error::InvalidRequest(format!(
+                    "Unknown visibility '{}' (use default, public or private)",
+                    value
+                ))
+            })?),
+            None => None,
+        };
+        let transparency = match show_as {
+            Some(value) => Some(Transparency::parse(value).ok_or_else(|| {
+                Error::InvalidRequest(format!("Unknown show-as '{}' (use busy or free)", value))
+            })?),
+            None => None,
+        };
+        if let Some(reminders) = &reminders {
+            if reminders.len() > MAX_REMINDERS {
+                return Err(Error::InvalidRequest(format!(
+                    "At most {} reminders can be set on an event",
+                    MAX_REMINDERS
+                )));
+            }
+            if let Some(reminder) = reminders
+                .iter()
+                .find(|r| !(0..=MAX_REMINDER_MINUTES).contains(&(r.minutes as i64)))
+            {
+                return Err(Error::InvalidRequest(format!(
+                    "Reminder {} minutes before is out of range (0 to four weeks)",
+                    reminder.minutes
+                )));
+            }
+        }
+
+        Ok(Self {
+            color_id: color_id.map(str::to_string),
+            visibility,
+            transparency,
+            reminders,
+        })
+    }
+
+    /// Whether no option is set
+    pub fn is_empty(&self) -> bool {
+        self.color_id.is_none()
+            && self.visibility.is_none()
+            && self.transparency.is_none()
+            && self.reminders.is_none()
+    }
+
+    /// Set the options on a Google Calendar API event body
+    pub fn apply(&self, body: &mut serde_json::Value) {
+        if let Some(color_id) = &self.color_id {
+            body["colorId"] = serde_json::json!(color_id);
+        }
+        if let Some(visibility) = self.visibility {
+            body["visibility"] = serde_json::json!(visibility.as_str());
+        }
+        if let Some(transparency) = &self.transparency {
+            body["transparency"] = serde_json::json!(transparency.as_str());
+        }
+        if let Some(reminders) = &self.reminders {
+            body["reminders"] = serde_json::json!({
+                "useDefault": false,
+                "overrides": reminders
+            });
+        }
+    }
+}
+
+/// Parse a reminder: how long before the event ("10m", "1h", "2d", or
+/// minutes), optionally prefixed with "popup:" (the default) or "email:"
+pub fn parse_reminder(value: &str) -> Result<Reminder> {
+    let invalid = || {
+        Error::InvalidRequest(format!(
+            "Invalid reminder '{}' (use e.g. 10m, popup:1h or email:1d)",
+            value
+        ))
+    };
+    let (method, lead) = match value.trim().split_once(':') {
+        Some((method, lead)) => (method.trim().to_lowercase(), lead.trim()),
+        None => ("popup".to_string(), value.trim()),
+    };
+    let method = match method.as_str() {
+        "popup" => ReminderMethod::Popup,
+        "email" => ReminderMethod::Email,
+        _ => return Err(invalid()),
+    };
+    let minutes = match lead.parse::<i64>() {
+        Ok(minutes) => minutes,
+        Err(_) => parse_duration(lead)
+            .or_else(|| parse_age(lead))
+            .ok_or_else(invalid)?
+            .num_minutes(),
+    };
+    if !(0..=MAX_REMINDER_MINUTES).contains(&minutes) {
+        return Err(invalid());
+    }
+    Ok(Reminder {
+        method,
+        minutes: minutes as i32,
+    })
+}
+
+/// A synced event that overlaps a new one
+#[derive(Debug, Clone, Serialize)]
+pub struct EventConflict {
+    pub id: String,
+    pub account_id: String,
+    pub calendar_id: String,
+    pub summary: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+/// Parse a meeting length: "45m", "1h", "1h30m", or minutes ("90")
+pub fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim().to_lowercase();
+    if let Ok(minutes) = value.parse::<i64>() {
+        return (minutes > 0).then(|| Duration::minutes(minutes));
+    }
+    let (hours, rest) = match value.split_once('h') {
+        Some((hours, rest)) => (hours.parse::<i64>().ok()?, rest),
+        None => (0, value.as_str()),
+    };
+    let minutes = match rest.strip_suffix('m') {
+        Some(minutes) => minutes.parse::<i64>().ok()?,
+        None if rest.is_empty() => 0,
+        None => return None,
+    };
+    let total = Duration::hours(hours) + Duration::minutes(minutes);
+    (total > Duration::zero()).then_some(total)
+}
+
+/// Parse a search window, in the user's timezone: "today", "tomorrow",
+/// "this-week", "next-week", a length from now ("3d", "2w"), or a date
+/// range ("2025-03-10..2025-03-14", end inclusive)
+///
+/// Windows never start before `now`.
+pub fn parse_window(
+    value: &str,
+    now: DateTime<Utc>,
+    tz: Tz,
+) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let value = value.trim().to_lowercase();
+    let today = now.with_timezone(&tz).date_naive();
+    let midnight = |date: NaiveDate| local_midnight(date, tz);
+    let next_monday = today + Duration::days(7 - today.weekday().num_days_from_monday() as i64);
+
+    let (start, end) = match value.as_str() {
+        "today" => (now, midnight(today + Duration::days(1))?),
+        "tomorrow" => (
+            midnight(today + Duration::days(1))?,
+            midnight(today + Duration::days(2))?,
+        ),
+        "this-week" | "this week" => (now, midnight(next_monday)?),
+        "next-week" | "next week" => (
+            midnight(next_monday)?,
+            midnight(next_monday + Duration::days(7))?,
+        ),
+        _ => match value.split_once("..") {
+            Some((from, to)) => (
+                midnight(NaiveDate::parse_from_str(from.trim(), "%Y-%m-%d").ok()?)?,
+                midnight(
+                    NaiveDate::parse_from_str(to.trim(), "%Y-%m-%d").ok()? + Duration::days(1),
+                )?,
+            ),
+            None => (now, now + parse_age(&value)?),
+        },
+    };
+
+    let start = start.max(now);
+    let end = end.min(start + Duration::days(MAX_WINDOW_DAYS));
+    (start < end).then_some((start, end))
+}
+
+/// Ranked slots of `duration` within `window`
+///
+/// Slots fall inside working hours and outside `own_busy`; `attendees` holds
+/// each attendee's free/busy (None when not visible). At most `limit` slots
+/// are returned, best first.
+pub fn propose_slots(
+    hours: &WorkingHours,
+    window: (DateTime<Utc>, DateTime<Utc>),
+    duration: Duration,
+    own_busy: &[(DateTime<Utc>, DateTime<Utc>)],
+    attendees: &[(String, FreeBusy)],
+    limit: usize,
+) -> Vec<ProposedSlot> {
+    let overlaps = |busy: &[(DateTime<Utc>, DateTime<Utc>)], start, end| {
+        busy.iter()
+            .any(|&(b_start, b_end)| b_start < end && start < b_end)
+    };
+
+    let mut candidates = Vec::new();
+    let mut start = align_to_step(window.0);
+    while start + duration <= window.1 {
+        let end = start + duration;
+        let within_hours = hours.contains(start)
+            && hours.contains(end - Duration::seconds(1))
+            && same_local_day(start, end - Duration::seconds(1), hours.timezone);
+        if within_hours && !overlaps(own_busy, start, end) {
+            candidates.push(rate_slot(start, end, attendees, hours.timezone));
+        }
+        start += Duration::minutes(SLOT_STEP_MINUTES);
+    }
+
+    // Best score first, then earliest
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.start.cmp(&b.start)));
+
+    // Spread the picks over the window first, then fill up with the rest
+    let mut chosen: Vec<ProposedSlot> = Vec::new();
+    for per_day_cap in [MAX_SLOTS_PER_DAY, usize::MAX] {
+        let mut per_day: HashMap<NaiveDate, usize> = HashMap::new();
+        for slot in &chosen {
+            *per_day
+                .entry(slot.start.with_timezone(&hours.timezone).date_naive())
+                .or_default() += 1;
+        }
+        for slot in &candidates {
+            if chosen.len() == limit {
+                break;
+            }
+            let day = slot.start.with_timezone(&hours.timezone).date_naive();
+            let taken = per_day.entry(day).or_default();
+            let clashes = chosen
+                .iter()
+                .any(|c| c.start < slot.end && slot.start < c.end);
+            if *taken < per_day_cap && !clashes {
+                *taken += 1;
+                chosen.push(slot.clone());
+            }
+        }
+    }
+    chosen
+}
+
+fn rate_slot(
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    attendees: &[(String, FreeBusy)],
+    tz: Tz,
+) -> ProposedSlot {
+    let (mut free, mut busy, mut unknown) = (Vec::new(), Vec::new(), Vec::new());
+    for (email, intervals) in attendees {
+        match intervals {
+            None => unknown.push(email.clone()),
+            Some(intervals) if intervals.iter().any(|&(s, e)| s < end && start < e) => {
+                busy.push(email.clone())
+            }
+            Some(_) => free.push(email.clone()),
+        }
+    }
+    let score = if attendees.is_empty() {
+        1.0
+    } else {
+        (free.len() as f64 + unknown.len() as f64 / 2.0) / attendees.len() as f64
+    };
+
+    ProposedSlot {
+        start,
+        end,
+        local_start: start.with_timezone(&tz).to_rfc3339(),
+        score: (score * 100.0).round() / 100.0,
+        free,
+        busy,
+        unknown,
+    }
+}
+
+/// Round up to the next slot step
+fn align_to_step(at: DateTime<Utc>) -> DateTime<Utc> {
+    let at = at
+        .with_second(0)
+        .and_then(|t| t.with_nanosecond(0))
+        .unwrap_or(at);
+    let past = at.minute() as i64 % SLOT_STEP_MINUTES;
+    if past == 0 {
+        at
+    } else {
+        at + Duration::minutes(SLOT_STEP_MINUTES - past)
+    }
+}
+
+fn same_local_day(a: DateTime<Utc>, b: DateTime<Utc>, tz: Tz) -> bool {
+    a.with_timezone(&tz).date_naive() == b.with_timezone(&tz).date_naive()
+}
+
+/// Parse an event start or end as given to `create_event`: RFC 3339, or a
+/// time without offset, read in `tz` (the event's timezone)
+pub fn parse_event_time(value: &str, tz: Tz) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    let naive = ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())?;
+    tz.from_local_datetime(&naive)
+        .earliest()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+/// Events among `events` that keep the user busy during `start..end`
+pub fn conflicts(
+    events: &[CalendarEvent],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    tz: Tz,
+) -> Vec<EventConflict> {
+    events
+        .iter()
+        .filter_map(|event| {
+            let (busy_start, busy_end) = busy_interval(event, tz)?;
+            (busy_start < end && start < busy_end).then(|| EventConflict {
+                id: event.id.clone(),
+                account_id: event.account_id.clone(),
+                calendar_id: event.calendar_id.clone(),
+                summary: event.summary.clone(),
+                start: busy_start,
+                end: busy_end,
+            })
+        })
+        .collect()
+}
+
+/// Start of `date` in `tz`
+fn local_midnight(date: NaiveDate, tz: Tz) -> Option<DateTime<Utc>> {
+    tz.from_local_datetime(&date.and_hms_opt(0, 0, 0)?)
+        .earliest()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+/// Synced events, on any account, that a new event at `start..end` would
+/// overlap
+pub async fn find_conflicts(
+    db: &Database,
+    config: &Config,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<EventConflict>> {
+    let tz: Tz = config.general.timezone.parse().unwrap_or(Tz::UTC);
+    // All-day events are stored by date, so look a day either side
+    let events = db
+        .list_events_overlapping(None, start - Duration::days(1), end + Duration::days(1))
+        .await?;
+    Ok(conflicts(&events, start, end, tz))
+}
+
+/// When an event keeps the user busy (None for free, cancelled or declined
+/// events)
+fn busy_interval(event: &CalendarEvent, tz: Tz) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let declined = event.attendees.iter().any(|a| {
+        a.email.eq_ignore_ascii_case(&event.account_id)
+            && a.response_status == Some(AttendeeStatus::Declined)
+    });
+    if event.status == EventStatus::Cancelled
+        || event.transparency == Transparency::Transparent
+        || declined
+    {
+        return None;
+    }
+    let time = |time: &EventTime| match time {
+        EventTime::DateTime(dt) => Some(*dt),
+        EventTime::Date(date) => local_midnight(*date, tz),
+    };
+    Some((time(&event.start)?, time(&event.end)?))
+}
+
+/// `conferenceData` asking Google Calendar to attach a new Meet call to an
+/// event it creates (the insert needs `conferenceDataVersion=1`)
+pub fn meet_request() -> serde_json::Value {
+    serde_json::json!({
+        "createRequest": {
+            "requestId": uuid::Uuid::new_v4().to_string(),
+            "conferenceSolutionKey": {"type": "hangoutsMeet"}
+        }
+    })
+}
+
+/// Propose meeting times, asking attendees' free/busy as `account_id`
+///
+/// The user counts as busy during events on any synced calendar and during
+/// out-of-office blocks and vacation responder periods.
+pub async fn propose_meeting_times(
+    db: &Database,
+    oauth: &OAuthManager,
+    config: &Config,
+    account_id: &str,
+    request: &MeetingRequest,
+) -> Result<MeetingProposal> {
+    let window = request.window;
+    let hours = WorkingHours::from_config(&config.availability, &config.general.timezone)?;
+    let tz = hours.timezone;
+
+    // All-day events are stored by date, so look a day either side
+    let events = db
+        .list_events_overlapping(
+            None,
+            window.0 - Duration::days(1),
+            window.1 + Duration::days(1),
+        )
+        .await?;
+    let mut own_busy: Vec<_> = events.iter().filter_map(|e| busy_interval(e, tz)).collect();
+
+    let mut states = Vec::new();
+    for account in db.list_accounts().await? {
+        match load_availability(oauth, config, &account.id, false).await {
+            Ok(state) => states.push(state),
+            Err(e) => warn!("Failed to load availability for {}: {}", account.id, e),
+        }
+    }
+    let context = availability_context(&hours, &states, window.0);
+    own_busy.extend(context.away.iter().map(|block| block.interval()));
+
+    let free_busy =
+        query_free_busy(oauth, account_id, &request.attendees, window.0, window.1).await?;
+    let slots = propose_slots(
+        &hours,
+        window,
+        request.duration,
+        &own_busy,
+        &free_busy,
+        request.limit,
+    );
+
+    Ok(MeetingProposal {
+        account_id: account_id.to_string(),
+        window_start: window.0,
+        window_end: window.1,
+        duration_minutes: request.duration.num_minutes(),
+        timezone: tz.name().to_string(),
+        not_visible: free_busy
+            .iter()
+            .filter(|(_, busy)| busy.is_none())
+            .map(|(email, _)| email.clone())
+            .collect(),
+        slots,
+        hold: None,
+    })
+}
+
+/// Place a tentative event on the account's primary calendar for `slot`
+///
+/// Attendees are listed in the description but not invited, so nobody is
+/// notified until the meeting is actually scheduled.
+pub async fn place_hold(
+    oauth: &OAuthManager,
+    account_id: &str,
+    slot: &ProposedSlot,
+    title: &str,
+    attendees: &[String],
+) -> Result<Hold> {
+    let access_token = oauth.get_valid_token(account_id).await?;
+    let mut body = serde_json::json!({
+        "summary": title,
+        "status": "tentative",
+        "start": {"dateTime": slot.start.to_rfc3339(), "timeZone": "UTC"},
+        "end": {"dateTime": slot.end.to_rfc3339(), "timeZone": "UTC"},
+    });
+    if !attendees.is_empty() {
+        body["description"] = serde_json::json!(format!("With {}", attendees.join(", ")));
+    }
+
+    let response = Client::new()
+        .post("https://www.googleapis.com/calendar/v3/calendars/primary/events")
+        .bearer_auth(&access_token)
+        .json(&body)
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(Error::CalDav(format!(
+            "Failed to create hold: {} - {}",
+            status, body
+        )));
+    }
+
+    let json: serde_json::Value = response.json().await?;
+    Ok(Hold {
+        event_id: json["id"].as_str().unwrap_or_default().to_string(),
+        start: slot.start,
+        end: slot.end,
+        html_link: json["htmlLink"].as_str().map(str::to_string),
+    })
+}
+
+/// Change fields of a synced event in Google Calendar, returning the updated
+/// event
+///
+/// This is a PATCH, so fields missing from `changes` keep their values. The
+/// local copy catches up on the next sync.
+pub async fn patch_event(
+    oauth: &OAuthManager,
+    event: &CalendarEvent,
+    changes: &serde_json::Value,
+) -> Result<serde_json::Value> {
+    let access_token = oauth.get_valid_token(&event.account_id).await?;
+    let url = format!(
+        "https://www.googleapis.com/calendar/v3/calendars/{}/events/{}",
+        urlencoding::encode(&event.calendar_id),
+        urlencoding::encode(&event.google_event_id)
+    );
+
+    let response = Client::new()
+        .patch(&url)
+        .bearer_auth(&access_token)
+        .json(changes)
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(Error::CalDav(format!(
+            "Failed to update event: {} - {}",
+            status, body
+        )));
+    }
+
+    Ok(response.json().await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AvailabilityConfig;
+
+    fn utc(d: u32, h: u32, m: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 3, d, h, m, 0).unwrap()
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("45m"), Some(Duration::minutes(45)));
+        assert_eq!(parse_duration("1h"), Some(Duration::hours(1)));
+        assert_eq!(parse_duration("1h30m"), Some(Duration::minutes(90)));
+        assert_eq!(parse_duration("90"), Some(Duration::minutes(90)));
+        assert_eq!(parse_duration("0m"), None);
+        assert_eq!(parse_duration("soon"), None);
+    }
+
+    #[test]
+    fn test_parse_window() {
+        // Wednesday 2025-03-12 15:00 UTC
+        let now = utc(12, 15, 0);
+        assert_eq!(
+            parse_window("next-week", now, Tz::UTC),
+            Some((utc(17, 0, 0), utc(24, 0, 0)))
+        );
+        assert_eq!(
+            parse_window("this-week", now, Tz::UTC),
+            Some((now, utc(17, 0, 0)))
+        );
+        assert_eq!(
+            parse_window("3d", now, Tz::UTC),
+            Some((now, utc(15, 15, 0)))
+        );
+        assert_eq!(
+            parse_window("2025-03-13..2025-03-14", now, Tz::UTC),
+            Some((utc(13, 0, 0), utc(15, 0, 0)))
+        );
+        assert_eq!(parse_window("2025-03-01..2025-03-02", now, Tz::UTC), None);
+    }
+
+    #[test]
+    fn test_propose_slots_ranks_free_attendees_first() {
+        let hours = WorkingHours::from_config(&AvailabilityConfig::default(), "UTC").unwrap();
+        // Monday 2025-03-17
+        let window = (utc(17, 9, 0), utc(18, 0, 0));
+        let own_busy = vec![(utc(17, 9, 0), utc(17, 12, 0))];
+        let attendees = vec![
+            (
+                "a@example.com".to_string(),
+                Some(vec![(utc(17, 12, 0), utc(17, 14, 0))]),
+            ),
+            ("b@example.com".to_string(), None),
+        ];
+
+        let slots = propose_slots(
+            &hours,
+            window,
+            Duration::minutes(45),
+            &own_busy,
+            &attendees,
+            5,
+        );
+        // Slots where a@ is free come first, none overlap, and all fall
+        // between my morning block and the end of the working day
+        assert_eq!(slots.len(), 5);
+        assert_eq!(slots[0].start, utc(17, 14, 0));
+        assert_eq!(slots[0].free, vec!["a@example.com"]);
+        assert_eq!(slots[0].unknown, vec!["b@example.com"]);
+        assert_eq!(slots[0].score, 0.75);
+        assert_eq!(slots[1].start, utc(17, 15, 0));
+        assert_eq!(slots[3].busy, vec!["a@example.com"]);
+        assert!(slots
+            .iter()
+            .all(|s| s.start >= utc(17, 12, 0) && s.end <= utc(17, 17, 0)));
+    }
+
+    #[test]
+    fn test_recurrence_rule() {
+        let new_york: Tz = "America/New_York".parse().unwrap();
+        let one_on_one = NewEventTime::parse(
+            "2025-03-17T10:00:00",
+            Some("2025-03-17T10:30:00"),
+            false,
+            new_york,
+        )
+        .unwrap();
+        let rule = |recurrence: Recurrence| recurrence.to_rrule(&one_on_one);
+
+        assert_eq!(rule(Recurrence::default()).unwrap(), None);
+        assert_eq!(
+            rule(Recurrence {
+                rule: Some("freq=weekly;byday=mo,we".to_string()),
+                ..Default::default()
+            })
+            .unwrap()
+            .as_deref(),
+            Some("RRULE:FREQ=WEEKLY;BYDAY=MO,WE")
+        );
+        assert_eq!(
+            rule(Recurrence {
+                repeat: Some("biweekly".to_string()),
+                on: vec!["mon".to_string(), "Thursday".to_string()],
+                until: NaiveDate::from_ymd_opt(2025, 6, 30),
+                ..Default::default()
+            })
+            .unwrap()
+            .as_deref(),
+            // End of June 30 in New York
+            Some("RRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH;UNTIL=20250701T035959Z")
+        );
+        assert_eq!(
+            rule(Recurrence {
+                repeat: Some("weekdays".to_string()),
+                count: Some(10),
+                ..Default::default()
+            })
+            .unwrap()
+            .as_deref(),
+            Some("RRULE:FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR;COUNT=10")
+        );
+
+        let reminder = NewEventTime::parse("2025-03-17", None, true, new_york).unwrap();
+        let yearly = Recurrence {
+            repeat: Some("yearly".to_string()),
+            until: NaiveDate::from_ymd_opt(2030, 3, 17),
+            ..Default::default()
+        };
+        assert_eq!(
+            yearly.to_rrule(&reminder).unwrap().as_deref(),
+            Some("RRULE:FREQ=YEARLY;UNTIL=20300317")
+        );
+
+        for bad in [
+            Recurrence {
+                rule: Some("FREQ=HOURLY".to_string()),
+                ..Default::default()
+            },
+            Recurrence {
+                rule: Some("BYDAY=MO".to_string()),
+                ..Default::default()
+            },
+            Recurrence {
+                repeat: Some("daily".to_string()),
+                on: vec!["mon".to_string()],
+                ..Default::default()
+            },
+            Recurrence {
+                repeat: Some("weekly".to_string()),
+                until: NaiveDate::from_ymd_opt(2025, 1, 1),
+                ..Default::default()
+            },
+            Recurrence {
+                count: Some(3),
+                ..Default::default()
+            },
+        ] {
+            assert!(rule(bad).is_err());
+        }
+    }
+
+    #[test]
+    fn test_new_event_time() {
+        let date = |d: u32| NaiveDate::from_ymd_opt(2025, 3, d).unwrap();
+        let new_york: Tz = "America/New_York".parse().unwrap();
+
+        // All-day: the last day is inclusive, Google's end date exclusive
+        let offsite = NewEventTime::parse("2025-03-17", Some("2025-03-19"), true, new_york);
+        assert_eq!(
+            offsite,
+            Some(NewEventTime::AllDay {
+                first: date(17),
+                end: date(20)
+            })
+        );
+        let (start, end) = offsite.as_ref().unwrap().to_api();
+        assert_eq!(start, serde_json::json!({"date": "2025-03-17"}));
+        assert_eq!(end, serde_json::json!({"date": "2025-03-20"}));
+        assert_eq!(
+            offsite.unwrap().interval(new_york),
+            Some((utc(17, 4, 0), utc(20, 4, 0)))
+        );
+        assert_eq!(
+            NewEventTime::parse("2025-03-17", None, true, new_york),
+            Some(NewEventTime::AllDay {
+                first: date(17),
+                end: date(18)
+            })
+        );
+        assert_eq!(
+            NewEventTime::parse("2025-03-17", Some("2025-03-16"), true, new_york),
+            None
+        );
+
+        let standup = NewEventTime::parse(
+            "2025-03-17T10:00:00",
+            Some("2025-03-17T10:30:00"),
+            false,
+            new_york,
+        )
+        .unwrap();
+        let (start, _) = standup.to_api();
+        assert_eq!(start["dateTime"], "2025-03-17T10:00:00-04:00");
+        assert_eq!(start["timeZone"], "America/New_York");
+        assert_eq!(standup.event_times().0.as_datetime(), Some(utc(17, 14, 0)));
+        assert_eq!(
+            NewEventTime::parse("2025-03-17T10:00:00", None, false, new_york),
+            None
+        );
+    }
+
+    #[test]
+    fn test_event_options() {
+        let reminder = parse_reminder("10m").unwrap();
+        assert_eq!(
+            (reminder.method, reminder.minutes),
+            (ReminderMethod::Popup, 10)
+        );
+        let reminder = parse_reminder("email:1d").unwrap();
+        assert_eq!(
+            (reminder.method, reminder.minutes),
+            (ReminderMethod::Email, 1440)
+        );
+        assert_eq!(parse_reminder("0").unwrap().minutes, 0);
+        assert_eq!(parse_reminder("popup:1h30m").unwrap().minutes, 90);
+        assert!(parse_reminder("sms:10m").is_err());
+        assert!(parse_reminder("5w").is_err());
+
+        let options = EventOptions::parse(
+            Some("Tomato"),
+            Some("private"),
+            Some("free"),
+            Some(vec![parse_reminder("30m").unwrap()]),
+        )
+        .unwrap();
+        let mut body = serde_json::json!({"summary": "Focus"});
+        options.apply(&mut body);
+        assert_eq!(body["colorId"], "11");
+        assert_eq!(body["visibility"], "private");
+        assert_eq!(body["transparency"], "transparent");
+        assert_eq!(body["reminders"]["useDefault"], false);
+        assert_eq!(body["reminders"]["overrides"][0]["minutes"], 30);
+        assert_eq!(body["reminders"]["overrides"][0]["method"], "popup");
+
+        // No reminders at all
+        let mut body = serde_json::json!({});
+        EventOptions::parse(None, None, None, Some(Vec::new()))
+            .unwrap()
+            .apply(&mut body);
+        assert_eq!(body["reminders"]["overrides"], serde_json::json!([]));
+        assert!(EventOptions::default().is_empty());
+
+        assert!(EventOptions::parse(Some("mauve"), None, None, None).is_err());
+        assert!(EventOptions::parse(None, Some("secret"), None, None).is_err());
+        assert!(EventOptions::parse(None, None, Some("away"), None).is_err());
+        let six = vec![parse_reminder("10m").unwrap(); 6];
+        assert!(EventOptions::parse(None, None, None, Some(six)).is_err());
+    }
+
+    fn event(id: &str, start: DateTime<Utc>, end: DateTime<Utc>) -> CalendarEvent {
+        CalendarEvent {
+            id: id.to_string(),
+            account_id: "me@example.com".to_string(),
+            account_alias: None,
+            google_event_id: id.to_string(),
+            ical_uid: id.to_string(),
+            etag: String::new(),
+            summary: id.to_string(),
+            description: None,
+            location: None,
+            start: EventTime::DateTime(start),
+            end: EventTime::DateTime(end),
+            timezone: "UTC".to_string(),
+            all_day: false,
+            recurrence_rule: None,
+            recurrence_id: None,
+            organizer: None,
+            attendees: Vec::new(),
+            status: EventStatus::default(),
+            transparency: Transparency::default(),
+            visibility: Visibility::default(),
+            color_id: None,
+            reminders: Vec::new(),
+            conference: None,
+            embedding: None,
+            calendar_id: "primary".to_string(),
+            synced_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let new_york: Tz = "America/New_York".parse().unwrap();
+        assert_eq!(
+            parse_event_time("2025-03-17T14:00:00", Tz::UTC),
+            Some(utc(17, 14, 0))
+        );
+        assert_eq!(
+            parse_event_time("2025-03-17T10:00:00", new_york),
+            Some(utc(17, 14, 0))
+        );
+        assert_eq!(
+            parse_event_time("2025-03-17T10:00:00-04:00", Tz::UTC),
+            Some(utc(17, 14, 0))
+        );
+
+        let standup = event("standup", utc(17, 13, 30), utc(17, 14, 15));
+        let adjacent = event("adjacent", utc(17, 15, 0), utc(17, 16, 0));
+        let mut focus = event("focus", utc(17, 14, 0), utc(17, 16, 0));
+        focus.transparency = Transparency::Transparent;
+        let mut cancelled = event("cancelled", utc(17, 14, 0), utc(17, 15, 0));
+        cancelled.status = EventStatus::Cancelled;
+        let all_day = CalendarEvent {
+            start: EventTime::Date(NaiveDate::from_ymd_opt(2025, 3, 17).unwrap()),
+            end: EventTime::Date(NaiveDate::from_ymd_opt(2025, 3, 18).unwrap()),
+            all_day: true,
+            ..event("offsite", utc(17, 0, 0), utc(18, 0, 0))
+        };
+
+        let events = [standup, adjacent, focus, cancelled, all_day];
+        let found = conflicts(&events, utc(17, 14, 0), utc(17, 15, 0), Tz::UTC);
+        let ids: Vec<_> = found.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["standup", "offsite"]);
+        assert_eq!(found[1].end, utc(18, 0, 0));
+    }
+}
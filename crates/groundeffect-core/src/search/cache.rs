@@ -0,0 +1,277 @@
+//! LRU cache for repeated searches
+//!
+//! Agents often run the same search several times in a session. Results
+//! are cached per query, options and synonym expansions, together with the
+//! version of the table they were read from: any write to the table (a sync
+//! batch, a deletion, an index rebuild) bumps its version and drops that
+//! table's cached results on the next lookup.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Hit/miss counters for `groundeffect search stats`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchCacheStats {
+    /// Lookups answered from the cache
+    pub hits: u64,
+
+    /// Lookups that ran the search
+    pub misses: u64,
+
+    /// Entries dropped to stay under capacity
+    pub evictions: u64,
+
+    /// Entries dropped because they outlived the TTL
+    pub expired: u64,
+
+    /// Entries dropped because their table changed
+    pub invalidations: u64,
+
+    /// Entries currently cached
+    pub entries: usize,
+
+    /// Maximum entries (0 = cache disabled)
+    pub capacity: usize,
+
+    /// Seconds an entry stays valid
+    pub ttl_secs: u64,
+
+    /// Process that recorded these stats
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u32>,
+
+    /// When the stats were last written
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl SearchCacheStats {
+    /// Share of lookups answered from the cache (0.0 when nothing was looked up)
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+
+    /// Load stats written by a long-running server (None if missing or unreadable)
+    pub fn load(path: &Path) -> Option<Self> {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+    }
+
+    /// Write stats to disk
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, json)
+    }
+}
+
+struct CacheEntry<V> {
+    table: &'static str,
+    value: V,
+    inserted: Instant,
+    last_used: u64,
+}
+
+/// Size- and age-bounded cache of search results
+pub struct SearchCache<V> {
+    entries: HashMap<String, CacheEntry<V>>,
+    versions: HashMap<&'static str, u64>,
+    capacity: usize,
+    ttl: Duration,
+    clock: u64,
+    stats: SearchCacheStats,
+}
+
+impl<V: Clone> SearchCache<V> {
+    /// Create a cache holding up to `capacity` results for `ttl` each
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            entries: HashMap::new(),
+            versions: HashMap::new(),
+            capacity,
+            ttl,
+            clock: 0,
+            stats: SearchCacheStats {
+                capacity,
+                ttl_secs: ttl.as_secs(),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Whether results are cached at all
+    pub fn enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Record the current version of a table, dropping its entries if it changed
+    pub fn observe_version(&mut self, table: &'static str, version: u64) {
+        let previous = self.versions.insert(table, version);
+        if previous.is_some_and(|v| v != version) {
+            let before = self.entries.len();
+            self.entries.retain(|_, entry| entry.table != table);
+            self.stats.invalidations += (before - self.entries.len()) as u64;
+        }
+    }
+
+    /// Cached result for a key, if present and fresh
+    pub fn get(&mut self, key: &str) -> Option<V> {
+        self.get_at(key, Instant::now())
+    }
+
+    fn get_at(&mut self, key: &str, now: Instant) -> Option<V> {
+        if !self.enabled() {
+            return None;
+        }
+        let fresh = match self.entries.get(key) {
+            Some(entry) => now.duration_since(entry.inserted) < self.ttl,
+            None => {
+                self.stats.misses += 1;
+                return None;
+            }
+        };
+        if !fresh {
+            self.entries.remove(key);
+            self.stats.expired += 1;
+            self.stats.misses += 1;
+            return None;
+        }
+
+        self.clock += 1;
+        let entry = self.entries.get_mut(key)?;
+        entry.last_used = self.clock;
+        self.stats.hits += 1;
+        Some(entry.value.clone())
+    }
+
+    /// Cache a result read from `table`, evicting the least recently used
+    /// entry when full
+    pub fn insert(&mut self, table: &'static str, key: String, value: V) {
+        self.insert_at(table, key, value, Instant::now());
+    }
+
+    fn insert_at(&mut self, table: &'static str, key: String, value: V, now: Instant) {
+        if !self.enabled() {
+            return;
+        }
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+                self.stats.evictions += 1;
+            }
+        }
+
+        self.clock += 1;
+        self.entries.insert(
+            key,
+            CacheEntry {
+                table,
+                value,
+                inserted: now,
+                last_used: self.clock,
+            },
+        );
+    }
+
+    /// Drop every cached result
+    pub fn clear(&mut self) {
+        self.stats.invalidations += self.entries.len() as u64;
+        self.entries.clear();
+    }
+
+    /// Current counters
+    pub fn stats(&self) -> SearchCacheStats {
+        SearchCacheStats {
+            entries: self.entries.len(),
+            ..self.stats.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(capacity: usize) -> SearchCache<u32> {
+        SearchCache::new(capacity, Duration::from_secs(60))
+    }
+
+    #[test]
+    fn test_hit_after_insert() {
+        let mut cache = cache(4);
+        assert_eq!(cache.get("q"), None);
+        cache.insert("emails", "q".to_string(), 7);
+        assert_eq!(cache.get("q"), Some(7));
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (1, 1, 1));
+        assert_eq!(stats.hit_rate(), 0.5);
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let mut cache = cache(2);
+        cache.insert("emails", "a".to_string(), 1);
+        cache.insert("emails", "b".to_string(), 2);
+        cache.get("a");
+        cache.insert("emails", "c".to_string(), 3);
+
+        assert_eq!(cache.get("a"), Some(1));
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.get("c"), Some(3));
+        assert_eq!(cache.stats().evictions, 1);
+    }
+
+    #[test]
+    fn test_expires_after_ttl() {
+        let mut cache = cache(4);
+        let start = Instant::now();
+        cache.insert_at("emails", "q".to_string(), 1, start);
+        assert_eq!(cache.get_at("q", start + Duration::from_secs(59)), Some(1));
+        assert_eq!(cache.get_at("q", start + Duration::from_secs(61)), None);
+        assert_eq!(cache.stats().expired, 1);
+        assert_eq!(cache.stats().entries, 0);
+    }
+
+    #[test]
+    fn test_table_version_change_invalidates_that_table() {
+        let mut cache = cache(4);
+        cache.observe_version("emails", 3);
+        cache.observe_version("events", 5);
+        cache.insert("emails", "e".to_string(), 1);
+        cache.insert("events", "c".to_string(), 2);
+
+        cache.observe_version("emails", 3);
+        assert_eq!(cache.get("e"), Some(1));
+
+        cache.observe_version("emails", 4);
+        assert_eq!(cache.get("e"), None);
+        assert_eq!(cache.get("c"), Some(2));
+        assert_eq!(cache.stats().invalidations, 1);
+    }
+
+    #[test]
+    fn test_zero_capacity_disables_cache() {
+        let mut cache = cache(0);
+        cache.insert("emails", "q".to_string(), 1);
+        assert_eq!(cache.get("q"), None);
+        assert_eq!(cache.stats().misses, 0);
+    }
+}
@@ -0,0 +1,202 @@
+//! Result diversity for email ranking
+//!
+//! A thread with twenty near-identical messages would otherwise fill the
+//! whole first page. By default only the best-scoring message of each thread
+//! is kept. With an MMR lambda below 1.0 the results are then picked by
+//! maximal marginal relevance: each pick maximizes
+//! `lambda * relevance - (1 - lambda) * similarity`, where relevance is the
+//! fused score scaled to 0-1 against the best candidate and similarity is the
+//! highest embedding cosine to anything already picked.
+
+use serde::{Deserialize, Serialize};
+
+use super::ScoreComponents;
+use crate::classify::cosine_similarity;
+use crate::config::SearchConfig;
+
+/// Diversity settings for one search
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Diversity {
+    /// Keep only the best-scoring message per thread
+    pub dedup_threads: bool,
+
+    /// Relevance vs novelty trade-off for MMR (1.0 disables, 0.0 = novelty only)
+    pub mmr_lambda: f32,
+}
+
+impl Default for Diversity {
+    fn default() -> Self {
+        Self {
+            dedup_threads: true,
+            mmr_lambda: 1.0,
+        }
+    }
+}
+
+impl Diversity {
+    /// Thread dedup and lambda from `[search]`
+    pub fn from_config(search: &SearchConfig) -> Self {
+        Self {
+            dedup_threads: search.dedup_threads,
+            mmr_lambda: search.mmr_lambda,
+        }
+    }
+
+    /// Whether MMR reordering runs at all
+    pub fn is_mmr_active(&self) -> bool {
+        self.mmr_lambda < 1.0
+    }
+
+    /// Whether results can be dropped or reordered, so more candidates
+    /// than the limit are needed
+    pub fn is_active(&self) -> bool {
+        self.dedup_threads || self.is_mmr_active()
+    }
+
+    /// Drop every result after the first from the same thread
+    ///
+    /// Results are expected best first. Results without a thread key are
+    /// always kept. Returns how many were dropped.
+    pub fn dedup(
+        &self,
+        results: &mut Vec<(String, f32, ScoreComponents)>,
+        thread: impl Fn(&str) -> Option<String>,
+    ) -> usize {
+        if !self.dedup_threads {
+            return 0;
+        }
+        let before = results.len();
+        let mut seen = std::collections::HashSet::new();
+        results.retain(|(id, _, _)| match thread(id) {
+            Some(key) => seen.insert(key),
+            None => true,
+        });
+        before - results.len()
+    }
+
+    /// Reorder the first `limit` results by maximal marginal relevance
+    ///
+    /// Results without an embedding count as unlike everything else. Those
+    /// past `limit` follow in their original order.
+    pub fn rerank<'a>(
+        &self,
+        results: &mut Vec<(String, f32, ScoreComponents)>,
+        embedding: impl Fn(&str) -> Option<&'a [f32]>,
+        limit: usize,
+    ) {
+        if !self.is_mmr_active() || results.len() < 2 {
+            return;
+        }
+        let lambda = self.mmr_lambda.max(0.0);
+        let best = results
+            .iter()
+            .map(|(_, score, _)| *score)
+            .fold(0.0_f32, f32::max);
+
+        let mut remaining = std::mem::take(results);
+        let mut picked: Vec<&'a [f32]> = Vec::new();
+        while results.len() < limit && !remaining.is_empty() {
+            let mut choice = (0, f32::NEG_INFINITY);
+            for (i, (id, score, _)) in remaining.iter().enumerate() {
+                let relevance = if best > 0.0 { *score / best } else { 0.0 };
+                let similarity = embedding(id)
+                    .map(|v| {
+                        picked
+                            .iter()
+                            .map(|p| cosine_similarity(v, p))
+                            .fold(0.0_f32, f32::max)
+                    })
+                    .unwrap_or(0.0);
+                let mmr = lambda * relevance - (1.0 - lambda) * similarity;
+                if mmr > choice.1 {
+                    choice = (i, mmr);
+                }
+            }
+            let next = remaining.remove(choice.0);
+            if let Some(v) = embedding(&next.0) {
+                picked.push(v);
+            }
+            results.push(next);
+        }
+        results.extend(remaining);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranked(ids: &[(&str, f32)]) -> Vec<(String, f32, ScoreComponents)> {
+        ids.iter()
+            .map(|(id, score)| (id.to_string(), *score, ScoreComponents::default()))
+            .collect()
+    }
+
+    fn order(results: &[(String, f32, ScoreComponents)]) -> Vec<&str> {
+        results.iter().map(|(id, _, _)| id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_dedup_keeps_best_message_per_thread() {
+        let thread = |id: &str| match id {
+            "a1" | "a2" | "a3" => Some("acct:1".to_string()),
+            "b1" => Some("acct:2".to_string()),
+            _ => None,
+        };
+        let mut results = ranked(&[
+            ("a1", 0.9),
+            ("a2", 0.8),
+            ("loose1", 0.7),
+            ("a3", 0.6),
+            ("b1", 0.5),
+            ("loose2", 0.4),
+        ]);
+
+        let off = Diversity {
+            dedup_threads: false,
+            ..Diversity::default()
+        };
+        assert_eq!(off.dedup(&mut results, thread), 0);
+        assert_eq!(results.len(), 6);
+
+        assert_eq!(Diversity::default().dedup(&mut results, thread), 2);
+        assert_eq!(order(&results), vec!["a1", "loose1", "b1", "loose2"]);
+    }
+
+    #[test]
+    fn test_mmr_promotes_a_different_result_over_a_near_duplicate() {
+        let vectors: std::collections::HashMap<&str, Vec<f32>> = [
+            ("flight", vec![1.0, 0.0]),
+            ("flight-copy", vec![0.99, 0.1]),
+            ("hotel", vec![0.0, 1.0]),
+        ]
+        .into_iter()
+        .collect();
+        let embedding = |id: &str| vectors.get(id).map(Vec::as_slice);
+        let mut results = ranked(&[
+            ("flight", 1.0),
+            ("flight-copy", 0.95),
+            ("hotel", 0.7),
+            ("unembedded", 0.1),
+        ]);
+
+        Diversity::default().rerank(&mut results, embedding, 3);
+        assert_eq!(
+            order(&results),
+            vec!["flight", "flight-copy", "hotel", "unembedded"],
+            "lambda 1.0 leaves the order alone"
+        );
+
+        let mmr = Diversity {
+            mmr_lambda: 0.5,
+            ..Diversity::default()
+        };
+        mmr.rerank(&mut results, embedding, 2);
+        assert_eq!(
+            order(&results),
+            vec!["flight", "hotel", "flight-copy", "unembedded"]
+        );
+        // Scores are left as fused
+        assert_eq!(results[1].1, 0.7);
+    }
+}
@@ -0,0 +1,337 @@
+//! Contact expertise: "who should I ask about ..."
+//!
+//! Each person the user exchanges mail with gets a profile: the sum of the
+//! embeddings of the mail they sent the user and the mail the user sent
+//! them. A question's embedding is compared with every profile, and the
+//! similarity is damped for contacts seen in few emails so one lucky
+//! message doesn't outrank someone who writes about the topic all the time.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tracing::info;
+
+use super::SearchEngine;
+use crate::classify::cosine_similarity;
+use crate::error::{Error, Result};
+use crate::models::{Email, EmailCategory};
+
+/// Emails at which a contact keeps half its profile similarity; the damping
+/// factor is `emails / (emails + PROFILE_PRIOR)`
+const PROFILE_PRIOR: f32 = 2.0;
+
+/// Best-matching emails listed per contact
+const EVIDENCE_PER_CONTACT: usize = 3;
+
+/// Options for ranking contacts by expertise
+#[derive(Debug, Clone)]
+pub struct ExpertiseOptions {
+    /// Account IDs whose mail builds the profiles (None = all accounts)
+    pub accounts: Option<Vec<String>>,
+
+    /// Only mail dated on or after this
+    pub since: DateTime<Utc>,
+
+    /// Contacts need at least this many emails to be ranked
+    pub min_emails: usize,
+
+    /// Maximum number of contacts
+    pub limit: usize,
+
+    /// The user's other addresses (send-as identities), never ranked
+    pub own_addresses: Vec<String>,
+}
+
+/// An email backing a contact's ranking
+#[derive(Debug, Clone, Serialize)]
+pub struct ExpertiseEvidence {
+    pub id: String,
+    pub subject: String,
+    pub date: DateTime<Utc>,
+
+    /// Whether the user sent it to the contact (else the contact sent it)
+    pub sent: bool,
+
+    /// Cosine similarity of the email to the query
+    pub similarity: f32,
+}
+
+/// A contact ranked by how much their mail is about the query
+#[derive(Debug, Clone, Serialize)]
+pub struct ContactExpertise {
+    /// Email address (lowercase)
+    pub email: String,
+
+    /// Display name, when any email gave one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// Profile similarity damped by how few emails built the profile
+    pub score: f32,
+
+    /// Cosine similarity of the contact's profile to the query
+    pub similarity: f32,
+
+    /// Emails from the contact
+    pub from_them: usize,
+
+    /// Emails the user sent them (To or Cc)
+    pub to_them: usize,
+
+    /// Date of the newest email
+    pub last_contact: DateTime<Utc>,
+
+    /// Their emails most similar to the query
+    pub evidence: Vec<ExpertiseEvidence>,
+}
+
+impl ContactExpertise {
+    /// Emails in the contact's profile
+    pub fn emails(&self) -> usize {
+        self.from_them + self.to_them
+    }
+}
+
+impl SearchEngine {
+    /// Contacts whose mail is most about `query`, best first
+    pub async fn contact_expertise(
+        &self,
+        query: &str,
+        options: &ExpertiseOptions,
+    ) -> Result<Vec<ContactExpertise>> {
+        let query_embedding = self.embedding.embed(query).await?.ok_or_else(|| {
+            Error::Embedding(
+                "Contact expertise needs an embedding model, but none is available".to_string(),
+            )
+        })?;
+
+        let all_accounts: Vec<String> = self
+            .db
+            .list_accounts()
+            .await?
+            .into_iter()
+            .map(|a| a.id)
+            .collect();
+        let own: HashSet<String> = all_accounts
+            .iter()
+            .chain(&options.own_addresses)
+            .map(|a| a.trim().to_lowercase())
+            .collect();
+
+        let start = std::time::Instant::now();
+        let mut emails = Vec::new();
+        for account_id in options.accounts.as_ref().unwrap_or(&all_accounts) {
+            emails.extend(
+                self.db
+                    .list_embedded_emails_since(account_id, options.since)
+                    .await?,
+            );
+        }
+        info!(
+            "Loaded {} emails for contact profiles in {:?}",
+            emails.len(),
+            start.elapsed()
+        );
+
+        let mut ranked = rank_contacts(&emails, &own, &query_embedding, options.min_emails);
+        ranked.truncate(options.limit);
+        Ok(ranked)
+    }
+}
+
+/// Build a profile per contact from `emails` and rank them by similarity
+/// to `query`
+///
+/// Received mail counts for its sender unless it was classified as bulk or
+/// automated; sent mail counts for each To and Cc recipient. Addresses in
+/// `own` (lowercase) are never contacts, and emails without an embedding
+/// are skipped.
+pub fn rank_contacts(
+    emails: &[Email],
+    own: &HashSet<String>,
+    query: &[f32],
+    min_emails: usize,
+) -> Vec<ContactExpertise> {
+    struct Profile<'a> {
+        name: Option<String>,
+        sum: Vec<f32>,
+        members: Vec<(&'a Email, bool)>,
+    }
+
+    let mut profiles: HashMap<String, Profile> = HashMap::new();
+    for email in emails {
+        let Some(embedding) = email.embedding.as_deref() else {
+            continue;
+        };
+        let sent = email.is_sent || own.contains(&email.from.email.trim().to_lowercase());
+        let people: Vec<_> = if sent {
+            email.to.iter().chain(&email.cc).collect()
+        } else if matches!(email.category, None | Some(EmailCategory::Personal)) {
+            vec![&email.from]
+        } else {
+            continue;
+        };
+
+        let mut seen = HashSet::new();
+        for person in people {
+            let key = person.email.trim().to_lowercase();
+            if key.is_empty() || own.contains(&key) || !seen.insert(key.clone()) {
+                continue;
+            }
+            let profile = profiles.entry(key).or_insert_with(|| Profile {
+                name: None,
+                sum: vec![0.0; embedding.len()],
+                members: Vec::new(),
+            });
+            if profile.name.is_none() {
+                profile.name = person
+                    .name
+                    .as_deref()
+                    .map(str::trim)
+                    .filter(|n| !n.is_empty())
+                    .map(str::to_string);
+            }
+            if profile.sum.len() == embedding.len() {
+                for (s, x) in profile.sum.iter_mut().zip(embedding) {
+                    *s += x;
+                }
+                profile.members.push((email, sent));
+            }
+        }
+    }
+
+    let mut ranked: Vec<ContactExpertise> = profiles
+        .into_iter()
+        .filter(|(_, p)| !p.members.is_empty() && p.members.len() >= min_emails)
+        .map(|(address, profile)| {
+            let count = profile.members.len() as f32;
+            let similarity = cosine_similarity(query, &profile.sum);
+
+            let mut evidence: Vec<ExpertiseEvidence> = profile
+                .members
+                .iter()
+                .map(|(email, sent)| ExpertiseEvidence {
+                    id: email.id.clone(),
+                    subject: email.subject.clone(),
+                    date: email.date,
+                    sent: *sent,
+                    similarity: cosine_similarity(
+                        query,
+                        email.embedding.as_deref().unwrap_or_default(),
+                    ),
+                })
+                .collect();
+            evidence.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+            evidence.truncate(EVIDENCE_PER_CONTACT);
+
+            ContactExpertise {
+                email: address,
+                name: profile.name,
+                score: similarity * count / (count + PROFILE_PRIOR),
+                similarity,
+                from_them: profile.members.iter().filter(|(_, sent)| !sent).count(),
+                to_them: profile.members.iter().filter(|(_, sent)| *sent).count(),
+                last_contact: profile
+                    .members
+                    .iter()
+                    .map(|(email, _)| email.date)
+                    .max()
+                    .unwrap_or_default(),
+                evidence,
+            }
+        })
+        .collect();
+    ranked.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then(b.emails().cmp(&a.emails()))
+            .then_with(|| a.email.cmp(&b.email))
+    });
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Address;
+    use chrono::{Duration, TimeZone};
+
+    fn email(n: i64, from: &str, to: &[&str], embedding: Vec<f32>) -> Email {
+        Email {
+            gmail_message_id: n as u64,
+            gmail_thread_id: n as u64,
+            uid: n as u32,
+            to: to.iter().map(|a| Address::new(*a)).collect(),
+            date: Utc.with_ymd_and_hms(2024, 6, 1, 9, 0, 0).unwrap() + Duration::hours(n),
+            embedding: Some(embedding),
+            ..Email::test(&format!("<{}@x>", n), from, &format!("Email {}", n))
+        }
+    }
+
+    fn own() -> HashSet<String> {
+        ["me@example.com".to_string()].into_iter().collect()
+    }
+
+    #[test]
+    fn test_ranks_contacts_by_their_mail() {
+        let kubernetes = vec![1.0, 0.0];
+        let budget = vec![0.0, 1.0];
+        let emails = vec![
+            email(
+                1,
+                "ops@example.com",
+                &["me@example.com"],
+                kubernetes.clone(),
+            ),
+            email(
+                2,
+                "ops@example.com",
+                &["me@example.com"],
+                kubernetes.clone(),
+            ),
+            email(3, "me@example.com", &["ops@example.com"], vec![0.9, 0.1]),
+            email(4, "cfo@example.com", &["me@example.com"], budget.clone()),
+            email(5, "cfo@example.com", &["me@example.com"], budget.clone()),
+            // One on-topic email doesn't beat a steady stream of them
+            email(
+                6,
+                "once@example.com",
+                &["me@example.com"],
+                kubernetes.clone(),
+            ),
+        ];
+
+        let ranked = rank_contacts(&emails, &own(), &kubernetes, 1);
+        let order: Vec<&str> = ranked.iter().map(|c| c.email.as_str()).collect();
+        assert_eq!(
+            order,
+            vec!["ops@example.com", "once@example.com", "cfo@example.com"]
+        );
+        let ops = &ranked[0];
+        assert_eq!((ops.from_them, ops.to_them), (2, 1));
+        assert_eq!(ops.evidence.len(), 3);
+        assert!(ops.evidence[2].sent, "least similar email was the sent one");
+        assert!(ranked[0].score > ranked[1].score);
+
+        let ranked = rank_contacts(&emails, &own(), &kubernetes, 2);
+        assert!(ranked.iter().all(|c| c.email != "once@example.com"));
+    }
+
+    #[test]
+    fn test_skips_bulk_mail_and_own_addresses() {
+        let topic = vec![1.0, 0.0];
+        let mut newsletter = email(1, "news@example.com", &["me@example.com"], topic.clone());
+        newsletter.category = Some(EmailCategory::Newsletter);
+        let mut sent = email(2, "me@example.com", &["me@example.com"], topic.clone());
+        sent.cc = vec![Address::new("Pat@Example.com")];
+        sent.is_sent = true;
+        let mut unembedded = email(3, "x@example.com", &["me@example.com"], topic.clone());
+        unembedded.embedding = None;
+
+        let ranked = rank_contacts(&[newsletter, sent, unembedded], &own(), &topic, 1);
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(ranked[0].email, "pat@example.com");
+        assert_eq!(ranked[0].to_them, 1);
+    }
+}
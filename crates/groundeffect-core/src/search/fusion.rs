@@ -0,0 +1,277 @@
+//! Combining BM25 and vector rankings
+//!
+//! Each query variant (the query and its synonym expansions) produces a BM25
+//! and a vector ranking. They are fused with the strategy from
+//! `search.fusion`: reciprocal rank fusion by default, or a weighted sum of
+//! normalized scores. Each result keeps its best score and rank per
+//! component so JSON output can show why it ranked where it did.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{FusionStrategy, SearchConfig};
+
+/// RRF constant (standard value is 60)
+pub const DEFAULT_RRF_K: f32 = 60.0;
+
+/// Which retrievers a search runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchMode {
+    /// BM25 and vector search, fused
+    #[default]
+    Hybrid,
+    /// Vector search only (meaning, not wording)
+    Semantic,
+    /// BM25 only (exact words; no embedding needed)
+    Keyword,
+}
+
+impl SearchMode {
+    /// Parse a mode name ("hybrid", "semantic", "keyword")
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "hybrid" => Some(Self::Hybrid),
+            "semantic" | "vector" => Some(Self::Semantic),
+            "keyword" | "bm25" => Some(Self::Keyword),
+            _ => None,
+        }
+    }
+
+    /// Name used in options and output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Hybrid => "hybrid",
+            Self::Semantic => "semantic",
+            Self::Keyword => "keyword",
+        }
+    }
+
+    /// Whether BM25 search runs
+    pub fn uses_bm25(&self) -> bool {
+        *self != Self::Semantic
+    }
+
+    /// Whether vector search runs
+    pub fn uses_vectors(&self) -> bool {
+        *self != Self::Keyword
+    }
+}
+
+/// Mode and fusion settings for one search
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FusionOptions {
+    /// Retrievers to run
+    #[serde(default)]
+    pub mode: SearchMode,
+
+    /// How their rankings are combined
+    #[serde(default)]
+    pub strategy: FusionStrategy,
+
+    /// BM25 weight (0.0-1.0)
+    pub bm25_weight: f32,
+
+    /// Vector weight (0.0-1.0)
+    pub vector_weight: f32,
+
+    /// Rank offset for reciprocal rank fusion
+    pub rrf_k: f32,
+}
+
+impl Default for FusionOptions {
+    fn default() -> Self {
+        Self {
+            mode: SearchMode::Hybrid,
+            strategy: FusionStrategy::Rrf,
+            bm25_weight: 0.5,
+            vector_weight: 0.5,
+            rrf_k: DEFAULT_RRF_K,
+        }
+    }
+}
+
+impl FusionOptions {
+    /// Strategy and weights from `[search]`
+    pub fn from_config(search: &SearchConfig) -> Self {
+        Self {
+            mode: SearchMode::Hybrid,
+            strategy: search.fusion,
+            bm25_weight: search.bm25_weight,
+            vector_weight: search.vector_weight,
+            rrf_k: if search.rrf_k > 0.0 {
+                search.rrf_k
+            } else {
+                DEFAULT_RRF_K
+            },
+        }
+    }
+
+    /// Same settings with another mode
+    pub fn with_mode(self, mode: SearchMode) -> Self {
+        Self { mode, ..self }
+    }
+}
+
+/// Retriever a ranking came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Component {
+    Bm25,
+    Vector,
+}
+
+/// One retriever's results for one query variant, best first
+pub struct Ranking {
+    pub component: Component,
+    /// (id, raw score): BM25 score, or `1 / (1 + distance)` for vectors
+    pub results: Vec<(String, f32)>,
+    /// Component weight times the query variant's weight
+    pub weight: f32,
+}
+
+/// Per-component scores of a result (best over query variants)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScoreComponents {
+    /// Raw BM25 score
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bm25: Option<f32>,
+
+    /// 1-based position in the BM25 ranking
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bm25_rank: Option<usize>,
+
+    /// Vector similarity, `1 / (1 + distance)`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vector: Option<f32>,
+
+    /// 1-based position in the vector ranking
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vector_rank: Option<usize>,
+
+    /// Recency decay factor, when a recency boost was applied
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recency: Option<f32>,
+}
+
+impl ScoreComponents {
+    fn record(&mut self, component: Component, score: f32, rank: usize) {
+        let (best_score, best_rank) = match component {
+            Component::Bm25 => (&mut self.bm25, &mut self.bm25_rank),
+            Component::Vector => (&mut self.vector, &mut self.vector_rank),
+        };
+        if !best_score.is_some_and(|s| s >= score) {
+            *best_score = Some(score);
+        }
+        if !best_rank.is_some_and(|r| r <= rank) {
+            *best_rank = Some(rank);
+        }
+    }
+}
+
+/// Combine rankings into (id, fused score, components), best first
+pub fn fuse(rankings: &[Ranking], options: &FusionOptions) -> Vec<(String, f32, ScoreComponents)> {
+    let mut fused: HashMap<String, (f32, ScoreComponents)> = HashMap::new();
+
+    for ranking in rankings {
+        let (min, max) = ranking
+            .results
+            .iter()
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), (_, s)| {
+                (lo.min(*s), hi.max(*s))
+            });
+
+        for (rank, (id, score)) in ranking.results.iter().enumerate() {
+            let contribution = match options.strategy {
+                FusionStrategy::Rrf => ranking.weight / (options.rrf_k + rank as f32 + 1.0),
+                FusionStrategy::Weighted => {
+                    // A ranking with a single distinct score says nothing
+                    // about relative quality; count each result fully
+                    let normalized = if max > min {
+                        (score - min) / (max - min)
+                    } else {
+                        1.0
+                    };
+                    ranking.weight * normalized
+                }
+            };
+            let entry = fused.entry(id.clone()).or_default();
+            entry.0 += contribution;
+            entry.1.record(ranking.component, *score, rank + 1);
+        }
+    }
+
+    let mut results: Vec<(String, f32, ScoreComponents)> = fused
+        .into_iter()
+        .map(|(id, (score, components))| (id, score, components))
+        .collect();
+    results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranking(component: Component, results: &[(&str, f32)], weight: f32) -> Ranking {
+        Ranking {
+            component,
+            results: results.iter().map(|(id, s)| (id.to_string(), *s)).collect(),
+            weight,
+        }
+    }
+
+    #[test]
+    fn test_rrf_rewards_agreement() {
+        let rankings = [
+            ranking(Component::Bm25, &[("a", 12.0), ("b", 3.0)], 0.5),
+            ranking(Component::Vector, &[("b", 0.9), ("c", 0.8)], 0.5),
+        ];
+        let fused = fuse(&rankings, &FusionOptions::default());
+
+        assert_eq!(fused[0].0, "b");
+        assert_eq!(
+            fused[0].2,
+            ScoreComponents {
+                bm25: Some(3.0),
+                bm25_rank: Some(2),
+                vector: Some(0.9),
+                vector_rank: Some(1),
+                recency: None,
+            }
+        );
+        assert_eq!(fused[1].0, "a");
+    }
+
+    #[test]
+    fn test_weighted_sum_keeps_score_gaps() {
+        // "a" leads BM25 by a wide margin and is a close second by vector;
+        // rank fusion ties it with "b", the weighted sum doesn't
+        let rankings = [
+            ranking(Component::Bm25, &[("a", 20.0), ("b", 2.0), ("c", 1.0)], 0.5),
+            ranking(
+                Component::Vector,
+                &[("b", 0.61), ("a", 0.60), ("c", 0.30)],
+                0.5,
+            ),
+        ];
+        let rrf = fuse(&rankings, &FusionOptions::default());
+        assert!((rrf[0].1 - rrf[1].1).abs() < 1e-6);
+
+        let options = FusionOptions {
+            strategy: FusionStrategy::Weighted,
+            ..Default::default()
+        };
+        let fused = fuse(&rankings, &options);
+        assert_eq!(fused[0].0, "a");
+        assert!(fused[0].1 > 0.95 && fused[1].1 < 0.55);
+    }
+
+    #[test]
+    fn test_mode_names() {
+        assert_eq!(SearchMode::from_name("Keyword"), Some(SearchMode::Keyword));
+        assert!(!SearchMode::Keyword.uses_vectors());
+        assert!(!SearchMode::from_name("semantic").unwrap().uses_bm25());
+        assert_eq!(SearchMode::from_name("fuzzy"), None);
+    }
+}
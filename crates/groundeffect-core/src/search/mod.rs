@@ -1,27 +1,52 @@
 //! Hybrid search engine using LanceDB's built-in BM25 and vector search
 //!
 //! Combines BM25 full-text search with vector similarity search using
-//! Reciprocal Rank Fusion (RRF) for optimal results.
-
-use std::collections::HashMap;
+//! Reciprocal Rank Fusion (RRF) or a weighted score sum (`search.fusion`).
+
+mod cache;
+mod diversity;
+mod expertise;
+mod fusion;
+mod plan;
+mod recency;
+mod snippet;
+mod synonyms;
+mod understand;
+mod unified;
+
+pub use cache::*;
+pub use diversity::*;
+pub use expertise::*;
+pub use fusion::*;
+pub use plan::*;
+pub use recency::*;
+pub use snippet::*;
+pub use synonyms::*;
+pub use understand::*;
+pub use unified::*;
+
+use std::path::PathBuf;
 use std::sync::Arc;
 
-use arrow_array::RecordBatch;
+use arrow_array::{Array, FixedSizeListArray, Float32Array, RecordBatch, StringArray};
 use lance_index::scalar::FullTextSearchQuery;
 use lancedb::query::{ExecutableQuery, QueryBase};
 use lancedb::Table;
-use tracing::{debug, info};
+use parking_lot::Mutex;
+use tracing::{debug, info, warn};
 
-use crate::db::Database;
+use crate::config::{Config, SearchConfig};
+use crate::db::{Database, EMAILS_TABLE, EVENTS_TABLE, THREADS_TABLE};
 use crate::embedding::HybridEmbeddingProvider;
-use crate::error::Result;
-use crate::models::{CalendarEvent, EmailSearchResult, EmailSummary};
-
-/// RRF constant (standard value is 60)
-const RRF_K: f32 = 60.0;
+use crate::error::{Error, Result};
+use crate::models::{
+    is_spam_or_trash, CalendarEvent, Email, EmailCategory, EmailSearchResult, EmailSummary, Thread,
+    SPAM_TRASH_FOLDERS, SPAM_TRASH_LABELS,
+};
+use crate::tagging::normalize_tag;
 
 /// Search options
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
 pub struct SearchOptions {
     /// Account IDs to search (None = all accounts)
     pub accounts: Option<Vec<String>>,
@@ -47,11 +72,39 @@ pub struct SearchOptions {
     /// Filter by attachment presence
     pub has_attachment: Option<bool>,
 
-    /// BM25 weight (0.0-1.0)
-    pub bm25_weight: f32,
+    /// Filter by sender category (newsletter, notification, transactional, personal)
+    pub category: Option<EmailCategory>,
+
+    /// Filter by a tag from local tagging rules
+    #[serde(default)]
+    pub tag: Option<String>,
+
+    /// Only mail the account owner sent (true) or received (false)
+    #[serde(default)]
+    pub sent: Option<bool>,
+
+    /// Leave out notification emails collapsed into activity items
+    pub exclude_activity: bool,
+
+    /// Also search Spam and Trash (left out unless `folder` names one)
+    #[serde(default)]
+    pub include_spam_trash: bool,
+
+    /// Mode, fusion strategy and weights
+    #[serde(default)]
+    pub fusion: FusionOptions,
+
+    /// Boost for recent emails
+    #[serde(default)]
+    pub recency: RecencyBoost,
 
-    /// Vector weight (0.0-1.0)
-    pub vector_weight: f32,
+    /// Thread dedup and MMR diversification
+    #[serde(default)]
+    pub diversity: Diversity,
+
+    /// Add `snippet_html` with the query terms wrapped in `<em>`
+    #[serde(default)]
+    pub highlight: bool,
 }
 
 impl SearchOptions {
@@ -59,8 +112,6 @@ impl SearchOptions {
     pub fn new(limit: usize) -> Self {
         Self {
             limit,
-            bm25_weight: 0.5,
-            vector_weight: 0.5,
             ..Default::default()
         }
     }
@@ -78,9 +129,12 @@ impl SearchOptions {
             }
         }
 
-        // Folder filter
+        // Folder filter (matches the IMAP folder or any Gmail label on the email)
         if let Some(folder) = &self.folder {
-            conditions.push(format!("folder = '{}'", folder));
+            conditions.push(format!(
+                "(folder = '{}' OR labels LIKE '%\"{}\"%')",
+                folder, folder
+            ));
         }
 
         // From filter (contains match)
@@ -115,6 +169,44 @@ impl SearchOptions {
             }
         }
 
+        // Category filter
+        if let Some(category) = &self.category {
+            conditions.push(format!("category = '{}'", category.as_str()));
+        }
+
+        // Tag filter (contains match in JSON array)
+        if let Some(tag) = &self.tag {
+            conditions.push(format!("tags LIKE '%\"{}\"%'", normalize_tag(tag)));
+        }
+
+        // Sent/received filter
+        if let Some(sent) = self.sent {
+            conditions.push(format!("is_sent = {}", sent));
+        }
+
+        // Activity filter
+        if self.exclude_activity {
+            conditions.push("activity_source IS NULL".to_string());
+        }
+
+        // Spam/Trash filter (by folder or label; labels are NULL when empty)
+        if !self.include_spam_trash && !self.folder.as_deref().is_some_and(is_spam_or_trash) {
+            let folders: Vec<String> = SPAM_TRASH_FOLDERS
+                .iter()
+                .map(|f| format!("'{}'", f))
+                .collect();
+            let labels: Vec<String> = SPAM_TRASH_FOLDERS
+                .iter()
+                .chain(SPAM_TRASH_LABELS)
+                .map(|l| format!("labels LIKE '%\"{}\"%'", l))
+                .collect();
+            conditions.push(format!(
+                "folder NOT IN ({}) AND (labels IS NULL OR NOT ({}))",
+                folders.join(", "),
+                labels.join(" OR ")
+            ));
+        }
+
         if conditions.is_empty() {
             None
         } else {
@@ -123,16 +215,131 @@ impl SearchOptions {
     }
 }
 
+/// Results kept in the search cache
+#[derive(Clone)]
+enum CachedSearch {
+    Emails(Vec<EmailSearchResult>, SearchPlan),
+    Calendar(Vec<CalendarSearchResult>),
+    Threads(Vec<ThreadSearchResult>),
+}
+
 /// Hybrid search engine
 pub struct SearchEngine {
     db: Arc<Database>,
     embedding: Arc<HybridEmbeddingProvider>,
+    synonyms_path: PathBuf,
+    cache: Mutex<SearchCache<CachedSearch>>,
+    cache_stats_path: Option<PathBuf>,
 }
 
 impl SearchEngine {
+    /// The embedding provider queries (and imported mail) are embedded with
+    pub fn embedding(&self) -> Arc<HybridEmbeddingProvider> {
+        self.embedding.clone()
+    }
+
     /// Create a new search engine
     pub fn new(db: Arc<Database>, embedding: Arc<HybridEmbeddingProvider>) -> Self {
-        Self { db, embedding }
+        let defaults = SearchConfig::default();
+        Self {
+            db,
+            embedding,
+            synonyms_path: Config::synonyms_path(),
+            cache: Mutex::new(SearchCache::new(
+                defaults.cache_entries,
+                std::time::Duration::from_secs(defaults.cache_ttl_secs),
+            )),
+            cache_stats_path: None,
+        }
+    }
+
+    /// Size the result cache from config and publish its stats for
+    /// `groundeffect search stats` (for long-running servers)
+    pub fn with_cache(mut self, config: &Config) -> Self {
+        self.cache = Mutex::new(SearchCache::new(
+            config.search.cache_entries,
+            std::time::Duration::from_secs(config.search.cache_ttl_secs),
+        ));
+        self.cache_stats_path = Some(config.search_cache_stats_file());
+        self
+    }
+
+    /// Hit/miss counters of the result cache
+    pub fn cache_stats(&self) -> SearchCacheStats {
+        self.cache.lock().stats()
+    }
+
+    /// Drop all cached results
+    pub fn clear_cache(&self) {
+        self.cache.lock().clear();
+        self.save_cache_stats();
+    }
+
+    /// Cached results for a key, after dropping entries for an older table version
+    fn cache_lookup(
+        &self,
+        table: &'static str,
+        version: Option<u64>,
+        key: &str,
+    ) -> Option<CachedSearch> {
+        let version = version?;
+        let hit = {
+            let mut cache = self.cache.lock();
+            cache.observe_version(table, version);
+            cache.get(key)
+        };
+        self.save_cache_stats();
+        if hit.is_some() {
+            self.embedding.touch();
+        }
+        hit
+    }
+
+    /// Cache results read at a table version
+    fn cache_store(
+        &self,
+        table: &'static str,
+        version: Option<u64>,
+        key: String,
+        value: CachedSearch,
+    ) {
+        if version.is_none() {
+            return;
+        }
+        self.cache.lock().insert(table, key, value);
+        self.save_cache_stats();
+    }
+
+    fn save_cache_stats(&self) {
+        let Some(path) = &self.cache_stats_path else {
+            return;
+        };
+        let mut stats = self.cache_stats();
+        stats.pid = Some(std::process::id());
+        stats.updated_at = Some(chrono::Utc::now());
+        if let Err(e) = stats.save(path) {
+            debug!("Failed to write search cache stats: {}", e);
+        }
+    }
+
+    /// The query plus its dictionary expansions, each with its weight
+    ///
+    /// The dictionary is re-read on every search so edits apply immediately.
+    fn query_variants(&self, query: &str) -> (Vec<(String, f32)>, Vec<QueryExpansion>) {
+        let expansions = match SynonymDictionary::load(&self.synonyms_path) {
+            Ok(dictionary) => dictionary.expand(query),
+            Err(e) => {
+                warn!("Ignoring synonyms dictionary: {}", e);
+                Vec::new()
+            }
+        };
+        if !expansions.is_empty() {
+            info!("Expanded query '{}' to {:?}", query, expansions);
+        }
+
+        let mut variants = vec![(query.to_string(), 1.0)];
+        variants.extend(expansions.iter().map(|e| (e.query.clone(), e.weight)));
+        (variants, expansions)
     }
 
     /// Search emails using hybrid BM25 + vector search
@@ -141,37 +348,110 @@ impl SearchEngine {
         query: &str,
         options: &SearchOptions,
     ) -> Result<Vec<EmailSearchResult>> {
+        let (results, _plan) = self.search_emails_with_plan(query, options).await?;
+        Ok(results)
+    }
+
+    /// Search emails and report the strategy the planner chose
+    ///
+    /// With strong filters (sender or recipient plus a date bound) matching
+    /// at most [`PREFILTER_MAX_CANDIDATES`] emails, the matching rows are
+    /// scanned and their stored embeddings ranked exactly instead of running
+    /// an ANN search over the whole table.
+    pub async fn search_emails_with_plan(
+        &self,
+        query: &str,
+        options: &SearchOptions,
+    ) -> Result<(Vec<EmailSearchResult>, SearchPlan)> {
         info!(
             "Searching emails: query='{}', limit={}",
             query, options.limit
         );
 
+        let start = std::time::Instant::now();
         let table = self.db.emails_table()?;
+        let (variants, expansions) = self.query_variants(query);
+
+        let version = table.version().await.ok();
+        let cache_key = format!("{}|{:?}|{:?}", EMAILS_TABLE, variants, options);
+        if let Some(CachedSearch::Emails(results, mut plan)) =
+            self.cache_lookup(EMAILS_TABLE, version, &cache_key)
+        {
+            info!("Search cache hit ({} results)", results.len());
+            plan.cached = true;
+            plan.elapsed_ms = start.elapsed().as_millis() as u64;
+            return Ok((results, plan));
+        }
+
         let filter = options.build_filter();
 
         debug!("Search filter: {:?}", filter);
 
-        // Run BM25 and vector search in parallel
+        let candidates = match &filter {
+            Some(f) if options.has_strong_filters() => {
+                Some(table.count_rows(Some(f.clone())).await?)
+            }
+            _ => None,
+        };
+        let fusion = options.fusion;
+        let mut plan = SearchPlan::choose(filter.clone(), candidates);
+        info!("Search plan: {} ({})", plan.strategy.as_str(), plan.reason);
+        plan.mode = fusion.mode;
+        plan.fusion = fusion.strategy;
+        plan.expansions = expansions;
+
+        // Run BM25 and/or vector search in parallel, once per query variant.
+        // Thread dedup and MMR drop or demote results, so they get a deeper pool
+        let strategy = plan.strategy;
+        let depth = options.limit * if options.diversity.is_active() { 4 } else { 2 };
         let search_start = std::time::Instant::now();
-        let (bm25_results, vector_results) = tokio::join!(
-            self.bm25_search_emails(&table, query, &filter, options.limit * 2),
-            self.vector_search_emails(&table, query, &filter, options.limit * 2)
-        );
+        let mut rankings = Vec::with_capacity(variants.len() * 2);
+        for (text, weight) in &variants {
+            let bm25 = async {
+                if !fusion.mode.uses_bm25() {
+                    return Ok(Vec::new());
+                }
+                self.bm25_search_emails(&table, text, &filter, depth).await
+            };
+            let vector = async {
+                if !fusion.mode.uses_vectors() {
+                    return Ok(Vec::new());
+                }
+                match strategy {
+                    SearchStrategy::Hybrid => {
+                        self.vector_search_emails(&table, text, &filter, depth)
+                            .await
+                    }
+                    SearchStrategy::Prefilter => {
+                        self.rerank_candidates(&table, text, &filter, depth).await
+                    }
+                }
+            };
+            let (bm25_results, vector_results) = tokio::join!(bm25, vector);
+            let bm25_results: Vec<(String, f32)> = bm25_results?;
+            let vector_results: Vec<(String, f32)> = vector_results?;
+            plan.bm25_hits += bm25_results.len();
+            plan.vector_hits += vector_results.len();
+            rankings.push(Ranking {
+                component: Component::Bm25,
+                results: bm25_results,
+                weight: fusion.bm25_weight * weight,
+            });
+            rankings.push(Ranking {
+                component: Component::Vector,
+                results: vector_results,
+                weight: fusion.vector_weight * weight,
+            });
+        }
         info!("Search phase took {:?}", search_start.elapsed());
-        let bm25_results = bm25_results?;
-        let vector_results = vector_results?;
-
-        // Combine using RRF
-        let combined = self.rrf_fusion(
-            &bm25_results,
-            &vector_results,
-            options.bm25_weight,
-            options.vector_weight,
-        );
 
-        // Get top result IDs and scores
-        let top_results: Vec<(String, f32)> = combined.into_iter().take(options.limit).collect();
-        let ids: Vec<String> = top_results.iter().map(|(id, _)| id.clone()).collect();
+        // Get top result IDs, scores and their components. A recency boost
+        // or diversity can reorder them, so then every candidate is fetched
+        let mut top_results = fuse(&rankings, &fusion);
+        if !options.recency.is_active() && !options.diversity.is_active() {
+            top_results.truncate(options.limit);
+        }
+        let ids: Vec<String> = top_results.iter().map(|(id, _, _)| id.clone()).collect();
 
         // Batch fetch all emails in a single query
         let fetch_start = std::time::Instant::now();
@@ -182,25 +462,160 @@ impl SearchEngine {
             fetch_start.elapsed()
         );
 
-        // Build a map for O(1) lookup while preserving RRF order
+        // Build a map for O(1) lookup while preserving fused order
         let email_map: std::collections::HashMap<String, _> =
             emails.into_iter().map(|e| (e.id.clone(), e)).collect();
 
-        // Build results in RRF-ranked order
+        if options.recency.is_active() {
+            options.recency.apply(
+                &mut top_results,
+                |id| email_map.get(id).map(|e| e.date),
+                chrono::Utc::now(),
+            );
+        }
+        plan.thread_duplicates = options.diversity.dedup(&mut top_results, |id| {
+            email_map.get(id).map(Email::conversation_key)
+        });
+        if options.diversity.is_mmr_active() {
+            // MMR picks from twice the limit; further candidates are unlikely
+            // to be worth promoting and would each need an embedding
+            top_results.truncate(options.limit * 2);
+            let ids: Vec<String> = top_results.iter().map(|(id, _, _)| id.clone()).collect();
+            let embeddings = self.db.get_email_embeddings(&ids).await?;
+            options.diversity.rerank(
+                &mut top_results,
+                |id| embeddings.get(id).map(Vec::as_slice),
+                options.limit,
+            );
+        }
+        top_results.truncate(options.limit);
+
+        // Build results in fused order, with snippets showing the match
+        let terms = query_terms(variants.iter().map(|(text, _)| text.as_str()));
         let mut results = Vec::with_capacity(top_results.len());
-        for (id, score) in top_results {
+        for (id, score, score_components) in top_results {
             if let Some(email) = email_map.get(&id) {
-                let summary = EmailSummary::from(email);
+                let mut summary = EmailSummary::from(email);
+                let body = if email.body_plain.trim().is_empty() {
+                    &email.snippet
+                } else {
+                    &email.body_plain
+                };
+                let snippet = match_snippet(body, &terms, options.highlight);
+                summary.snippet = snippet.text;
+                summary.snippet_html = snippet.html;
                 results.push(EmailSearchResult {
                     email: summary,
                     score,
+                    score_components,
                     markdown_summary: email.markdown_summary(),
                 });
             }
         }
 
         debug!("Found {} email results", results.len());
-        Ok(results)
+        plan.elapsed_ms = start.elapsed().as_millis() as u64;
+        self.cache_store(
+            EMAILS_TABLE,
+            version,
+            cache_key,
+            CachedSearch::Emails(results.clone(), plan.clone()),
+        );
+        Ok((results, plan))
+    }
+
+    /// Emails nearest another email by vector similarity, excluding the
+    /// email itself, its copies and the rest of its conversation (see
+    /// [`Email::conversation_key`])
+    ///
+    /// `options` filters the candidates the same way as for search; the
+    /// query-related options are ignored.
+    pub async fn similar_emails(
+        &self,
+        id: &str,
+        options: &SearchOptions,
+    ) -> Result<Vec<EmailSearchResult>> {
+        let source = self
+            .db
+            .get_email(id)
+            .await?
+            .ok_or_else(|| Error::EmailNotFound(id.to_string()))?;
+        let embedding = self.db.get_email_embedding(id).await?.ok_or_else(|| {
+            Error::InvalidRequest(format!(
+                "Email {} has no embedding yet, try again after sync has embedded it",
+                id
+            ))
+        })?;
+
+        let mut conditions: Vec<String> = options.build_filter().into_iter().collect();
+        conditions.push(format!("id != '{}'", id.replace('\'', "''")));
+        conditions.push(format!(
+            "message_id != '{}'",
+            source.message_id.replace('\'', "''")
+        ));
+        if source.gmail_thread_id != 0 {
+            conditions.push(format!(
+                "NOT (account_id = '{}' AND gmail_thread_id = {})",
+                source.account_id.replace('\'', "''"),
+                source.gmail_thread_id
+            ));
+        }
+        let filter = Some(conditions.join(" AND "));
+        debug!("Similar emails filter: {:?}", filter);
+
+        let table = self.db.emails_table()?;
+        let conversation = source.conversation_key();
+        // Mail without a thread ID is only known to be in the source's
+        // conversation once fetched, so the search widens until `limit`
+        // others are left or there are no more candidates
+        let mut fetch = options.limit * 2;
+        let mut looked_up = std::collections::HashSet::new();
+        let mut email_map = std::collections::HashMap::new();
+        let hits = loop {
+            let hits = self
+                .vector_search_by_embedding(&table, embedding.clone(), &filter, fetch)
+                .await?;
+            let ids: Vec<String> = hits
+                .iter()
+                .map(|(id, _)| id.clone())
+                .filter(|id| looked_up.insert(id.clone()))
+                .collect();
+            if !ids.is_empty() {
+                for email in self.db.get_emails_batch(&ids).await? {
+                    if email.conversation_key() != conversation {
+                        email_map.insert(email.id.clone(), email);
+                    }
+                }
+            }
+            let others = hits
+                .iter()
+                .filter(|(id, _)| email_map.contains_key(id))
+                .count();
+            if others >= options.limit || hits.len() < fetch {
+                break hits;
+            }
+            fetch *= 2;
+        };
+
+        Ok(hits
+            .into_iter()
+            .filter(|(id, _)| email_map.contains_key(id))
+            .take(options.limit)
+            .enumerate()
+            .filter_map(|(rank, (id, score))| {
+                let email = email_map.get(&id)?;
+                Some(EmailSearchResult {
+                    email: EmailSummary::from(email),
+                    score,
+                    score_components: ScoreComponents {
+                        vector: Some(score),
+                        vector_rank: Some(rank + 1),
+                        ..Default::default()
+                    },
+                    markdown_summary: email.markdown_summary(),
+                })
+            })
+            .collect())
     }
 
     /// BM25 full-text search
@@ -272,8 +687,6 @@ impl SearchEngine {
         filter: &Option<String>,
         limit: usize,
     ) -> Result<Vec<(String, f32)>> {
-        use futures::TryStreamExt;
-
         let start = std::time::Instant::now();
 
         // Generate query embedding (may return None if fallback is BM25-only)
@@ -287,7 +700,28 @@ impl SearchEngine {
         };
         info!("Query embedding took {:?}", embed_start.elapsed());
 
-        let mut search = table.vector_search(query_embedding)?;
+        let scored_results = self
+            .vector_search_by_embedding(table, query_embedding, filter, limit)
+            .await?;
+        info!(
+            "Vector search took {:?}, found {} results",
+            start.elapsed(),
+            scored_results.len()
+        );
+        Ok(scored_results)
+    }
+
+    /// ANN search for the rows nearest an embedding, as (id, similarity)
+    async fn vector_search_by_embedding(
+        &self,
+        table: &Table,
+        embedding: Vec<f32>,
+        filter: &Option<String>,
+        limit: usize,
+    ) -> Result<Vec<(String, f32)>> {
+        use futures::TryStreamExt;
+
+        let mut search = table.vector_search(embedding)?;
 
         if let Some(f) = filter {
             search = search.only_if(f);
@@ -330,41 +764,90 @@ impl SearchEngine {
             }
         }
 
-        info!(
-            "Vector search took {:?}, found {} results",
-            start.elapsed(),
-            scored_results.len()
-        );
         Ok(scored_results)
     }
 
-    /// Combine results using Reciprocal Rank Fusion
-    fn rrf_fusion(
+    /// Exact vector rerank of the rows matching a filter (no ANN index)
+    async fn rerank_candidates(
         &self,
-        bm25_results: &[(String, f32)],
-        vector_results: &[(String, f32)],
-        bm25_weight: f32,
-        vector_weight: f32,
-    ) -> Vec<(String, f32)> {
-        let mut scores: HashMap<String, f32> = HashMap::new();
+        table: &Table,
+        query: &str,
+        filter: &Option<String>,
+        limit: usize,
+    ) -> Result<Vec<(String, f32)>> {
+        use futures::TryStreamExt;
+
+        let start = std::time::Instant::now();
+
+        let query_embedding = match self.embedding.embed(query).await? {
+            Some(emb) => emb,
+            None => {
+                info!("No embedding available, skipping vector rerank (BM25-only)");
+                return Ok(Vec::new());
+            }
+        };
 
-        // Add BM25 scores
-        for (rank, (id, _score)) in bm25_results.iter().enumerate() {
-            let rrf_score = bm25_weight / (RRF_K + rank as f32 + 1.0);
-            *scores.entry(id.clone()).or_default() += rrf_score;
+        let mut scan = table.query();
+        if let Some(f) = filter {
+            scan = scan.only_if(f);
         }
+        let results = scan
+            .select(lancedb::query::Select::columns(&["id", "embedding"]))
+            .execute()
+            .await?;
 
-        // Add vector scores
-        for (rank, (id, _score)) in vector_results.iter().enumerate() {
-            let rrf_score = vector_weight / (RRF_K + rank as f32 + 1.0);
-            *scores.entry(id.clone()).or_default() += rrf_score;
+        let batches: Vec<RecordBatch> = results.try_collect().await?;
+
+        let mut scored_results = Vec::new();
+        for batch in &batches {
+            let Some(ids) = batch
+                .column_by_name("id")
+                .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+            else {
+                continue;
+            };
+            let Some(embeddings) = batch
+                .column_by_name("embedding")
+                .and_then(|c| c.as_any().downcast_ref::<FixedSizeListArray>())
+            else {
+                continue;
+            };
+            // Every row would silently score as unembedded; say why instead
+            let stored = embeddings.value_length() as usize;
+            if stored != query_embedding.len() {
+                return Err(Error::Embedding(format!(
+                    "The configured embedding model produces {}-dimensional vectors but the {} table stores {}; \
+                     restart the daemon to rebuild and re-embed the table, or set search.embedding_model back",
+                    query_embedding.len(),
+                    table.name(),
+                    stored
+                )));
+            }
+
+            for row in 0..batch.num_rows() {
+                if embeddings.is_null(row) {
+                    continue;
+                }
+                let values = embeddings.value(row);
+                let Some(vector) = values.as_any().downcast_ref::<Float32Array>() else {
+                    continue;
+                };
+                // Rows without an embedding yet are left to BM25
+                if let Some(distance) = l2_distance(&query_embedding, vector.values()) {
+                    scored_results.push((ids.value(row).to_string(), 1.0 / (1.0 + distance)));
+                }
+            }
         }
 
-        // Sort by combined score
-        let mut results: Vec<(String, f32)> = scores.into_iter().collect();
-        results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        scored_results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        scored_results.truncate(limit);
 
-        results
+        info!(
+            "Vector rerank took {:?}, found {} results",
+            start.elapsed(),
+            scored_results.len()
+        );
+        Ok(scored_results)
     }
 
     /// Search calendar events using hybrid BM25 + vector search
@@ -380,46 +863,179 @@ impl SearchEngine {
 
         let table = self.db.events_table()?;
         let filter = options.build_filter();
+        let (variants, _) = self.query_variants(query);
+
+        let version = table.version().await.ok();
+        let cache_key = format!("{}|{:?}|{:?}", EVENTS_TABLE, variants, options);
+        if let Some(CachedSearch::Calendar(results)) =
+            self.cache_lookup(EVENTS_TABLE, version, &cache_key)
+        {
+            info!("Search cache hit ({} results)", results.len());
+            return Ok(results);
+        }
 
-        // Run BM25 and vector search in parallel
-        let (bm25_results, vector_results) = tokio::join!(
-            self.bm25_search_events(&table, query, &filter, options.limit * 2),
-            self.vector_search_events(&table, query, &filter, options.limit * 2)
-        );
-        let bm25_results = bm25_results?;
-        let vector_results = vector_results?;
-
-        // Combine using RRF
-        let combined = self.rrf_fusion(&bm25_results, &vector_results, 0.5, 0.5);
+        // Run BM25 and/or vector search in parallel, once per query variant
+        let fusion = options.fusion;
+        let mut rankings = Vec::with_capacity(variants.len() * 2);
+        for (text, weight) in &variants {
+            let bm25 = async {
+                if !fusion.mode.uses_bm25() {
+                    return Ok(Vec::new());
+                }
+                self.bm25_search_table(&table, text, &filter, options.limit * 2)
+                    .await
+            };
+            let vector = async {
+                if !fusion.mode.uses_vectors() {
+                    return Ok(Vec::new());
+                }
+                self.vector_search_table(&table, text, &filter, options.limit * 2)
+                    .await
+            };
+            let (bm25_results, vector_results) = tokio::join!(bm25, vector);
+            rankings.push(Ranking {
+                component: Component::Bm25,
+                results: bm25_results?,
+                weight: fusion.bm25_weight * weight,
+            });
+            rankings.push(Ranking {
+                component: Component::Vector,
+                results: vector_results?,
+                weight: fusion.vector_weight * weight,
+            });
+        }
 
-        // Get top result IDs and scores
-        let top_results: Vec<(String, f32)> = combined.into_iter().take(options.limit).collect();
-        let ids: Vec<String> = top_results.iter().map(|(id, _)| id.clone()).collect();
+        // Get top result IDs, scores and their components
+        let top_results: Vec<(String, f32, ScoreComponents)> = fuse(&rankings, &fusion)
+            .into_iter()
+            .take(options.limit)
+            .collect();
+        let ids: Vec<String> = top_results.iter().map(|(id, _, _)| id.clone()).collect();
 
         // Batch fetch all events in a single query
         let events = self.db.get_events_batch(&ids).await?;
 
-        // Build a map for O(1) lookup while preserving RRF order
+        // Build a map for O(1) lookup while preserving fused order
         let event_map: std::collections::HashMap<String, _> =
             events.into_iter().map(|e| (e.id.clone(), e)).collect();
 
-        // Build results in RRF-ranked order
+        // Build results in fused order
         let mut results = Vec::with_capacity(top_results.len());
-        for (id, score) in top_results {
+        for (id, score, score_components) in top_results {
             if let Some(event) = event_map.get(&id) {
                 results.push(CalendarSearchResult {
+                    web_link: event.web_link(),
                     event: event.clone(),
                     score,
+                    score_components,
                 });
             }
         }
 
         debug!("Found {} calendar results", results.len());
+        self.cache_store(
+            EVENTS_TABLE,
+            version,
+            cache_key,
+            CachedSearch::Calendar(results.clone()),
+        );
         Ok(results)
     }
 
-    /// BM25 full-text search for events
-    async fn bm25_search_events(
+    /// Search conversation threads using hybrid BM25 + vector search
+    pub async fn search_threads(
+        &self,
+        query: &str,
+        options: &ThreadSearchOptions,
+    ) -> Result<Vec<ThreadSearchResult>> {
+        info!(
+            "Searching threads: query='{}', limit={}",
+            query, options.limit
+        );
+
+        let table = self.db.threads_table()?;
+        let filter = options.build_filter();
+        let (variants, _) = self.query_variants(query);
+
+        let version = table.version().await.ok();
+        let cache_key = format!("{}|{:?}|{:?}", THREADS_TABLE, variants, options);
+        if let Some(CachedSearch::Threads(results)) =
+            self.cache_lookup(THREADS_TABLE, version, &cache_key)
+        {
+            info!("Search cache hit ({} results)", results.len());
+            return Ok(results);
+        }
+
+        // Run BM25 and/or vector search in parallel, once per query variant
+        let fusion = options.fusion;
+        let mut rankings = Vec::with_capacity(variants.len() * 2);
+        for (text, weight) in &variants {
+            let bm25 = async {
+                if !fusion.mode.uses_bm25() {
+                    return Ok(Vec::new());
+                }
+                self.bm25_search_table(&table, text, &filter, options.limit * 2)
+                    .await
+            };
+            let vector = async {
+                if !fusion.mode.uses_vectors() {
+                    return Ok(Vec::new());
+                }
+                self.vector_search_table(&table, text, &filter, options.limit * 2)
+                    .await
+            };
+            let (bm25_results, vector_results) = tokio::join!(bm25, vector);
+            rankings.push(Ranking {
+                component: Component::Bm25,
+                results: bm25_results?,
+                weight: fusion.bm25_weight * weight,
+            });
+            rankings.push(Ranking {
+                component: Component::Vector,
+                results: vector_results?,
+                weight: fusion.vector_weight * weight,
+            });
+        }
+
+        // Get top result IDs, scores and their components
+        let top_results: Vec<(String, f32, ScoreComponents)> = fuse(&rankings, &fusion)
+            .into_iter()
+            .take(options.limit)
+            .collect();
+        let ids: Vec<String> = top_results.iter().map(|(id, _, _)| id.clone()).collect();
+
+        // Batch fetch all threads in a single query
+        let threads = self.db.get_threads_batch(&ids).await?;
+
+        // Build a map for O(1) lookup while preserving fused order
+        let thread_map: std::collections::HashMap<String, _> =
+            threads.into_iter().map(|t| (t.id.clone(), t)).collect();
+
+        // Build results in fused order
+        let mut results = Vec::with_capacity(top_results.len());
+        for (id, score, score_components) in top_results {
+            if let Some(thread) = thread_map.get(&id) {
+                results.push(ThreadSearchResult {
+                    web_link: thread.web_link(),
+                    thread: thread.clone(),
+                    score,
+                    score_components,
+                });
+            }
+        }
+
+        debug!("Found {} thread results", results.len());
+        self.cache_store(
+            THREADS_TABLE,
+            version,
+            cache_key,
+            CachedSearch::Threads(results.clone()),
+        );
+        Ok(results)
+    }
+
+    /// BM25 full-text search for events and threads
+    async fn bm25_search_table(
         &self,
         table: &Table,
         query: &str,
@@ -472,8 +1088,8 @@ impl SearchEngine {
         Ok(scored_results)
     }
 
-    /// Vector similarity search for events
-    async fn vector_search_events(
+    /// Vector similarity search for events and threads
+    async fn vector_search_table(
         &self,
         table: &Table,
         query: &str,
@@ -537,7 +1153,7 @@ impl SearchEngine {
 }
 
 /// Calendar search options
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
 pub struct CalendarSearchOptions {
     /// Account IDs to search (None = all accounts)
     pub accounts: Option<Vec<String>>,
@@ -553,6 +1169,10 @@ pub struct CalendarSearchOptions {
 
     /// Filter by date (before)
     pub date_to: Option<chrono::DateTime<chrono::Utc>>,
+
+    /// Mode, fusion strategy and weights
+    #[serde(default)]
+    pub fusion: FusionOptions,
 }
 
 impl CalendarSearchOptions {
@@ -599,6 +1219,89 @@ pub struct CalendarSearchResult {
 
     /// Search relevance score
     pub score: f32,
+
+    /// BM25 and vector scores behind `score`
+    #[serde(default)]
+    pub score_components: ScoreComponents,
+
+    /// The event in Google Calendar on the web
+    #[serde(default)]
+    pub web_link: String,
+}
+
+/// Thread search options
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct ThreadSearchOptions {
+    /// Account IDs to search (None = all accounts)
+    pub accounts: Option<Vec<String>>,
+
+    /// Maximum number of results
+    pub limit: usize,
+
+    /// Filter by participant (contains match on address or name)
+    pub participant: Option<String>,
+
+    /// Filter by latest activity (after)
+    pub date_from: Option<chrono::DateTime<chrono::Utc>>,
+
+    /// Filter by latest activity (before)
+    pub date_to: Option<chrono::DateTime<chrono::Utc>>,
+
+    /// Mode, fusion strategy and weights
+    #[serde(default)]
+    pub fusion: FusionOptions,
+}
+
+impl ThreadSearchOptions {
+    /// Build a SQL WHERE clause from the filters
+    pub fn build_filter(&self) -> Option<String> {
+        let mut conditions = Vec::new();
+
+        if let Some(accounts) = &self.accounts {
+            if !accounts.is_empty() {
+                let account_list: Vec<String> =
+                    accounts.iter().map(|a| format!("'{}'", a)).collect();
+                conditions.push(format!("account_id IN ({})", account_list.join(", ")));
+            }
+        }
+
+        // Participants are a JSON array of addresses
+        if let Some(participant) = &self.participant {
+            conditions.push(format!("participants LIKE '%{}%'", participant));
+        }
+
+        if let Some(date_from) = &self.date_from {
+            conditions.push(format!("last_activity >= {}", date_from.timestamp()));
+        }
+        if let Some(date_to) = &self.date_to {
+            conditions.push(format!("last_activity <= {}", date_to.timestamp()));
+        }
+
+        if conditions.is_empty() {
+            None
+        } else {
+            Some(conditions.join(" AND "))
+        }
+    }
+}
+
+/// Thread search result
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ThreadSearchResult {
+    /// The thread
+    #[serde(flatten)]
+    pub thread: Thread,
+
+    /// Search relevance score
+    pub score: f32,
+
+    /// BM25 and vector scores behind `score`
+    #[serde(default)]
+    pub score_components: ScoreComponents,
+
+    /// The conversation in Gmail on the web
+    #[serde(default)]
+    pub web_link: String,
 }
 
 /// Search response for MCP
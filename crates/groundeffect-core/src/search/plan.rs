@@ -0,0 +1,181 @@
+//! Query planning for email search
+//!
+//! Queries narrowed by sender/recipient and date usually match a few hundred
+//! emails at most. For those, scanning the matching rows and ranking their
+//! stored embeddings directly is both cheaper and more exact than an ANN
+//! search over the whole table followed by filtering.
+
+use serde::{Deserialize, Serialize};
+
+use super::{QueryExpansion, SearchMode, SearchOptions};
+use crate::config::FusionStrategy;
+
+/// Most candidates the metadata pre-filter will rerank in memory
+pub const PREFILTER_MAX_CANDIDATES: usize = 2000;
+
+/// How a search is executed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchStrategy {
+    /// BM25 + approximate nearest neighbour search over the whole table
+    Hybrid,
+    /// Metadata scan for candidates, then BM25 + exact vector rerank of those
+    Prefilter,
+}
+
+impl SearchStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Hybrid => "hybrid",
+            Self::Prefilter => "prefilter",
+        }
+    }
+}
+
+/// The chosen strategy and why (`--explain` output)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchPlan {
+    /// Strategy used
+    pub strategy: SearchStrategy,
+
+    /// Why it was chosen
+    pub reason: String,
+
+    /// Retrievers run
+    #[serde(default)]
+    pub mode: SearchMode,
+
+    /// How their rankings were combined
+    #[serde(default)]
+    pub fusion: FusionStrategy,
+
+    /// SQL filter applied to both BM25 and vector search
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+
+    /// Rows matching the filter (only counted when filters are strong)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub candidates: Option<usize>,
+
+    /// Synonym expansions also searched
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub expansions: Vec<QueryExpansion>,
+
+    /// Results from BM25 (summed over query variants)
+    pub bm25_hits: usize,
+
+    /// Results from vector search or rerank (summed over query variants)
+    pub vector_hits: usize,
+
+    /// Results dropped as other messages of a thread already shown
+    #[serde(default)]
+    pub thread_duplicates: usize,
+
+    /// Total search time in milliseconds
+    pub elapsed_ms: u64,
+
+    /// Served from the search cache (counts and plan are from the original run)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cached: bool,
+}
+
+impl SearchPlan {
+    /// Pick a strategy given the filter and, for strong filters, how many
+    /// rows it matches
+    pub fn choose(filter: Option<String>, candidates: Option<usize>) -> Self {
+        let (strategy, reason) = match candidates {
+            None => (
+                SearchStrategy::Hybrid,
+                "no sender/recipient + date filter to narrow candidates".to_string(),
+            ),
+            Some(n) if n > PREFILTER_MAX_CANDIDATES => (
+                SearchStrategy::Hybrid,
+                format!(
+                    "filters match {} emails, more than the {} rerank limit",
+                    n, PREFILTER_MAX_CANDIDATES
+                ),
+            ),
+            Some(n) => (
+                SearchStrategy::Prefilter,
+                format!("filters narrow the search to {} emails", n),
+            ),
+        };
+
+        Self {
+            strategy,
+            reason,
+            mode: SearchMode::Hybrid,
+            fusion: FusionStrategy::Rrf,
+            filter,
+            candidates,
+            expansions: Vec::new(),
+            bm25_hits: 0,
+            vector_hits: 0,
+            thread_duplicates: 0,
+            elapsed_ms: 0,
+            cached: false,
+        }
+    }
+}
+
+impl SearchOptions {
+    /// Whether the filters are selective enough to consider a pre-filter
+    /// (a sender or recipient plus a date bound)
+    pub fn has_strong_filters(&self) -> bool {
+        (self.from.is_some() || self.to.is_some())
+            && (self.date_from.is_some() || self.date_to.is_some())
+    }
+}
+
+/// Squared L2 distance (LanceDB's default metric), or None for an
+/// unembedded (all-zero) vector
+pub(crate) fn l2_distance(query: &[f32], vector: &[f32]) -> Option<f32> {
+    if vector.len() != query.len() || vector.iter().all(|x| *x == 0.0) {
+        return None;
+    }
+    Some(
+        query
+            .iter()
+            .zip(vector)
+            .map(|(a, b)| (a - b) * (a - b))
+            .sum(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strong_filters() {
+        let mut options = SearchOptions::new(10);
+        options.from = Some("alice@example.com".to_string());
+        assert!(!options.has_strong_filters());
+
+        options.date_from = Some(chrono::Utc::now());
+        assert!(options.has_strong_filters());
+    }
+
+    #[test]
+    fn test_choose_strategy() {
+        assert_eq!(
+            SearchPlan::choose(None, None).strategy,
+            SearchStrategy::Hybrid
+        );
+        assert_eq!(
+            SearchPlan::choose(None, Some(40)).strategy,
+            SearchStrategy::Prefilter
+        );
+        assert_eq!(
+            SearchPlan::choose(None, Some(PREFILTER_MAX_CANDIDATES + 1)).strategy,
+            SearchStrategy::Hybrid
+        );
+    }
+
+    #[test]
+    fn test_l2_distance_skips_unembedded_rows() {
+        assert_eq!(l2_distance(&[1.0, 0.0], &[0.0, 0.0]), None);
+        assert_eq!(l2_distance(&[1.0, 0.0], &[1.0]), None);
+        assert_eq!(l2_distance(&[1.0, 0.0], &[0.0, 1.0]), Some(2.0));
+    }
+}
@@ -0,0 +1,127 @@
+//! Recency boost for email ranking
+//!
+//! Assistant queries like "the flight confirmation" almost always mean the
+//! latest match. With a recency weight `w`, each candidate's fused score is
+//! scaled to 0-1 against the best candidate and blended with an exponential
+//! decay of its age: `(1 - w) * relevance + w * 0.5^(age / half_life)`.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::ScoreComponents;
+use crate::config::SearchConfig;
+
+/// Recency settings for one search
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RecencyBoost {
+    /// Share of the final score from recency (0.0-1.0; 0 disables)
+    pub weight: f32,
+
+    /// Age in days at which the boost halves
+    pub half_life_days: f32,
+}
+
+impl Default for RecencyBoost {
+    fn default() -> Self {
+        Self {
+            weight: 0.0,
+            half_life_days: 30.0,
+        }
+    }
+}
+
+impl RecencyBoost {
+    /// Weight and half-life from `[search]`
+    pub fn from_config(search: &SearchConfig) -> Self {
+        Self {
+            weight: search.recency_weight,
+            half_life_days: search.recency_half_life_days,
+        }
+    }
+
+    /// Same half-life with another weight
+    pub fn with_weight(self, weight: f32) -> Self {
+        Self { weight, ..self }
+    }
+
+    /// Whether the boost changes ranking at all
+    pub fn is_active(&self) -> bool {
+        self.weight > 0.0 && self.half_life_days > 0.0
+    }
+
+    /// Decay factor for something dated `date`: 1.0 now, 0.5 one half-life ago
+    pub fn decay(&self, date: DateTime<Utc>, now: DateTime<Utc>) -> f32 {
+        let age_days = (now - date).num_seconds().max(0) as f32 / 86_400.0;
+        0.5_f32.powf(age_days / self.half_life_days)
+    }
+
+    /// Blend recency into fused results and re-sort them
+    ///
+    /// Results without a date keep only their relevance share.
+    pub fn apply(
+        &self,
+        results: &mut [(String, f32, ScoreComponents)],
+        date: impl Fn(&str) -> Option<DateTime<Utc>>,
+        now: DateTime<Utc>,
+    ) {
+        if !self.is_active() {
+            return;
+        }
+        let weight = self.weight.min(1.0);
+        let best = results
+            .iter()
+            .map(|(_, score, _)| *score)
+            .fold(0.0_f32, f32::max);
+
+        for (id, score, components) in results.iter_mut() {
+            let relevance = if best > 0.0 { *score / best } else { 0.0 };
+            let recency = date(id).map(|d| self.decay(d, now));
+            components.recency = recency;
+            *score = (1.0 - weight) * relevance + weight * recency.unwrap_or(0.0);
+        }
+        results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_decay_halves_each_half_life() {
+        let boost = RecencyBoost::default().with_weight(0.5);
+        let now = Utc::now();
+        assert!((boost.decay(now, now) - 1.0).abs() < 1e-6);
+        assert!((boost.decay(now - Duration::days(30), now) - 0.5).abs() < 1e-4);
+        assert!((boost.decay(now - Duration::days(60), now) - 0.25).abs() < 1e-4);
+        // Future dates (clock skew) count as now
+        assert!((boost.decay(now + Duration::days(3), now) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_recent_match_overtakes_slightly_better_old_one() {
+        let now = Utc::now();
+        let dates = |id: &str| match id {
+            "old" => Some(now - Duration::days(400)),
+            "new" => Some(now - Duration::days(2)),
+            _ => None,
+        };
+        let mut results = vec![
+            ("old".to_string(), 0.030, ScoreComponents::default()),
+            ("new".to_string(), 0.027, ScoreComponents::default()),
+            ("undated".to_string(), 0.029, ScoreComponents::default()),
+        ];
+
+        RecencyBoost::default().apply(&mut results, dates, now);
+        assert_eq!(results[0].0, "old", "weight 0 leaves the order alone");
+
+        RecencyBoost::default()
+            .with_weight(0.3)
+            .apply(&mut results, dates, now);
+        let order: Vec<&str> = results.iter().map(|(id, _, _)| id.as_str()).collect();
+        assert_eq!(order, vec!["new", "old", "undated"]);
+        assert!(results[0].2.recency.unwrap() > 0.9);
+        assert_eq!(results[2].2.recency, None);
+    }
+}
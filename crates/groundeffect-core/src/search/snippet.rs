@@ -0,0 +1,230 @@
+//! Match-aware snippets for search results
+//!
+//! The stored snippet is the start of the body, which often doesn't show why
+//! an email matched. [`match_snippet`] instead picks the window of the body
+//! holding the most distinct query terms, and can mark each match with
+//! `<em>` for a result's `snippet_html`.
+
+/// Characters in a snippet (the length of the stored snippet)
+pub const SNIPPET_CHARS: usize = 200;
+
+/// Context kept before the first match in a window
+const LEAD_IN_CHARS: usize = 40;
+
+/// Words too common to be worth showing
+const STOPWORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "for", "from", "in", "is", "it", "of", "on",
+    "or", "that", "the", "this", "to", "was", "with",
+];
+
+/// A snippet, with the query terms marked up when highlighting was asked for
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snippet {
+    pub text: String,
+    pub html: Option<String>,
+}
+
+/// Lowercased, de-duplicated words of the query and its expansions
+pub fn query_terms<'a>(queries: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut terms: Vec<String> = Vec::new();
+    for query in queries {
+        for (start, end) in words(&query.chars().collect::<Vec<_>>()) {
+            let term: String = query
+                .chars()
+                .skip(start)
+                .take(end - start)
+                .collect::<String>()
+                .to_lowercase();
+            if term.chars().count() >= 2
+                && !STOPWORDS.contains(&term.as_str())
+                && !terms.contains(&term)
+            {
+                terms.push(term);
+            }
+        }
+    }
+    terms
+}
+
+/// The [`SNIPPET_CHARS`] of `body` that best show why it matched `terms`
+///
+/// Whitespace is collapsed. Words match a term exactly or, for terms of four
+/// or more letters, by prefix ("invoice" matches "invoices"). Without any
+/// match the snippet is the start of the body.
+pub fn match_snippet(body: &str, terms: &[String], highlight: bool) -> Snippet {
+    let chars: Vec<char> = body
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .collect();
+
+    // (start, end, term index) of every matching word
+    let matches: Vec<(usize, usize, usize)> = words(&chars)
+        .into_iter()
+        .filter_map(|(start, end)| {
+            let word: String = chars[start..end].iter().collect::<String>().to_lowercase();
+            terms
+                .iter()
+                .position(|term| {
+                    word == *term || (term.chars().count() >= 4 && word.starts_with(term.as_str()))
+                })
+                .map(|term| (start, end, term))
+        })
+        .collect();
+
+    // Window with the most distinct terms, then the most matches, earliest first
+    let mut best: Option<(usize, usize, usize)> = None; // (anchor, distinct, total)
+    for (i, &(anchor, _, _)) in matches.iter().enumerate() {
+        let in_window: Vec<_> = matches[i..]
+            .iter()
+            .take_while(|(_, end, _)| *end <= anchor + SNIPPET_CHARS - LEAD_IN_CHARS)
+            .collect();
+        let mut distinct: Vec<usize> = in_window.iter().map(|(_, _, term)| *term).collect();
+        distinct.sort_unstable();
+        distinct.dedup();
+        let score = (anchor, distinct.len(), in_window.len());
+        if best.map(|(_, d, t)| (d, t)) < Some((score.1, score.2)) {
+            best = Some(score);
+        }
+    }
+
+    let mut start = best.map_or(0, |(anchor, _, _)| anchor.saturating_sub(LEAD_IN_CHARS));
+    // Don't open mid-word: skip to the word after the first space
+    if start > 0 && chars[start - 1] != ' ' {
+        if let Some(space) = chars[start..].iter().position(|c| *c == ' ') {
+            let first_match = best.map_or(start, |(anchor, _, _)| anchor);
+            if start + space < first_match {
+                start += space + 1;
+            }
+        }
+    }
+    let mut end = (start + SNIPPET_CHARS).min(chars.len());
+    // Don't close mid-word either, unless that would drop a match
+    if end < chars.len() && chars[end] != ' ' {
+        let last_match = matches
+            .iter()
+            .filter(|(s, e, _)| *s >= start && *e <= end)
+            .map(|(_, e, _)| *e)
+            .max()
+            .unwrap_or(start);
+        if let Some(space) = chars[last_match..end].iter().rposition(|c| *c == ' ') {
+            end = last_match + space;
+        }
+    }
+
+    let prefix = if start > 0 { "…" } else { "" };
+    let suffix = if end < chars.len() { "…" } else { "" };
+    let text = format!(
+        "{}{}{}",
+        prefix,
+        chars[start..end].iter().collect::<String>(),
+        suffix
+    );
+
+    let html = highlight.then(|| {
+        let mut html = String::from(prefix);
+        let mut pos = start;
+        for &(s, e, _) in matches.iter().filter(|(s, e, _)| *s >= start && *e <= end) {
+            html.push_str(&escape_html(&chars[pos..s]));
+            html.push_str("<em>");
+            html.push_str(&escape_html(&chars[s..e]));
+            html.push_str("</em>");
+            pos = e;
+        }
+        html.push_str(&escape_html(&chars[pos..end]));
+        html.push_str(suffix);
+        html
+    });
+
+    Snippet { text, html }
+}
+
+/// (start, end) char offsets of each run of letters and digits
+fn words(chars: &[char]) -> Vec<(usize, usize)> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (i, c) in chars.iter().enumerate() {
+        match (c.is_alphanumeric(), start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                words.push((s, i));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        words.push((s, chars.len()));
+    }
+    words
+}
+
+fn escape_html(chars: &[char]) -> String {
+    let mut escaped = String::with_capacity(chars.len());
+    for c in chars {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(*c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_terms_drop_stopwords_and_duplicates() {
+        assert_eq!(
+            query_terms(["Invoice for the Q3 invoice", "quarterly invoice"]),
+            vec!["invoice", "q3", "quarterly"]
+        );
+    }
+
+    #[test]
+    fn test_snippet_centers_on_the_best_match() {
+        let body = format!(
+            "{} Please find the attached invoice for the Q3 retainer. {}",
+            "Lorem ipsum dolor sit amet. ".repeat(20),
+            "Regards, Alice. ".repeat(20)
+        );
+        let terms = query_terms(["q3 invoice"]);
+        let snippet = match_snippet(&body, &terms, true);
+
+        assert!(snippet.text.starts_with('…') && snippet.text.ends_with('…'));
+        assert!(snippet
+            .text
+            .contains("attached invoice for the Q3 retainer"));
+        assert!(snippet.text.chars().count() <= SNIPPET_CHARS + 2);
+        let html = snippet.html.unwrap();
+        assert!(html.contains("attached <em>invoice</em> for the <em>Q3</em> retainer"));
+    }
+
+    #[test]
+    fn test_snippet_prefix_match_and_escaping() {
+        let snippet = match_snippet(
+            "Two invoices <attached> & paid",
+            &query_terms(["invoice"]),
+            true,
+        );
+        assert_eq!(snippet.text, "Two invoices <attached> & paid");
+        assert_eq!(
+            snippet.html.as_deref(),
+            Some("Two <em>invoices</em> &lt;attached&gt; &amp; paid")
+        );
+    }
+
+    #[test]
+    fn test_snippet_without_match_is_the_start() {
+        let body = "word ".repeat(100);
+        let snippet = match_snippet(&body, &query_terms(["budget"]), false);
+        assert!(snippet.text.starts_with("word word"));
+        assert!(snippet.text.ends_with("word…"));
+        assert_eq!(snippet.html, None);
+    }
+}
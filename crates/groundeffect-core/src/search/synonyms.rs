@@ -0,0 +1,222 @@
+//! Query expansion from a user-editable synonyms/acronyms dictionary
+//!
+//! `~/.config/groundeffect/search.synonyms.toml` maps terms to expansions:
+//!
+//! ```toml
+//! discount = 0.6   # weight of expanded queries relative to the original
+//!
+//! [synonyms]
+//! QBR = ["quarterly business review"]
+//! PTO = { expansions = ["paid time off", "vacation"], discount = 0.8 }
+//! ```
+//!
+//! A query containing a term (whole word, case-insensitive) is also run with
+//! the term replaced by each expansion, and those results count for
+//! `discount` times as much when the rankings are fused.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+/// Default weight of expanded queries
+const DEFAULT_DISCOUNT: f32 = 0.6;
+
+/// Most expanded queries run per search (each is a full search)
+pub const MAX_EXPANSIONS: usize = 3;
+
+/// Parsed `search.synonyms.toml`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SynonymDictionary {
+    /// Weight of expanded queries unless an entry sets its own
+    #[serde(default)]
+    pub discount: Option<f32>,
+
+    /// Term -> expansions
+    #[serde(default)]
+    pub synonyms: BTreeMap<String, SynonymEntry>,
+}
+
+/// Expansions for one term
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum SynonymEntry {
+    /// `QBR = ["quarterly business review"]`
+    List(Vec<String>),
+    /// `PTO = { expansions = [...], discount = 0.8 }`
+    Weighted {
+        expansions: Vec<String>,
+        #[serde(default)]
+        discount: Option<f32>,
+    },
+}
+
+/// A query rewritten with one expansion
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryExpansion {
+    /// Dictionary term that matched
+    pub term: String,
+
+    /// Query with the term replaced
+    pub query: String,
+
+    /// Weight of this query's results (0.0-1.0)
+    pub weight: f32,
+}
+
+impl SynonymDictionary {
+    /// Load the dictionary (empty if the file doesn't exist)
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = std::fs::read_to_string(path)?;
+        Self::parse(&contents)
+            .map_err(|e| Error::Config(format!("Invalid synonyms file {:?}: {}", path, e)))
+    }
+
+    /// Parse dictionary TOML
+    pub fn parse(contents: &str) -> Result<Self> {
+        let dictionary: Self = toml::from_str(contents)?;
+        for (term, entry) in &dictionary.synonyms {
+            let discount = entry.discount().or(dictionary.discount);
+            if let Some(d) = discount.filter(|d| !(0.0..=1.0).contains(d)) {
+                return Err(Error::Config(format!(
+                    "discount for '{}' must be between 0 and 1, got {}",
+                    term, d
+                )));
+            }
+        }
+        Ok(dictionary)
+    }
+
+    /// Expanded versions of a query, best first (at most [`MAX_EXPANSIONS`])
+    pub fn expand(&self, query: &str) -> Vec<QueryExpansion> {
+        let default_discount = self.discount.unwrap_or(DEFAULT_DISCOUNT);
+        let mut expansions = Vec::new();
+
+        for (term, entry) in &self.synonyms {
+            let Some((start, end)) = find_word(query, term) else {
+                continue;
+            };
+            let weight = entry.discount().unwrap_or(default_discount);
+            for expansion in entry.expansions() {
+                if expansion.trim().is_empty() || expansion.eq_ignore_ascii_case(term) {
+                    continue;
+                }
+                expansions.push(QueryExpansion {
+                    term: term.clone(),
+                    query: format!("{}{}{}", &query[..start], expansion, &query[end..]),
+                    weight,
+                });
+            }
+        }
+
+        expansions.sort_by(|a, b| {
+            b.weight
+                .partial_cmp(&a.weight)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        expansions.truncate(MAX_EXPANSIONS);
+        expansions
+    }
+}
+
+impl SynonymEntry {
+    fn expansions(&self) -> &[String] {
+        match self {
+            Self::List(expansions) | Self::Weighted { expansions, .. } => expansions,
+        }
+    }
+
+    fn discount(&self) -> Option<f32> {
+        match self {
+            Self::List(_) => None,
+            Self::Weighted { discount, .. } => *discount,
+        }
+    }
+}
+
+/// Byte range of `term` in `text` as a whole word, ignoring ASCII case
+fn find_word(text: &str, term: &str) -> Option<(usize, usize)> {
+    let term = term.trim();
+    if term.is_empty() {
+        return None;
+    }
+    let lower = text.to_ascii_lowercase();
+    let needle = term.to_ascii_lowercase();
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric());
+
+    let mut from = 0;
+    while let Some(offset) = lower[from..].find(&needle) {
+        let start = from + offset;
+        let end = start + needle.len();
+        if !is_word(lower[..start].chars().next_back()) && !is_word(lower[end..].chars().next()) {
+            return Some((start, end));
+        }
+        from = start + 1;
+        while !lower.is_char_boundary(from) {
+            from += 1;
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DICTIONARY: &str = r#"
+discount = 0.5
+
+[synonyms]
+QBR = ["quarterly business review"]
+PTO = { expansions = ["paid time off", "vacation"], discount = 0.8 }
+"#;
+
+    #[test]
+    fn test_expand_replaces_whole_words() {
+        let dictionary = SynonymDictionary::parse(DICTIONARY).unwrap();
+
+        let expansions = dictionary.expand("notes from the qbr");
+        assert_eq!(
+            expansions,
+            vec![QueryExpansion {
+                term: "QBR".to_string(),
+                query: "notes from the quarterly business review".to_string(),
+                weight: 0.5,
+            }]
+        );
+
+        assert!(dictionary.expand("QBRs are coming").is_empty());
+        assert!(dictionary.expand("budget").is_empty());
+    }
+
+    #[test]
+    fn test_per_entry_discount_ranks_first() {
+        let dictionary = SynonymDictionary::parse(DICTIONARY).unwrap();
+        let expansions = dictionary.expand("PTO before the QBR");
+
+        assert_eq!(expansions.len(), MAX_EXPANSIONS);
+        assert_eq!(expansions[0].query, "paid time off before the QBR");
+        assert_eq!(expansions[0].weight, 0.8);
+        assert_eq!(
+            expansions[2].query,
+            "PTO before the quarterly business review"
+        );
+    }
+
+    #[test]
+    fn test_invalid_discount() {
+        assert!(SynonymDictionary::parse(
+            "[synonyms]\nX = { expansions = [\"y\"], discount = 2.0 }"
+        )
+        .is_err());
+        assert!(SynonymDictionary::parse("")
+            .unwrap()
+            .expand("anything")
+            .is_empty());
+    }
+}
@@ -0,0 +1,557 @@
+//! Query understanding: search filters from natural-language questions
+//!
+//! "emails from Dana about the offsite last month" becomes the query
+//! "offsite" with a sender filter and last month's date range. It is
+//! rule-based: sender and recipient phrases ("from Dana", "to bob@acme.io"),
+//! dates ("yesterday", "last week", "in March", "past 3 days", "since
+//! 2024-05-01", "on Monday"), "with attachments" and "I sent". Words it
+//! doesn't recognize stay in the query.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+use super::{SearchOptions, ThreadSearchOptions};
+
+/// Words that only say what is being searched for
+const FILLER_WORDS: &[&str] = &["email", "emails", "message", "messages", "mail", "mails"];
+
+/// Words that can open a question without being part of it
+const LEADING_WORDS: &[&str] = &[
+    "show", "me", "find", "search", "for", "get", "list", "any", "all", "the",
+];
+
+/// Words introducing the topic
+const TOPIC_WORDS: &[&str] = &["about", "regarding", "re", "concerning"];
+
+/// Words that are never a sender or recipient
+const NOT_A_PERSON: &[&str] = &[
+    "a",
+    "an",
+    "the",
+    "my",
+    "our",
+    "your",
+    "their",
+    "his",
+    "her",
+    "this",
+    "that",
+    "last",
+    "past",
+    "next",
+    "today",
+    "yesterday",
+    "do",
+    "be",
+    "make",
+    "see",
+    "get",
+];
+
+/// Filters found in a query, and what is left of it
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QueryUnderstanding {
+    /// The query without the words that became filters
+    pub query: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_from: Option<DateTime<Utc>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_to: Option<DateTime<Utc>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub has_attachment: Option<bool>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sent: Option<bool>,
+}
+
+impl QueryUnderstanding {
+    /// Whether no filter was found
+    pub fn is_empty(&self) -> bool {
+        self.from.is_none()
+            && self.to.is_none()
+            && self.date_from.is_none()
+            && self.date_to.is_none()
+            && self.has_attachment.is_none()
+            && self.sent.is_none()
+    }
+
+    /// Set the filters found on `options`, keeping any already set
+    pub fn apply(&self, options: &mut SearchOptions) {
+        if options.from.is_none() {
+            options.from = self.from.clone();
+        }
+        if options.to.is_none() {
+            options.to = self.to.clone();
+        }
+        if options.date_from.is_none() && options.date_to.is_none() {
+            options.date_from = self.date_from;
+            options.date_to = self.date_to;
+        }
+        if options.has_attachment.is_none() {
+            options.has_attachment = self.has_attachment;
+        }
+        if options.sent.is_none() {
+            options.sent = self.sent;
+        }
+    }
+
+    /// Set the filters threads have on `options`, keeping any already set:
+    /// the sender or recipient becomes the participant
+    pub fn apply_to_threads(&self, options: &mut ThreadSearchOptions) {
+        if options.participant.is_none() {
+            options.participant = self.from.clone().or_else(|| self.to.clone());
+        }
+        if options.date_from.is_none() && options.date_to.is_none() {
+            options.date_from = self.date_from;
+            options.date_to = self.date_to;
+        }
+    }
+}
+
+/// A date range; either end may be open
+type Range = (Option<DateTime<Utc>>, Option<DateTime<Utc>>);
+
+/// Find filters in `query`, reading dates relative to `now`
+///
+/// When every word became a filter, the query is left as it was, so there
+/// is still something to rank by.
+pub fn understand_query(query: &str, now: DateTime<Tz>) -> QueryUnderstanding {
+    let tokens: Vec<&str> = query.split_whitespace().collect();
+    let raw: Vec<&str> = tokens
+        .iter()
+        .map(|t| t.trim_matches(|c: char| ",.?!;:\"'()".contains(c)))
+        .collect();
+    let words: Vec<String> = raw.iter().map(|w| w.to_lowercase()).collect();
+    let mut kept = vec![true; tokens.len()];
+    let mut result = QueryUnderstanding::default();
+
+    let word = |i: usize| words.get(i).map(String::as_str).unwrap_or_default();
+    let mut i = 0;
+    while i < words.len() {
+        let w = word(i);
+
+        // "I sent", "sent by me" / "sent to me", "from me" / "to me"
+        if (w == "i" || w == "i've") && word(i + 1) == "sent" {
+            result.sent = Some(true);
+            kept[i] = false;
+            kept[i + 1] = false;
+            i += 2;
+            continue;
+        }
+        if (w == "from" || w == "by") && word(i + 1) == "me" {
+            result.sent = Some(true);
+            kept[i] = false;
+            kept[i + 1] = false;
+            i += 2;
+            continue;
+        }
+        if w == "to" && word(i + 1) == "me" {
+            result.sent = Some(false);
+            kept[i] = false;
+            kept[i + 1] = false;
+            i += 2;
+            continue;
+        }
+
+        // Sender and recipient
+        if w == "from" || w == "to" {
+            if let Some((person, len)) = person_at(&raw, &words, i + 1, w == "from") {
+                if w == "from" {
+                    result.from.get_or_insert(person);
+                } else {
+                    result.to.get_or_insert(person);
+                }
+                kept[i..=i + len].iter_mut().for_each(|k| *k = false);
+                i += len + 1;
+                continue;
+            }
+        }
+
+        // "with attachments", "has an attachment"
+        if matches!(w, "with" | "has" | "having") {
+            let next = if matches!(word(i + 1), "a" | "an") {
+                i + 2
+            } else {
+                i + 1
+            };
+            if matches!(word(next), "attachment" | "attachments" | "attached") {
+                result.has_attachment = Some(true);
+                kept[i..=next].iter_mut().for_each(|k| *k = false);
+                i = next + 1;
+                continue;
+            }
+        }
+
+        // Open-ended dates: "since March", "before 2024-05-01"
+        if matches!(w, "since" | "after" | "before" | "until") {
+            if let Some(((start, end), len)) = date_at(&words, i + 1, now, true) {
+                match w {
+                    "since" => result.date_from = start,
+                    "after" => result.date_from = end.map(|e| e + Duration::seconds(1)).or(start),
+                    "before" => result.date_to = start.map(|s| s - Duration::seconds(1)),
+                    _ => result.date_to = end.or(start),
+                }
+                kept[i..=i + len].iter_mut().for_each(|k| *k = false);
+                i += len + 1;
+                continue;
+            }
+        }
+
+        // Date ranges, optionally after "in", "on", "from", "during" or
+        // "over" (and "the")
+        let mut start_at = i;
+        if matches!(w, "in" | "on" | "from" | "during" | "over") {
+            start_at += 1;
+            if word(start_at) == "the" {
+                start_at += 1;
+            }
+        }
+        if let Some(((start, end), len)) = date_at(&words, start_at, now, start_at > i) {
+            result.date_from = start;
+            result.date_to = end;
+            kept[i..start_at + len].iter_mut().for_each(|k| *k = false);
+            i = start_at + len;
+            continue;
+        }
+
+        if FILLER_WORDS.contains(&w) || TOPIC_WORDS.contains(&w) {
+            kept[i] = false;
+        }
+        i += 1;
+    }
+
+    // Drop leading words like "show me all" once filters are out of the way
+    for (k, w) in kept.iter_mut().zip(&words) {
+        if !*k {
+            continue;
+        }
+        if LEADING_WORDS.contains(&w.as_str()) {
+            *k = false;
+        } else {
+            break;
+        }
+    }
+
+    let rest: Vec<&str> = tokens
+        .iter()
+        .zip(&kept)
+        .filter(|(_, k)| **k)
+        .map(|(t, _)| *t)
+        .collect();
+    result.query = if rest.is_empty() {
+        query.trim().to_string()
+    } else {
+        rest.join(" ")
+    };
+    result
+}
+
+/// A sender or recipient starting at word `i`: an email address, a
+/// capitalized name (two words when both are capitalized) or, after "from",
+/// any other word. Returns it and how many words it took.
+fn person_at(raw: &[&str], words: &[String], i: usize, any_word: bool) -> Option<(String, usize)> {
+    let word = words.get(i)?;
+    let original = raw[i];
+    if word.contains('@') {
+        return Some((original.to_string(), 1));
+    }
+    if word.is_empty()
+        || NOT_A_PERSON.contains(&word.as_str())
+        || month_number(word).is_some()
+        || weekday(word).is_some()
+        || word.chars().all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+    let capitalized = |w: &str| w.chars().next().is_some_and(char::is_uppercase);
+    if !capitalized(original) && !any_word {
+        return None;
+    }
+    match raw.get(i + 1) {
+        Some(next)
+            if capitalized(original)
+                && capitalized(next)
+                && month_number(&words[i + 1]).is_none()
+                && weekday(&words[i + 1]).is_none() =>
+        {
+            Some((format!("{} {}", original, next), 2))
+        }
+        _ => Some((original.to_string(), 1)),
+    }
+}
+
+/// A date expression starting at word `i` and how many words it took
+///
+/// Bare weekdays, month names and years are only read after a word like
+/// "on", "in" or "since" (`introduced`), or a month with a year ("March
+/// 2024"), so "Cyber Monday", "may" and numbers elsewhere stay in the query.
+fn date_at(
+    words: &[String],
+    i: usize,
+    now: DateTime<Tz>,
+    introduced: bool,
+) -> Option<(Range, usize)> {
+    let word = |i: usize| words.get(i).map(String::as_str).unwrap_or_default();
+    let tz = now.timezone();
+    let today = now.date_naive();
+    let w = word(i);
+
+    match w {
+        "today" => return Some((day_range(today, tz), 1)),
+        "yesterday" => return Some((day_range(today - Duration::days(1), tz), 1)),
+        _ => {}
+    }
+
+    if let Ok(date) = NaiveDate::parse_from_str(w, "%Y-%m-%d") {
+        return Some((day_range(date, tz), 1));
+    }
+
+    if matches!(w, "this" | "last" | "past") {
+        let this_week = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+        let this_month = today.with_day(1)?;
+        let this_year = NaiveDate::from_ymd_opt(today.year(), 1, 1)?;
+        let range = match (w, word(i + 1)) {
+            ("this", "week") => (start_of(this_week, tz), None),
+            ("last" | "past", "week") => (
+                start_of(this_week - Duration::days(7), tz),
+                end_before(this_week, tz),
+            ),
+            ("this", "month") => (start_of(this_month, tz), None),
+            ("last" | "past", "month") => (
+                start_of(this_month.checked_sub_months(chrono::Months::new(1))?, tz),
+                end_before(this_month, tz),
+            ),
+            ("this", "year") => (start_of(this_year, tz), None),
+            ("last" | "past", "year") => (
+                start_of(NaiveDate::from_ymd_opt(today.year() - 1, 1, 1)?, tz),
+                end_before(this_year, tz),
+            ),
+            (_, next) => {
+                if let Some(day) = weekday(next) {
+                    let date = last_weekday(today, day, w == "last");
+                    return Some((day_range(date, tz), 2));
+                }
+                // "last 3 days", "past 2 weeks"
+                let count: i64 = next.parse().ok().filter(|n| *n > 0 && w != "this")?;
+                let start = match word(i + 2).trim_end_matches('s') {
+                    "day" => now - Duration::days(count),
+                    "week" => now - Duration::weeks(count),
+                    "month" => now.checked_sub_months(chrono::Months::new(count as u32))?,
+                    _ => return None,
+                };
+                return Some(((Some(start.with_timezone(&Utc)), None), 3));
+            }
+        };
+        return Some((range, 2));
+    }
+
+    if let Some(day) = weekday(w).filter(|_| introduced) {
+        return Some((day_range(last_weekday(today, day, false), tz), 1));
+    }
+
+    if let Some(month) = month_number(w) {
+        let year = word(i + 1).parse::<i32>().ok().filter(|y| is_year(*y));
+        if year.is_none() && !introduced {
+            return None;
+        }
+        let year = year.unwrap_or(if month <= today.month() {
+            today.year()
+        } else {
+            today.year() - 1
+        });
+        let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+        let next = first.checked_add_months(chrono::Months::new(1))?;
+        let len = if word(i + 1).parse::<i32>().is_ok_and(is_year) {
+            2
+        } else {
+            1
+        };
+        return Some(((start_of(first, tz), end_before(next, tz)), len));
+    }
+
+    if introduced {
+        if let Some(year) = w.parse::<i32>().ok().filter(|y| is_year(*y)) {
+            let first = NaiveDate::from_ymd_opt(year, 1, 1)?;
+            let next = NaiveDate::from_ymd_opt(year + 1, 1, 1)?;
+            return Some(((start_of(first, tz), end_before(next, tz)), 1));
+        }
+    }
+
+    None
+}
+
+fn is_year(year: i32) -> bool {
+    (1990..=2100).contains(&year)
+}
+
+/// Midnight starting `date` in `tz`
+fn start_of(date: NaiveDate, tz: Tz) -> Option<DateTime<Utc>> {
+    tz.from_local_datetime(&date.and_time(NaiveTime::MIN))
+        .earliest()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+/// The last second before `date` starts in `tz`
+fn end_before(date: NaiveDate, tz: Tz) -> Option<DateTime<Utc>> {
+    start_of(date, tz).map(|start| start - Duration::seconds(1))
+}
+
+fn day_range(date: NaiveDate, tz: Tz) -> Range {
+    (start_of(date, tz), end_before(date + Duration::days(1), tz))
+}
+
+/// The latest `day` on or before `today` (before it when `strictly_before`)
+fn last_weekday(today: NaiveDate, day: Weekday, strictly_before: bool) -> NaiveDate {
+    let mut back =
+        (7 + today.weekday().num_days_from_monday() as i64 - day.num_days_from_monday() as i64) % 7;
+    if back == 0 && strictly_before {
+        back = 7;
+    }
+    today - Duration::days(back)
+}
+
+fn weekday(word: &str) -> Option<Weekday> {
+    match word {
+        "monday" => Some(Weekday::Mon),
+        "tuesday" => Some(Weekday::Tue),
+        "wednesday" => Some(Weekday::Wed),
+        "thursday" => Some(Weekday::Thu),
+        "friday" => Some(Weekday::Fri),
+        "saturday" => Some(Weekday::Sat),
+        "sunday" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn month_number(word: &str) -> Option<u32> {
+    const MONTHS: [&str; 12] = [
+        "january",
+        "february",
+        "march",
+        "april",
+        "may",
+        "june",
+        "july",
+        "august",
+        "september",
+        "october",
+        "november",
+        "december",
+    ];
+    MONTHS.iter().position(|m| *m == word).map(|i| i as u32 + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Wednesday 2024-06-12, 3pm in New York
+    fn now() -> DateTime<Tz> {
+        chrono_tz::America::New_York
+            .with_ymd_and_hms(2024, 6, 12, 15, 0, 0)
+            .unwrap()
+    }
+
+    fn local(y: i32, m: u32, d: u32, h: u32, min: u32, s: u32) -> Option<DateTime<Utc>> {
+        Some(
+            chrono_tz::America::New_York
+                .with_ymd_and_hms(y, m, d, h, min, s)
+                .unwrap()
+                .with_timezone(&Utc),
+        )
+    }
+
+    #[test]
+    fn test_sender_topic_and_month() {
+        let understood = understand_query("emails from Dana about the offsite last month", now());
+        assert_eq!(understood.query, "offsite");
+        assert_eq!(understood.from.as_deref(), Some("Dana"));
+        assert_eq!(understood.date_from, local(2024, 5, 1, 0, 0, 0));
+        assert_eq!(understood.date_to, local(2024, 5, 31, 23, 59, 59));
+        assert!(!understood.is_empty());
+    }
+
+    #[test]
+    fn test_dates() {
+        let range = |q: &str| {
+            let u = understand_query(q, now());
+            (u.date_from, u.date_to)
+        };
+        assert_eq!(
+            range("invoice yesterday"),
+            (local(2024, 6, 11, 0, 0, 0), local(2024, 6, 11, 23, 59, 59))
+        );
+        assert_eq!(
+            range("standup notes last week"),
+            (local(2024, 6, 3, 0, 0, 0), local(2024, 6, 9, 23, 59, 59))
+        );
+        assert_eq!(
+            range("receipts in March"),
+            (local(2024, 3, 1, 0, 0, 0), local(2024, 3, 31, 23, 59, 59))
+        );
+        assert_eq!(
+            range("receipts in December"),
+            (local(2023, 12, 1, 0, 0, 0), local(2023, 12, 31, 23, 59, 59))
+        );
+        assert_eq!(
+            range("offsite since 2024-05-20"),
+            (local(2024, 5, 20, 0, 0, 0), None)
+        );
+        assert_eq!(
+            range("offsite before March 2024"),
+            (None, local(2024, 2, 29, 23, 59, 59))
+        );
+        assert_eq!(
+            range("call notes last Wednesday"),
+            (local(2024, 6, 5, 0, 0, 0), local(2024, 6, 5, 23, 59, 59))
+        );
+        assert_eq!(
+            range("in the past 3 days"),
+            (Some((now() - Duration::days(3)).with_timezone(&Utc)), None)
+        );
+        assert_eq!(range("what may change"), (None, None));
+    }
+
+    #[test]
+    fn test_people_attachments_and_sent() {
+        let u = understand_query("contract to Bob Stone with an attachment", now());
+        assert_eq!(u.to.as_deref(), Some("Bob Stone"));
+        assert_eq!(u.has_attachment, Some(true));
+        assert_eq!(u.query, "contract");
+
+        let u = understand_query("what I sent to alice@acme.io", now());
+        assert_eq!(u.sent, Some(true));
+        assert_eq!(u.to.as_deref(), Some("alice@acme.io"));
+        assert_eq!(u.query, "what");
+
+        // Lowercase words after "to" aren't recipients
+        let u = understand_query("how to reset my password", now());
+        assert!(u.is_empty());
+        assert_eq!(u.query, "how to reset my password");
+
+        // Nothing left to rank by: keep the question
+        let u = understand_query("emails from dana", now());
+        assert_eq!(u.from.as_deref(), Some("dana"));
+        assert_eq!(u.query, "emails from dana");
+    }
+
+    #[test]
+    fn test_apply_keeps_explicit_filters() {
+        let u = understand_query("from Dana last week", now());
+        let mut options = SearchOptions::new(10);
+        options.from = Some("carol".to_string());
+        u.apply(&mut options);
+        assert_eq!(options.from.as_deref(), Some("carol"));
+        assert_eq!(options.date_from, u.date_from);
+    }
+}
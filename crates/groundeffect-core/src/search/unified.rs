@@ -0,0 +1,538 @@
+//! One search across emails, calendar events, attachments and contacts
+//!
+//! Emails and events are ranked by hybrid search, attachments by how many
+//! query terms their filename contains, and contacts (people in the From,
+//! To or Cc of synced mail) by how many terms their name or address
+//! contains, then by how often they appear. Scores aren't comparable between
+//! types, so before interleaving each type's scores are divided by its best
+//! one; `type_score` keeps the score within the type.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{query_terms, CalendarSearchOptions, FusionOptions, SearchEngine, SearchOptions};
+use crate::error::{Error, Result};
+use crate::models::{CalendarEvent, Email, EmailSummary};
+
+/// Most emails scanned for matching attachments or contacts
+const MAX_SCANNED_EMAILS: usize = 5000;
+
+/// Kind of a unified search result
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResultType {
+    Email,
+    Event,
+    Attachment,
+    Contact,
+}
+
+impl ResultType {
+    /// Parse a type name, singular or plural ("email", "events", ...)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "email" | "emails" => Some(Self::Email),
+            "event" | "events" | "calendar" => Some(Self::Event),
+            "attachment" | "attachments" => Some(Self::Attachment),
+            "contact" | "contacts" => Some(Self::Contact),
+            _ => None,
+        }
+    }
+
+    /// Name used in options and output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Email => "email",
+            Self::Event => "event",
+            Self::Attachment => "attachment",
+            Self::Contact => "contact",
+        }
+    }
+}
+
+/// An attachment whose filename matched the query
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttachmentHit {
+    pub attachment_id: String,
+    pub filename: String,
+    pub mime_type: String,
+    pub size: u64,
+    pub downloaded: bool,
+    /// Email the attachment came with
+    pub email_id: String,
+    pub account_id: String,
+    pub subject: String,
+    pub from: String,
+    pub date: DateTime<Utc>,
+}
+
+/// Someone in the From, To or Cc of synced mail whose name or address
+/// matched the query
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContactHit {
+    pub email: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Scanned emails they appear in
+    pub message_count: usize,
+    /// Date of the newest of those emails
+    pub last_contact: DateTime<Utc>,
+}
+
+/// A result of one type
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum UnifiedItem {
+    Email(EmailSummary),
+    Event(CalendarEvent),
+    Attachment(AttachmentHit),
+    Contact(ContactHit),
+}
+
+impl UnifiedItem {
+    pub fn result_type(&self) -> ResultType {
+        match self {
+            Self::Email(_) => ResultType::Email,
+            Self::Event(_) => ResultType::Event,
+            Self::Attachment(_) => ResultType::Attachment,
+            Self::Contact(_) => ResultType::Contact,
+        }
+    }
+}
+
+/// A unified search result
+#[derive(Debug, Clone, Serialize)]
+pub struct UnifiedResult {
+    #[serde(flatten)]
+    pub item: UnifiedItem,
+
+    /// Score relative to the best result of the same type (0-1), used for
+    /// interleaving
+    pub score: f32,
+
+    /// Score within its type (search score, or share of query terms matched)
+    pub type_score: f32,
+
+    /// 1-based position among results of its type
+    pub type_rank: usize,
+}
+
+/// Unified search options
+#[derive(Debug, Clone, Default)]
+pub struct UnifiedSearchOptions {
+    /// Types to search (empty = all)
+    pub types: Vec<ResultType>,
+
+    /// Account IDs to search (None = all accounts)
+    pub accounts: Option<Vec<String>>,
+
+    /// Maximum number of results overall
+    pub limit: usize,
+
+    /// Include mail in Spam and Trash
+    pub include_spam_trash: bool,
+
+    /// Leave out emails collapsed into activity items
+    pub exclude_activity: bool,
+
+    /// Mode and fusion settings for emails and events
+    pub fusion: FusionOptions,
+
+    /// Thread dedup and MMR for the email results
+    pub diversity: Diversity,
+}
+
+impl UnifiedSearchOptions {
+    /// Whether results of `kind` are wanted
+    pub fn wants(&self, kind: ResultType) -> bool {
+        self.types.is_empty() || self.types.contains(&kind)
+    }
+
+    fn email_options(&self) -> SearchOptions {
+        let mut options = SearchOptions::new(self.limit);
+        options.accounts = self.accounts.clone();
+        options.include_spam_trash = self.include_spam_trash;
+        options.exclude_activity = self.exclude_activity;
+        options.fusion = self.fusion;
+        options.diversity = self.diversity;
+        options
+    }
+
+    fn calendar_options(&self) -> CalendarSearchOptions {
+        CalendarSearchOptions {
+            accounts: self.accounts.clone(),
+            limit: self.limit,
+            fusion: self.fusion,
+            ..Default::default()
+        }
+    }
+}
+
+impl SearchEngine {
+    /// Search emails, events, attachments and contacts and interleave the
+    /// results
+    pub async fn search_all(
+        &self,
+        query: &str,
+        options: &UnifiedSearchOptions,
+    ) -> Result<Vec<UnifiedResult>> {
+        let terms = query_terms([query]);
+
+        let emails = async {
+            if !options.wants(ResultType::Email) {
+                return Ok(Vec::new());
+            }
+            let results = self.search_emails(query, &options.email_options()).await?;
+            Ok::<_, Error>(
+                results
+                    .into_iter()
+                    .map(|r| (UnifiedItem::Email(r.email), r.score))
+                    .collect(),
+            )
+        };
+        let events = async {
+            if !options.wants(ResultType::Event) {
+                return Ok(Vec::new());
+            }
+            let results = self
+                .search_calendar(query, &options.calendar_options())
+                .await?;
+            Ok::<_, Error>(
+                results
+                    .into_iter()
+                    .map(|r| (UnifiedItem::Event(r.event), r.score))
+                    .collect(),
+            )
+        };
+        let attachments = async {
+            if !options.wants(ResultType::Attachment) || terms.is_empty() {
+                return Ok(Vec::new());
+            }
+            let mut filter_options = options.email_options();
+            filter_options.has_attachment = Some(true);
+            let filter = with_terms(filter_options.build_filter(), &["attachments"], &terms);
+            let emails = self
+                .db
+                .list_email_headers_matching(&filter, MAX_SCANNED_EMAILS)
+                .await?;
+            Ok::<_, Error>(
+                attachment_hits(&emails, &terms)
+                    .into_iter()
+                    .map(|(hit, score)| (UnifiedItem::Attachment(hit), score))
+                    .collect(),
+            )
+        };
+        let contacts = async {
+            if !options.wants(ResultType::Contact) || terms.is_empty() {
+                return Ok(Vec::new());
+            }
+            let filter = with_terms(
+                options.email_options().build_filter(),
+                &["from_email", "from_name", "to", "cc"],
+                &terms,
+            );
+            let emails = self
+                .db
+                .list_email_headers_matching(&filter, MAX_SCANNED_EMAILS)
+                .await?;
+            Ok::<_, Error>(
+                contact_hits(&emails, &terms)
+                    .into_iter()
+                    .map(|(hit, score)| (UnifiedItem::Contact(hit), score))
+                    .collect(),
+            )
+        };
+
+        let (emails, events, attachments, contacts) =
+            tokio::join!(emails, events, attachments, contacts);
+        Ok(interleave(
+            vec![emails?, events?, attachments?, contacts?],
+            options.limit,
+        ))
+    }
+}
+
+/// `filter` AND-ed with any term occurring (case-insensitively) in any of
+/// `columns`
+fn with_terms(filter: Option<String>, columns: &[&str], terms: &[String]) -> String {
+    let matches: Vec<String> = terms
+        .iter()
+        .flat_map(|term| {
+            let term = term.replace('\'', "''");
+            columns
+                .iter()
+                .map(move |c| format!("lower({}) LIKE '%{}%'", c, term))
+        })
+        .collect();
+    let matches = format!("({})", matches.join(" OR "));
+    match filter {
+        Some(filter) => format!("{} AND {}", filter, matches),
+        None => matches,
+    }
+}
+
+/// Share of `terms` contained in `text` (already lowercase)
+fn term_share(text: &str, terms: &[String]) -> f32 {
+    if terms.is_empty() {
+        return 0.0;
+    }
+    let found = terms.iter().filter(|t| text.contains(t.as_str())).count();
+    found as f32 / terms.len() as f32
+}
+
+/// Attachments of `emails` (newest first) whose filename contains any of
+/// `terms`, best match first, scored by the share of terms contained
+pub fn attachment_hits(emails: &[Email], terms: &[String]) -> Vec<(AttachmentHit, f32)> {
+    let mut hits: Vec<(AttachmentHit, f32)> = emails
+        .iter()
+        .flat_map(|email| {
+            email.attachments.iter().filter_map(move |att| {
+                let score = term_share(&att.filename.to_lowercase(), terms);
+                (score > 0.0).then(|| {
+                    (
+                        AttachmentHit {
+                            attachment_id: att.id.clone(),
+                            filename: att.filename.clone(),
+                            mime_type: att.mime_type.clone(),
+                            size: att.size,
+                            downloaded: att.downloaded,
+                            email_id: email.id.clone(),
+                            account_id: email.account_id.clone(),
+                            subject: email.subject.clone(),
+                            from: email.from.to_string(),
+                            date: email.date,
+                        },
+                        score,
+                    )
+                })
+            })
+        })
+        .collect();
+    // Stable, so equal scores stay newest first
+    hits.sort_by(|a, b| b.1.total_cmp(&a.1));
+    hits
+}
+
+/// People in the From, To or Cc of `emails` (newest first) whose name or
+/// address contains any of `terms`, best match first (then most emails),
+/// scored by the share of terms contained
+pub fn contact_hits(emails: &[Email], terms: &[String]) -> Vec<(ContactHit, f32)> {
+    let mut contacts: Vec<ContactHit> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for email in emails {
+        for address in std::iter::once(&email.from)
+            .chain(&email.to)
+            .chain(&email.cc)
+        {
+            let key = address.email.trim().to_lowercase();
+            if key.is_empty() {
+                continue;
+            }
+            let name = address
+                .name
+                .as_deref()
+                .map(str::trim)
+                .filter(|n| !n.is_empty())
+                .map(str::to_string);
+            match index.get(&key) {
+                Some(&i) => {
+                    let contact = &mut contacts[i];
+                    contact.message_count += 1;
+                    contact.last_contact = contact.last_contact.max(email.date);
+                    if contact.name.is_none() {
+                        contact.name = name;
+                    }
+                }
+                None => {
+                    index.insert(key.clone(), contacts.len());
+                    contacts.push(ContactHit {
+                        email: key,
+                        name,
+                        message_count: 1,
+                        last_contact: email.date,
+                    });
+                }
+            }
+        }
+    }
+
+    let mut hits: Vec<(ContactHit, f32)> = contacts
+        .into_iter()
+        .filter_map(|contact| {
+            let text = format!(
+                "{} {}",
+                contact.name.as_deref().unwrap_or_default().to_lowercase(),
+                contact.email
+            );
+            let score = term_share(&text, terms);
+            (score > 0.0).then_some((contact, score))
+        })
+        .collect();
+    hits.sort_by(|a, b| {
+        b.1.total_cmp(&a.1)
+            .then(b.0.message_count.cmp(&a.0.message_count))
+    });
+    hits
+}
+
+/// Interleave ranked results of each type (best first) by their score
+/// relative to the best of their type, keeping at most `limit`
+///
+/// Ties go to the better-ranked result, then to the earlier group.
+pub fn interleave(groups: Vec<Vec<(UnifiedItem, f32)>>, limit: usize) -> Vec<UnifiedResult> {
+    let mut results: Vec<(usize, UnifiedResult)> = Vec::new();
+    for (group_index, group) in groups.into_iter().enumerate() {
+        let best = group.iter().map(|(_, s)| *s).fold(0.0_f32, f32::max);
+        for (rank, (item, type_score)) in group.into_iter().take(limit).enumerate() {
+            let score = if best > 0.0 { type_score / best } else { 0.0 };
+            results.push((
+                group_index,
+                UnifiedResult {
+                    item,
+                    score,
+                    type_score,
+                    type_rank: rank + 1,
+                },
+            ));
+        }
+    }
+    results.sort_by(|(ga, a), (gb, b)| {
+        b.score
+            .total_cmp(&a.score)
+            .then(a.type_rank.cmp(&b.type_rank))
+            .then(ga.cmp(gb))
+    });
+    results.truncate(limit);
+    results.into_iter().map(|(_, r)| r).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Address, Attachment};
+    use chrono::{Duration, TimeZone};
+
+    fn email(id: &str, from: Address, to: Vec<Address>, days_ago: i64) -> Email {
+        let base = Email::test(
+            &format!("<{}>", id),
+            &from.email,
+            &format!("Subject {}", id),
+        );
+        Email {
+            id: id.to_string(),
+            from,
+            to,
+            date: Utc.with_ymd_and_hms(2024, 6, 30, 12, 0, 0).unwrap() - Duration::days(days_ago),
+            ..base
+        }
+    }
+
+    fn terms(query: &str) -> Vec<String> {
+        query_terms([query])
+    }
+
+    #[test]
+    fn test_attachment_hits() {
+        let me = || vec![Address::new("me@example.com")];
+        let mut newer = email("1", Address::new("ann@acme.io"), me(), 1);
+        newer.attachments = vec![
+            Attachment::new("a1", "Q3 Invoice.pdf", "application/pdf", 10),
+            Attachment::new("a2", "logo.png", "image/png", 10),
+        ];
+        let mut older = email("2", Address::new("bob@acme.io"), me(), 5);
+        older.attachments = vec![
+            Attachment::new("a3", "acme-invoice-q3.pdf", "application/pdf", 10),
+            Attachment::new("a4", "invoice.pdf", "application/pdf", 10),
+        ];
+
+        let hits = attachment_hits(&[newer, older], &terms("Q3 invoice"));
+        let names: Vec<(&str, f32)> = hits
+            .iter()
+            .map(|(h, s)| (h.filename.as_str(), *s))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("Q3 Invoice.pdf", 1.0),
+                ("acme-invoice-q3.pdf", 1.0),
+                ("invoice.pdf", 0.5)
+            ]
+        );
+        assert_eq!(hits[1].0.email_id, "2");
+    }
+
+    #[test]
+    fn test_contact_hits() {
+        let priya = Address::with_name("Priya Shah", "priya@acme.io");
+        let emails = vec![
+            email("1", priya.clone(), vec![Address::new("me@example.com")], 1),
+            email(
+                "2",
+                Address::new("me@example.com"),
+                vec![Address::new("PRIYA@acme.io"), Address::new("sam@acme.io")],
+                3,
+            ),
+            email(
+                "3",
+                Address::with_name("Priya Patel", "ppatel@other.org"),
+                vec![],
+                9,
+            ),
+        ];
+
+        let hits = contact_hits(&emails, &terms("priya"));
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].0.email, "priya@acme.io");
+        assert_eq!(hits[0].0.name.as_deref(), Some("Priya Shah"));
+        assert_eq!(hits[0].0.message_count, 2);
+        assert_eq!(hits[0].0.last_contact, emails[0].date);
+        assert_eq!(hits[1].0.email, "ppatel@other.org");
+
+        let hits = contact_hits(&emails, &terms("priya patel"));
+        assert_eq!(hits[0].0.email, "ppatel@other.org");
+        assert_eq!(hits[0].1, 1.0);
+    }
+
+    #[test]
+    fn test_interleave_scales_scores_per_type() {
+        let contact = |email: &str| {
+            UnifiedItem::Contact(ContactHit {
+                email: email.to_string(),
+                name: None,
+                message_count: 1,
+                last_contact: Utc::now(),
+            })
+        };
+        // RRF-sized scores for one type, term shares for the other
+        let groups = vec![
+            vec![(contact("a"), 0.032), (contact("b"), 0.016)],
+            vec![
+                (contact("c"), 1.0),
+                (contact("d"), 0.75),
+                (contact("e"), 0.25),
+            ],
+        ];
+
+        let results = interleave(groups, 4);
+        let order: Vec<(String, f32, usize)> = results
+            .iter()
+            .map(|r| match &r.item {
+                UnifiedItem::Contact(c) => (c.email.clone(), r.score, r.type_rank),
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                ("a".to_string(), 1.0, 1),
+                ("c".to_string(), 1.0, 1),
+                ("d".to_string(), 0.75, 2),
+                ("b".to_string(), 0.5, 2),
+            ]
+        );
+        assert_eq!(results[0].type_score, 0.032);
+        assert_eq!(results[0].item.result_type(), ResultType::Contact);
+    }
+}
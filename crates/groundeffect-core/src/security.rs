@@ -0,0 +1,258 @@
+//! Sender authentication and phishing signals
+//!
+//! Gmail records its SPF, DKIM and DMARC checks in an
+//! `Authentication-Results` header when mail arrives. Sync reads that header
+//! (only Gmail's own, since senders can add fake ones), the `Return-Path`,
+//! and the From display name, so `email show` can report the verdicts and
+//! `email list --suspicious` can surface mail that failed them or whose
+//! display name impersonates another address.
+
+use crate::models::{Address, AuthVerdict, EmailAuth};
+
+/// authserv-id of the Authentication-Results header Gmail adds on receipt
+const GMAIL_AUTHSERV_ID: &str = "mx.google.com";
+
+/// Sender authentication from a message's raw header block
+///
+/// None when the message has neither Authentication-Results nor Return-Path
+/// (e.g. mail the user sent).
+pub fn parse_email_auth(headers: &[u8], from: &Address) -> Option<EmailAuth> {
+    let headers = unfold_headers(headers);
+    let return_path = header(&headers, "return-path")
+        .map(|v| {
+            v.trim()
+                .trim_start_matches('<')
+                .trim_end_matches('>')
+                .trim()
+        })
+        .filter(|v| !v.is_empty())
+        .map(str::to_string);
+
+    // Only Gmail's own result counts; any other was added before Gmail saw
+    // the message and could say anything
+    let trusted = headers
+        .iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case("authentication-results"))
+        .map(|(_, value)| value.as_str())
+        .find(|value| authserv_id(value).eq_ignore_ascii_case(GMAIL_AUTHSERV_ID));
+
+    if trusted.is_none() && return_path.is_none() {
+        return None;
+    }
+
+    let mut auth = EmailAuth {
+        return_path,
+        ..Default::default()
+    };
+    if let Some(value) = trusted {
+        for (method, verdict) in method_results(value) {
+            let slot = match method.as_str() {
+                "spf" => &mut auth.spf,
+                "dkim" => &mut auth.dkim,
+                "dmarc" => &mut auth.dmarc,
+                _ => continue,
+            };
+            // A message can carry several DKIM signatures; one passing is enough
+            if *slot != Some(AuthVerdict::Pass) {
+                *slot = Some(verdict);
+            }
+        }
+    }
+
+    for (name, verdict) in [
+        ("SPF", auth.spf),
+        ("DKIM", auth.dkim),
+        ("DMARC", auth.dmarc),
+    ] {
+        if let Some(verdict) = verdict.filter(AuthVerdict::is_failure) {
+            auth.warnings.push(format!("{} {}", name, verdict.as_str()));
+        }
+    }
+    if let Some(shown) = spoofed_display_name(from) {
+        auth.warnings.push(format!(
+            "Display name shows {} but the sender is {}",
+            shown, from.email
+        ));
+    }
+    auth.suspicious = !auth.warnings.is_empty();
+
+    Some(auth)
+}
+
+/// A domain or address in the display name that isn't the sender's
+///
+/// Catches `"service@paypal.com" <x@example.net>` and
+/// `"paypal.com Support" <x@example.net>`.
+fn spoofed_display_name(from: &Address) -> Option<String> {
+    let name = from.name.as_deref()?;
+    let sender_domain = domain_of(&from.email)?.to_ascii_lowercase();
+
+    name.split(|c: char| c.is_whitespace() || "<>()[]\"',;".contains(c))
+        .map(|token| token.trim_matches('.'))
+        .filter(|token| looks_like_domain(domain_of(token).unwrap_or(token)))
+        .find(|token| {
+            let shown = domain_of(token).unwrap_or(token).to_ascii_lowercase();
+            !same_organization(&shown, &sender_domain)
+        })
+        .map(str::to_string)
+}
+
+/// One domain is the other or a subdomain of it
+fn same_organization(a: &str, b: &str) -> bool {
+    a == b || a.ends_with(&format!(".{}", b)) || b.ends_with(&format!(".{}", a))
+}
+
+fn domain_of(address: &str) -> Option<&str> {
+    address.rsplit_once('@').map(|(_, domain)| domain)
+}
+
+/// `label.label` with a letters-only TLD of two or more characters
+fn looks_like_domain(s: &str) -> bool {
+    let Some((rest, tld)) = s.rsplit_once('.') else {
+        return false;
+    };
+    !rest.is_empty()
+        && tld.len() >= 2
+        && tld.chars().all(|c| c.is_ascii_alphabetic())
+        && rest
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+}
+
+/// Header (name, value) pairs with continuation lines joined
+fn unfold_headers(block: &[u8]) -> Vec<(String, String)> {
+    let text = String::from_utf8_lossy(block);
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for line in text.lines() {
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    headers
+}
+
+fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str())
+}
+
+/// The host that produced an Authentication-Results value
+fn authserv_id(value: &str) -> &str {
+    value
+        .split(';')
+        .next()
+        .and_then(|id| id.split_whitespace().next())
+        .unwrap_or("")
+}
+
+/// `(method, verdict)` for each `method=result` clause, e.g. `spf=pass (...)`
+fn method_results(value: &str) -> Vec<(String, AuthVerdict)> {
+    value
+        .split(';')
+        .skip(1)
+        .filter_map(|clause| {
+            let (method, result) = clause.split_whitespace().next()?.split_once('=')?;
+            Some((method.to_ascii_lowercase(), AuthVerdict::parse(result)?))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from(name: Option<&str>, email: &str) -> Address {
+        Address {
+            name: name.map(str::to_string),
+            email: email.to_string(),
+        }
+    }
+
+    const PASSING: &[u8] = b"Return-Path: <bounce-123@mail.example.com>\r\n\
+Authentication-Results: mx.google.com;\r\n\t\
+dkim=pass header.i=@example.com header.s=s1 header.b=abc;\r\n\t\
+spf=pass (google.com: domain of bounce-123@mail.example.com designates 1.2.3.4 as permitted sender) smtp.mailfrom=bounce-123@mail.example.com;\r\n\t\
+dmarc=pass (p=REJECT sp=REJECT dis=NONE) header.from=example.com\r\n\
+From: Example <news@example.com>\r\n";
+
+    #[test]
+    fn passing_mail_is_not_suspicious() {
+        let auth = parse_email_auth(PASSING, &from(Some("Example"), "news@example.com")).unwrap();
+        assert_eq!(auth.spf, Some(AuthVerdict::Pass));
+        assert_eq!(auth.dkim, Some(AuthVerdict::Pass));
+        assert_eq!(auth.dmarc, Some(AuthVerdict::Pass));
+        assert_eq!(
+            auth.return_path.as_deref(),
+            Some("bounce-123@mail.example.com")
+        );
+        assert!(!auth.suspicious);
+        assert!(auth.warnings.is_empty());
+    }
+
+    #[test]
+    fn failures_are_flagged_and_forged_results_ignored() {
+        let headers = b"Authentication-Results: mx.google.com; spf=softfail smtp.mailfrom=x@evil.test; dkim=fail header.i=@paypal.com; dmarc=fail (p=REJECT) header.from=paypal.com\r\n\
+Authentication-Results: evil.test; spf=pass; dkim=pass; dmarc=pass\r\n";
+        let auth = parse_email_auth(headers, &from(Some("PayPal"), "service@paypal.com")).unwrap();
+        assert_eq!(auth.spf, Some(AuthVerdict::Softfail));
+        assert_eq!(auth.dkim, Some(AuthVerdict::Fail));
+        assert_eq!(auth.dmarc, Some(AuthVerdict::Fail));
+        assert!(auth.suspicious);
+        assert_eq!(
+            auth.warnings,
+            vec!["SPF softfail", "DKIM fail", "DMARC fail"]
+        );
+    }
+
+    #[test]
+    fn any_passing_dkim_signature_counts() {
+        let headers =
+            b"Authentication-Results: mx.google.com; dkim=fail header.i=@old.test; dkim=pass header.i=@example.com\r\n";
+        let auth = parse_email_auth(headers, &from(None, "a@example.com")).unwrap();
+        assert_eq!(auth.dkim, Some(AuthVerdict::Pass));
+    }
+
+    #[test]
+    fn display_name_spoofing() {
+        let spoofed = parse_email_auth(
+            PASSING,
+            &from(Some("service@paypal.com"), "news@example.com"),
+        )
+        .unwrap();
+        assert!(spoofed.suspicious);
+        assert_eq!(
+            spoofed.warnings,
+            vec!["Display name shows service@paypal.com but the sender is news@example.com"]
+        );
+
+        let brand = parse_email_auth(
+            PASSING,
+            &from(Some("paypal.com Support"), "news@example.com"),
+        );
+        assert!(brand.unwrap().suspicious);
+
+        // Own domain or a subdomain of it is fine, and so are plain names
+        for name in [
+            "news@example.com",
+            "example.com team",
+            "Mail.Example.com",
+            "Dr. Jane Doe",
+        ] {
+            let auth = parse_email_auth(PASSING, &from(Some(name), "news@example.com")).unwrap();
+            assert!(!auth.suspicious, "{}", name);
+        }
+    }
+
+    #[test]
+    fn sent_mail_has_no_auth() {
+        let headers = b"From: Me <me@example.com>\r\nSubject: Hi\r\n";
+        assert!(parse_email_auth(headers, &from(None, "me@example.com")).is_none());
+    }
+}
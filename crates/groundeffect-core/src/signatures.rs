@@ -0,0 +1,342 @@
+//! Signatures for outgoing mail
+//!
+//! Gmail keeps one HTML signature per send-as address but only adds it to
+//! mail written in Gmail itself. Send and draft commands take
+//! `--signature default|none|<name>`: `default` is the sender's local
+//! signature from `[compose.identities]` if one is set, otherwise its Gmail
+//! signature; `<name>` is a `[compose.signatures]` entry or the Gmail
+//! signature of another send-as address. The plain-text and HTML versions
+//! are appended to the matching parts of the message.
+
+use std::sync::OnceLock;
+
+use regex::Regex;
+use reqwest::Client;
+use serde::Serialize;
+use serde_json::Value;
+use tracing::warn;
+
+use crate::config::ComposeConfig;
+use crate::error::{Error, Result};
+use crate::oauth::OAuthManager;
+
+/// Gmail API send-as settings endpoint
+const SEND_AS_URL: &str = "https://gmail.googleapis.com/gmail/v1/users/me/settings/sendAs";
+
+/// Line separating the body from the signature ("-- " per RFC 3676)
+const SIGNATURE_SEPARATOR: &str = "-- ";
+
+/// Which signature to add to an outgoing message
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum SignatureChoice {
+    /// The sender's local signature, else its Gmail signature
+    #[default]
+    Default,
+    /// No signature
+    None,
+    /// A `[compose.signatures]` entry or a send-as address
+    Named(String),
+}
+
+impl SignatureChoice {
+    /// Parse a `--signature` value
+    pub fn parse(s: &str) -> Self {
+        let s = s.trim();
+        if s.is_empty() || s.eq_ignore_ascii_case("default") {
+            Self::Default
+        } else if s.eq_ignore_ascii_case("none") {
+            Self::None
+        } else {
+            Self::Named(s.to_string())
+        }
+    }
+}
+
+/// A signature's plain-text and HTML versions
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Signature {
+    pub text: String,
+
+    /// HTML version (the text is escaped for HTML mail when unset)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub html: Option<String>,
+}
+
+impl Signature {
+    /// Build from either version; the text is derived from the HTML when
+    /// missing. None when both are blank.
+    pub fn from_parts(text: Option<&str>, html: Option<&str>) -> Option<Self> {
+        let html = html.map(str::trim).filter(|h| !h.is_empty());
+        let text = match text.map(str::trim).filter(|t| !t.is_empty()) {
+            Some(text) => text.to_string(),
+            None => html_to_text(html.unwrap_or_default()),
+        };
+        if text.is_empty() && html.is_none() {
+            return None;
+        }
+        Some(Self {
+            text,
+            html: html.map(str::to_string),
+        })
+    }
+
+    /// Append to a plain-text body unless it already ends with the signature
+    pub fn append_text(&self, body: &str) -> String {
+        if self.text.is_empty() || body.trim_end().ends_with(&self.text) {
+            return body.to_string();
+        }
+        format!(
+            "{}\n\n{}\n{}",
+            body.trim_end(),
+            SIGNATURE_SEPARATOR,
+            self.text
+        )
+    }
+
+    /// Append to an HTML body unless it already contains the signature
+    pub fn append_html(&self, html: &str) -> String {
+        let signature = self
+            .html
+            .clone()
+            .unwrap_or_else(|| escape_html(&self.text).replace('\n', "<br>\n"));
+        if html.contains(&signature) {
+            return html.to_string();
+        }
+        format!(
+            "{}<br>\n<br>\n<div class=\"signature\">{}<br>\n{}</div>",
+            html.trim_end(),
+            SIGNATURE_SEPARATOR,
+            signature
+        )
+    }
+}
+
+/// A Gmail send-as address and its signature
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GmailSignature {
+    pub send_as: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+
+    /// The address Gmail sends from by default
+    pub is_default: bool,
+
+    /// Signature HTML (empty when none is set)
+    pub html: String,
+}
+
+impl GmailSignature {
+    /// The signature, None when it's blank
+    pub fn signature(&self) -> Option<Signature> {
+        Signature::from_parts(None, Some(&self.html))
+    }
+}
+
+/// Fetch the send-as addresses of an account with their signatures
+pub async fn fetch_gmail_signatures(
+    oauth: &OAuthManager,
+    account_id: &str,
+) -> Result<Vec<GmailSignature>> {
+    let access_token = oauth.get_valid_token(account_id).await?;
+    let response = Client::new()
+        .get(SEND_AS_URL)
+        .bearer_auth(&access_token)
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(Error::Other(format!(
+            "Gmail API error {}: {}",
+            status, body
+        )));
+    }
+    Ok(parse_send_as(&response.json().await?))
+}
+
+/// Send-as entries from a `settings/sendAs` response
+fn parse_send_as(json: &Value) -> Vec<GmailSignature> {
+    json["sendAs"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            Some(GmailSignature {
+                send_as: entry["sendAsEmail"].as_str()?.to_string(),
+                display_name: entry["displayName"]
+                    .as_str()
+                    .filter(|n| !n.is_empty())
+                    .map(str::to_string),
+                is_default: entry["isDefault"].as_bool().unwrap_or(false),
+                html: entry["signature"].as_str().unwrap_or_default().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Resolve a `--signature` choice for mail sent as `sender` from `account_id`
+///
+/// The Gmail signature is fetched only when no local one applies. Failing to
+/// fetch it sends without a signature for `default`, but is an error for a
+/// named signature.
+pub async fn resolve_signature(
+    compose: &ComposeConfig,
+    oauth: &OAuthManager,
+    account_id: &str,
+    sender: &str,
+    choice: &SignatureChoice,
+) -> Result<Option<Signature>> {
+    match choice {
+        SignatureChoice::None => Ok(None),
+        SignatureChoice::Default => {
+            if let Some(signature) = compose
+                .identity(sender)
+                .and_then(|identity| identity.local_signature())
+            {
+                return Ok(Some(signature));
+            }
+            match fetch_gmail_signatures(oauth, account_id).await {
+                Ok(signatures) => Ok(signatures
+                    .iter()
+                    .find(|s| s.send_as.eq_ignore_ascii_case(sender))
+                    .and_then(GmailSignature::signature)),
+                Err(e) => {
+                    warn!("Couldn't fetch Gmail signatures for {}: {}", account_id, e);
+                    Ok(None)
+                }
+            }
+        }
+        SignatureChoice::Named(name) => {
+            if let Some(signature) = compose.signature(name) {
+                return Ok(Some(signature));
+            }
+            fetch_gmail_signatures(oauth, account_id)
+                .await?
+                .iter()
+                .find(|s| s.send_as.eq_ignore_ascii_case(name))
+                .and_then(GmailSignature::signature)
+                .map(Some)
+                .ok_or_else(|| {
+                    Error::InvalidRequest(format!(
+                        "Unknown signature '{}': not in [compose.signatures] or a Gmail send-as address with a signature",
+                        name
+                    ))
+                })
+        }
+    }
+}
+
+/// Plain text of an HTML signature: line breaks kept, links as "text (url)"
+fn html_to_text(html: &str) -> String {
+    static BREAK: OnceLock<Regex> = OnceLock::new();
+    static ANCHOR: OnceLock<Regex> = OnceLock::new();
+    static TAG: OnceLock<Regex> = OnceLock::new();
+    let line_break = BREAK
+        .get_or_init(|| Regex::new(r"(?i)<br\s*/?>|</?(?:p|div|li|tr|h[1-6])\b[^>]*>").unwrap());
+    let anchor = ANCHOR.get_or_init(|| {
+        Regex::new(r#"(?is)<a\s[^>]*href\s*=\s*"([^"]*)"[^>]*>(.*?)</a>"#).unwrap()
+    });
+    let tag = TAG.get_or_init(|| Regex::new(r"<[^>]*>").unwrap());
+
+    let text = line_break.replace_all(html, "\n");
+    let text = anchor.replace_all(&text, |caps: &regex::Captures| {
+        let url = &caps[1];
+        let label = tag.replace_all(&caps[2], "").trim().to_string();
+        let shown = url.strip_prefix("mailto:").unwrap_or(url);
+        if label.is_empty() || label == shown {
+            shown.to_string()
+        } else {
+            format!("{} ({})", label, shown)
+        }
+    });
+    let text = tag.replace_all(&text, "");
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+
+    let lines: Vec<String> = text
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect();
+    let mut out = lines.join("\n");
+    while out.contains("\n\n\n") {
+        out = out.replace("\n\n\n", "\n\n");
+    }
+    out.trim().to_string()
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_choice() {
+        assert_eq!(SignatureChoice::parse("default"), SignatureChoice::Default);
+        assert_eq!(SignatureChoice::parse(""), SignatureChoice::Default);
+        assert_eq!(SignatureChoice::parse("None"), SignatureChoice::None);
+        assert_eq!(
+            SignatureChoice::parse("support@example.com"),
+            SignatureChoice::Named("support@example.com".to_string())
+        );
+    }
+
+    #[test]
+    fn text_is_derived_from_html() {
+        let signature = Signature::from_parts(
+            None,
+            Some(
+                r#"<div dir="ltr"><b>Jane Doe</b><br>CEO &amp; Founder<div><a href="https://acme.example">Acme</a> | <a href="mailto:jane@acme.example">jane@acme.example</a></div></div>"#,
+            ),
+        )
+        .unwrap();
+        assert_eq!(
+            signature.text,
+            "Jane Doe\nCEO & Founder\nAcme (https://acme.example) | jane@acme.example"
+        );
+        assert!(Signature::from_parts(Some("  "), Some("")).is_none());
+    }
+
+    #[test]
+    fn append_to_both_parts_once() {
+        let signature = Signature::from_parts(Some("Jane\n<Acme>"), None).unwrap();
+
+        let body = signature.append_text("Thanks!\n");
+        assert_eq!(body, "Thanks!\n\n-- \nJane\n<Acme>");
+        assert_eq!(signature.append_text(&body), body);
+
+        let html = signature.append_html("<p>Thanks!</p>");
+        assert_eq!(
+            html,
+            "<p>Thanks!</p><br>\n<br>\n<div class=\"signature\">-- <br>\nJane<br>\n&lt;Acme&gt;</div>"
+        );
+        assert_eq!(signature.append_html(&html), html);
+    }
+
+    #[test]
+    fn send_as_entries() {
+        let json = serde_json::json!({
+            "sendAs": [
+                {"sendAsEmail": "me@example.com", "displayName": "Me", "isDefault": true, "signature": "<b>Me</b>"},
+                {"sendAsEmail": "support@example.com", "displayName": ""},
+                {"displayName": "no address"}
+            ]
+        });
+        let entries = parse_send_as(&json);
+        assert_eq!(entries.len(), 2);
+        assert!(entries[0].is_default);
+        assert_eq!(entries[0].signature().unwrap().text, "Me");
+        assert_eq!(entries[1].display_name, None);
+        assert!(entries[1].signature().is_none());
+    }
+}
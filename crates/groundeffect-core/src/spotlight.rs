@@ -0,0 +1,337 @@
+//! Spotlight stubs for synced emails
+//!
+//! macOS Spotlight only indexes files, so each email in the `[spotlight]`
+//! window gets a small stub file: a shell script whose comments hold the
+//! subject, addresses, date and body. The system's plain-text importer
+//! indexes it, so Spotlight finds the email, and opening the result runs
+//! `groundeffect email show <id> --human` in Terminal. The stubs live in
+//! `~/Library/Caches/Metadata/groundeffect` by default, the place Apple
+//! sets aside for files that exist only to be found by Spotlight.
+//!
+//! Each export rewrites stubs whose email changed and deletes stubs of
+//! emails that left the window or were deleted, so the directory mirrors
+//! the index.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tracing::{debug, info};
+
+use crate::config::{active_profile, restrict_to_owner, Config};
+use crate::db::Database;
+use crate::error::{Error, Result};
+use crate::models::{Address, Email};
+
+/// Extension of stub files; Finder opens `.command` scripts in Terminal
+const STUB_EXTENSION: &str = "command";
+
+/// Body characters kept in a stub
+const MAX_BODY_CHARS: usize = 20_000;
+
+/// Subject characters kept in a stub's file name
+const MAX_NAME_CHARS: usize = 80;
+
+/// What an export changed
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SpotlightReport {
+    /// Directory holding the stubs
+    pub dir: PathBuf,
+
+    /// Stubs created or rewritten
+    pub written: usize,
+
+    /// Stubs already up to date
+    pub unchanged: usize,
+
+    /// Stubs of emails no longer exported
+    pub removed: usize,
+}
+
+/// Write stubs for the `[spotlight]` accounts' emails dated within `days`
+/// of `now`, and remove the rest
+pub async fn export(db: &Database, config: &Config, now: DateTime<Utc>) -> Result<SpotlightReport> {
+    let spotlight = &config.spotlight;
+    let accounts = if spotlight.accounts.is_empty() {
+        db.list_accounts()
+            .await?
+            .into_iter()
+            .map(|a| a.id)
+            .collect()
+    } else {
+        spotlight
+            .accounts
+            .iter()
+            .map(|a| {
+                config
+                    .resolve_account(a)
+                    .ok_or_else(|| Error::AccountNotFound(a.clone()))
+            })
+            .collect::<Result<Vec<String>>>()?
+    };
+
+    let since = now - Duration::days(spotlight.days as i64);
+    let mut stubs = HashMap::new();
+    for account in &accounts {
+        for email in db.list_emails_since(Some(account), since).await? {
+            stubs.insert(
+                stub_path(&email),
+                (render_stub(&email, active_profile()), email.date),
+            );
+        }
+    }
+
+    let report = write_stubs(&config.spotlight_dir(), &stubs)?;
+    info!(
+        "Spotlight stubs: {} written, {} unchanged, {} removed",
+        report.written, report.unchanged, report.removed
+    );
+    Ok(report)
+}
+
+/// Make `dir` hold exactly `stubs` (path relative to `dir` -> contents and
+/// email date)
+///
+/// Unchanged stubs aren't rewritten, so Spotlight only re-imports what
+/// changed. Each stub's modification time is its email's date.
+pub fn write_stubs(
+    dir: &Path,
+    stubs: &HashMap<PathBuf, (String, DateTime<Utc>)>,
+) -> Result<SpotlightReport> {
+    std::fs::create_dir_all(dir)?;
+    restrict_to_owner(dir, 0o700)?;
+
+    let mut report = SpotlightReport {
+        dir: dir.to_path_buf(),
+        ..Default::default()
+    };
+    for (relative, (contents, date)) in stubs {
+        let path = dir.join(relative);
+        if std::fs::read_to_string(&path).is_ok_and(|old| old == *contents) {
+            report.unchanged += 1;
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, contents)?;
+        // Owner-only, and executable so Finder runs it
+        restrict_to_owner(&path, 0o700)?;
+        std::fs::File::options()
+            .write(true)
+            .open(&path)?
+            .set_modified((*date).into())?;
+        report.written += 1;
+    }
+
+    let keep: HashSet<PathBuf> = stubs.keys().map(|p| dir.join(p)).collect();
+    for path in stub_files(dir)? {
+        if !keep.contains(&path) {
+            debug!("Removing Spotlight stub {:?}", path);
+            std::fs::remove_file(&path)?;
+            report.removed += 1;
+        }
+    }
+    Ok(report)
+}
+
+/// Where an email's stub goes, relative to the stub directory:
+/// `<account>/<year>/<subject> (<id hash>).command`
+pub fn stub_path(email: &Email) -> PathBuf {
+    let subject: String = sanitize_name(&email.subject)
+        .chars()
+        .take(MAX_NAME_CHARS)
+        .collect();
+    let subject = match subject.trim() {
+        "" => "(no subject)",
+        s => s,
+    };
+    let hash: String = Sha256::digest(email.id.as_bytes())
+        .iter()
+        .take(4)
+        .map(|b| format!("{:02x}", b))
+        .collect();
+
+    PathBuf::from(sanitize_name(&email.account_id))
+        .join(email.date.format("%Y").to_string())
+        .join(format!("{} ({}).{}", subject, hash, STUB_EXTENSION))
+}
+
+/// A stub: a script that shows the email with the CLI, followed by the
+/// email's text as comments for Spotlight to index
+pub fn render_stub(email: &Email, profile: Option<&str>) -> String {
+    let mut command = String::from("exec groundeffect");
+    if let Some(profile) = profile {
+        command.push_str(" --profile ");
+        command.push_str(&shell_quote(profile));
+    }
+    command.push_str(" email show ");
+    command.push_str(&shell_quote(&email.id));
+    command.push_str(" --human");
+
+    let mut text = format!(
+        "Subject: {}\nFrom: {}\n",
+        email.subject,
+        email.from.to_string_full()
+    );
+    for (label, people) in [("To", &email.to), ("Cc", &email.cc)] {
+        if !people.is_empty() {
+            let people: Vec<String> = people.iter().map(Address::to_string_full).collect();
+            text.push_str(&format!("{}: {}\n", label, people.join(", ")));
+        }
+    }
+    text.push_str(&format!("Date: {}\n", email.date.to_rfc2822()));
+    let attachments: Vec<&str> = email
+        .attachments
+        .iter()
+        .map(|a| a.filename.as_str())
+        .collect();
+    if !attachments.is_empty() {
+        text.push_str(&format!("Attachments: {}\n", attachments.join(", ")));
+    }
+    text.push('\n');
+    let body = email.resolved_body();
+    text.extend(body.chars().take(MAX_BODY_CHARS));
+
+    let mut stub = format!("#!/bin/sh\n{}\n\n", command);
+    // Every line of email text is a comment, so none of it can run
+    for line in text.replace("\r\n", "\n").split(['\n', '\r']) {
+        let line: String = line
+            .chars()
+            .filter(|c| !c.is_control() || *c == '\t')
+            .collect();
+        stub.push_str(format!("# {}", line).trim_end());
+        stub.push('\n');
+    }
+    stub
+}
+
+/// Stub files under `dir`, at any depth
+fn stub_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if path.extension().is_some_and(|ext| ext == STUB_EXTENSION) {
+                files.push(path);
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// A file name without path separators, characters Finder rejects, or a
+/// leading dot (which would hide it from Spotlight)
+fn sanitize_name(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '\0' => '-',
+            c if c.is_control() => ' ',
+            c => c,
+        })
+        .collect::<String>()
+        .trim()
+        .trim_start_matches('.')
+        .to_string()
+}
+
+/// Single-quote `value` for `/bin/sh`
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn email(id: &str, subject: &str, body: &str) -> Email {
+        Email {
+            id: id.to_string(),
+            gmail_message_id: 1,
+            gmail_thread_id: 1,
+            uid: 1,
+            from: Address::with_name("Pat Lee", "pat@example.com"),
+            to: vec![Address::new("me@example.com")],
+            date: Utc.with_ymd_and_hms(2024, 6, 3, 9, 0, 0).unwrap(),
+            body_plain: body.to_string(),
+            ..Email::test("<1@x>", "pat@example.com", subject)
+        }
+    }
+
+    #[test]
+    fn test_stub_runs_only_the_show_command() {
+        let email = email(
+            "me@example.com:<it's@x>",
+            "Q3 plan\n$(rm -rf ~)",
+            "Numbers attached.\r\nexec rm -rf ~\n`whoami`",
+        );
+        let stub = render_stub(&email, Some("work"));
+
+        let mut lines = stub.lines();
+        assert_eq!(lines.next(), Some("#!/bin/sh"));
+        assert_eq!(
+            lines.next(),
+            Some("exec groundeffect --profile 'work' email show 'me@example.com:<it'\\''s@x>' --human")
+        );
+        assert!(lines.all(|l| l.is_empty() || l.starts_with('#')));
+        assert!(stub.contains("# From: Pat Lee <pat@example.com>\n"));
+        assert!(stub.contains("# exec rm -rf ~\n"));
+    }
+
+    #[test]
+    fn test_stub_names_are_safe_and_unique() {
+        let path = stub_path(&email("a", "../Re: Q3/Q4 plan", ""));
+        assert_eq!(path.parent(), Some(Path::new("me@example.com/2024")));
+        let name = path.file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with("-Re- Q3-Q4 plan ("), "{}", name);
+        assert!(name.ends_with(").command"));
+
+        assert_ne!(
+            stub_path(&email("a", "", "")),
+            stub_path(&email("b", "", ""))
+        );
+        assert!(stub_path(&email("a", "  ", ""))
+            .to_str()
+            .unwrap()
+            .contains("(no subject)"));
+    }
+
+    #[test]
+    fn test_write_stubs_mirrors_the_export() {
+        let dir = std::env::temp_dir().join(format!("ge-spotlight-{}", uuid::Uuid::new_v4()));
+        let first = email("a", "First", "one");
+        let second = email("b", "Second", "two");
+        let stubs = |emails: &[&Email]| {
+            emails
+                .iter()
+                .map(|e| (stub_path(e), (render_stub(e, None), e.date)))
+                .collect::<HashMap<_, _>>()
+        };
+
+        let report = write_stubs(&dir, &stubs(&[&first, &second])).unwrap();
+        assert_eq!(
+            (report.written, report.unchanged, report.removed),
+            (2, 0, 0)
+        );
+        let modified = std::fs::metadata(dir.join(stub_path(&first)))
+            .unwrap()
+            .modified()
+            .unwrap();
+        assert_eq!(DateTime::<Utc>::from(modified), first.date);
+
+        let report = write_stubs(&dir, &stubs(&[&first])).unwrap();
+        assert_eq!(
+            (report.written, report.unchanged, report.removed),
+            (0, 1, 1)
+        );
+        assert!(!dir.join(stub_path(&second)).exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
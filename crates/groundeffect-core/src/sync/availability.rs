@@ -0,0 +1,396 @@
+//! Gmail vacation responder and Calendar out-of-office events, with an
+//! on-disk cache, and free/busy lookups for other people's calendars
+//!
+//! The user's own state is refreshed on each calendar sync so MCP clients
+//! can read it without a round trip to Google; a stale cache is used when
+//! Google can't be reached.
+
+use std::path::Path;
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use reqwest::Client;
+use tracing::{debug, warn};
+
+use crate::config::Config;
+use crate::error::{Error, Result};
+use crate::models::{AvailabilityState, OutOfOffice, VacationResponder};
+use crate::oauth::OAuthManager;
+
+/// Gmail API vacation responder settings endpoint
+const VACATION_URL: &str = "https://gmail.googleapis.com/gmail/v1/users/me/settings/vacation";
+
+/// Google Calendar API primary calendar events endpoint
+const EVENTS_URL: &str = "https://www.googleapis.com/calendar/v3/calendars/primary/events";
+
+/// Google Calendar API free/busy endpoint
+const FREEBUSY_URL: &str = "https://www.googleapis.com/calendar/v3/freeBusy";
+
+/// Cached state older than this is refetched (1 hour)
+pub const AVAILABILITY_CACHE_TTL_SECS: u64 = 3600;
+
+/// Busy intervals from a free/busy lookup, or None when the calendar isn't
+/// visible to the asking account
+pub type FreeBusy = Option<Vec<(DateTime<Utc>, DateTime<Utc>)>>;
+
+/// Fetch the vacation responder and upcoming out-of-office blocks
+///
+/// Out-of-office events are read from now to `lookahead_days` ahead. If the
+/// vacation settings can't be read (e.g. a token without Gmail settings
+/// access) the state is returned without them.
+pub async fn fetch_availability(
+    oauth: &OAuthManager,
+    account_id: &str,
+    lookahead_days: u32,
+) -> Result<AvailabilityState> {
+    let access_token = oauth.get_valid_token(account_id).await?;
+    let client = Client::new();
+
+    let vacation = match client
+        .get(VACATION_URL)
+        .bearer_auth(&access_token)
+        .send()
+        .await
+    {
+        Ok(resp) if resp.status().is_success() => {
+            Some(parse_vacation(&resp.json::<serde_json::Value>().await?))
+        }
+        Ok(resp) => {
+            warn!(
+                "Vacation responder unavailable for {}: {}",
+                account_id,
+                resp.status()
+            );
+            None
+        }
+        Err(e) => {
+            warn!("Vacation responder unavailable for {}: {}", account_id, e);
+            None
+        }
+    };
+
+    let now = Utc::now();
+    let time_min = now.to_rfc3339();
+    let time_max = (now + Duration::days(lookahead_days as i64)).to_rfc3339();
+    let mut out_of_office = Vec::new();
+    let mut page_token: Option<String> = None;
+    loop {
+        let mut query = vec![
+            ("eventTypes", "outOfOffice"),
+            ("singleEvents", "true"),
+            ("orderBy", "startTime"),
+            ("timeMin", time_min.as_str()),
+            ("timeMax", time_max.as_str()),
+        ];
+        if let Some(token) = page_token.as_deref() {
+            query.push(("pageToken", token));
+        }
+        let response = client
+            .get(EVENTS_URL)
+            .query(&query)
+            .bearer_auth(&access_token)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(Error::CalDav(format!(
+                "Failed to fetch out-of-office events: {} - {}",
+                status, body
+            )));
+        }
+
+        let json: serde_json::Value = response.json().await?;
+        if let Some(items) = json["items"].as_array() {
+            out_of_office.extend(items.iter().filter_map(parse_out_of_office));
+        }
+        match json["nextPageToken"].as_str() {
+            Some(token) => page_token = Some(token.to_string()),
+            None => break,
+        }
+    }
+
+    debug!(
+        "Fetched availability for {}: {} out-of-office blocks",
+        account_id,
+        out_of_office.len()
+    );
+    Ok(AvailabilityState {
+        account_id: account_id.to_string(),
+        fetched_at: now,
+        vacation,
+        out_of_office,
+    })
+}
+
+/// Busy times on other people's primary calendars, asked as `account_id`
+///
+/// Calendars outside the account's organization usually aren't shared, so
+/// those come back as None rather than as free.
+pub async fn query_free_busy(
+    oauth: &OAuthManager,
+    account_id: &str,
+    emails: &[String],
+    time_min: DateTime<Utc>,
+    time_max: DateTime<Utc>,
+) -> Result<Vec<(String, FreeBusy)>> {
+    if emails.is_empty() {
+        return Ok(Vec::new());
+    }
+    let access_token = oauth.get_valid_token(account_id).await?;
+    let body = serde_json::json!({
+        "timeMin": time_min.to_rfc3339(),
+        "timeMax": time_max.to_rfc3339(),
+        "items": emails.iter().map(|email| serde_json::json!({"id": email})).collect::<Vec<_>>(),
+    });
+
+    let response = Client::new()
+        .post(FREEBUSY_URL)
+        .bearer_auth(&access_token)
+        .json(&body)
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(Error::CalDav(format!(
+            "Failed to query free/busy: {} - {}",
+            status, body
+        )));
+    }
+
+    let json: serde_json::Value = response.json().await?;
+    Ok(emails
+        .iter()
+        .map(|email| (email.clone(), parse_free_busy(&json["calendars"][email])))
+        .collect())
+}
+
+/// Fetch the state and write it to the cache
+pub async fn refresh_availability(
+    oauth: &OAuthManager,
+    config: &Config,
+    account_id: &str,
+) -> Result<AvailabilityState> {
+    let state = fetch_availability(oauth, account_id, config.availability.lookahead_days).await?;
+    save_availability(&config.availability_cache_file(account_id), &state)?;
+    Ok(state)
+}
+
+/// Availability for an account, using the on-disk cache when it is fresh
+///
+/// With `refresh` the cache is bypassed. If Google can't be reached, a stale
+/// cache is returned rather than failing.
+pub async fn load_availability(
+    oauth: &OAuthManager,
+    config: &Config,
+    account_id: &str,
+    refresh: bool,
+) -> Result<AvailabilityState> {
+    let cached = load_availability_cache(&config.availability_cache_file(account_id));
+    if !refresh {
+        if let Some(state) = cached
+            .as_ref()
+            .filter(|s| s.is_fresh(AVAILABILITY_CACHE_TTL_SECS))
+        {
+            return Ok(state.clone());
+        }
+    }
+
+    match refresh_availability(oauth, config, account_id).await {
+        Ok(state) => Ok(state),
+        Err(e) => match cached {
+            Some(state) => {
+                warn!(
+                    "Failed to fetch availability for {}, using cache from {}: {}",
+                    account_id, state.fetched_at, e
+                );
+                Ok(state)
+            }
+            None => Err(e),
+        },
+    }
+}
+
+/// Read an availability cache file (None if missing or unreadable)
+pub fn load_availability_cache(path: &Path) -> Option<AvailabilityState> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+fn save_availability(path: &Path, state: &AvailabilityState) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(state)?)?;
+    Ok(())
+}
+
+/// Parse a Gmail API vacation settings resource
+fn parse_vacation(json: &serde_json::Value) -> VacationResponder {
+    // Times are epoch milliseconds, sent as strings
+    let millis = |key: &str| {
+        json[key]
+            .as_str()
+            .and_then(|ms| ms.parse::<i64>().ok())
+            .and_then(|ms| Utc.timestamp_millis_opt(ms).single())
+    };
+    let text = |key: &str| {
+        json[key]
+            .as_str()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    };
+
+    VacationResponder {
+        enabled: json["enableAutoReply"].as_bool().unwrap_or(false),
+        start: millis("startTime"),
+        end: millis("endTime"),
+        subject: text("responseSubject"),
+        message: text("responseBodyPlainText"),
+        contacts_only: json["restrictToContacts"].as_bool().unwrap_or(false),
+        domain_only: json["restrictToDomain"].as_bool().unwrap_or(false),
+    }
+}
+
+/// Parse one calendar's entry in a free/busy response
+fn parse_free_busy(json: &serde_json::Value) -> FreeBusy {
+    // Unshared and unknown calendars come back with errors and no busy times
+    if json.is_null() || json["errors"].as_array().is_some_and(|e| !e.is_empty()) {
+        return None;
+    }
+    let time = |value: &serde_json::Value| {
+        DateTime::parse_from_rfc3339(value.as_str()?)
+            .ok()
+            .map(|dt| dt.with_timezone(&Utc))
+    };
+    Some(
+        json["busy"]
+            .as_array()
+            .map(|busy| {
+                busy.iter()
+                    .filter_map(|b| Some((time(&b["start"])?, time(&b["end"])?)))
+                    .collect()
+            })
+            .unwrap_or_default(),
+    )
+}
+
+/// Parse a Google Calendar API out-of-office event
+fn parse_out_of_office(json: &serde_json::Value) -> Option<OutOfOffice> {
+    if json["eventType"].as_str() != Some("outOfOffice") || json["status"] == "cancelled" {
+        return None;
+    }
+    let time = |key: &str| {
+        DateTime::parse_from_rfc3339(json[key]["dateTime"].as_str()?)
+            .ok()
+            .map(|dt| dt.with_timezone(&Utc))
+    };
+    let properties = &json["outOfOfficeProperties"];
+
+    Some(OutOfOffice {
+        event_id: json["id"].as_str()?.to_string(),
+        summary: json["summary"]
+            .as_str()
+            .unwrap_or("Out of office")
+            .to_string(),
+        start: time("start")?,
+        end: time("end")?,
+        auto_decline: properties["autoDeclineMode"]
+            .as_str()
+            .is_some_and(|mode| mode != "declineNone"),
+        decline_message: properties["declineMessage"]
+            .as_str()
+            .filter(|s| !s.trim().is_empty())
+            .map(str::to_string),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_vacation() {
+        let vacation = parse_vacation(&serde_json::json!({
+            "enableAutoReply": true,
+            "responseSubject": "Away",
+            "responseBodyPlainText": "Back on the 12th.",
+            "restrictToContacts": false,
+            "restrictToDomain": true,
+            "startTime": "1735689600000",
+            "endTime": "1736640000000"
+        }));
+        assert!(vacation.enabled);
+        assert!(vacation.domain_only);
+        assert_eq!(vacation.message.as_deref(), Some("Back on the 12th."));
+        let start = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+        assert_eq!(vacation.start, Some(start));
+        assert!(vacation.is_active_at(start + Duration::days(3)));
+        assert!(!vacation.is_active_at(start + Duration::days(30)));
+
+        let off = parse_vacation(&serde_json::json!({"enableAutoReply": false}));
+        assert!(!off.enabled);
+        assert!(off.start.is_none());
+        assert!(!off.is_active_at(start));
+    }
+
+    #[test]
+    fn test_parse_out_of_office() {
+        let block = parse_out_of_office(&serde_json::json!({
+            "id": "ooo1",
+            "eventType": "outOfOffice",
+            "status": "confirmed",
+            "summary": "Vacation",
+            "start": {"dateTime": "2025-03-10T00:00:00-07:00"},
+            "end": {"dateTime": "2025-03-15T00:00:00-07:00"},
+            "outOfOfficeProperties": {
+                "autoDeclineMode": "declineAllConflictingInvitations",
+                "declineMessage": "I'm away this week"
+            }
+        }))
+        .unwrap();
+        assert_eq!(block.summary, "Vacation");
+        assert_eq!(
+            block.start,
+            Utc.with_ymd_and_hms(2025, 3, 10, 7, 0, 0).unwrap()
+        );
+        assert!(block.auto_decline);
+        assert_eq!(block.decline_message.as_deref(), Some("I'm away this week"));
+
+        assert!(parse_out_of_office(&serde_json::json!({
+            "id": "meeting",
+            "eventType": "default",
+            "start": {"dateTime": "2025-03-10T09:00:00Z"},
+            "end": {"dateTime": "2025-03-10T10:00:00Z"}
+        }))
+        .is_none());
+    }
+
+    #[test]
+    fn test_parse_free_busy() {
+        let busy = parse_free_busy(&serde_json::json!({
+            "busy": [{"start": "2025-03-10T14:00:00Z", "end": "2025-03-10T15:00:00Z"}]
+        }))
+        .unwrap();
+        assert_eq!(
+            busy,
+            vec![(
+                Utc.with_ymd_and_hms(2025, 3, 10, 14, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2025, 3, 10, 15, 0, 0).unwrap()
+            )]
+        );
+
+        // Free all day
+        assert_eq!(
+            parse_free_busy(&serde_json::json!({"busy": []})),
+            Some(vec![])
+        );
+        // Not shared with this account
+        assert!(parse_free_busy(&serde_json::json!({
+            "errors": [{"domain": "global", "reason": "notFound"}],
+            "busy": []
+        }))
+        .is_none());
+    }
+}
@@ -0,0 +1,415 @@
+//! Message bodies: parsing, headers-only sync and on-demand fetching
+//!
+//! Accounts configured with `headers_only` sync each message's header block
+//! and only the start of its body, enough for a snippet. Nothing else is
+//! stored, so sender/subject/date search works while disk use stays small.
+//! `email show --fetch` downloads the full message from the Gmail API when
+//! the body is needed; it is shown but not stored.
+//!
+//! `email show --remote` goes further for messages that were never synced
+//! (too old, or not synced yet): it fetches them from the Gmail API and
+//! parses them the way sync does, optionally storing the result.
+
+use std::sync::Arc;
+
+use base64::engine::general_purpose::{URL_SAFE, URL_SAFE_NO_PAD};
+use base64::Engine;
+use mail_parser::{Message, MessageParser, MimeHeaders};
+use reqwest::Client;
+use tracing::debug;
+
+use crate::config::Config;
+use crate::error::{Error, Result};
+use crate::metrics;
+use crate::models::{Attachment, Email};
+use crate::oauth::OAuthManager;
+
+use super::{GlobalRateLimiter, ImapClient, SENT_FOLDER};
+
+/// Gmail API messages endpoint
+const MESSAGES_URL: &str = "https://gmail.googleapis.com/gmail/v1/users/me/messages";
+
+/// Bytes of the body fetched in headers-only mode, for the snippet
+pub const HEADERS_ONLY_TEXT_BYTES: usize = 2048;
+
+/// Length of an email's snippet in characters
+const SNIPPET_CHARS: usize = 200;
+
+/// Body, snippet and attachments of a parsed message
+#[derive(Debug, Clone, Default)]
+pub struct MessageBody {
+    pub body_plain: String,
+    pub body_html: Option<String>,
+    pub snippet: String,
+    pub attachments: Vec<Attachment>,
+}
+
+impl MessageBody {
+    /// Extract the body parts of a parsed message
+    pub fn from_message(parsed: &Message) -> Self {
+        let parsed_body_plain = parsed
+            .body_text(0)
+            .map(|s| s.to_string())
+            .unwrap_or_default();
+
+        let body_html = parsed.body_html(0).map(|s| s.to_string());
+
+        let body_plain =
+            Email::body_for_indexing_and_display(&parsed_body_plain, body_html.as_deref());
+        let snippet = body_plain.chars().take(SNIPPET_CHARS).collect();
+
+        let attachments = parsed
+            .attachments()
+            .map(|att| {
+                Attachment::new(
+                    uuid::Uuid::new_v4().to_string(),
+                    att.attachment_name().unwrap_or("attachment"),
+                    att.content_type()
+                        .map(|ct| ct.ctype().to_string())
+                        .unwrap_or_else(|| "application/octet-stream".to_string()),
+                    att.len() as u64,
+                )
+            })
+            .collect();
+
+        Self {
+            body_plain,
+            body_html,
+            snippet,
+            attachments,
+        }
+    }
+
+    /// Fill in an email's body, snippet and attachments
+    pub fn apply(self, email: &mut Email) {
+        email.body_plain = self.body_plain;
+        email.body_html = self.body_html;
+        email.snippet = self.snippet;
+        email.attachments = self.attachments;
+    }
+}
+
+/// A parseable message from a headers-only fetch: the header block
+/// (`BODY[HEADER]`) followed by the start of the body (`BODY[TEXT]<0.n>`)
+pub fn headers_only_source(header: &[u8], text: Option<&[u8]>) -> Vec<u8> {
+    let mut source = header.to_vec();
+    // BODY[HEADER] ends with the blank line; make sure there is one
+    if !(source.ends_with(b"\r\n\r\n") || source.ends_with(b"\n\n")) {
+        source.extend_from_slice(b"\r\n");
+    }
+    if let Some(text) = text {
+        source.extend_from_slice(text);
+    }
+    source
+}
+
+/// A message as the Gmail API returns it
+#[derive(Debug, Clone)]
+pub struct GmailMessage {
+    /// Full RFC 2822 source
+    pub source: Vec<u8>,
+    /// Gmail label ids (INBOX, SENT, UNREAD, STARRED, Label_123, ...)
+    pub label_ids: Vec<String>,
+    /// X-GM-MSGID (the API's `id`, in hex); 0 if missing
+    pub gmail_message_id: u64,
+    /// X-GM-THRID (the API's `threadId`, in hex); 0 if missing
+    pub gmail_thread_id: u64,
+}
+
+impl GmailMessage {
+    /// The folder sync would have found the message in, and its IMAP flags
+    pub fn folder_and_flags(&self) -> (&'static str, Vec<String>) {
+        let has = |label: &str| self.label_ids.iter().any(|l| l == label);
+        let folder = if has("TRASH") {
+            "[Gmail]/Trash"
+        } else if has("SPAM") {
+            "[Gmail]/Spam"
+        } else if has("SENT") && !has("INBOX") {
+            SENT_FOLDER
+        } else {
+            "INBOX"
+        };
+        let mut flags = Vec::new();
+        if !has("UNREAD") {
+            flags.push("\\Seen".to_string());
+        }
+        if has("STARRED") {
+            flags.push("\\Flagged".to_string());
+        }
+        (folder, flags)
+    }
+}
+
+/// Fetch a message's full source from the Gmail API
+///
+/// The message is found by its RFC Message-ID, which every stored email
+/// has (mail stored before Gmail IDs were recorded has none). None when the
+/// account has no such message.
+pub async fn fetch_gmail_message(
+    oauth: &OAuthManager,
+    account_id: &str,
+    message_id: &str,
+) -> Result<Option<GmailMessage>> {
+    let access_token = oauth.get_valid_token(account_id).await?;
+    let client = Client::new();
+
+    let query = format!(
+        "rfc822msgid:{}",
+        message_id
+            .trim()
+            .trim_start_matches('<')
+            .trim_end_matches('>')
+    );
+    let ids = list_gmail_ids(&client, &access_token, &query, 1).await?;
+    let Some(gmail_id) = ids.first() else {
+        return Ok(None);
+    };
+    debug!("Fetching body of {} (Gmail id {})", message_id, gmail_id);
+    fetch_gmail_raw(&client, &access_token, gmail_id)
+        .await
+        .map(Some)
+}
+
+/// Gmail API ids of up to `max` messages matching a Gmail search query,
+/// newest first (spam and trash included)
+pub(super) async fn list_gmail_ids(
+    client: &Client,
+    access_token: &str,
+    query: &str,
+    max: usize,
+) -> Result<Vec<String>> {
+    let mut ids = Vec::new();
+    let mut page_token: Option<String> = None;
+    while ids.len() < max {
+        let page_size = (max - ids.len()).min(500).to_string();
+        let mut request = client.get(MESSAGES_URL).query(&[
+            ("q", query),
+            ("maxResults", page_size.as_str()),
+            ("includeSpamTrash", "true"),
+        ]);
+        if let Some(token) = &page_token {
+            request = request.query(&[("pageToken", token.as_str())]);
+        }
+        let json = gmail_json(request.bearer_auth(access_token).send().await?).await?;
+        ids.extend(
+            json["messages"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|m| m["id"].as_str().map(str::to_string)),
+        );
+        match json["nextPageToken"].as_str() {
+            Some(token) => page_token = Some(token.to_string()),
+            None => break,
+        }
+    }
+    ids.truncate(max);
+    Ok(ids)
+}
+
+/// Fetch one message by its Gmail API id
+pub(super) async fn fetch_gmail_raw(
+    client: &Client,
+    access_token: &str,
+    gmail_id: &str,
+) -> Result<GmailMessage> {
+    let response = client
+        .get(format!("{}/{}", MESSAGES_URL, gmail_id))
+        .query(&[("format", "raw")])
+        .bearer_auth(access_token)
+        .send()
+        .await?;
+    let json = gmail_json(response).await?;
+    let raw = json["raw"]
+        .as_str()
+        .ok_or_else(|| Error::Other("Gmail API returned no raw message".to_string()))?;
+    let source = URL_SAFE
+        .decode(raw)
+        .or_else(|_| URL_SAFE_NO_PAD.decode(raw))
+        .map_err(|e| Error::InvalidEmailFormat(format!("Invalid raw message: {}", e)))?;
+    let label_ids = json["labelIds"]
+        .as_array()
+        .map(|ids| {
+            ids.iter()
+                .filter_map(|id| id.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(GmailMessage {
+        source,
+        label_ids,
+        gmail_message_id: api_id(&json["id"]),
+        gmail_thread_id: api_id(&json["threadId"]),
+    })
+}
+
+/// A Gmail API message or thread id: the X-GM-MSGID or X-GM-THRID in hex
+fn api_id(value: &serde_json::Value) -> u64 {
+    value
+        .as_str()
+        .and_then(|id| u64::from_str_radix(id, 16).ok())
+        .unwrap_or(0)
+}
+
+/// Parse a message fetched from the Gmail API the way sync parses mail
+///
+/// API messages have no folder UID (it stays 0); the Gmail IDs that thread
+/// and bounce linking use come from the API response.
+pub(super) async fn parse_gmail_message(
+    config: &Config,
+    oauth: Arc<OAuthManager>,
+    account_id: &str,
+    message: &GmailMessage,
+) -> Result<Email> {
+    let rate_limiter = Arc::new(GlobalRateLimiter::new(config.sync.rate_limit_per_second));
+    let own_addresses: Vec<String> = config.compose.identities.keys().cloned().collect();
+    let (folder, flags) = message.folder_and_flags();
+    let mut email = ImapClient::new(account_id, oauth, rate_limiter)
+        .await?
+        .with_folder(folder)
+        .with_own_addresses(own_addresses)
+        .parse_message(0, flags, &message.source)?;
+    email.gmail_message_id = message.gmail_message_id;
+    email.gmail_thread_id = message.gmail_thread_id;
+    Ok(email)
+}
+
+/// Fill in a headers-only email's body, snippet and attachments from Gmail
+///
+/// Only the given email is updated; nothing is written to the local index.
+pub async fn fetch_gmail_body(oauth: &OAuthManager, email: &mut Email) -> Result<()> {
+    let message = fetch_gmail_message(oauth, &email.account_id, &email.message_id)
+        .await?
+        .ok_or_else(|| {
+            Error::EmailNotFound(format!(
+                "{} not found in Gmail for {}",
+                email.message_id, email.account_id
+            ))
+        })?;
+    let parsed = MessageParser::default()
+        .parse(&message.source)
+        .ok_or_else(|| Error::InvalidEmailFormat("Failed to parse email".to_string()))?;
+    MessageBody::from_message(&parsed).apply(email);
+    Ok(())
+}
+
+/// Fetch an email that isn't in the local index straight from Gmail
+///
+/// Each account is asked in turn for the Message-ID; the first copy found is
+/// parsed the way sync parses it. None when no account has the message.
+/// Nothing is stored.
+pub async fn fetch_remote_email(
+    config: &Config,
+    oauth: Arc<OAuthManager>,
+    account_ids: &[String],
+    message_id: &str,
+) -> Result<Option<Email>> {
+    for account_id in account_ids {
+        if let Some(message) = fetch_gmail_message(&oauth, account_id, message_id).await? {
+            let email = parse_gmail_message(config, oauth.clone(), account_id, &message).await?;
+            return Ok(Some(email));
+        }
+    }
+    Ok(None)
+}
+
+pub(super) async fn gmail_json(response: reqwest::Response) -> Result<serde_json::Value> {
+    if !response.status().is_success() {
+        let status = response.status();
+        metrics::record_api_status(status);
+        let body = response.text().await.unwrap_or_default();
+        return Err(Error::Other(format!(
+            "Gmail API error {}: {}",
+            status, body
+        )));
+    }
+    Ok(response.json().await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headers_only_source_parses_with_partial_text() {
+        let header = b"From: Ann <ann@example.com>\r\nSubject: Quarterly numbers\r\n\
+Content-Type: text/plain; charset=utf-8\r\n\r\n";
+        let text = "Here are the numbers for Q3. ".repeat(20);
+        let truncated = &text.as_bytes()[..100];
+
+        let source = headers_only_source(header, Some(truncated));
+        let parsed = MessageParser::default().parse(&source).unwrap();
+        assert_eq!(parsed.subject(), Some("Quarterly numbers"));
+        let body = MessageBody::from_message(&parsed);
+        assert!(body.snippet.starts_with("Here are the numbers for Q3."));
+        assert!(body.attachments.is_empty());
+    }
+
+    #[test]
+    fn headers_only_source_adds_missing_blank_line() {
+        let source = headers_only_source(b"Subject: Hi\r\n", None);
+        assert_eq!(source, b"Subject: Hi\r\n\r\n");
+        let parsed = MessageParser::default().parse(&source).unwrap();
+        assert_eq!(parsed.subject(), Some("Hi"));
+
+        let source = headers_only_source(b"Subject: Hi\n\n", Some(b"Body"));
+        assert_eq!(source, b"Subject: Hi\n\nBody");
+    }
+
+    #[test]
+    fn gmail_api_ids_are_hex_x_gm_ids() {
+        let json = serde_json::json!({ "id": "18f8f9a87afb4f56", "threadId": "18f8f9a87afb4f00" });
+        assert_eq!(api_id(&json["id"]), 1_799_462_553_147_625_302);
+        assert_eq!(api_id(&json["threadId"]), 0x18f8f9a87afb4f00);
+        assert_eq!(api_id(&json["missing"]), 0);
+        assert_eq!(api_id(&serde_json::json!("not-hex")), 0);
+    }
+
+    #[test]
+    fn gmail_labels_map_to_folder_and_flags() {
+        let message = |labels: &[&str]| GmailMessage {
+            source: vec![],
+            label_ids: labels.iter().map(|l| l.to_string()).collect(),
+            gmail_message_id: 0,
+            gmail_thread_id: 0,
+        };
+
+        let (folder, flags) = message(&["INBOX", "UNREAD", "CATEGORY_UPDATES"]).folder_and_flags();
+        assert_eq!(folder, "INBOX");
+        assert!(flags.is_empty());
+
+        let (folder, flags) = message(&["SENT", "STARRED"]).folder_and_flags();
+        assert_eq!(folder, SENT_FOLDER);
+        assert_eq!(flags, vec!["\\Seen", "\\Flagged"]);
+
+        // A message sent to yourself is in both; sync finds it in INBOX
+        let (folder, _) = message(&["SENT", "INBOX"]).folder_and_flags();
+        assert_eq!(folder, "INBOX");
+
+        // Archived mail has neither
+        let (folder, flags) = message(&[]).folder_and_flags();
+        assert_eq!(folder, "INBOX");
+        assert_eq!(flags, vec!["\\Seen"]);
+
+        // Spam and Trash keep their folders so search can leave them out
+        let (folder, _) = message(&["SPAM", "UNREAD"]).folder_and_flags();
+        assert_eq!(folder, "[Gmail]/Spam");
+        let (folder, _) = message(&["TRASH", "SENT"]).folder_and_flags();
+        assert_eq!(folder, "[Gmail]/Trash");
+    }
+
+    #[test]
+    fn message_body_collects_attachments() {
+        let source = b"Subject: Report\r\n\
+Content-Type: multipart/mixed; boundary=\"b\"\r\n\r\n\
+--b\r\nContent-Type: text/plain\r\n\r\nSee attached.\r\n\
+--b\r\nContent-Type: application/pdf\r\n\
+Content-Disposition: attachment; filename=\"report.pdf\"\r\n\r\n%PDF-1.4\r\n\
+--b--\r\n";
+        let parsed = MessageParser::default().parse(&source[..]).unwrap();
+        let body = MessageBody::from_message(&parsed);
+        assert_eq!(body.body_plain.trim(), "See attached.");
+        assert_eq!(body.attachments.len(), 1);
+        assert_eq!(body.attachments[0].filename, "report.pdf");
+    }
+}
@@ -7,9 +7,10 @@ use reqwest::Client;
 use tracing::{debug, error, info};
 
 use crate::error::{Error, Result};
+use crate::metrics;
 use crate::models::{
-    Attendee, AttendeeStatus, CalendarEvent, EventStatus, EventTime, Reminder, ReminderMethod,
-    Transparency,
+    Attendee, AttendeeStatus, CalendarEvent, Conference, EventStatus, EventTime, Reminder,
+    ReminderMethod, Transparency, Visibility,
 };
 use crate::oauth::OAuthManager;
 
@@ -79,6 +80,7 @@ impl CalDavClient {
 
             if !response.status().is_success() {
                 let status = response.status();
+                metrics::record_api_status(status);
                 let body = response.text().await.unwrap_or_default();
                 return Err(Error::CalDav(format!(
                     "Failed to fetch events: {} - {}",
@@ -254,7 +256,13 @@ impl CalDavClient {
             attendees,
             status,
             transparency,
+            visibility: json["visibility"]
+                .as_str()
+                .and_then(Visibility::parse)
+                .unwrap_or_default(),
+            color_id: json["colorId"].as_str().map(|s| s.to_string()),
             reminders,
+            conference: Conference::from_google(json),
             embedding: None,
             calendar_id: "primary".to_string(),
             synced_at: Utc::now(),
@@ -265,28 +273,36 @@ impl CalDavClient {
 
     /// Create a new calendar event
     pub async fn create_event(&self, event: &CalendarEvent) -> Result<String> {
+        let body = self.event_to_google_json(event)?;
+        self.insert_event_json(&event.calendar_id, &body).await
+    }
+
+    /// Create an event from a Google Calendar API event body
+    pub async fn insert_event_json(
+        &self,
+        calendar_id: &str,
+        body: &serde_json::Value,
+    ) -> Result<String> {
         self.rate_limiter.wait().await;
 
         let access_token = self.oauth.get_valid_token(&self.account_id).await?;
 
-        let calendar_id = &event.calendar_id;
         let url = format!(
             "https://www.googleapis.com/calendar/v3/calendars/{}/events",
-            calendar_id
+            urlencoding::encode(calendar_id)
         );
 
-        let body = self.event_to_google_json(event)?;
-
         let response = self
             .client
             .post(&url)
             .bearer_auth(&access_token)
-            .json(&body)
+            .json(body)
             .send()
             .await?;
 
         if !response.status().is_success() {
             let status = response.status();
+            metrics::record_api_status(status);
             let body = response.text().await.unwrap_or_default();
             return Err(Error::CalDav(format!(
                 "Failed to create event: {} - {}",
@@ -324,6 +340,7 @@ impl CalDavClient {
 
         if !response.status().is_success() {
             let status = response.status();
+            metrics::record_api_status(status);
             let body = response.text().await.unwrap_or_default();
             return Err(Error::CalDav(format!(
                 "Failed to update event: {} - {}",
@@ -358,6 +375,7 @@ impl CalDavClient {
 
         if !response.status().is_success() && response.status() != reqwest::StatusCode::NOT_FOUND {
             let status = response.status();
+            metrics::record_api_status(status);
             let body = response.text().await.unwrap_or_default();
             return Err(Error::CalDav(format!(
                 "Failed to delete event: {} - {}",
@@ -405,6 +423,20 @@ impl CalDavClient {
             json["location"] = serde_json::Value::String(loc.clone());
         }
 
+        if let Some(color_id) = &event.color_id {
+            json["colorId"] = serde_json::Value::String(color_id.clone());
+        }
+
+        json["visibility"] = serde_json::json!(event.visibility.as_str());
+        json["transparency"] = serde_json::json!(event.transparency.as_str());
+
+        if !event.reminders.is_empty() {
+            json["reminders"] = serde_json::json!({
+                "useDefault": false,
+                "overrides": event.reminders
+            });
+        }
+
         if !event.attendees.is_empty() {
             json["attendees"] = serde_json::json!(event
                 .attendees
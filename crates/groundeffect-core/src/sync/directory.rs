@@ -0,0 +1,417 @@
+//! Google Workspace directory lookups via the People API, with an on-disk
+//! cache
+//!
+//! Used to turn attendee names like "Priya in Design" into email addresses,
+//! and (with `[directory] attendee_profiles`) to add titles and photos to
+//! event attendees.
+//! Needs the optional directory scope; accounts that didn't grant it (or
+//! consumer Gmail accounts) get an error explaining how to fix it instead.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use reqwest::{Client, StatusCode};
+use tracing::{debug, warn};
+
+use crate::config::Config;
+use crate::error::{Error, Result};
+use crate::models::{DirectoryCache, DirectoryPerson};
+use crate::oauth::{OAuthManager, DIRECTORY_SCOPE};
+
+/// People API directory search endpoint
+const DIRECTORY_SEARCH_URL: &str = "https://people.googleapis.com/v1/people:searchDirectoryPeople";
+
+/// Maximum results requested per query
+const DIRECTORY_PAGE_SIZE: usize = 25;
+
+/// Cached directory results older than this are refetched (1 day)
+pub const DIRECTORY_CACHE_TTL_SECS: u64 = 86400;
+
+/// An attendee name with an optional team qualifier ("Priya in Design")
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttendeeQuery {
+    pub name: String,
+    pub team: Option<String>,
+}
+
+impl AttendeeQuery {
+    /// Split "<name> in <team>" on the last " in "
+    pub fn parse(value: &str) -> Self {
+        let value = value.trim();
+        // ASCII lowercasing keeps byte offsets valid for slicing `value`
+        let lower = value.to_ascii_lowercase();
+        match lower.rfind(" in ") {
+            Some(idx) if idx > 0 && !value[idx + 4..].trim().is_empty() => Self {
+                name: value[..idx].trim().to_string(),
+                team: Some(value[idx + 4..].trim().to_string()),
+            },
+            _ => Self {
+                name: value.to_string(),
+                team: None,
+            },
+        }
+    }
+}
+
+/// Search the Workspace directory for people matching `query`
+pub async fn search_directory(
+    oauth: &OAuthManager,
+    account_id: &str,
+    query: &str,
+) -> Result<Vec<DirectoryPerson>> {
+    let access_token = oauth.get_valid_token(account_id).await?;
+    let page_size = DIRECTORY_PAGE_SIZE.to_string();
+
+    let response = Client::new()
+        .get(DIRECTORY_SEARCH_URL)
+        .query(&[
+            ("query", query),
+            ("readMask", "names,emailAddresses,organizations,photos"),
+            ("sources", "DIRECTORY_SOURCE_TYPE_DOMAIN_PROFILE"),
+            ("pageSize", page_size.as_str()),
+        ])
+        .bearer_auth(&access_token)
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        if status == StatusCode::FORBIDDEN {
+            return Err(Error::InvalidRequest(format!(
+                "Directory lookup isn't available for {} (not a Google Workspace account, \
+                 or directory sharing is disabled): {}",
+                account_id, body
+            )));
+        }
+        return Err(Error::Other(format!(
+            "People API error {}: {}",
+            status, body
+        )));
+    }
+
+    let json: serde_json::Value = response.json().await?;
+    let people: Vec<DirectoryPerson> = json["people"]
+        .as_array()
+        .map(|people| people.iter().filter_map(parse_person).collect())
+        .unwrap_or_default();
+    debug!(
+        "Directory search '{}' for {} returned {} people",
+        query,
+        account_id,
+        people.len()
+    );
+    Ok(people)
+}
+
+/// Search the directory, using the on-disk cache when it is fresh
+pub async fn lookup_directory(
+    oauth: &OAuthManager,
+    config: &Config,
+    account_id: &str,
+    query: &str,
+) -> Result<Vec<DirectoryPerson>> {
+    let cache_path = config.directory_cache_file(account_id);
+    let mut cache = load_directory_cache(&cache_path).unwrap_or_default();
+    if let Some(people) = cache.get(query, DIRECTORY_CACHE_TTL_SECS) {
+        return Ok(people.to_vec());
+    }
+
+    let people = search_directory(oauth, account_id, query).await?;
+    cache.insert(query, people.clone());
+    if let Err(e) = save_directory_cache(&cache_path, &cache) {
+        warn!("Failed to write directory cache for {}: {}", account_id, e);
+    }
+    Ok(people)
+}
+
+/// Resolve attendees to email addresses.
+///
+/// Entries containing '@' are kept as-is; anything else is looked up in the
+/// account's Workspace directory and must match exactly one person.
+pub async fn resolve_attendees(
+    oauth: &OAuthManager,
+    config: &Config,
+    account_id: &str,
+    attendees: &[String],
+) -> Result<Vec<String>> {
+    let mut resolved = Vec::with_capacity(attendees.len());
+    let mut scope_checked = false;
+
+    for attendee in attendees {
+        let attendee = attendee.trim();
+        if attendee.contains('@') {
+            resolved.push(attendee.to_string());
+            continue;
+        }
+
+        if !scope_checked {
+            check_directory_scope(oauth, account_id, attendee).await?;
+            scope_checked = true;
+        }
+
+        let query = AttendeeQuery::parse(attendee);
+        let people = lookup_directory(oauth, config, account_id, &query.name).await?;
+        let person = pick_person(&query, people).map_err(|candidates| {
+            if candidates.is_empty() {
+                Error::InvalidRequest(format!(
+                    "No one in the directory matches attendee '{}'",
+                    attendee
+                ))
+            } else {
+                Error::InvalidRequest(format!(
+                    "Attendee '{}' is ambiguous, use an email address or add a team \
+                     (\"<name> in <team>\"): {}",
+                    attendee,
+                    candidates
+                        .iter()
+                        .map(DirectoryPerson::describe)
+                        .collect::<Vec<_>>()
+                        .join("; ")
+                ))
+            }
+        })?;
+        debug!("Resolved attendee '{}' to {}", attendee, person.email);
+        resolved.push(person.email);
+    }
+
+    Ok(resolved)
+}
+
+/// Directory profiles for attendees, keyed by lowercased email address.
+///
+/// Read-through: profiles come from the on-disk cache while younger than
+/// `[directory] profile_ttl_secs` and are fetched otherwise. Best effort:
+/// people outside the directory are left out, and an account without
+/// directory access gets no profiles rather than an error.
+pub async fn attendee_profiles(
+    oauth: &OAuthManager,
+    config: &Config,
+    account_id: &str,
+    emails: &[String],
+) -> HashMap<String, DirectoryPerson> {
+    let mut profiles = HashMap::new();
+    if emails.is_empty()
+        || !has_directory_scope(oauth, account_id)
+            .await
+            .unwrap_or(false)
+    {
+        return profiles;
+    }
+
+    let cache_path = config.directory_cache_file(account_id);
+    let mut cache = load_directory_cache(&cache_path).unwrap_or_default();
+    let ttl = config.directory.profile_ttl_secs;
+    let mut fetched = false;
+
+    for email in emails {
+        let email = email.trim().to_lowercase();
+        if email.is_empty() || profiles.contains_key(&email) {
+            continue;
+        }
+        let people = match cache.get(&email, ttl) {
+            Some(people) => people.to_vec(),
+            None => match search_directory(oauth, account_id, &email).await {
+                Ok(people) => {
+                    // Cached even when empty so outside guests aren't looked up every read
+                    cache.insert(&email, people.clone());
+                    fetched = true;
+                    people
+                }
+                Err(e) => {
+                    // The rest would most likely fail the same way
+                    debug!("Stopping profile lookups for {}: {}", account_id, e);
+                    break;
+                }
+            },
+        };
+        if let Some(person) = people
+            .into_iter()
+            .find(|p| p.email.eq_ignore_ascii_case(&email))
+        {
+            profiles.insert(email, person);
+        }
+    }
+
+    if fetched {
+        if let Err(e) = save_directory_cache(&cache_path, &cache) {
+            warn!("Failed to write directory cache for {}: {}", account_id, e);
+        }
+    }
+    profiles
+}
+
+/// Whether the account's tokens include the directory scope
+async fn has_directory_scope(oauth: &OAuthManager, account_id: &str) -> Result<bool> {
+    Ok(oauth
+        .token_provider()
+        .get_tokens(account_id)
+        .await?
+        .is_some_and(|tokens| tokens.scopes.iter().any(|s| s == DIRECTORY_SCOPE)))
+}
+
+/// Fail with instructions if the account's tokens lack the directory scope
+async fn check_directory_scope(
+    oauth: &OAuthManager,
+    account_id: &str,
+    attendee: &str,
+) -> Result<()> {
+    if has_directory_scope(oauth, account_id).await? {
+        return Ok(());
+    }
+    Err(Error::InvalidRequest(format!(
+        "Can't resolve attendee '{}': directory access isn't granted for {}. \
+         Use an email address, or run `groundeffect account reauth {}` and allow \
+         directory access (Google Workspace accounts only)",
+        attendee, account_id, account_id
+    )))
+}
+
+/// Choose the single person matching a query.
+///
+/// Narrows by team, then prefers exact name matches. Returns the remaining
+/// candidates (possibly none) if the match isn't unique.
+fn pick_person(
+    query: &AttendeeQuery,
+    mut people: Vec<DirectoryPerson>,
+) -> std::result::Result<DirectoryPerson, Vec<DirectoryPerson>> {
+    if let Some(team) = &query.team {
+        people.retain(|p| p.in_team(team));
+    }
+    if people.len() > 1 {
+        let exact: Vec<DirectoryPerson> = people
+            .iter()
+            .filter(|p| p.name.eq_ignore_ascii_case(&query.name))
+            .cloned()
+            .collect();
+        if exact.len() == 1 {
+            people = exact;
+        }
+    }
+    if people.len() == 1 {
+        Ok(people.remove(0))
+    } else {
+        Err(people)
+    }
+}
+
+fn load_directory_cache(path: &Path) -> Option<DirectoryCache> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+fn save_directory_cache(path: &Path, cache: &DirectoryCache) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(cache)?)?;
+    Ok(())
+}
+
+/// Parse a People API person resource (None without an email address)
+fn parse_person(json: &serde_json::Value) -> Option<DirectoryPerson> {
+    let emails = json["emailAddresses"].as_array()?;
+    let email = emails
+        .iter()
+        .find(|e| e["metadata"]["primary"].as_bool() == Some(true))
+        .or_else(|| emails.first())?["value"]
+        .as_str()?
+        .to_string();
+    let name = json["names"][0]["displayName"]
+        .as_str()
+        .unwrap_or(&email)
+        .to_string();
+    let organization = &json["organizations"][0];
+    let photo = &json["photos"][0];
+    let photo_url = if photo["default"].as_bool() == Some(true) {
+        None
+    } else {
+        photo["url"].as_str().map(String::from)
+    };
+
+    Some(DirectoryPerson {
+        name,
+        email,
+        department: organization["department"].as_str().map(String::from),
+        title: organization["title"].as_str().map(String::from),
+        photo_url,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn person(name: &str, email: &str, department: &str) -> DirectoryPerson {
+        DirectoryPerson {
+            name: name.to_string(),
+            email: email.to_string(),
+            department: Some(department.to_string()),
+            title: None,
+            photo_url: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_attendee_query() {
+        assert_eq!(
+            AttendeeQuery::parse("Priya in Design"),
+            AttendeeQuery {
+                name: "Priya".to_string(),
+                team: Some("Design".to_string()),
+            }
+        );
+        assert_eq!(AttendeeQuery::parse("Priya Patel").team, None);
+        assert_eq!(AttendeeQuery::parse("Ines In  Sales").name, "Ines");
+    }
+
+    #[test]
+    fn test_pick_person_narrows_by_team_and_exact_name() {
+        let people = vec![
+            person("Priya Patel", "ppatel@corp.com", "Product Design"),
+            person("Priya Shah", "pshah@corp.com", "Sales"),
+        ];
+
+        let query = AttendeeQuery::parse("Priya in Design");
+        assert_eq!(
+            pick_person(&query, people.clone()).unwrap().email,
+            "ppatel@corp.com"
+        );
+
+        let query = AttendeeQuery::parse("Priya");
+        assert_eq!(pick_person(&query, people.clone()).unwrap_err().len(), 2);
+
+        let query = AttendeeQuery::parse("priya shah");
+        assert_eq!(pick_person(&query, people).unwrap().email, "pshah@corp.com");
+    }
+
+    #[test]
+    fn test_parse_person_prefers_primary_email() {
+        let parsed = parse_person(&serde_json::json!({
+            "names": [{"displayName": "Priya Patel"}],
+            "emailAddresses": [
+                {"value": "priya.alias@corp.com"},
+                {"value": "ppatel@corp.com", "metadata": {"primary": true}}
+            ],
+            "organizations": [{"department": "Design", "title": "Product Designer"}],
+            "photos": [{"url": "https://lh3.googleusercontent.com/a/priya"}]
+        }))
+        .unwrap();
+        assert_eq!(parsed.email, "ppatel@corp.com");
+        assert_eq!(parsed.department.as_deref(), Some("Design"));
+        assert_eq!(
+            parsed.photo_url.as_deref(),
+            Some("https://lh3.googleusercontent.com/a/priya")
+        );
+
+        let placeholder = parse_person(&serde_json::json!({
+            "emailAddresses": [{"value": "guest@corp.com"}],
+            "photos": [{"url": "https://lh3.googleusercontent.com/a/default", "default": true}]
+        }))
+        .unwrap();
+        assert_eq!(placeholder.photo_url, None);
+        assert!(
+            parse_person(&serde_json::json!({"names": [{"displayName": "No Email"}]})).is_none()
+        );
+    }
+}
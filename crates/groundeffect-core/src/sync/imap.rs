@@ -1,5 +1,7 @@
 //! IMAP client for Gmail with XOAUTH2 authentication
 
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use async_imap::{Authenticator, Client as ImapClientAsync};
@@ -7,22 +9,43 @@ use async_native_tls::TlsConnector;
 use chrono::{DateTime, Utc};
 use futures::StreamExt;
 use mail_parser::MimeHeaders;
+use parking_lot::Mutex;
 use tokio::net::TcpStream;
 use tokio::sync::mpsc;
 use tokio_util::compat::TokioAsyncReadCompatExt;
 use tracing::{debug, error, info, warn};
 
+use crate::classify::{classify_heuristic, CategorySignals};
 use crate::error::{Error, Result};
-use crate::models::{Address, Attachment, Email};
+use crate::models::{Address, Email};
 use crate::oauth::OAuthManager;
+use crate::raw;
+use crate::security::parse_email_auth;
 
-use super::{GlobalRateLimiter, SyncEvent};
+use super::{
+    headers_only_source, GlobalRateLimiter, MessageBody, SyncActivity, SyncEvent,
+    HEADERS_ONLY_TEXT_BYTES,
+};
+
+/// Gmail's sent mail folder
+pub const SENT_FOLDER: &str = "[Gmail]/Sent Mail";
+
+/// How long one IDLE wait lasts before it's renewed (Gmail drops IDLE
+/// after 30 minutes)
+pub const IDLE_TIMEOUT_SECS: u64 = 29 * 60;
 
 /// Retry configuration
 const MAX_RETRIES: u32 = 3;
 const INITIAL_RETRY_DELAY_MS: u64 = 1000;
 const MAX_RETRY_DELAY_MS: u64 = 30000;
 
+/// FETCH items for a full sync: the whole message
+const FETCH_FULL: &str = "(UID FLAGS ENVELOPE BODY.PEEK[] X-GM-MSGID X-GM-THRID X-GM-LABELS)";
+
+/// FETCH items for Gmail's message and thread IDs, which async-imap doesn't
+/// expose on its parsed responses (see [`parse_gmail_ids`])
+const FETCH_GMAIL_IDS: &str = "(UID X-GM-MSGID X-GM-THRID)";
+
 /// XOAUTH2 authenticator for IMAP
 struct XOAuth2Auth {
     auth_string: String,
@@ -55,6 +78,19 @@ pub struct ImapClient {
     account_id: String,
     oauth: Arc<OAuthManager>,
     rate_limiter: Arc<GlobalRateLimiter>,
+    /// Gmail search query restricting which messages are synced (X-GM-RAW)
+    label_filter: Option<String>,
+    /// Folder fetched from (INBOX unless set with [`Self::with_folder`])
+    folder: String,
+    /// Addresses besides the account's own that count as the owner's
+    /// (send-as identities)
+    own_addresses: Vec<String>,
+    /// Keep a compressed copy of each fetched message's source here
+    raw_dir: Option<PathBuf>,
+    /// Fetch only headers and the start of the body (see [`super::bodies`])
+    headers_only: bool,
+    /// Attachments larger than this aren't downloaded
+    attachment_max_bytes: u64,
 }
 
 impl ImapClient {
@@ -68,9 +104,104 @@ impl ImapClient {
             account_id: account_id.to_string(),
             oauth,
             rate_limiter,
+            label_filter: None,
+            folder: "INBOX".to_string(),
+            own_addresses: Vec::new(),
+            raw_dir: None,
+            headers_only: false,
+            attachment_max_bytes: 50 * 1024 * 1024,
         })
     }
 
+    /// Restrict searches to messages matching a Gmail label query
+    pub fn with_label_filter(mut self, query: Option<String>) -> Self {
+        self.label_filter = query;
+        self
+    }
+
+    /// Fetch from another folder (e.g. [`SENT_FOLDER`])
+    pub fn with_folder(mut self, folder: &str) -> Self {
+        self.folder = folder.to_string();
+        self
+    }
+
+    /// Also treat mail from these addresses as sent by the account owner
+    pub fn with_own_addresses(mut self, addresses: Vec<String>) -> Self {
+        self.own_addresses = addresses;
+        self
+    }
+
+    /// Skip attachments larger than `mb` megabytes when downloading (50
+    /// unless set)
+    pub fn with_attachment_max_mb(mut self, mb: u64) -> Self {
+        self.attachment_max_bytes = mb.saturating_mul(1024 * 1024);
+        self
+    }
+
+    /// Store the raw source of fetched messages under this directory
+    /// (see [`crate::raw`])
+    pub fn with_raw_store(mut self, dir: Option<PathBuf>) -> Self {
+        self.raw_dir = dir;
+        self
+    }
+
+    /// Fetch only headers and a snippet of each message, without bodies or
+    /// attachments
+    pub fn with_headers_only(mut self, headers_only: bool) -> Self {
+        self.headers_only = headers_only;
+        self
+    }
+
+    /// FETCH items for syncing messages
+    fn fetch_items(&self) -> String {
+        if self.headers_only {
+            format!(
+                "(UID FLAGS ENVELOPE BODY.PEEK[HEADER] BODY.PEEK[TEXT]<0.{}> X-GM-MSGID X-GM-THRID X-GM-LABELS)",
+                HEADERS_ONLY_TEXT_BYTES
+            )
+        } else {
+            FETCH_FULL.to_string()
+        }
+    }
+
+    /// Append the label filter (if any) to an IMAP SEARCH query
+    fn filtered_search(&self, query: String) -> String {
+        match &self.label_filter {
+            Some(filter) => format!(
+                "{} X-GM-RAW \"{}\"",
+                query,
+                filter.replace('\\', "\\\\").replace('"', "\\\"")
+            ),
+            None => query,
+        }
+    }
+
+    /// Gmail's message and thread IDs for the UIDs in `uid_range`
+    ///
+    /// UIDs are only unique within a folder, so emails are keyed by these
+    /// instead. If they can't be fetched the emails keep 0 (unknown) and are
+    /// left out of threading.
+    async fn fetch_gmail_ids(
+        &self,
+        session: &mut ImapSession,
+        uid_range: &str,
+    ) -> HashMap<u32, GmailIds> {
+        self.rate_limiter.wait().await;
+        match session
+            .run_command_and_read_response(format!("UID FETCH {} {}", uid_range, FETCH_GMAIL_IDS))
+            .await
+        {
+            Ok(response) => parse_gmail_ids(&String::from_utf8_lossy(&response)),
+            Err(e) => {
+                warn!(
+                    "Failed to fetch Gmail IDs in {} for {}: {:?}",
+                    self.folder, self.account_id, e
+                );
+                HashMap::new()
+            }
+        }
+    }
+
     /// Connect to Gmail IMAP with retry logic
     async fn connect_with_retry(&self) -> Result<ImapSession> {
         let mut last_error = None;
@@ -172,15 +303,15 @@ impl ImapClient {
         Ok(session)
     }
 
-    /// Count total emails in INBOX (for progress estimation)
+    /// Count total emails in the folder (for progress estimation)
     pub async fn count_emails(&self) -> Result<u64> {
         let mut session = self.connect().await?;
 
-        // Select INBOX and get message count
+        // Select the folder and get message count
         let mailbox = session
-            .select("INBOX")
+            .select(&self.folder)
             .await
-            .map_err(|e| Error::Imap(format!("Failed to select INBOX: {:?}", e)))?;
+            .map_err(|e| Error::Imap(format!("Failed to select {}: {:?}", self.folder, e)))?;
 
         let count = mailbox.exists as u64;
         session.logout().await.ok();
@@ -192,15 +323,15 @@ impl ImapClient {
     pub async fn count_emails_since(&self, since: DateTime<Utc>) -> Result<u64> {
         let mut session = self.connect().await?;
 
-        // Select INBOX
+        // Select the folder
         session
-            .select("INBOX")
+            .select(&self.folder)
             .await
-            .map_err(|e| Error::Imap(format!("Failed to select INBOX: {:?}", e)))?;
+            .map_err(|e| Error::Imap(format!("Failed to select {}: {:?}", self.folder, e)))?;
 
         // Search for emails since the target date
         let since_str = since.format("%d-%b-%Y").to_string();
-        let search_query = format!("SINCE {}", since_str);
+        let search_query = self.filtered_search(format!("SINCE {}", since_str));
 
         let uids = session
             .uid_search(&search_query)
@@ -234,25 +365,28 @@ impl ImapClient {
         // Connect with retry
         let mut session = self.connect_with_retry().await?;
 
-        // Select INBOX
-        if let Err(e) = session.select("INBOX").await {
-            warn!("First select INBOX failed, reconnecting: {:?}", e);
+        // Select the folder
+        if let Err(e) = session.select(&self.folder).await {
+            warn!("First select {} failed, reconnecting: {:?}", self.folder, e);
             session = self.connect_with_retry().await?;
-            session.select("INBOX").await.map_err(|e| {
-                Error::Imap(format!("Failed to select INBOX after reconnect: {:?}", e))
+            session.select(&self.folder).await.map_err(|e| {
+                Error::Imap(format!(
+                    "Failed to select {} after reconnect: {:?}",
+                    self.folder, e
+                ))
             })?;
         }
 
         // Search for emails in date range
         // Add 2-day buffer to before date to catch emails at boundaries and timezone edge cases
         let since_str = since.format("%d-%b-%Y").to_string();
-        let search_query = if let Some(before_date) = before {
+        let search_query = self.filtered_search(if let Some(before_date) = before {
             let before_with_buffer = before_date + chrono::Duration::days(2);
             let before_str = before_with_buffer.format("%d-%b-%Y").to_string();
             format!("SINCE {} BEFORE {}", since_str, before_str)
         } else {
             format!("SINCE {}", since_str)
-        };
+        });
 
         self.rate_limiter.wait().await;
         let uids = session
@@ -304,13 +438,7 @@ impl ImapClient {
             // to avoid borrow checker issues with the session
             let fetches: Vec<_> = {
                 use futures::StreamExt;
-                match session
-                    .uid_fetch(
-                        &uid_range,
-                        "(UID FLAGS ENVELOPE BODY.PEEK[] X-GM-MSGID X-GM-THRID X-GM-LABELS)",
-                    )
-                    .await
-                {
+                match session.uid_fetch(&uid_range, self.fetch_items()).await {
                     Ok(messages) => messages.collect().await,
                     Err(e) => {
                         warn!(
@@ -333,25 +461,19 @@ impl ImapClient {
                     }
                 };
 
-                // Re-select INBOX
-                if let Err(select_err) = session.select("INBOX").await {
-                    error!("Failed to re-select INBOX: {:?}", select_err);
+                // Re-select the folder
+                if let Err(select_err) = session.select(&self.folder).await {
+                    error!("Failed to re-select {}: {:?}", self.folder, select_err);
                     return Err(Error::Imap(format!(
-                        "Failed to re-select INBOX: {:?}",
-                        select_err
+                        "Failed to re-select {}: {:?}",
+                        self.folder, select_err
                     )));
                 }
 
                 // Retry this batch
                 self.rate_limiter.wait().await;
                 use futures::StreamExt;
-                match session
-                    .uid_fetch(
-                        &uid_range,
-                        "(UID FLAGS ENVELOPE BODY.PEEK[] X-GM-MSGID X-GM-THRID X-GM-LABELS)",
-                    )
-                    .await
-                {
+                match session.uid_fetch(&uid_range, self.fetch_items()).await {
                     Ok(messages) => messages.collect().await,
                     Err(retry_err) => {
                         error!("Fetch still failed after reconnect: {:?}", retry_err);
@@ -397,6 +519,8 @@ impl ImapClient {
                     parse_errors, batch_index
                 );
             }
+            let ids = self.fetch_gmail_ids(&mut session, &uid_range).await;
+            apply_gmail_ids(&mut emails, &ids);
 
             // Sort by date descending
             emails.sort_by(|a, b| b.date.cmp(&a.date));
@@ -436,15 +560,15 @@ impl ImapClient {
     ) -> Result<Vec<Email>> {
         let mut session = self.connect().await?;
 
-        // Select INBOX
+        // Select the folder
         session
-            .select("INBOX")
+            .select(&self.folder)
             .await
-            .map_err(|e| Error::Imap(format!("Failed to select INBOX: {:?}", e)))?;
+            .map_err(|e| Error::Imap(format!("Failed to select {}: {:?}", self.folder, e)))?;
 
         // Search for emails since the given date
         let since_str = since.format("%d-%b-%Y").to_string();
-        let search_query = format!("SINCE {}", since_str);
+        let search_query = self.filtered_search(format!("SINCE {}", since_str));
 
         self.rate_limiter.wait().await;
         let uids = session
@@ -480,16 +604,14 @@ impl ImapClient {
 
         self.rate_limiter.wait().await;
         let messages = session
-            .uid_fetch(
-                &uid_range,
-                "(UID FLAGS ENVELOPE BODY.PEEK[] X-GM-MSGID X-GM-THRID X-GM-LABELS)",
-            )
+            .uid_fetch(&uid_range, self.fetch_items())
             .await
             .map_err(|e| Error::Imap(format!("Fetch failed: {:?}", e)))?;
 
         // Collect all messages into a vector to release the borrow on session
         use futures::StreamExt;
         let fetches: Vec<_> = messages.collect().await;
+        let ids = self.fetch_gmail_ids(&mut session, &uid_range).await;
 
         // Now we can logout
         session.logout().await.ok();
@@ -509,6 +631,8 @@ impl ImapClient {
             }
         }
 
+        apply_gmail_ids(&mut emails, &ids);
+
         // Sort by date descending to ensure newest first
         emails.sort_by(|a, b| b.date.cmp(&a.date));
 
@@ -537,11 +661,34 @@ impl ImapClient {
             None => return Ok(None),
         };
 
-        let body = match fetch.body() {
-            Some(b) => b,
-            None => return Ok(None),
+        let partial;
+        let body = match (fetch.body(), fetch.header()) {
+            (Some(b), _) => b,
+            (None, Some(header)) if self.headers_only => {
+                partial = headers_only_source(header, fetch.text());
+                partial.as_slice()
+            }
+            _ => return Ok(None),
         };
 
+        let flags: Vec<String> = fetch.flags().map(|f| format!("{:?}", f)).collect();
+        let email = self.parse_message(uid, flags, body)?;
+
+        // A headers-only fetch isn't the whole message
+        if let Some(dir) = self.raw_dir.as_ref().filter(|_| !self.headers_only) {
+            if let Err(e) = raw::store(dir, &email, body) {
+                warn!("Failed to store raw source of {}: {}", email.id, e);
+            }
+        }
+
+        Ok(Some(email))
+    }
+
+    /// Parse a message's source into an Email the same way sync does
+    ///
+    /// Also used for messages fetched outside IMAP (see
+    /// [`super::fetch_remote_email`]), which have no UID and pass 0.
+    pub fn parse_message(&self, uid: u32, flags: Vec<String>, body: &[u8]) -> Result<Email> {
         // Parse email using mail-parser
         let parsed = mail_parser::MessageParser::default()
             .parse(body)
@@ -589,43 +736,22 @@ impl ImapClient {
 
         let subject = parsed.subject().unwrap_or("(No Subject)").to_string();
 
+        // Undecided emails are resolved by the embedding classifier during sync
+        let category = classify_heuristic(&CategorySignals::from_message(&parsed, &from.email));
+
         let date = parsed
             .date()
             .map(|d| DateTime::from_timestamp(d.to_timestamp(), 0).unwrap_or_else(Utc::now))
             .unwrap_or_else(Utc::now);
 
-        let parsed_body_plain = parsed
-            .body_text(0)
-            .map(|s| s.to_string())
-            .unwrap_or_default();
-
-        let body_html = parsed.body_html(0).map(|s| s.to_string());
-
-        let body_plain =
-            Email::body_for_indexing_and_display(&parsed_body_plain, body_html.as_deref());
-        let snippet = body_plain.chars().take(200).collect();
-
-        // Parse attachments
-        let attachments: Vec<Attachment> = parsed
-            .attachments()
-            .map(|att| {
-                Attachment::new(
-                    uuid::Uuid::new_v4().to_string(),
-                    att.attachment_name().unwrap_or("attachment"),
-                    att.content_type()
-                        .map(|ct| ct.ctype().to_string())
-                        .unwrap_or_else(|| "application/octet-stream".to_string()),
-                    att.len() as u64,
-                )
-            })
-            .collect();
-
-        // Parse flags
-        let flags: Vec<String> = fetch.flags().map(|f| format!("{:?}", f)).collect();
-
-        // Gmail extensions
-        let gmail_message_id = fetch.uid.unwrap_or(0) as u64; // TODO: Parse X-GM-MSGID
-        let gmail_thread_id = fetch.uid.unwrap_or(0) as u64; // TODO: Parse X-GM-THRID
+        let mut message_body = MessageBody::from_message(&parsed);
+        if self.headers_only {
+            // Only the start of the body was fetched; keep just the snippet
+            message_body = MessageBody {
+                snippet: message_body.snippet,
+                ..Default::default()
+            };
+        }
 
         let in_reply_to = parsed.in_reply_to().as_text().map(|s| s.to_string());
         let references: Vec<String> = parsed
@@ -637,35 +763,91 @@ impl ImapClient {
         // Use a stable ID based on account + message_id to prevent duplicates on re-sync
         let stable_id = format!("{}:{}", self.account_id, &message_id);
 
+        // Anything in the Sent folder was sent; elsewhere (e.g. a copy to
+        // yourself in INBOX) go by the sender
+        let is_sent = self.folder == SENT_FOLDER
+            || from.email.eq_ignore_ascii_case(&self.account_id)
+            || self
+                .own_addresses
+                .iter()
+                .any(|a| a.eq_ignore_ascii_case(&from.email));
+        let auth = if is_sent {
+            None
+        } else {
+            parse_email_auth(raw::header_section(body), &from)
+        };
+
         let email = Email {
             id: stable_id,
             account_id: self.account_id.clone(),
             account_alias: None,
             message_id,
-            gmail_message_id,
-            gmail_thread_id,
+            // Filled in from X-GM-MSGID / X-GM-THRID (see `apply_gmail_ids`)
+            gmail_message_id: 0,
+            gmail_thread_id: 0,
             uid,
             in_reply_to,
             references,
-            folder: "INBOX".to_string(),
+            folder: self.folder.clone(),
             labels: vec![],
+            is_sent,
             flags,
+            category,
+            activity_source: None,
+            tags: vec![],
             from,
             to,
             cc,
             bcc: vec![],
             subject,
             date,
-            body_plain,
-            body_html,
-            snippet,
-            attachments,
+            body_plain: message_body.body_plain,
+            body_html: message_body.body_html,
+            snippet: message_body.snippet,
+            attachments: message_body.attachments,
+            auth,
             embedding: None,
             synced_at: Utc::now(),
             raw_size: body.len() as u64,
+            headers_only: self.headers_only,
         };
 
-        Ok(Some(email))
+        Ok(email)
+    }
+
+    /// Fetch a message's full RFC 2822 source, headers included
+    ///
+    /// Uses BODY.PEEK so the message isn't marked as read.
+    pub async fn fetch_raw(&self, uid: u32) -> Result<Vec<u8>> {
+        debug!("Fetching raw source of UID {} for {}", uid, self.account_id);
+
+        let mut session = self.connect_with_retry().await?;
+
+        session
+            .select(&self.folder)
+            .await
+            .map_err(|e| Error::Imap(format!("Failed to select {}: {:?}", self.folder, e)))?;
+
+        self.rate_limiter.wait().await;
+        let fetch_result = session
+            .uid_fetch(uid.to_string(), "BODY.PEEK[]")
+            .await
+            .map_err(|e| Error::Imap(format!("Failed to fetch email UID {}: {:?}", uid, e)))?;
+
+        let fetches: Vec<_> = fetch_result.collect::<Vec<_>>().await;
+        let fetch = fetches
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::Imap(format!("Email UID {} not found in {}", uid, self.folder)))?
+            .map_err(|e| Error::Imap(format!("Fetch error: {:?}", e)))?;
+
+        let source = fetch
+            .body()
+            .ok_or_else(|| Error::Imap("No body in fetch".to_string()))?
+            .to_vec();
+
+        let _ = session.logout().await;
+        Ok(source)
     }
 
     /// Download a specific attachment from an email
@@ -682,11 +864,11 @@ impl ImapClient {
 
         let mut session = self.connect_with_retry().await?;
 
-        // Select INBOX (or we could make folder configurable)
+        // Select the folder the email was synced from
         session
-            .select("INBOX")
+            .select(&self.folder)
             .await
-            .map_err(|e| Error::Imap(format!("Failed to select INBOX: {:?}", e)))?;
+            .map_err(|e| Error::Imap(format!("Failed to select {}: {:?}", self.folder, e)))?;
 
         // Fetch the full email body
         let fetch_result = session
@@ -758,11 +940,11 @@ impl ImapClient {
 
         let mut session = self.connect_with_retry().await?;
 
-        // Select INBOX
+        // Select the folder
         session
-            .select("INBOX")
+            .select(&self.folder)
             .await
-            .map_err(|e| Error::Imap(format!("Failed to select INBOX: {:?}", e)))?;
+            .map_err(|e| Error::Imap(format!("Failed to select {}: {:?}", self.folder, e)))?;
 
         // Fetch the full email body
         let fetch_result = session
@@ -818,11 +1000,13 @@ impl ImapClient {
             let content = att.contents();
             let size = content.len() as u64;
 
-            // Skip large attachments (50MB limit)
-            if size > 50 * 1024 * 1024 {
+            // Skip large attachments
+            if size > self.attachment_max_bytes {
                 warn!(
-                    "Skipping attachment '{}' ({} bytes) - exceeds 50MB limit",
-                    filename, size
+                    "Skipping attachment '{}' ({} bytes) - exceeds {}MB limit",
+                    filename,
+                    size,
+                    self.attachment_max_bytes / (1024 * 1024)
                 );
                 continue;
             }
@@ -863,7 +1047,14 @@ impl ImapClient {
     }
 
     /// Start IMAP IDLE for real-time notifications
-    pub async fn start_idle(&self, event_tx: mpsc::Sender<SyncEvent>) -> Result<()> {
+    ///
+    /// Reports to `activity` each time IDLE starts or wakes, so the watchdog
+    /// can tell a wedged connection from a quiet inbox.
+    pub async fn start_idle(
+        &self,
+        event_tx: mpsc::Sender<SyncEvent>,
+        activity: Arc<Mutex<SyncActivity>>,
+    ) -> Result<()> {
         loop {
             let mut session = match self.connect().await {
                 Ok(s) => s,
@@ -889,12 +1080,15 @@ impl ImapClient {
                 error!("Failed to init IDLE: {:?}", e);
                 continue;
             }
+            activity.lock().idle_alive(&self.account_id, Utc::now());
 
             // Wait for IDLE response (timeout after 29 minutes - Gmail's limit is 30 min)
             // wait_with_timeout returns (Future, StopSource) - we await the future
             let (wait_future, _stop_source) =
-                idle_handle.wait_with_timeout(std::time::Duration::from_secs(29 * 60));
-            match wait_future.await {
+                idle_handle.wait_with_timeout(std::time::Duration::from_secs(IDLE_TIMEOUT_SECS));
+            let woke = wait_future.await;
+            activity.lock().idle_alive(&self.account_id, Utc::now());
+            match woke {
                 Ok(_) => {
                     // Got a notification, fetch new emails
                     let _ = event_tx
@@ -917,6 +1111,52 @@ impl ImapClient {
     }
 }
 
+/// A message's X-GM-MSGID and X-GM-THRID
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct GmailIds {
+    message_id: u64,
+    thread_id: u64,
+}
+
+/// Gmail IDs by UID from the untagged responses to a
+/// `UID FETCH ... (UID X-GM-MSGID X-GM-THRID)`, e.g.
+/// `* 12 FETCH (X-GM-THRID 1278455344230334865 X-GM-MSGID 1278455344230334866 UID 345)`
+fn parse_gmail_ids(response: &str) -> HashMap<u32, GmailIds> {
+    response
+        .lines()
+        .filter_map(|line| {
+            let (_, items) = line.trim().strip_prefix("* ")?.split_once(" FETCH (")?;
+            let mut tokens = items.trim_end().trim_end_matches(')').split_whitespace();
+            let (mut uid, mut message_id, mut thread_id) = (None, None, None);
+            while let (Some(name), Some(value)) = (tokens.next(), tokens.next()) {
+                match name.to_ascii_uppercase().as_str() {
+                    "UID" => uid = value.parse().ok(),
+                    "X-GM-MSGID" => message_id = value.parse().ok(),
+                    "X-GM-THRID" => thread_id = value.parse().ok(),
+                    _ => {}
+                }
+            }
+            Some((
+                uid?,
+                GmailIds {
+                    message_id: message_id?,
+                    thread_id: thread_id?,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Set each email's Gmail message and thread IDs by its UID
+fn apply_gmail_ids(emails: &mut [Email], ids: &HashMap<u32, GmailIds>) {
+    for email in emails {
+        if let Some(ids) = ids.get(&email.uid) {
+            email.gmail_message_id = ids.message_id;
+            email.gmail_thread_id = ids.thread_id;
+        }
+    }
+}
+
 /// Sanitize a filename to be safe for the filesystem
 fn sanitize_filename(name: &str) -> String {
     name.chars()
@@ -929,3 +1169,28 @@ fn sanitize_filename(name: &str) -> String {
         .trim()
         .to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_gmail_ids_by_uid() {
+        let response = "* 1 FETCH (X-GM-THRID 1278455344230334865 X-GM-MSGID 1278455344230334866 UID 345)\r\n\
+                        * 2 FETCH (UID 346 X-GM-MSGID 1278455344230334999 X-GM-THRID 1278455344230334865)\r\n\
+                        * 3 FETCH (UID 347)\r\n\
+                        A0005 OK Success\r\n";
+        let ids = parse_gmail_ids(response);
+
+        assert_eq!(ids.len(), 2);
+        assert_eq!(
+            ids[&345],
+            GmailIds {
+                message_id: 1278455344230334866,
+                thread_id: 1278455344230334865,
+            }
+        );
+        assert_eq!(ids[&346].thread_id, ids[&345].thread_id);
+        assert!(!ids.contains_key(&347));
+    }
+}
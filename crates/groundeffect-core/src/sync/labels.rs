@@ -0,0 +1,313 @@
+//! Gmail label listing via the Gmail API, with an on-disk cache
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use tracing::{debug, info, warn};
+
+use crate::config::Config;
+use crate::error::{Error, Result};
+use crate::models::{GmailLabel, LabelCache, LabelColor, LabelType};
+use crate::oauth::OAuthManager;
+
+use super::bodies::{gmail_json, list_gmail_ids};
+
+/// Gmail API labels endpoint
+const LABELS_URL: &str = "https://gmail.googleapis.com/gmail/v1/users/me/labels";
+
+/// Gmail API endpoint changing the labels of many messages at once
+const BATCH_MODIFY_URL: &str =
+    "https://gmail.googleapis.com/gmail/v1/users/me/messages/batchModify";
+
+/// Most messages one batchModify request takes
+const BATCH_MODIFY_MAX: usize = 1000;
+
+/// Cached labels older than this are refetched (1 hour)
+pub const LABEL_CACHE_TTL_SECS: u64 = 3600;
+
+/// Fetch all labels (with colors and message counts) for an account
+pub async fn fetch_gmail_labels(oauth: &OAuthManager, account_id: &str) -> Result<Vec<GmailLabel>> {
+    let access_token = oauth.get_valid_token(account_id).await?;
+    let client = Client::new();
+
+    let response = client
+        .get(LABELS_URL)
+        .bearer_auth(&access_token)
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(Error::Other(format!(
+            "Gmail API error {}: {}",
+            status, body
+        )));
+    }
+
+    let json: serde_json::Value = response.json().await?;
+    let items = json["labels"].as_array().cloned().unwrap_or_default();
+
+    // The list endpoint omits counts, so fetch each label individually
+    let mut labels = Vec::with_capacity(items.len());
+    for item in items {
+        let Some(id) = item["id"].as_str() else {
+            continue;
+        };
+
+        let detail = match client
+            .get(format!("{}/{}", LABELS_URL, urlencoding::encode(id)))
+            .bearer_auth(&access_token)
+            .send()
+            .await
+        {
+            Ok(resp) if resp.status().is_success() => resp.json().await.unwrap_or(item),
+            Ok(resp) => {
+                debug!("Label {} details unavailable: {}", id, resp.status());
+                item
+            }
+            Err(e) => {
+                debug!("Label {} details unavailable: {}", id, e);
+                item
+            }
+        };
+        labels.push(parse_label(&detail));
+    }
+
+    info!("Fetched {} Gmail labels for {}", labels.len(), account_id);
+    Ok(labels)
+}
+
+/// List labels for an account, using the on-disk cache when it is fresh.
+///
+/// With `refresh` the cache is bypassed. If Gmail can't be reached, a stale
+/// cache is returned rather than failing.
+pub async fn list_gmail_labels(
+    oauth: &OAuthManager,
+    config: &Config,
+    account_id: &str,
+    refresh: bool,
+) -> Result<LabelCache> {
+    let cache_path = config.label_cache_file(account_id);
+    let cached = load_label_cache(&cache_path);
+
+    if !refresh {
+        if let Some(cache) = cached.as_ref().filter(|c| c.is_fresh(LABEL_CACHE_TTL_SECS)) {
+            return Ok(cache.clone());
+        }
+    }
+
+    match fetch_gmail_labels(oauth, account_id).await {
+        Ok(labels) => {
+            let cache = LabelCache::new(account_id, labels);
+            if let Err(e) = save_label_cache(&cache_path, &cache) {
+                warn!("Failed to write label cache for {}: {}", account_id, e);
+            }
+            Ok(cache)
+        }
+        Err(e) => match cached {
+            Some(cache) => {
+                warn!(
+                    "Failed to fetch labels for {}, using cache from {}: {}",
+                    account_id, cache.fetched_at, e
+                );
+                Ok(cache)
+            }
+            None => Err(e),
+        },
+    }
+}
+
+/// X-GM-MSGIDs of up to `max` received messages dated on or after `since`
+/// that have no user label
+///
+/// Labels aren't stored locally, so Gmail is asked. Its API message ids are
+/// X-GM-MSGIDs in hex, which sync records as `Email::gmail_message_id` from
+/// the IMAP X-GM-MSGID fetch item (not the folder UID).
+pub async fn unlabeled_message_ids(
+    oauth: &OAuthManager,
+    account_id: &str,
+    since: DateTime<Utc>,
+    max: usize,
+) -> Result<HashSet<u64>> {
+    let access_token = oauth.get_valid_token(account_id).await?;
+    let query = format!(
+        "has:nouserlabels -in:sent -in:drafts -in:chats -in:spam -in:trash after:{}",
+        since.timestamp()
+    );
+    let ids = list_gmail_ids(&Client::new(), &access_token, &query, max).await?;
+    debug!("{} unlabeled messages in {}", ids.len(), account_id);
+    Ok(ids
+        .iter()
+        .filter_map(|id| u64::from_str_radix(id, 16).ok())
+        .collect())
+}
+
+/// Add a label to messages (by X-GM-MSGID), creating it when the account
+/// has no label by that name
+///
+/// Unknown ids (0) are skipped.
+pub async fn add_gmail_label(
+    oauth: &OAuthManager,
+    account_id: &str,
+    name: &str,
+    message_ids: &[u64],
+) -> Result<GmailLabel> {
+    let access_token = oauth.get_valid_token(account_id).await?;
+    let client = Client::new();
+
+    let json = gmail_json(
+        client
+            .get(LABELS_URL)
+            .bearer_auth(&access_token)
+            .send()
+            .await?,
+    )
+    .await?;
+    let existing = json["labels"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(parse_label)
+        .find(|label| label.matches(name));
+    let label = match existing {
+        Some(label) => label,
+        None => {
+            let response = client
+                .post(LABELS_URL)
+                .bearer_auth(&access_token)
+                .json(&serde_json::json!({
+                    "name": name,
+                    "labelListVisibility": "labelShow",
+                    "messageListVisibility": "show",
+                }))
+                .send()
+                .await?;
+            let label = parse_label(&gmail_json(response).await?);
+            info!(
+                "Created Gmail label {} ({}) in {}",
+                label.name, label.id, account_id
+            );
+            label
+        }
+    };
+
+    let message_ids: Vec<u64> = message_ids.iter().copied().filter(|id| *id != 0).collect();
+    for chunk in message_ids.chunks(BATCH_MODIFY_MAX) {
+        let ids: Vec<String> = chunk.iter().map(|id| format!("{:x}", id)).collect();
+        let response = client
+            .post(BATCH_MODIFY_URL)
+            .bearer_auth(&access_token)
+            .json(&serde_json::json!({
+                "ids": ids,
+                "addLabelIds": [label.id],
+            }))
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(Error::Other(format!(
+                "Gmail API error {}: {}",
+                status, body
+            )));
+        }
+    }
+
+    info!(
+        "Labeled {} messages {} in {}",
+        message_ids.len(),
+        label.name,
+        account_id
+    );
+    Ok(label)
+}
+
+/// Map a `--folder` filter value onto the folder name stored on emails.
+///
+/// Accepts Gmail label names and IDs from any account's cached label list
+/// (e.g. "SENT" -> "[Gmail]/Sent Mail"); unknown values pass through
+/// unchanged.
+pub fn resolve_label_folder(config: &Config, value: &str) -> String {
+    let Ok(entries) = std::fs::read_dir(config.label_cache_dir()) else {
+        return value.to_string();
+    };
+
+    entries
+        .flatten()
+        .filter_map(|entry| load_label_cache(&entry.path()))
+        .find_map(|cache| cache.find(value).map(|label| label.imap_folder()))
+        .unwrap_or_else(|| value.to_string())
+}
+
+/// Read a label cache file (None if missing or unreadable)
+pub fn load_label_cache(path: &Path) -> Option<LabelCache> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+fn save_label_cache(path: &Path, cache: &LabelCache) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(cache)?)?;
+    Ok(())
+}
+
+/// Parse a Gmail API label resource
+fn parse_label(json: &serde_json::Value) -> GmailLabel {
+    let label_type = match json["type"].as_str() {
+        Some("user") => LabelType::User,
+        _ => LabelType::System,
+    };
+    let color = match (
+        json["color"]["textColor"].as_str(),
+        json["color"]["backgroundColor"].as_str(),
+    ) {
+        (Some(text), Some(background)) => Some(LabelColor {
+            text_color: text.to_string(),
+            background_color: background.to_string(),
+        }),
+        _ => None,
+    };
+
+    GmailLabel {
+        id: json["id"].as_str().unwrap_or_default().to_string(),
+        name: json["name"].as_str().unwrap_or_default().to_string(),
+        label_type,
+        color,
+        messages_total: json["messagesTotal"].as_u64(),
+        messages_unread: json["messagesUnread"].as_u64(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_user_label_with_color() {
+        let label = parse_label(&serde_json::json!({
+            "id": "Label_42",
+            "name": "Work",
+            "type": "user",
+            "messagesTotal": 120,
+            "messagesUnread": 3,
+            "color": {"textColor": "#ffffff", "backgroundColor": "#4986e7"}
+        }));
+        assert_eq!(label.label_type, LabelType::User);
+        assert_eq!(label.messages_total, Some(120));
+        assert_eq!(label.messages_unread, Some(3));
+        assert_eq!(label.color.unwrap().background_color, "#4986e7");
+    }
+
+    #[test]
+    fn test_parse_system_label_without_counts() {
+        let label =
+            parse_label(&serde_json::json!({"id": "INBOX", "name": "INBOX", "type": "system"}));
+        assert_eq!(label.label_type, LabelType::System);
+        assert!(label.color.is_none());
+        assert!(label.messages_total.is_none());
+    }
+}
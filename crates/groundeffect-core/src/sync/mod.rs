@@ -2,28 +2,61 @@
 //!
 //! Handles IMAP sync (with IMAP IDLE) for Gmail and CalDAV sync for Google Calendar.
 
+mod availability;
+mod bodies;
 mod caldav;
+mod directory;
 mod imap;
+mod labels;
 mod rate_limiter;
+mod remote_search;
+mod watchdog;
+mod write_log;
 
+pub use availability::*;
+pub use bodies::*;
 pub use caldav::*;
+pub use directory::*;
 pub use imap::*;
+pub use labels::*;
 pub use rate_limiter::*;
+pub use remote_search::*;
+pub use watchdog::*;
+pub use write_log::*;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 use chrono::{DateTime, Duration, Utc};
-use parking_lot::RwLock;
-use tokio::sync::mpsc;
+use parking_lot::{Mutex, RwLock};
+use tokio::sync::{mpsc, Notify, OwnedSemaphorePermit, Semaphore};
 use tracing::{debug, error, info, warn};
 
-use crate::config::Config;
+use crate::activity::extract_activity;
+use crate::classify::{build_classifier, resolve_categories, CategoryClassifier};
+use crate::config::{Config, EmbeddingConfig, SyncConfig};
 use crate::db::Database;
-use crate::embedding::HybridEmbeddingProvider;
+use crate::embedding::{HybridEmbeddingProvider, DEFAULT_EVENT_TEXT_VERSION};
 use crate::error::{Error, Result};
-use crate::models::{Account, AccountStatus, CalendarEvent, Email};
+use crate::extractions::extract_all;
+use crate::health::SyncIncident;
+use crate::metrics::{self, Queue};
+use crate::models::{
+    Account, AccountStatus, ActivityItem, CalendarEvent, Email, ItineraryItem, Receipt, Thread,
+};
 use crate::oauth::OAuthManager;
+use crate::raw::raw_path;
+use crate::receipts::{extract_receipts, receipt_from_attachments};
+use crate::recovery::{check_cursors, check_fts, Issue, IssueKind, RecoveryReport, RepairStatus};
+use crate::retention::PruneReport;
+use crate::sync_window::PolicyState;
+use crate::tagging::{build_tag_rules, TagRules};
+use crate::tasks::extract_tasks;
+use crate::threads::{build_thread, thread_keys};
+use crate::travel::{calendar_event_json, extract_itineraries};
+use crate::vectors::VectorTable;
 
 /// Sync event types
 #[derive(Debug, Clone)]
@@ -85,6 +118,21 @@ pub enum SyncType {
 
 use serde::{Deserialize, Serialize};
 
+/// What the stored vectors were computed with (`embedding_state.json`)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct EmbeddingState {
+    #[serde(flatten)]
+    templates: EmbeddingConfig,
+    /// See [`crate::config::SearchConfig::vector_model`]; missing from files
+    /// written before the model was recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    model: Option<String>,
+    /// [`DEFAULT_EVENT_TEXT_VERSION`] events were embedded with; 0 for files
+    /// written before it was recorded
+    #[serde(default)]
+    event_text_version: u32,
+}
+
 /// Sync state for an account
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AccountSyncState {
@@ -97,6 +145,9 @@ pub struct AccountSyncState {
     pub error: Option<String>,
     /// Initial sync progress (None if not currently doing initial sync)
     pub initial_sync_progress: Option<InitialSyncProgress>,
+    /// Account is over its storage limit (backfill and attachment downloads paused)
+    #[serde(default)]
+    pub storage_quota_exceeded: bool,
 }
 
 /// Progress tracking for initial sync
@@ -155,16 +206,81 @@ pub enum SyncPhase {
     Completed,
 }
 
+/// Fetch limits that [`SyncManager::apply_limits`] and
+/// [`SyncManager::apply_policy`] can change while the daemon runs
+struct SyncLimits {
+    rate_limit_per_second: u32,
+    rate_limiter: Arc<GlobalRateLimiter>,
+    max_concurrent_fetches: usize,
+    /// Lower concurrency while throttled on battery
+    throttle: Option<usize>,
+    /// One permit per account sync allowed to run at once
+    fetch_permits: Arc<Semaphore>,
+    attachment_max_size_mb: u64,
+}
+
+impl SyncLimits {
+    fn new(sync: &SyncConfig) -> Self {
+        Self {
+            rate_limit_per_second: sync.rate_limit_per_second,
+            rate_limiter: Arc::new(GlobalRateLimiter::new(sync.rate_limit_per_second)),
+            max_concurrent_fetches: sync.max_concurrent_fetches,
+            throttle: None,
+            fetch_permits: Arc::new(Semaphore::new(sync.max_concurrent_fetches.max(1))),
+            attachment_max_size_mb: sync.attachment_max_size_mb,
+        }
+    }
+
+    /// Account syncs allowed at once
+    fn concurrent_fetches(&self) -> usize {
+        match self.throttle {
+            Some(throttle) => throttle.min(self.max_concurrent_fetches),
+            None => self.max_concurrent_fetches,
+        }
+        .max(1)
+    }
+
+    /// Change the configured or throttled concurrency; a new set of permits
+    /// starts if the number allowed at once changes
+    fn set_concurrency(&mut self, max_concurrent_fetches: usize, throttle: Option<usize>) {
+        let before = self.concurrent_fetches();
+        self.max_concurrent_fetches = max_concurrent_fetches;
+        self.throttle = throttle;
+        if self.concurrent_fetches() != before {
+            self.fetch_permits = Arc::new(Semaphore::new(self.concurrent_fetches()));
+        }
+    }
+}
+
 /// Sync manager for all accounts
 pub struct SyncManager {
     db: Arc<Database>,
     config: Arc<Config>,
     oauth: Arc<OAuthManager>,
     embedding: Arc<HybridEmbeddingProvider>,
-    rate_limiter: Arc<GlobalRateLimiter>,
+    limits: RwLock<SyncLimits>,
     account_states: Arc<RwLock<HashMap<String, AccountSyncState>>>,
     event_tx: mpsc::Sender<SyncEvent>,
     event_rx: RwLock<Option<mpsc::Receiver<SyncEvent>>>,
+    category_classifier: tokio::sync::OnceCell<Option<CategoryClassifier>>,
+    tag_rules: tokio::sync::OnceCell<TagRules>,
+    /// Running IMAP IDLE tasks by account
+    idle_tasks: Mutex<HashMap<String, tokio::task::AbortHandle>>,
+    /// Sync and IDLE liveness, for the watchdog
+    activity: Arc<Mutex<SyncActivity>>,
+    /// Signals that cancel an account's running sync
+    cancels: Mutex<HashMap<String, Arc<Notify>>>,
+    /// Email batches recorded before they're written
+    write_log: WriteLog,
+    /// Held for reading while a batch is written; shutdown takes it for
+    /// writing to wait for those writes and keep new ones from starting
+    write_gate: tokio::sync::RwLock<()>,
+    /// Set once shutdown starts; no new syncs start after that
+    shutting_down: AtomicBool,
+    /// Why new syncs are held by the sync window, if they are
+    pause: RwLock<Option<String>>,
+    /// Why attachment downloads are held by the sync window, if they are
+    attachments_held: RwLock<Option<String>>,
 }
 
 impl SyncManager {
@@ -176,18 +292,92 @@ impl SyncManager {
         embedding: Arc<HybridEmbeddingProvider>,
     ) -> Self {
         let (tx, rx) = mpsc::channel(1000);
-        let rate_limit = config.sync.rate_limit_per_second;
+        let limits = RwLock::new(SyncLimits::new(&config.sync));
+        let write_log = WriteLog::new(config.write_log_dir());
 
         Self {
             db,
             config,
             oauth,
             embedding,
-            rate_limiter: Arc::new(GlobalRateLimiter::new(rate_limit)),
+            limits,
             account_states: Arc::new(RwLock::new(HashMap::new())),
             event_tx: tx,
             event_rx: RwLock::new(Some(rx)),
+            category_classifier: tokio::sync::OnceCell::new(),
+            tag_rules: tokio::sync::OnceCell::new(),
+            idle_tasks: Mutex::new(HashMap::new()),
+            activity: Arc::new(Mutex::new(SyncActivity::default())),
+            cancels: Mutex::new(HashMap::new()),
+            write_log,
+            write_gate: tokio::sync::RwLock::new(()),
+            shutting_down: AtomicBool::new(false),
+            pause: RwLock::new(None),
+            attachments_held: RwLock::new(None),
+        }
+    }
+
+    /// Use the rate, concurrency and attachment size limits in `sync` for
+    /// fetches started from now on; fetches already running keep the old ones
+    pub fn apply_limits(&self, sync: &SyncConfig) {
+        let mut limits = self.limits.write();
+        if limits.rate_limit_per_second != sync.rate_limit_per_second {
+            limits.rate_limit_per_second = sync.rate_limit_per_second;
+            limits.rate_limiter = Arc::new(GlobalRateLimiter::new(sync.rate_limit_per_second));
+        }
+        let throttle = limits.throttle;
+        limits.set_concurrency(sync.max_concurrent_fetches, throttle);
+        limits.attachment_max_size_mb = sync.attachment_max_size_mb;
+    }
+
+    /// Apply the sync window: hold new syncs while it pauses syncing, limit
+    /// how many run at once while throttled, and hold attachment downloads
+    ///
+    /// Syncs already running finish; held syncs are skipped, and the first
+    /// one after the pause catches up.
+    pub fn apply_policy(&self, policy: &PolicyState) {
+        {
+            let mut limits = self.limits.write();
+            let max = limits.max_concurrent_fetches;
+            limits.set_concurrency(max, policy.max_concurrent_fetches);
         }
+        *self.pause.write() = policy.sync_paused.clone();
+        *self.attachments_held.write() = policy.attachments_paused.clone();
+    }
+
+    /// Why the sync window holds new syncs, if it does
+    pub fn sync_paused(&self) -> Option<String> {
+        self.pause.read().clone()
+    }
+
+    /// The rate limiter shared by new IMAP and CalDAV clients
+    fn rate_limiter(&self) -> Arc<GlobalRateLimiter> {
+        self.limits.read().rate_limiter.clone()
+    }
+
+    /// Wait for a turn to sync an account under `sync.max_concurrent_fetches`
+    async fn fetch_permit(&self) -> OwnedSemaphorePermit {
+        let permits = self.limits.read().fetch_permits.clone();
+        let _queued = metrics::enqueue(Queue::Sync);
+        permits
+            .acquire_owned()
+            .await
+            .expect("fetch permits are never closed")
+    }
+
+    /// Embedding-based category classifier, built on first use
+    async fn category_classifier(&self) -> Option<&CategoryClassifier> {
+        self.category_classifier
+            .get_or_init(|| build_classifier(&self.embedding))
+            .await
+            .as_ref()
+    }
+
+    /// Local tagging rules, with their examples embedded on first use
+    async fn tag_rules(&self) -> &TagRules {
+        self.tag_rules
+            .get_or_init(|| build_tag_rules(&self.config.tagging, &self.embedding))
+            .await
     }
 
     /// Take the event receiver (can only be called once)
@@ -244,6 +434,7 @@ impl SyncManager {
             event_count: self.db.count_events(Some(&account.id)).await?,
             error: None,
             initial_sync_progress: None,
+            storage_quota_exceeded: false,
         };
 
         self.account_states
@@ -254,6 +445,11 @@ impl SyncManager {
 
     /// Run initial sync for an account (smart sync strategy - newest first, with resume support)
     pub async fn initial_sync(&self, account_id: &str) -> Result<()> {
+        self.watched(account_id, self.run_initial_sync(account_id))
+            .await
+    }
+
+    async fn run_initial_sync(&self, account_id: &str) -> Result<()> {
         info!("Starting initial sync for {}", account_id);
 
         // Mark as syncing
@@ -264,20 +460,28 @@ impl SyncManager {
             }
         }
 
-        // Get account's sync_email_since preference, default to 90 days
+        // Get account's sync_email_since preference (default 90 days), capped
+        // by its retention policy
         let account = self
             .db
             .get_account(account_id)
             .await?
             .ok_or_else(|| Error::AccountNotFound(account_id.to_string()))?;
-        let target_since = account
-            .sync_email_since
-            .unwrap_or_else(|| Utc::now() - Duration::days(90));
+        let target_since = account.email_sync_target(Utc::now());
 
         // Check for existing emails to enable resume
-        let (oldest_synced, _newest_synced) = self.db.get_email_sync_boundaries(account_id).await?;
+        let (oldest_synced, newest_synced) = self.db.get_email_sync_boundaries(account_id).await?;
         let existing_count = self.db.count_emails(Some(account_id)).await?;
 
+        // Over the storage limit: keep recent mail flowing but pause historical backfill
+        let usage = self
+            .db
+            .get_storage_usage(account_id)
+            .await
+            .unwrap_or_default();
+        let over_quota = account.storage_quota_exceeded(&usage);
+        self.set_storage_quota_exceeded(account_id, over_quota);
+
         // Determine sync strategy:
         // 1. If backfill complete (oldest_synced date <= target_since date): incremental from last_sync_email
         // 2. If backfill incomplete: continue from target_since (with deduplication)
@@ -301,6 +505,20 @@ impl SyncManager {
                 existing_count
             );
             (false, incremental_since, None)
+        } else if over_quota && oldest_synced.is_some() {
+            // Over storage limit - skip the missing history, only fetch new emails
+            let incremental_since = account
+                .last_sync_email
+                .or(newest_synced)
+                .map(|t| t - Duration::hours(1))
+                .unwrap_or(target_since);
+            warn!(
+                "Storage limit reached for {} ({} of {} bytes), skipping historical backfill",
+                account_id,
+                usage.total(),
+                account.max_storage_bytes.unwrap_or_default()
+            );
+            (false, incremental_since, None)
         } else if let Some(oldest) = oldest_synced {
             // Backfill incomplete - only fetch the missing date range
             // Add 2-day buffer on both ends to catch boundary/timezone edge cases
@@ -324,6 +542,8 @@ impl SyncManager {
             (false, target_since, None)
         };
 
+        let incremental_only = backfill_complete || (over_quota && oldest_synced.is_some());
+
         // Skip email sync if very recent (within last 5 minutes) and backfill complete
         let skip_email_sync = backfill_complete
             && account
@@ -357,12 +577,16 @@ impl SyncManager {
                 existing_message_ids.len()
             );
 
-            let imap_client =
-                ImapClient::new(account_id, self.oauth.clone(), self.rate_limiter.clone()).await?;
+            let imap_client = ImapClient::new(account_id, self.oauth.clone(), self.rate_limiter())
+                .await?
+                .with_label_filter(account.label_filter_query())
+                .with_own_addresses(self.own_addresses())
+                .with_raw_store(self.raw_store())
+                .with_headers_only(account.headers_only);
 
             // Phase 0: Count emails to get progress estimate
-            // For incremental sync (backfill_complete), we don't need total INBOX count
-            let total_emails = if backfill_complete {
+            // For incremental sync (backfill complete or paused), we don't need total INBOX count
+            let total_emails = if incremental_only {
                 // Incremental sync - total is just existing + any new we find
                 existing_count
             } else {
@@ -391,7 +615,7 @@ impl SyncManager {
             {
                 let mut states = self.account_states.write();
                 if let Some(state) = states.get_mut(account_id) {
-                    let phase = if backfill_complete {
+                    let phase = if incremental_only {
                         SyncPhase::RecentEmails
                     } else if resume_mode {
                         SyncPhase::Backfill
@@ -430,10 +654,10 @@ impl SyncManager {
             let total_new_clone = total_new.clone();
             let account_states_clone = self.account_states.clone();
             let account_id_owned = account_id.to_string();
-            let db_clone = self.db.clone();
             let embedding_clone = self.embedding.clone();
             let existing_ids_clone = existing_message_ids.clone();
             let progress_file_path = self.config.sync_progress_file();
+            let activity = self.activity.clone();
 
             // Use single connection to fetch emails (incremental or backfill depending on fetch_since)
             // fetch_before limits the date range during backfill to avoid re-fetching all emails
@@ -443,11 +667,11 @@ impl SyncManager {
                     let total_new = total_new_clone.clone();
                     let account_states = account_states_clone.clone();
                     let account_id = account_id_owned.clone();
-                    let db = db_clone.clone();
                     let embedding = embedding_clone.clone();
                     let sync_start = sync_start.clone();
                     let existing_ids = existing_ids_clone.clone();
                     let progress_path = progress_file_path.clone();
+                    activity.lock().progress(&account_id, Utc::now());
 
                     async move {
                         // Filter out emails we already have (by message_id)
@@ -469,8 +693,10 @@ impl SyncManager {
                         const MAX_EMBED_RETRIES: u32 = 3;
 
                         for embed_chunk in new_emails.chunks(embed_batch_size) {
-                            let texts: Vec<String> =
-                                embed_chunk.iter().map(|e| e.searchable_text()).collect();
+                            let texts: Vec<String> = embed_chunk
+                                .iter()
+                                .map(|e| self.config.embedding.email_text(e))
+                                .collect();
 
                             // Try to get embeddings (may return None if fallback is BM25-only)
                             let embeddings_opt = match embedding.embed_batch(&texts).await {
@@ -485,7 +711,7 @@ impl SyncManager {
                             };
 
                             // Create emails with or without embeddings depending on result
-                            let emails_to_store: Vec<Email> = match embeddings_opt {
+                            let mut emails_to_store: Vec<Email> = match embeddings_opt {
                                 Some(embeddings) => embed_chunk
                                     .iter()
                                     .zip(embeddings.into_iter())
@@ -505,12 +731,29 @@ impl SyncManager {
                                 }
                             };
 
+                            resolve_categories(
+                                &mut emails_to_store,
+                                self.category_classifier().await,
+                            );
+                            self.tag_rules().await.apply(&mut emails_to_store);
+                            let activity =
+                                extract_activity(&mut emails_to_store, &self.config.activity);
+                            let receipts =
+                                extract_receipts(&emails_to_store, &self.config.receipts);
                             let emails_with_embeddings = emails_to_store;
 
-                            // Retry database upsert with exponential backoff
+                            // Retry database upsert with exponential backoff (emails and
+                            // their activity items and receipts are stored together)
                             let mut db_success = false;
                             for attempt in 1..=MAX_EMBED_RETRIES {
-                                match db.upsert_emails(&emails_with_embeddings).await {
+                                match self
+                                    .write_email_batch(
+                                        &emails_with_embeddings,
+                                        &activity,
+                                        &receipts,
+                                    )
+                                    .await
+                                {
                                     Ok(_) => {
                                         db_success = true;
                                         successfully_stored += emails_with_embeddings.len();
@@ -603,6 +846,14 @@ impl SyncManager {
                 return Err(e);
             }
 
+            // Then what the owner sent over the same period
+            if let Err(e) = self
+                .sync_sent_folder(account_id, account.headers_only, fetch_since)
+                .await
+            {
+                warn!("Failed to sync sent mail for {}: {}", account_id, e);
+            }
+
             let total_synced_count = total_synced.load(std::sync::atomic::Ordering::SeqCst);
             let new_emails_count = total_new.load(std::sync::atomic::Ordering::SeqCst);
 
@@ -649,9 +900,7 @@ impl SyncManager {
                     account.oldest_email_synced = new_oldest;
                 }
                 // Clear estimated_total_emails if backfill is complete (no longer meaningful)
-                let target = account
-                    .sync_email_since
-                    .unwrap_or_else(|| Utc::now() - Duration::days(90));
+                let target = account.email_sync_target(Utc::now());
                 let backfill_done = account
                     .oldest_email_synced
                     .map(|o| o.date_naive() <= target.date_naive())
@@ -729,7 +978,7 @@ impl SyncManager {
         let since = account.sync_email_since; // Uses same date range as email
 
         let caldav_client =
-            CalDavClient::new(account_id, self.oauth.clone(), self.rate_limiter.clone()).await?;
+            CalDavClient::new(account_id, self.oauth.clone(), self.rate_limiter()).await?;
 
         let events = caldav_client.fetch_events(since).await?;
 
@@ -777,7 +1026,10 @@ impl SyncManager {
 
             for chunk in changed_events.chunks(batch_size) {
                 // Collect texts for batch embedding
-                let texts: Vec<String> = chunk.iter().map(|e| e.searchable_text()).collect();
+                let texts: Vec<String> = chunk
+                    .iter()
+                    .map(|e| self.config.embedding.event_text(e))
+                    .collect();
 
                 // Try to get embeddings (may return None if fallback is BM25-only)
                 let embeddings_opt = match self.embedding.embed_batch(&texts).await {
@@ -829,6 +1081,10 @@ impl SyncManager {
             );
         }
 
+        if let Err(e) = refresh_availability(&self.oauth, &self.config, account_id).await {
+            warn!("Failed to refresh availability for {}: {}", account_id, e);
+        }
+
         self.emit_event(SyncEvent::SyncCompleted {
             account_id: account_id.to_string(),
             sync_type: SyncType::Calendar,
@@ -867,11 +1123,37 @@ impl SyncManager {
     /// Download attachments for emails that have them but haven't been downloaded yet
     /// Returns (downloaded_count, total_size_bytes)
     pub async fn download_attachments_for_account(&self, account_id: &str) -> Result<(usize, u64)> {
+        if let Some(reason) = self.attachments_held.read().clone() {
+            debug!("Not downloading attachments for {}: {}", account_id, reason);
+            return Ok((0, 0));
+        }
         info!("Downloading attachments for {}", account_id);
 
         let attachments_dir = self.config.attachments_dir();
         std::fs::create_dir_all(&attachments_dir)?;
 
+        // Attachment downloads stop once the account reaches its storage limit
+        let max_storage = self
+            .db
+            .get_account(account_id)
+            .await?
+            .and_then(|a| a.max_storage_bytes);
+        let used = match max_storage {
+            Some(_) => self.db.get_storage_usage(account_id).await?.total(),
+            None => 0,
+        };
+        if let Some(max) = max_storage {
+            let over_quota = used >= max;
+            self.set_storage_quota_exceeded(account_id, over_quota);
+            if over_quota {
+                warn!(
+                    "Storage limit reached for {} ({} of {} bytes), skipping attachment downloads",
+                    account_id, used, max
+                );
+                return Ok((0, 0));
+            }
+        }
+
         // Get emails with attachments that haven't been downloaded
         let emails = self
             .db
@@ -889,8 +1171,15 @@ impl SyncManager {
             account_id
         );
 
-        let imap_client =
-            ImapClient::new(account_id, self.oauth.clone(), self.rate_limiter.clone()).await?;
+        // UIDs are per folder, so sent mail is fetched from the Sent folder
+        let attachment_max_mb = self.limits.read().attachment_max_size_mb;
+        let inbox_client = ImapClient::new(account_id, self.oauth.clone(), self.rate_limiter())
+            .await?
+            .with_attachment_max_mb(attachment_max_mb);
+        let sent_client = ImapClient::new(account_id, self.oauth.clone(), self.rate_limiter())
+            .await?
+            .with_folder(SENT_FOLDER)
+            .with_attachment_max_mb(attachment_max_mb);
 
         let mut total_downloaded = 0usize;
         let mut total_size = 0u64;
@@ -900,7 +1189,23 @@ impl SyncManager {
                 continue;
             }
 
+            if let Some(max) = max_storage {
+                if used + total_size >= max {
+                    warn!(
+                        "Storage limit reached for {} after {} attachments, pausing downloads",
+                        account_id, total_downloaded
+                    );
+                    self.set_storage_quota_exceeded(account_id, true);
+                    break;
+                }
+            }
+
             // Download all attachments for this email
+            let imap_client = if email.folder == SENT_FOLDER {
+                &sent_client
+            } else {
+                &inbox_client
+            };
             match imap_client
                 .download_all_attachments(email.uid, &attachments_dir)
                 .await
@@ -923,9 +1228,28 @@ impl SyncManager {
 
                     total_downloaded += downloaded.len();
 
-                    // Update email in database
-                    if let Err(e) = self.db.upsert_emails(&[updated_email]).await {
-                        warn!("Failed to update email with attachment paths: {}", e);
+                    // Receipts that only carry a PDF get their total once it's on disk
+                    let receipts = &self.config.receipts;
+                    let receipt = if receipts.enabled && receipts.parse_attachments {
+                        let email = updated_email.clone();
+                        tokio::task::spawn_blocking(move || receipt_from_attachments(&email))
+                            .await
+                            .ok()
+                            .flatten()
+                    } else {
+                        None
+                    };
+
+                    // Update the email and its receipt together
+                    if let Err(e) = self
+                        .db
+                        .store_emails_with_derived(&[updated_email], &[], receipt.as_slice())
+                        .await
+                    {
+                        warn!(
+                            "Failed to update email {} with attachment paths: {}",
+                            email.id, e
+                        );
                     }
                 }
                 Err(e) => {
@@ -955,126 +1279,961 @@ impl SyncManager {
         info!("Starting IMAP IDLE for {}", account_id);
 
         let imap_client =
-            ImapClient::new(account_id, self.oauth.clone(), self.rate_limiter.clone()).await?;
+            ImapClient::new(account_id, self.oauth.clone(), self.rate_limiter()).await?;
 
         let event_tx = self.event_tx.clone();
-        let account_id = account_id.to_string();
+        let task_account = account_id.to_string();
+        let activity = self.activity.clone();
+        self.activity.lock().idle_alive(account_id, Utc::now());
 
-        tokio::spawn(async move {
-            if let Err(e) = imap_client.start_idle(event_tx).await {
-                error!("IMAP IDLE error for {}: {}", account_id, e);
+        let task = tokio::spawn(async move {
+            if let Err(e) = imap_client.start_idle(event_tx, activity).await {
+                error!("IMAP IDLE error for {}: {}", task_account, e);
             }
         });
+        if let Some(previous) = self
+            .idle_tasks
+            .lock()
+            .insert(account_id.to_string(), task.abort_handle())
+        {
+            previous.abort();
+        }
 
         Ok(())
     }
 
+    /// Stop every IMAP IDLE task, closing its connection. Returns the
+    /// accounts that were idling so they can be restarted with `start_idle`.
+    pub fn stop_idle(&self) -> Vec<String> {
+        let mut stopped = Vec::new();
+        for (account_id, task) in self.idle_tasks.lock().drain() {
+            self.activity.lock().idle_stopped(&account_id);
+            if !task.is_finished() {
+                task.abort();
+                stopped.push(account_id);
+            }
+        }
+        if !stopped.is_empty() {
+            info!("Stopped IMAP IDLE for {} account(s)", stopped.len());
+        }
+        stopped
+    }
+
     /// Force sync for specific accounts
     pub async fn trigger_sync(&self, account_ids: &[String], sync_type: SyncType) -> Result<()> {
         for account_id in account_ids {
             match sync_type {
                 SyncType::Email => {
-                    // Incremental email sync
-                    debug!("Starting incremental email sync for {}", account_id);
-                    let imap_client =
-                        ImapClient::new(account_id, self.oauth.clone(), self.rate_limiter.clone())
-                            .await?;
-
-                    let state = self.get_state(account_id);
-                    let since = state
-                        .and_then(|s| s.last_email_sync)
-                        .unwrap_or_else(|| Utc::now() - Duration::hours(1));
-
-                    let emails = imap_client.fetch_recent_emails(since, 100).await?;
-
-                    if !emails.is_empty() {
-                        info!(
-                            "Incremental sync: found {} new emails for {}",
-                            emails.len(),
-                            account_id
-                        );
+                    let _permit = self.fetch_permit().await;
+                    self.watched(account_id, self.sync_email_incremental(account_id))
+                        .await?;
+                }
+                SyncType::Calendar => {
+                    let _permit = self.fetch_permit().await;
+                    self.watched(account_id, self.sync_calendar(account_id))
+                        .await?;
+                }
+                SyncType::All => {
+                    Box::pin(self.trigger_sync(&[account_id.clone()], SyncType::Email)).await?;
+                    Box::pin(self.trigger_sync(&[account_id.clone()], SyncType::Calendar)).await?;
+                }
+            }
+        }
+        Ok(())
+    }
 
-                        // Batch embed and insert for performance
-                        let embed_batch_size = self.config.search.effective_embedding_batch_size();
-                        for chunk in emails.chunks(embed_batch_size) {
-                            let texts: Vec<String> =
-                                chunk.iter().map(|e| e.searchable_text()).collect();
+    /// Fetch and store the account's emails since its last sync
+    async fn sync_email_incremental(&self, account_id: &str) -> Result<()> {
+        debug!("Starting incremental email sync for {}", account_id);
+        let account = self.db.get_account(account_id).await?;
+        let label_filter = account.as_ref().and_then(|a| a.label_filter_query());
+        let headers_only = account.as_ref().is_some_and(|a| a.headers_only);
+        let imap_client = ImapClient::new(account_id, self.oauth.clone(), self.rate_limiter())
+            .await?
+            .with_label_filter(label_filter)
+            .with_own_addresses(self.own_addresses())
+            .with_raw_store(self.raw_store())
+            .with_headers_only(headers_only);
 
-                            // Try to get embeddings (may return None if fallback is BM25-only)
-                            let embeddings_opt = match self.embedding.embed_batch(&texts).await {
-                                Ok(opt) => opt,
-                                Err(e) => {
-                                    warn!("Email embedding failed: {}, storing emails without embeddings", e);
-                                    None
-                                }
-                            };
+        let state = self.get_state(account_id);
+        let since = state
+            .and_then(|s| s.last_email_sync)
+            .unwrap_or_else(|| Utc::now() - Duration::hours(1));
+
+        let emails = imap_client.fetch_recent_emails(since, 100).await?;
+
+        if !emails.is_empty() {
+            info!(
+                "Incremental sync: found {} new emails for {}",
+                emails.len(),
+                account_id
+            );
+            self.store_emails(&emails).await?;
+            info!(
+                "Incremental sync: stored {} emails for {}",
+                emails.len(),
+                account_id
+            );
+        }
+
+        // Replies live in the Sent folder, not INBOX
+        if let Err(e) = self.sync_sent_folder(account_id, headers_only, since).await {
+            warn!("Failed to sync sent mail for {}: {}", account_id, e);
+        }
+
+        // Update state and persist to database
+        let now = Utc::now();
+        if let Some(state) = self.account_states.write().get_mut(account_id) {
+            state.last_email_sync = Some(now);
+        }
+        let should_sync_attachments;
+        if let Ok(Some(mut account)) = self.db.get_account(account_id).await {
+            should_sync_attachments = account.sync_attachments;
+            account.last_sync_email = Some(now);
+            if let Err(e) = self.db.upsert_account(&account).await {
+                warn!("Failed to persist last_sync_email: {}", e);
+            }
+        } else {
+            should_sync_attachments = false;
+        }
+
+        // Download attachments if enabled for this account
+        if should_sync_attachments {
+            if let Err(e) = self.download_attachments_for_account(account_id).await {
+                warn!(
+                    "Failed to download attachments during incremental sync: {}",
+                    e
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Run one account's sync under the watchdog: its progress is tracked,
+    /// [`SyncManager::recover_account`] cancels it, and a failure is
+    /// reported as [`SyncEvent::SyncError`]
+    async fn watched(
+        &self,
+        account_id: &str,
+        sync: impl Future<Output = Result<()>>,
+    ) -> Result<()> {
+        if self.shutting_down.load(Ordering::SeqCst) {
+            debug!("Not syncing {}: shutting down", account_id);
+            return Ok(());
+        }
+        if let Some(reason) = self.sync_paused() {
+            debug!("Not syncing {}: {}", account_id, reason);
+            return Ok(());
+        }
+        let cancel = self.cancel_signal(account_id);
+        self.activity.lock().sync_started(account_id, Utc::now());
+        let result = tokio::select! {
+            result = sync => result,
+            _ = cancel.notified() => {
+                if let Some(state) = self.account_states.write().get_mut(account_id) {
+                    state.is_syncing = false;
+                }
+                Err(Error::Other(format!(
+                    "Sync for {} stalled and was cancelled",
+                    account_id
+                )))
+            }
+        };
+
+        let error = result.as_ref().err().map(|e| e.to_string());
+        self.activity
+            .lock()
+            .sync_finished(account_id, error.as_deref());
+        if let Some(error) = error {
+            self.emit_event(SyncEvent::SyncError {
+                account_id: account_id.to_string(),
+                error,
+            })
+            .await;
+        }
+        result
+    }
+
+    /// Signal that cancels the account's running sync
+    fn cancel_signal(&self, account_id: &str) -> Arc<Notify> {
+        self.cancels
+            .lock()
+            .entry(account_id.to_string())
+            .or_default()
+            .clone()
+    }
+
+    /// Accounts whose sync or IDLE connection is stuck, per the
+    /// `sync.stall_timeout_secs` and `sync.stall_error_repeats` settings
+    pub fn check_stalls(&self, now: DateTime<Utc>) -> Vec<SyncIncident> {
+        let sync = &self.config.sync;
+        if sync.stall_timeout_secs == 0 {
+            return Vec::new();
+        }
+        self.activity.lock().check(
+            Duration::seconds(sync.stall_timeout_secs as i64),
+            sync.stall_error_repeats,
+            now,
+        )
+    }
+
+    /// Cancel the account's running sync and reconnect its IDLE connection
+    ///
+    /// The caller starts the next sync; the cancelled one returns an error.
+    pub async fn recover_account(&self, account_id: &str) -> Result<()> {
+        self.cancel_signal(account_id).notify_waiters();
+        // IDLE stopped while idle resources are shed stays stopped
+        let idling = self.idle_tasks.lock().contains_key(account_id);
+        if idling {
+            self.start_idle(account_id).await?;
+        }
+        Ok(())
+    }
+
+    /// Store emails fetched outside a sync (remote search imports, saved
+    /// remote emails) the way sync stores mail: embedded, categorized and
+    /// tagged, with derived rows and threads, through the write log
+    pub async fn store_fetched(&self, emails: &[Email]) -> Result<()> {
+        self.store_emails(emails).await
+    }
+
+    /// Embed, classify and store fetched emails with what's extracted from them
+    async fn store_emails(&self, emails: &[Email]) -> Result<()> {
+        // Batch embed and insert for performance
+        let embed_batch_size = self.config.search.effective_embedding_batch_size();
+        for chunk in emails.chunks(embed_batch_size) {
+            let texts: Vec<String> = chunk
+                .iter()
+                .map(|e| self.config.embedding.email_text(e))
+                .collect();
+
+            // Try to get embeddings (may return None if fallback is BM25-only)
+            let embeddings_opt = match self.embedding.embed_batch(&texts).await {
+                Ok(opt) => opt,
+                Err(e) => {
+                    warn!(
+                        "Email embedding failed: {}, storing emails without embeddings",
+                        e
+                    );
+                    None
+                }
+            };
+
+            let mut emails_to_store: Vec<Email> = if let Some(embeddings) = embeddings_opt {
+                // Got embeddings - attach them to emails
+                chunk
+                    .iter()
+                    .zip(embeddings.into_iter())
+                    .map(|(email, embedding)| {
+                        let mut email = email.clone();
+                        email.embedding = Some(embedding);
+                        email
+                    })
+                    .collect()
+            } else {
+                // No embeddings (BM25-only) - store without embeddings
+                chunk.to_vec()
+            };
+            resolve_categories(&mut emails_to_store, self.category_classifier().await);
+            self.tag_rules().await.apply(&mut emails_to_store);
+            let activity = extract_activity(&mut emails_to_store, &self.config.activity);
+            let receipts = extract_receipts(&emails_to_store, &self.config.receipts);
+            self.write_email_batch(&emails_to_store, &activity, &receipts)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Store a batch of emails and everything derived from them
+    ///
+    /// The batch is recorded in the write log first, so if the daemon dies
+    /// part way through, [`SyncManager::recover_writes`] finishes it on the
+    /// next start. A failed write leaves the record for the same reason.
+    async fn write_email_batch(
+        &self,
+        emails: &[Email],
+        activity: &[ActivityItem],
+        receipts: &[Receipt],
+    ) -> Result<()> {
+        let _writing = self.write_gate.read().await;
+        let intent = EmailBatchIntent::new(emails, activity, receipts);
+        self.write_log.record(&intent)?;
+        self.db
+            .store_emails_with_derived(emails, activity, receipts)
+            .await?;
+        self.store_email_derived(emails).await;
+        self.write_log.complete(&intent.id)
+    }
+
+    /// Store the itinerary items, extractions, tasks and threads derived
+    /// from stored emails
+    async fn store_email_derived(&self, emails: &[Email]) {
+        let itinerary = extract_itineraries(emails, &self.config.travel, self.timezone());
+        self.store_itinerary(&itinerary).await;
+        self.store_extractions(emails).await;
+        self.store_tasks(emails).await;
+        self.update_threads(emails).await;
+    }
+
+    /// Finish email batches the write log recorded but a previous run never
+    /// completed (the daemon crashed or was killed mid-write); returns how
+    /// many were replayed
+    ///
+    /// Call before syncing starts. Batches that fail again stay in the log
+    /// for the next start.
+    pub async fn recover_writes(&self) -> Result<usize> {
+        let mut replayed = 0;
+        for intent in self.write_log.pending()? {
+            let emails = intent.emails();
+            let result = self
+                .db
+                .store_emails_with_derived(&emails, &intent.activity, &intent.receipts)
+                .await;
+            if let Err(e) = result {
+                warn!(
+                    "Failed to replay write log batch {} ({} emails): {}",
+                    intent.id,
+                    emails.len(),
+                    e
+                );
+                continue;
+            }
+            self.store_email_derived(&emails).await;
+            self.write_log.complete(&intent.id)?;
+            replayed += 1;
+        }
+        Ok(replayed)
+    }
 
-                            let emails_to_store: Vec<Email> =
-                                if let Some(embeddings) = embeddings_opt {
-                                    // Got embeddings - attach them to emails
-                                    chunk
-                                        .iter()
-                                        .zip(embeddings.into_iter())
-                                        .map(|(email, embedding)| {
-                                            let mut email = email.clone();
-                                            email.embedding = Some(embedding);
-                                            email
-                                        })
-                                        .collect()
-                                } else {
-                                    // No embeddings (BM25-only) - store without embeddings
-                                    chunk.to_vec()
-                                };
-
-                            self.db.upsert_emails(&emails_to_store).await?;
+    /// Stop starting syncs and wait up to `timeout` for batches being
+    /// written to land; returns whether they all did
+    ///
+    /// Syncs still fetching are left to be dropped with the daemon: their
+    /// batches wait here instead of writing, and the next run fetches them
+    /// again from the last checkpoint.
+    pub async fn drain(&self, timeout: std::time::Duration) -> bool {
+        self.shutting_down.store(true, Ordering::SeqCst);
+        match tokio::time::timeout(timeout, self.write_gate.write()).await {
+            Ok(guard) => {
+                // Held until the daemon exits
+                std::mem::forget(guard);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Persist each account's last sync times and the progress file, so
+    /// the next run resumes where this one stopped
+    pub async fn checkpoint(&self) -> Result<()> {
+        let states: Vec<AccountSyncState> = self.account_states.read().values().cloned().collect();
+        for state in &states {
+            let Some(mut account) = self.db.get_account(&state.account_id).await? else {
+                continue;
+            };
+            let newer = |synced: Option<DateTime<Utc>>, stored: Option<DateTime<Utc>>| {
+                synced.filter(|s| stored.is_none_or(|stored| *s > stored))
+            };
+            let email = newer(state.last_email_sync, account.last_sync_email);
+            let calendar = newer(state.last_calendar_sync, account.last_sync_calendar);
+            if email.is_none() && calendar.is_none() {
+                continue;
+            }
+            account.last_sync_email = email.or(account.last_sync_email);
+            account.last_sync_calendar = calendar.or(account.last_sync_calendar);
+            self.db.upsert_account(&account).await?;
+        }
+        self.write_progress_file();
+        Ok(())
+    }
+
+    /// Rebuild the threads that stored emails belong to
+    ///
+    /// Failures are logged: the emails are stored either way, and `email
+    /// thread` falls back to scanning emails for a stale thread.
+    async fn update_threads(&self, emails: &[Email]) {
+        if let Err(e) = self.refresh_threads(&thread_keys(emails)).await {
+            warn!("Failed to update threads: {}", e);
+        }
+    }
+
+    /// Rebuild, re-embed and store threads from their emails
+    async fn refresh_threads(&self, keys: &[(String, u64)]) -> Result<usize> {
+        let mut by_account: HashMap<&str, Vec<u64>> = HashMap::new();
+        for (account_id, thread_id) in keys {
+            by_account.entry(account_id).or_default().push(*thread_id);
+        }
+
+        let mut threads = Vec::with_capacity(keys.len());
+        for (account_id, thread_ids) in by_account {
+            let emails = self
+                .db
+                .get_emails_in_threads(account_id, &thread_ids)
+                .await?;
+            let mut by_thread: HashMap<u64, Vec<Email>> = HashMap::new();
+            for email in emails {
+                by_thread
+                    .entry(email.gmail_thread_id)
+                    .or_default()
+                    .push(email);
+            }
+            threads.extend(by_thread.values().filter_map(|emails| build_thread(emails)));
+        }
+
+        // Stored without vectors when embedding is unavailable (BM25-only)
+        let texts: Vec<String> = threads.iter().map(|t| t.content.clone()).collect();
+        match self.embedding.embed_batch(&texts).await {
+            Ok(Some(embeddings)) => {
+                for (thread, embedding) in threads.iter_mut().zip(embeddings) {
+                    thread.embedding = Some(embedding);
+                }
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Thread embedding failed: {}, storing without embeddings", e),
+        }
+
+        self.db.upsert_threads(&threads).await?;
+        Ok(threads.len())
+    }
+
+    /// Rebuild every thread of an account (or all accounts) from its emails
+    pub async fn rebuild_threads(&self, account_id: Option<&str>) -> Result<usize> {
+        let keys = self.db.list_thread_keys(account_id).await?;
+        let batch_size = self.config.search.effective_embedding_batch_size();
+        let mut count = 0;
+        for chunk in keys.chunks(batch_size) {
+            count += self.refresh_threads(chunk).await?;
+            debug!("Rebuilt {}/{} threads", count, keys.len());
+        }
+        Ok(count)
+    }
+
+    /// Build threads for emails synced before the threads table existed
+    pub async fn ensure_threads(&self) -> Result<()> {
+        if self.db.threads_table()?.count_rows(None).await? > 0
+            || self.db.emails_table()?.count_rows(None).await? == 0
+        {
+            return Ok(());
+        }
+        info!("Building conversation threads from synced emails");
+        let count = self.rebuild_threads(None).await?;
+        info!("Built {} threads", count);
+        Ok(())
+    }
+
+    /// Delete mail past an account's retention policy
+    ///
+    /// Removes the emails with their embeddings, then their downloaded
+    /// attachment files and stored raw copies, and rebuilds (or drops, when
+    /// emptied) the threads they were in. Nothing is deleted in Gmail.
+    pub async fn enforce_retention(&self, account_id: &str) -> Result<PruneReport> {
+        let account = self
+            .db
+            .get_account(account_id)
+            .await?
+            .ok_or_else(|| Error::AccountNotFound(account_id.to_string()))?;
+        let now = Utc::now();
+        let raw_dir = self.config.raw_dir();
+        let mut report = PruneReport::new(now);
+        let mut thread_ids = HashSet::new();
+
+        // Rules run in turn, so mail matched by an earlier rule is already gone
+        for (category, cutoff) in account.retention.cutoffs(now) {
+            let emails = self
+                .db
+                .list_emails_before(account_id, cutoff, category)
+                .await?;
+            if emails.is_empty() {
+                continue;
+            }
+            let ids: Vec<String> = emails.iter().map(|e| e.id.clone()).collect();
+            self.db.delete_emails(&ids).await?;
+
+            let rule = category.map_or("all", |c| c.as_str());
+            report.by_rule.insert(rule.to_string(), emails.len() as u64);
+            report.emails += emails.len() as u64;
+            for email in &emails {
+                report.email_bytes += email.raw_size;
+                thread_ids.insert(email.gmail_thread_id);
+                for attachment in email.attachments.iter().filter(|a| a.downloaded) {
+                    let Some(path) = &attachment.local_path else {
+                        continue;
+                    };
+                    match std::fs::remove_file(path) {
+                        Ok(()) => {
+                            report.attachments += 1;
+                            report.attachment_bytes += attachment.size;
                         }
-                        info!(
-                            "Incremental sync: stored {} emails for {}",
-                            emails.len(),
-                            account_id
-                        );
+                        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                        Err(e) => warn!("Failed to delete attachment {:?}: {}", path, e),
                     }
+                }
+                if std::fs::remove_file(raw_path(&raw_dir, email)).is_ok() {
+                    report.raw_copies += 1;
+                }
+            }
+        }
+
+        // Threads that still have mail are rebuilt; the rest are dropped
+        thread_ids.remove(&0);
+        if !thread_ids.is_empty() {
+            let thread_ids: Vec<u64> = thread_ids.into_iter().collect();
+            let remaining: HashSet<u64> = self
+                .db
+                .get_emails_in_threads(account_id, &thread_ids)
+                .await?
+                .iter()
+                .map(|e| e.gmail_thread_id)
+                .collect();
+            let (keep, drop): (Vec<u64>, Vec<u64>) = thread_ids
+                .into_iter()
+                .partition(|id| remaining.contains(id));
+            let drop: Vec<String> = drop
+                .into_iter()
+                .map(|id| Thread::id_for(account_id, id))
+                .collect();
+            self.db.delete_threads(&drop).await?;
+            let keep: Vec<(String, u64)> = keep
+                .into_iter()
+                .map(|id| (account_id.to_string(), id))
+                .collect();
+            if let Err(e) = self.refresh_threads(&keep).await {
+                warn!("Failed to rebuild threads after pruning: {}", e);
+            }
+        }
+
+        if report.emails > 0 {
+            info!("Retention pruned for {}: {}", account_id, report.summary());
+        } else {
+            debug!("Retention: nothing to prune for {}", account_id);
+        }
+        Ok(report)
+    }
+
+    /// Find reservations and packages in emails synced before the extractions
+    /// table existed
+    pub async fn ensure_extractions(&self) -> Result<()> {
+        if !self.config.extractions.enabled
+            || self.db.extractions_table()?.count_rows(None).await? > 0
+            || self.db.emails_table()?.count_rows(None).await? == 0
+        {
+            return Ok(());
+        }
+        const EXTRACTION_BACKFILL_BATCH: usize = 500;
+        info!("Extracting reservations and packages from synced emails");
+        let ids = self.db.list_ids(VectorTable::Emails).await?;
+        let mut count = 0;
+        for chunk in ids.chunks(EXTRACTION_BACKFILL_BATCH) {
+            let emails = self.db.get_emails_batch(chunk).await?;
+            let items = extract_all(&emails, &self.config.extractions, self.timezone());
+            self.db.upsert_extractions(&items).await?;
+            count += items.len();
+        }
+        info!("Extracted {} reservations and packages", count);
+        Ok(())
+    }
+
+    /// Find requests in recent emails synced before the tasks table existed
+    ///
+    /// Only the last month is read: older requests are done or forgotten.
+    pub async fn ensure_tasks(&self) -> Result<()> {
+        if !self.config.tasks.enabled
+            || self.db.tasks_table()?.count_rows(None).await? > 0
+            || self.db.emails_table()?.count_rows(None).await? == 0
+        {
+            return Ok(());
+        }
+        info!("Finding tasks in recent emails");
+        let emails = self
+            .db
+            .list_emails_since(None, Utc::now() - Duration::days(30))
+            .await?;
+        let tasks = extract_tasks(
+            &emails,
+            &self.config.tasks,
+            &self.own_addresses(),
+            self.timezone(),
+        );
+        self.db.upsert_tasks(&tasks).await?;
+        info!("Found {} tasks", tasks.len());
+        Ok(())
+    }
+
+    /// Fetch mail sent since `since` from the Sent folder
+    ///
+    /// Messages already stored (e.g. a copy sent to yourself that arrived in
+    /// INBOX) are skipped so they keep their folder and UID. The account's
+    /// label filter isn't applied: sent mail rarely carries the labels it
+    /// selects (an `INBOX` filter would match none of it).
+    async fn sync_sent_folder(
+        &self,
+        account_id: &str,
+        headers_only: bool,
+        since: DateTime<Utc>,
+    ) -> Result<usize> {
+        let imap_client = ImapClient::new(account_id, self.oauth.clone(), self.rate_limiter())
+            .await?
+            .with_folder(SENT_FOLDER)
+            .with_own_addresses(self.own_addresses())
+            .with_raw_store(self.raw_store())
+            .with_headers_only(headers_only);
+        let batch_size = self.config.search.effective_imap_fetch_batch_size();
+        let stored = std::sync::atomic::AtomicUsize::new(0);
+
+        imap_client
+            .fetch_all_emails_since(since, None, batch_size, |emails| {
+                let stored = &stored;
+                async move {
+                    let ids: Vec<String> = emails.iter().map(|e| e.id.clone()).collect();
+                    let existing: HashSet<String> = self
+                        .db
+                        .get_emails_batch(&ids)
+                        .await?
+                        .into_iter()
+                        .map(|e| e.id)
+                        .collect();
+                    let new_emails: Vec<Email> = emails
+                        .into_iter()
+                        .filter(|e| !existing.contains(&e.id))
+                        .collect();
+                    self.store_emails(&new_emails).await?;
+                    stored.fetch_add(new_emails.len(), std::sync::atomic::Ordering::Relaxed);
+                    Ok(())
+                }
+            })
+            .await?;
+
+        let stored = stored.into_inner();
+        if stored > 0 {
+            info!("Stored {} sent emails for {}", stored, account_id);
+        }
+        Ok(stored)
+    }
+
+    /// Send-as addresses configured under `[compose.identities]`
+    fn own_addresses(&self) -> Vec<String> {
+        self.config.compose.identities.keys().cloned().collect()
+    }
+
+    /// Where fetched message sources are kept, when `[sync] store_raw` is on
+    fn raw_store(&self) -> Option<std::path::PathBuf> {
+        self.config.sync.store_raw.then(|| self.config.raw_dir())
+    }
+
+    /// Compare every account's sync cursors with the mail and events
+    /// stored, fixing any that are off, and check full-text index coverage
+    ///
+    /// Run before syncing starts. Index rebuilds are only scheduled, and the
+    /// slower look for rows without embeddings is left to
+    /// [`SyncManager::repair`].
+    pub async fn check_consistency(&self) -> Result<RecoveryReport> {
+        let now = Utc::now();
+        let mut report = RecoveryReport::new(now);
+        for mut account in self.db.list_accounts().await? {
+            let emails = self.db.get_email_sync_boundaries(&account.id).await?;
+            let events = self.db.get_event_sync_boundaries(&account.id).await?;
+            let issues = check_cursors(&mut account, emails, events, now);
+            if !issues.is_empty() {
+                self.db.upsert_account(&account).await?;
+                report.issues.extend(issues);
+            }
+        }
+        report
+            .issues
+            .extend(check_fts(&self.db.fts_coverage().await?));
+        Ok(report)
+    }
 
-                    // Update state and persist to database
-                    let now = Utc::now();
-                    if let Some(state) = self.account_states.write().get_mut(account_id) {
-                        state.last_email_sync = Some(now);
+    /// Embed rows stored without an embedding, then run the full-text index
+    /// rebuild `report` scheduled, recording both in `report`
+    pub async fn repair(&self, report: &mut RecoveryReport) {
+        // BM25-only setups store every row without one
+        if self.embedding.can_embed() {
+            for table in [VectorTable::Emails, VectorTable::Events] {
+                let ids = match self.db.ids_without_embeddings(table).await {
+                    Ok(ids) => ids,
+                    Err(e) => {
+                        warn!("Failed to look for unembedded {}: {}", table.as_str(), e);
+                        continue;
                     }
-                    let should_sync_attachments;
-                    if let Ok(Some(mut account)) = self.db.get_account(account_id).await {
-                        should_sync_attachments = account.sync_attachments;
-                        account.last_sync_email = Some(now);
-                        if let Err(e) = self.db.upsert_account(&account).await {
-                            warn!("Failed to persist last_sync_email: {}", e);
-                        }
-                    } else {
-                        should_sync_attachments = false;
+                };
+                if ids.is_empty() {
+                    continue;
+                }
+                let status = match self.reembed_ids(table, &ids).await {
+                    Ok(count) => {
+                        info!("Embedded {} {} stored without one", count, table.as_str());
+                        RepairStatus::Repaired
                     }
-
-                    // Download attachments if enabled for this account
-                    if should_sync_attachments {
-                        if let Err(e) = self.download_attachments_for_account(account_id).await {
-                            warn!(
-                                "Failed to download attachments during incremental sync: {}",
-                                e
-                            );
-                        }
+                    Err(e) => {
+                        warn!("Failed to embed {}: {}", table.as_str(), e);
+                        RepairStatus::Failed
                     }
+                };
+                report.issues.push(Issue {
+                    account_id: None,
+                    kind: IssueKind::MissingEmbeddings,
+                    detail: format!("{} {} have no embedding", ids.len(), table.as_str()),
+                    action: "embed them".to_string(),
+                    status,
+                });
+            }
+        }
+
+        if report.has_scheduled() {
+            let status = match crate::fts::update_indexes(
+                &self.db,
+                crate::fts::FtsStrategy::Rebuild,
+                &self.config.index_state_file(),
+            )
+            .await
+            {
+                Ok(_) => RepairStatus::Repaired,
+                Err(e) => {
+                    warn!("Failed to rebuild full-text indexes: {}", e);
+                    RepairStatus::Failed
                 }
-                SyncType::Calendar => {
-                    self.sync_calendar(account_id).await?;
+            };
+            report.finish(
+                &[IssueKind::FtsIndexMissing, IssueKind::FtsIndexStale],
+                status,
+            );
+        }
+    }
+
+    /// Re-embed emails and/or events when the embedding model or their
+    /// `[embedding]` template changed since the stored vectors were computed
+    ///
+    /// A model with a different vector width gets a rebuilt embedding column
+    /// first (see [`Database::resize_vectors`]). What the vectors were
+    /// computed with is recorded only after a table is fully re-embedded, so
+    /// an interrupted run starts over on the next launch.
+    pub async fn reembed_if_changed(&self) -> Result<()> {
+        let current = &self.config.embedding;
+        current.validate()?;
+        let model = self.config.search.vector_model();
+        let dimension = self.config.search.vector_dimension();
+
+        // No state file means the vectors were computed with the defaults.
+        // A file without a model predates model tracking: trust the column
+        // width and adopt the configured model.
+        let path = self.config.embedding_state_path();
+        let mut stored: EmbeddingState = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        let model_changed = stored.model.as_ref().is_some_and(|m| *m != model);
+
+        for table in [VectorTable::Emails, VectorTable::Events] {
+            let resize = dimension.filter(|d| *d != self.db.vector_dimension(table));
+            let template_changed = match table {
+                VectorTable::Emails => stored.templates.email_template != current.email_template,
+                VectorTable::Events => {
+                    stored.templates.event_template != current.event_template
+                        || (current.event_template.is_none()
+                            && stored.event_text_version != DEFAULT_EVENT_TEXT_VERSION)
                 }
-                SyncType::All => {
-                    Box::pin(self.trigger_sync(&[account_id.clone()], SyncType::Email)).await?;
-                    Box::pin(self.trigger_sync(&[account_id.clone()], SyncType::Calendar)).await?;
+            };
+            if !model_changed && !template_changed && resize.is_none() {
+                continue;
+            }
+
+            if let Some(dimension) = resize {
+                warn!(
+                    "Embedding model {} produces {}-dimensional vectors but the {} table stores {}; rebuilding its vectors",
+                    model,
+                    dimension,
+                    table.as_str(),
+                    self.db.vector_dimension(table)
+                );
+                self.db.resize_vectors(table, dimension).await?;
+            } else if model_changed {
+                info!(
+                    "Embedding model changed to {}, re-embedding {}",
+                    model,
+                    table.as_str()
+                );
+            } else {
+                info!(
+                    "Embedding template for {} changed, re-embedding",
+                    table.as_str()
+                );
+            }
+            let count = self.reembed_table(table).await?;
+            info!("Re-embedded {} {}", count, table.as_str());
+            if table == VectorTable::Emails {
+                let count = self.rebuild_threads(None).await?;
+                info!("Rebuilt {} threads", count);
+            }
+
+            match table {
+                VectorTable::Emails => {
+                    stored.templates.email_template = current.email_template.clone()
+                }
+                VectorTable::Events => {
+                    stored.templates.event_template = current.event_template.clone();
+                    stored.event_text_version = DEFAULT_EVENT_TEXT_VERSION;
                 }
             }
+            std::fs::write(&path, serde_json::to_string_pretty(&stored)?)?;
+        }
+
+        if stored.model.as_deref() != Some(model.as_str()) {
+            stored.model = Some(model);
+            std::fs::write(&path, serde_json::to_string_pretty(&stored)?)?;
         }
         Ok(())
     }
 
+    /// Recompute every embedding in a table with the configured template
+    async fn reembed_table(&self, table: VectorTable) -> Result<usize> {
+        let ids = self.db.list_ids(table).await?;
+        self.reembed_ids(table, &ids).await
+    }
+
+    /// Recompute the embeddings of these rows with the configured template
+    async fn reembed_ids(&self, table: VectorTable, ids: &[String]) -> Result<usize> {
+        let batch_size = self.config.search.effective_embedding_batch_size();
+        let templates = &self.config.embedding;
+        let mut count = 0;
+
+        for chunk in ids.chunks(batch_size) {
+            match table {
+                VectorTable::Emails => {
+                    let mut emails = self.db.get_emails_batch(chunk).await?;
+                    let texts: Vec<String> =
+                        emails.iter().map(|e| templates.email_text(e)).collect();
+                    let Some(embeddings) = self.embedding.embed_batch(&texts).await? else {
+                        return Err(Error::Other(
+                            "No embedding provider available to re-embed with".to_string(),
+                        ));
+                    };
+                    for (email, embedding) in emails.iter_mut().zip(embeddings) {
+                        email.embedding = Some(embedding);
+                    }
+                    self.db.upsert_emails(&emails).await?;
+                    count += emails.len();
+                }
+                VectorTable::Events => {
+                    let mut events = self.db.get_events_batch(chunk).await?;
+                    let texts: Vec<String> =
+                        events.iter().map(|e| templates.event_text(e)).collect();
+                    let Some(embeddings) = self.embedding.embed_batch(&texts).await? else {
+                        return Err(Error::Other(
+                            "No embedding provider available to re-embed with".to_string(),
+                        ));
+                    };
+                    for (event, embedding) in events.iter_mut().zip(embeddings) {
+                        event.embedding = Some(embedding);
+                    }
+                    self.db.upsert_events(&events).await?;
+                    count += events.len();
+                }
+            }
+            debug!("Re-embedded {}/{} {}", count, ids.len(), table.as_str());
+        }
+
+        Ok(count)
+    }
+
+    /// The user's configured timezone (UTC if it doesn't parse)
+    fn timezone(&self) -> chrono_tz::Tz {
+        self.config
+            .general
+            .timezone
+            .parse()
+            .unwrap_or(chrono_tz::Tz::UTC)
+    }
+
+    /// Store reservations and package deliveries found in synced emails
+    ///
+    /// Failures are logged: the emails are stored either way.
+    async fn store_extractions(&self, emails: &[Email]) {
+        let items = extract_all(emails, &self.config.extractions, self.timezone());
+        if let Err(e) = self.db.upsert_extractions(&items).await {
+            warn!("Failed to store {} extractions: {}", items.len(), e);
+        }
+    }
+
+    /// Store requests found in synced emails
+    ///
+    /// Failures are logged: the emails are stored either way.
+    async fn store_tasks(&self, emails: &[Email]) {
+        let tasks = extract_tasks(
+            emails,
+            &self.config.tasks,
+            &self.own_addresses(),
+            self.timezone(),
+        );
+        if let Err(e) = self.db.upsert_tasks(&tasks).await {
+            warn!("Failed to store {} tasks: {}", tasks.len(), e);
+        }
+    }
+
+    /// Store itinerary items and, when enabled, add upcoming ones to the calendar
+    async fn store_itinerary(&self, items: &[ItineraryItem]) {
+        let stored = match self.db.upsert_itinerary(items).await {
+            Ok(stored) => stored,
+            Err(e) => {
+                warn!("Failed to store {} itinerary items: {}", items.len(), e);
+                return;
+            }
+        };
+        if !self.config.travel.create_events {
+            return;
+        }
+
+        // Items that already have an event keep it across re-syncs
+        let now = Utc::now();
+        let mut added = Vec::new();
+        for item in stored
+            .into_iter()
+            .filter(|item| item.event_id.is_none() && item.start > now)
+        {
+            let client =
+                match CalDavClient::new(&item.account_id, self.oauth.clone(), self.rate_limiter())
+                    .await
+                {
+                    Ok(client) => client,
+                    Err(e) => {
+                        warn!("Failed to create calendar client: {}", e);
+                        continue;
+                    }
+                };
+            let body = calendar_event_json(&item);
+            match client
+                .insert_event_json(&self.config.travel.calendar_id, &body)
+                .await
+            {
+                Ok(event_id) => {
+                    info!(
+                        "Added {} to the calendar of {}",
+                        item.title, item.account_id
+                    );
+                    added.push(ItineraryItem {
+                        event_id: Some(event_id),
+                        ..item
+                    });
+                }
+                Err(e) => warn!("Failed to add {} to the calendar: {}", item.title, e),
+            }
+        }
+
+        if let Err(e) = self.db.upsert_itinerary(&added).await {
+            warn!("Failed to record calendar events for itinerary: {}", e);
+        }
+    }
+
+    /// Record whether an account is over its storage limit
+    fn set_storage_quota_exceeded(&self, account_id: &str, exceeded: bool) {
+        if let Some(state) = self.account_states.write().get_mut(account_id) {
+            state.storage_quota_exceeded = exceeded;
+        }
+    }
+
     /// Emit a sync event
     async fn emit_event(&self, event: SyncEvent) {
+        metrics::record_sync_event(&event);
         if let Err(e) = self.event_tx.send(event).await {
             warn!("Failed to send sync event: {}", e);
         }
@@ -5,6 +5,8 @@ use std::num::NonZeroU32;
 use std::sync::Arc;
 use tracing::debug;
 
+use crate::metrics;
+
 /// Global rate limiter for all Google API requests
 pub struct GlobalRateLimiter {
     limiter: Arc<
@@ -32,6 +34,7 @@ impl GlobalRateLimiter {
     /// Wait until a request is allowed
     pub async fn wait(&self) {
         self.limiter.until_ready().await;
+        metrics::record_api_request();
         debug!("Rate limiter: request allowed");
     }
 
@@ -0,0 +1,52 @@
+//! Server-side Gmail search for mail that isn't synced locally
+//!
+//! `email remote-search --gmail-query` passes a Gmail search query (the same
+//! syntax as the Gmail search box, e.g. `from:foo has:attachment
+//! newer_than:7d`) to the Gmail API, fetches each hit and parses it the way
+//! sync does, so results look like local search results and can be imported.
+
+use std::sync::Arc;
+
+use reqwest::Client;
+use tracing::debug;
+
+use crate::config::Config;
+use crate::error::Result;
+use crate::models::Email;
+use crate::oauth::OAuthManager;
+
+use super::bodies::{fetch_gmail_raw, list_gmail_ids, parse_gmail_message};
+
+/// Most messages one remote search fetches per account
+pub const REMOTE_SEARCH_MAX_RESULTS: usize = 100;
+
+/// Run a Gmail search query for an account, newest first
+///
+/// Each hit is downloaded in full, so `limit` is capped at
+/// [`REMOTE_SEARCH_MAX_RESULTS`].
+pub async fn search_gmail(
+    config: &Config,
+    oauth: Arc<OAuthManager>,
+    account_id: &str,
+    query: &str,
+    limit: usize,
+) -> Result<Vec<Email>> {
+    let access_token = oauth.get_valid_token(account_id).await?;
+    let client = Client::new();
+
+    let limit = limit.min(REMOTE_SEARCH_MAX_RESULTS);
+    let ids = list_gmail_ids(&client, &access_token, query, limit).await?;
+    debug!(
+        "Gmail search {:?} matched {} messages in {}",
+        query,
+        ids.len(),
+        account_id
+    );
+
+    let mut emails = Vec::with_capacity(ids.len());
+    for gmail_id in &ids {
+        let message = fetch_gmail_raw(&client, &access_token, gmail_id).await?;
+        emails.push(parse_gmail_message(config, oauth.clone(), account_id, &message).await?);
+    }
+    Ok(emails)
+}
@@ -0,0 +1,249 @@
+//! Stalled sync detection
+//!
+//! The sync manager reports to a [`SyncActivity`] when each account's sync
+//! starts, makes progress and finishes, and when its IMAP IDLE connection
+//! is (re)established or wakes. The daemon's watchdog calls
+//! [`SyncActivity::check`] on every heartbeat, which turns three kinds of
+//! stuck account into [`SyncIncident`]s: a sync with no progress for the
+//! stall timeout, an IDLE connection that stopped renewing, and syncs that
+//! keep failing with the same error. The daemon then cancels and restarts
+//! that account's sync and records the incident for `doctor` and
+//! `daemon status --health`.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Duration, Utc};
+
+use super::IDLE_TIMEOUT_SECS;
+use crate::health::{StallKind, SyncIncident};
+
+/// Longest error message kept in an incident
+const MAX_ERROR_CHARS: usize = 200;
+
+/// What the watchdog knows about one account
+#[derive(Debug, Clone, Default)]
+struct AccountActivity {
+    /// When the running sync started (None while no sync runs)
+    sync_started: Option<DateTime<Utc>>,
+
+    /// Last progress from the running sync (a fetched batch)
+    last_progress: Option<DateTime<Utc>>,
+
+    /// Last time IDLE connected or woke (None while not idling)
+    idle_alive: Option<DateTime<Utc>>,
+
+    /// Error the latest syncs failed with
+    last_error: Option<String>,
+
+    /// Syncs in a row that failed with `last_error`
+    error_repeats: u32,
+}
+
+/// Liveness of every account's sync and IDLE connection
+#[derive(Debug, Default)]
+pub struct SyncActivity {
+    accounts: BTreeMap<String, AccountActivity>,
+}
+
+impl SyncActivity {
+    /// A sync for the account started
+    pub fn sync_started(&mut self, account_id: &str, now: DateTime<Utc>) {
+        let account = self.account(account_id);
+        account.sync_started = Some(now);
+        account.last_progress = Some(now);
+    }
+
+    /// The running sync made progress
+    pub fn progress(&mut self, account_id: &str, now: DateTime<Utc>) {
+        self.account(account_id).last_progress = Some(now);
+    }
+
+    /// The sync finished, failing with `error` if set
+    pub fn sync_finished(&mut self, account_id: &str, error: Option<&str>) {
+        let account = self.account(account_id);
+        account.sync_started = None;
+        account.last_progress = None;
+        match error {
+            Some(error) if account.last_error.as_deref() == Some(error) => {
+                account.error_repeats += 1;
+            }
+            Some(error) => {
+                account.last_error = Some(error.to_string());
+                account.error_repeats = 1;
+            }
+            None => {
+                account.last_error = None;
+                account.error_repeats = 0;
+            }
+        }
+    }
+
+    /// The account's IDLE connection connected or woke up
+    pub fn idle_alive(&mut self, account_id: &str, now: DateTime<Utc>) {
+        self.account(account_id).idle_alive = Some(now);
+    }
+
+    /// The account's IDLE connection was closed on purpose
+    pub fn idle_stopped(&mut self, account_id: &str) {
+        self.account(account_id).idle_alive = None;
+    }
+
+    /// Accounts stuck as of `now`: a sync without progress for `stall_after`,
+    /// an IDLE connection not renewed within its timeout plus `stall_after`,
+    /// or `error_repeats` failures in a row with the same error (0 turns
+    /// that check off)
+    ///
+    /// Each stall is reported once; its clock restarts afterwards.
+    pub fn check(
+        &mut self,
+        stall_after: Duration,
+        error_repeats: u32,
+        now: DateTime<Utc>,
+    ) -> Vec<SyncIncident> {
+        let minutes = |d: Duration| d.num_minutes().max(1);
+        let mut incidents = Vec::new();
+        for (account_id, account) in &mut self.accounts {
+            let mut report = |kind, detail| {
+                incidents.push(SyncIncident {
+                    at: now,
+                    account_id: account_id.clone(),
+                    kind,
+                    detail,
+                })
+            };
+
+            if let Some(last) = account.sync_started.and(account.last_progress) {
+                if now - last >= stall_after {
+                    report(
+                        StallKind::NoProgress,
+                        format!("sync made no progress for {} minutes", minutes(now - last)),
+                    );
+                    account.last_progress = Some(now);
+                }
+            }
+
+            if let Some(alive) = account.idle_alive {
+                if now - alive >= Duration::seconds(IDLE_TIMEOUT_SECS as i64) + stall_after {
+                    report(
+                        StallKind::IdleWedged,
+                        format!("IMAP IDLE not renewed for {} minutes", minutes(now - alive)),
+                    );
+                    account.idle_alive = Some(now);
+                }
+            }
+
+            if error_repeats > 0 && account.error_repeats >= error_repeats {
+                let error: String = account
+                    .last_error
+                    .as_deref()
+                    .unwrap_or_default()
+                    .chars()
+                    .take(MAX_ERROR_CHARS)
+                    .collect();
+                report(
+                    StallKind::RepeatedError,
+                    format!(
+                        "{} syncs in a row failed with: {}",
+                        account.error_repeats, error
+                    ),
+                );
+                account.error_repeats = 0;
+            }
+        }
+        incidents
+    }
+
+    fn account(&mut self, account_id: &str) -> &mut AccountActivity {
+        self.accounts.entry(account_id.to_string()).or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn kinds(incidents: &[SyncIncident]) -> Vec<(&str, StallKind)> {
+        incidents
+            .iter()
+            .map(|i| (i.account_id.as_str(), i.kind))
+            .collect()
+    }
+
+    #[test]
+    fn test_detects_sync_without_progress() {
+        let start = Utc.with_ymd_and_hms(2024, 6, 3, 9, 0, 0).unwrap();
+        let stall = Duration::minutes(30);
+        let mut activity = SyncActivity::default();
+        activity.sync_started("a@example.com", start);
+        activity.progress("a@example.com", start + Duration::minutes(20));
+        assert!(activity
+            .check(stall, 5, start + Duration::minutes(45))
+            .is_empty());
+
+        let incidents = activity.check(stall, 5, start + Duration::minutes(50));
+        assert_eq!(
+            kinds(&incidents),
+            vec![("a@example.com", StallKind::NoProgress)]
+        );
+        assert_eq!(incidents[0].detail, "sync made no progress for 30 minutes");
+
+        // Reported once, and not at all once the sync is over
+        assert!(activity
+            .check(stall, 5, start + Duration::minutes(51))
+            .is_empty());
+        activity.sync_finished("a@example.com", None);
+        assert!(activity
+            .check(stall, 5, start + Duration::days(1))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_detects_wedged_idle() {
+        let start = Utc.with_ymd_and_hms(2024, 6, 3, 9, 0, 0).unwrap();
+        let stall = Duration::minutes(30);
+        let mut activity = SyncActivity::default();
+        activity.idle_alive("a@example.com", start);
+        activity.idle_alive("b@example.com", start);
+        activity.idle_stopped("b@example.com");
+
+        // An IDLE wait lasts up to its timeout, so that much silence is fine
+        let renewed = start + Duration::seconds(IDLE_TIMEOUT_SECS as i64);
+        assert!(activity.check(stall, 5, renewed).is_empty());
+
+        let incidents = activity.check(stall, 5, renewed + stall);
+        assert_eq!(
+            kinds(&incidents),
+            vec![("a@example.com", StallKind::IdleWedged)]
+        );
+    }
+
+    #[test]
+    fn test_detects_repeated_identical_errors() {
+        let now = Utc.with_ymd_and_hms(2024, 6, 3, 9, 0, 0).unwrap();
+        let stall = Duration::minutes(30);
+        let mut activity = SyncActivity::default();
+        for error in ["timeout", "timeout", "connection reset", "timeout"] {
+            activity.sync_finished("a@example.com", Some(error));
+        }
+        assert!(activity.check(stall, 3, now).is_empty());
+
+        activity.sync_finished("a@example.com", Some("timeout"));
+        activity.sync_finished("a@example.com", Some("timeout"));
+        assert!(activity.check(stall, 0, now).is_empty());
+        let incidents = activity.check(stall, 3, now);
+        assert_eq!(
+            kinds(&incidents),
+            vec![("a@example.com", StallKind::RepeatedError)]
+        );
+        assert_eq!(incidents[0].detail, "3 syncs in a row failed with: timeout");
+        assert!(activity.check(stall, 3, now).is_empty());
+
+        // A success resets the count
+        activity.sync_finished("a@example.com", Some("timeout"));
+        activity.sync_finished("a@example.com", None);
+        activity.sync_finished("a@example.com", Some("timeout"));
+        activity.sync_finished("a@example.com", Some("timeout"));
+        assert!(activity.check(stall, 3, now).is_empty());
+    }
+}
@@ -0,0 +1,185 @@
+//! Write-ahead intent log for email batches
+//!
+//! Storing a batch of synced emails takes several writes: the emails with
+//! their activity items and receipts (one transaction), then the itinerary
+//! items, extractions, tasks and threads derived from them. The
+//! transaction's rollback lives in memory, so a crash part way through used
+//! to leave messages half indexed. Before the first write, the batch is
+//! recorded as `write_log/<id>.json` under the data directory, and the
+//! record is removed once the last write lands. Every write replaces rows by
+//! ID, so replaying a leftover record on the next start
+//! ([`super::SyncManager::recover_writes`]) finishes the batch without
+//! adding duplicates.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::error::Result;
+use crate::models::{ActivityItem, Email, Receipt};
+
+/// A batch of emails about to be written
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmailBatchIntent {
+    /// Derived from the batch's email IDs, so retrying a batch reuses its record
+    pub id: String,
+
+    pub created_at: DateTime<Utc>,
+
+    pub emails: Vec<Email>,
+
+    /// Each email's embedding, which `Email` doesn't serialize
+    pub embeddings: Vec<Option<Vec<f32>>>,
+
+    pub activity: Vec<ActivityItem>,
+
+    pub receipts: Vec<Receipt>,
+}
+
+impl EmailBatchIntent {
+    pub fn new(emails: &[Email], activity: &[ActivityItem], receipts: &[Receipt]) -> Self {
+        let mut hasher = DefaultHasher::new();
+        for email in emails {
+            email.id.hash(&mut hasher);
+        }
+        Self {
+            id: format!("{:016x}", hasher.finish()),
+            created_at: Utc::now(),
+            emails: emails.to_vec(),
+            embeddings: emails.iter().map(|e| e.embedding.clone()).collect(),
+            activity: activity.to_vec(),
+            receipts: receipts.to_vec(),
+        }
+    }
+
+    /// The emails with their embeddings restored
+    pub fn emails(&self) -> Vec<Email> {
+        self.emails
+            .iter()
+            .zip(&self.embeddings)
+            .map(|(email, embedding)| {
+                let mut email = email.clone();
+                email.embedding = embedding.clone();
+                email
+            })
+            .collect()
+    }
+}
+
+/// The directory of batches not yet fully written
+#[derive(Debug, Clone)]
+pub struct WriteLog {
+    dir: PathBuf,
+}
+
+impl WriteLog {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Record a batch before writing it; the record is flushed to disk
+    /// before this returns and replaces any earlier one for the same batch
+    pub fn record(&self, intent: &EmailBatchIntent) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.path(&intent.id);
+        let tmp = path.with_extension("json.tmp");
+        let mut file = std::fs::File::create(&tmp)?;
+        file.write_all(&serde_json::to_vec(intent)?)?;
+        file.sync_all()?;
+        std::fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    /// The batch is fully written
+    pub fn complete(&self, id: &str) -> Result<()> {
+        match std::fs::remove_file(self.path(id)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Batches recorded but never completed, oldest first
+    ///
+    /// Records that can't be read are removed: a record only becomes
+    /// visible once it's complete, so those are half-written temp files or
+    /// damaged ones that can't be replayed anyway.
+    pub fn pending(&self) -> Result<Vec<EmailBatchIntent>> {
+        if !self.dir.exists() {
+            return Ok(vec![]);
+        }
+        let mut intents = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            match read_intent(&path) {
+                Some(intent) => intents.push(intent),
+                None => {
+                    warn!("Discarding unreadable write log record {:?}", path);
+                    let _ = std::fs::remove_file(&path);
+                }
+            }
+        }
+        intents.sort_by_key(|i| i.created_at);
+        Ok(intents)
+    }
+
+    fn path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+}
+
+fn read_intent(path: &Path) -> Option<EmailBatchIntent> {
+    if path.extension().and_then(|e| e.to_str()) != Some("json") {
+        return None;
+    }
+    serde_json::from_slice(&std::fs::read(path).ok()?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Address;
+
+    fn email(id: &str, embedding: Option<Vec<f32>>) -> Email {
+        Email {
+            id: id.to_string(),
+            to: vec![Address::new("me@example.com")],
+            body_plain: "Hi".to_string(),
+            embedding,
+            ..Email::test(&format!("<{}@x>", id), "bob@acme.io", "Hello")
+        }
+    }
+
+    #[test]
+    fn test_records_survive_until_completed() {
+        let dir = std::env::temp_dir().join(format!("ge-write-log-{}", uuid::Uuid::new_v4()));
+        let log = WriteLog::new(dir.clone());
+        assert!(log.pending().unwrap().is_empty());
+
+        let batch = [email("a", Some(vec![0.5, 0.25])), email("b", None)];
+        let intent = EmailBatchIntent::new(&batch, &[], &[]);
+        log.record(&intent).unwrap();
+        // A retry of the same batch replaces the record
+        log.record(&EmailBatchIntent::new(&batch, &[], &[]))
+            .unwrap();
+        std::fs::write(dir.join("torn.json.tmp"), "{\"id\":").unwrap();
+
+        let pending = log.pending().unwrap();
+        assert_eq!(pending.len(), 1);
+        let emails = pending[0].emails();
+        assert_eq!(emails[0].id, "a");
+        assert_eq!(emails[0].embedding, Some(vec![0.5, 0.25]));
+        assert_eq!(emails[1].embedding, None);
+        assert!(!dir.join("torn.json.tmp").exists());
+
+        log.complete(&intent.id).unwrap();
+        log.complete(&intent.id).unwrap();
+        assert!(log.pending().unwrap().is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
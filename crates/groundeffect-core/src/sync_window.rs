@@ -0,0 +1,484 @@
+//! When the daemon may sync: quiet hours, battery and Wi-Fi
+//!
+//! `daemon.toml` can stop syncing during quiet hours, slow it down or stop
+//! it while the machine runs on battery, and hold attachment downloads
+//! until it's on Wi-Fi or a wired network (not a phone hotspot over USB or
+//! Bluetooth, or mobile broadband). Every [`POLICY_CHECK_SECS`] the daemon
+//! reads the clock, power source and network, turns them into a
+//! [`PolicyState`] with [`SyncSchedule::evaluate`], applies it to the sync
+//! manager and writes it to `sync_policy.json` for `daemon status`.
+//!
+//! While paused, IMAP IDLE stays connected but the syncs it and the poll
+//! timers ask for are skipped; the first one after the pause catches up.
+
+use std::path::Path;
+use std::process::Command;
+
+use chrono::{DateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+use crate::config::DaemonConfig;
+use crate::error::{Error, Result};
+
+/// How often the daemon re-checks the schedule, power and network
+pub const POLICY_CHECK_SECS: u64 = 60;
+
+/// What the daemon does while on battery
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BatteryPolicy {
+    /// Sync as on AC power
+    #[default]
+    Normal,
+    /// Sync fewer accounts at once (`battery_max_concurrent_fetches`)
+    Throttle,
+    /// Don't sync until back on AC power
+    Pause,
+}
+
+/// Where the machine draws power from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PowerSource {
+    Ac,
+    Battery,
+    /// Not checked, or no way to tell (treated as AC)
+    Unknown,
+}
+
+/// The kind of network the default route goes through
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NetworkKind {
+    Wifi,
+    Wired,
+    /// A phone over USB or Bluetooth, or a mobile broadband modem
+    Metered,
+    /// Not checked, or no way to tell (treated as Wi-Fi)
+    Unknown,
+}
+
+/// A daily local time range, which may wrap past midnight
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuietHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl QuietHours {
+    /// Parse "HH:MM-HH:MM", e.g. "22:00-07:00"
+    pub fn parse(value: &str) -> Option<Self> {
+        let (start, end) = value.split_once('-')?;
+        let start = NaiveTime::parse_from_str(start.trim(), "%H:%M").ok()?;
+        let end = NaiveTime::parse_from_str(end.trim(), "%H:%M").ok()?;
+        (start != end).then_some(Self { start, end })
+    }
+
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start < self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+impl std::fmt::Display for QuietHours {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}-{}",
+            self.start.format("%H:%M"),
+            self.end.format("%H:%M")
+        )
+    }
+}
+
+/// The scheduling settings from `daemon.toml`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SyncSchedule {
+    pub quiet_hours: Option<QuietHours>,
+    pub on_battery: BatteryPolicy,
+    pub battery_max_concurrent_fetches: usize,
+    pub attachments_wifi_only: bool,
+}
+
+impl SyncSchedule {
+    pub fn from_config(config: &DaemonConfig) -> Result<Self> {
+        let quiet_hours = match config.quiet_hours.as_deref().map(str::trim) {
+            None | Some("") => None,
+            Some(value) => Some(
+                QuietHours::parse(value).ok_or_else(|| Error::InvalidConfig {
+                    field: "daemon.quiet_hours".to_string(),
+                    reason: format!("'{}' is not a range like 22:00-07:00", value),
+                })?,
+            ),
+        };
+        Ok(Self {
+            quiet_hours,
+            on_battery: config.on_battery,
+            battery_max_concurrent_fetches: config.battery_max_concurrent_fetches.max(1),
+            attachments_wifi_only: config.attachments_wifi_only,
+        })
+    }
+
+    /// Whether the power source matters
+    pub fn watches_power(&self) -> bool {
+        self.on_battery != BatteryPolicy::Normal
+    }
+
+    /// Whether the network kind matters
+    pub fn watches_network(&self) -> bool {
+        self.attachments_wifi_only
+    }
+
+    /// Read the clock in `timezone`, and the power source and network if
+    /// the schedule depends on them, and evaluate. Runs `pmset` or
+    /// `networksetup` on macOS, so call it off the async runtime.
+    pub fn check(&self, timezone: &str, now: DateTime<Utc>) -> PolicyState {
+        let tz: Tz = timezone.parse().unwrap_or(Tz::UTC);
+        let power = if self.watches_power() {
+            detect_power()
+        } else {
+            PowerSource::Unknown
+        };
+        let network = if self.watches_network() {
+            detect_network()
+        } else {
+            NetworkKind::Unknown
+        };
+        self.evaluate(now.with_timezone(&tz).time(), power, network, now)
+    }
+
+    /// What the daemon may do at local time `local`
+    pub fn evaluate(
+        &self,
+        local: NaiveTime,
+        power: PowerSource,
+        network: NetworkKind,
+        now: DateTime<Utc>,
+    ) -> PolicyState {
+        let in_quiet_hours = self.quiet_hours.is_some_and(|q| q.contains(local));
+        let on_battery = power == PowerSource::Battery;
+
+        let sync_paused = if in_quiet_hours {
+            self.quiet_hours.map(|q| format!("quiet hours ({})", q))
+        } else if on_battery && self.on_battery == BatteryPolicy::Pause {
+            Some("on battery".to_string())
+        } else {
+            None
+        };
+        let max_concurrent_fetches = (on_battery && self.on_battery == BatteryPolicy::Throttle)
+            .then_some(self.battery_max_concurrent_fetches);
+        let attachments_paused = if sync_paused.is_some() {
+            sync_paused.clone()
+        } else if self.attachments_wifi_only && network == NetworkKind::Metered {
+            Some("not on Wi-Fi".to_string())
+        } else {
+            None
+        };
+
+        PolicyState {
+            checked_at: now,
+            power,
+            network,
+            sync_paused,
+            max_concurrent_fetches,
+            attachments_paused,
+        }
+    }
+}
+
+/// What the schedule allows right now, as last applied by the daemon
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PolicyState {
+    pub checked_at: DateTime<Utc>,
+
+    pub power: PowerSource,
+
+    pub network: NetworkKind,
+
+    /// Why syncing is paused, if it is
+    pub sync_paused: Option<String>,
+
+    /// Account syncs allowed at once while throttled on battery
+    pub max_concurrent_fetches: Option<usize>,
+
+    /// Why attachment downloads are held, if they are
+    pub attachments_paused: Option<String>,
+}
+
+impl PolicyState {
+    /// Read the state the daemon last wrote, if any
+    pub fn load(path: &Path) -> Option<Self> {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+    }
+
+    /// Write the state to disk
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, json)
+    }
+
+    /// One line, e.g. "syncing paused: quiet hours (22:00-07:00)"
+    pub fn summary(&self) -> String {
+        if let Some(reason) = &self.sync_paused {
+            return format!("syncing paused: {}", reason);
+        }
+        let mut parts = Vec::new();
+        if let Some(fetches) = self.max_concurrent_fetches {
+            parts.push(format!("throttled to {} at once on battery", fetches));
+        }
+        if let Some(reason) = &self.attachments_paused {
+            parts.push(format!("attachments held: {}", reason));
+        }
+        if parts.is_empty() {
+            "syncing normally".to_string()
+        } else {
+            parts.join("; ")
+        }
+    }
+}
+
+/// The machine's current power source
+pub fn detect_power() -> PowerSource {
+    if cfg!(target_os = "macos") {
+        Command::new("pmset")
+            .args(["-g", "batt"])
+            .output()
+            .ok()
+            .map(|out| parse_pmset(&String::from_utf8_lossy(&out.stdout)))
+            .unwrap_or(PowerSource::Unknown)
+    } else {
+        linux_power(Path::new("/sys/class/power_supply"))
+    }
+}
+
+/// `pmset -g batt` output: "Now drawing from 'AC Power'"
+fn parse_pmset(output: &str) -> PowerSource {
+    if output.contains("'AC Power'") {
+        PowerSource::Ac
+    } else if output.contains("'Battery Power'") {
+        PowerSource::Battery
+    } else {
+        PowerSource::Unknown
+    }
+}
+
+/// On AC if any mains supply is online, else on battery if there is one
+fn linux_power(dir: &Path) -> PowerSource {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return PowerSource::Unknown;
+    };
+    let read = |path: &Path, name: &str| {
+        std::fs::read_to_string(path.join(name))
+            .map(|s| s.trim().to_string())
+            .unwrap_or_default()
+    };
+    let mut battery = false;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        match read(&path, "type").as_str() {
+            "Mains" | "USB" if read(&path, "online") == "1" => return PowerSource::Ac,
+            "Battery" => battery = true,
+            _ => {}
+        }
+    }
+    if battery {
+        PowerSource::Battery
+    } else {
+        PowerSource::Unknown
+    }
+}
+
+/// The kind of network the default route currently uses
+pub fn detect_network() -> NetworkKind {
+    if cfg!(target_os = "macos") {
+        let run = |program: &str, args: &[&str]| {
+            Command::new(program)
+                .args(args)
+                .output()
+                .ok()
+                .map(|out| String::from_utf8_lossy(&out.stdout).into_owned())
+        };
+        run("route", &["-n", "get", "default"])
+            .as_deref()
+            .and_then(parse_route_interface)
+            .and_then(|interface| {
+                run("networksetup", &["-listallhardwareports"])
+                    .as_deref()
+                    .and_then(|ports| hardware_port(ports, &interface))
+            })
+            .map(|port| classify_port(&port))
+            .unwrap_or(NetworkKind::Unknown)
+    } else {
+        linux_network()
+    }
+}
+
+/// The "interface:" line of `route -n get default`
+fn parse_route_interface(output: &str) -> Option<String> {
+    output.lines().find_map(|line| {
+        line.trim()
+            .strip_prefix("interface:")
+            .map(|i| i.trim().to_string())
+    })
+}
+
+/// The "Hardware Port" name `networksetup -listallhardwareports` gives the
+/// device `interface`
+fn hardware_port(listing: &str, interface: &str) -> Option<String> {
+    let mut port = None;
+    for line in listing.lines() {
+        if let Some(name) = line.strip_prefix("Hardware Port:") {
+            port = Some(name.trim().to_string());
+        } else if line.strip_prefix("Device:").map(str::trim) == Some(interface) {
+            return port;
+        }
+    }
+    None
+}
+
+fn classify_port(port: &str) -> NetworkKind {
+    let port = port.to_lowercase();
+    if port == "wi-fi" || port == "airport" {
+        NetworkKind::Wifi
+    } else if port.contains("iphone") || port.contains("bluetooth") || port.contains("modem") {
+        NetworkKind::Metered
+    } else if port.contains("ethernet") || port.contains("thunderbolt") || port.contains("lan") {
+        NetworkKind::Wired
+    } else {
+        NetworkKind::Unknown
+    }
+}
+
+fn linux_network() -> NetworkKind {
+    let Ok(routes) = std::fs::read_to_string("/proc/net/route") else {
+        return NetworkKind::Unknown;
+    };
+    let Some(interface) = routes.lines().skip(1).find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let interface = fields.next()?;
+        (fields.next()? == "00000000").then(|| interface.to_string())
+    }) else {
+        return NetworkKind::Unknown;
+    };
+    if Path::new("/sys/class/net")
+        .join(&interface)
+        .join("wireless")
+        .exists()
+    {
+        NetworkKind::Wifi
+    } else if ["ww", "usb", "rndis", "bnep"]
+        .iter()
+        .any(|prefix| interface.starts_with(prefix))
+    {
+        NetworkKind::Metered
+    } else if interface.starts_with("en") || interface.starts_with("eth") {
+        NetworkKind::Wired
+    } else {
+        NetworkKind::Unknown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(value: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(value, "%H:%M").unwrap()
+    }
+
+    fn schedule(quiet_hours: Option<&str>, on_battery: BatteryPolicy) -> SyncSchedule {
+        SyncSchedule {
+            quiet_hours: quiet_hours.and_then(QuietHours::parse),
+            on_battery,
+            battery_max_concurrent_fetches: 2,
+            attachments_wifi_only: true,
+        }
+    }
+
+    #[test]
+    fn test_quiet_hours_wrap_past_midnight() {
+        let night = QuietHours::parse("22:00-07:00").unwrap();
+        assert!(night.contains(time("23:30")));
+        assert!(night.contains(time("06:59")));
+        assert!(!night.contains(time("07:00")));
+        assert!(!night.contains(time("12:00")));
+
+        let lunch = QuietHours::parse(" 12:00 - 13:00 ").unwrap();
+        assert!(lunch.contains(time("12:30")));
+        assert!(!lunch.contains(time("23:00")));
+        assert_eq!(lunch.to_string(), "12:00-13:00");
+
+        assert_eq!(QuietHours::parse("22:00"), None);
+        assert_eq!(QuietHours::parse("25:00-07:00"), None);
+        assert_eq!(QuietHours::parse("09:00-09:00"), None);
+    }
+
+    #[test]
+    fn test_evaluate_policy() {
+        let now = Utc::now();
+        let night = schedule(Some("22:00-07:00"), BatteryPolicy::Pause);
+        let state = night.evaluate(time("23:00"), PowerSource::Ac, NetworkKind::Wifi, now);
+        assert_eq!(
+            state.sync_paused.as_deref(),
+            Some("quiet hours (22:00-07:00)")
+        );
+        assert_eq!(state.attachments_paused, state.sync_paused);
+
+        let state = night.evaluate(time("10:00"), PowerSource::Battery, NetworkKind::Wifi, now);
+        assert_eq!(state.summary(), "syncing paused: on battery");
+        let state = night.evaluate(time("10:00"), PowerSource::Unknown, NetworkKind::Wifi, now);
+        assert_eq!(state.summary(), "syncing normally");
+
+        let throttle = schedule(None, BatteryPolicy::Throttle);
+        let state = throttle.evaluate(
+            time("10:00"),
+            PowerSource::Battery,
+            NetworkKind::Metered,
+            now,
+        );
+        assert_eq!(state.sync_paused, None);
+        assert_eq!(state.max_concurrent_fetches, Some(2));
+        assert_eq!(
+            state.summary(),
+            "throttled to 2 at once on battery; attachments held: not on Wi-Fi"
+        );
+    }
+
+    #[test]
+    fn test_parses_platform_output() {
+        assert_eq!(
+            parse_pmset("Now drawing from 'Battery Power'\n -InternalBattery-0 85%"),
+            PowerSource::Battery
+        );
+        assert_eq!(
+            parse_pmset("Now drawing from 'AC Power'\n"),
+            PowerSource::Ac
+        );
+
+        let route = "   route to: default\ndestination: default\n  interface: en7\n";
+        assert_eq!(parse_route_interface(route).as_deref(), Some("en7"));
+        let ports = "\nHardware Port: Wi-Fi\nDevice: en0\nEthernet Address: aa\n\n\
+                     Hardware Port: iPhone USB\nDevice: en7\nEthernet Address: bb\n";
+        let port = hardware_port(ports, "en7").unwrap();
+        assert_eq!(port, "iPhone USB");
+        assert_eq!(classify_port(&port), NetworkKind::Metered);
+        assert_eq!(
+            classify_port(&hardware_port(ports, "en0").unwrap()),
+            NetworkKind::Wifi
+        );
+        assert_eq!(
+            classify_port("Thunderbolt Ethernet Slot 1"),
+            NetworkKind::Wired
+        );
+        assert_eq!(hardware_port(ports, "en9"), None);
+    }
+}
@@ -0,0 +1,334 @@
+//! Local tagging rules
+//!
+//! `[[tagging.rules]]` entries in config.toml tag incoming mail during sync:
+//! by sender domain, by keywords in the subject or body, or by semantic
+//! similarity to example emails. A rule matches when every condition it sets
+//! matches, and can also override the email's category. Tags are stored with
+//! the email, independent of Gmail labels, and `email search --tag` filters
+//! on them.
+
+use tracing::{debug, warn};
+
+use crate::classify::cosine_similarity;
+use crate::config::{TagRule, TaggingConfig};
+use crate::embedding::HybridEmbeddingProvider;
+use crate::error::Result;
+use crate::models::{Email, EmailCategory};
+
+/// Canonical form of a tag: lowercase, with characters other than letters,
+/// digits, space, `-`, `_`, `.` and `/` removed
+pub fn normalize_tag(tag: &str) -> String {
+    tag.trim()
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_' | '.' | '/'))
+        .collect()
+}
+
+/// A rule with its tag normalized and example emails embedded
+struct CompiledRule {
+    tag: String,
+    category: Option<EmailCategory>,
+    domains: Vec<String>,
+    keywords: Vec<String>,
+    examples: Vec<Vec<f32>>,
+    wants_examples: bool,
+    min_similarity: f32,
+}
+
+impl CompiledRule {
+    fn new(rule: &TagRule, examples: Vec<Vec<f32>>) -> Self {
+        Self {
+            tag: normalize_tag(&rule.tag),
+            category: rule.category,
+            domains: rule
+                .from_domains
+                .iter()
+                .map(|d| d.trim().trim_start_matches('@').to_lowercase())
+                .filter(|d| !d.is_empty())
+                .collect(),
+            keywords: rule
+                .keywords
+                .iter()
+                .map(|k| k.trim().to_lowercase())
+                .filter(|k| !k.is_empty())
+                .collect(),
+            examples,
+            wants_examples: rule.similar_to.iter().any(|t| !t.trim().is_empty()),
+            min_similarity: rule.min_similarity,
+        }
+    }
+
+    fn matches(&self, email: &Email) -> bool {
+        if !self.domains.is_empty() {
+            let domain = email
+                .from
+                .email
+                .rsplit_once('@')
+                .map(|(_, d)| d.to_lowercase())
+                .unwrap_or_default();
+            if !self
+                .domains
+                .iter()
+                .any(|d| domain == *d || domain.ends_with(&format!(".{}", d)))
+            {
+                return false;
+            }
+        }
+
+        if !self.keywords.is_empty() {
+            let text = format!("{}\n{}", email.subject, email.body_plain).to_lowercase();
+            if !self.keywords.iter().any(|k| contains_phrase(&text, k)) {
+                return false;
+            }
+        }
+
+        if self.wants_examples {
+            // Without embeddings a similarity condition can't be checked
+            let Some(embedding) = email.embedding.as_deref() else {
+                return false;
+            };
+            if !self
+                .examples
+                .iter()
+                .any(|e| cosine_similarity(embedding, e) >= self.min_similarity)
+            {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// Whether `phrase` occurs in `text` on word boundaries
+fn contains_phrase(text: &str, phrase: &str) -> bool {
+    text.match_indices(phrase).any(|(start, _)| {
+        let before = text[..start].chars().next_back();
+        let after = text[start + phrase.len()..].chars().next();
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
+
+/// The configured rules, ready to apply
+#[derive(Default)]
+pub struct TagRules {
+    rules: Vec<CompiledRule>,
+}
+
+impl TagRules {
+    /// Compile the configured rules, embedding their example emails
+    ///
+    /// Rules without a tag or without conditions are skipped. When
+    /// embeddings are unavailable, rules with `similar_to` never match.
+    pub async fn new(config: &TaggingConfig, embedding: &HybridEmbeddingProvider) -> Result<Self> {
+        let texts: Vec<String> = config
+            .rules
+            .iter()
+            .flat_map(|r| r.similar_to.iter())
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty())
+            .collect();
+
+        let mut vectors = if texts.is_empty() {
+            Vec::new()
+        } else {
+            match embedding.embed_batch(&texts).await? {
+                Some(vectors) => vectors,
+                None => {
+                    debug!("Embeddings unavailable, similar_to tagging rules won't match");
+                    Vec::new()
+                }
+            }
+        }
+        .into_iter();
+
+        Ok(Self::from_examples(
+            config
+                .rules
+                .iter()
+                .map(|rule| {
+                    let count = rule
+                        .similar_to
+                        .iter()
+                        .filter(|t| !t.trim().is_empty())
+                        .count();
+                    (rule.clone(), vectors.by_ref().take(count).collect())
+                })
+                .collect(),
+        ))
+    }
+
+    /// Build from rules and their precomputed example vectors
+    pub fn from_examples(rules: Vec<(TagRule, Vec<Vec<f32>>)>) -> Self {
+        Self {
+            rules: rules
+                .iter()
+                .filter(|(rule, _)| is_usable(rule))
+                .map(|(rule, examples)| CompiledRule::new(rule, examples.clone()))
+                .collect(),
+        }
+    }
+
+    /// Whether there are no rules to apply
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Tags of the rules an email matches, and the category set by the
+    /// first of them that sets one
+    pub fn evaluate(&self, email: &Email) -> (Vec<String>, Option<EmailCategory>) {
+        let mut tags: Vec<String> = Vec::new();
+        let mut category = None;
+        for rule in self.rules.iter().filter(|r| r.matches(email)) {
+            if !tags.contains(&rule.tag) {
+                tags.push(rule.tag.clone());
+            }
+            category = category.or(rule.category);
+        }
+        (tags, category)
+    }
+
+    /// Set the tags of each email, and its category where a rule sets one
+    pub fn apply(&self, emails: &mut [Email]) {
+        for email in emails.iter_mut() {
+            let (tags, category) = self.evaluate(email);
+            email.tags = tags;
+            if category.is_some() {
+                email.category = category;
+            }
+        }
+    }
+}
+
+/// A rule needs a tag and at least one condition
+fn is_usable(rule: &TagRule) -> bool {
+    if normalize_tag(&rule.tag).is_empty() {
+        warn!("Skipping tagging rule without a tag");
+        return false;
+    }
+    let has_condition = [&rule.from_domains, &rule.keywords, &rule.similar_to]
+        .iter()
+        .any(|values| values.iter().any(|v| !v.trim().is_empty()));
+    if !has_condition {
+        warn!(
+            "Skipping tagging rule '{}': set from_domains, keywords or similar_to",
+            rule.tag
+        );
+    }
+    has_condition
+}
+
+/// Build the rules, logging instead of failing sync
+pub async fn build_tag_rules(
+    config: &TaggingConfig,
+    embedding: &HybridEmbeddingProvider,
+) -> TagRules {
+    match TagRules::new(config, embedding).await {
+        Ok(rules) => rules,
+        Err(e) => {
+            warn!("Failed to build tagging rules: {}", e);
+            TagRules::from_examples(
+                config
+                    .rules
+                    .iter()
+                    .map(|rule| (rule.clone(), Vec::new()))
+                    .collect(),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Address;
+
+    fn email(from: &str, subject: &str, body: &str) -> Email {
+        Email {
+            category: Some(EmailCategory::Personal),
+            to: vec![Address::new("me@example.com")],
+            body_plain: body.to_string(),
+            ..Email::test(&format!("<{}>", subject), from, subject)
+        }
+    }
+
+    fn rule(tag: &str) -> TagRule {
+        TagRule {
+            tag: tag.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_domain_and_keyword_conditions() {
+        let rules = TagRules::from_examples(vec![
+            (
+                TagRule {
+                    from_domains: vec!["@acme.io".to_string()],
+                    ..rule("Clients")
+                },
+                vec![],
+            ),
+            (
+                TagRule {
+                    from_domains: vec!["acme.io".to_string()],
+                    keywords: vec!["contract".to_string()],
+                    category: Some(EmailCategory::Transactional),
+                    ..rule("legal")
+                },
+                vec![],
+            ),
+        ]);
+
+        let mut emails = vec![
+            email("bob@eu.acme.io", "Contract renewal", "See attached."),
+            email("bob@acme.io", "Lunch", "Are contractors invited?"),
+            email("bob@notacme.io", "Contract", ""),
+        ];
+        rules.apply(&mut emails);
+        assert_eq!(emails[0].tags, vec!["clients", "legal"]);
+        assert_eq!(emails[0].category, Some(EmailCategory::Transactional));
+        assert_eq!(emails[1].tags, vec!["clients"]);
+        assert_eq!(emails[1].category, Some(EmailCategory::Personal));
+        assert!(emails[2].tags.is_empty());
+    }
+
+    #[test]
+    fn test_similarity_condition() {
+        let rules = TagRules::from_examples(vec![(
+            TagRule {
+                similar_to: vec!["Your pull request was merged".to_string()],
+                min_similarity: 0.9,
+                ..rule("code review")
+            },
+            vec![vec![1.0, 0.0]],
+        )]);
+
+        let mut close = email("a@b.com", "x", "");
+        close.embedding = Some(vec![0.95, 0.1]);
+        let mut far = email("a@b.com", "y", "");
+        far.embedding = Some(vec![0.5, 0.5]);
+        let unembedded = email("a@b.com", "z", "");
+
+        assert_eq!(rules.evaluate(&close).0, vec!["code review"]);
+        assert!(rules.evaluate(&far).0.is_empty());
+        assert!(rules.evaluate(&unembedded).0.is_empty());
+    }
+
+    #[test]
+    fn test_unusable_rules_are_skipped() {
+        let rules = TagRules::from_examples(vec![
+            (rule("empty"), vec![]),
+            (
+                TagRule {
+                    keywords: vec!["x".to_string()],
+                    ..rule("  ")
+                },
+                vec![],
+            ),
+        ]);
+        assert!(rules.is_empty());
+        assert_eq!(normalize_tag(" Client's \"VIP\" "), "clients vip");
+    }
+}
@@ -0,0 +1,306 @@
+//! Action item extraction
+//!
+//! Finds requests made of the account owner in inbound mail ("can you send
+//! the deck by Friday?", "please review the contract") and guesses a due
+//! date from the wording. Only mail that expects a reply is read (see
+//! [`crate::unanswered`]), and quoted replies and signatures are skipped so a
+//! request is recorded once, from the message that made it.
+//!
+//! The phrasing rules are deliberately narrow. An MCP client that has read
+//! the email can replace them with its own list (`set_email_tasks`).
+
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc, Weekday};
+use chrono_tz::Tz;
+use regex::Regex;
+
+use crate::config::TasksConfig;
+use crate::models::{Email, Task, TaskSource, TaskStatus};
+use crate::render::compact_body;
+use crate::unanswered::expects_reply;
+
+/// Most tasks recorded from one email
+const MAX_TASKS_PER_EMAIL: usize = 5;
+
+/// Shortest and longest sentence taken as a request (chars)
+const MIN_REQUEST_CHARS: usize = 12;
+const MAX_REQUEST_CHARS: usize = 240;
+
+/// Polite phrases that don't ask for anything
+const NON_REQUEST_HINTS: &[&str] = &[
+    "please find",
+    "please see attached",
+    "please see below",
+    "please let me know if you have",
+    "let me know if you have any",
+    "please don't hesitate",
+    "please do not hesitate",
+    "please feel free",
+    "please note",
+    "please ignore",
+    "please do not reply",
+    "please don't reply",
+    "can you believe",
+    "could you imagine",
+];
+
+/// Lines that end the message body (everything after is sign-off)
+const SIGN_OFFS: &[&str] = &[
+    "thanks",
+    "thank you",
+    "thanks!",
+    "best",
+    "best,",
+    "cheers",
+    "regards",
+    "best regards",
+    "kind regards",
+];
+
+/// Requests made of the account owner in an email
+///
+/// `own_addresses` are send-as addresses besides the account's own;
+/// `tz` is the user's timezone, which relative due dates are read in.
+pub fn extract_email_tasks(email: &Email, own_addresses: &[String], tz: Tz) -> Vec<Task> {
+    if !expects_reply(email, own_addresses) {
+        return Vec::new();
+    }
+
+    let mut tasks: Vec<Task> = Vec::new();
+    for sentence in sentences(&compact_body(&email.resolved_body())) {
+        if tasks.len() == MAX_TASKS_PER_EMAIL {
+            break;
+        }
+        if !is_request(&sentence) || tasks.iter().any(|t| t.text == sentence) {
+            continue;
+        }
+        let due = guess_due(&sentence, email.date, tz);
+        tasks.push(Task {
+            id: Task::id_for(&email.id, TaskSource::Heuristic, tasks.len()),
+            email_id: email.id.clone(),
+            account_id: email.account_id.clone(),
+            requester: email.from.to_string_full(),
+            subject: email.subject.clone(),
+            due: due.as_ref().map(|(date, _)| *date),
+            due_text: due.map(|(_, text)| text),
+            status: TaskStatus::Open,
+            source: TaskSource::Heuristic,
+            created_at: email.date,
+            completed_at: None,
+            text: sentence,
+        });
+    }
+    tasks
+}
+
+/// Requests made of the account owner in a batch of synced emails
+pub fn extract_tasks(
+    emails: &[Email],
+    config: &TasksConfig,
+    own_addresses: &[String],
+    tz: Tz,
+) -> Vec<Task> {
+    if !config.enabled {
+        return Vec::new();
+    }
+    emails
+        .iter()
+        .flat_map(|email| extract_email_tasks(email, own_addresses, tz))
+        .collect()
+}
+
+/// Sentences of the body's own text, up to the sign-off
+fn sentences(body: &str) -> Vec<String> {
+    let mut paragraphs: Vec<String> = vec![String::new()];
+    for line in body.lines().map(str::trim) {
+        // Elided quotes and signatures ("[quoted text: 4 lines]")
+        if line.starts_with('[') && line.ends_with(']') {
+            continue;
+        }
+        if SIGN_OFFS.contains(&line.to_lowercase().trim_end_matches(',')) {
+            break;
+        }
+        if line.is_empty() {
+            paragraphs.push(String::new());
+        } else {
+            let paragraph = paragraphs.last_mut().expect("never empty");
+            if !paragraph.is_empty() {
+                paragraph.push(' ');
+            }
+            paragraph.push_str(line);
+        }
+    }
+
+    let mut out = Vec::new();
+    for paragraph in &paragraphs {
+        let mut start = 0;
+        let chars: Vec<(usize, char)> = paragraph.char_indices().collect();
+        for (i, &(pos, c)) in chars.iter().enumerate() {
+            let at_break = matches!(c, '.' | '!' | '?')
+                && chars
+                    .get(i + 1)
+                    .is_none_or(|(_, next)| next.is_whitespace());
+            if at_break {
+                out.push(paragraph[start..pos + c.len_utf8()].trim().to_string());
+                start = pos + c.len_utf8();
+            }
+        }
+        out.push(paragraph[start..].trim().to_string());
+    }
+    out.retain(|s| !s.is_empty());
+    out
+}
+
+/// Whether a sentence asks the reader to do something
+fn is_request(sentence: &str) -> bool {
+    static REQUEST: OnceLock<Regex> = OnceLock::new();
+    let request = REQUEST.get_or_init(|| {
+        Regex::new(
+            r"(?i)\b(?:can|could|would|will) you\b|\bplease\b|\b(?:i|we) need you to\b|\bmake sure (?:to|you)\b|\b(?:don't|do not) forget to\b|\bremember to\b",
+        )
+        .unwrap()
+    });
+    let length = sentence.chars().count();
+    if !(MIN_REQUEST_CHARS..=MAX_REQUEST_CHARS).contains(&length) {
+        return false;
+    }
+    let lower = sentence.to_lowercase();
+    request.is_match(sentence) && !NON_REQUEST_HINTS.iter().any(|h| lower.contains(h))
+}
+
+/// A due date in a request, and the words it was read from
+///
+/// Weekdays mean the next one after the email was sent ("by Friday" on a
+/// Friday is a week out). Month-day dates more than two months before the
+/// email are taken to be next year's.
+pub fn guess_due(sentence: &str, sent: DateTime<Utc>, tz: Tz) -> Option<(NaiveDate, String)> {
+    static DUE: OnceLock<Regex> = OnceLock::new();
+    let due = DUE.get_or_init(|| {
+        Regex::new(
+            r"(?i)\b(?:(?:by|before|until|due|on|no later than)\s+)?(?:(?:the\s+)?(?P<rel>today|tonight|eod|end of (?:the )?day|tomorrow|eow|end of (?:the )?week|next week)|(?:next\s+)?(?P<weekday>monday|tuesday|wednesday|thursday|friday|saturday|sunday)|(?P<month>jan|feb|mar|apr|may|jun|jul|aug|sep|sept|oct|nov|dec)[a-z]*\.?\s+(?P<day>\d{1,2})(?:st|nd|rd|th)?|(?P<m>\d{1,2})/(?P<d>\d{1,2}))\b",
+        )
+        .unwrap()
+    });
+    let caps = due.captures(sentence)?;
+    let today = sent.with_timezone(&tz).date_naive();
+
+    let date = if let Some(rel) = caps.name("rel") {
+        match rel.as_str().to_lowercase().as_str() {
+            "tomorrow" => today + Duration::days(1),
+            "eow" | "end of week" | "end of the week" => next_weekday(today, Weekday::Fri, true),
+            "next week" => next_weekday(today, Weekday::Mon, false),
+            _ => today,
+        }
+    } else if let Some(weekday) = caps.name("weekday") {
+        next_weekday(today, weekday.as_str().parse().ok()?, false)
+    } else {
+        let (month, day) = match (caps.name("month"), caps.name("day")) {
+            (Some(month), Some(day)) => (
+                month_number(&month.as_str().to_lowercase())?,
+                day.as_str().parse().ok()?,
+            ),
+            _ => (caps["m"].parse().ok()?, caps["d"].parse().ok()?),
+        };
+        let this_year = NaiveDate::from_ymd_opt(today.year(), month, day)?;
+        if this_year < today - Duration::days(60) {
+            NaiveDate::from_ymd_opt(today.year() + 1, month, day)?
+        } else {
+            this_year
+        }
+    };
+    Some((date, caps[0].trim().to_string()))
+}
+
+/// The next `weekday` after `from` (or on it, with `inclusive`)
+fn next_weekday(from: NaiveDate, weekday: Weekday, inclusive: bool) -> NaiveDate {
+    let ahead = (weekday.num_days_from_monday() + 7 - from.weekday().num_days_from_monday()) % 7;
+    let ahead = if ahead == 0 && !inclusive { 7 } else { ahead };
+    from + Duration::days(ahead as i64)
+}
+
+fn month_number(name: &str) -> Option<u32> {
+    const MONTHS: [&str; 12] = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ];
+    MONTHS
+        .iter()
+        .position(|m| name.starts_with(m))
+        .map(|i| i as u32 + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Address;
+    use chrono::TimeZone;
+
+    fn email(body: &str) -> Email {
+        Email {
+            gmail_thread_id: 7,
+            from: Address::with_name("Bob Stone", "bob@acme.io"),
+            to: vec![Address::new("me@example.com")],
+            // A Wednesday
+            date: Utc.with_ymd_and_hms(2024, 6, 5, 15, 0, 0).unwrap(),
+            body_plain: body.to_string(),
+            ..Email::test("<1@x>", "bob@acme.io", "Board meeting")
+        }
+    }
+
+    #[test]
+    fn test_extracts_requests_with_due_dates() {
+        let email = email(
+            "Hi,\n\nGreat call today. Can you send the deck by Friday? \
+             Please review the budget before June 20.\n\
+             Please find attached the notes.\n\nThanks,\nBob\n\n\
+             On Mon, Jun 3, 2024 at 9:00 AM Me <me@example.com> wrote:\n\
+             > Could you share the agenda?",
+        );
+        let tasks = extract_email_tasks(&email, &[], Tz::UTC);
+
+        let texts: Vec<&str> = tasks.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec![
+                "Can you send the deck by Friday?",
+                "Please review the budget before June 20."
+            ]
+        );
+        assert_eq!(tasks[0].due, NaiveDate::from_ymd_opt(2024, 6, 7));
+        assert_eq!(tasks[0].due_text.as_deref(), Some("by Friday"));
+        assert_eq!(tasks[1].due, NaiveDate::from_ymd_opt(2024, 6, 20));
+        assert_eq!(tasks[0].id, "me@example.com:<1@x>#task0");
+        assert_eq!(tasks[0].requester, "Bob Stone <bob@acme.io>");
+        assert_eq!(tasks[0].status, TaskStatus::Open);
+    }
+
+    #[test]
+    fn test_skips_mail_not_addressed_to_me() {
+        let mut cc_only = email("Can you send the deck by Friday?");
+        cc_only.to = vec![Address::new("team@acme.io")];
+        assert!(extract_email_tasks(&cc_only, &[], Tz::UTC).is_empty());
+
+        let mut sent = email("Can you send the deck by Friday?");
+        sent.is_sent = true;
+        assert!(extract_email_tasks(&sent, &[], Tz::UTC).is_empty());
+    }
+
+    #[test]
+    fn test_guess_due() {
+        let sent = Utc.with_ymd_and_hms(2024, 6, 5, 15, 0, 0).unwrap();
+        let due = |s: &str| guess_due(s, sent, Tz::UTC).map(|(date, _)| date.to_string());
+
+        assert_eq!(due("send it by tomorrow").as_deref(), Some("2024-06-06"));
+        assert_eq!(due("by EOD please").as_deref(), Some("2024-06-05"));
+        assert_eq!(due("before end of week").as_deref(), Some("2024-06-07"));
+        assert_eq!(due("sometime next week").as_deref(), Some("2024-06-10"));
+        // "Wednesday" on a Wednesday is a week out
+        assert_eq!(due("by Wednesday").as_deref(), Some("2024-06-12"));
+        assert_eq!(due("due 1/15").as_deref(), Some("2025-01-15"));
+        assert_eq!(due("no rush"), None);
+
+        // Relative dates are read in the user's timezone (already Thursday in Tokyo)
+        let tokyo = guess_due("by tomorrow", sent, chrono_tz::Asia::Tokyo).unwrap();
+        assert_eq!(tokyo.0.to_string(), "2024-06-07");
+    }
+}
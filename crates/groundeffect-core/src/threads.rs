@@ -0,0 +1,236 @@
+//! Conversation threads built from synced email
+//!
+//! Messages sharing a Gmail thread ID (X-GM-THRID, which unlike a UID is
+//! the same in INBOX and Sent) are summarized into one [`Thread`]:
+//! participants, message and unread counts, first and last activity, and the
+//! text its embedding is computed from. That text holds each message once,
+//! with quoted replies and repeated signatures collapsed, so a long
+//! back-and-forth isn't dominated by the same paragraphs quoted over and over.
+
+use chrono::{DateTime, Utc};
+
+use crate::models::{Address, Email, Thread};
+use crate::render::ThreadCompactor;
+
+/// Distinct (account, Gmail thread ID) pairs of some emails
+///
+/// Emails without a thread ID (0: it couldn't be fetched, or the email was
+/// stored before thread IDs were) don't belong to a thread.
+pub fn thread_keys(emails: &[Email]) -> Vec<(String, u64)> {
+    let mut keys: Vec<(String, u64)> = emails
+        .iter()
+        .filter(|e| e.gmail_thread_id != 0)
+        .map(|e| (e.account_id.clone(), e.gmail_thread_id))
+        .collect();
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+/// Summarize the messages of one thread (in any order)
+///
+/// Returns None for an empty slice. The embedding is left unset.
+pub fn build_thread(emails: &[Email]) -> Option<Thread> {
+    let mut messages: Vec<&Email> = emails.iter().collect();
+    messages.sort_by_key(|e| e.date);
+    let first = *messages.first()?;
+    let last = *messages.last()?;
+
+    let subject = messages
+        .iter()
+        .map(|e| e.subject.trim())
+        .find(|s| !s.is_empty())
+        .unwrap_or_default()
+        .to_string();
+
+    let mut participants: Vec<Address> = Vec::new();
+    for email in &messages {
+        let addresses = std::iter::once(&email.from)
+            .chain(&email.to)
+            .chain(&email.cc);
+        for address in addresses {
+            add_participant(&mut participants, address);
+        }
+    }
+
+    let content = thread_text(&subject, &participants, &messages);
+    Some(Thread {
+        id: Thread::id_for(&first.account_id, first.gmail_thread_id),
+        account_id: first.account_id.clone(),
+        gmail_thread_id: first.gmail_thread_id,
+        subject,
+        participants,
+        message_count: messages.len() as u32,
+        unread_count: messages.iter().filter(|e| !e.is_read()).count() as u32,
+        first_date: first.date,
+        last_activity: last.date,
+        last_from: last.from.clone(),
+        snippet: last.snippet.clone(),
+        has_attachments: messages.iter().any(|e| e.has_attachments()),
+        has_sent: messages.iter().any(|e| e.is_sent),
+        email_ids: messages.iter().map(|e| e.id.clone()).collect(),
+        content,
+        embedding: None,
+    })
+}
+
+/// Add an address unless it's already listed; a later display name fills
+/// in one that was missing
+fn add_participant(participants: &mut Vec<Address>, address: &Address) {
+    if address.email.is_empty() {
+        return;
+    }
+    match participants
+        .iter_mut()
+        .find(|p| p.email.eq_ignore_ascii_case(&address.email))
+    {
+        Some(existing) => {
+            if existing.name.is_none() {
+                existing.name = address.name.clone();
+            }
+        }
+        None => participants.push(address.clone()),
+    }
+}
+
+/// Text embedded and indexed for a thread: subject, participants, then each
+/// message (oldest first) with quoted text and repeated signatures elided
+fn thread_text(subject: &str, participants: &[Address], messages: &[&Email]) -> String {
+    let names: Vec<String> = participants.iter().map(|p| p.to_string_full()).collect();
+    let mut compactor = ThreadCompactor::new();
+    let bodies: Vec<String> = messages
+        .iter()
+        .map(|e| {
+            format!(
+                "From: {} ({}):\n{}",
+                e.from.to_string_full(),
+                format_date(e.date),
+                compactor.compact(&e.markdown_body()).trim()
+            )
+        })
+        .collect();
+
+    // Long threads keep their start and their latest messages
+    format!(
+        "{}. Participants: {}.\n\n{}",
+        subject,
+        names.join(", "),
+        Email::embedding_body_excerpt(&bodies.join("\n\n"))
+    )
+}
+
+fn format_date(date: DateTime<Utc>) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn email(id: &str, from: Address, to: Vec<Address>, day: u32, body: &str) -> Email {
+        let base = Email::test(id, &from.email, "Re: Q3 pricing");
+        Email {
+            gmail_thread_id: 42,
+            is_sent: from.email == "me@example.com",
+            flags: vec!["\\Seen".to_string()],
+            from,
+            to,
+            date: Utc.with_ymd_and_hms(2024, 6, day, 9, 0, 0).unwrap(),
+            body_plain: body.to_string(),
+            snippet: body.lines().next().unwrap_or_default().to_string(),
+            ..base
+        }
+    }
+
+    fn conversation() -> Vec<Email> {
+        let bob = Address::with_name("Bob Stone", "bob@acme.io");
+        let me = Address::new("me@example.com");
+        let mut reply = email(
+            "<2@x>",
+            me.clone(),
+            vec![Address::new("bob@acme.io")],
+            5,
+            "Sounds good, I'll send the contract Friday.\n\nOn Mon, Bob Stone wrote:\n> Can we do 10% off for annual?\n> Thanks",
+        );
+        reply.flags.clear();
+        vec![
+            reply,
+            email(
+                "<1@x>",
+                bob,
+                vec![me],
+                3,
+                "Can we do 10% off for annual?\nThanks",
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_build_thread_summarizes_conversation() {
+        let thread = build_thread(&conversation()).unwrap();
+
+        assert_eq!(thread.id, "me@example.com:42");
+        assert_eq!(thread.message_count, 2);
+        assert_eq!(thread.unread_count, 1);
+        assert!(thread.has_sent);
+        assert_eq!(
+            thread.email_ids,
+            vec!["me@example.com:<1@x>", "me@example.com:<2@x>"]
+        );
+        assert_eq!(thread.last_from.email, "me@example.com");
+        assert!(thread.snippet.starts_with("Sounds good"));
+
+        // Bob's name is kept though the reply addressed him without it
+        let participants: Vec<String> = thread
+            .participants
+            .iter()
+            .map(|p| p.to_string_full())
+            .collect();
+        assert_eq!(
+            participants,
+            vec!["Bob Stone <bob@acme.io>", "me@example.com"]
+        );
+    }
+
+    #[test]
+    fn test_thread_text_elides_quoted_replies() {
+        let thread = build_thread(&conversation()).unwrap();
+
+        assert!(thread
+            .content
+            .starts_with("Re: Q3 pricing. Participants: Bob Stone <bob@acme.io>, me@example.com."));
+        assert_eq!(thread.content.matches("10% off for annual").count(), 1);
+        assert!(thread.content.contains("I'll send the contract Friday"));
+        assert!(thread.content.contains("[quoted text"));
+    }
+
+    #[test]
+    fn test_thread_keys_skip_unthreaded() {
+        let mut emails = conversation();
+        emails[0].gmail_thread_id = 0;
+        emails.push(emails[1].clone());
+        assert_eq!(
+            thread_keys(&emails),
+            vec![("me@example.com".to_string(), 42)]
+        );
+    }
+
+    #[test]
+    fn test_thread_keys_ignore_folder_uids() {
+        // An INBOX message and an unrelated sent one with the same UID
+        let mut emails = conversation();
+        emails[0].uid = 7;
+        emails[0].folder = "[Gmail]/Sent Mail".to_string();
+        emails[0].gmail_thread_id = 43;
+        emails[1].uid = 7;
+        assert_eq!(
+            thread_keys(&emails),
+            vec![
+                ("me@example.com".to_string(), 42),
+                ("me@example.com".to_string(), 43)
+            ]
+        );
+        assert_eq!(build_thread(&emails[1..]).unwrap().message_count, 1);
+    }
+}
@@ -0,0 +1,328 @@
+//! OAuth token health checks and re-auth notifications
+//!
+//! A revoked refresh token only shows up when a sync fails: the account is
+//! flipped to NeedsReauth and sync stops, with nothing telling the user. The
+//! daemon refreshes each account's token periodically and records the outcome
+//! (including why it failed) in a status file, which `groundeffect doctor`
+//! and the MCP server read. When an account newly needs re-authentication a
+//! desktop notification can be posted.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Command;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::error::Error;
+use crate::oauth::OAuthManager;
+
+/// Result of checking one account's tokens
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenCheck {
+    /// Tokens refreshed successfully
+    Ok,
+    /// The user has to re-authenticate (reason)
+    NeedsReauth(String),
+    /// The check couldn't complete, e.g. no network (reason)
+    Failed(String),
+}
+
+/// Token state recorded for an account
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenState {
+    Ok,
+    NeedsReauth,
+    /// Last check failed for a transient reason; the previous state is unknown
+    Unknown,
+}
+
+/// Last token check for one account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountTokenHealth {
+    pub state: TokenState,
+
+    /// Why the last check failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+
+    /// When the last check ran
+    pub checked_at: DateTime<Utc>,
+
+    /// Last successful check
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_ok: Option<DateTime<Utc>>,
+
+    /// When the account started needing re-authentication
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub needs_reauth_since: Option<DateTime<Utc>>,
+
+    /// Whether the user has been notified about the current failure
+    #[serde(default)]
+    pub notified: bool,
+}
+
+/// Token health for all accounts, persisted by the daemon
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TokenHealthReport {
+    pub accounts: BTreeMap<String, AccountTokenHealth>,
+}
+
+impl TokenHealthReport {
+    /// Load the report from disk (default if missing or unreadable)
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// Write the report to disk
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, json)
+    }
+
+    /// Record a check. Returns true if the account newly needs
+    /// re-authentication and the user hasn't been notified yet.
+    pub fn record(&mut self, account_id: &str, check: &TokenCheck, now: DateTime<Utc>) -> bool {
+        let previous = self.accounts.remove(account_id);
+        let last_ok = previous.as_ref().and_then(|p| p.last_ok);
+        let was_failing = previous
+            .as_ref()
+            .is_some_and(|p| p.state == TokenState::NeedsReauth);
+
+        let health = match check {
+            TokenCheck::Ok => AccountTokenHealth {
+                state: TokenState::Ok,
+                reason: None,
+                checked_at: now,
+                last_ok: Some(now),
+                needs_reauth_since: None,
+                notified: false,
+            },
+            TokenCheck::NeedsReauth(reason) => AccountTokenHealth {
+                state: TokenState::NeedsReauth,
+                reason: Some(reason.clone()),
+                checked_at: now,
+                last_ok,
+                needs_reauth_since: previous
+                    .as_ref()
+                    .and_then(|p| p.needs_reauth_since)
+                    .filter(|_| was_failing)
+                    .or(Some(now)),
+                notified: was_failing && previous.as_ref().is_some_and(|p| p.notified),
+            },
+            // Keep a known failure rather than masking it with a network error
+            TokenCheck::Failed(reason) => match previous {
+                Some(previous) if was_failing => AccountTokenHealth {
+                    checked_at: now,
+                    ..previous
+                },
+                _ => AccountTokenHealth {
+                    state: TokenState::Unknown,
+                    reason: Some(reason.clone()),
+                    checked_at: now,
+                    last_ok,
+                    needs_reauth_since: None,
+                    notified: false,
+                },
+            },
+        };
+
+        let notify = health.state == TokenState::NeedsReauth && !health.notified;
+        self.accounts.insert(account_id.to_string(), health);
+        notify
+    }
+
+    /// Mark the current failure of an account as notified
+    pub fn mark_notified(&mut self, account_id: &str) {
+        if let Some(health) = self.accounts.get_mut(account_id) {
+            health.notified = true;
+        }
+    }
+
+    /// Accounts whose last check said they need re-authentication
+    pub fn needs_reauth(&self) -> impl Iterator<Item = (&String, &AccountTokenHealth)> {
+        self.accounts
+            .iter()
+            .filter(|(_, health)| health.state == TokenState::NeedsReauth)
+    }
+
+    /// Forget an account (e.g. after it was deleted)
+    pub fn remove(&mut self, account_id: &str) {
+        self.accounts.remove(account_id);
+    }
+}
+
+/// Refresh an account's access token to check that its credentials still work
+pub async fn check_account_token(oauth: &OAuthManager, account_id: &str) -> TokenCheck {
+    match oauth.refresh_token(account_id).await {
+        Ok(_) => {
+            debug!("Token check passed for {}", account_id);
+            TokenCheck::Ok
+        }
+        Err(e) => {
+            let check = classify_token_error(&e);
+            warn!("Token check for {}: {:?}", account_id, check);
+            check
+        }
+    }
+}
+
+/// Decide whether a token error needs the user to re-authenticate
+pub fn classify_token_error(error: &Error) -> TokenCheck {
+    match error {
+        Error::TokenExpired { .. } => {
+            TokenCheck::NeedsReauth("No OAuth tokens are stored for this account".to_string())
+        }
+        Error::TokenRefreshFailed { reason, .. } => {
+            if reason.contains("invalid_grant") {
+                TokenCheck::NeedsReauth(
+                    "Refresh token was revoked or has expired (invalid_grant). This happens \
+                     after a password change, revoking access in the Google account, or 7 days \
+                     after sign-in for OAuth apps in testing mode"
+                        .to_string(),
+                )
+            } else if reason.contains("unauthorized_client") {
+                TokenCheck::NeedsReauth(
+                    "The OAuth client (or domain-wide delegation) is no longer authorized for \
+                     the required scopes (unauthorized_client)"
+                        .to_string(),
+                )
+            } else if reason.contains("invalid_client") || reason.contains("deleted_client") {
+                TokenCheck::NeedsReauth(
+                    "The OAuth client credentials are invalid or the client was deleted \
+                     (invalid_client)"
+                        .to_string(),
+                )
+            } else if reason.starts_with('4') {
+                TokenCheck::NeedsReauth(format!("Token refresh was rejected: {}", reason))
+            } else {
+                TokenCheck::Failed(format!("Token refresh failed: {}", reason))
+            }
+        }
+        other => TokenCheck::Failed(other.to_string()),
+    }
+}
+
+/// Post a desktop notification that an account needs re-authentication.
+///
+/// Uses Notification Center on macOS and `notify-send` elsewhere; returns
+/// false if no notification could be shown.
+pub fn notify_reauth(account_id: &str, reason: &str) -> bool {
+    let title = "GroundEffect: sign-in required";
+    let message = format!(
+        "{} needs re-authentication. Run: groundeffect account reauth {}",
+        account_id, account_id
+    );
+    debug!("Notifying re-auth for {}: {}", account_id, reason);
+
+    let status = if cfg!(target_os = "macos") {
+        let script = format!(
+            "display notification \"{}\" with title \"{}\" subtitle \"{}\"",
+            applescript_escape(&message),
+            title,
+            applescript_escape(&truncate(reason, 120))
+        );
+        Command::new("osascript").arg("-e").arg(script).status()
+    } else {
+        Command::new("notify-send")
+            .arg("--app-name=GroundEffect")
+            .arg(title)
+            .arg(format!("{}\n{}", message, truncate(reason, 200)))
+            .status()
+    };
+
+    match status {
+        Ok(status) if status.success() => true,
+        Ok(status) => {
+            warn!("Re-auth notification exited with {}", status);
+            false
+        }
+        Err(e) => {
+            debug!("Re-auth notification unavailable: {}", e);
+            false
+        }
+    }
+}
+
+fn applescript_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn truncate(value: &str, max_chars: usize) -> String {
+    if value.chars().count() <= max_chars {
+        value.to_string()
+    } else {
+        let truncated: String = value.chars().take(max_chars).collect();
+        format!("{}…", truncated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn refresh_failed(reason: &str) -> Error {
+        Error::TokenRefreshFailed {
+            account: "user@example.com".to_string(),
+            reason: reason.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_classify_token_errors() {
+        assert!(matches!(
+            classify_token_error(&refresh_failed(
+                r#"400 Bad Request - {"error": "invalid_grant", "error_description": "Token has been expired or revoked."}"#
+            )),
+            TokenCheck::NeedsReauth(reason) if reason.contains("invalid_grant")
+        ));
+        assert!(matches!(
+            classify_token_error(&refresh_failed("503 Service Unavailable - ")),
+            TokenCheck::Failed(_)
+        ));
+        assert!(matches!(
+            classify_token_error(&Error::TokenExpired {
+                account: "user@example.com".to_string()
+            }),
+            TokenCheck::NeedsReauth(_)
+        ));
+        assert!(matches!(
+            classify_token_error(&Error::Other("dns error".to_string())),
+            TokenCheck::Failed(_)
+        ));
+    }
+
+    #[test]
+    fn test_notify_once_per_failure() {
+        let mut report = TokenHealthReport::default();
+        let now = Utc::now();
+        let revoked = TokenCheck::NeedsReauth("revoked".to_string());
+
+        assert!(!report.record("a@example.com", &TokenCheck::Ok, now));
+        assert!(report.record("a@example.com", &revoked, now));
+        report.mark_notified("a@example.com");
+        assert!(!report.record("a@example.com", &revoked, now));
+
+        // A network failure doesn't hide the known problem
+        report.record(
+            "a@example.com",
+            &TokenCheck::Failed("offline".to_string()),
+            now,
+        );
+        assert_eq!(report.needs_reauth().count(), 1);
+
+        // Recovery resets, so the next failure notifies again
+        report.record("a@example.com", &TokenCheck::Ok, now);
+        assert_eq!(report.needs_reauth().count(), 0);
+        assert!(report.record("a@example.com", &revoked, now));
+    }
+}
@@ -143,6 +143,7 @@ impl TokenProvider for DawnTokenProvider {
                     refresh_token,
                     expires_at: expires_at_ts,
                     scopes: scopes.unwrap_or_default(),
+                    service_account: None,
                 };
 
                 debug!("Retrieved tokens for {} from Dawn oauth_tokens", account_id);
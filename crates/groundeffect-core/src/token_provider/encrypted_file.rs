@@ -0,0 +1,221 @@
+//! Encrypted file token provider
+//!
+//! Stores tokens in ~/.config/groundeffect/tokens/<account>.enc, encrypted
+//! with AES-256-GCM. For headless Linux servers without a Secret Service
+//! daemon, where plain-text token files are not acceptable.
+//!
+//! # Security
+//!
+//! - The key is derived from an environment variable using HKDF-SHA256
+//! - Each write uses a fresh 96-bit nonce, stored in front of the ciphertext
+//! - Files are written with 600 permissions, the directory with 700
+
+use aes_gcm::{
+    aead::{Aead, KeyInit, OsRng},
+    Aes256Gcm, Nonce,
+};
+use async_trait::async_trait;
+use hkdf::Hkdf;
+use sha2::Sha256;
+use std::fs;
+use std::path::PathBuf;
+use tracing::debug;
+
+use crate::config::restrict_to_owner;
+use crate::error::{Error, Result};
+use crate::keychain::{account_file_stem, account_from_file_stem, OAuthTokens};
+
+use super::TokenProvider;
+
+/// Nonce size for AES-256-GCM (96 bits = 12 bytes)
+const NONCE_SIZE: usize = 12;
+
+/// File extension for encrypted token files
+const EXTENSION: &str = "enc";
+
+/// Token provider storing AES-256-GCM encrypted token files
+pub struct EncryptedFileTokenProvider {
+    cipher: Aes256Gcm,
+    dir: PathBuf,
+}
+
+impl EncryptedFileTokenProvider {
+    /// Create a provider using the given encryption key
+    pub fn new(encryption_key: &str) -> Result<Self> {
+        Self::with_dir(encryption_key, crate::config::tokens_dir())
+    }
+
+    /// Create a provider storing files in a specific directory
+    pub fn with_dir(encryption_key: &str, dir: PathBuf) -> Result<Self> {
+        if encryption_key.is_empty() {
+            return Err(Error::Config("Token encryption key is empty".to_string()));
+        }
+        let key = Self::derive_key(encryption_key)?;
+        Ok(Self {
+            cipher: Aes256Gcm::new(&key.into()),
+            dir,
+        })
+    }
+
+    /// Derive a 256-bit key from the user-provided key using HKDF
+    fn derive_key(key: &str) -> Result<[u8; 32]> {
+        let hkdf = Hkdf::<Sha256>::new(Some(b"groundeffect-token-files"), key.as_bytes());
+        let mut okm = [0u8; 32];
+        hkdf.expand(b"aes-256-gcm", &mut okm)
+            .map_err(|_| Error::Token("Failed to derive encryption key".to_string()))?;
+        Ok(okm)
+    }
+
+    fn token_path(&self, account_id: &str) -> PathBuf {
+        self.dir
+            .join(format!("{}.{}", account_file_stem(account_id), EXTENSION))
+    }
+
+    fn encrypt(&self, tokens: &OAuthTokens) -> Result<Vec<u8>> {
+        use aes_gcm::aead::rand_core::RngCore;
+
+        let plaintext = serde_json::to_vec(tokens)?;
+
+        let mut nonce_bytes = [0u8; NONCE_SIZE];
+        OsRng.fill_bytes(&mut nonce_bytes);
+        let ciphertext = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce_bytes), plaintext.as_ref())
+            .map_err(|e| Error::Token(format!("Encryption failed: {}", e)))?;
+
+        let mut result = Vec::with_capacity(NONCE_SIZE + ciphertext.len());
+        result.extend_from_slice(&nonce_bytes);
+        result.extend_from_slice(&ciphertext);
+        Ok(result)
+    }
+
+    fn decrypt(&self, data: &[u8]) -> Result<OAuthTokens> {
+        if data.len() < NONCE_SIZE {
+            return Err(Error::Token(
+                "Invalid encrypted data: too short".to_string(),
+            ));
+        }
+
+        let (nonce_bytes, ciphertext) = data.split_at(NONCE_SIZE);
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce_bytes), ciphertext)
+            .map_err(|_| {
+                Error::Token(
+                    "Failed to decrypt tokens (wrong encryption key or corrupted file)".to_string(),
+                )
+            })?;
+
+        serde_json::from_slice(&plaintext)
+            .map_err(|e| Error::Token(format!("Invalid token data: {}", e)))
+    }
+}
+
+#[async_trait]
+impl TokenProvider for EncryptedFileTokenProvider {
+    async fn get_tokens(&self, account_id: &str) -> Result<Option<OAuthTokens>> {
+        let path = self.token_path(account_id);
+        if !path.exists() {
+            debug!("No encrypted tokens found for {}", account_id);
+            return Ok(None);
+        }
+        let data =
+            fs::read(&path).map_err(|e| Error::Token(format!("Failed to read tokens: {}", e)))?;
+        self.decrypt(&data).map(Some)
+    }
+
+    async fn store_tokens(&self, account_id: &str, tokens: &OAuthTokens) -> Result<()> {
+        if !self.dir.exists() {
+            fs::create_dir_all(&self.dir)?;
+            restrict_to_owner(&self.dir, 0o700)?;
+        }
+
+        let path = self.token_path(account_id);
+        fs::write(&path, self.encrypt(tokens)?)
+            .map_err(|e| Error::Token(format!("Failed to store tokens: {}", e)))?;
+        restrict_to_owner(&path, 0o600)
+            .map_err(|e| Error::Token(format!("Failed to set permissions: {}", e)))?;
+
+        debug!("Stored encrypted OAuth tokens for {}", account_id);
+        Ok(())
+    }
+
+    async fn delete_tokens(&self, account_id: &str) -> Result<()> {
+        let path = self.token_path(account_id);
+        if path.exists() {
+            fs::remove_file(&path)
+                .map_err(|e| Error::Token(format!("Failed to delete tokens: {}", e)))?;
+        }
+        Ok(())
+    }
+
+    async fn list_accounts(&self) -> Result<Vec<String>> {
+        if !self.dir.exists() {
+            return Ok(vec![]);
+        }
+
+        let entries = fs::read_dir(&self.dir)
+            .map_err(|e| Error::Token(format!("Failed to read tokens directory: {}", e)))?;
+
+        Ok(entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().map(|e| e == EXTENSION).unwrap_or(false))
+            .filter_map(|path| {
+                path.file_stem()
+                    .and_then(|stem| account_from_file_stem(&stem.to_string_lossy()))
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens() -> OAuthTokens {
+        OAuthTokens {
+            access_token: "access123".to_string(),
+            refresh_token: "refresh456".to_string(),
+            expires_at: 1234567890,
+            scopes: vec!["email".to_string()],
+            service_account: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_store_and_read_back() {
+        let dir = std::env::temp_dir().join(format!("ge-tokens-{}", uuid::Uuid::new_v4()));
+        let provider = EncryptedFileTokenProvider::with_dir("secret", dir.clone()).unwrap();
+
+        provider
+            .store_tokens("user@example.com", &tokens())
+            .await
+            .unwrap();
+        let stored = fs::read(dir.join("user_at_example_com.enc")).unwrap();
+        assert!(!String::from_utf8_lossy(&stored).contains("refresh456"));
+
+        let read = provider
+            .get_tokens("user@example.com")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(read.refresh_token, "refresh456");
+        assert_eq!(
+            provider.list_accounts().await.unwrap(),
+            vec!["user@example.com".to_string()]
+        );
+
+        // A different key can't read the file
+        let other = EncryptedFileTokenProvider::with_dir("other", dir.clone()).unwrap();
+        assert!(other.get_tokens("user@example.com").await.is_err());
+
+        provider.delete_tokens("user@example.com").await.unwrap();
+        assert!(provider
+            .get_tokens("user@example.com")
+            .await
+            .unwrap()
+            .is_none());
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -9,7 +9,7 @@ use std::path::PathBuf;
 use tracing::debug;
 
 use crate::error::Result;
-use crate::keychain::{KeychainManager, OAuthTokens};
+use crate::keychain::{account_from_file_stem, KeychainManager, OAuthTokens};
 
 use super::TokenProvider;
 
@@ -29,11 +29,7 @@ impl FileTokenProvider {
 
     /// Get the tokens directory path
     fn tokens_dir() -> PathBuf {
-        dirs::home_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join(".config")
-            .join("groundeffect")
-            .join("tokens")
+        crate::config::tokens_dir()
     }
 }
 
@@ -88,19 +84,11 @@ impl TokenProvider for FileTokenProvider {
         for entry in entries.flatten() {
             let path = entry.path();
             if path.extension().map(|e| e == "json").unwrap_or(false) {
-                if let Some(stem) = path.file_stem() {
-                    let filename = stem.to_string_lossy();
-                    // Reverse the sanitization: _at_ -> @, _ -> .
-                    // But be careful: underscores in domain names should stay
-                    // The pattern is: name_at_domain_tld.json
-                    if let Some(at_pos) = filename.find("_at_") {
-                        let local = &filename[..at_pos];
-                        let domain = &filename[at_pos + 4..];
-                        // Replace remaining underscores with dots in domain
-                        let domain = domain.replace('_', ".");
-                        let email = format!("{}@{}", local, domain);
-                        accounts.push(email);
-                    }
+                if let Some(email) = path
+                    .file_stem()
+                    .and_then(|stem| account_from_file_stem(&stem.to_string_lossy()))
+                {
+                    accounts.push(email);
                 }
             }
         }
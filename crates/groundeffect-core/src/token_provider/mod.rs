@@ -12,6 +12,15 @@
 //! [tokens]
 //! provider = "file"
 //!
+//! # Secret Service keyring (Linux desktops, requires `secret-tool`)
+//! [tokens]
+//! provider = "secret_service"
+//!
+//! # AES-256-GCM encrypted files (headless servers)
+//! [tokens]
+//! provider = "encrypted_file"
+//! encryption_key_env = "GE_TOKEN_ENCRYPTION_KEY"  # optional
+//!
 //! # PostgreSQL storage (requires "postgres" feature)
 //! [tokens]
 //! provider = "postgres"
@@ -22,15 +31,19 @@
 
 #[cfg(feature = "postgres")]
 mod dawn;
+mod encrypted_file;
 mod file;
 #[cfg(feature = "postgres")]
 mod postgres;
+mod secret_service;
 
 #[cfg(feature = "postgres")]
 pub use dawn::DawnTokenProvider;
+pub use encrypted_file::EncryptedFileTokenProvider;
 pub use file::FileTokenProvider;
 #[cfg(feature = "postgres")]
 pub use postgres::PostgresTokenProvider;
+pub use secret_service::SecretServiceTokenProvider;
 
 use async_trait::async_trait;
 use std::sync::Arc;
@@ -84,6 +97,18 @@ pub trait TokenProvider: Send + Sync {
 pub async fn create_token_provider(config: &Config) -> Result<Arc<dyn TokenProvider>> {
     match &config.tokens {
         TokenProviderConfig::File => Ok(Arc::new(FileTokenProvider::new())),
+        TokenProviderConfig::SecretService => {
+            Ok(Arc::new(SecretServiceTokenProvider::new().await?))
+        }
+        TokenProviderConfig::EncryptedFile { encryption_key_env } => {
+            let key = std::env::var(encryption_key_env).map_err(|_| {
+                crate::error::Error::Config(format!(
+                    "encryption key env var {} not set",
+                    encryption_key_env
+                ))
+            })?;
+            Ok(Arc::new(EncryptedFileTokenProvider::new(&key)?))
+        }
         #[cfg(feature = "postgres")]
         TokenProviderConfig::Dawn {
             database_url,
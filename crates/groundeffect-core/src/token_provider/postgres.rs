@@ -376,6 +376,7 @@ mod tests {
             refresh_token: "refresh456".to_string(),
             expires_at: 1234567890,
             scopes: vec!["email".to_string(), "calendar".to_string()],
+            service_account: None,
         };
 
         // Create a mock provider just for encryption testing
@@ -0,0 +1,208 @@
+//! Secret Service token provider (Linux)
+//!
+//! Stores tokens in the desktop keyring (GNOME Keyring, KWallet, KeePassXC)
+//! through the freedesktop Secret Service API, using libsecret's
+//! `secret-tool` command. Items are tagged with `service=groundeffect`,
+//! the active profile and the account ID.
+
+use async_trait::async_trait;
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tracing::debug;
+
+use crate::error::{Error, Result};
+use crate::keychain::OAuthTokens;
+
+use super::TokenProvider;
+
+/// Value of the `service` attribute on stored items
+const SERVICE: &str = "groundeffect";
+
+/// Token provider backed by the Secret Service API
+pub struct SecretServiceTokenProvider {
+    profile: String,
+    /// In-memory cache so token lookups don't spawn a process every time
+    cache: RwLock<HashMap<String, OAuthTokens>>,
+}
+
+impl SecretServiceTokenProvider {
+    /// Create a provider, checking that `secret-tool` is available
+    pub async fn new() -> Result<Self> {
+        let available = Command::new("secret-tool")
+            .arg("--version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .await
+            .is_ok();
+        if !available {
+            return Err(Error::Config(
+                "secret_service token provider requires 'secret-tool' (install libsecret-tools)"
+                    .to_string(),
+            ));
+        }
+
+        Ok(Self {
+            profile: crate::config::active_profile()
+                .unwrap_or("default")
+                .to_string(),
+            cache: RwLock::new(HashMap::new()),
+        })
+    }
+
+    /// Attributes identifying this profile's items (plus the account, if given)
+    fn attributes(&self, account_id: Option<&str>) -> Vec<String> {
+        let mut attrs = vec![
+            "service".to_string(),
+            SERVICE.to_string(),
+            "profile".to_string(),
+            self.profile.clone(),
+        ];
+        if let Some(account_id) = account_id {
+            attrs.push("account".to_string());
+            attrs.push(account_id.to_string());
+        }
+        attrs
+    }
+
+    /// Run `secret-tool`, returning whether it succeeded, its stdout (the
+    /// data) and its stderr (only for error messages)
+    async fn secret_tool(
+        &self,
+        args: &[String],
+        stdin: Option<&[u8]>,
+    ) -> Result<(bool, String, String)> {
+        let mut child = Command::new("secret-tool")
+            .args(args)
+            .stdin(if stdin.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| Error::Token(format!("Failed to run secret-tool: {}", e)))?;
+
+        if let (Some(data), Some(mut pipe)) = (stdin, child.stdin.take()) {
+            pipe.write_all(data).await?;
+        }
+
+        let output = child.wait_with_output().await?;
+        Ok((
+            output.status.success(),
+            String::from_utf8_lossy(&output.stdout).to_string(),
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ))
+    }
+}
+
+#[async_trait]
+impl TokenProvider for SecretServiceTokenProvider {
+    async fn get_tokens(&self, account_id: &str) -> Result<Option<OAuthTokens>> {
+        if let Some(tokens) = self.cache.read().get(account_id) {
+            return Ok(Some(tokens.clone()));
+        }
+
+        let mut args = vec!["lookup".to_string()];
+        args.extend(self.attributes(Some(account_id)));
+        let (found, output, _) = self.secret_tool(&args, None).await?;
+
+        // `lookup` exits non-zero with no output when nothing matches
+        if !found || output.trim().is_empty() {
+            debug!("No tokens found in Secret Service for {}", account_id);
+            return Ok(None);
+        }
+
+        let tokens: OAuthTokens = serde_json::from_str(output.trim())
+            .map_err(|e| Error::Token(format!("Invalid token data: {}", e)))?;
+        self.cache
+            .write()
+            .insert(account_id.to_string(), tokens.clone());
+        Ok(Some(tokens))
+    }
+
+    async fn store_tokens(&self, account_id: &str, tokens: &OAuthTokens) -> Result<()> {
+        let mut args = vec![
+            "store".to_string(),
+            format!("--label=GroundEffect OAuth tokens ({})", account_id),
+        ];
+        args.extend(self.attributes(Some(account_id)));
+
+        let data = serde_json::to_vec(tokens)?;
+        let (stored, _, errors) = self.secret_tool(&args, Some(&data)).await?;
+        if !stored {
+            return Err(Error::Token(format!(
+                "Failed to store tokens in Secret Service: {}",
+                errors.trim()
+            )));
+        }
+
+        self.cache
+            .write()
+            .insert(account_id.to_string(), tokens.clone());
+        debug!("Stored OAuth tokens for {} in Secret Service", account_id);
+        Ok(())
+    }
+
+    async fn delete_tokens(&self, account_id: &str) -> Result<()> {
+        self.cache.write().remove(account_id);
+
+        let mut args = vec!["clear".to_string()];
+        args.extend(self.attributes(Some(account_id)));
+        // `clear` succeeds whether or not an item existed
+        let (cleared, _, errors) = self.secret_tool(&args, None).await?;
+        if !cleared {
+            return Err(Error::Token(format!(
+                "Failed to delete tokens from Secret Service: {}",
+                errors.trim()
+            )));
+        }
+        Ok(())
+    }
+
+    async fn list_accounts(&self) -> Result<Vec<String>> {
+        let mut args = vec!["search".to_string(), "--all".to_string()];
+        args.extend(self.attributes(None));
+        let (_, output, _) = self.secret_tool(&args, None).await?;
+        Ok(parse_search_accounts(&output))
+    }
+}
+
+/// Extract account IDs from `secret-tool search` output
+/// (`attribute.account = user@example.com` lines)
+fn parse_search_accounts(output: &str) -> Vec<String> {
+    let mut accounts: Vec<String> = output
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("attribute.account = "))
+        .map(|account| account.trim().to_string())
+        .collect();
+    accounts.sort();
+    accounts.dedup();
+    accounts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_search_accounts() {
+        let output = "[/org/freedesktop/secrets/collection/login/12]
+label = GroundEffect OAuth tokens (b@example.com)
+secret = {}
+attribute.account = b@example.com
+attribute.profile = default
+attribute.service = groundeffect
+[/org/freedesktop/secrets/collection/login/13]
+attribute.account = a@example.com
+";
+        assert_eq!(
+            parse_search_accounts(output),
+            vec!["a@example.com".to_string(), "b@example.com".to_string()]
+        );
+    }
+}
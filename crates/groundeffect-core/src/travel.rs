@@ -0,0 +1,643 @@
+//! Travel itinerary extraction
+//!
+//! Reads flight and hotel reservations from the schema.org JSON-LD markup
+//! (`FlightReservation`, `LodgingReservation`) that airlines, hotels and
+//! booking sites embed in confirmation emails; the same markup Gmail uses for
+//! its own trip cards. Times carrying a UTC offset are used as-is; local times
+//! are placed in the airport's timezone when it is known, so departures and
+//! arrivals stay correct across timezones.
+
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use regex::Regex;
+use serde_json::Value;
+
+use crate::config::TravelConfig;
+use crate::models::{Email, ItineraryItem, ItineraryKind, Trip};
+
+/// Items further apart than this belong to different trips
+const TRIP_GAP_HOURS: i64 = 48;
+
+/// Longest gap inside a trip that hasn't flown back to its origin yet
+const OPEN_TRIP_GAP_DAYS: i64 = 30;
+
+/// IANA timezones of common airports, by IATA code
+const AIRPORT_TIMEZONES: &[(&str, &str)] = &[
+    ("AMS", "Europe/Amsterdam"),
+    ("ATL", "America/New_York"),
+    ("AUS", "America/Chicago"),
+    ("BCN", "Europe/Madrid"),
+    ("BER", "Europe/Berlin"),
+    ("BKK", "Asia/Bangkok"),
+    ("BNA", "America/Chicago"),
+    ("BOM", "Asia/Kolkata"),
+    ("BOS", "America/New_York"),
+    ("BRU", "Europe/Brussels"),
+    ("BWI", "America/New_York"),
+    ("CDG", "Europe/Paris"),
+    ("CLT", "America/New_York"),
+    ("CPH", "Europe/Copenhagen"),
+    ("DCA", "America/New_York"),
+    ("DEL", "Asia/Kolkata"),
+    ("DEN", "America/Denver"),
+    ("DFW", "America/Chicago"),
+    ("DOH", "Asia/Qatar"),
+    ("DTW", "America/Detroit"),
+    ("DUB", "Europe/Dublin"),
+    ("DXB", "Asia/Dubai"),
+    ("EWR", "America/New_York"),
+    ("FCO", "Europe/Rome"),
+    ("FLL", "America/New_York"),
+    ("FRA", "Europe/Berlin"),
+    ("GRU", "America/Sao_Paulo"),
+    ("HKG", "Asia/Hong_Kong"),
+    ("HND", "Asia/Tokyo"),
+    ("HNL", "Pacific/Honolulu"),
+    ("IAD", "America/New_York"),
+    ("IAH", "America/Chicago"),
+    ("ICN", "Asia/Seoul"),
+    ("IST", "Europe/Istanbul"),
+    ("JFK", "America/New_York"),
+    ("LAS", "America/Los_Angeles"),
+    ("LAX", "America/Los_Angeles"),
+    ("LGA", "America/New_York"),
+    ("LGW", "Europe/London"),
+    ("LHR", "Europe/London"),
+    ("LIS", "Europe/Lisbon"),
+    ("MAD", "Europe/Madrid"),
+    ("MCO", "America/New_York"),
+    ("MDW", "America/Chicago"),
+    ("MEL", "Australia/Melbourne"),
+    ("MEX", "America/Mexico_City"),
+    ("MIA", "America/New_York"),
+    ("MSP", "America/Chicago"),
+    ("MUC", "Europe/Berlin"),
+    ("MXP", "Europe/Rome"),
+    ("NRT", "Asia/Tokyo"),
+    ("OAK", "America/Los_Angeles"),
+    ("ORD", "America/Chicago"),
+    ("OSL", "Europe/Oslo"),
+    ("PDX", "America/Los_Angeles"),
+    ("PEK", "Asia/Shanghai"),
+    ("PHL", "America/New_York"),
+    ("PHX", "America/Phoenix"),
+    ("PVG", "Asia/Shanghai"),
+    ("SAN", "America/Los_Angeles"),
+    ("SEA", "America/Los_Angeles"),
+    ("SFO", "America/Los_Angeles"),
+    ("SIN", "Asia/Singapore"),
+    ("SJC", "America/Los_Angeles"),
+    ("SLC", "America/Denver"),
+    ("STN", "Europe/London"),
+    ("SYD", "Australia/Sydney"),
+    ("TPA", "America/New_York"),
+    ("TPE", "Asia/Taipei"),
+    ("VIE", "Europe/Vienna"),
+    ("YUL", "America/Toronto"),
+    ("YVR", "America/Vancouver"),
+    ("YYZ", "America/Toronto"),
+    ("ZRH", "Europe/Zurich"),
+];
+
+/// IANA timezone of an airport, when it is in the built-in table
+pub fn airport_timezone(iata: &str) -> Option<&'static str> {
+    let iata = iata.trim().to_uppercase();
+    AIRPORT_TIMEZONES
+        .binary_search_by(|(code, _)| (*code).cmp(iata.as_str()))
+        .ok()
+        .map(|idx| AIRPORT_TIMEZONES[idx].1)
+}
+
+/// Extract flight legs and hotel stays from a confirmation email.
+///
+/// `default_tz` places local times whose airport or hotel timezone is unknown.
+pub fn extract_itinerary(email: &Email, default_tz: Tz) -> Vec<ItineraryItem> {
+    let documents = json_ld_documents(email);
+    let mut reservations = Vec::new();
+    for document in &documents {
+        collect_reservations(document, &mut reservations);
+    }
+
+    let mut seen = HashSet::new();
+    let mut items = Vec::new();
+    for reservation in reservations {
+        let item = match type_name(reservation).as_deref() {
+            Some("FlightReservation") => parse_flight(reservation, default_tz),
+            Some("LodgingReservation") => parse_hotel(reservation, default_tz),
+            _ => None,
+        };
+        let Some(mut item) = item else {
+            continue;
+        };
+        // One reservation per passenger repeats the same flight
+        if !seen.insert((item.title.clone(), item.start)) {
+            continue;
+        }
+        item.id = format!("{}#{}", email.id, items.len());
+        item.email_id = email.id.clone();
+        item.account_id = email.account_id.clone();
+        items.push(item);
+    }
+    items
+}
+
+/// Extract itineraries from a batch of synced emails
+pub fn extract_itineraries(
+    emails: &[Email],
+    config: &TravelConfig,
+    default_tz: Tz,
+) -> Vec<ItineraryItem> {
+    if !config.enabled {
+        return Vec::new();
+    }
+    emails
+        .iter()
+        .flat_map(|email| extract_itinerary(email, default_tz))
+        .collect()
+}
+
+/// Group itinerary items into trips, ordered by start.
+///
+/// Items starting within two days of the previous item's end share a trip, as
+/// do items before the flight back to the trip's first departure airport.
+pub fn group_trips(mut items: Vec<ItineraryItem>) -> Vec<Trip> {
+    items.sort_by_key(|item| item.start);
+
+    let mut trips: Vec<Trip> = Vec::new();
+    for item in items {
+        let joins = trips.last().is_some_and(|trip| {
+            let gap = item.start - trip.end;
+            gap <= Duration::hours(TRIP_GAP_HOURS)
+                || (gap <= Duration::days(OPEN_TRIP_GAP_DAYS) && !returned_home(trip))
+        });
+        match trips.last_mut() {
+            Some(trip) if joins => {
+                trip.end = trip.end.max(item.finish());
+                trip.items.push(item);
+            }
+            _ => trips.push(Trip {
+                start: item.start,
+                end: item.finish(),
+                destinations: Vec::new(),
+                items: vec![item],
+            }),
+        }
+    }
+
+    for trip in &mut trips {
+        // The first departure is home, not a destination
+        let home = trip_origin(trip);
+        for item in &trip.items {
+            if let Some(place) = &item.destination {
+                if Some(place) != home.as_ref() && !trip.destinations.contains(place) {
+                    trip.destinations.push(place.clone());
+                }
+            }
+        }
+    }
+    trips
+}
+
+/// Departure airport of the trip's first flight
+fn trip_origin(trip: &Trip) -> Option<String> {
+    trip.items
+        .iter()
+        .find(|item| item.kind == ItineraryKind::Flight)
+        .and_then(|item| item.origin.clone())
+}
+
+/// Whether the trip's last flight lands where its first one left from
+fn returned_home(trip: &Trip) -> bool {
+    let Some(home) = trip_origin(trip) else {
+        return true;
+    };
+    trip.items
+        .iter()
+        .rev()
+        .find(|item| item.kind == ItineraryKind::Flight)
+        .is_some_and(|flight| flight.destination.as_deref() == Some(home.as_str()))
+}
+
+/// Google Calendar API body for an itinerary item
+///
+/// Flights get separate start and end timezones, so the event shows local
+/// departure and arrival times; hotel stays are all-day events.
+pub fn calendar_event_json(item: &ItineraryItem) -> Value {
+    let summary = match item.kind {
+        ItineraryKind::Flight => format!("Flight {}", item.title),
+        ItineraryKind::Hotel => format!("Stay at {}", item.title),
+    };
+    let (start, end) = if item.all_day {
+        let start = item.start.date_naive();
+        let end = item
+            .end
+            .map(|end| end.date_naive())
+            .filter(|end| *end > start)
+            .unwrap_or(start + Duration::days(1));
+        (
+            serde_json::json!({ "date": start.to_string() }),
+            serde_json::json!({ "date": end.to_string() }),
+        )
+    } else {
+        let end = item.end.unwrap_or(item.start + Duration::hours(1));
+        (
+            event_time(item.start, item.start_timezone.as_deref()),
+            event_time(
+                end,
+                item.end_timezone
+                    .as_deref()
+                    .or(item.start_timezone.as_deref()),
+            ),
+        )
+    };
+
+    let mut description = Vec::new();
+    if let Some(provider) = &item.provider {
+        description.push(provider.clone());
+    }
+    if let Some(confirmation) = &item.confirmation {
+        description.push(format!("Confirmation: {}", confirmation));
+    }
+    description.push("Added by groundeffect from a confirmation email".to_string());
+
+    let mut body = serde_json::json!({
+        "summary": summary,
+        "start": start,
+        "end": end,
+        "description": description.join("\n"),
+        "transparency": if item.all_day { "transparent" } else { "opaque" },
+    });
+    if let Some(location) = &item.location {
+        body["location"] = Value::String(location.clone());
+    }
+    body
+}
+
+fn event_time(time: DateTime<Utc>, timezone: Option<&str>) -> Value {
+    match timezone {
+        Some(tz) => serde_json::json!({ "dateTime": time.to_rfc3339(), "timeZone": tz }),
+        None => serde_json::json!({ "dateTime": time.to_rfc3339() }),
+    }
+}
+
+fn json_ld_regex() -> &'static Regex {
+    static JSON_LD: OnceLock<Regex> = OnceLock::new();
+    JSON_LD.get_or_init(|| {
+        Regex::new(
+            r#"(?is)<script[^>]*type\s*=\s*["']application/ld\+json["'][^>]*>(.*?)</script>"#,
+        )
+        .unwrap()
+    })
+}
+
+/// Parsed JSON-LD `<script>` blocks of an email's HTML body
+pub(crate) fn json_ld_documents(email: &Email) -> Vec<Value> {
+    let Some(html) = email.body_html.as_deref() else {
+        return Vec::new();
+    };
+    json_ld_regex()
+        .captures_iter(html)
+        .filter_map(|caps| {
+            let raw = caps[1].trim();
+            serde_json::from_str(raw)
+                .or_else(|_| {
+                    // Some senders entity-escape the script body
+                    serde_json::from_str(&raw.replace("&quot;", "\"").replace("&amp;", "&"))
+                })
+                .ok()
+        })
+        .collect()
+}
+
+/// Collect reservation and parcel delivery objects from arrays and `@graph`
+/// containers
+pub(crate) fn collect_reservations<'a>(value: &'a Value, out: &mut Vec<&'a Value>) {
+    match value {
+        Value::Array(values) => {
+            for value in values {
+                collect_reservations(value, out);
+            }
+        }
+        Value::Object(object) => {
+            if let Some(graph) = object.get("@graph") {
+                collect_reservations(graph, out);
+            }
+            if type_name(value).is_some_and(|t| t.ends_with("Reservation") || t == "ParcelDelivery")
+            {
+                out.push(value);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The `@type` of an object, without a "http://schema.org/" prefix
+pub(crate) fn type_name(value: &Value) -> Option<String> {
+    let raw = match &value["@type"] {
+        Value::String(s) => s.as_str(),
+        Value::Array(types) => types.first()?.as_str()?,
+        _ => return None,
+    };
+    Some(raw.rsplit('/').next().unwrap_or(raw).to_string())
+}
+
+/// A string field, or the `name` of an object field
+pub(crate) fn text(value: &Value, key: &str) -> Option<String> {
+    let field = &value[key];
+    let s = match field {
+        Value::String(s) => s.as_str(),
+        Value::Object(_) => field["name"].as_str()?,
+        _ => return None,
+    };
+    let s = s.trim();
+    (!s.is_empty()).then(|| s.to_string())
+}
+
+fn parse_flight(reservation: &Value, default_tz: Tz) -> Option<ItineraryItem> {
+    let flight = &reservation["reservationFor"];
+    let airline = &flight["airline"];
+    let airline_code = text(airline, "iataCode");
+    let number = text(flight, "flightNumber")?;
+    // flightNumber is sometimes "UA123", sometimes just "123"
+    let flight_code = match &airline_code {
+        Some(code) if !number.starts_with(code.as_str()) => format!("{} {}", code, number),
+        _ => number,
+    };
+
+    let origin = text(&flight["departureAirport"], "iataCode");
+    let destination = text(&flight["arrivalAirport"], "iataCode");
+    let start_timezone = origin.as_deref().and_then(airport_timezone);
+    let end_timezone = destination.as_deref().and_then(airport_timezone);
+
+    let (start, _) = parse_time(&text(flight, "departureTime")?, start_timezone, default_tz)?;
+    let end = text(flight, "arrivalTime")
+        .and_then(|t| parse_time(&t, end_timezone, default_tz))
+        .map(|(end, _)| end)
+        .filter(|end| *end > start);
+
+    let title = match (&origin, &destination) {
+        (Some(from), Some(to)) => format!("{} {} → {}", flight_code, from, to),
+        _ => flight_code,
+    };
+
+    Some(ItineraryItem {
+        id: String::new(),
+        email_id: String::new(),
+        account_id: String::new(),
+        kind: ItineraryKind::Flight,
+        title,
+        provider: text(flight, "airline").or(airline_code),
+        confirmation: text(reservation, "reservationNumber"),
+        start,
+        end,
+        all_day: false,
+        start_timezone: start_timezone.map(String::from),
+        end_timezone: end_timezone.map(String::from),
+        location: text(&flight["departureAirport"], "name"),
+        origin,
+        destination,
+        event_id: None,
+    })
+}
+
+fn parse_hotel(reservation: &Value, default_tz: Tz) -> Option<ItineraryItem> {
+    let lodging = &reservation["reservationFor"];
+    let name = text(lodging, "name")?;
+
+    let (start, start_all_day) = parse_time(&text(reservation, "checkinTime")?, None, default_tz)?;
+    let checkout = text(reservation, "checkoutTime").and_then(|t| parse_time(&t, None, default_tz));
+    let end = checkout.map(|(end, _)| end).filter(|end| *end > start);
+    let all_day = start_all_day || checkout.is_some_and(|(_, all_day)| all_day);
+
+    let (location, city) = postal_address(&lodging["address"]);
+
+    Some(ItineraryItem {
+        id: String::new(),
+        email_id: String::new(),
+        account_id: String::new(),
+        kind: ItineraryKind::Hotel,
+        title: name.clone(),
+        provider: text(reservation, "provider").or(Some(name)),
+        confirmation: text(reservation, "reservationNumber"),
+        start,
+        end,
+        all_day,
+        start_timezone: None,
+        end_timezone: None,
+        origin: None,
+        destination: city,
+        location,
+        event_id: None,
+    })
+}
+
+/// A schema.org address as one line, and its city
+pub(crate) fn postal_address(address: &Value) -> (Option<String>, Option<String>) {
+    match address {
+        Value::String(s) => (Some(s.trim().to_string()), None),
+        Value::Object(_) => {
+            let parts: Vec<String> = [
+                "streetAddress",
+                "addressLocality",
+                "addressRegion",
+                "postalCode",
+                "addressCountry",
+            ]
+            .iter()
+            .filter_map(|key| text(address, key))
+            .collect();
+            (
+                (!parts.is_empty()).then(|| parts.join(", ")),
+                text(address, "addressLocality"),
+            )
+        }
+        _ => (None, None),
+    }
+}
+
+/// Parse a schema.org date or date-time.
+///
+/// Offsets are honoured; local times go in `tz` (or `default_tz`). Bare dates
+/// are returned at noon UTC with `true`, which keeps the calendar date in any
+/// timezone.
+pub(crate) fn parse_time(
+    s: &str,
+    tz: Option<&str>,
+    default_tz: Tz,
+) -> Option<(DateTime<Utc>, bool)> {
+    let s = s.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Some((dt.with_timezone(&Utc), false));
+    }
+    for format in ["%Y-%m-%dT%H:%M:%S%z", "%Y-%m-%dT%H:%M%z"] {
+        if let Ok(dt) = DateTime::parse_from_str(s, format) {
+            return Some((dt.with_timezone(&Utc), false));
+        }
+    }
+
+    let tz: Tz = tz.and_then(|tz| tz.parse().ok()).unwrap_or(default_tz);
+    for format in ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(s, format) {
+            let local = tz.from_local_datetime(&naive).earliest()?;
+            return Some((local.with_timezone(&Utc), false));
+        }
+    }
+
+    let date = NaiveDate::parse_from_str(s.get(..10)?, "%Y-%m-%d").ok()?;
+    Some((date.and_hms_opt(12, 0, 0)?.and_utc(), true))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Address;
+
+    fn email(html: &str) -> Email {
+        Email {
+            from: Address::with_name("United Airlines", "unitedairlines@united.com"),
+            date: Utc.with_ymd_and_hms(2024, 5, 20, 9, 30, 0).unwrap(),
+            body_html: Some(html.to_string()),
+            ..Email::test(
+                "<conf@example.com>",
+                "unitedairlines@united.com",
+                "Your flight confirmation",
+            )
+        }
+    }
+
+    fn script(json: &str) -> String {
+        format!(
+            "<html><head><script type=\"application/ld+json\">{}</script></head><body>Hi</body></html>",
+            json
+        )
+    }
+
+    const FLIGHT: &str = r#"{
+        "@context": "http://schema.org",
+        "@type": "FlightReservation",
+        "reservationNumber": "K7XQ2P",
+        "underName": {"@type": "Person", "name": "Jane Doe"},
+        "reservationFor": {
+            "@type": "Flight",
+            "flightNumber": "123",
+            "airline": {"@type": "Airline", "name": "United Airlines", "iataCode": "UA"},
+            "departureAirport": {"@type": "Airport", "name": "San Francisco International", "iataCode": "SFO"},
+            "departureTime": "2024-06-03T08:15:00",
+            "arrivalAirport": {"@type": "Airport", "name": "Newark Liberty", "iataCode": "EWR"},
+            "arrivalTime": "2024-06-03T16:45:00"
+        }
+    }"#;
+
+    #[test]
+    fn test_flight_local_times_use_airport_timezones() {
+        let items = extract_itinerary(&email(&script(FLIGHT)), Tz::UTC);
+        assert_eq!(items.len(), 1);
+        let flight = &items[0];
+        assert_eq!(flight.kind, ItineraryKind::Flight);
+        assert_eq!(flight.title, "UA 123 SFO → EWR");
+        assert_eq!(flight.confirmation.as_deref(), Some("K7XQ2P"));
+        assert_eq!(flight.provider.as_deref(), Some("United Airlines"));
+        // 08:15 PDT and 16:45 EDT
+        assert_eq!(
+            flight.start,
+            Utc.with_ymd_and_hms(2024, 6, 3, 15, 15, 0).unwrap()
+        );
+        assert_eq!(
+            flight.end,
+            Some(Utc.with_ymd_and_hms(2024, 6, 3, 20, 45, 0).unwrap())
+        );
+        assert_eq!(
+            flight.start_timezone.as_deref(),
+            Some("America/Los_Angeles")
+        );
+        assert_eq!(flight.end_timezone.as_deref(), Some("America/New_York"));
+        assert_eq!(flight.id, "me@example.com:<conf@example.com>#0");
+
+        let event = calendar_event_json(flight);
+        assert_eq!(event["summary"], "Flight UA 123 SFO → EWR");
+        assert_eq!(event["start"]["timeZone"], "America/Los_Angeles");
+        assert_eq!(event["end"]["timeZone"], "America/New_York");
+    }
+
+    #[test]
+    fn test_offsets_win_and_passengers_dedupe() {
+        let with_offset = FLIGHT
+            .replace("2024-06-03T08:15:00", "2024-06-03T08:15:00-07:00")
+            .replace("\"SFO\"", "\"XYZ\"");
+        let html = script(&format!("[{}, {}]", with_offset, with_offset));
+        let items = extract_itinerary(&email(&html), Tz::UTC);
+        assert_eq!(items.len(), 1);
+        assert_eq!(
+            items[0].start,
+            Utc.with_ymd_and_hms(2024, 6, 3, 15, 15, 0).unwrap()
+        );
+        assert_eq!(items[0].start_timezone, None);
+    }
+
+    #[test]
+    fn test_hotel_stay_with_dates() {
+        let hotel = r#"{
+            "@context": "https://schema.org",
+            "@graph": [{
+                "@type": "LodgingReservation",
+                "reservationNumber": "88213",
+                "reservationFor": {
+                    "@type": "LodgingBusiness",
+                    "name": "Hotel Example",
+                    "address": {
+                        "@type": "PostalAddress",
+                        "streetAddress": "1 Main St",
+                        "addressLocality": "New York",
+                        "addressRegion": "NY",
+                        "addressCountry": "US"
+                    }
+                },
+                "checkinTime": "2024-06-03",
+                "checkoutTime": "2024-06-07"
+            }]
+        }"#;
+        let items = extract_itinerary(&email(&script(hotel)), Tz::UTC);
+        assert_eq!(items.len(), 1);
+        let stay = &items[0];
+        assert_eq!(stay.kind, ItineraryKind::Hotel);
+        assert!(stay.all_day);
+        assert_eq!(stay.destination.as_deref(), Some("New York"));
+        assert_eq!(
+            stay.location.as_deref(),
+            Some("1 Main St, New York, NY, US")
+        );
+
+        let event = calendar_event_json(stay);
+        assert_eq!(event["start"]["date"], "2024-06-03");
+        assert_eq!(event["end"]["date"], "2024-06-07");
+    }
+
+    #[test]
+    fn test_group_trips() {
+        let mut outbound = extract_itinerary(&email(&script(FLIGHT)), Tz::UTC).remove(0);
+        outbound.destination = Some("EWR".to_string());
+        let mut back = outbound.clone();
+        back.origin = Some("EWR".to_string());
+        back.destination = Some("SFO".to_string());
+        back.start = outbound.start + Duration::days(4);
+        back.end = Some(back.start + Duration::hours(6));
+        let mut later = outbound.clone();
+        later.start = outbound.start + Duration::days(30);
+        later.end = None;
+
+        let trips = group_trips(vec![later, back, outbound]);
+        assert_eq!(trips.len(), 2);
+        assert_eq!(trips[0].items.len(), 2);
+        assert_eq!(trips[0].destinations, vec!["EWR".to_string()]);
+    }
+
+    #[test]
+    fn test_airport_table_is_sorted() {
+        assert!(AIRPORT_TIMEZONES.windows(2).all(|w| w[0].0 < w[1].0));
+        assert_eq!(airport_timezone("sfo"), Some("America/Los_Angeles"));
+        assert_eq!(airport_timezone("XYZ"), None);
+    }
+}
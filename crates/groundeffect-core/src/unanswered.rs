@@ -0,0 +1,252 @@
+//! Unanswered email detection
+//!
+//! Finds inbound mail addressed directly to the account owner (in To, not
+//! just Cc) where nothing the owner sent later in the same conversation
+//! answers it. Newsletters, automated notifications and receipts never
+//! expect a reply, so they are skipped.
+//!
+//! A conversation is the messages sharing a Gmail thread ID, joined with
+//! those linked to them by In-Reply-To or References. Sent mail comes from
+//! the Sent folder, so the reply links are what tie a reply to what it
+//! answers when a message has no thread ID.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::{Email, EmailCategory, EmailSummary};
+
+/// An inbound email still waiting for a reply
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnansweredEmail {
+    /// The latest unanswered message of its thread
+    #[serde(flatten)]
+    pub email: EmailSummary,
+
+    /// Whole days since it arrived
+    pub waiting_days: i64,
+
+    /// Messages in the thread since `since` that are also waiting (including this one)
+    pub pending_count: usize,
+}
+
+/// Parse a relative age such as "30m", "12h", "7d" or "2w"
+pub fn parse_age(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let unit = value.chars().last()?;
+    let count: i64 = value[..value.len() - unit.len_utf8()].parse().ok()?;
+    if count < 0 {
+        return None;
+    }
+    match unit.to_ascii_lowercase() {
+        'm' => Some(Duration::minutes(count)),
+        'h' => Some(Duration::hours(count)),
+        'd' => Some(Duration::days(count)),
+        'w' => Some(Duration::weeks(count)),
+        _ => None,
+    }
+}
+
+/// Whether an email expects an answer from one of `own_addresses`
+pub(crate) fn expects_reply(email: &Email, own_addresses: &[String]) -> bool {
+    if email.is_sent || email.activity_source.is_some() {
+        return false;
+    }
+    if matches!(
+        email.category,
+        Some(
+            EmailCategory::Newsletter | EmailCategory::Notification | EmailCategory::Transactional
+        )
+    ) {
+        return false;
+    }
+    let is_own = |address: &str| {
+        address.eq_ignore_ascii_case(&email.account_id)
+            || own_addresses
+                .iter()
+                .any(|a| a.eq_ignore_ascii_case(address))
+    };
+    !is_own(&email.from.email) && email.to.iter().any(|a| is_own(&a.email))
+}
+
+/// Unanswered emails among `emails` (which must include the account's sent
+/// mail over the same period), newest first
+///
+/// `own_addresses` are send-as addresses besides each account's own.
+pub fn find_unanswered(
+    emails: &[Email],
+    own_addresses: &[String],
+    now: DateTime<Utc>,
+) -> Vec<UnansweredEmail> {
+    let mut unanswered = Vec::new();
+    for messages in conversations(emails) {
+        // Anything sent answers everything before it
+        let answered_until = messages
+            .iter()
+            .filter(|e| e.is_sent)
+            .map(|e| e.date)
+            .max()
+            .unwrap_or(DateTime::<Utc>::MIN_UTC);
+        let pending: Vec<&&Email> = messages
+            .iter()
+            .filter(|e| e.date > answered_until)
+            .filter(|e| expects_reply(e, own_addresses))
+            .collect();
+        if let Some(latest) = pending.iter().max_by_key(|e| e.date) {
+            unanswered.push(UnansweredEmail {
+                email: EmailSummary::from(**latest),
+                waiting_days: (now - latest.date).num_days(),
+                pending_count: pending.len(),
+            });
+        }
+    }
+
+    unanswered.sort_by(|a, b| b.email.date.cmp(&a.email.date));
+    unanswered
+}
+
+/// Group emails into conversations: per account, the same Gmail thread ID
+/// or a reply link (In-Reply-To / References) to another email's Message-ID
+fn conversations(emails: &[Email]) -> Vec<Vec<&Email>> {
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    let normalize = |id: &str| {
+        id.trim()
+            .trim_start_matches('<')
+            .trim_end_matches('>')
+            .to_string()
+    };
+
+    let mut by_message_id: HashMap<(&str, String), usize> = HashMap::new();
+    let mut by_thread: HashMap<(&str, u64), usize> = HashMap::new();
+    for (i, email) in emails.iter().enumerate() {
+        by_message_id.insert((email.account_id.as_str(), normalize(&email.message_id)), i);
+        if email.gmail_thread_id != 0 {
+            by_thread.insert((email.account_id.as_str(), email.gmail_thread_id), i);
+        }
+    }
+
+    let mut parent: Vec<usize> = (0..emails.len()).collect();
+    for (i, email) in emails.iter().enumerate() {
+        let account = email.account_id.as_str();
+        // Only known thread IDs are in `by_thread`
+        let thread = by_thread.get(&(account, email.gmail_thread_id));
+        let replied_to = email
+            .in_reply_to
+            .iter()
+            .chain(&email.references)
+            .filter_map(|id| by_message_id.get(&(account, normalize(id))));
+        for &j in thread.into_iter().chain(replied_to) {
+            let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+            parent[a] = b;
+        }
+    }
+
+    let mut groups: HashMap<usize, Vec<&Email>> = HashMap::new();
+    for (i, email) in emails.iter().enumerate() {
+        groups.entry(root(&mut parent, i)).or_default().push(email);
+    }
+    groups.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Address;
+    use chrono::TimeZone;
+
+    fn email(id: &str, thread: u64, from: &str, to: &str, day: u32) -> Email {
+        Email {
+            gmail_thread_id: thread,
+            is_sent: from == "me@example.com",
+            category: Some(EmailCategory::Personal),
+            to: vec![Address::new(to)],
+            date: Utc.with_ymd_and_hms(2024, 6, day, 9, 0, 0).unwrap(),
+            ..Email::test(id, from, "Question")
+        }
+    }
+
+    #[test]
+    fn test_parse_age() {
+        assert_eq!(parse_age("7d"), Some(Duration::days(7)));
+        assert_eq!(parse_age("2W"), Some(Duration::weeks(2)));
+        assert_eq!(parse_age("36h"), Some(Duration::hours(36)));
+        assert_eq!(parse_age("90m"), Some(Duration::minutes(90)));
+        assert_eq!(parse_age("2024-06-01"), None);
+        assert_eq!(parse_age("d"), None);
+    }
+
+    #[test]
+    fn test_reply_answers_earlier_messages_only() {
+        let now = Utc.with_ymd_and_hms(2024, 6, 10, 9, 0, 0).unwrap();
+        let emails = vec![
+            // Answered
+            email("<1@x>", 1, "bob@acme.io", "me@example.com", 2),
+            email("<2@x>", 1, "me@example.com", "bob@acme.io", 3),
+            // Ann followed up after the reply, twice
+            email("<3@x>", 2, "ann@acme.io", "me@example.com", 2),
+            email("<4@x>", 2, "me@example.com", "ann@acme.io", 3),
+            email("<5@x>", 2, "ann@acme.io", "me@example.com", 4),
+            email("<6@x>", 2, "ann@acme.io", "me@example.com", 5),
+        ];
+
+        let unanswered = find_unanswered(&emails, &[], now);
+        assert_eq!(unanswered.len(), 1);
+        assert_eq!(unanswered[0].email.message_id, "<6@x>");
+        assert_eq!(unanswered[0].pending_count, 2);
+        assert_eq!(unanswered[0].waiting_days, 5);
+    }
+
+    #[test]
+    fn test_reply_links_match_sent_mail_across_folders() {
+        let now = Utc.with_ymd_and_hms(2024, 6, 10, 9, 0, 0).unwrap();
+        // Stored without thread IDs; the reply from the Sent folder has its
+        // own UID and only In-Reply-To ties it to the question
+        let mut question = email("<1@x>", 0, "bob@acme.io", "me@example.com", 2);
+        question.uid = 41;
+        let mut reply = email("<2@x>", 0, "me@example.com", "bob@acme.io", 3);
+        reply.uid = 7;
+        reply.folder = "[Gmail]/Sent Mail".to_string();
+        reply.in_reply_to = Some("1@x".to_string());
+        // An unrelated sent message sharing the other question's UID
+        let mut other = email("<3@x>", 0, "ann@acme.io", "me@example.com", 2);
+        other.uid = 7;
+        let mut unrelated = email("<4@x>", 0, "me@example.com", "carl@acme.io", 4);
+        unrelated.uid = 7;
+        unrelated.folder = "[Gmail]/Sent Mail".to_string();
+
+        let unanswered = find_unanswered(&[question, reply, other, unrelated], &[], now);
+        let ids: Vec<&str> = unanswered
+            .iter()
+            .map(|u| u.email.message_id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["<3@x>"]);
+    }
+
+    #[test]
+    fn test_skips_bulk_and_indirect_mail() {
+        let now = Utc.with_ymd_and_hms(2024, 6, 10, 9, 0, 0).unwrap();
+        let mut newsletter = email("<1@x>", 1, "news@shop.com", "me@example.com", 2);
+        newsletter.category = Some(EmailCategory::Newsletter);
+        let mut cc_only = email("<2@x>", 2, "bob@acme.io", "team@acme.io", 2);
+        cc_only.cc = vec![Address::new("me@example.com")];
+        let alias = email("<3@x>", 3, "bob@acme.io", "Support@Example.com", 2);
+
+        let unanswered = find_unanswered(
+            &[newsletter, cc_only, alias],
+            &["support@example.com".to_string()],
+            now,
+        );
+        let ids: Vec<&str> = unanswered
+            .iter()
+            .map(|u| u.email.message_id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["<3@x>"]);
+    }
+}
@@ -0,0 +1,374 @@
+//! Bring-your-own embedding vectors
+//!
+//! Exports (id, vector) pairs so embeddings can be computed by an external
+//! pipeline (e.g. a GPU box) and imported back. Parquet is the default
+//! interchange format; JSON Lines is supported for small jobs and scripting.
+//! Imported vectors must match the index dimension and should come from the
+//! same model the search side uses to embed queries.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow::array::AsArray;
+use arrow::datatypes::Float32Type;
+use arrow_array::{Array, ArrayRef, FixedSizeListArray, Float32Array, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ArrowWriter;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::EMBEDDING_DIMENSION;
+
+/// Rows per record batch when writing Parquet
+const PARQUET_BATCH_SIZE: usize = 4096;
+
+/// File format for vector import/export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VectorFormat {
+    Parquet,
+    Jsonl,
+}
+
+impl VectorFormat {
+    /// Parse a format name ("parquet", "jsonl")
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.to_lowercase().as_str() {
+            "parquet" => Ok(Self::Parquet),
+            "jsonl" | "ndjson" => Ok(Self::Jsonl),
+            other => Err(Error::InvalidRequest(format!(
+                "Unknown vector format '{}', expected 'parquet' or 'jsonl'",
+                other
+            ))),
+        }
+    }
+
+    /// Infer the format from a file extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "parquet" => Some(Self::Parquet),
+            "jsonl" | "ndjson" => Some(Self::Jsonl),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Parquet => "parquet",
+            Self::Jsonl => "jsonl",
+        }
+    }
+}
+
+/// Which table the vectors belong to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VectorTable {
+    Emails,
+    Events,
+}
+
+impl VectorTable {
+    /// Parse a table name ("emails", "events")
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.to_lowercase().as_str() {
+            "emails" | "email" => Ok(Self::Emails),
+            "events" | "event" | "calendar" => Ok(Self::Events),
+            other => Err(Error::InvalidRequest(format!(
+                "Unknown table '{}', expected 'emails' or 'events'",
+                other
+            ))),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Emails => "emails",
+            Self::Events => "events",
+        }
+    }
+}
+
+/// One exported or imported row
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VectorRecord {
+    /// Email or event ID
+    pub id: String,
+
+    /// Embedding (None if the row has not been embedded)
+    #[serde(default)]
+    pub vector: Option<Vec<f32>>,
+
+    /// Text to embed (only exported with `--with-text`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+/// Check that every record has a finite vector of the table's dimension
+/// (see [`crate::db::Database::vector_dimension`])
+pub fn validate_vectors(records: &[VectorRecord], dimension: usize) -> Result<()> {
+    for record in records {
+        let Some(vector) = &record.vector else {
+            return Err(Error::InvalidRequest(format!(
+                "Missing vector for id '{}'",
+                record.id
+            )));
+        };
+        if vector.len() != dimension {
+            return Err(Error::InvalidRequest(format!(
+                "Vector for id '{}' has dimension {}, expected {}",
+                record.id,
+                vector.len(),
+                dimension
+            )));
+        }
+        if vector.iter().any(|v| !v.is_finite()) {
+            return Err(Error::InvalidRequest(format!(
+                "Vector for id '{}' contains NaN or infinite values",
+                record.id
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Write records to a file, returning the number written
+pub fn write_vectors(path: &Path, format: VectorFormat, records: &[VectorRecord]) -> Result<usize> {
+    match format {
+        VectorFormat::Parquet => write_parquet(path, records)?,
+        VectorFormat::Jsonl => {
+            let mut writer = BufWriter::new(File::create(path)?);
+            for record in records {
+                serde_json::to_writer(&mut writer, record)?;
+                writer.write_all(b"\n")?;
+            }
+            writer.flush()?;
+        }
+    }
+    Ok(records.len())
+}
+
+/// Read records from a file
+pub fn read_vectors(path: &Path, format: VectorFormat) -> Result<Vec<VectorRecord>> {
+    match format {
+        VectorFormat::Parquet => read_parquet(path),
+        VectorFormat::Jsonl => {
+            let reader = BufReader::new(File::open(path)?);
+            let mut records = Vec::new();
+            for (line_no, line) in reader.lines().enumerate() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let record = serde_json::from_str(&line)
+                    .map_err(|e| Error::InvalidRequest(format!("Line {}: {}", line_no + 1, e)))?;
+                records.push(record);
+            }
+            Ok(records)
+        }
+    }
+}
+
+fn parquet_schema(dimension: usize, with_text: bool) -> Schema {
+    let mut fields = vec![
+        Field::new("id", DataType::Utf8, false),
+        Field::new(
+            "vector",
+            DataType::FixedSizeList(
+                Arc::new(Field::new("item", DataType::Float32, true)),
+                dimension as i32,
+            ),
+            true,
+        ),
+    ];
+    if with_text {
+        fields.push(Field::new("text", DataType::Utf8, true));
+    }
+    Schema::new(fields)
+}
+
+fn write_parquet(path: &Path, records: &[VectorRecord]) -> Result<()> {
+    let with_text = records.iter().any(|r| r.text.is_some());
+    // The column width follows the vectors (the table they came from)
+    let dimension = records
+        .iter()
+        .find_map(|r| r.vector.as_ref().map(|v| v.len()))
+        .unwrap_or(EMBEDDING_DIMENSION);
+    if let Some(record) = records
+        .iter()
+        .find(|r| r.vector.as_ref().is_some_and(|v| v.len() != dimension))
+    {
+        return Err(Error::InvalidRequest(format!(
+            "Vector for id '{}' has dimension {}, expected {}",
+            record.id,
+            record.vector.as_ref().map_or(0, |v| v.len()),
+            dimension
+        )));
+    }
+    let schema = Arc::new(parquet_schema(dimension, with_text));
+    let mut writer = ArrowWriter::try_new(File::create(path)?, schema.clone(), None)?;
+
+    for chunk in records.chunks(PARQUET_BATCH_SIZE) {
+        let ids = StringArray::from_iter_values(chunk.iter().map(|r| r.id.as_str()));
+
+        // Rows without a vector are stored as nulls backed by zeroed values
+        let values: Vec<f32> = chunk
+            .iter()
+            .flat_map(|r| r.vector.clone().unwrap_or_else(|| vec![0.0; dimension]))
+            .collect();
+        let validity: Vec<bool> = chunk.iter().map(|r| r.vector.is_some()).collect();
+        let vectors = FixedSizeListArray::try_new(
+            Arc::new(Field::new("item", DataType::Float32, true)),
+            dimension as i32,
+            Arc::new(Float32Array::from(values)),
+            Some(validity.into()),
+        )?;
+
+        let mut columns: Vec<ArrayRef> = vec![Arc::new(ids), Arc::new(vectors)];
+        if with_text {
+            columns.push(Arc::new(StringArray::from(
+                chunk.iter().map(|r| r.text.as_deref()).collect::<Vec<_>>(),
+            )));
+        }
+
+        let batch = RecordBatch::try_new(schema.clone(), columns)?;
+        writer.write(&batch)?;
+    }
+
+    writer.close()?;
+    Ok(())
+}
+
+fn read_parquet(path: &Path) -> Result<Vec<VectorRecord>> {
+    let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(path)?)
+        .and_then(|builder| builder.build())?;
+
+    let mut records = Vec::new();
+    for batch in reader {
+        let batch = batch?;
+        let ids = batch
+            .column_by_name("id")
+            .ok_or_else(|| Error::InvalidRequest("Parquet file has no 'id' column".to_string()))?;
+        let ids = arrow::compute::cast(ids, &DataType::Utf8)?;
+        let ids = ids.as_string::<i32>();
+        let vectors = batch.column_by_name("vector").ok_or_else(|| {
+            Error::InvalidRequest("Parquet file has no 'vector' column".to_string())
+        })?;
+        let texts = batch
+            .column_by_name("text")
+            .map(|c| arrow::compute::cast(c, &DataType::Utf8))
+            .transpose()?;
+
+        for row in 0..batch.num_rows() {
+            if ids.is_null(row) {
+                return Err(Error::InvalidRequest(format!(
+                    "Row {} has a null id",
+                    records.len()
+                )));
+            }
+            records.push(VectorRecord {
+                id: ids.value(row).to_string(),
+                vector: list_row(vectors, row)?,
+                text: texts.as_ref().and_then(|t| {
+                    let t = t.as_string::<i32>();
+                    (!t.is_null(row)).then(|| t.value(row).to_string())
+                }),
+            });
+        }
+    }
+    Ok(records)
+}
+
+/// Extract one row of a list-of-floats column (fixed or variable size,
+/// float32 or float64, as written by pyarrow/polars/pandas)
+fn list_row(column: &ArrayRef, row: usize) -> Result<Option<Vec<f32>>> {
+    if column.is_null(row) {
+        return Ok(None);
+    }
+    let values = match column.data_type() {
+        DataType::FixedSizeList(..) => column.as_fixed_size_list().value(row),
+        DataType::List(_) => column.as_list::<i32>().value(row),
+        DataType::LargeList(_) => column.as_list::<i64>().value(row),
+        other => {
+            return Err(Error::InvalidRequest(format!(
+                "'vector' column must be a list of floats, found {}",
+                other
+            )))
+        }
+    };
+    let values = arrow::compute::cast(&values, &DataType::Float32)?;
+    let values = values.as_primitive::<Float32Type>();
+    if values.null_count() > 0 {
+        return Err(Error::InvalidRequest(format!(
+            "Vector in row {} contains null values",
+            row
+        )));
+    }
+    Ok(Some(values.values().to_vec()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(id: &str, vector: Option<Vec<f32>>) -> VectorRecord {
+        VectorRecord {
+            id: id.to_string(),
+            vector,
+            text: None,
+        }
+    }
+
+    #[test]
+    fn test_validate_dimension() {
+        let dim = EMBEDDING_DIMENSION;
+        assert!(validate_vectors(&[record("a", Some(vec![0.5; dim]))], dim).is_ok());
+
+        let err = validate_vectors(&[record("b", Some(vec![0.5; 384]))], dim).unwrap_err();
+        assert!(err.to_string().contains("dimension 384"));
+        assert!(validate_vectors(&[record("c", None)], dim).is_err());
+
+        let mut nan = vec![0.5; dim];
+        nan[3] = f32::NAN;
+        assert!(validate_vectors(&[record("d", Some(nan))], dim).is_err());
+
+        // A table built with a smaller model takes its own width
+        assert!(validate_vectors(&[record("e", Some(vec![0.5; 384]))], 384).is_ok());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let dir = std::env::temp_dir().join(format!("ge-vectors-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut with_text = record("e2", None);
+        with_text.text = Some("Subject: hello".to_string());
+        let records = vec![
+            record(
+                "e1",
+                Some((0..EMBEDDING_DIMENSION).map(|i| i as f32).collect()),
+            ),
+            with_text,
+        ];
+
+        for format in [VectorFormat::Parquet, VectorFormat::Jsonl] {
+            let path = dir.join(format!("vectors.{}", format.as_str()));
+            assert_eq!(VectorFormat::from_path(&path), Some(format));
+            write_vectors(&path, format, &records).unwrap();
+            assert_eq!(read_vectors(&path, format).unwrap(), records);
+        }
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_parse_names() {
+        assert_eq!(
+            VectorFormat::parse("Parquet").unwrap(),
+            VectorFormat::Parquet
+        );
+        assert!(VectorFormat::parse("csv").is_err());
+        assert_eq!(VectorTable::parse("calendar").unwrap(), VectorTable::Events);
+    }
+}
@@ -1,9 +1,10 @@
 //! GroundEffect Daemon
 //!
-//! Long-running launchd service that handles email/calendar sync,
+//! Long-running launchd/systemd service that handles email/calendar sync,
 //! indexing, and writes to LanceDB.
 
-use std::sync::{Arc, RwLock};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, OnceLock, RwLock};
 
 use anyhow::Result;
 use chrono::{Duration, Utc};
@@ -11,22 +12,50 @@ use clap::{Parser, Subcommand};
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::TcpListener;
 use tokio::signal;
-use tracing::{error, info, warn, Level};
+use tokio::sync::{mpsc, watch};
+use tracing::{debug, error, info, warn, Level};
 use tracing_appender::rolling::{RollingFileAppender, Rotation};
 use tracing_subscriber::fmt::format::FmtSpan;
 use tracing_subscriber::fmt::time::ChronoLocal;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
-use tracing_subscriber::Layer;
-
-use groundeffect_core::config::{Config, EmbeddingFallback};
+use tracing_subscriber::{EnvFilter, Layer, Registry};
+
+use groundeffect_core::config::{
+    daemon_log_dir, set_active_profile, Config, DaemonConfig, EmbeddingFallback, LogFormat,
+};
+use groundeffect_core::config_layers::set_cli_overrides;
+#[cfg(unix)]
+use groundeffect_core::control;
+use groundeffect_core::control::ReloadRequest;
+use groundeffect_core::dav::CaldavServer;
 use groundeffect_core::db::Database;
+use groundeffect_core::digest::{run_scheduled, DIGEST_CHECK_SECS};
 use groundeffect_core::embedding::{EmbeddingEngine, EmbeddingModel, HybridEmbeddingProvider};
-use groundeffect_core::mcp::McpServer;
+use groundeffect_core::fts::{self, FtsScheduler, FTS_CHECK_SECS};
+use groundeffect_core::health::{
+    install_panic_hook, DaemonHealth, CRASH_LOOP_WINDOW_SECS, HEARTBEAT_INTERVAL_SECS,
+    STABLE_UPTIME_SECS,
+};
+use groundeffect_core::ics::{serve_feed, write_feed};
+use groundeffect_core::logs::TEXT_TIMESTAMP_FORMAT;
+use groundeffect_core::mcp::{McpServer, MCP_HTTP_DEFAULT_PORT, MCP_HTTP_TOKEN_ENV};
+use groundeffect_core::metrics;
 use groundeffect_core::models::{Account, AccountStatus};
 use groundeffect_core::oauth::OAuthManager;
+use groundeffect_core::reload::{self, ConfigWatcher, ReloadReport, WATCH_INTERVAL_SECS};
+use groundeffect_core::retention::RetentionReport;
+#[cfg(unix)]
+use groundeffect_core::search::SearchEngine;
+use groundeffect_core::spotlight;
 use groundeffect_core::sync::{SyncEvent, SyncManager, SyncType};
+use groundeffect_core::sync_window::{PolicyState, SyncSchedule, POLICY_CHECK_SECS};
+use groundeffect_core::token_health::{
+    check_account_token, notify_reauth, TokenCheck, TokenHealthReport,
+};
 use groundeffect_core::token_provider::create_token_provider;
+use groundeffect_core::vectors::VectorTable;
+use groundeffect_core::EMBEDDING_DIMENSION;
 
 #[derive(Parser)]
 #[command(name = "groundeffect-daemon")]
@@ -38,6 +67,16 @@ struct Cli {
     /// Enable file logging to ~/.local/share/groundeffect/logs/daemon.log
     #[arg(long, global = true)]
     log: bool,
+
+    /// Use a separate profile (own config, database, attachments and tokens).
+    /// Defaults to $GROUNDEFFECT_PROFILE.
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
+    /// Override a setting for this run (e.g. sync.store_raw=true). Repeatable;
+    /// beats config files and GROUNDEFFECT__* environment variables.
+    #[arg(long = "set", global = true, value_name = "KEY=VALUE")]
+    set: Vec<String>,
 }
 
 #[derive(Subcommand)]
@@ -57,47 +96,106 @@ enum Commands {
     },
     /// Run the daemon (default if no command specified)
     Run,
-    /// Run as MCP server (stdio JSON-RPC for Claude Code)
-    Mcp,
+    /// Run as MCP server (stdio JSON-RPC for Claude Code, or Streamable HTTP
+    /// for remote clients)
+    Mcp {
+        /// Transport: stdio or http
+        #[arg(long, default_value = "stdio")]
+        transport: String,
+        /// Address to listen on with --transport http. Anything but loopback
+        /// requires $GROUNDEFFECT_MCP_TOKEN.
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+        /// Port to listen on with --transport http
+        #[arg(long, default_value_t = MCP_HTTP_DEFAULT_PORT)]
+        port: u16,
+    },
+}
+
+/// LanceDB's internal logs are noisy below warn
+const QUIET_TARGETS: &str = "lance=warn,lancedb=warn,lance_core=warn,lance_index=warn,lance_table=warn,lance_file=warn,lance_encoding=warn";
+
+/// How long shutdown waits for email batches being written; launchd kills
+/// a service 20 seconds after SIGTERM
+const SHUTDOWN_DRAIN_SECS: u64 = 15;
+
+/// Swaps the log filter when `general.log_level` is reloaded
+static LOG_FILTER: OnceLock<tracing_subscriber::reload::Handle<EnvFilter, Registry>> =
+    OnceLock::new();
+
+/// The log filter for `general.log_level` (info if it isn't a level)
+fn log_filter(level: &str) -> EnvFilter {
+    let level = level.parse::<Level>().unwrap_or(Level::INFO);
+    EnvFilter::new(format!(
+        "{},{}",
+        level.as_str().to_lowercase(),
+        QUIET_TARGETS
+    ))
+}
+
+/// Log at `level` from now on
+fn set_log_level(level: &str) {
+    if let Some(handle) = LOG_FILTER.get() {
+        if let Err(e) = handle.reload(log_filter(level)) {
+            warn!("Failed to change log level: {}", e);
+        }
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
 
-    // Initialize logging (but not for MCP mode - it uses stdio for JSON-RPC)
-    let is_mcp = matches!(cli.command, Some(Commands::Mcp));
-    if !is_mcp {
+    // Select the profile before any config or data path is resolved
+    if let Some(profile) = &cli.profile {
+        set_active_profile(profile)?;
+    }
+    set_cli_overrides(&cli.set)?;
+
+    // Initialize logging (but not for stdio MCP mode - stdio carries the JSON-RPC)
+    let is_stdio_mcp =
+        matches!(&cli.command, Some(Commands::Mcp { transport, .. }) if transport != "http");
+    if !is_stdio_mcp {
         // Check CLI flag OR environment variable for logging
         let enable_logging = cli.log
             || std::env::var("GROUNDEFFECT_DAEMON_LOGGING")
                 .map(|v| v == "true" || v == "1")
                 .unwrap_or(false);
 
+        // The level can change at runtime (see set_log_level)
+        let general = Config::load().map(|c| c.general).unwrap_or_default();
+        let (filter, handle) =
+            tracing_subscriber::reload::Layer::new(log_filter(&general.log_level));
+        let _ = LOG_FILTER.set(handle);
+
         if enable_logging {
             // File logging to XDG data directory (~/.local/share/groundeffect/logs)
-            let log_dir = dirs::home_dir()
-                .unwrap_or_default()
-                .join(".local")
-                .join("share")
-                .join("groundeffect")
-                .join("logs");
+            let log_dir = daemon_log_dir();
             std::fs::create_dir_all(&log_dir).ok();
 
             let file_appender = RollingFileAppender::new(Rotation::DAILY, &log_dir, "daemon.log");
 
-            // Filter out noisy LanceDB internal logs
-            let filter = tracing_subscriber::filter::EnvFilter::new(
-                "info,lance=warn,lancedb=warn,lance_core=warn,lance_index=warn,lance_table=warn,lance_file=warn,lance_encoding=warn"
-            );
-
-            let file_layer = tracing_subscriber::fmt::layer()
-                .with_writer(file_appender)
-                .with_timer(ChronoLocal::new("%Y-%m-%d %H:%M:%S%.3f".to_string()))
-                .with_ansi(false)
-                .with_target(true)
-                .with_thread_ids(true)
-                .with_span_events(FmtSpan::CLOSE);
+            // `groundeffect logs` reads either format
+            let file_layer: Box<dyn Layer<Registry> + Send + Sync> = match general.log_format {
+                LogFormat::Text => Box::new(
+                    tracing_subscriber::fmt::layer()
+                        .with_writer(file_appender)
+                        .with_timer(ChronoLocal::new(TEXT_TIMESTAMP_FORMAT.to_string()))
+                        .with_ansi(false)
+                        .with_target(true)
+                        .with_thread_ids(true)
+                        .with_span_events(FmtSpan::CLOSE),
+                ),
+                LogFormat::Json => Box::new(
+                    tracing_subscriber::fmt::layer()
+                        .json()
+                        .with_writer(file_appender)
+                        .with_timer(ChronoLocal::rfc_3339())
+                        .with_target(true)
+                        .with_thread_ids(true)
+                        .with_span_events(FmtSpan::CLOSE),
+                ),
+            };
 
             tracing_subscriber::registry()
                 .with(file_layer.with_filter(filter))
@@ -107,11 +205,13 @@ async fn main() -> Result<()> {
             info!("Logging to {:?}", log_dir.join("daemon.log"));
         } else {
             // Console logging (default)
-            let _subscriber = tracing_subscriber::fmt()
-                .with_max_level(Level::INFO)
+            let console_layer = tracing_subscriber::fmt::layer()
                 .with_span_events(FmtSpan::CLOSE)
                 .with_target(true)
-                .with_thread_ids(true)
+                .with_thread_ids(true);
+
+            tracing_subscriber::registry()
+                .with(console_layer.with_filter(filter))
                 .init();
         }
     }
@@ -121,7 +221,11 @@ async fn main() -> Result<()> {
         Some(Commands::ListAccounts) => list_accounts().await,
         Some(Commands::RemoveAccount { account }) => remove_account(&account).await,
         Some(Commands::Run) | None => run_daemon().await,
-        Some(Commands::Mcp) => run_mcp_server().await,
+        Some(Commands::Mcp {
+            transport,
+            host,
+            port,
+        }) => run_mcp_server(&transport, &host, port).await,
     }
 }
 
@@ -249,6 +353,11 @@ Content-Type: text/html; charset=utf-8
             oldest_event_synced: None,
             sync_attachments: false, // Off by default
             estimated_total_emails: None,
+            include_labels: vec![],
+            exclude_labels: vec![],
+            max_storage_bytes: None,
+            retention: Default::default(),
+            headers_only: false,
         };
         db.upsert_account(&account).await?;
         println!(" Account created: {}", account.id);
@@ -419,6 +528,8 @@ async fn run_daemon() -> Result<()> {
         warn!("Failed to load config: {}, using defaults", e);
         Config::default()
     }));
+    // The settings in effect; reloads replace them (see reload_config)
+    let (config_tx, mut poll_settings) = watch::channel(config.clone());
 
     // Ensure data directories exist
     std::fs::create_dir_all(config.lancedb_dir())?;
@@ -426,9 +537,46 @@ async fn run_daemon() -> Result<()> {
     std::fs::create_dir_all(config.models_dir())?;
     std::fs::create_dir_all(config.sync_state_dir())?;
 
+    // Record this start so crash loops hidden by launchd KeepAlive are visible
+    let health_file = config.daemon_health_file();
+    let mut health = DaemonHealth::load(&health_file);
+    if health.record_start(std::process::id(), Utc::now()) {
+        warn!(
+            "Previous daemon run exited uncleanly ({} unclean restarts total)",
+            health.restart_count
+        );
+    }
+    if let Err(e) = health.save(&health_file) {
+        warn!("Failed to write daemon health file: {}", e);
+    }
+    install_panic_hook(health_file.clone());
+
+    // While crash-looping, skip historical backfill and slow down polling
+    // until the daemon has stayed up for a while
+    let crash_looping = Arc::new(AtomicBool::new(health.is_crash_looping()));
+    let sync_backoff = Arc::new(AtomicU32::new(health.sync_backoff_factor()));
+    if health.is_crash_looping() {
+        warn!(
+            "Crash loop detected: {} unclean exits in the last {} minutes. \
+             Deferring historical sync and polling {}x less often until stable.",
+            health.recent_crashes.len(),
+            CRASH_LOOP_WINDOW_SECS / 60,
+            health.sync_backoff_factor()
+        );
+    }
+
     // Initialize database
     info!("Opening database at {:?}", config.lancedb_dir());
-    let db = Arc::new(Database::open(config.lancedb_dir()).await?);
+    let db = Arc::new(
+        Database::open_with_dimension(
+            config.lancedb_dir(),
+            config
+                .search
+                .vector_dimension()
+                .unwrap_or(EMBEDDING_DIMENSION),
+        )
+        .await?,
+    );
 
     // Ensure indexes exist in background (doesn't block startup)
     let db_for_indexes = db.clone();
@@ -456,17 +604,28 @@ async fn run_daemon() -> Result<()> {
         let model_type = EmbeddingModel::from_str(&config.search.embedding_model)
             .unwrap_or(EmbeddingModel::BgeBaseEn);
         Some(Arc::new(
-            EmbeddingEngine::from_cache(config.models_dir(), model_type, config.search.use_gpu)
-                .map_err(|e| {
-                    error!("Failed to load embedding model: {}", e);
-                    e
-                })?,
+            EmbeddingEngine::from_cache(
+                config.models_dir(),
+                model_type,
+                config.search.use_gpu,
+                config.search.embedding_precision,
+            )
+            .map_err(|e| {
+                error!("Failed to load embedding model: {}", e);
+                e
+            })?,
         ))
     };
-    let embedding = Arc::new(HybridEmbeddingProvider::from_search_config(
-        local_embedding,
-        &config.search,
-    )?);
+    // The width the configured model produces; the re-embed task below
+    // migrates the tables to it
+    let dimension = config
+        .search
+        .vector_dimension()
+        .unwrap_or_else(|| db.vector_dimension(VectorTable::Emails));
+    let embedding = Arc::new(
+        HybridEmbeddingProvider::from_search_config(local_embedding, &config.search)?
+            .with_vector_dimension(dimension),
+    );
 
     // Initialize token provider and OAuth manager
     let token_provider = create_token_provider(&config).await?;
@@ -496,6 +655,11 @@ async fn run_daemon() -> Result<()> {
                         oldest_event_synced: None,
                         sync_attachments: false,
                         estimated_total_emails: None,
+                        include_labels: vec![],
+                        exclude_labels: vec![],
+                        max_storage_bytes: None,
+                        retention: Default::default(),
+                        headers_only: false,
                     };
                     if let Err(e) = db.upsert_account(&account).await {
                         error!("Failed to create account record for {}: {}", email, e);
@@ -521,6 +685,227 @@ async fn run_daemon() -> Result<()> {
         embedding.clone(),
     ));
 
+    // Finish email batches the last run was killed in the middle of writing
+    match sync_manager.recover_writes().await {
+        Ok(0) => {}
+        Ok(replayed) => info!("Replayed {} unfinished email batches", replayed),
+        Err(e) => warn!("Failed to read the write log: {}", e),
+    }
+
+    // Check sync cursors against the stored data before syncing from them;
+    // index and embedding repairs run in the background below
+    let recovery_file = config.recovery_report_file();
+    let mut recovery = match sync_manager.check_consistency().await {
+        Ok(report) => {
+            for issue in &report.issues {
+                info!(
+                    "Consistency: {}{} ({:?}: {})",
+                    issue
+                        .account_id
+                        .as_ref()
+                        .map(|id| format!("{}: ", id))
+                        .unwrap_or_default(),
+                    issue.detail,
+                    issue.status,
+                    issue.action
+                );
+            }
+            info!("Startup consistency scan: {}", report.summary());
+            if let Err(e) = report.save(&recovery_file) {
+                warn!("Failed to write consistency report: {}", e);
+            }
+            Some(report)
+        }
+        Err(e) => {
+            warn!("Startup consistency scan failed: {}", e);
+            None
+        }
+    };
+
+    // Apply the sync window (quiet hours, battery, Wi-Fi) before the first
+    // syncs, then re-check it every minute
+    let schedule = SyncSchedule::from_config(&DaemonConfig::load().unwrap_or_default())
+        .unwrap_or_else(|e| {
+            warn!("Ignoring sync window settings: {}", e);
+            SyncSchedule::default()
+        });
+    let policy = apply_sync_window(&schedule, &config, &sync_manager).await;
+    if let Some(policy) = &policy {
+        info!("Sync window: {}", policy.summary());
+    }
+    {
+        let sync_manager_window = sync_manager.clone();
+        let db_window = db.clone();
+        let config_window = config.clone();
+        let crash_looping_window = crash_looping.clone();
+        tokio::spawn(async move {
+            let mut last = policy;
+            let mut window_timer = poll_timer(POLICY_CHECK_SECS);
+            loop {
+                window_timer.tick().await;
+                let Some(policy) =
+                    apply_sync_window(&schedule, &config_window, &sync_manager_window).await
+                else {
+                    continue;
+                };
+                if last.as_ref().map(PolicyState::summary) != Some(policy.summary()) {
+                    info!("Sync window: {}", policy.summary());
+                }
+                let resumed = last.as_ref().is_some_and(|p| p.sync_paused.is_some())
+                    && policy.sync_paused.is_none();
+                last = Some(policy);
+
+                // Catch up on the sync checks and polls skipped while paused;
+                // when crash-looping, the watchdog runs them once stable
+                if resumed && !crash_looping_window.load(Ordering::Relaxed) {
+                    let sync_manager_resume = sync_manager_window.clone();
+                    let db_resume = db_window.clone();
+                    tokio::spawn(async move {
+                        let Ok(accounts) = db_resume.list_accounts().await else {
+                            return;
+                        };
+                        for account in &accounts {
+                            match sync_manager_resume.initial_sync(&account.id).await {
+                                Ok(_) => info!("Sync check completed for {}", account.id),
+                                Err(e) => error!("Sync failed for {}: {}", account.id, e),
+                            }
+                        }
+                    });
+                }
+            }
+        });
+    }
+
+    // Serve CLI searches from the warm database and embedding model, and
+    // `groundeffect daemon reload`
+    let search = Arc::new(SearchEngine::new(db.clone(), embedding.clone()));
+    let (reload_tx, mut reload_rx) = mpsc::channel::<ReloadRequest>(4);
+    #[cfg(unix)]
+    {
+        let search = search.clone();
+        let socket = config.control_socket_path();
+        tokio::spawn(async move {
+            if let Err(e) = control::serve(socket, search, reload_tx).await {
+                warn!("Control socket stopped: {}", e);
+            }
+        });
+    }
+
+    // Spawn watchdog: heartbeat, lift crash-loop backoff once stable, shed
+    // idle resources, and restart stalled account syncs. It starts before
+    // the first syncs so it can rescue one that hangs at startup.
+    let sync_manager_watchdog = sync_manager.clone();
+    let db_watchdog = db.clone();
+    let embedding_watchdog = embedding.clone();
+    let search_watchdog = search.clone();
+    let shed_after = config.sync.shed_idle_after_secs;
+    let crash_looping_watchdog = crash_looping.clone();
+    let sync_backoff_watchdog = sync_backoff.clone();
+    let health_file_watchdog = health_file.clone();
+    tokio::spawn(async move {
+        let started = std::time::Instant::now();
+        let mut stable = false;
+        // Accounts whose IMAP IDLE was stopped, while resources are shed
+        let mut shed_accounts: Option<Vec<String>> = None;
+        let mut heartbeat_timer =
+            tokio::time::interval(tokio::time::Duration::from_secs(HEARTBEAT_INTERVAL_SECS));
+
+        loop {
+            heartbeat_timer.tick().await;
+
+            // Reload so panic records written by the hook aren't clobbered
+            let mut health = DaemonHealth::load(&health_file_watchdog);
+            health.record_heartbeat(Utc::now());
+            let became_stable = !stable && started.elapsed().as_secs() >= STABLE_UPTIME_SECS;
+            if became_stable {
+                stable = true;
+                health.mark_stable();
+            }
+
+            let idle_secs = embedding_watchdog.idle_for().as_secs();
+            shed_accounts = match shed_accounts.take() {
+                None if shed_after > 0 && idle_secs >= shed_after => {
+                    let stopped = shed_idle_resources(
+                        &sync_manager_watchdog,
+                        &embedding_watchdog,
+                        &search_watchdog,
+                        &db_watchdog,
+                    )
+                    .await;
+                    info!(
+                        "No queries or sync work for {}s; released idle resources",
+                        idle_secs
+                    );
+                    health.record_shed(Utc::now());
+                    Some(stopped)
+                }
+                Some(accounts) if idle_secs < shed_after => {
+                    // The model reloaded itself on first use; bring IDLE back
+                    info!("Activity after idle period; restoring IMAP IDLE");
+                    for account_id in &accounts {
+                        if let Err(e) = sync_manager_watchdog.start_idle(account_id).await {
+                            warn!("Failed to restart IMAP IDLE for {}: {}", account_id, e);
+                        }
+                    }
+                    health.record_restore(Utc::now());
+                    None
+                }
+                unchanged => unchanged,
+            };
+
+            // Cancel and restart stuck syncs, recording why. The restart runs
+            // in its own task so a reconnect or sync that hangs again can't
+            // stop the next check
+            for incident in sync_manager_watchdog.check_stalls(Utc::now()) {
+                warn!(
+                    "Sync watchdog: {} for {}; restarting its sync",
+                    incident.detail, incident.account_id
+                );
+                let sync_manager_restart = sync_manager_watchdog.clone();
+                let account_id = incident.account_id.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = sync_manager_restart.recover_account(&account_id).await {
+                        warn!("Failed to reconnect IMAP IDLE for {}: {}", account_id, e);
+                    }
+                    if let Err(e) = sync_manager_restart
+                        .trigger_sync(&[account_id.clone()], SyncType::Email)
+                        .await
+                    {
+                        warn!(
+                            "Sync after watchdog restart failed for {}: {}",
+                            account_id, e
+                        );
+                    }
+                });
+                health.record_sync_incident(incident);
+            }
+
+            if let Err(e) = health.save(&health_file_watchdog) {
+                warn!("Failed to write daemon health file: {}", e);
+            }
+
+            if became_stable && crash_looping_watchdog.swap(false, Ordering::Relaxed) {
+                info!("Daemon stable; resuming normal sync after crash loop");
+                sync_backoff_watchdog.store(1, Ordering::Relaxed);
+                // In its own task: a backfill that hangs mustn't stop the
+                // heartbeat or the stall checks that would rescue it
+                let sync_manager_resume = sync_manager_watchdog.clone();
+                let db_resume = db_watchdog.clone();
+                tokio::spawn(async move {
+                    let Ok(accounts) = db_resume.list_accounts().await else {
+                        return;
+                    };
+                    for account in &accounts {
+                        match sync_manager_resume.initial_sync(&account.id).await {
+                            Ok(_) => info!("Sync check completed for {}", account.id),
+                            Err(e) => error!("Sync failed for {}: {}", account.id, e),
+                        }
+                    }
+                });
+            }
+        }
+    });
+
     // Take the event receiver
     let mut event_rx = sync_manager
         .take_event_receiver()
@@ -548,9 +933,16 @@ async fn run_daemon() -> Result<()> {
                 // - Skip (if historical sync already complete)
                 // - Resume (if partially synced)
                 // - Start fresh (if no emails)
-                match sync_manager.initial_sync(&account.id).await {
-                    Ok(_) => info!("Sync check completed for {}", account.id),
-                    Err(e) => error!("Sync failed for {}: {}", account.id, e),
+                // When crash-looping, the watchdog runs it once the daemon is stable.
+                if crash_looping.load(Ordering::Relaxed) {
+                    info!("Deferring sync check for {} (crash loop)", account.id);
+                } else if let Some(reason) = sync_manager.sync_paused() {
+                    info!("Deferring sync check for {} ({})", account.id, reason);
+                } else {
+                    match sync_manager.initial_sync(&account.id).await {
+                        Ok(_) => info!("Sync check completed for {}", account.id),
+                        Err(e) => error!("Sync failed for {}: {}", account.id, e),
+                    }
                 }
 
                 // Start IMAP IDLE for real-time notifications
@@ -566,14 +958,69 @@ async fn run_daemon() -> Result<()> {
         }
     }
 
+    // Re-embed in the background if the embedding model or an [embedding]
+    // template changed, run the consistency scan's repairs, then build
+    // threads, extractions and tasks for mail synced before they existed
+    let sync_manager_reembed = sync_manager.clone();
+    tokio::spawn(async move {
+        if let Err(e) = sync_manager_reembed.reembed_if_changed().await {
+            error!("Failed to re-embed after embedding change: {}", e);
+        }
+        if let Some(report) = recovery.as_mut() {
+            sync_manager_reembed.repair(report).await;
+            info!("Consistency repairs done: {}", report.summary());
+            if let Err(e) = report.save(&recovery_file) {
+                warn!("Failed to write consistency report: {}", e);
+            }
+        }
+        if let Err(e) = sync_manager_reembed.ensure_threads().await {
+            error!("Failed to build threads: {}", e);
+        }
+        if let Err(e) = sync_manager_reembed.ensure_extractions().await {
+            error!("Failed to extract reservations and packages: {}", e);
+        }
+        if let Err(e) = sync_manager_reembed.ensure_tasks().await {
+            error!("Failed to find tasks: {}", e);
+        }
+    });
+
+    // Spawn full-text index updates: syncs report the rows they wrote and
+    // the scheduler decides when the indexes are worth updating
+    let fts_scheduler = Arc::new(std::sync::Mutex::new(FtsScheduler::new(&config.index)));
+    {
+        let db_fts = db.clone();
+        let config_fts = config.clone();
+        let fts_scheduler = fts_scheduler.clone();
+        tokio::spawn(async move {
+            let mut fts_timer =
+                tokio::time::interval(tokio::time::Duration::from_secs(FTS_CHECK_SECS));
+            loop {
+                fts_timer.tick().await;
+                let started = chrono::Utc::now();
+                if !fts_scheduler.lock().unwrap().due(started) {
+                    continue;
+                }
+                // Rows written while the update runs wait for the next one
+                fts_scheduler.lock().unwrap().updated(started);
+                if let Err(e) = fts::update_indexes(
+                    &db_fts,
+                    config_fts.index.fts_strategy,
+                    &config_fts.index_state_file(),
+                )
+                .await
+                {
+                    error!("Failed to update FTS indexes: {}", e);
+                }
+            }
+        });
+    }
+
     // Spawn event handler
     let sync_manager_clone = sync_manager.clone();
     let db_clone = db.clone();
+    let config_events = config.clone();
+    let oauth_events = oauth.clone();
     tokio::spawn(async move {
-        // Track last FTS index rebuild time (rebuild at most every 5 minutes)
-        let mut last_fts_rebuild: Option<std::time::Instant> = None;
-        let fts_rebuild_interval = std::time::Duration::from_secs(300);
-
         while let Some(event) = event_rx.recv().await {
             match event {
                 SyncEvent::NewEmail { account_id, .. } => {
@@ -597,45 +1044,38 @@ async fn run_daemon() -> Result<()> {
                     );
 
                     // Reset status to Active if it was NeedsReauth (successful sync proves auth works)
-                    if let Ok(Some(mut account)) = db_clone.get_account(&account_id).await {
+                    if let Ok(Some(account)) = db_clone.get_account(&account_id).await {
                         if account.status == AccountStatus::NeedsReauth {
                             info!("Resetting account {} status from NeedsReauth to Active after successful sync", account_id);
-                            account.status = AccountStatus::Active;
-                            if let Err(e) = db_clone.upsert_account(&account).await {
-                                error!("Failed to update account status: {}", e);
-                            }
+                            record_token_check(
+                                &db_clone,
+                                &config_events,
+                                &account_id,
+                                TokenCheck::Ok,
+                            )
+                            .await;
                         }
                     }
 
-                    // Rebuild FTS indexes if enough time has passed since last rebuild
-                    if count > 0 {
-                        let should_rebuild = last_fts_rebuild
-                            .map(|t| t.elapsed() >= fts_rebuild_interval)
-                            .unwrap_or(true);
-
-                        if should_rebuild {
-                            let db_for_fts = db_clone.clone();
-                            tokio::spawn(async move {
-                                if let Err(e) = db_for_fts.rebuild_fts_indexes().await {
-                                    error!("Failed to rebuild FTS indexes: {}", e);
-                                }
-                            });
-                            last_fts_rebuild = Some(std::time::Instant::now());
-                        }
-                    }
+                    fts_scheduler
+                        .lock()
+                        .unwrap()
+                        .record_writes(count, chrono::Utc::now());
                 }
                 SyncEvent::SyncError { account_id, error } => {
-                    error!("Sync error for {}: {}", account_id, error);
+                    // The caller that ran the sync logs the failure too
+                    debug!("Sync error for {}: {}", account_id, error);
                 }
                 SyncEvent::AuthRequired { account_id } => {
                     warn!("Re-authentication required for {}", account_id);
-                    // Update account status in database
-                    if let Ok(Some(mut account)) = db_clone.get_account(&account_id).await {
-                        account.status = AccountStatus::NeedsReauth;
-                        if let Err(e) = db_clone.upsert_account(&account).await {
-                            error!("Failed to update account status: {}", e);
-                        }
-                    }
+                    // Find out why (revoked, expired, missing) before recording it
+                    let check = match check_account_token(&oauth_events, &account_id).await {
+                        TokenCheck::NeedsReauth(reason) => TokenCheck::NeedsReauth(reason),
+                        _ => TokenCheck::NeedsReauth(
+                            "Google rejected the credentials during sync".to_string(),
+                        ),
+                    };
+                    record_token_check(&db_clone, &config_events, &account_id, check).await;
                 }
                 _ => {
                     info!("Sync event: {:?}", event);
@@ -648,25 +1088,268 @@ async fn run_daemon() -> Result<()> {
     let initialized_accounts: Arc<RwLock<std::collections::HashSet<String>>> =
         Arc::new(RwLock::new(accounts.iter().map(|a| a.id.clone()).collect()));
 
+    // Spawn token health checks so revoked refresh tokens are noticed (and
+    // reported) even when no sync happens to fail
+    if config.sync.token_check_interval_secs > 0 {
+        let db_tokens = db.clone();
+        let config_tokens = config.clone();
+        let oauth_tokens = oauth.clone();
+        tokio::spawn(async move {
+            let mut token_timer = tokio::time::interval(tokio::time::Duration::from_secs(
+                config_tokens.sync.token_check_interval_secs,
+            ));
+            loop {
+                token_timer.tick().await;
+                let Ok(accounts) = db_tokens.list_accounts().await else {
+                    continue;
+                };
+                for account in accounts
+                    .iter()
+                    .filter(|a| a.status != AccountStatus::Disabled)
+                {
+                    let check = check_account_token(&oauth_tokens, &account.id).await;
+                    record_token_check(&db_tokens, &config_tokens, &account.id, check).await;
+                }
+            }
+        });
+    }
+
+    // Spawn retention pruning: drop mail past each account's retention
+    // policy and record what went for `sync status`
+    if config.sync.retention_interval_secs > 0 {
+        let db_retention = db.clone();
+        let config_retention = config.clone();
+        let sync_manager_retention = sync_manager.clone();
+        tokio::spawn(async move {
+            let mut retention_timer = tokio::time::interval(tokio::time::Duration::from_secs(
+                config_retention.sync.retention_interval_secs,
+            ));
+            loop {
+                retention_timer.tick().await;
+                let Ok(accounts) = db_retention.list_accounts().await else {
+                    continue;
+                };
+                let report_file = config_retention.retention_report_file();
+                let mut report = RetentionReport::load(&report_file);
+                for account in accounts.iter().filter(|a| !a.retention.is_empty()) {
+                    match sync_manager_retention.enforce_retention(&account.id).await {
+                        Ok(pruned) => report.record(&account.id, pruned),
+                        Err(e) => error!("Retention pruning failed for {}: {}", account.id, e),
+                    }
+                }
+                if let Err(e) = report.save(&report_file) {
+                    warn!("Failed to write retention report: {}", e);
+                }
+            }
+        });
+    }
+
+    // Spawn the scheduled digest email: checked every few minutes, sent once
+    // per slot (the last-sent time survives restarts)
+    if config.digest.enabled {
+        let db_digest = db.clone();
+        let config_digest = config.clone();
+        let oauth_digest = oauth.clone();
+        tokio::spawn(async move {
+            let state_file = config_digest.digest_state_file();
+            let mut digest_timer =
+                tokio::time::interval(tokio::time::Duration::from_secs(DIGEST_CHECK_SECS));
+            loop {
+                digest_timer.tick().await;
+                let now = Utc::now();
+                let last_sent = std::fs::read_to_string(&state_file)
+                    .ok()
+                    .and_then(|s| chrono::DateTime::parse_from_rfc3339(s.trim()).ok())
+                    .map(|t| t.with_timezone(&Utc));
+                match run_scheduled(&db_digest, &config_digest, &oauth_digest, last_sent, now).await
+                {
+                    Ok(false) => {}
+                    Ok(true) => {
+                        if let Err(e) = std::fs::write(&state_file, now.to_rfc3339()) {
+                            warn!("Failed to record digest send time: {}", e);
+                        }
+                    }
+                    Err(e) => error!("Scheduled digest failed: {}", e),
+                }
+            }
+        });
+    }
+
+    // Spawn the ICS feed: the merged calendars are rewritten to a file on
+    // an interval, and served read-only on loopback when a port is set
+    if config.ics_feed.enabled {
+        let db_feed = db.clone();
+        let config_feed = config.clone();
+        if let Some(port) = config.ics_feed.port {
+            let path = config.ics_feed_file();
+            tokio::spawn(async move {
+                if let Err(e) = serve_feed(port, path).await {
+                    error!("Calendar feed server failed: {}", e);
+                }
+            });
+        }
+        tokio::spawn(async move {
+            let mut feed_timer = tokio::time::interval(tokio::time::Duration::from_secs(
+                config_feed.ics_feed.interval_secs.max(60),
+            ));
+            loop {
+                feed_timer.tick().await;
+                match write_feed(&db_feed, &config_feed, Utc::now()).await {
+                    Ok(path) => debug!("Wrote calendar feed to {:?}", path),
+                    Err(e) => warn!("Failed to write calendar feed: {}", e),
+                }
+            }
+        });
+    }
+
+    // Spawn the metrics endpoint, and the textfile export when one is set
+    if config.metrics.enabled {
+        let started = std::time::Instant::now();
+        let db_metrics = db.clone();
+        let port = config.metrics.port;
+        tokio::spawn(async move {
+            if let Err(e) = metrics::serve(port, db_metrics, started).await {
+                error!("Metrics server failed: {}", e);
+            }
+        });
+        if let Some(path) = config.metrics_textfile() {
+            let db_metrics = db.clone();
+            let interval_secs = config.metrics.interval_secs.max(5);
+            tokio::spawn(async move {
+                let mut metrics_timer =
+                    tokio::time::interval(tokio::time::Duration::from_secs(interval_secs));
+                loop {
+                    metrics_timer.tick().await;
+                    if let Err(e) = metrics::write_textfile(&db_metrics, started, &path).await {
+                        warn!("Failed to write metrics to {:?}: {}", path, e);
+                    }
+                }
+            });
+        }
+    }
+
+    // Spawn the read-only CalDAV server for calendar apps
+    if config.caldav.enabled {
+        let caldav = Arc::new(CaldavServer::new(db.clone(), config.clone()));
+        tokio::spawn(async move {
+            if let Err(e) = caldav.run().await {
+                error!("CalDAV server failed: {}", e);
+            }
+        });
+    }
+
+    // Spawn the export to macOS Calendar and Contacts
+    #[cfg(feature = "apple-bridge")]
+    if config.apple.enabled {
+        let db_apple = db.clone();
+        let config_apple = config.clone();
+        tokio::spawn(async move {
+            let mut apple_timer = tokio::time::interval(tokio::time::Duration::from_secs(
+                config_apple.apple.interval_secs.max(300),
+            ));
+            loop {
+                apple_timer.tick().await;
+                if let Err(e) =
+                    groundeffect_core::apple::export(&db_apple, &config_apple, Utc::now()).await
+                {
+                    warn!("Failed to export to Calendar and Contacts: {}", e);
+                }
+            }
+        });
+    }
+    #[cfg(not(feature = "apple-bridge"))]
+    if config.apple.enabled {
+        warn!("[apple] is enabled, but this build lacks the apple-bridge feature");
+    }
+
+    // Spawn the Spotlight stub writer, so system search finds synced mail
+    if config.spotlight.enabled {
+        let db_spotlight = db.clone();
+        let config_spotlight = config.clone();
+        tokio::spawn(async move {
+            let mut spotlight_timer = tokio::time::interval(tokio::time::Duration::from_secs(
+                config_spotlight.spotlight.interval_secs.max(300),
+            ));
+            loop {
+                spotlight_timer.tick().await;
+                if let Err(e) =
+                    spotlight::export(&db_spotlight, &config_spotlight, Utc::now()).await
+                {
+                    warn!("Failed to update Spotlight stubs: {}", e);
+                }
+            }
+        });
+    }
+
+    // Apply config changes when config.toml is edited or on `daemon reload`
+    let sync_manager_reload = sync_manager.clone();
+    tokio::spawn(async move {
+        let mut watcher = ConfigWatcher::new(Config::config_path());
+        let mut watch_timer =
+            tokio::time::interval(tokio::time::Duration::from_secs(WATCH_INTERVAL_SECS));
+        loop {
+            let reply = tokio::select! {
+                _ = watch_timer.tick() => {
+                    if !watcher.changed() {
+                        continue;
+                    }
+                    info!("Config file changed, reloading");
+                    None
+                }
+                Some(reply) = reload_rx.recv() => Some(reply),
+            };
+            let result = reload_config(&config_tx, &sync_manager_reload);
+            if let Err(e) = &result {
+                warn!("Config reload failed, keeping current settings: {}", e);
+            }
+            if let Some(reply) = reply {
+                let _ = reply.send(result);
+            }
+        }
+    });
+
     // Spawn periodic sync task with new account detection
     let sync_manager_poll = sync_manager.clone();
     let config_poll = config.clone();
     let db_poll = db.clone();
     let initialized_accounts_poll = initialized_accounts.clone();
+    let sync_backoff_poll = sync_backoff.clone();
     tokio::spawn(async move {
-        let email_interval =
-            tokio::time::Duration::from_secs(config_poll.sync.email_poll_interval_secs);
-        let calendar_interval =
-            tokio::time::Duration::from_secs(config_poll.sync.calendar_poll_interval_secs);
+        let (mut email_secs, mut calendar_secs) = {
+            let settings = poll_settings.borrow_and_update();
+            (
+                settings.sync.email_poll_interval_secs,
+                settings.sync.calendar_poll_interval_secs,
+            )
+        };
         // Check for new accounts every 5 seconds
         let new_account_interval = tokio::time::Duration::from_secs(5);
 
-        let mut email_timer = tokio::time::interval(email_interval);
-        let mut calendar_timer = tokio::time::interval(calendar_interval);
+        let mut email_timer = tokio::time::interval(tokio::time::Duration::from_secs(email_secs));
+        let mut calendar_timer =
+            tokio::time::interval(tokio::time::Duration::from_secs(calendar_secs));
         let mut new_account_timer = tokio::time::interval(new_account_interval);
 
+        // Poll ticks are skipped while backing off from a crash loop
+        let mut email_ticks: u32 = 0;
+        let mut calendar_ticks: u32 = 0;
+
         loop {
             tokio::select! {
+                Ok(()) = poll_settings.changed() => {
+                    // Restart the timers whose interval was reloaded
+                    let settings = poll_settings.borrow_and_update().clone();
+                    if settings.sync.email_poll_interval_secs != email_secs {
+                        email_secs = settings.sync.email_poll_interval_secs;
+                        info!("Polling email every {}s", email_secs);
+                        email_timer = poll_timer(email_secs);
+                    }
+                    if settings.sync.calendar_poll_interval_secs != calendar_secs {
+                        calendar_secs = settings.sync.calendar_poll_interval_secs;
+                        info!("Polling calendars every {}s", calendar_secs);
+                        calendar_timer = poll_timer(calendar_secs);
+                    }
+                }
                 _ = new_account_timer.tick() => {
                     // Check for newly added accounts
                     if let Ok(accounts) = db_poll.list_accounts().await {
@@ -714,6 +1397,10 @@ async fn run_daemon() -> Result<()> {
                     }
                 }
                 _ = email_timer.tick() => {
+                    email_ticks = email_ticks.wrapping_add(1);
+                    if email_ticks % sync_backoff_poll.load(Ordering::Relaxed).max(1) != 0 {
+                        continue;
+                    }
                     // Poll sync for all accounts (fallback if IDLE is disabled or disconnected)
                     if let Ok(accounts) = db_poll.list_accounts().await {
                         let account_ids: Vec<String> = accounts.iter().map(|a| a.id.clone()).collect();
@@ -723,6 +1410,10 @@ async fn run_daemon() -> Result<()> {
                     }
                 }
                 _ = calendar_timer.tick() => {
+                    calendar_ticks = calendar_ticks.wrapping_add(1);
+                    if calendar_ticks % sync_backoff_poll.load(Ordering::Relaxed).max(1) != 0 {
+                        continue;
+                    }
                     // Poll calendar sync for all accounts
                     if let Ok(accounts) = db_poll.list_accounts().await {
                         let account_ids: Vec<String> = accounts.iter().map(|a| a.id.clone()).collect();
@@ -738,16 +1429,190 @@ async fn run_daemon() -> Result<()> {
     info!("Daemon is running. Press Ctrl+C to stop.");
 
     // Wait for shutdown signal
-    signal::ctrl_c().await?;
+    wait_for_shutdown().await?;
 
     info!("Shutting down daemon...");
+    if !sync_manager
+        .drain(std::time::Duration::from_secs(SHUTDOWN_DRAIN_SECS))
+        .await
+    {
+        warn!(
+            "Email writes still running after {}s; the write log will finish them on the next start",
+            SHUTDOWN_DRAIN_SECS
+        );
+    }
+    if let Err(e) = sync_manager.checkpoint().await {
+        warn!("Failed to checkpoint sync state: {}", e);
+    }
+    let _ = std::fs::remove_file(config.control_socket_path());
+    let mut health = DaemonHealth::load(&health_file);
+    health.record_shutdown(Utc::now());
+    if let Err(e) = health.save(&health_file) {
+        warn!("Failed to write daemon health file: {}", e);
+    }
+    Ok(())
+}
+
+/// Re-read the config and apply the settings that can change at runtime
+///
+/// `current` holds the settings in effect; changes that need a restart are
+/// logged and left out of it, so later reloads still report them.
+fn reload_config(
+    current: &watch::Sender<Arc<Config>>,
+    sync_manager: &SyncManager,
+) -> groundeffect_core::error::Result<ReloadReport> {
+    let new = reload::load()?;
+    let running = current.borrow().clone();
+    let report = reload::plan(&running, &new)?;
+
+    if !report.applied.is_empty() {
+        if new.general.log_level != running.general.log_level {
+            set_log_level(&new.general.log_level);
+        }
+        sync_manager.apply_limits(&new.sync);
+        info!("Applied config changes: {}", report.applied.join(", "));
+        current.send_replace(Arc::new(reload::merge(&running, &new)));
+    }
+    if !report.restart_required.is_empty() {
+        warn!(
+            "Restart the daemon to apply: {}",
+            report.restart_required.join(", ")
+        );
+    }
+    Ok(report)
+}
+
+/// Check the sync window, apply it to the sync manager and write it for
+/// `daemon status`
+async fn apply_sync_window(
+    schedule: &SyncSchedule,
+    config: &Config,
+    sync_manager: &SyncManager,
+) -> Option<PolicyState> {
+    let check = {
+        let schedule = schedule.clone();
+        let timezone = config.general.timezone.clone();
+        tokio::task::spawn_blocking(move || schedule.check(&timezone, Utc::now()))
+    };
+    let policy = match check.await {
+        Ok(policy) => policy,
+        Err(e) => {
+            warn!("Failed to check the sync window: {}", e);
+            return None;
+        }
+    };
+    sync_manager.apply_policy(&policy);
+    if let Err(e) = policy.save(&config.sync_policy_file()) {
+        warn!("Failed to write sync policy state: {}", e);
+    }
+    Some(policy)
+}
+
+/// A poll timer whose first tick is a full interval away
+fn poll_timer(secs: u64) -> tokio::time::Interval {
+    let period = tokio::time::Duration::from_secs(secs);
+    tokio::time::interval_at(tokio::time::Instant::now() + period, period)
+}
+
+/// Release memory the daemon only needs while it's busy: the local
+/// embedding model (reloaded by the next embed), IMAP IDLE connections,
+/// cached search results and table handles. Returns the accounts whose
+/// IDLE connection was closed.
+async fn shed_idle_resources(
+    sync_manager: &SyncManager,
+    embedding: &HybridEmbeddingProvider,
+    search: &SearchEngine,
+    db: &Database,
+) -> Vec<String> {
+    embedding.unload_local();
+    let stopped = sync_manager.stop_idle();
+    search.clear_cache();
+    if let Err(e) = db.refresh_tables().await {
+        warn!("Failed to reopen database tables: {}", e);
+    }
+    stopped
+}
+
+/// Record a token check in the health file, update the account status, and
+/// notify the user the first time an account needs re-authentication
+async fn record_token_check(db: &Database, config: &Config, account_id: &str, check: TokenCheck) {
+    let health_file = config.token_health_file();
+    let mut report = TokenHealthReport::load(&health_file);
+    let notify = report.record(account_id, &check, Utc::now());
+
+    let status = match &check {
+        TokenCheck::Ok => Some(AccountStatus::Active),
+        TokenCheck::NeedsReauth(reason) => {
+            warn!("Account {} needs re-authentication: {}", account_id, reason);
+            if notify && config.sync.reauth_notifications && notify_reauth(account_id, reason) {
+                report.mark_notified(account_id);
+            }
+            Some(AccountStatus::NeedsReauth)
+        }
+        TokenCheck::Failed(_) => None,
+    };
+
+    if let Err(e) = report.save(&health_file) {
+        warn!("Failed to write token health file: {}", e);
+    }
+
+    // Disabled accounts stay disabled; only NeedsReauth is cleared by a good check
+    let Some(status) = status else {
+        return;
+    };
+    if let Ok(Some(mut account)) = db.get_account(account_id).await {
+        let update = match status {
+            AccountStatus::NeedsReauth => !matches!(
+                account.status,
+                AccountStatus::NeedsReauth | AccountStatus::Disabled
+            ),
+            _ => account.status == AccountStatus::NeedsReauth,
+        };
+        if update {
+            account.status = status;
+            if let Err(e) = db.upsert_account(&account).await {
+                error!("Failed to update account status: {}", e);
+            }
+        }
+    }
+}
+
+/// Wait for Ctrl+C, or SIGTERM (sent by launchd/systemd when the service is stopped)
+#[cfg(unix)]
+async fn wait_for_shutdown() -> Result<()> {
+    let mut sigterm = signal::unix::signal(signal::unix::SignalKind::terminate())?;
+    tokio::select! {
+        result = signal::ctrl_c() => result?,
+        _ = sigterm.recv() => {}
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+async fn wait_for_shutdown() -> Result<()> {
+    signal::ctrl_c().await?;
     Ok(())
 }
 
 /// Run the MCP server on stdio for Claude Code integration
-async fn run_mcp_server() -> Result<()> {
-    // Disable tracing output for MCP mode (it would interfere with stdio JSON-RPC)
-    // We rely on the server's internal logging to stderr if needed
+async fn run_mcp_server(transport: &str, host: &str, port: u16) -> Result<()> {
+    // Stdio mode has tracing output disabled (it would interfere with JSON-RPC);
+    // we rely on the server's internal logging to stderr if needed
+    let http_addr = match transport {
+        "stdio" => None,
+        "http" => Some(
+            format!("{}:{}", host, port)
+                .parse::<std::net::SocketAddr>()
+                .or_else(|_| format!("[{}]:{}", host, port).parse())
+                .map_err(|_| anyhow::anyhow!("Invalid --host '{}'", host))?,
+        ),
+        other => {
+            return Err(anyhow::anyhow!(
+                "Unknown transport '{}'. Use: stdio, http",
+                other
+            ))
+        }
+    };
 
     // Load configuration
     let config = Arc::new(Config::load().unwrap_or_default());
@@ -757,7 +1622,16 @@ async fn run_mcp_server() -> Result<()> {
     std::fs::create_dir_all(config.models_dir())?;
 
     // Initialize database
-    let db = Arc::new(Database::open(config.lancedb_dir()).await?);
+    let db = Arc::new(
+        Database::open_with_dimension(
+            config.lancedb_dir(),
+            config
+                .search
+                .vector_dimension()
+                .unwrap_or(EMBEDDING_DIMENSION),
+        )
+        .await?,
+    );
 
     // Initialize embedding engine with hybrid remote/local support
     // Skip loading local model if using remote with BM25 fallback (saves CPU/memory)
@@ -772,20 +1646,30 @@ async fn run_mcp_server() -> Result<()> {
             config.models_dir(),
             model_type,
             config.search.use_gpu,
+            config.search.embedding_precision,
         )?))
     };
-    let embedding = Arc::new(HybridEmbeddingProvider::from_search_config(
-        local_embedding,
-        &config.search,
-    )?);
+    let dimension = db.vector_dimension(VectorTable::Emails);
+    let embedding = Arc::new(
+        HybridEmbeddingProvider::from_search_config(local_embedding, &config.search)?
+            .with_vector_dimension(dimension),
+    );
 
     // Initialize token provider and OAuth manager
     let token_provider = create_token_provider(&config).await?;
     let oauth = Arc::new(OAuthManager::new(token_provider));
 
     // Create and run MCP server
-    let mcp = McpServer::new(db, config.clone(), embedding, oauth);
-    mcp.run().await.map_err(|e| anyhow::anyhow!(e))
+    let mcp = Arc::new(McpServer::new(db, config.clone(), embedding, oauth));
+    match http_addr {
+        Some(addr) => {
+            let token = std::env::var(MCP_HTTP_TOKEN_ENV).ok();
+            mcp.run_http(addr, token)
+                .await
+                .map_err(|e| anyhow::anyhow!(e))
+        }
+        None => mcp.run().await.map_err(|e| anyhow::anyhow!(e)),
+    }
 }
 
 /// URL decoding
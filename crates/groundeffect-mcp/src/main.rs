@@ -11,17 +11,34 @@ use tracing_appender::rolling::{RollingFileAppender, Rotation};
 use tracing_subscriber::fmt::time::ChronoLocal;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
-use tracing_subscriber::Layer;
+use tracing_subscriber::{Layer, Registry};
 
-use groundeffect_core::config::{Config, EmbeddingFallback};
+use groundeffect_core::config::{set_active_profile, Config, EmbeddingFallback, LogFormat};
 use groundeffect_core::db::Database;
 use groundeffect_core::embedding::{EmbeddingEngine, EmbeddingModel, HybridEmbeddingProvider};
+use groundeffect_core::logs::TEXT_TIMESTAMP_FORMAT;
 use groundeffect_core::mcp::McpServer;
 use groundeffect_core::oauth::OAuthManager;
 use groundeffect_core::token_provider::create_token_provider;
+use groundeffect_core::vectors::VectorTable;
+use groundeffect_core::EMBEDDING_DIMENSION;
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    // Select the profile (`--profile <name>` or GROUNDEFFECT_PROFILE) before
+    // any config or data path is resolved
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--profile" {
+            let name = args
+                .next()
+                .ok_or_else(|| anyhow::anyhow!("--profile requires a name"))?;
+            set_active_profile(&name)?;
+        } else if let Some(name) = arg.strip_prefix("--profile=") {
+            set_active_profile(name)?;
+        }
+    }
+
     // Load config first to get log path
     let config = Arc::new(Config::load().unwrap_or_else(|_| Config::default()));
 
@@ -37,13 +54,25 @@ async fn main() -> Result<()> {
 
         let file_appender = RollingFileAppender::new(Rotation::DAILY, &log_dir, "mcp.log");
 
-        // Create a file layer with timestamps
-        let file_layer = tracing_subscriber::fmt::layer()
-            .with_writer(file_appender)
-            .with_timer(ChronoLocal::new("%Y-%m-%d %H:%M:%S%.3f".to_string()))
-            .with_ansi(false)
-            .with_target(true)
-            .with_thread_ids(true);
+        // Create a file layer with timestamps, in the format `groundeffect logs` reads
+        let file_layer: Box<dyn Layer<Registry> + Send + Sync> = match config.general.log_format {
+            LogFormat::Text => Box::new(
+                tracing_subscriber::fmt::layer()
+                    .with_writer(file_appender)
+                    .with_timer(ChronoLocal::new(TEXT_TIMESTAMP_FORMAT.to_string()))
+                    .with_ansi(false)
+                    .with_target(true)
+                    .with_thread_ids(true),
+            ),
+            LogFormat::Json => Box::new(
+                tracing_subscriber::fmt::layer()
+                    .json()
+                    .with_writer(file_appender)
+                    .with_timer(ChronoLocal::rfc_3339())
+                    .with_target(true)
+                    .with_thread_ids(true),
+            ),
+        };
 
         // Filter out noisy LanceDB internal logs
         let filter = tracing_subscriber::filter::EnvFilter::new(
@@ -68,7 +97,16 @@ async fn main() -> Result<()> {
 
     // Open database in read-only mode
     // Note: LanceDB supports concurrent readers, so this is safe
-    let db = Arc::new(Database::open(&db_path).await?);
+    let db = Arc::new(
+        Database::open_with_dimension(
+            &db_path,
+            config
+                .search
+                .vector_dimension()
+                .unwrap_or(EMBEDDING_DIMENSION),
+        )
+        .await?,
+    );
 
     // Initialize embedding engine with hybrid remote/local support
     // Skip loading local model if using remote with BM25 fallback (saves CPU/memory)
@@ -82,24 +120,30 @@ async fn main() -> Result<()> {
         let model_type = EmbeddingModel::from_str(&config.search.embedding_model)
             .unwrap_or(EmbeddingModel::BgeBaseEn);
         Some(Arc::new(
-            EmbeddingEngine::from_cache(config.models_dir(), model_type, config.search.use_gpu)
-                .map_err(|e| {
-                    error!("Failed to load embedding model: {}", e);
-                    e
-                })?,
+            EmbeddingEngine::from_cache(
+                config.models_dir(),
+                model_type,
+                config.search.use_gpu,
+                config.search.embedding_precision,
+            )
+            .map_err(|e| {
+                error!("Failed to load embedding model: {}", e);
+                e
+            })?,
         ))
     };
-    let embedding = Arc::new(HybridEmbeddingProvider::from_search_config(
-        local_embedding,
-        &config.search,
-    )?);
+    let dimension = db.vector_dimension(VectorTable::Emails);
+    let embedding = Arc::new(
+        HybridEmbeddingProvider::from_search_config(local_embedding, &config.search)?
+            .with_vector_dimension(dimension),
+    );
 
     // Initialize token provider and OAuth manager (for mutations that go directly to IMAP/CalDAV)
     let token_provider = create_token_provider(&config).await?;
     let oauth = Arc::new(OAuthManager::new(token_provider));
 
     // Create and run MCP server
-    let server = McpServer::new(db, config.clone(), embedding, oauth);
+    let server = Arc::new(McpServer::new(db, config.clone(), embedding, oauth));
 
     info!("Starting MCP server on stdio");
     server.run().await?;